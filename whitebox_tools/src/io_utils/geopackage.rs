@@ -0,0 +1,194 @@
+/*
+This code is part of the WhiteboxTools geospatial analysis library.
+Authors: Dr. John Lindsay
+Created: March 31, 2018
+Last Modified: April 6, 2018
+License: MIT
+
+Notes: The core tables of the OGC GeoPackage 1.2 encoding standard (http://www.geopackage.org/spec120/),
+which are shared by the GeoPackage feature (vector) and gridded coverage (raster) formats.
+A GeoPackage written by WhiteboxTools holds a single feature table or coverage, which is
+named after the file.
+*/
+
+extern crate time;
+
+use std::io::{Error, ErrorKind};
+use std::path::Path;
+use spatial_ref_system::SpatialRef;
+use super::sqlite::{SqliteReader, SqliteWriter, SqlValue};
+
+/// The SQLite application id of a GeoPackage, i.e. 'GPKG'.
+pub const GPKG_APPLICATION_ID: u32 = 0x4750_4B47;
+/// The SQLite user version of a GeoPackage 1.2 file.
+pub const GPKG_USER_VERSION: u32 = 10200;
+/// The srs_id used for coordinate reference systems that do not have an EPSG code.
+const CUSTOM_SRS_ID: i64 = 100000;
+
+const SPATIAL_REF_SYS_SQL: &'static str = "CREATE TABLE gpkg_spatial_ref_sys (
+  srs_name TEXT NOT NULL,
+  srs_id INTEGER NOT NULL PRIMARY KEY,
+  organization TEXT NOT NULL,
+  organization_coordsys_id INTEGER NOT NULL,
+  definition  TEXT NOT NULL,
+  description TEXT
+)";
+
+const CONTENTS_SQL: &'static str = "CREATE TABLE gpkg_contents (
+  table_name TEXT NOT NULL PRIMARY KEY,
+  data_type TEXT NOT NULL,
+  identifier TEXT UNIQUE,
+  description TEXT DEFAULT '',
+  last_change DATETIME NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%fZ','now')),
+  min_x DOUBLE,
+  min_y DOUBLE,
+  max_x DOUBLE,
+  max_y DOUBLE,
+  srs_id INTEGER,
+  CONSTRAINT fk_gc_r_srs_id FOREIGN KEY (srs_id) REFERENCES gpkg_spatial_ref_sys(srs_id)
+)";
+
+const EXTENSIONS_SQL: &'static str = "CREATE TABLE gpkg_extensions (
+  table_name TEXT,
+  column_name TEXT,
+  extension_name TEXT NOT NULL,
+  definition TEXT NOT NULL,
+  scope TEXT NOT NULL,
+  CONSTRAINT ge_tce UNIQUE (table_name, column_name, extension_name)
+)";
+
+const WGS84_WKT: &'static str = "GEOGCS[\"WGS 84\",DATUM[\"WGS_1984\",SPHEROID[\"WGS 84\",6378137,298.257223563,AUTHORITY[\"EPSG\",\"7030\"]],AUTHORITY[\"EPSG\",\"6326\"]],PRIMEM[\"Greenwich\",0,AUTHORITY[\"EPSG\",\"8901\"]],UNIT[\"degree\",0.0174532925199433,AUTHORITY[\"EPSG\",\"9122\"]],AUTHORITY[\"EPSG\",\"4326\"]]";
+
+/// Returns true if the file has a GeoPackage (.gpkg) extension.
+pub fn is_geopackage_file(file_name: &str) -> bool {
+    match Path::new(file_name).extension() {
+        Some(e) => e.to_string_lossy().to_lowercase() == "gpkg",
+        None => false,
+    }
+}
+
+/// A row of the gpkg_contents table, which describes a feature table or coverage.
+#[derive(Clone, Debug, Default)]
+pub struct GeoPackageContents {
+    pub table_name: String,
+    pub data_type: String,
+    pub min_x: Option<f64>,
+    pub min_y: Option<f64>,
+    pub max_x: Option<f64>,
+    pub max_y: Option<f64>,
+    pub srs_id: i64,
+}
+
+/// Opens a GeoPackage and returns it along with its first contents entry of the given
+/// data type, i.e. 'features' or '2d-gridded-coverage'.
+pub fn open_geopackage(file_name: &str, data_type: &str) -> Result<(SqliteReader, GeoPackageContents), Error> {
+    let db = SqliteReader::new(file_name)?;
+    if !db.has_table("gpkg_contents") {
+        return Err(Error::new(ErrorKind::InvalidData, format!("The file {} is not a GeoPackage.", file_name)));
+    }
+    let table = db.read_table("gpkg_contents")?;
+    for row in 0..table.rows.len() {
+        let row_type = table.get_value(row, "data_type");
+        if row_type.as_str().map(|t| t.to_lowercase()) != Some(data_type.to_string()) {
+            continue;
+        }
+        let contents = GeoPackageContents {
+            table_name: table.get_value(row, "table_name").as_str().unwrap_or("").to_string(),
+            data_type: data_type.to_string(),
+            min_x: table.get_value(row, "min_x").as_f64(),
+            min_y: table.get_value(row, "min_y").as_f64(),
+            max_x: table.get_value(row, "max_x").as_f64(),
+            max_y: table.get_value(row, "max_y").as_f64(),
+            srs_id: table.get_value(row, "srs_id").as_i64().unwrap_or(-1),
+        };
+        return Ok((db, contents));
+    }
+    Err(Error::new(ErrorKind::InvalidData, format!("The GeoPackage {} does not contain any {} tables.", file_name, data_type)))
+}
+
+/// Returns the EPSG code (or zero) and WKT definition of the spatial reference system of
+/// the first table listed in a GeoPackage's contents.
+pub fn read_geopackage_srs(file_name: &str) -> Result<(u16, String), Error> {
+    let db = SqliteReader::new(file_name)?;
+    let table = db.read_table("gpkg_contents")?;
+    if table.rows.len() == 0 {
+        return Ok((0, String::new()));
+    }
+    read_srs(&db, table.get_value(0, "srs_id").as_i64().unwrap_or(-1))
+}
+
+/// Returns the EPSG code (or zero) and WKT definition of a GeoPackage spatial reference
+/// system. Both are empty for the undefined systems.
+pub fn read_srs(db: &SqliteReader, srs_id: i64) -> Result<(u16, String), Error> {
+    let table = db.read_table("gpkg_spatial_ref_sys")?;
+    for row in 0..table.rows.len() {
+        if table.get_value(row, "srs_id").as_i64() != Some(srs_id) {
+            continue;
+        }
+        let organization = table.get_value(row, "organization").as_str().unwrap_or("").to_uppercase();
+        let code = table.get_value(row, "organization_coordsys_id").as_i64().unwrap_or(0);
+        let definition = table.get_value(row, "definition").as_str().unwrap_or("").trim().to_string();
+        let epsg_code = if organization == "EPSG" && code > 0 && code <= 65535 { code as u16 } else { 0 };
+        let wkt = if definition.to_lowercase() == "undefined" { String::new() } else { definition };
+        return Ok((epsg_code, wkt));
+    }
+    Ok((0, String::new()))
+}
+
+/// Returns the srs_id used to store a coordinate reference system, given its EPSG code
+/// and WKT definition.
+pub fn get_srs_id(epsg_code: u16, wkt: &str) -> i64 {
+    if epsg_code != 0 {
+        epsg_code as i64
+    } else if let Ok(srs) = SpatialRef::from_wkt(wkt) {
+        if srs.epsg_code != 0 { srs.epsg_code as i64 } else { CUSTOM_SRS_ID }
+    } else if !wkt.trim().is_empty() {
+        CUSTOM_SRS_ID
+    } else {
+        -1
+    }
+}
+
+/// Creates a new GeoPackage file and writes its spatial reference system, contents, and
+/// extensions tables. The caller then adds the feature or coverage tables and finishes the file.
+pub fn create_geopackage(file_name: &str, contents: &GeoPackageContents, epsg_code: u16, wkt: &str, extensions: Vec<Vec<SqlValue>>) -> Result<SqliteWriter, Error> {
+    let mut writer = SqliteWriter::new(file_name)?;
+    writer.application_id = GPKG_APPLICATION_ID;
+    writer.user_version = GPKG_USER_VERSION;
+
+    let text = |s: &str| SqlValue::Text(s.to_string());
+    let mut srs_rows = vec![
+        vec![text("WGS 84 geodetic"), SqlValue::Integer(4326), text("EPSG"), SqlValue::Integer(4326), text(WGS84_WKT),
+            text("longitude/latitude coordinates in decimal degrees on the WGS 84 spheroid")],
+        vec![text("Undefined cartesian SRS"), SqlValue::Integer(-1), text("NONE"), SqlValue::Integer(-1), text("undefined"),
+            text("undefined cartesian coordinate reference system")],
+        vec![text("Undefined geographic SRS"), SqlValue::Integer(0), text("NONE"), SqlValue::Integer(0), text("undefined"),
+            text("undefined geographic coordinate reference system")],
+    ];
+    if contents.srs_id > 0 && contents.srs_id != 4326 {
+        let srs = if epsg_code != 0 { SpatialRef::from_epsg(epsg_code) } else { SpatialRef::from_wkt(wkt) };
+        let (name, definition) = match srs {
+            Ok(srs) => {
+                let definition = if wkt.trim().is_empty() { srs.to_wkt() } else { wkt.trim().to_string() };
+                (srs.name.clone(), definition)
+            },
+            Err(_) => ("Unknown".to_string(), wkt.trim().to_string()),
+        };
+        let (organization, code) = if contents.srs_id == CUSTOM_SRS_ID { ("NONE", CUSTOM_SRS_ID) } else { ("EPSG", contents.srs_id) };
+        srs_rows.push(vec![text(&name), SqlValue::Integer(contents.srs_id), text(organization), SqlValue::Integer(code),
+            text(&definition), SqlValue::Null]);
+    }
+    writer.add_table("gpkg_spatial_ref_sys", SPATIAL_REF_SYS_SQL, srs_rows)?;
+
+    let real = |v: Option<f64>| match v { Some(v) => SqlValue::Real(v), None => SqlValue::Null };
+    let last_change = time::strftime("%Y-%m-%dT%H:%M:%S.000Z", &time::now_utc()).unwrap_or(String::new());
+    writer.add_table("gpkg_contents", CONTENTS_SQL, vec![vec![
+        text(&contents.table_name), text(&contents.data_type), text(&contents.table_name), text(""),
+        text(&last_change), real(contents.min_x), real(contents.min_y), real(contents.max_x), real(contents.max_y),
+        SqlValue::Integer(contents.srs_id)]])?;
+
+    if extensions.len() > 0 {
+        writer.add_table("gpkg_extensions", EXTENSIONS_SQL, extensions)?;
+    }
+    Ok(writer)
+}
@@ -1,8 +1,12 @@
 // private sub-module defined in other files
 mod byte_order_reader;
 mod byte_order_writer;
+mod geopackage;
+mod sqlite;
 
 // exports identifiers from private sub-modules in the current module namespace
 pub use self::byte_order_reader::ByteOrderReader;
 pub use self::byte_order_reader::Endianness;
+pub use self::geopackage::{is_geopackage_file, open_geopackage, read_geopackage_srs, read_srs, get_srs_id, create_geopackage, GeoPackageContents};
+pub use self::sqlite::{SqliteReader, SqliteWriter, SqlColumn, SqlTable, SqlValue};
 // pub use self::byte_order_writer::ByteOrderWriter;
\ No newline at end of file
@@ -0,0 +1,1276 @@
+/*
+This code is part of the WhiteboxTools geospatial analysis library.
+Authors: Dr. John Lindsay
+Created: March 31, 2018
+Last Modified: April 6, 2018
+License: MIT
+
+Notes: A reader and writer for the SQLite 3 database file format (https://sqlite.org/fileformat.html),
+which is used by the GeoPackage raster and vector formats. This is not a SQL engine. The
+reader walks the table b-trees of a database, decoding the records of whole tables, and
+parses just enough of each CREATE TABLE statement to name the columns. The writer creates
+a new database from a set of tables, including the automatic indexes that SQLite expects
+for UNIQUE and PRIMARY KEY constraints and the sqlite_sequence table used by AUTOINCREMENT
+columns. Databases are always written in a single pass, with the pages of each table written
+as they are filled, so that large tables can be streamed a row at a time; existing databases
+cannot be modified. Only UTF-8 databases are supported.
+*/
+
+use std::cmp::Ordering;
+use std::fs::File;
+use std::io::prelude::*;
+use std::io::{BufWriter, Error, ErrorKind, SeekFrom};
+
+const HEADER_STRING: &'static [u8] = b"SQLite format 3\0";
+const TABLE_INTERIOR: u8 = 0x05;
+const TABLE_LEAF: u8 = 0x0D;
+const INDEX_INTERIOR: u8 = 0x02;
+const INDEX_LEAF: u8 = 0x0A;
+const WRITE_PAGE_SIZE: usize = 4096;
+
+/// A value stored in a SQLite database.
+#[derive(Clone, Debug, PartialEq)]
+pub enum SqlValue {
+    Null,
+    Integer(i64),
+    Real(f64),
+    Text(String),
+    Blob(Vec<u8>),
+}
+
+impl SqlValue {
+    pub fn as_i64(&self) -> Option<i64> {
+        match self {
+            &SqlValue::Integer(v) => Some(v),
+            &SqlValue::Real(v) => Some(v as i64),
+            &SqlValue::Text(ref s) => s.trim().parse::<i64>().ok(),
+            _ => None,
+        }
+    }
+
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            &SqlValue::Integer(v) => Some(v as f64),
+            &SqlValue::Real(v) => Some(v),
+            &SqlValue::Text(ref s) => s.trim().parse::<f64>().ok(),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            &SqlValue::Text(ref s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn is_null(&self) -> bool {
+        *self == SqlValue::Null
+    }
+}
+
+/// A column of a table, as declared in its CREATE TABLE statement.
+#[derive(Clone, Debug)]
+pub struct SqlColumn {
+    pub name: String,
+    /// The declared type, in upper case, e.g. "INTEGER" or "TEXT(20)".
+    pub declared_type: String,
+    /// True for an INTEGER PRIMARY KEY column, which holds the rowid of the record.
+    pub is_rowid: bool,
+}
+
+/// The columns and rows of a table.
+#[derive(Clone, Debug, Default)]
+pub struct SqlTable {
+    pub name: String,
+    pub columns: Vec<SqlColumn>,
+    pub rows: Vec<Vec<SqlValue>>,
+}
+
+impl SqlTable {
+    pub fn get_column_num(&self, name: &str) -> Option<usize> {
+        self.columns.iter().position(|c| c.name.eq_ignore_ascii_case(name))
+    }
+
+    /// Returns the value of a named column in a row, or Null if there is no such column.
+    pub fn get_value(&self, row: usize, column_name: &str) -> SqlValue {
+        match self.get_column_num(column_name) {
+            Some(c) if c < self.rows[row].len() => self.rows[row][c].clone(),
+            _ => SqlValue::Null,
+        }
+    }
+}
+
+/// An entry in the sqlite_master schema table.
+#[derive(Clone, Debug)]
+pub struct SchemaEntry {
+    pub entry_type: String,
+    pub name: String,
+    pub table_name: String,
+    pub root_page: usize,
+    pub sql: String,
+}
+
+/// Reads the tables of a SQLite database file.
+pub struct SqliteReader {
+    buffer: Vec<u8>,
+    page_size: usize,
+    usable_size: usize,
+    pub application_id: u32,
+    pub user_version: u32,
+    pub schema: Vec<SchemaEntry>,
+}
+
+impl SqliteReader {
+    pub fn new(file_name: &str) -> Result<SqliteReader, Error> {
+        let mut f = File::open(file_name)?;
+        let mut buffer = vec![];
+        f.read_to_end(&mut buffer)?;
+        if buffer.len() < 100 || &buffer[0..16] != HEADER_STRING {
+            return Err(Error::new(ErrorKind::InvalidData, format!("{} is not a SQLite database.", file_name)));
+        }
+        let page_size = match read_u16(&buffer, 16) as usize {
+            1 => 65536,
+            n => n,
+        };
+        if page_size < 512 || !page_size.is_power_of_two() {
+            return Err(Error::new(ErrorKind::InvalidData, "The SQLite database has an invalid page size."));
+        }
+        if read_u32(&buffer, 56) > 1 {
+            return Err(Error::new(ErrorKind::InvalidData, "Only UTF-8 SQLite databases are supported."));
+        }
+        if buffer[18] == 2 {
+            return Err(Error::new(ErrorKind::InvalidData,
+                "The SQLite database is in WAL mode and may have uncommitted changes; please checkpoint it first."));
+        }
+        let mut reader = SqliteReader {
+            page_size: page_size,
+            usable_size: page_size - buffer[20] as usize,
+            application_id: read_u32(&buffer, 68),
+            user_version: read_u32(&buffer, 60),
+            buffer: buffer,
+            schema: vec![],
+        };
+        let mut schema = vec![];
+        for (_, record) in reader.read_btree(1)? {
+            let get_text = |i: usize| match record.get(i) {
+                Some(&SqlValue::Text(ref s)) => s.clone(),
+                _ => String::new(),
+            };
+            schema.push(SchemaEntry {
+                entry_type: get_text(0),
+                name: get_text(1),
+                table_name: get_text(2),
+                root_page: record.get(3).and_then(|v| v.as_i64()).unwrap_or(0) as usize,
+                sql: get_text(4),
+            });
+        }
+        reader.schema = schema;
+        Ok(reader)
+    }
+
+    pub fn has_table(&self, name: &str) -> bool {
+        self.schema.iter().any(|e| e.entry_type == "table" && e.name.eq_ignore_ascii_case(name))
+    }
+
+    /// Reads all of the rows of a table. The value of an INTEGER PRIMARY KEY column is the
+    /// rowid of the record.
+    pub fn read_table(&self, name: &str) -> Result<SqlTable, Error> {
+        let entry = match self.schema.iter().find(|e| e.entry_type == "table" && e.name.eq_ignore_ascii_case(name)) {
+            Some(e) => e,
+            None => return Err(Error::new(ErrorKind::NotFound, format!("The table {} does not exist.", name))),
+        };
+        if entry.sql.to_uppercase().contains("WITHOUT ROWID") {
+            return Err(Error::new(ErrorKind::InvalidData, "WITHOUT ROWID tables are not supported."));
+        }
+        let columns = parse_columns(&entry.sql);
+        let mut rows = vec![];
+        for (rowid, mut record) in self.read_btree(entry.root_page)? {
+            // columns added with ALTER TABLE may be missing from older records
+            while record.len() < columns.len() {
+                record.push(SqlValue::Null);
+            }
+            record.truncate(columns.len());
+            for (i, c) in columns.iter().enumerate() {
+                if c.is_rowid {
+                    record[i] = SqlValue::Integer(rowid);
+                }
+            }
+            rows.push(record);
+        }
+        Ok(SqlTable { name: entry.name.clone(), columns: columns, rows: rows })
+    }
+
+    fn page(&self, page_num: usize) -> Result<&[u8], Error> {
+        let start = (page_num - 1) * self.page_size;
+        if page_num == 0 || start + self.page_size > self.buffer.len() {
+            return Err(Error::new(ErrorKind::InvalidData, "The SQLite database is malformed (page out of range)."));
+        }
+        Ok(&self.buffer[start..start + self.page_size])
+    }
+
+    // reads the (rowid, record) pairs of a table b-tree in order
+    fn read_btree(&self, root_page: usize) -> Result<Vec<(i64, Vec<SqlValue>)>, Error> {
+        let mut records = vec![];
+        let mut stack = vec![root_page];
+        let mut visited = 0usize;
+        while let Some(page_num) = stack.pop() {
+            visited += 1;
+            if visited > self.buffer.len() / self.page_size + 1 {
+                return Err(Error::new(ErrorKind::InvalidData, "The SQLite database is malformed (b-tree loop)."));
+            }
+            let page = self.page(page_num)?;
+            let offset = if page_num == 1 { 100 } else { 0 };
+            let page_type = page[offset];
+            let num_cells = read_u16(page, offset + 3) as usize;
+            match page_type {
+                TABLE_INTERIOR => {
+                    // children are pushed in reverse so that they are visited in order
+                    stack.push(read_u32(page, offset + 8) as usize);
+                    for i in (0..num_cells).rev() {
+                        let cell = read_u16(page, offset + 12 + 2 * i) as usize;
+                        stack.push(read_u32(page, cell) as usize);
+                    }
+                },
+                TABLE_LEAF => {
+                    for i in 0..num_cells {
+                        let mut pos = read_u16(page, offset + 8 + 2 * i) as usize;
+                        let (payload_size, n) = read_varint(page, pos);
+                        pos += n;
+                        let (rowid, n) = read_varint(page, pos);
+                        pos += n;
+                        let payload = self.read_payload(page, pos, payload_size as usize)?;
+                        records.push((rowid as i64, decode_record(&payload)?));
+                    }
+                },
+                _ => return Err(Error::new(ErrorKind::InvalidData, "The SQLite database is malformed (unexpected page type).")),
+            }
+        }
+        Ok(records)
+    }
+
+    // reads a table leaf cell payload, following any overflow pages
+    fn read_payload(&self, page: &[u8], pos: usize, payload_size: usize) -> Result<Vec<u8>, Error> {
+        let local = local_payload_size(self.usable_size, payload_size, false);
+        if pos + local > page.len() {
+            return Err(Error::new(ErrorKind::InvalidData, "The SQLite database is malformed (cell out of range)."));
+        }
+        let mut payload = Vec::with_capacity(payload_size);
+        payload.extend_from_slice(&page[pos..pos + local]);
+        if local < payload_size {
+            let mut next = read_u32(page, pos + local) as usize;
+            while payload.len() < payload_size {
+                if next == 0 {
+                    return Err(Error::new(ErrorKind::InvalidData, "The SQLite database is malformed (missing overflow page)."));
+                }
+                let overflow = self.page(next)?;
+                let n = (payload_size - payload.len()).min(self.usable_size - 4);
+                payload.extend_from_slice(&overflow[4..4 + n]);
+                next = read_u32(overflow, 0) as usize;
+            }
+        }
+        Ok(payload)
+    }
+}
+
+// The number of bytes of a payload stored on a b-tree page, with the remainder in overflow pages.
+fn local_payload_size(usable_size: usize, payload_size: usize, is_index: bool) -> usize {
+    let max_local = if is_index { (usable_size - 12) * 64 / 255 - 23 } else { usable_size - 35 };
+    if payload_size <= max_local {
+        return payload_size;
+    }
+    let min_local = (usable_size - 12) * 32 / 255 - 23;
+    let k = min_local + (payload_size - min_local) % (usable_size - 4);
+    if k <= max_local { k } else { min_local }
+}
+
+fn read_u16(buf: &[u8], pos: usize) -> u16 {
+    ((buf[pos] as u16) << 8) | buf[pos + 1] as u16
+}
+
+fn read_u32(buf: &[u8], pos: usize) -> u32 {
+    ((buf[pos] as u32) << 24) | ((buf[pos + 1] as u32) << 16) | ((buf[pos + 2] as u32) << 8) | buf[pos + 3] as u32
+}
+
+// returns the value of a variable-length integer and the number of bytes that it occupies
+fn read_varint(buf: &[u8], pos: usize) -> (u64, usize) {
+    let mut value = 0u64;
+    for i in 0..8 {
+        let b = buf[pos + i];
+        value = (value << 7) | (b & 0x7F) as u64;
+        if b & 0x80 == 0 {
+            return (value, i + 1);
+        }
+    }
+    ((value << 8) | buf[pos + 8] as u64, 9)
+}
+
+fn write_varint(value: u64, out: &mut Vec<u8>) {
+    if value > 0x00FF_FFFF_FFFF_FFFF {
+        // nine bytes, the last of which holds eight bits
+        let mut bytes = [0u8; 9];
+        bytes[8] = value as u8;
+        let mut v = value >> 8;
+        for i in (0..8).rev() {
+            bytes[i] = (v & 0x7F) as u8 | 0x80;
+            v >>= 7;
+        }
+        out.extend_from_slice(&bytes);
+        return;
+    }
+    let mut bytes = vec![];
+    let mut v = value;
+    loop {
+        bytes.push((v & 0x7F) as u8);
+        v >>= 7;
+        if v == 0 { break; }
+    }
+    for i in (0..bytes.len()).rev() {
+        out.push(if i > 0 { bytes[i] | 0x80 } else { bytes[i] });
+    }
+}
+
+fn varint_len(value: u64) -> usize {
+    let mut v = Vec::with_capacity(9);
+    write_varint(value, &mut v);
+    v.len()
+}
+
+fn decode_record(payload: &[u8]) -> Result<Vec<SqlValue>, Error> {
+    let malformed = || Error::new(ErrorKind::InvalidData, "The SQLite database is malformed (invalid record).");
+    if payload.is_empty() {
+        return Ok(vec![]);
+    }
+    let (header_size, mut pos) = read_varint(payload, 0);
+    let header_size = header_size as usize;
+    if header_size > payload.len() {
+        return Err(malformed());
+    }
+    let mut serial_types = vec![];
+    while pos < header_size {
+        let (t, n) = read_varint(payload, pos);
+        serial_types.push(t);
+        pos += n;
+    }
+    let mut values = Vec::with_capacity(serial_types.len());
+    let mut pos = header_size;
+    for t in serial_types {
+        let size = match t {
+            0 | 8 | 9 => 0,
+            1...4 => t as usize,
+            5 => 6,
+            6 | 7 => 8,
+            10 | 11 => return Err(malformed()),
+            _ => ((t - 12) / 2) as usize,
+        };
+        if pos + size > payload.len() {
+            return Err(malformed());
+        }
+        let bytes = &payload[pos..pos + size];
+        let value = match t {
+            0 => SqlValue::Null,
+            8 => SqlValue::Integer(0),
+            9 => SqlValue::Integer(1),
+            1...6 => {
+                // big-endian two's complement integers
+                let mut v = if bytes[0] & 0x80 != 0 { -1i64 } else { 0i64 };
+                for &b in bytes {
+                    v = (v << 8) | b as i64;
+                }
+                SqlValue::Integer(v)
+            },
+            7 => {
+                let mut v = 0u64;
+                for &b in bytes {
+                    v = (v << 8) | b as u64;
+                }
+                SqlValue::Real(f64::from_bits(v))
+            },
+            _ if t % 2 == 0 => SqlValue::Blob(bytes.to_vec()),
+            _ => SqlValue::Text(String::from_utf8_lossy(bytes).to_string()),
+        };
+        values.push(value);
+        pos += size;
+    }
+    Ok(values)
+}
+
+fn encode_record(values: &[SqlValue]) -> Vec<u8> {
+    let mut serial_types = Vec::with_capacity(values.len());
+    let mut body = vec![];
+    for value in values {
+        let t = match value {
+            &SqlValue::Null => 0u64,
+            &SqlValue::Integer(0) => 8,
+            &SqlValue::Integer(1) => 9,
+            &SqlValue::Integer(v) => {
+                let (t, size) = if v >= -128 && v < 128 { (1, 1) }
+                    else if v >= -32768 && v < 32768 { (2, 2) }
+                    else if v >= -8388608 && v < 8388608 { (3, 3) }
+                    else if v >= -2147483648 && v < 2147483648 { (4, 4) }
+                    else if v >= -140737488355328 && v < 140737488355328 { (5, 6) }
+                    else { (6, 8) };
+                for i in (0..size).rev() {
+                    body.push((v >> (8 * i)) as u8);
+                }
+                t
+            },
+            &SqlValue::Real(v) => {
+                let bits = v.to_bits();
+                for i in (0..8).rev() {
+                    body.push((bits >> (8 * i)) as u8);
+                }
+                7
+            },
+            &SqlValue::Text(ref s) => {
+                body.extend_from_slice(s.as_bytes());
+                13 + 2 * s.len() as u64
+            },
+            &SqlValue::Blob(ref b) => {
+                body.extend_from_slice(b);
+                12 + 2 * b.len() as u64
+            },
+        };
+        serial_types.push(t);
+    }
+    let types_len: usize = serial_types.iter().map(|&t| varint_len(t)).sum();
+    // the header size includes the varint that holds it
+    let mut header_size = types_len + 1;
+    if varint_len(header_size as u64) > 1 {
+        header_size = types_len + varint_len((types_len + 9) as u64);
+    }
+    let mut record = Vec::with_capacity(header_size + body.len());
+    write_varint(header_size as u64, &mut record);
+    for t in serial_types {
+        write_varint(t, &mut record);
+    }
+    record.extend_from_slice(&body);
+    record
+}
+
+// orders values as SQLite does with the BINARY collating sequence
+fn compare_values(a: &SqlValue, b: &SqlValue) -> Ordering {
+    fn rank(v: &SqlValue) -> u8 {
+        match v {
+            &SqlValue::Null => 0,
+            &SqlValue::Integer(_) | &SqlValue::Real(_) => 1,
+            &SqlValue::Text(_) => 2,
+            &SqlValue::Blob(_) => 3,
+        }
+    }
+    match (a, b) {
+        (&SqlValue::Integer(x), &SqlValue::Integer(y)) => x.cmp(&y),
+        (&SqlValue::Text(ref x), &SqlValue::Text(ref y)) => x.as_bytes().cmp(y.as_bytes()),
+        (&SqlValue::Blob(ref x), &SqlValue::Blob(ref y)) => x.cmp(y),
+        _ if rank(a) == 1 && rank(b) == 1 => {
+            a.as_f64().unwrap_or(0f64).partial_cmp(&b.as_f64().unwrap_or(0f64)).unwrap_or(Ordering::Equal)
+        },
+        _ => rank(a).cmp(&rank(b)),
+    }
+}
+
+// Splits the column definitions and table constraints of a CREATE TABLE statement.
+fn split_definitions(sql: &str) -> Vec<String> {
+    let start = match sql.find('(') {
+        Some(p) => p + 1,
+        None => return vec![],
+    };
+    let mut definitions = vec![];
+    let mut current = String::new();
+    let mut depth = 0i32;
+    let mut quote: Option<char> = None;
+    for c in sql[start..].chars() {
+        if let Some(q) = quote {
+            current.push(c);
+            if c == q || (q == '[' && c == ']') {
+                quote = None;
+            }
+            continue;
+        }
+        match c {
+            '"' | '\'' | '`' | '[' => { quote = Some(c); current.push(c); },
+            '(' => { depth += 1; current.push(c); },
+            ')' if depth == 0 => break,
+            ')' => { depth -= 1; current.push(c); },
+            ',' if depth == 0 => {
+                definitions.push(current.trim().to_string());
+                current = String::new();
+            },
+            _ => current.push(c),
+        }
+    }
+    if !current.trim().is_empty() {
+        definitions.push(current.trim().to_string());
+    }
+    definitions
+}
+
+// splits an SQL fragment into identifiers, keywords, and parenthesized groups
+fn tokenize(s: &str) -> Vec<String> {
+    let mut tokens = vec![];
+    let chars: Vec<char> = s.chars().collect();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        if c.is_whitespace() {
+            i += 1;
+        } else if c == '"' || c == '`' || c == '[' || c == '\'' {
+            let close = if c == '[' { ']' } else { c };
+            let mut token = String::new();
+            i += 1;
+            while i < chars.len() && chars[i] != close {
+                token.push(chars[i]);
+                i += 1;
+            }
+            tokens.push(token);
+            i += 1;
+        } else if c == '(' {
+            let mut depth = 0;
+            let mut token = String::new();
+            while i < chars.len() {
+                if chars[i] == '(' { depth += 1; }
+                if chars[i] == ')' { depth -= 1; }
+                token.push(chars[i]);
+                i += 1;
+                if depth == 0 { break; }
+            }
+            tokens.push(token);
+        } else {
+            let mut token = String::new();
+            while i < chars.len() && !chars[i].is_whitespace() && chars[i] != '(' && chars[i] != '"' {
+                token.push(chars[i]);
+                i += 1;
+            }
+            tokens.push(token);
+        }
+    }
+    tokens
+}
+
+fn is_table_constraint(definition: &str) -> bool {
+    let upper = definition.trim_start().to_uppercase();
+    ["CONSTRAINT", "PRIMARY", "UNIQUE", "CHECK", "FOREIGN"].iter().any(|k| {
+        upper.starts_with(k) && upper[k.len()..].chars().next().map(|c| !c.is_alphanumeric() && c != '_').unwrap_or(true)
+    })
+}
+
+// the column names listed in a parenthesized group, e.g. "(table_name, column_name)"
+fn column_list(group: &str) -> Vec<String> {
+    let inner = group.trim().trim_start_matches('(').trim_end_matches(')');
+    inner.split(',').filter_map(|s| tokenize(s).into_iter().next()).collect()
+}
+
+/// Returns the columns declared in a CREATE TABLE statement.
+pub fn parse_columns(sql: &str) -> Vec<SqlColumn> {
+    let definitions = split_definitions(sql);
+    let mut columns = vec![];
+    let mut pk_columns: Vec<String> = vec![];
+    for d in &definitions {
+        let tokens = tokenize(d);
+        if tokens.is_empty() { continue; }
+        if is_table_constraint(d) {
+            let upper: Vec<String> = tokens.iter().map(|t| t.to_uppercase()).collect();
+            if let Some(p) = upper.iter().position(|t| t == "PRIMARY") {
+                if let Some(group) = tokens.iter().skip(p + 1).find(|t| t.starts_with('(')) {
+                    pk_columns = column_list(group);
+                }
+            }
+            continue;
+        }
+        let constraint_words = ["CONSTRAINT", "PRIMARY", "NOT", "NULL", "UNIQUE", "CHECK", "DEFAULT", "COLLATE", "REFERENCES", "GENERATED", "AS"];
+        let mut declared_type = vec![];
+        for t in tokens.iter().skip(1) {
+            if constraint_words.contains(&t.to_uppercase().as_ref()) { break; }
+            declared_type.push(t.to_uppercase());
+        }
+        let upper = d.to_uppercase();
+        let declared_type = declared_type.join(" ").replace(" (", "(");
+        columns.push(SqlColumn {
+            name: tokens[0].clone(),
+            is_rowid: declared_type == "INTEGER" && upper.contains("PRIMARY KEY") && !upper.contains("PRIMARY KEY DESC"),
+            declared_type: declared_type,
+        });
+    }
+    if pk_columns.len() == 1 {
+        for c in &mut columns {
+            if c.name.eq_ignore_ascii_case(&pk_columns[0]) && c.declared_type == "INTEGER" {
+                c.is_rowid = true;
+            }
+        }
+    }
+    columns
+}
+
+// The column sets of the automatic indexes that SQLite creates for the UNIQUE and
+// (non-rowid) PRIMARY KEY constraints of a table, in the order in which it creates them.
+fn automatic_indexes(sql: &str, columns: &[SqlColumn]) -> Vec<Vec<usize>> {
+    let find = |name: &str| columns.iter().position(|c| c.name.eq_ignore_ascii_case(name));
+    let mut indexes: Vec<Vec<usize>> = vec![];
+    for d in split_definitions(sql) {
+        let tokens = tokenize(&d);
+        if tokens.is_empty() { continue; }
+        let upper: Vec<String> = tokens.iter().map(|t| t.to_uppercase()).collect();
+        let mut index = vec![];
+        if is_table_constraint(&d) {
+            if let Some(p) = upper.iter().position(|t| t == "PRIMARY" || t == "UNIQUE") {
+                if let Some(group) = tokens.iter().skip(p + 1).find(|t| t.starts_with('(')) {
+                    index = column_list(group).iter().filter_map(|c| find(c)).collect();
+                }
+            }
+        } else if upper.iter().any(|t| t == "UNIQUE") || upper.iter().any(|t| t == "PRIMARY") {
+            if let Some(c) = find(&tokens[0]) {
+                index = vec![c];
+            }
+        }
+        if index.is_empty() || (index.len() == 1 && columns[index[0]].is_rowid) {
+            continue;
+        }
+        if !indexes.contains(&index) {
+            indexes.push(index);
+        }
+    }
+    indexes
+}
+
+// A table b-tree that is being built from cells in rowid order. The cells of the leaf
+// page being filled are held until it is full, and the full pages are written out.
+struct TableBtree {
+    cells: Vec<Vec<u8>>,
+    children: Vec<(usize, i64)>,
+    last_rowid: i64,
+}
+
+impl TableBtree {
+    fn new() -> TableBtree {
+        TableBtree { cells: vec![], children: vec![], last_rowid: 0 }
+    }
+}
+
+// A table whose rows are being added to the database.
+struct TableBuilder {
+    name: String,
+    sql: String,
+    num_columns: usize,
+    rowid_column: Option<usize>,
+    autoincrement: bool,
+    indexes: Vec<Vec<usize>>,
+    index_keys: Vec<Vec<Vec<SqlValue>>>,
+    next_rowid: i64,
+    last_rowid: Option<i64>,
+    tree: TableBtree,
+}
+
+/// Creates a new SQLite database. Tables are written to the file as they are added, either
+/// whole with `add_table` or one row at a time, between calls to `begin_table` and
+/// `end_table`, with `add_row`; `finish` then writes the schema and completes the file. The
+/// value of an INTEGER PRIMARY KEY column is used as the rowid of the record, or the rows
+/// are numbered from 1 if the value is Null.
+pub struct SqliteWriter {
+    pub application_id: u32,
+    pub user_version: u32,
+    writer: BufWriter<File>,
+    num_pages: usize,
+    first_page: Vec<u8>, // page 1 holds the file header and schema and is written last
+    schema: Vec<Vec<SqlValue>>,
+    sequences: Vec<Vec<SqlValue>>,
+    sequence_entry: Option<usize>,
+    table: Option<TableBuilder>,
+}
+
+impl SqliteWriter {
+    pub fn new(file_name: &str) -> Result<SqliteWriter, Error> {
+        let f = File::create(file_name)?;
+        let mut writer = BufWriter::new(f);
+        writer.write_all(&[0u8; WRITE_PAGE_SIZE])?;
+        Ok(SqliteWriter {
+            application_id: 0,
+            user_version: 0,
+            writer: writer,
+            num_pages: 1,
+            first_page: vec![0u8; WRITE_PAGE_SIZE],
+            schema: vec![],
+            sequences: vec![],
+            sequence_entry: None,
+            table: None,
+        })
+    }
+
+    /// Adds a table, given its CREATE TABLE statement and its rows.
+    pub fn add_table(&mut self, name: &str, sql: &str, rows: Vec<Vec<SqlValue>>) -> Result<(), Error> {
+        self.begin_table(name, sql)?;
+        // number the rows that have no rowid and sort them into rowid order
+        let rowid_column = parse_columns(sql).iter().position(|c| c.is_rowid);
+        let mut records: Vec<(i64, Vec<SqlValue>)> = Vec::with_capacity(rows.len());
+        let mut next_rowid = 1i64;
+        for mut row in rows {
+            let rowid = match rowid_column.and_then(|c| row.get(c)) {
+                Some(&SqlValue::Integer(v)) => v,
+                _ => next_rowid,
+            };
+            if let Some(c) = rowid_column {
+                if row.len() <= c {
+                    row.resize(c + 1, SqlValue::Null);
+                }
+                row[c] = SqlValue::Integer(rowid);
+            }
+            next_rowid = next_rowid.max(rowid + 1);
+            records.push((rowid, row));
+        }
+        records.sort_by_key(|r| r.0);
+        if records.windows(2).any(|w| w[0].0 == w[1].0) {
+            return Err(Error::new(ErrorKind::InvalidInput, format!("The table {} has duplicate primary key values.", name)));
+        }
+        for (_, row) in records {
+            self.add_row(row)?;
+        }
+        self.end_table()
+    }
+
+    /// Begins a table, given its CREATE TABLE statement. Its rows are then added with
+    /// `add_row`, in ascending rowid order, and the table is completed with `end_table`.
+    pub fn begin_table(&mut self, name: &str, sql: &str) -> Result<(), Error> {
+        if let Some(ref table) = self.table {
+            return Err(Error::new(ErrorKind::InvalidInput, format!("The table {} has not been ended.", table.name)));
+        }
+        let columns = parse_columns(sql);
+        let indexes = automatic_indexes(sql, &columns);
+        self.table = Some(TableBuilder {
+            name: name.to_string(),
+            sql: sql.to_string(),
+            num_columns: columns.len(),
+            rowid_column: columns.iter().position(|c| c.is_rowid),
+            autoincrement: sql.to_uppercase().contains("AUTOINCREMENT"),
+            index_keys: vec![vec![]; indexes.len()],
+            indexes: indexes,
+            next_rowid: 1i64,
+            last_rowid: None,
+            tree: TableBtree::new(),
+        });
+        Ok(())
+    }
+
+    /// Adds a row to the table begun by `begin_table`. The row is written to the file once
+    /// the page that holds it is full.
+    pub fn add_row(&mut self, row: Vec<SqlValue>) -> Result<(), Error> {
+        let mut table = match self.table.take() {
+            Some(t) => t,
+            None => return Err(Error::new(ErrorKind::InvalidInput, "A row cannot be added before a table is begun.")),
+        };
+        let result = self.add_table_row(&mut table, row);
+        self.table = Some(table);
+        result
+    }
+
+    fn add_table_row(&mut self, table: &mut TableBuilder, mut row: Vec<SqlValue>) -> Result<(), Error> {
+        row.resize(table.num_columns, SqlValue::Null);
+        let rowid = match table.rowid_column.map(|c| row[c].clone()) {
+            Some(SqlValue::Integer(v)) => v,
+            _ => table.next_rowid,
+        };
+        if table.last_rowid.map(|last| rowid <= last).unwrap_or(false) {
+            return Err(Error::new(ErrorKind::InvalidInput,
+                format!("The rows of the table {} must be added in ascending primary key order, without duplicates.", table.name)));
+        }
+        if let Some(c) = table.rowid_column {
+            // the rowid is not repeated in the record
+            row[c] = SqlValue::Null;
+        }
+        for (index, keys) in table.indexes.iter().zip(table.index_keys.iter_mut()) {
+            let mut key: Vec<SqlValue> = index.iter().map(|&c| row[c].clone()).collect();
+            key.push(SqlValue::Integer(rowid));
+            keys.push(key);
+        }
+        table.next_rowid = table.next_rowid.max(rowid + 1);
+        table.last_rowid = Some(rowid);
+        self.push_table_cell(&mut table.tree, rowid, &encode_record(&row))
+    }
+
+    /// Completes the table begun by `begin_table`, writing its remaining pages and indexes.
+    pub fn end_table(&mut self) -> Result<(), Error> {
+        let mut table = match self.table.take() {
+            Some(t) => t,
+            None => return Err(Error::new(ErrorKind::InvalidInput, "There is no table to end.")),
+        };
+        let tree = ::std::mem::replace(&mut table.tree, TableBtree::new());
+        let root = self.finish_table_btree(tree, false)?;
+        self.schema.push(vec![SqlValue::Text("table".to_string()), SqlValue::Text(table.name.clone()),
+            SqlValue::Text(table.name.clone()), SqlValue::Integer(root as i64), SqlValue::Text(table.sql.clone())]);
+        if table.autoincrement && self.sequence_entry.is_none() {
+            // SQLite creates the sequence table along with the first AUTOINCREMENT table
+            self.sequence_entry = Some(self.schema.len());
+            self.schema.push(vec![]);
+        }
+        if let (true, Some(last)) = (table.autoincrement, table.last_rowid) {
+            self.sequences.push(vec![SqlValue::Text(table.name.clone()), SqlValue::Integer(last)]);
+        }
+
+        for (i, mut entries) in table.index_keys.into_iter().enumerate() {
+            entries.sort_by(|a, b| {
+                for (x, y) in a.iter().zip(b.iter()) {
+                    let o = compare_values(x, y);
+                    if o != Ordering::Equal { return o; }
+                }
+                Ordering::Equal
+            });
+            let root = self.build_index_btree(entries.iter().map(|e| encode_record(e)).collect())?;
+            let name = format!("sqlite_autoindex_{}_{}", table.name, i + 1);
+            self.schema.push(vec![SqlValue::Text("index".to_string()), SqlValue::Text(name),
+                SqlValue::Text(table.name.clone()), SqlValue::Integer(root as i64), SqlValue::Null]);
+        }
+        Ok(())
+    }
+
+    /// Writes the schema and the file header, completing the database.
+    pub fn finish(&mut self) -> Result<(), Error> {
+        if self.table.is_some() {
+            self.end_table()?;
+        }
+        if let Some(entry) = self.sequence_entry {
+            let sequences = ::std::mem::replace(&mut self.sequences, vec![]);
+            let mut tree = TableBtree::new();
+            for (i, r) in sequences.iter().enumerate() {
+                self.push_table_cell(&mut tree, i as i64 + 1, &encode_record(r))?;
+            }
+            let root = self.finish_table_btree(tree, false)?;
+            self.schema[entry] = vec![SqlValue::Text("table".to_string()), SqlValue::Text("sqlite_sequence".to_string()),
+                SqlValue::Text("sqlite_sequence".to_string()), SqlValue::Integer(root as i64),
+                SqlValue::Text("CREATE TABLE sqlite_sequence(name,seq)".to_string())];
+        }
+        let schema = ::std::mem::replace(&mut self.schema, vec![]);
+        let mut tree = TableBtree::new();
+        for (i, r) in schema.iter().enumerate() {
+            self.push_table_cell(&mut tree, i as i64 + 1, &encode_record(r))?;
+        }
+        self.finish_table_btree(tree, true)?;
+
+        self.write_file_header();
+        self.writer.seek(SeekFrom::Start(0))?;
+        self.writer.write_all(&self.first_page)?;
+        self.writer.flush()?;
+        Ok(())
+    }
+
+    fn write_file_header(&mut self) {
+        let num_pages = self.num_pages as u32;
+        let application_id = self.application_id;
+        let user_version = self.user_version;
+        let h = &mut self.first_page;
+        h[0..16].copy_from_slice(HEADER_STRING);
+        put_u16(h, 16, WRITE_PAGE_SIZE as u16);
+        h[18] = 1; // legacy (rollback journal) write and read versions
+        h[19] = 1;
+        h[20] = 0; // reserved space at the end of each page
+        h[21] = 64; // maximum embedded payload fraction
+        h[22] = 32; // minimum embedded payload fraction
+        h[23] = 32; // leaf payload fraction
+        put_u32(h, 24, 1); // file change counter
+        put_u32(h, 28, num_pages);
+        put_u32(h, 32, 0); // freelist
+        put_u32(h, 36, 0);
+        put_u32(h, 40, 1); // schema cookie
+        put_u32(h, 44, 4); // schema format number
+        put_u32(h, 48, 0);
+        put_u32(h, 52, 0); // not auto-vacuum
+        put_u32(h, 56, 1); // UTF-8
+        put_u32(h, 60, user_version);
+        put_u32(h, 64, 0);
+        put_u32(h, 68, application_id);
+        put_u32(h, 92, 1); // version-valid-for
+        put_u32(h, 96, 3022000); // the SQLite version number that the format is compatible with
+    }
+
+    // Appends a page to the file and returns its page number.
+    fn append_page(&mut self, page: &[u8]) -> Result<usize, Error> {
+        self.writer.write_all(page)?;
+        self.num_pages += 1;
+        Ok(self.num_pages)
+    }
+
+    // Writes a b-tree page, either to page 1 or appended to the file, and returns its page number.
+    fn put_btree_page(&mut self, on_first_page: bool, page_type: u8, cells: &[Vec<u8>], right_child: Option<usize>) -> Result<usize, Error> {
+        if on_first_page {
+            write_page(&mut self.first_page, 100, page_type, cells, right_child);
+            Ok(1)
+        } else {
+            let mut page = vec![0u8; WRITE_PAGE_SIZE];
+            write_page(&mut page, 0, page_type, cells, right_child);
+            self.append_page(&page)
+        }
+    }
+
+    // Splits a payload into its local portion and a chain of overflow pages. Returns the
+    // bytes of the cell that follow the cell's header fields.
+    fn payload_cell_bytes(&mut self, payload: &[u8], is_index: bool) -> Result<Vec<u8>, Error> {
+        let local = local_payload_size(WRITE_PAGE_SIZE, payload.len(), is_index);
+        let mut bytes = payload[0..local].to_vec();
+        if local < payload.len() {
+            let chunk_size = WRITE_PAGE_SIZE - 4;
+            let chunks: Vec<&[u8]> = payload[local..].chunks(chunk_size).collect();
+            let first = self.num_pages + 1;
+            let mut page = vec![0u8; WRITE_PAGE_SIZE];
+            for (i, chunk) in chunks.iter().enumerate() {
+                let next = if i < chunks.len() - 1 { (first + i + 1) as u32 } else { 0 };
+                put_u32(&mut page, 0, next);
+                page[4..4 + chunk.len()].copy_from_slice(chunk);
+                for b in page[4 + chunk.len()..].iter_mut() {
+                    *b = 0;
+                }
+                self.append_page(&page)?;
+            }
+            let mut ptr = [0u8; 4];
+            put_u32(&mut ptr, 0, first as u32);
+            bytes.extend_from_slice(&ptr);
+        }
+        Ok(bytes)
+    }
+
+    fn fits(cells: &[Vec<u8>], on_first_page: bool, is_interior: bool) -> bool {
+        let offset = if on_first_page { 100 } else { 0 };
+        let header_size = if is_interior { 12 } else { 8 };
+        let size: usize = cells.iter().map(|c| c.len() + 2).sum();
+        offset + header_size + size <= WRITE_PAGE_SIZE
+    }
+
+    // Adds a (rowid, record) cell to a table b-tree, writing out its leaf page when full.
+    // Cells must be added in rowid order.
+    fn push_table_cell(&mut self, tree: &mut TableBtree, rowid: i64, payload: &[u8]) -> Result<(), Error> {
+        let mut cell = vec![];
+        write_varint(payload.len() as u64, &mut cell);
+        write_varint(rowid as u64, &mut cell);
+        cell.extend(self.payload_cell_bytes(payload, false)?);
+        tree.cells.push(cell);
+        if !Self::fits(&tree.cells, false, false) {
+            let c = tree.cells.pop().unwrap();
+            let page_num = self.put_btree_page(false, TABLE_LEAF, &tree.cells, None)?;
+            tree.children.push((page_num, tree.last_rowid));
+            tree.cells = vec![c];
+        }
+        tree.last_rowid = rowid;
+        Ok(())
+    }
+
+    // Writes the remaining pages of a table b-tree and returns its root page, which is page 1
+    // for the schema table.
+    fn finish_table_btree(&mut self, tree: TableBtree, on_first_page: bool) -> Result<usize, Error> {
+        let mut children = tree.children;
+        if children.is_empty() && Self::fits(&tree.cells, on_first_page, false) {
+            return self.put_btree_page(on_first_page, TABLE_LEAF, &tree.cells, None);
+        }
+        let page_num = self.put_btree_page(false, TABLE_LEAF, &tree.cells, None)?;
+        children.push((page_num, tree.last_rowid));
+
+        // Interior levels. Each child except the last of a page has a cell holding its
+        // largest rowid, and the last is the page's right-most pointer.
+        let cell_for = |child: &(usize, i64)| {
+            let mut cell = vec![0u8; 4];
+            put_u32(&mut cell, 0, child.0 as u32);
+            write_varint(child.1 as u64, &mut cell);
+            cell
+        };
+        loop {
+            let n = children.len();
+            let all_cells: Vec<Vec<u8>> = children[..n - 1].iter().map(|c| cell_for(c)).collect();
+            if Self::fits(&all_cells, on_first_page, true) {
+                return self.put_btree_page(on_first_page, TABLE_INTERIOR, &all_cells, Some(children[n - 1].0));
+            }
+            let mut parents: Vec<(usize, i64)> = vec![];
+            let mut start = 0;
+            while start < n {
+                // children[start..end] become cells and children[end] the right-most pointer
+                let mut end = start;
+                let mut page_cells: Vec<Vec<u8>> = vec![];
+                while end < n - 1 {
+                    page_cells.push(cell_for(&children[end]));
+                    if !Self::fits(&page_cells, false, true) {
+                        page_cells.pop();
+                        break;
+                    }
+                    end += 1;
+                }
+                if end == n - 2 && page_cells.len() > 1 {
+                    // a lone remaining child could not form a page of its own
+                    page_cells.pop();
+                    end -= 1;
+                }
+                let page_num = self.put_btree_page(false, TABLE_INTERIOR, &page_cells, Some(children[end].0))?;
+                parents.push((page_num, children[end].1));
+                start = end + 1;
+            }
+            children = parents;
+        }
+    }
+
+    // Builds an index b-tree from sorted key records and returns its root page.
+    fn build_index_btree(&mut self, keys: Vec<Vec<u8>>) -> Result<usize, Error> {
+        let mut entries = Vec::with_capacity(keys.len());
+        for payload in keys {
+            let mut cell = vec![];
+            write_varint(payload.len() as u64, &mut cell);
+            cell.extend(self.payload_cell_bytes(&payload, true)?);
+            entries.push(cell);
+        }
+        // On interior levels, children[i] is the left child of entries[i] and the last child
+        // is the right-most pointer. The leaf level has no children.
+        let mut children: Vec<usize> = vec![];
+        loop {
+            let is_interior = !children.is_empty();
+            let page_type = if is_interior { INDEX_INTERIOR } else { INDEX_LEAF };
+            let n = entries.len();
+            let all_cells: Vec<Vec<u8>> = (0..n).map(|i| index_cell(&entries, &children, i)).collect();
+            if Self::fits(&all_cells, false, is_interior) {
+                let right = children.last().cloned();
+                return self.put_btree_page(false, page_type, &all_cells, right);
+            }
+            // Each page takes entries until it is full, and the following entry becomes a
+            // divider in the parent level, with the page as its left child.
+            let mut dividers: Vec<Vec<u8>> = vec![];
+            let mut pages: Vec<usize> = vec![];
+            let mut start = 0;
+            while start < n {
+                let mut end = start;
+                let mut page_cells: Vec<Vec<u8>> = vec![];
+                while end < n {
+                    page_cells.push(index_cell(&entries, &children, end));
+                    if !Self::fits(&page_cells, false, is_interior) {
+                        page_cells.pop();
+                        break;
+                    }
+                    end += 1;
+                }
+                if end == n - 1 && page_cells.len() > 1 {
+                    // the last page needs an entry besides the divider
+                    page_cells.pop();
+                    end -= 1;
+                }
+                let right = if is_interior { Some(children[end]) } else { None };
+                let page_num = self.put_btree_page(false, page_type, &page_cells, right)?;
+                pages.push(page_num);
+                if end < n {
+                    dividers.push(entries[end].clone());
+                }
+                start = end + 1;
+            }
+            entries = dividers;
+            children = pages;
+        }
+    }
+}
+
+// The cell of an index entry, preceded on interior levels by its left child page.
+fn index_cell(entries: &[Vec<u8>], children: &[usize], i: usize) -> Vec<u8> {
+    if children.is_empty() { return entries[i].clone(); }
+    let mut cell = vec![0u8; 4];
+    put_u32(&mut cell, 0, children[i] as u32);
+    cell.extend_from_slice(&entries[i]);
+    cell
+}
+
+// Writes the cells of a b-tree page, whose header starts at the offset, placing them at the
+// end of the page in reverse order.
+fn write_page(page: &mut [u8], offset: usize, page_type: u8, cells: &[Vec<u8>], right_child: Option<usize>) {
+    let header_size = if right_child.is_some() { 12 } else { 8 };
+    let mut content_start = WRITE_PAGE_SIZE;
+    for (i, cell) in cells.iter().enumerate() {
+        content_start -= cell.len();
+        page[content_start..content_start + cell.len()].copy_from_slice(cell);
+        put_u16(page, offset + header_size + 2 * i, content_start as u16);
+    }
+    page[offset] = page_type;
+    put_u16(page, offset + 1, 0); // no freeblocks
+    put_u16(page, offset + 3, cells.len() as u16);
+    put_u16(page, offset + 5, if content_start == 65536 { 0 } else { content_start as u16 });
+    page[offset + 7] = 0;
+    if let Some(child) = right_child {
+        put_u32(page, offset + 8, child as u32);
+    }
+}
+
+fn put_u16(buf: &mut [u8], pos: usize, value: u16) {
+    buf[pos] = (value >> 8) as u8;
+    buf[pos + 1] = value as u8;
+}
+
+fn put_u32(buf: &mut [u8], pos: usize, value: u32) {
+    buf[pos] = (value >> 24) as u8;
+    buf[pos + 1] = (value >> 16) as u8;
+    buf[pos + 2] = (value >> 8) as u8;
+    buf[pos + 3] = value as u8;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use std::fs;
+
+    fn temp_file(name: &str) -> String {
+        env::temp_dir().join(name).to_str().unwrap().to_string()
+    }
+
+    // A row of every storage class, whose blobs and text are long enough in some rows to
+    // spill onto overflow pages.
+    fn test_row(i: i64) -> Vec<SqlValue> {
+        let mut seed = (i as u64).wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+        let mut blob = vec![0u8; if i % 100 == 0 { 10_000 } else { (i % 50) as usize }];
+        for b in blob.iter_mut() {
+            seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            *b = (seed >> 56) as u8;
+        }
+        let integer = match i % 7 {
+            0 => 0,
+            1 => 1,
+            2 => -i,
+            3 => i * 1000,
+            4 => i * 1_000_000_000,
+            5 => i64::min_value() + i,
+            _ => i64::max_value() - i,
+        };
+        vec![
+            SqlValue::Integer(i),
+            SqlValue::Integer(integer),
+            SqlValue::Real(i as f64 / 3.0 - 100.0),
+            SqlValue::Text(format!("row {} — été {}", i, "x".repeat(if i % 250 == 0 { 5000 } else { 0 }))),
+            if i % 3 == 0 { SqlValue::Null } else { SqlValue::Blob(blob) },
+        ]
+    }
+
+    const TEST_TABLE_SQL: &'static str = "CREATE TABLE test (id INTEGER PRIMARY KEY AUTOINCREMENT, value INTEGER, real_value DOUBLE, name TEXT UNIQUE, data BLOB)";
+
+    #[test]
+    fn tables_are_read_as_they_were_written() {
+        let file_name = temp_file("wbt_sqlite_tables.sqlite");
+        let rows: Vec<Vec<SqlValue>> = (1..3001).map(|i| test_row(i)).collect();
+        let mut writer = SqliteWriter::new(&file_name).unwrap();
+        writer.application_id = 0x47504B47;
+        writer.user_version = 10200;
+        // rows are sorted by their primary key
+        writer.add_table("test", TEST_TABLE_SQL, rows.iter().rev().cloned().collect()).unwrap();
+        writer.add_table("empty", "CREATE TABLE empty (a TEXT, b REAL)", vec![]).unwrap();
+        // rows without a rowid are numbered from 1
+        writer.add_table("numbered", "CREATE TABLE numbered (a TEXT, b REAL)",
+            vec![vec![SqlValue::Text("a".to_string())], vec![SqlValue::Text("b".to_string()), SqlValue::Real(2.5)]]).unwrap();
+        writer.finish().unwrap();
+
+        let db = SqliteReader::new(&file_name).unwrap();
+        assert_eq!(db.application_id, 0x47504B47);
+        assert_eq!(db.user_version, 10200);
+        assert!(db.has_table("TEST"));
+        assert!(!db.has_table("missing"));
+        assert!(db.schema.iter().any(|e| e.entry_type == "index" && e.name == "sqlite_autoindex_test_1" && e.table_name == "test"));
+
+        let table = db.read_table("test").unwrap();
+        let names: Vec<&str> = table.columns.iter().map(|c| c.name.as_ref()).collect();
+        assert_eq!(names, vec!["id", "value", "real_value", "name", "data"]);
+        assert!(table.columns[0].is_rowid);
+        assert_eq!(table.columns[2].declared_type, "DOUBLE");
+        assert_eq!(table.rows, rows);
+        assert_eq!(table.get_value(9, "NAME"), SqlValue::Text("row 10 — été ".to_string()));
+        assert_eq!(table.get_value(9, "missing"), SqlValue::Null);
+
+        // the sequence table records the largest rowid of the AUTOINCREMENT table
+        let sequence = db.read_table("sqlite_sequence").unwrap();
+        assert_eq!(sequence.rows, vec![vec![SqlValue::Text("test".to_string()), SqlValue::Integer(3000)]]);
+
+        assert_eq!(db.read_table("empty").unwrap().rows.len(), 0);
+        assert_eq!(db.read_table("numbered").unwrap().rows, vec![
+            vec![SqlValue::Text("a".to_string()), SqlValue::Null],
+            vec![SqlValue::Text("b".to_string()), SqlValue::Real(2.5)]]);
+        assert_eq!(db.read_table("missing").unwrap_err().kind(), ErrorKind::NotFound);
+        let _ = fs::remove_file(&file_name);
+    }
+
+    #[test]
+    fn streamed_tables_match_those_added_whole() {
+        let (whole, streamed) = (temp_file("wbt_sqlite_whole.sqlite"), temp_file("wbt_sqlite_streamed.sqlite"));
+        let rows: Vec<Vec<SqlValue>> = (1..20001).map(|i| test_row(i * 2)).collect();
+
+        let mut writer = SqliteWriter::new(&whole).unwrap();
+        writer.add_table("test", TEST_TABLE_SQL, rows.clone()).unwrap();
+        writer.finish().unwrap();
+
+        let mut writer = SqliteWriter::new(&streamed).unwrap();
+        writer.begin_table("test", TEST_TABLE_SQL).unwrap();
+        for row in &rows {
+            writer.add_row(row.clone()).unwrap();
+        }
+        writer.end_table().unwrap();
+        writer.finish().unwrap();
+
+        assert_eq!(fs::read(&whole).unwrap(), fs::read(&streamed).unwrap());
+        let table = SqliteReader::new(&streamed).unwrap().read_table("test").unwrap();
+        assert_eq!(table.rows, rows);
+        let _ = fs::remove_file(&whole);
+        let _ = fs::remove_file(&streamed);
+    }
+
+    #[test]
+    fn rows_must_be_added_in_ascending_rowid_order() {
+        let file_name = temp_file("wbt_sqlite_order.sqlite");
+        let mut writer = SqliteWriter::new(&file_name).unwrap();
+        assert_eq!(writer.add_row(test_row(1)).unwrap_err().kind(), ErrorKind::InvalidInput);
+        assert_eq!(writer.end_table().unwrap_err().kind(), ErrorKind::InvalidInput);
+
+        writer.begin_table("test", TEST_TABLE_SQL).unwrap();
+        assert_eq!(writer.begin_table("other", TEST_TABLE_SQL).unwrap_err().kind(), ErrorKind::InvalidInput);
+        writer.add_row(test_row(5)).unwrap();
+        assert_eq!(writer.add_row(test_row(5)).unwrap_err().kind(), ErrorKind::InvalidInput);
+        assert_eq!(writer.add_row(test_row(4)).unwrap_err().kind(), ErrorKind::InvalidInput);
+        // a row without a rowid follows the largest one
+        let mut row = test_row(6);
+        row[0] = SqlValue::Null;
+        writer.add_row(row).unwrap();
+        writer.end_table().unwrap();
+
+        let duplicates = vec![test_row(1), test_row(2), test_row(1)];
+        assert_eq!(writer.add_table("duplicates", TEST_TABLE_SQL, duplicates).unwrap_err().kind(), ErrorKind::InvalidInput);
+        writer.finish().unwrap();
+
+        let table = SqliteReader::new(&file_name).unwrap().read_table("test").unwrap();
+        assert_eq!(table.rows.len(), 2);
+        assert_eq!(table.get_value(1, "id"), SqlValue::Integer(6));
+        let _ = fs::remove_file(&file_name);
+    }
+
+    #[test]
+    fn varints_round_trip() {
+        let values = [0u64, 1, 127, 128, 240, 2287, 2288, 16383, 16384, 0x00FF_FFFF_FFFF_FFFF, 0x0100_0000_0000_0000, u64::max_value()];
+        for &v in values.iter() {
+            let mut buf = vec![];
+            write_varint(v, &mut buf);
+            assert_eq!(buf.len(), varint_len(v));
+            assert!(buf.len() <= 9);
+            assert_eq!(read_varint(&buf, 0), (v, buf.len()));
+        }
+    }
+
+    #[test]
+    fn columns_are_parsed_from_create_table_statements() {
+        let columns = parse_columns("CREATE TABLE \"my table\" (
+  \"fid\" INTEGER NOT NULL,
+  name TEXT(20) DEFAULT 'a, b',
+  [value] DOUBLE PRECISION,
+  flag BOOLEAN CHECK (flag IN (0, 1)),
+  untyped,
+  CONSTRAINT pk PRIMARY KEY (fid),
+  UNIQUE (name, value))");
+        let names: Vec<&str> = columns.iter().map(|c| c.name.as_ref()).collect();
+        assert_eq!(names, vec!["fid", "name", "value", "flag", "untyped"]);
+        let types: Vec<&str> = columns.iter().map(|c| c.declared_type.as_ref()).collect();
+        assert_eq!(types, vec!["INTEGER", "TEXT(20)", "DOUBLE PRECISION", "BOOLEAN", ""]);
+        assert!(columns[0].is_rowid);
+        assert!(!columns[1].is_rowid);
+
+        assert!(!parse_columns("CREATE TABLE t (id INTEGER PRIMARY KEY DESC, a)")[0].is_rowid);
+        assert!(!parse_columns("CREATE TABLE t (id INT PRIMARY KEY, a)")[0].is_rowid);
+        let sql = "CREATE TABLE t (a TEXT PRIMARY KEY, b UNIQUE, c, UNIQUE (b, c), UNIQUE (b))";
+        assert_eq!(automatic_indexes(sql, &parse_columns(sql)), vec![vec![0], vec![1], vec![1, 2]]);
+    }
+
+    #[test]
+    fn files_that_are_not_databases_are_rejected() {
+        let file_name = temp_file("wbt_sqlite_invalid.sqlite");
+        fs::write(&file_name, vec![0u8; 4096]).unwrap();
+        assert_eq!(SqliteReader::new(&file_name).err().unwrap().kind(), ErrorKind::InvalidData);
+        let _ = fs::remove_file(&file_name);
+    }
+}
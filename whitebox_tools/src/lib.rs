@@ -0,0 +1,33 @@
+/*
+This code is part of the WhiteboxTools geospatial analysis library.
+Authors: Dr. John Lindsay
+Created: March 21, 2018
+Last Modified: March 29, 2018
+License: MIT
+*/
+
+//! WhiteboxTools is an advanced geospatial data analysis engine. Besides the `whitebox_tools`
+//! command-line program, the library can be used directly from Rust. The raster, vector, and
+//! lidar modules read and write the supported data formats, the spatial_ref_system module
+//! transforms coordinates between coordinate reference systems, and the tools are run through
+//! `tools::ToolManager`, either using command-line style arguments with `run_tool` or using
+//! typed arguments with `run_tool_with_args`, which returns the tool's outputs, including
+//! any output rasters that are to be kept in memory, and reports errors as `tools::ToolError`
+//! values. The progress, messages, and warnings of a running tool are sent to the manager's
+//! `tools::ProgressSink`, which may also be used to cancel the tool.
+
+extern crate byteorder;
+extern crate serde;
+extern crate serde_json;
+
+#[macro_use]
+extern crate serde_derive;
+
+pub mod io_utils;
+pub mod lidar;
+pub mod raster;
+pub mod rendering;
+pub mod spatial_ref_system;
+pub mod tools;
+pub mod structures;
+pub mod vector;
@@ -121,6 +121,17 @@ impl LasHeader {
 
         // read the file's header bytes into a buffer
         f.read(&mut buffer)?;
+        let (header, _) = LasHeader::from_bytes(buffer)?;
+        Ok(header)
+    }
+
+    /// Reads the header from the bytes at the start of a LAS file. LAZ files flag the point
+    /// format of compressed points with its two highest bits; these are removed from the
+    /// point format and returned as a boolean indicating whether the points are compressed.
+    pub fn from_bytes(mut buffer: Vec<u8>) -> Result<(LasHeader, bool), Error> {
+        if buffer.len() < 375 {
+            buffer.resize(375, 0u8);
+        }
         let mut header: LasHeader = Default::default();
 
         header.project_id_used = true;
@@ -167,6 +178,8 @@ impl LasHeader {
         header.offset_to_points = bor.read_u32();
         header.number_of_vlrs = bor.read_u32();
         header.point_format = bor.read_u8();
+        let is_compressed = header.point_format & 0xC0 != 0;
+        header.point_format &= 0x3F;
         header.point_record_length = bor.read_u16();
         header.number_of_points_old = bor.read_u32();
 
@@ -188,6 +201,9 @@ impl LasHeader {
 
         if header.version_major == 1 && header.version_minor >= 3 {
             header.waveform_data_start = bor.read_u64();
+        }
+        if header.version_major == 1 && header.version_minor >= 4 {
+            // the extended VLRs and 64-bit point counts were added in LAS 1.4
             header.offset_to_ex_vlrs = bor.read_u64();
             header.number_of_extended_vlrs = bor.read_u32();
             header.number_of_points = bor.read_u64();
@@ -205,6 +221,6 @@ impl LasHeader {
             }
         }
             
-        Ok(header)
+        Ok((header, is_compressed))
     }
 }
\ No newline at end of file
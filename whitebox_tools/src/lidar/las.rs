@@ -2,12 +2,13 @@
 extern crate time;
 extern crate zip;
 
+use std::io;
 use std::io::prelude::*;
-use std::io::{Error, ErrorKind};
+use std::io::{BufReader, Cursor, Error, ErrorKind, SeekFrom};
 use std::fmt;
 use std::f64;
 use std::io::BufWriter;
-use std::fs::File;
+use std::fs::{File, OpenOptions};
 use std::fs;
 use std::mem;
 use std::path::Path;
@@ -15,11 +16,13 @@ use std::str;
 use lidar::header::LasHeader;
 use lidar::point_data::{ PointData, ColourData, WaveformPacket };
 use lidar::vlr::Vlr;
+use lidar::laz::{LazReader, LazVlr, LazWriter};
 use raster::geotiff::geokeys::GeoKeys;
 use structures::BoundingBox;
 use io_utils::{ByteOrderReader, Endianness};
 use std::ops::Index;
 use std::io::Seek;
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
 use self::zip::result::ZipResult;
 use self::zip::CompressionMethod;
 use self::zip::read::{ ZipArchive, ZipFile };
@@ -32,11 +35,15 @@ pub struct LasFile {
     file_mode: String,
     pub header: LasHeader,
     pub vlr_data: Vec<Vlr>,
+    pub evlr_data: Vec<Vlr>,
     point_data: Vec<PointData>,
     // point_buffer_size: usize,
     gps_data: Vec<f64>,
     colour_data: Vec<ColourData>,
     waveform_data: Vec<WaveformPacket>,
+    // the bytes that follow the standard fields of each point record, which are described
+    // by the Extra Bytes VLR
+    extra_bytes: Vec<u8>,
     pub geokeys: GeoKeys,
     // starting_point: usize,
     header_is_set: bool,
@@ -84,10 +91,13 @@ impl LasFile {
 
         output.add_header(input.header.clone());
 
-        // Copy the VLRs
+        // Copy the VLRs and EVLRs
         for i in 0..(input.header.number_of_vlrs as usize) {
             output.add_vlr(input.vlr_data[i].clone());
         }
+        for evlr in &input.evlr_data {
+            output.add_evlr(evlr.clone());
+        }
 
         output
     }
@@ -97,10 +107,12 @@ impl LasFile {
         self.header = header;
 
         self.header.number_of_vlrs = 0;
+        self.header.number_of_extended_vlrs = 0;
+        self.header.offset_to_ex_vlrs = 0;
         self.header.number_of_points = 0;
 
         self.header.version_major = 1;
-        self.header.version_minor = 3;
+        self.header.version_minor = 4;
     		// These must be set by the data
         self.header.min_x = f64::INFINITY;
         self.header.max_x = f64::NEG_INFINITY;
@@ -112,6 +124,7 @@ impl LasFile {
 		self.header.system_id = "WhiteboxTools by John Lindsay   ".to_string();
 		self.header.generating_software = "WhiteboxTools                   ".to_string();
 		self.header.number_of_points_by_return_old = [0, 0, 0, 0, 0];
+        self.header.number_of_points_by_return = [0u64; 15];
 
 		self.header.x_scale_factor = 0.0001;
 		self.header.y_scale_factor = 0.0001;
@@ -130,125 +143,81 @@ impl LasFile {
         self.header.number_of_vlrs += 1;
     }
 
+    /// Adds an extended VLR, which is written after the point records. Unlike a VLR, the
+    /// binary data of an EVLR may be larger than 65,535 bytes.
+    pub fn add_evlr(&mut self, evlr: Vlr) {
+        if self.file_mode == "r" { return; }
+        if !self.header_is_set {
+            panic!("The header of a LAS file must be added before any EVLRs. Please see add_header().");
+        }
+        self.evlr_data.push(evlr);
+        self.header.number_of_extended_vlrs += 1;
+    }
+
+    /// Returns the OGC WKT coordinate reference system of the file, if it has one.
+    pub fn get_wkt(&self) -> Option<String> {
+        self.vlr_data.iter().chain(self.evlr_data.iter())
+            .find(|vlr| vlr.is_wkt())
+            .map(|vlr| vlr.get_wkt())
+    }
+
+    /// Sets the coordinate reference system of the file using OGC WKT, replacing any existing
+    /// WKT or GeoTIFF CRS records. LAS 1.4 requires WKT for the 64-bit point formats.
+    pub fn set_wkt(&mut self, wkt: &str) {
+        if self.file_mode == "r" { return; }
+        self.vlr_data.retain(|vlr| !vlr.is_crs());
+        self.evlr_data.retain(|vlr| !vlr.is_crs());
+        self.geokeys = Default::default();
+
+        let vlr = Vlr::new_wkt(wkt);
+        if vlr.binary_data.len() > u16::max_value() as usize {
+            self.evlr_data.push(vlr);
+        } else {
+            self.vlr_data.push(vlr);
+        }
+        self.header.number_of_vlrs = self.vlr_data.len() as u32;
+        self.header.number_of_extended_vlrs = self.evlr_data.len() as u32;
+        self.header.global_encoding.value |= 0b0001_0000u16;
+    }
+
     pub fn add_point_record(&mut self, point: LidarPointRecord) {
         if self.file_mode == "r" { return; }
         if !self.header_is_set {
             panic!("The header of a LAS file must be added before any point records. Please see add_header().");
         }
-        let mut which_return = 0_usize;
-        let x: f64;
-        let y: f64;
-        let z: f64;
-        match point {
-            LidarPointRecord::PointRecord0 { point_data }  => {
-                self.point_data.push(point_data);
-                x = point_data.x;
-                y = point_data.y;
-                z = point_data.z;
-                which_return = point_data.return_number() as usize;
-            },
-            LidarPointRecord::PointRecord1 { point_data, gps_data } => {
-                self.point_data.push(point_data);
-                x = point_data.x;
-                y = point_data.y;
-                z = point_data.z;
-                which_return = point_data.return_number() as usize;
-                self.gps_data.push(gps_data);
-            },
-            LidarPointRecord::PointRecord2 { point_data, colour_data } => {
-                self.point_data.push(point_data);
-                x = point_data.x;
-                y = point_data.y;
-                z = point_data.z;
-                which_return = point_data.return_number() as usize;
-                self.colour_data.push(colour_data);
-            },
-            LidarPointRecord::PointRecord3 { point_data, gps_data, colour_data } => {
-                self.point_data.push(point_data);
-                x = point_data.x;
-                y = point_data.y;
-                z = point_data.z;
-                which_return = point_data.return_number() as usize;
-                self.gps_data.push(gps_data);
-                self.colour_data.push(colour_data);
-            },
-            LidarPointRecord::PointRecord4 { point_data, gps_data, wave_packet } => {
-                self.point_data.push(point_data);
-                x = point_data.x;
-                y = point_data.y;
-                z = point_data.z;
-                which_return = point_data.return_number() as usize;
-                self.gps_data.push(gps_data);
-                self.waveform_data.push(wave_packet);
-            },
-            LidarPointRecord::PointRecord5 { point_data, gps_data, colour_data, wave_packet } => {
-                self.point_data.push(point_data);
-                x = point_data.x;
-                y = point_data.y;
-                z = point_data.z;
-                which_return = point_data.return_number() as usize;
-                self.gps_data.push(gps_data);
-                self.colour_data.push(colour_data);
-                self.waveform_data.push(wave_packet);
-            },
-            LidarPointRecord::PointRecord6 { point_data, gps_data } => {
-                self.point_data.push(point_data);
-                x = point_data.x;
-                y = point_data.y;
-                z = point_data.z;
-                which_return = point_data.return_number() as usize;
-                self.gps_data.push(gps_data);
-            },
-            LidarPointRecord::PointRecord7 { point_data, gps_data, colour_data } => {
-                self.point_data.push(point_data);
-                x = point_data.x;
-                y = point_data.y;
-                z = point_data.z;
-                which_return = point_data.return_number() as usize;
-                self.gps_data.push(gps_data);
-                self.colour_data.push(colour_data);
-            },
-            LidarPointRecord::PointRecord8 { point_data, gps_data, colour_data } => {
-                self.point_data.push(point_data);
-                x = point_data.x;
-                y = point_data.y;
-                z = point_data.z;
-                which_return = point_data.return_number() as usize;
-                self.gps_data.push(gps_data);
-                self.colour_data.push(colour_data);
-            },
-            LidarPointRecord::PointRecord9 { point_data, gps_data, wave_packet } => {
-                self.point_data.push(point_data);
-                x = point_data.x;
-                y = point_data.y;
-                z = point_data.z;
-                which_return = point_data.return_number() as usize;
-                self.gps_data.push(gps_data);
-                self.waveform_data.push(wave_packet);
-            },
-            LidarPointRecord::PointRecord10 { point_data, gps_data, colour_data, wave_packet } => {
-                self.point_data.push(point_data);
-                x = point_data.x;
-                y = point_data.y;
-                z = point_data.z;
-                which_return = point_data.return_number() as usize;
-                self.gps_data.push(gps_data);
-                self.colour_data.push(colour_data);
-                self.waveform_data.push(wave_packet);
-            },
-        }
+        update_header_stats(&mut self.header, &point.point_data());
+        self.push_point_record(point);
+    }
 
-        if x < self.header.min_x { self.header.min_x = x; }
-        if x > self.header.max_x { self.header.max_x = x; }
-        if y < self.header.min_y { self.header.min_y = y; }
-        if y > self.header.max_y { self.header.max_y = y; }
-        if z < self.header.min_z { self.header.min_z = z; }
-        if z > self.header.max_z { self.header.max_z = z; }
+    /// Adds a point record along with the bytes that follow its standard fields, which are
+    /// described by the Extra Bytes VLR of the file. The extra bytes are only written if they
+    /// are provided for every point.
+    pub fn add_point_record_with_extra_bytes(&mut self, point: LidarPointRecord, extra_bytes: &[u8]) {
+        if self.file_mode == "r" { return; }
+        self.add_point_record(point);
+        self.extra_bytes.extend_from_slice(extra_bytes);
+    }
 
-        self.header.number_of_points += 1;
-        if which_return == 0 { which_return = 1; }
-        if which_return <= 5 {
-            self.header.number_of_points_by_return[which_return-1] += 1;
+    /// Returns the extra bytes of a point record, which are empty if the file has none.
+    pub fn get_extra_bytes(&self, index: usize) -> &[u8] {
+        let num_points = self.point_data.len();
+        if num_points == 0 || self.extra_bytes.len() % num_points != 0 {
+            return &[];
+        }
+        let length = self.extra_bytes.len() / num_points;
+        &self.extra_bytes[index * length..(index + 1) * length]
+    }
+
+    fn push_point_record(&mut self, point: LidarPointRecord) {
+        self.point_data.push(point.point_data());
+        if let Some(gps_time) = point.gps_time() {
+            self.gps_data.push(gps_time);
+        }
+        if let Some(colour_data) = point.colour_data() {
+            self.colour_data.push(colour_data);
+        }
+        if let Some(wave_packet) = point.waveform_packet() {
+            self.waveform_data.push(wave_packet);
         }
     }
 
@@ -375,445 +344,68 @@ impl LasFile {
                 f.read(&mut buffer)?;
                 buffer
             },
-            true => {
-                let file = File::open(&self.file_name)?;
-                let mut zip = (zip::ZipArchive::new(file))?;
-                let mut f = zip.by_index(0).unwrap();
-                if !f.name().to_lowercase().ends_with(".las") {
-                    return Err(Error::new(ErrorKind::InvalidData,
-                     "The data file contained within zipped archive does not have the proper 'las' extension."))
-                }
-                match f.compression() {
-                    CompressionMethod::Stored | CompressionMethod::Deflated | CompressionMethod::Bzip2 => (),
-                    _ => return Err(Error::new(ErrorKind::InvalidData, 
-                    "Either the file is formatted incorrectly or it is an unsupported compression type.")),
-                }
-                let file_size: usize = f.size() as usize;
-                let mut buffer = vec![0; file_size];
-
-                // read the file's bytes into a buffer
-                match f.read_exact(&mut buffer) {
-                    Err(e) => return Err(e),
-                    Ok(()) => (),
-                }
-                buffer
-            },
+            true => read_zipped_file(&self.file_name)?,
         };
 
-        self.header.project_id_used = true;
-        self.header.version_major = buffer[24];
-        self.header.version_minor = buffer[25];
-        if self.header.version_major < 1 || self.header.version_major > 2 || self.header.version_minor > 5 {
-            // There's something wrong. It could be that the project ID values, which are optional,
-            // are not included in the header.
-            self.header.version_major = buffer[8];
-            self.header.version_minor = buffer[9];
-            if self.header.version_major < 1 || self.header.version_major > 2 || self.header.version_minor > 5 {
-                // There's something very wrong. Throw an error.
-                return Err(Error::new(ErrorKind::Other, "Either the file is formatted incorrectly or it is an unsupported LAS version."));
-            }
-            self.header.project_id_used = false;
-        }
+        let header_length = buffer.len().min(375);
+        let (header, is_compressed) = LasHeader::from_bytes(buffer[0..header_length].to_vec())?;
+        self.header = header;
 
         let mut bor = ByteOrderReader::new(buffer, Endianness::LittleEndian);
-        
-        bor.pos = 0;
-        self.header.file_signature = bor.read_utf8(4);
-        if self.header.file_signature != "LASF" {
-            return Err(Error::new(ErrorKind::Other, "Either the file is formatted incorrectly or it is an unsupported LAS version."));
-        }
-        self.header.file_source_id = bor.read_u16();
-        let ge_val = bor.read_u16();
-        self.header.global_encoding = GlobalEncodingField { value: ge_val};
-        if self.header.project_id_used {
-            self.header.project_id1 = bor.read_u32();
-            self.header.project_id2 = bor.read_u16();
-            self.header.project_id3 = bor.read_u16();
-            for i in 0..8 {
-                self.header.project_id4[i] = bor.read_u8();
-            }
-        }
-        // The version major and minor are read earlier.
-        // Two bytes that must be added to the offset here.
-        bor.pos += 2;
-        self.header.system_id = bor.read_utf8(32); 
-        self.header.generating_software = bor.read_utf8(32);
-        self.header.file_creation_day = bor.read_u16();
-        self.header.file_creation_year = bor.read_u16();
-        self.header.header_size = bor.read_u16();
-        self.header.offset_to_points = bor.read_u32();
-        self.header.number_of_vlrs = bor.read_u32();
-        self.header.point_format = bor.read_u8();
-        self.header.point_record_length = bor.read_u16();
-        self.header.number_of_points_old = bor.read_u32();
-
-        for i in 0..5 {
-            self.header.number_of_points_by_return_old[i] = bor.read_u32();
-        }
-        self.header.x_scale_factor = bor.read_f64();
-        self.header.y_scale_factor = bor.read_f64();
-        self.header.z_scale_factor = bor.read_f64();
-        self.header.x_offset = bor.read_f64();
-        self.header.y_offset = bor.read_f64();
-        self.header.z_offset = bor.read_f64();
-        self.header.max_x = bor.read_f64();
-        self.header.min_x = bor.read_f64();
-        self.header.max_y = bor.read_f64();
-        self.header.min_y = bor.read_f64();
-        self.header.max_z = bor.read_f64();
-        self.header.min_z = bor.read_f64();
-
-        if self.header.version_major == 1 && self.header.version_minor >= 3 {
-            self.header.waveform_data_start = bor.read_u64();
-            self.header.offset_to_ex_vlrs = bor.read_u64();
-            self.header.number_of_extended_vlrs = bor.read_u32();
-            self.header.number_of_points = bor.read_u64();
-            for i in 0..15 {
-                self.header.number_of_points_by_return[i] = bor.read_u64();
-            }
-        }
-
-        if self.header.number_of_points_old != 0 { 
-            self.header.number_of_points = self.header.number_of_points_old as u64;
-            for i in 0..5 {
-                if self.header.number_of_points_by_return_old[i] as u64 > self.header.number_of_points_by_return[i] {
-                    self.header.number_of_points_by_return[i] = self.header.number_of_points_by_return_old[i] as u64;
-                }
-            }
-        }
 
         ///////////////////////
         // Read the VLR data //
         ///////////////////////
         bor.seek(self.header.header_size as usize);
-        for _ in 0..self.header.number_of_vlrs {
-            let mut vlr: Vlr = Default::default();
-            vlr.reserved = bor.read_u16();
-            vlr.user_id = bor.read_utf8(16);
-            vlr.record_id = bor.read_u16();
-            vlr.record_length_after_header = bor.read_u16();
-            vlr.description = bor.read_utf8(32);
-            // get the byte data
-            for _ in 0..vlr.record_length_after_header {
-                vlr.binary_data.push(bor.read_u8());
-            }
-            
-            if vlr.record_id == 34_735 {
-                self.geokeys.add_key_directory(&vlr.binary_data);
-            } else if vlr.record_id == 34_736 {
-                self.geokeys.add_double_params(&vlr.binary_data);
-            } else if vlr.record_id == 34_737 {
-                self.geokeys.add_ascii_params(&vlr.binary_data);
+        let laz_vlr = read_vlrs(&mut bor, &mut self.header, &mut self.vlr_data, &mut self.geokeys)?;
+
+        ////////////////////////
+        // Read the EVLR data //
+        ////////////////////////
+        if self.header.number_of_extended_vlrs > 0 && self.header.offset_to_ex_vlrs > self.header.offset_to_points as u64
+            && self.header.offset_to_ex_vlrs < bor.buffer.len() as u64 {
+            let start = self.header.offset_to_ex_vlrs as usize;
+            self.evlr_data = read_evlrs(&mut Cursor::new(&bor.buffer[start..]), self.header.number_of_extended_vlrs)?;
+        } else {
+            self.header.number_of_extended_vlrs = 0;
+        }
+
+        if is_compressed && self.file_mode != "rh" {
+            // Decompress the points and put them in place of the compressed point data, so
+            // that they can be read in the same way as those of a LAS file.
+            let laz_vlr = match laz_vlr {
+                Some(v) => v,
+                None => return Err(Error::new(ErrorKind::InvalidData, "The compressed LAZ file does not contain a LASzip VLR.")),
+            };
+            let record_length = self.header.point_record_length as usize;
+            let num_points = self.header.number_of_points as usize;
+            let mut points = vec![0u8; record_length * num_points];
+            {
+                let mut reader = LazReader::new(Cursor::new(&bor.buffer[..]), &laz_vlr,
+                    self.header.offset_to_points as u64, num_points as u64, record_length)?;
+                for point in points.chunks_mut(record_length) {
+                    reader.read_point(point)?;
+                }
             }
-            self.vlr_data.push(vlr);
+            bor.buffer.truncate(self.header.offset_to_points as usize);
+            bor.buffer.extend_from_slice(&points);
         }
 
         if self.file_mode != "rh" { // file_mode = "rh" does not read points, only the header.
             /////////////////////////
             // Read the point data //
             /////////////////////////
-            
-            // Intensity and userdata are both optional. Figure out if they need to be read.
-            // The only way to do this is to compare the point record length by point format
-            let rec_lengths = [ [20_u16, 18_u16, 19_u16, 17_u16],
-                                [28_u16, 26_u16, 27_u16, 25_u16],
-                                [26_u16, 24_u16, 25_u16, 23_u16],
-                                [34_u16, 32_u16, 33_u16, 31_u16],
-                                [57_u16, 55_u16, 56_u16, 54_u16],
-                                [63_u16, 61_u16, 62_u16, 60_u16],
-                                [30_u16, 28_u16, 29_u16, 27_u16],
-                                [36_u16, 34_u16, 35_u16, 33_u16],
-                                [38_u16, 36_u16, 37_u16, 35_u16],
-                                [59_u16, 57_u16, 58_u16, 56_u16],
-                                [67_u16, 65_u16, 66_u16, 64_u16] ];
-
-            if self.header.point_record_length == rec_lengths[self.header.point_format as usize][0] {
-                self.use_point_intensity = true;
-                self.use_point_userdata = true;
-            } else if self.header.point_record_length == rec_lengths[self.header.point_format as usize][1] {
-                self.use_point_intensity = false;
-                self.use_point_userdata = true;
-            } else if self.header.point_record_length == rec_lengths[self.header.point_format as usize][2] {
-                self.use_point_intensity = true;
-                self.use_point_userdata = false;
-            } else if self.header.point_record_length == rec_lengths[self.header.point_format as usize][3] {
-                self.use_point_intensity = false;
-                self.use_point_userdata = false;
-            }
-
-            if self.header.point_format == 0 {
-                for i in 0..self.header.number_of_points {
-                    bor.seek(self.header.offset_to_points as usize + (i as usize) * (self.header.point_record_length as usize));
-                    let mut p: PointData = Default::default();
-                    p.x = bor.read_u32() as f64 * self.header.x_scale_factor + self.header.x_offset;
-                    p.y = bor.read_u32() as f64 * self.header.y_scale_factor + self.header.y_offset;
-                    p.z = bor.read_u32() as f64 * self.header.z_scale_factor + self.header.z_offset;
-                    if self.use_point_intensity { p.intensity = bor.read_u16(); }
-                    p.point_bit_field = bor.read_u8();
-                    p.class_bit_field = bor.read_u8();
-                    p.scan_angle = bor.read_i8() as i16;
-                    if self.use_point_userdata { p.user_data = bor.read_u8(); }
-                    p.point_source_id = bor.read_u16();
-                    self.point_data.push(p);
-                }
-            } else if self.header.point_format == 1 {
-                for i in 0..self.header.number_of_points {
-                    bor.seek(self.header.offset_to_points as usize + (i as usize) * (self.header.point_record_length as usize));
-                    let mut p: PointData = Default::default();
-                    p.x = bor.read_u32() as f64 * self.header.x_scale_factor + self.header.x_offset;
-                    p.y = bor.read_u32() as f64 * self.header.y_scale_factor + self.header.y_offset;
-                    p.z = bor.read_u32() as f64 * self.header.z_scale_factor + self.header.z_offset;
-                    if self.use_point_intensity { p.intensity = bor.read_u16(); }
-                    p.point_bit_field = bor.read_u8();
-                    p.class_bit_field = bor.read_u8();
-                    p.scan_angle = bor.read_i8() as i16;
-                    if self.use_point_userdata { p.user_data = bor.read_u8(); }
-                    p.point_source_id = bor.read_u16();
-                    self.point_data.push(p);
-                    // read the GPS data
-                    self.gps_data.push(bor.read_f64());
-                }
-            } else if self.header.point_format == 2 {
-                for i in 0..self.header.number_of_points {
-                    bor.seek(self.header.offset_to_points as usize + (i as usize) * (self.header.point_record_length as usize));
-                    let mut p: PointData = Default::default();
-                    p.x = bor.read_u32() as f64 * self.header.x_scale_factor + self.header.x_offset;
-                    p.y = bor.read_u32() as f64 * self.header.y_scale_factor + self.header.y_offset;
-                    p.z = bor.read_u32() as f64 * self.header.z_scale_factor + self.header.z_offset;
-                    if self.use_point_intensity { p.intensity = bor.read_u16(); }
-                    p.point_bit_field = bor.read_u8();
-                    p.class_bit_field = bor.read_u8();
-                    p.scan_angle = bor.read_i8() as i16;
-                    if self.use_point_userdata { p.user_data = bor.read_u8(); }
-                    p.point_source_id = bor.read_u16();
-                    self.point_data.push(p);
-                    // read the RGB data
-                    let mut rgb: ColourData = Default::default();
-                    rgb.red = bor.read_u16();
-                    rgb.green = bor.read_u16();
-                    rgb.blue = bor.read_u16();
-                    self.colour_data.push(rgb);
-                }
-            } else if self.header.point_format == 3 {
-                for i in 0..self.header.number_of_points {
-                    bor.seek(self.header.offset_to_points as usize + (i as usize) * (self.header.point_record_length as usize));
-                    let mut p: PointData = Default::default();
-                    p.x = bor.read_u32() as f64 * self.header.x_scale_factor + self.header.x_offset;
-                    p.y = bor.read_u32() as f64 * self.header.y_scale_factor + self.header.y_offset;
-                    p.z = bor.read_u32() as f64 * self.header.z_scale_factor + self.header.z_offset;
-                    if self.use_point_intensity { p.intensity = bor.read_u16(); }
-                    p.point_bit_field = bor.read_u8();
-                    p.class_bit_field = bor.read_u8();
-                    p.scan_angle = bor.read_i8() as i16;
-                    if self.use_point_userdata { p.user_data = bor.read_u8(); }
-                    p.point_source_id = bor.read_u16();
-                    self.point_data.push(p);
-                    // read the GPS data
-                    self.gps_data.push(bor.read_f64());
-                    // read the RGB data
-                    let mut rgb: ColourData = Default::default();
-                    rgb.red = bor.read_u16();
-                    rgb.green = bor.read_u16();
-                    rgb.blue = bor.read_u16();
-                    self.colour_data.push(rgb);
-                }
-            } else if self.header.point_format == 4 {
-                for i in 0..self.header.number_of_points {
-                    bor.seek(self.header.offset_to_points as usize + (i as usize) * (self.header.point_record_length as usize));
-                    let mut p: PointData = Default::default();
-                    p.x = bor.read_u32() as f64 * self.header.x_scale_factor + self.header.x_offset;
-                    p.y = bor.read_u32() as f64 * self.header.y_scale_factor + self.header.y_offset;
-                    p.z = bor.read_u32() as f64 * self.header.z_scale_factor + self.header.z_offset;
-                    if self.use_point_intensity { p.intensity = bor.read_u16(); }
-                    p.point_bit_field = bor.read_u8();
-                    p.class_bit_field = bor.read_u8();
-                    p.scan_angle = bor.read_i8() as i16;
-                    if self.use_point_userdata { p.user_data = bor.read_u8(); }
-                    p.point_source_id = bor.read_u16();
-                    self.point_data.push(p);
-                    // read the GPS data
-                    self.gps_data.push(bor.read_f64());
-                    // read the waveform data
-                    let mut wfp: WaveformPacket = Default::default();
-                    wfp.packet_descriptor_index = bor.read_u8();
-                    wfp.offset_to_waveform_data = bor.read_u64();
-                    wfp.waveform_packet_size = bor.read_u32();
-                    wfp.ret_point_waveform_loc = bor.read_f32();
-                    wfp.xt = bor.read_f32();
-                    wfp.yt = bor.read_f32();
-                    wfp.zt = bor.read_f32();
-                    self.waveform_data.push(wfp);
-                }
-            } else if self.header.point_format == 5 {
-                for i in 0..self.header.number_of_points {
-                    bor.seek(self.header.offset_to_points as usize + (i as usize) * (self.header.point_record_length as usize));
-                    let mut p: PointData = Default::default();
-                    p.x = bor.read_u32() as f64 * self.header.x_scale_factor + self.header.x_offset;
-                    p.y = bor.read_u32() as f64 * self.header.y_scale_factor + self.header.y_offset;
-                    p.z = bor.read_u32() as f64 * self.header.z_scale_factor + self.header.z_offset;
-                    if self.use_point_intensity { p.intensity = bor.read_u16(); }
-                    p.point_bit_field = bor.read_u8();
-                    p.class_bit_field = bor.read_u8();
-                    p.scan_angle = bor.read_i8() as i16;
-                    if self.use_point_userdata { p.user_data = bor.read_u8(); }
-                    p.point_source_id = bor.read_u16();
-                    self.point_data.push(p);
-                    // read the GPS data
-                    self.gps_data.push(bor.read_f64());
-                    // read the RGB data
-                    let mut rgb: ColourData = Default::default();
-                    rgb.red = bor.read_u16();
-                    rgb.green = bor.read_u16();
-                    rgb.blue = bor.read_u16();
-                    self.colour_data.push(rgb);
-                    // read the waveform data
-                    let mut wfp: WaveformPacket = Default::default();
-                    wfp.packet_descriptor_index = bor.read_u8();
-                    wfp.offset_to_waveform_data = bor.read_u64();
-                    wfp.waveform_packet_size = bor.read_u32();
-                    wfp.ret_point_waveform_loc = bor.read_f32();
-                    wfp.xt = bor.read_f32();
-                    wfp.yt = bor.read_f32();
-                    wfp.zt = bor.read_f32();
-                    self.waveform_data.push(wfp);
-                }
-            } else if self.header.point_format == 6 { // 64-bit
-                for i in 0..self.header.number_of_points {
-                    bor.seek(self.header.offset_to_points as usize + (i as usize) * (self.header.point_record_length as usize));
-                    let mut p: PointData = Default::default();
-                    p.is_64bit = true;
-                    p.x = bor.read_u32() as f64 * self.header.x_scale_factor + self.header.x_offset;
-                    p.y = bor.read_u32() as f64 * self.header.y_scale_factor + self.header.y_offset;
-                    p.z = bor.read_u32() as f64 * self.header.z_scale_factor + self.header.z_offset;
-                    if self.use_point_intensity { p.intensity = bor.read_u16(); }
-                    p.point_bit_field = bor.read_u8();
-                    p.class_bit_field = bor.read_u8();
-                    p.classification = bor.read_u8();
-                    if self.use_point_userdata { p.user_data = bor.read_u8(); }
-                    p.scan_angle = bor.read_i16();
-                    p.point_source_id = bor.read_u16();
-                    self.point_data.push(p);
-                    // read the GPS data
-                    self.gps_data.push(bor.read_f64());
-                }
-            } else if self.header.point_format == 7 { // 64-bit
-                for i in 0..self.header.number_of_points {
-                    bor.seek(self.header.offset_to_points as usize + (i as usize) * (self.header.point_record_length as usize));
-                    let mut p: PointData = Default::default();
-                    p.is_64bit = true;
-                    p.x = bor.read_u32() as f64 * self.header.x_scale_factor + self.header.x_offset;
-                    p.y = bor.read_u32() as f64 * self.header.y_scale_factor + self.header.y_offset;
-                    p.z = bor.read_u32() as f64 * self.header.z_scale_factor + self.header.z_offset;
-                    if self.use_point_intensity { p.intensity = bor.read_u16(); }
-                    p.point_bit_field = bor.read_u8();
-                    p.class_bit_field = bor.read_u8();
-                    p.classification = bor.read_u8();
-                    if self.use_point_userdata { p.user_data = bor.read_u8(); }
-                    p.scan_angle = bor.read_i16();
-                    p.point_source_id = bor.read_u16();
-                    self.point_data.push(p);
-                    // read the GPS data
-                    self.gps_data.push(bor.read_f64());
-                    // read the RGB data
-                    let mut rgb: ColourData = Default::default();
-                    rgb.red = bor.read_u16();
-                    rgb.green = bor.read_u16();
-                    rgb.blue = bor.read_u16();
-                    self.colour_data.push(rgb);
-                }
-            } else if self.header.point_format == 8 { // 64-bit
-                // adds a NIR band to Point Format 7
-                for i in 0..self.header.number_of_points {
-                    bor.seek(self.header.offset_to_points as usize + (i as usize) * (self.header.point_record_length as usize));
-                    let mut p: PointData = Default::default();
-                    p.is_64bit = true;
-                    p.x = bor.read_u32() as f64 * self.header.x_scale_factor + self.header.x_offset;
-                    p.y = bor.read_u32() as f64 * self.header.y_scale_factor + self.header.y_offset;
-                    p.z = bor.read_u32() as f64 * self.header.z_scale_factor + self.header.z_offset;
-                    if self.use_point_intensity { p.intensity = bor.read_u16(); }
-                    p.point_bit_field = bor.read_u8();
-                    p.class_bit_field = bor.read_u8();
-                    p.classification = bor.read_u8();
-                    if self.use_point_userdata { p.user_data = bor.read_u8(); }
-                    p.scan_angle = bor.read_i16();
-                    p.point_source_id = bor.read_u16();
-                    self.point_data.push(p);
-                    // read the GPS data
-                    self.gps_data.push(bor.read_f64());
-                    // read the RGBNIR data
-                    let mut rgb: ColourData = Default::default();
-                    rgb.red = bor.read_u16();
-                    rgb.green = bor.read_u16();
-                    rgb.blue = bor.read_u16();
-                    rgb.nir = bor.read_u16();
-                    self.colour_data.push(rgb);
-                }
-            } else if self.header.point_format == 9 { // 64-bit
-                // adds waveform packets to Point Format 6
-                for i in 0..self.header.number_of_points {
-                    bor.seek(self.header.offset_to_points as usize + (i as usize) * (self.header.point_record_length as usize));
-                    let mut p: PointData = Default::default();
-                    p.is_64bit = true;
-                    p.x = bor.read_u32() as f64 * self.header.x_scale_factor + self.header.x_offset;
-                    p.y = bor.read_u32() as f64 * self.header.y_scale_factor + self.header.y_offset;
-                    p.z = bor.read_u32() as f64 * self.header.z_scale_factor + self.header.z_offset;
-                    if self.use_point_intensity { p.intensity = bor.read_u16(); }
-                    p.point_bit_field = bor.read_u8();
-                    p.class_bit_field = bor.read_u8();
-                    p.classification = bor.read_u8();
-                    if self.use_point_userdata { p.user_data = bor.read_u8(); }
-                    p.scan_angle = bor.read_i16();
-                    p.point_source_id = bor.read_u16();
-                    self.point_data.push(p);
-                    // read the GPS data
-                    self.gps_data.push(bor.read_f64());
-                    // read the waveform data
-                    let mut wfp: WaveformPacket = Default::default();
-                    wfp.packet_descriptor_index = bor.read_u8();
-                    wfp.offset_to_waveform_data = bor.read_u64();
-                    wfp.waveform_packet_size = bor.read_u32();
-                    wfp.ret_point_waveform_loc = bor.read_f32();
-                    wfp.xt = bor.read_f32();
-                    wfp.yt = bor.read_f32();
-                    wfp.zt = bor.read_f32();
-                    self.waveform_data.push(wfp);
-                }
-            } else if self.header.point_format == 10 { // 64-bit
-                // Everythin in one record
-                for i in 0..self.header.number_of_points {
-                    bor.seek(self.header.offset_to_points as usize + (i as usize) * (self.header.point_record_length as usize));
-                    let mut p: PointData = Default::default();
-                    p.is_64bit = true;
-                    p.x = bor.read_u32() as f64 * self.header.x_scale_factor + self.header.x_offset;
-                    p.y = bor.read_u32() as f64 * self.header.y_scale_factor + self.header.y_offset;
-                    p.z = bor.read_u32() as f64 * self.header.z_scale_factor + self.header.z_offset;
-                    if self.use_point_intensity { p.intensity = bor.read_u16(); }
-                    p.point_bit_field = bor.read_u8();
-                    p.class_bit_field = bor.read_u8();
-                    p.classification = bor.read_u8();
-                    if self.use_point_userdata { p.user_data = bor.read_u8(); }
-                    p.scan_angle = bor.read_i16();
-                    p.point_source_id = bor.read_u16();
-                    self.point_data.push(p);
-                    // read the GPS data
-                    self.gps_data.push(bor.read_f64());
-                    // read the RGBNIR data
-                    let mut rgb: ColourData = Default::default();
-                    rgb.red = bor.read_u16();
-                    rgb.green = bor.read_u16();
-                    rgb.blue = bor.read_u16();
-                    rgb.nir = bor.read_u16();
-                    self.colour_data.push(rgb);
-                    // read the waveform data
-                    let mut wfp: WaveformPacket = Default::default();
-                    wfp.packet_descriptor_index = bor.read_u8();
-                    wfp.offset_to_waveform_data = bor.read_u64();
-                    wfp.waveform_packet_size = bor.read_u32();
-                    wfp.ret_point_waveform_loc = bor.read_f32();
-                    wfp.xt = bor.read_f32();
-                    wfp.yt = bor.read_f32();
-                    wfp.zt = bor.read_f32();
-                    self.waveform_data.push(wfp);
+            let (use_point_intensity, use_point_userdata) = point_record_options(&self.header)?;
+            self.use_point_intensity = use_point_intensity;
+            self.use_point_userdata = use_point_userdata;
+            let record_length = self.header.point_record_length as usize;
+            for i in 0..self.header.number_of_points as usize {
+                let start = self.header.offset_to_points as usize + i * record_length;
+                bor.seek(start);
+                let point = read_point_record(&mut bor, &self.header, self.use_point_intensity, self.use_point_userdata);
+                self.push_point_record(point);
+                if bor.pos < start + record_length {
+                    self.extra_bytes.extend_from_slice(&bor.buffer[bor.pos..start + record_length]);
                 }
             }
         }
@@ -873,399 +465,237 @@ impl LasFile {
     }
 
     fn write_data<W: Write>(&mut self, writer: &mut W) -> Result<(), Error> {
-        /////////////////////////////////
-        // Write the header to the file /
-        /////////////////////////////////
-        let mut u16_bytes: [u8; 2];
-        let mut u32_bytes: [u8; 4];
-        let mut u64_bytes: [u8; 8];
+        let is_laz = self.file_name.to_lowercase().ends_with(".laz");
+        if is_laz {
+            // compressed point records must contain all of the fields of the point format
+            self.use_point_intensity = true;
+            self.use_point_userdata = true;
+        }
 
-        self.header.file_signature = "LASF".to_string();
-        writer.write_all(self.header.file_signature.as_bytes())?;
-        
-        u16_bytes = unsafe {mem::transmute(self.header.file_source_id)};
-        writer.write_all(&u16_bytes)?;
-        
-        u16_bytes = unsafe { mem::transmute(self.header.global_encoding) };
-        writer.write_all(&u16_bytes)?;
-        
-        if self.header.project_id_used {
-            u32_bytes = unsafe { mem::transmute(self.header.project_id1) };
-            writer.write_all(&u32_bytes)?;
-            
-            u16_bytes = unsafe { mem::transmute(self.header.project_id2) };
-            writer.write_all(&u16_bytes)?;
-            
-            u16_bytes = unsafe { mem::transmute(self.header.project_id3) };
-            writer.write_all(&u16_bytes)?;
-            
-            u64_bytes = unsafe { mem::transmute(self.header.project_id4) };
-            writer.write_all(&u64_bytes)?;
+        if let Some(laz_vlr) = self.prepare_header(is_laz)? {
+            // The points are compressed before the header is written because the position of
+            // the EVLRs, which follow the points, is stored in the header.
+            let record_length = self.header.point_record_length as usize;
+            let offset_to_points = self.header.offset_to_points as u64;
+            let mut points = vec![];
+            {
+                let mut laz_writer = LazWriter::new(&mut points, &laz_vlr, record_length, offset_to_points)?;
+                self.write_points(&mut laz_writer)?;
+                laz_writer.done()?;
+            }
+            // The offset of the chunk table, which the LAZ writer appends to the end of the
+            // points, is filled in so that it can be found when EVLRs follow the points.
+            let n = points.len();
+            let table_position = points[n - 8..n].to_vec();
+            points[0..8].copy_from_slice(&table_position);
+            self.set_evlr_position(offset_to_points + points.len() as u64);
+            self.write_header(writer, Some(&laz_vlr))?;
+            writer.write_all(&points)?;
+        } else {
+            let points_length = self.header.number_of_points * self.header.point_record_length as u64;
+            let offset_to_points = self.header.offset_to_points as u64;
+            self.set_evlr_position(offset_to_points + points_length);
+            self.write_header(writer, None)?;
+            self.write_points(writer)?;
         }
+        self.write_evlrs(writer)?;
+
+        Ok(())
+    }
+
+    /// Updates the fields of the header that depend on the data, e.g. the point record length,
+    /// the offset to the points, and the legacy point counts, before the header is written.
+    /// For a LAZ file, the LASzip VLR, which is written after the other VLRs, is returned.
+    fn prepare_header(&mut self, is_laz: bool) -> Result<Option<LazVlr>, Error> {
+        if self.header.point_format > 10 {
+            return Err(Error::new(ErrorKind::Other, "Unsupported point format"));
+        }
+        self.header.file_signature = "LASF".to_string();
+        self.header.version_major = 1;
+        self.header.version_minor = 4;
+        self.header.header_size = 375;
 
-        self.header.version_major = 1u8;
-        let mut u8_bytes: [u8; 1] = unsafe {mem::transmute(self.header.version_major)};
-        writer.write_all(&u8_bytes)?;
-        
-        self.header.version_minor = 3u8;
-        u8_bytes = unsafe {mem::transmute(self.header.version_minor)};
-        writer.write_all(&u8_bytes)?;
-        
         if self.header.system_id.len() == 0 {
             self.header.system_id = fixed_length_string("OTHER", 32);
-        } else if !self.header.system_id.len() != 32 {
+        } else if self.header.system_id.len() != 32 {
             self.header.system_id = fixed_length_string(&(self.header.system_id), 32);
         }
-        writer.write_all(self.header.system_id.as_bytes())?; //string_bytes));
-        
         self.header.generating_software = fixed_length_string("WhiteboxTools                   ", 32);
-        writer.write_all(self.header.generating_software.as_bytes())?;
-        
+
         let now = time::now();
         self.header.file_creation_day = now.tm_yday as u16;
-        u16_bytes = unsafe { mem::transmute(self.header.file_creation_day) };
-        writer.write_all(&u16_bytes)?;
-        
         self.header.file_creation_year = (now.tm_year + 1900) as u16;
-        u16_bytes = unsafe { mem::transmute(self.header.file_creation_year) };
-        writer.write_all(&u16_bytes)?;
-        
-        self.header.header_size = 235; // THIS NEEDS TO BE FIXED WHEN LAS 1.4 SUPPORT IS ADDED FOR WRITING
-        u16_bytes = unsafe { mem::transmute(self.header.header_size) };
-        writer.write_all(&u16_bytes)?;
-    
-        // figure out the offset to points
-        let mut total_vlr_size = 54 * self.header.number_of_vlrs;
-        for i in 0..(self.header.number_of_vlrs as usize) {
-            total_vlr_size += self.vlr_data[i].record_length_after_header as u32;
+
+        // The Extra Bytes VLR describes the bytes that follow the standard fields of each point
+        // record. It is removed if the extra bytes of the points are not available.
+        let extra_bytes_length = self.extra_bytes_length();
+        if extra_bytes_length == 0 {
+            self.vlr_data.retain(|vlr| !is_extra_bytes_vlr(vlr));
         }
-        self.header.offset_to_points = 235 + total_vlr_size; // THIS NEEDS TO BE FIXED WHEN LAS 1.4 SUPPORT IS ADDED FOR WRITING
-        u32_bytes = unsafe { mem::transmute(self.header.offset_to_points) };
-        writer.write_all(&u32_bytes)?;
-        
-        u32_bytes = unsafe { mem::transmute(self.header.number_of_vlrs) };
-        writer.write_all(&u32_bytes)?;
-
-        ////////////////////////////////////////////////////////////////////////
-        // THIS NEEDS TO BE REMOVED WHEN LAS 1.4 SUPPORT IS ADDED FOR WRITING //
-        ////////////////////////////////////////////////////////////////////////
-        self.header.point_format = match self.header.point_format {
-            0u8 => 0u8,
-            1u8 => 1u8,
-            2u8 => 2u8,
-            3u8 => 3u8,
-            4u8 => { 
-                println!("Warning: Point Format 4 is not supported for output. Some data will be lost."); 
-                1u8
-            }, 
-            5u8 => { 
-                println!("Warning: Point Format 5 is not supported for output. Some data will be lost."); 
-                3u8
-            }, 
-            6u8 => 1u8,
-            7u8 => 3u8,
-            8u8 => { 
-                println!("Warning: Point Format 8 is not supported for output. Some data will be lost."); 
-                3u8
-            },  
-            9u8 => { 
-                println!("Warning: Point Format 9 is not supported for output. Some data will be lost."); 
-                1u8
-            }, 
-            10u8 => { 
-                println!("Warning: Point Format 10 is not supported for output. Some data will be lost."); 
-                3u8
-            },  
-            _ => { 
-                return Err(Error::new(ErrorKind::Other, "Unsupported point format")); 
-            },
-        };
-        
-        u8_bytes = unsafe {mem::transmute(self.header.point_format)};
-        writer.write_all(&u8_bytes)?;
-
-        // Intensity and userdata are both optional. Figure out if they need to be read.
-        // The only way to do this is to compare the point record length by point format
-        let rec_lengths = [ [20_u16, 18_u16, 19_u16, 17_u16],
-                            [28_u16, 26_u16, 27_u16, 25_u16],
-                            [26_u16, 24_u16, 25_u16, 23_u16],
-                            [34_u16, 32_u16, 33_u16, 31_u16] ];
-
-        if self.use_point_intensity && self.use_point_userdata {
-            self.header.point_record_length = rec_lengths[self.header.point_format as usize][0];
-        } else if !self.use_point_intensity && self.use_point_userdata {
-            self.header.point_record_length = rec_lengths[self.header.point_format as usize][1];
-        } else if self.use_point_intensity && !self.use_point_userdata {
-            self.header.point_record_length = rec_lengths[self.header.point_format as usize][2];
-        } else { //if !self.use_point_intensity && !self.use_point_userdata {
-            self.header.point_record_length = rec_lengths[self.header.point_format as usize][3];
-        }
-
-        u16_bytes = unsafe { mem::transmute(self.header.point_record_length) };
-        writer.write_all(&u16_bytes)?;
-        
-        if self.header.number_of_points <= u32::max_value() as u64 {
-            self.header.number_of_points_old = self.header.number_of_points as u32; // THIS NEEDS TO BE FIXED WHEN LAS 1.4 SUPPORT IS ADDED FOR WRITING
+        self.header.point_record_length = point_record_length(self.header.point_format,
+            self.use_point_intensity, self.use_point_userdata) + extra_bytes_length as u16;
+
+        // The global encoding flags a WKT coordinate reference system. The waveform packets of
+        // the points are kept but the waveform data themselves are not copied into the file.
+        if self.vlr_data.iter().chain(self.evlr_data.iter()).any(|vlr| vlr.is_wkt()) {
+            self.header.global_encoding.value |= 0b0001_0000u16;
         } else {
-            return Err(Error::new(ErrorKind::Other, "The number of points in this file requires a 64-bit format. Currently LAS 1.4 files cannot be written.")); 
+            self.header.global_encoding.value &= !0b0001_0000u16;
         }
-        u32_bytes = unsafe { mem::transmute(self.header.number_of_points_old) };
-        writer.write_all(&u32_bytes)?;
-        
-        for i in 0..5 { // THIS NEEDS TO BE FIXED WHEN LAS 1.4 SUPPORT IS ADDED FOR WRITING
-            u32_bytes = unsafe { mem::transmute(self.header.number_of_points_by_return[i] as u32) };
-            writer.write_all(&u32_bytes)?;
+        self.header.global_encoding.value &= !0b0000_0010u16;
+        self.header.waveform_data_start = 0;
+
+        let laz_vlr = if is_laz {
+            Some(LazVlr::new(self.header.point_format, self.header.point_record_length)?)
+        } else {
+            None
+        };
+
+        // figure out the offset to points
+        let mut total_vlr_size = 0u32;
+        for vlr in &self.vlr_data {
+            if vlr.binary_data.len() > u16::max_value() as usize {
+                return Err(Error::new(ErrorKind::InvalidData,
+                    format!("The '{}' VLR is too large for a VLR and must be stored as an EVLR.", vlr.description.trim_matches('\0'))));
+            }
+            total_vlr_size += 54 + vlr.binary_data.len() as u32;
+        }
+        if let Some(ref laz_vlr) = laz_vlr {
+            total_vlr_size += 54 + laz_vlr.to_vlr().binary_data.len() as u32;
+        }
+        self.header.number_of_vlrs = self.vlr_data.len() as u32;
+        self.header.offset_to_points = self.header.header_size as u32 + total_vlr_size;
+        self.header.number_of_extended_vlrs = self.evlr_data.len() as u32;
+
+        // The legacy point counts are used by readers of earlier LAS versions. They must be zero
+        // for the 64-bit point formats and for files with more points than a 32-bit count allows.
+        if self.header.point_format < 6 && self.header.number_of_points <= u32::max_value() as u64 {
+            self.header.number_of_points_old = self.header.number_of_points as u32;
+            for i in 0..5 {
+                self.header.number_of_points_by_return_old[i] = self.header.number_of_points_by_return[i] as u32;
+            }
+        } else {
+            self.header.number_of_points_old = 0;
+            self.header.number_of_points_by_return_old = [0u32; 5];
         }
 
-        u64_bytes = unsafe { mem::transmute(self.header.x_scale_factor) };
-        writer.write_all(&u64_bytes)?;
-        
-        u64_bytes = unsafe { mem::transmute(self.header.y_scale_factor) };
-        writer.write_all(&u64_bytes)?;
-        
-        u64_bytes = unsafe { mem::transmute(self.header.z_scale_factor) };
-        writer.write_all(&u64_bytes)?;
-        
-        u64_bytes = unsafe { mem::transmute(self.header.x_offset) };
-        writer.write_all(&u64_bytes)?;
-        
-        u64_bytes = unsafe { mem::transmute(self.header.y_offset) };
-        writer.write_all(&u64_bytes)?;
-        
-        u64_bytes = unsafe { mem::transmute(self.header.z_offset) };
-        writer.write_all(&u64_bytes)?;
-        
-        u64_bytes = unsafe { mem::transmute(self.header.max_x) };
-        writer.write_all(&u64_bytes)?;
-        
-        u64_bytes = unsafe { mem::transmute(self.header.min_x) };
-        writer.write_all(&u64_bytes)?;
-        
-        u64_bytes = unsafe { mem::transmute(self.header.max_y) };
-        writer.write_all(&u64_bytes)?;
-        
-        u64_bytes = unsafe { mem::transmute(self.header.min_y) };
-        writer.write_all(&u64_bytes)?;
-        
-        u64_bytes = unsafe { mem::transmute(self.header.max_z) };
-        writer.write_all(&u64_bytes)?;
-        
-        u64_bytes = unsafe { mem::transmute(self.header.min_z) };
-        writer.write_all(&u64_bytes)?;
-        
-        u64_bytes = unsafe { mem::transmute(self.header.waveform_data_start) };
-        writer.write_all(&u64_bytes)?;
+        Ok(laz_vlr)
+    }
+
+    fn set_evlr_position(&mut self, position: u64) {
+        self.header.offset_to_ex_vlrs = if self.evlr_data.is_empty() { 0 } else { position };
+    }
+
+    /// Writes the header and VLRs, followed by the LASzip VLR for a LAZ file.
+    fn write_header<W: Write>(&self, writer: &mut W, laz_vlr: Option<&LazVlr>) -> Result<(), Error> {
+        let h = &self.header;
+        writer.write_all(h.file_signature.as_bytes())?;
+        writer.write_u16::<LittleEndian>(h.file_source_id)?;
+        writer.write_u16::<LittleEndian>(h.global_encoding.value)?;
+        // the project ID is written as zeros if it is not used
+        writer.write_u32::<LittleEndian>(h.project_id1)?;
+        writer.write_u16::<LittleEndian>(h.project_id2)?;
+        writer.write_u16::<LittleEndian>(h.project_id3)?;
+        writer.write_all(&h.project_id4)?;
+        writer.write_u8(h.version_major)?;
+        writer.write_u8(h.version_minor)?;
+        writer.write_all(fixed_length_string(&h.system_id, 32).as_bytes())?;
+        writer.write_all(fixed_length_string(&h.generating_software, 32).as_bytes())?;
+        writer.write_u16::<LittleEndian>(h.file_creation_day)?;
+        writer.write_u16::<LittleEndian>(h.file_creation_year)?;
+        writer.write_u16::<LittleEndian>(h.header_size)?;
+        writer.write_u32::<LittleEndian>(h.offset_to_points)?;
+        let num_vlrs = if laz_vlr.is_some() { h.number_of_vlrs + 1 } else { h.number_of_vlrs };
+        writer.write_u32::<LittleEndian>(num_vlrs)?;
+        // the point format of a LAZ file is flagged as compressed
+        let point_format = if laz_vlr.is_some() { h.point_format | 128 } else { h.point_format };
+        writer.write_u8(point_format)?;
+        writer.write_u16::<LittleEndian>(h.point_record_length)?;
+        writer.write_u32::<LittleEndian>(h.number_of_points_old)?;
+        for i in 0..5 {
+            writer.write_u32::<LittleEndian>(h.number_of_points_by_return_old[i])?;
+        }
+        writer.write_f64::<LittleEndian>(h.x_scale_factor)?;
+        writer.write_f64::<LittleEndian>(h.y_scale_factor)?;
+        writer.write_f64::<LittleEndian>(h.z_scale_factor)?;
+        writer.write_f64::<LittleEndian>(h.x_offset)?;
+        writer.write_f64::<LittleEndian>(h.y_offset)?;
+        writer.write_f64::<LittleEndian>(h.z_offset)?;
+        writer.write_f64::<LittleEndian>(h.max_x)?;
+        writer.write_f64::<LittleEndian>(h.min_x)?;
+        writer.write_f64::<LittleEndian>(h.max_y)?;
+        writer.write_f64::<LittleEndian>(h.min_y)?;
+        writer.write_f64::<LittleEndian>(h.max_z)?;
+        writer.write_f64::<LittleEndian>(h.min_z)?;
+        // LAS 1.3
+        writer.write_u64::<LittleEndian>(h.waveform_data_start)?;
+        // LAS 1.4
+        writer.write_u64::<LittleEndian>(h.offset_to_ex_vlrs)?;
+        writer.write_u32::<LittleEndian>(h.number_of_extended_vlrs)?;
+        writer.write_u64::<LittleEndian>(h.number_of_points)?;
+        for i in 0..15 {
+            writer.write_u64::<LittleEndian>(h.number_of_points_by_return[i])?;
+        }
 
         ///////////////////////////////
         // Write the VLRs to the file /
         ///////////////////////////////
-        for i in 0..(self.header.number_of_vlrs as usize) {
-            let vlr = self.vlr_data[i].clone();
-            u16_bytes = unsafe { mem::transmute(vlr.reserved) };
-            writer.write_all(&u16_bytes)?;
-
-            let user_id: &str = &vlr.user_id;
-            //string_bytes = unsafe { mem::transmute(user_id) };
-            writer.write_all(fixed_length_string(user_id, 16).as_bytes())?; //string_bytes));
-            
-            u16_bytes = unsafe { mem::transmute(vlr.record_id) };
-            writer.write_all(&u16_bytes)?;
-            
-            u16_bytes = unsafe { mem::transmute(vlr.record_length_after_header) };
-            writer.write_all(&u16_bytes)?;
-            
-            let description: &str = &vlr.description;
-            //string_bytes = unsafe { mem::transmute(description) };
-            writer.write_all(fixed_length_string(description, 32).as_bytes())?;
-            
+        let laszip_vlr = laz_vlr.map(|v| v.to_vlr());
+        for vlr in self.vlr_data.iter().chain(laszip_vlr.iter()) {
+            writer.write_u16::<LittleEndian>(vlr.reserved)?;
+            writer.write_all(fixed_length_string(&vlr.user_id, 16).as_bytes())?;
+            writer.write_u16::<LittleEndian>(vlr.record_id)?;
+            writer.write_u16::<LittleEndian>(vlr.binary_data.len() as u16)?;
+            writer.write_all(fixed_length_string(&vlr.description, 32).as_bytes())?;
             writer.write_all(&vlr.binary_data)?;
         }
 
+        Ok(())
+    }
+
+    fn write_evlrs<W: Write>(&self, writer: &mut W) -> Result<(), Error> {
+        for evlr in &self.evlr_data {
+            writer.write_u16::<LittleEndian>(evlr.reserved)?;
+            writer.write_all(fixed_length_string(&evlr.user_id, 16).as_bytes())?;
+            writer.write_u16::<LittleEndian>(evlr.record_id)?;
+            writer.write_u64::<LittleEndian>(evlr.binary_data.len() as u64)?;
+            writer.write_all(fixed_length_string(&evlr.description, 32).as_bytes())?;
+            writer.write_all(&evlr.binary_data)?;
+        }
+        Ok(())
+    }
+
+    fn write_points<W: Write>(&mut self, writer: &mut W) -> Result<(), Error> {
         ////////////////////////////////
         // Write the point to the file /
         ////////////////////////////////
-        let mut val: i32;
-        match self.header.point_format {
-            0 => {
-                for i in 0..self.header.number_of_points as usize {
-                    val = ((self.point_data[i].x - self.header.x_offset) / self.header.x_scale_factor) as i32;
-                    u32_bytes = unsafe { mem::transmute(val) };
-                    writer.write_all(&u32_bytes)?;
-                    
-                    val = ((self.point_data[i].y - self.header.y_offset) / self.header.y_scale_factor) as i32;
-                    u32_bytes = unsafe { mem::transmute(val) };
-                    writer.write_all(&u32_bytes)?;
-                    
-                    val = ((self.point_data[i].z - self.header.z_offset) / self.header.z_scale_factor) as i32;
-                    u32_bytes = unsafe { mem::transmute(val) };
-                    writer.write_all(&u32_bytes)?;
-                    
-                    if self.use_point_intensity {
-                        u16_bytes = unsafe { mem::transmute(self.point_data[i].intensity) };
-                        writer.write_all(&u16_bytes)?;
-                    }
-
-                    u8_bytes = unsafe {mem::transmute(self.point_data[i].point_bit_field)};
-                    writer.write_all(&u8_bytes)?;
-                    
-                    u8_bytes = unsafe {mem::transmute(self.point_data[i].class_bit_field)};
-                    writer.write_all(&u8_bytes)?;
-                    
-                    u8_bytes = unsafe {mem::transmute(self.point_data[i].scan_angle as i8)};
-                    writer.write_all(&u8_bytes)?;
-                    
-                    if self.use_point_userdata {
-                        u8_bytes = unsafe {mem::transmute(self.point_data[i].user_data)};
-                        writer.write_all(&u8_bytes)?;
-                    }
-
-                    u16_bytes = unsafe { mem::transmute(self.point_data[i].point_source_id) };
-                    writer.write_all(&u16_bytes)?;
-                }
-            },
-            1 => {
-                for i in 0..self.header.number_of_points as usize {
-                    val = ((self.point_data[i].x - self.header.x_offset) / self.header.x_scale_factor) as i32;
-                    u32_bytes = unsafe { mem::transmute(val) };
-                    writer.write_all(&u32_bytes)?;
-                    
-                    val = ((self.point_data[i].y - self.header.y_offset) / self.header.y_scale_factor) as i32;
-                    u32_bytes = unsafe { mem::transmute(val) };
-                    writer.write_all(&u32_bytes)?;
-                    
-                    val = ((self.point_data[i].z - self.header.z_offset) / self.header.z_scale_factor) as i32;
-                    u32_bytes = unsafe { mem::transmute(val) };
-                    writer.write_all(&u32_bytes)?;
-                    
-                    if self.use_point_intensity {
-                        u16_bytes = unsafe { mem::transmute(self.point_data[i].intensity) };
-                        writer.write_all(&u16_bytes)?;
-                    }
-
-                    u8_bytes = unsafe {mem::transmute(self.point_data[i].point_bit_field)};
-                    writer.write_all(&u8_bytes)?;
-                    
-                    u8_bytes = unsafe {mem::transmute(self.point_data[i].class_bit_field)};
-                    writer.write_all(&u8_bytes)?;
-                    
-                    u8_bytes = unsafe {mem::transmute(self.point_data[i].scan_angle as i8)};
-                    writer.write_all(&u8_bytes)?;
-                    
-                    if self.use_point_userdata {
-                        u8_bytes = unsafe {mem::transmute(self.point_data[i].user_data)};
-                        writer.write_all(&u8_bytes)?;
-                    }
-
-                    u16_bytes = unsafe { mem::transmute(self.point_data[i].point_source_id) };
-                    writer.write_all(&u16_bytes)?;
-                    
-                    u64_bytes = unsafe { mem::transmute(self.gps_data[i]) };
-                    writer.write_all(&u64_bytes)?;
-                }
-            },
-            2 => {
-                for i in 0..self.header.number_of_points as usize {
-                    val = ((self.point_data[i].x - self.header.x_offset) / self.header.x_scale_factor) as i32;
-                    u32_bytes = unsafe { mem::transmute(val) };
-                    writer.write_all(&u32_bytes)?;
-                    
-                    val = ((self.point_data[i].y - self.header.y_offset) / self.header.y_scale_factor) as i32;
-                    u32_bytes = unsafe { mem::transmute(val) };
-                    writer.write_all(&u32_bytes)?;
-                    
-                    val = ((self.point_data[i].z - self.header.z_offset) / self.header.z_scale_factor) as i32;
-                    u32_bytes = unsafe { mem::transmute(val) };
-                    writer.write_all(&u32_bytes)?;
-                    
-                    if self.use_point_intensity {
-                        u16_bytes = unsafe { mem::transmute(self.point_data[i].intensity) };
-                        writer.write_all(&u16_bytes)?;
-                    }
-
-                    u8_bytes = unsafe {mem::transmute(self.point_data[i].point_bit_field)};
-                    writer.write_all(&u8_bytes)?;
-
-                    u8_bytes = unsafe {mem::transmute(self.point_data[i].class_bit_field)};
-                    writer.write_all(&u8_bytes)?;
-                    
-                    u8_bytes = unsafe {mem::transmute(self.point_data[i].scan_angle as i8)};
-                    writer.write_all(&u8_bytes)?;
-                    
-                    if self.use_point_userdata {
-                        u8_bytes = unsafe {mem::transmute(self.point_data[i].user_data)};
-                        writer.write_all(&u8_bytes)?;
-                    }
-
-                    u16_bytes = unsafe { mem::transmute(self.point_data[i].point_source_id) };
-                    writer.write_all(&u16_bytes)?;
-                    
-                    u16_bytes = unsafe { mem::transmute(self.colour_data[i].red) };
-                    writer.write_all(&u16_bytes)?;
-                    
-                    u16_bytes = unsafe { mem::transmute(self.colour_data[i].green) };
-                    writer.write_all(&u16_bytes)?;
-                    
-                    u16_bytes = unsafe { mem::transmute(self.colour_data[i].blue) };
-                    writer.write_all(&u16_bytes)?;
-                }
-            },
-            3 => {
-                for i in 0..self.header.number_of_points as usize {
-                    val = ((self.point_data[i].x - self.header.x_offset) / self.header.x_scale_factor) as i32;
-                    u32_bytes = unsafe { mem::transmute(val) };
-                    writer.write_all(&u32_bytes)?;
-                    
-                    val = ((self.point_data[i].y - self.header.y_offset) / self.header.y_scale_factor) as i32;
-                    u32_bytes = unsafe { mem::transmute(val) };
-                    writer.write_all(&u32_bytes)?;
-                    
-                    val = ((self.point_data[i].z - self.header.z_offset) / self.header.z_scale_factor) as i32;
-                    u32_bytes = unsafe { mem::transmute(val) };
-                    writer.write_all(&u32_bytes)?;
-                    
-                    if self.use_point_intensity {
-                        u16_bytes = unsafe { mem::transmute(self.point_data[i].intensity) };
-                        writer.write_all(&u16_bytes)?;
-                    }
-
-                    u8_bytes = unsafe {mem::transmute(self.point_data[i].point_bit_field)};
-                    writer.write_all(&u8_bytes)?;
-                    
-                    u8_bytes = unsafe {mem::transmute(self.point_data[i].class_bit_field)};
-                    writer.write_all(&u8_bytes)?;
-                    
-                    u8_bytes = unsafe {mem::transmute(self.point_data[i].scan_angle as i8)};
-                    writer.write_all(&u8_bytes)?;
-                    
-                    if self.use_point_userdata {
-                        u8_bytes = unsafe {mem::transmute(self.point_data[i].user_data)};
-                        writer.write_all(&u8_bytes)?;
-                    }
-
-                    u16_bytes = unsafe { mem::transmute(self.point_data[i].point_source_id) };
-                    writer.write_all(&u16_bytes)?;
-                    
-                    u64_bytes = unsafe { mem::transmute(self.gps_data[i]) };
-                    writer.write_all(&u64_bytes)?;
-                    
-                    u16_bytes = unsafe { mem::transmute(self.colour_data[i].red) };
-                    writer.write_all(&u16_bytes)?;
-                    
-                    u16_bytes = unsafe { mem::transmute(self.colour_data[i].green) };
-                    writer.write_all(&u16_bytes)?;
-                    
-                    u16_bytes = unsafe { mem::transmute(self.colour_data[i].blue) };
-                    writer.write_all(&u16_bytes)?;
-                }
-            },
-            _ => {
-                return Err(Error::new(ErrorKind::Other, "Unsupported point format"));
-            },
+        let extra_bytes_length = self.extra_bytes_length();
+        for i in 0..self.header.number_of_points as usize {
+            let point = self.get_record(i);
+            write_point_record(writer, &self.header, self.use_point_intensity, self.use_point_userdata, &point)?;
+            if extra_bytes_length > 0 {
+                writer.write_all(&self.extra_bytes[i * extra_bytes_length..(i + 1) * extra_bytes_length])?;
+            }
         }
 
         Ok(())
     }
 
+    // The number of extra bytes that are written after the standard fields of each point
+    // record. This is zero unless there is an Extra Bytes VLR and the extra bytes of every
+    // point are available.
+    fn extra_bytes_length(&self) -> usize {
+        let length = match self.vlr_data.iter().find(|vlr| is_extra_bytes_vlr(vlr)) {
+            Some(vlr) => extra_bytes_vlr_length(vlr),
+            None => 0,
+        };
+        let num_points = self.header.number_of_points as usize;
+        if length > 0 && num_points > 0 && self.extra_bytes.len() == num_points * length {
+            length
+        } else {
+            0
+        }
+    }
 
     pub fn get_vlr_data_as_string(&self) -> String {
         let mut s = "".to_string();
@@ -1274,6 +704,11 @@ impl LasFile {
             s = s + &format!("\nVLR {}:\n{}", i, vlr);
             i += 1;
         }
+        i = 1;
+        for evlr in &self.evlr_data {
+            s = s + &format!("\nEVLR {}:\n{}", i, evlr);
+            i += 1;
+        }
         return s;
     }
 
@@ -1288,6 +723,303 @@ impl fmt::Display for LasFile {
     }
 }
 
+trait ReadSeek: Read + Seek {}
+
+impl<T: Read + Seek> ReadSeek for T {}
+
+enum PointSource {
+    Las(Box<ReadSeek>),
+    Laz(LazReader<Box<ReadSeek>>),
+}
+
+/// Reads the point records of a LAS or LAZ file in chunks, rather than all at once as
+/// `LasFile` does, so that files of any size can be processed in a fixed amount of memory.
+/// `LasReader` is an iterator over the chunks, e.g.:
+///
+/// ```ignore
+/// let mut reader = LasReader::new(&input_file)?;
+/// for chunk in &mut reader {
+///     for point in chunk? {
+///         let p = point.point_data();
+///     }
+/// }
+/// ```
+pub struct LasReader {
+    pub header: LasHeader,
+    pub vlr_data: Vec<Vlr>,
+    pub evlr_data: Vec<Vlr>,
+    pub geokeys: GeoKeys,
+    source: PointSource,
+    use_point_intensity: bool,
+    use_point_userdata: bool,
+    chunk_size: usize,
+    points_read: u64,
+    buffer: Vec<u8>,
+}
+
+impl LasReader {
+    /// Opens a LAS or LAZ file and reads its header and VLRs. The file contained within a
+    /// zipped archive is decompressed into memory.
+    pub fn new(file_name: &str) -> Result<LasReader, Error> {
+        let mut source: Box<ReadSeek> = if file_name.to_lowercase().ends_with(".zip") {
+            Box::new(Cursor::new(read_zipped_file(file_name)?))
+        } else {
+            Box::new(BufReader::new(File::open(file_name)?))
+        };
+
+        let mut buffer = vec![];
+        (&mut source).take(375).read_to_end(&mut buffer)?;
+        let (mut header, is_compressed) = LasHeader::from_bytes(buffer)?;
+
+        let mut buffer = vec![0u8; header.offset_to_points as usize];
+        source.seek(SeekFrom::Start(0))?;
+        source.read_exact(&mut buffer)?;
+        let mut bor = ByteOrderReader::new(buffer, Endianness::LittleEndian);
+        bor.seek(header.header_size as usize);
+        let mut vlr_data = vec![];
+        let mut geokeys: GeoKeys = Default::default();
+        let laz_vlr = read_vlrs(&mut bor, &mut header, &mut vlr_data, &mut geokeys)?;
+        let (use_point_intensity, use_point_userdata) = point_record_options(&header)?;
+
+        let mut evlr_data = vec![];
+        if header.number_of_extended_vlrs > 0 && header.offset_to_ex_vlrs > header.offset_to_points as u64 {
+            source.seek(SeekFrom::Start(header.offset_to_ex_vlrs))?;
+            evlr_data = read_evlrs(&mut source, header.number_of_extended_vlrs)?;
+        } else {
+            header.number_of_extended_vlrs = 0;
+        }
+
+        let source = if is_compressed {
+            let laz_vlr = match laz_vlr {
+                Some(v) => v,
+                None => return Err(Error::new(ErrorKind::InvalidData, "The compressed LAZ file does not contain a LASzip VLR.")),
+            };
+            PointSource::Laz(LazReader::new(source, &laz_vlr, header.offset_to_points as u64,
+                header.number_of_points, header.point_record_length as usize)?)
+        } else {
+            source.seek(SeekFrom::Start(header.offset_to_points as u64))?;
+            PointSource::Las(source)
+        };
+
+        Ok(LasReader {
+            header: header,
+            vlr_data: vlr_data,
+            evlr_data: evlr_data,
+            geokeys: geokeys,
+            source: source,
+            use_point_intensity: use_point_intensity,
+            use_point_userdata: use_point_userdata,
+            chunk_size: 100_000,
+            points_read: 0,
+            buffer: vec![],
+        })
+    }
+
+    /// Returns the OGC WKT coordinate reference system of the file, if it has one.
+    pub fn get_wkt(&self) -> Option<String> {
+        self.vlr_data.iter().chain(self.evlr_data.iter())
+            .find(|vlr| vlr.is_wkt())
+            .map(|vlr| vlr.get_wkt())
+    }
+
+    /// Sets the maximum number of points that are read in each chunk. The default is 100,000.
+    pub fn set_chunk_size(&mut self, chunk_size: usize) {
+        self.chunk_size = chunk_size.max(1);
+    }
+
+    /// Returns the number of points that have been read so far.
+    pub fn points_read(&self) -> u64 {
+        self.points_read
+    }
+
+    /// Reads the next chunk of point records. The chunk is empty once all of the points
+    /// have been read.
+    pub fn read_chunk(&mut self) -> Result<Vec<LidarPointRecord>, Error> {
+        let num_points = (self.header.number_of_points - self.points_read).min(self.chunk_size as u64) as usize;
+        let record_length = self.header.point_record_length as usize;
+        let mut buffer = mem::replace(&mut self.buffer, vec![]);
+        buffer.resize(num_points * record_length, 0u8);
+        match self.source {
+            PointSource::Las(ref mut source) => source.read_exact(&mut buffer)?,
+            PointSource::Laz(ref mut reader) => {
+                for point in buffer.chunks_mut(record_length) {
+                    reader.read_point(point)?;
+                }
+            },
+        }
+
+        let mut bor = ByteOrderReader::new(buffer, Endianness::LittleEndian);
+        let mut points = Vec::with_capacity(num_points);
+        for i in 0..num_points {
+            bor.seek(i * record_length);
+            points.push(read_point_record(&mut bor, &self.header, self.use_point_intensity, self.use_point_userdata));
+        }
+        self.buffer = bor.buffer;
+        self.points_read += num_points as u64;
+        Ok(points)
+    }
+}
+
+impl Iterator for LasReader {
+    type Item = Result<Vec<LidarPointRecord>, Error>;
+
+    fn next(&mut self) -> Option<Result<Vec<LidarPointRecord>, Error>> {
+        if self.points_read >= self.header.number_of_points {
+            return None;
+        }
+        let chunk = self.read_chunk();
+        if chunk.is_err() {
+            // there is no way to recover the position of the next point
+            self.points_read = self.header.number_of_points;
+        }
+        Some(chunk)
+    }
+}
+
+enum PointSink {
+    Las(BufWriter<File>),
+    Laz(LazWriter<BufWriter<File>>),
+}
+
+/// Writes point records to a LAS or LAZ file as they are added, rather than holding them
+/// in memory as `LasFile` does. The header is written when the writer is created and is
+/// updated with the point counts and extent when `finish` is called, which also writes any
+/// EVLRs after the points. Unlike `LasFile`, the
+/// system ID, scale factors and offsets of the header are kept, because the extent of the
+/// points is not known before they are written.
+pub struct LasWriter {
+    file_name: String,
+    output: LasFile,
+    sink: Option<PointSink>,
+    is_laz: bool,
+}
+
+impl LasWriter {
+    /// Creates a LAS file, initialized with a header and VLRs, e.g. those of an input file.
+    pub fn new(file_name: &str, header: &LasHeader, vlr_data: &[Vlr]) -> Result<LasWriter, Error> {
+        let mut output = LasFile::new(file_name, "w")?;
+        output.add_header(header.clone());
+        output.header.system_id = header.system_id.clone();
+        if header.x_scale_factor > 0f64 && header.y_scale_factor > 0f64 && header.z_scale_factor > 0f64 {
+            output.header.x_scale_factor = header.x_scale_factor;
+            output.header.y_scale_factor = header.y_scale_factor;
+            output.header.z_scale_factor = header.z_scale_factor;
+        }
+        output.header.x_offset = header.x_offset;
+        output.header.y_offset = header.y_offset;
+        output.header.z_offset = header.z_offset;
+        for vlr in vlr_data {
+            output.add_vlr(vlr.clone());
+        }
+
+        // A zipped file is written to a temporary LAS file and then compressed into the archive.
+        let las_file_name = if file_name.to_lowercase().ends_with(".zip") {
+            format!("{}.tmp.las", file_name)
+        } else {
+            file_name.to_string()
+        };
+        let is_laz = las_file_name.to_lowercase().ends_with(".laz");
+        if is_laz {
+            // compressed point records must contain all of the fields of the point format
+            output.use_point_intensity = true;
+            output.use_point_userdata = true;
+        }
+
+        // the file is also read from, to fill in the position of the chunk table of a LAZ file
+        let f = OpenOptions::new().read(true).write(true).create(true).truncate(true).open(&las_file_name)?;
+        let mut writer = BufWriter::new(f);
+        let laz_vlr = output.prepare_header(is_laz)?;
+        output.write_header(&mut writer, laz_vlr.as_ref())?;
+        let sink = match laz_vlr {
+            Some(laz_vlr) => {
+                let record_length = output.header.point_record_length as usize;
+                let offset_to_points = output.header.offset_to_points as u64;
+                PointSink::Laz(LazWriter::new(writer, &laz_vlr, record_length, offset_to_points)?)
+            },
+            None => PointSink::Las(writer),
+        };
+
+        Ok(LasWriter {
+            file_name: file_name.to_string(),
+            output: output,
+            sink: Some(sink),
+            is_laz: is_laz,
+        })
+    }
+
+    pub fn add_point_record(&mut self, point: LidarPointRecord) -> Result<(), Error> {
+        update_header_stats(&mut self.output.header, &point.point_data());
+        let output = &self.output;
+        match self.sink {
+            Some(PointSink::Las(ref mut writer)) => write_point_record(writer, &output.header, output.use_point_intensity, output.use_point_userdata, &point),
+            Some(PointSink::Laz(ref mut writer)) => write_point_record(writer, &output.header, output.use_point_intensity, output.use_point_userdata, &point),
+            None => Err(Error::new(ErrorKind::Other, "The LAS file has already been finished.")),
+        }
+    }
+
+    /// Adds an extended VLR, which is written after the points when the file is finished.
+    pub fn add_evlr(&mut self, evlr: Vlr) {
+        self.output.add_evlr(evlr);
+    }
+
+    /// Returns the number of points that have been added so far.
+    pub fn num_points(&self) -> u64 {
+        self.output.header.number_of_points
+    }
+
+    /// Writes the remaining points and updates the header with the point counts and extent.
+    pub fn finish(mut self) -> Result<(), Error> {
+        let writer = match self.sink.take() {
+            Some(PointSink::Las(writer)) => writer,
+            Some(PointSink::Laz(writer)) => writer.done()?,
+            None => return Ok(()),
+        };
+        let mut f = writer.into_inner()?;
+        let end_of_points = f.seek(SeekFrom::End(0))?;
+        if self.is_laz {
+            // fill in the position of the chunk table, which was appended to the points
+            let mut table_position = [0u8; 8];
+            f.seek(SeekFrom::End(-8))?;
+            f.read_exact(&mut table_position)?;
+            f.seek(SeekFrom::Start(self.output.header.offset_to_points as u64))?;
+            f.write_all(&table_position)?;
+            f.seek(SeekFrom::End(0))?;
+        }
+        self.output.set_evlr_position(end_of_points);
+        self.output.write_evlrs(&mut f)?;
+
+        // the header and VLRs are the same length as when they were first written
+        let laz_vlr = self.output.prepare_header(self.is_laz)?;
+        let mut header_bytes = vec![];
+        self.output.write_header(&mut header_bytes, laz_vlr.as_ref())?;
+        f.seek(SeekFrom::Start(0))?;
+        f.write_all(&header_bytes)?;
+        f.flush()?;
+
+        if self.file_name.to_lowercase().ends_with(".zip") {
+            let las_file_name = format!("{}.tmp.las", self.file_name);
+            drop(f);
+            {
+                let mut writer = ZipWriter::new(File::create(&self.file_name)?);
+                let path = Path::new(&self.file_name);
+                let lasfile_name = if self.file_name.to_lowercase().ends_with(".las.zip") {
+                    path.file_stem().unwrap().to_str().unwrap().to_owned()
+                } else {
+                    path.file_stem().unwrap().to_str().unwrap().to_owned() + ".las"
+                };
+                let options = FileOptions::default().compression_method(CompressionMethod::Deflated);
+                writer.start_file(lasfile_name, options)?;
+                let mut las = BufReader::new(File::open(&las_file_name)?);
+                io::copy(&mut las, &mut writer)?;
+                writer.finish()?;
+            }
+            fs::remove_file(&las_file_name)?;
+        }
+
+        Ok(())
+    }
+}
+
 #[derive(Default, Debug, Clone, Copy)]
 pub struct GlobalEncodingField {
     pub value: u16,
@@ -1373,6 +1105,82 @@ pub enum LidarPointRecord {
     PointRecord10 { point_data: PointData, gps_data: f64, colour_data: ColourData, wave_packet: WaveformPacket }
 }
 
+impl LidarPointRecord {
+    /// Returns the point data, which is common to all of the point formats.
+    pub fn point_data(&self) -> PointData {
+        match *self {
+            LidarPointRecord::PointRecord0 { point_data } => point_data,
+            LidarPointRecord::PointRecord1 { point_data, .. } => point_data,
+            LidarPointRecord::PointRecord2 { point_data, .. } => point_data,
+            LidarPointRecord::PointRecord3 { point_data, .. } => point_data,
+            LidarPointRecord::PointRecord4 { point_data, .. } => point_data,
+            LidarPointRecord::PointRecord5 { point_data, .. } => point_data,
+            LidarPointRecord::PointRecord6 { point_data, .. } => point_data,
+            LidarPointRecord::PointRecord7 { point_data, .. } => point_data,
+            LidarPointRecord::PointRecord8 { point_data, .. } => point_data,
+            LidarPointRecord::PointRecord9 { point_data, .. } => point_data,
+            LidarPointRecord::PointRecord10 { point_data, .. } => point_data,
+        }
+    }
+
+    /// Replaces the point data, e.g. after the coordinates of the point have been modified.
+    pub fn set_point_data(&mut self, value: PointData) {
+        match *self {
+            LidarPointRecord::PointRecord0 { ref mut point_data } => *point_data = value,
+            LidarPointRecord::PointRecord1 { ref mut point_data, .. } => *point_data = value,
+            LidarPointRecord::PointRecord2 { ref mut point_data, .. } => *point_data = value,
+            LidarPointRecord::PointRecord3 { ref mut point_data, .. } => *point_data = value,
+            LidarPointRecord::PointRecord4 { ref mut point_data, .. } => *point_data = value,
+            LidarPointRecord::PointRecord5 { ref mut point_data, .. } => *point_data = value,
+            LidarPointRecord::PointRecord6 { ref mut point_data, .. } => *point_data = value,
+            LidarPointRecord::PointRecord7 { ref mut point_data, .. } => *point_data = value,
+            LidarPointRecord::PointRecord8 { ref mut point_data, .. } => *point_data = value,
+            LidarPointRecord::PointRecord9 { ref mut point_data, .. } => *point_data = value,
+            LidarPointRecord::PointRecord10 { ref mut point_data, .. } => *point_data = value,
+        }
+    }
+
+    /// Returns the GPS time, if the point format includes it.
+    pub fn gps_time(&self) -> Option<f64> {
+        match *self {
+            LidarPointRecord::PointRecord0 { .. } | LidarPointRecord::PointRecord2 { .. } => None,
+            LidarPointRecord::PointRecord1 { gps_data, .. } => Some(gps_data),
+            LidarPointRecord::PointRecord3 { gps_data, .. } => Some(gps_data),
+            LidarPointRecord::PointRecord4 { gps_data, .. } => Some(gps_data),
+            LidarPointRecord::PointRecord5 { gps_data, .. } => Some(gps_data),
+            LidarPointRecord::PointRecord6 { gps_data, .. } => Some(gps_data),
+            LidarPointRecord::PointRecord7 { gps_data, .. } => Some(gps_data),
+            LidarPointRecord::PointRecord8 { gps_data, .. } => Some(gps_data),
+            LidarPointRecord::PointRecord9 { gps_data, .. } => Some(gps_data),
+            LidarPointRecord::PointRecord10 { gps_data, .. } => Some(gps_data),
+        }
+    }
+
+    /// Returns the colour data, if the point format includes it.
+    pub fn colour_data(&self) -> Option<ColourData> {
+        match *self {
+            LidarPointRecord::PointRecord2 { colour_data, .. } => Some(colour_data),
+            LidarPointRecord::PointRecord3 { colour_data, .. } => Some(colour_data),
+            LidarPointRecord::PointRecord5 { colour_data, .. } => Some(colour_data),
+            LidarPointRecord::PointRecord7 { colour_data, .. } => Some(colour_data),
+            LidarPointRecord::PointRecord8 { colour_data, .. } => Some(colour_data),
+            LidarPointRecord::PointRecord10 { colour_data, .. } => Some(colour_data),
+            _ => None,
+        }
+    }
+
+    /// Returns the waveform packet, if the point format includes it.
+    pub fn waveform_packet(&self) -> Option<WaveformPacket> {
+        match *self {
+            LidarPointRecord::PointRecord4 { wave_packet, .. } => Some(wave_packet),
+            LidarPointRecord::PointRecord5 { wave_packet, .. } => Some(wave_packet),
+            LidarPointRecord::PointRecord9 { wave_packet, .. } => Some(wave_packet),
+            LidarPointRecord::PointRecord10 { wave_packet, .. } => Some(wave_packet),
+            _ => None,
+        }
+    }
+}
+
 #[derive(Default, Copy, Clone, Debug)]
 pub struct PointRecord0 {
     pub point_data: PointData,
@@ -1513,6 +1321,290 @@ impl PointRecord10 {
     }
 }
 
+// Reads the VLRs, starting at the current position of the reader. Any GeoKeys are added
+// to geokeys. The LASzip VLR of a LAZ file is returned rather than being added to vlr_data.
+fn read_vlrs(bor: &mut ByteOrderReader, header: &mut LasHeader, vlr_data: &mut Vec<Vlr>, geokeys: &mut GeoKeys) -> Result<Option<LazVlr>, Error> {
+    let mut laz_vlr: Option<LazVlr> = None;
+    let num_vlrs = header.number_of_vlrs;
+    for _ in 0..num_vlrs {
+        let mut vlr: Vlr = Default::default();
+        vlr.reserved = bor.read_u16();
+        vlr.user_id = bor.read_utf8(16);
+        vlr.record_id = bor.read_u16();
+        vlr.record_length_after_header = bor.read_u16();
+        vlr.description = bor.read_utf8(32);
+        // get the byte data
+        for _ in 0..vlr.record_length_after_header {
+            vlr.binary_data.push(bor.read_u8());
+        }
+        
+        if vlr.record_id == 34_735 {
+            geokeys.add_key_directory(&vlr.binary_data);
+        } else if vlr.record_id == 34_736 {
+            geokeys.add_double_params(&vlr.binary_data);
+        } else if vlr.record_id == 34_737 {
+            geokeys.add_ascii_params(&vlr.binary_data);
+        } else if LazVlr::is_laszip_vlr(&vlr) {
+            // The LASzip VLR only describes the compression of the points, which are
+            // decompressed when they are read, and so it is not retained.
+            laz_vlr = Some(LazVlr::from_vlr(&vlr)?);
+            header.number_of_vlrs -= 1;
+            continue;
+        }
+        vlr_data.push(vlr);
+    }
+    Ok(laz_vlr)
+}
+
+// The point record lengths of each point format, in the order: with intensity and userdata,
+// without intensity, without userdata, and without either.
+const RECORD_LENGTHS: [[u16; 4]; 11] = [ [20_u16, 18_u16, 19_u16, 17_u16],
+                                         [28_u16, 26_u16, 27_u16, 25_u16],
+                                         [26_u16, 24_u16, 25_u16, 23_u16],
+                                         [34_u16, 32_u16, 33_u16, 31_u16],
+                                         [57_u16, 55_u16, 56_u16, 54_u16],
+                                         [63_u16, 61_u16, 62_u16, 60_u16],
+                                         [30_u16, 28_u16, 29_u16, 27_u16],
+                                         [36_u16, 34_u16, 35_u16, 33_u16],
+                                         [38_u16, 36_u16, 37_u16, 35_u16],
+                                         [59_u16, 57_u16, 58_u16, 56_u16],
+                                         [67_u16, 65_u16, 66_u16, 64_u16] ];
+
+// Intensity and userdata are both optional. Figure out if they need to be read.
+// The only way to do this is to compare the point record length by point format.
+// Returns whether intensity and userdata are included in the point records.
+fn point_record_options(header: &LasHeader) -> Result<(bool, bool), Error> {
+    if header.point_format as usize >= RECORD_LENGTHS.len() {
+        return Err(Error::new(ErrorKind::InvalidData, format!("Unsupported point format ({}).", header.point_format)));
+    }
+    let lengths = RECORD_LENGTHS[header.point_format as usize];
+    let options = if header.point_record_length == lengths[1] {
+        (false, true)
+    } else if header.point_record_length == lengths[2] {
+        (true, false)
+    } else if header.point_record_length == lengths[3] {
+        (false, false)
+    } else {
+        (true, true)
+    };
+    Ok(options)
+}
+
+// The length of the standard fields of a point record, i.e. without any extra bytes.
+fn point_record_length(point_format: u8, use_point_intensity: bool, use_point_userdata: bool) -> u16 {
+    let lengths = RECORD_LENGTHS[point_format as usize];
+    match (use_point_intensity, use_point_userdata) {
+        (true, true) => lengths[0],
+        (false, true) => lengths[1],
+        (true, false) => lengths[2],
+        (false, false) => lengths[3],
+    }
+}
+
+// Reads the EVLRs, which begin at the current position of the reader.
+fn read_evlrs<R: Read>(source: &mut R, num_evlrs: u32) -> Result<Vec<Vlr>, Error> {
+    let mut evlr_data = vec![];
+    for _ in 0..num_evlrs {
+        let mut evlr: Vlr = Default::default();
+        evlr.reserved = source.read_u16::<LittleEndian>()?;
+        let mut user_id = [0u8; 16];
+        source.read_exact(&mut user_id)?;
+        evlr.user_id = String::from_utf8_lossy(&user_id).to_string();
+        evlr.record_id = source.read_u16::<LittleEndian>()?;
+        let length = source.read_u64::<LittleEndian>()?;
+        let mut description = [0u8; 32];
+        source.read_exact(&mut description)?;
+        evlr.description = String::from_utf8_lossy(&description).to_string();
+        evlr.binary_data = vec![0u8; length as usize];
+        source.read_exact(&mut evlr.binary_data)?;
+        evlr.record_length_after_header = length.min(u16::max_value() as u64) as u16;
+        evlr_data.push(evlr);
+    }
+    Ok(evlr_data)
+}
+
+fn is_vlr(vlr: &Vlr, user_id: &str, record_id: u16) -> bool {
+    vlr.record_id == record_id && vlr.user_id.trim_matches('\0').trim() == user_id
+}
+
+fn is_extra_bytes_vlr(vlr: &Vlr) -> bool {
+    is_vlr(vlr, "LASF_Spec", 4)
+}
+
+// The number of extra bytes in each point record that are described by an Extra Bytes VLR,
+// which contains a 192-byte descriptor for each extra attribute.
+fn extra_bytes_vlr_length(vlr: &Vlr) -> usize {
+    let mut length = 0;
+    for descriptor in vlr.binary_data.chunks(192) {
+        if descriptor.len() < 192 {
+            break;
+        }
+        let data_type = descriptor[2] as usize;
+        length += match data_type {
+            // an undocumented extra byte, with the number of bytes in the options field
+            0 => descriptor[3] as usize,
+            1...10 => [1, 1, 2, 2, 4, 4, 8, 8, 4, 8][data_type - 1],
+            // two- and three-member arrays of the basic types, which are deprecated
+            11...20 => 2 * [1, 1, 2, 2, 4, 4, 8, 8, 4, 8][data_type - 11],
+            21...30 => 3 * [1, 1, 2, 2, 4, 4, 8, 8, 4, 8][data_type - 21],
+            _ => 0,
+        };
+    }
+    length
+}
+
+// Reads the point record at the current position of the reader.
+fn read_point_record(bor: &mut ByteOrderReader, header: &LasHeader, use_point_intensity: bool, use_point_userdata: bool) -> LidarPointRecord {
+    let format = header.point_format;
+    let mut p: PointData = Default::default();
+    p.x = bor.read_i32() as f64 * header.x_scale_factor + header.x_offset;
+    p.y = bor.read_i32() as f64 * header.y_scale_factor + header.y_offset;
+    p.z = bor.read_i32() as f64 * header.z_scale_factor + header.z_offset;
+    if use_point_intensity { p.intensity = bor.read_u16(); }
+    p.point_bit_field = bor.read_u8();
+    p.class_bit_field = bor.read_u8();
+    if format < 6 {
+        p.scan_angle = bor.read_i8() as i16;
+        if use_point_userdata { p.user_data = bor.read_u8(); }
+        p.point_source_id = bor.read_u16();
+    } else { // 64-bit
+        p.is_64bit = true;
+        p.classification = bor.read_u8();
+        if use_point_userdata { p.user_data = bor.read_u8(); }
+        p.scan_angle = bor.read_i16();
+        p.point_source_id = bor.read_u16();
+    }
+
+    // read the GPS data
+    let gps_data = if format == 0 || format == 2 { 0f64 } else { bor.read_f64() };
+
+    // read the RGB data; Point Formats 8 and 10 add a NIR band
+    let mut rgb: ColourData = Default::default();
+    if format == 2 || format == 3 || format == 5 || format == 7 || format == 8 || format == 10 {
+        rgb.red = bor.read_u16();
+        rgb.green = bor.read_u16();
+        rgb.blue = bor.read_u16();
+        if format == 8 || format == 10 {
+            rgb.nir = bor.read_u16();
+        }
+    }
+
+    // read the waveform data
+    let mut wfp: WaveformPacket = Default::default();
+    if format == 4 || format == 5 || format == 9 || format == 10 {
+        wfp.packet_descriptor_index = bor.read_u8();
+        wfp.offset_to_waveform_data = bor.read_u64();
+        wfp.waveform_packet_size = bor.read_u32();
+        wfp.ret_point_waveform_loc = bor.read_f32();
+        wfp.xt = bor.read_f32();
+        wfp.yt = bor.read_f32();
+        wfp.zt = bor.read_f32();
+    }
+
+    match format {
+        0 => LidarPointRecord::PointRecord0 { point_data: p },
+        1 => LidarPointRecord::PointRecord1 { point_data: p, gps_data: gps_data },
+        2 => LidarPointRecord::PointRecord2 { point_data: p, colour_data: rgb },
+        3 => LidarPointRecord::PointRecord3 { point_data: p, gps_data: gps_data, colour_data: rgb },
+        4 => LidarPointRecord::PointRecord4 { point_data: p, gps_data: gps_data, wave_packet: wfp },
+        5 => LidarPointRecord::PointRecord5 { point_data: p, gps_data: gps_data, colour_data: rgb, wave_packet: wfp },
+        6 => LidarPointRecord::PointRecord6 { point_data: p, gps_data: gps_data },
+        7 => LidarPointRecord::PointRecord7 { point_data: p, gps_data: gps_data, colour_data: rgb },
+        8 => LidarPointRecord::PointRecord8 { point_data: p, gps_data: gps_data, colour_data: rgb },
+        9 => LidarPointRecord::PointRecord9 { point_data: p, gps_data: gps_data, wave_packet: wfp },
+        _ => LidarPointRecord::PointRecord10 { point_data: p, gps_data: gps_data, colour_data: rgb, wave_packet: wfp },
+    }
+}
+
+// Updates the extent and point counts of the header for an added point.
+fn update_header_stats(header: &mut LasHeader, p: &PointData) {
+    if p.x < header.min_x { header.min_x = p.x; }
+    if p.x > header.max_x { header.max_x = p.x; }
+    if p.y < header.min_y { header.min_y = p.y; }
+    if p.y > header.max_y { header.max_y = p.y; }
+    if p.z < header.min_z { header.min_z = p.z; }
+    if p.z > header.max_z { header.max_z = p.z; }
+
+    header.number_of_points += 1;
+    // the return is counted as it will be written in the point format of the header
+    let mut which_return = p.to_record_layout(header.point_format >= 6).return_number() as usize;
+    if which_return == 0 { which_return = 1; }
+    if which_return <= 15 {
+        header.number_of_points_by_return[which_return-1] += 1;
+    }
+}
+
+// Scales a coordinate to the integer that is stored in a point record.
+fn quantize(value: f64, offset: f64, scale: f64) -> Result<i32, Error> {
+    let val = ((value - offset) / scale).round();
+    if val < i32::min_value() as f64 || val > i32::max_value() as f64 {
+        return Err(Error::new(ErrorKind::InvalidData,
+            "A point coordinate cannot be stored using the scale factor and offset of the LAS header."));
+    }
+    Ok(val as i32)
+}
+
+// Writes a point record in the point format of the header. The point is converted to the
+// layout of the format, e.g. a point that was read from a file with a 32-bit point format is
+// written using the classification and scan angle fields of a 64-bit point format.
+fn write_point_record<W: Write>(writer: &mut W, header: &LasHeader, use_point_intensity: bool, use_point_userdata: bool, point: &LidarPointRecord) -> Result<(), Error> {
+    let format = header.point_format;
+    if format > 10 {
+        return Err(Error::new(ErrorKind::Other, "Unsupported point format"));
+    }
+    let p = point.point_data().to_record_layout(format >= 6);
+    writer.write_i32::<LittleEndian>(quantize(p.x, header.x_offset, header.x_scale_factor)?)?;
+    writer.write_i32::<LittleEndian>(quantize(p.y, header.y_offset, header.y_scale_factor)?)?;
+    writer.write_i32::<LittleEndian>(quantize(p.z, header.z_offset, header.z_scale_factor)?)?;
+    if use_point_intensity {
+        writer.write_u16::<LittleEndian>(p.intensity)?;
+    }
+    writer.write_u8(p.point_bit_field)?;
+    writer.write_u8(p.class_bit_field)?;
+    if format < 6 {
+        writer.write_i8(p.scan_angle as i8)?;
+        if use_point_userdata {
+            writer.write_u8(p.user_data)?;
+        }
+    } else { // 64-bit
+        writer.write_u8(p.classification)?;
+        if use_point_userdata {
+            writer.write_u8(p.user_data)?;
+        }
+        writer.write_i16::<LittleEndian>(p.scan_angle)?;
+    }
+    writer.write_u16::<LittleEndian>(p.point_source_id)?;
+
+    // write the GPS data
+    if format != 0 && format != 2 {
+        writer.write_f64::<LittleEndian>(point.gps_time().unwrap_or(0f64))?;
+    }
+
+    // write the RGB data; Point Formats 8 and 10 add a NIR band
+    if format == 2 || format == 3 || format == 5 || format == 7 || format == 8 || format == 10 {
+        let rgb = point.colour_data().unwrap_or_default();
+        writer.write_u16::<LittleEndian>(rgb.red)?;
+        writer.write_u16::<LittleEndian>(rgb.green)?;
+        writer.write_u16::<LittleEndian>(rgb.blue)?;
+        if format == 8 || format == 10 {
+            writer.write_u16::<LittleEndian>(rgb.nir)?;
+        }
+    }
+
+    // write the waveform data
+    if format == 4 || format == 5 || format == 9 || format == 10 {
+        let wfp = point.waveform_packet().unwrap_or_default();
+        writer.write_u8(wfp.packet_descriptor_index)?;
+        writer.write_u64::<LittleEndian>(wfp.offset_to_waveform_data)?;
+        writer.write_u32::<LittleEndian>(wfp.waveform_packet_size)?;
+        writer.write_f32::<LittleEndian>(wfp.ret_point_waveform_loc)?;
+        writer.write_f32::<LittleEndian>(wfp.xt)?;
+        writer.write_f32::<LittleEndian>(wfp.yt)?;
+        writer.write_f32::<LittleEndian>(wfp.zt)?;
+    }
+    Ok(())
+}
+
 fn fixed_length_string(s: &str, len: usize) -> String {
     //let array: &[u8: 32];
     let l = s.len();
@@ -1530,9 +1622,364 @@ fn fixed_length_string(s: &str, len: usize) -> String {
     ret
 }
 
+// Reads the LAS or LAZ file contained within a zip archive into memory.
+fn read_zipped_file(file_name: &str) -> Result<Vec<u8>, Error> {
+    let file = File::open(file_name)?;
+    let mut zip = (zip::ZipArchive::new(file))?;
+    let mut f = zip.by_index(0).unwrap();
+    if !f.name().to_lowercase().ends_with(".las") && !f.name().to_lowercase().ends_with(".laz") {
+        return Err(Error::new(ErrorKind::InvalidData,
+         "The data file contained within zipped archive does not have the proper 'las' or 'laz' extension."))
+    }
+    match f.compression() {
+        CompressionMethod::Stored | CompressionMethod::Deflated | CompressionMethod::Bzip2 => (),
+        _ => return Err(Error::new(ErrorKind::InvalidData, 
+        "Either the file is formatted incorrectly or it is an unsupported compression type.")),
+    }
+    let file_size: usize = f.size() as usize;
+    let mut buffer = vec![0; file_size];
+
+    // read the file's bytes into a buffer
+    f.read_exact(&mut buffer)?;
+    Ok(buffer)
+}
+
 fn browse_zip_archive<T, F, U>(buf: &mut T, browse_func: F) -> ZipResult<Vec<U>> where T: Read + Seek, F: Fn(&ZipFile) -> ZipResult<U> {
     let mut archive = ZipArchive::new(buf)?;
     (0..archive.len())
         .map(|i| archive.by_index(i).and_then(|file| browse_func(&file)))
         .collect()
 }
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use byteorder::ByteOrder;
+    use super::*;
+
+    const WKT: &str = "PROJCS[\"NAD83 / UTM zone 17N\",GEOGCS[\"NAD83\",DATUM[\"North_American_Datum_1983\",SPHEROID[\"GRS 1980\",6378137,298.257222101]],PRIMEM[\"Greenwich\",0],UNIT[\"degree\",0.0174532925199433]],PROJECTION[\"Transverse_Mercator\"],PARAMETER[\"central_meridian\",-81],PARAMETER[\"scale_factor\",0.9996],PARAMETER[\"false_easting\",500000],PARAMETER[\"false_northing\",0],UNIT[\"metre\",1],AUTHORITY[\"EPSG\",\"26917\"]]";
+
+    fn temp_file(name: &str) -> String {
+        env::temp_dir().join(name).to_str().unwrap().to_string()
+    }
+
+    // A point of the given format, with the i'th return of a pulse of up to five returns.
+    fn test_point(point_format: u8, i: usize) -> LidarPointRecord {
+        let mut p = PointData {
+            x: 1000.0 + (i % 37) as f64 * 0.5,
+            y: 2000.0 + (i / 37) as f64 * 0.5,
+            z: 100.0 + (i % 11) as f64 * 0.125,
+            intensity: (i * 97) as u16,
+            user_data: i as u8,
+            point_source_id: 7,
+            is_64bit: point_format >= 6,
+            ..Default::default()
+        };
+        p.set_number_of_returns(5);
+        p.set_return_number((i % 5) as u8 + 1);
+        p.set_classification(if point_format >= 6 { (i % 64) as u8 } else { (i % 19) as u8 });
+        p.set_scan_direction_flag(i % 2 == 0);
+        p.scan_angle = if point_format >= 6 { (i as i16 % 300) * 10 - 1500 } else { i as i16 % 90 - 45 };
+        let gps_data = 1_000.0 + i as f64 * 0.001;
+        let colour_data = ColourData { red: i as u16, green: 2 * i as u16, blue: 3 * i as u16,
+            nir: if point_format == 8 || point_format == 10 { 4 * i as u16 } else { 0 } };
+        let wave_packet = WaveformPacket { packet_descriptor_index: 1, offset_to_waveform_data: 60 + 128 * i as u64,
+            waveform_packet_size: 128, ret_point_waveform_loc: i as f32, xt: 0.5, yt: -0.5, zt: -1.0 };
+        match point_format {
+            0 => LidarPointRecord::PointRecord0 { point_data: p },
+            1 => LidarPointRecord::PointRecord1 { point_data: p, gps_data: gps_data },
+            2 => LidarPointRecord::PointRecord2 { point_data: p, colour_data: colour_data },
+            3 => LidarPointRecord::PointRecord3 { point_data: p, gps_data: gps_data, colour_data: colour_data },
+            4 => LidarPointRecord::PointRecord4 { point_data: p, gps_data: gps_data, wave_packet: wave_packet },
+            5 => LidarPointRecord::PointRecord5 { point_data: p, gps_data: gps_data, colour_data: colour_data, wave_packet: wave_packet },
+            6 => LidarPointRecord::PointRecord6 { point_data: p, gps_data: gps_data },
+            7 => LidarPointRecord::PointRecord7 { point_data: p, gps_data: gps_data, colour_data: colour_data },
+            8 => LidarPointRecord::PointRecord8 { point_data: p, gps_data: gps_data, colour_data: colour_data },
+            9 => LidarPointRecord::PointRecord9 { point_data: p, gps_data: gps_data, wave_packet: wave_packet },
+            _ => LidarPointRecord::PointRecord10 { point_data: p, gps_data: gps_data, colour_data: colour_data, wave_packet: wave_packet },
+        }
+    }
+
+    fn assert_same_point(expected: &LidarPointRecord, actual: &LidarPointRecord) {
+        let (a, b) = (expected.point_data(), actual.point_data());
+        assert!((a.x - b.x).abs() < 1e-6 && (a.y - b.y).abs() < 1e-6 && (a.z - b.z).abs() < 1e-6);
+        assert_eq!((a.intensity, a.point_bit_field, a.class_bit_field, a.classification()),
+            (b.intensity, b.point_bit_field, b.class_bit_field, b.classification()));
+        assert_eq!((a.scan_angle, a.user_data, a.point_source_id), (b.scan_angle, b.user_data, b.point_source_id));
+        assert_eq!(expected.gps_time(), actual.gps_time());
+        assert_eq!(expected.colour_data(), actual.colour_data());
+        assert_eq!(expected.waveform_packet().map(|w| (w.offset_to_waveform_data, w.ret_point_waveform_loc)),
+            actual.waveform_packet().map(|w| (w.offset_to_waveform_data, w.ret_point_waveform_loc)));
+    }
+
+    // An Extra Bytes VLR describing a single signed long attribute.
+    fn extra_bytes_vlr() -> Vlr {
+        let mut binary_data = vec![0u8; 192];
+        binary_data[2] = 6;
+        binary_data[4..9].copy_from_slice(b"range");
+        Vlr {
+            user_id: "LASF_Spec".to_string(),
+            record_id: 4,
+            record_length_after_header: 192,
+            description: "Extra Bytes Record".to_string(),
+            binary_data: binary_data,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn las14_round_trip_for_every_point_format() {
+        let num_points = 500;
+        for point_format in 0..11u8 {
+            let file_name = temp_file(&format!("wbt_las14_round_trip_{}.las", point_format));
+            let mut output = LasFile::new(&file_name, "w").unwrap();
+            output.add_header(LasHeader { point_format: point_format, ..Default::default() });
+            let points: Vec<LidarPointRecord> = (0..num_points).map(|i| test_point(point_format, i)).collect();
+            for point in &points {
+                output.add_point_record(*point);
+            }
+            output.write().unwrap();
+
+            let bytes = fs::read(&file_name).unwrap();
+            assert_eq!(&bytes[0..4], b"LASF");
+            assert_eq!((bytes[24], bytes[25]), (1, 4));
+            assert_eq!(LittleEndian::read_u16(&bytes[94..96]), 375);
+            assert_eq!(LittleEndian::read_u64(&bytes[247..255]), num_points as u64);
+            // the legacy point counts are zero for the 64-bit point formats
+            let legacy_count = if point_format < 6 { num_points as u32 } else { 0 };
+            assert_eq!(LittleEndian::read_u32(&bytes[107..111]), legacy_count);
+
+            let input = LasFile::new(&file_name, "r").unwrap();
+            assert_eq!(input.header.point_format, point_format);
+            assert_eq!(input.header.number_of_points, num_points as u64);
+            assert_eq!(bytes.len(), input.header.offset_to_points as usize + num_points * input.header.point_record_length as usize);
+            assert_eq!(&input.header.number_of_points_by_return[0..6], &[100, 100, 100, 100, 100, 0]);
+            assert!((input.header.min_x - 1000.0).abs() < 1e-6 && (input.header.max_x - 1018.0).abs() < 1e-6);
+            for i in 0..num_points {
+                assert_same_point(&points[i], &input.get_record(i));
+            }
+            let _ = fs::remove_file(&file_name);
+        }
+    }
+
+    #[test]
+    fn evlrs_extra_bytes_and_wkt_are_written() {
+        let file_name = temp_file("wbt_las14_evlrs.las");
+        let num_points = 200;
+        let mut output = LasFile::new(&file_name, "w").unwrap();
+        output.add_header(LasHeader { point_format: 6, ..Default::default() });
+        output.add_vlr(extra_bytes_vlr());
+        output.set_wkt(WKT);
+        // an EVLR may be larger than the 65,535 bytes of a VLR
+        let evlr = Vlr {
+            user_id: "WhiteboxTools".to_string(),
+            record_id: 1,
+            description: "Large EVLR".to_string(),
+            binary_data: (0..100_000).map(|i| (i % 251) as u8).collect(),
+            ..Default::default()
+        };
+        output.add_evlr(evlr.clone());
+        for i in 0..num_points {
+            let range = -(i as i32) * 1000;
+            let mut extra_bytes = [0u8; 4];
+            LittleEndian::write_i32(&mut extra_bytes, range);
+            output.add_point_record_with_extra_bytes(test_point(6, i), &extra_bytes);
+        }
+        output.write().unwrap();
+
+        let input = LasFile::new(&file_name, "r").unwrap();
+        assert_eq!(input.header.point_record_length, 30 + 4);
+        assert_eq!(input.header.global_encoding.value & 0b0001_0000, 0b0001_0000);
+        assert_eq!(input.get_wkt(), Some(WKT.to_string()));
+        assert_eq!(input.header.number_of_extended_vlrs, 1);
+        assert_eq!(input.header.offset_to_ex_vlrs,
+            input.header.offset_to_points as u64 + num_points as u64 * input.header.point_record_length as u64);
+        assert_eq!(input.evlr_data[0].user_id.trim_matches('\0'), "WhiteboxTools");
+        assert_eq!(input.evlr_data[0].binary_data, evlr.binary_data);
+        for i in 0..num_points {
+            assert_eq!(LittleEndian::read_i32(input.get_extra_bytes(i)), -(i as i32) * 1000);
+            assert_same_point(&test_point(6, i), &input.get_record(i));
+        }
+
+        // the EVLRs are also found when the points are read in chunks
+        let mut reader = LasReader::new(&file_name).unwrap();
+        assert_eq!(reader.get_wkt(), Some(WKT.to_string()));
+        assert_eq!(reader.evlr_data.len(), 1);
+        reader.set_chunk_size(64);
+        let chunks: Vec<Vec<LidarPointRecord>> = reader.map(|c| c.unwrap()).collect();
+        assert_eq!(chunks.iter().map(|c| c.len()).collect::<Vec<usize>>(), vec![64, 64, 64, 8]);
+        let _ = fs::remove_file(&file_name);
+    }
+
+    #[test]
+    fn extra_bytes_vlr_is_dropped_without_extra_bytes() {
+        let file_name = temp_file("wbt_las14_no_extra_bytes.las");
+        let mut output = LasFile::new(&file_name, "w").unwrap();
+        output.add_header(LasHeader { point_format: 1, ..Default::default() });
+        output.add_vlr(extra_bytes_vlr());
+        for i in 0..10 {
+            output.add_point_record(test_point(1, i));
+        }
+        output.write().unwrap();
+
+        let input = LasFile::new(&file_name, "r").unwrap();
+        assert_eq!(input.header.point_record_length, 28);
+        assert!(input.vlr_data.is_empty());
+        assert!(input.get_extra_bytes(0).is_empty());
+        let _ = fs::remove_file(&file_name);
+    }
+
+    #[test]
+    fn overlap_and_scanner_channel_are_preserved() {
+        let file_name = temp_file("wbt_las14_overlap.las");
+        let mut p = PointData { x: 1.0, y: 2.0, z: 3.0, is_64bit: true, ..Default::default() };
+        p.set_classification(2);
+        p.set_overlap(true);
+        p.set_scanner_channel(3);
+        p.set_return_number(9);
+        p.set_number_of_returns(12);
+        // a point of a 32-bit format in the legacy overlap class
+        let mut legacy = PointData { x: 2.0, y: 3.0, z: 4.0, scan_angle: 30, ..Default::default() };
+        legacy.set_classification(12);
+
+        let mut output = LasFile::new(&file_name, "w").unwrap();
+        output.add_header(LasHeader { point_format: 6, ..Default::default() });
+        output.add_point_record(LidarPointRecord::PointRecord6 { point_data: p, gps_data: 1.0 });
+        output.add_point_record(LidarPointRecord::PointRecord1 { point_data: legacy, gps_data: 2.0 });
+        output.write().unwrap();
+
+        let input = LasFile::new(&file_name, "r").unwrap();
+        let a = input.get_point_info(0);
+        assert!(a.overlap());
+        assert_eq!((a.classification(), a.scanner_channel()), (2, 3));
+        assert_eq!((a.return_number(), a.number_of_returns()), (9, 12));
+        let b = input.get_point_info(1);
+        assert!(b.overlap());
+        assert_eq!(b.classification(), 1);
+        assert_eq!(b.scan_angle, 5_000);
+        assert_eq!(input.header.number_of_points_by_return[8], 1);
+        let _ = fs::remove_file(&file_name);
+    }
+
+    #[test]
+    fn streamed_points_match_those_written_at_once() {
+        let file_name = temp_file("wbt_las14_streamed.las");
+        let header = LasHeader { point_format: 7, x_scale_factor: 0.001, y_scale_factor: 0.001, z_scale_factor: 0.001,
+            x_offset: 1000.0, y_offset: 2000.0, z_offset: 0.0, ..Default::default() };
+        let mut writer = LasWriter::new(&file_name, &header, &[extra_bytes_vlr()]).unwrap();
+        writer.add_evlr(Vlr::new_wkt(WKT));
+        for i in 0..300 {
+            writer.add_point_record(test_point(7, i)).unwrap();
+        }
+        assert_eq!(writer.num_points(), 300);
+        writer.finish().unwrap();
+
+        let input = LasFile::new(&file_name, "r").unwrap();
+        assert_eq!(input.header.number_of_points, 300);
+        assert_eq!(input.header.x_offset, 1000.0);
+        assert!((input.header.max_y - (2000.0 + (299 / 37) as f64 * 0.5)).abs() < 1e-6);
+        // the Extra Bytes VLR does not describe the points, because none were provided
+        assert_eq!(input.header.point_record_length, 36);
+        assert_eq!(input.get_wkt(), Some(WKT.to_string()));
+        for i in 0..300 {
+            assert_same_point(&test_point(7, i), &input.get_record(i));
+        }
+        let _ = fs::remove_file(&file_name);
+    }
+
+    #[test]
+    fn streamed_headers_are_patched_and_streamed_reads_match_full_reads() {
+        let num_points = 1234;
+        for &point_format in [1u8, 6u8].iter() {
+            for &ext in ["las", "laz"].iter() {
+                let file_name = temp_file(&format!("wbt_streamed_round_trip_{}.{}", point_format, ext));
+                // the header of an input file, whose counts and extent are not those of the output
+                let mut header = LasHeader { point_format: point_format, x_scale_factor: 0.001, y_scale_factor: 0.001,
+                    z_scale_factor: 0.001, x_offset: 1000.0, y_offset: 2000.0, ..Default::default() };
+                header.number_of_points = 17;
+                header.number_of_points_old = 17;
+                header.number_of_points_by_return = [17u64; 15];
+                header.number_of_points_by_return_old = [17u32; 5];
+                header.min_x = 0.0;
+                header.max_x = 1e6;
+                header.min_z = -500.0;
+                let mut seed = 11u64;
+                let points: Vec<LidarPointRecord> = (0..num_points).map(|i| {
+                    let mut point = test_point(point_format, i);
+                    let mut p = point.point_data();
+                    seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+                    p.z = 100.0 + ((seed >> 11) % 50_000) as f64 * 0.001;
+                    // single returns as well as the returns of pulses of five
+                    if i % 7 == 0 {
+                        p.set_number_of_returns(1);
+                        p.set_return_number(1);
+                    }
+                    point.set_point_data(p);
+                    point
+                }).collect();
+
+                let mut writer = LasWriter::new(&file_name, &header, &[]).unwrap();
+                for point in &points {
+                    writer.add_point_record(*point).unwrap();
+                }
+                writer.finish().unwrap();
+
+                let mut by_return = [0u64; 15];
+                let (mut min_x, mut max_x, mut min_y, mut max_y, mut min_z, mut max_z) =
+                    (f64::INFINITY, f64::NEG_INFINITY, f64::INFINITY, f64::NEG_INFINITY, f64::INFINITY, f64::NEG_INFINITY);
+                for point in &points {
+                    let p = point.point_data();
+                    by_return[p.return_number() as usize - 1] += 1;
+                    min_x = min_x.min(p.x);
+                    max_x = max_x.max(p.x);
+                    min_y = min_y.min(p.y);
+                    max_y = max_y.max(p.y);
+                    min_z = min_z.min(p.z);
+                    max_z = max_z.max(p.z);
+                }
+                assert_eq!(by_return[0], 388);
+
+                let written = LasHeader::read_las_header(&file_name).unwrap();
+                assert_eq!(written.number_of_points, num_points as u64);
+                assert_eq!(written.number_of_points_by_return, by_return);
+                let (legacy_count, legacy_by_return) = if point_format < 6 {
+                    (num_points as u32, [by_return[0] as u32, by_return[1] as u32, by_return[2] as u32, by_return[3] as u32, by_return[4] as u32])
+                } else {
+                    (0u32, [0u32; 5])
+                };
+                assert_eq!(written.number_of_points_old, legacy_count);
+                assert_eq!(written.number_of_points_by_return_old, legacy_by_return);
+                let bounds = [written.min_x, written.max_x, written.min_y, written.max_y, written.min_z, written.max_z];
+                let expected = [min_x, max_x, min_y, max_y, min_z, max_z];
+                for (a, b) in bounds.iter().zip(expected.iter()) {
+                    assert!((a - b).abs() <= 0.0005, "{:?} != {:?}", bounds, expected);
+                }
+                assert_eq!((written.x_offset, written.y_offset), (1000.0, 2000.0));
+
+                // reading the file in chunks gives the points read all at once
+                let full = LasFile::new(&file_name, "r").unwrap();
+                assert_eq!(full.header.number_of_points, num_points as u64);
+                let mut reader = LasReader::new(&file_name).unwrap();
+                assert_eq!(reader.header.number_of_points_by_return, full.header.number_of_points_by_return);
+                assert_eq!((reader.header.min_z, reader.header.max_z), (full.header.min_z, full.header.max_z));
+                reader.set_chunk_size(100);
+                let mut i = 0;
+                let mut num_chunks = 0;
+                for chunk in &mut reader {
+                    let chunk = chunk.unwrap();
+                    assert_eq!(chunk.len(), if num_chunks < 12 { 100 } else { 34 });
+                    for point in chunk {
+                        assert_same_point(&full.get_record(i), &point);
+                        assert_same_point(&points[i], &point);
+                        i += 1;
+                    }
+                    num_chunks += 1;
+                }
+                assert_eq!((i, num_chunks), (num_points, 13));
+                assert_eq!(reader.points_read(), num_points as u64);
+                assert!(reader.read_chunk().unwrap().is_empty());
+                let _ = fs::remove_file(&file_name);
+            }
+        }
+    }
+}
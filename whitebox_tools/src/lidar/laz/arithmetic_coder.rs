@@ -0,0 +1,457 @@
+/*
+This code is part of the WhiteboxTools geospatial analysis library.
+Authors: Dr. John Lindsay
+Created: March 26, 2018
+Last Modified: March 26, 2018
+License: MIT
+
+Notes: The adaptive arithmetic coder used by LASzip, which is itself based on the
+FastAC coder of Amir Said. The encoder and decoder must make exactly the same
+model updates, in the same order, for a stream to be decoded.
+*/
+
+/// The threshold for interval renormalization.
+const AC_MIN_LENGTH: u32 = 0x01000000;
+/// The maximum length of the coding interval.
+const AC_MAX_LENGTH: u32 = 0xFFFFFFFF;
+
+/// Bit models are scaled to 13 bits.
+const BM_LENGTH_SHIFT: u32 = 13;
+const BM_MAX_COUNT: u32 = 1 << BM_LENGTH_SHIFT;
+
+/// Symbol models are scaled to 15 bits.
+const DM_LENGTH_SHIFT: u32 = 15;
+const DM_MAX_COUNT: u32 = 1 << DM_LENGTH_SHIFT;
+
+/// An adaptive model of a binary symbol.
+#[derive(Clone, Debug)]
+pub struct BitModel {
+    bit_0_count: u32,
+    bit_count: u32,
+    bit_0_prob: u32,
+    bits_until_update: u32,
+    update_cycle: u32,
+}
+
+impl BitModel {
+    pub fn new() -> BitModel {
+        let mut m = BitModel {
+            bit_0_count: 0,
+            bit_count: 0,
+            bit_0_prob: 0,
+            bits_until_update: 0,
+            update_cycle: 0,
+        };
+        m.init();
+        m
+    }
+
+    /// Resets the model to equiprobable bits.
+    pub fn init(&mut self) {
+        self.bit_0_count = 1;
+        self.bit_count = 2;
+        self.bit_0_prob = 1 << (BM_LENGTH_SHIFT - 1);
+        self.update_cycle = 4;
+        self.bits_until_update = 4;
+    }
+
+    fn update(&mut self) {
+        // halve the counts when a threshold is reached
+        self.bit_count += self.update_cycle;
+        if self.bit_count > BM_MAX_COUNT {
+            self.bit_count = (self.bit_count + 1) >> 1;
+            self.bit_0_count = (self.bit_0_count + 1) >> 1;
+            if self.bit_0_count == self.bit_count {
+                self.bit_count += 1;
+            }
+        }
+        // compute the scaled bit 0 probability
+        let scale = 0x80000000u32 / self.bit_count;
+        self.bit_0_prob = (self.bit_0_count.wrapping_mul(scale)) >> (31 - BM_LENGTH_SHIFT);
+
+        // set the frequency of model updates
+        self.update_cycle = (5 * self.update_cycle) >> 2;
+        if self.update_cycle > 64 {
+            self.update_cycle = 64;
+        }
+        self.bits_until_update = self.update_cycle;
+    }
+}
+
+/// An adaptive model of a symbol with a fixed number of possible values.
+#[derive(Clone, Debug)]
+pub struct SymbolModel {
+    symbols: u32,
+    last_symbol: u32,
+    distribution: Vec<u32>,
+    symbol_count: Vec<u32>,
+    total_count: u32,
+    update_cycle: u32,
+    symbols_until_update: u32,
+}
+
+impl SymbolModel {
+    /// Creates a model of `symbols` symbols, which must be in the range 2 to 2048.
+    pub fn new(symbols: u32) -> SymbolModel {
+        debug_assert!(symbols >= 2 && symbols <= 2048);
+        let mut m = SymbolModel {
+            symbols: symbols,
+            last_symbol: symbols - 1,
+            distribution: vec![0u32; symbols as usize],
+            symbol_count: vec![0u32; symbols as usize],
+            total_count: 0,
+            update_cycle: 0,
+            symbols_until_update: 0,
+        };
+        m.init();
+        m
+    }
+
+    /// Resets the model to equiprobable symbols.
+    pub fn init(&mut self) {
+        self.total_count = 0;
+        self.update_cycle = self.symbols;
+        for k in 0..self.symbols as usize {
+            self.symbol_count[k] = 1;
+        }
+        self.update();
+        self.update_cycle = (self.symbols + 6) >> 1;
+        self.symbols_until_update = self.update_cycle;
+    }
+
+    fn update(&mut self) {
+        // halve the counts when a threshold is reached
+        self.total_count += self.update_cycle;
+        if self.total_count > DM_MAX_COUNT {
+            self.total_count = 0;
+            for n in 0..self.symbols as usize {
+                self.symbol_count[n] = (self.symbol_count[n] + 1) >> 1;
+                self.total_count += self.symbol_count[n];
+            }
+        }
+
+        // compute the cumulative distribution
+        let scale = 0x80000000u32 / self.total_count;
+        let mut sum = 0u32;
+        for k in 0..self.symbols as usize {
+            self.distribution[k] = (scale.wrapping_mul(sum)) >> (31 - DM_LENGTH_SHIFT);
+            sum += self.symbol_count[k];
+        }
+
+        // set the frequency of model updates
+        self.update_cycle = (5 * self.update_cycle) >> 2;
+        let max_cycle = (self.symbols + 6) << 3;
+        if self.update_cycle > max_cycle {
+            self.update_cycle = max_cycle;
+        }
+        self.symbols_until_update = self.update_cycle;
+    }
+}
+
+/// Encodes symbols into an in-memory byte stream.
+pub struct ArithmeticEncoder {
+    base: u32,
+    length: u32,
+    bytes: Vec<u8>,
+}
+
+impl ArithmeticEncoder {
+    pub fn new() -> ArithmeticEncoder {
+        ArithmeticEncoder {
+            base: 0,
+            length: AC_MAX_LENGTH,
+            bytes: vec![],
+        }
+    }
+
+    /// Starts a new stream, discarding any bytes from an earlier stream.
+    pub fn init(&mut self) {
+        self.base = 0;
+        self.length = AC_MAX_LENGTH;
+        self.bytes.clear();
+    }
+
+    /// Returns the bytes of the stream. The stream is only complete after `done`.
+    pub fn bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    /// Finishes the stream. Trailing zero bytes are added so that the decoder, which reads
+    /// four bytes ahead, finishes reading exactly at the end of the stream.
+    pub fn done(&mut self) {
+        let init_base = self.base;
+        let mut another_byte = true;
+        if self.length > 2 * AC_MIN_LENGTH {
+            self.base = self.base.wrapping_add(AC_MIN_LENGTH);
+            self.length = AC_MIN_LENGTH >> 1;
+        } else {
+            self.base = self.base.wrapping_add(AC_MIN_LENGTH >> 1);
+            self.length = AC_MIN_LENGTH >> 9;
+            another_byte = false;
+        }
+        if init_base > self.base {
+            self.propagate_carry();
+        }
+        self.renorm_enc_interval();
+        self.bytes.push(0);
+        self.bytes.push(0);
+        if another_byte {
+            self.bytes.push(0);
+        }
+    }
+
+    pub fn encode_bit(&mut self, m: &mut BitModel, sym: u32) {
+        let x = m.bit_0_prob * (self.length >> BM_LENGTH_SHIFT);
+        if sym == 0 {
+            self.length = x;
+            m.bit_0_count += 1;
+        } else {
+            let init_base = self.base;
+            self.base = self.base.wrapping_add(x);
+            self.length -= x;
+            if init_base > self.base {
+                self.propagate_carry();
+            }
+        }
+        if self.length < AC_MIN_LENGTH {
+            self.renorm_enc_interval();
+        }
+        m.bits_until_update -= 1;
+        if m.bits_until_update == 0 {
+            m.update();
+        }
+    }
+
+    pub fn encode_symbol(&mut self, m: &mut SymbolModel, sym: u32) {
+        let init_base = self.base;
+        if sym == m.last_symbol {
+            let x = m.distribution[sym as usize] * (self.length >> DM_LENGTH_SHIFT);
+            self.base = self.base.wrapping_add(x);
+            self.length -= x;
+        } else {
+            self.length >>= DM_LENGTH_SHIFT;
+            let x = m.distribution[sym as usize] * self.length;
+            self.base = self.base.wrapping_add(x);
+            self.length = m.distribution[sym as usize + 1] * self.length - x;
+        }
+        if init_base > self.base {
+            self.propagate_carry();
+        }
+        if self.length < AC_MIN_LENGTH {
+            self.renorm_enc_interval();
+        }
+        m.symbol_count[sym as usize] += 1;
+        m.symbols_until_update -= 1;
+        if m.symbols_until_update == 0 {
+            m.update();
+        }
+    }
+
+    /// Writes up to 32 raw bits.
+    pub fn write_bits(&mut self, mut bits: u32, mut sym: u32) {
+        if bits > 19 {
+            self.write_short(sym & 0xFFFF);
+            sym >>= 16;
+            bits -= 16;
+        }
+        let init_base = self.base;
+        self.length >>= bits;
+        self.base = self.base.wrapping_add(sym * self.length);
+        if init_base > self.base {
+            self.propagate_carry();
+        }
+        if self.length < AC_MIN_LENGTH {
+            self.renorm_enc_interval();
+        }
+    }
+
+    pub fn write_short(&mut self, sym: u32) {
+        let init_base = self.base;
+        self.length >>= 16;
+        self.base = self.base.wrapping_add(sym * self.length);
+        if init_base > self.base {
+            self.propagate_carry();
+        }
+        if self.length < AC_MIN_LENGTH {
+            self.renorm_enc_interval();
+        }
+    }
+
+    pub fn write_int(&mut self, sym: u32) {
+        self.write_short(sym & 0xFFFF);
+        self.write_short(sym >> 16);
+    }
+
+    pub fn write_int64(&mut self, sym: u64) {
+        self.write_int((sym & 0xFFFFFFFF) as u32);
+        self.write_int((sym >> 32) as u32);
+    }
+
+    fn propagate_carry(&mut self) {
+        let mut i = self.bytes.len();
+        while i > 0 {
+            i -= 1;
+            if self.bytes[i] == 0xFF {
+                self.bytes[i] = 0;
+            } else {
+                self.bytes[i] += 1;
+                break;
+            }
+        }
+    }
+
+    fn renorm_enc_interval(&mut self) {
+        loop {
+            self.bytes.push((self.base >> 24) as u8);
+            self.base <<= 8;
+            self.length <<= 8;
+            if self.length >= AC_MIN_LENGTH {
+                break;
+            }
+        }
+    }
+}
+
+/// Decodes symbols from an in-memory byte stream.
+pub struct ArithmeticDecoder {
+    bytes: Vec<u8>,
+    pos: usize,
+    value: u32,
+    length: u32,
+}
+
+impl ArithmeticDecoder {
+    pub fn new(bytes: Vec<u8>) -> ArithmeticDecoder {
+        ArithmeticDecoder {
+            bytes: bytes,
+            pos: 0,
+            value: 0,
+            length: AC_MAX_LENGTH,
+        }
+    }
+
+    /// Starts decoding at the current position of the stream.
+    pub fn init(&mut self) {
+        self.length = AC_MAX_LENGTH;
+        self.value = (self.get_byte() as u32) << 24;
+        self.value |= (self.get_byte() as u32) << 16;
+        self.value |= (self.get_byte() as u32) << 8;
+        self.value |= self.get_byte() as u32;
+    }
+
+    /// Reads a byte without decoding it, i.e. from a part of the stream that precedes the
+    /// encoded data. Bytes past the end of the stream are read as zero.
+    pub fn get_byte(&mut self) -> u8 {
+        let b = if self.pos < self.bytes.len() {
+            self.bytes[self.pos]
+        } else {
+            0u8
+        };
+        self.pos += 1;
+        b
+    }
+
+    pub fn decode_bit(&mut self, m: &mut BitModel) -> u32 {
+        let x = m.bit_0_prob * (self.length >> BM_LENGTH_SHIFT);
+        let sym = if self.value >= x { 1 } else { 0 };
+        if sym == 0 {
+            self.length = x;
+            m.bit_0_count += 1;
+        } else {
+            self.value -= x;
+            self.length -= x;
+        }
+        if self.length < AC_MIN_LENGTH {
+            self.renorm_dec_interval();
+        }
+        m.bits_until_update -= 1;
+        if m.bits_until_update == 0 {
+            m.update();
+        }
+        sym
+    }
+
+    pub fn decode_symbol(&mut self, m: &mut SymbolModel) -> u32 {
+        let mut y = self.length;
+        let mut x = 0u32;
+        let mut sym = 0u32;
+        let mut n = m.symbols;
+        self.length >>= DM_LENGTH_SHIFT;
+        // bisection search of the symbol in the cumulative distribution
+        let mut k = n >> 1;
+        loop {
+            let z = self.length * m.distribution[k as usize];
+            if z > self.value {
+                n = k;
+                y = z;
+            } else {
+                sym = k;
+                x = z;
+            }
+            k = (sym + n) >> 1;
+            if k == sym {
+                break;
+            }
+        }
+        self.value -= x;
+        self.length = y - x;
+        if self.length < AC_MIN_LENGTH {
+            self.renorm_dec_interval();
+        }
+        m.symbol_count[sym as usize] += 1;
+        m.symbols_until_update -= 1;
+        if m.symbols_until_update == 0 {
+            m.update();
+        }
+        sym
+    }
+
+    /// Reads up to 32 raw bits.
+    pub fn read_bits(&mut self, mut bits: u32) -> u32 {
+        if bits > 19 {
+            let lower = self.read_short();
+            bits -= 16;
+            let upper = self.read_bits(bits) << 16;
+            return upper | lower;
+        }
+        self.length >>= bits;
+        let sym = self.value / self.length;
+        self.value -= self.length * sym;
+        if self.length < AC_MIN_LENGTH {
+            self.renorm_dec_interval();
+        }
+        sym
+    }
+
+    pub fn read_short(&mut self) -> u32 {
+        self.length >>= 16;
+        let sym = self.value / self.length;
+        self.value -= self.length * sym;
+        if self.length < AC_MIN_LENGTH {
+            self.renorm_dec_interval();
+        }
+        sym
+    }
+
+    pub fn read_int(&mut self) -> u32 {
+        let lower = self.read_short();
+        let upper = self.read_short();
+        (upper << 16) | lower
+    }
+
+    pub fn read_int64(&mut self) -> u64 {
+        let lower = self.read_int() as u64;
+        let upper = self.read_int() as u64;
+        (upper << 32) | lower
+    }
+
+    fn renorm_dec_interval(&mut self) {
+        loop {
+            self.value = (self.value << 8) | self.get_byte() as u32;
+            self.length <<= 8;
+            if self.length >= AC_MIN_LENGTH {
+                break;
+            }
+        }
+    }
+}
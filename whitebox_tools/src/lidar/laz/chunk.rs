@@ -0,0 +1,276 @@
+/*
+This code is part of the WhiteboxTools geospatial analysis library.
+Authors: Dr. John Lindsay
+Created: March 26, 2018
+Last Modified: March 26, 2018
+License: MIT
+
+Notes: A chunk is a run of points (50,000 by default) that is compressed independently
+of the other chunks. Its first point is stored raw. In a point-wise chunk, the remaining
+points follow as a single arithmetic-coded stream. In a layered chunk, the raw point is
+followed by the number of points, the byte size of each layer of each item, and then
+the layers themselves.
+*/
+
+use std::io::{Error, ErrorKind};
+use byteorder::{ByteOrder, LittleEndian};
+use super::arithmetic_coder::{ArithmeticDecoder, ArithmeticEncoder};
+use super::laz_vlr::*;
+use super::layered::*;
+use super::pointwise::*;
+
+fn unsupported_item(item: &LazItem) -> Error {
+    Error::new(ErrorKind::InvalidData,
+        format!("Unsupported LAZ item (type {}, size {}, version {}).", item.item_type, item.size, item.version))
+}
+
+fn pointwise_items(vlr: &LazVlr) -> Result<Vec<Box<PointwiseItem>>, Error> {
+    let mut items: Vec<Box<PointwiseItem>> = vec![];
+    for item in &vlr.items {
+        match (item.item_type, item.version) {
+            (ITEM_POINT10, 2) => items.push(Box::new(Point10::new())),
+            (ITEM_GPSTIME11, 2) => items.push(Box::new(GpsTime11::new())),
+            (ITEM_RGB12, 2) => items.push(Box::new(Rgb12::new())),
+            (ITEM_WAVEPACKET13, 1) => items.push(Box::new(Wavepacket13::new())),
+            (ITEM_BYTE, 2) => items.push(Box::new(Byte::new(item.size as usize))),
+            _ => return Err(unsupported_item(item)),
+        }
+    }
+    Ok(items)
+}
+
+fn layered_items(vlr: &LazVlr) -> Result<Vec<Box<LayeredItem>>, Error> {
+    let mut items: Vec<Box<LayeredItem>> = vec![];
+    for item in &vlr.items {
+        match (item.item_type, item.version) {
+            (ITEM_POINT14, 3) => items.push(Box::new(Point14Item::new())),
+            (ITEM_RGB14, 3) => items.push(Box::new(RgbNir14::new(false))),
+            (ITEM_RGBNIR14, 3) => items.push(Box::new(RgbNir14::new(true))),
+            (ITEM_WAVEPACKET14, 3) => items.push(Box::new(Wavepacket14::new())),
+            (ITEM_BYTE14, 3) => items.push(Box::new(Byte14::new(item.size as usize))),
+            _ => return Err(unsupported_item(item)),
+        }
+    }
+    if items.is_empty() || vlr.items[0].item_type != ITEM_POINT14 {
+        return Err(Error::new(ErrorKind::InvalidData, "A layered LAZ point record must begin with a POINT14 item."));
+    }
+    Ok(items)
+}
+
+enum Items {
+    Pointwise(Vec<Box<PointwiseItem>>),
+    Layered(Vec<Box<LayeredItem>>),
+}
+
+fn create_items(vlr: &LazVlr) -> Result<Items, Error> {
+    if vlr.compressor == COMPRESSOR_LAYERED_CHUNKED {
+        Ok(Items::Layered(layered_items(vlr)?))
+    } else {
+        Ok(Items::Pointwise(pointwise_items(vlr)?))
+    }
+}
+
+// the byte ranges of the items within a point record
+fn item_ranges(vlr: &LazVlr) -> Vec<(usize, usize)> {
+    let mut ranges = vec![];
+    let mut start = 0;
+    for item in &vlr.items {
+        ranges.push((start, start + item.size as usize));
+        start += item.size as usize;
+    }
+    ranges
+}
+
+/// Compresses the points of one chunk at a time.
+pub struct ChunkCompressor {
+    items: Items,
+    ranges: Vec<(usize, usize)>,
+    enc: ArithmeticEncoder,
+    first_point: Vec<u8>,
+    context: usize,
+    num_points: u32,
+}
+
+impl ChunkCompressor {
+    pub fn new(vlr: &LazVlr) -> Result<ChunkCompressor, Error> {
+        Ok(ChunkCompressor {
+            items: create_items(vlr)?,
+            ranges: item_ranges(vlr),
+            enc: ArithmeticEncoder::new(),
+            first_point: vec![],
+            context: 0,
+            num_points: 0,
+        })
+    }
+
+    /// The number of points in the current chunk.
+    pub fn num_points(&self) -> u32 {
+        self.num_points
+    }
+
+    pub fn compress(&mut self, point: &[u8]) {
+        if self.num_points == 0 {
+            self.first_point = point.to_vec();
+            self.context = 0;
+            match self.items {
+                Items::Pointwise(ref mut items) => {
+                    for (item, r) in items.iter_mut().zip(self.ranges.iter()) {
+                        item.init(&point[r.0..r.1]);
+                    }
+                    self.enc.init();
+                }
+                Items::Layered(ref mut items) => {
+                    for (item, r) in items.iter_mut().zip(self.ranges.iter()) {
+                        item.init_compressor(&point[r.0..r.1], &mut self.context);
+                    }
+                }
+            }
+        } else {
+            match self.items {
+                Items::Pointwise(ref mut items) => {
+                    for (item, r) in items.iter_mut().zip(self.ranges.iter()) {
+                        item.compress(&mut self.enc, &point[r.0..r.1]);
+                    }
+                }
+                Items::Layered(ref mut items) => {
+                    for (item, r) in items.iter_mut().zip(self.ranges.iter()) {
+                        item.compress(&point[r.0..r.1], &mut self.context);
+                    }
+                }
+            }
+        }
+        self.num_points += 1;
+    }
+
+    /// Finishes the current chunk and returns its bytes. The next point starts a new chunk.
+    pub fn finish(&mut self) -> Vec<u8> {
+        let mut bytes = self.first_point.clone();
+        match self.items {
+            Items::Pointwise(_) => {
+                self.enc.done();
+                bytes.extend_from_slice(self.enc.bytes());
+            }
+            Items::Layered(ref mut items) => {
+                let mut buf = [0u8; 4];
+                LittleEndian::write_u32(&mut buf, self.num_points);
+                bytes.extend_from_slice(&buf);
+                let layers: Vec<Vec<Vec<u8>>> = items.iter_mut().map(|item| item.finish_layers()).collect();
+                for item_layers in &layers {
+                    for layer in item_layers {
+                        LittleEndian::write_u32(&mut buf, layer.len() as u32);
+                        bytes.extend_from_slice(&buf);
+                    }
+                }
+                for item_layers in &layers {
+                    for layer in item_layers {
+                        bytes.extend_from_slice(layer);
+                    }
+                }
+            }
+        }
+        self.num_points = 0;
+        bytes
+    }
+}
+
+/// Decompresses the points of one chunk at a time.
+pub struct ChunkDecompressor {
+    items: Items,
+    ranges: Vec<(usize, usize)>,
+    record_length: usize,
+    dec: ArithmeticDecoder,
+    chunk: Vec<u8>,
+    context: usize,
+    points_read: u32,
+}
+
+impl ChunkDecompressor {
+    pub fn new(vlr: &LazVlr) -> Result<ChunkDecompressor, Error> {
+        Ok(ChunkDecompressor {
+            items: create_items(vlr)?,
+            ranges: item_ranges(vlr),
+            record_length: vlr.record_length(),
+            dec: ArithmeticDecoder::new(vec![]),
+            chunk: vec![],
+            context: 0,
+            points_read: 0,
+        })
+    }
+
+    /// Starts decompressing the bytes of a chunk. For a layered chunk, the number of points
+    /// that it contains is returned.
+    pub fn start(&mut self, chunk: Vec<u8>) -> Result<Option<u32>, Error> {
+        if chunk.len() < self.record_length {
+            return Err(Error::new(ErrorKind::InvalidData, "The LAZ chunk is too short."));
+        }
+        self.points_read = 0;
+        self.context = 0;
+        let rl = self.record_length;
+        match self.items {
+            Items::Pointwise(ref mut items) => {
+                for (item, r) in items.iter_mut().zip(self.ranges.iter()) {
+                    item.init(&chunk[r.0..r.1]);
+                }
+                self.dec = ArithmeticDecoder::new(chunk[rl..].to_vec());
+                self.dec.init();
+                self.chunk = chunk;
+                Ok(None)
+            }
+            Items::Layered(ref mut items) => {
+                let err = || Error::new(ErrorKind::InvalidData, "The LAZ chunk is too short.");
+                if chunk.len() < rl + 4 {
+                    return Err(err());
+                }
+                let count = LittleEndian::read_u32(&chunk[rl..rl + 4]);
+                let mut pos = rl + 4;
+                let mut sizes = vec![];
+                for item in items.iter() {
+                    let mut item_sizes = vec![];
+                    for _ in 0..item.num_layers() {
+                        if chunk.len() < pos + 4 {
+                            return Err(err());
+                        }
+                        item_sizes.push(LittleEndian::read_u32(&chunk[pos..pos + 4]) as usize);
+                        pos += 4;
+                    }
+                    sizes.push(item_sizes);
+                }
+                for (i, item) in items.iter_mut().enumerate() {
+                    let mut layers = vec![];
+                    for size in &sizes[i] {
+                        if chunk.len() < pos + size {
+                            return Err(err());
+                        }
+                        layers.push(chunk[pos..pos + size].to_vec());
+                        pos += size;
+                    }
+                    let r = self.ranges[i];
+                    item.init_decompressor(layers, &chunk[r.0..r.1], &mut self.context);
+                }
+                self.chunk = chunk;
+                Ok(Some(count))
+            }
+        }
+    }
+
+    /// Decompresses the next point of the chunk into `point`.
+    pub fn decompress(&mut self, point: &mut [u8]) {
+        if self.points_read == 0 {
+            point[0..self.record_length].copy_from_slice(&self.chunk[0..self.record_length]);
+        } else {
+            match self.items {
+                Items::Pointwise(ref mut items) => {
+                    for (item, r) in items.iter_mut().zip(self.ranges.iter()) {
+                        item.decompress(&mut self.dec, &mut point[r.0..r.1]);
+                    }
+                }
+                Items::Layered(ref mut items) => {
+                    for (item, r) in items.iter_mut().zip(self.ranges.iter()) {
+                        item.decompress(&mut point[r.0..r.1], &mut self.context);
+                    }
+                }
+            }
+        }
+        self.points_read += 1;
+    }
+}
@@ -0,0 +1,447 @@
+/*
+This code is part of the WhiteboxTools geospatial analysis library.
+Authors: Dr. John Lindsay
+Created: March 26, 2018
+Last Modified: March 26, 2018
+License: MIT
+*/
+
+use byteorder::{ByteOrder, LittleEndian};
+use super::arithmetic_coder::{ArithmeticDecoder, ArithmeticEncoder, SymbolModel};
+use super::integer_compressor::IntegerCompressor;
+
+// The GPS time is coded as a multiple of the last time difference.
+pub const GPSTIME_MULTI: i32 = 500;
+pub const GPSTIME_MULTI_MINUS: i32 = -10;
+pub const GPSTIME_MULTI_UNCHANGED: u32 = (GPSTIME_MULTI - GPSTIME_MULTI_MINUS + 1) as u32;
+pub const GPSTIME_MULTI_CODE_FULL: u32 = (GPSTIME_MULTI - GPSTIME_MULTI_MINUS + 2) as u32;
+pub const GPSTIME_MULTI_TOTAL: u32 = (GPSTIME_MULTI - GPSTIME_MULTI_MINUS + 6) as u32;
+
+/// Maps the number of returns and return number of a point-format 0-5 point to one of
+/// 16 contexts.
+pub const NUMBER_RETURN_MAP: [[u8; 8]; 8] = [
+    [15, 14, 13, 12, 11, 10, 9, 8],
+    [14, 0, 1, 3, 6, 10, 10, 9],
+    [13, 1, 2, 4, 7, 11, 11, 10],
+    [12, 3, 4, 5, 8, 12, 12, 11],
+    [11, 6, 7, 8, 9, 13, 13, 12],
+    [10, 10, 11, 12, 13, 14, 14, 13],
+    [9, 10, 11, 12, 13, 14, 15, 14],
+    [8, 9, 10, 11, 12, 13, 14, 15],
+];
+
+/// The distance between the return number and the number of returns of a point-format
+/// 0-5 point.
+pub const NUMBER_RETURN_LEVEL: [[u8; 8]; 8] = [
+    [0, 1, 2, 3, 4, 5, 6, 7],
+    [1, 0, 1, 2, 3, 4, 5, 6],
+    [2, 1, 0, 1, 2, 3, 4, 5],
+    [3, 2, 1, 0, 1, 2, 3, 4],
+    [4, 3, 2, 1, 0, 1, 2, 3],
+    [5, 4, 3, 2, 1, 0, 1, 2],
+    [6, 5, 4, 3, 2, 1, 0, 1],
+    [7, 6, 5, 4, 3, 2, 1, 0],
+];
+
+/// Maps the number of returns and return number of a point-format 6-10 point to one of
+/// 6 contexts.
+pub const NUMBER_RETURN_MAP_6CTX: [[u8; 16]; 16] = [
+    [0, 1, 2, 3, 4, 5, 3, 4, 4, 5, 5, 5, 5, 5, 5, 5],
+    [1, 0, 1, 3, 4, 5, 3, 4, 4, 5, 5, 5, 5, 5, 5, 5],
+    [2, 1, 2, 4, 5, 5, 4, 4, 5, 5, 5, 5, 5, 5, 5, 5],
+    [3, 3, 4, 5, 4, 5, 4, 5, 5, 5, 5, 5, 5, 5, 5, 5],
+    [4, 4, 5, 4, 5, 5, 5, 5, 5, 5, 5, 5, 5, 5, 5, 5],
+    [5, 5, 5, 5, 5, 5, 5, 5, 5, 5, 5, 5, 5, 5, 5, 5],
+    [3, 3, 4, 4, 5, 5, 5, 5, 5, 5, 5, 5, 5, 5, 5, 5],
+    [4, 4, 4, 5, 5, 5, 5, 5, 5, 5, 5, 5, 5, 5, 5, 5],
+    [4, 4, 5, 5, 5, 5, 5, 5, 5, 5, 5, 5, 5, 5, 5, 5],
+    [5, 5, 5, 5, 5, 5, 5, 5, 5, 5, 5, 5, 5, 5, 5, 5],
+    [5, 5, 5, 5, 5, 5, 5, 5, 5, 5, 5, 5, 5, 5, 5, 5],
+    [5, 5, 5, 5, 5, 5, 5, 5, 5, 5, 5, 5, 5, 5, 5, 5],
+    [5, 5, 5, 5, 5, 5, 5, 5, 5, 5, 5, 5, 5, 5, 5, 5],
+    [5, 5, 5, 5, 5, 5, 5, 5, 5, 5, 5, 5, 5, 5, 5, 5],
+    [5, 5, 5, 5, 5, 5, 5, 5, 5, 5, 5, 5, 5, 5, 5, 5],
+    [5, 5, 5, 5, 5, 5, 5, 5, 5, 5, 5, 5, 5, 5, 5, 5],
+];
+
+/// The distance between the return number and the number of returns of a point-format
+/// 6-10 point, capped at 7.
+pub fn number_return_level_8ctx(n: u32, r: u32) -> u32 {
+    let d = if n > r { n - r } else { r - n };
+    if d > 7 { 7 } else { d }
+}
+
+/// Keeps the median of the last five values added.
+#[derive(Clone, Copy, Debug)]
+pub struct StreamingMedian5 {
+    values: [i32; 5],
+    high: bool,
+}
+
+impl Default for StreamingMedian5 {
+    fn default() -> StreamingMedian5 {
+        StreamingMedian5 {
+            values: [0; 5],
+            high: true,
+        }
+    }
+}
+
+impl StreamingMedian5 {
+    pub fn init(&mut self) {
+        self.values = [0; 5];
+        self.high = true;
+    }
+
+    pub fn add(&mut self, v: i32) {
+        let values = &mut self.values;
+        if self.high {
+            if v < values[2] {
+                values[4] = values[3];
+                values[3] = values[2];
+                if v < values[0] {
+                    values[2] = values[1];
+                    values[1] = values[0];
+                    values[0] = v;
+                } else if v < values[1] {
+                    values[2] = values[1];
+                    values[1] = v;
+                } else {
+                    values[2] = v;
+                }
+            } else {
+                if v < values[3] {
+                    values[4] = values[3];
+                    values[3] = v;
+                } else {
+                    values[4] = v;
+                }
+                self.high = false;
+            }
+        } else {
+            if values[2] < v {
+                values[0] = values[1];
+                values[1] = values[2];
+                if values[4] < v {
+                    values[2] = values[3];
+                    values[3] = values[4];
+                    values[4] = v;
+                } else if values[3] < v {
+                    values[2] = values[3];
+                    values[3] = v;
+                } else {
+                    values[2] = v;
+                }
+            } else {
+                if values[1] < v {
+                    values[0] = values[1];
+                    values[1] = v;
+                } else {
+                    values[0] = v;
+                }
+                self.high = true;
+            }
+        }
+    }
+
+    pub fn get(&self) -> i32 {
+        self.values[2]
+    }
+}
+
+/// Folds a byte difference into the range 0-255.
+pub fn u8_fold(n: i32) -> u8 {
+    n as u8
+}
+
+pub fn u8_clamp(n: i32) -> i32 {
+    if n < 0 {
+        0
+    } else if n > 255 {
+        255
+    } else {
+        n
+    }
+}
+
+/// Rounds to the nearest integer, with halves rounded away from zero.
+pub fn i32_quantize(n: f32) -> i32 {
+    if n >= 0f32 {
+        (n + 0.5f32) as i32
+    } else {
+        (n - 0.5f32) as i32
+    }
+}
+
+/// The fields of a waveform packet, which follow its descriptor index.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Wavepacket {
+    pub offset: u64,
+    pub packet_size: u32,
+    pub return_point: i32,
+    pub x: i32,
+    pub y: i32,
+    pub z: i32,
+}
+
+impl Wavepacket {
+    /// Reads the 28 bytes that follow the descriptor index. The floating-point values
+    /// are coded using their bits.
+    pub fn unpack(bytes: &[u8]) -> Wavepacket {
+        Wavepacket {
+            offset: LittleEndian::read_u64(&bytes[0..8]),
+            packet_size: LittleEndian::read_u32(&bytes[8..12]),
+            return_point: LittleEndian::read_i32(&bytes[12..16]),
+            x: LittleEndian::read_i32(&bytes[16..20]),
+            y: LittleEndian::read_i32(&bytes[20..24]),
+            z: LittleEndian::read_i32(&bytes[24..28]),
+        }
+    }
+
+    pub fn pack(&self, bytes: &mut [u8]) {
+        LittleEndian::write_u64(&mut bytes[0..8], self.offset);
+        LittleEndian::write_u32(&mut bytes[8..12], self.packet_size);
+        LittleEndian::write_i32(&mut bytes[12..16], self.return_point);
+        LittleEndian::write_i32(&mut bytes[16..20], self.x);
+        LittleEndian::write_i32(&mut bytes[20..24], self.y);
+        LittleEndian::write_i32(&mut bytes[24..28], self.z);
+    }
+}
+
+/// Codes GPS times as integer differences of their bits. Up to four interleaved time
+/// sequences are tracked, and a difference is predicted as a multiple of the last
+/// difference in its sequence. Version 2 items code every time, including unchanged
+/// ones. Version 3 items only code a time when it has changed, and so their zero
+/// difference model has no symbol for an unchanged time.
+pub struct GpsTimeCoder {
+    v3: bool,
+    last: usize,
+    next: usize,
+    last_gpstime: [i64; 4],
+    last_gpstime_diff: [i32; 4],
+    multi_extreme_counter: [i32; 4],
+    m_gpstime_multi: SymbolModel,
+    m_gpstime_0diff: SymbolModel,
+    ic_gpstime: IntegerCompressor,
+}
+
+impl GpsTimeCoder {
+    pub fn new(v3: bool) -> GpsTimeCoder {
+        GpsTimeCoder {
+            v3: v3,
+            last: 0,
+            next: 0,
+            last_gpstime: [0; 4],
+            last_gpstime_diff: [0; 4],
+            multi_extreme_counter: [0; 4],
+            m_gpstime_multi: SymbolModel::new(GPSTIME_MULTI_TOTAL),
+            m_gpstime_0diff: SymbolModel::new(if v3 { 5 } else { 6 }),
+            ic_gpstime: IntegerCompressor::new(32, 9),
+        }
+    }
+
+    pub fn init(&mut self, gps_time: i64) {
+        self.last = 0;
+        self.next = 0;
+        self.last_gpstime = [gps_time, 0, 0, 0];
+        self.last_gpstime_diff = [0; 4];
+        self.multi_extreme_counter = [0; 4];
+        self.m_gpstime_multi.init();
+        self.m_gpstime_0diff.init();
+        self.ic_gpstime.init();
+    }
+
+    // the symbols of the zero difference model are shifted by one in version 2, where
+    // symbol 0 means that the time is unchanged
+    fn sym_0diff(&self, sym: u32) -> u32 {
+        if self.v3 { sym } else { sym + 1 }
+    }
+
+    fn extreme(&mut self, diff: i32) {
+        let last = self.last;
+        self.multi_extreme_counter[last] += 1;
+        if self.multi_extreme_counter[last] > 3 {
+            self.last_gpstime_diff[last] = diff;
+            self.multi_extreme_counter[last] = 0;
+        }
+    }
+
+    pub fn compress(&mut self, enc: &mut ArithmeticEncoder, this_gpstime: i64) {
+        let last = self.last;
+        if self.last_gpstime_diff[last] == 0 {
+            // the last integer difference was zero
+            if !self.v3 && this_gpstime == self.last_gpstime[last] {
+                enc.encode_symbol(&mut self.m_gpstime_0diff, 0);
+                return;
+            }
+            let curr_diff_64 = this_gpstime.wrapping_sub(self.last_gpstime[last]);
+            let curr_diff = curr_diff_64 as i32;
+            if curr_diff_64 == curr_diff as i64 {
+                // the difference can be represented with 32 bits
+                let sym = self.sym_0diff(0);
+                enc.encode_symbol(&mut self.m_gpstime_0diff, sym);
+                self.ic_gpstime.compress(enc, 0, curr_diff, 0);
+                self.last_gpstime_diff[last] = curr_diff;
+                self.multi_extreme_counter[last] = 0;
+            } else {
+                // the difference is huge, but the time may belong to another sequence
+                for i in 1..4 {
+                    let other_diff_64 = this_gpstime.wrapping_sub(self.last_gpstime[(last + i) & 3]);
+                    if other_diff_64 == (other_diff_64 as i32) as i64 {
+                        let sym = self.sym_0diff(i as u32 + 1);
+                        enc.encode_symbol(&mut self.m_gpstime_0diff, sym);
+                        self.last = (last + i) & 3;
+                        return self.compress(enc, this_gpstime);
+                    }
+                }
+                // no other sequence was found, so start a new one
+                let sym = self.sym_0diff(1);
+                enc.encode_symbol(&mut self.m_gpstime_0diff, sym);
+                self.start_sequence(enc, this_gpstime);
+            }
+            self.last_gpstime[self.last] = this_gpstime;
+        } else {
+            // the last integer difference was not zero
+            if !self.v3 && this_gpstime == self.last_gpstime[last] {
+                enc.encode_symbol(&mut self.m_gpstime_multi, GPSTIME_MULTI_UNCHANGED);
+                return;
+            }
+            let curr_diff_64 = this_gpstime.wrapping_sub(self.last_gpstime[last]);
+            let curr_diff = curr_diff_64 as i32;
+            if curr_diff_64 == curr_diff as i64 {
+                // code the difference relative to a multiple of the last difference
+                let last_diff = self.last_gpstime_diff[last];
+                let multi = i32_quantize(curr_diff as f32 / last_diff as f32);
+                if multi == 1 {
+                    enc.encode_symbol(&mut self.m_gpstime_multi, 1);
+                    self.ic_gpstime.compress(enc, last_diff, curr_diff, 1);
+                    self.multi_extreme_counter[last] = 0;
+                } else if multi > 0 {
+                    if multi < GPSTIME_MULTI {
+                        enc.encode_symbol(&mut self.m_gpstime_multi, multi as u32);
+                        let context = if multi < 10 { 2 } else { 3 };
+                        self.ic_gpstime.compress(enc, multi.wrapping_mul(last_diff), curr_diff, context);
+                    } else {
+                        enc.encode_symbol(&mut self.m_gpstime_multi, GPSTIME_MULTI as u32);
+                        self.ic_gpstime.compress(enc, GPSTIME_MULTI.wrapping_mul(last_diff), curr_diff, 4);
+                        self.extreme(curr_diff);
+                    }
+                } else if multi < 0 {
+                    if multi > GPSTIME_MULTI_MINUS {
+                        enc.encode_symbol(&mut self.m_gpstime_multi, (GPSTIME_MULTI - multi) as u32);
+                        self.ic_gpstime.compress(enc, multi.wrapping_mul(last_diff), curr_diff, 5);
+                    } else {
+                        enc.encode_symbol(&mut self.m_gpstime_multi, (GPSTIME_MULTI - GPSTIME_MULTI_MINUS) as u32);
+                        self.ic_gpstime.compress(enc, GPSTIME_MULTI_MINUS.wrapping_mul(last_diff), curr_diff, 6);
+                        self.extreme(curr_diff);
+                    }
+                } else {
+                    enc.encode_symbol(&mut self.m_gpstime_multi, 0);
+                    self.ic_gpstime.compress(enc, 0, curr_diff, 7);
+                    self.extreme(curr_diff);
+                }
+            } else {
+                // the difference is huge, but the time may belong to another sequence
+                for i in 1..4 {
+                    let other_diff_64 = this_gpstime.wrapping_sub(self.last_gpstime[(last + i) & 3]);
+                    if other_diff_64 == (other_diff_64 as i32) as i64 {
+                        enc.encode_symbol(&mut self.m_gpstime_multi, GPSTIME_MULTI_CODE_FULL + i as u32);
+                        self.last = (last + i) & 3;
+                        return self.compress(enc, this_gpstime);
+                    }
+                }
+                // no other sequence was found, so start a new one
+                enc.encode_symbol(&mut self.m_gpstime_multi, GPSTIME_MULTI_CODE_FULL);
+                self.start_sequence(enc, this_gpstime);
+            }
+            self.last_gpstime[self.last] = this_gpstime;
+        }
+    }
+
+    fn start_sequence(&mut self, enc: &mut ArithmeticEncoder, this_gpstime: i64) {
+        let last = self.last;
+        self.ic_gpstime.compress(enc, (self.last_gpstime[last] >> 32) as i32, (this_gpstime >> 32) as i32, 8);
+        enc.write_int(this_gpstime as u32);
+        self.next = (self.next + 1) & 3;
+        self.last = self.next;
+        self.last_gpstime_diff[self.last] = 0;
+        self.multi_extreme_counter[self.last] = 0;
+    }
+
+    pub fn decompress(&mut self, dec: &mut ArithmeticDecoder) -> i64 {
+        let last = self.last;
+        if self.last_gpstime_diff[last] == 0 {
+            // the last integer difference was zero
+            let mut multi = dec.decode_symbol(&mut self.m_gpstime_0diff);
+            if !self.v3 {
+                if multi == 0 {
+                    // the time is unchanged
+                    return self.last_gpstime[last];
+                }
+                multi -= 1;
+            }
+            if multi == 0 {
+                // the difference can be represented with 32 bits
+                let diff = self.ic_gpstime.decompress(dec, 0, 0);
+                self.last_gpstime_diff[last] = diff;
+                self.last_gpstime[last] = self.last_gpstime[last].wrapping_add(diff as i64);
+                self.multi_extreme_counter[last] = 0;
+            } else if multi == 1 {
+                // the difference is huge
+                self.read_sequence(dec);
+            } else {
+                // switch to another sequence
+                self.last = (last + multi as usize - 1) & 3;
+                return self.decompress(dec);
+            }
+        } else {
+            // the last integer difference was not zero
+            let multi = dec.decode_symbol(&mut self.m_gpstime_multi);
+            let last_diff = self.last_gpstime_diff[last];
+            if multi == 1 {
+                let diff = self.ic_gpstime.decompress(dec, last_diff, 1);
+                self.last_gpstime[last] = self.last_gpstime[last].wrapping_add(diff as i64);
+                self.multi_extreme_counter[last] = 0;
+            } else if multi < GPSTIME_MULTI_UNCHANGED {
+                let diff;
+                if multi == 0 {
+                    diff = self.ic_gpstime.decompress(dec, 0, 7);
+                    self.extreme(diff);
+                } else if (multi as i32) < GPSTIME_MULTI {
+                    let context = if multi < 10 { 2 } else { 3 };
+                    diff = self.ic_gpstime.decompress(dec, (multi as i32).wrapping_mul(last_diff), context);
+                } else if multi as i32 == GPSTIME_MULTI {
+                    diff = self.ic_gpstime.decompress(dec, GPSTIME_MULTI.wrapping_mul(last_diff), 4);
+                    self.extreme(diff);
+                } else {
+                    let multi = GPSTIME_MULTI - multi as i32;
+                    if multi > GPSTIME_MULTI_MINUS {
+                        diff = self.ic_gpstime.decompress(dec, multi.wrapping_mul(last_diff), 5);
+                    } else {
+                        diff = self.ic_gpstime.decompress(dec, GPSTIME_MULTI_MINUS.wrapping_mul(last_diff), 6);
+                        self.extreme(diff);
+                    }
+                }
+                self.last_gpstime[last] = self.last_gpstime[last].wrapping_add(diff as i64);
+            } else if multi == GPSTIME_MULTI_CODE_FULL {
+                self.read_sequence(dec);
+            } else if multi > GPSTIME_MULTI_CODE_FULL {
+                // switch to another sequence
+                self.last = (last + (multi - GPSTIME_MULTI_CODE_FULL) as usize) & 3;
+                return self.decompress(dec);
+            }
+        }
+        self.last_gpstime[self.last]
+    }
+
+    fn read_sequence(&mut self, dec: &mut ArithmeticDecoder) {
+        let last = self.last;
+        self.next = (self.next + 1) & 3;
+        let high = self.ic_gpstime.decompress(dec, (self.last_gpstime[last] >> 32) as i32, 8);
+        let low = dec.read_int();
+        self.last_gpstime[self.next] = (((high as u32 as u64) << 32) | low as u64) as i64;
+        self.last = self.next;
+        self.last_gpstime_diff[self.last] = 0;
+        self.multi_extreme_counter[self.last] = 0;
+    }
+}
@@ -0,0 +1,172 @@
+/*
+This code is part of the WhiteboxTools geospatial analysis library.
+Authors: Dr. John Lindsay
+Created: March 26, 2018
+Last Modified: March 26, 2018
+License: MIT
+
+Notes: Compresses an integer as the corrector of a prediction. The corrector is coded
+as the number of bits, k, that it needs, followed by its value within the k-bit
+interval. Small intervals are entropy coded in a single step. For larger intervals,
+only the highest bits_high bits are entropy coded and the remaining bits are raw.
+*/
+
+use super::arithmetic_coder::{ArithmeticDecoder, ArithmeticEncoder, BitModel, SymbolModel};
+
+pub struct IntegerCompressor {
+    k: u32,
+    bits_high: u32,
+    corr_range: u32,
+    corr_min: i32,
+    m_bits: Vec<SymbolModel>,
+    m_corrector_0: BitModel,
+    m_corrector: Vec<SymbolModel>,
+}
+
+impl IntegerCompressor {
+    /// Creates a compressor of `bits`-bit integers (32 for full range) that uses
+    /// `contexts` independent sets of models.
+    pub fn new(bits: u32, contexts: u32) -> IntegerCompressor {
+        IntegerCompressor::with_bits_high(bits, contexts, 8)
+    }
+
+    pub fn with_bits_high(bits: u32, contexts: u32, bits_high: u32) -> IntegerCompressor {
+        let (corr_bits, corr_range, corr_min) = if bits > 0 && bits < 32 {
+            let corr_range = 1u32 << bits;
+            (bits, corr_range, -((corr_range / 2) as i32))
+        } else {
+            (32u32, 0u32, i32::min_value())
+        };
+
+        let mut m_bits = Vec::with_capacity(contexts as usize);
+        for _ in 0..contexts {
+            m_bits.push(SymbolModel::new(corr_bits + 1));
+        }
+        // m_corrector[0] is unused; k = 0 is coded with the bit model
+        let mut m_corrector = Vec::with_capacity(corr_bits as usize + 1);
+        m_corrector.push(SymbolModel::new(2));
+        for i in 1..(corr_bits + 1) {
+            if i <= bits_high {
+                m_corrector.push(SymbolModel::new(1 << i));
+            } else {
+                m_corrector.push(SymbolModel::new(1 << bits_high));
+            }
+        }
+
+        IntegerCompressor {
+            k: 0,
+            bits_high: bits_high,
+            corr_range: corr_range,
+            corr_min: corr_min,
+            m_bits: m_bits,
+            m_corrector_0: BitModel::new(),
+            m_corrector: m_corrector,
+        }
+    }
+
+    /// Resets all of the models. This is done at the start of each chunk.
+    pub fn init(&mut self) {
+        for m in self.m_bits.iter_mut() {
+            m.init();
+        }
+        self.m_corrector_0.init();
+        for m in self.m_corrector.iter_mut().skip(1) {
+            m.init();
+        }
+    }
+
+    /// The number of bits of the last corrector, which is used by some items as a context.
+    pub fn get_k(&self) -> u32 {
+        self.k
+    }
+
+    pub fn compress(&mut self, enc: &mut ArithmeticEncoder, pred: i32, real: i32, context: u32) {
+        // the corrector is wrapped into the interval [corr_min, corr_max]
+        let mut corr = real.wrapping_sub(pred);
+        if self.corr_range != 0 {
+            let corr_max = self.corr_min + self.corr_range as i32 - 1;
+            if corr < self.corr_min {
+                corr += self.corr_range as i32;
+            } else if corr > corr_max {
+                corr -= self.corr_range as i32;
+            }
+        }
+        self.write_corrector(enc, corr, context as usize);
+    }
+
+    pub fn decompress(&mut self, dec: &mut ArithmeticDecoder, pred: i32, context: u32) -> i32 {
+        let mut real = pred.wrapping_add(self.read_corrector(dec, context as usize));
+        if self.corr_range != 0 {
+            if real < 0 {
+                real += self.corr_range as i32;
+            } else if real as u32 >= self.corr_range {
+                real -= self.corr_range as i32;
+            }
+        }
+        real
+    }
+
+    fn write_corrector(&mut self, enc: &mut ArithmeticEncoder, mut c: i32, context: usize) {
+        // find the number of bits needed by the corrector, i.e. the interval that it is in
+        let mut c1: u32 = if c <= 0 { c.wrapping_neg() as u32 } else { (c - 1) as u32 };
+        self.k = 0;
+        while c1 != 0 {
+            c1 >>= 1;
+            self.k += 1;
+        }
+        let k = self.k;
+        enc.encode_symbol(&mut self.m_bits[context], k);
+
+        if k != 0 {
+            if k < 32 {
+                // translate c into the k-bit interval [0, 2^k - 1]
+                if c < 0 {
+                    c = c.wrapping_add(((1u32 << k) - 1) as i32);
+                } else {
+                    c -= 1;
+                }
+                if k <= self.bits_high {
+                    enc.encode_symbol(&mut self.m_corrector[k as usize], c as u32);
+                } else {
+                    // code the highest bits_high bits and store the lower bits raw
+                    let k1 = k - self.bits_high;
+                    let c1 = (c & ((1 << k1) - 1)) as u32;
+                    let c = (c >> k1) as u32;
+                    enc.encode_symbol(&mut self.m_corrector[k as usize], c);
+                    enc.write_bits(k1, c1);
+                }
+            }
+        } else {
+            // c is 0 or 1
+            enc.encode_bit(&mut self.m_corrector_0, c as u32);
+        }
+    }
+
+    fn read_corrector(&mut self, dec: &mut ArithmeticDecoder, context: usize) -> i32 {
+        self.k = dec.decode_symbol(&mut self.m_bits[context]);
+        let k = self.k;
+        if k != 0 {
+            if k < 32 {
+                let mut c = if k <= self.bits_high {
+                    dec.decode_symbol(&mut self.m_corrector[k as usize]) as i32
+                } else {
+                    let k1 = k - self.bits_high;
+                    let c = dec.decode_symbol(&mut self.m_corrector[k as usize]) as i32;
+                    let c1 = dec.read_bits(k1) as i32;
+                    (c << k1) | c1
+                };
+                // translate c back into its interval
+                if c >= (1 << (k - 1)) {
+                    c += 1;
+                } else {
+                    c = c.wrapping_sub(((1u32 << k) - 1) as i32);
+                }
+                c
+            } else {
+                self.corr_min
+            }
+        } else {
+            dec.decode_bit(&mut self.m_corrector_0) as i32
+        }
+    }
+}
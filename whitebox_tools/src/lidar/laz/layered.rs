@@ -0,0 +1,1037 @@
+/*
+This code is part of the WhiteboxTools geospatial analysis library.
+Authors: Dr. John Lindsay
+Created: March 26, 2018
+Last Modified: March 26, 2018
+License: MIT
+
+Notes: The layered item codecs of LASzip, which are used for point formats 6-10. Each
+attribute is coded into its own layer, using its own arithmetic coder, so that a layer
+in which no value changes within a chunk need not be stored at all. The models are kept
+separately for each of the four scanner channels.
+*/
+
+use byteorder::{ByteOrder, LittleEndian};
+use super::arithmetic_coder::{ArithmeticDecoder, ArithmeticEncoder, SymbolModel};
+use super::common::*;
+use super::integer_compressor::IntegerCompressor;
+use super::pointwise::{read_rgb, write_rgb, RgbCoder, WavepacketCoder};
+
+/// An item codec that codes its attributes into separate layers. The first point of each
+/// chunk is stored raw and is passed to `init_compressor` or `init_decompressor`. The
+/// context is the scanner channel, which is set by the POINT14 item for the items that
+/// follow it.
+pub trait LayeredItem {
+    fn num_layers(&self) -> usize;
+    fn init_compressor(&mut self, item: &[u8], context: &mut usize);
+    fn init_decompressor(&mut self, layers: Vec<Vec<u8>>, item: &[u8], context: &mut usize);
+    fn compress(&mut self, item: &[u8], context: &mut usize);
+    fn decompress(&mut self, item: &mut [u8], context: &mut usize);
+    /// Finishes the chunk and returns the bytes of each layer. The layers in which nothing
+    /// changed are empty.
+    fn finish_layers(&mut self) -> Vec<Vec<u8>>;
+}
+
+/// A layer of a chunk, which is written with an encoder or read with a decoder.
+struct Layer {
+    enc: ArithmeticEncoder,
+    dec: ArithmeticDecoder,
+    changed: bool,
+}
+
+impl Layer {
+    fn new() -> Layer {
+        Layer {
+            enc: ArithmeticEncoder::new(),
+            dec: ArithmeticDecoder::new(vec![]),
+            changed: false,
+        }
+    }
+
+    fn start_write(&mut self) {
+        self.enc.init();
+        self.changed = false;
+    }
+
+    fn start_read(&mut self, bytes: Vec<u8>) {
+        self.changed = !bytes.is_empty();
+        self.dec = ArithmeticDecoder::new(bytes);
+        if self.changed {
+            self.dec.init();
+        }
+    }
+
+    fn finish(&mut self) -> Vec<u8> {
+        if self.changed {
+            self.enc.done();
+            self.enc.bytes().to_vec()
+        } else {
+            vec![]
+        }
+    }
+}
+
+fn lazy_model(models: &mut Vec<Option<SymbolModel>>, index: usize, symbols: u32) -> &mut SymbolModel {
+    if models[index].is_none() {
+        models[index] = Some(SymbolModel::new(symbols));
+    }
+    models[index].as_mut().unwrap()
+}
+
+fn init_models(models: &mut Vec<Option<SymbolModel>>) {
+    for m in models.iter_mut() {
+        if let Some(ref mut m) = *m {
+            m.init();
+        }
+    }
+}
+
+/// The fields of a 30-byte point format 6-10 core record.
+#[derive(Clone, Copy, Debug, Default)]
+struct Point14 {
+    x: i32,
+    y: i32,
+    z: i32,
+    intensity: u16,
+    return_number: u8,
+    number_of_returns: u8,
+    classification_flags: u8,
+    scanner_channel: u8,
+    scan_direction_flag: u8,
+    edge_of_flight_line: u8,
+    classification: u8,
+    user_data: u8,
+    scan_angle: i16,
+    point_source_id: u16,
+    gps_time: u64,
+    // whether the GPS time of this point changed, which is used as a context for the next
+    gps_time_change: bool,
+}
+
+impl Point14 {
+    fn unpack(item: &[u8]) -> Point14 {
+        Point14 {
+            x: LittleEndian::read_i32(&item[0..4]),
+            y: LittleEndian::read_i32(&item[4..8]),
+            z: LittleEndian::read_i32(&item[8..12]),
+            intensity: LittleEndian::read_u16(&item[12..14]),
+            return_number: item[14] & 0x0F,
+            number_of_returns: item[14] >> 4,
+            classification_flags: item[15] & 0x0F,
+            scanner_channel: (item[15] >> 4) & 0x03,
+            scan_direction_flag: (item[15] >> 6) & 1,
+            edge_of_flight_line: item[15] >> 7,
+            classification: item[16],
+            user_data: item[17],
+            scan_angle: LittleEndian::read_i16(&item[18..20]),
+            point_source_id: LittleEndian::read_u16(&item[20..22]),
+            gps_time: LittleEndian::read_u64(&item[22..30]),
+            gps_time_change: false,
+        }
+    }
+
+    fn pack(&self, item: &mut [u8]) {
+        LittleEndian::write_i32(&mut item[0..4], self.x);
+        LittleEndian::write_i32(&mut item[4..8], self.y);
+        LittleEndian::write_i32(&mut item[8..12], self.z);
+        LittleEndian::write_u16(&mut item[12..14], self.intensity);
+        item[14] = (self.return_number & 0x0F) | (self.number_of_returns << 4);
+        item[15] = (self.classification_flags & 0x0F) | ((self.scanner_channel & 0x03) << 4)
+            | ((self.scan_direction_flag & 1) << 6) | (self.edge_of_flight_line << 7);
+        item[16] = self.classification;
+        item[17] = self.user_data;
+        LittleEndian::write_i16(&mut item[18..20], self.scan_angle);
+        LittleEndian::write_u16(&mut item[20..22], self.point_source_id);
+        LittleEndian::write_u64(&mut item[22..30], self.gps_time);
+    }
+
+    fn flags(&self) -> u32 {
+        ((self.edge_of_flight_line as u32) << 5) | ((self.scan_direction_flag as u32) << 4) | self.classification_flags as u32
+    }
+
+    // the GPS times are compared as doubles, as LASzip does
+    fn gps_time_differs(&self, other: &Point14) -> bool {
+        f64::from_bits(self.gps_time) != f64::from_bits(other.gps_time)
+    }
+
+    // single (3), first (1), last (2), or intermediate (0) return, plus 4 if the GPS time changed
+    fn last_point_return_context(&self) -> usize {
+        let mut lpr = if self.return_number == 1 { 1 } else { 0 };
+        lpr += if self.return_number >= self.number_of_returns { 2 } else { 0 };
+        lpr += if self.gps_time_change { 4 } else { 0 };
+        lpr
+    }
+}
+
+// the layers of the POINT14 item
+const CHANNEL_RETURNS_XY: usize = 0;
+const Z: usize = 1;
+const CLASSIFICATION: usize = 2;
+const FLAGS: usize = 3;
+const INTENSITY: usize = 4;
+const SCAN_ANGLE: usize = 5;
+const USER_DATA: usize = 6;
+const POINT_SOURCE: usize = 7;
+const GPS_TIME: usize = 8;
+
+struct Point14Context {
+    unused: bool,
+    last_item: Point14,
+    last_intensity: [u16; 8],
+    last_x_diff_median5: [StreamingMedian5; 12],
+    last_y_diff_median5: [StreamingMedian5; 12],
+    last_z: [i32; 8],
+    m_changed_values: Vec<SymbolModel>,
+    m_scanner_channel: SymbolModel,
+    m_number_of_returns: Vec<Option<SymbolModel>>,
+    m_return_number: Vec<Option<SymbolModel>>,
+    m_return_number_gps_same: SymbolModel,
+    ic_dx: IntegerCompressor,
+    ic_dy: IntegerCompressor,
+    ic_z: IntegerCompressor,
+    m_classification: Vec<Option<SymbolModel>>,
+    m_flags: Vec<Option<SymbolModel>>,
+    m_user_data: Vec<Option<SymbolModel>>,
+    ic_intensity: IntegerCompressor,
+    ic_scan_angle: IntegerCompressor,
+    ic_point_source_id: IntegerCompressor,
+    gps_time: GpsTimeCoder,
+}
+
+impl Point14Context {
+    fn new() -> Point14Context {
+        Point14Context {
+            unused: true,
+            last_item: Point14::default(),
+            last_intensity: [0; 8],
+            last_x_diff_median5: [StreamingMedian5::default(); 12],
+            last_y_diff_median5: [StreamingMedian5::default(); 12],
+            last_z: [0; 8],
+            m_changed_values: vec![SymbolModel::new(128); 8],
+            m_scanner_channel: SymbolModel::new(3),
+            m_number_of_returns: vec![None; 16],
+            m_return_number: vec![None; 16],
+            m_return_number_gps_same: SymbolModel::new(13),
+            ic_dx: IntegerCompressor::new(32, 2),
+            ic_dy: IntegerCompressor::new(32, 22),
+            ic_z: IntegerCompressor::new(32, 20),
+            m_classification: vec![None; 64],
+            m_flags: vec![None; 64],
+            m_user_data: vec![None; 64],
+            ic_intensity: IntegerCompressor::new(16, 4),
+            ic_scan_angle: IntegerCompressor::new(16, 2),
+            ic_point_source_id: IntegerCompressor::new(16, 1),
+            gps_time: GpsTimeCoder::new(true),
+        }
+    }
+
+    fn init(&mut self, item: &Point14) {
+        for m in self.m_changed_values.iter_mut() {
+            m.init();
+        }
+        self.m_scanner_channel.init();
+        init_models(&mut self.m_number_of_returns);
+        init_models(&mut self.m_return_number);
+        self.m_return_number_gps_same.init();
+        self.ic_dx.init();
+        self.ic_dy.init();
+        for i in 0..12 {
+            self.last_x_diff_median5[i].init();
+            self.last_y_diff_median5[i].init();
+        }
+        self.ic_z.init();
+        self.last_z = [item.z; 8];
+        init_models(&mut self.m_classification);
+        init_models(&mut self.m_flags);
+        init_models(&mut self.m_user_data);
+        self.ic_intensity.init();
+        self.last_intensity = [item.intensity; 8];
+        self.ic_scan_angle.init();
+        self.ic_point_source_id.init();
+        self.gps_time.init(item.gps_time as i64);
+        self.last_item = *item;
+        self.last_item.gps_time_change = false;
+        self.unused = false;
+    }
+}
+
+/// The 30-byte core of point formats 6-10, version 3.
+pub struct Point14Item {
+    layers: Vec<Layer>,
+    contexts: Vec<Option<Point14Context>>,
+    current_context: usize,
+}
+
+impl Point14Item {
+    pub fn new() -> Point14Item {
+        Point14Item {
+            layers: (0..9).map(|_| Layer::new()).collect(),
+            contexts: vec![None, None, None, None],
+            current_context: 0,
+        }
+    }
+
+    fn create_and_init_context(&mut self, context: usize, item: &Point14) {
+        if self.contexts[context].is_none() {
+            self.contexts[context] = Some(Point14Context::new());
+        }
+        self.contexts[context].as_mut().unwrap().init(item);
+    }
+
+    fn is_unused(&self, context: usize) -> bool {
+        match self.contexts[context] {
+            Some(ref c) => c.unused,
+            None => true,
+        }
+    }
+
+    fn start_chunk(&mut self, item: &[u8], context: &mut usize) {
+        for c in self.contexts.iter_mut() {
+            if let Some(ref mut c) = *c {
+                c.unused = true;
+            }
+        }
+        let point = Point14::unpack(item);
+        self.current_context = point.scanner_channel as usize;
+        *context = self.current_context;
+        self.create_and_init_context(self.current_context, &point);
+    }
+}
+
+impl LayeredItem for Point14Item {
+    fn num_layers(&self) -> usize {
+        9
+    }
+
+    fn init_compressor(&mut self, item: &[u8], context: &mut usize) {
+        for layer in self.layers.iter_mut() {
+            layer.start_write();
+        }
+        // the coordinate layers are always stored
+        self.layers[CHANNEL_RETURNS_XY].changed = true;
+        self.layers[Z].changed = true;
+        self.start_chunk(item, context);
+    }
+
+    fn init_decompressor(&mut self, layers: Vec<Vec<u8>>, item: &[u8], context: &mut usize) {
+        for (layer, bytes) in self.layers.iter_mut().zip(layers.into_iter()) {
+            layer.start_read(bytes);
+        }
+        self.start_chunk(item, context);
+    }
+
+    fn compress(&mut self, item: &[u8], context: &mut usize) {
+        let point = Point14::unpack(item);
+        let mut last = self.contexts[self.current_context].as_ref().unwrap().last_item;
+        let lpr = last.last_point_return_context();
+
+        // if the scanner channel has changed, and its context exists, use its last point
+        let scanner_channel = point.scanner_channel as usize;
+        if scanner_channel != self.current_context && !self.is_unused(scanner_channel) {
+            last = self.contexts[scanner_channel].as_ref().unwrap().last_item;
+        }
+
+        let point_source_change = point.point_source_id != last.point_source_id;
+        let gps_time_change = point.gps_time_differs(&last);
+        let scan_angle_change = point.scan_angle != last.scan_angle;
+        let last_n = last.number_of_returns as u32;
+        let last_r = last.return_number as u32;
+        let n = point.number_of_returns as u32;
+        let r = point.return_number as u32;
+
+        // a 7-bit mask of the values that have changed
+        let mut changed_values = ((scanner_channel != self.current_context) as u32) << 6
+            | (point_source_change as u32) << 5
+            | (gps_time_change as u32) << 4
+            | (scan_angle_change as u32) << 3
+            | ((n != last_n) as u32) << 2;
+        // the return number is the same (0), plus one (1), minus one (2), or different (3)
+        if r != last_r {
+            if r == (last_r + 1) % 16 {
+                changed_values |= 1;
+            } else if r == (last_r + 15) % 16 {
+                changed_values |= 2;
+            } else {
+                changed_values |= 3;
+            }
+        }
+        {
+            let ctx = self.contexts[self.current_context].as_mut().unwrap();
+            let enc = &mut self.layers[CHANNEL_RETURNS_XY].enc;
+            enc.encode_symbol(&mut ctx.m_changed_values[lpr], changed_values);
+            if changed_values & (1 << 6) != 0 {
+                let diff = scanner_channel as i32 - self.current_context as i32;
+                let sym = if diff > 0 { diff - 1 } else { diff - 1 + 4 };
+                enc.encode_symbol(&mut ctx.m_scanner_channel, sym as u32);
+            }
+        }
+        if changed_values & (1 << 6) != 0 {
+            if self.is_unused(scanner_channel) {
+                let prev = self.contexts[self.current_context].as_ref().unwrap().last_item;
+                self.create_and_init_context(scanner_channel, &prev);
+                last = self.contexts[scanner_channel].as_ref().unwrap().last_item;
+            }
+            self.current_context = scanner_channel;
+        }
+        *context = self.current_context;
+
+        let ctx = self.contexts[self.current_context].as_mut().unwrap();
+        let gps_bit = gps_time_change as usize;
+        {
+            let enc = &mut self.layers[CHANNEL_RETURNS_XY].enc;
+            if changed_values & (1 << 2) != 0 {
+                enc.encode_symbol(lazy_model(&mut ctx.m_number_of_returns, last_n as usize, 16), n);
+            }
+            if changed_values & 3 == 3 {
+                if gps_time_change {
+                    enc.encode_symbol(lazy_model(&mut ctx.m_return_number, last_r as usize, 16), r);
+                } else {
+                    let diff = r as i32 - last_r as i32;
+                    let sym = if diff > 1 { diff - 2 } else { diff - 2 + 16 };
+                    enc.encode_symbol(&mut ctx.m_return_number_gps_same, sym as u32);
+                }
+            }
+        }
+
+        let m = NUMBER_RETURN_MAP_6CTX[n as usize][r as usize] as usize;
+        let l = number_return_level_8ctx(n, r) as usize;
+        let mut cpr = if r == 1 { 2 } else { 0 };
+        cpr += if r >= n { 1 } else { 0 };
+
+        // the x and y coordinates
+        {
+            let enc = &mut self.layers[CHANNEL_RETURNS_XY].enc;
+            let idx = (m << 1) | gps_bit;
+            let median = ctx.last_x_diff_median5[idx].get();
+            let diff = point.x.wrapping_sub(last.x);
+            ctx.ic_dx.compress(enc, median, diff, (n == 1) as u32);
+            ctx.last_x_diff_median5[idx].add(diff);
+
+            let k_bits = ctx.ic_dx.get_k();
+            let median = ctx.last_y_diff_median5[idx].get();
+            let diff = point.y.wrapping_sub(last.y);
+            let c = (n == 1) as u32 + if k_bits < 20 { k_bits & 0xFFFFFFFE } else { 20 };
+            ctx.ic_dy.compress(enc, median, diff, c);
+            ctx.last_y_diff_median5[idx].add(diff);
+        }
+
+        // the z coordinate
+        {
+            let k_bits = (ctx.ic_dx.get_k() + ctx.ic_dy.get_k()) / 2;
+            let c = (n == 1) as u32 + if k_bits < 18 { k_bits & 0xFFFFFFFE } else { 18 };
+            ctx.ic_z.compress(&mut self.layers[Z].enc, ctx.last_z[l], point.z, c);
+            ctx.last_z[l] = point.z;
+        }
+
+        // the classification
+        {
+            let layer = &mut self.layers[CLASSIFICATION];
+            if point.classification != last.classification {
+                layer.changed = true;
+            }
+            let ccc = (((last.classification & 0x1F) as usize) << 1) + if cpr == 3 { 1 } else { 0 };
+            layer.enc.encode_symbol(lazy_model(&mut ctx.m_classification, ccc, 256), point.classification as u32);
+        }
+
+        // the flags
+        {
+            let layer = &mut self.layers[FLAGS];
+            let last_flags = last.flags();
+            let flags = point.flags();
+            if flags != last_flags {
+                layer.changed = true;
+            }
+            layer.enc.encode_symbol(lazy_model(&mut ctx.m_flags, last_flags as usize, 64), flags);
+        }
+
+        // the intensity
+        {
+            let layer = &mut self.layers[INTENSITY];
+            if point.intensity != last.intensity {
+                layer.changed = true;
+            }
+            let idx = (cpr << 1) | gps_bit;
+            ctx.ic_intensity.compress(&mut layer.enc, ctx.last_intensity[idx] as i32, point.intensity as i32, cpr as u32);
+            ctx.last_intensity[idx] = point.intensity;
+        }
+
+        // the scan angle
+        if scan_angle_change {
+            let layer = &mut self.layers[SCAN_ANGLE];
+            layer.changed = true;
+            ctx.ic_scan_angle.compress(&mut layer.enc, last.scan_angle as i32, point.scan_angle as i32, gps_bit as u32);
+        }
+
+        // the user data
+        {
+            let layer = &mut self.layers[USER_DATA];
+            if point.user_data != last.user_data {
+                layer.changed = true;
+            }
+            let model = lazy_model(&mut ctx.m_user_data, (last.user_data / 4) as usize, 256);
+            layer.enc.encode_symbol(model, point.user_data as u32);
+        }
+
+        // the point source ID
+        if point_source_change {
+            let layer = &mut self.layers[POINT_SOURCE];
+            layer.changed = true;
+            ctx.ic_point_source_id.compress(&mut layer.enc, last.point_source_id as i32, point.point_source_id as i32, 0);
+        }
+
+        // the GPS time
+        if gps_time_change {
+            let layer = &mut self.layers[GPS_TIME];
+            layer.changed = true;
+            ctx.gps_time.compress(&mut layer.enc, point.gps_time as i64);
+        }
+
+        ctx.last_item = point;
+        ctx.last_item.gps_time_change = gps_time_change;
+    }
+
+    fn decompress(&mut self, item: &mut [u8], context: &mut usize) {
+        let lpr = self.contexts[self.current_context].as_ref().unwrap().last_item.last_point_return_context();
+        let changed_values = {
+            let ctx = self.contexts[self.current_context].as_mut().unwrap();
+            self.layers[CHANNEL_RETURNS_XY].dec.decode_symbol(&mut ctx.m_changed_values[lpr])
+        };
+
+        if changed_values & (1 << 6) != 0 {
+            let diff = {
+                let ctx = self.contexts[self.current_context].as_mut().unwrap();
+                self.layers[CHANNEL_RETURNS_XY].dec.decode_symbol(&mut ctx.m_scanner_channel) as usize
+            };
+            let scanner_channel = (self.current_context + diff + 1) % 4;
+            if self.is_unused(scanner_channel) {
+                let prev = self.contexts[self.current_context].as_ref().unwrap().last_item;
+                self.create_and_init_context(scanner_channel, &prev);
+            }
+            self.current_context = scanner_channel;
+            self.contexts[scanner_channel].as_mut().unwrap().last_item.scanner_channel = scanner_channel as u8;
+        }
+        *context = self.current_context;
+
+        let point_source_change = changed_values & (1 << 5) != 0;
+        let gps_time_change = changed_values & (1 << 4) != 0;
+        let scan_angle_change = changed_values & (1 << 3) != 0;
+        let gps_bit = gps_time_change as usize;
+
+        let ctx = self.contexts[self.current_context].as_mut().unwrap();
+        let mut last = ctx.last_item;
+        let last_n = last.number_of_returns as u32;
+        let last_r = last.return_number as u32;
+
+        let n;
+        let r;
+        {
+            let dec = &mut self.layers[CHANNEL_RETURNS_XY].dec;
+            n = if changed_values & (1 << 2) != 0 {
+                dec.decode_symbol(lazy_model(&mut ctx.m_number_of_returns, last_n as usize, 16))
+            } else {
+                last_n
+            };
+            r = match changed_values & 3 {
+                0 => last_r,
+                1 => (last_r + 1) % 16,
+                2 => (last_r + 15) % 16,
+                _ => {
+                    if gps_time_change {
+                        dec.decode_symbol(lazy_model(&mut ctx.m_return_number, last_r as usize, 16))
+                    } else {
+                        let sym = dec.decode_symbol(&mut ctx.m_return_number_gps_same);
+                        (last_r + sym + 2) % 16
+                    }
+                }
+            };
+        }
+        last.number_of_returns = n as u8;
+        last.return_number = r as u8;
+
+        let m = NUMBER_RETURN_MAP_6CTX[n as usize][r as usize] as usize;
+        let l = number_return_level_8ctx(n, r) as usize;
+        let mut cpr = if r == 1 { 2 } else { 0 };
+        cpr += if r >= n { 1 } else { 0 };
+
+        // the x and y coordinates
+        {
+            let dec = &mut self.layers[CHANNEL_RETURNS_XY].dec;
+            let idx = (m << 1) | gps_bit;
+            let median = ctx.last_x_diff_median5[idx].get();
+            let diff = ctx.ic_dx.decompress(dec, median, (n == 1) as u32);
+            last.x = last.x.wrapping_add(diff);
+            ctx.last_x_diff_median5[idx].add(diff);
+
+            let median = ctx.last_y_diff_median5[idx].get();
+            let k_bits = ctx.ic_dx.get_k();
+            let c = (n == 1) as u32 + if k_bits < 20 { k_bits & 0xFFFFFFFE } else { 20 };
+            let diff = ctx.ic_dy.decompress(dec, median, c);
+            last.y = last.y.wrapping_add(diff);
+            ctx.last_y_diff_median5[idx].add(diff);
+        }
+
+        // the z coordinate
+        if self.layers[Z].changed {
+            let k_bits = (ctx.ic_dx.get_k() + ctx.ic_dy.get_k()) / 2;
+            let c = (n == 1) as u32 + if k_bits < 18 { k_bits & 0xFFFFFFFE } else { 18 };
+            last.z = ctx.ic_z.decompress(&mut self.layers[Z].dec, ctx.last_z[l], c);
+            ctx.last_z[l] = last.z;
+        }
+
+        // the classification
+        if self.layers[CLASSIFICATION].changed {
+            let ccc = (((last.classification & 0x1F) as usize) << 1) + if cpr == 3 { 1 } else { 0 };
+            let model = lazy_model(&mut ctx.m_classification, ccc, 256);
+            last.classification = self.layers[CLASSIFICATION].dec.decode_symbol(model) as u8;
+        }
+
+        // the flags
+        if self.layers[FLAGS].changed {
+            let model = lazy_model(&mut ctx.m_flags, last.flags() as usize, 64);
+            let flags = self.layers[FLAGS].dec.decode_symbol(model);
+            last.edge_of_flight_line = ((flags >> 5) & 1) as u8;
+            last.scan_direction_flag = ((flags >> 4) & 1) as u8;
+            last.classification_flags = (flags & 0x0F) as u8;
+        }
+
+        // the intensity
+        if self.layers[INTENSITY].changed {
+            let idx = (cpr << 1) | gps_bit;
+            let intensity = ctx.ic_intensity.decompress(&mut self.layers[INTENSITY].dec, ctx.last_intensity[idx] as i32, cpr as u32) as u16;
+            ctx.last_intensity[idx] = intensity;
+            last.intensity = intensity;
+        }
+
+        // the scan angle
+        if self.layers[SCAN_ANGLE].changed && scan_angle_change {
+            last.scan_angle = ctx.ic_scan_angle.decompress(&mut self.layers[SCAN_ANGLE].dec, last.scan_angle as i32, gps_bit as u32) as i16;
+        }
+
+        // the user data
+        if self.layers[USER_DATA].changed {
+            let model = lazy_model(&mut ctx.m_user_data, (last.user_data / 4) as usize, 256);
+            last.user_data = self.layers[USER_DATA].dec.decode_symbol(model) as u8;
+        }
+
+        // the point source ID
+        if self.layers[POINT_SOURCE].changed && point_source_change {
+            last.point_source_id = ctx.ic_point_source_id.decompress(&mut self.layers[POINT_SOURCE].dec, last.point_source_id as i32, 0) as u16;
+        }
+
+        // the GPS time
+        if self.layers[GPS_TIME].changed && gps_time_change {
+            last.gps_time = ctx.gps_time.decompress(&mut self.layers[GPS_TIME].dec) as u64;
+        }
+
+        last.pack(item);
+        last.gps_time_change = gps_time_change;
+        ctx.last_item = last;
+    }
+
+    fn finish_layers(&mut self) -> Vec<Vec<u8>> {
+        self.layers.iter_mut().map(|l| l.finish()).collect()
+    }
+}
+
+struct RgbContext {
+    unused: bool,
+    last_item: [u16; 3],
+    last_nir: u16,
+    rgb: RgbCoder,
+    m_nir_bytes_used: SymbolModel,
+    m_nir_diff: [SymbolModel; 2],
+}
+
+impl RgbContext {
+    fn new() -> RgbContext {
+        RgbContext {
+            unused: true,
+            last_item: [0; 3],
+            last_nir: 0,
+            rgb: RgbCoder::new(),
+            m_nir_bytes_used: SymbolModel::new(4),
+            m_nir_diff: [SymbolModel::new(256), SymbolModel::new(256)],
+        }
+    }
+
+    fn init(&mut self, item: &[u8], has_nir: bool) {
+        self.rgb.init();
+        self.m_nir_bytes_used.init();
+        self.m_nir_diff[0].init();
+        self.m_nir_diff[1].init();
+        self.last_item = read_rgb(item);
+        if has_nir {
+            self.last_nir = LittleEndian::read_u16(&item[6..8]);
+        }
+        self.unused = false;
+    }
+}
+
+/// The colour of point formats 7, 8, and 10, version 3. The near infrared band of formats 8
+/// and 10 is coded in a second layer.
+pub struct RgbNir14 {
+    has_nir: bool,
+    layers: Vec<Layer>,
+    contexts: Vec<Option<RgbContext>>,
+    current_context: usize,
+}
+
+impl RgbNir14 {
+    pub fn new(has_nir: bool) -> RgbNir14 {
+        RgbNir14 {
+            has_nir: has_nir,
+            layers: (0..if has_nir { 2 } else { 1 }).map(|_| Layer::new()).collect(),
+            contexts: vec![None, None, None, None],
+            current_context: 0,
+        }
+    }
+
+    fn is_unused(&self, context: usize) -> bool {
+        match self.contexts[context] {
+            Some(ref c) => c.unused,
+            None => true,
+        }
+    }
+
+    fn create_and_init_context(&mut self, context: usize, item: &[u8]) {
+        if self.contexts[context].is_none() {
+            self.contexts[context] = Some(RgbContext::new());
+        }
+        let has_nir = self.has_nir;
+        self.contexts[context].as_mut().unwrap().init(item, has_nir);
+    }
+
+    fn start_chunk(&mut self, item: &[u8], context: &mut usize) {
+        for c in self.contexts.iter_mut() {
+            if let Some(ref mut c) = *c {
+                c.unused = true;
+            }
+        }
+        self.current_context = *context;
+        self.create_and_init_context(*context, item);
+    }
+
+    // switches to the context of the current point, creating it from the last point if needed
+    fn switch_context(&mut self, context: usize) {
+        if self.current_context != context {
+            if self.is_unused(context) {
+                let mut last = [0u8; 8];
+                {
+                    let c = self.contexts[self.current_context].as_ref().unwrap();
+                    write_rgb(&c.last_item, &mut last);
+                    LittleEndian::write_u16(&mut last[6..8], c.last_nir);
+                }
+                self.create_and_init_context(context, &last);
+            }
+            self.current_context = context;
+        }
+    }
+}
+
+impl LayeredItem for RgbNir14 {
+    fn num_layers(&self) -> usize {
+        self.layers.len()
+    }
+
+    fn init_compressor(&mut self, item: &[u8], context: &mut usize) {
+        for layer in self.layers.iter_mut() {
+            layer.start_write();
+        }
+        self.start_chunk(item, context);
+    }
+
+    fn init_decompressor(&mut self, layers: Vec<Vec<u8>>, item: &[u8], context: &mut usize) {
+        for (layer, bytes) in self.layers.iter_mut().zip(layers.into_iter()) {
+            layer.start_read(bytes);
+        }
+        self.start_chunk(item, context);
+    }
+
+    fn compress(&mut self, item: &[u8], context: &mut usize) {
+        self.switch_context(*context);
+        let ctx = self.contexts[self.current_context].as_mut().unwrap();
+
+        let rgb = read_rgb(item);
+        if ctx.rgb.compress(&mut self.layers[0].enc, &ctx.last_item, &rgb) {
+            self.layers[0].changed = true;
+        }
+        ctx.last_item = rgb;
+
+        if self.has_nir {
+            let nir = LittleEndian::read_u16(&item[6..8]);
+            let last = ctx.last_nir;
+            let enc = &mut self.layers[1].enc;
+            let mut sym = ((last & 0x00FF) != (nir & 0x00FF)) as u32;
+            sym |= (((last & 0xFF00) != (nir & 0xFF00)) as u32) << 1;
+            enc.encode_symbol(&mut ctx.m_nir_bytes_used, sym);
+            if sym & 1 != 0 {
+                let diff_l = (nir & 255) as i32 - (last & 255) as i32;
+                enc.encode_symbol(&mut ctx.m_nir_diff[0], u8_fold(diff_l) as u32);
+            }
+            if sym & (1 << 1) != 0 {
+                let diff_h = (nir >> 8) as i32 - (last >> 8) as i32;
+                enc.encode_symbol(&mut ctx.m_nir_diff[1], u8_fold(diff_h) as u32);
+            }
+            if sym != 0 {
+                self.layers[1].changed = true;
+            }
+            ctx.last_nir = nir;
+        }
+    }
+
+    fn decompress(&mut self, item: &mut [u8], context: &mut usize) {
+        self.switch_context(*context);
+        let ctx = self.contexts[self.current_context].as_mut().unwrap();
+
+        if self.layers[0].changed {
+            ctx.last_item = ctx.rgb.decompress(&mut self.layers[0].dec, &ctx.last_item);
+        }
+        write_rgb(&ctx.last_item, item);
+
+        if self.has_nir {
+            if self.layers[1].changed {
+                let last = ctx.last_nir;
+                let dec = &mut self.layers[1].dec;
+                let sym = dec.decode_symbol(&mut ctx.m_nir_bytes_used);
+                let mut nir = if sym & 1 != 0 {
+                    let corr = dec.decode_symbol(&mut ctx.m_nir_diff[0]) as i32;
+                    u8_fold(corr + (last & 255) as i32) as u16
+                } else {
+                    last & 0x00FF
+                };
+                if sym & (1 << 1) != 0 {
+                    let corr = dec.decode_symbol(&mut ctx.m_nir_diff[1]) as i32;
+                    nir |= (u8_fold(corr + (last >> 8) as i32) as u16) << 8;
+                } else {
+                    nir |= last & 0xFF00;
+                }
+                ctx.last_nir = nir;
+            }
+            LittleEndian::write_u16(&mut item[6..8], ctx.last_nir);
+        }
+    }
+
+    fn finish_layers(&mut self) -> Vec<Vec<u8>> {
+        self.layers.iter_mut().map(|l| l.finish()).collect()
+    }
+}
+
+struct WavepacketContext {
+    unused: bool,
+    last_item: [u8; 29],
+    coder: WavepacketCoder,
+}
+
+/// The waveform packet of point formats 9 and 10, version 3.
+pub struct Wavepacket14 {
+    layer: Layer,
+    contexts: Vec<Option<WavepacketContext>>,
+    current_context: usize,
+}
+
+impl Wavepacket14 {
+    pub fn new() -> Wavepacket14 {
+        Wavepacket14 {
+            layer: Layer::new(),
+            contexts: vec![None, None, None, None],
+            current_context: 0,
+        }
+    }
+
+    fn create_and_init_context(&mut self, context: usize, item: &[u8]) {
+        if self.contexts[context].is_none() {
+            self.contexts[context] = Some(WavepacketContext {
+                unused: true,
+                last_item: [0; 29],
+                coder: WavepacketCoder::new(),
+            });
+        }
+        let c = self.contexts[context].as_mut().unwrap();
+        c.coder.init();
+        c.last_item.copy_from_slice(&item[0..29]);
+        c.unused = false;
+    }
+
+    fn start_chunk(&mut self, item: &[u8], context: &mut usize) {
+        for c in self.contexts.iter_mut() {
+            if let Some(ref mut c) = *c {
+                c.unused = true;
+            }
+        }
+        self.current_context = *context;
+        self.create_and_init_context(*context, item);
+    }
+
+    fn switch_context(&mut self, context: usize) {
+        if self.current_context != context {
+            let unused = match self.contexts[context] {
+                Some(ref c) => c.unused,
+                None => true,
+            };
+            if unused {
+                let last = self.contexts[self.current_context].as_ref().unwrap().last_item;
+                self.create_and_init_context(context, &last);
+            }
+            self.current_context = context;
+        }
+    }
+}
+
+impl LayeredItem for Wavepacket14 {
+    fn num_layers(&self) -> usize {
+        1
+    }
+
+    fn init_compressor(&mut self, item: &[u8], context: &mut usize) {
+        self.layer.start_write();
+        self.start_chunk(item, context);
+    }
+
+    fn init_decompressor(&mut self, layers: Vec<Vec<u8>>, item: &[u8], context: &mut usize) {
+        if let Some(bytes) = layers.into_iter().next() {
+            self.layer.start_read(bytes);
+        }
+        self.start_chunk(item, context);
+    }
+
+    fn compress(&mut self, item: &[u8], context: &mut usize) {
+        self.switch_context(*context);
+        let ctx = self.contexts[self.current_context].as_mut().unwrap();
+        if item[0..29] != ctx.last_item[..] {
+            self.layer.changed = true;
+        }
+        ctx.coder.compress(&mut self.layer.enc, &ctx.last_item, item);
+        ctx.last_item.copy_from_slice(&item[0..29]);
+    }
+
+    fn decompress(&mut self, item: &mut [u8], context: &mut usize) {
+        self.switch_context(*context);
+        let ctx = self.contexts[self.current_context].as_mut().unwrap();
+        if self.layer.changed {
+            ctx.coder.decompress(&mut self.layer.dec, &ctx.last_item, item);
+            ctx.last_item.copy_from_slice(&item[0..29]);
+        } else {
+            item[0..29].copy_from_slice(&ctx.last_item);
+        }
+    }
+
+    fn finish_layers(&mut self) -> Vec<Vec<u8>> {
+        vec![self.layer.finish()]
+    }
+}
+
+struct BytesContext {
+    unused: bool,
+    last_item: Vec<u8>,
+    m_bytes: Vec<SymbolModel>,
+}
+
+/// Extra bytes that follow the standard fields of a point format 6-10 record, version 3.
+/// Each byte is coded in its own layer.
+pub struct Byte14 {
+    number: usize,
+    layers: Vec<Layer>,
+    contexts: Vec<Option<BytesContext>>,
+    current_context: usize,
+}
+
+impl Byte14 {
+    pub fn new(number: usize) -> Byte14 {
+        Byte14 {
+            number: number,
+            layers: (0..number).map(|_| Layer::new()).collect(),
+            contexts: vec![None, None, None, None],
+            current_context: 0,
+        }
+    }
+
+    fn create_and_init_context(&mut self, context: usize, item: &[u8]) {
+        let number = self.number;
+        if self.contexts[context].is_none() {
+            self.contexts[context] = Some(BytesContext {
+                unused: true,
+                last_item: vec![0u8; number],
+                m_bytes: vec![SymbolModel::new(256); number],
+            });
+        }
+        let c = self.contexts[context].as_mut().unwrap();
+        for m in c.m_bytes.iter_mut() {
+            m.init();
+        }
+        c.last_item.copy_from_slice(&item[0..number]);
+        c.unused = false;
+    }
+
+    fn start_chunk(&mut self, item: &[u8], context: &mut usize) {
+        for c in self.contexts.iter_mut() {
+            if let Some(ref mut c) = *c {
+                c.unused = true;
+            }
+        }
+        self.current_context = *context;
+        self.create_and_init_context(*context, item);
+    }
+
+    fn switch_context(&mut self, context: usize) {
+        if self.current_context != context {
+            let unused = match self.contexts[context] {
+                Some(ref c) => c.unused,
+                None => true,
+            };
+            if unused {
+                let last = self.contexts[self.current_context].as_ref().unwrap().last_item.clone();
+                self.create_and_init_context(context, &last);
+            }
+            self.current_context = context;
+        }
+    }
+}
+
+impl LayeredItem for Byte14 {
+    fn num_layers(&self) -> usize {
+        self.number
+    }
+
+    fn init_compressor(&mut self, item: &[u8], context: &mut usize) {
+        for layer in self.layers.iter_mut() {
+            layer.start_write();
+        }
+        self.start_chunk(item, context);
+    }
+
+    fn init_decompressor(&mut self, layers: Vec<Vec<u8>>, item: &[u8], context: &mut usize) {
+        for (layer, bytes) in self.layers.iter_mut().zip(layers.into_iter()) {
+            layer.start_read(bytes);
+        }
+        self.start_chunk(item, context);
+    }
+
+    fn compress(&mut self, item: &[u8], context: &mut usize) {
+        self.switch_context(*context);
+        let ctx = self.contexts[self.current_context].as_mut().unwrap();
+        for i in 0..self.number {
+            let diff = item[i] as i32 - ctx.last_item[i] as i32;
+            self.layers[i].enc.encode_symbol(&mut ctx.m_bytes[i], u8_fold(diff) as u32);
+            if diff != 0 {
+                self.layers[i].changed = true;
+            }
+            ctx.last_item[i] = item[i];
+        }
+    }
+
+    fn decompress(&mut self, item: &mut [u8], context: &mut usize) {
+        self.switch_context(*context);
+        let ctx = self.contexts[self.current_context].as_mut().unwrap();
+        for i in 0..self.number {
+            if self.layers[i].changed {
+                let value = ctx.last_item[i] as i32 + self.layers[i].dec.decode_symbol(&mut ctx.m_bytes[i]) as i32;
+                ctx.last_item[i] = u8_fold(value);
+            }
+            item[i] = ctx.last_item[i];
+        }
+    }
+
+    fn finish_layers(&mut self) -> Vec<Vec<u8>> {
+        self.layers.iter_mut().map(|l| l.finish()).collect()
+    }
+}
@@ -0,0 +1,202 @@
+/*
+This code is part of the WhiteboxTools geospatial analysis library.
+Authors: Dr. John Lindsay
+Created: March 26, 2018
+Last Modified: April 6, 2018
+License: MIT
+*/
+
+use std::io::{Error, ErrorKind};
+use byteorder::{ByteOrder, LittleEndian};
+use lidar::Vlr;
+
+pub const LASZIP_USER_ID: &str = "laszip encoded";
+pub const LASZIP_RECORD_ID: u16 = 22_204;
+
+/// Point-wise chunked compression, used by point formats 0-5.
+pub const COMPRESSOR_POINTWISE_CHUNKED: u16 = 2;
+/// Layered chunked compression, used by point formats 6-10.
+pub const COMPRESSOR_LAYERED_CHUNKED: u16 = 3;
+
+pub const ITEM_BYTE: u16 = 0;
+pub const ITEM_POINT10: u16 = 6;
+pub const ITEM_GPSTIME11: u16 = 7;
+pub const ITEM_RGB12: u16 = 8;
+pub const ITEM_WAVEPACKET13: u16 = 9;
+pub const ITEM_POINT14: u16 = 10;
+pub const ITEM_RGB14: u16 = 11;
+pub const ITEM_RGBNIR14: u16 = 12;
+pub const ITEM_WAVEPACKET14: u16 = 13;
+pub const ITEM_BYTE14: u16 = 14;
+
+const DEFAULT_CHUNK_SIZE: u32 = 50_000;
+
+// the sizes of the standard fields of each point format
+const BASE_RECORD_LENGTHS: [u16; 11] = [20, 28, 26, 34, 57, 63, 30, 36, 38, 59, 67];
+
+/// One of the items that make up a compressed point record.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LazItem {
+    pub item_type: u16,
+    pub size: u16,
+    pub version: u16,
+}
+
+impl LazItem {
+    fn new(item_type: u16, size: u16, version: u16) -> LazItem {
+        LazItem { item_type: item_type, size: size, version: version }
+    }
+}
+
+/// The contents of the LASzip VLR, which describes how the point records of a LAZ file
+/// were compressed.
+#[derive(Clone, Debug)]
+pub struct LazVlr {
+    pub compressor: u16,
+    pub coder: u16,
+    pub version_major: u8,
+    pub version_minor: u8,
+    pub version_revision: u16,
+    pub options: u32,
+    pub chunk_size: u32,
+    pub number_of_special_evlrs: i64,
+    pub offset_to_special_evlrs: i64,
+    pub items: Vec<LazItem>,
+}
+
+impl LazVlr {
+    /// Creates the description of a compressor for the point format and record length.
+    /// Any bytes that follow the standard fields of the format are compressed as extra bytes.
+    pub fn new(point_format: u8, point_record_length: u16) -> Result<LazVlr, Error> {
+        if point_format > 10 {
+            return Err(Error::new(ErrorKind::InvalidInput, format!("Point format {} cannot be compressed.", point_format)));
+        }
+        let base = BASE_RECORD_LENGTHS[point_format as usize];
+        if point_record_length < base {
+            return Err(Error::new(ErrorKind::InvalidInput,
+                "The point record length is shorter than the point format requires, e.g. because intensity or user data were omitted. This cannot be compressed."));
+        }
+        let extra_bytes = point_record_length - base;
+
+        let mut items = vec![];
+        if point_format < 6 {
+            items.push(LazItem::new(ITEM_POINT10, 20, 2));
+            if point_format == 1 || point_format >= 3 {
+                items.push(LazItem::new(ITEM_GPSTIME11, 8, 2));
+            }
+            if point_format == 2 || point_format == 3 || point_format == 5 {
+                items.push(LazItem::new(ITEM_RGB12, 6, 2));
+            }
+            if point_format == 4 || point_format == 5 {
+                items.push(LazItem::new(ITEM_WAVEPACKET13, 29, 1));
+            }
+            if extra_bytes > 0 {
+                items.push(LazItem::new(ITEM_BYTE, extra_bytes, 2));
+            }
+        } else {
+            items.push(LazItem::new(ITEM_POINT14, 30, 3));
+            match point_format {
+                7 => items.push(LazItem::new(ITEM_RGB14, 6, 3)),
+                8 | 10 => items.push(LazItem::new(ITEM_RGBNIR14, 8, 3)),
+                _ => {}
+            }
+            if point_format == 9 || point_format == 10 {
+                items.push(LazItem::new(ITEM_WAVEPACKET14, 29, 3));
+            }
+            if extra_bytes > 0 {
+                items.push(LazItem::new(ITEM_BYTE14, extra_bytes, 3));
+            }
+        }
+
+        Ok(LazVlr {
+            compressor: if point_format < 6 { COMPRESSOR_POINTWISE_CHUNKED } else { COMPRESSOR_LAYERED_CHUNKED },
+            coder: 0,
+            version_major: 3,
+            version_minor: 2,
+            version_revision: 0,
+            options: 0,
+            chunk_size: DEFAULT_CHUNK_SIZE,
+            number_of_special_evlrs: -1,
+            offset_to_special_evlrs: -1,
+            items: items,
+        })
+    }
+
+    /// Returns true if the VLR is a LASzip VLR.
+    pub fn is_laszip_vlr(vlr: &Vlr) -> bool {
+        vlr.record_id == LASZIP_RECORD_ID && vlr.user_id.trim_matches('\0').trim() == LASZIP_USER_ID
+    }
+
+    pub fn from_vlr(vlr: &Vlr) -> Result<LazVlr, Error> {
+        let data = &vlr.binary_data;
+        if data.len() < 34 {
+            return Err(Error::new(ErrorKind::InvalidData, "The LASzip VLR is too short."));
+        }
+        let num_items = LittleEndian::read_u16(&data[32..34]) as usize;
+        if data.len() < 34 + 6 * num_items {
+            return Err(Error::new(ErrorKind::InvalidData, "The LASzip VLR is too short."));
+        }
+        let mut items = Vec::with_capacity(num_items);
+        for i in 0..num_items {
+            let p = 34 + 6 * i;
+            items.push(LazItem::new(
+                LittleEndian::read_u16(&data[p..p + 2]),
+                LittleEndian::read_u16(&data[p + 2..p + 4]),
+                LittleEndian::read_u16(&data[p + 4..p + 6]),
+            ));
+        }
+        let lv = LazVlr {
+            compressor: LittleEndian::read_u16(&data[0..2]),
+            coder: LittleEndian::read_u16(&data[2..4]),
+            version_major: data[4],
+            version_minor: data[5],
+            version_revision: LittleEndian::read_u16(&data[6..8]),
+            options: LittleEndian::read_u32(&data[8..12]),
+            chunk_size: LittleEndian::read_u32(&data[12..16]),
+            number_of_special_evlrs: LittleEndian::read_i64(&data[16..24]),
+            offset_to_special_evlrs: LittleEndian::read_i64(&data[24..32]),
+            items: items,
+        };
+        if lv.compressor != COMPRESSOR_POINTWISE_CHUNKED && lv.compressor != COMPRESSOR_LAYERED_CHUNKED {
+            return Err(Error::new(ErrorKind::InvalidData,
+                format!("Unsupported LAZ compressor ({}). Only chunked compression is supported.", lv.compressor)));
+        }
+        if lv.coder != 0 {
+            return Err(Error::new(ErrorKind::InvalidData, "Unsupported LAZ coder."));
+        }
+        Ok(lv)
+    }
+
+    pub fn to_vlr(&self) -> Vlr {
+        let mut data = vec![0u8; 34 + 6 * self.items.len()];
+        LittleEndian::write_u16(&mut data[0..2], self.compressor);
+        LittleEndian::write_u16(&mut data[2..4], self.coder);
+        data[4] = self.version_major;
+        data[5] = self.version_minor;
+        LittleEndian::write_u16(&mut data[6..8], self.version_revision);
+        LittleEndian::write_u32(&mut data[8..12], self.options);
+        LittleEndian::write_u32(&mut data[12..16], self.chunk_size);
+        LittleEndian::write_i64(&mut data[16..24], self.number_of_special_evlrs);
+        LittleEndian::write_i64(&mut data[24..32], self.offset_to_special_evlrs);
+        LittleEndian::write_u16(&mut data[32..34], self.items.len() as u16);
+        for (i, item) in self.items.iter().enumerate() {
+            let p = 34 + 6 * i;
+            LittleEndian::write_u16(&mut data[p..p + 2], item.item_type);
+            LittleEndian::write_u16(&mut data[p + 2..p + 4], item.size);
+            LittleEndian::write_u16(&mut data[p + 4..p + 6], item.version);
+        }
+        Vlr {
+            reserved: 0,
+            user_id: LASZIP_USER_ID.to_string(),
+            record_id: LASZIP_RECORD_ID,
+            record_length_after_header: data.len() as u16,
+            description: "by laszip of www.laszip.org".to_string(),
+            binary_data: data,
+        }
+    }
+
+    /// The length of a point record, i.e. the sum of the item sizes.
+    pub fn record_length(&self) -> usize {
+        self.items.iter().map(|i| i.size as usize).sum()
+    }
+}
@@ -0,0 +1,299 @@
+// private sub-modules defined in other files
+mod arithmetic_coder;
+mod chunk;
+mod common;
+mod integer_compressor;
+mod layered;
+mod laz_vlr;
+mod pointwise;
+mod reader;
+mod writer;
+
+// exports identifiers from private sub-modules in the current module namespace
+pub use self::laz_vlr::LazItem;
+pub use self::laz_vlr::LazVlr;
+pub use self::reader::LazReader;
+pub use self::writer::LazWriter;
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::fs;
+    use std::fs::File;
+    use std::io::{Cursor, Write};
+    use byteorder::{ByteOrder, LittleEndian};
+    use lidar::*;
+    use super::*;
+
+    // the length of the standard fields of each point format
+    const RECORD_LENGTHS: [usize; 11] = [20, 28, 26, 34, 57, 63, 30, 36, 38, 59, 67];
+
+    struct Lcg(u64);
+
+    impl Lcg {
+        fn next(&mut self) -> u64 {
+            self.0 = self.0.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            self.0 >> 33
+        }
+    }
+
+    fn temp_file(name: &str) -> String {
+        env::temp_dir().join(name).to_str().unwrap().to_string()
+    }
+
+    // An Extra Bytes VLR describing a single unsigned short attribute.
+    fn extra_bytes_vlr() -> Vlr {
+        let mut binary_data = vec![0u8; 192];
+        binary_data[2] = 3;
+        binary_data[4..11].copy_from_slice(b"quality");
+        Vlr {
+            user_id: "LASF_Spec".to_string(),
+            record_id: 4,
+            record_length_after_header: 192,
+            description: "Extra Bytes Record".to_string(),
+            binary_data: binary_data,
+            ..Default::default()
+        }
+    }
+
+    // A point of the given format in which each of the fields of the format varies.
+    fn test_point(point_format: u8, i: usize, rng: &mut Lcg) -> LidarPointRecord {
+        let is_64bit = point_format >= 6;
+        let mut p = PointData {
+            x: 500_000.0 + i as f64 * 0.25 + (rng.next() % 100) as f64 * 0.001,
+            y: 4_800_000.0 + (i / 10) as f64 * 0.5,
+            z: 200.0 + (rng.next() % 50_000) as f64 * 0.001,
+            intensity: rng.next() as u16,
+            point_bit_field: rng.next() as u8,
+            class_bit_field: rng.next() as u8,
+            user_data: rng.next() as u8,
+            point_source_id: (i / 100) as u16,
+            is_64bit: is_64bit,
+            ..Default::default()
+        };
+        if is_64bit {
+            p.classification = (rng.next() % 256) as u8;
+            p.scan_angle = (rng.next() % 30_000) as i16 - 15_000;
+        } else {
+            p.scan_angle = (rng.next() % 180) as i16 - 90;
+        }
+        let gps_data = 250_000.0 + i as f64 * 0.00001 + (rng.next() % 10) as f64;
+        let colour_data = ColourData {
+            red: rng.next() as u16,
+            green: rng.next() as u16,
+            blue: rng.next() as u16,
+            nir: if point_format == 8 || point_format == 10 { rng.next() as u16 } else { 0 },
+        };
+        let wave_packet = WaveformPacket {
+            packet_descriptor_index: (rng.next() % 4) as u8,
+            offset_to_waveform_data: 60 + i as u64 * 256,
+            waveform_packet_size: 256,
+            ret_point_waveform_loc: (rng.next() % 1000) as f32 * 0.5,
+            xt: -0.0001,
+            yt: 0.0002,
+            zt: (rng.next() % 100) as f32 * -0.0001,
+        };
+        match point_format {
+            0 => LidarPointRecord::PointRecord0 { point_data: p },
+            1 => LidarPointRecord::PointRecord1 { point_data: p, gps_data: gps_data },
+            2 => LidarPointRecord::PointRecord2 { point_data: p, colour_data: colour_data },
+            3 => LidarPointRecord::PointRecord3 { point_data: p, gps_data: gps_data, colour_data: colour_data },
+            4 => LidarPointRecord::PointRecord4 { point_data: p, gps_data: gps_data, wave_packet: wave_packet },
+            5 => LidarPointRecord::PointRecord5 { point_data: p, gps_data: gps_data, colour_data: colour_data, wave_packet: wave_packet },
+            6 => LidarPointRecord::PointRecord6 { point_data: p, gps_data: gps_data },
+            7 => LidarPointRecord::PointRecord7 { point_data: p, gps_data: gps_data, colour_data: colour_data },
+            8 => LidarPointRecord::PointRecord8 { point_data: p, gps_data: gps_data, colour_data: colour_data },
+            9 => LidarPointRecord::PointRecord9 { point_data: p, gps_data: gps_data, wave_packet: wave_packet },
+            _ => LidarPointRecord::PointRecord10 { point_data: p, gps_data: gps_data, colour_data: colour_data, wave_packet: wave_packet },
+        }
+    }
+
+    fn assert_same_point(expected: &LidarPointRecord, actual: &LidarPointRecord) {
+        let (a, b) = (expected.point_data(), actual.point_data());
+        assert!((a.x - b.x).abs() < 1e-6 && (a.y - b.y).abs() < 1e-6 && (a.z - b.z).abs() < 1e-6);
+        assert_eq!((a.intensity, a.point_bit_field, a.class_bit_field, a.classification),
+            (b.intensity, b.point_bit_field, b.class_bit_field, b.classification));
+        assert_eq!((a.scan_angle, a.user_data, a.point_source_id, a.is_64bit),
+            (b.scan_angle, b.user_data, b.point_source_id, b.is_64bit));
+        assert_eq!(expected.gps_time(), actual.gps_time());
+        assert_eq!(expected.colour_data(), actual.colour_data());
+        match (expected.waveform_packet(), actual.waveform_packet()) {
+            (Some(a), Some(b)) => {
+                assert_eq!((a.packet_descriptor_index, a.offset_to_waveform_data, a.waveform_packet_size),
+                    (b.packet_descriptor_index, b.offset_to_waveform_data, b.waveform_packet_size));
+                assert_eq!((a.ret_point_waveform_loc, a.xt, a.yt, a.zt), (b.ret_point_waveform_loc, b.xt, b.yt, b.zt));
+            },
+            (None, None) => {},
+            _ => panic!("The waveform packets of the points differ."),
+        }
+    }
+
+    #[test]
+    fn laz_round_trip_for_every_point_format() {
+        for point_format in 0..11u8 {
+            for &with_extra_bytes in &[false, true] {
+                let file_name = temp_file(&format!("wbt_laz_round_trip_{}_{}.laz", point_format, with_extra_bytes));
+                let mut rng = Lcg(point_format as u64 + 1);
+                let num_points = 750;
+                let mut points = vec![];
+                let mut output = LasFile::new(&file_name, "w").unwrap();
+                output.add_header(LasHeader { point_format: point_format, ..Default::default() });
+                if with_extra_bytes {
+                    output.add_vlr(extra_bytes_vlr());
+                }
+                for i in 0..num_points {
+                    let point = test_point(point_format, i, &mut rng);
+                    if with_extra_bytes {
+                        output.add_point_record_with_extra_bytes(point, &[i as u8, (i >> 8) as u8]);
+                    } else {
+                        output.add_point_record(point);
+                    }
+                    points.push(point);
+                }
+                output.write().unwrap();
+
+                // the point format of a compressed file is flagged in the header
+                let bytes = fs::read(&file_name).unwrap();
+                assert_eq!(bytes[104], point_format | 128);
+
+                let input = LasFile::new(&file_name, "r").unwrap();
+                assert_eq!(input.header.point_format, point_format);
+                assert_eq!(input.header.number_of_points, num_points as u64);
+                assert_eq!(input.header.point_record_length as usize,
+                    RECORD_LENGTHS[point_format as usize] + if with_extra_bytes { 2 } else { 0 });
+                // the LASzip VLR is not retained when the file is read
+                assert_eq!(input.vlr_data.len(), if with_extra_bytes { 1 } else { 0 });
+                for i in 0..num_points {
+                    assert_same_point(&points[i], &input.get_record(i));
+                    if with_extra_bytes {
+                        assert_eq!(input.get_extra_bytes(i), &[i as u8, (i >> 8) as u8]);
+                    } else {
+                        assert!(input.get_extra_bytes(i).is_empty());
+                    }
+                }
+                let _ = fs::remove_file(&file_name);
+            }
+        }
+    }
+
+    #[test]
+    fn point_records_are_decompressed_unchanged_across_chunks() {
+        let mut rng = Lcg(42);
+        for point_format in 0..11u8 {
+            let record_length = RECORD_LENGTHS[point_format as usize] + 3;
+            let mut vlr = LazVlr::new(point_format, record_length as u16).unwrap();
+            vlr.chunk_size = 100;
+            let vlr = LazVlr::from_vlr(&vlr.to_vlr()).unwrap();
+            assert_eq!(vlr.chunk_size, 100);
+
+            // arbitrary bytes, including values that are unusual for each field
+            let num_points = 1_050;
+            let records: Vec<u8> = (0..num_points * record_length).map(|_| rng.next() as u8).collect();
+            let mut writer = LazWriter::new(Cursor::new(vec![]), &vlr, record_length, 0).unwrap();
+            for record in records.chunks(record_length) {
+                writer.write_point(record).unwrap();
+            }
+            let compressed = writer.done().unwrap().into_inner();
+
+            let mut reader = LazReader::new(Cursor::new(&compressed[..]), &vlr, 0, num_points as u64, record_length).unwrap();
+            let mut record = vec![0u8; record_length];
+            for expected in records.chunks(record_length) {
+                reader.read_point(&mut record).unwrap();
+                assert_eq!(&record[..], expected);
+            }
+        }
+    }
+
+    #[test]
+    fn records_without_every_field_cannot_be_compressed() {
+        assert!(LazVlr::new(1, 27).is_err());
+        assert!(LazVlr::new(11, 100).is_err());
+    }
+
+    // LASzip stores the first point of each chunk verbatim and codes the rest, so a file
+    // written by LASzip with a chunk size of one point holds its points unchanged. The bytes
+    // that end each chunk and the coded chunk table below were worked out by hand from the
+    // arithmetic coder of the LASzip reference implementation, rather than by the encoder
+    // in this module.
+    #[test]
+    fn laszip_files_are_read() {
+        let las_file = temp_file("wbt_laszip_fixture.las");
+        let laz_file = temp_file("wbt_laszip_fixture.laz");
+        let mut rng = Lcg(7);
+        let mut output = LasFile::new(&las_file, "w").unwrap();
+        output.add_header(LasHeader { point_format: 0, ..Default::default() });
+        for i in 0..2 {
+            output.add_point_record(test_point(0, i, &mut rng));
+        }
+        output.write().unwrap();
+        let las = fs::read(&las_file).unwrap();
+        let offset_to_points = LittleEndian::read_u32(&las[96..100]) as usize;
+        assert_eq!(las.len(), offset_to_points + 40);
+
+        let mut laz = las[..offset_to_points].to_vec();
+        laz[104] |= 128;
+        let num_vlrs = LittleEndian::read_u32(&laz[100..104]);
+        LittleEndian::write_u32(&mut laz[100..104], num_vlrs + 1);
+        LittleEndian::write_u32(&mut laz[96..100], offset_to_points as u32 + 94);
+        // the LASzip VLR, describing a single POINT10 item
+        laz.extend_from_slice(&[0, 0]);
+        laz.extend_from_slice(b"laszip encoded\0\0");
+        laz.extend_from_slice(&[0xBC, 0x56, 40, 0]);
+        let mut description = [0u8; 32];
+        description[..27].copy_from_slice(b"by laszip of www.laszip.org");
+        laz.extend_from_slice(&description);
+        laz.extend_from_slice(&[
+            2, 0, 0, 0, // point-wise chunked compressor, arithmetic coder
+            2, 2, 0, 0, // LASzip 2.2r0
+            0, 0, 0, 0, // options
+            1, 0, 0, 0, // chunk size
+            255, 255, 255, 255, 255, 255, 255, 255, // no special EVLRs
+            255, 255, 255, 255, 255, 255, 255, 255,
+            1, 0, // number of items
+            6, 0, 20, 0, 2, 0, // POINT10, version 2
+        ]);
+        let mut buf = [0u8; 8];
+        LittleEndian::write_i64(&mut buf, (offset_to_points + 94 + 8 + 48) as i64);
+        laz.extend_from_slice(&buf);
+        let mut point_data = vec![];
+        for point in las[offset_to_points..].chunks(20) {
+            point_data.extend_from_slice(point);
+            // the coder finishes a stream without any symbols with one byte and three zeros
+            point_data.extend_from_slice(&[1, 0, 0, 0]);
+        }
+        // the chunk table: version 0, two chunks, and their lengths of 24 bytes, each coded
+        // as the difference from the length of the previous chunk
+        point_data.extend_from_slice(&[0, 0, 0, 0, 2, 0, 0, 0, 0x2C, 0x5B, 1, 0, 0, 0]);
+        laz.extend_from_slice(&point_data);
+        {
+            let mut f = File::create(&laz_file).unwrap();
+            f.write_all(&laz).unwrap();
+        }
+
+        let expected = LasFile::new(&las_file, "r").unwrap();
+        let input = LasFile::new(&laz_file, "r").unwrap();
+        assert_eq!(input.header.point_format, 0);
+        assert_eq!(input.header.number_of_points, 2);
+        assert_eq!(input.vlr_data.len(), expected.vlr_data.len());
+        for i in 0..2 {
+            assert_same_point(&expected.get_record(i), &input.get_record(i));
+        }
+
+        // the writer produces the same point data, although it writes the position of the
+        // chunk table after the table
+        let vlr = LazVlr::from_vlr(&Vlr {
+            binary_data: laz[offset_to_points + 54..offset_to_points + 94].to_vec(),
+            ..Default::default()
+        }).unwrap();
+        assert_eq!(vlr.chunk_size, 1);
+        let mut writer = LazWriter::new(Cursor::new(vec![]), &vlr, 20, 0).unwrap();
+        for point in las[offset_to_points..].chunks(20) {
+            writer.write_point(point).unwrap();
+        }
+        let compressed = writer.done().unwrap().into_inner();
+        assert_eq!(&compressed[8..compressed.len() - 8], &point_data[..]);
+
+        let _ = fs::remove_file(&las_file);
+        let _ = fs::remove_file(&laz_file);
+    }
+}
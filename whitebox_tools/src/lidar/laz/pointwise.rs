@@ -0,0 +1,612 @@
+/*
+This code is part of the WhiteboxTools geospatial analysis library.
+Authors: Dr. John Lindsay
+Created: March 26, 2018
+Last Modified: March 26, 2018
+License: MIT
+
+Notes: The point-wise item codecs of LASzip, which are used for point formats 0-5.
+All of the items of a point are coded in turn using a single arithmetic coder.
+*/
+
+use byteorder::{ByteOrder, LittleEndian};
+use super::arithmetic_coder::{ArithmeticDecoder, ArithmeticEncoder, SymbolModel};
+use super::common::*;
+use super::integer_compressor::IntegerCompressor;
+
+/// An item codec, e.g. for the POINT10 or RGB12 part of a point record. The first point of
+/// each chunk is stored raw and is passed to `init`. Each codec keeps the state needed to
+/// both compress and decompress, although a single codec only ever does one of the two.
+pub trait PointwiseItem {
+    fn init(&mut self, item: &[u8]);
+    fn compress(&mut self, enc: &mut ArithmeticEncoder, item: &[u8]);
+    fn decompress(&mut self, dec: &mut ArithmeticDecoder, item: &mut [u8]);
+}
+
+/// Returns a lazily created symbol model, e.g. one of a table of models indexed by a byte.
+fn lazy_model(models: &mut Vec<Option<SymbolModel>>, index: usize, symbols: u32) -> &mut SymbolModel {
+    if models[index].is_none() {
+        models[index] = Some(SymbolModel::new(symbols));
+    }
+    models[index].as_mut().unwrap()
+}
+
+/// The 20-byte core of point formats 0-5, version 2.
+pub struct Point10 {
+    last_item: [u8; 20],
+    last_x_diff_median5: [StreamingMedian5; 16],
+    last_y_diff_median5: [StreamingMedian5; 16],
+    last_intensity: [u16; 16],
+    last_height: [i32; 8],
+    m_changed_values: SymbolModel,
+    m_scan_angle_rank: [SymbolModel; 2],
+    m_bit_byte: Vec<Option<SymbolModel>>,
+    m_classification: Vec<Option<SymbolModel>>,
+    m_user_data: Vec<Option<SymbolModel>>,
+    ic_intensity: IntegerCompressor,
+    ic_point_source_id: IntegerCompressor,
+    ic_dx: IntegerCompressor,
+    ic_dy: IntegerCompressor,
+    ic_z: IntegerCompressor,
+}
+
+impl Point10 {
+    pub fn new() -> Point10 {
+        Point10 {
+            last_item: [0; 20],
+            last_x_diff_median5: [StreamingMedian5::default(); 16],
+            last_y_diff_median5: [StreamingMedian5::default(); 16],
+            last_intensity: [0; 16],
+            last_height: [0; 8],
+            m_changed_values: SymbolModel::new(64),
+            m_scan_angle_rank: [SymbolModel::new(256), SymbolModel::new(256)],
+            m_bit_byte: vec![None; 256],
+            m_classification: vec![None; 256],
+            m_user_data: vec![None; 256],
+            ic_intensity: IntegerCompressor::new(16, 4),
+            ic_point_source_id: IntegerCompressor::new(16, 1),
+            ic_dx: IntegerCompressor::new(32, 2),
+            ic_dy: IntegerCompressor::new(32, 22),
+            ic_z: IntegerCompressor::new(32, 20),
+        }
+    }
+
+    fn return_contexts(&self) -> (u32, usize, usize) {
+        let r = (self.last_item[14] & 7) as usize;
+        let n = ((self.last_item[14] >> 3) & 7) as usize;
+        let m = NUMBER_RETURN_MAP[n][r] as usize;
+        let l = NUMBER_RETURN_LEVEL[n][r] as usize;
+        (n as u32, m, l)
+    }
+}
+
+fn xy_context(n: u32, k_bits: u32, max: u32) -> u32 {
+    (if n == 1 { 1 } else { 0 }) + if k_bits < max { k_bits & 0xFFFFFFFE } else { max }
+}
+
+impl PointwiseItem for Point10 {
+    fn init(&mut self, item: &[u8]) {
+        for i in 0..16 {
+            self.last_x_diff_median5[i].init();
+            self.last_y_diff_median5[i].init();
+            self.last_intensity[i] = 0;
+            self.last_height[i / 2] = 0;
+        }
+        self.m_changed_values.init();
+        self.m_scan_angle_rank[0].init();
+        self.m_scan_angle_rank[1].init();
+        for i in 0..256 {
+            if let Some(ref mut m) = self.m_bit_byte[i] {
+                m.init();
+            }
+            if let Some(ref mut m) = self.m_classification[i] {
+                m.init();
+            }
+            if let Some(ref mut m) = self.m_user_data[i] {
+                m.init();
+            }
+        }
+        self.ic_intensity.init();
+        self.ic_point_source_id.init();
+        self.ic_dx.init();
+        self.ic_dy.init();
+        self.ic_z.init();
+        self.last_item.copy_from_slice(&item[0..20]);
+    }
+
+    fn compress(&mut self, enc: &mut ArithmeticEncoder, item: &[u8]) {
+        let r = (item[14] & 7) as usize;
+        let n = ((item[14] >> 3) & 7) as usize;
+        let m = NUMBER_RETURN_MAP[n][r] as usize;
+        let l = NUMBER_RETURN_LEVEL[n][r] as usize;
+        let intensity = LittleEndian::read_u16(&item[12..14]);
+        let point_source_id = LittleEndian::read_u16(&item[18..20]);
+        let last_point_source_id = LittleEndian::read_u16(&self.last_item[18..20]);
+
+        // which of the other values have changed
+        let changed_values = ((self.last_item[14] != item[14]) as u32) << 5
+            | ((self.last_intensity[m] != intensity) as u32) << 4
+            | ((self.last_item[15] != item[15]) as u32) << 3
+            | ((self.last_item[16] != item[16]) as u32) << 2
+            | ((self.last_item[17] != item[17]) as u32) << 1
+            | (last_point_source_id != point_source_id) as u32;
+        enc.encode_symbol(&mut self.m_changed_values, changed_values);
+
+        if changed_values & 32 != 0 {
+            let model = lazy_model(&mut self.m_bit_byte, self.last_item[14] as usize, 256);
+            enc.encode_symbol(model, item[14] as u32);
+        }
+        if changed_values & 16 != 0 {
+            self.ic_intensity.compress(enc, self.last_intensity[m] as i32, intensity as i32, if m < 3 { m as u32 } else { 3 });
+            self.last_intensity[m] = intensity;
+        }
+        if changed_values & 8 != 0 {
+            let model = lazy_model(&mut self.m_classification, self.last_item[15] as usize, 256);
+            enc.encode_symbol(model, item[15] as u32);
+        }
+        if changed_values & 4 != 0 {
+            let scan_direction_flag = ((item[14] >> 6) & 1) as usize;
+            enc.encode_symbol(&mut self.m_scan_angle_rank[scan_direction_flag],
+                              u8_fold(item[16] as i32 - self.last_item[16] as i32) as u32);
+        }
+        if changed_values & 2 != 0 {
+            let model = lazy_model(&mut self.m_user_data, self.last_item[17] as usize, 256);
+            enc.encode_symbol(model, item[17] as u32);
+        }
+        if changed_values & 1 != 0 {
+            self.ic_point_source_id.compress(enc, last_point_source_id as i32, point_source_id as i32, 0);
+        }
+
+        // the x coordinate
+        let median = self.last_x_diff_median5[m].get();
+        let diff = LittleEndian::read_i32(&item[0..4]).wrapping_sub(LittleEndian::read_i32(&self.last_item[0..4]));
+        self.ic_dx.compress(enc, median, diff, (n == 1) as u32);
+        self.last_x_diff_median5[m].add(diff);
+
+        // the y coordinate
+        let k_bits = self.ic_dx.get_k();
+        let median = self.last_y_diff_median5[m].get();
+        let diff = LittleEndian::read_i32(&item[4..8]).wrapping_sub(LittleEndian::read_i32(&self.last_item[4..8]));
+        self.ic_dy.compress(enc, median, diff, xy_context(n as u32, k_bits, 20));
+        self.last_y_diff_median5[m].add(diff);
+
+        // the z coordinate
+        let k_bits = (self.ic_dx.get_k() + self.ic_dy.get_k()) / 2;
+        let z = LittleEndian::read_i32(&item[8..12]);
+        self.ic_z.compress(enc, self.last_height[l], z, xy_context(n as u32, k_bits, 18));
+        self.last_height[l] = z;
+
+        self.last_item.copy_from_slice(&item[0..20]);
+    }
+
+    fn decompress(&mut self, dec: &mut ArithmeticDecoder, item: &mut [u8]) {
+        let changed_values = dec.decode_symbol(&mut self.m_changed_values);
+        let (n, m, l);
+        if changed_values != 0 {
+            if changed_values & 32 != 0 {
+                let model = lazy_model(&mut self.m_bit_byte, self.last_item[14] as usize, 256);
+                self.last_item[14] = dec.decode_symbol(model) as u8;
+            }
+            let c = self.return_contexts();
+            n = c.0;
+            m = c.1;
+            l = c.2;
+            if changed_values & 16 != 0 {
+                let intensity = self.ic_intensity.decompress(dec, self.last_intensity[m] as i32, if m < 3 { m as u32 } else { 3 }) as u16;
+                self.last_intensity[m] = intensity;
+            }
+            if changed_values & 8 != 0 {
+                let model = lazy_model(&mut self.m_classification, self.last_item[15] as usize, 256);
+                self.last_item[15] = dec.decode_symbol(model) as u8;
+            }
+            if changed_values & 4 != 0 {
+                let scan_direction_flag = ((self.last_item[14] >> 6) & 1) as usize;
+                let val = dec.decode_symbol(&mut self.m_scan_angle_rank[scan_direction_flag]) as i32;
+                self.last_item[16] = u8_fold(val + self.last_item[16] as i32);
+            }
+            if changed_values & 2 != 0 {
+                let model = lazy_model(&mut self.m_user_data, self.last_item[17] as usize, 256);
+                self.last_item[17] = dec.decode_symbol(model) as u8;
+            }
+            if changed_values & 1 != 0 {
+                let last_point_source_id = LittleEndian::read_u16(&self.last_item[18..20]);
+                let point_source_id = self.ic_point_source_id.decompress(dec, last_point_source_id as i32, 0) as u16;
+                LittleEndian::write_u16(&mut self.last_item[18..20], point_source_id);
+            }
+        } else {
+            let c = self.return_contexts();
+            n = c.0;
+            m = c.1;
+            l = c.2;
+        }
+        // the intensity is that of the last point with the same return context
+        let intensity = self.last_intensity[m];
+        LittleEndian::write_u16(&mut self.last_item[12..14], intensity);
+
+        // the x coordinate
+        let median = self.last_x_diff_median5[m].get();
+        let diff = self.ic_dx.decompress(dec, median, (n == 1) as u32);
+        let x = LittleEndian::read_i32(&self.last_item[0..4]).wrapping_add(diff);
+        LittleEndian::write_i32(&mut self.last_item[0..4], x);
+        self.last_x_diff_median5[m].add(diff);
+
+        // the y coordinate
+        let k_bits = self.ic_dx.get_k();
+        let median = self.last_y_diff_median5[m].get();
+        let diff = self.ic_dy.decompress(dec, median, xy_context(n, k_bits, 20));
+        let y = LittleEndian::read_i32(&self.last_item[4..8]).wrapping_add(diff);
+        LittleEndian::write_i32(&mut self.last_item[4..8], y);
+        self.last_y_diff_median5[m].add(diff);
+
+        // the z coordinate
+        let k_bits = (self.ic_dx.get_k() + self.ic_dy.get_k()) / 2;
+        let z = self.ic_z.decompress(dec, self.last_height[l], xy_context(n, k_bits, 18));
+        LittleEndian::write_i32(&mut self.last_item[8..12], z);
+        self.last_height[l] = z;
+
+        item[0..20].copy_from_slice(&self.last_item);
+    }
+}
+
+/// The GPS time of point formats 1 and 3-5, version 2.
+pub struct GpsTime11 {
+    coder: GpsTimeCoder,
+}
+
+impl GpsTime11 {
+    pub fn new() -> GpsTime11 {
+        GpsTime11 { coder: GpsTimeCoder::new(false) }
+    }
+}
+
+impl PointwiseItem for GpsTime11 {
+    fn init(&mut self, item: &[u8]) {
+        self.coder.init(LittleEndian::read_i64(&item[0..8]));
+    }
+
+    fn compress(&mut self, enc: &mut ArithmeticEncoder, item: &[u8]) {
+        self.coder.compress(enc, LittleEndian::read_i64(&item[0..8]));
+    }
+
+    fn decompress(&mut self, dec: &mut ArithmeticDecoder, item: &mut [u8]) {
+        let gps_time = self.coder.decompress(dec);
+        LittleEndian::write_i64(&mut item[0..8], gps_time);
+    }
+}
+
+/// Compresses the colour of a point relative to the last colour. The low and high bytes of
+/// each channel are coded separately and green and blue are predicted from the change
+/// in red. Shared by the version 2 and version 3 colour items.
+pub struct RgbCoder {
+    m_byte_used: SymbolModel,
+    m_rgb_diff: [SymbolModel; 6],
+}
+
+impl RgbCoder {
+    pub fn new() -> RgbCoder {
+        RgbCoder {
+            m_byte_used: SymbolModel::new(128),
+            m_rgb_diff: [SymbolModel::new(256), SymbolModel::new(256), SymbolModel::new(256),
+                         SymbolModel::new(256), SymbolModel::new(256), SymbolModel::new(256)],
+        }
+    }
+
+    pub fn init(&mut self) {
+        self.m_byte_used.init();
+        for m in self.m_rgb_diff.iter_mut() {
+            m.init();
+        }
+    }
+
+    /// Compresses the colour and returns true if it differs from the last colour.
+    pub fn compress(&mut self, enc: &mut ArithmeticEncoder, last: &[u16; 3], rgb: &[u16; 3]) -> bool {
+        let mut sym = ((last[0] & 0x00FF) != (rgb[0] & 0x00FF)) as u32;
+        sym |= (((last[0] & 0xFF00) != (rgb[0] & 0xFF00)) as u32) << 1;
+        sym |= (((last[1] & 0x00FF) != (rgb[1] & 0x00FF)) as u32) << 2;
+        sym |= (((last[1] & 0xFF00) != (rgb[1] & 0xFF00)) as u32) << 3;
+        sym |= (((last[2] & 0x00FF) != (rgb[2] & 0x00FF)) as u32) << 4;
+        sym |= (((last[2] & 0xFF00) != (rgb[2] & 0xFF00)) as u32) << 5;
+        sym |= (((rgb[0] & 0x00FF) != (rgb[1] & 0x00FF) || (rgb[0] & 0x00FF) != (rgb[2] & 0x00FF)
+            || (rgb[0] & 0xFF00) != (rgb[1] & 0xFF00) || (rgb[0] & 0xFF00) != (rgb[2] & 0xFF00)) as u32) << 6;
+        enc.encode_symbol(&mut self.m_byte_used, sym);
+
+        let mut diff_l = 0i32;
+        let mut diff_h = 0i32;
+        if sym & 1 != 0 {
+            diff_l = (rgb[0] & 255) as i32 - (last[0] & 255) as i32;
+            enc.encode_symbol(&mut self.m_rgb_diff[0], u8_fold(diff_l) as u32);
+        }
+        if sym & (1 << 1) != 0 {
+            diff_h = (rgb[0] >> 8) as i32 - (last[0] >> 8) as i32;
+            enc.encode_symbol(&mut self.m_rgb_diff[1], u8_fold(diff_h) as u32);
+        }
+        if sym & (1 << 6) != 0 {
+            if sym & (1 << 2) != 0 {
+                let corr = (rgb[1] & 255) as i32 - u8_clamp(diff_l + (last[1] & 255) as i32);
+                enc.encode_symbol(&mut self.m_rgb_diff[2], u8_fold(corr) as u32);
+            }
+            if sym & (1 << 4) != 0 {
+                diff_l = (diff_l + (rgb[1] & 255) as i32 - (last[1] & 255) as i32) / 2;
+                let corr = (rgb[2] & 255) as i32 - u8_clamp(diff_l + (last[2] & 255) as i32);
+                enc.encode_symbol(&mut self.m_rgb_diff[4], u8_fold(corr) as u32);
+            }
+            if sym & (1 << 3) != 0 {
+                let corr = (rgb[1] >> 8) as i32 - u8_clamp(diff_h + (last[1] >> 8) as i32);
+                enc.encode_symbol(&mut self.m_rgb_diff[3], u8_fold(corr) as u32);
+            }
+            if sym & (1 << 5) != 0 {
+                diff_h = (diff_h + (rgb[1] >> 8) as i32 - (last[1] >> 8) as i32) / 2;
+                let corr = (rgb[2] >> 8) as i32 - u8_clamp(diff_h + (last[2] >> 8) as i32);
+                enc.encode_symbol(&mut self.m_rgb_diff[5], u8_fold(corr) as u32);
+            }
+        }
+        sym & 0x3F != 0
+    }
+
+    pub fn decompress(&mut self, dec: &mut ArithmeticDecoder, last: &[u16; 3]) -> [u16; 3] {
+        let mut rgb = [0u16; 3];
+        let sym = dec.decode_symbol(&mut self.m_byte_used);
+        if sym & 1 != 0 {
+            let corr = dec.decode_symbol(&mut self.m_rgb_diff[0]) as i32;
+            rgb[0] = u8_fold(corr + (last[0] & 255) as i32) as u16;
+        } else {
+            rgb[0] = last[0] & 0xFF;
+        }
+        if sym & (1 << 1) != 0 {
+            let corr = dec.decode_symbol(&mut self.m_rgb_diff[1]) as i32;
+            rgb[0] |= (u8_fold(corr + (last[0] >> 8) as i32) as u16) << 8;
+        } else {
+            rgb[0] |= last[0] & 0xFF00;
+        }
+        if sym & (1 << 6) != 0 {
+            let mut diff = (rgb[0] & 0x00FF) as i32 - (last[0] & 0x00FF) as i32;
+            if sym & (1 << 2) != 0 {
+                let corr = dec.decode_symbol(&mut self.m_rgb_diff[2]) as i32;
+                rgb[1] = u8_fold(corr + u8_clamp(diff + (last[1] & 255) as i32)) as u16;
+            } else {
+                rgb[1] = last[1] & 0xFF;
+            }
+            if sym & (1 << 4) != 0 {
+                let corr = dec.decode_symbol(&mut self.m_rgb_diff[4]) as i32;
+                diff = (diff + (rgb[1] & 0x00FF) as i32 - (last[1] & 0x00FF) as i32) / 2;
+                rgb[2] = u8_fold(corr + u8_clamp(diff + (last[2] & 255) as i32)) as u16;
+            } else {
+                rgb[2] = last[2] & 0xFF;
+            }
+            diff = (rgb[0] >> 8) as i32 - (last[0] >> 8) as i32;
+            if sym & (1 << 3) != 0 {
+                let corr = dec.decode_symbol(&mut self.m_rgb_diff[3]) as i32;
+                rgb[1] |= (u8_fold(corr + u8_clamp(diff + (last[1] >> 8) as i32)) as u16) << 8;
+            } else {
+                rgb[1] |= last[1] & 0xFF00;
+            }
+            if sym & (1 << 5) != 0 {
+                let corr = dec.decode_symbol(&mut self.m_rgb_diff[5]) as i32;
+                diff = (diff + (rgb[1] >> 8) as i32 - (last[1] >> 8) as i32) / 2;
+                rgb[2] |= (u8_fold(corr + u8_clamp(diff + (last[2] >> 8) as i32)) as u16) << 8;
+            } else {
+                rgb[2] |= last[2] & 0xFF00;
+            }
+        } else {
+            rgb[1] = rgb[0];
+            rgb[2] = rgb[0];
+        }
+        rgb
+    }
+}
+
+pub fn read_rgb(item: &[u8]) -> [u16; 3] {
+    [LittleEndian::read_u16(&item[0..2]),
+     LittleEndian::read_u16(&item[2..4]),
+     LittleEndian::read_u16(&item[4..6])]
+}
+
+pub fn write_rgb(rgb: &[u16; 3], item: &mut [u8]) {
+    LittleEndian::write_u16(&mut item[0..2], rgb[0]);
+    LittleEndian::write_u16(&mut item[2..4], rgb[1]);
+    LittleEndian::write_u16(&mut item[4..6], rgb[2]);
+}
+
+/// The colour of point formats 2, 3, and 5, version 2.
+pub struct Rgb12 {
+    last_item: [u16; 3],
+    coder: RgbCoder,
+}
+
+impl Rgb12 {
+    pub fn new() -> Rgb12 {
+        Rgb12 {
+            last_item: [0; 3],
+            coder: RgbCoder::new(),
+        }
+    }
+}
+
+impl PointwiseItem for Rgb12 {
+    fn init(&mut self, item: &[u8]) {
+        self.coder.init();
+        self.last_item = read_rgb(item);
+    }
+
+    fn compress(&mut self, enc: &mut ArithmeticEncoder, item: &[u8]) {
+        let rgb = read_rgb(item);
+        self.coder.compress(enc, &self.last_item, &rgb);
+        self.last_item = rgb;
+    }
+
+    fn decompress(&mut self, dec: &mut ArithmeticDecoder, item: &mut [u8]) {
+        let rgb = self.coder.decompress(dec, &self.last_item);
+        write_rgb(&rgb, item);
+        self.last_item = rgb;
+    }
+}
+
+/// Compresses a waveform packet relative to the last packet. Shared by the version 1
+/// and version 3 waveform items.
+pub struct WavepacketCoder {
+    last_diff_32: i32,
+    sym_last_offset_diff: usize,
+    m_packet_index: SymbolModel,
+    m_offset_diff: [SymbolModel; 4],
+    ic_offset_diff: IntegerCompressor,
+    ic_packet_size: IntegerCompressor,
+    ic_return_point: IntegerCompressor,
+    ic_xyz: IntegerCompressor,
+}
+
+impl WavepacketCoder {
+    pub fn new() -> WavepacketCoder {
+        WavepacketCoder {
+            last_diff_32: 0,
+            sym_last_offset_diff: 0,
+            m_packet_index: SymbolModel::new(256),
+            m_offset_diff: [SymbolModel::new(4), SymbolModel::new(4), SymbolModel::new(4), SymbolModel::new(4)],
+            ic_offset_diff: IntegerCompressor::new(32, 1),
+            ic_packet_size: IntegerCompressor::new(32, 1),
+            ic_return_point: IntegerCompressor::new(32, 1),
+            ic_xyz: IntegerCompressor::new(32, 3),
+        }
+    }
+
+    pub fn init(&mut self) {
+        self.last_diff_32 = 0;
+        self.sym_last_offset_diff = 0;
+        self.m_packet_index.init();
+        for m in self.m_offset_diff.iter_mut() {
+            m.init();
+        }
+        self.ic_offset_diff.init();
+        self.ic_packet_size.init();
+        self.ic_return_point.init();
+        self.ic_xyz.init();
+    }
+
+    /// Compresses a 29-byte packet, i.e. the descriptor index and packet fields.
+    pub fn compress(&mut self, enc: &mut ArithmeticEncoder, last_item: &[u8], item: &[u8]) {
+        enc.encode_symbol(&mut self.m_packet_index, item[0] as u32);
+        let this = Wavepacket::unpack(&item[1..29]);
+        let last = Wavepacket::unpack(&last_item[1..29]);
+
+        let curr_diff_64 = this.offset.wrapping_sub(last.offset) as i64;
+        let curr_diff_32 = curr_diff_64 as i32;
+        if curr_diff_64 == curr_diff_32 as i64 {
+            if curr_diff_32 == 0 {
+                enc.encode_symbol(&mut self.m_offset_diff[self.sym_last_offset_diff], 0);
+                self.sym_last_offset_diff = 0;
+            } else if curr_diff_32 == last.packet_size as i32 {
+                enc.encode_symbol(&mut self.m_offset_diff[self.sym_last_offset_diff], 1);
+                self.sym_last_offset_diff = 1;
+            } else {
+                enc.encode_symbol(&mut self.m_offset_diff[self.sym_last_offset_diff], 2);
+                self.sym_last_offset_diff = 2;
+                self.ic_offset_diff.compress(enc, self.last_diff_32, curr_diff_32, 0);
+                self.last_diff_32 = curr_diff_32;
+            }
+        } else {
+            enc.encode_symbol(&mut self.m_offset_diff[self.sym_last_offset_diff], 3);
+            self.sym_last_offset_diff = 3;
+            enc.write_int64(this.offset);
+        }
+        self.ic_packet_size.compress(enc, last.packet_size as i32, this.packet_size as i32, 0);
+        self.ic_return_point.compress(enc, last.return_point, this.return_point, 0);
+        self.ic_xyz.compress(enc, last.x, this.x, 0);
+        self.ic_xyz.compress(enc, last.y, this.y, 1);
+        self.ic_xyz.compress(enc, last.z, this.z, 2);
+    }
+
+    pub fn decompress(&mut self, dec: &mut ArithmeticDecoder, last_item: &[u8], item: &mut [u8]) {
+        item[0] = dec.decode_symbol(&mut self.m_packet_index) as u8;
+        let last = Wavepacket::unpack(&last_item[1..29]);
+        let mut this = Wavepacket::default();
+
+        self.sym_last_offset_diff = dec.decode_symbol(&mut self.m_offset_diff[self.sym_last_offset_diff]) as usize;
+        this.offset = match self.sym_last_offset_diff {
+            0 => last.offset,
+            1 => last.offset.wrapping_add(last.packet_size as u64),
+            2 => {
+                self.last_diff_32 = self.ic_offset_diff.decompress(dec, self.last_diff_32, 0);
+                last.offset.wrapping_add(self.last_diff_32 as i64 as u64)
+            }
+            _ => dec.read_int64(),
+        };
+        this.packet_size = self.ic_packet_size.decompress(dec, last.packet_size as i32, 0) as u32;
+        this.return_point = self.ic_return_point.decompress(dec, last.return_point, 0);
+        this.x = self.ic_xyz.decompress(dec, last.x, 0);
+        this.y = self.ic_xyz.decompress(dec, last.y, 1);
+        this.z = self.ic_xyz.decompress(dec, last.z, 2);
+        this.pack(&mut item[1..29]);
+    }
+}
+
+/// The waveform packet of point formats 4 and 5, version 1.
+pub struct Wavepacket13 {
+    last_item: [u8; 29],
+    coder: WavepacketCoder,
+}
+
+impl Wavepacket13 {
+    pub fn new() -> Wavepacket13 {
+        Wavepacket13 {
+            last_item: [0; 29],
+            coder: WavepacketCoder::new(),
+        }
+    }
+}
+
+impl PointwiseItem for Wavepacket13 {
+    fn init(&mut self, item: &[u8]) {
+        self.coder.init();
+        self.last_item.copy_from_slice(&item[0..29]);
+    }
+
+    fn compress(&mut self, enc: &mut ArithmeticEncoder, item: &[u8]) {
+        self.coder.compress(enc, &self.last_item, item);
+        self.last_item.copy_from_slice(&item[0..29]);
+    }
+
+    fn decompress(&mut self, dec: &mut ArithmeticDecoder, item: &mut [u8]) {
+        self.coder.decompress(dec, &self.last_item, item);
+        self.last_item.copy_from_slice(&item[0..29]);
+    }
+}
+
+/// Extra bytes that follow the standard fields of a point record, version 2.
+pub struct Byte {
+    last_item: Vec<u8>,
+    m_byte: Vec<SymbolModel>,
+}
+
+impl Byte {
+    pub fn new(number: usize) -> Byte {
+        Byte {
+            last_item: vec![0u8; number],
+            m_byte: vec![SymbolModel::new(256); number],
+        }
+    }
+}
+
+impl PointwiseItem for Byte {
+    fn init(&mut self, item: &[u8]) {
+        for m in self.m_byte.iter_mut() {
+            m.init();
+        }
+        let number = self.last_item.len();
+        self.last_item.copy_from_slice(&item[0..number]);
+    }
+
+    fn compress(&mut self, enc: &mut ArithmeticEncoder, item: &[u8]) {
+        for i in 0..self.last_item.len() {
+            let diff = item[i] as i32 - self.last_item[i] as i32;
+            enc.encode_symbol(&mut self.m_byte[i], u8_fold(diff) as u32);
+            self.last_item[i] = item[i];
+        }
+    }
+
+    fn decompress(&mut self, dec: &mut ArithmeticDecoder, item: &mut [u8]) {
+        for i in 0..self.last_item.len() {
+            let value = self.last_item[i] as i32 + dec.decode_symbol(&mut self.m_byte[i]) as i32;
+            item[i] = u8_fold(value);
+            self.last_item[i] = item[i];
+        }
+    }
+}
@@ -0,0 +1,122 @@
+/*
+This code is part of the WhiteboxTools geospatial analysis library.
+Authors: Dr. John Lindsay
+Created: March 26, 2018
+Last Modified: March 26, 2018
+License: MIT
+*/
+
+use std::io::{Error, ErrorKind, Read, Seek, SeekFrom};
+use byteorder::{ByteOrder, LittleEndian};
+use super::arithmetic_coder::ArithmeticDecoder;
+use super::chunk::ChunkDecompressor;
+use super::integer_compressor::IntegerCompressor;
+use super::laz_vlr::LazVlr;
+
+/// Decompresses the point records of a LAZ file, one chunk at a time.
+pub struct LazReader<R: Read + Seek> {
+    source: R,
+    decompressor: ChunkDecompressor,
+    // the byte position, length, and number of points of each chunk
+    chunks: Vec<(u64, u64, u64)>,
+    current_chunk: usize,
+    points_left_in_chunk: u64,
+    points_left: u64,
+}
+
+impl<R: Read + Seek> LazReader<R> {
+    /// Creates a reader of the `num_points` compressed point records that begin at
+    /// `offset_to_points` within the source.
+    pub fn new(mut source: R, vlr: &LazVlr, offset_to_points: u64, num_points: u64, record_length: usize) -> Result<LazReader<R>, Error> {
+        if vlr.record_length() != record_length {
+            return Err(Error::new(ErrorKind::InvalidData, "The LAZ items do not match the point record length."));
+        }
+        let decompressor = ChunkDecompressor::new(vlr)?;
+        let chunks = if num_points > 0 {
+            read_chunk_table(&mut source, vlr, offset_to_points)?
+        } else {
+            vec![]
+        };
+        Ok(LazReader {
+            source: source,
+            decompressor: decompressor,
+            chunks: chunks,
+            current_chunk: 0,
+            points_left_in_chunk: 0,
+            points_left: num_points,
+        })
+    }
+
+    /// Decompresses the next point record into `point`.
+    pub fn read_point(&mut self, point: &mut [u8]) -> Result<(), Error> {
+        if self.points_left == 0 {
+            return Err(Error::new(ErrorKind::UnexpectedEof, "There are no more points in the LAZ file."));
+        }
+        if self.points_left_in_chunk == 0 {
+            if self.current_chunk >= self.chunks.len() {
+                return Err(Error::new(ErrorKind::InvalidData, "The LAZ file has fewer chunks than points."));
+            }
+            let (start, length, num_points) = self.chunks[self.current_chunk];
+            self.source.seek(SeekFrom::Start(start))?;
+            let mut chunk = vec![0u8; length as usize];
+            self.source.read_exact(&mut chunk)?;
+            let count = self.decompressor.start(chunk)?;
+            self.points_left_in_chunk = match count {
+                Some(c) => c as u64,
+                None => num_points,
+            };
+            self.current_chunk += 1;
+        }
+        self.decompressor.decompress(point);
+        self.points_left_in_chunk -= 1;
+        self.points_left -= 1;
+        Ok(())
+    }
+}
+
+fn read_chunk_table<R: Read + Seek>(source: &mut R, vlr: &LazVlr, offset_to_points: u64) -> Result<Vec<(u64, u64, u64)>, Error> {
+    let missing = || Error::new(ErrorKind::InvalidData, "The LAZ chunk table is missing; the file may be truncated.");
+    let mut buf = [0u8; 8];
+    source.seek(SeekFrom::Start(offset_to_points))?;
+    source.read_exact(&mut buf)?;
+    let mut table_position = LittleEndian::read_i64(&buf);
+    let file_size = source.seek(SeekFrom::End(0))?;
+    if table_position == -1 {
+        // the file was written by a streaming writer and the offset is at the end of the file
+        source.seek(SeekFrom::End(-8))?;
+        source.read_exact(&mut buf)?;
+        table_position = LittleEndian::read_i64(&buf);
+    }
+    if table_position <= offset_to_points as i64 || table_position as u64 + 8 > file_size {
+        return Err(missing());
+    }
+
+    source.seek(SeekFrom::Start(table_position as u64))?;
+    source.read_exact(&mut buf)?;
+    let number_of_chunks = LittleEndian::read_u32(&buf[4..8]) as usize;
+    let mut bytes = vec![];
+    source.read_to_end(&mut bytes)?;
+    let mut dec = ArithmeticDecoder::new(bytes);
+    dec.init();
+    let mut ic = IntegerCompressor::new(32, 2);
+    ic.init();
+    let variable_size = vlr.chunk_size == u32::max_value();
+    let mut chunks = Vec::with_capacity(number_of_chunks);
+    let mut start = offset_to_points + 8;
+    let mut pred_count = 0;
+    let mut pred_size = 0;
+    for _ in 0..number_of_chunks {
+        if variable_size {
+            pred_count = ic.decompress(&mut dec, pred_count, 0);
+        }
+        pred_size = ic.decompress(&mut dec, pred_size, 1);
+        let length = pred_size as u32 as u64;
+        if start + length > table_position as u64 {
+            return Err(missing());
+        }
+        let num_points = if variable_size { pred_count as u32 as u64 } else { vlr.chunk_size as u64 };
+        chunks.push((start, length, num_points));
+        start += length;
+    }
+    Ok(chunks)
+}
@@ -0,0 +1,130 @@
+/*
+This code is part of the WhiteboxTools geospatial analysis library.
+Authors: Dr. John Lindsay
+Created: March 26, 2018
+Last Modified: March 26, 2018
+License: MIT
+
+Notes: The writer does not require its destination to be seekable, which allows LAZ
+files to be written into zip archives too. Rather than going back to fill in the offset
+of the chunk table, the offset is written as -1 and the actual offset is appended to the
+end of the file, after the chunk table, as LASzip does for streamed output.
+*/
+
+use std::io::{Error, ErrorKind, Write};
+use byteorder::{ByteOrder, LittleEndian};
+use super::arithmetic_coder::ArithmeticEncoder;
+use super::chunk::ChunkCompressor;
+use super::integer_compressor::IntegerCompressor;
+use super::laz_vlr::LazVlr;
+
+/// Compresses point records into the point data of a LAZ file. The records may either be
+/// written one at a time using `write_point` or as a byte stream using the `Write` trait,
+/// in which case the records are compressed as each one is completed. `done` must be
+/// called after the last point to write the chunk table.
+pub struct LazWriter<W: Write> {
+    dest: W,
+    chunk_size: u32,
+    record_length: usize,
+    compressor: ChunkCompressor,
+    record: Vec<u8>,
+    chunk_sizes: Vec<u32>,
+    // the byte position of the point data in the file
+    offset_to_points: u64,
+    bytes_written: u64,
+}
+
+impl<W: Write> LazWriter<W> {
+    /// Creates a writer that writes the point data to `dest`, which must be positioned at
+    /// `offset_to_points`, i.e. just after the header and VLRs, which include the LASzip VLR.
+    pub fn new(mut dest: W, vlr: &LazVlr, record_length: usize, offset_to_points: u64) -> Result<LazWriter<W>, Error> {
+        if vlr.record_length() != record_length {
+            return Err(Error::new(ErrorKind::InvalidInput, "The LAZ items do not match the point record length."));
+        }
+        let compressor = ChunkCompressor::new(vlr)?;
+        let mut buf = [0u8; 8];
+        LittleEndian::write_i64(&mut buf, -1);
+        dest.write_all(&buf)?;
+        Ok(LazWriter {
+            dest: dest,
+            chunk_size: vlr.chunk_size,
+            record_length: record_length,
+            compressor: compressor,
+            record: Vec::with_capacity(record_length),
+            chunk_sizes: vec![],
+            offset_to_points: offset_to_points,
+            bytes_written: 8,
+        })
+    }
+
+    pub fn write_point(&mut self, point: &[u8]) -> Result<(), Error> {
+        if point.len() != self.record_length {
+            return Err(Error::new(ErrorKind::InvalidInput, "The point record does not have the expected length."));
+        }
+        if self.compressor.num_points() == self.chunk_size {
+            self.finish_chunk()?;
+        }
+        self.compressor.compress(point);
+        Ok(())
+    }
+
+    fn finish_chunk(&mut self) -> Result<(), Error> {
+        if self.compressor.num_points() > 0 {
+            let bytes = self.compressor.finish();
+            self.dest.write_all(&bytes)?;
+            self.chunk_sizes.push(bytes.len() as u32);
+            self.bytes_written += bytes.len() as u64;
+        }
+        Ok(())
+    }
+
+    /// Finishes the last chunk and writes the chunk table. Returns the destination.
+    pub fn done(mut self) -> Result<W, Error> {
+        if !self.record.is_empty() {
+            return Err(Error::new(ErrorKind::InvalidData, "The last point record was incomplete."));
+        }
+        self.finish_chunk()?;
+
+        let table_position = self.offset_to_points + self.bytes_written;
+        let mut buf = [0u8; 8];
+        LittleEndian::write_u32(&mut buf[0..4], 0); // version
+        LittleEndian::write_u32(&mut buf[4..8], self.chunk_sizes.len() as u32);
+        self.dest.write_all(&buf)?;
+        if !self.chunk_sizes.is_empty() {
+            let mut enc = ArithmeticEncoder::new();
+            let mut ic = IntegerCompressor::new(32, 2);
+            ic.init();
+            let mut pred = 0i32;
+            for size in &self.chunk_sizes {
+                ic.compress(&mut enc, pred, *size as i32, 1);
+                pred = *size as i32;
+            }
+            enc.done();
+            self.dest.write_all(enc.bytes())?;
+        }
+        LittleEndian::write_i64(&mut buf, table_position as i64);
+        self.dest.write_all(&buf)?;
+        self.dest.flush()?;
+        Ok(self.dest)
+    }
+}
+
+impl<W: Write> Write for LazWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> Result<usize, Error> {
+        let mut i = 0;
+        while i < buf.len() {
+            let n = (self.record_length - self.record.len()).min(buf.len() - i);
+            self.record.extend_from_slice(&buf[i..i + n]);
+            i += n;
+            if self.record.len() == self.record_length {
+                let record = ::std::mem::replace(&mut self.record, Vec::with_capacity(self.record_length));
+                self.write_point(&record)?;
+            }
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> Result<(), Error> {
+        self.dest.flush()
+    }
+}
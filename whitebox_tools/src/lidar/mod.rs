@@ -2,6 +2,7 @@
 // private sub-module defined in other files
 mod header;
 mod las;
+mod laz;
 mod point_data;
 mod vlr;
 
@@ -11,6 +12,8 @@ pub use self::las::GlobalEncodingField;
 pub use self::las::GpsTimeType;
 pub use self::header::LasHeader;
 pub use self::las::LasFile;
+pub use self::las::LasReader;
+pub use self::las::LasWriter;
 pub use self::las::LidarPointRecord;
 pub use self::las::PointRecord0;
 pub use self::las::PointRecord1;
@@ -18,6 +21,10 @@ pub use self::las::PointRecord2;
 pub use self::las::PointRecord3;
 pub use self::las::PointRecord4;
 pub use self::las::PointRecord5;
+pub use self::laz::LazItem;
+pub use self::laz::LazReader;
+pub use self::laz::LazVlr;
+pub use self::laz::LazWriter;
 pub use self::point_data::PointData;
 pub use self::point_data::ColourData;
 pub use self::point_data::WaveformPacket;
@@ -443,7 +443,7 @@ impl PointData {
             if value {
                 self.class_bit_field = self.class_bit_field | 0b0000_0010u8;
             } else {
-                self.class_bit_field = self.class_bit_field & 0b1011_1101u8;
+                self.class_bit_field = self.class_bit_field & 0b1111_1101u8;
             }
         }
     }
@@ -505,7 +505,7 @@ impl PointData {
     /// Returns the scanner channel
     pub fn scanner_channel(&self) -> u8 {
         if self.is_64bit {
-            return self.class_bit_field & 0b0011_0000u8;
+            return (self.class_bit_field & 0b0011_0000u8) >> 4;
         }
         0u8 // 32-bit mode only supports 1 channel systems
     }
@@ -517,6 +517,55 @@ impl PointData {
         }
     }
 
+    /// Returns a copy of the point that uses either the 64-bit point record layout of Point
+    /// Formats 6-10 or the 32-bit layout of Point Formats 0-5. Legacy class 12 (overlap) maps
+    /// to the overlap flag of the 64-bit layout, and the scan angle is converted between the
+    /// whole degrees of the 32-bit layout and the 0.006 degree increments of the 64-bit layout.
+    /// Return numbers greater than 7 are clamped in the 32-bit layout.
+    pub fn to_record_layout(&self, is_64bit: bool) -> PointData {
+        if self.is_64bit == is_64bit {
+            return *self;
+        }
+        let mut p = PointData {
+            x: self.x,
+            y: self.y,
+            z: self.z,
+            intensity: self.intensity,
+            user_data: self.user_data,
+            point_source_id: self.point_source_id,
+            is_64bit: is_64bit,
+            ..Default::default()
+        };
+        // the 32-bit layout only has room for up to 7 returns
+        let max_returns = if is_64bit { 15u8 } else { 7u8 };
+        p.set_return_number(self.return_number().min(max_returns));
+        p.set_number_of_returns(self.number_of_returns().min(max_returns));
+        p.set_scan_direction_flag(self.scan_direction_flag());
+        p.set_edge_of_flightline_flag(self.edge_of_flightline_flag());
+        p.set_synthetic(self.synthetic());
+        p.set_keypoint(self.keypoint());
+        p.set_withheld(self.withheld());
+        if is_64bit {
+            if self.classification() == 12 {
+                p.set_overlap(true);
+                p.set_classification(1);
+            } else {
+                p.set_classification(self.classification());
+            }
+            p.scan_angle = (self.scan_angle as f64 / 0.006).round() as i16;
+        } else {
+            if self.overlap() {
+                p.set_classification(12);
+            } else if self.classification() < 32 {
+                p.set_classification(self.classification());
+            } else {
+                p.set_classification(1); // the class cannot be represented in 5 bits
+            }
+            p.scan_angle = (self.scan_angle as f64 * 0.006).round() as i16;
+        }
+        p
+    }
+
 }
 
 #[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -1,5 +1,6 @@
 use std::fmt;
 use std::mem;
+use spatial_ref_system::GeoKeyValue;
 
 #[derive(Default, Clone, Debug)]
 pub struct Vlr {
@@ -11,6 +12,100 @@ pub struct Vlr {
     pub binary_data: Vec<u8>,
 }
 
+impl Vlr {
+    /// Creates an OGC WKT coordinate system record. A WKT string longer than 65,535 bytes
+    /// must be stored as an EVLR.
+    pub fn new_wkt(wkt: &str) -> Vlr {
+        let mut binary_data = wkt.as_bytes().to_vec();
+        binary_data.push(0u8);
+        Vlr {
+            reserved: 0,
+            user_id: "LASF_Projection".to_string(),
+            record_id: 2112,
+            record_length_after_header: binary_data.len().min(u16::max_value() as usize) as u16,
+            description: "OGC WKT Coordinate System".to_string(),
+            binary_data: binary_data,
+        }
+    }
+
+    /// Creates the GeoKey directory, double parameters and ASCII parameters records that
+    /// describe a coordinate reference system in a LAS file older than version 1.4. The
+    /// parameter records are only created if they are used by one of the keys.
+    pub fn new_geokeys(keys: &[(u16, GeoKeyValue)]) -> Vec<Vlr> {
+        let mut directory = vec![1u16, 1, 0, keys.len() as u16];
+        let mut doubles: Vec<f64> = vec![];
+        let mut ascii = String::new();
+        for &(key, ref value) in keys {
+            match *value {
+                GeoKeyValue::Short(v) => directory.extend_from_slice(&[key, 0, 1, v]),
+                GeoKeyValue::Double(v) => {
+                    directory.extend_from_slice(&[key, 34_736, 1, doubles.len() as u16]);
+                    doubles.push(v);
+                },
+                GeoKeyValue::Ascii(ref v) => {
+                    // ASCII parameters are terminated by a pipe character
+                    directory.extend_from_slice(&[key, 34_737, v.len() as u16 + 1, ascii.len() as u16]);
+                    ascii.push_str(v);
+                    ascii.push('|');
+                },
+            }
+        }
+
+        let mut binary_data = Vec::with_capacity(directory.len() * 2);
+        for v in directory {
+            binary_data.extend_from_slice(&[(v & 0xFF) as u8, (v >> 8) as u8]);
+        }
+        let mut vlrs = vec![Vlr::new_projection(34_735, "GeoTiff GeoKeyDirectoryTag", binary_data)];
+        if !doubles.is_empty() {
+            let mut binary_data = Vec::with_capacity(doubles.len() * 8);
+            for v in doubles {
+                let bits = v.to_bits();
+                for i in 0..8 {
+                    binary_data.push((bits >> (8 * i)) as u8);
+                }
+            }
+            vlrs.push(Vlr::new_projection(34_736, "GeoTiff GeoDoubleParamsTag", binary_data));
+        }
+        if !ascii.is_empty() {
+            let mut binary_data = ascii.into_bytes();
+            binary_data.push(0u8);
+            vlrs.push(Vlr::new_projection(34_737, "GeoTiff GeoAsciiParamsTag", binary_data));
+        }
+        vlrs
+    }
+
+    fn new_projection(record_id: u16, description: &str, binary_data: Vec<u8>) -> Vlr {
+        Vlr {
+            reserved: 0,
+            user_id: "LASF_Projection".to_string(),
+            record_id: record_id,
+            record_length_after_header: binary_data.len() as u16,
+            description: description.to_string(),
+            binary_data: binary_data,
+        }
+    }
+
+    fn is(&self, user_id: &str, record_id: u16) -> bool {
+        self.record_id == record_id && self.user_id.trim_matches('\0').trim() == user_id
+    }
+
+    /// Returns true if the record is an OGC WKT coordinate system record.
+    pub fn is_wkt(&self) -> bool {
+        self.is("LASF_Projection", 2112)
+    }
+
+    /// Returns true if the record is one of the WKT or GeoTIFF coordinate reference system
+    /// records.
+    pub fn is_crs(&self) -> bool {
+        [2111u16, 2112, 34_735, 34_736, 34_737].iter().any(|id| self.is("LASF_Projection", *id))
+    }
+
+    /// Returns the WKT of an OGC WKT coordinate system record.
+    pub fn get_wkt(&self) -> String {
+        String::from_utf8_lossy(&self.binary_data).trim_right_matches('\0').to_string()
+    }
+}
+
 impl fmt::Display for Vlr {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         let mut s = format!("\tReserved: {}", self.reserved);
@@ -2,29 +2,20 @@
 This code is part of the WhiteboxTools geospatial analysis library.
 Authors: Dr. John Lindsay
 Created: June 21, 2017
-Last Modified: February 7, 2018
+Last Modified: March 25, 2018
 License: MIT
 */
 
-extern crate byteorder;
-extern crate serde;
-extern crate serde_json;
-
-pub mod io_utils;
-pub mod lidar;
-pub mod raster;
-pub mod rendering;
-pub mod tools;
-pub mod structures;
-pub mod vector;
+extern crate whitebox_tools;
 
 use std::io::Error;
+use std::io::ErrorKind;
 use std::env;
 use std::path;
-use tools::ToolManager;
-
-#[macro_use]
-extern crate serde_derive;
+use std::sync::Arc;
+use whitebox_tools::raster;
+use whitebox_tools::raster::{RasterCompression, RasterLayout, RasterPredictor};
+use whitebox_tools::tools::{JsonProgress, ToolManager};
 
 
 /// WhiteboxTools is an advanced geospatial data analysis engine. 
@@ -66,11 +57,18 @@ fn run() -> Result<(), Error> {
     let mut tool_parameters = false;
     let mut toolbox = false;
     let mut list_tools = false;
+    let mut catalog = false;
     let mut keywords: Vec<String> = vec![];
     let mut view_code = false;
+    let mut workflow_file = String::new();
+    let mut json_progress = false;
     let mut tool_args_vec: Vec<String> = vec![];
     let mut verbose = false;
     let mut finding_working_dir = false;
+    let mut compression = RasterCompression::None;
+    let mut predictor: Option<RasterPredictor> = None;
+    let mut layout = RasterLayout::Strips;
+    let mut max_raster_memory = 0usize;
     let args: Vec<String> = env::args().collect();
     if args.len() <= 1 {
         // return Err(Error::new(ErrorKind::InvalidInput,
@@ -103,6 +101,16 @@ fn run() -> Result<(), Error> {
                 v.push_str(sep);
             }
             working_dir = v.to_string();
+        } else if arg.starts_with("-raster_layout") || arg.starts_with("--raster_layout") {
+            // this must precede the -r flag
+            let mut v = arg.replace("--raster_layout", "")
+                .replace("-raster_layout", "")
+                .replace("\"", "")
+                .replace("\'", "");
+            if v.starts_with("=") {
+                v = v[1..v.len()].to_string();
+            }
+            layout = RasterLayout::from_str(&v)?;
         } else if arg.starts_with("-run") || arg.starts_with("--run") || arg.starts_with("-r") {
             let mut v = arg.replace("--run", "")
                 .replace("-run", "")
@@ -144,6 +152,8 @@ fn run() -> Result<(), Error> {
             }
             tool_name = v;
             toolbox = true;
+        } else if arg.starts_with("-catalog") || arg.starts_with("--catalog") {
+            catalog = true;
         } else if arg.starts_with("-listtools") || arg.starts_with("--listtools") || arg.starts_with("--list_tools") {
             // let mut v = arg.replace("--listtools", "")
             //     .replace("-listtools", "")
@@ -164,6 +174,30 @@ fn run() -> Result<(), Error> {
             }
             tool_name = v;
             view_code = true;
+        } else if arg.starts_with("-workflow") || arg.starts_with("--workflow") {
+            let mut v = arg.replace("--workflow", "")
+                .replace("-workflow", "")
+                .replace("\"", "")
+                .replace("\'", "");
+            if v.starts_with("=") {
+                v = v[1..v.len()].to_string();
+            }
+            workflow_file = v;
+        } else if arg.starts_with("-progress") || arg.starts_with("--progress") {
+            let mut v = arg.replace("--progress", "")
+                .replace("-progress", "")
+                .replace("\"", "")
+                .replace("\'", "");
+            if v.starts_with("=") {
+                v = v[1..v.len()].to_string();
+            }
+            // Progress is only reported in verbose mode.
+            match v.trim().to_lowercase().as_ref() {
+                "json" => { json_progress = true; verbose = true; },
+                "text" => json_progress = false,
+                _ => return Err(Error::new(ErrorKind::InvalidInput,
+                                           format!("Unrecognized --progress value '{}'; the value must be 'text' or 'json'.", v))),
+            }
         } else if arg.starts_with("-license") || arg.starts_with("-licence") ||
                   arg.starts_with("--license") ||
                   arg.starts_with("--licence") || arg.starts_with("-l") {
@@ -172,6 +206,38 @@ fn run() -> Result<(), Error> {
         } else if arg.starts_with("-version") || arg.starts_with("--version") {
             version();
             return Ok(());
+        } else if arg.starts_with("-compress_rasters") || arg.starts_with("--compress_rasters") {
+            let mut v = arg.replace("--compress_rasters", "")
+                .replace("-compress_rasters", "")
+                .replace("\"", "")
+                .replace("\'", "");
+            if v.starts_with("=") {
+                v = v[1..v.len()].to_string();
+            }
+            compression = RasterCompression::from_str(&v)?;
+        } else if arg.starts_with("-predictor") || arg.starts_with("--predictor") {
+            let mut v = arg.replace("--predictor", "")
+                .replace("-predictor", "")
+                .replace("\"", "")
+                .replace("\'", "");
+            if v.starts_with("=") {
+                v = v[1..v.len()].to_string();
+            }
+            predictor = Some(RasterPredictor::from_str(&v)?);
+        } else if arg.starts_with("-max_raster_memory") || arg.starts_with("--max_raster_memory") {
+            let mut v = arg.replace("--max_raster_memory", "")
+                .replace("-max_raster_memory", "")
+                .replace("\"", "")
+                .replace("\'", "");
+            if v.starts_with("=") {
+                v = v[1..v.len()].to_string();
+            }
+            // The limit is given in megabytes.
+            max_raster_memory = match v.trim().parse::<usize>() {
+                Ok(mb) => mb * 1024 * 1024,
+                Err(_) => return Err(Error::new(ErrorKind::InvalidInput,
+                                                format!("Unrecognized --max_raster_memory value '{}'; the value must be given in megabytes.", v))),
+            };
         } else if arg.trim() == "-v" {
             verbose = true;
         } else if arg.starts_with("-") {
@@ -200,8 +266,17 @@ fn run() -> Result<(), Error> {
     if !working_dir.ends_with(sep) {
         working_dir.push_str(&(sep.to_string()));
     }
-    let tm = ToolManager::new(&working_dir, &verbose)?;
-    if run_tool {
+    // Unless otherwise specified, compressed rasters use the predictor best suited to their data type.
+    raster::set_default_compression(compression, predictor.unwrap_or(RasterPredictor::FloatingPoint));
+    raster::set_default_layout(layout);
+    raster::set_max_raster_memory(max_raster_memory);
+    let mut tm = ToolManager::new(&working_dir, &verbose)?;
+    if json_progress {
+        tm.set_progress_sink(Arc::new(JsonProgress::new()));
+    }
+    if !workflow_file.is_empty() {
+        return tm.run_workflow(&workflow_file);
+    } else if run_tool {
         if tool_name.is_empty() && keywords.len() > 0 { tool_name = keywords[0].clone(); }
         return tm.run_tool(tool_name, tool_args_vec);
     } else if tool_help {
@@ -214,6 +289,8 @@ fn run() -> Result<(), Error> {
         if tool_name.is_empty() && keywords.len() > 0 { tool_name = keywords[0].clone(); }
         if tool_name.is_empty() { tool_name = String::new(); }
         return tm.toolbox(tool_name);
+    } else if catalog {
+        return tm.catalog();
     } else if list_tools {
         if keywords.len() == 0 {
             tm.list_tools();
@@ -239,16 +316,23 @@ fn help() {
     let s = "whitebox-tools Help
 
 The following commands are recognized:
+--catalog        Prints the name, toolbox, description, example usage and parameters of every tool as JSON.
 --cd, --wd       Changes the working directory; used in conjunction with --run flag.
+--compress_rasters Sets the compression of output GeoTIFFs; 'none', 'deflate', 'lzw', or 'packbits'.
 -h, --help       Prints help information.
 -l, --license    Prints the whitebox-tools license.
 --listtools      Lists all available tools. Keywords may also be used, --listtools slope.
+--max_raster_memory Sets the memory, in MB, above which rasters are read and written out-of-core.
+--predictor      Sets the predictor used with --compress_rasters; 'none', 'horizontal', or 'floatingpoint'.
+--progress       Sets the format of tool progress output; 'text' (default) or 'json', one JSON object per line.
+--raster_layout  Sets the layout of output GeoTIFFs; 'strips', 'tiled', or 'cog' (cloud-optimized, with overviews).
 -r, --run        Runs a tool; used in conjuction with --wd flag; -r=\"LidarInfo\".
 --toolbox        Prints the toolbox associated with a tool; --toolbox=Slope.
 --toolhelp       Prints the help associated with a tool; --toolhelp=\"LidarInfo\".
 --toolparameters Prints the parameters (in json form) for a specific tool; --toolparameters=\"LidarInfo\".
 -v               Verbose mode. Without this flag, tool outputs will not be printed.
 --viewcode       Opens the source code of a tool in a web browser; --viewcode=\"LidarInfo\".
+--workflow       Runs a workflow of tools described in a JSON file; --workflow=\"flow.json\".
 --version        Prints the version information.
 
 Example Usage:
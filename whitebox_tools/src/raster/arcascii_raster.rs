@@ -104,9 +104,9 @@ pub fn write_arcascii<'a>(r: &'a mut Raster) -> Result<(), Error> {
     let mut col = 0;
     for i in 0..num_cells {
         if col < r.configs.columns - 1 {
-            s2 += &format!("{:.*} ", 2, r.data[i]);
+            s2 += &format!("{:.*} ", 2, r.data.get_value(i));
         } else {
-            s2 += &format!("{:.*}\n", 2, r.data[i]);
+            s2 += &format!("{:.*}\n", 2, r.data.get_value(i));
         }
         col += 1;
         if col == r.configs.columns {
@@ -150,7 +150,7 @@ pub fn write_arcbinary<'a>(r: &'a mut Raster) -> Result<(), Error> {
 
     let num_cells: usize = r.configs.rows * r.configs.columns;
     for i in 0..num_cells {
-        u32_bytes = unsafe { mem::transmute(r.data[i] as f32) };
+        u32_bytes = unsafe { mem::transmute(r.data.get_value(i) as f32) };
         writer.write(&u32_bytes)?;
     }
 
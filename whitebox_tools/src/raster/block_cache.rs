@@ -0,0 +1,487 @@
+/*
+This tool is part of the WhiteboxTools geospatial analysis library.
+Authors: Dr. John Lindsay
+Created: March 16, 2018
+Last Modified: April 4, 2018
+License: MIT
+*/
+
+use std::cmp::{max, min};
+use std::collections::HashMap;
+use std::env;
+use std::fmt;
+use std::fs;
+use std::fs::{File, OpenOptions};
+use std::io::{Error, ErrorKind, SeekFrom};
+use std::io::prelude::*;
+use std::process;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use byteorder::{ByteOrder, LittleEndian, NativeEndian};
+use raster::DataType;
+use raster::geotiff::GeoTiffBlocks;
+
+// The preferred size of a block, in bytes.
+const BLOCK_SIZE: usize = 8 * 1024 * 1024;
+
+static SCRATCH_FILE_COUNT: AtomicUsize = AtomicUsize::new(0);
+
+/// The source of the cell values of a block that is read for the first time.
+#[derive(Clone, Debug)]
+pub enum BlockSource {
+    /// Cells that have not yet been written hold the cache's fill value, e.g. a new output.
+    Fill,
+    /// The data (.tas) file of a Whitebox raster.
+    Whitebox { file_name: String, data_type: DataType },
+    /// The strips or tiles of a GeoTIFF image.
+    GeoTiff(GeoTiffBlocks),
+}
+
+impl BlockSource {
+    // The number of rows that the source reads at a time. Blocks are a multiple of this.
+    fn natural_rows(&self) -> usize {
+        match *self {
+            BlockSource::GeoTiff(ref blocks) => blocks.block_height(),
+            _ => 1,
+        }
+    }
+}
+
+/// Out-of-core storage for the cells of a raster that is too large to hold in memory.
+///
+/// The cells of each band are divided into blocks of whole rows, which are paged in from the
+/// source file on demand. Only a limited number of blocks are held in memory; when this limit
+/// is reached, the least recently used block is evicted, and modified blocks are written to a
+/// temporary scratch file from which they are read back when next needed. Cells are only
+/// ever accessed by value, so an evicted block is freed immediately and the memory used by
+/// the cache is bounded, however the raster is read. Reading a cell at a time takes a lock,
+/// so tools that scan a raster should read it by rows or in windows of rows.
+pub struct BlockCache {
+    rows: usize,
+    columns: usize,
+    bands: usize,
+    first_band: usize,
+    block_rows: usize,
+    blocks_per_band: usize,
+    capacity: usize,
+    fill: f64,
+    source: BlockSource,
+    state: Mutex<CacheState>,
+}
+
+struct CacheState {
+    blocks: HashMap<usize, Block>,
+    clock: usize,
+    scratch: Option<ScratchFile>,
+}
+
+struct Block {
+    values: Box<[f64]>,
+    last_used: usize,
+    dirty: bool,
+}
+
+struct ScratchFile {
+    file_name: String,
+    file: File,
+    written: Vec<bool>,
+}
+
+impl BlockCache {
+    /// Creates a cache for a raster of the given dimensions that holds at most roughly
+    /// cache_size bytes of cell values in memory.
+    pub fn new(rows: usize, columns: usize, bands: usize, fill: f64, source: BlockSource, cache_size: usize) -> BlockCache {
+        let row_size = max(1, columns) * 8;
+        let natural_rows = max(1, source.natural_rows());
+        let mut block_rows = max(1, min(BLOCK_SIZE, cache_size / 8) / row_size);
+        block_rows = (block_rows + natural_rows - 1) / natural_rows * natural_rows;
+        block_rows = max(1, min(block_rows, rows));
+        BlockCache {
+            rows: rows,
+            columns: columns,
+            bands: max(1, bands),
+            first_band: 0,
+            block_rows: block_rows,
+            blocks_per_band: (rows + block_rows - 1) / block_rows,
+            capacity: max(4, cache_size / (block_rows * row_size)),
+            fill: fill,
+            source: source,
+            state: Mutex::new(CacheState::new()),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.rows * self.columns * self.bands
+    }
+
+    /// Restricts an unmodified cache to a single band, numbered from zero.
+    pub fn select_band(&mut self, band: usize) {
+        self.first_band += band;
+        self.bands = 1;
+        *self.state.get_mut().unwrap() = CacheState::new();
+    }
+
+    pub fn get_value(&self, index: usize) -> f64 {
+        let (block, offset) = self.locate(index);
+        let mut state = self.state.lock().unwrap();
+        self.load(&mut state, block).values[offset]
+    }
+
+    /// Returns the cells from start up to, but not including, end.
+    pub fn get_values(&self, start: usize, end: usize) -> Vec<f64> {
+        let mut values = Vec::with_capacity(end - start);
+        let mut state = self.state.lock().unwrap();
+        let mut index = start;
+        while index < end {
+            let (block, offset) = self.locate(index);
+            let b = self.load(&mut state, block);
+            let n = min(b.values.len() - offset, end - index);
+            values.extend_from_slice(&b.values[offset..offset + n]);
+            index += n;
+        }
+        values
+    }
+
+    pub fn set_value(&mut self, index: usize, value: f64) {
+        let (block, offset) = self.locate(index);
+        let mut state = self.state.lock().unwrap();
+        let b = self.load(&mut state, block);
+        b.values[offset] = value;
+        b.dirty = true;
+    }
+
+    /// Overwrites the cells starting at start with values.
+    pub fn set_values(&mut self, start: usize, values: &[f64]) {
+        let mut state = self.state.lock().unwrap();
+        let mut i = 0;
+        while i < values.len() {
+            let (block, offset) = self.locate(start + i);
+            let b = self.load(&mut state, block);
+            let n = min(b.values.len() - offset, values.len() - i);
+            b.values[offset..offset + n].copy_from_slice(&values[i..i + n]);
+            b.dirty = true;
+            i += n;
+        }
+    }
+
+    // Returns the block containing a cell and the cell's offset within it.
+    fn locate(&self, index: usize) -> (usize, usize) {
+        let band_cells = self.rows * self.columns;
+        let block_cells = self.block_rows * self.columns;
+        let cell = index % band_cells;
+        let block_in_band = cell / block_cells;
+        (index / band_cells * self.blocks_per_band + block_in_band, cell - block_in_band * block_cells)
+    }
+
+    fn load<'a>(&self, state: &'a mut CacheState, block: usize) -> &'a mut Block {
+        state.clock += 1;
+        let clock = state.clock;
+        if !state.blocks.contains_key(&block) {
+            while state.blocks.len() >= self.capacity {
+                self.evict(state);
+            }
+            let values = self.read_block(state, block).expect("Error reading raster block");
+            state.blocks.insert(block, Block { values: values, last_used: clock, dirty: false });
+        }
+        let b = state.blocks.get_mut(&block).unwrap();
+        b.last_used = clock;
+        b
+    }
+
+    fn evict(&self, state: &mut CacheState) {
+        let lru = match state.blocks.iter().min_by_key(|&(_, b)| b.last_used) {
+            Some((k, _)) => *k,
+            None => return,
+        };
+        let b = state.blocks.remove(&lru).unwrap();
+        if b.dirty {
+            self.write_scratch(state, lru, &b.values).expect("Error writing raster block to scratch file");
+        }
+    }
+
+    fn read_block(&self, state: &mut CacheState, block: usize) -> Result<Box<[f64]>, Error> {
+        let band = block / self.blocks_per_band;
+        let first_row = (block % self.blocks_per_band) * self.block_rows;
+        let num_rows = min(self.block_rows, self.rows - first_row);
+        let num_cells = num_rows * self.columns;
+        if let Some(ref mut scratch) = state.scratch {
+            if scratch.written[block] {
+                let mut buf = vec![0u8; num_cells * 8];
+                scratch.file.seek(SeekFrom::Start(self.scratch_offset(block)))?;
+                scratch.file.read_exact(&mut buf)?;
+                let values: Vec<f64> = (0..num_cells).map(|i| LittleEndian::read_f64(&buf[i * 8..])).collect();
+                return Ok(values.into_boxed_slice());
+            }
+        }
+        let values = match self.source {
+            BlockSource::Fill => vec![self.fill; num_cells],
+            BlockSource::Whitebox { ref file_name, data_type } => {
+                let data_size = match data_type {
+                    DataType::F64 => 8,
+                    DataType::F32 | DataType::I32 | DataType::RGBA32 => 4,
+                    DataType::I16 => 2,
+                    DataType::U8 => 1,
+                    _ => return Err(Error::new(ErrorKind::NotFound, "Raster data type is unknown.")),
+                };
+                let start = ((self.first_band + band) * self.rows + first_row) * self.columns;
+                let mut buf = vec![0u8; num_cells * data_size];
+                let mut f = File::open(file_name)?;
+                f.seek(SeekFrom::Start((start * data_size) as u64))?;
+                f.read_exact(&mut buf)?;
+                (0..num_cells).map(|i| match data_type {
+                    DataType::F64 => NativeEndian::read_f64(&buf[i * 8..]),
+                    DataType::F32 => NativeEndian::read_f32(&buf[i * 4..]) as f64,
+                    DataType::I32 | DataType::RGBA32 => NativeEndian::read_i32(&buf[i * 4..]) as f64,
+                    DataType::I16 => NativeEndian::read_i16(&buf[i * 2..]) as f64,
+                    _ => buf[i] as f64,
+                }).collect()
+            }
+            BlockSource::GeoTiff(ref blocks) => blocks.read_rows(self.first_band + band, first_row, num_rows)?,
+        };
+        Ok(values.into_boxed_slice())
+    }
+
+    fn write_scratch(&self, state: &mut CacheState, block: usize, values: &[f64]) -> Result<(), Error> {
+        if state.scratch.is_none() {
+            state.scratch = Some(ScratchFile::create(self.bands * self.blocks_per_band)?);
+        }
+        let scratch = state.scratch.as_mut().unwrap();
+        let mut buf = vec![0u8; values.len() * 8];
+        for i in 0..values.len() {
+            LittleEndian::write_f64(&mut buf[i * 8..], values[i]);
+        }
+        scratch.file.seek(SeekFrom::Start(self.scratch_offset(block)))?;
+        scratch.file.write_all(&buf)?;
+        scratch.written[block] = true;
+        Ok(())
+    }
+
+    // Each block has a fixed location within the scratch file.
+    fn scratch_offset(&self, block: usize) -> u64 {
+        (block * self.block_rows * self.columns * 8) as u64
+    }
+}
+
+impl Clone for BlockCache {
+    fn clone(&self) -> BlockCache {
+        let state = self.state.lock().unwrap();
+        let mut copy = CacheState::new();
+        copy.clock = state.clock;
+        if let Some(ref scratch) = state.scratch {
+            copy.scratch = Some(scratch.try_clone().expect("Error copying raster scratch file"));
+        }
+        for (k, b) in state.blocks.iter() {
+            copy.blocks.insert(*k, Block { values: b.values.clone(), last_used: b.last_used, dirty: b.dirty });
+        }
+        BlockCache {
+            rows: self.rows,
+            columns: self.columns,
+            bands: self.bands,
+            first_band: self.first_band,
+            block_rows: self.block_rows,
+            blocks_per_band: self.blocks_per_band,
+            capacity: self.capacity,
+            fill: self.fill,
+            source: self.source.clone(),
+            state: Mutex::new(copy),
+        }
+    }
+}
+
+impl fmt::Debug for BlockCache {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "BlockCache {{ rows: {}, columns: {}, bands: {}, block_rows: {}, capacity: {}, source: {:?} }}",
+               self.rows, self.columns, self.bands, self.block_rows, self.capacity, self.source)
+    }
+}
+
+impl CacheState {
+    fn new() -> CacheState {
+        CacheState { blocks: HashMap::new(), clock: 0, scratch: None }
+    }
+}
+
+impl ScratchFile {
+    fn create(num_blocks: usize) -> Result<ScratchFile, Error> {
+        ScratchFile::open(scratch_file_name(), vec![false; num_blocks])
+    }
+
+    fn open(file_name: String, written: Vec<bool>) -> Result<ScratchFile, Error> {
+        let file = OpenOptions::new().read(true).write(true).create(true).open(&file_name)?;
+        Ok(ScratchFile { file_name: file_name, file: file, written: written })
+    }
+
+    fn try_clone(&self) -> Result<ScratchFile, Error> {
+        let file_name = scratch_file_name();
+        fs::copy(&self.file_name, &file_name)?;
+        ScratchFile::open(file_name, self.written.clone())
+    }
+}
+
+impl Drop for ScratchFile {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.file_name);
+    }
+}
+
+fn scratch_file_name() -> String {
+    let id = SCRATCH_FILE_COUNT.fetch_add(1, Ordering::SeqCst);
+    let path = env::temp_dir().join(format!("whitebox_tools_{}_{}.tmp", process::id(), id));
+    path.to_string_lossy().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use raster::{Raster, RasterConfigs, RasterData};
+
+    fn num_resident(cache: &BlockCache) -> usize {
+        cache.state.lock().unwrap().blocks.len()
+    }
+
+    fn resident_bytes(cache: &BlockCache) -> usize {
+        cache.state.lock().unwrap().blocks.values().map(|b| b.values.len() * 8).sum()
+    }
+
+    fn num_written(cache: &BlockCache) -> usize {
+        match cache.state.lock().unwrap().scratch {
+            Some(ref scratch) => scratch.written.iter().filter(|w| **w).count(),
+            None => 0,
+        }
+    }
+
+    #[test]
+    fn blocks_are_evicted_at_the_memory_limit() {
+        // a limit of ten rows of ten cells
+        let limit = 800;
+        let mut cache = BlockCache::new(100, 10, 1, -1.0, BlockSource::Fill, limit);
+        assert_eq!((cache.block_rows, cache.blocks_per_band, cache.capacity), (1, 100, 10));
+
+        // reading does not write blocks to the scratch file
+        assert!(cache.get_values(0, 1000).iter().all(|&v| v == -1.0));
+        assert_eq!(num_resident(&cache), 10);
+        assert!(cache.state.lock().unwrap().scratch.is_none());
+
+        for i in 0..1000 {
+            cache.set_value(i, i as f64);
+            assert!(num_resident(&cache) <= 10);
+            assert!(resident_bytes(&cache) <= limit);
+        }
+        // the least recently used blocks are those that were evicted
+        assert_eq!(num_written(&cache), 90);
+        let resident: Vec<usize> = {
+            let state = cache.state.lock().unwrap();
+            let mut blocks: Vec<usize> = state.blocks.keys().cloned().collect();
+            blocks.sort();
+            blocks
+        };
+        assert_eq!(resident, (90..100).collect::<Vec<usize>>());
+        for i in 0..1000 {
+            assert_eq!(cache.get_value(i), i as f64);
+        }
+        assert!(resident_bytes(&cache) <= limit);
+
+        // blocks of several rows, each an eighth of the cache, and a minimum of four blocks
+        let cache = BlockCache::new(100, 10, 2, 0.0, BlockSource::Fill, 3200);
+        assert_eq!((cache.block_rows, cache.blocks_per_band, cache.capacity), (5, 20, 8));
+        assert_eq!(cache.len(), 2000);
+        let cache = BlockCache::new(100, 10, 1, 0.0, BlockSource::Fill, 8);
+        assert_eq!((cache.block_rows, cache.capacity), (1, 4));
+    }
+
+    #[test]
+    fn modified_blocks_are_written_back() {
+        // a Whitebox data file of 16-bit integers, with three bands of 20 rows of 5 columns
+        let file_name = env::temp_dir().join("wbt_block_cache_source.tas").to_str().unwrap().to_string();
+        let mut bytes = vec![0u8; 300 * 2];
+        for i in 0..300 {
+            NativeEndian::write_i16(&mut bytes[i * 2..], i as i16 - 100);
+        }
+        File::create(&file_name).unwrap().write_all(&bytes).unwrap();
+        let source = BlockSource::Whitebox { file_name: file_name.clone(), data_type: DataType::I16 };
+        let mut cache = BlockCache::new(20, 5, 3, -32768.0, source, 640);
+        assert_eq!((cache.block_rows, cache.capacity), (2, 8));
+        cache.select_band(1);
+        assert_eq!(cache.len(), 100);
+        let original: Vec<f64> = (100..200).map(|i| i as f64 - 100.0).collect();
+        assert_eq!(cache.get_values(0, 100), original);
+
+        // modify a span of cells across four blocks, and the last block, then read the whole
+        // band to evict them
+        let changes: Vec<f64> = (0..30).map(|i| -0.5 * i as f64).collect();
+        cache.set_values(15, &changes);
+        cache.set_value(99, 1e6);
+        let mut expected = original.clone();
+        expected[15..45].copy_from_slice(&changes);
+        expected[99] = 1e6;
+        {
+            let state = cache.state.lock().unwrap();
+            assert_eq!(state.blocks.values().filter(|b| b.dirty).count(), 5);
+        }
+        for _ in 0..2 {
+            assert_eq!(cache.get_values(0, 100), expected);
+            assert!(num_resident(&cache) <= 8);
+        }
+        assert_eq!(num_written(&cache), 5);
+
+        // a copy has the changes, including those in the scratch file, but is independent
+        let mut copy = cache.clone();
+        copy.set_value(0, 42.0);
+        assert_eq!(copy.get_values(0, 100)[1..], expected[1..]);
+        assert_eq!((copy.get_value(0), cache.get_value(0)), (42.0, expected[0]));
+        drop(copy);
+        assert_eq!(cache.get_values(0, 100), expected);
+
+        // the source file is never modified
+        let mut contents = vec![];
+        File::open(&file_name).unwrap().read_to_end(&mut contents).unwrap();
+        assert_eq!(contents, bytes);
+        let scratch_file = cache.state.lock().unwrap().scratch.as_ref().unwrap().file_name.clone();
+        assert!(fs::metadata(&scratch_file).is_ok());
+        drop(cache);
+        assert!(fs::metadata(&scratch_file).is_err());
+        let _ = fs::remove_file(&file_name);
+    }
+
+    #[test]
+    fn out_of_core_rasters_are_written_with_their_changes() {
+        let mut configs = RasterConfigs { ..Default::default() };
+        configs.rows = 60;
+        configs.columns = 7;
+        configs.nodata = -32768f64;
+        configs.north = 60f64;
+        configs.south = 0f64;
+        configs.west = 0f64;
+        configs.east = 7f64;
+        configs.resolution_x = 1f64;
+        configs.resolution_y = 1f64;
+        configs.data_type = DataType::F32;
+        for name in ["wbt_block_cache_output.tif", "wbt_block_cache_output.dep"].iter() {
+            let file_name = env::temp_dir().join(name).to_str().unwrap().to_string();
+            let mut r = Raster::initialize_using_config(&file_name, &configs);
+            // hold at most five rows in memory
+            r.data = RasterData::Cached(BlockCache::new(60, 7, 1, -32768.0, BlockSource::Fill, 5 * 7 * 8));
+            assert!(r.is_out_of_core());
+            for row in 0..60 {
+                for col in 0..7 {
+                    if (row + col) % 5 != 0 {
+                        r.set_value(row, col, (row * 7 + col) as f64 * 0.5);
+                    }
+                }
+            }
+            r.write().unwrap();
+
+            let input = Raster::new(&file_name, "r").unwrap();
+            assert!(!input.is_out_of_core());
+            for row in 0..60 {
+                for col in 0..7 {
+                    let expected = if (row + col) % 5 != 0 { (row * 7 + col) as f64 * 0.5 } else { -32768.0 };
+                    assert_eq!(input.get_value(row, col), expected);
+                }
+            }
+            assert_eq!((input.configs.minimum, input.configs.maximum), (0.5, 209.0));
+            let _ = fs::remove_file(&file_name);
+            let _ = fs::remove_file(file_name.replace(".dep", ".tas"));
+        }
+    }
+}
@@ -0,0 +1,746 @@
+/*
+This code is part of the WhiteboxTools geospatial analysis library.
+Authors: Dr. John Lindsay
+Created: March 31, 2018
+Last Modified: April 6, 2018
+License: MIT
+
+Notes: GeoPackage rasters are stored as tiled gridded coverages (OGC 17-066r1), i.e. a tile
+pyramid user data table whose tiles are 16-bit PNG or 32-bit floating-point TIFF images. The
+first coverage listed in a GeoPackage's contents is read at its finest zoom level; missing
+tiles are read as nodata. Single-band rasters are written as a single zoom level of 256 x 256
+LZW-compressed floating-point TIFF tiles, and tiles that contain only nodata are not written.
+*/
+
+use std::f64;
+use std::io::{Error, ErrorKind};
+use std::path::Path;
+use byteorder::{ByteOrder, BigEndian, LittleEndian};
+use io_utils::{create_geopackage, get_srs_id, open_geopackage, read_srs, Endianness, GeoPackageContents, SqlValue};
+use raster::*;
+use raster::geotiff::compression::*;
+
+const TILE_SIZE: usize = 256;
+const COVERAGE_EXTENSION: &'static str = "gpkg_2d_gridded_coverage";
+const COVERAGE_DEFINITION: &'static str = "http://docs.opengeospatial.org/is/17-066r1/17-066r1.html";
+
+const TILE_MATRIX_SET_SQL: &'static str = "CREATE TABLE gpkg_tile_matrix_set (
+  table_name TEXT NOT NULL PRIMARY KEY,
+  srs_id INTEGER NOT NULL,
+  min_x DOUBLE NOT NULL,
+  min_y DOUBLE NOT NULL,
+  max_x DOUBLE NOT NULL,
+  max_y DOUBLE NOT NULL,
+  CONSTRAINT fk_gtms_table_name FOREIGN KEY (table_name) REFERENCES gpkg_contents(table_name),
+  CONSTRAINT fk_gtms_srs FOREIGN KEY (srs_id) REFERENCES gpkg_spatial_ref_sys (srs_id)
+)";
+
+const TILE_MATRIX_SQL: &'static str = "CREATE TABLE gpkg_tile_matrix (
+  table_name TEXT NOT NULL,
+  zoom_level INTEGER NOT NULL,
+  matrix_width INTEGER NOT NULL,
+  matrix_height INTEGER NOT NULL,
+  tile_width INTEGER NOT NULL,
+  tile_height INTEGER NOT NULL,
+  pixel_x_size DOUBLE NOT NULL,
+  pixel_y_size DOUBLE NOT NULL,
+  CONSTRAINT pk_ttm PRIMARY KEY (table_name, zoom_level),
+  CONSTRAINT fk_tmm_table_name FOREIGN KEY (table_name) REFERENCES gpkg_contents(table_name)
+)";
+
+const COVERAGE_ANCILLARY_SQL: &'static str = "CREATE TABLE gpkg_2d_gridded_coverage_ancillary (
+  id INTEGER PRIMARY KEY AUTOINCREMENT,
+  tile_matrix_set_name TEXT NOT NULL UNIQUE,
+  datatype TEXT NOT NULL DEFAULT 'integer',
+  scale REAL NOT NULL DEFAULT 1.0,
+  offset REAL NOT NULL DEFAULT 0.0,
+  precision REAL DEFAULT 1.0,
+  data_null REAL,
+  grid_cell_encoding TEXT DEFAULT 'grid-value-is-center',
+  uom TEXT,
+  field_name TEXT DEFAULT 'Height',
+  quantity_definition TEXT DEFAULT 'Height',
+  CONSTRAINT fk_g2dgtct_name FOREIGN KEY('tile_matrix_set_name') REFERENCES gpkg_tile_matrix_set ( table_name )
+  CHECK (datatype in ('integer','float')))";
+
+const TILE_ANCILLARY_SQL: &'static str = "CREATE TABLE gpkg_2d_gridded_tile_ancillary (
+  id INTEGER PRIMARY KEY AUTOINCREMENT,
+  tpudt_name TEXT NOT NULL,
+  tpudt_id INTEGER NOT NULL,
+  scale REAL NOT NULL DEFAULT 1.0,
+  offset REAL NOT NULL DEFAULT 0.0,
+  min REAL DEFAULT NULL,
+  max REAL DEFAULT NULL,
+  mean REAL DEFAULT NULL,
+  std_dev REAL DEFAULT NULL,
+  CONSTRAINT fk_g2dgtat_name FOREIGN KEY (tpudt_name) REFERENCES gpkg_contents(table_name),
+  UNIQUE (tpudt_name, tpudt_id))";
+
+fn invalid(message: String) -> Error {
+    Error::new(ErrorKind::InvalidData, message)
+}
+
+fn same_name(value: &SqlValue, name: &str) -> bool {
+    value.as_str().map(|v| v.to_lowercase()) == Some(name.to_lowercase())
+}
+
+pub fn read_geopackage_raster(file_name: &String, configs: &mut RasterConfigs, data: &mut Vec<f64>) -> Result<(), Error> {
+    let (db, contents) = open_geopackage(file_name, "2d-gridded-coverage")?;
+    let name = contents.table_name.clone();
+
+    // the tile matrix set and the finest zoom level
+    let tms = db.read_table("gpkg_tile_matrix_set")?;
+    let tms_row = match (0..tms.rows.len()).find(|&r| same_name(&tms.get_value(r, "table_name"), &name)) {
+        Some(r) => r,
+        None => return Err(invalid(format!("The GeoPackage coverage {} does not have a tile matrix set.", name))),
+    };
+    let tms_min_x = tms.get_value(tms_row, "min_x").as_f64().unwrap_or(0f64);
+    let tms_max_y = tms.get_value(tms_row, "max_y").as_f64().unwrap_or(0f64);
+    let matrices = db.read_table("gpkg_tile_matrix")?;
+    let matrix_row = (0..matrices.rows.len())
+        .filter(|&r| same_name(&matrices.get_value(r, "table_name"), &name))
+        .max_by_key(|&r| matrices.get_value(r, "zoom_level").as_i64().unwrap_or(0));
+    let matrix_row = match matrix_row {
+        Some(r) => r,
+        None => return Err(invalid(format!("The GeoPackage coverage {} does not have a tile matrix.", name))),
+    };
+    let get_usize = |column: &str| matrices.get_value(matrix_row, column).as_i64().unwrap_or(0).max(0) as usize;
+    let zoom_level = matrices.get_value(matrix_row, "zoom_level").as_i64().unwrap_or(0);
+    let (matrix_width, matrix_height) = (get_usize("matrix_width"), get_usize("matrix_height"));
+    let (tile_width, tile_height) = (get_usize("tile_width"), get_usize("tile_height"));
+    let pixel_x = matrices.get_value(matrix_row, "pixel_x_size").as_f64().unwrap_or(1f64);
+    let pixel_y = matrices.get_value(matrix_row, "pixel_y_size").as_f64().unwrap_or(1f64);
+
+    // The raster extent is that of the contents, if specified, snapped to the tile matrix.
+    let full_columns = matrix_width * tile_width;
+    let full_rows = matrix_height * tile_height;
+    let to_col = |x: f64| (((x - tms_min_x) / pixel_x).round().max(0f64) as usize).min(full_columns);
+    let to_row = |y: f64| (((tms_max_y - y) / pixel_y).round().max(0f64) as usize).min(full_rows);
+    let first_col = contents.min_x.map(|x| to_col(x)).unwrap_or(0);
+    let last_col = contents.max_x.map(|x| to_col(x)).unwrap_or(full_columns);
+    let first_row = contents.max_y.map(|y| to_row(y)).unwrap_or(0);
+    let last_row = contents.min_y.map(|y| to_row(y)).unwrap_or(full_rows);
+    let (first_col, last_col) = if last_col > first_col { (first_col, last_col) } else { (0, full_columns) };
+    let (first_row, last_row) = if last_row > first_row { (first_row, last_row) } else { (0, full_rows) };
+    configs.columns = last_col - first_col;
+    configs.rows = last_row - first_row;
+    configs.resolution_x = pixel_x;
+    configs.resolution_y = pixel_y;
+    configs.west = tms_min_x + first_col as f64 * pixel_x;
+    configs.east = configs.west + configs.columns as f64 * pixel_x;
+    configs.north = tms_max_y - first_row as f64 * pixel_y;
+    configs.south = configs.north - configs.rows as f64 * pixel_y;
+
+    // the coverage's data type, scale and offset, and nodata value
+    let ancillary = db.read_table("gpkg_2d_gridded_coverage_ancillary")?;
+    let (mut is_float, mut scale, mut offset, mut data_null) = (false, 1f64, 0f64, None);
+    if let Some(r) = (0..ancillary.rows.len()).find(|&r| same_name(&ancillary.get_value(r, "tile_matrix_set_name"), &name)) {
+        is_float = ancillary.get_value(r, "datatype").as_str().map(|t| t.to_lowercase() == "float").unwrap_or(false);
+        scale = ancillary.get_value(r, "scale").as_f64().unwrap_or(1f64);
+        offset = ancillary.get_value(r, "offset").as_f64().unwrap_or(0f64);
+        data_null = ancillary.get_value(r, "data_null").as_f64();
+    }
+    configs.nodata = match data_null {
+        Some(v) if is_float => v,
+        Some(v) => v * scale + offset,
+        None => -32768f64,
+    };
+    configs.data_type = if is_float || scale != 1f64 || offset != 0f64 { DataType::F32 } else { DataType::I32 };
+    configs.photometric_interp = PhotometricInterpretation::Continuous;
+
+    let (epsg_code, wkt) = read_srs(&db, contents.srs_id)?;
+    configs.epsg_code = epsg_code;
+    if !wkt.is_empty() {
+        configs.coordinate_ref_system_wkt = wkt;
+    }
+
+    // the per-tile scales and offsets
+    let mut tile_scales = vec![];
+    if db.has_table("gpkg_2d_gridded_tile_ancillary") {
+        let t = db.read_table("gpkg_2d_gridded_tile_ancillary")?;
+        for r in 0..t.rows.len() {
+            if same_name(&t.get_value(r, "tpudt_name"), &name) {
+                if let Some(id) = t.get_value(r, "tpudt_id").as_i64() {
+                    tile_scales.push((id, t.get_value(r, "scale").as_f64().unwrap_or(1f64), t.get_value(r, "offset").as_f64().unwrap_or(0f64)));
+                }
+            }
+        }
+    }
+    tile_scales.sort_by_key(|t| t.0);
+
+    let nodata = configs.nodata;
+    *data = vec![nodata; configs.rows * configs.columns];
+    let tiles = db.read_table(&name)?;
+    let id_column = tiles.columns.iter().position(|c| c.is_rowid);
+    for t in 0..tiles.rows.len() {
+        if tiles.get_value(t, "zoom_level").as_i64() != Some(zoom_level) {
+            continue;
+        }
+        let tile_col = tiles.get_value(t, "tile_column").as_i64().unwrap_or(-1);
+        let tile_row = tiles.get_value(t, "tile_row").as_i64().unwrap_or(-1);
+        if tile_col < 0 || tile_row < 0 {
+            continue;
+        }
+        let blob = match tiles.get_value(t, "tile_data") {
+            SqlValue::Blob(b) => b,
+            _ => continue,
+        };
+        let (width, height, values, is_float_tile) = decode_tile(&blob)?;
+        let (tile_scale, tile_offset) = match id_column.and_then(|c| tiles.rows[t][c].as_i64()) {
+            Some(id) => match tile_scales.binary_search_by_key(&id, |s| s.0) {
+                Ok(i) => (tile_scales[i].1, tile_scales[i].2),
+                Err(_) => (1f64, 0f64),
+            },
+            None => (1f64, 0f64),
+        };
+        for r in 0..height.min(tile_height) {
+            let row = tile_row as usize * tile_height + r;
+            if row < first_row || row >= last_row { continue; }
+            for c in 0..width.min(tile_width) {
+                let col = tile_col as usize * tile_width + c;
+                if col < first_col || col >= last_col { continue; }
+                let raw = values[r * width + c];
+                let is_nodata = match data_null {
+                    Some(v) if is_float_tile => raw as f32 == v as f32,
+                    Some(v) => raw == v,
+                    None => false,
+                };
+                data[(row - first_row) * configs.columns + col - first_col] = if is_nodata {
+                    nodata
+                } else {
+                    (raw * tile_scale + tile_offset) * scale + offset
+                };
+            }
+        }
+    }
+    Ok(())
+}
+
+// decodes a PNG or TIFF tile, returning its width, height, values, and whether they are floating-point
+fn decode_tile(blob: &[u8]) -> Result<(usize, usize, Vec<f64>, bool), Error> {
+    if blob.len() > 8 && &blob[0..8] == b"\x89PNG\r\n\x1a\n" {
+        let (width, height, values) = decode_png(blob)?;
+        Ok((width, height, values, false))
+    } else if blob.len() > 8 && (&blob[0..4] == b"II*\0" || &blob[0..4] == b"MM\0*") {
+        let (width, height, values) = decode_tiff(blob)?;
+        Ok((width, height, values, true))
+    } else {
+        Err(invalid("Unsupported GeoPackage tile encoding; coverage tiles must be PNG or TIFF images.".to_string()))
+    }
+}
+
+// decodes a non-interlaced 8- or 16-bit greyscale PNG
+fn decode_png(blob: &[u8]) -> Result<(usize, usize, Vec<f64>), Error> {
+    let (mut width, mut height, mut bit_depth) = (0usize, 0usize, 0u8);
+    let mut compressed = vec![];
+    let mut pos = 8;
+    while pos + 8 <= blob.len() {
+        let length = BigEndian::read_u32(&blob[pos..pos + 4]) as usize;
+        let chunk_type = &blob[pos + 4..pos + 8];
+        if pos + 8 + length > blob.len() {
+            return Err(invalid("A PNG tile is truncated.".to_string()));
+        }
+        let chunk = &blob[pos + 8..pos + 8 + length];
+        match chunk_type {
+            b"IHDR" if length >= 13 => {
+                width = BigEndian::read_u32(&chunk[0..4]) as usize;
+                height = BigEndian::read_u32(&chunk[4..8]) as usize;
+                bit_depth = chunk[8];
+                if chunk[9] != 0 || (bit_depth != 8 && bit_depth != 16) || chunk[12] != 0 {
+                    return Err(invalid("Only non-interlaced 8- and 16-bit greyscale PNG tiles are supported.".to_string()));
+                }
+            },
+            b"IDAT" => compressed.extend_from_slice(chunk),
+            b"IEND" => break,
+            _ => {},
+        }
+        pos += 12 + length;
+    }
+    let bpp = bit_depth as usize / 8;
+    let stride = width * bpp;
+    let raw = deflate_decoder(&compressed, (stride + 1) * height)?;
+    if raw.len() < (stride + 1) * height {
+        return Err(invalid("A PNG tile is truncated.".to_string()));
+    }
+    let mut image = vec![0u8; stride * height];
+    for r in 0..height {
+        let filter = raw[r * (stride + 1)];
+        let line = &raw[r * (stride + 1) + 1..(r + 1) * (stride + 1)];
+        for i in 0..stride {
+            let a = if i >= bpp { image[r * stride + i - bpp] as i32 } else { 0 };
+            let b = if r > 0 { image[(r - 1) * stride + i] as i32 } else { 0 };
+            let c = if r > 0 && i >= bpp { image[(r - 1) * stride + i - bpp] as i32 } else { 0 };
+            let predictor = match filter {
+                0 => 0,
+                1 => a,
+                2 => b,
+                3 => (a + b) / 2,
+                4 => {
+                    let p = a + b - c;
+                    let (pa, pb, pc) = ((p - a).abs(), (p - b).abs(), (p - c).abs());
+                    if pa <= pb && pa <= pc { a } else if pb <= pc { b } else { c }
+                },
+                _ => return Err(invalid(format!("Unrecognized PNG filter type {}.", filter))),
+            };
+            image[r * stride + i] = (line[i] as i32 + predictor) as u8;
+        }
+    }
+    let values = if bpp == 2 {
+        image.chunks(2).map(|b| BigEndian::read_u16(b) as f64).collect()
+    } else {
+        image.iter().map(|&b| b as f64).collect()
+    };
+    Ok((width, height, values))
+}
+
+// decodes a single-band 32-bit floating-point TIFF that is stored in strips
+fn decode_tiff(blob: &[u8]) -> Result<(usize, usize, Vec<f64>), Error> {
+    let little_endian = blob[0] == b'I';
+    let truncated = || invalid("A TIFF tile is truncated.".to_string());
+    let read_u16 = |pos: usize| -> Result<u32, Error> {
+        if pos + 2 > blob.len() { return Err(truncated()); }
+        Ok(if little_endian { LittleEndian::read_u16(&blob[pos..]) } else { BigEndian::read_u16(&blob[pos..]) } as u32)
+    };
+    let read_u32 = |pos: usize| -> Result<u32, Error> {
+        if pos + 4 > blob.len() { return Err(truncated()); }
+        Ok(if little_endian { LittleEndian::read_u32(&blob[pos..]) } else { BigEndian::read_u32(&blob[pos..]) })
+    };
+    let ifd = read_u32(4)? as usize;
+    let num_entries = read_u16(ifd)? as usize;
+    let (mut width, mut height, mut bits, mut compression, mut samples) = (0usize, 0usize, 32u32, 1u32, 1u32);
+    let (mut rows_per_strip, mut predictor, mut sample_format) = (0usize, 1u32, 1u32);
+    let (mut offsets, mut byte_counts) = (vec![], vec![]);
+    for e in 0..num_entries {
+        let entry = ifd + 2 + e * 12;
+        let tag = read_u16(entry)?;
+        let field_type = read_u16(entry + 2)?;
+        let count = read_u32(entry + 4)? as usize;
+        let size = if field_type == 3 { 2 } else { 4 };
+        let start = if size * count > 4 { read_u32(entry + 8)? as usize } else { entry + 8 };
+        let mut values = Vec::with_capacity(count);
+        for i in 0..count {
+            values.push(if field_type == 3 { read_u16(start + i * 2)? } else { read_u32(start + i * 4)? });
+        }
+        let first = values.get(0).cloned().unwrap_or(0);
+        match tag {
+            256 => width = first as usize,
+            257 => height = first as usize,
+            258 => bits = first,
+            259 => compression = first,
+            273 => offsets = values,
+            277 => samples = first,
+            278 => rows_per_strip = first as usize,
+            279 => byte_counts = values,
+            317 => predictor = first,
+            339 => sample_format = first,
+            322 | 323 => return Err(invalid("Tiled TIFF coverage tiles are not supported.".to_string())),
+            _ => {},
+        }
+    }
+    if bits != 32 || samples != 1 || sample_format != 3 {
+        return Err(invalid("TIFF coverage tiles must contain a single band of 32-bit floating-point values.".to_string()));
+    }
+    if rows_per_strip == 0 || rows_per_strip > height {
+        rows_per_strip = height;
+    }
+    let endian = if little_endian { Endianness::LittleEndian } else { Endianness::BigEndian };
+    let mut values = Vec::with_capacity(width * height);
+    for (s, (&offset, &count)) in offsets.iter().zip(byte_counts.iter()).enumerate() {
+        let (offset, count) = (offset as usize, count as usize);
+        if offset + count > blob.len() {
+            return Err(truncated());
+        }
+        let strip_rows = rows_per_strip.min(height - s * rows_per_strip);
+        let expected = strip_rows * width * 4;
+        let data = &blob[offset..offset + count];
+        let mut buf = match compression {
+            1 => data.to_vec(),
+            5 => lzw_decoder(data, expected)?,
+            8 | 32946 => deflate_decoder(data, expected)?,
+            c => return Err(invalid(format!("Unsupported TIFF tile compression method {}.", c))),
+        };
+        if buf.len() < expected {
+            return Err(truncated());
+        }
+        match predictor {
+            2 => undo_horizontal_predictor(&mut buf[0..expected], width, 1, 4, endian)?,
+            3 => undo_floating_point_predictor(&mut buf[0..expected], width, 1, 4, endian)?,
+            _ => {},
+        }
+        for v in buf[0..expected].chunks(4) {
+            values.push(if little_endian { LittleEndian::read_f32(v) } else { BigEndian::read_f32(v) } as f64);
+        }
+    }
+    if values.len() < width * height {
+        return Err(truncated());
+    }
+    Ok((width, height, values))
+}
+
+pub fn write_geopackage_raster<'a>(r: &'a mut Raster) -> Result<(), Error> {
+    match r.configs.data_type {
+        DataType::RGB24 | DataType::RGB48 | DataType::RGBA32 => {
+            return Err(Error::new(ErrorKind::InvalidInput, "RGB rasters cannot be written as GeoPackage gridded coverages."));
+        },
+        _ => {},
+    }
+    if r.configs.photometric_interp == PhotometricInterpretation::RGB {
+        return Err(Error::new(ErrorKind::InvalidInput, "RGB rasters cannot be written as GeoPackage gridded coverages."));
+    }
+    if r.num_bands() > 1 {
+        return Err(Error::new(ErrorKind::InvalidInput,
+            format!("GeoPackage gridded coverages hold a single band, but {} has {} bands.", r.file_name, r.num_bands())));
+    }
+    let name = Path::new(&r.file_name).file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or("coverage".to_string());
+    let (rows, columns) = (r.configs.rows, r.configs.columns);
+    let matrix_width = (columns + TILE_SIZE - 1) / TILE_SIZE;
+    let matrix_height = (rows + TILE_SIZE - 1) / TILE_SIZE;
+    let (res_x, res_y) = (r.configs.resolution_x, r.configs.resolution_y);
+    let nodata = r.configs.nodata as f32;
+
+    let wkt = if r.configs.coordinate_ref_system_wkt == "not specified" { String::new() } else { r.configs.coordinate_ref_system_wkt.clone() };
+    let srs_id = get_srs_id(r.configs.epsg_code, &wkt);
+    let contents = GeoPackageContents {
+        table_name: name.clone(),
+        data_type: "2d-gridded-coverage".to_string(),
+        min_x: Some(r.configs.west),
+        min_y: Some(r.configs.south),
+        max_x: Some(r.configs.east),
+        max_y: Some(r.configs.north),
+        srs_id: srs_id,
+    };
+    let text = |s: &str| SqlValue::Text(s.to_string());
+    let extensions = vec![
+        vec![text("gpkg_2d_gridded_coverage_ancillary"), SqlValue::Null, text(COVERAGE_EXTENSION), text(COVERAGE_DEFINITION), text("read-write")],
+        vec![text("gpkg_2d_gridded_tile_ancillary"), SqlValue::Null, text(COVERAGE_EXTENSION), text(COVERAGE_DEFINITION), text("read-write")],
+        vec![text(&name), text("tile_data"), text(COVERAGE_EXTENSION), text(COVERAGE_DEFINITION), text("read-write")],
+    ];
+    let mut writer = create_geopackage(&r.file_name, &contents, r.configs.epsg_code, &wkt, extensions)?;
+
+    writer.add_table("gpkg_tile_matrix_set", TILE_MATRIX_SET_SQL, vec![vec![
+        text(&name), SqlValue::Integer(srs_id),
+        SqlValue::Real(r.configs.west), SqlValue::Real(r.configs.north - (matrix_height * TILE_SIZE) as f64 * res_y),
+        SqlValue::Real(r.configs.west + (matrix_width * TILE_SIZE) as f64 * res_x), SqlValue::Real(r.configs.north)]])?;
+    writer.add_table("gpkg_tile_matrix", TILE_MATRIX_SQL, vec![vec![
+        text(&name), SqlValue::Integer(0), SqlValue::Integer(matrix_width as i64), SqlValue::Integer(matrix_height as i64),
+        SqlValue::Integer(TILE_SIZE as i64), SqlValue::Integer(TILE_SIZE as i64), SqlValue::Real(res_x), SqlValue::Real(res_y)]])?;
+    let uom = if r.configs.z_units == "not specified" { SqlValue::Null } else { text(&r.configs.z_units) };
+    writer.add_table("gpkg_2d_gridded_coverage_ancillary", COVERAGE_ANCILLARY_SQL, vec![vec![
+        SqlValue::Integer(1), text(&name), text("float"), SqlValue::Real(1f64), SqlValue::Real(0f64), SqlValue::Real(1f64),
+        SqlValue::Real(nodata as f64), text("grid-value-is-area"), uom, text("Height"), text("Height")]])?;
+
+    // Each tile is written as soon as it is encoded; only the statistics of the tiles are kept
+    // for the tile ancillary table.
+    let tiles_sql = format!("CREATE TABLE \"{}\" (
+  id INTEGER PRIMARY KEY AUTOINCREMENT,
+  zoom_level INTEGER NOT NULL,
+  tile_column INTEGER NOT NULL,
+  tile_row INTEGER NOT NULL,
+  tile_data BLOB NOT NULL,
+  UNIQUE (zoom_level, tile_column, tile_row))", name.replace("\"", "\"\""));
+    writer.begin_table(&name, &tiles_sql)?;
+    let mut tile_ancillary = vec![];
+    let mut tile_values = vec![nodata; TILE_SIZE * TILE_SIZE];
+    for tile_row in 0..matrix_height {
+        for tile_col in 0..matrix_width {
+            let (mut n, mut sum, mut sq_sum) = (0usize, 0f64, 0f64);
+            let (mut min, mut max) = (f64::INFINITY, f64::NEG_INFINITY);
+            for tr in 0..TILE_SIZE {
+                let row = tile_row * TILE_SIZE + tr;
+                for tc in 0..TILE_SIZE {
+                    let col = tile_col * TILE_SIZE + tc;
+                    let mut z = nodata;
+                    if row < rows && col < columns {
+                        let value = r.data.get_value(row * columns + col);
+                        if value as f32 != nodata {
+                            z = value as f32;
+                            n += 1;
+                            sum += z as f64;
+                            sq_sum += z as f64 * z as f64;
+                            if (z as f64) < min { min = z as f64; }
+                            if (z as f64) > max { max = z as f64; }
+                        }
+                    }
+                    tile_values[tr * TILE_SIZE + tc] = z;
+                }
+            }
+            if n == 0 {
+                continue;
+            }
+            let id = tile_ancillary.len() as i64 + 1;
+            writer.add_row(vec![SqlValue::Integer(id), SqlValue::Integer(0), SqlValue::Integer(tile_col as i64),
+                SqlValue::Integer(tile_row as i64), SqlValue::Blob(encode_tiff(&tile_values))])?;
+            let mean = sum / n as f64;
+            let std_dev = (sq_sum / n as f64 - mean * mean).max(0f64).sqrt();
+            tile_ancillary.push(vec![SqlValue::Integer(id), text(&name), SqlValue::Integer(id), SqlValue::Real(1f64),
+                SqlValue::Real(0f64), SqlValue::Real(min), SqlValue::Real(max), SqlValue::Real(mean), SqlValue::Real(std_dev)]);
+        }
+    }
+    writer.end_table()?;
+    writer.add_table("gpkg_2d_gridded_tile_ancillary", TILE_ANCILLARY_SQL, tile_ancillary)?;
+    writer.finish()
+}
+
+// encodes a tile as a little-endian, LZW-compressed, single-strip floating-point TIFF
+fn encode_tiff(values: &[f32]) -> Vec<u8> {
+    let mut raw = vec![0u8; values.len() * 4];
+    for (i, v) in values.iter().enumerate() {
+        LittleEndian::write_f32(&mut raw[i * 4..], *v);
+    }
+    let compressed = lzw_encoder(&raw);
+
+    // tag, field type, and value, in ascending tag order
+    let size = TILE_SIZE as u32;
+    let entries: [(u16, u16, u32); 11] = [
+        (256, 3, size), // image width
+        (257, 3, size), // image length
+        (258, 3, 32), // bits per sample
+        (259, 3, 5), // compression (LZW)
+        (262, 3, 1), // photometric interpretation (black is zero)
+        (273, 4, 0), // strip offsets, set below
+        (277, 3, 1), // samples per pixel
+        (278, 3, size), // rows per strip
+        (279, 4, compressed.len() as u32), // strip byte counts
+        (284, 3, 1), // planar configuration
+        (339, 3, 3), // sample format (floating point)
+    ];
+    let ifd_size = 2 + entries.len() * 12 + 4;
+    let data_offset = (8 + ifd_size) as u32;
+    let mut tiff = Vec::with_capacity(data_offset as usize + compressed.len());
+    let mut buf = [0u8; 4];
+    tiff.extend_from_slice(b"II*\0");
+    LittleEndian::write_u32(&mut buf, 8);
+    tiff.extend_from_slice(&buf);
+    LittleEndian::write_u16(&mut buf, entries.len() as u16);
+    tiff.extend_from_slice(&buf[0..2]);
+    for &(tag, field_type, value) in entries.iter() {
+        let value = if tag == 273 { data_offset } else { value };
+        LittleEndian::write_u16(&mut buf, tag);
+        tiff.extend_from_slice(&buf[0..2]);
+        LittleEndian::write_u16(&mut buf, field_type);
+        tiff.extend_from_slice(&buf[0..2]);
+        LittleEndian::write_u32(&mut buf, 1);
+        tiff.extend_from_slice(&buf);
+        buf = [0u8; 4];
+        if field_type == 3 {
+            LittleEndian::write_u16(&mut buf, value as u16);
+        } else {
+            LittleEndian::write_u32(&mut buf, value);
+        }
+        tiff.extend_from_slice(&buf);
+    }
+    tiff.extend_from_slice(&[0u8; 4]); // no further IFDs
+    tiff.extend_from_slice(&compressed);
+    tiff
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use std::fs;
+    use io_utils::SqliteReader;
+
+    fn temp_file(name: &str) -> String {
+        env::temp_dir().join(name).to_str().unwrap().to_string()
+    }
+
+    // A floating-point raster that spans several tiles, with a few NoData cells and a tile
+    // that holds only NoData.
+    fn test_raster(file_name: &str, rows: usize, columns: usize) -> Raster {
+        let mut configs = RasterConfigs { ..Default::default() };
+        configs.rows = rows;
+        configs.columns = columns;
+        configs.nodata = -32768f64;
+        configs.north = 4_800_000f64;
+        configs.south = configs.north - 2.5 * rows as f64;
+        configs.west = 500_000f64;
+        configs.east = configs.west + 2.5 * columns as f64;
+        configs.resolution_x = 2.5;
+        configs.resolution_y = 2.5;
+        configs.data_type = DataType::F32;
+        configs.photometric_interp = PhotometricInterpretation::Continuous;
+        configs.epsg_code = 26917;
+        let mut r = Raster::initialize_using_config(file_name, &configs);
+
+        let mut seed = 987654321u64;
+        for row in 0..rows as isize {
+            for col in 0..columns as isize {
+                seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+                let x = (seed >> 11) as f64 / (1u64 << 53) as f64;
+                let value = if (row + col) % 37 == 0 || (row < 256 && col >= 256 && col < 512) {
+                    -32768f64
+                } else {
+                    (1000f64 * x - 250f64) as f32 as f64
+                };
+                r.set_value(row, col, value);
+            }
+        }
+        r
+    }
+
+    #[test]
+    fn geopackage_raster_round_trip() {
+        let file_name = temp_file("wbt_geopackage_raster.gpkg");
+        let (rows, columns) = (300, 600);
+        let mut r = test_raster(&file_name, rows, columns);
+        r.write().unwrap();
+
+        // six tiles cover the raster, one of which holds only NoData and is not written
+        let db = SqliteReader::new(&file_name).unwrap();
+        let tiles = db.read_table("wbt_geopackage_raster").unwrap();
+        assert_eq!(tiles.rows.len(), 5);
+        assert!(!(0..5).any(|t| tiles.get_value(t, "tile_column") == SqlValue::Integer(1) && tiles.get_value(t, "tile_row") == SqlValue::Integer(0)));
+        assert_eq!(db.read_table("gpkg_2d_gridded_tile_ancillary").unwrap().rows.len(), 5);
+
+        let input = Raster::new(&file_name, "r").unwrap();
+        assert_eq!((input.configs.rows, input.configs.columns), (rows, columns));
+        assert_eq!((input.configs.west, input.configs.north, input.configs.south, input.configs.east),
+            (r.configs.west, r.configs.north, r.configs.south, r.configs.east));
+        assert_eq!((input.configs.resolution_x, input.configs.resolution_y), (2.5, 2.5));
+        assert_eq!(input.configs.nodata, -32768f64);
+        assert_eq!(input.configs.epsg_code, 26917);
+        for row in 0..rows as isize {
+            for col in 0..columns as isize {
+                assert_eq!(input.get_value(row, col), r.get_value(row, col), "row {}, column {}", row, col);
+            }
+        }
+        let _ = fs::remove_file(&file_name);
+    }
+
+    #[test]
+    fn multiband_and_rgb_rasters_are_not_written() {
+        let file_name = temp_file("wbt_geopackage_bands.gpkg");
+        let mut r = test_raster(&file_name, 10, 10);
+        r.configs.photometric_interp = PhotometricInterpretation::RGB;
+        assert_eq!(write_geopackage_raster(&mut r).unwrap_err().kind(), ErrorKind::InvalidInput);
+        r.configs.photometric_interp = PhotometricInterpretation::Continuous;
+        r.configs.data_type = DataType::RGBA32;
+        assert_eq!(write_geopackage_raster(&mut r).unwrap_err().kind(), ErrorKind::InvalidInput);
+
+        let mut configs = r.configs.clone();
+        configs.data_type = DataType::F32;
+        configs.bands = 3;
+        let mut r = Raster::initialize_using_config(&file_name, &configs);
+        assert_eq!(write_geopackage_raster(&mut r).unwrap_err().kind(), ErrorKind::InvalidInput);
+        let _ = fs::remove_file(&file_name);
+    }
+
+    #[test]
+    fn tiff_tiles_round_trip() {
+        let values: Vec<f32> = (0..TILE_SIZE * TILE_SIZE).map(|i| (i % 1000) as f32 * 0.25 - 100.0).collect();
+        let (width, height, decoded) = decode_tiff(&encode_tiff(&values)).unwrap();
+        assert_eq!((width, height), (TILE_SIZE, TILE_SIZE));
+        assert!(decoded.iter().zip(values.iter()).all(|(&a, &b)| a == b as f64));
+    }
+
+    // encodes a 16-bit greyscale PNG, using a different filter for each row
+    fn encode_png(width: usize, height: usize, values: &[u16]) -> Vec<u8> {
+        let stride = width * 2;
+        let mut image = vec![0u8; stride * height];
+        for (i, &v) in values.iter().enumerate() {
+            BigEndian::write_u16(&mut image[i * 2..], v);
+        }
+        let mut raw = vec![];
+        for r in 0..height {
+            let filter = (r % 5) as u8;
+            raw.push(filter);
+            for i in 0..stride {
+                let a = if i >= 2 { image[r * stride + i - 2] as i32 } else { 0 };
+                let b = if r > 0 { image[(r - 1) * stride + i] as i32 } else { 0 };
+                let c = if r > 0 && i >= 2 { image[(r - 1) * stride + i - 2] as i32 } else { 0 };
+                let predictor = match filter {
+                    0 => 0,
+                    1 => a,
+                    2 => b,
+                    3 => (a + b) / 2,
+                    _ => {
+                        let p = a + b - c;
+                        let (pa, pb, pc) = ((p - a).abs(), (p - b).abs(), (p - c).abs());
+                        if pa <= pb && pa <= pc { a } else if pb <= pc { b } else { c }
+                    },
+                };
+                raw.push((image[r * stride + i] as i32 - predictor) as u8);
+            }
+        }
+        let chunk = |png: &mut Vec<u8>, chunk_type: &[u8], data: &[u8]| {
+            let mut buf = [0u8; 4];
+            BigEndian::write_u32(&mut buf, data.len() as u32);
+            png.extend_from_slice(&buf);
+            png.extend_from_slice(chunk_type);
+            png.extend_from_slice(data);
+            png.extend_from_slice(&[0u8; 4]); // the CRC is not checked
+        };
+        let mut header = vec![0u8; 13];
+        BigEndian::write_u32(&mut header[0..4], width as u32);
+        BigEndian::write_u32(&mut header[4..8], height as u32);
+        header[8] = 16;
+        let mut png = b"\x89PNG\r\n\x1a\n".to_vec();
+        chunk(&mut png, b"IHDR", &header);
+        chunk(&mut png, b"IDAT", &deflate_encoder(&raw).unwrap());
+        chunk(&mut png, b"IEND", &[]);
+        png
+    }
+
+    #[test]
+    fn integer_png_coverages_are_read() {
+        // a coverage of two 4 x 5 tiles, the second of which is missing, whose contents
+        // exclude the first column of the tile matrix
+        let file_name = temp_file("wbt_geopackage_png.gpkg");
+        let (tile_width, tile_height) = (4usize, 5usize);
+        let raw: Vec<u16> = (0..tile_width * tile_height).map(|i| if i == 6 { 65535 } else { (i * 1237 % 60000) as u16 }).collect();
+        let contents = GeoPackageContents {
+            table_name: "dem".to_string(),
+            data_type: "2d-gridded-coverage".to_string(),
+            min_x: Some(101f64),
+            min_y: Some(0f64),
+            max_x: Some(107f64),
+            max_y: Some(5f64),
+            srs_id: 4326,
+        };
+        let text = |s: &str| SqlValue::Text(s.to_string());
+        let mut writer = create_geopackage(&file_name, &contents, 4326, "", vec![]).unwrap();
+        writer.add_table("gpkg_tile_matrix_set", TILE_MATRIX_SET_SQL, vec![vec![text("dem"), SqlValue::Integer(4326),
+            SqlValue::Real(100f64), SqlValue::Real(0f64), SqlValue::Real(108f64), SqlValue::Real(5f64)]]).unwrap();
+        writer.add_table("gpkg_tile_matrix", TILE_MATRIX_SQL, vec![
+            vec![text("dem"), SqlValue::Integer(0), SqlValue::Integer(1), SqlValue::Integer(1), SqlValue::Integer(4),
+                SqlValue::Integer(5), SqlValue::Real(2f64), SqlValue::Real(1f64)],
+            vec![text("dem"), SqlValue::Integer(1), SqlValue::Integer(2), SqlValue::Integer(1), SqlValue::Integer(tile_width as i64),
+                SqlValue::Integer(tile_height as i64), SqlValue::Real(1f64), SqlValue::Real(1f64)]]).unwrap();
+        writer.add_table("gpkg_2d_gridded_coverage_ancillary", COVERAGE_ANCILLARY_SQL, vec![vec![SqlValue::Integer(1), text("dem"),
+            text("integer"), SqlValue::Real(0.5), SqlValue::Real(100f64), SqlValue::Real(1f64), SqlValue::Real(65535f64)]]).unwrap();
+        writer.add_table("gpkg_2d_gridded_tile_ancillary", TILE_ANCILLARY_SQL, vec![vec![SqlValue::Integer(1), text("dem"),
+            SqlValue::Integer(7), SqlValue::Real(2f64), SqlValue::Real(1f64)]]).unwrap();
+        writer.add_table("dem", "CREATE TABLE dem (id INTEGER PRIMARY KEY AUTOINCREMENT, zoom_level INTEGER NOT NULL,
+            tile_column INTEGER NOT NULL, tile_row INTEGER NOT NULL, tile_data BLOB NOT NULL, UNIQUE (zoom_level, tile_column, tile_row))", vec![
+            vec![SqlValue::Integer(3), SqlValue::Integer(0), SqlValue::Integer(0), SqlValue::Integer(0), SqlValue::Blob(encode_png(1, 1, &[0]))],
+            vec![SqlValue::Integer(7), SqlValue::Integer(1), SqlValue::Integer(0), SqlValue::Integer(0), SqlValue::Blob(encode_png(tile_width, tile_height, &raw))],
+        ]).unwrap();
+        writer.finish().unwrap();
+
+        let (width, height, decoded) = decode_png(&encode_png(tile_width, tile_height, &raw)).unwrap();
+        assert_eq!((width, height), (tile_width, tile_height));
+        assert!(decoded.iter().zip(raw.iter()).all(|(&a, &b)| a == b as f64));
+
+        // the finest zoom level is read
+        let input = Raster::new(&file_name, "r").unwrap();
+        assert_eq!((input.configs.rows, input.configs.columns), (5, 6));
+        assert_eq!((input.configs.west, input.configs.east, input.configs.north, input.configs.south), (101f64, 107f64, 5f64, 0f64));
+        assert_eq!(input.configs.epsg_code, 4326);
+        let nodata = 65535f64 * 0.5 + 100f64;
+        assert_eq!(input.configs.nodata, nodata);
+        for row in 0..5 {
+            for col in 0..6 {
+                let expected = if col >= 3 || row * tile_width + col + 1 == 6 {
+                    nodata
+                } else {
+                    (raw[row * tile_width + col + 1] as f64 * 2.0 + 1.0) * 0.5 + 100.0
+                };
+                assert_eq!(input.get_value(row as isize, col as isize), expected, "row {}, column {}", row, col);
+            }
+        }
+        let _ = fs::remove_file(&file_name);
+    }
+}
@@ -0,0 +1,508 @@
+/*
+This code is part of the WhiteboxTools geospatial analysis library.
+Authors: Dr. John Lindsay
+Created: March 12, 2018
+Last Modified: March 12, 2018
+License: MIT
+*/
+extern crate flate2;
+
+use std::collections::HashMap;
+use std::io::prelude::*;
+use std::io::{Error, ErrorKind};
+use io_utils::Endianness;
+use self::flate2::Compression;
+use self::flate2::read::ZlibDecoder;
+use self::flate2::write::ZlibEncoder;
+
+const LZW_CLEAR_CODE: usize = 256;
+const LZW_EOI_CODE: usize = 257;
+const LZW_FIRST_CODE: usize = 258;
+const LZW_MIN_BITS: usize = 9;
+const LZW_MAX_BITS: usize = 12;
+const LZW_TABLE_SIZE: usize = 1 << LZW_MAX_BITS;
+
+/// Decodes a TIFF LZW-compressed (compression code 5) strip or tile.
+///
+/// TIFF LZW codes are packed MSB-first and use the 'early change' convention,
+/// i.e. the code width increases one code before the table is full.
+pub fn lzw_decoder(input_data: &[u8], expected_size: usize) -> Result<Vec<u8>, Error> {
+    if input_data.len() >= 2 && input_data[0] == 0 && (input_data[1] & 0x01) == 1 {
+        return Err(Error::new(ErrorKind::InvalidData,
+                              "Old-style (pre TIFF 6.0) LZW compression is not supported."));
+    }
+
+    let mut output_data: Vec<u8> = Vec::with_capacity(expected_size);
+
+    // Each table entry is stored as a link to its prefix entry plus a final byte.
+    let mut prefix = vec![0u16; LZW_TABLE_SIZE];
+    let mut suffix = vec![0u8; LZW_TABLE_SIZE];
+    let mut length = vec![0u16; LZW_TABLE_SIZE];
+    for i in 0..256 {
+        suffix[i] = i as u8;
+        length[i] = 1;
+    }
+    let mut next_code = LZW_FIRST_CODE;
+    let mut code_len = LZW_MIN_BITS;
+    let mut old_code: Option<usize> = None;
+
+    let mut bit_buffer = 0u32;
+    let mut bits_in_buffer = 0usize;
+    let mut i = 0usize;
+    loop {
+        while bits_in_buffer < code_len && i < input_data.len() {
+            bit_buffer = (bit_buffer << 8) | input_data[i] as u32;
+            bits_in_buffer += 8;
+            i += 1;
+        }
+        if bits_in_buffer < code_len {
+            // Some encoders omit the EOI code at the end of the strip.
+            break;
+        }
+        let code = ((bit_buffer >> (bits_in_buffer - code_len)) & ((1u32 << code_len) - 1)) as usize;
+        bits_in_buffer -= code_len;
+
+        if code == LZW_EOI_CODE {
+            break;
+        }
+        if code == LZW_CLEAR_CODE {
+            next_code = LZW_FIRST_CODE;
+            code_len = LZW_MIN_BITS;
+            old_code = None;
+            continue;
+        }
+
+        match old_code {
+            None => {
+                if code > 255 {
+                    return Err(Error::new(ErrorKind::InvalidData, "Corrupt LZW data."));
+                }
+                output_data.push(code as u8);
+            }
+            Some(old) => {
+                let first_byte: u8;
+                if code < next_code {
+                    let start = output_data.len();
+                    lzw_emit(code, &prefix, &suffix, &length, &mut output_data);
+                    first_byte = output_data[start];
+                } else if code == next_code {
+                    // The KwKwK case; the code isn't in the table yet.
+                    let start = output_data.len();
+                    lzw_emit(old, &prefix, &suffix, &length, &mut output_data);
+                    first_byte = output_data[start];
+                    output_data.push(first_byte);
+                } else {
+                    return Err(Error::new(ErrorKind::InvalidData, "Corrupt LZW data."));
+                }
+                if next_code < LZW_TABLE_SIZE {
+                    prefix[next_code] = old as u16;
+                    suffix[next_code] = first_byte;
+                    length[next_code] = length[old] + 1;
+                    next_code += 1;
+                }
+            }
+        }
+        old_code = Some(code);
+
+        if next_code + 1 >= (1 << code_len) && code_len < LZW_MAX_BITS {
+            code_len += 1;
+        }
+    }
+
+    Ok(output_data)
+}
+
+// Appends the string associated with an LZW table entry to the output.
+#[inline]
+fn lzw_emit(code: usize, prefix: &[u16], suffix: &[u8], length: &[u16], output_data: &mut Vec<u8>) {
+    let len = length[code] as usize;
+    let start = output_data.len();
+    output_data.resize(start + len, 0u8);
+    let mut c = code;
+    for j in (0..len).rev() {
+        output_data[start + j] = suffix[c];
+        c = prefix[c] as usize;
+    }
+}
+
+/// Encodes a strip or tile using TIFF LZW compression (compression code 5).
+pub fn lzw_encoder(input_data: &[u8]) -> Vec<u8> {
+    let mut writer = LzwBitWriter::new(input_data.len() / 2);
+    writer.put(LZW_CLEAR_CODE);
+
+    // Maps a (prefix code, next byte) pair onto its table entry.
+    let mut table: HashMap<u32, u16> = HashMap::with_capacity(LZW_TABLE_SIZE);
+    let mut next_code = LZW_FIRST_CODE;
+    let mut current: Option<usize> = None;
+    for &byte in input_data {
+        let prefix = match current {
+            Some(code) => code,
+            None => {
+                current = Some(byte as usize);
+                continue;
+            }
+        };
+        let key = ((prefix as u32) << 8) | byte as u32;
+        match table.get(&key) {
+            Some(&code) => {
+                current = Some(code as usize);
+                continue;
+            }
+            None => {}
+        }
+        writer.put(prefix);
+        table.insert(key, next_code as u16);
+        next_code += 1;
+        lzw_update_table(&mut writer, &mut table, &mut next_code);
+        current = Some(byte as usize);
+    }
+    if let Some(code) = current {
+        writer.put(code);
+        next_code += 1;
+        lzw_update_table(&mut writer, &mut table, &mut next_code);
+    }
+    writer.put(LZW_EOI_CODE);
+    writer.finish()
+}
+
+// Widens the code length as the table grows and resets the table before it overflows.
+// The encoder widens one code later than the decoder, which lags one code behind it.
+#[inline]
+fn lzw_update_table(writer: &mut LzwBitWriter, table: &mut HashMap<u32, u16>, next_code: &mut usize) {
+    if *next_code == LZW_TABLE_SIZE - 2 {
+        writer.put(LZW_CLEAR_CODE);
+        writer.code_len = LZW_MIN_BITS;
+        table.clear();
+        *next_code = LZW_FIRST_CODE;
+    } else if *next_code > (1 << writer.code_len) - 1 {
+        writer.code_len += 1;
+    }
+}
+
+struct LzwBitWriter {
+    output_data: Vec<u8>,
+    bit_buffer: u32,
+    bits_in_buffer: usize,
+    code_len: usize,
+}
+
+impl LzwBitWriter {
+    fn new(capacity: usize) -> LzwBitWriter {
+        LzwBitWriter {
+            output_data: Vec::with_capacity(capacity),
+            bit_buffer: 0u32,
+            bits_in_buffer: 0usize,
+            code_len: LZW_MIN_BITS,
+        }
+    }
+
+    #[inline]
+    fn put(&mut self, code: usize) {
+        self.bit_buffer = (self.bit_buffer << self.code_len) | code as u32;
+        self.bits_in_buffer += self.code_len;
+        while self.bits_in_buffer >= 8 {
+            self.bits_in_buffer -= 8;
+            self.output_data.push((self.bit_buffer >> self.bits_in_buffer) as u8);
+        }
+        self.bit_buffer &= (1u32 << self.bits_in_buffer) - 1;
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if self.bits_in_buffer > 0 {
+            self.output_data.push((self.bit_buffer << (8 - self.bits_in_buffer)) as u8);
+        }
+        self.output_data
+    }
+}
+
+/// Decodes a Deflate-compressed (compression codes 8 and 32946) strip or tile.
+/// Both codes store the data as a zlib stream.
+pub fn deflate_decoder(input_data: &[u8], expected_size: usize) -> Result<Vec<u8>, Error> {
+    let mut output_data: Vec<u8> = Vec::with_capacity(expected_size);
+    let mut decoder = ZlibDecoder::new(input_data);
+    decoder.read_to_end(&mut output_data)?;
+    Ok(output_data)
+}
+
+/// Encodes a strip or tile using Deflate compression (compression code 8).
+pub fn deflate_encoder(input_data: &[u8]) -> Result<Vec<u8>, Error> {
+    let mut encoder = ZlibEncoder::new(Vec::with_capacity(input_data.len() / 2), Compression::default());
+    encoder.write_all(input_data)?;
+    encoder.finish()
+}
+
+/// Encodes a single row of data using PackBits compression (compression code 32773).
+/// The TIFF specification requires that each row is packed separately.
+pub fn packbits_encoder(input_data: &[u8], output_data: &mut Vec<u8>) {
+    let n = input_data.len();
+    let mut i = 0usize;
+    while i < n {
+        let mut run = 1usize;
+        while i + run < n && run < 128 && input_data[i + run] == input_data[i] {
+            run += 1;
+        }
+        if run >= 3 {
+            // a replicate run
+            output_data.push((257 - run) as u8);
+            output_data.push(input_data[i]);
+            i += run;
+        } else {
+            // a literal run, which continues until the next replicate run begins
+            let start = i;
+            while i < n && i - start < 128 {
+                if i + 2 < n && input_data[i] == input_data[i + 1] && input_data[i] == input_data[i + 2] {
+                    break;
+                }
+                i += 1;
+            }
+            output_data.push((i - start - 1) as u8);
+            output_data.extend_from_slice(&input_data[start..i]);
+        }
+    }
+}
+
+/// Applies the horizontal differencing predictor (Predictor = 2) in place.
+/// The arguments are the same as for `undo_horizontal_predictor`.
+pub fn apply_horizontal_predictor(buf: &mut [u8], row_width: usize, samples_per_pixel: usize, bytes_per_sample: usize, endian: Endianness) -> Result<(), Error> {
+    let row_samples = row_width * samples_per_pixel;
+    let row_bytes = row_samples * bytes_per_sample;
+    if row_bytes == 0 {
+        return Ok(());
+    }
+    let num_rows = buf.len() / row_bytes;
+    for row in 0..num_rows {
+        let offset = row * row_bytes;
+        match bytes_per_sample {
+            1 => {
+                for i in (samples_per_pixel..row_samples).rev() {
+                    buf[offset + i] = buf[offset + i].wrapping_sub(buf[offset + i - samples_per_pixel]);
+                }
+            }
+            2 | 4 | 8 => {
+                for i in (samples_per_pixel..row_samples).rev() {
+                    let a = offset + (i - samples_per_pixel) * bytes_per_sample;
+                    let b = offset + i * bytes_per_sample;
+                    let value = read_uint(&buf[b..b + bytes_per_sample], endian)
+                        .wrapping_sub(read_uint(&buf[a..a + bytes_per_sample], endian));
+                    write_uint(&mut buf[b..b + bytes_per_sample], value, endian);
+                }
+            }
+            _ => {
+                return Err(Error::new(ErrorKind::InvalidData,
+                                      "Unsupported sample size for the horizontal differencing predictor."));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Applies the floating-point predictor (Predictor = 3) in place.
+/// The arguments are the same as for `undo_floating_point_predictor`.
+pub fn apply_floating_point_predictor(buf: &mut [u8], row_width: usize, samples_per_pixel: usize, bytes_per_sample: usize, endian: Endianness) -> Result<(), Error> {
+    if bytes_per_sample != 2 && bytes_per_sample != 4 && bytes_per_sample != 8 {
+        return Err(Error::new(ErrorKind::InvalidData,
+                              "Unsupported sample size for the floating-point predictor."));
+    }
+    let row_samples = row_width * samples_per_pixel;
+    let row_bytes = row_samples * bytes_per_sample;
+    if row_bytes == 0 {
+        return Ok(());
+    }
+    let num_rows = buf.len() / row_bytes;
+    let mut tmp = vec![0u8; row_bytes];
+    for row in 0..num_rows {
+        let offset = row * row_bytes;
+        tmp.copy_from_slice(&buf[offset..offset + row_bytes]);
+        for s in 0..row_samples {
+            for b in 0..bytes_per_sample {
+                buf[offset + b * row_samples + s] = match endian {
+                    Endianness::BigEndian => tmp[s * bytes_per_sample + b],
+                    Endianness::LittleEndian => tmp[s * bytes_per_sample + bytes_per_sample - b - 1],
+                };
+            }
+        }
+        for i in (samples_per_pixel..row_bytes).rev() {
+            buf[offset + i] = buf[offset + i].wrapping_sub(buf[offset + i - samples_per_pixel]);
+        }
+    }
+    Ok(())
+}
+
+/// Reverses the horizontal differencing predictor (Predictor = 2) in place.
+///
+/// * `buf`: The decompressed block data.
+/// * `row_width`: The number of pixels in each row of the block.
+/// * `samples_per_pixel`: The number of samples in each pixel.
+/// * `bytes_per_sample`: The size of each sample, in bytes.
+/// * `endian`: The byte order of the TIFF file.
+pub fn undo_horizontal_predictor(buf: &mut [u8], row_width: usize, samples_per_pixel: usize, bytes_per_sample: usize, endian: Endianness) -> Result<(), Error> {
+    let row_samples = row_width * samples_per_pixel;
+    let row_bytes = row_samples * bytes_per_sample;
+    if row_bytes == 0 {
+        return Ok(());
+    }
+    let num_rows = buf.len() / row_bytes;
+    for row in 0..num_rows {
+        let offset = row * row_bytes;
+        match bytes_per_sample {
+            1 => {
+                for i in samples_per_pixel..row_samples {
+                    buf[offset + i] = buf[offset + i].wrapping_add(buf[offset + i - samples_per_pixel]);
+                }
+            }
+            2 | 4 | 8 => {
+                for i in samples_per_pixel..row_samples {
+                    let a = offset + (i - samples_per_pixel) * bytes_per_sample;
+                    let b = offset + i * bytes_per_sample;
+                    let value = read_uint(&buf[b..b + bytes_per_sample], endian)
+                        .wrapping_add(read_uint(&buf[a..a + bytes_per_sample], endian));
+                    write_uint(&mut buf[b..b + bytes_per_sample], value, endian);
+                }
+            }
+            _ => {
+                return Err(Error::new(ErrorKind::InvalidData,
+                                      "Unsupported sample size for the horizontal differencing predictor."));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Reverses the floating-point predictor (Predictor = 3) in place.
+///
+/// The floating-point predictor splits each row into byte planes, ordered from the
+/// most to the least significant byte, and then applies byte-wise horizontal
+/// differencing to the entire row. The restored samples are written back in the
+/// byte order of the TIFF file.
+pub fn undo_floating_point_predictor(buf: &mut [u8], row_width: usize, samples_per_pixel: usize, bytes_per_sample: usize, endian: Endianness) -> Result<(), Error> {
+    if bytes_per_sample != 2 && bytes_per_sample != 4 && bytes_per_sample != 8 {
+        return Err(Error::new(ErrorKind::InvalidData,
+                              "Unsupported sample size for the floating-point predictor."));
+    }
+    let row_samples = row_width * samples_per_pixel;
+    let row_bytes = row_samples * bytes_per_sample;
+    if row_bytes == 0 {
+        return Ok(());
+    }
+    let num_rows = buf.len() / row_bytes;
+    let mut tmp = vec![0u8; row_bytes];
+    for row in 0..num_rows {
+        let offset = row * row_bytes;
+        for i in samples_per_pixel..row_bytes {
+            buf[offset + i] = buf[offset + i].wrapping_add(buf[offset + i - samples_per_pixel]);
+        }
+        tmp.copy_from_slice(&buf[offset..offset + row_bytes]);
+        for s in 0..row_samples {
+            for b in 0..bytes_per_sample {
+                let byte = tmp[b * row_samples + s];
+                match endian {
+                    Endianness::BigEndian => buf[offset + s * bytes_per_sample + b] = byte,
+                    Endianness::LittleEndian => buf[offset + s * bytes_per_sample + bytes_per_sample - b - 1] = byte,
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+#[inline]
+fn read_uint(bytes: &[u8], endian: Endianness) -> u64 {
+    let mut value = 0u64;
+    match endian {
+        Endianness::BigEndian => {
+            for b in bytes.iter() {
+                value = (value << 8) | *b as u64;
+            }
+        }
+        Endianness::LittleEndian => {
+            for b in bytes.iter().rev() {
+                value = (value << 8) | *b as u64;
+            }
+        }
+    }
+    value
+}
+
+#[inline]
+fn write_uint(bytes: &mut [u8], value: u64, endian: Endianness) {
+    let n = bytes.len();
+    for j in 0..n {
+        let byte = ((value >> (8 * j)) & 0xFF) as u8;
+        match endian {
+            Endianness::BigEndian => bytes[n - j - 1] = byte,
+            Endianness::LittleEndian => bytes[j] = byte,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use raster::geotiff::packbits_decoder;
+
+    // repetitive data with some noise, so that every codec has runs and literals to encode
+    fn sample_data(n: usize) -> Vec<u8> {
+        let mut seed = 12345u32;
+        (0..n).map(|i| {
+            seed = seed.wrapping_mul(1103515245).wrapping_add(12345);
+            if (i / 50) % 3 == 0 { (i / 50) as u8 } else { (seed >> 16) as u8 % 8 }
+        }).collect()
+    }
+
+    #[test]
+    fn lzw_round_trip() {
+        // long enough for the code table to fill and be cleared several times
+        for &n in [0usize, 1, 2, 257, 5000, 200000].iter() {
+            let data = sample_data(n);
+            let encoded = lzw_encoder(&data);
+            assert_eq!(lzw_decoder(&encoded, n).unwrap(), data, "n = {}", n);
+        }
+    }
+
+    #[test]
+    fn deflate_round_trip() {
+        let data = sample_data(100000);
+        let encoded = deflate_encoder(&data).unwrap();
+        assert!(encoded.len() < data.len());
+        assert_eq!(deflate_decoder(&encoded, data.len()).unwrap(), data);
+    }
+
+    #[test]
+    fn packbits_round_trip() {
+        let mut data = sample_data(3000);
+        data.extend(vec![7u8; 300]); // a run longer than the longest replicate run
+        let mut encoded = vec![];
+        packbits_encoder(&data, &mut encoded);
+        assert_eq!(packbits_decoder(encoded), data);
+    }
+
+    #[test]
+    fn horizontal_predictor_round_trip() {
+        for &bytes_per_sample in [1usize, 2, 4, 8].iter() {
+            for &endian in [Endianness::LittleEndian, Endianness::BigEndian].iter() {
+                let (row_width, samples_per_pixel) = (13, 3);
+                let data = sample_data(row_width * samples_per_pixel * bytes_per_sample * 4);
+                let mut buf = data.clone();
+                apply_horizontal_predictor(&mut buf, row_width, samples_per_pixel, bytes_per_sample, endian).unwrap();
+                undo_horizontal_predictor(&mut buf, row_width, samples_per_pixel, bytes_per_sample, endian).unwrap();
+                assert_eq!(buf, data, "{} bytes per sample", bytes_per_sample);
+            }
+        }
+    }
+
+    #[test]
+    fn floating_point_predictor_round_trip() {
+        for &bytes_per_sample in [2usize, 4, 8].iter() {
+            for &endian in [Endianness::LittleEndian, Endianness::BigEndian].iter() {
+                let (row_width, samples_per_pixel) = (17, 2);
+                let data = sample_data(row_width * samples_per_pixel * bytes_per_sample * 3);
+                let mut buf = data.clone();
+                apply_floating_point_predictor(&mut buf, row_width, samples_per_pixel, bytes_per_sample, endian).unwrap();
+                undo_floating_point_predictor(&mut buf, row_width, samples_per_pixel, bytes_per_sample, endian).unwrap();
+                assert_eq!(buf, data, "{} bytes per sample", bytes_per_sample);
+            }
+        }
+        let mut buf = vec![0u8; 12];
+        assert!(undo_floating_point_predictor(&mut buf, 4, 1, 3, Endianness::LittleEndian).is_err());
+    }
+}
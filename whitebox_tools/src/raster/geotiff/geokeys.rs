@@ -57,6 +57,62 @@ impl GeoKeys {
             .to_owned();
     }
 
+    /// Returns true if no GeoKey directory has been added.
+    pub fn is_empty(&self) -> bool {
+        self.geo_key_directory.len() < 4
+    }
+
+    // the location, count and value offset of a key in the directory
+    fn find_key(&self, key: u16) -> Option<(u16, u16, u16)> {
+        if self.is_empty() {
+            return None;
+        }
+        let number_of_keys = self.geo_key_directory[3] as usize;
+        for i in 0..number_of_keys {
+            let offset = 4 * (i + 1);
+            if offset + 3 >= self.geo_key_directory.len() {
+                break;
+            }
+            if self.geo_key_directory[offset] == key {
+                return Some((self.geo_key_directory[offset + 1],
+                             self.geo_key_directory[offset + 2],
+                             self.geo_key_directory[offset + 3]));
+            }
+        }
+        None
+    }
+
+    /// Returns the value of a key of SHORT type, which is stored in the directory itself.
+    pub fn get_short(&self, key: u16) -> Option<u16> {
+        match self.find_key(key) {
+            Some((0, _, value)) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// Returns the first value of a key of DOUBLE type.
+    pub fn get_double(&self, key: u16) -> Option<f64> {
+        match self.find_key(key) {
+            Some((34736, count, offset)) if count > 0 => self.geo_double_params.get(offset as usize).cloned(),
+            _ => None,
+        }
+    }
+
+    /// Returns the value of a key of ASCII type, without its '|' terminator.
+    pub fn get_ascii(&self, key: u16) -> Option<String> {
+        match self.find_key(key) {
+            Some((34737, count, offset)) => {
+                let start = offset as usize;
+                let end = start + count as usize;
+                if end > self.geo_ascii_params.len() || !self.geo_ascii_params.is_char_boundary(start) || !self.geo_ascii_params.is_char_boundary(end) {
+                    return None;
+                }
+                Some(self.geo_ascii_params[start..end].trim_right_matches('|').trim_right_matches('\0').to_string())
+            },
+            _ => None,
+        }
+    }
+
     pub fn get_ifd_map(&self, byte_order: Endianness) -> HashMap<u16, IfdDirectory> {
         if self.geo_key_directory.len() == 0 {
             panic!("Error reading geokeys");
@@ -106,8 +162,8 @@ impl GeoKeys {
 
             let ifd = IfdDirectory::new(key_id,
                                         field_type,
-                                        count as u32,
-                                        value_offset as u32,
+                                        count as u64,
+                                        value_offset as u64,
                                         data,
                                         byte_order);
             ifd_map.insert(key_id, ifd.clone());
@@ -2524,7 +2580,11 @@ pub fn get_field_type_map() -> HashMap<u16, &'static str> {
         9u16 => "DT_Slong",
         10u16 => "DT_Srational",
         11u16 => "DT_Float",
-        12u16 => "DT_Double"
+        12u16 => "DT_Double",
+        13u16 => "DT_IFD",
+        16u16 => "DT_Long8",
+        17u16 => "DT_Slong8",
+        18u16 => "DT_IFD8"
     ]
 }
 
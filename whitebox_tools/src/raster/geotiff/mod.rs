@@ -1,4 +1,5 @@
 #![allow(unused_assignments, dead_code)]
+pub mod compression;
 pub mod geokeys;
 pub mod tiff_consts;
 
@@ -7,110 +8,46 @@ use std::io::Error;
 use std::io::ErrorKind;
 use std::fmt;
 use std::default::Default;
-use std::cmp::min;
+use std::cmp::{max, min};
 // use std::cmp::Ordering;
-use std::io::BufWriter;
+use std::io::{BufWriter, SeekFrom};
 use std::io::prelude::*;
 use std::f64;
+use std::u32;
 use std::fs::File;
 use std::fs;
 use raster::*;
+use raster::geotiff::compression::*;
 use raster::geotiff::geokeys::*;
 use raster::geotiff::tiff_consts::*;
+use spatial_ref_system::{GeoKeyValue, SpatialRef};
 use io_utils::{ByteOrderReader, Endianness};
-use byteorder::{BigEndian, LittleEndian, WriteBytesExt};
+use byteorder::{BigEndian, ByteOrder, LittleEndian, WriteBytesExt};
 
 pub fn print_tags<'a>(file_name: &'a String) -> Result<(), Error> {
     let mut f = File::open(file_name.clone())?;
     let metadata = fs::metadata(file_name.clone())?;
     let file_size: usize = metadata.len() as usize;
-    let mut buffer = vec![0; file_size];
 
-    // read the file's bytes into a buffer
-    f.read(&mut buffer)?;
-
-    //let byte_order = LittleEndian::read_u16(&buffer[0..2]);
-    let endian = match &buffer[0..2] { 
-        b"II" => Endianness::LittleEndian,
-        b"MM" => Endianness::BigEndian,
-        _ => return Err(Error::new(ErrorKind::InvalidData, "Incorrect TIFF header.")),
-    };
-
-    let mut th = ByteOrderReader::new(buffer, endian);
-    th.seek(2);
-
-    match th.read_u16() {
-        42 => (), // do nothing
-        43 => {
-            return Err(Error::new(ErrorKind::InvalidData,
-                                  "The BigTiff format is not currently supported."))
-        }
-        _ => return Err(Error::new(ErrorKind::InvalidData, "Incorrect TIFF header.")),
-    }
-
-    let mut ifd_offset = th.read_u32() as usize;
+    let (endian, is_big_tiff, mut ifd_offset) = read_tiff_header(&mut f)?;
 
     let mut ifd_map = HashMap::new();
 
     let mut geokeys: GeoKeys = Default::default();
-    let mut cur_pos: usize;
 
     println!("TIFF Tags:");
 
     while ifd_offset > 0 {
-        th.seek(ifd_offset);
-        let num_directories = th.read_u16();
-        
-        for _ in 0..num_directories {
-            let tag_id = th.read_u16();
-            let field_type = th.read_u16();
-
-            let num_values = th.read_u32();
-            let value_offset = th.read_u32();
-            let data_size = match field_type {
-                1u16 | 2u16 | 6u16 | 7u16 => 1,
-                3u16 | 8u16 => 2,
-                4u16 | 9u16 | 11u16 => 4,
-                5u16 | 10u16 | 12u16 => 8,
-                _ => return Err(Error::new(ErrorKind::InvalidInput, "Error reading the IFDs.")),
-            };
-
-            // read the tag data
-            let mut data: Vec<u8> = vec![];
-            if (data_size * num_values) > 4 {
-                // the values are stored at the offset location
-                cur_pos = th.pos;
-                th.seek(value_offset as usize);
-                for _ in 0..num_values * data_size {
-                    data.push(th.read_u8());
-                }
-                th.seek(cur_pos);
-            } else {
-                // the value(s) are contained in the offset
-                cur_pos = th.pos;
-                th.seek(cur_pos - 4);
-                for _ in 0..num_values * data_size {
-                    data.push(th.read_u8());
-                }
-                th.seek(cur_pos);
-            }
-
-            let ifd = IfdDirectory::new(tag_id,
-                                        field_type,
-                                        num_values,
-                                        value_offset,
-                                        data,
-                                        endian);
-            ifd_map.insert(tag_id, ifd.clone());
-
+        let (ifds, next_ifd_offset) = read_ifd(&mut f, file_size, endian, ifd_offset, is_big_tiff)?;
+        for ifd in ifds {
             println!("{}", ifd);
-
+            ifd_map.insert(ifd.tag, ifd);
         }
-        ifd_offset = th.read_u32() as usize;
+        ifd_offset = next_ifd_offset;
     }
 
     match ifd_map.get(&34735) {
-        Some(ifd) => geokeys.add_key_directory(&ifd.data),
+        Some(ifd) => geokeys.add_key_directory(&ifd.little_endian_data()),
         _ => {
             return Err(Error::new(ErrorKind::InvalidData,
                                   "The TIFF file does not contain geokeys"))
@@ -118,7 +55,7 @@ pub fn print_tags<'a>(file_name: &'a String) -> Result<(), Error> {
     };
 
     match ifd_map.get(&34736) {
-        Some(ifd) => geokeys.add_double_params(&ifd.data),
+        Some(ifd) => geokeys.add_double_params(&ifd.little_endian_data()),
         _ => {}
     };
 
@@ -145,91 +82,54 @@ pub fn print_tags<'a>(file_name: &'a String) -> Result<(), Error> {
     Ok(())
 }
 
+/// Reads the GeoKeys of the first image of a GeoTIFF file, without reading the image data.
+pub fn read_geotiff_geokeys(file_name: &str) -> Result<GeoKeys, Error> {
+    let mut f = File::open(file_name)?;
+    let file_size = fs::metadata(file_name)?.len() as usize;
+    let (endian, is_big_tiff, ifd_offset) = read_tiff_header(&mut f)?;
+    let (ifds, _) = read_ifd(&mut f, file_size, endian, ifd_offset, is_big_tiff)?;
+    let mut geokeys: GeoKeys = Default::default();
+    for ifd in ifds {
+        match ifd.tag {
+            34735 => geokeys.add_key_directory(&ifd.little_endian_data()),
+            34736 => geokeys.add_double_params(&ifd.little_endian_data()),
+            34737 => geokeys.add_ascii_params(&ifd.data),
+            _ => {}
+        }
+    }
+    Ok(geokeys)
+}
+
 pub fn read_geotiff<'a>(file_name: &'a String,
                         configs: &'a mut RasterConfigs,
-                        data: &'a mut Vec<f64>)
+                        data: &'a mut RasterData)
                         -> Result<(), Error> {
+    // Only the header and IFDs are read up front. The image data are read one strip or tile
+    // at a time, which allows the blocks of large images to be read on demand instead.
     let mut f = File::open(file_name.clone())?;
 
     let metadata = fs::metadata(file_name.clone())?;
     let file_size: usize = metadata.len() as usize;
-    let mut buffer = vec![0; file_size];
-
-    // read the file's bytes into a buffer
-    f.read(&mut buffer)?;
 
-    //let byte_order = LittleEndian::read_u16(&buffer[0..2]);
-    match &buffer[0..2] { //byte_order { //LittleEndian::read_u16(&buffer[0..2]) {
-        b"II" => configs.endian = Endianness::LittleEndian,
-        b"MM" => configs.endian = Endianness::BigEndian,
-        _ => return Err(Error::new(ErrorKind::InvalidData, "Incorrect TIFF header.")),
-    }
-
-    let mut th = ByteOrderReader::new(buffer, configs.endian);
-    th.seek(2);
-
-    match th.read_u16() {
-        42 => (), // do nothing
-        43 => {
-            return Err(Error::new(ErrorKind::InvalidData,
-                                  "The BigTiff format is not currently supported."))
-        }
-        _ => return Err(Error::new(ErrorKind::InvalidData, "Incorrect TIFF header.")),
-    }
-
-    let mut ifd_offset = th.read_u32() as usize;
+    let (endian, is_big_tiff, mut ifd_offset) = read_tiff_header(&mut f)?;
+    configs.endian = endian;
 
     let mut ifd_map = HashMap::new();
 
     let mut geokeys: GeoKeys = Default::default();
-    let mut cur_pos: usize;
     while ifd_offset > 0 {
-        th.seek(ifd_offset);
-        let num_directories = th.read_u16();
-        
-        for _ in 0..num_directories {
-            let tag_id = th.read_u16();
-            let field_type = th.read_u16();
-
-            let num_values = th.read_u32();
-            let value_offset = th.read_u32();
-            let data_size = match field_type {
-                1u16 | 2u16 | 6u16 | 7u16 => 1,
-                3u16 | 8u16 => 2,
-                4u16 | 9u16 | 11u16 => 4,
-                5u16 | 10u16 | 12u16 => 8,
-                _ => return Err(Error::new(ErrorKind::InvalidInput, "Error reading the IFDs.")),
-            };
-
-            // read the tag data
-            let mut data: Vec<u8> = vec![];
-            if (data_size * num_values) > 4 {
-                // the values are stored at the offset location
-                cur_pos = th.pos;
-                th.seek(value_offset as usize);
-                for _ in 0..num_values * data_size {
-                    data.push(th.read_u8());
-                }
-                th.seek(cur_pos);
-            } else {
-                // the value(s) are contained in the offset
-                cur_pos = th.pos;
-                th.seek(cur_pos - 4);
-                for _ in 0..num_values * data_size {
-                    data.push(th.read_u8());
-                }
-                th.seek(cur_pos);
+        let (ifds, next_ifd_offset) = read_ifd(&mut f, file_size, configs.endian, ifd_offset, is_big_tiff)?;
+        // Files may contain more than one image, e.g. the overviews of a cloud-optimized
+        // GeoTIFF. Read the first image that is neither a reduced-resolution version of
+        // another image nor a transparency mask.
+        let is_subimage = ifds.iter().any(|ifd| ifd.tag == TAG_NEWSUBFILETYPE && ifd.interpret_as_u64().iter().any(|v| v & 5 != 0));
+        if !is_subimage {
+            for ifd in ifds {
+                ifd_map.insert(ifd.tag, ifd);
             }
-
-            let ifd = IfdDirectory::new(tag_id,
-                                        field_type,
-                                        num_values,
-                                        value_offset,
-                                        data,
-                                        configs.endian);
-            ifd_map.insert(tag_id, ifd.clone());
+            break;
         }
-        ifd_offset = th.read_u32() as usize;
+        ifd_offset = next_ifd_offset;
     }
 
     configs.columns = match ifd_map.get(&256) {
@@ -262,12 +162,6 @@ pub fn read_geotiff<'a>(file_name: &'a String,
         }
     };
 
-    //data = vec![0.0f64; configs.rows * configs.columns];
-    data.clear();
-    for _ in 0..configs.rows * configs.columns {
-        data.push(0.0f64);
-    }
-
     let bits_per_sample = match ifd_map.get(&258) {
         Some(ifd) => ifd.interpret_as_u16(),
         _ => {
@@ -302,13 +196,19 @@ pub fn read_geotiff<'a>(file_name: &'a String,
         _ => 0,
     };
 
+    // SampleFormat defaults to unsigned integer data.
     let sample_format = match ifd_map.get(&339) {
         Some(ifd) => ifd.interpret_as_u16(),
-        _ => [0].to_vec(),
+        _ => [1].to_vec(),
+    };
+
+    let predictor = match ifd_map.get(&317) {
+        Some(ifd) => ifd.interpret_as_u16()[0],
+        _ => PREDICTOR_NONE,
     };
 
     match ifd_map.get(&34735) {
-        Some(ifd) => geokeys.add_key_directory(&ifd.data),
+        Some(ifd) => geokeys.add_key_directory(&ifd.little_endian_data()),
         _ => {
             return Err(Error::new(ErrorKind::InvalidData,
                                   "The TIFF file does not contain geokeys"))
@@ -316,7 +216,7 @@ pub fn read_geotiff<'a>(file_name: &'a String,
     };
 
     match ifd_map.get(&34736) {
-        Some(ifd) => geokeys.add_double_params(&ifd.data),
+        Some(ifd) => geokeys.add_double_params(&ifd.little_endian_data()),
         _ => {}
     };
 
@@ -325,7 +225,7 @@ pub fn read_geotiff<'a>(file_name: &'a String,
         _ => {}
     };
 
-    let geokeys_map = geokeys.get_ifd_map(configs.endian);
+    let geokeys_map = geokeys.get_ifd_map(Endianness::LittleEndian);
 
     let model_tiepoints = match ifd_map.get(&33922) {
         Some(ifd) => ifd.interpret_as_f64(),
@@ -346,11 +246,21 @@ pub fn read_geotiff<'a>(file_name: &'a String,
         configs.south = configs.north - configs.resolution_y * configs.rows as f64;
     }
 
-    // Get the EPSG code
-    if geokeys_map.contains_key(&2048) { // geographic coordinate system
-        configs.epsg_code = geokeys_map.get(&2048).unwrap().interpret_as_u16()[0];
-    } else if geokeys_map.contains_key(&3072) { // projected coordinate system
+    // Get the EPSG code. A projected system also records the geographic system it is based
+    // on, so its code takes precedence; user-defined (32767) systems have no code.
+    if geokeys_map.contains_key(&3072) { // projected coordinate system
         configs.epsg_code = geokeys_map.get(&3072).unwrap().interpret_as_u16()[0];
+    } else if geokeys_map.contains_key(&2048) { // geographic coordinate system
+        configs.epsg_code = geokeys_map.get(&2048).unwrap().interpret_as_u16()[0];
+    }
+    if configs.epsg_code == 32767u16 {
+        configs.epsg_code = 0u16;
+    }
+    // a user-defined coordinate reference system is kept as WKT
+    if let Ok(srs) = SpatialRef::from_geokeys(&geokeys) {
+        if SpatialRef::from_epsg(configs.epsg_code).is_err() {
+            configs.coordinate_ref_system_wkt = srs.to_wkt();
+        }
     }
 
     // Determine the image mode.
@@ -361,7 +271,7 @@ pub fn read_geotiff<'a>(file_name: &'a String,
         .unwrap()
         .to_string();
     // let mode: ImageMode;
-    let mode: u16;
+    let mut mode: u16;
     let mut palette = vec![];
     if photomet_str == "RGB" {
         configs.photometric_interp = PhotometricInterpretation::RGB;
@@ -431,6 +341,40 @@ pub fn read_geotiff<'a>(file_name: &'a String,
         return Err(Error::new(ErrorKind::InvalidData, "Unsupported image format."));
     }
 
+    // Images with more than one sample per pixel, other than chunky RGB images, are read as
+    // multi-band rasters, with each band stored one after another. Planar RGB images are
+    // read band-by-band and packed into RGB values once all of the bands have been read.
+    let samples_per_pixel = bits_per_sample.len();
+    let planar_config = match ifd_map.get(&284) {
+        Some(ifd) => ifd.interpret_as_u16()[0],
+        _ => 1u16,
+    };
+    let rgb_mode = mode;
+    if samples_per_pixel > 1 && (mode == IM_GRAY || mode == IM_GRAYINVERT || planar_config == 2) {
+        mode = IM_MULTIBAND;
+    }
+    if mode == IM_MULTIBAND && rgb_mode != IM_RGB && rgb_mode != IM_RGBA && rgb_mode != IM_NRGBA {
+        configs.bands = samples_per_pixel as u8;
+        configs.planar_config = if planar_config == 2 { PlanarConfiguration::Planar } else { PlanarConfiguration::Chunky };
+    }
+    let num_cells = configs.rows * configs.columns;
+
+    // Scalar integer and single-precision images are stored natively rather than as f64 values.
+    let is_scalar = mode == IM_GRAY || mode == IM_GRAYINVERT || (mode == IM_MULTIBAND && rgb_mode != IM_RGB && rgb_mode != IM_RGBA && rgb_mode != IM_NRGBA);
+    let storage_type = if is_scalar {
+        match (sample_format[0], bits_per_sample[0]) {
+            (1, 8) => DataType::U8,
+            (1, 16) => DataType::U16,
+            (2, 8) => DataType::I8,
+            (2, 16) => DataType::I16,
+            (2, 32) => DataType::I32,
+            (3, 32) => DataType::F32,
+            _ => DataType::F64,
+        }
+    } else {
+        DataType::F64
+    };
+
     let width = configs.columns;
     let height = configs.rows;
 
@@ -440,8 +384,8 @@ pub fn read_geotiff<'a>(file_name: &'a String,
     let mut blocks_across = 1;
     let blocks_down; // = 1;
 
-    let block_offsets: Vec<u32>; //  = vec![];
-    let block_counts: Vec<u32>; // = vec![];
+    let block_offsets: Vec<u64>; //  = vec![];
+    let block_counts: Vec<u64>; // = vec![];
 
     if ifd_map.contains_key(&322) {
         block_padding = true;
@@ -480,7 +424,8 @@ pub fn read_geotiff<'a>(file_name: &'a String,
         blocks_down = (height + block_height - 1) / block_height;
 
         block_offsets = match ifd_map.get(&324) {
-            Some(ifd) => ifd.interpret_as_u32(),
+            // The 324 tag can be either u32 or, in BigTIFFs, u64 type
+            Some(ifd) => ifd.interpret_as_u64(),
             _ => {
                 return Err(Error::new(ErrorKind::InvalidData,
                                       "The raster BitsPerSample value was not read correctly"))
@@ -488,19 +433,8 @@ pub fn read_geotiff<'a>(file_name: &'a String,
         };
 
         block_counts = match ifd_map.get(&325) {
-            Some(ifd) => {
-                // The 325 tag can be either u16 or u32 type
-                if ifd.ifd_type == 3 {
-                    let ifd_data = ifd.interpret_as_u16();
-                    let mut ret: Vec<u32> = vec![];
-                    for val in ifd_data.iter() {
-                        ret.push(*val as u32);
-                    }
-                    ret
-                } else {
-                    ifd.interpret_as_u32()
-                }
-            }
+            // The 325 tag can be either u16, u32 or, in BigTIFFs, u64 type
+            Some(ifd) => ifd.interpret_as_u64(),
             _ => {
                 return Err(Error::new(ErrorKind::InvalidData,
                                       "The TileLength value was not read correctly"))
@@ -525,19 +459,8 @@ pub fn read_geotiff<'a>(file_name: &'a String,
         blocks_down = (height + block_height - 1) / block_height;
 
         block_offsets = match ifd_map.get(&273) {
-            Some(ifd) => {
-                // The 273 tag can be either u16 or u32 type
-                if ifd.ifd_type == 3 {
-                    let ifd_data = ifd.interpret_as_u16();
-                    let mut ret: Vec<u32> = vec![];
-                    for val in ifd_data.iter() {
-                        ret.push(*val as u32);
-                    }
-                    ret
-                } else {
-                    ifd.interpret_as_u32()
-                }
-            }
+            // The 273 tag can be either u16, u32 or, in BigTIFFs, u64 type
+            Some(ifd) => ifd.interpret_as_u64(),
             _ => {
                 return Err(Error::new(ErrorKind::InvalidData,
                                       "The raster StripOffsets value was not read correctly"))
@@ -545,19 +468,8 @@ pub fn read_geotiff<'a>(file_name: &'a String,
         };
 
         block_counts = match ifd_map.get(&279) {
-            Some(ifd) => {
-                // The 279 tag can be either u16 or u32 type
-                if ifd.ifd_type == 3 {
-                    let ifd_data = ifd.interpret_as_u16();
-                    let mut ret: Vec<u32> = vec![];
-                    for val in ifd_data.iter() {
-                        ret.push(*val as u32);
-                    }
-                    ret
-                } else {
-                    ifd.interpret_as_u32()
-                }
-            }
+            // The 279 tag can be either u16, u32 or, in BigTIFFs, u64 type
+            Some(ifd) => ifd.interpret_as_u64(),
             _ => {
                 return Err(Error::new(ErrorKind::InvalidData,
                                       "The raster StripByteCounts value was not read correctly"))
@@ -565,380 +477,434 @@ pub fn read_geotiff<'a>(file_name: &'a String,
         };
     }
 
-    for i in 0..blocks_across {
-        let mut blk_w = block_width;
-        if !block_padding && i == blocks_across - 1 && width % block_width != 0 {
-            blk_w = width % block_width;
-        }
-        for j in 0..blocks_down {
-            let mut blk_h = block_height;
-            if !block_padding && j == blocks_down - 1 && height % block_height != 0 {
-                blk_h = height % block_height;
-            }
-            let offset = block_offsets[j * blocks_across + i] as usize;
-            let n = block_counts[j * blocks_across + i] as usize;
-            let mut buf: Vec<u8> = vec![];
-            match compression {
-                COMPRESS_NONE => {
-                    // no compression
-                    buf = th.buffer[offset..(offset + n)].to_vec();
-                }
-                COMPRESS_PACKBITS => {
-                    buf = packbits_decoder(th.buffer[offset..(offset + n)].to_vec());
-                }
-                _ => {}
-            }
-            let mut bor = ByteOrderReader::new(buf, configs.endian);
-
+    let bytes_per_sample = bits_per_sample[0] as usize / 8;
+    let bytes_per_pixel = bits_per_sample.iter().fold(0usize, |sum, b| sum + *b as usize) / 8;
 
-            let xmin = i * block_width;
-            let ymin = j * block_height;
-            let mut xmax = xmin + blk_w;
-            let mut ymax = ymin + blk_h;
+    // With the planar configuration, the blocks of each band are stored separately, one
+    // band after another, and each block contains a single sample per pixel.
+    let (planes, block_samples, block_bytes_per_pixel) = if planar_config == 2 {
+        (samples_per_pixel, 1usize, bytes_per_sample)
+    } else {
+        (1usize, samples_per_pixel, bytes_per_pixel)
+    };
+    if block_offsets.len() < planes * blocks_across * blocks_down || block_counts.len() < planes * blocks_across * blocks_down {
+        return Err(Error::new(ErrorKind::InvalidData, "The TIFF file has too few strips or tiles."));
+    }
+    if predictor != PREDICTOR_NONE && predictor != PREDICTOR_HORIZONTAL && predictor != PREDICTOR_FLOATINGPOINT {
+        return Err(Error::new(ErrorKind::InvalidData,
+                              format!("Unsupported TIFF predictor ({}).", predictor)));
+    }
 
-            xmax = min(xmax, width);
-            ymax = min(ymax, height);
+    let blocks = GeoTiffBlocks {
+        file_name: file_name.clone(),
+        endian: configs.endian,
+        width: width,
+        height: height,
+        block_width: block_width,
+        block_height: block_height,
+        blocks_across: blocks_across,
+        blocks_down: blocks_down,
+        block_padding: block_padding,
+        offsets: block_offsets,
+        counts: block_counts,
+        compression: compression,
+        predictor: predictor,
+        sample_format: sample_format[0],
+        bits_per_sample: bits_per_sample[0],
+        block_samples: block_samples,
+        block_bytes_per_pixel: block_bytes_per_pixel,
+    };
 
-            let mut off = 0;
+    match mode {
+        IM_GRAYINVERT | IM_GRAY | IM_MULTIBAND => { //ImageMode::GrayInvert | ImageMode::Gray => {
+            configs.photometric_interp = PhotometricInterpretation::Continuous;
+            match sample_format[0] {
+                1 => {
+                    // unsigned integer
+                    match bits_per_sample[0] {
+                        8 => {
+                            configs.data_type = DataType::U8;
+                        }
+                        16 => {
+                            configs.data_type = DataType::U16;
+                        }
+                        32 => {
+                            configs.data_type = DataType::U32;
+                        }
+                        64 => {
+                            configs.data_type = DataType::U64;
+                        }
+                        _ => {
+                            return Err(Error::new(ErrorKind::InvalidData,
+                                                  "The raster was not read correctly"))
+                        }
+                    }
+                }
+                2 => {
+                    // signed integer
+                    match bits_per_sample[0] {
+                        8 => {
+                            configs.data_type = DataType::I8;
+                        }
+                        16 => {
+                            configs.data_type = DataType::I16;
+                        }
+                        32 => {
+                            configs.data_type = DataType::I32;
+                        }
+                        64 => {
+                            configs.data_type = DataType::I64;
+                        }
+                        _ => {
+                            return Err(Error::new(ErrorKind::InvalidData,
+                                                  "The raster was not read correctly"))
+                        }
+                    }
+                }
+                3 => {
+                    // floating point
+                    match bits_per_sample[0] {
+                        32 => {
+                            configs.data_type = DataType::F32;
+                        }
+                        64 => {
+                            configs.data_type = DataType::F64;
+                        }
+                        _ => {
+                            return Err(Error::new(ErrorKind::InvalidData,
+                                                  "The raster was not read correctly"))
+                        }
+                    }
+                }
+                _ => {
+                    return Err(Error::new(ErrorKind::InvalidData,
+                                          "The raster was not read correctly"))
+                }
+            }
+        }
+        IM_PALETTED => { //ImageMode::Paletted => {
+            configs.photometric_interp = PhotometricInterpretation::Categorical;
+            configs.data_type = DataType::U8;
+        }
+        IM_RGB => { //ImageMode::RGB => {
+            configs.photometric_interp = PhotometricInterpretation::RGB;
+            if bits_per_sample[0] == 8 {
+                configs.data_type = DataType::U8;
+            } else if bits_per_sample[0] == 16 {
+                configs.data_type = DataType::U16;
+            } else {
+                return Err(Error::new(ErrorKind::InvalidData,
+                                      "The raster was not read correctly"));
+            }
+        }
+        IM_NRGBA | IM_RGBA => { //ImageMode::NRGBA | ImageMode::RGBA => {
+            if bits_per_sample[0] == 8 {
+                configs.data_type = DataType::U32;
+            } else if bits_per_sample[0] == 16 {
+                configs.data_type = DataType::U64;
+            } else {
+                return Err(Error::new(ErrorKind::InvalidData,
+                                      "The raster was not read correctly"));
+            }
+        }
+        _ => {
+            return Err(Error::new(ErrorKind::InvalidData,
+                                  "The raster was not read correctly"))
+        }
+    }
 
-            match mode {
-                IM_GRAYINVERT | IM_GRAY => { //ImageMode::GrayInvert | ImageMode::Gray => {
-                    match sample_format[0] {
-                        1 => {
-                            // unsigned integer
-                            match bits_per_sample[0] {
-                                8 => {
-                                    for y in ymin..ymax {
-                                        for x in xmin..xmax {
-                                            if off <= bor.len() {
-                                                let i = y * width + x;
-                                                data[i] = bor.read_u8() as f64; //buf[off] as f64;
-                                                off += 1;
+    // Images of scalar values that are too large to hold in memory are read on demand, one
+    // block of rows at a time, rather than being read in their entirety here.
+    let num_bands = if mode == IM_MULTIBAND { samples_per_pixel } else { 1 };
+    if is_scalar && exceeds_max_raster_memory(num_cells * num_bands) {
+        *data = RasterData::Cached(BlockCache::new(configs.rows, configs.columns, num_bands, 0f64, BlockSource::GeoTiff(blocks), get_max_raster_memory()));
+        return Ok(());
+    }
+    *data = RasterData::new(storage_type, num_cells * num_bands, 0f64);
+
+    for plane in 0..planes {
+        for i in 0..blocks_across {
+            for j in 0..blocks_down {
+                let buf = blocks.decode_block(&mut f, plane, i, j)?;
+                let (xmin, ymin, xmax, ymax) = blocks.block_extent(i, j);
+
+                let mut bor = ByteOrderReader::new(buf, configs.endian);
+
+                let mut off = 0;
+
+                match mode {
+                    IM_GRAYINVERT | IM_GRAY => { //ImageMode::GrayInvert | ImageMode::Gray => {
+                        match sample_format[0] {
+                            1 => {
+                                // unsigned integer
+                                match bits_per_sample[0] {
+                                    8 => {
+                                        for y in ymin..ymax {
+                                            for x in xmin..xmax {
+                                                if off <= bor.len() {
+                                                    let i = y * width + x;
+                                                    data.set_value(i, bor.read_u8() as f64); //buf[off] as f64;
+                                                    off += 1;
+                                                }
                                             }
                                         }
                                     }
-                                }
-                                16 => {
-                                    for y in ymin..ymax {
-                                        for x in xmin..xmax {
-                                            if off <= bor.len() {
-                                                let value = bor.read_u16(); // g.ByteOrder.Uint16(g.buf[g.off : g.off+2])
-                                                let i = y * width + x;
-                                                data[i] = value as f64;
-                                                off += 2;
+                                    16 => {
+                                        for y in ymin..ymax {
+                                            for x in xmin..xmax {
+                                                if off <= bor.len() {
+                                                    let value = bor.read_u16(); // g.ByteOrder.Uint16(g.buf[g.off : g.off+2])
+                                                    let i = y * width + x;
+                                                    data.set_value(i, value as f64);
+                                                    off += 2;
+                                                }
                                             }
                                         }
                                     }
-                                }
-                                32 => {
-                                    for y in ymin..ymax {
-                                        for x in xmin..xmax {
-                                            if off <= bor.len() {
-                                                let value = bor.read_u32();
-                                                let i = y * width + x;
-                                                data[i] = value as f64;
-                                                off += 4;
+                                    32 => {
+                                        for y in ymin..ymax {
+                                            for x in xmin..xmax {
+                                                if off <= bor.len() {
+                                                    let value = bor.read_u32();
+                                                    let i = y * width + x;
+                                                    data.set_value(i, value as f64);
+                                                    off += 4;
+                                                }
                                             }
                                         }
                                     }
-                                }
-                                64 => {
-                                    for y in ymin..ymax {
-                                        for x in xmin..xmax {
-                                            if off <= bor.len() {
-                                                let value = bor.read_u64();
-                                                let i = y * width + x;
-                                                data[i] = value as f64;
-                                                off += 8;
+                                    64 => {
+                                        for y in ymin..ymax {
+                                            for x in xmin..xmax {
+                                                if off <= bor.len() {
+                                                    let value = bor.read_u64();
+                                                    let i = y * width + x;
+                                                    data.set_value(i, value as f64);
+                                                    off += 8;
+                                                }
                                             }
                                         }
                                     }
-                                }
-                                _ => {
-                                    return Err(Error::new(ErrorKind::InvalidData,
-                                                          "The raster was not read correctly"))
+                                    _ => {
+                                        return Err(Error::new(ErrorKind::InvalidData,
+                                                              "The raster was not read correctly"))
+                                    }
                                 }
                             }
-                        }
-                        2 => {
-                            // signed integer
-                            match bits_per_sample[0] {
-                                8 => {
-                                    for y in ymin..ymax {
-                                        for x in xmin..xmax {
-                                            if off <= bor.len() {
-                                                let i = y * width + x;
-                                                data[i] = bor.read_i8() as f64;
-                                                off += 1;
+                            2 => {
+                                // signed integer
+                                match bits_per_sample[0] {
+                                    8 => {
+                                        for y in ymin..ymax {
+                                            for x in xmin..xmax {
+                                                if off <= bor.len() {
+                                                    let i = y * width + x;
+                                                    data.set_value(i, bor.read_i8() as f64);
+                                                    off += 1;
+                                                }
                                             }
                                         }
                                     }
-                                }
-                                16 => {
-                                    for y in ymin..ymax {
-                                        for x in xmin..xmax {
-                                            if off <= bor.len() {
-                                                let value = bor.read_i16();
-                                                let i = y * width + x;
-                                                data[i] = value as f64;
-                                                off += 2;
+                                    16 => {
+                                        for y in ymin..ymax {
+                                            for x in xmin..xmax {
+                                                if off <= bor.len() {
+                                                    let value = bor.read_i16();
+                                                    let i = y * width + x;
+                                                    data.set_value(i, value as f64);
+                                                    off += 2;
+                                                }
                                             }
                                         }
                                     }
-                                }
-                                32 => {
-                                    for y in ymin..ymax {
-                                        for x in xmin..xmax {
-                                            if off <= bor.len() {
-                                                let value = bor.read_i32();
-                                                let i = y * width + x;
-                                                data[i] = value as f64;
-                                                off += 4;
+                                    32 => {
+                                        for y in ymin..ymax {
+                                            for x in xmin..xmax {
+                                                if off <= bor.len() {
+                                                    let value = bor.read_i32();
+                                                    let i = y * width + x;
+                                                    data.set_value(i, value as f64);
+                                                    off += 4;
+                                                }
                                             }
                                         }
                                     }
-                                }
-                                64 => {
-                                    for y in ymin..ymax {
-                                        for x in xmin..xmax {
-                                            if off <= bor.len() {
-                                                let value = bor.read_i64();
-                                                let i = y * width + x;
-                                                data[i] = value as f64;
-                                                off += 8;
+                                    64 => {
+                                        for y in ymin..ymax {
+                                            for x in xmin..xmax {
+                                                if off <= bor.len() {
+                                                    let value = bor.read_i64();
+                                                    let i = y * width + x;
+                                                    data.set_value(i, value as f64);
+                                                    off += 8;
+                                                }
                                             }
                                         }
                                     }
-                                }
-                                _ => {
-                                    return Err(Error::new(ErrorKind::InvalidData,
-                                                          "The raster was not read correctly"))
+                                    _ => {
+                                        return Err(Error::new(ErrorKind::InvalidData,
+                                                              "The raster was not read correctly"))
+                                    }
                                 }
                             }
-                        }
-                        3 => {
-                            // floating point
-                            match bits_per_sample[0] {
-                                32 => {
-                                    for y in ymin..ymax {
-                                        for x in xmin..xmax {
-                                            let value = bor.read_f32();
-                                            let i = y * width + x;
-                                            data[i] = value as f64;
-                                            off += 4;
+                            3 => {
+                                // floating point
+                                match bits_per_sample[0] {
+                                    32 => {
+                                        for y in ymin..ymax {
+                                            for x in xmin..xmax {
+                                                let value = bor.read_f32();
+                                                let i = y * width + x;
+                                                data.set_value(i, value as f64);
+                                                off += 4;
+                                            }
                                         }
                                     }
-                                }
-                                64 => {
-                                    for y in ymin..ymax {
-                                        for x in xmin..xmax {
-                                            if off <= bor.len() {
-                                                let value = bor.read_f64();
-                                                let i = y * width + x;
-                                                data[i] = value;
-                                                off += 8;
+                                    64 => {
+                                        for y in ymin..ymax {
+                                            for x in xmin..xmax {
+                                                if off <= bor.len() {
+                                                    let value = bor.read_f64();
+                                                    let i = y * width + x;
+                                                    data.set_value(i, value);
+                                                    off += 8;
+                                                }
                                             }
                                         }
                                     }
+                                    _ => {
+                                        return Err(Error::new(ErrorKind::InvalidData,
+                                                              "The raster was not read correctly"))
+                                    }
                                 }
-                                _ => {
-                                    return Err(Error::new(ErrorKind::InvalidData,
-                                                          "The raster was not read correctly"))
-                                }
-                            }
-                        }
-                        _ => {
-                            return Err(Error::new(ErrorKind::InvalidData,
-                                                  "The raster was not read correctly"))
-                        }
-                    }
-                }
-                IM_PALETTED => { //ImageMode::Paletted => {
-                    for y in ymin..ymax {
-                        for x in xmin..xmax {
-                            let i = y * width + x;
-                            let value = bor.read_u8() as usize;
-                            data[i] = palette[value] as f64;
-                        }
-                    }
-                }
-                IM_RGB => { //ImageMode::RGB => {
-                    if bits_per_sample[0] == 8 {
-                        for y in ymin..ymax {
-                            for x in xmin..xmax {
-                                let red = bor.read_u8() as u32; //uint32(g.buf[g.off]);
-                                let green = bor.read_u8() as u32; //uint32(g.buf[g.off+1]);
-                                let blue = bor.read_u8() as u32; //uint32(g.buf[g.off+2]);
-                                let a = 255u32;
-                                let value = (a << 24) | (blue << 16) | (green << 8) | red;
-                                let i = y * width + x;
-                                data[i] = value as f64;
                             }
-                        }
-                    } else if bits_per_sample[0] == 16 {
-                        // the spec doesn't talk about 16-bit RGB images so
-                        // I'm not sure why I bother with this. They specifically
-                        // say that RGB images are 8-bits per channel. Anyhow,
-                        // I rescale the 16-bits to an 8-bit channel for simplicity.
-                        for y in ymin..ymax {
-                            for x in xmin..xmax {
-                                let red = (bor.read_u16() as f64 / 65535f64 * 255f64) as u32;
-                                let green = (bor.read_u16() as f64 / 65535f64 * 255f64) as u32;
-                                let blue = (bor.read_u16() as f64 / 65535f64 * 255f64) as u32;
-                                let a = 255u32;
-                                let value = (a << 24) | (blue << 16) | (green << 8) | red;
-                                let i = y * width + x;
-                                data[i] = value as f64;
+                            _ => {
+                                return Err(Error::new(ErrorKind::InvalidData,
+                                                      "The raster was not read correctly"))
                             }
                         }
-                    } else {
-                        return Err(Error::new(ErrorKind::InvalidData,
-                                              "The raster was not read correctly"));
                     }
-                }
-                IM_NRGBA | IM_RGBA => { //ImageMode::NRGBA | ImageMode::RGBA => {
-                    if bits_per_sample[0] == 8 {
+                    IM_MULTIBAND => {
                         for y in ymin..ymax {
                             for x in xmin..xmax {
-                                let red = bor.read_u8() as u32; //uint32(g.buf[g.off]);
-                                let green = bor.read_u8() as u32; //uint32(g.buf[g.off+1]);
-                                let blue = bor.read_u8() as u32; //uint32(g.buf[g.off+2]);
-                                let a = bor.read_u8() as u32;
-                                let value = (a << 24) | (blue << 16) | (green << 8) | red;
                                 let i = y * width + x;
-                                data[i] = value as f64;
+                                if planar_config == 2 {
+                                    data.set_value(plane * num_cells + i, read_sample(&mut bor, sample_format[0], bits_per_sample[0])?);
+                                } else {
+                                    for band in 0..samples_per_pixel {
+                                        data.set_value(band * num_cells + i, read_sample(&mut bor, sample_format[0], bits_per_sample[0])?);
+                                    }
+                                }
                             }
                         }
-                    } else if bits_per_sample[0] == 16 {
-                        // the spec doesn't talk about 16-bit RGB images so
-                        // I'm not sure why I bother with this. They specifically
-                        // say that RGB images are 8-bits per channel. Anyhow,
-                        // I rescale the 16-bits to an 8-bit channel for simplicity.
+                    }
+                    IM_PALETTED => { //ImageMode::Paletted => {
                         for y in ymin..ymax {
                             for x in xmin..xmax {
-                                let red = (bor.read_u16() as f64 / 65535f64 * 255f64) as u32;
-                                let green = (bor.read_u16() as f64 / 65535f64 * 255f64) as u32;
-                                let blue = (bor.read_u16() as f64 / 65535f64 * 255f64) as u32;
-                                let a = (bor.read_u16() as f64 / 65535f64 * 255f64) as u32;
-                                let value = (a << 24) | (blue << 16) | (green << 8) | red;
                                 let i = y * width + x;
-                                data[i] = value as f64;
+                                let value = bor.read_u8() as usize;
+                                data.set_value(i, palette[value] as f64);
                             }
                         }
-                    } else {
-                        return Err(Error::new(ErrorKind::InvalidData,
-                                              "The raster was not read correctly"));
                     }
-                }
-                _ => {
-                    return Err(Error::new(ErrorKind::InvalidData,
-                                          "The raster was not read correctly"))
-                }
-            }
-
-
-
-
-            match mode {
-                IM_GRAYINVERT | IM_GRAY => { //ImageMode::GrayInvert | ImageMode::Gray => {
-                    configs.photometric_interp = PhotometricInterpretation::Continuous;
-                    match sample_format[0] {
-                        1 => {
-                            // unsigned integer
-                            match bits_per_sample[0] {
-                                8 => {
-                                    configs.data_type = DataType::U8;
-                                }
-                                16 => {
-                                    configs.data_type = DataType::U16;
-                                }
-                                32 => {
-                                    configs.data_type = DataType::U32;
-                                }
-                                64 => {
-                                    configs.data_type = DataType::U64;
-                                }
-                                _ => {
-                                    return Err(Error::new(ErrorKind::InvalidData,
-                                                          "The raster was not read correctly"))
+                    IM_RGB => { //ImageMode::RGB => {
+                        if bits_per_sample[0] == 8 {
+                            for y in ymin..ymax {
+                                for x in xmin..xmax {
+                                    let red = bor.read_u8() as u32; //uint32(g.buf[g.off]);
+                                    let green = bor.read_u8() as u32; //uint32(g.buf[g.off+1]);
+                                    let blue = bor.read_u8() as u32; //uint32(g.buf[g.off+2]);
+                                    let a = 255u32;
+                                    let value = (a << 24) | (blue << 16) | (green << 8) | red;
+                                    let i = y * width + x;
+                                    data.set_value(i, value as f64);
                                 }
                             }
-                        }
-                        2 => {
-                            // signed integer
-                            match bits_per_sample[0] {
-                                8 => {
-                                    configs.data_type = DataType::I8;
-                                }
-                                16 => {
-                                    configs.data_type = DataType::I16;
-                                }
-                                32 => {
-                                    configs.data_type = DataType::I32;
-                                }
-                                64 => {
-                                    configs.data_type = DataType::I64;
-                                }
-                                _ => {
-                                    return Err(Error::new(ErrorKind::InvalidData,
-                                                          "The raster was not read correctly"))
+                        } else if bits_per_sample[0] == 16 {
+                            // the spec doesn't talk about 16-bit RGB images so
+                            // I'm not sure why I bother with this. They specifically
+                            // say that RGB images are 8-bits per channel. Anyhow,
+                            // I rescale the 16-bits to an 8-bit channel for simplicity.
+                            for y in ymin..ymax {
+                                for x in xmin..xmax {
+                                    let red = (bor.read_u16() as f64 / 65535f64 * 255f64) as u32;
+                                    let green = (bor.read_u16() as f64 / 65535f64 * 255f64) as u32;
+                                    let blue = (bor.read_u16() as f64 / 65535f64 * 255f64) as u32;
+                                    let a = 255u32;
+                                    let value = (a << 24) | (blue << 16) | (green << 8) | red;
+                                    let i = y * width + x;
+                                    data.set_value(i, value as f64);
                                 }
                             }
+                        } else {
+                            return Err(Error::new(ErrorKind::InvalidData,
+                                                  "The raster was not read correctly"));
                         }
-                        3 => {
-                            // floating point
-                            match bits_per_sample[0] {
-                                32 => {
-                                    configs.data_type = DataType::F32;
-                                }
-                                64 => {
-                                    configs.data_type = DataType::F64;
+                    }
+                    IM_NRGBA | IM_RGBA => { //ImageMode::NRGBA | ImageMode::RGBA => {
+                        if bits_per_sample[0] == 8 {
+                            for y in ymin..ymax {
+                                for x in xmin..xmax {
+                                    let red = bor.read_u8() as u32; //uint32(g.buf[g.off]);
+                                    let green = bor.read_u8() as u32; //uint32(g.buf[g.off+1]);
+                                    let blue = bor.read_u8() as u32; //uint32(g.buf[g.off+2]);
+                                    let a = bor.read_u8() as u32;
+                                    let value = (a << 24) | (blue << 16) | (green << 8) | red;
+                                    let i = y * width + x;
+                                    data.set_value(i, value as f64);
                                 }
-                                _ => {
-                                    return Err(Error::new(ErrorKind::InvalidData,
-                                                          "The raster was not read correctly"))
+                            }
+                        } else if bits_per_sample[0] == 16 {
+                            // the spec doesn't talk about 16-bit RGB images so
+                            // I'm not sure why I bother with this. They specifically
+                            // say that RGB images are 8-bits per channel. Anyhow,
+                            // I rescale the 16-bits to an 8-bit channel for simplicity.
+                            for y in ymin..ymax {
+                                for x in xmin..xmax {
+                                    let red = (bor.read_u16() as f64 / 65535f64 * 255f64) as u32;
+                                    let green = (bor.read_u16() as f64 / 65535f64 * 255f64) as u32;
+                                    let blue = (bor.read_u16() as f64 / 65535f64 * 255f64) as u32;
+                                    let a = (bor.read_u16() as f64 / 65535f64 * 255f64) as u32;
+                                    let value = (a << 24) | (blue << 16) | (green << 8) | red;
+                                    let i = y * width + x;
+                                    data.set_value(i, value as f64);
                                 }
                             }
-                        }
-                        _ => {
+                        } else {
                             return Err(Error::new(ErrorKind::InvalidData,
-                                                  "The raster was not read correctly"))
+                                                  "The raster was not read correctly"));
                         }
                     }
-                }
-                IM_PALETTED => { //ImageMode::Paletted => {
-                    configs.photometric_interp = PhotometricInterpretation::Categorical;
-                    configs.data_type = DataType::U8;
-                }
-                IM_RGB => { //ImageMode::RGB => {
-                    configs.photometric_interp = PhotometricInterpretation::RGB;
-                    if bits_per_sample[0] == 8 {
-                        configs.data_type = DataType::U8;
-                    } else if bits_per_sample[0] == 16 {
-                        configs.data_type = DataType::U16;
-                    } else {
-                        return Err(Error::new(ErrorKind::InvalidData,
-                                              "The raster was not read correctly"));
-                    }
-                }
-                IM_NRGBA | IM_RGBA => { //ImageMode::NRGBA | ImageMode::RGBA => {
-                    if bits_per_sample[0] == 8 {
-                        configs.data_type = DataType::U32;
-                    } else if bits_per_sample[0] == 16 {
-                        configs.data_type = DataType::U64;
-                    } else {
+                    _ => {
                         return Err(Error::new(ErrorKind::InvalidData,
-                                              "The raster was not read correctly"));
+                                              "The raster was not read correctly"))
                     }
                 }
-                _ => {
-                    return Err(Error::new(ErrorKind::InvalidData,
-                                          "The raster was not read correctly"))
-                }
             }
         }
+
     }
 
+    if mode == IM_MULTIBAND && (rgb_mode == IM_RGB || rgb_mode == IM_RGBA || rgb_mode == IM_NRGBA) {
+        // Pack the bands of a planar RGB image into RGB values. As with chunky RGB images,
+        // 16-bit channels are rescaled to 8 bits.
+        let scale = if bits_per_sample[0] == 16 { 255f64 / 65535f64 } else { 1f64 };
+        for i in 0..num_cells {
+            let red = (data.get_value(i) * scale) as u32;
+            let green = (data.get_value(num_cells + i) * scale) as u32;
+            let blue = (data.get_value(2 * num_cells + i) * scale) as u32;
+            let a = if samples_per_pixel > 3 { (data.get_value(3 * num_cells + i) * scale) as u32 } else { 255u32 };
+            data.set_value(i, ((a << 24) | (blue << 16) | (green << 8) | red) as f64);
+        }
+        data.truncate(num_cells);
+        configs.photometric_interp = PhotometricInterpretation::RGB;
+        configs.data_type = match (samples_per_pixel > 3, bits_per_sample[0]) {
+            (false, 8) => DataType::U8,
+            (false, _) => DataType::U16,
+            (true, 8) => DataType::U32,
+            (true, _) => DataType::U64,
+        };
+    }
     // match geokeys_map.get(&1024) {
     //     Some(ifd) => geokeys.add_key_directory(&ifd.data),
     //     _ => return Err(Error::new(ErrorKind::InvalidData, "The TIFF file does not contain geokeys")),
@@ -970,973 +936,800 @@ pub fn read_geotiff<'a>(file_name: &'a String,
     Ok(())
 }
 
-pub fn write_geotiff<'a>(r: &'a mut Raster) -> Result<(), Error> {
-    let f = File::create(r.file_name.clone())?;
-    let mut writer = BufWriter::new(f);
+/// The layout and encoding of the strips or tiles of a GeoTIFF image, which allows the rows
+/// of a large image to be read without reading the remainder of the file.
+#[derive(Clone, Debug)]
+pub struct GeoTiffBlocks {
+    file_name: String,
+    endian: Endianness,
+    width: usize,
+    height: usize,
+    block_width: usize,
+    block_height: usize,
+    blocks_across: usize,
+    blocks_down: usize,
+    block_padding: bool,
+    offsets: Vec<u64>,
+    counts: Vec<u64>,
+    compression: u16,
+    predictor: u16,
+    sample_format: u16,
+    bits_per_sample: u16,
+    block_samples: usize,
+    block_bytes_per_pixel: usize,
+}
 
-    // get the endianness of the raster
-    let little_endian = match r.configs.endian {
-        Endianness::LittleEndian => true,
-        Endianness::BigEndian => false,
-    };
+impl GeoTiffBlocks {
+    /// Returns the number of rows in each strip or tile.
+    pub fn block_height(&self) -> usize {
+        self.block_height
+    }
 
-    if little_endian {
-        //////////////////////
-        // Write the header //
-        //////////////////////
-        writer.write_all("II".as_bytes())?;
-        // magic number
-        writer.write_u16::<LittleEndian>(42u16)?;
-        // offset to first IFD
-        let total_bytes_per_pixel = r.configs.data_type.get_data_size();
-        if total_bytes_per_pixel == 0 {
-            return Err(Error::new(ErrorKind::InvalidData, "Unknown data type."));
-        }
-        let mut ifd_start = (8usize + r.configs.rows as usize * r.configs.columns as usize *
-                        total_bytes_per_pixel) as u32; // plus the 8-byte header
-        let mut ifd_start_needs_extra_byte = false;
-        if ifd_start % 2 == 1 {
-            ifd_start += 1;
-            ifd_start_needs_extra_byte = true;
-        }
-        writer.write_u32::<LittleEndian>(ifd_start)?;
-
-        //////////////////////////////
-        // Write the image the data //
-        //////////////////////////////
-        match r.configs.photometric_interp {
-            PhotometricInterpretation::Continuous |
-            PhotometricInterpretation::Categorical |
-            PhotometricInterpretation::Boolean => {
-                match r.configs.data_type {
-                    DataType::F64 => {
-                        let mut i: usize;
-                        for row in 0..r.configs.rows {
-                            for col in 0..r.configs.columns {
-                                i = row * r.configs.columns + col;
-                                writer.write_f64::<LittleEndian>(r.data[i])?;
-                            }
-                        }
-                    },
-                    DataType::F32 => {
-                        let mut i: usize;
-                        for row in 0..r.configs.rows {
-                            for col in 0..r.configs.columns {
-                                i = row * r.configs.columns + col;
-                                writer.write_f32::<LittleEndian>(r.data[i] as f32)?;
-                            }
-                        }
-                    },
-                    DataType::U64 => {
-                        let mut i: usize;
-                        for row in 0..r.configs.rows {
-                            for col in 0..r.configs.columns {
-                                i = row * r.configs.columns + col;
-                                writer.write_u64::<LittleEndian>(r.data[i] as u64)?;
+    /// Reads num_rows rows of a band, numbered from zero, starting at first_row.
+    pub fn read_rows(&self, band: usize, first_row: usize, num_rows: usize) -> Result<Vec<f64>, Error> {
+        let mut f = File::open(&self.file_name)?;
+        let mut values = vec![0f64; num_rows * self.width];
+        // Each block of a planar image contains a single band; otherwise, blocks contain
+        // the interleaved samples of every band.
+        let (plane, sample) = if self.block_samples == 1 { (band, 0) } else { (0, band) };
+        let last_row = min(first_row + num_rows, self.height);
+        for j in (first_row / self.block_height)..((last_row + self.block_height - 1) / self.block_height) {
+            for i in 0..self.blocks_across {
+                let buf = self.decode_block(&mut f, plane, i, j)?;
+                let (xmin, ymin, xmax, ymax) = self.block_extent(i, j);
+                let mut bor = ByteOrderReader::new(buf, self.endian);
+                for y in ymin..ymax {
+                    for x in xmin..xmax {
+                        for s in 0..self.block_samples {
+                            let value = read_sample(&mut bor, self.sample_format, self.bits_per_sample)?;
+                            if s == sample && y >= first_row && y < last_row {
+                                values[(y - first_row) * self.width + x] = value;
                             }
                         }
-                    },
-                    DataType::U32 => {
-                        let mut i: usize;
-                        for row in 0..r.configs.rows {
-                            for col in 0..r.configs.columns {
-                                i = row * r.configs.columns + col;
-                                writer.write_u32::<LittleEndian>(r.data[i] as u32)?;
-                            }
-                        }
-                    },
-                    DataType::U16 => {
-                        let mut i: usize;
-                        for row in 0..r.configs.rows {
-                            for col in 0..r.configs.columns {
-                                i = row * r.configs.columns + col;
-                                writer.write_u16::<LittleEndian>(r.data[i] as u16)?;
-                            }
-                        }
-                    },
-                    DataType::U8 => {
-                        let mut i: usize;
-                        for row in 0..r.configs.rows {
-                            for col in 0..r.configs.columns {
-                                i = row * r.configs.columns + col;
-                                writer.write(&[r.data[i] as u8])?;
-                            }
-                        }
-                    },
-                    DataType::I64 => {
-                        let mut i: usize;
-                        for row in 0..r.configs.rows {
-                            for col in 0..r.configs.columns {
-                                i = row * r.configs.columns + col;
-                                writer.write_i64::<LittleEndian>(r.data[i] as i64)?;
-                            }
-                        }
-                    },
-                    DataType::I32 => {
-                        let mut i: usize;
-                        for row in 0..r.configs.rows {
-                            for col in 0..r.configs.columns {
-                                i = row * r.configs.columns + col;
-                                writer.write_i32::<LittleEndian>(r.data[i] as i32)?;
-                            }
-                        }
-                    },
-                    DataType::I16 => {
-                        let mut i: usize;
-                        for row in 0..r.configs.rows {
-                            for col in 0..r.configs.columns {
-                                i = row * r.configs.columns + col;
-                                writer.write_i16::<LittleEndian>(r.data[i] as i16)?;
-                            }
-                        }
-                    },
-                    DataType::I8 => {
-                        let mut i: usize;
-                        for row in 0..r.configs.rows {
-                            for col in 0..r.configs.columns {
-                                i = row * r.configs.columns + col;
-                                writer.write(&[r.data[i] as u8])?;
-                            }
-                        }
-                    },
-                    _ => {
-                        return Err(Error::new(ErrorKind::InvalidData, "Unknown data type."));
-                    },
-                }
-            },
-            PhotometricInterpretation::RGB => {
-                match r.configs.data_type {
-                    DataType::RGB24 => {
-                        let mut bytes: [u8; 3] = [0u8; 3];
-                        let mut i: usize;
-                        for row in 0..r.configs.rows {
-                            for col in 0..r.configs.columns {
-                                i = row * r.configs.columns + col;
-                                // writer.write_u24::<LittleEndian>(r.data[i] as u32)?;
-                                let val = r.data[i] as u32;
-                                bytes[2] = ((val >> 16u32) & 0xFF) as u8; // blue
-                                bytes[1] = ((val >> 8u32) & 0xFF) as u8; // green
-                                bytes[0] = (val & 0xFF) as u8; // red
-                                writer.write(&bytes)?;
-                            }
-                        }
-                    },
-                    DataType::RGBA32 => {
-                        let mut i: usize;
-                        let mut bytes: [u8; 4] = [0u8; 4];
-                        for row in 0..r.configs.rows {
-                            for col in 0..r.configs.columns {
-                                i = row * r.configs.columns + col;
-                                let val = r.data[i] as u32;
-                                bytes[2] = ((val >> 16u32) & 0xFF) as u8; // blue
-                                bytes[1] = ((val >> 8u32) & 0xFF) as u8; // green
-                                bytes[0] = (val & 0xFF) as u8; // red
-                                bytes[3] = ((val >> 24u32) & 0xFF) as u8; // a
-                                writer.write(&bytes)?;
-                                // let val2 = ((val << 24u32) & 0xFF) | ((val << 16u32) & 0xFF) | ((val << 8u32) & 0xFF) | (val & 0xFF);
-                                // writer.write_u32::<LittleEndian>(val2)?;
-                            }
-                        }
-                    },
-                    _ => {
-                        return Err(Error::new(ErrorKind::InvalidData, "Unknown data type."));
-                    },
+                    }
                 }
-            },
-            PhotometricInterpretation::Paletted => {
-                return Err(Error::new(ErrorKind::InvalidData,
-                                    "Paletted GeoTIFFs are currently unsupported for writing."));
-            },
-            PhotometricInterpretation::Unknown => {
-                return Err(Error::new(ErrorKind::InvalidData, "Error while writing GeoTIFF file."));
-            },
-        }
-
-        // This is just because the IFD must start on a word (i.e. an even value). If the data are
-        // single bytes, then this may not be the case.
-        if ifd_start_needs_extra_byte {
-            writer.write_u8(0u8)?;
-        }
-
-        ////////////////////////////
-        // Create the IFD entries //
-        ////////////////////////////
-
-        let mut ifd_entries: Vec<IfdEntry> = vec![];
-        let mut larger_values_data: Vec<u8> = vec![];
-
-        /* 
-        IFD entries
-
-        Bytes 0-1 The Tag that identifies the field.
-        Bytes 2-3 The field Type.
-        Bytes 4-7 The number of values, Count of the indicated Type.
-        Bytes 8-11 The Value Offset, the file offset (in bytes) of the Value for the field.
-        The Value is expected to begin on a word boundary; the corresponding
-        Value Offset will thus be an even number. This file offset may
-        point anywhere in the file, even after the image data. 
-
-        To save time and space the Value Offset contains the Value instead of pointing to
-        the Value if and only if the Value fits into 4 bytes. If the Value is shorter than 4
-        bytes, it is left-justified within the 4-byte Value Offset, i.e., stored in the lowernumbered
-        bytes. Whether the Value fits within 4 bytes is determined by the Type
-        and Count of the field.
-        */
-
-        // ImageWidth tag (256)
-        ifd_entries.push(IfdEntry::new(TAG_IMAGEWIDTH, DT_LONG, 1u32, r.configs.columns as u32));
-
-        // ImageLength tag (257)
-        ifd_entries.push(IfdEntry::new(TAG_IMAGELENGTH, DT_LONG, 1u32, r.configs.rows as u32));
-
-        let bits_per_sample = match r.configs.data_type {
-            DataType::I8 | DataType::U8 => 8u16,
-            DataType::I16 | DataType::U16 => 16u16,
-            DataType::I32 | DataType::U32 | DataType::F32 => 32u16,
-            DataType::I64 | DataType::U64 | DataType::F64 => 64u16,
-            DataType::RGB24 => 8u16,
-            DataType::RGBA32 => 8u16,
-            DataType::RGB48 => 16u16,
-            _ => {
-                return Err(Error::new(ErrorKind::InvalidData, "Unknown data type."));
             }
-        };
+        }
+        Ok(values)
+    }
+
+    // Returns the columns and rows, (xmin, ymin, xmax, ymax), of the image covered by the
+    // block in column i and row j of the grid of blocks.
+    fn block_extent(&self, i: usize, j: usize) -> (usize, usize, usize, usize) {
+        let xmin = i * self.block_width;
+        let ymin = j * self.block_height;
+        (xmin, ymin, min(xmin + self.block_width, self.width), min(ymin + self.block_height, self.height))
+    }
 
-        let samples_per_pixel = match r.configs.data_type {
-            DataType::I8 | DataType::U8 => 1u16,
-            DataType::I16 | DataType::U16 => 1u16,
-            DataType::I32 | DataType::U32 | DataType::F32 => 1u16,
-            DataType::I64 | DataType::U64 | DataType::F64 => 1u16,
-            DataType::RGB24 => 3u16,
-            DataType::RGBA32 => 4u16,
-            DataType::RGB48 => 3u16,
+    // Reads and decodes the block in column i and row j of the grid of blocks of a plane.
+    // Tiles along the right edge of the image are padded out to the full tile width; the
+    // padding is dropped so that the pixels can be read sequentially.
+    fn decode_block(&self, f: &mut File, plane: usize, i: usize, j: usize) -> Result<Vec<u8>, Error> {
+        let (xmin, ymin, xmax, ymax) = self.block_extent(i, j);
+        let blk_h = if self.block_padding { self.block_height } else { ymax - ymin };
+        let block = (plane * self.blocks_down + j) * self.blocks_across + i;
+        let mut raw = vec![0u8; self.counts[block] as usize];
+        f.seek(SeekFrom::Start(self.offsets[block]))?;
+        f.read_exact(&mut raw)?;
+        let expected_size = self.block_width * blk_h * self.block_bytes_per_pixel;
+        let mut buf: Vec<u8> = match self.compression {
+            COMPRESS_NONE => {
+                // no compression
+                raw
+            }
+            COMPRESS_PACKBITS => {
+                packbits_decoder(raw)
+            }
+            COMPRESS_LZW => {
+                lzw_decoder(&raw, expected_size)?
+            }
+            COMPRESS_DEFLATE | COMPRESS_DEFLATEOLD => {
+                deflate_decoder(&raw, expected_size)?
+            }
             _ => {
-                return Err(Error::new(ErrorKind::InvalidData, "Unknown data type."));
+                return Err(Error::new(ErrorKind::InvalidData,
+                                      format!("Unsupported TIFF compression method ({}).", self.compression)))
             }
         };
 
-        // BitsPerSample tag (258)
-        if r.configs.photometric_interp != PhotometricInterpretation::Boolean {
-            if samples_per_pixel == 1 {
-                ifd_entries.push(IfdEntry::new(TAG_BITSPERSAMPLE, DT_SHORT, samples_per_pixel as u32, bits_per_sample as u32));
-            } else {
-                ifd_entries.push(IfdEntry::new(TAG_BITSPERSAMPLE, DT_SHORT, samples_per_pixel as u32, larger_values_data.len() as u32));
-                for _ in 0..samples_per_pixel {
-                    let _ = larger_values_data.write_u16::<LittleEndian>(bits_per_sample);
+        let bytes_per_sample = self.bits_per_sample as usize / 8;
+        match self.predictor {
+            PREDICTOR_HORIZONTAL => undo_horizontal_predictor(&mut buf, self.block_width, self.block_samples, bytes_per_sample, self.endian)?,
+            PREDICTOR_FLOATINGPOINT => undo_floating_point_predictor(&mut buf, self.block_width, self.block_samples, bytes_per_sample, self.endian)?,
+            _ => {}
+        }
+
+        if xmax - xmin < self.block_width {
+            let row_bytes = self.block_width * self.block_bytes_per_pixel;
+            let valid_bytes = (xmax - xmin) * self.block_bytes_per_pixel;
+            let mut compacted: Vec<u8> = Vec::with_capacity(valid_bytes * (ymax - ymin));
+            for r in 0..(ymax - ymin) {
+                let start = r * row_bytes;
+                let end = min(start + valid_bytes, buf.len());
+                if start < end {
+                    compacted.extend_from_slice(&buf[start..end]);
                 }
             }
-            
+            buf = compacted;
         }
+        Ok(buf)
+    }
+}
 
-        // Compression tag (259)
-        ifd_entries.push(IfdEntry::new(TAG_COMPRESSION, DT_SHORT, 1u32, COMPRESS_NONE as u32));
-
-        // PhotometricInterpretation tag (262)
-        let pi = match r.configs.photometric_interp {
-            PhotometricInterpretation::Continuous => PI_BLACKISZERO,
-            PhotometricInterpretation::Categorical | PhotometricInterpretation::Paletted => PI_PALETTED,
-            PhotometricInterpretation::Boolean => PI_BLACKISZERO,
-            PhotometricInterpretation::RGB => PI_RGB,
-            PhotometricInterpretation::Unknown => {
-                return Err(Error::new(ErrorKind::InvalidData, "Error while writing GeoTIFF file. Unknown Photometric Interpretation."));
-            },
-        };
-        ifd_entries.push(IfdEntry::new(TAG_PHOTOMETRICINTERPRETATION, DT_SHORT, 1u32, pi as u32));
+// Reads a single sample, given its SampleFormat (1 = unsigned integer, 2 = signed integer,
+// 3 = floating point) and size in bits.
+fn read_sample(bor: &mut ByteOrderReader, sample_format: u16, bits_per_sample: u16) -> Result<f64, Error> {
+    let value = match (sample_format, bits_per_sample) {
+        (1, 8) => bor.read_u8() as f64,
+        (1, 16) => bor.read_u16() as f64,
+        (1, 32) => bor.read_u32() as f64,
+        (1, 64) => bor.read_u64() as f64,
+        (2, 8) => bor.read_i8() as f64,
+        (2, 16) => bor.read_i16() as f64,
+        (2, 32) => bor.read_i32() as f64,
+        (2, 64) => bor.read_i64() as f64,
+        (3, 32) => bor.read_f32() as f64,
+        (3, 64) => bor.read_f64(),
+        _ => {
+            return Err(Error::new(ErrorKind::InvalidData,
+                                  "The raster was not read correctly"))
+        }
+    };
+    Ok(value)
+}
 
-        // StripOffsets tag (273)
-        ifd_entries.push(IfdEntry::new(TAG_STRIPOFFSETS, DT_LONG, r.configs.rows as u32, larger_values_data.len() as u32));
-        let row_length_in_bytes: u32 = r.configs.columns as u32 * total_bytes_per_pixel as u32;
-        for i in 0..r.configs.rows as u32 {
-            let _ = larger_values_data.write_u32::<LittleEndian>(8u32 + row_length_in_bytes * i);
+// Reads the TIFF header and returns the file's byte order, whether it is a BigTIFF, and the
+// offset of the first IFD.
+fn read_tiff_header(f: &mut File) -> Result<(Endianness, bool, usize), Error> {
+    let mut buffer = vec![0u8; 16];
+    f.seek(SeekFrom::Start(0))?;
+    f.read_exact(&mut buffer[0..8])?;
+    // Classic TIFF files may be as small as eight bytes.
+    let _ = f.read(&mut buffer[8..16])?;
+    let endian = match &buffer[0..2] {
+        b"II" => Endianness::LittleEndian,
+        b"MM" => Endianness::BigEndian,
+        _ => return Err(Error::new(ErrorKind::InvalidData, "Incorrect TIFF header.")),
+    };
+    let mut th = ByteOrderReader::new(buffer, endian);
+    th.seek(2);
+    match th.read_u16() {
+        42 => Ok((endian, false, th.read_u32() as usize)),
+        43 => {
+            // BigTIFF; the magic number is followed by the size of offsets, which
+            // is always 8, and two bytes of padding.
+            let offset_size = th.read_u16();
+            let _ = th.read_u16();
+            if offset_size != 8 {
+                return Err(Error::new(ErrorKind::InvalidData, "Incorrect BigTIFF header."));
+            }
+            Ok((endian, true, th.read_u64() as usize))
         }
+        _ => Err(Error::new(ErrorKind::InvalidData, "Incorrect TIFF header.")),
+    }
+}
 
-        // SamplesPerPixel tag (277)
-        ifd_entries.push(IfdEntry::new(TAG_SAMPLESPERPIXEL, DT_SHORT, 1u32, samples_per_pixel as u32));
+// Reads the entries of the IFD located at ifd_offset and returns them along with the
+// offset of the next IFD, which is zero for the last IFD in the file.
+fn read_ifd(f: &mut File, file_size: usize, byte_order: Endianness, ifd_offset: usize, is_big_tiff: bool) -> Result<(Vec<IfdDirectory>, usize), Error> {
+    // BigTIFF files use 64-bit counts and offsets, and therefore, have larger IFD entries.
+    let offset_size = if is_big_tiff { 8usize } else { 4usize };
+    let count_size = if is_big_tiff { 8usize } else { 2usize };
+    let entry_size = if is_big_tiff { 20usize } else { 12usize };
+    if ifd_offset + count_size > file_size {
+        return Err(Error::new(ErrorKind::InvalidData, "Error reading the IFDs."));
+    }
+    let mut buffer = vec![0u8; count_size];
+    f.seek(SeekFrom::Start(ifd_offset as u64))?;
+    f.read_exact(&mut buffer)?;
+    let num_directories = {
+        let mut th = ByteOrderReader::new(buffer, byte_order);
+        if is_big_tiff { th.read_u64() as usize } else { th.read_u16() as usize }
+    };
+    let ifd_size = num_directories * entry_size + offset_size;
+    if ifd_offset + count_size + ifd_size > file_size {
+        return Err(Error::new(ErrorKind::InvalidData, "Error reading the IFDs."));
+    }
+    let mut buffer = vec![0u8; ifd_size];
+    f.read_exact(&mut buffer)?;
+    let mut th = ByteOrderReader::new(buffer, byte_order);
+
+    let mut ifds = vec![];
+    for _ in 0..num_directories {
+        let tag_id = th.read_u16();
+        let field_type = th.read_u16();
+
+        let num_values = if is_big_tiff { th.read_u64() } else { th.read_u32() as u64 };
+        let value_offset = if is_big_tiff { th.read_u64() } else { th.read_u32() as u64 };
+        let data_size = match field_type {
+            1u16 | 2u16 | 6u16 | 7u16 => 1,
+            3u16 | 8u16 => 2,
+            4u16 | 9u16 | 11u16 | 13u16 => 4,
+            5u16 | 10u16 | 12u16 | 16u16 | 17u16 | 18u16 => 8,
+            _ => return Err(Error::new(ErrorKind::InvalidInput, "Error reading the IFDs.")),
+        };
 
-        // RowsPerStrip tag (278)
-        ifd_entries.push(IfdEntry::new(TAG_ROWSPERSTRIP, DT_SHORT, 1u32, 1u32));
-
-        // StripByteCounts tag (279)
-        ifd_entries.push(IfdEntry::new(TAG_STRIPBYTECOUNTS, DT_LONG, r.configs.rows as u32, larger_values_data.len() as u32));
-        let total_bytes_per_pixel = match r.configs.data_type {
-            DataType::I8 | DataType::U8 => 1u32,
-            DataType::I16 | DataType::U16 => 2u32,
-            DataType::I32 | DataType::U32 | DataType::F32 => 4u32,
-            DataType::I64 | DataType::U64 | DataType::F64 => 8u32,
-            DataType::RGB24 => 3u32,
-            DataType::RGBA32 => 4u32,
-            DataType::RGB48 => 6u32,
-            _ => {
-                return Err(Error::new(ErrorKind::InvalidData, "Unknown data type."));
+        // read the tag data
+        let num_bytes = (data_size * num_values) as usize;
+        let data = if num_bytes > offset_size {
+            // the values are stored at the offset location
+            if value_offset as usize + num_bytes > file_size {
+                return Err(Error::new(ErrorKind::InvalidData, "Error reading the IFDs."));
             }
+            let mut data = vec![0u8; num_bytes];
+            f.seek(SeekFrom::Start(value_offset))?;
+            f.read_exact(&mut data)?;
+            data
+        } else {
+            // the value(s) are contained in the offset
+            let data_start = th.pos - offset_size;
+            th.buffer[data_start..(data_start + num_bytes)].to_vec()
         };
-        let row_length_in_bytes: u32 = r.configs.columns as u32 * total_bytes_per_pixel;
-        for _ in 0..r.configs.rows as u32 {
-            let _ = larger_values_data.write_u32::<LittleEndian>(row_length_in_bytes);
-        }
-        
-        // There is currently no support for storing the image resolution, so give a bogus value of 72x72 dpi.
-        // XResolution tag (282)
-        ifd_entries.push(IfdEntry::new(TAG_XRESOLUTION, DT_RATIONAL, 1u32, larger_values_data.len() as u32));
-        let _ = larger_values_data.write_u32::<LittleEndian>(72u32);
-        let _ = larger_values_data.write_u32::<LittleEndian>(1u32);
 
-        // YResolution tag (283)
-        ifd_entries.push(IfdEntry::new(TAG_YRESOLUTION, DT_RATIONAL, 1u32, larger_values_data.len() as u32));
-        let _ = larger_values_data.write_u32::<LittleEndian>(72u32);
-        let _ = larger_values_data.write_u32::<LittleEndian>(1u32);
+        ifds.push(IfdDirectory::new(tag_id,
+                                    field_type,
+                                    num_values,
+                                    value_offset,
+                                    data,
+                                    th.byte_order));
+    }
+    let next_ifd_offset = if is_big_tiff { th.read_u64() } else { th.read_u32() as u64 };
+    Ok((ifds, next_ifd_offset as usize))
+}
 
-        // ResolutionUnit tag (296)
-        ifd_entries.push(IfdEntry::new(TAG_RESOLUTIONUNIT, DT_SHORT, 1u32, 2u32));
+pub fn write_geotiff<'a>(r: &'a mut Raster) -> Result<(), Error> {
+    // get the endianness of the raster
+    match r.configs.endian {
+        Endianness::LittleEndian => write_geotiff_with_byte_order::<LittleEndian>(r, false),
+        Endianness::BigEndian => write_geotiff_with_byte_order::<BigEndian>(r, false),
+    }
+}
 
-        // Software tag (305)
-        let software = "WhiteboxTools".to_owned();
-        let mut soft_bytes = software.into_bytes();
-        soft_bytes.push(0);
-        ifd_entries.push(IfdEntry::new(TAG_SOFTWARE, DT_ASCII, soft_bytes.len() as u32, larger_values_data.len() as u32));
-        let _ = larger_values_data.write_all(&soft_bytes);
+// The width and height, in pixels, of the tiles used in tiled and cloud-optimized GeoTIFFs.
+const TILE_SIZE: usize = 256;
 
-        if samples_per_pixel == 4 {
-            // ExtraSamples tag (338)
-            ifd_entries.push(IfdEntry::new(TAG_EXTRASAMPLES, DT_SHORT, 1u32, 2u32));
-        }
-        
-        // SampleFormat tag (339)
-        let samples_format = match r.configs.data_type {
-            DataType::U8 | DataType::U16 | DataType::U32 | DataType::U64 => 1u16,
-            DataType::I8 | DataType::I16 | DataType::I32 | DataType::I64 => 2u16,
-            DataType::F32 | DataType::F64 => 3u16,
-            DataType::RGB24 | DataType::RGBA32 | DataType::RGB48 => 1u16,
-            _ => {
-                return Err(Error::new(ErrorKind::InvalidData, "Unknown data type."));
-            }
-        };
-        if samples_per_pixel == 1 {
-            ifd_entries.push(IfdEntry::new(TAG_SAMPLEFORMAT, DT_SHORT, samples_per_pixel as u32, samples_format as u32));
-        } else {
-            ifd_entries.push(IfdEntry::new(TAG_SAMPLEFORMAT, DT_SHORT, samples_per_pixel as u32, larger_values_data.len() as u32));
-            for _ in 0..samples_per_pixel {
-                let _ = larger_values_data.write_u16::<LittleEndian>(samples_format);
-            }
-        }
+// Files are written in the BigTIFF format when they may exceed 4 GB, or if force_big_tiff is set.
+fn write_geotiff_with_byte_order<'a, T: ByteOrder>(r: &'a Raster, force_big_tiff: bool) -> Result<(), Error> {
+    let f = File::create(r.file_name.clone())?;
+    let mut writer = BufWriter::new(f);
 
-        // ModelTiepointTag tag (33550)
-        ifd_entries.push(IfdEntry::new(TAG_MODELPIXELSCALETAG, DT_DOUBLE, 3u32, larger_values_data.len() as u32));
-        let _ = larger_values_data.write_f64::<LittleEndian>(r.configs.resolution_x);
-        let _ = larger_values_data.write_f64::<LittleEndian>(r.configs.resolution_y);
-        let _ = larger_values_data.write_f64::<LittleEndian>(0f64);
-        
-        // ModelPixelScaleTag tag (33922)
-        ifd_entries.push(IfdEntry::new(TAG_MODELTIEPOINTTAG, DT_DOUBLE, 6u32, larger_values_data.len() as u32));
-        let _ = larger_values_data.write_f64::<LittleEndian>(0f64); // I
-        let _ = larger_values_data.write_f64::<LittleEndian>(0f64); // J
-        let _ = larger_values_data.write_f64::<LittleEndian>(0f64); // K
-        let _ = larger_values_data.write_f64::<LittleEndian>(r.configs.west); // X
-        let _ = larger_values_data.write_f64::<LittleEndian>(r.configs.north); // Y
-        let _ = larger_values_data.write_f64::<LittleEndian>(0f64); // Z
-
-        // TAG_GDAL_NODATA tag (42113)
-        let nodata_str = format!("{}", r.configs.nodata);
-        let mut nodata_bytes = nodata_str.into_bytes();
-        nodata_bytes.push(0);
-        ifd_entries.push(IfdEntry::new(TAG_GDAL_NODATA, DT_ASCII, nodata_bytes.len() as u32, larger_values_data.len() as u32));
-        if nodata_bytes.len() % 2 == 1 {
-            nodata_bytes.push(0);
+    let total_bytes_per_pixel = r.configs.data_type.get_data_size();
+    if total_bytes_per_pixel == 0 {
+        return Err(Error::new(ErrorKind::InvalidData, "Unknown data type."));
+    }
+
+    let bits_per_sample = match r.configs.data_type {
+        DataType::I8 | DataType::U8 => 8u16,
+        DataType::I16 | DataType::U16 => 16u16,
+        DataType::I32 | DataType::U32 | DataType::F32 => 32u16,
+        DataType::I64 | DataType::U64 | DataType::F64 => 64u16,
+        DataType::RGB24 => 8u16,
+        DataType::RGBA32 => 8u16,
+        DataType::RGB48 => 16u16,
+        _ => {
+            return Err(Error::new(ErrorKind::InvalidData, "Unknown data type."));
         }
-        let _ = larger_values_data.write_all(&nodata_bytes);
-        
-        let kw_map = get_keyword_map();
-        let geographic_type_map = match kw_map.get(&2048u16) {
-            Some(map) => map,
-            None => return Err(Error::new(ErrorKind::InvalidData, "Error generating geographic type map.")),
-        };
-        let projected_cs_type_map = match kw_map.get(&3072u16) {
-            Some(map) => map,
-            None => return Err(Error::new(ErrorKind::InvalidData, "Error generating projected coordinate system type map.")),
-        };
+    };
 
-        //let key_map = get_keys_map();
-        let mut gk_entries: Vec<GeoKeyEntry> = vec![];
-        let mut ascii_params = String::new(); //: Vec<u8> = vec![];
-        let double_params: Vec<f64> = vec![];
-        if geographic_type_map.contains_key(&r.configs.epsg_code) {
-            // tGTModelTypeGeoKey (1024)
-            gk_entries.push(GeoKeyEntry{ tag: TAG_GTMODELTYPEGEOKEY, location: 0u16, count: 1u16, value_offset: 2u16 });
-            
-            // GTRasterTypeGeoKey (1025)
-            if r.configs.pixel_is_area {
-                gk_entries.push(GeoKeyEntry{ tag: TAG_GTRASTERTYPEGEOKEY, location: 0u16, count: 1u16, value_offset: 1u16 });
-            } else {
-                gk_entries.push(GeoKeyEntry{ tag: TAG_GTRASTERTYPEGEOKEY, location: 0u16, count: 1u16, value_offset: 2u16 });
-            }
-            
-            // tGTCitationGeoKey (1026)
-            let mut v = String::from(geographic_type_map.get(&r.configs.epsg_code).unwrap().clone());
-            v.push_str("|");
-            v = v.replace("_", " ");
-            gk_entries.push(GeoKeyEntry{ tag: TAG_GTCITATIONGEOKEY, location: 34737u16, count: v.len() as u16, value_offset: ascii_params.len() as u16 });
-            ascii_params.push_str(&v);
+    // Each band of a multi-band raster is stored as a separate sample.
+    let num_bands = r.num_bands();
+    let samples_per_pixel = match r.configs.data_type {
+        DataType::I8 | DataType::U8 => num_bands as u16,
+        DataType::I16 | DataType::U16 => num_bands as u16,
+        DataType::I32 | DataType::U32 | DataType::F32 => num_bands as u16,
+        DataType::I64 | DataType::U64 | DataType::F64 => num_bands as u16,
+        DataType::RGB24 => 3u16,
+        DataType::RGBA32 => 4u16,
+        DataType::RGB48 => 3u16,
+        _ => {
+            return Err(Error::new(ErrorKind::InvalidData, "Unknown data type."));
+        }
+    };
+    if num_bands > 1 && r.configs.photometric_interp == PhotometricInterpretation::RGB {
+        return Err(Error::new(ErrorKind::InvalidData, "Multi-band rasters cannot contain RGB data."));
+    }
 
-            // tGeographicTypeGeoKey (2048)
-            gk_entries.push(GeoKeyEntry{ tag: TAG_GEOGRAPHICTYPEGEOKEY, location: 0u16, count: 1u16, value_offset: r.configs.epsg_code });
-            
-            if r.configs.z_units.to_lowercase() != "not specified" {
-                // VerticalUnitsGeoKey (4099)
-                let units = r.configs.z_units.to_lowercase();
-                if units.contains("met") {
-                    gk_entries.push(GeoKeyEntry{ tag: TAG_VERTICALUNITSGEOKEY, location: 0u16, count: 1u16, value_offset: 9001u16 });
-                } else if units.contains("ft") | units.contains("feet") | units.contains("foot") {
-                    gk_entries.push(GeoKeyEntry{ tag: TAG_VERTICALUNITSGEOKEY, location: 0u16, count: 1u16, value_offset: 9002u16 });
-                }
-            }
-        } else if projected_cs_type_map.contains_key(&r.configs.epsg_code) {
-            // tGTModelTypeGeoKey (1024)
-            gk_entries.push(GeoKeyEntry{ tag: TAG_GTMODELTYPEGEOKEY, location: 0u16, count: 1u16, value_offset: 1u16 });
-            
-            // GTRasterTypeGeoKey (1025)
-            if r.configs.pixel_is_area {
-                gk_entries.push(GeoKeyEntry{ tag: TAG_GTRASTERTYPEGEOKEY, location: 0u16, count: 1u16, value_offset: 1u16 });
-            } else {
-                gk_entries.push(GeoKeyEntry{ tag: TAG_GTRASTERTYPEGEOKEY, location: 0u16, count: 1u16, value_offset: 2u16 });
-            }
-            
-            // tProjectedCSTypeGeoKey (3072)
-            gk_entries.push(GeoKeyEntry{ tag: TAG_PROJECTEDCSTYPEGEOKEY, location: 0u16, count: 1u16, value_offset: r.configs.epsg_code });
-            
-            // PCSCitationGeoKey (3073)
-            let mut v = String::from(projected_cs_type_map.get(&r.configs.epsg_code).unwrap().clone());
-            v.push_str("|");
-            v = v.replace("_", " ");
-            gk_entries.push(GeoKeyEntry{ tag: 3073u16, location: 34737u16, count: v.len() as u16, value_offset: ascii_params.len() as u16 });
-            ascii_params.push_str(&v);
+    // With the planar configuration, each band is written as a separate set of blocks,
+    // and therefore, each block contains a single sample per pixel.
+    let planes = if num_bands > 1 && r.configs.planar_config == PlanarConfiguration::Planar { num_bands } else { 1usize };
+    let block_bytes_per_pixel = (bits_per_sample as usize / 8) * samples_per_pixel as usize / planes;
 
-            if r.configs.xy_units.to_lowercase() != "not specified" {
-                // ProjLinearUnitsGeoKey (3076)
-                let units = r.configs.xy_units.to_lowercase();
-                if units.contains("met") {
-                    gk_entries.push(GeoKeyEntry{ tag: TAG_PROJLINEARUNITSGEOKEY, location: 0u16, count: 1u16, value_offset: 9001u16 });
-                } else if units.contains("ft") | units.contains("feet") | units.contains("foot") {
-                    gk_entries.push(GeoKeyEntry{ tag: TAG_PROJLINEARUNITSGEOKEY, location: 0u16, count: 1u16, value_offset: 9002u16 });
-                }
-            }
+    let compression = match r.configs.compression {
+        RasterCompression::None => COMPRESS_NONE,
+        RasterCompression::Deflate => COMPRESS_DEFLATE,
+        RasterCompression::LZW => COMPRESS_LZW,
+        RasterCompression::PackBits => COMPRESS_PACKBITS,
+    };
 
-            if r.configs.z_units.to_lowercase() != "not specified" {
-                // VerticalUnitsGeoKey (4099)
-                let units = r.configs.z_units.to_lowercase();
-                if units.contains("met") {
-                    gk_entries.push(GeoKeyEntry{ tag: TAG_VERTICALUNITSGEOKEY, location: 0u16, count: 1u16, value_offset: 9001u16 });
-                } else if units.contains("ft") | units.contains("feet") | units.contains("foot") {
-                    gk_entries.push(GeoKeyEntry{ tag: TAG_VERTICALUNITSGEOKEY, location: 0u16, count: 1u16, value_offset: 9002u16 });
+    // Predictors are only supported by the LZW and Deflate codecs.
+    let is_floating_point = r.configs.data_type == DataType::F32 || r.configs.data_type == DataType::F64;
+    let predictor = if compression != COMPRESS_LZW && compression != COMPRESS_DEFLATE {
+        PREDICTOR_NONE
+    } else {
+        match r.configs.predictor {
+            RasterPredictor::None => PREDICTOR_NONE,
+            RasterPredictor::Horizontal => PREDICTOR_HORIZONTAL,
+            RasterPredictor::FloatingPoint => {
+                if is_floating_point {
+                    PREDICTOR_FLOATINGPOINT
+                } else {
+                    PREDICTOR_HORIZONTAL
                 }
             }
-        } else {
-            // we don't know much about the coordinate system used.
-            
-            // tGTModelTypeGeoKey (1024)
-            gk_entries.push(GeoKeyEntry{ tag: TAG_GTMODELTYPEGEOKEY, location: 0u16, count: 1u16, value_offset: 0u16 });
-            
-            // GTRasterTypeGeoKey (1025)
-            if r.configs.pixel_is_area {
-                gk_entries.push(GeoKeyEntry{ tag: TAG_GTRASTERTYPEGEOKEY, location: 0u16, count: 1u16, value_offset: 1u16 });
-            } else {
-                gk_entries.push(GeoKeyEntry{ tag: TAG_GTRASTERTYPEGEOKEY, location: 0u16, count: 1u16, value_offset: 2u16 });
-            }
-            
-        }
-
-        // create the GeoKeyDirectoryTag tag (34735)
-        ifd_entries.push(IfdEntry::new(TAG_GEOKEYDIRECTORYTAG, DT_SHORT, (4 + gk_entries.len() * 4) as u32, larger_values_data.len() as u32));
-        let _ = larger_values_data.write_u16::<LittleEndian>(1u16); // KeyDirectoryVersion
-        let _ = larger_values_data.write_u16::<LittleEndian>(1u16); // KeyRevision
-        let _ = larger_values_data.write_u16::<LittleEndian>(0u16); // MinorRevision
-        let _ = larger_values_data.write_u16::<LittleEndian>(gk_entries.len() as u16); // NumberOfKeys
-
-        for entry in gk_entries {
-            let _ = larger_values_data.write_u16::<LittleEndian>(entry.tag); // KeyID
-            let _ = larger_values_data.write_u16::<LittleEndian>(entry.location); // TIFFTagLocation
-            let _ = larger_values_data.write_u16::<LittleEndian>(entry.count); // Count
-            let _ = larger_values_data.write_u16::<LittleEndian>(entry.value_offset); // Value_Offset
         }
+    };
 
-        if double_params.len() > 0 {
-            // create the GeoDoubleParamsTag tag (34736)
-            ifd_entries.push(IfdEntry::new(TAG_GEODOUBLEPARAMSTAG, DT_DOUBLE, double_params.len() as u32, larger_values_data.len() as u32));
-            for double_val in double_params {
-                let _ = larger_values_data.write_f64::<LittleEndian>(double_val);
+    /////////////////////////////////////////
+    // Describe the images within the file //
+    /////////////////////////////////////////
+
+    // Cloud-optimized GeoTIFFs contain a pyramid of reduced-resolution overviews, each
+    // half the size of the one before it, down to an image that fits within a single tile.
+    let mut overviews: Vec<(usize, usize, RasterData)> = vec![];
+    if r.configs.layout == RasterLayout::CloudOptimized {
+        let mut columns = r.configs.columns;
+        let mut rows = r.configs.rows;
+        while columns > TILE_SIZE || rows > TILE_SIZE {
+            let mut data = RasterData::with_capacity(r.configs.data_type, ((columns + 1) / 2) * ((rows + 1) / 2) * num_bands);
+            for band in 0..num_bands {
+                let previous = match overviews.last() {
+                    Some(overview) => &overview.2,
+                    None => &r.data,
+                };
+                for value in build_overview(&r.configs, previous, band * rows * columns, columns, rows) {
+                    data.push(value);
+                }
             }
+            columns = (columns + 1) / 2;
+            rows = (rows + 1) / 2;
+            overviews.push((columns, rows, data));
         }
+    }
 
-        if ascii_params.len() > 0 {
-            // create the GeoAsciiParamsTag tag (34737)
-            let mut ascii_params_bytes = ascii_params.into_bytes();
-            ascii_params_bytes.push(0);
-            ifd_entries.push(IfdEntry::new(TAG_GEOASCIIPARAMSTAG, DT_ASCII, ascii_params_bytes.len() as u32, larger_values_data.len() as u32));
-            if ascii_params_bytes.len() % 2 == 1 {
-                // it has to end on a word so that the next value starts on a word
-                ascii_params_bytes.push(0);
-            }
-            let _ = larger_values_data.write_all(&ascii_params_bytes);
+    let mut images: Vec<TiffImage> = Vec::with_capacity(overviews.len() + 1);
+    if r.configs.layout == RasterLayout::Strips {
+        // Uncompressed rasters are written one row per strip. Compressed rasters use
+        // strips of roughly 64 KB, which gives the compressor more data to work with.
+        let row_length_in_bytes = r.configs.columns * block_bytes_per_pixel;
+        let rows_per_strip = if compression == COMPRESS_NONE || row_length_in_bytes == 0 {
+            1usize
+        } else {
+            min(r.configs.rows, max(1usize, 65536usize / row_length_in_bytes))
+        };
+        images.push(TiffImage::strips(r.configs.columns, r.configs.rows, &r.data, max(1usize, rows_per_strip)));
+    } else {
+        images.push(TiffImage::tiles(r.configs.columns, r.configs.rows, &r.data, false));
+        for overview in &overviews {
+            images.push(TiffImage::tiles(overview.0, overview.1, &overview.2, true));
         }
+    }
 
-        ///////////////////
-        // Write the IFD //
-        ///////////////////
-
-        // Number of Directory Entries.
-        writer.write_u16::<LittleEndian>(ifd_entries.len() as u16)?;
+    // Classic TIFFs use 32-bit offsets and are therefore limited to 4 GB. Use the BigTIFF
+    // format whenever the encoded image data could possibly exceed this limit. In the
+    // worst case, LZW uses a 12-bit code for each byte and PackBits adds a byte for every
+    // 128 bytes of data.
+    let mut data_size = 0f64;
+    let mut num_blocks = 0usize;
+    let mut num_block_rows = 0usize;
+    for image in &images {
+        data_size += (image.num_blocks() * planes * image.block_width * image.block_height * block_bytes_per_pixel) as f64;
+        num_blocks += image.num_blocks() * planes;
+        num_block_rows += image.num_blocks() * planes * image.block_height;
+    }
+    let max_data_size = match compression {
+        COMPRESS_LZW => data_size * 1.5,
+        COMPRESS_DEFLATE | COMPRESS_PACKBITS => data_size * 1.01 + (num_block_rows * 16) as f64,
+        _ => data_size,
+    };
+    let is_big_tiff = force_big_tiff || max_data_size + (num_blocks * 16) as f64 + (images.len() * 65536) as f64 > u32::MAX as f64;
+
+    let encoding = TiffEncoding {
+        bits_per_sample,
+        samples_per_pixel,
+        planes,
+        compression,
+        predictor,
+        is_big_tiff,
+    };
 
-        // Sort the IFD entries
-        ifd_entries.sort_by(|a, b| a.tag.cmp(&b.tag));
-        
-        // Write the entries
-        let ifd_length = 2u32 + ifd_entries.len() as u32 * 12u32 + 4u32;
-
-        for ifde in ifd_entries {
-            writer.write_u16::<LittleEndian>(ifde.tag)?; // Tag
-            writer.write_u16::<LittleEndian>(ifde.ifd_type)?; // Field type
-            writer.write_u32::<LittleEndian>(ifde.num_values)?; // Num of values
-            if ifde.ifd_type == DT_SHORT && ifde.num_values == 1 {
-                // it's a value
-                writer.write_u16::<LittleEndian>(ifde.offset as u16)?; // Value
-                writer.write_u16::<LittleEndian>(0u16)?; // Fill the remaining 2 right bytes of the u32
-            } else if ifde.ifd_type == DT_LONG && ifde.num_values == 1 {
-                // it's a value
-                writer.write_u32::<LittleEndian>(ifde.offset)?;
-            } else if ifde.ifd_type == DT_SHORT && ifde.num_values == 2 {
-                // I'm not really sure about this one. Two shorts will fit in the value_offset, but will they be interpreted correctly?
-                writer.write_u32::<LittleEndian>(ifde.offset)?; // Value
-            } else {
-                // it's an offset
-                writer.write_u32::<LittleEndian>(ifd_start + ifd_length + ifde.offset)?;
-            }
-        }
-
-        // 4-byte offset of the next IFD; Note, only single image TIFFs are currently supported
-        // and therefore, this will always be set to '0'.
-        writer.write_u32::<LittleEndian>(0u32)?;
-
-        //////////////////////////////////
-        // Write the larger_values_data //
-        //////////////////////////////////
-        writer.write_all(&larger_values_data)?;
-
-
-        /*
-            Required Fields for Bilevel Images
-            - ImageWidth 
-            - ImageLength 
-            - Compression 
-            - PhotometricInterpretation 
-            - StripOffsets 
-            - RowsPerStrip 
-            - StripByteCounts 
-            - XResolution 
-            - YResolution 
-            - ResolutionUnit
-        */
-
-        /*
-            Required Fields for Grayscale Images
-            - ImageWidth
-            - ImageLength
-            - BitsPerSample
-            - Compression
-            - PhotometricInterpretation
-            - StripOffsets
-            - RowsPerStrip
-            - StripByteCounts
-            - XResolution
-            - YResolution
-            - ResolutionUnit
-        */
-
-        /*
-            Required Fields for Palette Colour Images
-            - ImageWidth
-            - ImageLength
-            - BitsPerSample
-            - Compression
-            - PhotometricInterpretation
-            - StripOffsets
-            - RowsPerStrip
-            - StripByteCounts
-            - XResolution
-            - YResolution
-            - ResolutionUnit
-            - ColorMap
-        */
-
-        /*
-            Required Fields for RGB Images
-            - ImageWidth
-            - ImageLength
-            - BitsPerSample
-            - Compression
-            - PhotometricInterpretation
-            - StripOffsets
-            - SamplesPerPixel
-            - RowsPerStrip
-            - StripByteCounts
-            - XResolution
-            - YResolution
-            - ResolutionUnit
-        */
-
-         
+    //////////////////////
+    // Write the header //
+    //////////////////////
+    match r.configs.endian {
+        Endianness::LittleEndian => writer.write_all("II".as_bytes())?,
+        Endianness::BigEndian => writer.write_all("MM".as_bytes())?,
+    }
+    let header_size = if is_big_tiff {
+        // magic number
+        writer.write_u16::<T>(43u16)?;
+        // the size of offsets, followed by two bytes of padding
+        writer.write_u16::<T>(8u16)?;
+        writer.write_u16::<T>(0u16)?;
+        // offset to first IFD, which immediately follows the header
+        writer.write_u64::<T>(16u64)?;
+        16usize // the 16-byte BigTIFF header
     } else {
-        //////////////////////
-        // Write the header //
-        //////////////////////
-        writer.write_all("II".as_bytes())?;
         // magic number
-        writer.write_u16::<BigEndian>(42u16)?;
-        // offset to first IFD
-        let total_bytes_per_pixel = r.configs.data_type.get_data_size();
-        if total_bytes_per_pixel == 0 {
-            return Err(Error::new(ErrorKind::InvalidData, "Unknown data type."));
-        }
-        let mut ifd_start = (8usize + r.configs.rows as usize * r.configs.columns as usize *
-                        total_bytes_per_pixel) as u32; // plus the 8-byte header
-        let mut ifd_start_needs_extra_byte = false;
-        if ifd_start % 2 == 1 {
-            ifd_start += 1;
-            ifd_start_needs_extra_byte = true;
-        }
-        writer.write_u32::<BigEndian>(ifd_start)?;
-
-        //////////////////////////////
-        // Write the image the data //
-        //////////////////////////////
-        match r.configs.photometric_interp {
-            PhotometricInterpretation::Continuous |
-            PhotometricInterpretation::Categorical |
-            PhotometricInterpretation::Boolean => {
-                match r.configs.data_type {
-                    DataType::F64 => {
-                        let mut i: usize;
-                        for row in 0..r.configs.rows {
-                            for col in 0..r.configs.columns {
-                                i = row * r.configs.columns + col;
-                                writer.write_f64::<BigEndian>(r.data[i])?;
-                            }
-                        }
-                    },
-                    DataType::F32 => {
-                        let mut i: usize;
-                        for row in 0..r.configs.rows {
-                            for col in 0..r.configs.columns {
-                                i = row * r.configs.columns + col;
-                                writer.write_f32::<BigEndian>(r.data[i] as f32)?;
-                            }
-                        }
-                    },
-                    DataType::U64 => {
-                        let mut i: usize;
-                        for row in 0..r.configs.rows {
-                            for col in 0..r.configs.columns {
-                                i = row * r.configs.columns + col;
-                                writer.write_u64::<BigEndian>(r.data[i] as u64)?;
-                            }
-                        }
-                    },
-                    DataType::U32 => {
-                        let mut i: usize;
-                        for row in 0..r.configs.rows {
-                            for col in 0..r.configs.columns {
-                                i = row * r.configs.columns + col;
-                                writer.write_u32::<BigEndian>(r.data[i] as u32)?;
-                            }
-                        }
-                    },
-                    DataType::U16 => {
-                        let mut i: usize;
-                        for row in 0..r.configs.rows {
-                            for col in 0..r.configs.columns {
-                                i = row * r.configs.columns + col;
-                                writer.write_u16::<BigEndian>(r.data[i] as u16)?;
-                            }
-                        }
-                    },
-                    DataType::U8 => {
-                        let mut i: usize;
-                        for row in 0..r.configs.rows {
-                            for col in 0..r.configs.columns {
-                                i = row * r.configs.columns + col;
-                                writer.write(&[r.data[i] as u8])?;
-                            }
-                        }
-                    },
-                    DataType::I64 => {
-                        let mut i: usize;
-                        for row in 0..r.configs.rows {
-                            for col in 0..r.configs.columns {
-                                i = row * r.configs.columns + col;
-                                writer.write_i64::<BigEndian>(r.data[i] as i64)?;
-                            }
-                        }
-                    },
-                    DataType::I32 => {
-                        let mut i: usize;
-                        for row in 0..r.configs.rows {
-                            for col in 0..r.configs.columns {
-                                i = row * r.configs.columns + col;
-                                writer.write_i32::<BigEndian>(r.data[i] as i32)?;
-                            }
+        writer.write_u16::<T>(42u16)?;
+        // offset to first IFD, which immediately follows the header
+        writer.write_u32::<T>(8u32)?;
+        8usize // the 8-byte header
+    };
+
+    // The IFDs are placed at the start of the file, ahead of the image data, so that
+    // readers can locate any image, or portion of an image, after reading only the first
+    // few kilobytes of the file. This is required of cloud-optimized GeoTIFFs. The size of
+    // each IFD is known before the image data are written, and so space is reserved for
+    // them here and they are filled in once the block offsets are known.
+    let mut ifd_starts: Vec<u64> = Vec::with_capacity(images.len());
+    let mut current_offset = header_size;
+    for image in &images {
+        ifd_starts.push(current_offset as u64);
+        current_offset += build_ifd::<T>(r, image, &encoding, current_offset as u64, 0u64)?.len();
+    }
+    writer.write_all(&vec![0u8; current_offset - header_size])?;
+
+    //////////////////////////////
+    // Write the image the data //
+    //////////////////////////////
+
+    // The overviews are written from smallest to largest, followed by the full-resolution image.
+    let mut block_data: Vec<u8> = vec![];
+    for image in images.iter_mut().rev() {
+        let row_length_in_bytes = image.block_width * block_bytes_per_pixel;
+        let block_samples = samples_per_pixel as usize / planes;
+        for plane in 0..planes {
+            for block_row in 0..image.blocks_down() {
+                for block_col in 0..image.blocks_across() {
+                    block_data.clear();
+                    write_block_data::<T>(&r.configs, image, &encoding, plane, block_row, block_col, &mut block_data)?;
+
+                    match predictor {
+                        PREDICTOR_HORIZONTAL => apply_horizontal_predictor(&mut block_data, image.block_width, block_samples, bits_per_sample as usize / 8, r.configs.endian)?,
+                        PREDICTOR_FLOATINGPOINT => apply_floating_point_predictor(&mut block_data, image.block_width, block_samples, bits_per_sample as usize / 8, r.configs.endian)?,
+                        _ => {}
+                    }
+
+                    let num_bytes = match compression {
+                        COMPRESS_DEFLATE => {
+                            let compressed = deflate_encoder(&block_data)?;
+                            writer.write_all(&compressed)?;
+                            compressed.len()
                         }
-                    },
-                    DataType::I16 => {
-                        let mut i: usize;
-                        for row in 0..r.configs.rows {
-                            for col in 0..r.configs.columns {
-                                i = row * r.configs.columns + col;
-                                writer.write_i16::<BigEndian>(r.data[i] as i16)?;
-                            }
+                        COMPRESS_LZW => {
+                            let compressed = lzw_encoder(&block_data);
+                            writer.write_all(&compressed)?;
+                            compressed.len()
                         }
-                    },
-                    DataType::I8 => {
-                        let mut i: usize;
-                        for row in 0..r.configs.rows {
-                            for col in 0..r.configs.columns {
-                                i = row * r.configs.columns + col;
-                                writer.write(&[r.data[i] as u8])?;
+                        COMPRESS_PACKBITS => {
+                            let mut compressed: Vec<u8> = Vec::with_capacity(block_data.len());
+                            for row_data in block_data.chunks(row_length_in_bytes) {
+                                packbits_encoder(row_data, &mut compressed);
                             }
+                            writer.write_all(&compressed)?;
+                            compressed.len()
                         }
-                    },
-                    _ => {
-                        return Err(Error::new(ErrorKind::InvalidData, "Unknown data type."));
-                    },
-                }
-            },
-            PhotometricInterpretation::RGB => {
-                match r.configs.data_type {
-                    DataType::RGB24 => {
-                        let mut bytes: [u8; 3] = [0u8; 3];
-                        let mut i: usize;
-                        for row in 0..r.configs.rows {
-                            for col in 0..r.configs.columns {
-                                i = row * r.configs.columns + col;
-                                // writer.write_u24::<BigEndian>(r.data[i] as u32)?;
-                                let val = r.data[i] as u32;
-                                bytes[0] = ((val >> 16u32) & 0xFF) as u8; // red
-                                bytes[1] = ((val >> 8u32) & 0xFF) as u8; // green
-                                bytes[2] = (val & 0xFF) as u8; // blue
-                                writer.write(&bytes)?;
-                            }
-                        }
-                    },
-                    DataType::RGBA32 => {
-                        let mut i: usize;
-                        for row in 0..r.configs.rows {
-                            for col in 0..r.configs.columns {
-                                i = row * r.configs.columns + col;
-                                let val = r.data[i] as u32;
-                                let val2 = ((val >> 24u32) & 0xFF) | ((val >> 16u32) & 0xFF) | ((val >> 8u32) & 0xFF) | (val & 0xFF);
-                                writer.write_u32::<BigEndian>(val2)?;
-                            }
-                        }
-                    },
-                    _ => {
-                        return Err(Error::new(ErrorKind::InvalidData, "Unknown data type."));
-                    },
-                }
-            },
-            PhotometricInterpretation::Paletted => {
-                return Err(Error::new(ErrorKind::InvalidData,
-                                    "Paletted GeoTIFFs are currently unsupported for writing."));
-            },
-            PhotometricInterpretation::Unknown => {
-                return Err(Error::new(ErrorKind::InvalidData, "Error while writing GeoTIFF file."));
-            },
-        }
-
-        // This is just because the IFD must start on a word (i.e. an even value). If the data are
-        // single bytes, then this may not be the case.
-        if ifd_start_needs_extra_byte {
-            writer.write_u8(0u8)?;
-        }
-
-        ////////////////////////////
-        // Create the IFD entries //
-        ////////////////////////////
-
-        let mut ifd_entries: Vec<IfdEntry> = vec![];
-        let mut larger_values_data: Vec<u8> = vec![];
-
-        /* 
-        IFD entries
-
-        Bytes 0-1 The Tag that identifies the field.
-        Bytes 2-3 The field Type.
-        Bytes 4-7 The number of values, Count of the indicated Type.
-        Bytes 8-11 The Value Offset, the file offset (in bytes) of the Value for the field.
-        The Value is expected to begin on a word boundary; the corresponding
-        Value Offset will thus be an even number. This file offset may
-        point anywhere in the file, even after the image data. 
-
-        To save time and space the Value Offset contains the Value instead of pointing to
-        the Value if and only if the Value fits into 4 bytes. If the Value is shorter than 4
-        bytes, it is left-justified within the 4-byte Value Offset, i.e., stored in the lowernumbered
-        bytes. Whether the Value fits within 4 bytes is determined by the Type
-        and Count of the field.
-        */
-
-        // ImageWidth tag (256)
-        ifd_entries.push(IfdEntry::new(TAG_IMAGEWIDTH, DT_LONG, 1u32, r.configs.columns as u32));
-
-        // ImageLength tag (257)
-        ifd_entries.push(IfdEntry::new(TAG_IMAGELENGTH, DT_LONG, 1u32, r.configs.rows as u32));
-
-        let bits_per_sample = match r.configs.data_type {
-            DataType::I8 | DataType::U8 => 8u16,
-            DataType::I16 | DataType::U16 => 16u16,
-            DataType::I32 | DataType::U32 | DataType::F32 => 32u16,
-            DataType::I64 | DataType::U64 | DataType::F64 => 64u16,
-            DataType::RGB24 => 8u16,
-            DataType::RGBA32 => 8u16,
-            DataType::RGB48 => 16u16,
-            _ => {
-                return Err(Error::new(ErrorKind::InvalidData, "Unknown data type."));
-            }
-        };
-
-        let samples_per_pixel = match r.configs.data_type {
-            DataType::I8 | DataType::U8 => 1u16,
-            DataType::I16 | DataType::U16 => 1u16,
-            DataType::I32 | DataType::U32 | DataType::F32 => 1u16,
-            DataType::I64 | DataType::U64 | DataType::F64 => 1u16,
-            DataType::RGB24 => 3u16,
-            DataType::RGBA32 => 4u16,
-            DataType::RGB48 => 3u16,
-            _ => {
-                return Err(Error::new(ErrorKind::InvalidData, "Unknown data type."));
-            }
-        };
+                        _ => {
+                            writer.write_all(&block_data)?;
+                            block_data.len()
+                        }
+                    };
 
-        // BitsPerSample tag (258)
-        if r.configs.photometric_interp != PhotometricInterpretation::Boolean {
-            if samples_per_pixel == 1 {
-                ifd_entries.push(IfdEntry::new(TAG_BITSPERSAMPLE, DT_SHORT, samples_per_pixel as u32, bits_per_sample as u32));
-            } else {
-                ifd_entries.push(IfdEntry::new(TAG_BITSPERSAMPLE, DT_SHORT, samples_per_pixel as u32, larger_values_data.len() as u32));
-                for _ in 0..samples_per_pixel {
-                    let _ = larger_values_data.write_u16::<BigEndian>(bits_per_sample);
+                    image.block_offsets.push(current_offset as u64);
+                    image.block_byte_counts.push(num_bytes as u64);
+                    current_offset += num_bytes;
                 }
             }
-            
         }
+    }
+
+    if !is_big_tiff && current_offset > u32::MAX as usize {
+        return Err(Error::new(ErrorKind::InvalidData, "The raster is too large to be written as a GeoTIFF file."));
+    }
 
-        // Compression tag (259)
-        ifd_entries.push(IfdEntry::new(TAG_COMPRESSION, DT_SHORT, 1u32, COMPRESS_NONE as u32));
+    ////////////////////
+    // Write the IFDs //
+    ////////////////////
 
-        // PhotometricInterpretation tag (262)
-        let pi = match r.configs.photometric_interp {
-            PhotometricInterpretation::Continuous => PI_BLACKISZERO,
-            PhotometricInterpretation::Categorical | PhotometricInterpretation::Paletted => PI_PALETTED,
-            PhotometricInterpretation::Boolean => PI_BLACKISZERO,
-            PhotometricInterpretation::RGB => PI_RGB,
-            PhotometricInterpretation::Unknown => {
-                return Err(Error::new(ErrorKind::InvalidData, "Error while writing GeoTIFF file. Unknown Photometric Interpretation."));
-            },
-        };
-        ifd_entries.push(IfdEntry::new(TAG_PHOTOMETRICINTERPRETATION, DT_SHORT, 1u32, pi as u32));
+    // The full-resolution image is first in the chain of IFDs, followed by the overviews.
+    writer.seek(SeekFrom::Start(header_size as u64))?;
+    for i in 0..images.len() {
+        let next_ifd_offset = if i < images.len() - 1 { ifd_starts[i + 1] } else { 0u64 };
+        let ifd = build_ifd::<T>(r, &images[i], &encoding, ifd_starts[i], next_ifd_offset)?;
+        writer.write_all(&ifd)?;
+    }
+
+    writer.flush()?;
+
+    Ok(())
+}
+
+// An image stored within a TIFF file, i.e. either the full-resolution raster or one of its overviews.
+struct TiffImage<'a> {
+    columns: usize,
+    rows: usize,
+    data: &'a RasterData,
+    is_overview: bool,
+    is_tiled: bool,
+    block_width: usize,
+    block_height: usize,
+    block_offsets: Vec<u64>,
+    block_byte_counts: Vec<u64>,
+}
+
+impl<'a> TiffImage<'a> {
+    // An image stored in strips spanning the full width of the image.
+    fn strips(columns: usize, rows: usize, data: &'a RasterData, rows_per_strip: usize) -> TiffImage<'a> {
+        TiffImage {
+            columns,
+            rows,
+            data,
+            is_overview: false,
+            is_tiled: false,
+            block_width: max(1usize, columns),
+            block_height: rows_per_strip,
+            block_offsets: vec![],
+            block_byte_counts: vec![],
+        }
+    }
 
-        // StripOffsets tag (273)
-        ifd_entries.push(IfdEntry::new(TAG_STRIPOFFSETS, DT_LONG, r.configs.columns as u32, larger_values_data.len() as u32));
-        let row_length_in_bytes: u32 = r.configs.columns as u32 * total_bytes_per_pixel as u32;
-        for i in 0..r.configs.rows as u32 {
-            let _ = larger_values_data.write_u32::<BigEndian>(8u32 + row_length_in_bytes * i);
+    // An image stored in square tiles.
+    fn tiles(columns: usize, rows: usize, data: &'a RasterData, is_overview: bool) -> TiffImage<'a> {
+        TiffImage {
+            columns,
+            rows,
+            data,
+            is_overview,
+            is_tiled: true,
+            block_width: TILE_SIZE,
+            block_height: TILE_SIZE,
+            block_offsets: vec![],
+            block_byte_counts: vec![],
         }
+    }
+
+    fn blocks_across(&self) -> usize {
+        (self.columns + self.block_width - 1) / self.block_width
+    }
 
-        // SamplesPerPixel tag (277)
-        ifd_entries.push(IfdEntry::new(TAG_SAMPLESPERPIXEL, DT_SHORT, 1u32, samples_per_pixel as u32));
+    fn blocks_down(&self) -> usize {
+        (self.rows + self.block_height - 1) / self.block_height
+    }
 
-        // RowsPerStrip tag (278)
-        ifd_entries.push(IfdEntry::new(TAG_ROWSPERSTRIP, DT_SHORT, 1u32, 1u32));
-
-        // StripByteCounts tag (279)
-        ifd_entries.push(IfdEntry::new(TAG_STRIPBYTECOUNTS, DT_LONG, r.configs.columns as u32, larger_values_data.len() as u32));
-        let total_bytes_per_pixel = match r.configs.data_type {
-            DataType::I8 | DataType::U8 => 1u32,
-            DataType::I16 | DataType::U16 => 2u32,
-            DataType::I32 | DataType::U32 | DataType::F32 => 4u32,
-            DataType::I64 | DataType::U64 | DataType::F64 => 8u32,
-            DataType::RGB24 => 3u32,
-            DataType::RGBA32 => 4u32,
-            DataType::RGB48 => 6u32,
-            _ => {
-                return Err(Error::new(ErrorKind::InvalidData, "Unknown data type."));
+    fn num_blocks(&self) -> usize {
+        self.blocks_across() * self.blocks_down()
+    }
+}
+
+// The properties of the encoded image data that are shared by all of the images in a file.
+struct TiffEncoding {
+    bits_per_sample: u16,
+    samples_per_pixel: u16,
+    planes: usize, // the number of separately stored bands, i.e. 1 unless the planar configuration is used
+    compression: u16,
+    predictor: u16,
+    is_big_tiff: bool,
+}
+
+// Creates the IFD describing an image, followed by the values of any fields that are too
+// large to be stored within the IFD itself. The IFD is written at ifd_start within the file.
+fn build_ifd<'a, T: ByteOrder>(r: &'a Raster, image: &TiffImage, encoding: &TiffEncoding, ifd_start: u64, next_ifd_offset: u64) -> Result<Vec<u8>, Error> {
+    let is_big_tiff = encoding.is_big_tiff;
+    let samples_per_pixel = encoding.samples_per_pixel;
+    let num_bands = r.num_bands();
+    let num_blocks = image.num_blocks() * encoding.planes;
+
+    // Block offsets and byte counts are stored as 64-bit values in BigTIFFs. The offsets
+    // are not known when the size of the IFD is first measured and zeros are used instead.
+    let offsets_type = if is_big_tiff { DT_LONG8 } else { DT_LONG };
+    let block_offsets = if image.block_offsets.len() == num_blocks { image.block_offsets.clone() } else { vec![0u64; num_blocks] };
+    let block_byte_counts = if image.block_byte_counts.len() == num_blocks { image.block_byte_counts.clone() } else { vec![0u64; num_blocks] };
+
+    ////////////////////////////
+    // Create the IFD entries //
+    ////////////////////////////
+
+    let mut ifd_entries: Vec<IfdEntry> = vec![];
+    let mut larger_values_data: Vec<u8> = vec![];
+
+    /* 
+    IFD entries
+
+    Bytes 0-1 The Tag that identifies the field.
+    Bytes 2-3 The field Type.
+    Bytes 4-7 The number of values, Count of the indicated Type.
+    Bytes 8-11 The Value Offset, the file offset (in bytes) of the Value for the field.
+    The Value is expected to begin on a word boundary; the corresponding
+    Value Offset will thus be an even number. This file offset may
+    point anywhere in the file, even after the image data. 
+
+    To save time and space the Value Offset contains the Value instead of pointing to
+    the Value if and only if the Value fits into 4 bytes. If the Value is shorter than 4
+    bytes, it is left-justified within the 4-byte Value Offset, i.e., stored in the lowernumbered
+    bytes. Whether the Value fits within 4 bytes is determined by the Type
+    and Count of the field.
+    */
+
+    if image.is_overview {
+        // NewSubfileType tag (254); a reduced-resolution version of another image in the file.
+        ifd_entries.push(IfdEntry::new(TAG_NEWSUBFILETYPE, DT_LONG, 1u32, 1u64));
+    }
+
+    // ImageWidth tag (256)
+    ifd_entries.push(IfdEntry::new(TAG_IMAGEWIDTH, DT_LONG, 1u32, image.columns as u64));
+
+    // ImageLength tag (257)
+    ifd_entries.push(IfdEntry::new(TAG_IMAGELENGTH, DT_LONG, 1u32, image.rows as u64));
+
+    // BitsPerSample tag (258)
+    if r.configs.photometric_interp != PhotometricInterpretation::Boolean {
+        if samples_per_pixel == 1 {
+            ifd_entries.push(IfdEntry::new(TAG_BITSPERSAMPLE, DT_SHORT, samples_per_pixel as u32, encoding.bits_per_sample as u64));
+        } else {
+            ifd_entries.push(IfdEntry::new(TAG_BITSPERSAMPLE, DT_SHORT, samples_per_pixel as u32, larger_values_data.len() as u64));
+            for _ in 0..samples_per_pixel {
+                let _ = larger_values_data.write_u16::<T>(encoding.bits_per_sample);
             }
-        };
-        let row_length_in_bytes: u32 = r.configs.columns as u32 * total_bytes_per_pixel;
-        for _ in 0..r.configs.rows as u32 {
-            let _ = larger_values_data.write_u32::<BigEndian>(row_length_in_bytes);
         }
         
+    }
+
+    // Compression tag (259)
+    ifd_entries.push(IfdEntry::new(TAG_COMPRESSION, DT_SHORT, 1u32, encoding.compression as u64));
+
+    // PhotometricInterpretation tag (262)
+    let pi = match r.configs.photometric_interp {
+        _ if num_bands > 1 => PI_BLACKISZERO,
+        PhotometricInterpretation::Continuous => PI_BLACKISZERO,
+        // No ColorMap (320) is written, so categorical data are stored as grey-scale values;
+        // a Paletted interpretation without a colour map could not be read back.
+        PhotometricInterpretation::Categorical => PI_BLACKISZERO,
+        PhotometricInterpretation::Paletted => {
+            return Err(Error::new(ErrorKind::InvalidData, "Paletted GeoTIFFs are currently unsupported for writing."));
+        },
+        PhotometricInterpretation::Boolean => PI_BLACKISZERO,
+        PhotometricInterpretation::RGB => PI_RGB,
+        // e.g. rasters initialized from formats that don't record a data scale
+        PhotometricInterpretation::Unknown => PI_BLACKISZERO,
+    };
+    ifd_entries.push(IfdEntry::new(TAG_PHOTOMETRICINTERPRETATION, DT_SHORT, 1u32, pi as u64));
+
+    // StripOffsets (273) or TileOffsets (324) tag
+    let offsets_tag = if image.is_tiled { TAG_TILEOFFSETS } else { TAG_STRIPOFFSETS };
+    if num_blocks == 1 {
+        ifd_entries.push(IfdEntry::new(offsets_tag, offsets_type, 1u32, block_offsets[0]));
+    } else {
+        ifd_entries.push(IfdEntry::new(offsets_tag, offsets_type, num_blocks as u32, larger_values_data.len() as u64));
+        for offset in &block_offsets {
+            if is_big_tiff {
+                let _ = larger_values_data.write_u64::<T>(*offset);
+            } else {
+                let _ = larger_values_data.write_u32::<T>(*offset as u32);
+            }
+        }
+    }
+
+    // SamplesPerPixel tag (277)
+    ifd_entries.push(IfdEntry::new(TAG_SAMPLESPERPIXEL, DT_SHORT, 1u32, samples_per_pixel as u64));
+
+    if image.is_tiled {
+        // TileWidth tag (322)
+        ifd_entries.push(IfdEntry::new(TAG_TILEWIDTH, DT_LONG, 1u32, image.block_width as u64));
+
+        // TileLength tag (323)
+        ifd_entries.push(IfdEntry::new(TAG_TILELENGTH, DT_LONG, 1u32, image.block_height as u64));
+    } else {
+        // RowsPerStrip tag (278)
+        ifd_entries.push(IfdEntry::new(TAG_ROWSPERSTRIP, DT_LONG, 1u32, image.block_height as u64));
+    }
+
+    // StripByteCounts (279) or TileByteCounts (325) tag
+    let byte_counts_tag = if image.is_tiled { TAG_TILEBYTECOUNTS } else { TAG_STRIPBYTECOUNTS };
+    if num_blocks == 1 {
+        ifd_entries.push(IfdEntry::new(byte_counts_tag, offsets_type, 1u32, block_byte_counts[0]));
+    } else {
+        ifd_entries.push(IfdEntry::new(byte_counts_tag, offsets_type, num_blocks as u32, larger_values_data.len() as u64));
+        for count in &block_byte_counts {
+            if is_big_tiff {
+                let _ = larger_values_data.write_u64::<T>(*count);
+            } else {
+                let _ = larger_values_data.write_u32::<T>(*count as u32);
+            }
+        }
+    }
+    
+    if !image.is_overview {
         // There is currently no support for storing the image resolution, so give a bogus value of 72x72 dpi.
         // XResolution tag (282)
-        ifd_entries.push(IfdEntry::new(TAG_XRESOLUTION, DT_RATIONAL, 1u32, larger_values_data.len() as u32));
-        let _ = larger_values_data.write_u32::<BigEndian>(72u32);
-        let _ = larger_values_data.write_u32::<BigEndian>(1u32);
+        ifd_entries.push(IfdEntry::new(TAG_XRESOLUTION, DT_RATIONAL, 1u32, larger_values_data.len() as u64));
+        let _ = larger_values_data.write_u32::<T>(72u32);
+        let _ = larger_values_data.write_u32::<T>(1u32);
 
         // YResolution tag (283)
-        ifd_entries.push(IfdEntry::new(TAG_YRESOLUTION, DT_RATIONAL, 1u32, larger_values_data.len() as u32));
-        let _ = larger_values_data.write_u32::<BigEndian>(72u32);
-        let _ = larger_values_data.write_u32::<BigEndian>(1u32);
+        ifd_entries.push(IfdEntry::new(TAG_YRESOLUTION, DT_RATIONAL, 1u32, larger_values_data.len() as u64));
+        let _ = larger_values_data.write_u32::<T>(72u32);
+        let _ = larger_values_data.write_u32::<T>(1u32);
 
         // ResolutionUnit tag (296)
-        ifd_entries.push(IfdEntry::new(TAG_RESOLUTIONUNIT, DT_SHORT, 1u32, 2u32));
+        ifd_entries.push(IfdEntry::new(TAG_RESOLUTIONUNIT, DT_SHORT, 1u32, 2u64));
 
         // Software tag (305)
         let software = "WhiteboxTools".to_owned();
         let mut soft_bytes = software.into_bytes();
         soft_bytes.push(0);
-        ifd_entries.push(IfdEntry::new(TAG_SOFTWARE, DT_ASCII, soft_bytes.len() as u32, larger_values_data.len() as u32));
+        ifd_entries.push(IfdEntry::new(TAG_SOFTWARE, DT_ASCII, soft_bytes.len() as u32, larger_values_data.len() as u64));
         let _ = larger_values_data.write_all(&soft_bytes);
+    }
 
-        // SampleFormat tag (339)
-        let samples_format = match r.configs.data_type {
-            DataType::U8 | DataType::U16 | DataType::U32 | DataType::U64 => 1u16,
-            DataType::I8 | DataType::I16 | DataType::I32 | DataType::I64 => 2u16,
-            DataType::F32 | DataType::F64 => 3u16,
-            DataType::RGB24 | DataType::RGBA32 | DataType::RGB48 => 1u16,
-            _ => {
-                return Err(Error::new(ErrorKind::InvalidData, "Unknown data type."));
-            }
-        };
-        if samples_per_pixel == 1 {
-            ifd_entries.push(IfdEntry::new(TAG_SAMPLEFORMAT, DT_SHORT, samples_per_pixel as u32, samples_format as u32));
+    if encoding.predictor != PREDICTOR_NONE {
+        // Predictor tag (317)
+        ifd_entries.push(IfdEntry::new(TAG_PREDICTOR, DT_SHORT, 1u32, encoding.predictor as u64));
+    }
+
+    if num_bands > 1 {
+        // PlanarConfiguration tag (284)
+        ifd_entries.push(IfdEntry::new(TAG_PLANARCONFIGURATION, DT_SHORT, 1u32, if encoding.planes > 1 { 2u64 } else { 1u64 }));
+
+        // ExtraSamples tag (338); the bands beyond the first are of unspecified type.
+        if num_bands == 2 {
+            ifd_entries.push(IfdEntry::new(TAG_EXTRASAMPLES, DT_SHORT, 1u32, 0u64));
         } else {
-            ifd_entries.push(IfdEntry::new(TAG_SAMPLEFORMAT, DT_SHORT, samples_per_pixel as u32, larger_values_data.len() as u32));
-            for _ in 0..samples_per_pixel {
-                let _ = larger_values_data.write_u16::<BigEndian>(samples_format);
+            ifd_entries.push(IfdEntry::new(TAG_EXTRASAMPLES, DT_SHORT, (num_bands - 1) as u32, larger_values_data.len() as u64));
+            for _ in 1..num_bands {
+                let _ = larger_values_data.write_u16::<T>(0u16);
             }
         }
+    } else if samples_per_pixel == 4 {
+        // ExtraSamples tag (338)
+        ifd_entries.push(IfdEntry::new(TAG_EXTRASAMPLES, DT_SHORT, 1u32, 2u64));
+    }
+    
+    // SampleFormat tag (339)
+    let samples_format = match r.configs.data_type {
+        DataType::U8 | DataType::U16 | DataType::U32 | DataType::U64 => 1u16,
+        DataType::I8 | DataType::I16 | DataType::I32 | DataType::I64 => 2u16,
+        DataType::F32 | DataType::F64 => 3u16,
+        DataType::RGB24 | DataType::RGBA32 | DataType::RGB48 => 1u16,
+        _ => {
+            return Err(Error::new(ErrorKind::InvalidData, "Unknown data type."));
+        }
+    };
+    if samples_per_pixel == 1 {
+        ifd_entries.push(IfdEntry::new(TAG_SAMPLEFORMAT, DT_SHORT, samples_per_pixel as u32, samples_format as u64));
+    } else {
+        ifd_entries.push(IfdEntry::new(TAG_SAMPLEFORMAT, DT_SHORT, samples_per_pixel as u32, larger_values_data.len() as u64));
+        for _ in 0..samples_per_pixel {
+            let _ = larger_values_data.write_u16::<T>(samples_format);
+        }
+    }
+
+    // TAG_GDAL_NODATA tag (42113)
+    let nodata_str = format!("{}", r.configs.nodata);
+    let mut nodata_bytes = nodata_str.into_bytes();
+    nodata_bytes.push(0);
+    ifd_entries.push(IfdEntry::new(TAG_GDAL_NODATA, DT_ASCII, nodata_bytes.len() as u32, larger_values_data.len() as u64));
+    if nodata_bytes.len() % 2 == 1 {
+        nodata_bytes.push(0);
+    }
+    let _ = larger_values_data.write_all(&nodata_bytes);
 
+    // Overviews share the georeferencing of the full-resolution image.
+    if !image.is_overview {
         // ModelTiepointTag tag (33550)
-        ifd_entries.push(IfdEntry::new(TAG_MODELPIXELSCALETAG, DT_DOUBLE, 3u32, larger_values_data.len() as u32));
-        let _ = larger_values_data.write_f64::<BigEndian>(r.configs.resolution_x);
-        let _ = larger_values_data.write_f64::<BigEndian>(r.configs.resolution_y);
-        let _ = larger_values_data.write_f64::<BigEndian>(0f64);
-        
+        ifd_entries.push(IfdEntry::new(TAG_MODELPIXELSCALETAG, DT_DOUBLE, 3u32, larger_values_data.len() as u64));
+        let _ = larger_values_data.write_f64::<T>(r.configs.resolution_x);
+        let _ = larger_values_data.write_f64::<T>(r.configs.resolution_y);
+        let _ = larger_values_data.write_f64::<T>(0f64);
+
         // ModelPixelScaleTag tag (33922)
-        ifd_entries.push(IfdEntry::new(TAG_MODELTIEPOINTTAG, DT_DOUBLE, 6u32, larger_values_data.len() as u32));
-        let _ = larger_values_data.write_f64::<BigEndian>(0f64); // I
-        let _ = larger_values_data.write_f64::<BigEndian>(0f64); // J
-        let _ = larger_values_data.write_f64::<BigEndian>(0f64); // K
-        let _ = larger_values_data.write_f64::<BigEndian>(r.configs.west); // X
-        let _ = larger_values_data.write_f64::<BigEndian>(r.configs.north); // Y
-        let _ = larger_values_data.write_f64::<BigEndian>(0f64); // Z
-
-        // TAG_GDAL_NODATA tag (42113)
-        let nodata_str = format!("{}", r.configs.nodata);
-        let mut nodata_bytes = nodata_str.into_bytes();
-        nodata_bytes.push(0);
-        ifd_entries.push(IfdEntry::new(TAG_GDAL_NODATA, DT_ASCII, nodata_bytes.len() as u32, larger_values_data.len() as u32));
-        if nodata_bytes.len() % 2 == 1 {
-            nodata_bytes.push(0);
-        }
-        let _ = larger_values_data.write_all(&nodata_bytes);
-        
+        ifd_entries.push(IfdEntry::new(TAG_MODELTIEPOINTTAG, DT_DOUBLE, 6u32, larger_values_data.len() as u64));
+        let _ = larger_values_data.write_f64::<T>(0f64); // I
+        let _ = larger_values_data.write_f64::<T>(0f64); // J
+        let _ = larger_values_data.write_f64::<T>(0f64); // K
+        let _ = larger_values_data.write_f64::<T>(r.configs.west); // X
+        let _ = larger_values_data.write_f64::<T>(r.configs.north); // Y
+        let _ = larger_values_data.write_f64::<T>(0f64); // Z
+
         let kw_map = get_keyword_map();
         let geographic_type_map = match kw_map.get(&2048u16) {
             Some(map) => map,
@@ -1948,9 +1741,14 @@ pub fn write_geotiff<'a>(r: &'a mut Raster) -> Result<(), Error> {
         };
 
         //let key_map = get_keys_map();
+        let srs = SpatialRef::from_epsg_or_wkt(r.configs.epsg_code, &r.configs.coordinate_ref_system_wkt);
+        let is_known_projected_code = match srs {
+            Some(ref s) => r.configs.epsg_code != 0 && s.epsg_code == r.configs.epsg_code && !s.is_geographic(),
+            None => false,
+        };
         let mut gk_entries: Vec<GeoKeyEntry> = vec![];
         let mut ascii_params = String::new(); //: Vec<u8> = vec![];
-        let double_params: Vec<f64> = vec![];
+        let mut double_params: Vec<f64> = vec![];
         if geographic_type_map.contains_key(&r.configs.epsg_code) {
             // tGTModelTypeGeoKey (1024)
             gk_entries.push(GeoKeyEntry{ tag: TAG_GTMODELTYPEGEOKEY, location: 0u16, count: 1u16, value_offset: 2u16 });
@@ -1981,7 +1779,7 @@ pub fn write_geotiff<'a>(r: &'a mut Raster) -> Result<(), Error> {
                     gk_entries.push(GeoKeyEntry{ tag: TAG_VERTICALUNITSGEOKEY, location: 0u16, count: 1u16, value_offset: 9002u16 });
                 }
             }
-        } else if projected_cs_type_map.contains_key(&r.configs.epsg_code) {
+        } else if projected_cs_type_map.contains_key(&r.configs.epsg_code) || is_known_projected_code {
             // tGTModelTypeGeoKey (1024)
             gk_entries.push(GeoKeyEntry{ tag: TAG_GTMODELTYPEGEOKEY, location: 0u16, count: 1u16, value_offset: 1u16 });
             
@@ -1996,7 +1794,10 @@ pub fn write_geotiff<'a>(r: &'a mut Raster) -> Result<(), Error> {
             gk_entries.push(GeoKeyEntry{ tag: TAG_PROJECTEDCSTYPEGEOKEY, location: 0u16, count: 1u16, value_offset: r.configs.epsg_code });
             
             // PCSCitationGeoKey (3073)
-            let mut v = String::from(projected_cs_type_map.get(&r.configs.epsg_code).unwrap().clone());
+            let mut v = match projected_cs_type_map.get(&r.configs.epsg_code) {
+                Some(name) => name.to_string(),
+                None => srs.as_ref().map(|s| s.name.clone()).unwrap_or_default(),
+            };
             v.push_str("|");
             v = v.replace("_", " ");
             gk_entries.push(GeoKeyEntry{ tag: 3073u16, location: 34737u16, count: v.len() as u16, value_offset: ascii_params.len() as u16 });
@@ -2021,6 +1822,31 @@ pub fn write_geotiff<'a>(r: &'a mut Raster) -> Result<(), Error> {
                     gk_entries.push(GeoKeyEntry{ tag: TAG_VERTICALUNITSGEOKEY, location: 0u16, count: 1u16, value_offset: 9002u16 });
                 }
             }
+        } else if let Some(ref srs) = srs {
+            // a user-defined coordinate reference system, e.g. one read from WKT
+            for (key, value) in srs.to_geokeys() {
+                match value {
+                    GeoKeyValue::Short(v) => {
+                        gk_entries.push(GeoKeyEntry{ tag: key, location: 0u16, count: 1u16, value_offset: v });
+                    },
+                    GeoKeyValue::Double(v) => {
+                        gk_entries.push(GeoKeyEntry{ tag: key, location: 34736u16, count: 1u16, value_offset: double_params.len() as u16 });
+                        double_params.push(v);
+                    },
+                    GeoKeyValue::Ascii(mut v) => {
+                        v.push_str("|");
+                        gk_entries.push(GeoKeyEntry{ tag: key, location: 34737u16, count: v.len() as u16, value_offset: ascii_params.len() as u16 });
+                        ascii_params.push_str(&v);
+                    },
+                }
+            }
+
+            // GTRasterTypeGeoKey (1025)
+            if r.configs.pixel_is_area {
+                gk_entries.push(GeoKeyEntry{ tag: TAG_GTRASTERTYPEGEOKEY, location: 0u16, count: 1u16, value_offset: 1u16 });
+            } else {
+                gk_entries.push(GeoKeyEntry{ tag: TAG_GTRASTERTYPEGEOKEY, location: 0u16, count: 1u16, value_offset: 2u16 });
+            }
         } else {
             // we don't know much about the coordinate system used.
             
@@ -2036,25 +1862,28 @@ pub fn write_geotiff<'a>(r: &'a mut Raster) -> Result<(), Error> {
             
         }
 
+        // the keys must be sorted by their IDs
+        gk_entries.sort_by_key(|e| e.tag);
+
         // create the GeoKeyDirectoryTag tag (34735)
-        ifd_entries.push(IfdEntry::new(TAG_GEOKEYDIRECTORYTAG, DT_SHORT, (4 + gk_entries.len() * 4) as u32, larger_values_data.len() as u32));
-        let _ = larger_values_data.write_u16::<BigEndian>(1u16); // KeyDirectoryVersion
-        let _ = larger_values_data.write_u16::<BigEndian>(1u16); // KeyRevision
-        let _ = larger_values_data.write_u16::<BigEndian>(0u16); // MinorRevision
-        let _ = larger_values_data.write_u16::<BigEndian>(gk_entries.len() as u16); // NumberOfKeys
+        ifd_entries.push(IfdEntry::new(TAG_GEOKEYDIRECTORYTAG, DT_SHORT, (4 + gk_entries.len() * 4) as u32, larger_values_data.len() as u64));
+        let _ = larger_values_data.write_u16::<T>(1u16); // KeyDirectoryVersion
+        let _ = larger_values_data.write_u16::<T>(1u16); // KeyRevision
+        let _ = larger_values_data.write_u16::<T>(0u16); // MinorRevision
+        let _ = larger_values_data.write_u16::<T>(gk_entries.len() as u16); // NumberOfKeys
 
         for entry in gk_entries {
-            let _ = larger_values_data.write_u16::<BigEndian>(entry.tag); // KeyID
-            let _ = larger_values_data.write_u16::<BigEndian>(entry.location); // TIFFTagLocation
-            let _ = larger_values_data.write_u16::<BigEndian>(entry.count); // Count
-            let _ = larger_values_data.write_u16::<BigEndian>(entry.value_offset); // Value_Offset
+            let _ = larger_values_data.write_u16::<T>(entry.tag); // KeyID
+            let _ = larger_values_data.write_u16::<T>(entry.location); // TIFFTagLocation
+            let _ = larger_values_data.write_u16::<T>(entry.count); // Count
+            let _ = larger_values_data.write_u16::<T>(entry.value_offset); // Value_Offset
         }
 
         if double_params.len() > 0 {
             // create the GeoDoubleParamsTag tag (34736)
-            ifd_entries.push(IfdEntry::new(TAG_GEODOUBLEPARAMSTAG, DT_DOUBLE, double_params.len() as u32, larger_values_data.len() as u32));
+            ifd_entries.push(IfdEntry::new(TAG_GEODOUBLEPARAMSTAG, DT_DOUBLE, double_params.len() as u32, larger_values_data.len() as u64));
             for double_val in double_params {
-                let _ = larger_values_data.write_f64::<BigEndian>(double_val);
+                let _ = larger_values_data.write_f64::<T>(double_val);
             }
         }
 
@@ -2062,704 +1891,305 @@ pub fn write_geotiff<'a>(r: &'a mut Raster) -> Result<(), Error> {
             // create the GeoAsciiParamsTag tag (34737)
             let mut ascii_params_bytes = ascii_params.into_bytes();
             ascii_params_bytes.push(0);
-            ifd_entries.push(IfdEntry::new(TAG_GEOASCIIPARAMSTAG, DT_ASCII, ascii_params_bytes.len() as u32, larger_values_data.len() as u32));
+            ifd_entries.push(IfdEntry::new(TAG_GEOASCIIPARAMSTAG, DT_ASCII, ascii_params_bytes.len() as u32, larger_values_data.len() as u64));
             if ascii_params_bytes.len() % 2 == 1 {
                 // it has to end on a word so that the next value starts on a word
                 ascii_params_bytes.push(0);
             }
             let _ = larger_values_data.write_all(&ascii_params_bytes);
         }
+    }
 
-        ///////////////////
-        // Write the IFD //
-        ///////////////////
+    ///////////////////
+    // Write the IFD //
+    ///////////////////
 
-        // Number of Directory Entries.
-        writer.write_u16::<BigEndian>(ifd_entries.len() as u16)?;
+    // Sort the IFD entries
+    ifd_entries.sort_by(|a, b| a.tag.cmp(&b.tag));
 
-        // Sort the IFD entries
-        ifd_entries.sort_by(|a, b| a.tag.cmp(&b.tag));
-        
-        // Write the entries
-        let ifd_length = 2u32 + ifd_entries.len() as u32 * 12u32 + 4u32;
-
-        for ifde in ifd_entries {
-            writer.write_u16::<BigEndian>(ifde.tag)?; // Tag
-            writer.write_u16::<BigEndian>(ifde.ifd_type)?; // Field type
-            writer.write_u32::<BigEndian>(ifde.num_values)?; // Num of values
-            if ifde.ifd_type == DT_SHORT && ifde.num_values == 1 {
-                // it's a value
-                writer.write_u16::<BigEndian>(ifde.offset as u16)?; // Value
-                writer.write_u16::<BigEndian>(0u16)?; // Fill the remaining 2 right bytes of the u32
-            } else if ifde.ifd_type == DT_LONG && ifde.num_values == 1 {
-                // it's a value
-                writer.write_u32::<BigEndian>(ifde.offset)?;
-            } else if ifde.ifd_type == DT_SHORT && ifde.num_values == 2 {
-                // I'm not really sure about this one. Two shorts will fit in the value_offset, but will they be interpreted correctly?
-                writer.write_u32::<BigEndian>(ifde.offset)?; // Value
+    // BigTIFF IFDs use 8-byte entry counts, 20-byte entries and 8-byte offsets.
+    let value_offset_size = if is_big_tiff { 8usize } else { 4usize };
+    let ifd_length = if is_big_tiff {
+        8u64 + ifd_entries.len() as u64 * 20u64 + 8u64
+    } else {
+        2u64 + ifd_entries.len() as u64 * 12u64 + 4u64
+    };
+
+    let mut writer: Vec<u8> = Vec::with_capacity(ifd_length as usize + larger_values_data.len());
+
+    // Number of Directory Entries.
+    if is_big_tiff {
+        writer.write_u64::<T>(ifd_entries.len() as u64)?;
+    } else {
+        writer.write_u16::<T>(ifd_entries.len() as u16)?;
+    }
+    
+    // Write the entries
+    for ifde in ifd_entries {
+        writer.write_u16::<T>(ifde.tag)?; // Tag
+        writer.write_u16::<T>(ifde.ifd_type)?; // Field type
+        if is_big_tiff {
+            writer.write_u64::<T>(ifde.num_values as u64)?; // Num of values
+        } else {
+            writer.write_u32::<T>(ifde.num_values)?; // Num of values
+        }
+        let num_bytes = get_data_type_size(ifde.ifd_type) * ifde.num_values as usize;
+        let mut value_bytes: Vec<u8> = Vec::with_capacity(value_offset_size);
+        if ifde.num_values == 1 && (ifde.ifd_type == DT_SHORT || ifde.ifd_type == DT_LONG || ifde.ifd_type == DT_LONG8) {
+            // it's a value
+            match ifde.ifd_type {
+                DT_SHORT => value_bytes.write_u16::<T>(ifde.offset as u16)?,
+                DT_LONG => value_bytes.write_u32::<T>(ifde.offset as u32)?,
+                _ => value_bytes.write_u64::<T>(ifde.offset)?,
+            }
+        } else if num_bytes <= value_offset_size {
+            // the values fit within the value_offset and must be stored there
+            let start = ifde.offset as usize;
+            value_bytes.write_all(&larger_values_data[start..(start + num_bytes)])?;
+        } else if is_big_tiff {
+            // it's an offset
+            value_bytes.write_u64::<T>(ifd_start + ifd_length + ifde.offset)?;
+        } else {
+            // it's an offset
+            value_bytes.write_u32::<T>((ifd_start + ifd_length + ifde.offset) as u32)?;
+        }
+        // Values are left-justified within the value_offset
+        value_bytes.resize(value_offset_size, 0u8);
+        writer.write_all(&value_bytes)?;
+    }
+
+    // The offset of the next IFD, or '0' if this is the last image in the file.
+    if is_big_tiff {
+        writer.write_u64::<T>(next_ifd_offset)?;
+    } else {
+        writer.write_u32::<T>(next_ifd_offset as u32)?;
+    }
+
+    //////////////////////////////////
+    // Write the larger_values_data //
+    //////////////////////////////////
+    writer.write_all(&larger_values_data)?;
+
+    // The next IFD must start on a word (i.e. an even value).
+    if writer.len() % 2 == 1 {
+        writer.write_u8(0u8)?;
+    }
+
+    Ok(writer)
+}
+
+// Returns the size, in bytes, of a single value of a TIFF field type.
+fn get_data_type_size(ifd_type: u16) -> usize {
+    match ifd_type {
+        DT_BYTE | DT_ASCII | DT_SBYTE | DT_UNDEFINED => 1,
+        DT_SHORT | DT_SSHORT => 2,
+        DT_LONG | DT_SLONG | DT_FLOAT | DT_IFD => 4,
+        _ => 8, // DT_RATIONAL, DT_SRATIONAL, DT_DOUBLE, DT_LONG8, DT_SLONG8, DT_IFD8
+    }
+}
+
+// Halves the resolution of an image for use as an overview. Continuous data are averaged,
+// ignoring nodata values, while other data, e.g. categorical and RGB values, are sampled
+// from the upper-left cell of each 2 x 2 block. The band to reduce begins at the cell start
+// of data.
+fn build_overview(configs: &RasterConfigs, data: &RasterData, start: usize, columns: usize, rows: usize) -> Vec<f64> {
+    let nodata = configs.nodata;
+    let is_continuous = configs.photometric_interp == PhotometricInterpretation::Continuous;
+    let is_floating_point = configs.data_type == DataType::F32 || configs.data_type == DataType::F64;
+    let overview_columns = (columns + 1) / 2;
+    let overview_rows = (rows + 1) / 2;
+    let mut output = vec![nodata; overview_rows * overview_columns];
+    for row in 0..overview_rows {
+        for col in 0..overview_columns {
+            let idx = row * overview_columns + col;
+            if is_continuous {
+                let mut sum = 0f64;
+                let mut n = 0f64;
+                for r in (row * 2)..min(row * 2 + 2, rows) {
+                    for c in (col * 2)..min(col * 2 + 2, columns) {
+                        let z = data.get_value(start + r * columns + c);
+                        if z != nodata {
+                            sum += z;
+                            n += 1f64;
+                        }
+                    }
+                }
+                if n > 0f64 {
+                    output[idx] = if is_floating_point { sum / n } else { (sum / n).round() };
+                }
             } else {
-                // it's an offset
-                writer.write_u32::<BigEndian>(ifd_start + ifd_length + ifde.offset)?;
-            }
-        }
-
-        // 4-byte offset of the next IFD; Note, only single image TIFFs are currently supported
-        // and therefore, this will always be set to '0'.
-        writer.write_u32::<BigEndian>(0u32)?;
-
-        //////////////////////////////////
-        // Write the larger_values_data //
-        //////////////////////////////////
-        writer.write_all(&larger_values_data)?;
-
-    }
-
-
-    // if little_endian {
-    //     //////////////////////
-    //     // Write the header //
-    //     //////////////////////
-    //     writer.write_all("II".as_bytes())?;
-    //     // magic number
-    //     writer.write_u16::<LittleEndian>(42u16)?;
-    //     // offset to first IFD
-    //     let total_bytes_per_pixel = r.configs.data_type.get_data_size();
-    //     if total_bytes_per_pixel == 0 {
-    //         return Err(Error::new(ErrorKind::InvalidData, "Unknown data type."));
-    //     }
-    //     let mut ifd_start = (8usize + r.configs.rows as usize * r.configs.columns as usize *
-    //                     total_bytes_per_pixel) as u32; // plus the 8-byte header
-    //     let mut ifd_start_needs_extra_byte = false;
-    //     if ifd_start % 2 == 1 {
-    //         ifd_start += 1;
-    //         ifd_start_needs_extra_byte = true;
-    //     }
-    //     writer.write_u32::<LittleEndian>(ifd_start)?;
-
-    //     //////////////////////////////
-    //     // Write the image the data //
-    //     //////////////////////////////
-    //     match r.configs.photometric_interp {
-    //         PhotometricInterpretation::Continuous |
-    //         PhotometricInterpretation::Categorical |
-    //         PhotometricInterpretation::Boolean => {
-    //             match r.configs.data_type {
-    //                 DataType::F64 => {
-    //                     let mut i: usize;
-    //                     for row in 0..r.configs.rows {
-    //                         for col in 0..r.configs.columns {
-    //                             i = row * r.configs.columns + col;
-    //                             writer.write_f64::<LittleEndian>(r.data[i])?;
-    //                         }
-    //                     }
-    //                 },
-    //                 DataType::F32 => {
-    //                     let mut i: usize;
-    //                     for row in 0..r.configs.rows {
-    //                         for col in 0..r.configs.columns {
-    //                             i = row * r.configs.columns + col;
-    //                             writer.write_f32::<LittleEndian>(r.data[i] as f32)?;
-    //                         }
-    //                     }
-    //                 },
-    //                 DataType::U64 => {
-    //                     let mut i: usize;
-    //                     for row in 0..r.configs.rows {
-    //                         for col in 0..r.configs.columns {
-    //                             i = row * r.configs.columns + col;
-    //                             writer.write_u64::<LittleEndian>(r.data[i] as u64)?;
-    //                         }
-    //                     }
-    //                 },
-    //                 DataType::U32 => {
-    //                     let mut i: usize;
-    //                     for row in 0..r.configs.rows {
-    //                         for col in 0..r.configs.columns {
-    //                             i = row * r.configs.columns + col;
-    //                             writer.write_u32::<LittleEndian>(r.data[i] as u32)?;
-    //                         }
-    //                     }
-    //                 },
-    //                 DataType::U16 => {
-    //                     let mut i: usize;
-    //                     for row in 0..r.configs.rows {
-    //                         for col in 0..r.configs.columns {
-    //                             i = row * r.configs.columns + col;
-    //                             writer.write_u16::<LittleEndian>(r.data[i] as u16)?;
-    //                         }
-    //                     }
-    //                 },
-    //                 DataType::U8 => {
-    //                     let mut i: usize;
-    //                     for row in 0..r.configs.rows {
-    //                         for col in 0..r.configs.columns {
-    //                             i = row * r.configs.columns + col;
-    //                             writer.write(&[r.data[i] as u8])?;
-    //                         }
-    //                     }
-    //                 },
-    //                 DataType::I64 => {
-    //                     let mut i: usize;
-    //                     for row in 0..r.configs.rows {
-    //                         for col in 0..r.configs.columns {
-    //                             i = row * r.configs.columns + col;
-    //                             writer.write_i64::<LittleEndian>(r.data[i] as i64)?;
-    //                         }
-    //                     }
-    //                 },
-    //                 DataType::I32 => {
-    //                     let mut i: usize;
-    //                     for row in 0..r.configs.rows {
-    //                         for col in 0..r.configs.columns {
-    //                             i = row * r.configs.columns + col;
-    //                             writer.write_i32::<LittleEndian>(r.data[i] as i32)?;
-    //                         }
-    //                     }
-    //                 },
-    //                 DataType::I16 => {
-    //                     let mut i: usize;
-    //                     for row in 0..r.configs.rows {
-    //                         for col in 0..r.configs.columns {
-    //                             i = row * r.configs.columns + col;
-    //                             writer.write_i16::<LittleEndian>(r.data[i] as i16)?;
-    //                         }
-    //                     }
-    //                 },
-    //                 DataType::I8 => {
-    //                     let mut i: usize;
-    //                     for row in 0..r.configs.rows {
-    //                         for col in 0..r.configs.columns {
-    //                             i = row * r.configs.columns + col;
-    //                             writer.write(&[r.data[i] as u8])?;
-    //                         }
-    //                     }
-    //                 },
-    //                 _ => {
-    //                     return Err(Error::new(ErrorKind::InvalidData, "Unknown data type."));
-    //                 },
-    //             }
-    //         },
-    //         PhotometricInterpretation::RGB => {
-    //             match r.configs.data_type {
-    //                 DataType::RGB24 => {
-    //                     let mut bytes: [u8; 3] = [0u8; 3];
-    //                     let mut i: usize;
-    //                     for row in 0..r.configs.rows {
-    //                         for col in 0..r.configs.columns {
-    //                             i = row * r.configs.columns + col;
-    //                             // writer.write_u24::<LittleEndian>(r.data[i] as u32)?;
-    //                             let val = r.data[i] as u32;
-    //                             bytes[0] = ((val >> 16u32) & 0xFF) as u8; // red
-    //                             bytes[1] = ((val >> 8u32) & 0xFF) as u8; // green
-    //                             bytes[2] = (val & 0xFF) as u8; // blue
-    //                             writer.write(&bytes)?;
-    //                         }
-    //                     }
-    //                 },
-    //                 DataType::RGBA32 => {
-    //                     let mut i: usize;
-    //                     for row in 0..r.configs.rows {
-    //                         for col in 0..r.configs.columns {
-    //                             i = row * r.configs.columns + col;
-    //                             let val = r.data[i] as u32;
-    //                             let val2 = ((val >> 24u32) & 0xFF) | ((val >> 16u32) & 0xFF) | ((val >> 8u32) & 0xFF) | (val & 0xFF);
-    //                             writer.write_u32::<LittleEndian>(val2)?;
-    //                         }
-    //                     }
-    //                 },
-    //                 _ => {
-    //                     return Err(Error::new(ErrorKind::InvalidData, "Unknown data type."));
-    //                 },
-    //             }
-    //         },
-    //         PhotometricInterpretation::Paletted => {
-    //             return Err(Error::new(ErrorKind::InvalidData,
-    //                                 "Paletted GeoTIFFs are currently unsupported for writing."));
-    //         },
-    //         PhotometricInterpretation::Unknown => {
-    //             return Err(Error::new(ErrorKind::InvalidData, "Error while writing GeoTIFF file."));
-    //         },
-    //     }
-
-    //     // This is just because the IFD must start on a word (i.e. an even value). If the data are
-    //     // single bytes, then this may not be the case.
-    //     if ifd_start_needs_extra_byte {
-    //         writer.write_u8(0u8)?;
-    //     }
-
-    //     ///////////////////
-    //     // write the IFD //
-    //     ///////////////////
-        
-    //     // Number of Directory Entries. This will depend on type (photometric interp)
-    //     // and must include the required tags for the image type, the software tag (305),
-    //     // and the 5 geokeys: ModelTiepointTag, ModelPixelScaleTag,
-    //     // GeoKeyDirectoryTag, GeoAsciiParamsTag, GeoDoubleParamsTag
-    //     let num_ifd_entries = match r.configs.photometric_interp {
-    //         PhotometricInterpretation::Continuous => 19u16,
-    //         PhotometricInterpretation::Categorical | PhotometricInterpretation::Paletted => 19u16,
-    //         PhotometricInterpretation::Boolean => 18u16,
-    //         PhotometricInterpretation::RGB => 19u16,
-    //         PhotometricInterpretation::Unknown => {
-    //             return Err(Error::new(ErrorKind::InvalidData, "Error while writing GeoTIFF file."));
-    //         },
-    //     };
-    //     writer.write_u16::<LittleEndian>(num_ifd_entries)?;
-
-    //     let ifd_length = 2u32 + num_ifd_entries as u32 * 12u32 + 4u32;
-    //     let mut larger_values_data: Vec<u8> = vec![];
-
-    //     /* 
-    //     Write the IFD entries
-
-    //     Bytes 0-1 The Tag that identifies the field.
-    //     Bytes 2-3 The field Type.
-    //     Bytes 4-7 The number of values, Count of the indicated Type.
-    //     Bytes 8-11 The Value Offset, the file offset (in bytes) of the Value for the field.
-    //     The Value is expected to begin on a word boundary; the corresponding
-    //     Value Offset will thus be an even number. This file offset may
-    //     point anywhere in the file, even after the image data. 
-
-    //     To save time and space the Value Offset contains the Value instead of pointing to
-    //     the Value if and only if the Value fits into 4 bytes. If the Value is shorter than 4
-    //     bytes, it is left-justified within the 4-byte Value Offset, i.e., stored in the lowernumbered
-    //     bytes. Whether the Value fits within 4 bytes is determined by the Type
-    //     and Count of the field.
-    //     */
-
-    //     // ImageWidth tag (256)
-    //     writer.write_u16::<LittleEndian>(TAG_IMAGEWIDTH)?; // Tag
-    //     writer.write_u16::<LittleEndian>(DT_LONG)?; // Field type LONG
-    //     writer.write_u32::<LittleEndian>(1u32)?; // Num of values
-    //     writer.write_u32::<LittleEndian>(r.configs.columns as u32)?; // Value/offset
-
-    //     // ImageLength tag (257)
-    //     writer.write_u16::<LittleEndian>(TAG_IMAGELENGTH)?; // Tag
-    //     writer.write_u16::<LittleEndian>(DT_LONG)?; // Field type LONG
-    //     writer.write_u32::<LittleEndian>(1u32)?; // Num of values
-    //     writer.write_u32::<LittleEndian>(r.configs.rows as u32)?; // Value/offset
-
-    //     let bits_per_sample = match r.configs.data_type {
-    //         DataType::I8 | DataType::U8 => 8u16,
-    //         DataType::I16 | DataType::U16 => 16u16,
-    //         DataType::I32 | DataType::U32 | DataType::F32 => 32u16,
-    //         DataType::I64 | DataType::U64 | DataType::F64 => 64u16,
-    //         DataType::RGB24 => 8u16,
-    //         DataType::RGBA32 => 8u16,
-    //         DataType::RGB48 => 16u16,
-    //         _ => {
-    //             return Err(Error::new(ErrorKind::InvalidData, "Unknown data type."));
-    //         }
-    //     };
-
-    //     let samples_per_pixel = match r.configs.data_type {
-    //         DataType::I8 | DataType::U8 => 1u16,
-    //         DataType::I16 | DataType::U16 => 1u16,
-    //         DataType::I32 | DataType::U32 | DataType::F32 => 1u16,
-    //         DataType::I64 | DataType::U64 | DataType::F64 => 1u16,
-    //         DataType::RGB24 => 3u16,
-    //         DataType::RGBA32 => 4u16,
-    //         DataType::RGB48 => 3u16,
-    //         _ => {
-    //             return Err(Error::new(ErrorKind::InvalidData, "Unknown data type."));
-    //         }
-    //     };
-
-    //     // BitsPerSample tag (258)
-    //     if r.configs.photometric_interp != PhotometricInterpretation::Boolean {
-    //         writer.write_u16::<LittleEndian>(TAG_BITSPERSAMPLE)?; // Tag
-    //         writer.write_u16::<LittleEndian>(DT_SHORT)?; // Field type
-    //         writer.write_u32::<LittleEndian>(samples_per_pixel as u32)?; // Num of values
-    //         if samples_per_pixel == 1 {
-    //             writer.write_u16::<LittleEndian>(bits_per_sample)?; // Value/offset
-    //             writer.write_u16::<LittleEndian>(0u16)?; // Fill the remaining 2 right bytes of the u32
-    //         } else {
-    //             writer.write_u32::<LittleEndian>(ifd_start + ifd_length + larger_values_data.len() as u32)?; // Value/offset
-    //             for _ in 0..samples_per_pixel {
-    //                 let _ = larger_values_data.write_u16::<LittleEndian>(bits_per_sample);
-    //             }
-    //         }
-            
-    //     }
-
-    //     // Compression tag (259)
-    //     writer.write_u16::<LittleEndian>(TAG_COMPRESSION)?; // Tag
-    //     writer.write_u16::<LittleEndian>(DT_SHORT)?; // Field type
-    //     writer.write_u32::<LittleEndian>(1u32)?; // Num of values
-    //     writer.write_u16::<LittleEndian>(COMPRESS_NONE)?; // Value/offset -- No compression
-    //     writer.write_u16::<LittleEndian>(0u16)?; // Fill the remaining 2 right bytes of the u32
-
-    //     // PhotometricInterpretation tag (262)
-    //     writer.write_u16::<LittleEndian>(TAG_PHOTOMETRICINTERPRETATION)?; // Tag
-    //     writer.write_u16::<LittleEndian>(DT_SHORT)?; // Field type SHORT
-    //     writer.write_u32::<LittleEndian>(1u32)?; // Num of values
-    //     let pi = match r.configs.photometric_interp {
-    //         PhotometricInterpretation::Continuous => PI_BLACKISZERO,
-    //         PhotometricInterpretation::Categorical | PhotometricInterpretation::Paletted => PI_PALETTED,
-    //         PhotometricInterpretation::Boolean => PI_BLACKISZERO,
-    //         PhotometricInterpretation::RGB => PI_RGB,
-    //         PhotometricInterpretation::Unknown => {
-    //             return Err(Error::new(ErrorKind::InvalidData, "Error while writing GeoTIFF file. Unknown Photometric Interpretation."));
-    //         },
-    //     };
-    //     writer.write_u16::<LittleEndian>(pi)?; // Value/offset
-    //     writer.write_u16::<LittleEndian>(0u16)?; // Fill the remaining 2 right bytes of the u32
-
-    //     // StripOffsets tag (273)
-    //     writer.write_u16::<LittleEndian>(TAG_STRIPOFFSETS)?; // Tag
-    //     writer.write_u16::<LittleEndian>(DT_LONG)?; // Field type
-    //     writer.write_u32::<LittleEndian>(r.configs.columns as u32)?; // Num of values
-    //     writer.write_u32::<LittleEndian>(ifd_start + ifd_length + larger_values_data.len() as u32)?; // Value/offset -- No compression
-    //     let row_length_in_bytes: u32 = r.configs.columns as u32 * total_bytes_per_pixel as u32;
-    //     for i in 0..r.configs.rows as u32 {
-    //         let _ = larger_values_data.write_u32::<LittleEndian>(8u32 + row_length_in_bytes * i);
-    //     }
-
-    //     // SamplesPerPixel tag (277)
-    //     writer.write_u16::<LittleEndian>(TAG_SAMPLESPERPIXEL)?; // Tag
-    //     writer.write_u16::<LittleEndian>(DT_SHORT)?; // Field type
-    //     writer.write_u32::<LittleEndian>(1u32)?; // Num of values
-    //     writer.write_u16::<LittleEndian>(samples_per_pixel)?; // Value/offset
-    //     writer.write_u16::<LittleEndian>(0u16)?; // Fill the remaining 2 right bytes of the u32
-
-    //     // RowsPerStrip tag (278)
-    //     writer.write_u16::<LittleEndian>(TAG_ROWSPERSTRIP)?; // Tag
-    //     writer.write_u16::<LittleEndian>(DT_SHORT)?; // Field type
-    //     writer.write_u32::<LittleEndian>(1u32)?; // Num of values
-    //     writer.write_u16::<LittleEndian>(1u16)?; // Value/offset -- No compression
-    //     writer.write_u16::<LittleEndian>(0u16)?; // Fill the remaining 2 right bytes of the u32
-
-    //     // StripByteCounts tag (279)
-    //     writer.write_u16::<LittleEndian>(TAG_STRIPBYTECOUNTS)?; // Tag
-    //     writer.write_u16::<LittleEndian>(DT_LONG)?; // Field type
-    //     writer.write_u32::<LittleEndian>(r.configs.columns as u32)?; // Num of values
-    //     writer.write_u32::<LittleEndian>(ifd_start + ifd_length + larger_values_data.len() as u32)?; // Value/offset -- No compression
-    //     let total_bytes_per_pixel = match r.configs.data_type {
-    //         DataType::I8 | DataType::U8 => 1u32,
-    //         DataType::I16 | DataType::U16 => 2u32,
-    //         DataType::I32 | DataType::U32 | DataType::F32 => 4u32,
-    //         DataType::I64 | DataType::U64 | DataType::F64 => 8u32,
-    //         DataType::RGB24 => 3u32,
-    //         DataType::RGBA32 => 4u32,
-    //         DataType::RGB48 => 6u32,
-    //         _ => {
-    //             return Err(Error::new(ErrorKind::InvalidData, "Unknown data type."));
-    //         }
-    //     };
-    //     let row_length_in_bytes: u32 = r.configs.columns as u32 * total_bytes_per_pixel;
-    //     for _ in 0..r.configs.rows as u32 {
-    //         let _ = larger_values_data.write_u32::<LittleEndian>(row_length_in_bytes);
-    //     }
-        
-    //     // There is currently no support for storing the image resolution, so give a bogus value of 72x72 dpi.
-    //     // XResolution tag (282)
-    //     writer.write_u16::<LittleEndian>(TAG_XRESOLUTION)?; // Tag
-    //     writer.write_u16::<LittleEndian>(DT_RATIONAL)?; // Field type
-    //     writer.write_u32::<LittleEndian>(1u32)?; // Num of values
-    //     writer.write_u32::<LittleEndian>(ifd_start + ifd_length + larger_values_data.len() as u32)?; // Value/offset -- No compression
-    //     let _ = larger_values_data.write_u32::<LittleEndian>(72u32);
-    //     let _ = larger_values_data.write_u32::<LittleEndian>(1u32);
-
-    //     // YResolution tag (283)
-    //     writer.write_u16::<LittleEndian>(TAG_YRESOLUTION)?; // Tag
-    //     writer.write_u16::<LittleEndian>(DT_RATIONAL)?; // Field type
-    //     writer.write_u32::<LittleEndian>(1u32)?; // Num of values
-    //     writer.write_u32::<LittleEndian>(ifd_start + ifd_length + larger_values_data.len() as u32)?; // Value/offset -- No compression
-    //     let _ = larger_values_data.write_u32::<LittleEndian>(72u32);
-    //     let _ = larger_values_data.write_u32::<LittleEndian>(1u32);
-
-    //     // ResolutionUnit tag (296)
-    //     writer.write_u16::<LittleEndian>(TAG_RESOLUTIONUNIT)?; // Tag
-    //     writer.write_u16::<LittleEndian>(DT_SHORT)?; // Field type
-    //     writer.write_u32::<LittleEndian>(1u32)?; // Num of values
-    //     writer.write_u16::<LittleEndian>(2u16)?; // Value/offset
-    //     writer.write_u16::<LittleEndian>(0u16)?; // Fill the remaining 2 right bytes of the u32
-
-    //     // Software tag (305)
-    //     let software = "WhiteboxTools".to_owned();
-    //     let mut soft_bytes = software.into_bytes();
-    //     soft_bytes.push(0);
-    //     writer.write_u16::<LittleEndian>(TAG_SOFTWARE)?; // Tag
-    //     writer.write_u16::<LittleEndian>(DT_ASCII)?; // Field type
-    //     writer.write_u32::<LittleEndian>(soft_bytes.len() as u32)?; // Num of values
-    //     writer.write_u32::<LittleEndian>(ifd_start + ifd_length + larger_values_data.len() as u32)?; // Value/offset -- No compression
-    //     let _ = larger_values_data.write_all(&soft_bytes);
-
-    //     // SampleFormat tag (339)
-    //     writer.write_u16::<LittleEndian>(TAG_SAMPLEFORMAT)?; // Tag
-    //     writer.write_u16::<LittleEndian>(DT_SHORT)?; // Field type
-    //     writer.write_u32::<LittleEndian>(samples_per_pixel as u32)?; // Num of values
-    //     let samples_format = match r.configs.data_type {
-    //         DataType::U8 | DataType::U16 | DataType::U32 | DataType::U64 => 1u16,
-    //         DataType::I8 | DataType::I16 | DataType::I32 | DataType::I64 => 2u16,
-    //         DataType::F32 | DataType::F64 => 3u16,
-    //         DataType::RGB24 | DataType::RGBA32 | DataType::RGB48 => 1u16,
-    //         _ => {
-    //             return Err(Error::new(ErrorKind::InvalidData, "Unknown data type."));
-    //         }
-    //     };
-    //     if samples_per_pixel == 1 {
-    //         writer.write_u16::<LittleEndian>(samples_format)?; // Value/offset
-    //         writer.write_u16::<LittleEndian>(0u16)?; // Fill the remaining 2 right bytes of the u32
-    //     } else {
-    //         writer.write_u32::<LittleEndian>(ifd_start + ifd_length + larger_values_data.len() as u32)?; // Value/offset
-    //         for _ in 0..samples_per_pixel {
-    //             let _ = larger_values_data.write_u16::<LittleEndian>(samples_format);
-    //         }
-    //     }
-
-    //     // ModelTiepointTag tag (33550)
-    //     writer.write_u16::<LittleEndian>(TAG_MODELPIXELSCALETAG)?; // Tag
-    //     writer.write_u16::<LittleEndian>(DT_DOUBLE)?; // Field type
-    //     writer.write_u32::<LittleEndian>(3u32)?; // Num of values
-    //     writer.write_u32::<LittleEndian>(ifd_start + ifd_length + larger_values_data.len() as u32)?; // Value/offset -- No compression
-    //     let _ = larger_values_data.write_f64::<LittleEndian>(r.configs.resolution_x);
-    //     let _ = larger_values_data.write_f64::<LittleEndian>(r.configs.resolution_y);
-    //     let _ = larger_values_data.write_f64::<LittleEndian>(0f64);
-        
-    //     // ModelPixelScaleTag tag (33922)
-    //     writer.write_u16::<LittleEndian>(TAG_MODELTIEPOINTTAG)?; // Tag
-    //     writer.write_u16::<LittleEndian>(DT_DOUBLE)?; // Field type
-    //     writer.write_u32::<LittleEndian>(6u32)?; // Num of values
-    //     writer.write_u32::<LittleEndian>(ifd_start + ifd_length + larger_values_data.len() as u32)?; // Value/offset -- No compression
-    //     let _ = larger_values_data.write_f64::<LittleEndian>(0f64); // I
-    //     let _ = larger_values_data.write_f64::<LittleEndian>(0f64); // J
-    //     let _ = larger_values_data.write_f64::<LittleEndian>(0f64); // K
-    //     let _ = larger_values_data.write_f64::<LittleEndian>(r.configs.west); // X
-    //     let _ = larger_values_data.write_f64::<LittleEndian>(r.configs.north); // Y
-    //     let _ = larger_values_data.write_f64::<LittleEndian>(0f64); // Z
-
-    //     // TAG_GDAL_NODATA tag (42113)
-    //     let nodata_str = format!("{}", r.configs.nodata);
-    //     let mut nodata_bytes = nodata_str.into_bytes();
-    //     nodata_bytes.push(0);
-    //     writer.write_u16::<LittleEndian>(TAG_GDAL_NODATA)?; // Tag
-    //     writer.write_u16::<LittleEndian>(DT_ASCII)?; // Field type
-    //     writer.write_u32::<LittleEndian>(nodata_bytes.len() as u32)?; // Num of values
-    //     if nodata_bytes.len() % 2 == 1 {
-    //         nodata_bytes.push(0);
-    //     }
-    //     writer.write_u32::<LittleEndian>(ifd_start + ifd_length + larger_values_data.len() as u32)?; // Value/offset -- No compression
-    //     let _ = larger_values_data.write_all(&nodata_bytes);
-        
-    //     let kw_map = get_keyword_map();
-    //     let geographic_type_map = match kw_map.get(&2048u16) {
-    //         Some(map) => map,
-    //         None => return Err(Error::new(ErrorKind::InvalidData, "Error generating geographic type map.")),
-    //     };
-    //     let projected_cs_type_map = match kw_map.get(&3072u16) {
-    //         Some(map) => map,
-    //         None => return Err(Error::new(ErrorKind::InvalidData, "Error generating projected coordinate system type map.")),
-    //     };
-
-    //     //let key_map = get_keys_map();
-    //     let mut gk_entries: Vec<GeoKeyEntry> = vec![];
-    //     let mut ascii_params = String::new(); //: Vec<u8> = vec![];
-    //     // let mut double_params: Vec<f64> = vec![];
-    //     if geographic_type_map.contains_key(&r.configs.epsg_code) {
-    //         // tGTModelTypeGeoKey (1024)
-    //         gk_entries.push(GeoKeyEntry{ tag: TAG_GTMODELTYPEGEOKEY, location: 0u16, count: 1u16, value_offset: 2u16 });
-            
-    //         // GTRasterTypeGeoKey (1025)
-    //         if r.configs.pixel_is_area {
-    //             gk_entries.push(GeoKeyEntry{ tag: TAG_GTRASTERTYPEGEOKEY, location: 0u16, count: 1u16, value_offset: 1u16 });
-    //         } else {
-    //             gk_entries.push(GeoKeyEntry{ tag: TAG_GTRASTERTYPEGEOKEY, location: 0u16, count: 1u16, value_offset: 2u16 });
-    //         }
-            
-    //         // tGTCitationGeoKey (1026)
-    //         let mut v = String::from(geographic_type_map.get(&r.configs.epsg_code).unwrap().clone());
-    //         v.push_str("|");
-    //         v = v.replace("_", " ");
-    //         gk_entries.push(GeoKeyEntry{ tag: TAG_GTCITATIONGEOKEY, location: 34737u16, count: v.len() as u16, value_offset: ascii_params.len() as u16 });
-    //         ascii_params.push_str(&v);
-
-    //         // tGeographicTypeGeoKey (2048)
-    //         gk_entries.push(GeoKeyEntry{ tag: TAG_GEOGRAPHICTYPEGEOKEY, location: 0u16, count: 1u16, value_offset: r.configs.epsg_code });
-            
-    //         if r.configs.z_units.to_lowercase() != "not specified" {
-    //             println!("I'm here 4");
-    //             // VerticalUnitsGeoKey (4099)
-    //             let units = r.configs.z_units.to_lowercase();
-    //             if units.contains("met") {
-    //                 gk_entries.push(GeoKeyEntry{ tag: TAG_VERTICALUNITSGEOKEY, location: 0u16, count: 1u16, value_offset: 9001u16 });
-    //             } else if units.contains("ft") | units.contains("feet") | units.contains("foot") {
-    //                 gk_entries.push(GeoKeyEntry{ tag: TAG_VERTICALUNITSGEOKEY, location: 0u16, count: 1u16, value_offset: 9002u16 });
-    //             }
-    //         }
-    //     } else if projected_cs_type_map.contains_key(&r.configs.epsg_code) {
-    //         // tGTModelTypeGeoKey (1024)
-    //         gk_entries.push(GeoKeyEntry{ tag: TAG_GTMODELTYPEGEOKEY, location: 0u16, count: 1u16, value_offset: 1u16 });
-            
-    //         // GTRasterTypeGeoKey (1025)
-    //         if r.configs.pixel_is_area {
-    //             gk_entries.push(GeoKeyEntry{ tag: TAG_GTRASTERTYPEGEOKEY, location: 0u16, count: 1u16, value_offset: 1u16 });
-    //         } else {
-    //             gk_entries.push(GeoKeyEntry{ tag: TAG_GTRASTERTYPEGEOKEY, location: 0u16, count: 1u16, value_offset: 2u16 });
-    //         }
-            
-    //         // tProjectedCSTypeGeoKey (3072)
-    //         gk_entries.push(GeoKeyEntry{ tag: TAG_PROJECTEDCSTYPEGEOKEY, location: 0u16, count: 1u16, value_offset: r.configs.epsg_code });
-            
-    //         // PCSCitationGeoKey (3073)
-    //         let mut v = String::from(projected_cs_type_map.get(&r.configs.epsg_code).unwrap().clone());
-    //         v.push_str("|");
-    //         v = v.replace("_", " ");
-    //         gk_entries.push(GeoKeyEntry{ tag: 3073u16, location: 34737u16, count: v.len() as u16, value_offset: ascii_params.len() as u16 });
-    //         ascii_params.push_str(&v);
-
-    //         if r.configs.xy_units.to_lowercase() != "not specified" {
-    //             // ProjLinearUnitsGeoKey (3076)
-    //             let units = r.configs.xy_units.to_lowercase();
-    //             if units.contains("met") {
-    //                 gk_entries.push(GeoKeyEntry{ tag: TAG_PROJLINEARUNITSGEOKEY, location: 0u16, count: 1u16, value_offset: 9001u16 });
-    //             } else if units.contains("ft") | units.contains("feet") | units.contains("foot") {
-    //                 gk_entries.push(GeoKeyEntry{ tag: TAG_PROJLINEARUNITSGEOKEY, location: 0u16, count: 1u16, value_offset: 9002u16 });
-    //             }
-    //         }
-
-    //         if r.configs.z_units.to_lowercase() != "not specified" {
-    //             // VerticalUnitsGeoKey (4099)
-    //             let units = r.configs.z_units.to_lowercase();
-    //             if units.contains("met") {
-    //                 gk_entries.push(GeoKeyEntry{ tag: TAG_VERTICALUNITSGEOKEY, location: 0u16, count: 1u16, value_offset: 9001u16 });
-    //             } else if units.contains("ft") | units.contains("feet") | units.contains("foot") {
-    //                 gk_entries.push(GeoKeyEntry{ tag: TAG_VERTICALUNITSGEOKEY, location: 0u16, count: 1u16, value_offset: 9002u16 });
-    //             }
-    //         }
-    //     } else {
-    //         // we don't know much about the coordinate system used.
-            
-    //         // tGTModelTypeGeoKey (1024)
-    //         gk_entries.push(GeoKeyEntry{ tag: TAG_GTMODELTYPEGEOKEY, location: 0u16, count: 1u16, value_offset: 0u16 });
-            
-    //         // GTRasterTypeGeoKey (1025)
-    //         if r.configs.pixel_is_area {
-    //             gk_entries.push(GeoKeyEntry{ tag: TAG_GTRASTERTYPEGEOKEY, location: 0u16, count: 1u16, value_offset: 1u16 });
-    //         } else {
-    //             gk_entries.push(GeoKeyEntry{ tag: TAG_GTRASTERTYPEGEOKEY, location: 0u16, count: 1u16, value_offset: 2u16 });
-    //         }
-            
-    //     }
-
-    //     // create the GeoKeyDirectoryTag tag (34735)
-    //     writer.write_u16::<LittleEndian>(TAG_GEOKEYDIRECTORYTAG)?; // Tag
-    //     writer.write_u16::<LittleEndian>(DT_SHORT)?; // Field type
-    //     writer.write_u32::<LittleEndian>((4 + gk_entries.len() * 4) as u32)?; // Num of values
-    //     writer.write_u32::<LittleEndian>(ifd_start + ifd_length + larger_values_data.len() as u32)?; // Value/offset -- No compression
-    //     let _ = larger_values_data.write_u16::<LittleEndian>(1u16); // KeyDirectoryVersion
-    //     let _ = larger_values_data.write_u16::<LittleEndian>(1u16); // KeyRevision
-    //     let _ = larger_values_data.write_u16::<LittleEndian>(0u16); // MinorRevision
-    //     let _ = larger_values_data.write_u16::<LittleEndian>(gk_entries.len() as u16); // NumberOfKeys
-
-    //     for entry in gk_entries {
-    //         let _ = larger_values_data.write_u16::<LittleEndian>(entry.tag); // KeyID
-    //         let _ = larger_values_data.write_u16::<LittleEndian>(entry.location); // TIFFTagLocation
-    //         let _ = larger_values_data.write_u16::<LittleEndian>(entry.count); // Count
-    //         let _ = larger_values_data.write_u16::<LittleEndian>(entry.value_offset); // Value_Offset
-    //     }
-
-    //     // if double_params.len() > 0 {
-    //     //     // create the GeoDoubleParamsTag tag (34736)
-    //     //     writer.write_u16::<LittleEndian>(TAG_GEODOUBLEPARAMSTAG)?; // Tag
-    //     //     writer.write_u16::<LittleEndian>(DT_DOUBLE)?; // Field type
-    //     //     writer.write_u32::<LittleEndian>(double_params.len() as u32)?; // Num of values
-    //     //     writer.write_u32::<LittleEndian>(ifd_start + ifd_length + larger_values_data.len() as u32)?; // Value/offset -- No compression
-    //     //     for double_val in double_params {
-    //     //         let _ = larger_values_data.write_f64::<LittleEndian>(double_val);
-    //     //     }
-    //     // }
-
-    //     if ascii_params.len() > 0 {
-    //         // create the GeoAsciiParamsTag tag (34737)
-    //         let mut ascii_params_bytes = ascii_params.into_bytes();
-    //         ascii_params_bytes.push(0);
-    //         writer.write_u16::<LittleEndian>(TAG_GEOASCIIPARAMSTAG)?; // Tag
-    //         writer.write_u16::<LittleEndian>(DT_ASCII)?; // Field type
-    //         writer.write_u32::<LittleEndian>(ascii_params_bytes.len() as u32)?; // Num of values
-    //         writer.write_u32::<LittleEndian>(ifd_start + ifd_length + larger_values_data.len() as u32)?; // Value/offset -- No compression
-    //         let _ = larger_values_data.write_all(&ascii_params_bytes);
-    //     }
-
-    //     // 4-byte offset of the next IFD; Note, only single image TIFFs are currently supported
-    //     // and therefore, this will always be set to '0'.
-    //     writer.write_u32::<LittleEndian>(0u32)?;
-
-    //     //////////////////////////////////
-    //     // Write the larger_values_data //
-    //     //////////////////////////////////
-    //     writer.write_all(&larger_values_data)?;
-
-
-    //     /*
-    //         Required Fields for Bilevel Images
-    //         - ImageWidth 
-    //         - ImageLength 
-    //         - Compression 
-    //         - PhotometricInterpretation 
-    //         - StripOffsets 
-    //         - RowsPerStrip 
-    //         - StripByteCounts 
-    //         - XResolution 
-    //         - YResolution 
-    //         - ResolutionUnit
-    //     */
-
-    //     /*
-    //         Required Fields for Grayscale Images
-    //         - ImageWidth
-    //         - ImageLength
-    //         - BitsPerSample
-    //         - Compression
-    //         - PhotometricInterpretation
-    //         - StripOffsets
-    //         - RowsPerStrip
-    //         - StripByteCounts
-    //         - XResolution
-    //         - YResolution
-    //         - ResolutionUnit
-    //     */
-
-    //     /*
-    //         Required Fields for Palette Colour Images
-    //         - ImageWidth
-    //         - ImageLength
-    //         - BitsPerSample
-    //         - Compression
-    //         - PhotometricInterpretation
-    //         - StripOffsets
-    //         - RowsPerStrip
-    //         - StripByteCounts
-    //         - XResolution
-    //         - YResolution
-    //         - ResolutionUnit
-    //         - ColorMap
-    //     */
-
-    //     /*
-    //         Required Fields for RGB Images
-    //         - ImageWidth
-    //         - ImageLength
-    //         - BitsPerSample
-    //         - Compression
-    //         - PhotometricInterpretation
-    //         - StripOffsets
-    //         - SamplesPerPixel
-    //         - RowsPerStrip
-    //         - StripByteCounts
-    //         - XResolution
-    //         - YResolution
-    //         - ResolutionUnit
-    //     */
-
-         
-    // } else {
-        
-    // }
+                output[idx] = data.get_value(start + row * 2 * columns + col * 2);
+            }
+        }
+    }
+    output
+}
 
+// Appends one of an image's blocks, encoded as the raster's data type, to buf. Tiles that
+// extend beyond the edges of the image are padded with nodata values. With the planar
+// configuration, the block contains the single band given by plane; otherwise, the values
+// of all bands are interleaved. Natively stored data are written directly, without
+// conversion to f64.
+fn write_block_data<'a, T: ByteOrder>(configs: &'a RasterConfigs, image: &TiffImage, encoding: &TiffEncoding, plane: usize, block_row: usize, block_col: usize, buf: &mut Vec<u8>) -> Result<(), Error> {
+    let is_scalar = configs.photometric_interp != PhotometricInterpretation::RGB;
+    match *image.data {
+        RasterData::F32(ref d) if is_scalar && configs.data_type == DataType::F32 => {
+            for value in block_values(configs, image, encoding, plane, block_row, block_col, |i| d[i], configs.nodata as f32) {
+                buf.write_f32::<T>(value)?;
+            }
+        }
+        RasterData::I32(ref d) if is_scalar && configs.data_type == DataType::I32 => {
+            for value in block_values(configs, image, encoding, plane, block_row, block_col, |i| d[i], configs.nodata as i32) {
+                buf.write_i32::<T>(value)?;
+            }
+        }
+        RasterData::I16(ref d) if is_scalar && configs.data_type == DataType::I16 => {
+            for value in block_values(configs, image, encoding, plane, block_row, block_col, |i| d[i], configs.nodata as i16) {
+                buf.write_i16::<T>(value)?;
+            }
+        }
+        RasterData::U16(ref d) if is_scalar && configs.data_type == DataType::U16 => {
+            for value in block_values(configs, image, encoding, plane, block_row, block_col, |i| d[i], configs.nodata as u16) {
+                buf.write_u16::<T>(value)?;
+            }
+        }
+        RasterData::I8(ref d) if is_scalar && configs.data_type == DataType::I8 => {
+            for value in block_values(configs, image, encoding, plane, block_row, block_col, |i| d[i], configs.nodata as i8) {
+                buf.write_i8(value)?;
+            }
+        }
+        RasterData::U8(ref d) if is_scalar && configs.data_type == DataType::U8 => {
+            for value in block_values(configs, image, encoding, plane, block_row, block_col, |i| d[i], configs.nodata as u8) {
+                buf.write_u8(value)?;
+            }
+        }
+        _ => {
+            let values = block_values(configs, image, encoding, plane, block_row, block_col, |i| image.data.get_value(i), configs.nodata);
+            write_pixel_data::<T>(configs, &values, buf)?;
+        }
+    }
+    Ok(())
+}
 
-    let _ = writer.flush();
+// Gathers the values of one of an image's blocks, in the order that they are stored.
+fn block_values<V: Copy, F: Fn(usize) -> V>(configs: &RasterConfigs, image: &TiffImage, encoding: &TiffEncoding, plane: usize, block_row: usize, block_col: usize, get_value: F, nodata: V) -> Vec<V> {
+    let (first_band, last_band) = if encoding.planes > 1 {
+        (plane, plane + 1)
+    } else {
+        (0usize, max(1usize, configs.bands as usize))
+    };
+    let num_cells = image.rows * image.columns;
+    let start_row = block_row * image.block_height;
+    let end_row = if image.is_tiled { start_row + image.block_height } else { min(start_row + image.block_height, image.rows) };
+    let start_col = block_col * image.block_width;
+    let end_col = start_col + image.block_width;
+    let mut values: Vec<V> = Vec::with_capacity((end_row - start_row) * image.block_width * (last_band - first_band));
+    for row in start_row..end_row {
+        for col in start_col..end_col {
+            for band in first_band..last_band {
+                if row < image.rows && col < image.columns {
+                    values.push(get_value(band * num_cells + row * image.columns + col));
+                } else {
+                    values.push(nodata);
+                }
+            }
+        }
+    }
+    values
+}
 
+// Appends a sequence of pixel values, encoded as the raster's data type, to buf.
+fn write_pixel_data<'a, T: ByteOrder>(configs: &'a RasterConfigs, values: &[f64], buf: &mut Vec<u8>) -> Result<(), Error> {
+    match configs.photometric_interp {
+        PhotometricInterpretation::Continuous |
+        PhotometricInterpretation::Categorical |
+        PhotometricInterpretation::Boolean |
+        PhotometricInterpretation::Unknown => {
+            match configs.data_type {
+                DataType::F64 => {
+                    for value in values {
+                        buf.write_f64::<T>(*value)?;
+                    }
+                },
+                DataType::F32 => {
+                    for value in values {
+                        buf.write_f32::<T>(*value as f32)?;
+                    }
+                },
+                DataType::U64 => {
+                    for value in values {
+                        buf.write_u64::<T>(*value as u64)?;
+                    }
+                },
+                DataType::U32 => {
+                    for value in values {
+                        buf.write_u32::<T>(*value as u32)?;
+                    }
+                },
+                DataType::U16 => {
+                    for value in values {
+                        buf.write_u16::<T>(*value as u16)?;
+                    }
+                },
+                DataType::U8 => {
+                    for value in values {
+                        buf.write_u8(*value as u8)?;
+                    }
+                },
+                DataType::I64 => {
+                    for value in values {
+                        buf.write_i64::<T>(*value as i64)?;
+                    }
+                },
+                DataType::I32 => {
+                    for value in values {
+                        buf.write_i32::<T>(*value as i32)?;
+                    }
+                },
+                DataType::I16 => {
+                    for value in values {
+                        buf.write_i16::<T>(*value as i16)?;
+                    }
+                },
+                DataType::I8 => {
+                    for value in values {
+                        buf.write_i8(*value as i8)?;
+                    }
+                },
+                _ => {
+                    return Err(Error::new(ErrorKind::InvalidData, "Unknown data type."));
+                },
+            }
+        },
+        PhotometricInterpretation::RGB => {
+            match configs.data_type {
+                DataType::RGB24 => {
+                    for value in values {
+                        let val = *value as u32;
+                        buf.push((val & 0xFF) as u8); // red
+                        buf.push(((val >> 8u32) & 0xFF) as u8); // green
+                        buf.push(((val >> 16u32) & 0xFF) as u8); // blue
+                    }
+                },
+                DataType::RGBA32 => {
+                    for value in values {
+                        let val = *value as u32;
+                        buf.push((val & 0xFF) as u8); // red
+                        buf.push(((val >> 8u32) & 0xFF) as u8); // green
+                        buf.push(((val >> 16u32) & 0xFF) as u8); // blue
+                        buf.push(((val >> 24u32) & 0xFF) as u8); // a
+                    }
+                },
+                _ => {
+                    return Err(Error::new(ErrorKind::InvalidData, "Unknown data type."));
+                },
+            }
+        },
+        PhotometricInterpretation::Paletted => {
+            return Err(Error::new(ErrorKind::InvalidData,
+                                "Paletted GeoTIFFs are currently unsupported for writing."));
+        },
+    }
     Ok(())
 }
 
@@ -2768,11 +2198,11 @@ struct IfdEntry {
     tag: u16,
     ifd_type: u16,
     num_values: u32,
-    offset: u32,
+    offset: u64,
 }
 
 impl IfdEntry {
-    fn new(tag: u16, ifd_type: u16, num_values: u32, offset: u32) -> IfdEntry {
+    fn new(tag: u16, ifd_type: u16, num_values: u32, offset: u64) -> IfdEntry {
         IfdEntry{ tag, ifd_type, num_values, offset }
     }
 }
@@ -2820,8 +2250,8 @@ struct GeoKeyEntry {
 pub struct IfdDirectory {
     pub tag: u16,
     pub ifd_type: u16,
-    pub num_values: u32,
-    pub offset: u32,
+    pub num_values: u64,
+    pub offset: u64,
     pub data: Vec<u8>,
     byte_order: Endianness,
 }
@@ -2829,8 +2259,8 @@ pub struct IfdDirectory {
 impl IfdDirectory {
     pub fn new(tag: u16,
                ifd_type: u16,
-               num_values: u32,
-               offset: u32,
+               num_values: u64,
+               offset: u64,
                data: Vec<u8>,
                byte_order: Endianness)
                -> IfdDirectory {
@@ -2844,6 +2274,24 @@ impl IfdDirectory {
         }
     }
 
+    /// Returns the data with each value converted to little-endian byte order,
+    /// which is the order expected by the GeoKeys parser.
+    pub fn little_endian_data(&self) -> Vec<u8> {
+        let value_size = match self.ifd_type {
+            DT_SHORT | DT_SSHORT => 2,
+            DT_LONG | DT_SLONG | DT_FLOAT | DT_IFD => 4,
+            DT_DOUBLE | DT_LONG8 | DT_SLONG8 | DT_IFD8 => 8,
+            _ => 1,
+        };
+        let mut data = self.data.clone();
+        if self.byte_order == Endianness::BigEndian && value_size > 1 {
+            for value in data.chunks_mut(value_size) {
+                value.reverse();
+            }
+        }
+        data
+    }
+
     pub fn interpret_as_u16(&self) -> Vec<u16> {
         let mut bor = ByteOrderReader::new(self.data.clone(), self.byte_order);
         let mut vals: Vec<u16> = vec![];
@@ -2864,6 +2312,24 @@ impl IfdDirectory {
         vals
     }
 
+    /// Interprets the data as unsigned integers of any width. This is useful for
+    /// tags such as StripOffsets, which may be stored as SHORT, LONG or, in BigTIFF
+    /// files, LONG8 values.
+    pub fn interpret_as_u64(&self) -> Vec<u64> {
+        let mut bor = ByteOrderReader::new(self.data.clone(), self.byte_order);
+        let mut vals: Vec<u64> = vec![];
+        for _ in 0..self.num_values {
+            let val = match self.ifd_type {
+                DT_BYTE => bor.read_u8() as u64,
+                DT_SHORT => bor.read_u16() as u64,
+                DT_LONG | DT_IFD => bor.read_u32() as u64,
+                _ => bor.read_u64(),
+            };
+            vals.push(val);
+        }
+        vals
+    }
+
     pub fn interpret_as_f64(&self) -> Vec<f64> {
         let mut bor = ByteOrderReader::new(self.data.clone(), self.byte_order);
         let mut vals: Vec<f64> = vec![];
@@ -2917,6 +2383,9 @@ impl IfdDirectory {
                 vals.push(val);
             }
             return format!("{:?}", vals);
+        } else if self.ifd_type == 16 {
+            // u64
+            return format!("{:?}", self.interpret_as_u64());
         } else if self.ifd_type == 12 {
             // f64
             let mut vals: Vec<f64> = vec![];
@@ -3100,4 +2569,251 @@ pub fn packbits_decoder(input_data: Vec<u8>) -> Vec<u8> {
 // // const PI_TRANSMASK: u16   = 4; // transparency mask
 // // const PI_CMYK: u16        = 5;
 // // const PI_YCBCR: u16       = 6;
-// // const PI_CIELAB: u16      = 8;
\ No newline at end of file
+// // const PI_CIELAB: u16      = 8;
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+
+    // A raster of the given data type, filled with pseudo-random values that the type can
+    // represent exactly, with a few NoData cells.
+    fn test_raster(file_name: &str, data_type: DataType, rows: usize, columns: usize) -> Raster {
+        let (photometric_interp, nodata, range) = match data_type {
+            DataType::U8 => (PhotometricInterpretation::Continuous, 255f64, 255f64),
+            DataType::I8 => (PhotometricInterpretation::Continuous, -128f64, 255f64),
+            DataType::U16 => (PhotometricInterpretation::Continuous, 65535f64, 65535f64),
+            DataType::I16 => (PhotometricInterpretation::Continuous, -32768f64, 65535f64),
+            DataType::RGB24 => (PhotometricInterpretation::RGB, 0f64, 16777216f64),
+            DataType::RGBA32 => (PhotometricInterpretation::RGB, 0f64, 4294967296f64),
+            DataType::I32 | DataType::I64 => (PhotometricInterpretation::Continuous, -32768f64, 2147483648f64),
+            DataType::U32 | DataType::U64 => (PhotometricInterpretation::Continuous, 0f64, 4294967296f64),
+            _ => (PhotometricInterpretation::Continuous, -32768f64, 4294967296f64),
+        };
+        let mut configs = RasterConfigs { ..Default::default() };
+        configs.rows = rows;
+        configs.columns = columns;
+        configs.nodata = nodata;
+        configs.north = 5000f64 + rows as f64;
+        configs.south = 5000f64;
+        configs.west = 200f64;
+        configs.east = 200f64 + columns as f64;
+        configs.resolution_x = 1f64;
+        configs.resolution_y = 1f64;
+        configs.data_type = data_type;
+        configs.photometric_interp = photometric_interp;
+        let mut r = Raster::initialize_using_config(file_name, &configs);
+
+        let mut seed = 987654321u64;
+        for row in 0..rows as isize {
+            for col in 0..columns as isize {
+                seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+                let x = (seed >> 11) as f64 / (1u64 << 53) as f64;
+                let value = match data_type {
+                    DataType::F64 => 1000f64 * x - 250f64,
+                    DataType::F32 => (1000f64 * x - 250f64) as f32 as f64,
+                    DataType::I8 | DataType::I16 | DataType::I32 | DataType::I64 => ((range - 1f64) * x).floor() - (range / 2f64).floor() + 1f64,
+                    _ => (range * x).floor().min(range - 2f64) + 1f64,
+                };
+                // smooth the values in every other row, so that the predictors have something to do
+                let value = if row % 2 == 1 && data_type != DataType::RGB24 && data_type != DataType::RGBA32 {
+                    r.get_value(row - 1, col)
+                } else {
+                    value
+                };
+                r.set_value(row, col, if (row + col) % 37 == 0 { nodata } else { value });
+            }
+        }
+        r
+    }
+
+    fn temp_file(name: &str) -> String {
+        env::temp_dir().join(name).to_str().unwrap().to_string()
+    }
+
+    fn assert_same_values(expected: &Raster, file_name: &str) {
+        let r = Raster::new(file_name, "r").unwrap();
+        assert_eq!(r.configs.rows, expected.configs.rows, "{}", file_name);
+        assert_eq!(r.configs.columns, expected.configs.columns, "{}", file_name);
+        // RGB images are read as packed integer values
+        let is_rgb = expected.configs.photometric_interp == PhotometricInterpretation::RGB;
+        if !is_rgb {
+            assert_eq!(r.configs.data_type, expected.configs.data_type, "{}", file_name);
+        }
+        assert!((r.configs.north - expected.configs.north).abs() < 1e-9, "{}", file_name);
+        assert!((r.configs.west - expected.configs.west).abs() < 1e-9, "{}", file_name);
+        for row in 0..r.configs.rows as isize {
+            for col in 0..r.configs.columns as isize {
+                let (value, expected_value) = if expected.configs.data_type == DataType::RGB24 {
+                    ((r.get_value(row, col) as u32 & 0xFFFFFF) as f64, expected.get_value(row, col))
+                } else {
+                    (r.get_value(row, col), expected.get_value(row, col))
+                };
+                assert_eq!(value, expected_value, "{} at ({}, {})", file_name, row, col);
+            }
+        }
+    }
+
+    const DATA_TYPES: [DataType; 12] = [DataType::F64, DataType::F32, DataType::I64, DataType::I32,
+        DataType::I16, DataType::I8, DataType::U64, DataType::U32, DataType::U16, DataType::U8,
+        DataType::RGB24, DataType::RGBA32];
+
+    #[test]
+    fn geotiff_round_trip_for_every_compression_and_data_type() {
+        let compressions = [RasterCompression::None, RasterCompression::Deflate, RasterCompression::LZW, RasterCompression::PackBits];
+        let predictors = [RasterPredictor::None, RasterPredictor::Horizontal, RasterPredictor::FloatingPoint];
+        for &data_type in DATA_TYPES.iter() {
+            for &compression in compressions.iter() {
+                for &predictor in predictors.iter() {
+                    for &endian in [Endianness::LittleEndian, Endianness::BigEndian].iter() {
+                        let file_name = temp_file(&format!("wbt_geotiff_{:?}_{:?}_{:?}_{:?}.tif", data_type, compression, predictor, endian));
+                        // 300 columns of 8-byte values produce compressed images of several strips
+                        let mut r = test_raster(&file_name, data_type, 61, 300);
+                        r.configs.compression = compression;
+                        r.configs.predictor = predictor;
+                        r.configs.endian = endian;
+                        r.write().unwrap();
+                        assert_same_values(&r, &file_name);
+                        let _ = fs::remove_file(&file_name);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn compressed_geotiff_is_smaller() {
+        let mut sizes = vec![];
+        for &compression in [RasterCompression::None, RasterCompression::Deflate, RasterCompression::LZW].iter() {
+            let file_name = temp_file(&format!("wbt_geotiff_size_{:?}.tif", compression));
+            let mut r = test_raster(&file_name, DataType::U8, 200, 200);
+            for row in 0..200 {
+                for col in 0..200 {
+                    r.set_value(row, col, (col / 20) as f64);
+                }
+            }
+            r.configs.compression = compression;
+            r.configs.predictor = RasterPredictor::Horizontal;
+            r.write().unwrap();
+            sizes.push(fs::metadata(&file_name).unwrap().len());
+            assert_same_values(&r, &file_name);
+            let _ = fs::remove_file(&file_name);
+        }
+        assert!(sizes[1] * 4 < sizes[0] && sizes[2] * 4 < sizes[0], "{:?}", sizes);
+    }
+
+    #[test]
+    fn bigtiff_round_trip() {
+        for &(data_type, compression) in [(DataType::F64, RasterCompression::None), (DataType::I16, RasterCompression::LZW),
+                                          (DataType::F32, RasterCompression::Deflate), (DataType::U8, RasterCompression::PackBits)].iter() {
+            for &endian in [Endianness::LittleEndian, Endianness::BigEndian].iter() {
+                for &layout in [RasterLayout::Strips, RasterLayout::CloudOptimized].iter() {
+                    let file_name = temp_file(&format!("wbt_bigtiff_{:?}_{:?}_{:?}_{:?}.tif", data_type, compression, endian, layout));
+                    let mut r = test_raster(&file_name, data_type, 300, 270);
+                    r.configs.compression = compression;
+                    r.configs.predictor = RasterPredictor::Horizontal;
+                    r.configs.endian = endian;
+                    r.configs.layout = layout;
+                    match endian {
+                        Endianness::LittleEndian => write_geotiff_with_byte_order::<LittleEndian>(&r, true).unwrap(),
+                        Endianness::BigEndian => write_geotiff_with_byte_order::<BigEndian>(&r, true).unwrap(),
+                    }
+
+                    let mut f = File::open(&file_name).unwrap();
+                    let (file_endian, is_big_tiff, first_ifd) = read_tiff_header(&mut f).unwrap();
+                    assert_eq!(file_endian, endian);
+                    assert!(is_big_tiff, "{}", file_name);
+                    assert_eq!(first_ifd, 16);
+
+                    assert_same_values(&r, &file_name);
+                    let _ = fs::remove_file(&file_name);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn small_geotiff_is_not_bigtiff() {
+        let file_name = temp_file("wbt_not_bigtiff.tif");
+        let mut r = test_raster(&file_name, DataType::F64, 20, 20);
+        r.write().unwrap();
+        let (_, is_big_tiff, first_ifd) = read_tiff_header(&mut File::open(&file_name).unwrap()).unwrap();
+        assert!(!is_big_tiff);
+        assert_eq!(first_ifd, 8);
+        let _ = fs::remove_file(&file_name);
+    }
+
+    #[test]
+    fn tiled_geotiff_round_trip() {
+        // neither dimension is a multiple of the tile size, so the edge tiles are padded
+        for &data_type in [DataType::F64, DataType::F32, DataType::I32, DataType::U16, DataType::U8, DataType::RGB24].iter() {
+            for &compression in [RasterCompression::None, RasterCompression::Deflate, RasterCompression::LZW, RasterCompression::PackBits].iter() {
+                for &layout in [RasterLayout::Tiled, RasterLayout::CloudOptimized].iter() {
+                    let file_name = temp_file(&format!("wbt_tiled_{:?}_{:?}_{:?}.tif", data_type, compression, layout));
+                    let mut r = test_raster(&file_name, data_type, 300, 520);
+                    r.configs.compression = compression;
+                    r.configs.predictor = RasterPredictor::FloatingPoint;
+                    r.configs.layout = layout;
+                    r.write().unwrap();
+                    assert_same_values(&r, &file_name);
+                    let _ = fs::remove_file(&file_name);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn cloud_optimized_geotiff_structure() {
+        let file_name = temp_file("wbt_cog_structure.tif");
+        let mut r = test_raster(&file_name, DataType::F32, 600, 700);
+        r.configs.compression = RasterCompression::Deflate;
+        r.configs.layout = RasterLayout::CloudOptimized;
+        r.write().unwrap();
+
+        let file_size = fs::metadata(&file_name).unwrap().len() as usize;
+        let mut f = File::open(&file_name).unwrap();
+        let (endian, is_big_tiff, mut ifd_offset) = read_tiff_header(&mut f).unwrap();
+        let mut ifd_offsets = vec![];
+        let mut images = vec![];
+        while ifd_offset > 0 {
+            ifd_offsets.push(ifd_offset);
+            let (ifds, next_ifd_offset) = read_ifd(&mut f, file_size, endian, ifd_offset, is_big_tiff).unwrap();
+            let tag = |t: u16| ifds.iter().find(|ifd| ifd.tag == t).map(|ifd| ifd.interpret_as_u64()).unwrap_or(vec![]);
+            assert!(tag(TAG_STRIPOFFSETS).is_empty());
+            assert_eq!(tag(TAG_TILEWIDTH), vec![256]);
+            assert_eq!(tag(TAG_TILELENGTH), vec![256]);
+            images.push((tag(TAG_IMAGEWIDTH)[0], tag(TAG_IMAGELENGTH)[0], tag(TAG_NEWSUBFILETYPE), tag(TAG_TILEOFFSETS)));
+            ifd_offset = next_ifd_offset;
+        }
+
+        // the full-resolution image, followed by overviews down to the size of a single tile
+        let sizes: Vec<(u64, u64)> = images.iter().map(|i| (i.0, i.1)).collect();
+        assert_eq!(sizes, vec![(700, 600), (350, 300), (175, 150)]);
+        assert!(images[0].2.iter().all(|&v| v == 0));
+        for overview in &images[1..] {
+            assert_eq!(overview.2, vec![1]);
+        }
+
+        // all IFDs precede the image data, and the overviews precede the full-resolution tiles,
+        // smallest first
+        let first_tile = images.iter().flat_map(|i| i.3.iter().cloned()).min().unwrap() as usize;
+        assert!(ifd_offsets.iter().all(|&o| o < first_tile));
+        assert!(images[2].3.iter().max() < images[1].3.iter().min());
+        assert!(images[1].3.iter().max() < images[0].3.iter().min());
+        let _ = fs::remove_file(&file_name);
+    }
+
+    #[test]
+    fn overviews_average_valid_cells() {
+        let file_name = temp_file("wbt_overview.tif");
+        let mut r = test_raster(&file_name, DataType::F64, 3, 3);
+        let nodata = r.configs.nodata;
+        r.set_row_data(0, vec![1f64, 3f64, 10f64]);
+        r.set_row_data(1, vec![nodata, 8f64, 20f64]);
+        r.set_row_data(2, vec![nodata, nodata, 5f64]);
+        assert_eq!(build_overview(&r.configs, &r.data, 0, 3, 3), vec![4f64, 15f64, nodata, 5f64]);
+
+        // categorical rasters are sampled rather than averaged
+        r.configs.photometric_interp = PhotometricInterpretation::Categorical;
+        assert_eq!(build_overview(&r.configs, &r.data, 0, 3, 3), vec![1f64, 10f64, nodata, 5f64]);
+    }
+}
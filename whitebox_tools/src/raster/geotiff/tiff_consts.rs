@@ -166,6 +166,7 @@ pub const IM_GRAYINVERT: u16 = 4u16;
 pub const IM_RGB: u16 = 5u16;
 pub const IM_RGBA: u16 = 6u16;
 pub const IM_NRGBA: u16 = 7u16;
+pub const IM_MULTIBAND: u16 = 8u16;
 
 
 pub const COMPRESS_NONE: u16 = 1;
@@ -179,6 +180,10 @@ pub const COMPRESS_DEFLATE: u16 = 8; // zlib compression.
 pub const COMPRESS_PACKBITS: u16 = 32773;
 pub const COMPRESS_DEFLATEOLD: u16 = 32946; // Superseded by cDeflate.
 
+pub const PREDICTOR_NONE: u16 = 1;
+pub const PREDICTOR_HORIZONTAL: u16 = 2; // Horizontal differencing.
+pub const PREDICTOR_FLOATINGPOINT: u16 = 3;
+
 pub const DT_BYTE: u16 = 1;
 pub const DT_ASCII: u16 = 2;
 pub const DT_SHORT: u16 = 3;
@@ -191,6 +196,10 @@ pub const DT_SLONG: u16 = 9;
 pub const DT_SRATIONAL: u16 = 10;
 pub const DT_FLOAT: u16 = 11;
 pub const DT_DOUBLE: u16 = 12;
+pub const DT_IFD: u16 = 13;
+pub const DT_LONG8: u16 = 16; // BigTIFF
+pub const DT_SLONG8: u16 = 17; // BigTIFF
+pub const DT_IFD8: u16 = 18; // BigTIFF
 
 pub const PI_WHITEISZERO: u16 = 0;
 pub const PI_BLACKISZERO: u16 = 1;
@@ -210,8 +210,7 @@ pub fn read_idrisi(file_name: &String,
 
 pub fn write_idrisi<'a>(r: &'a mut Raster) -> Result<(), Error> {
     // figure out the minimum and maximum values
-    for val in &r.data {
-        let v = *val;
+    for v in &r.data {
         if v != r.configs.nodata {
             if v < r.configs.minimum {
                 r.configs.minimum = v;
@@ -337,7 +336,7 @@ pub fn write_idrisi<'a>(r: &'a mut Raster) -> Result<(), Error> {
     match r.configs.data_type {
         DataType::F32 => {
             for i in 0..num_cells {
-                u32_bytes = unsafe { mem::transmute(r.data[i] as f32) };
+                u32_bytes = unsafe { mem::transmute(r.data.get_value(i) as f32) };
                 try!(writer.write(&u32_bytes));
             }
         }
@@ -352,13 +351,13 @@ pub fn write_idrisi<'a>(r: &'a mut Raster) -> Result<(), Error> {
         }
         DataType::I16 => {
             for i in 0..num_cells {
-                u16_bytes = unsafe { mem::transmute(r.data[i] as u16) };
+                u16_bytes = unsafe { mem::transmute(r.data.get_value(i) as u16) };
                 try!(writer.write(&u16_bytes));
             }
         }
         DataType::U8 => {
             for i in 0..num_cells {
-                try!(writer.write(&[r.data[i] as u8]));
+                try!(writer.write(&[r.data.get_value(i) as u8]));
             }
         }
         _ => {
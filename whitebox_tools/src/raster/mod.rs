@@ -2,7 +2,7 @@
 This tool is part of the WhiteboxTools geospatial analysis library.
 Authors: Dr. John Lindsay
 Created: June 2, 2017
-Last Modified: July 7, 2017
+Last Modified: April 6, 2018
 License: MIT
 */
 
@@ -10,102 +10,83 @@ extern crate num_cpus;
 
 pub mod arcascii_raster;
 pub mod arcbinary_raster;
+pub mod block_cache;
+pub mod geopackage_raster;
 pub mod geotiff;
 pub mod grass_raster;
 pub mod idrisi_raster;
+pub mod raster_data;
 pub mod saga_raster;
 pub mod surfer7_raster;
 pub mod surfer_ascii_raster;
 pub mod whitebox_raster;
 
+use std::cmp::{max, min};
 use std::cmp::Ordering::Equal;
 use std::default::Default;
 use std::io::Error;
 use std::io::prelude::*;
 use std::io::ErrorKind;
 use std::io::BufReader;
+use std::cell::RefCell;
+use std::collections::HashMap;
 use std::fs::File;
 use std::f64;
 use std::path::Path;
-use std::ops::{Index, IndexMut};
 use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::mpsc;
 use std::thread;
 use raster::arcascii_raster::*;
 use raster::arcbinary_raster::*;
+use raster::block_cache::*;
+use raster::geopackage_raster::*;
 use raster::geotiff::*;
 use raster::grass_raster::*;
 use raster::idrisi_raster::*;
+use raster::raster_data::*;
 use raster::saga_raster::*;
 use raster::surfer7_raster::*;
 use raster::surfer_ascii_raster::*;
 use raster::whitebox_raster::*;
 use io_utils::*;
+use spatial_ref_system::SpatialRef;
 use structures::Array2D;
 
+// The number of cells in the windows of rows returned by get_window.
+const WINDOW_SIZE: usize = 4 * 1024 * 1024;
+
 #[derive(Default, Clone)]
 pub struct Raster {
     pub file_name: String,
     file_mode: String,
     pub raster_type: RasterType,
     pub configs: RasterConfigs,
-    data: Vec<f64>,
-}
-
-impl Index<(isize, isize)> for Raster {
-    type Output = f64;
-
-    fn index<'a>(&'a self, index: (isize, isize)) -> &'a f64 {
-        let row = index.0;
-        let column = index.1;
-
-        if column < 0 {
-            return &self.configs.nodata;
-        }
-        if row < 0 {
-            return &self.configs.nodata;
-        }
-
-        let c: usize = column as usize;
-        let r: usize = row as usize;
-
-        if c >= self.configs.columns {
-            return &self.configs.nodata;
-        }
-        if r >= self.configs.rows {
-            return &self.configs.nodata;
-        }
-        let idx: usize = r * self.configs.columns + c;
-        &self.data[idx]
-    }
-}
-
-impl IndexMut<(isize, isize)> for Raster {
-    fn index_mut<'a>(&'a mut self, index: (isize, isize)) -> &'a mut f64 {
-        let row = index.0;
-        let column = index.1;
-        if column < 0 {
-            return &mut self.configs.nodata;
-        }
-        if row < 0 {
-            return &mut self.configs.nodata;
-        }
-        let c: usize = column as usize;
-        let r: usize = row as usize;
-        if c >= self.configs.columns {
-            return &mut self.configs.nodata;
-        }
-        if r >= self.configs.rows {
-            return &mut self.configs.nodata;
-        }
-        let idx = r * self.configs.columns + c;
-        &mut self.data[idx as usize]
-    }
+    data: RasterData,
 }
 
 impl Raster {
+    /// Opens a raster file. A single band of a multi-band raster may be selected by
+    /// appending the band number, starting from 1, to the file name, e.g. `image.tif:3`.
     pub fn new<'a>(file_name: &'a str, file_mode: &'a str) -> Result<Raster, Error> {
         let fm: String = file_mode.to_lowercase();
+        let (file_name, band) = if fm.contains("r") {
+            split_band_selection(file_name)
+        } else {
+            (file_name.to_string(), None)
+        };
+        if fm.contains("r") && is_in_memory_raster_name(&file_name) {
+            let mut r = match get_in_memory_raster(&file_name) {
+                Some(r) => r,
+                None => return Err(Error::new(ErrorKind::NotFound,
+                                              format!("In-memory raster {} not found.", file_name))),
+            };
+            r.file_mode = fm;
+            if let Some(band) = band {
+                r.select_band(band)?;
+            }
+            return Ok(r);
+        }
         let mut r = Raster {
             file_name: file_name.to_string(),
             file_mode: fm.clone(),
@@ -115,47 +96,62 @@ impl Raster {
         if r.file_mode.contains("r") {
             match get_raster_type_from_file(file_name.to_string(), fm) {
                 RasterType::ArcBinary => {
-                    let _ = read_arcbinary(&r.file_name, &mut r.configs, &mut r.data).unwrap();
-                    return Ok(r);
+                    let mut data = vec![];
+                    let _ = read_arcbinary(&r.file_name, &mut r.configs, &mut data).unwrap();
+                    r.set_values(data);
                 }
                 RasterType::ArcAscii => {
-                    let _ = read_arcascii(&r.file_name, &mut r.configs, &mut r.data).unwrap();
-                    return Ok(r);
+                    let mut data = vec![];
+                    let _ = read_arcascii(&r.file_name, &mut r.configs, &mut data).unwrap();
+                    r.set_values(data);
+                }
+                RasterType::GeoPackage => {
+                    let mut data = vec![];
+                    read_geopackage_raster(&r.file_name, &mut r.configs, &mut data)?;
+                    r.set_values(data);
                 }
                 RasterType::GeoTiff => {
-                    let _ = read_geotiff(&r.file_name, &mut r.configs, &mut r.data).unwrap();
+                    read_geotiff(&r.file_name, &mut r.configs, &mut r.data)?;
                     r.update_min_max();
-                    return Ok(r);
                 }
                 RasterType::GrassAscii => {
-                    let _ = read_grass_raster(&r.file_name, &mut r.configs, &mut r.data).unwrap();
-                    return Ok(r);
+                    let mut data = vec![];
+                    let _ = read_grass_raster(&r.file_name, &mut r.configs, &mut data).unwrap();
+                    r.set_values(data);
                 }
                 RasterType::IdrisiBinary => {
-                    let _ = read_idrisi(&r.file_name, &mut r.configs, &mut r.data).unwrap();
-                    return Ok(r);
+                    let mut data = vec![];
+                    let _ = read_idrisi(&r.file_name, &mut r.configs, &mut data).unwrap();
+                    r.set_values(data);
                 }
                 RasterType::SagaBinary => {
-                    let _ = read_saga(&r.file_name, &mut r.configs, &mut r.data).unwrap();
-                    return Ok(r);
+                    let mut data = vec![];
+                    let _ = read_saga(&r.file_name, &mut r.configs, &mut data).unwrap();
+                    r.set_values(data);
                 }
                 RasterType::Surfer7Binary => {
-                    let _ = read_surfer7(&r.file_name, &mut r.configs, &mut r.data).unwrap();
-                    return Ok(r);
+                    let mut data = vec![];
+                    let _ = read_surfer7(&r.file_name, &mut r.configs, &mut data).unwrap();
+                    r.set_values(data);
                 }
                 RasterType::SurferAscii => {
-                    let _ = read_surfer_ascii_raster(&r.file_name, &mut r.configs, &mut r.data)
+                    let mut data = vec![];
+                    let _ = read_surfer_ascii_raster(&r.file_name, &mut r.configs, &mut data)
                         .unwrap();
-                    return Ok(r);
+                    r.set_values(data);
                 }
                 RasterType::Whitebox => {
                     let _ = read_whitebox(&r.file_name, &mut r.configs, &mut r.data).unwrap();
-                    return Ok(r);
                 }
                 RasterType::Unknown => {
                     return Err(Error::new(ErrorKind::Other, "Unrecognized raster type"));
                 }
             }
+            r.read_prj_file();
+            if let Some(band) = band {
+                r.select_band(band)?;
+            }
+            return Ok(r);
         } else {
             // write
             return Ok(r);
@@ -172,7 +168,8 @@ impl Raster {
         output.file_mode = "w".to_string();
         output.raster_type = get_raster_type_from_file(file_name.to_string(), "w".to_string());
 
-        output.data = vec![output.configs.nodata; output.configs.rows * output.configs.columns];
+        let nodata = output.configs.nodata;
+        output.data = output.new_data(nodata);
 
         output
     }
@@ -210,11 +207,20 @@ impl Raster {
             output.configs.nodata = 1.71041e38;
         }
 
-        output.data = vec![output.configs.nodata; output.configs.rows * output.configs.columns];
+        let nodata = output.configs.nodata;
+        output.data = output.new_data(nodata);
 
         output
     }
 
+    /// Changes the file name of the raster, and with it the format in which the raster will
+    /// be written, e.g. to save an in-memory raster to disk.
+    pub fn set_file_name<'a>(&mut self, file_name: &'a str) {
+        self.file_name = file_name.to_string();
+        self.file_mode = "w".to_string();
+        self.raster_type = get_raster_type_from_file(file_name.to_string(), "w".to_string());
+    }
+
     pub fn get_short_filename(&self) -> String {
         let path = Path::new(&self.file_name);
         let file_name = path.file_stem().unwrap();
@@ -232,7 +238,7 @@ impl Raster {
             let r: usize = row as usize;
         
             let idx: usize = r * self.configs.columns + c;
-            return self.data[idx];
+            return self.data.get_value(idx);
         }
 
         // it's not within the area of the data
@@ -294,7 +300,7 @@ impl Raster {
             let r: usize = row as usize;
             if c < self.configs.columns && r < self.configs.rows {
                 let idx = r * self.configs.columns + c;
-                self.data[idx] = value;
+                self.data.set_value(idx, value);
             }
         }
     }
@@ -305,10 +311,11 @@ impl Raster {
             let r: usize = row as usize;
             if c < self.configs.columns && r < self.configs.rows {
                 let idx = r * self.configs.columns + c;
-                if self.data[idx] != self.configs.nodata {
-                    self.data[idx] -= value;
+                let z = self.data.get_value(idx);
+                if z != self.configs.nodata {
+                    self.data.set_value(idx, z - value);
                 } else {
-                    self.data[idx] = value;
+                    self.data.set_value(idx, value);
                 }
             }
         }
@@ -320,36 +327,54 @@ impl Raster {
             let r: usize = row as usize;
             if c < self.configs.columns && r < self.configs.rows {
                 let idx = r * self.configs.columns + c;
-                if self.data[idx] != self.configs.nodata {
-                    self.data[idx] += value;
+                let z = self.data.get_value(idx);
+                if z != self.configs.nodata {
+                    self.data.set_value(idx, z + value);
                 } else {
-                    self.data[idx] = value;
+                    self.data.set_value(idx, value);
                 }
             }
         }
     }
 
     pub fn set_row_data(&mut self, row: isize, values: Vec<f64>) {
-        for column in 0..values.len() {
-            if row >= 0 {
-                let c: usize = column as usize;
-                let r: usize = row as usize;
-                if c < self.configs.columns && r < self.configs.rows {
-                    let idx = r * self.configs.columns + c;
-                    self.data[idx] = values[c];
-                }
-            }
+        if row >= 0 && row < self.configs.rows as isize {
+            let n = min(values.len(), self.configs.columns);
+            self.data.set_values(row as usize * self.configs.columns, &values[0..n]);
         }
     }
 
     pub fn get_row_data(&self, row: isize) -> Vec<f64> {
-        let mut values: Vec<f64> = vec![self.configs.nodata; self.configs.columns];
         if row >= 0 && row < self.configs.rows as isize {
-            for column in 0..values.len() {
-                values[column] = self.data[row as usize * self.configs.columns + column];
-            }
+            let start = row as usize * self.configs.columns;
+            return self.data.get_values(start, start + self.configs.columns);
         }
-        values
+        vec![self.configs.nodata; self.configs.columns]
+    }
+
+    /// Returns a copy of the rows from start_row up to, but not including, end_row, which
+    /// may extend beyond the edges of the raster.
+    pub fn get_window(&self, start_row: isize, end_row: isize) -> RasterWindow {
+        let first_row = max(0, start_row);
+        let last_row = max(first_row, min(end_row, self.configs.rows as isize));
+        let columns = self.configs.columns;
+        RasterWindow {
+            first_row: first_row,
+            num_rows: last_row - first_row,
+            columns: columns as isize,
+            nodata: self.configs.nodata,
+            data: self.data.get_values(first_row as usize * columns, last_row as usize * columns),
+        }
+    }
+
+    /// Returns the number of rows that tools working on local neighbourhoods should process
+    /// at a time, using get_window, such that their memory use is bounded.
+    pub fn get_window_rows(&self) -> isize {
+        let mut window_size = WINDOW_SIZE;
+        if get_max_raster_memory() > 0 {
+            window_size = min(window_size, get_max_raster_memory() / 8);
+        }
+        max(1, window_size / max(1, self.configs.columns)) as isize
     }
 
     pub fn increment_row_data(&mut self, row: isize, values: Vec<f64>) {
@@ -359,7 +384,8 @@ impl Raster {
                 let r: usize = row as usize;
                 if c < self.configs.columns && r < self.configs.rows {
                     let idx = r * self.configs.columns + c;
-                    self.data[idx] += values[c];
+                    let z = self.data.get_value(idx);
+                    self.data.set_value(idx, z + values[c]);
                 }
             }
         }
@@ -372,7 +398,8 @@ impl Raster {
                 let r: usize = row as usize;
                 if c < self.configs.columns && r < self.configs.rows {
                     let idx = r * self.configs.columns + c;
-                    self.data[idx] -= values[c];
+                    let z = self.data.get_value(idx);
+                    self.data.set_value(idx, z - values[c]);
                 }
             }
         }
@@ -402,7 +429,110 @@ impl Raster {
     }
 
     pub fn reinitialize_values(&mut self, value: f64) {
-        self.data = vec![value; self.configs.rows * self.configs.columns];
+        self.data = self.new_data(value);
+    }
+
+    // Creates the cell values of a new raster, each set to value. Whitebox and GeoTIFF
+    // rasters that are too large to hold in memory are stored out-of-core.
+    fn new_data(&self, value: f64) -> RasterData {
+        let num_cells = self.configs.rows * self.configs.columns * self.num_bands();
+        if (self.raster_type == RasterType::Whitebox || self.raster_type == RasterType::GeoTiff) && exceeds_max_raster_memory(num_cells) {
+            return RasterData::Cached(BlockCache::new(self.configs.rows, self.configs.columns, self.num_bands(), value, BlockSource::Fill, get_max_raster_memory()));
+        }
+        RasterData::new(self.configs.data_type, num_cells, value)
+    }
+
+    /// Returns true if the raster is stored out-of-core, rather than in memory.
+    pub fn is_out_of_core(&self) -> bool {
+        self.data.is_cached()
+    }
+
+    /// Sets the data type of the raster. Rasters of the F32 and the 8-, 16- and 32-bit
+    /// integer types are stored natively, using less memory than the f64 values of other
+    /// types, provided that their values can be represented by the data type.
+    pub fn set_data_type(&mut self, data_type: DataType) {
+        self.configs.data_type = data_type;
+        self.data.convert_to(data_type);
+    }
+
+    // Replaces the cell values with values read from a file, storing them natively if
+    // the raster's data type allows it.
+    fn set_values(&mut self, values: Vec<f64>) {
+        self.data = RasterData::F64(values);
+        self.data.convert_to(self.configs.data_type);
+    }
+
+    /// Returns the number of bands in the raster. The bands of a multi-band raster are
+    /// stored one after another and the single-band methods, e.g. `get_value` and
+    /// `set_value`, operate on the first band.
+    pub fn num_bands(&self) -> usize {
+        max(1usize, self.configs.bands as usize)
+    }
+
+    /// Returns the value of a cell within a band, numbered from zero.
+    pub fn get_band_value(&self, band: usize, row: isize, column: isize) -> f64 {
+        if band < self.num_bands() && column >= 0 && row >= 0 &&
+           column < self.configs.columns as isize && row < self.configs.rows as isize {
+            let idx = (band * self.configs.rows + row as usize) * self.configs.columns + column as usize;
+            return self.data.get_value(idx);
+        }
+        self.configs.nodata
+    }
+
+    /// Sets the value of a cell within a band, numbered from zero.
+    pub fn set_band_value(&mut self, band: usize, row: isize, column: isize, value: f64) {
+        if band < self.num_bands() && column >= 0 && row >= 0 &&
+           column < self.configs.columns as isize && row < self.configs.rows as isize {
+            let idx = (band * self.configs.rows + row as usize) * self.configs.columns + column as usize;
+            self.data.set_value(idx, value);
+        }
+    }
+
+    /// Returns a copy of a single band, numbered from zero, as a single-band raster.
+    pub fn get_band(&self, band: usize) -> Result<Raster, Error> {
+        if band >= self.num_bands() {
+            return Err(Error::new(ErrorKind::InvalidInput,
+                                  format!("Band {} does not exist in {}, which has {} band(s).", band + 1, self.file_name, self.num_bands())));
+        }
+        let num_cells = self.configs.rows * self.configs.columns;
+        let mut output = Raster {
+            file_name: self.file_name.clone(),
+            file_mode: self.file_mode.clone(),
+            raster_type: self.raster_type.clone(),
+            configs: self.configs.clone(),
+            data: self.data.slice(band * num_cells, (band + 1) * num_cells),
+        };
+        output.configs.bands = 1;
+        output.configs.minimum = f64::INFINITY;
+        output.configs.maximum = f64::NEG_INFINITY;
+        output.configs.display_min = f64::INFINITY;
+        output.configs.display_max = f64::NEG_INFINITY;
+        output.update_min_max();
+        Ok(output)
+    }
+
+    // Discards all but one band, numbered from 1, of a multi-band raster.
+    fn select_band(&mut self, band: usize) -> Result<(), Error> {
+        if band == 0 {
+            return Err(Error::new(ErrorKind::InvalidInput, "Raster bands are numbered starting from 1."));
+        }
+        if band <= self.num_bands() {
+            if let RasterData::Cached(ref mut cache) = self.data {
+                // Rather than copying the band, restrict the cache to it.
+                cache.select_band(band - 1);
+                self.configs.bands = 1;
+                self.configs.minimum = f64::INFINITY;
+                self.configs.maximum = f64::NEG_INFINITY;
+                self.configs.display_min = f64::INFINITY;
+                self.configs.display_max = f64::NEG_INFINITY;
+            }
+            if self.data.is_cached() {
+                self.update_min_max();
+                return Ok(());
+            }
+        }
+        *self = self.get_band(band - 1)?;
+        Ok(())
     }
 
     pub fn get_value_as_rgba(&self, row: isize, column: isize) -> (u8, u8, u8, u8) {
@@ -423,7 +553,7 @@ impl Raster {
             return (0, 0, 0, 0);
         }
         let idx: usize = r * self.configs.columns + c;
-        let z = self.data[idx];
+        let z = self.data.get_value(idx);
 
         let r = (z as u32 & 0xFF) as u8;
         let g = ((z as u32 >> 8) & 0xFF) as u8;
@@ -440,7 +570,8 @@ impl Raster {
             if c < self.configs.columns && r < self.configs.rows {
                 let idx = r * self.configs.columns + c;
                 let (r, g, b, a) = rgba;
-                self.data[idx] += ((a << 24) | (b << 16) | (g << 8) | r) as f64;
+                let z = self.data.get_value(idx);
+                self.data.set_value(idx, z + ((a << 24) | (b << 16) | (g << 8) | r) as f64);
             }
         }
     }
@@ -468,7 +599,7 @@ impl Raster {
 
     pub fn clip_display_min_max(&mut self, percent: f64) {
         let t = (percent / 100.0 * (self.configs.rows * self.configs.columns) as f64) as usize;
-        let mut d = self.data.clone();
+        let mut d = self.data.to_vec();
         d.sort_by(|a, b| a.partial_cmp(b).unwrap_or(Equal));
         let mut sum = 0;
         for i in 0..d.len() {
@@ -495,7 +626,7 @@ impl Raster {
 
     pub fn clip_display_min(&mut self, percent: f64) {
         let t = (percent / 100.0 * (self.configs.rows * self.configs.columns) as f64) as usize;
-        let mut d = self.data.clone();
+        let mut d = self.data.to_vec();
         d.sort_by(|a, b| a.partial_cmp(b).unwrap_or(Equal));
         let mut sum = 0;
         for i in 0..d.len() {
@@ -511,7 +642,7 @@ impl Raster {
 
     pub fn clip_display_max(&mut self, percent: f64) {
         let t = (percent / 100.0 * (self.configs.rows * self.configs.columns) as f64) as usize;
-        let mut d = self.data.clone();
+        let mut d = self.data.to_vec();
         d.sort_by(|a, b| a.partial_cmp(b).unwrap_or(Equal));
         let mut sum = 0;
         for i in (0..d.len()).rev() {
@@ -564,7 +695,7 @@ impl Raster {
 
     pub fn clip_min_by_percent(&mut self, percent: f64) {
         let t = (percent / 100.0 * (self.configs.rows * self.configs.columns) as f64) as usize;
-        let mut d = self.data.clone();
+        let mut d = self.data.to_vec();
         d.sort_by(|a, b| a.partial_cmp(b).unwrap_or(Equal));
         let mut sum = 0;
         let mut val = 0.0;
@@ -579,9 +710,9 @@ impl Raster {
         }
 
         for i in 0..self.data.len() {
-            if self.data[i] != self.configs.nodata {
-                if self.data[i] < val {
-                    self.data[i] = val;
+            if self.data.get_value(i) != self.configs.nodata {
+                if self.data.get_value(i) < val {
+                    self.data.set_value(i, val);
                 }
             }
         }
@@ -591,7 +722,7 @@ impl Raster {
 
     pub fn clip_max_by_percent(&mut self, percent: f64) {
         let t = (percent / 100.0 * (self.configs.rows * self.configs.columns) as f64) as usize;
-        let mut d = self.data.clone();
+        let mut d = self.data.to_vec();
         d.sort_by(|a, b| a.partial_cmp(b).unwrap_or(Equal));
         let mut sum = 0;
         let mut val = 0.0;
@@ -606,9 +737,9 @@ impl Raster {
         }
 
         for i in 0..self.data.len() {
-            if self.data[i] != self.configs.nodata {
-                if self.data[i] > val {
-                    self.data[i] = val;
+            if self.data.get_value(i) != self.configs.nodata {
+                if self.data.get_value(i) > val {
+                    self.data.set_value(i, val);
                 }
             }
         }
@@ -618,7 +749,7 @@ impl Raster {
 
     pub fn clip_min_and_max_by_percent(&mut self, percent: f64) {
         let t = (percent / 100.0 * (self.configs.rows * self.configs.columns) as f64) as usize;
-        let mut d = self.data.clone();
+        let mut d = self.data.to_vec();
         d.sort_by(|a, b| a.partial_cmp(b).unwrap_or(Equal));
         let mut sum = 0;
         let mut lower_val = 0.0;
@@ -645,11 +776,11 @@ impl Raster {
         }
 
         for i in 0..self.data.len() {
-            if self.data[i] != self.configs.nodata {
-                if self.data[i] < lower_val {
-                    self.data[i] = lower_val;
-                } else if self.data[i] > upper_val {
-                    self.data[i] = upper_val;
+            if self.data.get_value(i) != self.configs.nodata {
+                if self.data.get_value(i) < lower_val {
+                    self.data.set_value(i, lower_val);
+                } else if self.data.get_value(i) > upper_val {
+                    self.data.set_value(i, upper_val);
                 }
             }
         }
@@ -659,6 +790,29 @@ impl Raster {
     }
 
     pub fn update_min_max(&mut self) {
+        if self.data.is_cached() {
+            let nodata = self.configs.nodata;
+            let (mut min_val, mut max_val) = (self.configs.minimum, self.configs.maximum);
+            self.scan_values(0, self.data.len(), |value| {
+                if value != nodata {
+                    if value < min_val {
+                        min_val = value;
+                    }
+                    if value > max_val {
+                        max_val = value;
+                    }
+                }
+            });
+            self.configs.minimum = min_val;
+            self.configs.maximum = max_val;
+            if self.configs.display_min == f64::INFINITY {
+                self.configs.display_min = self.configs.minimum;
+            }
+            if self.configs.display_max == f64::NEG_INFINITY {
+                self.configs.display_max = self.configs.maximum;
+            }
+            return;
+        }
         let num_procs = num_cpus::get();
         let nodata = self.configs.nodata;
         let values = Arc::new(self.data.clone());
@@ -671,7 +825,7 @@ impl Raster {
                 let mut max_val = f64::NEG_INFINITY;
                 let mut value: f64;
                 for i in (0..values.len()).filter(|v| v % num_procs == tid) {
-                    value = values[i];
+                    value = values.get_value(i);
                     if value != nodata {
                         if value < min_val {
                             min_val = value;
@@ -723,11 +877,34 @@ impl Raster {
         self.configs.rows * self.configs.columns
     }
 
+    // Applies f to each of the values from start up to, but not including, end. The values
+    // are read one row at a time, which suits rasters that are stored out-of-core.
+    fn scan_values<F: FnMut(f64)>(&self, start: usize, end: usize, mut f: F) {
+        let columns = max(1, self.configs.columns);
+        let mut i = start;
+        while i < end {
+            let n = min(columns, end - i);
+            for value in self.data.get_values(i, i + n) {
+                f(value);
+            }
+            i += n;
+        }
+    }
+
     pub fn calculate_mean(&self) -> f64 {
         if self.data.len() == 0 {
             return 0.0;
         }
         let nodata = self.configs.nodata;
+        if self.data.is_cached() {
+            let mut sum = 0.0f64;
+            let mut count = 0.0f64;
+            self.scan_values(0, self.num_cells(), |value| if value != nodata {
+                sum += value;
+                count += 1.0;
+            });
+            return sum / count;
+        }
         let values = Arc::new(self.data.clone());
         let mut starting_idx;
         let mut ending_idx = 0;
@@ -749,8 +926,8 @@ impl Raster {
                 let mut sum = 0.0f64;
                 let mut count = 0.0f64;
                 for i in starting_idx..ending_idx {
-                    if values[i] != nodata {
-                        sum += values[i];
+                    if values.get_value(i) != nodata {
+                        sum += values.get_value(i);
                         count += 1.0;
                     }
                     tx.send((sum, count)).unwrap();
@@ -776,6 +953,15 @@ impl Raster {
 
         let mean = self.calculate_mean();
         let nodata = self.configs.nodata;
+        if self.data.is_cached() {
+            let mut sq_diff_sum = 0.0f64;
+            let mut count = 0.0f64;
+            self.scan_values(0, self.num_cells(), |value| if value != nodata {
+                sq_diff_sum += (value - mean) * (value - mean);
+                count += 1.0;
+            });
+            return (mean, (sq_diff_sum / count).sqrt());
+        }
         let values = Arc::new(self.data.clone());
         let num_procs = num_cpus::get();
         let num_cells = self.num_cells();
@@ -787,8 +973,8 @@ impl Raster {
                 let mut sq_diff_sum = 0.0f64;
                 let mut count = 0.0f64;
                 for i in (0..num_cells).filter(|r| r % num_procs == tid) {
-                    if values[i] != nodata {
-                        sq_diff_sum += (values[i] - mean) * (values[i] - mean);
+                    if values.get_value(i) != nodata {
+                        sq_diff_sum += (values.get_value(i) - mean) * (values.get_value(i) - mean);
                         count += 1.0;
                     }
                     tx.send((sq_diff_sum, count)).unwrap();
@@ -811,7 +997,7 @@ impl Raster {
         let t = (percent / 100.0 * (self.configs.rows * self.configs.columns) as f64) as usize;
         let mut lower_tail = f64::NEG_INFINITY;
         let mut upper_tail = f64::NEG_INFINITY;
-        let mut d = self.data.clone();
+        let mut d = self.data.to_vec();
         d.sort_by(|a, b| a.partial_cmp(b).unwrap_or(Equal));
         let mut sum = 0;
         for i in 0..d.len() {
@@ -839,68 +1025,104 @@ impl Raster {
     }
 
     pub fn write(&mut self) -> Result<(), Error> {
+        if is_in_memory_raster_name(&self.file_name) {
+            let name = self.file_name.clone();
+            set_in_memory_raster(&name, Arc::new(self.clone()));
+            return Ok(());
+        }
+        if self.num_bands() > 1 && self.raster_type != RasterType::GeoTiff && self.raster_type != RasterType::Whitebox {
+            return Err(Error::new(ErrorKind::InvalidInput,
+                                  "Multi-band rasters can only be written in the GeoTIFF and Whitebox formats."));
+        }
         match self.raster_type {
             RasterType::ArcAscii => {
-                let _ = match write_arcascii(self) {
-                    Ok(_) => (),
-                    Err(e) => println!("error while writing: {:?}", e),
-                };
+                write_arcascii(self)?;
             }
             RasterType::ArcBinary => {
-                let _ = match write_arcbinary(self) {
-                    Ok(_) => (),
-                    Err(e) => println!("error while writing: {:?}", e),
-                };
+                write_arcbinary(self)?;
+            }
+            RasterType::GeoPackage => {
+                write_geopackage_raster(self)?;
             }
             RasterType::GeoTiff => {
-                let _ = match write_geotiff(self) {
-                    Ok(_) => (),
-                    Err(e) => println!("error while writing: {:?}", e),
-                };
+                write_geotiff(self)?;
             }
             RasterType::GrassAscii => {
-                let _ = match write_grass_raster(self) {
-                    Ok(_) => (),
-                    Err(e) => println!("error while writing: {:?}", e),
-                };
+                write_grass_raster(self)?;
             }
             RasterType::IdrisiBinary => {
-                let _ = match write_idrisi(self) {
-                    Ok(_) => (),
-                    Err(e) => println!("error while writing: {:?}", e),
-                };
+                write_idrisi(self)?;
             }
             RasterType::SagaBinary => {
-                let _ = match write_saga(self) {
-                    Ok(_) => (),
-                    Err(e) => println!("error while writing: {:?}", e),
-                };
+                write_saga(self)?;
             }
             RasterType::Surfer7Binary => {
-                let _ = match write_surfer7(self) {
-                    Ok(_) => (),
-                    Err(e) => println!("error while writing: {:?}", e),
-                };
+                write_surfer7(self)?;
             }
             RasterType::SurferAscii => {
-                let _ = match write_surfer_ascii_raster(self) {
-                    Ok(_) => (),
-                    Err(e) => println!("error while writing: {:?}", e),
-                };
+                write_surfer_ascii_raster(self)?;
             }
             RasterType::Whitebox => {
-                let _ = match write_whitebox(self) {
-                    Ok(_) => (),
-                    Err(e) => println!("error while writing: {:?}", e),
-                };
+                write_whitebox(self)?;
             }
             RasterType::Unknown => {
                 return Err(Error::new(ErrorKind::Other, "Unrecognized raster type"));
             }
         }
+        self.write_prj_file()?;
         Ok(())
     }
 
+    // The formats without a field for the coordinate reference system have it in a .prj file.
+    fn has_prj_file(&self) -> bool {
+        match self.raster_type {
+            RasterType::ArcAscii | RasterType::ArcBinary | RasterType::GrassAscii |
+            RasterType::SagaBinary | RasterType::Surfer7Binary | RasterType::SurferAscii |
+            RasterType::Whitebox => true,
+            _ => false,
+        }
+    }
+
+    fn read_prj_file(&mut self) {
+        let wkt = self.configs.coordinate_ref_system_wkt.trim().to_lowercase();
+        if !self.has_prj_file() || self.configs.epsg_code != 0 || !(wkt.is_empty() || wkt == "not specified") {
+            return;
+        }
+        let prj = Path::new(&self.file_name).with_extension("prj");
+        if let Ok(mut f) = File::open(&prj) {
+            let mut wkt = String::new();
+            if f.read_to_string(&mut wkt).is_ok() && !wkt.trim().is_empty() {
+                if let Ok(srs) = SpatialRef::from_wkt(&wkt) {
+                    self.configs.epsg_code = srs.epsg_code;
+                }
+                self.configs.coordinate_ref_system_wkt = wkt.trim().to_string();
+            }
+        }
+    }
+
+    fn write_prj_file(&self) -> Result<(), Error> {
+        if !self.has_prj_file() {
+            return Ok(());
+        }
+        let wkt = self.configs.coordinate_ref_system_wkt.trim();
+        let wkt = if wkt.contains("[") {
+            wkt.to_string()
+        } else {
+            match SpatialRef::from_epsg_or_wkt(self.configs.epsg_code, "") {
+                Some(srs) => srs.to_wkt(),
+                None => return Ok(()),
+            }
+        };
+        let mut f = File::create(Path::new(&self.file_name).with_extension("prj"))?;
+        f.write_all(wkt.as_bytes())
+    }
+
+    /// Returns the coordinate reference system of the raster, if it is specified by a
+    /// supported EPSG code or WKT.
+    pub fn get_spatial_ref(&self) -> Option<SpatialRef> {
+        SpatialRef::from_epsg_or_wkt(self.configs.epsg_code, &self.configs.coordinate_ref_system_wkt)
+    }
+
     pub fn add_metadata_entry(&mut self, value: String) {
         self.configs.metadata.push(value);
     }
@@ -913,6 +1135,9 @@ impl Raster {
     }
 
     pub fn is_in_geographic_coordinates(&self) -> bool {
+        if let Some(srs) = self.get_spatial_ref() {
+            return srs.is_geographic();
+        }
         if self.configs.epsg_code == 4322 || self.configs.epsg_code == 4326 ||
            self.configs.epsg_code == 4629 || self.configs.epsg_code == 4277 {
             return true;
@@ -928,6 +1153,30 @@ impl Raster {
     }
 }
 
+/// A copy of a band of consecutive rows of a raster, indexed using the rows and columns of
+/// the full raster. Cells outside of the window are nodata.
+///
+/// Tools that work on local neighbourhoods process rasters one window at a time, with a
+/// halo of rows on either side, so that rasters stored out-of-core are read sequentially
+/// rather than paged in and out of memory as the neighbourhood moves.
+pub struct RasterWindow {
+    first_row: isize,
+    num_rows: isize,
+    columns: isize,
+    nodata: f64,
+    data: Vec<f64>,
+}
+
+impl RasterWindow {
+    pub fn get_value(&self, row: isize, column: isize) -> f64 {
+        let row = row - self.first_row;
+        if row < 0 || column < 0 || row >= self.num_rows || column >= self.columns {
+            return self.nodata;
+        }
+        self.data[(row * self.columns + column) as usize]
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct RasterConfigs {
     pub title: String,
@@ -958,6 +1207,10 @@ pub struct RasterConfigs {
     pub epsg_code: u16,
     pub coordinate_ref_system_wkt: String,
     pub metadata: Vec<String>,
+    pub compression: RasterCompression,
+    pub predictor: RasterPredictor,
+    pub layout: RasterLayout,
+    pub planar_config: PlanarConfiguration,
 }
 
 impl Default for RasterConfigs {
@@ -991,6 +1244,10 @@ impl Default for RasterConfigs {
             epsg_code: 0u16,
             coordinate_ref_system_wkt: "not specified".to_string(),
             metadata: vec![],
+            compression: RasterCompression::default(),
+            predictor: RasterPredictor::default(),
+            layout: RasterLayout::default(),
+            planar_config: PlanarConfiguration::default(),
         }
     }
 }
@@ -1000,6 +1257,7 @@ pub enum RasterType {
     Unknown,
     ArcAscii,
     ArcBinary,
+    GeoPackage,
     GeoTiff,
     GrassAscii,
     IdrisiBinary,
@@ -1015,6 +1273,22 @@ impl Default for RasterType {
     }
 }
 
+// Splits a band selection, e.g. the '3' in 'image.tif:3', from a file name. The suffix is
+// only treated as a band number if no file exists with the full name.
+fn split_band_selection(file_name: &str) -> (String, Option<usize>) {
+    if let Some(pos) = file_name.rfind(':') {
+        let name = &file_name[..pos];
+        let suffix = file_name[(pos + 1)..].trim();
+        if !name.is_empty() && !suffix.is_empty() && suffix.chars().all(|c| c.is_digit(10)) &&
+           !Path::new(file_name).exists() {
+            if let Ok(band) = suffix.parse::<usize>() {
+                return (name.to_string(), Some(band));
+            }
+        }
+    }
+    (file_name.to_string(), None)
+}
+
 fn get_raster_type_from_file(file_name: String, file_mode: String) -> RasterType {
     // get the file extension
     let extension: String = match Path::new(&file_name).extension().unwrap().to_str() {
@@ -1028,6 +1302,8 @@ fn get_raster_type_from_file(file_name: String, file_mode: String) -> RasterType
         return RasterType::GeoTiff;
     } else if extension == "flt" {
         return RasterType::ArcBinary;
+    } else if extension == "gpkg" {
+        return RasterType::GeoPackage;
     } else if extension == "rdc" || extension == "rst" {
         return RasterType::IdrisiBinary;
     } else if extension == "sdat" || extension == "sgrd" {
@@ -1143,6 +1419,206 @@ impl Default for PhotometricInterpretation {
     }
 }
 
+/// The arrangement of the bands of a multi-band raster within a file. With the chunky
+/// configuration, the values of all bands are stored together for each pixel, while with
+/// the planar configuration, each band is stored separately. This corresponds to the TIFF
+/// PlanarConfiguration tag.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum PlanarConfiguration {
+    Chunky = 1,
+    Planar = 2,
+}
+
+impl Default for PlanarConfiguration {
+    fn default() -> PlanarConfiguration {
+        PlanarConfiguration::Chunky
+    }
+}
+
+// The process-wide defaults used for newly created rasters; see set_default_compression.
+static DEFAULT_COMPRESSION: AtomicUsize = AtomicUsize::new(0);
+static DEFAULT_PREDICTOR: AtomicUsize = AtomicUsize::new(0);
+static DEFAULT_LAYOUT: AtomicUsize = AtomicUsize::new(0);
+
+/// Sets the compression method and predictor used by default when rasters are written.
+/// This is how the `--compress_rasters` and `--predictor` command-line flags are applied.
+pub fn set_default_compression(compression: RasterCompression, predictor: RasterPredictor) {
+    DEFAULT_COMPRESSION.store(compression as usize, Ordering::SeqCst);
+    DEFAULT_PREDICTOR.store(predictor as usize, Ordering::SeqCst);
+}
+
+/// The compression method used when writing a raster. Currently, only
+/// the GeoTIFF format supports compression and it is ignored by other formats.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum RasterCompression {
+    None = 0,
+    Deflate = 1,
+    LZW = 2,
+    PackBits = 3,
+}
+
+impl Default for RasterCompression {
+    fn default() -> RasterCompression {
+        match DEFAULT_COMPRESSION.load(Ordering::SeqCst) {
+            1 => RasterCompression::Deflate,
+            2 => RasterCompression::LZW,
+            3 => RasterCompression::PackBits,
+            _ => RasterCompression::None,
+        }
+    }
+}
+
+impl RasterCompression {
+    pub fn from_str<'a>(val: &'a str) -> Result<RasterCompression, Error> {
+        match val.trim().to_lowercase().as_ref() {
+            "none" | "" => Ok(RasterCompression::None),
+            "deflate" | "zip" => Ok(RasterCompression::Deflate),
+            "lzw" => Ok(RasterCompression::LZW),
+            "packbits" => Ok(RasterCompression::PackBits),
+            _ => Err(Error::new(ErrorKind::InvalidInput,
+                                format!("Unrecognized raster compression method '{}'. Options include 'none', 'deflate', 'lzw', and 'packbits'.", val))),
+        }
+    }
+}
+
+/// The predictor applied to raster data prior to compression. The floating-point
+/// predictor only applies to floating-point data; integer data use horizontal
+/// differencing instead.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum RasterPredictor {
+    None = 0,
+    Horizontal = 1,
+    FloatingPoint = 2,
+}
+
+impl Default for RasterPredictor {
+    fn default() -> RasterPredictor {
+        match DEFAULT_PREDICTOR.load(Ordering::SeqCst) {
+            1 => RasterPredictor::Horizontal,
+            2 => RasterPredictor::FloatingPoint,
+            _ => RasterPredictor::None,
+        }
+    }
+}
+
+impl RasterPredictor {
+    pub fn from_str<'a>(val: &'a str) -> Result<RasterPredictor, Error> {
+        match val.trim().to_lowercase().as_ref() {
+            "none" | "1" | "" => Ok(RasterPredictor::None),
+            "horizontal" | "2" => Ok(RasterPredictor::Horizontal),
+            "floatingpoint" | "floating_point" | "float" | "3" => Ok(RasterPredictor::FloatingPoint),
+            _ => Err(Error::new(ErrorKind::InvalidInput,
+                                format!("Unrecognized raster predictor '{}'. Options include 'none', 'horizontal', and 'floatingpoint'.", val))),
+        }
+    }
+}
+
+/// Sets the internal layout used by default when rasters are written. This is how
+/// the `--raster_layout` command-line flag is applied.
+pub fn set_default_layout(layout: RasterLayout) {
+    DEFAULT_LAYOUT.store(layout as usize, Ordering::SeqCst);
+}
+
+/// The internal organization of the image data when writing a raster. Like compression,
+/// this currently only applies to the GeoTIFF format. Cloud-optimized GeoTIFFs (COGs) are
+/// tiled and contain a pyramid of overviews, with all of the IFDs at the start of the file,
+/// followed by the overviews and then the full-resolution tiles.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum RasterLayout {
+    Strips = 0,
+    Tiled = 1,
+    CloudOptimized = 2,
+}
+
+impl Default for RasterLayout {
+    fn default() -> RasterLayout {
+        match DEFAULT_LAYOUT.load(Ordering::SeqCst) {
+            1 => RasterLayout::Tiled,
+            2 => RasterLayout::CloudOptimized,
+            _ => RasterLayout::Strips,
+        }
+    }
+}
+
+impl RasterLayout {
+    pub fn from_str<'a>(val: &'a str) -> Result<RasterLayout, Error> {
+        match val.trim().to_lowercase().as_ref() {
+            "strips" | "striped" | "" => Ok(RasterLayout::Strips),
+            "tiled" | "tiles" => Ok(RasterLayout::Tiled),
+            "cog" | "cloud_optimized" | "cloudoptimized" => Ok(RasterLayout::CloudOptimized),
+            _ => Err(Error::new(ErrorKind::InvalidInput,
+                                format!("Unrecognized raster layout '{}'. Options include 'strips', 'tiled', and 'cog'.", val))),
+        }
+    }
+}
+
+// The maximum memory, in bytes, that the values of a raster may occupy before the raster is
+// stored out-of-core; see set_max_raster_memory. Zero indicates that there is no limit.
+static MAX_RASTER_MEMORY: AtomicUsize = AtomicUsize::new(0);
+
+/// Sets the maximum amount of memory, in bytes, that the values of a raster may occupy.
+/// Whitebox and GeoTIFF rasters that are larger than this are stored out-of-core, in a
+/// block cache that holds at most this much of the raster in memory, with the remainder
+/// paged in from disk on demand. A limit of zero, the default, holds all rasters in memory.
+/// This is how the `--max_raster_memory` command-line flag is applied.
+pub fn set_max_raster_memory(bytes: usize) {
+    MAX_RASTER_MEMORY.store(bytes, Ordering::SeqCst);
+}
+
+pub fn get_max_raster_memory() -> usize {
+    MAX_RASTER_MEMORY.load(Ordering::SeqCst)
+}
+
+/// Returns true if a raster containing num_cells cells, including those of every band, is
+/// too large to hold in memory.
+pub fn exceeds_max_raster_memory(num_cells: usize) -> bool {
+    let limit = get_max_raster_memory();
+    limit > 0 && num_cells * 8 > limit
+}
+
+/// The prefix of the file names of in-memory rasters, e.g. `memory://dem.tif`.
+pub const IN_MEMORY_RASTER_PREFIX: &'static str = "memory://";
+
+thread_local! {
+    // Rasters held in memory rather than on disk, keyed by file name.
+    static IN_MEMORY_RASTERS: RefCell<HashMap<String, Arc<Raster>>> = RefCell::new(HashMap::new());
+}
+
+/// Returns true if a file name refers to an in-memory raster, i.e. it begins with
+/// IN_MEMORY_RASTER_PREFIX.
+pub fn is_in_memory_raster_name(file_name: &str) -> bool {
+    file_name.starts_with(IN_MEMORY_RASTER_PREFIX)
+}
+
+/// Places a raster in memory under the given file name, which must begin with
+/// IN_MEMORY_RASTER_PREFIX and end with a raster file extension. Opening the file name with
+/// `Raster::new` returns a copy of the raster and writing a raster with this file name
+/// replaces it, without either touching the disk. The raster is shared rather than copied,
+/// so that it can also be held elsewhere, e.g. by a workflow. In-memory rasters are only
+/// visible from the thread on which they are set.
+pub fn set_in_memory_raster(file_name: &str, raster: Arc<Raster>) {
+    IN_MEMORY_RASTERS.with(|m| {
+        m.borrow_mut().insert(file_name.to_string(), raster);
+    });
+}
+
+/// Returns a copy of an in-memory raster, if there is one with the given file name.
+pub fn get_in_memory_raster(file_name: &str) -> Option<Raster> {
+    IN_MEMORY_RASTERS.with(|m| m.borrow().get(file_name).map(|r| (**r).clone()))
+}
+
+/// Returns a copy of the configurations of an in-memory raster, without copying its data.
+pub fn get_in_memory_raster_configs(file_name: &str) -> Option<RasterConfigs> {
+    IN_MEMORY_RASTERS.with(|m| m.borrow().get(file_name).map(|r| r.configs.clone()))
+}
+
+/// Removes an in-memory raster, returning it if there was one with the given file name. The
+/// raster is copied only if it is still shared.
+pub fn take_in_memory_raster(file_name: &str) -> Option<Raster> {
+    IN_MEMORY_RASTERS.with(|m| m.borrow_mut().remove(file_name))
+        .map(|r| Arc::try_unwrap(r).unwrap_or_else(|r| (*r).clone()))
+}
+
 // #[derive(Debug, Copy, Clone, PartialEq)]
 // pub enum RasterByteOrder {
 //     LittleEndian,
@@ -0,0 +1,497 @@
+/*
+This tool is part of the WhiteboxTools geospatial analysis library.
+Authors: Dr. John Lindsay
+Created: March 15, 2018
+Last Modified: April 4, 2018
+License: MIT
+*/
+
+use std::{i16, i32, i8, u16, u8};
+use raster::DataType;
+use raster::block_cache::BlockCache;
+
+/// The cell values of a raster.
+///
+/// Rasters of the F32 and the 8-, 16- and 32-bit integer data types are stored natively,
+/// such that, for example, a U8 classified raster occupies a single byte per cell. All
+/// other data types are stored as f64. Values are converted to the native type one at a
+/// time, as they are assigned. Storage is lossless: assigning a value that cannot be
+/// represented exactly by the native type, e.g. an intermediate result that a tool reads
+/// back, converts the buffer to f64 storage.
+///
+/// Rasters that are too large to hold in memory are stored out-of-core, in a `BlockCache`.
+#[derive(Clone, Debug)]
+pub enum RasterData {
+    F64(Vec<f64>),
+    F32(Vec<f32>),
+    I32(Vec<i32>),
+    I16(Vec<i16>),
+    U16(Vec<u16>),
+    I8(Vec<i8>),
+    U8(Vec<u8>),
+    Cached(BlockCache),
+}
+
+impl Default for RasterData {
+    fn default() -> RasterData {
+        RasterData::F64(vec![])
+    }
+}
+
+impl RasterData {
+    /// Creates a buffer of len cells, each set to value, using the native storage for
+    /// data_type if value can be represented by it.
+    pub fn new(data_type: DataType, len: usize, value: f64) -> RasterData {
+        match data_type {
+            DataType::F32 if fits_f32(value) => RasterData::F32(vec![value as f32; len]),
+            DataType::I32 if fits(value, i32::MIN as f64, i32::MAX as f64) => RasterData::I32(vec![value as i32; len]),
+            DataType::I16 if fits(value, i16::MIN as f64, i16::MAX as f64) => RasterData::I16(vec![value as i16; len]),
+            DataType::U16 if fits(value, u16::MIN as f64, u16::MAX as f64) => RasterData::U16(vec![value as u16; len]),
+            DataType::I8 if fits(value, i8::MIN as f64, i8::MAX as f64) => RasterData::I8(vec![value as i8; len]),
+            DataType::U8 if fits(value, u8::MIN as f64, u8::MAX as f64) => RasterData::U8(vec![value as u8; len]),
+            _ => RasterData::F64(vec![value; len]),
+        }
+    }
+
+    /// Creates an empty buffer, using the native storage for data_type if there is one.
+    pub fn with_capacity(data_type: DataType, capacity: usize) -> RasterData {
+        match data_type {
+            DataType::F32 => RasterData::F32(Vec::with_capacity(capacity)),
+            DataType::I32 => RasterData::I32(Vec::with_capacity(capacity)),
+            DataType::I16 => RasterData::I16(Vec::with_capacity(capacity)),
+            DataType::U16 => RasterData::U16(Vec::with_capacity(capacity)),
+            DataType::I8 => RasterData::I8(Vec::with_capacity(capacity)),
+            DataType::U8 => RasterData::U8(Vec::with_capacity(capacity)),
+            _ => RasterData::F64(Vec::with_capacity(capacity)),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        match *self {
+            RasterData::F64(ref d) => d.len(),
+            RasterData::F32(ref d) => d.len(),
+            RasterData::I32(ref d) => d.len(),
+            RasterData::I16(ref d) => d.len(),
+            RasterData::U16(ref d) => d.len(),
+            RasterData::I8(ref d) => d.len(),
+            RasterData::U8(ref d) => d.len(),
+            RasterData::Cached(ref c) => c.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns true if the values are stored using a native type rather than f64.
+    pub fn is_native(&self) -> bool {
+        match *self {
+            RasterData::F64(_) | RasterData::Cached(_) => false,
+            _ => true,
+        }
+    }
+
+    /// Returns true if the values are stored out-of-core.
+    pub fn is_cached(&self) -> bool {
+        match *self {
+            RasterData::Cached(_) => true,
+            _ => false,
+        }
+    }
+
+    pub fn get_value(&self, index: usize) -> f64 {
+        match *self {
+            RasterData::F64(ref d) => d[index],
+            RasterData::F32(ref d) => d[index] as f64,
+            RasterData::I32(ref d) => d[index] as f64,
+            RasterData::I16(ref d) => d[index] as f64,
+            RasterData::U16(ref d) => d[index] as f64,
+            RasterData::I8(ref d) => d[index] as f64,
+            RasterData::U8(ref d) => d[index] as f64,
+            RasterData::Cached(ref c) => c.get_value(index),
+        }
+    }
+
+    /// Returns the values of the cells from start up to, but not including, end.
+    pub fn get_values(&self, start: usize, end: usize) -> Vec<f64> {
+        match *self {
+            RasterData::F64(ref d) => d[start..end].to_vec(),
+            RasterData::Cached(ref c) => c.get_values(start, end),
+            _ => (start..end).map(|i| self.get_value(i)).collect(),
+        }
+    }
+
+    pub fn set_value(&mut self, index: usize, value: f64) {
+        match *self {
+            RasterData::F64(ref mut d) => {
+                d[index] = value;
+                return;
+            }
+            RasterData::Cached(ref mut c) => {
+                c.set_value(index, value);
+                return;
+            }
+            RasterData::F32(ref mut d) => if fits_f32(value) {
+                d[index] = value as f32;
+                return;
+            },
+            RasterData::I32(ref mut d) => if fits(value, i32::MIN as f64, i32::MAX as f64) {
+                d[index] = value as i32;
+                return;
+            },
+            RasterData::I16(ref mut d) => if fits(value, i16::MIN as f64, i16::MAX as f64) {
+                d[index] = value as i16;
+                return;
+            },
+            RasterData::U16(ref mut d) => if fits(value, u16::MIN as f64, u16::MAX as f64) {
+                d[index] = value as u16;
+                return;
+            },
+            RasterData::I8(ref mut d) => if fits(value, i8::MIN as f64, i8::MAX as f64) {
+                d[index] = value as i8;
+                return;
+            },
+            RasterData::U8(ref mut d) => if fits(value, u8::MIN as f64, u8::MAX as f64) {
+                d[index] = value as u8;
+                return;
+            },
+        }
+        self.convert_to_f64();
+        self.set_value(index, value);
+    }
+
+    /// Sets the values of the cells starting at start.
+    pub fn set_values(&mut self, start: usize, values: &[f64]) {
+        match *self {
+            RasterData::F64(ref mut d) => d[start..start + values.len()].copy_from_slice(values),
+            RasterData::Cached(ref mut c) => c.set_values(start, values),
+            _ => {
+                for i in 0..values.len() {
+                    self.set_value(start + i, values[i]);
+                }
+            }
+        }
+    }
+
+    pub fn push(&mut self, value: f64) {
+        match *self {
+            RasterData::F64(ref mut d) => {
+                d.push(value);
+                return;
+            }
+            RasterData::F32(ref mut d) => if fits_f32(value) {
+                d.push(value as f32);
+                return;
+            },
+            RasterData::I32(ref mut d) => if fits(value, i32::MIN as f64, i32::MAX as f64) {
+                d.push(value as i32);
+                return;
+            },
+            RasterData::I16(ref mut d) => if fits(value, i16::MIN as f64, i16::MAX as f64) {
+                d.push(value as i16);
+                return;
+            },
+            RasterData::U16(ref mut d) => if fits(value, u16::MIN as f64, u16::MAX as f64) {
+                d.push(value as u16);
+                return;
+            },
+            RasterData::I8(ref mut d) => if fits(value, i8::MIN as f64, i8::MAX as f64) {
+                d.push(value as i8);
+                return;
+            },
+            RasterData::U8(ref mut d) => if fits(value, u8::MIN as f64, u8::MAX as f64) {
+                d.push(value as u8);
+                return;
+            },
+            RasterData::Cached(_) => panic!("Cells cannot be added to or removed from an out-of-core raster."),
+        }
+        self.convert_to_f64();
+        self.push(value);
+    }
+
+    pub fn resize(&mut self, len: usize, value: f64) {
+        match *self {
+            RasterData::F64(ref mut d) => {
+                d.resize(len, value);
+                return;
+            }
+            RasterData::F32(ref mut d) => if fits_f32(value) {
+                d.resize(len, value as f32);
+                return;
+            },
+            RasterData::I32(ref mut d) => if fits(value, i32::MIN as f64, i32::MAX as f64) {
+                d.resize(len, value as i32);
+                return;
+            },
+            RasterData::I16(ref mut d) => if fits(value, i16::MIN as f64, i16::MAX as f64) {
+                d.resize(len, value as i16);
+                return;
+            },
+            RasterData::U16(ref mut d) => if fits(value, u16::MIN as f64, u16::MAX as f64) {
+                d.resize(len, value as u16);
+                return;
+            },
+            RasterData::I8(ref mut d) => if fits(value, i8::MIN as f64, i8::MAX as f64) {
+                d.resize(len, value as i8);
+                return;
+            },
+            RasterData::U8(ref mut d) => if fits(value, u8::MIN as f64, u8::MAX as f64) {
+                d.resize(len, value as u8);
+                return;
+            },
+            RasterData::Cached(_) => panic!("Cells cannot be added to or removed from an out-of-core raster."),
+        }
+        self.convert_to_f64();
+        self.resize(len, value);
+    }
+
+    pub fn truncate(&mut self, len: usize) {
+        match *self {
+            RasterData::F64(ref mut d) => d.truncate(len),
+            RasterData::F32(ref mut d) => d.truncate(len),
+            RasterData::I32(ref mut d) => d.truncate(len),
+            RasterData::I16(ref mut d) => d.truncate(len),
+            RasterData::U16(ref mut d) => d.truncate(len),
+            RasterData::I8(ref mut d) => d.truncate(len),
+            RasterData::U8(ref mut d) => d.truncate(len),
+            RasterData::Cached(_) => panic!("Cells cannot be added to or removed from an out-of-core raster."),
+        }
+    }
+
+    /// Returns a copy of the cells from start up to, but not including, end.
+    pub fn slice(&self, start: usize, end: usize) -> RasterData {
+        match *self {
+            RasterData::F64(ref d) => RasterData::F64(d[start..end].to_vec()),
+            RasterData::F32(ref d) => RasterData::F32(d[start..end].to_vec()),
+            RasterData::I32(ref d) => RasterData::I32(d[start..end].to_vec()),
+            RasterData::I16(ref d) => RasterData::I16(d[start..end].to_vec()),
+            RasterData::U16(ref d) => RasterData::U16(d[start..end].to_vec()),
+            RasterData::I8(ref d) => RasterData::I8(d[start..end].to_vec()),
+            RasterData::U8(ref d) => RasterData::U8(d[start..end].to_vec()),
+            RasterData::Cached(ref c) => RasterData::F64(c.get_values(start, end)),
+        }
+    }
+
+    /// Returns a copy of the values as f64s. For out-of-core data, the values are read into
+    /// memory.
+    pub fn to_vec(&self) -> Vec<f64> {
+        match *self {
+            RasterData::F64(ref d) => d.clone(),
+            RasterData::Cached(ref c) => c.get_values(0, c.len()),
+            _ => self.iter().collect(),
+        }
+    }
+
+    pub fn iter<'a>(&'a self) -> RasterDataIter<'a> {
+        RasterDataIter { data: self, index: 0 }
+    }
+
+    /// Converts the buffer to the native storage for data_type, provided that data_type has
+    /// one and that every value can be represented by it. Otherwise, the buffer is unchanged.
+    /// Out-of-core data are never converted.
+    pub fn convert_to(&mut self, data_type: DataType) {
+        if self.is_cached() {
+            return;
+        }
+        if data_type == DataType::F32 {
+            if self.iter().all(|v| fits_f32(v)) {
+                *self = RasterData::F32(self.iter().map(|v| v as f32).collect());
+            }
+            return;
+        }
+        let (min, max) = match data_type {
+            DataType::I32 => (i32::MIN as f64, i32::MAX as f64),
+            DataType::I16 => (i16::MIN as f64, i16::MAX as f64),
+            DataType::U16 => (u16::MIN as f64, u16::MAX as f64),
+            DataType::I8 => (i8::MIN as f64, i8::MAX as f64),
+            DataType::U8 => (u8::MIN as f64, u8::MAX as f64),
+            _ => return,
+        };
+        if !self.iter().all(|v| fits(v, min, max)) {
+            return;
+        }
+        let mut converted = RasterData::with_capacity(data_type, self.len());
+        for v in self.iter() {
+            converted.push(v);
+        }
+        *self = converted;
+    }
+
+    fn convert_to_f64(&mut self) {
+        let values: Vec<f64> = match *self {
+            RasterData::F64(_) | RasterData::Cached(_) => return,
+            RasterData::F32(ref d) => d.iter().map(|v| *v as f64).collect(),
+            RasterData::I32(ref d) => d.iter().map(|v| *v as f64).collect(),
+            RasterData::I16(ref d) => d.iter().map(|v| *v as f64).collect(),
+            RasterData::U16(ref d) => d.iter().map(|v| *v as f64).collect(),
+            RasterData::I8(ref d) => d.iter().map(|v| *v as f64).collect(),
+            RasterData::U8(ref d) => d.iter().map(|v| *v as f64).collect(),
+        };
+        *self = RasterData::F64(values);
+    }
+}
+
+pub struct RasterDataIter<'a> {
+    data: &'a RasterData,
+    index: usize,
+}
+
+impl<'a> Iterator for RasterDataIter<'a> {
+    type Item = f64;
+
+    fn next(&mut self) -> Option<f64> {
+        if self.index < self.data.len() {
+            self.index += 1;
+            Some(self.data.get_value(self.index - 1))
+        } else {
+            None
+        }
+    }
+}
+
+impl<'a> IntoIterator for &'a RasterData {
+    type Item = f64;
+    type IntoIter = RasterDataIter<'a>;
+
+    fn into_iter(self) -> RasterDataIter<'a> {
+        self.iter()
+    }
+}
+
+fn fits(value: f64, min: f64, max: f64) -> bool {
+    value >= min && value <= max && value.fract() == 0f64
+}
+
+fn fits_f32(value: f64) -> bool {
+    value as f32 as f64 == value || value.is_nan()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f32;
+    use raster::block_cache::BlockSource;
+
+    // the data types that are stored natively, with their extreme values and a typical NoData value
+    fn native_types() -> Vec<(DataType, f64, f64, f64)> {
+        vec![
+            (DataType::F32, f32::MIN as f64, f32::MAX as f64, -32768f64),
+            (DataType::I32, i32::MIN as f64, i32::MAX as f64, i32::MIN as f64),
+            (DataType::I16, i16::MIN as f64, i16::MAX as f64, -32768f64),
+            (DataType::U16, 0f64, u16::MAX as f64, 0f64),
+            (DataType::I8, i8::MIN as f64, i8::MAX as f64, -128f64),
+            (DataType::U8, 0f64, u8::MAX as f64, 255f64),
+        ]
+    }
+
+    fn variant(data: &RasterData) -> &'static str {
+        match *data {
+            RasterData::F64(_) => "F64",
+            RasterData::F32(_) => "F32",
+            RasterData::I32(_) => "I32",
+            RasterData::I16(_) => "I16",
+            RasterData::U16(_) => "U16",
+            RasterData::I8(_) => "I8",
+            RasterData::U8(_) => "U8",
+            RasterData::Cached(_) => "Cached",
+        }
+    }
+
+    #[test]
+    fn values_are_stored_natively() {
+        for (data_type, min, max, nodata) in native_types() {
+            let name = format!("{:?}", data_type);
+            let mut data = RasterData::new(data_type, 10, nodata);
+            assert_eq!(variant(&data), name);
+            assert!(data.is_native() && !data.is_cached());
+            assert_eq!(data.len(), 10);
+            assert!(data.iter().all(|v| v == nodata));
+
+            data.set_value(1, min);
+            data.set_value(2, max);
+            data.set_values(3, &[1.0, 2.0, 3.0]);
+            assert_eq!(variant(&data), name);
+            assert_eq!(data.get_value(0), nodata);
+            assert_eq!((data.get_value(1), data.get_value(2)), (min, max));
+            assert_eq!(data.get_values(2, 6), vec![max, 1.0, 2.0, 3.0]);
+
+            let slice = data.slice(1, 4);
+            assert_eq!(variant(&slice), name);
+            assert_eq!(slice.to_vec(), vec![min, max, 1.0]);
+
+            data.push(max);
+            data.resize(13, nodata);
+            data.truncate(12);
+            assert_eq!(variant(&data), name);
+            assert_eq!(data.len(), 12);
+            assert_eq!((data.get_value(10), data.get_value(11)), (max, nodata));
+
+            let mut empty = RasterData::with_capacity(data_type, 4);
+            assert!(empty.is_empty() && empty.is_native());
+            empty.push(nodata);
+            assert_eq!((variant(&empty), empty.get_value(0)), (name.as_ref(), nodata));
+        }
+        // the other data types are stored as f64
+        for &data_type in [DataType::F64, DataType::I64, DataType::U32, DataType::U64, DataType::RGBA32, DataType::Unknown].iter() {
+            assert_eq!(variant(&RasterData::new(data_type, 3, 1.0)), "F64");
+            assert_eq!(variant(&RasterData::with_capacity(data_type, 3)), "F64");
+        }
+        // NaN is a float
+        let mut data = RasterData::new(DataType::F32, 2, f64::NAN);
+        assert!(data.is_native() && data.get_value(0).is_nan());
+        data.set_value(1, 0.5);
+        assert!(data.is_native() && data.get_value(1) == 0.5);
+    }
+
+    #[test]
+    fn unrepresentable_values_are_stored_as_f64() {
+        for (data_type, min, max, nodata) in native_types() {
+            let fraction = if data_type == DataType::F32 { 0.1 } else { 0.5 };
+            let below = if data_type == DataType::F32 { -1e300 } else { min - 1.0 };
+            let above = if data_type == DataType::F32 { 1e300 } else { max + 1.0 };
+            for &value in [fraction, below, above].iter() {
+                let mut data = RasterData::new(data_type, 4, nodata);
+                data.set_value(0, min);
+                data.set_value(3, value);
+                // the conversion is lossless
+                assert_eq!(variant(&data), "F64", "{:?}: {}", data_type, value);
+                assert_eq!(data.to_vec(), vec![min, nodata, nodata, value]);
+
+                let mut data = RasterData::new(data_type, 2, nodata);
+                data.push(value);
+                assert_eq!((variant(&data), data.to_vec()), ("F64", vec![nodata, nodata, value]));
+                let mut data = RasterData::new(data_type, 1, nodata);
+                data.resize(3, value);
+                assert_eq!((variant(&data), data.to_vec()), ("F64", vec![nodata, value, value]));
+                assert_eq!(variant(&RasterData::new(data_type, 2, value)), "F64");
+            }
+        }
+    }
+
+    #[test]
+    fn buffers_are_converted_when_every_value_fits() {
+        for (data_type, min, max, nodata) in native_types() {
+            let mut data = RasterData::F64(vec![nodata, min, max, 0.0]);
+            data.convert_to(data_type);
+            assert_eq!(variant(&data), format!("{:?}", data_type));
+            assert_eq!(data.to_vec(), vec![nodata, min, max, 0.0]);
+            // but not after a value that does not fit has been assigned
+            data.set_value(3, if data_type == DataType::F32 { 0.1 } else { 0.25 });
+            assert_eq!(variant(&data), "F64");
+            data.convert_to(data_type);
+            assert_eq!(variant(&data), "F64");
+        }
+        let mut data = RasterData::new(DataType::U8, 3, 7.0);
+        data.convert_to(DataType::I16);
+        assert_eq!((variant(&data), data.to_vec()), ("I16", vec![7.0, 7.0, 7.0]));
+        data.convert_to(DataType::F64);
+        assert_eq!(variant(&data), "I16");
+
+        // out-of-core data are never converted
+        let mut data = RasterData::Cached(BlockCache::new(2, 2, 1, 1.0, BlockSource::Fill, 64));
+        data.convert_to(DataType::U8);
+        assert!(data.is_cached() && !data.is_native());
+        data.set_value(3, 0.5);
+        assert_eq!(data.to_vec(), vec![1.0, 1.0, 1.0, 0.5]);
+    }
+}
@@ -396,8 +396,7 @@ pub fn read_saga(file_name: &String,
 
 pub fn write_saga<'a>(r: &'a mut Raster) -> Result<(), Error> {
     // figure out the minimum and maximum values
-    for val in &r.data {
-        let v = *val;
+    for v in &r.data {
         if v != r.configs.nodata {
             if v < r.configs.minimum {
                 r.configs.minimum = v;
@@ -515,7 +514,7 @@ pub fn write_saga<'a>(r: &'a mut Raster) -> Result<(), Error> {
             for row in 0..r.configs.rows { //(0..r.configs.rows).rev() {
                 for col in 0..r.configs.columns {
                     i = row * r.configs.columns + col;
-                    u64_bytes = unsafe { mem::transmute(r.data[i]) };
+                    u64_bytes = unsafe { mem::transmute(r.data.get_value(i)) };
                     try!(writer.write(&u64_bytes));
                 }
             }
@@ -524,7 +523,7 @@ pub fn write_saga<'a>(r: &'a mut Raster) -> Result<(), Error> {
             for row in 0..r.configs.rows { //(0..r.configs.rows).rev() {
                 for col in 0..r.configs.columns {
                     i = row * r.configs.columns + col;
-                    u32_bytes = unsafe { mem::transmute(r.data[i] as f32) };
+                    u32_bytes = unsafe { mem::transmute(r.data.get_value(i) as f32) };
                     try!(writer.write(&u32_bytes));
                 }
             }
@@ -533,7 +532,7 @@ pub fn write_saga<'a>(r: &'a mut Raster) -> Result<(), Error> {
             for row in 0..r.configs.rows { //(0..r.configs.rows).rev() {
                 for col in 0..r.configs.columns {
                     i = row * r.configs.columns + col;
-                    u32_bytes = unsafe { mem::transmute(r.data[i] as i32) };
+                    u32_bytes = unsafe { mem::transmute(r.data.get_value(i) as i32) };
                     try!(writer.write(&u32_bytes));
                 }
             }
@@ -542,7 +541,7 @@ pub fn write_saga<'a>(r: &'a mut Raster) -> Result<(), Error> {
             for row in 0..r.configs.rows { //(0..r.configs.rows).rev() {
                 for col in 0..r.configs.columns {
                     i = row * r.configs.columns + col;
-                    u32_bytes = unsafe { mem::transmute(r.data[i] as u32) };
+                    u32_bytes = unsafe { mem::transmute(r.data.get_value(i) as u32) };
                     try!(writer.write(&u32_bytes));
                 }
             }
@@ -551,7 +550,7 @@ pub fn write_saga<'a>(r: &'a mut Raster) -> Result<(), Error> {
             for row in 0..r.configs.rows { //(0..r.configs.rows).rev() {
                 for col in 0..r.configs.columns {
                     i = row * r.configs.columns + col;
-                    u16_bytes = unsafe { mem::transmute(r.data[i] as i16) };
+                    u16_bytes = unsafe { mem::transmute(r.data.get_value(i) as i16) };
                     try!(writer.write(&u16_bytes));
                 }
             }
@@ -560,7 +559,7 @@ pub fn write_saga<'a>(r: &'a mut Raster) -> Result<(), Error> {
             for row in 0..r.configs.rows { //(0..r.configs.rows).rev() {
                 for col in 0..r.configs.columns {
                     i = row * r.configs.columns + col;
-                    u16_bytes = unsafe { mem::transmute(r.data[i] as u16) };
+                    u16_bytes = unsafe { mem::transmute(r.data.get_value(i) as u16) };
                     try!(writer.write(&u16_bytes));
                 }
             }
@@ -569,7 +568,7 @@ pub fn write_saga<'a>(r: &'a mut Raster) -> Result<(), Error> {
             for row in 0..r.configs.rows { //(0..r.configs.rows).rev() {
                 for col in 0..r.configs.columns {
                     i = row * r.configs.columns + col;
-                    try!(writer.write(&[r.data[i] as u8]));
+                    try!(writer.write(&[r.data.get_value(i) as u8]));
                 }
             }
         }
@@ -144,8 +144,7 @@ pub fn read_surfer7(file_name: &String, configs: &mut RasterConfigs, data: &mut
 
 pub fn write_surfer7<'a>(r: &'a mut Raster) -> Result<(), Error> {
     // figure out the minimum and maximum values
-    for val in &r.data {
-        let v = *val;
+    for v in &r.data {
         if v != r.configs.nodata {
             if v < r.configs.minimum { r.configs.minimum = v; }
             if v > r.configs.maximum { r.configs.maximum = v; }
@@ -215,7 +214,7 @@ pub fn write_surfer7<'a>(r: &'a mut Raster) -> Result<(), Error> {
     for row in (0..r.configs.rows).rev() {
         for col in 0..r.configs.columns {
             i = row * r.configs.columns + col;
-            u64_bytes = unsafe { mem::transmute(r.data[i]) };
+            u64_bytes = unsafe { mem::transmute(r.data.get_value(i)) };
             try!(writer.write(&u64_bytes));
         }
     }
@@ -99,8 +99,7 @@ pub fn write_surfer_ascii_raster<'a>(r: &'a mut Raster) -> Result<(), Error> {
     if r.configs.nodata != 1.71041e38 { r.configs.nodata = 1.71041e38; }
 
     // figure out the minimum and maximum values
-    for val in &r.data {
-        let v = *val;
+    for v in &r.data {
         if v != r.configs.nodata {
             if v < r.configs.minimum { r.configs.minimum = v; }
             if v > r.configs.maximum { r.configs.maximum = v; }
@@ -128,14 +127,14 @@ pub fn write_surfer_ascii_raster<'a>(r: &'a mut Raster) -> Result<(), Error> {
         for col in 0..r.configs.columns {
             let i = row * r.configs.columns + col;
             if col < r.configs.columns - 1 {
-                if r.data[i] != r.configs.nodata {
-                    s2 += &format!("{:.*} ", num_decimals, r.data[i]);
+                if r.data.get_value(i) != r.configs.nodata {
+                    s2 += &format!("{:.*} ", num_decimals, r.data.get_value(i));
                 } else {
                     s2 += &format!("1.71041e38 ");
                 }
             } else {
-                if r.data[i] != r.configs.nodata {
-                    s2 += &format!("{:.*}\n", num_decimals, r.data[i]);
+                if r.data.get_value(i) != r.configs.nodata {
+                    s2 += &format!("{:.*}\n", num_decimals, r.data.get_value(i));
                 } else {
                     s2 += &format!("1.71041e38\n");
                 }
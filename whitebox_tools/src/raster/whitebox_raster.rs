@@ -1,3 +1,4 @@
+use std::cmp::max;
 use std::io::Error;
 use std::io::ErrorKind;
 use std::io::BufReader;
@@ -11,7 +12,7 @@ use io_utils::Endianness;
 
 pub fn read_whitebox(file_name: &String,
                      configs: &mut RasterConfigs,
-                     data: &mut Vec<f64>)
+                     data: &mut RasterData)
                      -> Result<(), Error> {
     // read the header file
     let header_file = file_name.replace(".tas", ".dep");
@@ -135,6 +136,18 @@ pub fn read_whitebox(file_name: &String,
 
     // read the data file
     let data_file = file_name.replace(".dep", ".tas");
+
+    // The bands of multi-band (stacked) rasters are stored one after another.
+    let num_cells = configs.rows * configs.columns * max(1, configs.bands as usize);
+
+    // Rasters that are too large to hold in memory are read on demand, one block of rows
+    // at a time.
+    if exceeds_max_raster_memory(num_cells) {
+        let source = BlockSource::Whitebox { file_name: data_file, data_type: configs.data_type };
+        *data = RasterData::Cached(BlockCache::new(configs.rows, configs.columns, configs.bands as usize, configs.nodata, source, get_max_raster_memory()));
+        return Ok(());
+    }
+
     let mut f = File::open(data_file.clone())?;
     //let br = BufReader::new(f);
     // let metadata = try!(fs::metadata(data_file.clone()));
@@ -154,8 +167,8 @@ pub fn read_whitebox(file_name: &String,
         1
     };
 
-    let num_cells = configs.rows * configs.columns;
     let buf_size = 1_000_000usize;
+    *data = RasterData::with_capacity(configs.data_type, num_cells);
     let mut j = 0;
     while j < num_cells {
         let mut buffer = vec![0; buf_size * data_size];
@@ -251,8 +264,8 @@ pub fn read_whitebox(file_name: &String,
 
 pub fn write_whitebox<'a>(r: &'a mut Raster) -> Result<(), Error> {
     // figure out the minimum and maximum values
-    for val in &r.data {
-        let v = *val;
+    for i in 0..r.data.len() {
+        let v = r.data.get_value(i);
         if v != r.configs.nodata {
             if v < r.configs.minimum {
                 r.configs.minimum = v;
@@ -410,30 +423,30 @@ pub fn write_whitebox<'a>(r: &'a mut Raster) -> Result<(), Error> {
     let mut u32_bytes: [u8; 4];
     let mut u64_bytes: [u8; 8];
 
-    let num_cells: usize = r.configs.rows * r.configs.columns;
+    let num_cells: usize = r.configs.rows * r.configs.columns * r.num_bands();
     match r.configs.data_type {
         DataType::F64 | DataType::U32 => {
             if r.configs.photometric_interp != PhotometricInterpretation::RGB {
                 for i in 0..num_cells {
-                    u64_bytes = unsafe { mem::transmute(r.data[i]) };
+                    u64_bytes = unsafe { mem::transmute(r.data.get_value(i)) };
                     writer.write(&u64_bytes)?;
                 }
             } else {
                 for i in 0..num_cells {
-                    u32_bytes = unsafe { mem::transmute(r.data[i] as u32) };
+                    u32_bytes = unsafe { mem::transmute(r.data.get_value(i) as u32) };
                     writer.write(&u32_bytes)?;
                 }
             }
         }
         DataType::F32 => {
             for i in 0..num_cells {
-                u32_bytes = unsafe { mem::transmute(r.data[i] as f32) };
+                u32_bytes = unsafe { mem::transmute(r.data.get_value(i) as f32) };
                 writer.write(&u32_bytes)?;
             }
         }
         DataType::I32 | DataType::U16 | DataType::RGBA32 => {
             for i in 0..num_cells {
-                u32_bytes = unsafe { mem::transmute(r.data[i] as u32) };
+                u32_bytes = unsafe { mem::transmute(r.data.get_value(i) as u32) };
                 writer.write(&u32_bytes)?;
             }
         }
@@ -443,20 +456,40 @@ pub fn write_whitebox<'a>(r: &'a mut Raster) -> Result<(), Error> {
             let mut val: u32;
             let alpha_mask = (255 << 24) as u32;
             for i in 0..num_cells {
-                val = alpha_mask | (r.data[i] as u32);
+                val = alpha_mask | (r.data.get_value(i) as u32);
                 u32_bytes = unsafe { mem::transmute(val) };
                 writer.write(&u32_bytes)?;
             }
         }
         DataType::I16 => {
-            for i in 0..num_cells {
-                u16_bytes = unsafe { mem::transmute(r.data[i] as u16) };
-                writer.write(&u16_bytes)?;
+            match r.data {
+                RasterData::I16(ref d) => {
+                    for i in 0..num_cells {
+                        u16_bytes = unsafe { mem::transmute(d[i]) };
+                        writer.write(&u16_bytes)?;
+                    }
+                }
+                _ => {
+                    for i in 0..num_cells {
+                        u16_bytes = unsafe { mem::transmute(r.data.get_value(i) as i16) };
+                        writer.write(&u16_bytes)?;
+                    }
+                }
             }
         }
         DataType::U8 | DataType::I8 => {
-            for i in 0..num_cells {
-                writer.write(&[r.data[i] as u8])?;
+            match r.data {
+                RasterData::U8(ref d) => writer.write_all(&d[0..num_cells])?,
+                RasterData::I8(ref d) => {
+                    for i in 0..num_cells {
+                        writer.write(&[d[i] as u8])?;
+                    }
+                }
+                _ => {
+                    for i in 0..num_cells {
+                        writer.write(&[r.data.get_value(i) as u8])?;
+                    }
+                }
             }
         }
         _ => {
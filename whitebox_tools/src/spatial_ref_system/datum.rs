@@ -0,0 +1,244 @@
+/*
+This code is part of the WhiteboxTools geospatial analysis library.
+Authors: Dr. John Lindsay
+Created: March 29, 2018
+Last Modified: March 29, 2018
+License: MIT
+
+Notes: Datum shifts use the seven-parameter Helmert transformation through WGS84 in the
+position vector convention (EPSG method 9606), i.e. the rotations of a TOWGS84 clause of
+OGC WKT. Translations are in metres, rotations in arc-seconds and the scale in parts per
+million. Grid-based shifts, e.g. NTv2 or NADCON, are not supported.
+*/
+
+use std::f64;
+use std::f64::consts::PI;
+
+/// A reference ellipsoid.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Ellipsoid {
+    pub name: String,
+    /// The semi-major axis, in metres.
+    pub a: f64,
+    pub inverse_flattening: f64,
+}
+
+impl Ellipsoid {
+    pub fn new(name: &str, a: f64, inverse_flattening: f64) -> Ellipsoid {
+        Ellipsoid { name: name.to_string(), a: a, inverse_flattening: inverse_flattening }
+    }
+
+    pub fn wgs84() -> Ellipsoid {
+        Ellipsoid::new("WGS 84", 6_378_137.0, 298.257223563)
+    }
+
+    pub fn grs80() -> Ellipsoid {
+        Ellipsoid::new("GRS 1980", 6_378_137.0, 298.257222101)
+    }
+
+    pub fn wgs72() -> Ellipsoid {
+        Ellipsoid::new("WGS 72", 6_378_135.0, 298.26)
+    }
+
+    pub fn clarke1866() -> Ellipsoid {
+        Ellipsoid::new("Clarke 1866", 6_378_206.4, 294.978698213898)
+    }
+
+    pub fn international1924() -> Ellipsoid {
+        Ellipsoid::new("International 1924", 6_378_388.0, 297.0)
+    }
+
+    pub fn airy1830() -> Ellipsoid {
+        Ellipsoid::new("Airy 1830", 6_377_563.396, 299.3249646)
+    }
+
+    /// Returns the ellipsoid with an EPSG ellipsoid code, e.g. 7030 for WGS 84.
+    pub fn from_epsg(code: u16) -> Option<Ellipsoid> {
+        match code {
+            7030 => Some(Ellipsoid::wgs84()),
+            7019 => Some(Ellipsoid::grs80()),
+            7043 => Some(Ellipsoid::wgs72()),
+            7008 => Some(Ellipsoid::clarke1866()),
+            7022 => Some(Ellipsoid::international1924()),
+            7001 => Some(Ellipsoid::airy1830()),
+            _ => None,
+        }
+    }
+
+    /// Creates an ellipsoid from its semi-major and semi-minor axes.
+    pub fn from_axes(name: &str, a: f64, b: f64) -> Ellipsoid {
+        let inverse_flattening = if a == b { 0f64 } else { a / (a - b) };
+        Ellipsoid::new(name, a, inverse_flattening)
+    }
+
+    pub fn flattening(&self) -> f64 {
+        if self.inverse_flattening == 0f64 { 0f64 } else { 1f64 / self.inverse_flattening }
+    }
+
+    /// The semi-minor axis, in metres.
+    pub fn b(&self) -> f64 {
+        self.a * (1f64 - self.flattening())
+    }
+
+    /// The square of the first eccentricity.
+    pub fn e2(&self) -> f64 {
+        let f = self.flattening();
+        f * (2f64 - f)
+    }
+
+    pub fn is_equivalent(&self, other: &Ellipsoid) -> bool {
+        (self.a - other.a).abs() < 1.0e-3 && (self.flattening() - other.flattening()).abs() < 1.0e-12
+    }
+}
+
+/// A geodetic datum, along with the name and EPSG code of the geographic coordinate
+/// system that is based on it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Datum {
+    pub name: String,
+    pub ellipsoid: Ellipsoid,
+    /// The Helmert parameters (tx, ty, tz, rx, ry, rz, ds) of the shift to WGS84.
+    pub to_wgs84: [f64; 7],
+    pub geographic_name: String,
+    pub geographic_epsg_code: u16,
+}
+
+impl Datum {
+    pub fn new(name: &str, ellipsoid: Ellipsoid, to_wgs84: [f64; 7], geographic_name: &str, geographic_epsg_code: u16) -> Datum {
+        Datum {
+            name: name.to_string(),
+            ellipsoid: ellipsoid,
+            to_wgs84: to_wgs84,
+            geographic_name: geographic_name.to_string(),
+            geographic_epsg_code: geographic_epsg_code,
+        }
+    }
+
+    pub fn wgs84() -> Datum {
+        Datum::new("WGS_1984", Ellipsoid::wgs84(), [0f64; 7], "WGS 84", 4326)
+    }
+
+    /// Returns the datum of the geographic coordinate system with an EPSG code, e.g. 4269
+    /// for NAD83. Datums that are within a metre or so of WGS84 are not shifted.
+    pub fn from_geographic_epsg(code: u16) -> Option<Datum> {
+        let d = match code {
+            4326 => Datum::wgs84(),
+            4269 => Datum::new("North_American_Datum_1983", Ellipsoid::grs80(), [0f64; 7], "NAD83", 4269),
+            4617 => Datum::new("NAD83_Canadian_Spatial_Reference_System", Ellipsoid::grs80(), [0f64; 7], "NAD83(CSRS)", 4617),
+            4267 => Datum::new("North_American_Datum_1927", Ellipsoid::clarke1866(), [-8.0, 160.0, 176.0, 0.0, 0.0, 0.0, 0.0], "NAD27", 4267),
+            4258 => Datum::new("European_Terrestrial_Reference_System_1989", Ellipsoid::grs80(), [0f64; 7], "ETRS89", 4258),
+            4230 => Datum::new("European_Datum_1950", Ellipsoid::international1924(), [-87.0, -98.0, -121.0, 0.0, 0.0, 0.0, 0.0], "ED50", 4230),
+            4283 => Datum::new("Geocentric_Datum_of_Australia_1994", Ellipsoid::grs80(), [0f64; 7], "GDA94", 4283),
+            4171 => Datum::new("Reseau_Geodesique_Francais_1993", Ellipsoid::grs80(), [0f64; 7], "RGF93", 4171),
+            4277 => Datum::new("OSGB_1936", Ellipsoid::airy1830(), [446.448, -125.157, 542.06, 0.15, 0.247, 0.842, -20.489], "OSGB 1936", 4277),
+            4322 => Datum::new("WGS_1972", Ellipsoid::wgs72(), [0.0, 0.0, 4.5, 0.0, 0.0, 0.554, 0.2263], "WGS 72", 4322),
+            _ => return None,
+        };
+        Some(d)
+    }
+
+    /// Recognizes the common datums by the names used in OGC and ESRI WKT, e.g.
+    /// `North_American_Datum_1983` or `D_North_American_1983`.
+    pub fn from_name(name: &str) -> Option<Datum> {
+        let mut n: String = name.to_lowercase().chars().filter(|c| c.is_alphanumeric()).collect();
+        if n.starts_with("d") && name.to_lowercase().starts_with("d_") {
+            n.remove(0);
+        }
+        let code = if n == "wgs1984" || n == "wgs84" || n == "worldgeodeticsystem1984" {
+            4326
+        } else if n.contains("csrs") {
+            4617
+        } else if n == "northamericandatum1983" || n == "northamerican1983" || n == "nad83" {
+            4269
+        } else if n == "northamericandatum1927" || n == "northamerican1927" || n == "nad27" {
+            4267
+        } else if n.starts_with("europeanterrestrialreferencesystem1989") || n == "etrs1989" || n == "etrs89" {
+            4258
+        } else if n == "europeandatum1950" || n == "european1950" || n == "ed50" {
+            4230
+        } else if n == "geocentricdatumofaustralia1994" || n == "gda1994" || n == "gda94" {
+            4283
+        } else if n == "reseaugeodesiquefrancais1993" || n == "rgf1993" || n == "rgf93" {
+            4171
+        } else if n == "osgb1936" || n == "osgb36" {
+            4277
+        } else if n == "wgs1972" || n == "wgs72" || n == "worldgeodeticsystem1972" {
+            4322
+        } else {
+            return None;
+        };
+        Datum::from_geographic_epsg(code)
+    }
+
+    pub fn is_wgs84_equivalent(&self) -> bool {
+        self.to_wgs84.iter().all(|v| *v == 0f64)
+    }
+
+    /// Returns true if coordinates do not need to be shifted between the two datums.
+    pub fn is_equivalent(&self, other: &Datum) -> bool {
+        self.ellipsoid.is_equivalent(&other.ellipsoid) &&
+            self.to_wgs84.iter().zip(other.to_wgs84.iter()).all(|(a, b)| (a - b).abs() < 1.0e-6)
+    }
+
+    /// Converts geodetic coordinates on the datum, in radians, to WGS84 Earth-centred
+    /// Earth-fixed coordinates.
+    pub fn to_wgs84_geocentric(&self, lon: f64, lat: f64, h: f64) -> (f64, f64, f64) {
+        let (x, y, z) = geodetic_to_geocentric(&self.ellipsoid, lon, lat, h);
+        helmert(&self.to_wgs84, x, y, z, false)
+    }
+
+    /// Converts WGS84 Earth-centred Earth-fixed coordinates to geodetic coordinates on the
+    /// datum, in radians.
+    pub fn from_wgs84_geocentric(&self, x: f64, y: f64, z: f64) -> (f64, f64, f64) {
+        let (x, y, z) = helmert(&self.to_wgs84, x, y, z, true);
+        geocentric_to_geodetic(&self.ellipsoid, x, y, z)
+    }
+}
+
+fn geodetic_to_geocentric(ellipsoid: &Ellipsoid, lon: f64, lat: f64, h: f64) -> (f64, f64, f64) {
+    let e2 = ellipsoid.e2();
+    let n = ellipsoid.a / (1f64 - e2 * lat.sin() * lat.sin()).sqrt();
+    let x = (n + h) * lat.cos() * lon.cos();
+    let y = (n + h) * lat.cos() * lon.sin();
+    let z = (n * (1f64 - e2) + h) * lat.sin();
+    (x, y, z)
+}
+
+fn geocentric_to_geodetic(ellipsoid: &Ellipsoid, x: f64, y: f64, z: f64) -> (f64, f64, f64) {
+    let a = ellipsoid.a;
+    let e2 = ellipsoid.e2();
+    let p = (x * x + y * y).sqrt();
+    let lon = y.atan2(x);
+    let mut lat = z.atan2(p * (1f64 - e2));
+    let mut h = 0f64;
+    for _ in 0..10 {
+        let n = a / (1f64 - e2 * lat.sin() * lat.sin()).sqrt();
+        h = if lat.cos().abs() > 1.0e-10 { p / lat.cos() - n } else { z.abs() - n * (1f64 - e2) };
+        let next = z.atan2(p * (1f64 - e2 * n / (n + h)));
+        if (next - lat).abs() < 1.0e-14 {
+            lat = next;
+            break;
+        }
+        lat = next;
+    }
+    (lon, lat, h)
+}
+
+// The position vector transformation. The inverse applies the negated parameters, which
+// is accurate to well under a millimetre for the small rotations of datum shifts.
+fn helmert(p: &[f64; 7], x: f64, y: f64, z: f64, inverse: bool) -> (f64, f64, f64) {
+    if p.iter().all(|v| *v == 0f64) {
+        return (x, y, z);
+    }
+    let sign = if inverse { -1f64 } else { 1f64 };
+    let sec_to_rad = PI / (180f64 * 3600f64);
+    let (tx, ty, tz) = (sign * p[0], sign * p[1], sign * p[2]);
+    let (rx, ry, rz) = (sign * p[3] * sec_to_rad, sign * p[4] * sec_to_rad, sign * p[5] * sec_to_rad);
+    let s = 1f64 + sign * p[6] * 1.0e-6;
+    if inverse {
+        let (x, y, z) = (x + tx, y + ty, z + tz);
+        (s * (x - rz * y + ry * z), s * (rz * x + y - rx * z), s * (-ry * x + rx * y + z))
+    } else {
+        (tx + s * (x - rz * y + ry * z), ty + s * (rz * x + y - rx * z), tz + s * (-ry * x + rx * y + z))
+    }
+}
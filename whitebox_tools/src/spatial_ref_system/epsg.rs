@@ -0,0 +1,90 @@
+/*
+This code is part of the WhiteboxTools geospatial analysis library.
+Authors: Dr. John Lindsay
+Created: March 29, 2018
+Last Modified: March 29, 2018
+License: MIT
+
+Notes: Only a subset of the EPSG registry is supported, i.e. the common geographic systems,
+the UTM zones of their datums, and a handful of national and continental projections.
+Systems with other EPSG codes may still be used if they are defined by WKT.
+*/
+
+use std::io::{Error, ErrorKind};
+use super::datum::Datum;
+use super::projections::Projection;
+use super::spatial_ref::SpatialRef;
+
+/// Returns the coordinate reference system with an EPSG code.
+pub fn from_epsg(code: u16) -> Result<SpatialRef, Error> {
+    if let Some(datum) = Datum::from_geographic_epsg(code) {
+        return Ok(SpatialRef::geographic(datum));
+    }
+    if let Some((geographic_code, zone, south)) = utm_zone(code) {
+        let datum = Datum::from_geographic_epsg(geographic_code).unwrap();
+        let name = if geographic_code == 4283 {
+            format!("GDA94 / MGA zone {}", zone)
+        } else {
+            format!("{} / UTM zone {}{}", datum.geographic_name, zone, if south { "S" } else { "N" })
+        };
+        return Ok(SpatialRef::projected(&name, code, datum, Projection::utm(zone, south)));
+    }
+
+    let datum = |c: u16| Datum::from_geographic_epsg(c).unwrap();
+    let tm = |lat0: f64, lon0: f64, k0: f64, fe: f64, fn_: f64| Projection::TransverseMercator {
+        latitude_of_origin: lat0, central_meridian: lon0, scale_factor: k0, false_easting: fe, false_northing: fn_,
+    };
+    let lcc = |lat0: f64, lon0: f64, sp1: f64, sp2: f64, fe: f64, fn_: f64| Projection::LambertConformalConic {
+        latitude_of_origin: lat0, central_meridian: lon0, standard_parallel_1: sp1, standard_parallel_2: sp2,
+        scale_factor: 1f64, false_easting: fe, false_northing: fn_,
+    };
+    let albers = |lat0: f64, lon0: f64, sp1: f64, sp2: f64, fe: f64, fn_: f64| Projection::AlbersEqualArea {
+        latitude_of_origin: lat0, central_meridian: lon0, standard_parallel_1: sp1, standard_parallel_2: sp2,
+        false_easting: fe, false_northing: fn_,
+    };
+    let srs = match code {
+        3857 => SpatialRef::projected("WGS 84 / Pseudo-Mercator", code, Datum::wgs84(), Projection::WebMercator),
+        3395 => SpatialRef::projected("WGS 84 / World Mercator", code, Datum::wgs84(),
+            Projection::Mercator { central_meridian: 0f64, scale_factor: 1f64, false_easting: 0f64, false_northing: 0f64 }),
+        5070 => SpatialRef::projected("NAD83 / Conus Albers", code, datum(4269), albers(23.0, -96.0, 29.5, 45.5, 0.0, 0.0)),
+        3310 => SpatialRef::projected("NAD83 / California Albers", code, datum(4269), albers(0.0, -120.0, 34.0, 40.5, 0.0, -4_000_000.0)),
+        3338 => SpatialRef::projected("NAD83 / Alaska Albers", code, datum(4269), albers(50.0, -154.0, 55.0, 65.0, 0.0, 0.0)),
+        3005 => SpatialRef::projected("NAD83 / BC Albers", code, datum(4269), albers(45.0, -126.0, 50.0, 58.5, 1_000_000.0, 0.0)),
+        3577 => SpatialRef::projected("GDA94 / Australian Albers", code, datum(4283), albers(0.0, 132.0, -18.0, -36.0, 0.0, 0.0)),
+        3347 => SpatialRef::projected("NAD83 / Statistics Canada Lambert", code, datum(4269),
+            lcc(63.390675, -91.8666666666667, 49.0, 77.0, 6_200_000.0, 3_000_000.0)),
+        3978 => SpatialRef::projected("NAD83 / Canada Atlas Lambert", code, datum(4269), lcc(49.0, -95.0, 49.0, 77.0, 0.0, 0.0)),
+        3979 => SpatialRef::projected("NAD83(CSRS) / Canada Atlas Lambert", code, datum(4617), lcc(49.0, -95.0, 49.0, 77.0, 0.0, 0.0)),
+        3161 => SpatialRef::projected("NAD83 / Ontario MNR Lambert", code, datum(4269), lcc(0.0, -85.0, 44.5, 53.5, 930_000.0, 6_430_000.0)),
+        2154 => SpatialRef::projected("RGF93 / Lambert-93", code, datum(4171), lcc(46.5, 3.0, 49.0, 44.0, 700_000.0, 6_600_000.0)),
+        3034 => SpatialRef::projected("ETRS89 / LCC Europe", code, datum(4258), lcc(52.0, 10.0, 35.0, 65.0, 4_000_000.0, 2_800_000.0)),
+        27700 => SpatialRef::projected("OSGB 1936 / British National Grid", code, datum(4277),
+            tm(49.0, -2.0, 0.9996012717, 400_000.0, -100_000.0)),
+        _ => return Err(Error::new(ErrorKind::InvalidInput,
+            format!("EPSG:{} is not a supported coordinate reference system. Please specify it using WKT instead.", code))),
+    };
+    Ok(srs)
+}
+
+// The geographic system, zone and hemisphere of the UTM zones of each datum.
+fn utm_zone(code: u16) -> Option<(u16, u8, bool)> {
+    let c = code as u32;
+    let zone = |base: u32| (c - base) as u8;
+    match c {
+        32601...32660 => Some((4326, zone(32600), false)),
+        32701...32760 => Some((4326, zone(32700), true)),
+        32201...32260 => Some((4322, zone(32200), false)),
+        32301...32360 => Some((4322, zone(32300), true)),
+        26901...26923 => Some((4269, zone(26900), false)),
+        26701...26722 => Some((4267, zone(26700), false)),
+        25828...25838 => Some((4258, zone(25800), false)),
+        23028...23038 => Some((4230, zone(23000), false)),
+        28348...28358 => Some((4283, zone(28300), true)),
+        2955...2957 => Some((4617, zone(2944), false)),
+        2958...2962 => Some((4617, zone(2941), false)),
+        3154...3157 => Some((4617, zone(3147), false)),
+        3158...3160 => Some((4617, zone(3144), false)),
+        3761 => Some((4617, 22, false)),
+        _ => None,
+    }
+}
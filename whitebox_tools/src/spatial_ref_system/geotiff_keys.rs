@@ -0,0 +1,384 @@
+/*
+This code is part of the WhiteboxTools geospatial analysis library.
+Authors: Dr. John Lindsay
+Created: March 29, 2018
+Last Modified: April 6, 2018
+License: MIT
+
+Notes: GeoTIFF files, and LAS files that use GeoTIFF keys, describe their coordinate
+reference system either with an EPSG code (ProjectedCSTypeGeoKey or GeographicTypeGeoKey)
+or, for a user-defined system, with the code of the coordinate transformation and its
+parameters. Only the transformations that have a `Projection` are supported.
+*/
+
+use std::io::{Error, ErrorKind};
+use raster::geotiff::geokeys::GeoKeys;
+use raster::geotiff::tiff_consts::*;
+use super::datum::{Datum, Ellipsoid};
+use super::projections::Projection;
+use super::spatial_ref::SpatialRef;
+
+const USER_DEFINED: u16 = 32767;
+
+// the GeoTIFF codes of the coordinate transformations
+const CT_TRANSVERSE_MERCATOR: u16 = 1;
+const CT_MERCATOR: u16 = 7;
+const CT_LAMBERT_CONF_CONIC_2SP: u16 = 8;
+const CT_LAMBERT_CONF_CONIC_1SP: u16 = 9;
+const CT_ALBERS_EQUAL_AREA: u16 = 11;
+
+/// The value of a GeoKey, which is stored in the GeoKey directory, the GeoDoubleParamsTag,
+/// or the GeoAsciiParamsTag depending on its type.
+#[derive(Clone, Debug, PartialEq)]
+pub enum GeoKeyValue {
+    Short(u16),
+    Double(f64),
+    Ascii(String),
+}
+
+fn unsupported(message: &str) -> Error {
+    Error::new(ErrorKind::InvalidData, format!("Unsupported GeoTIFF coordinate reference system: {}", message))
+}
+
+// the length of a linear unit, in metres
+fn linear_unit(code: u16) -> Option<(&'static str, f64)> {
+    match code {
+        9001 => Some(("metre", 1f64)),
+        9002 => Some(("foot", 0.3048)),
+        9003 => Some(("US survey foot", 0.3048006096012192)),
+        _ => None,
+    }
+}
+
+fn read_datum(geokeys: &GeoKeys) -> Datum {
+    if let Some(datum) = geokeys.get_short(TAG_GEOGRAPHICTYPEGEOKEY).and_then(Datum::from_geographic_epsg) {
+        return datum;
+    }
+    // the datum codes are 2000 more than those of their geographic systems, e.g. 6326 for 4326
+    if let Some(datum) = geokeys.get_short(TAG_GEOGGEODETICDATUMGEOKEY)
+        .and_then(|c| if c > 2000 { Datum::from_geographic_epsg(c - 2000) } else { None }) {
+        return datum;
+    }
+    let ellipsoid = match geokeys.get_short(TAG_GEOGELLIPSOIDGEOKEY).and_then(Ellipsoid::from_epsg) {
+        Some(e) => Some(e),
+        None => match geokeys.get_double(TAG_GEOGSEMIMAJORAXISGEOKEY) {
+            Some(a) => {
+                match (geokeys.get_double(TAG_GEOGINVFLATTENINGGEOKEY), geokeys.get_double(TAG_GEOGSEMIMINORAXISGEOKEY)) {
+                    (Some(invf), _) => Some(Ellipsoid::new("User defined", a, invf)),
+                    (None, Some(b)) => Some(Ellipsoid::from_axes("User defined", a, b)),
+                    (None, None) => None,
+                }
+            },
+            None => None,
+        },
+    };
+    match ellipsoid {
+        Some(e) => {
+            let name = geokeys.get_ascii(TAG_GEOGCITATIONGEOKEY).unwrap_or("User defined".to_string());
+            Datum::new("User defined", e, [0f64; 7], &name, 0)
+        },
+        None => Datum::wgs84(),
+    }
+}
+
+/// Returns true if a set of GeoKeys describes a coordinate reference system. Files without
+/// one may still have GeoKeys, e.g. a raster type and a model type of zero, as written by
+/// WhiteboxTools for rasters whose system is unknown.
+pub fn specifies_crs(geokeys: &GeoKeys) -> bool {
+    let is_set = |key: u16| geokeys.get_short(key).map(|v| v != 0).unwrap_or(false);
+    is_set(TAG_GTMODELTYPEGEOKEY) || is_set(TAG_PROJECTEDCSTYPEGEOKEY) || is_set(TAG_GEOGRAPHICTYPEGEOKEY) ||
+        is_set(TAG_PROJECTIONGEOKEY) || is_set(TAG_PROJCOORDTRANSGEOKEY)
+}
+
+/// Reads the coordinate reference system described by a set of GeoKeys.
+pub fn from_geokeys(geokeys: &GeoKeys) -> Result<SpatialRef, Error> {
+    if geokeys.is_empty() {
+        return Err(unsupported("there are no GeoKeys"));
+    }
+    if let Some(code) = geokeys.get_short(TAG_PROJECTEDCSTYPEGEOKEY) {
+        if code != 0 && code != USER_DEFINED {
+            return SpatialRef::from_epsg(code);
+        }
+    }
+    let datum = read_datum(geokeys);
+    let model_type = geokeys.get_short(TAG_GTMODELTYPEGEOKEY).unwrap_or(0);
+    let projection_code = geokeys.get_short(TAG_PROJECTIONGEOKEY).unwrap_or(0);
+    let transformation = geokeys.get_short(TAG_PROJCOORDTRANSGEOKEY).unwrap_or(0);
+    if model_type == 2 || (model_type != 1 && projection_code == 0 && transformation == 0) {
+        if model_type == 0 && geokeys.get_short(TAG_GEOGRAPHICTYPEGEOKEY).is_none() {
+            return Err(unsupported("the model type is not specified"));
+        }
+        return Ok(SpatialRef::geographic(datum));
+    }
+
+    let (unit_name, unit) = match geokeys.get_short(TAG_PROJLINEARUNITSGEOKEY).and_then(linear_unit) {
+        Some((name, unit)) => (name.to_string(), unit),
+        None => match geokeys.get_double(TAG_PROJLINEARUNITSIZEGEOKEY) {
+            Some(size) if size > 0f64 => ("User defined".to_string(), size),
+            _ => ("metre".to_string(), 1f64),
+        },
+    };
+
+    // the UTM projection codes, i.e. 16001-16060 north and 16101-16160 south
+    let projection = if projection_code >= 16001 && projection_code <= 16060 {
+        Projection::utm((projection_code - 16000) as u8, false)
+    } else if projection_code >= 16101 && projection_code <= 16160 {
+        Projection::utm((projection_code - 16100) as u8, true)
+    } else {
+        let get = |keys: &[u16], default: f64| {
+            keys.iter().filter_map(|k| geokeys.get_double(*k)).next().unwrap_or(default)
+        };
+        let lat0 = get(&[TAG_PROJNATORIGINLATGEOKEY, TAG_PROJFALSEORIGINLATGEOKEY, TAG_PROJCENTERLATGEOKEY], 0f64);
+        let lon0 = get(&[TAG_PROJNATORIGINLONGGEOKEY, TAG_PROJFALSEORIGINLONGGEOKEY, TAG_PROJCENTERLONGGEOKEY], 0f64);
+        let sp1 = get(&[TAG_PROJSTDPARALLEL1GEOKEY], lat0);
+        let sp2 = get(&[TAG_PROJSTDPARALLEL2GEOKEY], sp1);
+        let k0 = get(&[TAG_PROJSCALEATNATORIGINGEOKEY, TAG_PROJSCALEATCENTERGEOKEY], 1f64);
+        let fe = get(&[TAG_PROJFALSEEASTINGGEOKEY, TAG_PROJFALSEORIGINEASTINGGEOKEY, TAG_PROJCENTEREASTINGGEOKEY], 0f64) * unit;
+        let fn_ = get(&[TAG_PROJFALSENORTHINGGEOKEY, TAG_PROJFALSEORIGINNORTHINGGEOKEY, TAG_PROJCENTERNORTHINGGEOKEY], 0f64) * unit;
+        match transformation {
+            CT_TRANSVERSE_MERCATOR => Projection::TransverseMercator {
+                latitude_of_origin: lat0, central_meridian: lon0, scale_factor: k0, false_easting: fe, false_northing: fn_,
+            },
+            CT_MERCATOR => Projection::Mercator { central_meridian: lon0, scale_factor: k0, false_easting: fe, false_northing: fn_ },
+            CT_LAMBERT_CONF_CONIC_2SP => Projection::LambertConformalConic {
+                latitude_of_origin: lat0, central_meridian: lon0, standard_parallel_1: sp1, standard_parallel_2: sp2,
+                scale_factor: 1f64, false_easting: fe, false_northing: fn_,
+            },
+            CT_LAMBERT_CONF_CONIC_1SP => Projection::LambertConformalConic {
+                latitude_of_origin: lat0, central_meridian: lon0, standard_parallel_1: lat0, standard_parallel_2: lat0,
+                scale_factor: k0, false_easting: fe, false_northing: fn_,
+            },
+            CT_ALBERS_EQUAL_AREA => Projection::AlbersEqualArea {
+                latitude_of_origin: lat0, central_meridian: lon0, standard_parallel_1: sp1, standard_parallel_2: sp2,
+                false_easting: fe, false_northing: fn_,
+            },
+            0 => return Err(unsupported("the projection is not specified")),
+            t => return Err(unsupported(&format!("coordinate transformation {}", t))),
+        }
+    };
+
+    let name = geokeys.get_ascii(TAG_PCSCITATIONGEOKEY)
+        .or_else(|| geokeys.get_ascii(TAG_GTCITATIONGEOKEY))
+        .unwrap_or("User defined".to_string());
+    Ok(SpatialRef {
+        name: name,
+        epsg_code: 0,
+        datum: datum,
+        projection: projection,
+        linear_unit_name: unit_name,
+        linear_unit: unit,
+    })
+}
+
+/// Describes a coordinate reference system with GeoKeys, other than the GTRasterTypeGeoKey,
+/// in the order of their key IDs. Systems with a known EPSG code are described by the code.
+pub fn to_geokeys(srs: &SpatialRef) -> Vec<(u16, GeoKeyValue)> {
+    let mut keys = vec![];
+    if srs.is_geographic() {
+        keys.push((TAG_GTMODELTYPEGEOKEY, GeoKeyValue::Short(2)));
+        keys.push((TAG_GTCITATIONGEOKEY, GeoKeyValue::Ascii(srs.name.clone())));
+    } else {
+        keys.push((TAG_GTMODELTYPEGEOKEY, GeoKeyValue::Short(1)));
+    }
+    if SpatialRef::from_epsg(srs.epsg_code).is_ok() {
+        if srs.is_geographic() {
+            keys.push((TAG_GEOGRAPHICTYPEGEOKEY, GeoKeyValue::Short(srs.epsg_code)));
+        } else {
+            keys.push((TAG_PROJECTEDCSTYPEGEOKEY, GeoKeyValue::Short(srs.epsg_code)));
+            keys.push((TAG_PCSCITATIONGEOKEY, GeoKeyValue::Ascii(srs.name.clone())));
+        }
+        return keys;
+    }
+
+    let datum = &srs.datum;
+    if datum.geographic_epsg_code != 0 {
+        keys.push((TAG_GEOGRAPHICTYPEGEOKEY, GeoKeyValue::Short(datum.geographic_epsg_code)));
+    } else {
+        keys.push((TAG_GEOGRAPHICTYPEGEOKEY, GeoKeyValue::Short(USER_DEFINED)));
+        keys.push((TAG_GEOGCITATIONGEOKEY, GeoKeyValue::Ascii(datum.geographic_name.clone())));
+        keys.push((TAG_GEOGGEODETICDATUMGEOKEY, GeoKeyValue::Short(USER_DEFINED)));
+        keys.push((TAG_GEOGANGULARUNITSGEOKEY, GeoKeyValue::Short(9102)));
+        keys.push((TAG_GEOGELLIPSOIDGEOKEY, GeoKeyValue::Short(USER_DEFINED)));
+        keys.push((TAG_GEOGSEMIMAJORAXISGEOKEY, GeoKeyValue::Double(datum.ellipsoid.a)));
+        keys.push((TAG_GEOGINVFLATTENINGGEOKEY, GeoKeyValue::Double(datum.ellipsoid.inverse_flattening)));
+    }
+    if srs.is_geographic() {
+        return keys;
+    }
+
+    keys.push((TAG_PROJECTEDCSTYPEGEOKEY, GeoKeyValue::Short(USER_DEFINED)));
+    keys.push((TAG_PCSCITATIONGEOKEY, GeoKeyValue::Ascii(srs.name.clone())));
+    keys.push((TAG_PROJECTIONGEOKEY, GeoKeyValue::Short(USER_DEFINED)));
+    let unit = srs.linear_unit;
+    let mut params: Vec<(u16, f64)> = vec![];
+    let one_sp = srs.projection.name() != "Lambert_Conformal_Conic_2SP";
+    let transformation = match srs.projection {
+        Projection::TransverseMercator { latitude_of_origin, central_meridian, scale_factor, false_easting, false_northing } |
+        Projection::LambertConformalConic { latitude_of_origin, central_meridian, scale_factor, false_easting, false_northing, .. } if one_sp => {
+            params.push((TAG_PROJNATORIGINLONGGEOKEY, central_meridian));
+            params.push((TAG_PROJNATORIGINLATGEOKEY, latitude_of_origin));
+            params.push((TAG_PROJFALSEEASTINGGEOKEY, false_easting / unit));
+            params.push((TAG_PROJFALSENORTHINGGEOKEY, false_northing / unit));
+            params.push((TAG_PROJSCALEATNATORIGINGEOKEY, scale_factor));
+            if srs.projection.name() == "Transverse_Mercator" { CT_TRANSVERSE_MERCATOR } else { CT_LAMBERT_CONF_CONIC_1SP }
+        },
+        Projection::LambertConformalConic { latitude_of_origin, central_meridian, standard_parallel_1, standard_parallel_2, false_easting, false_northing, .. } => {
+            params.push((TAG_PROJSTDPARALLEL1GEOKEY, standard_parallel_1));
+            params.push((TAG_PROJSTDPARALLEL2GEOKEY, standard_parallel_2));
+            params.push((TAG_PROJFALSEORIGINLONGGEOKEY, central_meridian));
+            params.push((TAG_PROJFALSEORIGINLATGEOKEY, latitude_of_origin));
+            params.push((TAG_PROJFALSEORIGINEASTINGGEOKEY, false_easting / unit));
+            params.push((TAG_PROJFALSEORIGINNORTHINGGEOKEY, false_northing / unit));
+            CT_LAMBERT_CONF_CONIC_2SP
+        },
+        Projection::AlbersEqualArea { latitude_of_origin, central_meridian, standard_parallel_1, standard_parallel_2, false_easting, false_northing } => {
+            params.push((TAG_PROJSTDPARALLEL1GEOKEY, standard_parallel_1));
+            params.push((TAG_PROJSTDPARALLEL2GEOKEY, standard_parallel_2));
+            params.push((TAG_PROJNATORIGINLONGGEOKEY, central_meridian));
+            params.push((TAG_PROJNATORIGINLATGEOKEY, latitude_of_origin));
+            params.push((TAG_PROJFALSEEASTINGGEOKEY, false_easting / unit));
+            params.push((TAG_PROJFALSENORTHINGGEOKEY, false_northing / unit));
+            CT_ALBERS_EQUAL_AREA
+        },
+        Projection::Mercator { central_meridian, scale_factor, false_easting, false_northing } => {
+            params.push((TAG_PROJNATORIGINLONGGEOKEY, central_meridian));
+            params.push((TAG_PROJNATORIGINLATGEOKEY, 0f64));
+            params.push((TAG_PROJFALSEEASTINGGEOKEY, false_easting / unit));
+            params.push((TAG_PROJFALSENORTHINGGEOKEY, false_northing / unit));
+            params.push((TAG_PROJSCALEATNATORIGINGEOKEY, scale_factor));
+            CT_MERCATOR
+        },
+        _ => {
+            // Web Mercator always has an EPSG code, 3857
+            return vec![
+                (TAG_GTMODELTYPEGEOKEY, GeoKeyValue::Short(1)),
+                (TAG_PROJECTEDCSTYPEGEOKEY, GeoKeyValue::Short(3857)),
+            ];
+        },
+    };
+    keys.push((TAG_PROJCOORDTRANSGEOKEY, GeoKeyValue::Short(transformation)));
+    match linear_unit_code(unit) {
+        Some(code) => keys.push((TAG_PROJLINEARUNITSGEOKEY, GeoKeyValue::Short(code))),
+        None => {
+            keys.push((TAG_PROJLINEARUNITSGEOKEY, GeoKeyValue::Short(USER_DEFINED)));
+            keys.push((TAG_PROJLINEARUNITSIZEGEOKEY, GeoKeyValue::Double(unit)));
+        },
+    }
+    for (key, value) in params {
+        keys.push((key, GeoKeyValue::Double(value)));
+    }
+    keys.sort_by_key(|k| k.0);
+    keys
+}
+
+fn linear_unit_code(unit: f64) -> Option<u16> {
+    [9001u16, 9002, 9003].iter().cloned().find(|c| (linear_unit(*c).unwrap().1 - unit).abs() < 1.0e-12)
+}
+
+#[cfg(test)]
+mod tests {
+    use lidar::Vlr;
+    use super::*;
+
+    // Reads GeoKeys in the same way as those of a LAS file.
+    fn read_keys(keys: &[(u16, GeoKeyValue)]) -> GeoKeys {
+        let mut geokeys: GeoKeys = Default::default();
+        for vlr in Vlr::new_geokeys(keys) {
+            match vlr.record_id {
+                34_735 => geokeys.add_key_directory(&vlr.binary_data),
+                34_736 => geokeys.add_double_params(&vlr.binary_data),
+                _ => geokeys.add_ascii_params(&vlr.binary_data),
+            }
+        }
+        geokeys
+    }
+
+    fn user_defined(name: &str, datum: Datum, projection: Projection, unit: f64) -> SpatialRef {
+        let mut srs = SpatialRef::projected(name, 0, datum, projection);
+        srs.linear_unit = unit;
+        srs
+    }
+
+    #[test]
+    fn epsg_systems_are_described_by_their_codes() {
+        for code in [4326u16, 4267, 26917, 32755, 3857, 5070, 27700].iter() {
+            let srs = SpatialRef::from_epsg(*code).unwrap();
+            let keys = to_geokeys(&srs);
+            let code_key = if srs.is_geographic() { TAG_GEOGRAPHICTYPEGEOKEY } else { TAG_PROJECTEDCSTYPEGEOKEY };
+            assert!(keys.contains(&(code_key, GeoKeyValue::Short(*code))));
+            let geokeys = read_keys(&keys);
+            assert!(specifies_crs(&geokeys));
+            assert_eq!(from_geokeys(&geokeys).unwrap(), srs);
+        }
+    }
+
+    #[test]
+    fn user_defined_systems_are_read_back() {
+        let nad83 = Datum::from_geographic_epsg(4269).unwrap();
+        let custom = Datum::new("Custom", Ellipsoid::new("Custom", 6_378_000.0, 300.0), [0f64; 7], "GCS Custom", 0);
+        let systems = vec![
+            user_defined("TM", nad83.clone(), Projection::TransverseMercator { latitude_of_origin: 0f64,
+                central_meridian: -79.5, scale_factor: 0.9999, false_easting: 304_800f64, false_northing: 0f64 }, 1f64),
+            user_defined("LCC 1SP", custom.clone(), Projection::LambertConformalConic { latitude_of_origin: 45f64,
+                central_meridian: -90f64, standard_parallel_1: 45f64, standard_parallel_2: 45f64, scale_factor: 0.9999,
+                false_easting: 100f64, false_northing: 200f64 }, 1f64),
+            user_defined("LCC 2SP in feet", nad83.clone(), Projection::LambertConformalConic { latitude_of_origin: 27.8333333333,
+                central_meridian: -99f64, standard_parallel_1: 28.3833333333, standard_parallel_2: 30.2833333333, scale_factor: 1f64,
+                false_easting: 600_000f64, false_northing: 4_000_000f64 }, 0.3048006096012192),
+            user_defined("Albers", custom.clone(), Projection::AlbersEqualArea { latitude_of_origin: 0f64,
+                central_meridian: -120f64, standard_parallel_1: 34f64, standard_parallel_2: 40.5, false_easting: 0f64,
+                false_northing: -4_000_000f64 }, 1f64),
+            user_defined("Mercator in custom units", nad83.clone(), Projection::Mercator { central_meridian: 10f64,
+                scale_factor: 0.997, false_easting: 1_000f64, false_northing: 2_000f64 }, 2.5),
+            SpatialRef::geographic(custom.clone()),
+        ];
+        for srs in &systems {
+            let geokeys = read_keys(&to_geokeys(srs));
+            assert!(specifies_crs(&geokeys));
+            let srs2 = from_geokeys(&geokeys).unwrap();
+            assert!(srs.is_equivalent(&srs2), "{} was read back as {:?}", srs.name, srs2);
+            assert_eq!(srs.name, srs2.name);
+            assert!((srs.linear_unit - srs2.linear_unit).abs() < 1.0e-12);
+        }
+    }
+
+    #[test]
+    fn utm_projection_codes_are_read() {
+        let geokeys = read_keys(&[
+            (TAG_GTMODELTYPEGEOKEY, GeoKeyValue::Short(1)),
+            (TAG_GEOGRAPHICTYPEGEOKEY, GeoKeyValue::Short(4267)),
+            (TAG_PROJECTEDCSTYPEGEOKEY, GeoKeyValue::Short(USER_DEFINED)),
+            (TAG_PROJECTIONGEOKEY, GeoKeyValue::Short(16_155)),
+        ]);
+        let srs = from_geokeys(&geokeys).unwrap();
+        assert_eq!(srs.projection, Projection::utm(55, true));
+        assert_eq!(srs.datum.geographic_epsg_code, 4267);
+    }
+
+    #[test]
+    fn keys_without_a_system_do_not_specify_one() {
+        assert!(!specifies_crs(&Default::default()));
+        assert!(from_geokeys(&Default::default()).is_err());
+        // as written by WhiteboxTools for rasters whose system is not known
+        let geokeys = read_keys(&[(TAG_GTMODELTYPEGEOKEY, GeoKeyValue::Short(0)), (TAG_GTRASTERTYPEGEOKEY, GeoKeyValue::Short(1))]);
+        assert!(!geokeys.is_empty());
+        assert!(!specifies_crs(&geokeys));
+        assert!(from_geokeys(&geokeys).is_err());
+        // a system given only by its geographic code
+        let geokeys = read_keys(&[(TAG_GEOGRAPHICTYPEGEOKEY, GeoKeyValue::Short(4326))]);
+        assert!(specifies_crs(&geokeys));
+        assert!(from_geokeys(&geokeys).unwrap().is_geographic());
+    }
+
+    #[test]
+    fn unsupported_transformations_are_rejected() {
+        let geokeys = read_keys(&[
+            (TAG_GTMODELTYPEGEOKEY, GeoKeyValue::Short(1)),
+            (TAG_PROJECTEDCSTYPEGEOKEY, GeoKeyValue::Short(USER_DEFINED)),
+            (TAG_PROJECTIONGEOKEY, GeoKeyValue::Short(USER_DEFINED)),
+            (TAG_PROJCOORDTRANSGEOKEY, GeoKeyValue::Short(22)),
+        ]);
+        assert!(specifies_crs(&geokeys));
+        assert_eq!(from_geokeys(&geokeys).unwrap_err().kind(), ErrorKind::InvalidData);
+    }
+}
@@ -0,0 +1,103 @@
+/*
+This code is part of the WhiteboxTools geospatial analysis library.
+Authors: Dr. John Lindsay
+Created: March 29, 2018
+Last Modified: April 6, 2018
+License: MIT
+*/
+
+//! Coordinate reference systems and the transformation of coordinates between them. A
+//! `SpatialRef` may be created from an EPSG code, OGC or ESRI WKT, or the GeoKeys of a
+//! GeoTIFF or LAS file, and `read_spatial_ref` reads the system of any supported data file.
+
+use std::fs::File;
+use std::io::{Error, Read};
+use std::path::Path;
+use io_utils::read_geopackage_srs;
+use lidar::LasReader;
+use raster;
+use raster::geotiff::read_geotiff_geokeys;
+use self::geotiff_keys::specifies_crs;
+use vector::read_geojson_crs;
+
+// private sub-module defined in other files
+mod datum;
+mod epsg;
+mod geotiff_keys;
+mod projections;
+mod spatial_ref;
+mod wkt;
+
+// exports identifiers from private sub-modules in the current module namespace
+pub use self::datum::Datum;
+pub use self::datum::Ellipsoid;
+pub use self::geotiff_keys::GeoKeyValue;
+pub use self::projections::Projection;
+pub use self::spatial_ref::SpatialRef;
+pub use self::spatial_ref::Transformation;
+
+/// Reads the coordinate reference system of a raster, vector, or LiDAR file. GeoTIFF and
+/// LAS files are described by their GeoKeys or WKT VLRs, GeoJSON files by their crs
+/// member alone, GeoPackages by the spatial reference system of their first table, and
+/// other formats by a .prj file of the same name, if there is one. Returns None if the file
+/// does not specify a coordinate reference system; in particular, a GeoJSON file without a
+/// crs member is only taken to be WGS 84 when it is read, if its coordinates are geographic.
+pub fn read_spatial_ref(file_name: &str) -> Result<Option<SpatialRef>, Error> {
+    if raster::is_in_memory_raster_name(file_name) {
+        return Ok(raster::get_in_memory_raster_configs(file_name)
+            .and_then(|configs| SpatialRef::from_epsg_or_wkt(configs.epsg_code, &configs.coordinate_ref_system_wkt)));
+    }
+    // raster band selections, e.g. image.tif:2
+    let file_name = match file_name.rfind(':') {
+        Some(pos) if !Path::new(file_name).is_file() && file_name[(pos + 1)..].chars().all(|c| c.is_digit(10)) => &file_name[..pos],
+        _ => file_name,
+    };
+    let extension = Path::new(file_name).extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .unwrap_or(String::new());
+    match extension.as_ref() {
+        "las" | "laz" => {
+            let reader = LasReader::new(file_name)?;
+            if let Some(wkt) = reader.get_wkt() {
+                return SpatialRef::from_wkt(&wkt).map(Some);
+            }
+            if !specifies_crs(&reader.geokeys) {
+                return Ok(None);
+            }
+            SpatialRef::from_geokeys(&reader.geokeys).map(Some)
+        },
+        "tif" | "tiff" | "gtif" | "gtiff" => {
+            let geokeys = read_geotiff_geokeys(file_name)?;
+            if !specifies_crs(&geokeys) {
+                return Ok(None);
+            }
+            SpatialRef::from_geokeys(&geokeys).map(Some)
+        },
+        "geojson" | "json" => {
+            match read_geojson_crs(file_name)? {
+                Some(wkt) => SpatialRef::from_wkt(&wkt).map(Some),
+                None => Ok(None),
+            }
+        },
+        "gpkg" => {
+            let (epsg_code, wkt) = read_geopackage_srs(file_name)?;
+            Ok(SpatialRef::from_epsg_or_wkt(epsg_code, &wkt))
+        },
+        _ => {
+            let prj = Path::new(file_name).with_extension("prj");
+            if prj.is_file() {
+                read_prj(&prj.to_string_lossy()).map(Some)
+            } else {
+                Ok(None)
+            }
+        },
+    }
+}
+
+/// Reads the WKT coordinate reference system of a .prj file.
+pub fn read_prj(file_name: &str) -> Result<SpatialRef, Error> {
+    let mut f = File::open(file_name)?;
+    let mut wkt = String::new();
+    f.read_to_string(&mut wkt)?;
+    SpatialRef::from_wkt(&wkt)
+}
@@ -0,0 +1,623 @@
+/*
+This code is part of the WhiteboxTools geospatial analysis library.
+Authors: Dr. John Lindsay
+Created: March 29, 2018
+Last Modified: March 29, 2018
+License: MIT
+
+Notes: The transverse Mercator projection uses the sixth-order Krüger series of Karney
+(2011, Transverse Mercator with an accuracy of a few nanometers, J. Geodesy 85), which is
+accurate to well under a millimetre within several thousand kilometres of the central
+meridian. The conic and Mercator projections follow the ellipsoidal formulae of Snyder
+(1987, Map Projections - A Working Manual, USGS Professional Paper 1395). Web Mercator
+applies the spherical formulae to the WGS84 semi-major axis, as EPSG:3857 does.
+*/
+
+use std::f64;
+use std::f64::consts::PI;
+use super::datum::Ellipsoid;
+
+/// A map projection. Angles are in degrees and false eastings and northings in metres.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Projection {
+    /// Longitude and latitude, in degrees.
+    Geographic,
+    TransverseMercator {
+        latitude_of_origin: f64,
+        central_meridian: f64,
+        scale_factor: f64,
+        false_easting: f64,
+        false_northing: f64,
+    },
+    /// The one standard parallel variant has both standard parallels equal to the
+    /// latitude of origin and a scale factor that may differ from one.
+    LambertConformalConic {
+        latitude_of_origin: f64,
+        central_meridian: f64,
+        standard_parallel_1: f64,
+        standard_parallel_2: f64,
+        scale_factor: f64,
+        false_easting: f64,
+        false_northing: f64,
+    },
+    AlbersEqualArea {
+        latitude_of_origin: f64,
+        central_meridian: f64,
+        standard_parallel_1: f64,
+        standard_parallel_2: f64,
+        false_easting: f64,
+        false_northing: f64,
+    },
+    Mercator {
+        central_meridian: f64,
+        scale_factor: f64,
+        false_easting: f64,
+        false_northing: f64,
+    },
+    WebMercator,
+}
+
+impl Projection {
+    /// Creates a Universal Transverse Mercator zone.
+    pub fn utm(zone: u8, south: bool) -> Projection {
+        Projection::TransverseMercator {
+            latitude_of_origin: 0f64,
+            central_meridian: zone as f64 * 6f64 - 183f64,
+            scale_factor: 0.9996,
+            false_easting: 500_000f64,
+            false_northing: if south { 10_000_000f64 } else { 0f64 },
+        }
+    }
+
+    /// Converts geodetic longitude and latitude, in radians, to projected coordinates in
+    /// metres. Points that cannot be projected, e.g. the poles in Mercator, are NaN.
+    pub fn forward(&self, ellipsoid: &Ellipsoid, lon: f64, lat: f64) -> (f64, f64) {
+        match *self {
+            Projection::Geographic => (lon.to_degrees(), lat.to_degrees()),
+            Projection::TransverseMercator { latitude_of_origin, central_meridian, scale_factor, false_easting, false_northing } => {
+                let tm = TransverseMercator::new(ellipsoid, latitude_of_origin, scale_factor);
+                let (x, y) = tm.forward(normalize_lon(lon - central_meridian.to_radians()), lat);
+                (x + false_easting, y + false_northing)
+            },
+            Projection::LambertConformalConic { latitude_of_origin, central_meridian, standard_parallel_1, standard_parallel_2, scale_factor, false_easting, false_northing } => {
+                let lcc = LambertConformalConic::new(ellipsoid, latitude_of_origin, standard_parallel_1, standard_parallel_2, scale_factor);
+                let (x, y) = lcc.forward(normalize_lon(lon - central_meridian.to_radians()), lat);
+                (x + false_easting, y + false_northing)
+            },
+            Projection::AlbersEqualArea { latitude_of_origin, central_meridian, standard_parallel_1, standard_parallel_2, false_easting, false_northing } => {
+                let aea = AlbersEqualArea::new(ellipsoid, latitude_of_origin, standard_parallel_1, standard_parallel_2);
+                let (x, y) = aea.forward(normalize_lon(lon - central_meridian.to_radians()), lat);
+                (x + false_easting, y + false_northing)
+            },
+            Projection::Mercator { central_meridian, scale_factor, false_easting, false_northing } => {
+                if lat.abs() >= PI / 2f64 {
+                    return (f64::NAN, f64::NAN);
+                }
+                let e = ellipsoid.e2().sqrt();
+                let x = ellipsoid.a * scale_factor * normalize_lon(lon - central_meridian.to_radians());
+                let y = -ellipsoid.a * scale_factor * tsfn(e, lat).ln();
+                (x + false_easting, y + false_northing)
+            },
+            Projection::WebMercator => {
+                if lat.abs() >= PI / 2f64 {
+                    return (f64::NAN, f64::NAN);
+                }
+                let x = ellipsoid.a * normalize_lon(lon);
+                let y = ellipsoid.a * (PI / 4f64 + lat / 2f64).tan().ln();
+                (x, y)
+            },
+        }
+    }
+
+    /// Converts projected coordinates in metres to geodetic longitude and latitude, in radians.
+    pub fn inverse(&self, ellipsoid: &Ellipsoid, x: f64, y: f64) -> (f64, f64) {
+        match *self {
+            Projection::Geographic => (x.to_radians(), y.to_radians()),
+            Projection::TransverseMercator { latitude_of_origin, central_meridian, scale_factor, false_easting, false_northing } => {
+                let tm = TransverseMercator::new(ellipsoid, latitude_of_origin, scale_factor);
+                let (lon, lat) = tm.inverse(x - false_easting, y - false_northing);
+                (normalize_lon(lon + central_meridian.to_radians()), lat)
+            },
+            Projection::LambertConformalConic { latitude_of_origin, central_meridian, standard_parallel_1, standard_parallel_2, scale_factor, false_easting, false_northing } => {
+                let lcc = LambertConformalConic::new(ellipsoid, latitude_of_origin, standard_parallel_1, standard_parallel_2, scale_factor);
+                let (lon, lat) = lcc.inverse(x - false_easting, y - false_northing);
+                (normalize_lon(lon + central_meridian.to_radians()), lat)
+            },
+            Projection::AlbersEqualArea { latitude_of_origin, central_meridian, standard_parallel_1, standard_parallel_2, false_easting, false_northing } => {
+                let aea = AlbersEqualArea::new(ellipsoid, latitude_of_origin, standard_parallel_1, standard_parallel_2);
+                let (lon, lat) = aea.inverse(x - false_easting, y - false_northing);
+                (normalize_lon(lon + central_meridian.to_radians()), lat)
+            },
+            Projection::Mercator { central_meridian, scale_factor, false_easting, false_northing } => {
+                let e = ellipsoid.e2().sqrt();
+                let ts = (-(y - false_northing) / (ellipsoid.a * scale_factor)).exp();
+                let lon = (x - false_easting) / (ellipsoid.a * scale_factor) + central_meridian.to_radians();
+                (lon, phi2(e, ts))
+            },
+            Projection::WebMercator => {
+                let lon = x / ellipsoid.a;
+                let lat = PI / 2f64 - 2f64 * (-y / ellipsoid.a).exp().atan();
+                (lon, lat)
+            },
+        }
+    }
+
+    /// Returns true if the two projections are the same, allowing for rounding in the
+    /// parameters, e.g. of a WKT definition.
+    pub fn is_equivalent(&self, other: &Projection) -> bool {
+        let a = self.named_parameters();
+        let b = other.named_parameters();
+        if a.len() != b.len() || self.name() != other.name() {
+            return false;
+        }
+        a.iter().zip(b.iter()).all(|(p1, p2)| {
+            // the false eastings and northings are in metres, the others in degrees
+            let tolerance = if p1.0.starts_with("false_") { 1.0e-3 } else { 1.0e-9 };
+            (p1.1 - p2.1).abs() < tolerance
+        })
+    }
+
+    /// The name used for the projection in OGC WKT.
+    pub fn name(&self) -> &'static str {
+        match *self {
+            Projection::Geographic => "",
+            Projection::TransverseMercator { .. } => "Transverse_Mercator",
+            Projection::LambertConformalConic { standard_parallel_1, standard_parallel_2, latitude_of_origin, .. } => {
+                if standard_parallel_1 == standard_parallel_2 && standard_parallel_1 == latitude_of_origin {
+                    "Lambert_Conformal_Conic_1SP"
+                } else {
+                    "Lambert_Conformal_Conic_2SP"
+                }
+            },
+            Projection::AlbersEqualArea { .. } => "Albers_Conic_Equal_Area",
+            Projection::Mercator { .. } => "Mercator_1SP",
+            Projection::WebMercator => "Popular_Visualisation_Pseudo_Mercator",
+        }
+    }
+
+    /// The parameters of the projection, in the order and with the names used in OGC WKT.
+    /// The false easting and northing are in metres.
+    pub fn named_parameters(&self) -> Vec<(&'static str, f64)> {
+        match *self {
+            Projection::Geographic => vec![],
+            Projection::TransverseMercator { latitude_of_origin, central_meridian, scale_factor, false_easting, false_northing } => vec![
+                ("latitude_of_origin", latitude_of_origin),
+                ("central_meridian", central_meridian),
+                ("scale_factor", scale_factor),
+                ("false_easting", false_easting),
+                ("false_northing", false_northing),
+            ],
+            Projection::LambertConformalConic { latitude_of_origin, central_meridian, standard_parallel_1, standard_parallel_2, scale_factor, false_easting, false_northing } => {
+                if self.name() == "Lambert_Conformal_Conic_1SP" {
+                    vec![
+                        ("latitude_of_origin", latitude_of_origin),
+                        ("central_meridian", central_meridian),
+                        ("scale_factor", scale_factor),
+                        ("false_easting", false_easting),
+                        ("false_northing", false_northing),
+                    ]
+                } else {
+                    vec![
+                        ("standard_parallel_1", standard_parallel_1),
+                        ("standard_parallel_2", standard_parallel_2),
+                        ("latitude_of_origin", latitude_of_origin),
+                        ("central_meridian", central_meridian),
+                        ("false_easting", false_easting),
+                        ("false_northing", false_northing),
+                    ]
+                }
+            },
+            Projection::AlbersEqualArea { latitude_of_origin, central_meridian, standard_parallel_1, standard_parallel_2, false_easting, false_northing } => vec![
+                ("standard_parallel_1", standard_parallel_1),
+                ("standard_parallel_2", standard_parallel_2),
+                ("latitude_of_center", latitude_of_origin),
+                ("longitude_of_center", central_meridian),
+                ("false_easting", false_easting),
+                ("false_northing", false_northing),
+            ],
+            Projection::Mercator { central_meridian, scale_factor, false_easting, false_northing } => vec![
+                ("central_meridian", central_meridian),
+                ("scale_factor", scale_factor),
+                ("false_easting", false_easting),
+                ("false_northing", false_northing),
+            ],
+            Projection::WebMercator => vec![
+                ("central_meridian", 0f64),
+                ("scale_factor", 1f64),
+                ("false_easting", 0f64),
+                ("false_northing", 0f64),
+            ],
+        }
+    }
+}
+
+fn normalize_lon(lon: f64) -> f64 {
+    let mut lon = lon;
+    while lon > PI {
+        lon -= 2f64 * PI;
+    }
+    while lon < -PI {
+        lon += 2f64 * PI;
+    }
+    lon
+}
+
+// Snyder's t, i.e. the exponential of minus the isometric latitude (eq. 15-9).
+fn tsfn(e: f64, lat: f64) -> f64 {
+    let es = e * lat.sin();
+    (PI / 4f64 - lat / 2f64).tan() / ((1f64 - es) / (1f64 + es)).powf(e / 2f64)
+}
+
+// The inverse of tsfn (eq. 7-9).
+fn phi2(e: f64, ts: f64) -> f64 {
+    let mut lat = PI / 2f64 - 2f64 * ts.atan();
+    for _ in 0..30 {
+        let es = e * lat.sin();
+        let next = PI / 2f64 - 2f64 * (ts * ((1f64 - es) / (1f64 + es)).powf(e / 2f64)).atan();
+        if (next - lat).abs() < 1.0e-14 {
+            return next;
+        }
+        lat = next;
+    }
+    lat
+}
+
+// Snyder's m (eq. 14-15).
+fn msfn(e2: f64, lat: f64) -> f64 {
+    lat.cos() / (1f64 - e2 * lat.sin() * lat.sin()).sqrt()
+}
+
+// Snyder's q (eq. 3-12).
+fn qsfn(e: f64, lat: f64) -> f64 {
+    let sin_lat = lat.sin();
+    if e < 1.0e-10 {
+        return 2f64 * sin_lat;
+    }
+    let es = e * sin_lat;
+    (1f64 - e * e) * (sin_lat / (1f64 - es * es) - (1f64 / (2f64 * e)) * ((1f64 - es) / (1f64 + es)).ln())
+}
+
+struct TransverseMercator {
+    e: f64,
+    // the rectifying radius times the scale factor
+    ka: f64,
+    alpha: [f64; 6],
+    beta: [f64; 6],
+    // the northing of the latitude of origin
+    y0: f64,
+}
+
+impl TransverseMercator {
+    fn new(ellipsoid: &Ellipsoid, latitude_of_origin: f64, scale_factor: f64) -> TransverseMercator {
+        let f = ellipsoid.flattening();
+        let n = f / (2f64 - f);
+        let n2 = n * n;
+        let n3 = n2 * n;
+        let n4 = n3 * n;
+        let n5 = n4 * n;
+        let n6 = n5 * n;
+        let a = ellipsoid.a / (1f64 + n) * (1f64 + n2 / 4f64 + n4 / 64f64 + n6 / 256f64);
+        let alpha = [
+            n / 2f64 - 2f64 * n2 / 3f64 + 5f64 * n3 / 16f64 + 41f64 * n4 / 180f64 - 127f64 * n5 / 288f64 + 7891f64 * n6 / 37800f64,
+            13f64 * n2 / 48f64 - 3f64 * n3 / 5f64 + 557f64 * n4 / 1440f64 + 281f64 * n5 / 630f64 - 1983433f64 * n6 / 1935360f64,
+            61f64 * n3 / 240f64 - 103f64 * n4 / 140f64 + 15061f64 * n5 / 26880f64 + 167603f64 * n6 / 181440f64,
+            49561f64 * n4 / 161280f64 - 179f64 * n5 / 168f64 + 6601661f64 * n6 / 7257600f64,
+            34729f64 * n5 / 80640f64 - 3418889f64 * n6 / 1995840f64,
+            212378941f64 * n6 / 319334400f64,
+        ];
+        let beta = [
+            n / 2f64 - 2f64 * n2 / 3f64 + 37f64 * n3 / 96f64 - n4 / 360f64 - 81f64 * n5 / 512f64 + 96199f64 * n6 / 604800f64,
+            n2 / 48f64 + n3 / 15f64 - 437f64 * n4 / 1440f64 + 46f64 * n5 / 105f64 - 1118711f64 * n6 / 3870720f64,
+            17f64 * n3 / 480f64 - 37f64 * n4 / 840f64 - 209f64 * n5 / 4480f64 + 5569f64 * n6 / 90720f64,
+            4397f64 * n4 / 161280f64 - 11f64 * n5 / 504f64 - 830251f64 * n6 / 7257600f64,
+            4583f64 * n5 / 161280f64 - 108847f64 * n6 / 3991680f64,
+            20648693f64 * n6 / 638668800f64,
+        ];
+        let mut tm = TransverseMercator {
+            e: ellipsoid.e2().sqrt(),
+            ka: scale_factor * a,
+            alpha: alpha,
+            beta: beta,
+            y0: 0f64,
+        };
+        if latitude_of_origin != 0f64 {
+            tm.y0 = tm.forward(0f64, latitude_of_origin.to_radians()).1;
+        }
+        tm
+    }
+
+    // the conformal latitude, as its tangent
+    fn tau_prime(&self, tau: f64) -> f64 {
+        let sigma = (self.e * (self.e * tau / (1f64 + tau * tau).sqrt()).atanh()).sinh();
+        tau * (1f64 + sigma * sigma).sqrt() - sigma * (1f64 + tau * tau).sqrt()
+    }
+
+    fn forward(&self, lon: f64, lat: f64) -> (f64, f64) {
+        let tau_p = self.tau_prime(lat.tan());
+        let xi_p = tau_p.atan2(lon.cos());
+        let eta_p = (lon.sin() / (tau_p * tau_p + lon.cos() * lon.cos()).sqrt()).asinh();
+        let mut xi = xi_p;
+        let mut eta = eta_p;
+        for j in 0..6 {
+            let k = 2f64 * (j + 1) as f64;
+            xi += self.alpha[j] * (k * xi_p).sin() * (k * eta_p).cosh();
+            eta += self.alpha[j] * (k * xi_p).cos() * (k * eta_p).sinh();
+        }
+        (self.ka * eta, self.ka * xi - self.y0)
+    }
+
+    fn inverse(&self, x: f64, y: f64) -> (f64, f64) {
+        let xi = (y + self.y0) / self.ka;
+        let eta = x / self.ka;
+        let mut xi_p = xi;
+        let mut eta_p = eta;
+        for j in 0..6 {
+            let k = 2f64 * (j + 1) as f64;
+            xi_p -= self.beta[j] * (k * xi).sin() * (k * eta).cosh();
+            eta_p -= self.beta[j] * (k * xi).cos() * (k * eta).sinh();
+        }
+        let tau_p = xi_p.sin() / (eta_p.sinh() * eta_p.sinh() + xi_p.cos() * xi_p.cos()).sqrt();
+        let lon = eta_p.sinh().atan2(xi_p.cos());
+
+        // Newton's method for the latitude with this conformal latitude
+        let e2 = self.e * self.e;
+        let mut tau = tau_p;
+        for _ in 0..10 {
+            let tau_i = self.tau_prime(tau);
+            let delta = (tau_p - tau_i) / (1f64 + tau_i * tau_i).sqrt() *
+                (1f64 + (1f64 - e2) * tau * tau) / ((1f64 - e2) * (1f64 + tau * tau).sqrt());
+            tau += delta;
+            if delta.abs() < 1.0e-12 {
+                break;
+            }
+        }
+        (lon, tau.atan())
+    }
+}
+
+struct LambertConformalConic {
+    a: f64,
+    e: f64,
+    n: f64,
+    f: f64,
+    rho0: f64,
+}
+
+impl LambertConformalConic {
+    fn new(ellipsoid: &Ellipsoid, latitude_of_origin: f64, sp1: f64, sp2: f64, scale_factor: f64) -> LambertConformalConic {
+        let e2 = ellipsoid.e2();
+        let e = e2.sqrt();
+        let (phi1, phi2) = (sp1.to_radians(), sp2.to_radians());
+        let (m1, m2) = (msfn(e2, phi1), msfn(e2, phi2));
+        let (t1, t2) = (tsfn(e, phi1), tsfn(e, phi2));
+        let n = if (phi1 - phi2).abs() > 1.0e-10 {
+            (m1.ln() - m2.ln()) / (t1.ln() - t2.ln())
+        } else {
+            phi1.sin()
+        };
+        let f = m1 / (n * t1.powf(n));
+        let a = ellipsoid.a * scale_factor;
+        let t0 = tsfn(e, latitude_of_origin.to_radians());
+        LambertConformalConic { a: a, e: e, n: n, f: f, rho0: a * f * t0.powf(n) }
+    }
+
+    fn forward(&self, lon: f64, lat: f64) -> (f64, f64) {
+        let rho = if (lat.abs() - PI / 2f64).abs() < 1.0e-10 {
+            if lat * self.n > 0f64 { 0f64 } else { return (f64::NAN, f64::NAN); }
+        } else {
+            self.a * self.f * tsfn(self.e, lat).powf(self.n)
+        };
+        let theta = self.n * lon;
+        (rho * theta.sin(), self.rho0 - rho * theta.cos())
+    }
+
+    fn inverse(&self, x: f64, y: f64) -> (f64, f64) {
+        let sign = self.n.signum();
+        let dy = self.rho0 - y;
+        let rho = sign * (x * x + dy * dy).sqrt();
+        let theta = (sign * x).atan2(sign * dy);
+        let lon = theta / self.n;
+        if rho == 0f64 {
+            return (lon, sign * PI / 2f64);
+        }
+        let ts = (rho / (self.a * self.f)).powf(1f64 / self.n);
+        (lon, phi2(self.e, ts))
+    }
+}
+
+struct AlbersEqualArea {
+    a: f64,
+    e: f64,
+    n: f64,
+    c: f64,
+    rho0: f64,
+}
+
+impl AlbersEqualArea {
+    fn new(ellipsoid: &Ellipsoid, latitude_of_origin: f64, sp1: f64, sp2: f64) -> AlbersEqualArea {
+        let e2 = ellipsoid.e2();
+        let e = e2.sqrt();
+        let (phi1, phi2) = (sp1.to_radians(), sp2.to_radians());
+        let (m1, m2) = (msfn(e2, phi1), msfn(e2, phi2));
+        let (q1, q2) = (qsfn(e, phi1), qsfn(e, phi2));
+        let n = if (phi1 - phi2).abs() > 1.0e-10 {
+            (m1 * m1 - m2 * m2) / (q2 - q1)
+        } else {
+            phi1.sin()
+        };
+        let c = m1 * m1 + n * q1;
+        let a = ellipsoid.a;
+        let q0 = qsfn(e, latitude_of_origin.to_radians());
+        AlbersEqualArea { a: a, e: e, n: n, c: c, rho0: a * (c - n * q0).max(0f64).sqrt() / n }
+    }
+
+    fn forward(&self, lon: f64, lat: f64) -> (f64, f64) {
+        let q = qsfn(self.e, lat);
+        let rho = self.a * (self.c - self.n * q).max(0f64).sqrt() / self.n;
+        let theta = self.n * lon;
+        (rho * theta.sin(), self.rho0 - rho * theta.cos())
+    }
+
+    fn inverse(&self, x: f64, y: f64) -> (f64, f64) {
+        let sign = self.n.signum();
+        let dy = self.rho0 - y;
+        let rho = (x * x + dy * dy).sqrt();
+        let theta = (sign * x).atan2(sign * dy);
+        let lon = theta / self.n;
+        let q = (self.c - rho * rho * self.n * self.n / (self.a * self.a)) / self.n;
+        let e = self.e;
+        let e2 = e * e;
+        // the latitude of the authalic q, by iteration (Snyder eq. 3-16)
+        let limit = 1f64 - (1f64 - e2) / (2f64 * e) * ((1f64 - e) / (1f64 + e)).ln();
+        if (q.abs() - limit).abs() < 1.0e-10 {
+            return (lon, q.signum() * PI / 2f64);
+        }
+        let mut lat = (q / 2f64).max(-1f64).min(1f64).asin();
+        for _ in 0..30 {
+            let sin_lat = lat.sin();
+            let es2 = e2 * sin_lat * sin_lat;
+            let delta = (1f64 - es2) * (1f64 - es2) / (2f64 * lat.cos()) *
+                (q / (1f64 - e2) - sin_lat / (1f64 - es2) + (1f64 / (2f64 * e)) * ((1f64 - e * sin_lat) / (1f64 + e * sin_lat)).ln());
+            lat += delta;
+            if delta.abs() < 1.0e-14 {
+                break;
+            }
+        }
+        (lon, lat)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dms(d: f64, m: f64, s: f64) -> f64 {
+        d.signum() * (d.abs() + m / 60f64 + s / 3600f64)
+    }
+
+    fn assert_projects_to(projection: &Projection, ellipsoid: &Ellipsoid, lon: f64, lat: f64, x: f64, y: f64, tolerance: f64) {
+        let (px, py) = projection.forward(ellipsoid, lon.to_radians(), lat.to_radians());
+        assert!((px - x).abs() < tolerance && (py - y).abs() < tolerance,
+            "{} projected ({}, {}) to ({}, {}) rather than ({}, {})", projection.name(), lon, lat, px, py, x, y);
+        let (plon, plat) = projection.inverse(ellipsoid, x, y);
+        assert!((plon.to_degrees() - lon).abs() < 1.0e-7 && (plat.to_degrees() - lat).abs() < 1.0e-7);
+    }
+
+    #[test]
+    fn transverse_mercator_matches_the_ordnance_survey_example() {
+        // Ordnance Survey, A guide to coordinate systems in Great Britain, annex C
+        let projection = Projection::TransverseMercator {
+            latitude_of_origin: 49f64, central_meridian: -2f64, scale_factor: 0.9996012717,
+            false_easting: 400_000f64, false_northing: -100_000f64,
+        };
+        assert_projects_to(&projection, &Ellipsoid::airy1830(), dms(1.0, 43.0, 4.5177), dms(52.0, 39.0, 27.2531),
+            651_409.903, 313_177.270, 1.0e-3);
+    }
+
+    #[test]
+    fn utm_coordinates_are_on_the_central_meridian_and_equator() {
+        let projection = Projection::utm(17, false);
+        let (x, y) = projection.forward(&Ellipsoid::wgs84(), -81f64.to_radians(), 0f64);
+        assert!((x - 500_000f64).abs() < 1.0e-6 && y.abs() < 1.0e-6);
+        assert_projects_to(&projection, &Ellipsoid::wgs84(), -81.0, 43.0, 500_000.0, 4_760_814.796, 1.0e-3);
+        let south = Projection::utm(17, true);
+        let (_, y) = south.forward(&Ellipsoid::wgs84(), -81f64.to_radians(), 0f64);
+        assert!((y - 10_000_000f64).abs() < 1.0e-3);
+    }
+
+    #[test]
+    fn lambert_conformal_conic_matches_the_epsg_example() {
+        // EPSG Guidance Note 7-2, NAD27 / Texas South Central, in US survey feet
+        let us_foot = 0.3048006096012192;
+        let projection = Projection::LambertConformalConic {
+            latitude_of_origin: dms(27.0, 50.0, 0.0), central_meridian: -99f64,
+            standard_parallel_1: dms(28.0, 23.0, 0.0), standard_parallel_2: dms(30.0, 17.0, 0.0),
+            scale_factor: 1f64, false_easting: 2_000_000f64 * us_foot, false_northing: 0f64,
+        };
+        assert_eq!(projection.name(), "Lambert_Conformal_Conic_2SP");
+        assert_projects_to(&projection, &Ellipsoid::clarke1866(), -96.0, 28.5,
+            2_963_503.91 * us_foot, 254_759.80 * us_foot, 0.01);
+    }
+
+    #[test]
+    fn mercator_matches_the_epsg_example() {
+        // EPSG Guidance Note 7-2, Batavia / NEIEZ, on the Bessel 1841 ellipsoid
+        let projection = Projection::Mercator {
+            central_meridian: 110f64, scale_factor: 0.997, false_easting: 3_900_000f64, false_northing: 900_000f64,
+        };
+        let bessel = Ellipsoid::new("Bessel 1841", 6_377_397.155, 299.1528128);
+        assert_projects_to(&projection, &bessel, 120.0, -3.0, 5_009_726.58, 569_150.82, 0.01);
+        let (_, y) = projection.forward(&bessel, 0f64, PI / 2f64);
+        assert!(y.is_nan());
+    }
+
+    #[test]
+    fn web_mercator_spans_the_world() {
+        let half_world = 20_037_508.342789244;
+        let max_lat = (2f64 * PI.exp().atan() - PI / 2f64).to_degrees();
+        assert_projects_to(&Projection::WebMercator, &Ellipsoid::wgs84(), 180.0, max_lat, half_world, half_world, 1.0e-6);
+        assert_projects_to(&Projection::WebMercator, &Ellipsoid::wgs84(), -180.0, -max_lat, -half_world, -half_world, 1.0e-6);
+    }
+
+    #[test]
+    fn albers_origin_is_at_the_false_origin() {
+        let projection = Projection::AlbersEqualArea {
+            latitude_of_origin: 23f64, central_meridian: -96f64, standard_parallel_1: 29.5, standard_parallel_2: 45.5,
+            false_easting: 1_000f64, false_northing: -2_000f64,
+        };
+        assert_projects_to(&projection, &Ellipsoid::grs80(), -96.0, 23.0, 1_000.0, -2_000.0, 1.0e-6);
+    }
+
+    #[test]
+    fn projections_are_inverted() {
+        let ellipsoid = Ellipsoid::grs80();
+        let projections = vec![
+            Projection::Geographic,
+            Projection::utm(17, false),
+            Projection::TransverseMercator { latitude_of_origin: 49f64, central_meridian: -2f64, scale_factor: 0.9996,
+                false_easting: 400_000f64, false_northing: -100_000f64 },
+            Projection::LambertConformalConic { latitude_of_origin: 49f64, central_meridian: -95f64, standard_parallel_1: 49f64,
+                standard_parallel_2: 77f64, scale_factor: 1f64, false_easting: 0f64, false_northing: 0f64 },
+            Projection::LambertConformalConic { latitude_of_origin: 45f64, central_meridian: -90f64, standard_parallel_1: 45f64,
+                standard_parallel_2: 45f64, scale_factor: 0.9999, false_easting: 100f64, false_northing: 200f64 },
+            Projection::LambertConformalConic { latitude_of_origin: -32f64, central_meridian: 135f64, standard_parallel_1: -28f64,
+                standard_parallel_2: -36f64, scale_factor: 1f64, false_easting: 0f64, false_northing: 0f64 },
+            Projection::AlbersEqualArea { latitude_of_origin: 23f64, central_meridian: -96f64, standard_parallel_1: 29.5,
+                standard_parallel_2: 45.5, false_easting: 0f64, false_northing: 0f64 },
+            Projection::AlbersEqualArea { latitude_of_origin: 0f64, central_meridian: 132f64, standard_parallel_1: -18f64,
+                standard_parallel_2: -36f64, false_easting: 0f64, false_northing: 0f64 },
+            Projection::Mercator { central_meridian: 0f64, scale_factor: 1f64, false_easting: 0f64, false_northing: 0f64 },
+            Projection::WebMercator,
+        ];
+        for projection in &projections {
+            let (lon0, lat0) = match *projection {
+                Projection::TransverseMercator { central_meridian, latitude_of_origin, .. } => (central_meridian, latitude_of_origin),
+                Projection::LambertConformalConic { central_meridian, latitude_of_origin, .. } => (central_meridian, latitude_of_origin),
+                Projection::AlbersEqualArea { central_meridian, standard_parallel_1, .. } => (central_meridian, standard_parallel_1),
+                _ => (0f64, 0f64),
+            };
+            for i in 0..9 {
+                for j in 0..9 {
+                    let lon = (lon0 + i as f64 * 1.5 - 6f64).to_radians();
+                    let lat = (lat0 + j as f64 * 1.25 - 5f64).to_radians();
+                    let (x, y) = projection.forward(&ellipsoid, lon, lat);
+                    let (lon2, lat2) = projection.inverse(&ellipsoid, x, y);
+                    assert!((lon2 - lon).abs() < 1.0e-10 && (lat2 - lat).abs() < 1.0e-10,
+                        "{:?} did not invert ({}, {})", projection, lon.to_degrees(), lat.to_degrees());
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn equivalent_projections_allow_for_rounding() {
+        let a = Projection::utm(17, false);
+        let b = Projection::TransverseMercator { latitude_of_origin: 0f64, central_meridian: -81.0000000000001,
+            scale_factor: 0.9996, false_easting: 500_000.0001, false_northing: 0f64 };
+        assert!(a.is_equivalent(&b));
+        assert!(!a.is_equivalent(&Projection::utm(18, false)));
+        assert!(!a.is_equivalent(&Projection::utm(17, true)));
+        assert!(!Projection::WebMercator.is_equivalent(&Projection::Mercator { central_meridian: 0f64,
+            scale_factor: 1f64, false_easting: 0f64, false_northing: 0f64 }));
+    }
+}
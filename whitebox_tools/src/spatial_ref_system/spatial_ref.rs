@@ -0,0 +1,351 @@
+/*
+This code is part of the WhiteboxTools geospatial analysis library.
+Authors: Dr. John Lindsay
+Created: March 29, 2018
+Last Modified: April 6, 2018
+License: MIT
+*/
+
+use std::f64;
+use std::fmt;
+use std::io::{Error, ErrorKind};
+use std::path::Path;
+use super::datum::Datum;
+use super::projections::Projection;
+use raster::geotiff::geokeys::GeoKeys;
+use super::geotiff_keys::GeoKeyValue;
+use super::{epsg, geotiff_keys, wkt};
+
+/// A coordinate reference system, i.e. a datum, a map projection, and the linear units
+/// of the projected coordinates.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SpatialRef {
+    pub name: String,
+    /// The EPSG code of the coordinate reference system, or zero if it is not known.
+    pub epsg_code: u16,
+    pub datum: Datum,
+    pub projection: Projection,
+    pub linear_unit_name: String,
+    /// The length of the linear unit, in metres. Geographic coordinates are in degrees.
+    pub linear_unit: f64,
+}
+
+impl SpatialRef {
+    /// Creates a geographic coordinate reference system on a datum.
+    pub fn geographic(datum: Datum) -> SpatialRef {
+        SpatialRef {
+            name: datum.geographic_name.clone(),
+            epsg_code: datum.geographic_epsg_code,
+            datum: datum,
+            projection: Projection::Geographic,
+            linear_unit_name: "degree".to_string(),
+            linear_unit: 1f64,
+        }
+    }
+
+    /// Creates a projected coordinate reference system with coordinates in metres.
+    pub fn projected(name: &str, epsg_code: u16, datum: Datum, projection: Projection) -> SpatialRef {
+        SpatialRef {
+            name: name.to_string(),
+            epsg_code: epsg_code,
+            datum: datum,
+            projection: projection,
+            linear_unit_name: "metre".to_string(),
+            linear_unit: 1f64,
+        }
+    }
+
+    /// Returns the coordinate reference system with an EPSG code, e.g. 32617 for
+    /// WGS 84 / UTM zone 17N.
+    pub fn from_epsg(code: u16) -> Result<SpatialRef, Error> {
+        epsg::from_epsg(code)
+    }
+
+    /// Parses an OGC or ESRI well-known text (WKT) coordinate reference system, e.g. the
+    /// contents of a .prj file.
+    pub fn from_wkt(wkt: &str) -> Result<SpatialRef, Error> {
+        wkt::from_wkt(wkt)
+    }
+
+    /// Reads the coordinate reference system described by the GeoKeys of a GeoTIFF or LAS file.
+    pub fn from_geokeys(geokeys: &GeoKeys) -> Result<SpatialRef, Error> {
+        geotiff_keys::from_geokeys(geokeys)
+    }
+
+    /// Returns the coordinate reference system described by an EPSG code or, failing that,
+    /// a WKT string, such as those of `RasterConfigs`. Returns None if neither is usable.
+    pub fn from_epsg_or_wkt(epsg_code: u16, wkt: &str) -> Option<SpatialRef> {
+        if epsg_code != 0 {
+            if let Ok(srs) = SpatialRef::from_epsg(epsg_code) {
+                return Some(srs);
+            }
+        }
+        let wkt = wkt.trim();
+        if wkt.is_empty() || wkt.to_lowercase() == "not specified" {
+            return None;
+        }
+        SpatialRef::from_wkt(wkt).ok()
+    }
+
+    /// Parses a coordinate reference system given by a user, which may be an EPSG code
+    /// (e.g. `32617` or `EPSG:32617`), WKT, a .prj file, or a raster, vector, or LiDAR
+    /// file whose coordinate reference system is used.
+    pub fn from_user_input(value: &str, working_directory: &str) -> Result<SpatialRef, Error> {
+        let mut value = value.trim();
+        // a quoted value, e.g. a WKT string that is enclosed in quotes
+        for quote in &['"', '\''] {
+            if value.len() > 1 && value.starts_with(*quote) && value.ends_with(*quote) {
+                value = value[1..(value.len() - 1)].trim();
+            }
+        }
+        let lower = value.to_lowercase();
+        let code = if lower.starts_with("epsg:") { &value[5..] } else { value };
+        if let Ok(code) = code.trim().parse::<u32>() {
+            if code > u16::max_value() as u32 {
+                return Err(Error::new(ErrorKind::InvalidInput, format!("EPSG:{} is not a supported coordinate reference system.", code)));
+            }
+            return SpatialRef::from_epsg(code as u16);
+        }
+        if value.contains("[") {
+            return SpatialRef::from_wkt(value);
+        }
+        let file_name = if !value.contains(&::std::path::MAIN_SEPARATOR.to_string()) && !value.contains("/") {
+            format!("{}{}", working_directory, value)
+        } else {
+            value.to_string()
+        };
+        if !Path::new(&file_name).is_file() {
+            return Err(Error::new(ErrorKind::InvalidInput,
+                format!("'{}' is not an EPSG code, WKT, or an existing file.", value)));
+        }
+        if file_name.to_lowercase().ends_with(".prj") {
+            return super::read_prj(&file_name);
+        }
+        match super::read_spatial_ref(&file_name)? {
+            Some(srs) => Ok(srs),
+            None => Err(Error::new(ErrorKind::InvalidInput,
+                format!("The file {} does not specify a coordinate reference system.", file_name))),
+        }
+    }
+
+    pub fn is_geographic(&self) -> bool {
+        self.projection == Projection::Geographic
+    }
+
+    /// Returns true if coordinates in the two systems are the same, i.e. they do not need
+    /// to be transformed from one to the other, regardless of their names.
+    pub fn is_equivalent(&self, other: &SpatialRef) -> bool {
+        if self.epsg_code != 0 && self.epsg_code == other.epsg_code {
+            return true;
+        }
+        self.datum.is_equivalent(&other.datum) &&
+            self.projection.is_equivalent(&other.projection) &&
+            (self.is_geographic() || (self.linear_unit - other.linear_unit).abs() < 1.0e-9)
+    }
+
+    /// Returns the coordinate reference system as OGC WKT.
+    pub fn to_wkt(&self) -> String {
+        wkt::to_wkt(self)
+    }
+
+    /// Describes the coordinate reference system with GeoKeys, in the order of their key IDs.
+    pub fn to_geokeys(&self) -> Vec<(u16, GeoKeyValue)> {
+        geotiff_keys::to_geokeys(self)
+    }
+
+    /// Converts coordinates in this system to geodetic longitude and latitude on its
+    /// datum, in radians.
+    pub fn to_geodetic(&self, x: f64, y: f64) -> (f64, f64) {
+        if self.is_geographic() {
+            return (x.to_radians(), y.to_radians());
+        }
+        self.projection.inverse(&self.datum.ellipsoid, x * self.linear_unit, y * self.linear_unit)
+    }
+
+    /// Converts geodetic longitude and latitude on the datum, in radians, to coordinates
+    /// in this system.
+    pub fn from_geodetic(&self, lon: f64, lat: f64) -> (f64, f64) {
+        if self.is_geographic() {
+            return (lon.to_degrees(), lat.to_degrees());
+        }
+        let (x, y) = self.projection.forward(&self.datum.ellipsoid, lon, lat);
+        (x / self.linear_unit, y / self.linear_unit)
+    }
+}
+
+impl fmt::Display for SpatialRef {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.epsg_code != 0 {
+            write!(f, "{} (EPSG:{})", self.name, self.epsg_code)
+        } else {
+            write!(f, "{}", self.name)
+        }
+    }
+}
+
+/// Transforms coordinates from one coordinate reference system to another, shifting them
+/// between datums if necessary.
+///
+/// ```ignore
+/// let t = Transformation::new(&SpatialRef::from_epsg(4326)?, &SpatialRef::from_epsg(32617)?);
+/// let (x, y) = t.transform(-81.0, 43.0); // 500000.0, 4760814.8
+/// ```
+#[derive(Clone, Debug)]
+pub struct Transformation {
+    source: SpatialRef,
+    destination: SpatialRef,
+    identity: bool,
+    shift_datum: bool,
+}
+
+impl Transformation {
+    pub fn new(source: &SpatialRef, destination: &SpatialRef) -> Transformation {
+        Transformation {
+            source: source.clone(),
+            destination: destination.clone(),
+            identity: source.is_equivalent(destination),
+            shift_datum: !source.datum.is_equivalent(&destination.datum),
+        }
+    }
+
+    /// Returns true if the two coordinate reference systems are equivalent, in which case
+    /// coordinates are not changed by the transformation.
+    pub fn is_identity(&self) -> bool {
+        self.identity
+    }
+
+    /// Transforms a point. The coordinates of points that cannot be transformed, e.g. that
+    /// lie outside of the domain of the destination projection, are NaN.
+    pub fn transform(&self, x: f64, y: f64) -> (f64, f64) {
+        if self.identity {
+            return (x, y);
+        }
+        let (mut lon, mut lat) = self.source.to_geodetic(x, y);
+        if !lon.is_finite() || !lat.is_finite() {
+            return (f64::NAN, f64::NAN);
+        }
+        if self.shift_datum {
+            let (gx, gy, gz) = self.source.datum.to_wgs84_geocentric(lon, lat, 0f64);
+            let (lon2, lat2, _) = self.destination.datum.from_wgs84_geocentric(gx, gy, gz);
+            lon = lon2;
+            lat = lat2;
+        }
+        let (x2, y2) = self.destination.from_geodetic(lon, lat);
+        if !x2.is_finite() || !y2.is_finite() {
+            return (f64::NAN, f64::NAN);
+        }
+        (x2, y2)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::fs;
+    use std::path::MAIN_SEPARATOR;
+    use super::*;
+
+    fn distance(a: (f64, f64), b: (f64, f64)) -> f64 {
+        ((a.0 - b.0) * (a.0 - b.0) + (a.1 - b.1) * (a.1 - b.1)).sqrt()
+    }
+
+    #[test]
+    fn geographic_coordinates_are_projected() {
+        let t = Transformation::new(&SpatialRef::from_epsg(4326).unwrap(), &SpatialRef::from_epsg(32617).unwrap());
+        assert!(!t.is_identity());
+        assert!(distance(t.transform(-81.0, 43.0), (500_000.0, 4_760_814.796)) < 1.0e-3);
+        // Web Mercator cannot represent the poles
+        let t = Transformation::new(&SpatialRef::from_epsg(4326).unwrap(), &SpatialRef::from_epsg(3857).unwrap());
+        assert!(t.transform(0.0, 90.0).0.is_nan());
+    }
+
+    #[test]
+    fn equivalent_systems_are_not_transformed() {
+        let nad83 = SpatialRef::from_epsg(26917).unwrap();
+        let t = Transformation::new(&nad83, &SpatialRef::from_wkt(&nad83.to_wkt()).unwrap());
+        assert!(t.is_identity());
+        assert_eq!(t.transform(1.5, 2.5), (1.5, 2.5));
+        // NAD83 and WGS 84 are not shifted, although their ellipsoids differ very slightly
+        let t = Transformation::new(&nad83, &SpatialRef::from_epsg(32617).unwrap());
+        assert!(distance(t.transform(560_000.0, 4_820_000.0), (560_000.0, 4_820_000.0)) < 1.0e-3);
+        let t = Transformation::new(&nad83, &SpatialRef::from_epsg(32618).unwrap());
+        assert!(!t.is_identity());
+    }
+
+    #[test]
+    fn datums_are_shifted() {
+        // NAD27 coordinates in southern Ontario are roughly 200 m south of those of NAD83
+        let nad27 = SpatialRef::from_epsg(26717).unwrap();
+        let nad83 = SpatialRef::from_epsg(26917).unwrap();
+        let forward = Transformation::new(&nad27, &nad83);
+        let backward = Transformation::new(&nad83, &nad27);
+        let p = (560_000.0, 4_820_000.0);
+        let q = forward.transform(p.0, p.1);
+        assert!(q.1 - p.1 > 150.0 && q.1 - p.1 < 250.0 && (q.0 - p.0).abs() < 50.0, "{:?}", q);
+        assert!(distance(backward.transform(q.0, q.1), p) < 1.0e-3);
+
+        // the seven parameter shift of the British National Grid
+        let bng = SpatialRef::from_epsg(27700).unwrap();
+        let wgs84 = SpatialRef::from_epsg(4326).unwrap();
+        let (lon, lat) = Transformation::new(&bng, &wgs84).transform(651_409.903, 313_177.270);
+        let (x, y) = Transformation::new(&wgs84, &bng).transform(lon, lat);
+        // the ellipsoidal heights of the shifted points are discarded, which moves them by about a millimetre
+        assert!(distance((x, y), (651_409.903, 313_177.270)) < 5.0e-3);
+        // the WGS 84 coordinates of the OSGB36 position of the Ordnance Survey example are about 100 m away
+        let (x, y) = Transformation::new(&wgs84, &bng).transform(1.71792158333, 52.65757030555);
+        let shift = distance((x, y), (651_409.903, 313_177.270));
+        assert!(shift > 50.0 && shift < 150.0, "{}", shift);
+    }
+
+    #[test]
+    fn coordinates_are_converted_between_linear_units() {
+        let metres = SpatialRef::from_epsg(26917).unwrap();
+        let mut feet = metres.clone();
+        feet.epsg_code = 0;
+        feet.linear_unit_name = "foot".to_string();
+        feet.linear_unit = 0.3048;
+        assert!(!metres.is_equivalent(&feet));
+        let (x, y) = Transformation::new(&metres, &feet).transform(500_000.0, 4_000_000.0);
+        assert!(distance((x, y), (500_000.0 / 0.3048, 4_000_000.0 / 0.3048)) < 1.0e-3);
+    }
+
+    #[test]
+    fn user_input_is_parsed() {
+        let utm = SpatialRef::from_epsg(26917).unwrap();
+        for value in ["26917", "EPSG:26917", "epsg:26917", " 26917 ", "'EPSG:26917'"].iter() {
+            assert_eq!(SpatialRef::from_user_input(value, "").unwrap(), utm);
+        }
+        let wkt = utm.to_wkt();
+        assert_eq!(SpatialRef::from_user_input(&wkt, "").unwrap(), utm);
+        assert_eq!(SpatialRef::from_user_input(&format!("\"{}\"", wkt), "").unwrap(), utm);
+
+        let directory = format!("{}{}", env::temp_dir().to_str().unwrap(), MAIN_SEPARATOR);
+        let prj = format!("{}wbt_user_input.prj", directory);
+        fs::write(&prj, &wkt).unwrap();
+        assert_eq!(SpatialRef::from_user_input("wbt_user_input.prj", &directory).unwrap(), utm);
+        assert_eq!(SpatialRef::from_user_input(&prj, "").unwrap(), utm);
+        // the .prj file of a shapefile describes its system
+        let shp = format!("{}wbt_user_input.shp", directory);
+        fs::write(&shp, "").unwrap();
+        assert_eq!(super::super::read_spatial_ref(&shp).unwrap(), Some(utm.clone()));
+        let _ = fs::remove_file(&prj);
+        assert_eq!(super::super::read_spatial_ref(&shp).unwrap(), None);
+        assert!(SpatialRef::from_user_input(&shp, "").is_err());
+        let _ = fs::remove_file(&shp);
+
+        for value in ["99999", "EPSG:1234567", "wbt_no_such_file.tif", ""].iter() {
+            assert_eq!(SpatialRef::from_user_input(value, &directory).unwrap_err().kind(), ErrorKind::InvalidInput, "{}", value);
+        }
+    }
+
+    #[test]
+    fn systems_are_taken_from_an_epsg_code_or_wkt() {
+        let utm = SpatialRef::from_epsg(26917).unwrap();
+        assert_eq!(SpatialRef::from_epsg_or_wkt(26917, ""), Some(utm.clone()));
+        assert_eq!(SpatialRef::from_epsg_or_wkt(0, &utm.to_wkt()), Some(utm.clone()));
+        assert_eq!(SpatialRef::from_epsg_or_wkt(1, &utm.to_wkt()), Some(utm.clone()));
+        assert_eq!(SpatialRef::from_epsg_or_wkt(0, "not specified"), None);
+        assert_eq!(SpatialRef::from_epsg_or_wkt(0, ""), None);
+        assert_eq!(format!("{}", utm), "NAD83 / UTM zone 17N (EPSG:26917)");
+    }
+}
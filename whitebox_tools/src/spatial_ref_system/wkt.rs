@@ -0,0 +1,497 @@
+/*
+This code is part of the WhiteboxTools geospatial analysis library.
+Authors: Dr. John Lindsay
+Created: March 29, 2018
+Last Modified: March 29, 2018
+License: MIT
+
+Notes: Coordinate reference systems are read from the OGC WKT of the OGC 01-009
+specification, which is used in LAS VLRs and GeoTIFF citations, and from the ESRI dialect
+of .prj files, which differs mostly in its projection and parameter names. The keywords of
+WKT2 (ISO 19162) are accepted for the parts that are common to both. A system that has a
+supported EPSG authority code is taken from the EPSG table rather than its WKT, since some
+writers describe e.g. Web Mercator as an ellipsoidal Mercator projection.
+*/
+
+use std::f64;
+use std::io::{Error, ErrorKind};
+use super::datum::{Datum, Ellipsoid};
+use super::epsg;
+use super::projections::Projection;
+use super::spatial_ref::SpatialRef;
+
+#[derive(Clone, Debug)]
+enum WktValue {
+    Node(WktNode),
+    Text(String),
+    Number(f64),
+}
+
+#[derive(Clone, Debug)]
+struct WktNode {
+    keyword: String,
+    values: Vec<WktValue>,
+}
+
+impl WktNode {
+    fn is(&self, keywords: &[&str]) -> bool {
+        keywords.iter().any(|k| self.keyword.eq_ignore_ascii_case(k))
+    }
+
+    // the first child node with one of the keywords
+    fn child(&self, keywords: &[&str]) -> Option<&WktNode> {
+        self.children(keywords).into_iter().next()
+    }
+
+    fn children(&self, keywords: &[&str]) -> Vec<&WktNode> {
+        self.values.iter().filter_map(|v| match *v {
+            WktValue::Node(ref n) if n.is(keywords) => Some(n),
+            _ => None,
+        }).collect()
+    }
+
+    // the first node with one of the keywords, searching depth first
+    fn find(&self, keywords: &[&str]) -> Option<&WktNode> {
+        for v in &self.values {
+            if let WktValue::Node(ref n) = *v {
+                if n.is(keywords) {
+                    return Some(n);
+                }
+                if let Some(found) = n.find(keywords) {
+                    return Some(found);
+                }
+            }
+        }
+        None
+    }
+
+    fn text(&self, index: usize) -> Option<&str> {
+        match self.values.get(index) {
+            Some(&WktValue::Text(ref s)) => Some(s),
+            _ => None,
+        }
+    }
+
+    fn name(&self) -> String {
+        self.text(0).unwrap_or("").to_string()
+    }
+
+    fn number(&self, index: usize) -> Option<f64> {
+        match self.values.get(index) {
+            Some(&WktValue::Number(v)) => Some(v),
+            Some(&WktValue::Text(ref s)) => s.trim().parse::<f64>().ok(),
+            _ => None,
+        }
+    }
+
+    fn numbers(&self) -> Vec<f64> {
+        (0..self.values.len()).filter_map(|i| self.number(i)).collect()
+    }
+
+    // the EPSG code of an AUTHORITY or ID clause
+    fn epsg_code(&self) -> Option<u16> {
+        let authority = self.child(&["AUTHORITY", "ID"])?;
+        if !authority.name().eq_ignore_ascii_case("epsg") {
+            return None;
+        }
+        authority.number(1).and_then(|v| if v > 0f64 && v <= u16::max_value() as f64 { Some(v as u16) } else { None })
+    }
+}
+
+fn invalid(message: &str) -> Error {
+    Error::new(ErrorKind::InvalidData, format!("Error parsing WKT: {}", message))
+}
+
+struct Parser<'a> {
+    chars: Vec<char>,
+    pos: usize,
+    source: &'a str,
+}
+
+impl<'a> Parser<'a> {
+    fn skip_whitespace(&mut self) {
+        while self.pos < self.chars.len() && self.chars[self.pos].is_whitespace() {
+            self.pos += 1;
+        }
+    }
+
+    fn node(&mut self) -> Result<WktNode, Error> {
+        self.skip_whitespace();
+        let start = self.pos;
+        while self.pos < self.chars.len() && (self.chars[self.pos].is_alphanumeric() || self.chars[self.pos] == '_') {
+            self.pos += 1;
+        }
+        let keyword: String = self.chars[start..self.pos].iter().collect();
+        if keyword.is_empty() {
+            return Err(invalid(&format!("expected a keyword at position {} of '{}'", start, self.source)));
+        }
+        self.skip_whitespace();
+        let mut values = vec![];
+        if self.pos < self.chars.len() && (self.chars[self.pos] == '[' || self.chars[self.pos] == '(') {
+            let close = if self.chars[self.pos] == '[' { ']' } else { ')' };
+            self.pos += 1;
+            loop {
+                self.skip_whitespace();
+                if self.pos >= self.chars.len() {
+                    return Err(invalid("unexpected end of text"));
+                }
+                let c = self.chars[self.pos];
+                if c == close {
+                    self.pos += 1;
+                    break;
+                } else if c == ',' {
+                    self.pos += 1;
+                } else if c == '"' {
+                    self.pos += 1;
+                    let mut s = String::new();
+                    while self.pos < self.chars.len() {
+                        if self.chars[self.pos] == '"' {
+                            // a doubled quote is an escaped quote
+                            if self.pos + 1 < self.chars.len() && self.chars[self.pos + 1] == '"' {
+                                s.push('"');
+                                self.pos += 2;
+                                continue;
+                            }
+                            break;
+                        }
+                        s.push(self.chars[self.pos]);
+                        self.pos += 1;
+                    }
+                    self.pos += 1;
+                    values.push(WktValue::Text(s));
+                } else if c == '-' || c == '+' || c == '.' || c.is_digit(10) {
+                    let start = self.pos;
+                    while self.pos < self.chars.len() && (self.chars[self.pos].is_alphanumeric() || "+-.".contains(self.chars[self.pos])) {
+                        self.pos += 1;
+                    }
+                    let s: String = self.chars[start..self.pos].iter().collect();
+                    match s.parse::<f64>() {
+                        Ok(v) => values.push(WktValue::Number(v)),
+                        Err(_) => return Err(invalid(&format!("'{}' is not a number", s))),
+                    }
+                } else {
+                    let n = self.node()?;
+                    if n.values.is_empty() {
+                        // an enumerated value, e.g. the direction of an AXIS
+                        values.push(WktValue::Text(n.keyword));
+                    } else {
+                        values.push(WktValue::Node(n));
+                    }
+                }
+            }
+        }
+        Ok(WktNode { keyword: keyword, values: values })
+    }
+}
+
+fn parse(wkt: &str) -> Result<WktNode, Error> {
+    let mut parser = Parser { chars: wkt.trim().chars().collect(), pos: 0, source: wkt };
+    parser.node()
+}
+
+const PROJECTED_KEYWORDS: [&str; 3] = ["PROJCS", "PROJCRS", "PROJECTEDCRS"];
+const GEOGRAPHIC_KEYWORDS: [&str; 6] = ["GEOGCS", "GEOGCRS", "GEODCRS", "BASEGEOGCRS", "BASEGEODCRS", "GEOGRAPHICCRS"];
+
+/// Parses an OGC or ESRI WKT coordinate reference system.
+pub fn from_wkt(wkt: &str) -> Result<SpatialRef, Error> {
+    let root = parse(wkt)?;
+    let is_projected = root.is(&PROJECTED_KEYWORDS);
+    if !is_projected && !root.is(&GEOGRAPHIC_KEYWORDS) {
+        return Err(invalid(&format!("{} coordinate reference systems are not supported", root.keyword)));
+    }
+    let code = root.epsg_code();
+    if let Some(code) = code {
+        if let Ok(srs) = epsg::from_epsg(code) {
+            return Ok(srs);
+        }
+    }
+
+    let geogcs = if is_projected {
+        match root.child(&GEOGRAPHIC_KEYWORDS) {
+            Some(n) => n,
+            None => return Err(invalid("the projected system does not have a geographic system")),
+        }
+    } else {
+        &root
+    };
+    let datum = read_datum(geogcs)?;
+    // the angular unit, in degrees
+    let angular_unit = match geogcs.child(&["UNIT", "ANGLEUNIT"]).and_then(|u| u.number(1)) {
+        Some(v) if v > 0f64 => v.to_degrees(),
+        _ => 1f64,
+    };
+
+    if !is_projected {
+        let mut srs = SpatialRef::geographic(datum);
+        srs.name = root.name();
+        srs.epsg_code = code.unwrap_or(0);
+        return Ok(srs);
+    }
+
+    let (linear_unit_name, linear_unit) = match root.child(&["UNIT", "LENGTHUNIT"]) {
+        Some(u) => (u.name(), u.number(1).unwrap_or(1f64)),
+        None => ("metre".to_string(), 1f64),
+    };
+    let method = match root.find(&["PROJECTION", "METHOD"]) {
+        Some(n) => n.name(),
+        None => return Err(invalid("the projected system does not have a projection")),
+    };
+    let mut parameters = vec![];
+    collect_parameters(&root, &mut parameters);
+    let param = |names: &[&str]| -> Option<f64> {
+        parameters.iter().find(|p| names.contains(&p.0.as_str())).map(|p| p.1)
+    };
+    let angle = |names: &[&str], default: f64| param(names).map(|v| v * angular_unit).unwrap_or(default);
+    let lat0 = angle(&["latitudeoforigin", "latitudeofnaturalorigin", "latitudeofcenter", "latitudeoffalseorigin",
+        "latitudeofprojectioncentre", "centrallatitude"], 0f64);
+    let lon0 = angle(&["centralmeridian", "longitudeofnaturalorigin", "longitudeofcenter", "longitudeoffalseorigin",
+        "longitudeoforigin", "longitudeofprojectioncentre"], 0f64);
+    let sp1 = angle(&["standardparallel1", "latitudeof1ststandardparallel"], lat0);
+    let sp2 = angle(&["standardparallel2", "latitudeof2ndstandardparallel"], sp1);
+    let k0 = param(&["scalefactor", "scalefactoratnaturalorigin"]).unwrap_or(1f64);
+    // false eastings and northings are kept in metres
+    let fe = param(&["falseeasting", "eastingatfalseorigin", "eastingofprojectioncentre"]).unwrap_or(0f64) * linear_unit;
+    let fn_ = param(&["falsenorthing", "northingatfalseorigin", "northingofprojectioncentre"]).unwrap_or(0f64) * linear_unit;
+
+    let m: String = method.to_lowercase().chars().filter(|c| c.is_alphanumeric()).collect();
+    let projection = if m.contains("pseudomercator") || m.contains("auxiliarysphere") || m.contains("webmercator") {
+        Projection::WebMercator
+    } else if m.contains("transversemercator") || m.contains("gausskruger") {
+        Projection::TransverseMercator {
+            latitude_of_origin: lat0, central_meridian: lon0, scale_factor: k0, false_easting: fe, false_northing: fn_,
+        }
+    } else if m.contains("lambertconformalconic") {
+        let one_sp = m.contains("1sp") || param(&["standardparallel1", "latitudeof1ststandardparallel"]).is_none();
+        Projection::LambertConformalConic {
+            latitude_of_origin: lat0,
+            central_meridian: lon0,
+            standard_parallel_1: if one_sp { lat0 } else { sp1 },
+            standard_parallel_2: if one_sp { lat0 } else { sp2 },
+            scale_factor: if one_sp { k0 } else { 1f64 },
+            false_easting: fe,
+            false_northing: fn_,
+        }
+    } else if m.contains("albers") {
+        Projection::AlbersEqualArea {
+            latitude_of_origin: lat0, central_meridian: lon0, standard_parallel_1: sp1, standard_parallel_2: sp2,
+            false_easting: fe, false_northing: fn_,
+        }
+    } else if m.contains("mercator") {
+        // the two standard parallel variant has the scale factor of its standard parallel
+        let k0 = match param(&["standardparallel1", "latitudeof1ststandardparallel"]) {
+            Some(lat_ts) => {
+                let lat_ts = (lat_ts * angular_unit).to_radians();
+                let e2 = datum.ellipsoid.e2();
+                lat_ts.cos() / (1f64 - e2 * lat_ts.sin() * lat_ts.sin()).sqrt()
+            },
+            None => k0,
+        };
+        Projection::Mercator { central_meridian: lon0, scale_factor: k0, false_easting: fe, false_northing: fn_ }
+    } else {
+        return Err(invalid(&format!("the {} projection is not supported", method)));
+    };
+
+    Ok(SpatialRef {
+        name: root.name(),
+        epsg_code: code.unwrap_or(0),
+        datum: datum,
+        projection: projection,
+        linear_unit_name: linear_unit_name,
+        linear_unit: linear_unit,
+    })
+}
+
+fn read_datum(geogcs: &WktNode) -> Result<Datum, Error> {
+    let datum_node = match geogcs.child(&["DATUM", "GEODETICDATUM", "TRF"]) {
+        Some(n) => n,
+        None => return Err(invalid("the geographic system does not have a datum")),
+    };
+    let known = geogcs.epsg_code().and_then(Datum::from_geographic_epsg)
+        .or_else(|| Datum::from_name(&datum_node.name()));
+    let spheroid = datum_node.child(&["SPHEROID", "ELLIPSOID"]);
+    let mut datum = match (known, spheroid) {
+        (Some(d), _) => d,
+        (None, Some(s)) => {
+            let a = s.number(1).unwrap_or(0f64);
+            if a <= 0f64 {
+                return Err(invalid("the ellipsoid does not have a semi-major axis"));
+            }
+            let ellipsoid = Ellipsoid::new(&s.name(), a, s.number(2).unwrap_or(0f64));
+            Datum::new(&datum_node.name(), ellipsoid, [0f64; 7], &geogcs.name(), 0)
+        },
+        (None, None) => return Err(invalid("the datum does not have an ellipsoid")),
+    };
+    if let Some(t) = datum_node.child(&["TOWGS84"]) {
+        let p = t.numbers();
+        if p.len() >= 3 {
+            datum.to_wgs84 = [0f64; 7];
+            for i in 0..p.len().min(7) {
+                datum.to_wgs84[i] = p[i];
+            }
+        }
+    }
+    if !geogcs.name().is_empty() {
+        datum.geographic_name = geogcs.name();
+    }
+    Ok(datum)
+}
+
+// the PARAMETER clauses of a projection, with normalized names
+fn collect_parameters(node: &WktNode, parameters: &mut Vec<(String, f64)>) {
+    for v in &node.values {
+        if let WktValue::Node(ref n) = *v {
+            if n.is(&GEOGRAPHIC_KEYWORDS) {
+                continue;
+            }
+            if n.is(&["PARAMETER"]) {
+                if let Some(value) = n.number(1) {
+                    let name: String = n.name().to_lowercase().chars().filter(|c| c.is_alphanumeric()).collect();
+                    parameters.push((name, value));
+                }
+            } else {
+                collect_parameters(n, parameters);
+            }
+        }
+    }
+}
+
+fn format_number(v: f64) -> String {
+    let s = format!("{}", v);
+    if s.contains('.') || s.contains('e') { s } else { format!("{}.0", s) }
+}
+
+/// Writes a coordinate reference system as OGC WKT.
+pub fn to_wkt(srs: &SpatialRef) -> String {
+    let datum = &srs.datum;
+    let e = &datum.ellipsoid;
+    let mut datum_wkt = format!("DATUM[\"{}\",SPHEROID[\"{}\",{},{}]", datum.name, e.name, format_number(e.a), format_number(e.inverse_flattening));
+    if !datum.is_wgs84_equivalent() {
+        let p: Vec<String> = datum.to_wgs84.iter().map(|v| format_number(*v)).collect();
+        datum_wkt.push_str(&format!(",TOWGS84[{}]", p.join(",")));
+    }
+    datum_wkt.push(']');
+    let mut geogcs = format!("GEOGCS[\"{}\",{},PRIMEM[\"Greenwich\",0],UNIT[\"degree\",0.0174532925199433]", datum.geographic_name, datum_wkt);
+    if datum.geographic_epsg_code != 0 {
+        geogcs.push_str(&format!(",AUTHORITY[\"EPSG\",\"{}\"]", datum.geographic_epsg_code));
+    }
+    geogcs.push(']');
+    if srs.is_geographic() {
+        return geogcs;
+    }
+
+    let mut s = format!("PROJCS[\"{}\",{},PROJECTION[\"{}\"]", srs.name, geogcs, srs.projection.name());
+    for (name, value) in srs.projection.named_parameters() {
+        let value = if name.starts_with("false_") { value / srs.linear_unit } else { value };
+        s.push_str(&format!(",PARAMETER[\"{}\",{}]", name, format_number(value)));
+    }
+    s.push_str(&format!(",UNIT[\"{}\",{}]", srs.linear_unit_name, format_number(srs.linear_unit)));
+    if srs.projection == Projection::WebMercator {
+        s.push_str(",EXTENSION[\"PROJ4\",\"+proj=merc +a=6378137 +b=6378137 +lat_ts=0 +lon_0=0 +x_0=0 +y_0=0 +k=1 +units=m +nadgrids=@null +wktext +no_defs\"]");
+    }
+    if srs.epsg_code != 0 {
+        s.push_str(&format!(",AUTHORITY[\"EPSG\",\"{}\"]", srs.epsg_code));
+    }
+    s.push(']');
+    s
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EPSG_CODES: [u16; 18] = [4326, 4269, 4267, 4277, 26917, 32617, 32755, 3857, 3395, 5070, 3310, 3347,
+        3978, 3161, 2154, 27700, 28355, 2958];
+
+    #[test]
+    fn ogc_wkt_without_an_authority_is_parsed() {
+        let wkt = "PROJCS[\"NAD83 / UTM zone 17N\",GEOGCS[\"NAD83\",DATUM[\"North_American_Datum_1983\",SPHEROID[\"GRS 1980\",6378137,298.257222101]],PRIMEM[\"Greenwich\",0],UNIT[\"degree\",0.0174532925199433]],PROJECTION[\"Transverse_Mercator\"],PARAMETER[\"latitude_of_origin\",0],PARAMETER[\"central_meridian\",-81],PARAMETER[\"scale_factor\",0.9996],PARAMETER[\"false_easting\",500000],PARAMETER[\"false_northing\",0],UNIT[\"metre\",1]]";
+        let srs = from_wkt(wkt).unwrap();
+        assert_eq!(srs.name, "NAD83 / UTM zone 17N");
+        assert_eq!(srs.epsg_code, 0);
+        assert_eq!(srs.datum.geographic_epsg_code, 4269);
+        assert!(srs.projection.is_equivalent(&Projection::utm(17, false)));
+        assert!(srs.is_equivalent(&epsg::from_epsg(26917).unwrap()));
+    }
+
+    #[test]
+    fn esri_wkt_is_parsed() {
+        let wkt = "PROJCS[\"NAD_1983_StatePlane_Texas_South_Central_FIPS_4204_Feet\",GEOGCS[\"GCS_North_American_1983\",DATUM[\"D_North_American_1983\",SPHEROID[\"GRS_1980\",6378137.0,298.257222101]],PRIMEM[\"Greenwich\",0.0],UNIT[\"Degree\",0.0174532925199433]],PROJECTION[\"Lambert_Conformal_Conic\"],PARAMETER[\"False_Easting\",1968500.0],PARAMETER[\"False_Northing\",13123333.33333333],PARAMETER[\"Central_Meridian\",-99.0],PARAMETER[\"Standard_Parallel_1\",28.38333333333333],PARAMETER[\"Standard_Parallel_2\",30.28333333333334],PARAMETER[\"Latitude_Of_Origin\",27.83333333333333],UNIT[\"Foot_US\",0.3048006096012192]]";
+        let srs = from_wkt(wkt).unwrap();
+        assert_eq!(srs.datum.geographic_epsg_code, 4269);
+        assert_eq!(srs.linear_unit_name, "Foot_US");
+        assert_eq!(srs.linear_unit, 0.3048006096012192);
+        match srs.projection {
+            Projection::LambertConformalConic { latitude_of_origin, standard_parallel_1, standard_parallel_2, false_easting, false_northing, .. } => {
+                assert!((latitude_of_origin - 27.83333333333333).abs() < 1.0e-9);
+                assert!((standard_parallel_1 - 28.38333333333333).abs() < 1.0e-9);
+                assert!((standard_parallel_2 - 30.28333333333334).abs() < 1.0e-9);
+                // the false origin is kept in metres
+                assert!((false_easting - 600_000f64).abs() < 1.0e-3);
+                assert!((false_northing - 4_000_000f64).abs() < 1.0e-3);
+            },
+            ref p => panic!("Unexpected projection {:?}", p),
+        }
+        // coordinates are in feet
+        let (x, _) = srs.from_geodetic((-99f64).to_radians(), 27.83333333333333f64.to_radians());
+        assert!((x - 1_968_500f64).abs() < 1.0e-3);
+    }
+
+    #[test]
+    fn esri_web_mercator_is_recognized() {
+        let wkt = "PROJCS[\"WGS_1984_Web_Mercator_Auxiliary_Sphere\",GEOGCS[\"GCS_WGS_1984\",DATUM[\"D_WGS_1984\",SPHEROID[\"WGS_1984\",6378137.0,298.257223563]],PRIMEM[\"Greenwich\",0.0],UNIT[\"Degree\",0.0174532925199433]],PROJECTION[\"Mercator_Auxiliary_Sphere\"],PARAMETER[\"False_Easting\",0.0],PARAMETER[\"False_Northing\",0.0],PARAMETER[\"Central_Meridian\",0.0],PARAMETER[\"Standard_Parallel_1\",0.0],PARAMETER[\"Auxiliary_Sphere_Type\",0.0],UNIT[\"Meter\",1.0]]";
+        let srs = from_wkt(wkt).unwrap();
+        assert_eq!(srs.projection, Projection::WebMercator);
+        assert!(srs.is_equivalent(&epsg::from_epsg(3857).unwrap()));
+    }
+
+    #[test]
+    fn wkt2_geographic_system_is_parsed() {
+        let wkt = "GEOGCRS[\"WGS 84\",DATUM[\"World Geodetic System 1984\",ELLIPSOID[\"WGS 84\",6378137,298.257223563,LENGTHUNIT[\"metre\",1]]],PRIMEM[\"Greenwich\",0,ANGLEUNIT[\"degree\",0.0174532925199433]],CS[ellipsoidal,2],AXIS[\"latitude\",north,ORDER[1]],AXIS[\"longitude\",east,ORDER[2]],ANGLEUNIT[\"degree\",0.0174532925199433],ID[\"EPSG\",4326]]";
+        let srs = from_wkt(wkt).unwrap();
+        assert!(srs.is_geographic());
+        assert_eq!(srs.epsg_code, 4326);
+    }
+
+    #[test]
+    fn datum_shifts_are_read() {
+        let wkt = "GEOGCS[\"Custom\",DATUM[\"Custom_Datum\",SPHEROID[\"Clarke 1866\",6378206.4,294.9786982139006],TOWGS84[-8,160,176,0,0,0,0]],PRIMEM[\"Greenwich\",0],UNIT[\"degree\",0.0174532925199433]]";
+        let srs = from_wkt(wkt).unwrap();
+        assert_eq!(srs.name, "Custom");
+        assert_eq!(srs.epsg_code, 0);
+        assert_eq!(srs.datum.to_wgs84, [-8.0, 160.0, 176.0, 0.0, 0.0, 0.0, 0.0]);
+        assert!(srs.datum.is_equivalent(&Datum::from_geographic_epsg(4267).unwrap()));
+    }
+
+    #[test]
+    fn written_wkt_is_read_back() {
+        for code in EPSG_CODES.iter() {
+            let mut srs = epsg::from_epsg(*code).unwrap();
+            // without a code the system is read from the WKT itself rather than the EPSG table
+            srs.epsg_code = 0;
+            let srs2 = from_wkt(&to_wkt(&srs)).unwrap();
+            assert!(srs.is_equivalent(&srs2), "EPSG:{} was read back as {:?}", code, srs2);
+            assert_eq!(srs.name, srs2.name);
+
+            let srs = epsg::from_epsg(*code).unwrap();
+            assert_eq!(from_wkt(&to_wkt(&srs)).unwrap(), srs);
+        }
+    }
+
+    #[test]
+    fn malformed_wkt_is_rejected() {
+        let wkts = [
+            "",
+            "GEOGCS[\"WGS 84\",DATUM[\"WGS_1984\",SPHEROID[\"WGS 84\",6378137,298.257223563]]",
+            "GEOGCS[\"WGS 84,DATUM[\"WGS_1984\"]]",
+            "LOCAL_CS[\"Engineering\",LOCAL_DATUM[\"Site\",0],UNIT[\"metre\",1]]",
+            "GEOGCS[\"No datum\",PRIMEM[\"Greenwich\",0]]",
+            "GEOGCS[\"Unknown\",DATUM[\"Unknown\",SPHEROID[\"Unknown\",0,0]]]",
+            "PROJCS[\"No projection\",GEOGCS[\"WGS 84\",DATUM[\"WGS_1984\",SPHEROID[\"WGS 84\",6378137,298.257223563]]],UNIT[\"metre\",1]]",
+            "PROJCS[\"Polyconic\",GEOGCS[\"WGS 84\",DATUM[\"WGS_1984\",SPHEROID[\"WGS 84\",6378137,298.257223563]]],PROJECTION[\"Polyconic\"],UNIT[\"metre\",1]]",
+        ];
+        for wkt in wkts.iter() {
+            let err = from_wkt(wkt).err().expect(wkt);
+            assert_eq!(err.kind(), ErrorKind::InvalidData);
+        }
+    }
+}
@@ -0,0 +1,821 @@
+/*
+This code is part of the WhiteboxTools geospatial analysis library.
+Authors: Dr. John Lindsay
+Created: April 1, 2018
+Last Modified: April 2, 2018
+License: MIT
+
+Notes: A Delaunay triangulation of a set of points, built using the sweep-hull algorithm
+(Sinclair, 2016, S-hull: a fast radial sweep-hull routine for Delaunay triangulation). The
+points are added in order of their distance from the circumcentre of a seed triangle, each
+being connected to the edges of the convex hull that are visible from it, and the Delaunay
+condition is restored by edge flipping. The orientation and in-circle tests use a
+floating-point filter with an exact fallback computed using the expansion arithmetic of
+Shewchuk (1997, Adaptive precision floating-point arithmetic and fast robust geometric
+predicates), so that the triangulation is valid for the many collinear and co-circular
+points of gridded and LiDAR data. The rare point that is not outside of the hull when it
+is reached, because it lies on a hull edge or because rounding has placed it out of order,
+is inserted into the triangle or edge that contains it. Duplicate points, and those with
+non-finite coordinates, are left out of the triangulation.
+
+The triangulation is stored as halfedges. Halfedge e starts at point triangles[e], belongs
+to triangle e / 3, and its opposite halfedge in the neighbouring triangle is halfedges[e],
+or EMPTY for edges on the convex hull.
+*/
+
+use std::f64;
+use std::usize;
+use vector::Point2D;
+
+/// The opposite of a halfedge on the convex hull.
+pub const EMPTY: usize = usize::MAX;
+
+pub struct Triangulation {
+    /// The point indices of the vertices of each triangle, in counter-clockwise order.
+    pub triangles: Vec<usize>,
+    /// The opposite halfedge of each halfedge, or EMPTY for hull edges.
+    pub halfedges: Vec<usize>,
+    /// The point indices of the convex hull, in counter-clockwise order.
+    pub hull: Vec<usize>,
+}
+
+impl Triangulation {
+    /// Triangulates a set of points. The triangulation is empty if there are fewer than
+    /// three distinct points or if all of the points are collinear.
+    pub fn new(points: &[Point2D]) -> Triangulation {
+        let mut builder = match Builder::new(points) {
+            Some(b) => b,
+            None => return Triangulation { triangles: vec![], halfedges: vec![], hull: vec![] },
+        };
+        builder.sweep(points);
+
+        let mut hull = vec![];
+        let mut e = builder.hull_start;
+        loop {
+            hull.push(e);
+            e = builder.hull_next[e];
+            if e == builder.hull_start {
+                break;
+            }
+        }
+        Triangulation {
+            triangles: builder.triangles,
+            halfedges: builder.halfedges,
+            hull: hull,
+        }
+    }
+
+    /// Returns the number of triangles.
+    pub fn num_triangles(&self) -> usize {
+        self.triangles.len() / 3
+    }
+
+    /// Returns the next halfedge of a triangle, i.e. the one that starts where it ends.
+    pub fn next_halfedge(e: usize) -> usize {
+        if e % 3 == 2 { e - 2 } else { e + 1 }
+    }
+
+    /// Returns the previous halfedge of a triangle, i.e. the one that ends where it starts.
+    pub fn prev_halfedge(e: usize) -> usize {
+        if e % 3 == 0 { e + 2 } else { e - 1 }
+    }
+
+    /// Returns the point indices of the vertices of a triangle.
+    pub fn triangle_points(&self, triangle: usize) -> [usize; 3] {
+        [self.triangles[3 * triangle], self.triangles[3 * triangle + 1], self.triangles[3 * triangle + 2]]
+    }
+}
+
+struct Builder {
+    triangles: Vec<usize>,
+    halfedges: Vec<usize>,
+    hull_start: usize,
+    hull_next: Vec<usize>,
+    hull_prev: Vec<usize>,
+    // the halfedge of the hull edge that starts at each hull point
+    hull_tri: Vec<usize>,
+    hull_hash: Vec<usize>,
+    center: (f64, f64),
+    edge_stack: Vec<usize>,
+    seeds: [usize; 3],
+}
+
+impl Builder {
+    // Finds a seed triangle near the centre of the points, returning None if every
+    // point is collinear.
+    fn new(points: &[Point2D]) -> Option<Builder> {
+        let n = points.len();
+        if n < 3 {
+            return None;
+        }
+        let (mut min_x, mut min_y) = (f64::INFINITY, f64::INFINITY);
+        let (mut max_x, mut max_y) = (f64::NEG_INFINITY, f64::NEG_INFINITY);
+        for p in points.iter().filter(|p| is_finite(p)) {
+            if p.x < min_x { min_x = p.x; }
+            if p.y < min_y { min_y = p.y; }
+            if p.x > max_x { max_x = p.x; }
+            if p.y > max_y { max_y = p.y; }
+        }
+        let c = Point2D { x: (min_x + max_x) / 2f64, y: (min_y + max_y) / 2f64 };
+
+        let i0 = (0..n).filter(|&i| is_finite(&points[i])).min_by(|&a, &b| dist2(&points[a], &c).partial_cmp(&dist2(&points[b], &c)).unwrap())?;
+        let i1 = (0..n).filter(|&i| is_finite(&points[i]) && dist2(&points[i], &points[i0]) > 0f64)
+            .min_by(|&a, &b| dist2(&points[a], &points[i0]).partial_cmp(&dist2(&points[b], &points[i0])).unwrap())?;
+        let mut i2 = EMPTY;
+        let mut min_radius = f64::INFINITY;
+        for i in (0..n).filter(|&i| is_finite(&points[i])) {
+            if orient2d(&points[i0], &points[i1], &points[i]) == 0f64 {
+                continue;
+            }
+            let r = circumradius2(&points[i0], &points[i1], &points[i]);
+            if r < min_radius {
+                min_radius = r;
+                i2 = i;
+            }
+        }
+        if i2 == EMPTY {
+            return None;
+        }
+        let (i1, i2) = if orient2d(&points[i0], &points[i1], &points[i2]) < 0f64 { (i2, i1) } else { (i1, i2) };
+
+        let hash_size = (n as f64).sqrt().ceil() as usize;
+        let max_triangles = 2 * n - 5;
+        let mut b = Builder {
+            triangles: Vec::with_capacity(3 * max_triangles),
+            halfedges: Vec::with_capacity(3 * max_triangles),
+            hull_start: i0,
+            hull_next: vec![0; n],
+            hull_prev: vec![0; n],
+            hull_tri: vec![0; n],
+            hull_hash: vec![EMPTY; hash_size],
+            center: circumcenter(&points[i0], &points[i1], &points[i2]),
+            edge_stack: vec![],
+            seeds: [i0, i1, i2],
+        };
+        b.hull_next[i0] = i1;
+        b.hull_prev[i2] = i1;
+        b.hull_next[i1] = i2;
+        b.hull_prev[i0] = i2;
+        b.hull_next[i2] = i0;
+        b.hull_prev[i1] = i0;
+        b.hull_tri[i0] = 0;
+        b.hull_tri[i1] = 1;
+        b.hull_tri[i2] = 2;
+        for &i in &[i0, i1, i2] {
+            let key = b.hash_key(&points[i]);
+            b.hull_hash[key] = i;
+        }
+        b.add_triangle(i0, i1, i2, EMPTY, EMPTY, EMPTY);
+        Some(b)
+    }
+
+    fn sweep(&mut self, points: &[Point2D]) {
+        let (cx, cy) = self.center;
+        let c = Point2D { x: cx, y: cy };
+        let dists: Vec<f64> = points.iter().map(|p| dist2(p, &c)).collect();
+        // duplicate points have the same distance, and are made adjacent by the tie-breaks
+        let mut ids: Vec<usize> = (0..points.len()).filter(|&i| is_finite(&points[i])).collect();
+        ids.sort_by(|&a, &b| {
+            let (pa, pb) = (&points[a], &points[b]);
+            (dists[a], pa.x, pa.y).partial_cmp(&(dists[b], pb.x, pb.y)).unwrap()
+        });
+
+        let hash_size = self.hull_hash.len();
+        for k in 0..ids.len() {
+            let i = ids[k];
+            let p = &points[i];
+            if i == self.seeds[0] || i == self.seeds[1] || i == self.seeds[2] {
+                continue;
+            }
+            if k > 0 && p.x == points[ids[k - 1]].x && p.y == points[ids[k - 1]].y {
+                continue;
+            }
+
+            // find a visible edge of the hull, starting from a hull point at a similar angle
+            let key = self.hash_key(p);
+            let mut start = 0;
+            for j in 0..hash_size {
+                start = self.hull_hash[(key + j) % hash_size];
+                if start != EMPTY && start != self.hull_next[start] {
+                    break;
+                }
+            }
+            start = self.hull_prev[start];
+            let mut e = start;
+            loop {
+                let q = self.hull_next[e];
+                if orient2d(&points[e], &points[q], p) < 0f64 {
+                    break;
+                }
+                e = q;
+                if e == start {
+                    e = EMPTY;
+                    break;
+                }
+            }
+            if e == EMPTY {
+                self.insert_inside(i, start, points);
+                continue;
+            }
+
+            let next = self.hull_next[e];
+            let t = self.add_triangle(e, i, next, EMPTY, EMPTY, self.hull_tri[e]);
+            self.hull_tri[i] = t + 1;
+            self.hull_tri[e] = t;
+            self.legalize(t + 2, points);
+
+            // connect the point to the visible hull edges that follow, then those before
+            let mut n = self.hull_next[e];
+            loop {
+                let q = self.hull_next[n];
+                if orient2d(&points[n], &points[q], p) >= 0f64 {
+                    break;
+                }
+                let t = self.add_triangle(n, i, q, self.hull_tri[i], EMPTY, self.hull_tri[n]);
+                self.hull_tri[i] = t + 1;
+                self.legalize(t + 2, points);
+                self.hull_next[n] = n; // removed from the hull
+                n = q;
+            }
+            if e == start {
+                loop {
+                    let q = self.hull_prev[e];
+                    if orient2d(&points[q], &points[e], p) >= 0f64 {
+                        break;
+                    }
+                    let t = self.add_triangle(q, i, e, EMPTY, self.hull_tri[e], self.hull_tri[q]);
+                    self.hull_tri[q] = t;
+                    self.legalize(t + 2, points);
+                    self.hull_next[e] = e;
+                    e = q;
+                }
+            }
+
+            self.hull_start = e;
+            self.hull_prev[i] = e;
+            self.hull_next[e] = i;
+            self.hull_prev[n] = i;
+            self.hull_next[i] = n;
+            let key = self.hash_key(p);
+            self.hull_hash[key] = i;
+            let key = self.hash_key(&points[e]);
+            self.hull_hash[key] = e;
+        }
+    }
+
+    // Inserts a point that is not outside of the hull, by splitting the hull edge or the
+    // triangle or edge that contains it. Duplicates of existing points are ignored.
+    fn insert_inside(&mut self, i: usize, hull_point: usize, points: &[Point2D]) {
+        let p = &points[i];
+        let mut e = hull_point;
+        loop {
+            let q = self.hull_next[e];
+            if orient2d(&points[e], &points[q], p) == 0f64 && is_between(p, &points[e], &points[q]) {
+                let h = self.hull_tri[e];
+                self.split_edge(h, i, points);
+                let key = self.hash_key(p);
+                self.hull_hash[key] = i;
+                return;
+            }
+            e = q;
+            if e == hull_point {
+                break;
+            }
+        }
+
+        // search for the containing triangle, which is only ever needed for a handful of points
+        for t in 0..self.triangles.len() / 3 {
+            let h = 3 * t;
+            let (a, b, c) = (&points[self.triangles[h]], &points[self.triangles[h + 1]], &points[self.triangles[h + 2]]);
+            let orient = [orient2d(a, b, p), orient2d(b, c, p), orient2d(c, a, p)];
+            if orient[0] < 0f64 || orient[1] < 0f64 || orient[2] < 0f64 {
+                continue;
+            }
+            if [a, b, c].iter().any(|v| v.x == p.x && v.y == p.y) {
+                return;
+            }
+            match orient.iter().position(|&o| o == 0f64) {
+                Some(j) => self.split_edge(h + j, i, points),
+                None => self.split_triangle(t, i, points),
+            }
+            return;
+        }
+    }
+
+    // Splits the triangle that contains a point into three.
+    fn split_triangle(&mut self, t: usize, i: usize, points: &[Point2D]) {
+        let h0 = 3 * t;
+        let (h1, h2) = (h0 + 1, h0 + 2);
+        let (a, b, c) = (self.triangles[h0], self.triangles[h1], self.triangles[h2]);
+        let (o1, o2) = (self.halfedges[h1], self.halfedges[h2]);
+        self.triangles[h2] = i;
+        let t1 = self.add_triangle(b, c, i, o1, EMPTY, h1);
+        let t2 = self.add_triangle(c, a, i, o2, h2, t1 + 1);
+        if o1 == EMPTY {
+            self.hull_tri[b] = t1;
+        }
+        if o2 == EMPTY {
+            self.hull_tri[c] = t2;
+        }
+        self.legalize(h0, points);
+        self.legalize(t1, points);
+        self.legalize(t2, points);
+    }
+
+    // Splits the edge of halfedge h, which contains a point, along with the one or two
+    // triangles on either side of it.
+    fn split_edge(&mut self, h: usize, i: usize, points: &[Point2D]) {
+        let g = self.halfedges[h];
+        let hn = Triangulation::next_halfedge(h);
+        let hp = Triangulation::prev_halfedge(h);
+        let (a, b, c) = (self.triangles[h], self.triangles[hn], self.triangles[hp]);
+        let o_hp = self.halfedges[hp];
+
+        // triangle (a, b, c) becomes (i, b, c), and (a, i, c) is added
+        self.triangles[h] = i;
+        let t1 = self.add_triangle(a, i, c, g, hp, o_hp);
+        if o_hp == EMPTY {
+            self.hull_tri[c] = t1 + 2;
+        }
+        if g == EMPTY {
+            // a hull edge, which is divided in two
+            self.hull_tri[a] = t1;
+            self.hull_tri[i] = h;
+            self.hull_next[a] = i;
+            self.hull_prev[i] = a;
+            self.hull_next[i] = b;
+            self.hull_prev[b] = i;
+        } else {
+            // triangle (b, a, d) becomes (i, a, d), and (b, i, d) is added
+            let gn = Triangulation::next_halfedge(g);
+            let gp = Triangulation::prev_halfedge(g);
+            let d = self.triangles[gp];
+            let o_gp = self.halfedges[gp];
+            self.triangles[g] = i;
+            let t2 = self.add_triangle(b, i, d, h, gp, o_gp);
+            if o_gp == EMPTY {
+                self.hull_tri[d] = t2 + 2;
+            }
+            self.legalize(gn, points);
+            self.legalize(t2 + 2, points);
+        }
+        self.legalize(hn, points);
+        self.legalize(t1 + 2, points);
+    }
+
+    fn hash_key(&self, p: &Point2D) -> usize {
+        let n = self.hull_hash.len();
+        let dx = p.x - self.center.0;
+        let dy = p.y - self.center.1;
+        // a monotonic function of the angle, in the range 0 to 1
+        let r = dx / (dx.abs() + dy.abs());
+        let angle = (if dy > 0f64 { 3f64 - r } else { 1f64 + r }) / 4f64;
+        if !angle.is_finite() {
+            return 0;
+        }
+        ((angle * n as f64).floor() as usize) % n
+    }
+
+    fn add_triangle(&mut self, i0: usize, i1: usize, i2: usize, a: usize, b: usize, c: usize) -> usize {
+        let t = self.triangles.len();
+        self.triangles.push(i0);
+        self.triangles.push(i1);
+        self.triangles.push(i2);
+        self.halfedges.push(EMPTY);
+        self.halfedges.push(EMPTY);
+        self.halfedges.push(EMPTY);
+        self.link(t, a);
+        self.link(t + 1, b);
+        self.link(t + 2, c);
+        t
+    }
+
+    fn link(&mut self, a: usize, b: usize) {
+        self.halfedges[a] = b;
+        if b != EMPTY {
+            self.halfedges[b] = a;
+        }
+    }
+
+    // Flips the edges opposite a newly added point until the triangles around it are
+    // Delaunay. Halfedge a belongs to a triangle whose third point is the new point.
+    fn legalize(&mut self, a: usize, points: &[Point2D]) {
+        self.edge_stack.push(a);
+        while let Some(a) = self.edge_stack.pop() {
+            loop {
+                let b = self.halfedges[a];
+                if b == EMPTY {
+                    break;
+                }
+                let a0 = a - a % 3;
+                let al = a0 + (a + 1) % 3;
+                let ar = a0 + (a + 2) % 3;
+                let b0 = b - b % 3;
+                let br = b0 + (b + 1) % 3;
+                let bl = b0 + (b + 2) % 3;
+
+                let p0 = self.triangles[ar];
+                let pr = self.triangles[a];
+                let pl = self.triangles[al];
+                let p1 = self.triangles[bl];
+                if incircle(&points[pr], &points[pl], &points[p0], &points[p1]) <= 0f64 {
+                    break;
+                }
+
+                // flip the edge, such that halfedge ar moves to b and bl moves to a
+                self.triangles[a] = p1;
+                self.triangles[b] = p0;
+                let hbl = self.halfedges[bl];
+                let har = self.halfedges[ar];
+                if hbl == EMPTY && self.hull_tri[p1] == bl {
+                    self.hull_tri[p1] = a;
+                }
+                if har == EMPTY && self.hull_tri[p0] == ar {
+                    self.hull_tri[p0] = b;
+                }
+                self.link(a, hbl);
+                self.link(b, har);
+                self.link(ar, bl);
+                // halfedge a is now the edge opposite the new point in the flipped triangle
+                self.edge_stack.push(br);
+            }
+        }
+    }
+}
+
+fn is_finite(p: &Point2D) -> bool {
+    p.x.is_finite() && p.y.is_finite()
+}
+
+// Returns true if a point that is collinear with a segment lies strictly between its ends.
+fn is_between(p: &Point2D, a: &Point2D, b: &Point2D) -> bool {
+    if (p.x == a.x && p.y == a.y) || (p.x == b.x && p.y == b.y) {
+        return false;
+    }
+    p.x >= a.x.min(b.x) && p.x <= a.x.max(b.x) && p.y >= a.y.min(b.y) && p.y <= a.y.max(b.y)
+}
+
+fn dist2(a: &Point2D, b: &Point2D) -> f64 {
+    let dx = a.x - b.x;
+    let dy = a.y - b.y;
+    dx * dx + dy * dy
+}
+
+fn circumradius2(a: &Point2D, b: &Point2D, c: &Point2D) -> f64 {
+    let (x, y) = circumcenter(a, b, c);
+    let r = dist2(a, &Point2D { x: x, y: y });
+    if r.is_finite() { r } else { f64::INFINITY }
+}
+
+fn circumcenter(a: &Point2D, b: &Point2D, c: &Point2D) -> (f64, f64) {
+    let dx = b.x - a.x;
+    let dy = b.y - a.y;
+    let ex = c.x - a.x;
+    let ey = c.y - a.y;
+    let bl = dx * dx + dy * dy;
+    let cl = ex * ex + ey * ey;
+    let d = 0.5f64 / (dx * ey - dy * ex);
+    (a.x + (ey * bl - dy * cl) * d, a.y + (dx * cl - ex * bl) * d)
+}
+
+// The relative error bounds of the floating-point filters (Shewchuk, 1997).
+const EPSILON: f64 = 1.1102230246251565e-16; // 2^-53
+const CCW_ERR_BOUND: f64 = (3f64 + 16f64 * EPSILON) * EPSILON;
+const ICC_ERR_BOUND: f64 = (10f64 + 96f64 * EPSILON) * EPSILON;
+
+/// Returns a positive value if the points a, b, and c are in counter-clockwise order, a
+/// negative value if they are clockwise, and zero if they are collinear. The sign is exact.
+pub fn orient2d(a: &Point2D, b: &Point2D, c: &Point2D) -> f64 {
+    let det_left = (a.x - c.x) * (b.y - c.y);
+    let det_right = (a.y - c.y) * (b.x - c.x);
+    let det = det_left - det_right;
+    let det_sum = det_left.abs() + det_right.abs();
+    if det.abs() >= CCW_ERR_BOUND * det_sum {
+        return det;
+    }
+    let acx = diff(a.x, c.x);
+    let bcx = diff(b.x, c.x);
+    let acy = diff(a.y, c.y);
+    let bcy = diff(b.y, c.y);
+    let det = expansion_sum(&expansion_product(&acx, &bcy), &negate(&expansion_product(&acy, &bcx)));
+    det[det.len() - 1]
+}
+
+/// Returns a positive value if the point d lies inside the circle passing through the
+/// counter-clockwise points a, b, and c, a negative value if it lies outside, and zero if
+/// the four points are co-circular. The sign is exact.
+pub fn incircle(a: &Point2D, b: &Point2D, c: &Point2D, d: &Point2D) -> f64 {
+    let adx = a.x - d.x;
+    let bdx = b.x - d.x;
+    let cdx = c.x - d.x;
+    let ady = a.y - d.y;
+    let bdy = b.y - d.y;
+    let cdy = c.y - d.y;
+
+    let bdxcdy = bdx * cdy;
+    let cdxbdy = cdx * bdy;
+    let alift = adx * adx + ady * ady;
+    let cdxady = cdx * ady;
+    let adxcdy = adx * cdy;
+    let blift = bdx * bdx + bdy * bdy;
+    let adxbdy = adx * bdy;
+    let bdxady = bdx * ady;
+    let clift = cdx * cdx + cdy * cdy;
+
+    let det = alift * (bdxcdy - cdxbdy) + blift * (cdxady - adxcdy) + clift * (adxbdy - bdxady);
+    let permanent = (bdxcdy.abs() + cdxbdy.abs()) * alift
+        + (cdxady.abs() + adxcdy.abs()) * blift
+        + (adxbdy.abs() + bdxady.abs()) * clift;
+    if det.abs() > ICC_ERR_BOUND * permanent {
+        return det;
+    }
+
+    let adx = diff(a.x, d.x);
+    let bdx = diff(b.x, d.x);
+    let cdx = diff(c.x, d.x);
+    let ady = diff(a.y, d.y);
+    let bdy = diff(b.y, d.y);
+    let cdy = diff(c.y, d.y);
+    let lift = |x: &[f64], y: &[f64]| expansion_sum(&expansion_product(x, x), &expansion_product(y, y));
+    let cross = |x1: &[f64], y2: &[f64], x2: &[f64], y1: &[f64]| {
+        expansion_sum(&expansion_product(x1, y2), &negate(&expansion_product(x2, y1)))
+    };
+    let a_term = expansion_product(&lift(&adx, &ady), &cross(&bdx, &cdy, &cdx, &bdy));
+    let b_term = expansion_product(&lift(&bdx, &bdy), &cross(&cdx, &ady, &adx, &cdy));
+    let c_term = expansion_product(&lift(&cdx, &cdy), &cross(&adx, &bdy, &bdx, &ady));
+    let det = expansion_sum(&expansion_sum(&a_term, &b_term), &c_term);
+    det[det.len() - 1]
+}
+
+// Exact arithmetic on expansions, i.e. sums of non-overlapping floating-point values
+// stored in order of increasing magnitude, the last of which carries the sign.
+
+fn two_sum(a: f64, b: f64) -> (f64, f64) {
+    let x = a + b;
+    let b_virtual = x - a;
+    let a_virtual = x - b_virtual;
+    let b_roundoff = b - b_virtual;
+    let a_roundoff = a - a_virtual;
+    (x, a_roundoff + b_roundoff)
+}
+
+fn split(a: f64) -> (f64, f64) {
+    let c = 134217729f64 * a; // 2^27 + 1
+    let a_big = c - a;
+    let hi = c - a_big;
+    (hi, a - hi)
+}
+
+fn two_product(a: f64, b: f64) -> (f64, f64) {
+    let x = a * b;
+    let (ahi, alo) = split(a);
+    let (bhi, blo) = split(b);
+    let err1 = x - ahi * bhi;
+    let err2 = err1 - alo * bhi;
+    let err3 = err2 - ahi * blo;
+    (x, alo * blo - err3)
+}
+
+fn diff(a: f64, b: f64) -> Vec<f64> {
+    let (x, y) = two_sum(a, -b);
+    if y != 0f64 { vec![y, x] } else { vec![x] }
+}
+
+fn negate(e: &[f64]) -> Vec<f64> {
+    e.iter().map(|v| -v).collect()
+}
+
+fn grow_expansion(e: &[f64], b: f64) -> Vec<f64> {
+    let mut h = Vec::with_capacity(e.len() + 1);
+    let mut q = b;
+    for &v in e {
+        let (sum, err) = two_sum(q, v);
+        if err != 0f64 {
+            h.push(err);
+        }
+        q = sum;
+    }
+    if q != 0f64 || h.len() == 0 {
+        h.push(q);
+    }
+    h
+}
+
+fn expansion_sum(e: &[f64], f: &[f64]) -> Vec<f64> {
+    let mut h = e.to_vec();
+    for &v in f {
+        h = grow_expansion(&h, v);
+    }
+    h
+}
+
+fn scale_expansion(e: &[f64], b: f64) -> Vec<f64> {
+    let mut h = Vec::with_capacity(2 * e.len());
+    let (mut q, err) = two_product(e[0], b);
+    if err != 0f64 {
+        h.push(err);
+    }
+    for &v in &e[1..] {
+        let (product1, product0) = two_product(v, b);
+        let (sum, err) = two_sum(q, product0);
+        if err != 0f64 {
+            h.push(err);
+        }
+        let (sum, err) = two_sum(product1, sum);
+        if err != 0f64 {
+            h.push(err);
+        }
+        q = sum;
+    }
+    if q != 0f64 || h.len() == 0 {
+        h.push(q);
+    }
+    h
+}
+
+fn expansion_product(e: &[f64], f: &[f64]) -> Vec<f64> {
+    let mut h = vec![0f64];
+    for &v in f {
+        h = expansion_sum(&h, &scale_expansion(e, v));
+    }
+    h
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Lcg(u64);
+
+    impl Lcg {
+        fn next(&mut self) -> f64 {
+            self.0 = self.0.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            (self.0 >> 11) as f64 / (1u64 << 53) as f64
+        }
+    }
+
+    fn p(x: f64, y: f64) -> Point2D {
+        Point2D { x: x, y: y }
+    }
+
+    // Checks the invariants of a triangulation of a set of points, of which num_distinct are
+    // distinct and finite.
+    fn check(points: &[Point2D], tin: &Triangulation, num_distinct: usize) {
+        let n = tin.triangles.len();
+        assert_eq!(n % 3, 0);
+        assert_eq!(tin.halfedges.len(), n);
+
+        // every distinct point is a vertex
+        let mut used = vec![false; points.len()];
+        for &v in &tin.triangles {
+            used[v] = true;
+        }
+        assert_eq!(used.iter().filter(|&&u| u).count(), num_distinct);
+
+        let mut num_hull_edges = 0;
+        for e in 0..n {
+            let (a, b) = (tin.triangles[e], tin.triangles[Triangulation::next_halfedge(e)]);
+            if e % 3 == 0 {
+                let t = tin.triangle_points(e / 3);
+                assert!(orient2d(&points[t[0]], &points[t[1]], &points[t[2]]) > 0f64, "triangle {} is not counter-clockwise", e / 3);
+            }
+            let opposite = tin.halfedges[e];
+            if opposite == EMPTY {
+                num_hull_edges += 1;
+                continue;
+            }
+            // neighbouring triangles share the edge in opposite directions
+            assert_eq!(tin.halfedges[opposite], e);
+            assert_eq!((tin.triangles[opposite], tin.triangles[Triangulation::next_halfedge(opposite)]), (b, a));
+            // the point opposite an edge is not inside the circumcircle of the neighbouring triangle
+            let c = tin.triangles[Triangulation::prev_halfedge(e)];
+            let d = tin.triangles[Triangulation::prev_halfedge(opposite)];
+            assert!(incircle(&points[a], &points[b], &points[c], &points[d]) <= 0f64, "edge {} is not locally Delaunay", e);
+        }
+
+        // the hull is convex and counter-clockwise, and is bounded by the unpaired halfedges
+        let h = tin.hull.len();
+        assert_eq!(num_hull_edges, h);
+        for i in 0..h {
+            let (a, b, c) = (&points[tin.hull[i]], &points[tin.hull[(i + 1) % h]], &points[tin.hull[(i + 2) % h]]);
+            assert!(orient2d(a, b, c) >= 0f64);
+        }
+        for i in 0..points.len() {
+            if used[i] {
+                for j in 0..h {
+                    assert!(orient2d(&points[tin.hull[j]], &points[tin.hull[(j + 1) % h]], &points[i]) >= 0f64);
+                }
+            }
+        }
+        // Euler's formula for a triangulated point set
+        assert_eq!(tin.num_triangles(), 2 * num_distinct - 2 - h);
+    }
+
+    #[test]
+    fn random_points_are_triangulated() {
+        let mut rng = Lcg(1);
+        for &n in [3usize, 4, 10, 100, 2000].iter() {
+            let points: Vec<Point2D> = (0..n).map(|_| p(rng.next() * 1000.0, rng.next() * 500.0)).collect();
+            let tin = Triangulation::new(&points);
+            check(&points, &tin, n);
+        }
+    }
+
+    #[test]
+    fn grids_with_collinear_and_cocircular_points_are_triangulated() {
+        let mut points = vec![];
+        for row in 0..30 {
+            for col in 0..40 {
+                points.push(p(col as f64 * 0.5, row as f64 * 0.5));
+            }
+        }
+        let tin = Triangulation::new(&points);
+        check(&points, &tin, 1200);
+        // the hull includes the points on the sides of the grid
+        assert_eq!(tin.hull.len(), 2 * (30 + 40) - 4);
+        assert_eq!(tin.num_triangles(), 2 * 29 * 39);
+
+        // the same grid at typical projected coordinates
+        let offset: Vec<Point2D> = points.iter().map(|q| p(q.x + 583_000.25, q.y + 4_507_000.5)).collect();
+        check(&offset, &Triangulation::new(&offset), 1200);
+    }
+
+    #[test]
+    fn cocircular_points_are_triangulated() {
+        let mut points: Vec<Point2D> = (0..64).map(|i| {
+            let a = i as f64 * f64::consts::PI / 32.0;
+            p(100.0 * a.cos(), 100.0 * a.sin())
+        }).collect();
+        points.extend_from_slice(&[p(3.0, 4.0), p(-4.0, 3.0), p(0.0, -5.0), p(5.0, 0.0), p(0.0, 0.0)]);
+        let tin = Triangulation::new(&points);
+        check(&points, &tin, points.len());
+        assert_eq!(tin.hull.len(), 64);
+    }
+
+    #[test]
+    fn duplicate_and_non_finite_points_are_left_out() {
+        let mut rng = Lcg(7);
+        let mut points: Vec<Point2D> = (0..200).map(|_| p(rng.next(), rng.next())).collect();
+        for i in 0..50 {
+            let q = points[i * 3].clone();
+            points.push(q);
+        }
+        points.push(p(f64::NAN, 0.5));
+        points.push(p(0.5, f64::INFINITY));
+        // points that are almost, but not quite, duplicates
+        points.push(p(points[0].x + 1e-12, points[0].y));
+        points.push(p(points[1].x, points[1].y - 1e-12));
+        let tin = Triangulation::new(&points);
+        check(&points, &tin, 202);
+    }
+
+    #[test]
+    fn points_on_the_hull_and_inside_triangles_are_inserted() {
+        // points on a line, with a few off it, stress the insertion of points onto hull edges
+        let mut points: Vec<Point2D> = (0..50).map(|i| p(i as f64, 0.0)).collect();
+        points.push(p(10.0, 1.0));
+        points.push(p(30.0, -1.0));
+        points.extend((0..50).map(|i| p(i as f64 + 0.5, 0.0)));
+        check(&points, &Triangulation::new(&points), 102);
+    }
+
+    #[test]
+    fn degenerate_inputs_are_not_triangulated() {
+        let inputs = vec![
+            vec![],
+            vec![p(0.0, 0.0), p(1.0, 1.0)],
+            vec![p(0.0, 0.0), p(0.0, 0.0), p(0.0, 0.0), p(0.0, 0.0)],
+            (0..100).map(|i| p(i as f64 * 0.1, i as f64 * 0.2)).collect(),
+            vec![p(0.0, 0.0), p(1.0, 1.0), p(f64::NAN, 5.0)],
+        ];
+        for points in inputs {
+            let tin = Triangulation::new(&points);
+            assert_eq!(tin.num_triangles(), 0);
+            assert_eq!(tin.halfedges.len(), 0);
+            assert_eq!(tin.hull.len(), 0);
+        }
+    }
+
+    #[test]
+    fn predicates_are_exact() {
+        let mut rng = Lcg(3);
+        for _ in 0..1000 {
+            // doubling is exact, so these points are exactly collinear
+            let (a, b, c) = (rng.next() * 0.1, rng.next() * 0.1 + 0.1, rng.next() * 1000.0 + 0.2);
+            assert_eq!(orient2d(&p(a, 2.0 * a), &p(b, 2.0 * b), &p(c, 2.0 * c)), 0f64);
+            // the next floating-point value above the line is to its left
+            let d = p(b, f64::from_bits((2.0 * b).to_bits() + 1));
+            assert!(orient2d(&p(a, 2.0 * a), &p(c, 2.0 * c), &d) > 0f64);
+            assert!(orient2d(&p(c, 2.0 * c), &p(a, 2.0 * a), &d) < 0f64);
+        }
+        assert!(orient2d(&p(0.0, 0.0), &p(1.0, 0.0), &p(0.0, 1.0)) > 0f64);
+        assert!(orient2d(&p(0.0, 0.0), &p(0.0, 1.0), &p(1.0, 0.0)) < 0f64);
+
+        let (a, b, c) = (p(5.0, 0.0), p(3.0, 4.0), p(-4.0, 3.0));
+        assert_eq!(incircle(&a, &b, &c, &p(0.0, -5.0)), 0f64);
+        assert!(incircle(&a, &b, &c, &p(0.0, -4.999999999999999)) > 0f64);
+        assert!(incircle(&a, &b, &c, &p(0.0, -5.000000000000001)) < 0f64);
+        let offset = |q: &Point2D| p(q.x + 583_000.0, q.y + 4_507_000.0);
+        assert_eq!(incircle(&offset(&a), &offset(&b), &offset(&c), &offset(&p(-3.0, -4.0))), 0f64);
+    }
+}
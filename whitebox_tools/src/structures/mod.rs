@@ -1,12 +1,14 @@
 // private sub-module defined in other files
 mod array2d;
 mod bounding_box;
+pub mod delaunay;
 mod fixed_radius_search;
 pub mod kd_tree;
 
 // exports identifiers from private sub-modules in the current module namespace
 pub use self::array2d::Array2D;
 pub use self::bounding_box::BoundingBox;
+pub use self::delaunay::Triangulation;
 pub use self::fixed_radius_search::FixedRadiusSearch2D;
 pub use self::fixed_radius_search::FixedRadiusSearch3D;
 pub use self::kd_tree::KdTree;
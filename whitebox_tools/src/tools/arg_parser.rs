@@ -0,0 +1,1182 @@
+/*
+This code is part of the WhiteboxTools geospatial analysis library.
+Authors: Dr. John Lindsay
+Created: March 22, 2018
+Last Modified: April 6, 2018
+License: MIT
+*/
+
+use std::cmp;
+use std::collections::BTreeMap;
+use std::error;
+use std::fmt;
+use std::io::{Error, ErrorKind};
+use std::panic;
+use std::path;
+use std::path::Path;
+use std::sync::Arc;
+use serde_json;
+use raster;
+use raster::Raster;
+use spatial_ref_system::{read_spatial_ref, SpatialRef};
+use io_utils::is_geopackage_file;
+use vector::{is_geojson_file, AttributeTable, Shapefile};
+use tools::{AttributeType, ParameterFileType, ParameterType, ProgressSink, ToolParameter, WhiteboxTool};
+
+/// Reads the parameters of a tool from its JSON description.
+pub fn get_parameters(tool: &Box<WhiteboxTool>) -> Result<Vec<ToolParameter>, Error> {
+    let json: serde_json::Value = match serde_json::from_str(&tool.get_tool_parameters()) {
+        Ok(v) => v,
+        Err(e) => return Err(Error::new(ErrorKind::InvalidData, format!("{}", e))),
+    };
+    match serde_json::from_value(json["parameters"].clone()) {
+        Ok(v) => Ok(v),
+        Err(e) => Err(Error::new(ErrorKind::InvalidData, format!("{}", e))),
+    }
+}
+
+/// Parses and validates the command-line arguments of a tool against its parameters. The
+/// arguments are returned in a canonical form, with each parameter given once by its long
+/// flag (e.g. `--output=/path/to/data/out.tif`) and file names resolved against the working
+/// directory.
+pub fn parse_args(tool: &Box<WhiteboxTool>,
+                  args: &[String],
+                  working_directory: &str)
+                  -> Result<Vec<String>, ToolError> {
+    let parameters = get_parameters(tool).map_err(ToolError::Failed)?;
+    let values = read_values(&parameters, args)?;
+    validate_args(&parameters, values, working_directory)
+}
+
+// Assigns the command-line arguments to the parameters that their flags belong to. A value
+// may follow its flag after an equals sign or as a separate argument, and boolean flags may
+// be given without a value.
+fn read_values(parameters: &[ToolParameter], args: &[String]) -> Result<Vec<Option<String>>, ToolError> {
+    let mut values = ParameterValues::new(parameters);
+    let mut i = 0;
+    while i < args.len() {
+        let arg = strip_quotes(&args[i]);
+        if !arg.starts_with("-") {
+            return Err(ToolError::UnrecognizedParameter { flag: arg.to_string(), suggestion: None });
+        }
+        let (flag, value) = match arg.find('=') {
+            Some(pos) => (&arg[..pos], Some(strip_quotes(&arg[(pos + 1)..]).to_string())),
+            None => (arg, None),
+        };
+        let indices = find_parameters(parameters, flag)?;
+        let is_boolean = indices.iter().all(|&j| match parameters[j].parameter_type {
+            ParameterType::Boolean => true,
+            _ => false,
+        });
+        // the value may also follow the flag as a separate argument
+        let value = match value {
+            Some(v) => v,
+            None => {
+                let next = if i + 1 < args.len() { Some(strip_quotes(&args[i + 1]).to_string()) } else { None };
+                match next {
+                    Some(ref v) if is_boolean && (v.to_lowercase() == "true" || v.to_lowercase() == "false") => {
+                        i += 1;
+                        v.clone()
+                    },
+                    Some(ref v) if !is_boolean && (!v.starts_with("-") || v.parse::<f64>().is_ok()) => {
+                        i += 1;
+                        v.clone()
+                    },
+                    _ if is_boolean => "true".to_string(),
+                    _ => return Err(invalid(&parameters[indices[0]], "no value was given")),
+                }
+            }
+        };
+        let shared = indices.len() > 1;
+        for &j in &indices {
+            values.set(flag, j, value.clone(), shared)?;
+        }
+        i += 1;
+    }
+    Ok(values.into_values())
+}
+
+// Removes the pair of quotes, if any, that encloses a whole argument or value. Quotes within
+// the value, e.g. those of a WKT coordinate reference system, are kept.
+fn strip_quotes(s: &str) -> &str {
+    let s = s.trim();
+    for quote in &['"', '\'']  {
+        if s.len() > 1 && s.starts_with(*quote) && s.ends_with(*quote) {
+            return &s[1..(s.len() - 1)];
+        }
+    }
+    s
+}
+
+/// The values of a tool's arguments. Tools read their typed parameter values from it,
+/// rather than parsing their arguments themselves. A parameter may be referred to by any of
+/// its flags, and its default value is used when it was not given.
+pub struct ArgValues {
+    parameters: Vec<ToolParameter>,
+    values: Vec<Option<String>>,
+}
+
+impl ArgValues {
+    /// Reads the arguments of a tool against its parameters.
+    pub fn parse(parameters: &[ToolParameter], args: &[String]) -> Result<ArgValues, Error> {
+        let values = read_values(parameters, args)?;
+        Ok(ArgValues { parameters: parameters.to_vec(), values: values })
+    }
+
+    // The parameter with the flag and its value, or its default value if it was not given.
+    fn value(&self, flag: &str) -> Result<(&ToolParameter, Option<String>), Error> {
+        let j = find_parameters(&self.parameters, flag)?[0];
+        let p = &self.parameters[j];
+        Ok((p, self.values[j].clone().or(p.default_value.clone())))
+    }
+
+    /// Returns true if the parameter was given, rather than taking its default value.
+    pub fn is_given(&self, flag: &str) -> Result<bool, Error> {
+        let j = find_parameters(&self.parameters, flag)?[0];
+        Ok(self.values[j].is_some())
+    }
+
+    /// Returns true if a boolean parameter is set.
+    pub fn get_bool(&self, flag: &str) -> Result<bool, Error> {
+        let (p, value) = self.value(flag)?;
+        match value {
+            None => Ok(false),
+            Some(v) => match v.to_lowercase().as_ref() {
+                "" | "true" => Ok(true),
+                "false" => Ok(false),
+                _ => Err(invalid(p, &format!("'{}' is not true or false", v)).into()),
+            },
+        }
+    }
+
+    pub fn get_string(&self, flag: &str) -> Result<Option<String>, Error> {
+        let (_, value) = self.value(flag)?;
+        Ok(value.filter(|v| !v.is_empty()))
+    }
+
+    /// Returns a file name, resolved against the working directory.
+    pub fn get_file(&self, flag: &str, working_directory: &str) -> Result<Option<String>, Error> {
+        Ok(self.get_string(flag)?.map(|v| full_path(&v, working_directory)))
+    }
+
+    pub fn get_f64(&self, flag: &str) -> Result<Option<f64>, Error> {
+        let (p, value) = self.value(flag)?;
+        match value {
+            None => Ok(None),
+            Some(v) => match v.trim().parse::<f64>() {
+                Ok(f) if f.is_finite() => Ok(Some(f)),
+                _ => Err(invalid(p, &format!("'{}' is not a number", v)).into()),
+            },
+        }
+    }
+
+    pub fn get_i64(&self, flag: &str) -> Result<Option<i64>, Error> {
+        let (p, value) = self.value(flag)?;
+        match value {
+            None => Ok(None),
+            Some(v) => match v.trim().parse::<i64>() {
+                Ok(i) => Ok(Some(i)),
+                Err(_) => match v.trim().parse::<f64>() {
+                    Ok(f) if f.is_finite() && f.fract() == 0f64 && f.abs() < 9.0e15 => Ok(Some(f as i64)),
+                    _ => Err(invalid(p, &format!("'{}' is not a whole number", v)).into()),
+                },
+            },
+        }
+    }
+
+    pub fn get_usize(&self, flag: &str) -> Result<Option<usize>, Error> {
+        let (p, _) = self.value(flag)?;
+        match self.get_i64(flag)? {
+            Some(i) if i < 0 => Err(invalid(p, &format!("{} is less than zero", i)).into()),
+            i => Ok(i.map(|i| i as usize)),
+        }
+    }
+
+    /// Returns the value of a parameter that must be given.
+    pub fn required_string(&self, flag: &str) -> Result<String, Error> {
+        match self.get_string(flag)? {
+            Some(v) => Ok(v),
+            None => {
+                let (p, _) = self.value(flag)?;
+                Err(ToolError::MissingParameter { name: p.name.clone(), flag: long_flag(p) }.into())
+            },
+        }
+    }
+
+    /// Returns a file name that must be given, resolved against the working directory.
+    pub fn required_file(&self, flag: &str, working_directory: &str) -> Result<String, Error> {
+        Ok(full_path(&self.required_string(flag)?, working_directory))
+    }
+}
+
+/// The values assigned to each of a tool's parameters. A flag shared by several
+/// parameters (e.g. `--filter` for both `--filterx` and `--filtery`) sets each of them,
+/// unless a parameter is also given by a flag of its own, which takes precedence.
+pub struct ParameterValues<'a> {
+    parameters: &'a [ToolParameter],
+    values: Vec<Option<String>>,
+    shared: Vec<bool>,
+}
+
+impl<'a> ParameterValues<'a> {
+    pub fn new(parameters: &'a [ToolParameter]) -> ParameterValues<'a> {
+        ParameterValues {
+            parameters: parameters,
+            values: parameters.iter().map(|_| None).collect(),
+            shared: parameters.iter().map(|_| false).collect(),
+        }
+    }
+
+    /// Sets the value of the parameter at `index`; `shared` indicates that the flag is
+    /// shared with other parameters.
+    pub fn set(&mut self, flag: &str, index: usize, value: String, shared: bool) -> Result<(), ToolError> {
+        if self.values[index].is_some() {
+            if shared && !self.shared[index] {
+                return Ok(());
+            }
+            if shared == self.shared[index] {
+                return Err(invalid(&self.parameters[index], &format!("the parameter was specified more than once ({})", flag)));
+            }
+        }
+        self.values[index] = Some(value);
+        self.shared[index] = shared;
+        Ok(())
+    }
+
+    pub fn into_values(self) -> Vec<Option<String>> {
+        self.values
+    }
+}
+
+/// Finds the parameters that are set by a flag. Flags are matched without regard to case
+/// or to the number of leading hyphens, so `-input` and `--input` are equivalent.
+pub fn find_parameters(parameters: &[ToolParameter], flag: &str) -> Result<Vec<usize>, ToolError> {
+    let key = normalize_flag(flag);
+    let indices: Vec<usize> = (0..parameters.len())
+        .filter(|&j| parameters[j].flags.iter().any(|f| normalize_flag(f) == key))
+        .collect();
+    if indices.is_empty() {
+        // suggest the closest flag, if there is a plausible one
+        let mut suggestion = None;
+        let mut min_dist = cmp::max(2, key.len() / 3) + 1;
+        for p in parameters {
+            for f in &p.flags {
+                let dist = edit_distance(&key, &normalize_flag(f));
+                if dist < min_dist {
+                    min_dist = dist;
+                    suggestion = Some(long_flag(p));
+                }
+            }
+        }
+        return Err(ToolError::UnrecognizedParameter { flag: flag.to_string(), suggestion: suggestion });
+    }
+    Ok(indices)
+}
+
+/// Checks the value assigned to each parameter against the parameter's type and returns the
+/// canonical arguments of the tool. Boolean parameters are passed as a bare flag when true
+/// and are omitted when false; parameters without a value are omitted, unless they are
+/// required, i.e. neither optional nor given a default value. The input files must share a
+/// coordinate reference system, where they specify one.
+pub fn validate_args(parameters: &[ToolParameter],
+                     values: Vec<Option<String>>,
+                     working_directory: &str)
+                     -> Result<Vec<String>, ToolError> {
+    // files are resolved first, since attribute fields are checked against their vector file
+    let mut resolved: Vec<Option<String>> = vec![];
+    for (p, value) in parameters.iter().zip(values.into_iter()) {
+        let value = match value {
+            Some(v) => v.trim().to_string(),
+            None => {
+                resolved.push(None);
+                continue;
+            }
+        };
+        let is_boolean = match p.parameter_type { ParameterType::Boolean => true, _ => false };
+        if value.is_empty() && !is_boolean {
+            return Err(invalid(p, "no value was given"));
+        }
+        let v = match p.parameter_type {
+            ParameterType::Boolean => {
+                match value.to_lowercase().as_ref() {
+                    "" | "true" => Some("true".to_string()),
+                    "false" => None,
+                    _ => return Err(invalid(p, &format!("'{}' is not true or false", value))),
+                }
+            },
+            ParameterType::Integer => {
+                let i = match value.parse::<i64>() {
+                    Ok(i) => i,
+                    Err(_) => {
+                        match value.parse::<f64>() {
+                            Ok(f) if f.is_finite() && f.fract() == 0f64 && f.abs() < 9.0e15 => f as i64,
+                            _ => return Err(invalid(p, &format!("'{}' is not a whole number", value))),
+                        }
+                    }
+                };
+                Some(i.to_string())
+            },
+            ParameterType::Float => {
+                match value.parse::<f64>() {
+                    Ok(f) if f.is_finite() => Some(value),
+                    _ => return Err(invalid(p, &format!("'{}' is not a number", value))),
+                }
+            },
+            ParameterType::OptionList(ref options) => {
+                if !options.iter().any(|o| matches_option(&value, o)) {
+                    return Err(invalid(p, &format!("'{}' is not one of the options: {}", value, options.join(", "))));
+                }
+                Some(value)
+            },
+            ParameterType::ExistingFile(ref ft) => {
+                Some(existing_file(p, ft, &value, working_directory)?)
+            },
+            ParameterType::ExistingFileOrFloat(ref ft) => {
+                match value.parse::<f64>() {
+                    Ok(f) if f.is_finite() => Some(value),
+                    _ => Some(existing_file(p, ft, &value, working_directory)?),
+                }
+            },
+            ParameterType::FileList(ref ft) => {
+                let files: Vec<&str> = if value.contains(";") {
+                    value.split(";").collect()
+                } else {
+                    value.split(",").collect()
+                };
+                let mut list = vec![];
+                for f in files.iter().map(|f| f.trim()).filter(|f| !f.is_empty()) {
+                    list.push(existing_file(p, ft, f, working_directory)?);
+                }
+                if list.is_empty() {
+                    return Err(invalid(p, "no files were given"));
+                }
+                Some(list.join(";"))
+            },
+            ParameterType::NewFile(_) => {
+                let file_name = full_path(&value, working_directory);
+                if !raster::is_in_memory_raster_name(&file_name) {
+                    if let Some(dir) = Path::new(&file_name).parent() {
+                        if !dir.as_os_str().is_empty() && !dir.is_dir() {
+                            return Err(invalid(p, &format!("the directory {} does not exist", dir.display())));
+                        }
+                    }
+                }
+                Some(file_name)
+            },
+            ParameterType::Directory => {
+                let dir = full_path(&value, working_directory);
+                if !Path::new(&dir).is_dir() {
+                    return Err(invalid(p, &format!("the directory {} does not exist", value)));
+                }
+                Some(dir)
+            },
+            ParameterType::String |
+            ParameterType::StringList |
+            ParameterType::StringOrNumber |
+            ParameterType::VectorAttributeField(_, _) => Some(value),
+        };
+        resolved.push(v);
+    }
+    check_spatial_refs(parameters, &resolved)?;
+
+    let mut args = vec![];
+    for (j, p) in parameters.iter().enumerate() {
+        let is_boolean = match p.parameter_type { ParameterType::Boolean => true, _ => false };
+        let value = match resolved[j] {
+            Some(ref v) => v.clone(),
+            None => {
+                let required = !is_boolean && p.default_value.is_none() && !p.optional;
+                if required {
+                    return Err(ToolError::MissingParameter { name: p.name.clone(), flag: long_flag(p) });
+                }
+                continue;
+            }
+        };
+        if let ParameterType::VectorAttributeField(ref attribute_type, ref vector_flag) = p.parameter_type {
+            let file_name = find_parameters(parameters, vector_flag).ok()
+                .and_then(|indices| resolved[indices[0]].clone());
+            if let Some(file_name) = file_name {
+                check_attribute_field(p, attribute_type, &value, &file_name)?;
+            }
+        }
+        let flag = long_flag(p);
+        if is_boolean {
+            args.push(flag);
+        } else if value.contains("=") {
+            // the tools split their arguments on the first '=', so the value is passed separately
+            args.push(flag);
+            args.push(value);
+        } else {
+            args.push(format!("{}={}", flag, value));
+        }
+    }
+    Ok(args)
+}
+
+/// The flag used to pass a parameter to a tool, i.e. the last of its flags, which is
+/// normally the long (--) form.
+pub fn long_flag(p: &ToolParameter) -> String {
+    p.flags[p.flags.len() - 1].clone()
+}
+
+pub fn normalize_flag(flag: &str) -> String {
+    flag.trim().trim_matches('-').to_lowercase()
+}
+
+/// Prefixes the working directory to a file name that does not contain a directory, as the
+/// tools themselves do.
+pub fn full_path(file_name: &str, working_directory: &str) -> String {
+    let sep: String = path::MAIN_SEPARATOR.to_string();
+    if !file_name.contains(&sep) && !file_name.contains("/") {
+        format!("{}{}", working_directory, file_name)
+    } else {
+        file_name.to_string()
+    }
+}
+
+fn invalid(p: &ToolParameter, message: &str) -> ToolError {
+    ToolError::InvalidParameter { parameter: p.name.clone(), message: message.to_string() }
+}
+
+// Input files must exist, allowing for the band selection suffix of raster file names
+// (e.g. image.tif:2).
+fn existing_file(p: &ToolParameter,
+                 file_type: &ParameterFileType,
+                 file_name: &str,
+                 working_directory: &str)
+                 -> Result<String, ToolError> {
+    let file_name = full_path(file_name, working_directory);
+    if raster::is_in_memory_raster_name(&file_name) || Path::new(&file_name).is_file() {
+        return Ok(file_name);
+    }
+    let has_bands = match *file_type {
+        ParameterFileType::Raster | ParameterFileType::RasterAndVector(_) | ParameterFileType::Any => true,
+        _ => false,
+    };
+    if has_bands {
+        if let Some(pos) = file_name.rfind(':') {
+            let band = file_name[(pos + 1)..].trim();
+            if !band.is_empty() && band.chars().all(|c| c.is_digit(10)) && Path::new(&file_name[..pos]).is_file() {
+                return Ok(file_name);
+            }
+        }
+    }
+    Err(invalid(p, &format!("the file {} does not exist", file_name)))
+}
+
+// The spatial input files of a tool, e.g. rasters that are overlaid, must share a
+// coordinate reference system. Files that do not specify a system are not checked, since
+// their coordinates are assumed to be compatible with the other inputs, but when there are
+// other inputs to compare with, a file whose system cannot be read is reported.
+fn check_spatial_refs(parameters: &[ToolParameter], values: &[Option<String>]) -> Result<(), ToolError> {
+    let mut inputs: Vec<(&ToolParameter, &str)> = vec![];
+    for (p, value) in parameters.iter().zip(values.iter()) {
+        let value = match *value {
+            Some(ref v) => v,
+            None => continue,
+        };
+        match p.parameter_type {
+            ParameterType::ExistingFile(ref ft) if is_spatial_file_type(ft) => inputs.push((p, value)),
+            ParameterType::ExistingFileOrFloat(ref ft) if is_spatial_file_type(ft) && value.parse::<f64>().is_err() => inputs.push((p, value)),
+            ParameterType::FileList(ref ft) if is_spatial_file_type(ft) => {
+                for file_name in value.split(";") {
+                    inputs.push((p, file_name));
+                }
+            },
+            _ => {},
+        }
+    }
+    if inputs.len() < 2 {
+        return Ok(());
+    }
+    let mut first: Option<(&str, SpatialRef)> = None;
+    for (p, file_name) in inputs {
+        let srs = match read_spatial_ref(file_name) {
+            Ok(Some(srs)) => srs,
+            Ok(None) => continue,
+            Err(e) => return Err(invalid(p, &format!("the coordinate reference system of {} could not be determined, \
+                so it cannot be compared with those of the other inputs: {}", file_name, e))),
+        };
+        match first {
+            None => first = Some((file_name, srs)),
+            Some((first_file, ref first_srs)) => {
+                if !srs.is_equivalent(first_srs) {
+                    return Err(invalid(p, &format!("the coordinate reference system of {} ({}) differs from that of {} ({}). \
+                        The ReprojectRaster and ReprojectLidar tools may be used to transform the inputs into a common system.",
+                        file_name, srs, first_file, first_srs)));
+                }
+            },
+        }
+    }
+    Ok(())
+}
+
+fn is_spatial_file_type(file_type: &ParameterFileType) -> bool {
+    match *file_type {
+        ParameterFileType::Lidar |
+        ParameterFileType::Raster |
+        ParameterFileType::Vector(_) |
+        ParameterFileType::RasterAndVector(_) => true,
+        _ => false,
+    }
+}
+
+fn check_attribute_field(p: &ToolParameter,
+                         attribute_type: &AttributeType,
+                         field_name: &str,
+                         vector_file: &str)
+                         -> Result<(), ToolError> {
+    // the tool will report an unreadable table itself
+    let table = if is_geojson_file(vector_file) || is_geopackage_file(vector_file) {
+        match Shapefile::new(vector_file, "r") {
+            Ok(sf) => sf.attributes,
+            Err(_) => return Ok(()),
+        }
+    } else {
+        let dbf_file = Path::new(vector_file).with_extension("dbf");
+        if !dbf_file.is_file() {
+            return Ok(());
+        }
+        match AttributeTable::read(&dbf_file.to_string_lossy()) {
+            Ok(t) => t,
+            Err(_) => return Ok(()),
+        }
+    };
+    let field = match table.get_field_num(field_name) {
+        Some(i) => &table.fields[i],
+        None => {
+            let names: Vec<String> = table.fields.iter().map(|f| f.name.clone()).collect();
+            return Err(invalid(p, &format!("the field '{}' was not found; the fields of {} are: {}",
+                                            field_name, vector_file, names.join(", "))));
+        }
+    };
+    let matches_type = match *attribute_type {
+        AttributeType::Any => true,
+        AttributeType::Integer => field.field_type == 'N' && field.decimal_count == 0,
+        AttributeType::Float | AttributeType::Number => field.field_type == 'N' || field.field_type == 'F',
+        AttributeType::Text => field.field_type == 'C',
+        AttributeType::Boolean => field.field_type == 'L',
+        AttributeType::Date => field.field_type == 'D',
+    };
+    if !matches_type {
+        return Err(invalid(p, &format!("the field '{}' is not of type {:?}", field_name, attribute_type)));
+    }
+    Ok(())
+}
+
+// Option values are matched without regard to case, and the tools also accept abbreviations
+// of an option, e.g. 'sca' for 'Specific Contributing Area', 'v' for 'vertical', or 'JandR'
+// for 'Johnston and Rosenfeld'; i.e. the letters of the value must appear in order within the
+// option, beginning with its first letter.
+fn matches_option(value: &str, option: &str) -> bool {
+    let value: Vec<char> = value.to_lowercase().chars().filter(|c| !c.is_whitespace() && *c != '_').collect();
+    let option: Vec<char> = option.to_lowercase().chars().filter(|c| !c.is_whitespace() && *c != '_').collect();
+    if value.is_empty() || option.is_empty() || value[0] != option[0] {
+        return false;
+    }
+    let mut j = 0;
+    for c in option {
+        if j < value.len() && c == value[j] {
+            j += 1;
+        }
+    }
+    j == value.len()
+}
+
+// The Levenshtein distance between two strings.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let mut prev: Vec<usize> = (0..(b.len() + 1)).collect();
+    let mut current = vec![0; b.len() + 1];
+    for i in 0..a.len() {
+        current[0] = i + 1;
+        for j in 0..b.len() {
+            let cost = if a[i] == b[j] { 0 } else { 1 };
+            current[j + 1] = cmp::min(cmp::min(prev[j + 1] + 1, current[j] + 1), prev[j] + cost);
+        }
+        prev.clone_from(&current);
+    }
+    prev[b.len()]
+}
+
+/// The value of a tool parameter, used when running a tool with `ToolManager::run_tool_with_args`.
+pub enum ParameterValue {
+    Boolean(bool),
+    Integer(i64),
+    Float(f64),
+    String(String),
+    StringList(Vec<String>),
+    /// An in-memory raster, used in place of an input raster file. The raster is shared with
+    /// the tool, which reads its own copy of it.
+    Raster(Arc<Raster>),
+    /// Requests that an output raster be returned in memory, in `ToolOutput::rasters`,
+    /// rather than written to disk.
+    InMemoryRaster,
+}
+
+impl From<bool> for ParameterValue {
+    fn from(v: bool) -> ParameterValue { ParameterValue::Boolean(v) }
+}
+
+impl From<i32> for ParameterValue {
+    fn from(v: i32) -> ParameterValue { ParameterValue::Integer(v as i64) }
+}
+
+impl From<i64> for ParameterValue {
+    fn from(v: i64) -> ParameterValue { ParameterValue::Integer(v) }
+}
+
+impl From<f64> for ParameterValue {
+    fn from(v: f64) -> ParameterValue { ParameterValue::Float(v) }
+}
+
+impl<'a> From<&'a str> for ParameterValue {
+    fn from(v: &'a str) -> ParameterValue { ParameterValue::String(v.to_string()) }
+}
+
+impl From<String> for ParameterValue {
+    fn from(v: String) -> ParameterValue { ParameterValue::String(v) }
+}
+
+impl From<Vec<String>> for ParameterValue {
+    fn from(v: Vec<String>) -> ParameterValue { ParameterValue::StringList(v) }
+}
+
+impl From<Raster> for ParameterValue {
+    fn from(v: Raster) -> ParameterValue { ParameterValue::Raster(Arc::new(v)) }
+}
+
+impl From<Arc<Raster>> for ParameterValue {
+    fn from(v: Arc<Raster>) -> ParameterValue { ParameterValue::Raster(v) }
+}
+
+/// The arguments of a tool, keyed by parameter flag without the leading hyphens,
+/// e.g. `input` or `i` for a parameter with the flags `-i` and `--input`.
+///
+/// # Examples
+///
+/// ```no_run
+/// # extern crate whitebox_tools;
+/// # use whitebox_tools::raster::Raster;
+/// # use whitebox_tools::tools::{ParameterValue, ToolArgs, ToolManager};
+/// # fn main() { run().unwrap(); }
+/// # fn run() -> Result<(), std::io::Error> {
+/// let tm = ToolManager::new("/path/to/data/", &false)?;
+/// let dem = Raster::new("/path/to/data/DEM.tif", "r")?;
+/// let mut args = ToolArgs::new();
+/// args.set("dem", dem).set("output", ParameterValue::InMemoryRaster).set("zfactor", 1.0);
+/// let mut result = tm.run_tool_with_args("Slope", args)?;
+/// let slope = result.rasters.remove("output").unwrap();
+/// # Ok(())
+/// # }
+/// ```
+pub struct ToolArgs {
+    values: BTreeMap<String, ParameterValue>,
+}
+
+impl ToolArgs {
+    pub fn new() -> ToolArgs {
+        ToolArgs { values: BTreeMap::new() }
+    }
+
+    pub fn set<T: Into<ParameterValue>>(&mut self, flag: &str, value: T) -> &mut ToolArgs {
+        self.values.insert(normalize_flag(flag), value.into());
+        self
+    }
+
+    pub fn get(&self, flag: &str) -> Option<&ParameterValue> {
+        self.values.get(&normalize_flag(flag))
+    }
+
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+}
+
+/// The outputs of a tool run using `ToolManager::run_tool_with_args`. Both maps are keyed
+/// by the long flag of the output parameter without the leading hyphens, e.g. `output`.
+pub struct ToolOutput {
+    /// The full path of each output file written to disk.
+    pub files: BTreeMap<String, String>,
+    /// Each output raster that was requested in memory using `ParameterValue::InMemoryRaster`.
+    pub rasters: BTreeMap<String, Raster>,
+}
+
+/// An error raised when a tool's arguments are invalid or the tool fails.
+#[derive(Debug)]
+pub enum ToolError {
+    UnrecognizedTool(String),
+    /// A flag that does not belong to any of the tool's parameters, along with the
+    /// closest matching flag, if there is one.
+    UnrecognizedParameter { flag: String, suggestion: Option<String> },
+    MissingParameter { name: String, flag: String },
+    InvalidParameter { parameter: String, message: String },
+    /// The tool returned an error.
+    Failed(Error),
+    /// The tool panicked; the message of the panic is included.
+    Panicked(String),
+}
+
+impl fmt::Display for ToolError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ToolError::UnrecognizedTool(ref name) => write!(f, "Unrecognized tool name {}.", name),
+            ToolError::UnrecognizedParameter { ref flag, ref suggestion } => {
+                match *suggestion {
+                    Some(ref s) => write!(f, "Unrecognized parameter {}; did you mean {}?", flag, s),
+                    None => write!(f, "Unrecognized parameter {}.", flag),
+                }
+            },
+            ToolError::MissingParameter { ref name, ref flag } => write!(f, "The required parameter '{}' ({}) was not specified.", name, flag),
+            ToolError::InvalidParameter { ref parameter, ref message } => write!(f, "Invalid value for parameter '{}': {}", parameter, message),
+            ToolError::Failed(ref e) => write!(f, "{}", e),
+            ToolError::Panicked(ref message) => write!(f, "The tool stopped unexpectedly: {}", message),
+        }
+    }
+}
+
+impl error::Error for ToolError {
+    fn description(&self) -> &str {
+        match *self {
+            ToolError::UnrecognizedTool(_) => "unrecognized tool",
+            ToolError::UnrecognizedParameter { .. } => "unrecognized parameter",
+            ToolError::MissingParameter { .. } => "missing parameter",
+            ToolError::InvalidParameter { .. } => "invalid parameter",
+            ToolError::Failed(_) => "tool failed",
+            ToolError::Panicked(_) => "tool panicked",
+        }
+    }
+}
+
+impl From<ToolError> for Error {
+    fn from(e: ToolError) -> Error {
+        match e {
+            ToolError::Failed(e) => e,
+            ToolError::UnrecognizedTool(_) => Error::new(ErrorKind::NotFound, format!("{}", e)),
+            ToolError::Panicked(_) => Error::new(ErrorKind::Other, format!("{}", e)),
+            _ => Error::new(ErrorKind::InvalidInput, format!("{}", e)),
+        }
+    }
+}
+
+fn is_raster_file_type(ft: &ParameterFileType) -> bool {
+    match *ft {
+        ParameterFileType::Raster | ParameterFileType::RasterAndVector(_) => true,
+        _ => false,
+    }
+}
+
+/// Validates the arguments of a tool against its parameters, runs the tool, and collects
+/// its outputs. The typed values are converted to command-line values and checked by the
+/// same parser used for the tool's command-line arguments. In-memory input rasters are made
+/// available to the tool for the duration of the run, and in-memory output rasters are
+/// intercepted when the tool writes them.
+pub fn run_with_args(tool: Box<WhiteboxTool>,
+                     args: ToolArgs,
+                     working_directory: &str,
+                     verbose: bool,
+                     sink: &Arc<ProgressSink>)
+                     -> Result<ToolOutput, ToolError> {
+    let tool_name = tool.get_tool_name();
+    let parameters = get_parameters(&tool).map_err(ToolError::Failed)?;
+
+    let mut values = ParameterValues::new(&parameters);
+    let mut memory_inputs = vec![];
+    let mut memory_outputs = vec![];
+    let mut output = ToolOutput { files: BTreeMap::new(), rasters: BTreeMap::new() };
+    for (flag, value) in args.values {
+        let indices = find_parameters(&parameters, &flag)?;
+        let shared = indices.len() > 1;
+        for &j in &indices {
+            let p = &parameters[j];
+            let key = normalize_flag(&long_flag(p));
+            let v = match (&p.parameter_type, &value) {
+                (&ParameterType::Boolean, &ParameterValue::Boolean(b)) => b.to_string(),
+                (&ParameterType::Integer, &ParameterValue::Integer(i)) |
+                (&ParameterType::Float, &ParameterValue::Integer(i)) |
+                (&ParameterType::StringOrNumber, &ParameterValue::Integer(i)) |
+                (&ParameterType::ExistingFileOrFloat(_), &ParameterValue::Integer(i)) => i.to_string(),
+                (&ParameterType::Integer, &ParameterValue::Float(f)) |
+                (&ParameterType::Float, &ParameterValue::Float(f)) |
+                (&ParameterType::StringOrNumber, &ParameterValue::Float(f)) |
+                (&ParameterType::ExistingFileOrFloat(_), &ParameterValue::Float(f)) => f.to_string(),
+                (&ParameterType::StringList, &ParameterValue::StringList(ref v)) => v.join(","),
+                (&ParameterType::FileList(_), &ParameterValue::StringList(ref v)) => v.join(";"),
+                (&ParameterType::NewFile(_), &ParameterValue::String(ref s)) => {
+                    output.files.insert(key, full_path(s, working_directory));
+                    s.clone()
+                },
+                (&ParameterType::Boolean, &ParameterValue::String(_)) |
+                (&ParameterType::Integer, &ParameterValue::String(_)) |
+                (&ParameterType::Float, &ParameterValue::String(_)) => {
+                    return Err(invalid(p, &format!("the value does not match the parameter type {:?}", p.parameter_type)));
+                },
+                (_, &ParameterValue::String(ref s)) => s.clone(),
+                (&ParameterType::ExistingFile(ref ft), &ParameterValue::Raster(ref r)) |
+                (&ParameterType::ExistingFileOrFloat(ref ft), &ParameterValue::Raster(ref r)) if is_raster_file_type(ft) => {
+                    let name = format!("{}{}/{}.tif", raster::IN_MEMORY_RASTER_PREFIX, tool_name, key);
+                    memory_inputs.push((name.clone(), r.clone()));
+                    name
+                },
+                (&ParameterType::NewFile(ref ft), &ParameterValue::InMemoryRaster) if is_raster_file_type(ft) => {
+                    let name = format!("{}{}/{}.tif", raster::IN_MEMORY_RASTER_PREFIX, tool_name, key);
+                    memory_outputs.push((key, name.clone()));
+                    name
+                },
+                (t, _) => {
+                    return Err(invalid(p, &format!("the value does not match the parameter type {:?}", t)));
+                },
+            };
+            values.set(&flag, j, v, shared)?;
+        }
+    }
+
+    // the in-memory inputs are set before the arguments are validated, so that their
+    // coordinate reference systems can be compared with those of the other inputs, and
+    // are removed again if the arguments are not valid
+    let mut input_names = vec![];
+    for (name, r) in memory_inputs {
+        raster::set_in_memory_raster(&name, r);
+        input_names.push(name);
+    }
+    let tool_args = match validate_args(&parameters, values.into_values(), working_directory) {
+        Ok(tool_args) => tool_args,
+        Err(e) => {
+            for name in &input_names {
+                raster::take_in_memory_raster(name);
+            }
+            return Err(e);
+        },
+    };
+
+    let working_directory = working_directory.to_string();
+    sink.started(&tool_name);
+    let result = panic::catch_unwind(panic::AssertUnwindSafe(|| tool.run(tool_args, &working_directory, verbose, sink)));
+    for name in &input_names {
+        raster::take_in_memory_raster(name);
+    }
+    for (key, name) in memory_outputs {
+        if let Some(r) = raster::take_in_memory_raster(&name) {
+            output.rasters.insert(key, r);
+        }
+    }
+
+    let result = match result {
+        Ok(r) => r,
+        Err(payload) => {
+            let message = if let Some(s) = payload.downcast_ref::<&str>() {
+                s.to_string()
+            } else if let Some(s) = payload.downcast_ref::<String>() {
+                s.clone()
+            } else {
+                "unknown error".to_string()
+            };
+            let e = Error::new(ErrorKind::Other, message.clone());
+            sink.finished(&tool_name, &Err(e));
+            return Err(ToolError::Panicked(message));
+        },
+    };
+    sink.finished(&tool_name, &result);
+    match result {
+        Ok(()) => Ok(output),
+        Err(e) => Err(ToolError::Failed(e)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use std::fs;
+    use raster::{DataType, RasterConfigs};
+    use tools::{StdoutProgress, ToolManager};
+
+    fn quiet_manager() -> ToolManager {
+        let mut tm = ToolManager::new("", &false).unwrap();
+        let mut sink = StdoutProgress::new();
+        sink.set_quiet(true);
+        tm.set_progress_sink(Arc::new(sink));
+        tm
+    }
+
+    fn test_raster() -> Raster {
+        let mut configs = RasterConfigs { ..Default::default() };
+        configs.rows = 4;
+        configs.columns = 5;
+        configs.nodata = -9999f64;
+        configs.north = 40f64;
+        configs.south = 0f64;
+        configs.west = 0f64;
+        configs.east = 50f64;
+        configs.resolution_x = 10f64;
+        configs.resolution_y = 10f64;
+        configs.data_type = DataType::F32;
+        let mut r = Raster::initialize_using_config("test.tif", &configs);
+        for row in 0..4 {
+            for col in 0..5 {
+                r.set_value(row, col, if row == col { -9999f64 } else { (row * 5 + col) as f64 });
+            }
+        }
+        r
+    }
+
+    #[test]
+    fn tool_args_normalize_flags() {
+        let mut args = ToolArgs::new();
+        args.set("--Input", "dem.tif").set("-z", 1.5).set("clip", true);
+        assert_eq!(args.len(), 3);
+        match args.get("input") {
+            Some(&ParameterValue::String(ref s)) => assert_eq!(s, "dem.tif"),
+            _ => panic!("expected a string"),
+        }
+        match args.get("--Z") {
+            Some(&ParameterValue::Float(f)) => assert_eq!(f, 1.5),
+            _ => panic!("expected a float"),
+        }
+        args.set("input", "other.tif");
+        assert_eq!(args.len(), 3);
+    }
+
+    #[test]
+    fn in_memory_rasters_are_passed_to_and_returned_from_tools() {
+        let tm = quiet_manager();
+        let input = Arc::new(test_raster());
+        let mut args = ToolArgs::new();
+        args.set("input1", input.clone()).set("input2", 2.5).set("output", ParameterValue::InMemoryRaster);
+        let mut result = tm.run_tool_with_args("Add", args).unwrap();
+        assert!(result.files.is_empty());
+        let output = result.rasters.remove("output").unwrap();
+        assert_eq!((output.configs.rows, output.configs.columns), (4, 5));
+        for row in 0..4 {
+            for col in 0..5 {
+                let expected = if row == col { output.configs.nodata } else { input.get_value(row, col) + 2.5 };
+                assert_eq!(output.get_value(row, col), expected);
+            }
+        }
+        // neither the input nor the output is left behind in memory
+        assert!(raster::get_in_memory_raster("memory://Add/input1.tif").is_none());
+        assert!(raster::get_in_memory_raster("memory://Add/output.tif").is_none());
+    }
+
+    #[test]
+    fn output_files_are_reported() {
+        let tm = quiet_manager();
+        let file_name = env::temp_dir().join("wbt_tool_args_output.tif").to_str().unwrap().to_string();
+        let mut args = ToolArgs::new();
+        args.set("input1", test_raster()).set("input2", test_raster()).set("output", file_name.as_ref());
+        let result = tm.run_tool_with_args("Add", args).unwrap();
+        assert_eq!(result.files.get("output"), Some(&file_name));
+        assert!(result.rasters.is_empty());
+        let output = Raster::new(&file_name, "r").unwrap();
+        assert_eq!(output.get_value(0, 1), 2f64);
+        let _ = fs::remove_file(&file_name);
+    }
+
+    #[test]
+    fn typed_arguments_are_validated() {
+        let tm = quiet_manager();
+        match tm.run_tool_with_args("NoSuchTool", ToolArgs::new()) {
+            Err(ToolError::UnrecognizedTool(name)) => assert_eq!(name, "NoSuchTool"),
+            _ => panic!("expected an unrecognized tool"),
+        }
+
+        let mut args = ToolArgs::new();
+        args.set("input1", test_raster()).set("input2", true).set("output", ParameterValue::InMemoryRaster);
+        match tm.run_tool_with_args("Add", args) {
+            Err(ToolError::InvalidParameter { .. }) => {},
+            _ => panic!("expected an invalid parameter"),
+        }
+
+        let mut args = ToolArgs::new();
+        args.set("inptu1", test_raster());
+        match tm.run_tool_with_args("Add", args) {
+            Err(ToolError::UnrecognizedParameter { flag, suggestion }) => {
+                assert_eq!(flag, "inptu1");
+                assert_eq!(suggestion, Some("--input1".to_string()));
+            },
+            _ => panic!("expected an unrecognized parameter"),
+        }
+
+        let mut args = ToolArgs::new();
+        args.set("input1", test_raster()).set("input2", 1.0);
+        match tm.run_tool_with_args("Add", args) {
+            Err(ToolError::MissingParameter { flag, .. }) => assert_eq!(flag, "--output"),
+            _ => panic!("expected a missing parameter"),
+        }
+        // the in-memory input of a rejected call is not left behind
+        assert!(raster::get_in_memory_raster("memory://Add/input1.tif").is_none());
+    }
+
+    fn parameter(name: &str, flags: &[&str], parameter_type: ParameterType, default_value: Option<&str>, optional: bool) -> ToolParameter {
+        ToolParameter {
+            name: name.to_string(),
+            flags: flags.iter().map(|f| f.to_string()).collect(),
+            description: String::new(),
+            parameter_type: parameter_type,
+            default_value: default_value.map(|v| v.to_string()),
+            optional: optional,
+        }
+    }
+
+    fn test_parameters() -> Vec<ToolParameter> {
+        vec![
+            parameter("Input File", &["-i", "--input"], ParameterType::ExistingFile(ParameterFileType::Raster), None, false),
+            parameter("Output File", &["-o", "--output"], ParameterType::NewFile(ParameterFileType::Raster), None, false),
+            parameter("Filter X", &["--filter", "--filterx"], ParameterType::Integer, Some("11"), true),
+            parameter("Filter Y", &["--filter", "--filtery"], ParameterType::Integer, Some("11"), true),
+            parameter("Z Factor", &["--zfactor"], ParameterType::Float, None, true),
+            parameter("Clip", &["--clip"], ParameterType::Boolean, None, true),
+            parameter("Method", &["--method"], ParameterType::OptionList(vec!["Specific Contributing Area".to_string(), "Total Contributing Area".to_string()]), Some("Specific Contributing Area"), true),
+            parameter("Name", &["--name"], ParameterType::String, None, true),
+            parameter("Inputs", &["--inputs"], ParameterType::FileList(ParameterFileType::Raster), None, true),
+        ]
+    }
+
+    fn strings(args: &[&str]) -> Vec<String> {
+        args.iter().map(|a| a.to_string()).collect()
+    }
+
+    #[test]
+    fn quotes_enclosing_a_value_are_stripped() {
+        assert_eq!(strip_quotes("\"dem.tif\""), "dem.tif");
+        assert_eq!(strip_quotes(" 'dem.tif' "), "dem.tif");
+        assert_eq!(strip_quotes("'"), "'");
+        assert_eq!(strip_quotes("\"dem.tif'"), "\"dem.tif'");
+        assert_eq!(strip_quotes("GEOGCS[\"WGS 84\"]"), "GEOGCS[\"WGS 84\"]");
+        assert_eq!(strip_quotes("'GEOGCS[\"WGS 84\"]'"), "GEOGCS[\"WGS 84\"]");
+    }
+
+    #[test]
+    fn values_are_read_by_any_flag() {
+        let parameters = test_parameters();
+        let args = ArgValues::parse(&parameters, &strings(&["-I='dem.tif'", "--output", "out.tif", "--filtery=7",
+            "-filter", "5", "--zfactor", "-2.5", "--clip", "--name=a=b"])).unwrap();
+        assert_eq!(args.required_string("--input").unwrap(), "dem.tif");
+        assert_eq!(args.required_file("-o", "/data/").unwrap(), "/data/out.tif");
+        // a parameter's own flag takes precedence over a shared one, whatever their order
+        assert_eq!(args.get_usize("--filterx").unwrap(), Some(5));
+        assert_eq!(args.get_usize("--filtery").unwrap(), Some(7));
+        assert_eq!(args.get_f64("zfactor").unwrap(), Some(-2.5));
+        assert_eq!(args.get_bool("--clip").unwrap(), true);
+        assert_eq!(args.get_string("--name").unwrap(), Some("a=b".to_string()));
+        assert_eq!(args.get_string("--method").unwrap(), Some("Specific Contributing Area".to_string()));
+        assert_eq!(args.get_string("--inputs").unwrap(), None);
+        assert!(args.is_given("--filtery").unwrap());
+        assert!(!args.is_given("--method").unwrap());
+
+        let args = ArgValues::parse(&parameters, &strings(&["--clip", "false", "--filterx=-3", "--zfactor=x"])).unwrap();
+        assert_eq!(args.get_bool("--clip").unwrap(), false);
+        assert_eq!(args.get_i64("--filterx").unwrap(), Some(-3));
+        assert!(args.get_usize("--filterx").is_err());
+        assert_eq!(args.get_usize("--filtery").unwrap(), Some(11));
+        assert!(args.get_f64("--zfactor").is_err());
+        assert!(args.required_string("--input").is_err());
+        assert!(args.get_bool("--unknown").is_err());
+    }
+
+    #[test]
+    fn malformed_arguments_are_rejected() {
+        let parameters = test_parameters();
+        for args in [vec!["--filterx=3", "--filterx=4"], vec!["dem.tif"], vec!["--output"], vec!["--input", "--clip"]].iter() {
+            assert!(ArgValues::parse(&parameters, &strings(args)).is_err(), "{:?}", args);
+        }
+        match find_parameters(&parameters, "--ouptut") {
+            Err(ToolError::UnrecognizedParameter { suggestion, .. }) => assert_eq!(suggestion, Some("--output".to_string())),
+            _ => panic!("expected an unrecognized parameter"),
+        }
+        match find_parameters(&parameters, "--elevation") {
+            Err(ToolError::UnrecognizedParameter { suggestion, .. }) => assert_eq!(suggestion, None),
+            _ => panic!("expected an unrecognized parameter"),
+        }
+        assert_eq!(find_parameters(&parameters, "--FILTER").unwrap(), vec![2, 3]);
+    }
+
+    #[test]
+    fn arguments_are_validated_and_made_canonical() {
+        let dir = env::temp_dir().join("wbt_arg_parser");
+        let _ = fs::create_dir_all(&dir);
+        let wd = format!("{}{}", dir.to_str().unwrap(), path::MAIN_SEPARATOR);
+        for f in ["a.tif", "b.tif"].iter() {
+            let mut r = test_raster();
+            r.set_file_name(dir.join(f).to_str().unwrap());
+            r.write().unwrap();
+        }
+        let parameters = test_parameters();
+        let validate = |args: &[&str]| {
+            let values = read_values(&parameters, &strings(args))?;
+            validate_args(&parameters, values, &wd)
+        };
+
+        let args = validate(&["-i=a.tif", "-o=out.tif", "--filter=5.0", "--method=tca", "--clip=false",
+                              "--name=x=1", "--inputs=a.tif, b.tif:2"]).unwrap();
+        assert_eq!(args, vec![format!("--input={}a.tif", wd), format!("--output={}out.tif", wd),
+            "--filterx=5".to_string(), "--filtery=5".to_string(), "--method=tca".to_string(),
+            "--name".to_string(), "x=1".to_string(), format!("--inputs={0}a.tif;{0}b.tif:2", wd)]);
+
+        match validate(&["-o=out.tif"]) {
+            Err(ToolError::MissingParameter { flag, .. }) => assert_eq!(flag, "--input"),
+            _ => panic!("expected a missing parameter"),
+        }
+        let invalid_args: Vec<Vec<&str>> = vec![
+            vec!["-i=c.tif", "-o=out.tif"],
+            vec!["-i=a.tif:x", "-o=out.tif"],
+            vec!["-i=a.tif", "-o=/no/such/directory/out.tif"],
+            vec!["-i=a.tif", "-o=out.tif", "--filterx=2.5"],
+            vec!["-i=a.tif", "-o=out.tif", "--zfactor=inf"],
+            vec!["-i=a.tif", "-o=out.tif", "--method=area"],
+            vec!["-i=a.tif", "-o=out.tif", "--clip=maybe"],
+            vec!["-i=a.tif", "-o=out.tif", "--name="],
+            vec!["-i=a.tif", "-o=out.tif", "--inputs=;"],
+        ];
+        for args in &invalid_args {
+            match validate(args) {
+                Err(ToolError::InvalidParameter { .. }) => {},
+                r => panic!("{:?} gave {:?}", args, r),
+            }
+        }
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn options_may_be_abbreviated() {
+        assert!(matches_option("sca", "Specific Contributing Area"));
+        assert!(matches_option("specific_contributing_area", "Specific Contributing Area"));
+        assert!(matches_option("V", "vertical"));
+        assert!(matches_option("JandR", "Johnston and Rosenfeld"));
+        assert!(!matches_option("ca", "Specific Contributing Area"));
+        assert!(!matches_option("", "vertical"));
+        assert!(!matches_option("verticals", "vertical"));
+        assert_eq!(edit_distance("ouptut", "output"), 2);
+        assert_eq!(edit_distance("", "abc"), 3);
+        assert_eq!(edit_distance("input", "input"), 0);
+    }
+
+    #[test]
+    fn inputs_must_share_a_coordinate_reference_system() {
+        let dir = env::temp_dir().join("wbt_arg_parser_crs");
+        let _ = fs::create_dir_all(&dir);
+        let wd = format!("{}{}", dir.to_str().unwrap(), path::MAIN_SEPARATOR);
+        for &(f, epsg_code) in [("none.tif", 0u16), ("utm17.tif", 26917u16), ("utm17b.tif", 26917u16), ("utm18.tif", 26918u16)].iter() {
+            let mut r = test_raster();
+            r.configs.epsg_code = epsg_code;
+            r.set_file_name(dir.join(f).to_str().unwrap());
+            r.write().unwrap();
+        }
+        let parameters = test_parameters();
+        let validate = |inputs: &str| {
+            let values = read_values(&parameters, &strings(&["-i=none.tif", "-o=out.tif", inputs]))?;
+            validate_args(&parameters, values, &wd)
+        };
+        // files without a system are not compared
+        assert!(validate("--inputs=utm17.tif;utm17b.tif").is_ok());
+        match validate("--inputs=utm17.tif;utm18.tif") {
+            Err(ToolError::InvalidParameter { parameter, message }) => {
+                assert_eq!(parameter, "Inputs");
+                assert!(message.contains("utm18.tif") && message.contains("differs"), "{}", message);
+            },
+            r => panic!("expected an invalid parameter, not {:?}", r),
+        }
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
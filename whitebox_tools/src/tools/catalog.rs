@@ -0,0 +1,144 @@
+/*
+This code is part of the WhiteboxTools geospatial analysis library.
+Authors: Dr. John Lindsay
+Created: March 25, 2018
+Last Modified: March 25, 2018
+License: MIT
+*/
+
+use std::io::{Error, ErrorKind};
+use serde_json;
+use tools::arg_parser;
+use tools::{ToolManager, ToolParameter};
+
+// The catalog is a single JSON document describing every tool, e.g.
+//
+// {
+//   "version": "0.5.0",
+//   "tools": [
+//     {
+//       "name": "Slope",
+//       "toolbox": "Geomorphometric Analysis",
+//       "description": "Calculates a slope raster from an input DEM.",
+//       "example_usage": ">>./whitebox_tools -r=Slope -v --wd=\"/path/to/data/\" --dem=DEM.tif -o=output.tif",
+//       "source_file": "src/tools/terrain_analysis/slope.rs",
+//       "parameters": [
+//         {
+//           "name": "Input DEM File",
+//           "flags": ["-i", "--input", "--dem"],
+//           "description": "Input raster DEM file.",
+//           "parameter_type": { "ExistingFile": "Raster" },
+//           "default_value": null,
+//           "optional": false
+//         },
+//         ...
+//       ]
+//     },
+//     ...
+//   ]
+// }
+//
+// Parameters are described exactly as they are by --toolparameters, i.e. the parameter_type
+// is the serialized form of ParameterType, with any file type given as a ParameterFileType.
+
+#[derive(Serialize, Debug)]
+struct Catalog {
+    version: String,
+    tools: Vec<CatalogEntry>,
+}
+
+#[derive(Serialize, Debug)]
+struct CatalogEntry {
+    name: String,
+    toolbox: String,
+    description: String,
+    example_usage: String,
+    source_file: String,
+    parameters: Vec<ToolParameter>,
+}
+
+/// Returns the catalog of all of the tools, i.e. each tool's name, toolbox, description,
+/// example usage, and parameters, as a JSON document.
+pub fn get_catalog(tm: &ToolManager) -> Result<String, Error> {
+    const VERSION: Option<&'static str> = option_env!("CARGO_PKG_VERSION");
+    let mut tools = Vec::with_capacity(tm.tool_names.len());
+    for tool_name in &tm.tool_names {
+        let tool = match tm.get_tool(tool_name) {
+            Some(tool) => tool,
+            None => {
+                return Err(Error::new(ErrorKind::NotFound,
+                                      format!("Unrecognized tool name {}.", tool_name)))
+            }
+        };
+        // the parameters are read into their typed form, which checks each tool's parameter descriptions
+        let parameters = match arg_parser::get_parameters(&tool) {
+            Ok(p) => p,
+            Err(e) => {
+                return Err(Error::new(ErrorKind::InvalidData,
+                                      format!("The parameters of {} could not be read: {}", tool_name, e)))
+            }
+        };
+        tools.push(CatalogEntry {
+            name: tool.get_tool_name(),
+            toolbox: tool.get_toolbox(),
+            description: tool.get_tool_description(),
+            example_usage: tool.get_example_usage(),
+            source_file: tool.get_source_file(),
+            parameters: parameters,
+        });
+    }
+    let catalog = Catalog {
+        version: VERSION.unwrap_or("unknown").to_string(),
+        tools: tools,
+    };
+    match serde_json::to_string_pretty(&catalog) {
+        Ok(s) => Ok(s),
+        Err(e) => Err(Error::new(ErrorKind::Other, format!("{}", e))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::Value;
+
+    #[test]
+    fn the_catalog_describes_every_tool() {
+        let tm = ToolManager::new("", &false).unwrap();
+        let catalog: Value = serde_json::from_str(&get_catalog(&tm).unwrap()).unwrap();
+        assert_eq!(catalog["version"].as_str(), Some(env!("CARGO_PKG_VERSION")));
+        let tools = catalog["tools"].as_array().unwrap();
+        assert_eq!(tools.len(), tm.tool_names.len());
+        let fields = ["default_value", "description", "flags", "name", "optional", "parameter_type"];
+        for (entry, tool_name) in tools.iter().zip(tm.tool_names.iter()) {
+            let tool = tm.get_tool(tool_name).unwrap();
+            assert_eq!(entry["name"].as_str(), Some(tool_name.as_ref()));
+            assert_eq!(entry["toolbox"].as_str(), Some(tool.get_toolbox().as_ref()));
+            assert!(!entry["description"].as_str().unwrap().is_empty(), "{}", tool_name);
+            assert!(entry["example_usage"].is_string() && entry["source_file"].is_string(), "{}", tool_name);
+
+            // the parameters are those given by --toolparameters
+            let expected: Value = serde_json::from_str(&tool.get_tool_parameters()).unwrap();
+            let expected = expected["parameters"].as_array().unwrap();
+            let parameters = entry["parameters"].as_array().unwrap();
+            assert_eq!(parameters.len(), expected.len(), "{}", tool_name);
+            for (p, e) in parameters.iter().zip(expected.iter()) {
+                let mut keys: Vec<&str> = p.as_object().unwrap().keys().map(|k| k.as_ref()).collect();
+                keys.sort();
+                assert_eq!(keys, fields, "{}", tool_name);
+                for key in ["name", "flags", "description", "parameter_type", "optional"].iter() {
+                    assert_eq!(p[*key], e[*key], "{}: {}", tool_name, key);
+                }
+                assert!(p["flags"].as_array().unwrap().iter().all(|f| f.as_str().unwrap().starts_with('-')), "{}", tool_name);
+                assert!(p["default_value"].is_null() || p["default_value"].is_string(), "{}", tool_name);
+            }
+        }
+
+        let slope = tools.iter().find(|t| t["name"] == "Slope").unwrap();
+        assert_eq!(slope["toolbox"], "Geomorphometric Analysis");
+        let dem = &slope["parameters"][0];
+        assert_eq!(dem["flags"][0], "-i");
+        assert_eq!(dem["parameter_type"]["ExistingFile"], "Raster");
+        assert_eq!(dem["optional"], false);
+    }
+}
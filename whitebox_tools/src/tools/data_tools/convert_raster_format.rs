@@ -15,8 +15,10 @@ extern crate time;
 use std::env;
 use std::path;
 use std::io::{Error, ErrorKind};
+use std::sync::Arc;
 use raster::*;
 use tools::*;
+use tools::arg_parser::ArgValues;
 use tools::ToolParameter;
 use tools::ParameterType;
 use tools::ParameterFileType;
@@ -114,56 +116,25 @@ impl WhiteboxTool for ConvertRasterFormat {
     fn run<'a>(&self,
                args: Vec<String>,
                working_directory: &'a str,
-               verbose: bool)
+               verbose: bool,
+               sink: &Arc<ProgressSink>)
                -> Result<(), Error> {
-        let mut input_file = String::new();
-        let mut output_file = String::new();
-
         if args.len() == 0 {
             return Err(Error::new(ErrorKind::InvalidInput,
                                   "Tool run with no paramters."));
         }
-        for i in 0..args.len() {
-            let mut arg = args[i].replace("\"", "");
-            arg = arg.replace("\'", "");
-            let cmd = arg.split("="); // in case an equals sign was used
-            let vec = cmd.collect::<Vec<&str>>();
-            let mut keyval = false;
-            if vec.len() > 1 {
-                keyval = true;
-            }
-            if vec[0].to_lowercase() == "-i" || vec[0].to_lowercase() == "--input" {
-                if keyval {
-                    input_file = vec[1].to_string();
-                } else {
-                    input_file = args[i + 1].to_string();
-                }
-            } else if vec[0].to_lowercase() == "-o" || vec[0].to_lowercase() == "--output" {
-                if keyval {
-                    output_file = vec[1].to_string();
-                } else {
-                    output_file = args[i + 1].to_string();
-                }
-            }
-        }
+        let args = ArgValues::parse(&self.parameters, &args)?;
+        let input_file = args.required_file("--input", working_directory)?;
+        let output_file = args.required_file("--output", working_directory)?;
 
         if verbose {
-            println!("***************{}", "*".repeat(self.get_tool_name().len()));
-            println!("* Welcome to {} *", self.get_tool_name());
-            println!("***************{}", "*".repeat(self.get_tool_name().len()));
-        }
-
-        let sep: String = path::MAIN_SEPARATOR.to_string();
-
-        if !input_file.contains(&sep) && !input_file.contains("/") {
-            input_file = format!("{}{}", working_directory, input_file);
-        }
-        if !output_file.contains(&sep) && !output_file.contains("/") {
-            output_file = format!("{}{}", working_directory, output_file);
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
+            sink.message(&format!("* Welcome to {} *", self.get_tool_name()));
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
         }
 
         if verbose {
-            println!("Reading data...")
+            sink.message("Reading data...")
         };
 
         let input = Raster::new(&input_file, "r")?;
@@ -173,7 +144,7 @@ impl WhiteboxTool for ConvertRasterFormat {
         let start = time::now();
 
         let mut output = Raster::initialize_using_file(&output_file, &input);
-        println!("Initializing the output raster...");
+        sink.message("Initializing the output raster...");
         match output.set_data_from_raster(&input) {
             Ok(_) => (), // do nothings
             Err(err) => return Err(err),
@@ -196,20 +167,19 @@ impl WhiteboxTool for ConvertRasterFormat {
                                       .replace("PT", ""));
 
         if verbose {
-            println!("Saving data...")
+            sink.message("Saving data...")
         };
         let _ = match output.write() {
             Ok(_) => {
                 if verbose {
-                    println!("Output file written")
+                    sink.message("Output file written")
                 }
             }
             Err(e) => return Err(e),
         };
 
         if verbose {
-            println!("{}",
-                    &format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
+            sink.message(&format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
         }
 
         Ok(())
@@ -3,6 +3,7 @@ mod convert_nodata_to_zero;
 mod convert_raster_format;
 mod new_raster;
 mod print_geotiff_tags;
+mod reproject_raster;
 mod set_nodata_value;
 
 // exports identifiers from private sub-modules in the current module namespace
@@ -10,4 +11,5 @@ pub use self::convert_nodata_to_zero::ConvertNodataToZero;
 pub use self::convert_raster_format::ConvertRasterFormat;
 pub use self::new_raster::NewRasterFromBase;
 pub use self::print_geotiff_tags::PrintGeoTiffTags;
+pub use self::reproject_raster::ReprojectRaster;
 pub use self::set_nodata_value::SetNodataValue;
\ No newline at end of file
@@ -2,7 +2,7 @@
 This tool is part of the WhiteboxTools geospatial analysis library.
 Authors: Dr. John Lindsay
 Created: July 11, 2017
-Last Modified: November 14, 2017
+Last Modified: March 15, 2018
 License: MIT
 */
 extern crate time;
@@ -12,7 +12,9 @@ use std::path;
 use std::f64;
 use raster::*;
 use std::io::{Error, ErrorKind};
+use std::sync::Arc;
 use tools::*;
+use tools::arg_parser::ArgValues;
 use tools::ToolParameter;
 use tools::ParameterType;
 use tools::ParameterFileType;
@@ -67,7 +69,7 @@ impl NewRasterFromBase {
 
         parameters.push(ToolParameter{
             name: "Data Type".to_owned(), 
-            flags: vec!["--data_type".to_owned()], 
+            flags: vec!["--datatype".to_owned(), "--data_type".to_owned()], 
             description: "Output raster data type; options include 'double' (64-bit), 'float' (32-bit), and 'integer' (signed 16-bit) (default is 'float').".to_owned(),
             parameter_type: ParameterType::OptionList(vec!["double".to_owned(), "float".to_owned(), "integer".to_owned()]),
             default_value: Some("float".to_owned()),
@@ -128,69 +130,23 @@ impl WhiteboxTool for NewRasterFromBase {
     fn run<'a>(&self,
                args: Vec<String>,
                working_directory: &'a str,
-               verbose: bool)
+               verbose: bool,
+               sink: &Arc<ProgressSink>)
                -> Result<(), Error> {
-        let mut base_file = String::new();
-        let mut output_file = String::new();
-        let mut out_val_str = String::new();
-        let mut data_type = String::new();
-
         if args.len() == 0 {
             return Err(Error::new(ErrorKind::InvalidInput,
                                   "Tool run with no paramters."));
         }
-        for i in 0..args.len() {
-            let mut arg = args[i].replace("\"", "");
-            arg = arg.replace("\'", "");
-            let cmd = arg.split("="); // in case an equals sign was used
-            let vec = cmd.collect::<Vec<&str>>();
-            let mut keyval = false;
-            if vec.len() > 1 {
-                keyval = true;
-            }
-            if vec[0].to_lowercase() == "-base" || vec[0].to_lowercase() == "--base"
-            || vec[0].to_lowercase() == "-i" {
-                if keyval {
-                    base_file = vec[1].to_string();
-                } else {
-                    base_file = args[i + 1].to_string();
-                }
-            } else if vec[0].to_lowercase() == "-o" || vec[0].to_lowercase() == "--output" {
-                if keyval {
-                    output_file = vec[1].to_string();
-                } else {
-                    output_file = args[i + 1].to_string();
-                }
-            } else if vec[0].to_lowercase() == "-value" || vec[0].to_lowercase() == "--value" {
-                if keyval {
-                    out_val_str = vec[1].to_string();
-                } else {
-                    out_val_str = args[i + 1].to_string();
-                }
-            } else if vec[0].to_lowercase() == "-data_type" ||
-                      vec[0].to_lowercase() == "--data_type" ||
-                      vec[0].to_lowercase() == "--datatype" {
-                if keyval {
-                    data_type = vec[1].to_string();
-                } else {
-                    data_type = args[i + 1].to_string();
-                }
-            }
-        }
+        let args = ArgValues::parse(&self.parameters, &args)?;
+        let base_file = args.required_file("--base", working_directory)?;
+        let output_file = args.required_file("--output", working_directory)?;
+        let out_val_str = args.get_string("--value")?.unwrap_or(String::new());
+        let data_type = args.get_string("--data_type")?.unwrap_or(String::new());
 
         if verbose {
-            println!("***************{}", "*".repeat(self.get_tool_name().len()));
-            println!("* Welcome to {} *", self.get_tool_name());
-            println!("***************{}", "*".repeat(self.get_tool_name().len()));
-        }
-
-        let sep: String = path::MAIN_SEPARATOR.to_string();
-
-        if !base_file.contains(&sep) && !base_file.contains("/") {
-            base_file = format!("{}{}", working_directory, base_file);
-        }
-        if !output_file.contains(&sep) && !output_file.contains("/") {
-            output_file = format!("{}{}", working_directory, output_file);
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
+            sink.message(&format!("* Welcome to {} *", self.get_tool_name()));
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
         }
 
         let base = Raster::new(&base_file, "r")?;
@@ -211,11 +167,11 @@ impl WhiteboxTool for NewRasterFromBase {
         }
 
         if data_type.to_lowercase().contains("i") {
-            output.configs.data_type = DataType::I16;
+            output.set_data_type(DataType::I16);
         } else if data_type.to_lowercase().contains("d") {
-            output.configs.data_type = DataType::F64;
+            output.set_data_type(DataType::F64);
         } else {
-            output.configs.data_type = DataType::F32;
+            output.set_data_type(DataType::F32);
         }
 
         let end = time::now();
@@ -227,20 +183,19 @@ impl WhiteboxTool for NewRasterFromBase {
                                       .replace("PT", ""));
 
         if verbose {
-            println!("Saving data...")
+            sink.message("Saving data...")
         };
         let _ = match output.write() {
             Ok(_) => {
                 if verbose {
-                    println!("Output file written")
+                    sink.message("Output file written")
                 }
             }
             Err(e) => return Err(e),
         };
 
         if verbose {
-            println!("{}",
-                 &format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
+            sink.message(&format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
         }
 
         Ok(())
@@ -0,0 +1,355 @@
+/*
+This tool is part of the WhiteboxTools geospatial analysis library.
+Authors: Dr. John Lindsay
+Created: March 29, 2018
+Last Modified: March 29, 2018
+License: MIT
+
+Notes: The extent of the output raster is the bounding rectangle of the input raster's
+edges after they have been transformed into the output coordinate reference system. Each
+output grid cell is then transformed back into the input system and its value is
+interpolated from the input raster, in the same way as the Resample tool. Unless a cell
+size is specified, the output raster has about the same number of grid cells as the input.
+*/
+extern crate time;
+extern crate num_cpus;
+
+use std::env;
+use std::path;
+use std::f64;
+use std::sync::Arc;
+use std::sync::mpsc;
+use std::thread;
+use raster::*;
+use spatial_ref_system::{SpatialRef, Transformation};
+use std::io::{Error, ErrorKind};
+use tools::*;
+use tools::arg_parser::ArgValues;
+
+pub struct ReprojectRaster {
+    name: String,
+    description: String,
+    toolbox: String,
+    parameters: Vec<ToolParameter>,
+    example_usage: String,
+}
+
+impl ReprojectRaster {
+    pub fn new() -> ReprojectRaster { // public constructor
+        let name = "ReprojectRaster".to_string();
+        let toolbox = "Data Tools".to_string();
+        let description = "Transforms a raster into another coordinate reference system.".to_string();
+
+        let mut parameters = vec![];
+        parameters.push(ToolParameter{
+            name: "Input File".to_owned(),
+            flags: vec!["-i".to_owned(), "--input".to_owned()],
+            description: "Input raster file.".to_owned(),
+            parameter_type: ParameterType::ExistingFile(ParameterFileType::Raster),
+            default_value: None,
+            optional: false
+        });
+
+        parameters.push(ToolParameter{
+            name: "Output File".to_owned(),
+            flags: vec!["-o".to_owned(), "--output".to_owned()],
+            description: "Output raster file.".to_owned(),
+            parameter_type: ParameterType::NewFile(ParameterFileType::Raster),
+            default_value: None,
+            optional: false
+        });
+
+        parameters.push(ToolParameter{
+            name: "Output Coordinate Reference System".to_owned(),
+            flags: vec!["--crs".to_owned()],
+            description: "Output coordinate reference system, as an EPSG code, WKT, .prj file, or a file with the desired system.".to_owned(),
+            parameter_type: ParameterType::String,
+            default_value: None,
+            optional: false
+        });
+
+        parameters.push(ToolParameter{
+            name: "Cell Size (optional)".to_owned(),
+            flags: vec!["--cell_size".to_owned()],
+            description: "Optionally specified cell size of output raster, in the units of the output coordinate reference system.".to_owned(),
+            parameter_type: ParameterType::Float,
+            default_value: None,
+            optional: true
+        });
+
+        parameters.push(ToolParameter{
+            name: "Resampling Method".to_owned(),
+            flags: vec!["--method".to_owned()],
+            description: "Resampling method".to_owned(),
+            parameter_type: ParameterType::OptionList(vec!["nn".to_owned(), "bilinear".to_owned(), "cc".to_owned()]),
+            default_value: Some("cc".to_owned()),
+            optional: true
+        });
+
+        let sep: String = path::MAIN_SEPARATOR.to_string();
+        let p = format!("{}", env::current_dir().unwrap().display());
+        let e = format!("{}", env::current_exe().unwrap().display());
+        let mut short_exe = e.replace(&p, "").replace(".exe", "").replace(".", "").replace(&sep, "");
+        if e.contains(".exe") {
+            short_exe += ".exe";
+        }
+        let usage = format!(">>.*{0} -r={1} -v --wd='*path*to*data*' -i=dem.tif -o=output.tif --crs=EPSG:3857 --method=bilinear
+>>.*{0} -r={1} -v --wd='*path*to*data*' -i=dem.tif -o=output.tif --crs=other.tif --cell_size=10.0 --method=nn", short_exe, name).replace("*", &sep);
+
+        ReprojectRaster {
+            name: name,
+            description: description,
+            toolbox: toolbox,
+            parameters: parameters,
+            example_usage: usage
+        }
+    }
+}
+
+impl WhiteboxTool for ReprojectRaster {
+    fn get_source_file(&self) -> String {
+        String::from(file!())
+    }
+
+    fn get_tool_name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn get_tool_description(&self) -> String {
+        self.description.clone()
+    }
+
+    fn get_tool_parameters(&self) -> String {
+        match serde_json::to_string(&self.parameters) {
+            Ok(json_str) => return format!("{{\"parameters\":{}}}", json_str),
+            Err(err) => return format!("{:?}", err),
+        }
+    }
+
+    fn get_example_usage(&self) -> String {
+        self.example_usage.clone()
+    }
+
+    fn get_toolbox(&self) -> String {
+        self.toolbox.clone()
+    }
+
+    fn run<'a>(&self, args: Vec<String>, working_directory: &'a str, verbose: bool, sink: &Arc<ProgressSink>) -> Result<(), Error> {
+        if args.len() == 0 {
+            return Err(Error::new(ErrorKind::InvalidInput, "Tool run with no paramters."));
+        }
+        let args = ArgValues::parse(&self.parameters, &args)?;
+        let input_file = args.required_file("--input", working_directory)?;
+        let output_file = args.required_file("--output", working_directory)?;
+        let crs = args.required_string("--crs")?;
+        let mut cell_size = args.get_f64("--cell_size")?.unwrap_or(0f64);
+        let mut method = args.get_string("--method")?.unwrap_or("cc".to_string());
+        if method.to_lowercase().contains("nn") || method.to_lowercase().contains("nearest") {
+            method = "nn".to_string();
+        } else if method.to_lowercase().contains("bilinear") || method.to_lowercase().contains("bi") {
+            method = "bilinear".to_string();
+        } else if method.to_lowercase().contains("cc") || method.to_lowercase().contains("cubic") {
+            method = "cc".to_string();
+        }
+
+        if verbose {
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
+            sink.message(&format!("* Welcome to {} *", self.get_tool_name()));
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
+        }
+
+        let mut progress: usize;
+        let mut old_progress: usize = 1;
+
+        let destination = SpatialRef::from_user_input(&crs, working_directory)?;
+
+        if verbose { sink.message("Reading data...") };
+        let input = Arc::new(Raster::new(&input_file, "r")?);
+
+        let start = time::now();
+
+        let source = match input.get_spatial_ref() {
+            Some(srs) => srs,
+            None => return Err(Error::new(ErrorKind::InvalidInput,
+                "The input raster does not specify a supported coordinate reference system.")),
+        };
+        if verbose {
+            sink.message(&format!("Input coordinate reference system: {}", source));
+            sink.message(&format!("Output coordinate reference system: {}", destination));
+        }
+        let forward = Transformation::new(&source, &destination);
+        let inverse = Arc::new(Transformation::new(&destination, &source));
+
+        // find the extent of the output raster by transforming points along the input edges
+        let (mut west, mut east) = (f64::INFINITY, f64::NEG_INFINITY);
+        let (mut south, mut north) = (f64::INFINITY, f64::NEG_INFINITY);
+        let num_steps = 100;
+        for i in 0..(num_steps + 1) {
+            let t = i as f64 / num_steps as f64;
+            let x = input.configs.west + t * (input.configs.east - input.configs.west);
+            let y = input.configs.south + t * (input.configs.north - input.configs.south);
+            let edge_points = [(x, input.configs.south), (x, input.configs.north),
+                               (input.configs.west, y), (input.configs.east, y)];
+            for &(px, py) in edge_points.iter() {
+                let (tx, ty) = forward.transform(px, py);
+                if !tx.is_finite() || !ty.is_finite() { continue; }
+                if tx < west { west = tx; }
+                if tx > east { east = tx; }
+                if ty < south { south = ty; }
+                if ty > north { north = ty; }
+            }
+        }
+        if !(west < east) || !(south < north) {
+            return Err(Error::new(ErrorKind::InvalidInput,
+                "The extent of the input raster could not be transformed into the output coordinate reference system."));
+        }
+
+        if cell_size <= 0f64 {
+            // the same number of grid cells as the input raster
+            let num_cells = (input.configs.rows * input.configs.columns) as f64;
+            cell_size = ((east - west) * (north - south) / num_cells).sqrt();
+        }
+        let rows = ((north - south) / cell_size).ceil() as isize;
+        let columns = ((east - west) / cell_size).ceil() as isize;
+        let south = north - rows as f64 * cell_size;
+        let east = west + columns as f64 * cell_size;
+        let nodata = input.configs.nodata;
+
+        let mut configs = RasterConfigs { ..Default::default() };
+        configs.rows = rows as usize;
+        configs.columns = columns as usize;
+        configs.north = north;
+        configs.south = south;
+        configs.east = east;
+        configs.west = west;
+        configs.resolution_x = cell_size;
+        configs.resolution_y = cell_size;
+        configs.nodata = nodata;
+        configs.data_type = input.configs.data_type;
+        configs.photometric_interp = input.configs.photometric_interp;
+        configs.palette = input.configs.palette.clone();
+        configs.z_units = input.configs.z_units.clone();
+        configs.xy_units = if destination.is_geographic() { "degrees".to_string() } else { destination.linear_unit_name.clone() };
+        configs.epsg_code = destination.epsg_code;
+        configs.coordinate_ref_system_wkt = destination.to_wkt();
+        if method != "nn" {
+            configs.photometric_interp = PhotometricInterpretation::Continuous;
+            configs.data_type = DataType::F32;
+        }
+
+        let mut output = Raster::initialize_using_config(&output_file, &configs);
+        if output.raster_type == RasterType::GeoTiff && destination.epsg_code == 0 {
+            sink.warning("The output coordinate reference system does not have an EPSG code and is written to the GeoTIFF as user-defined GeoKeys.");
+        }
+
+        // the neighbourhoods and inverse-distance weighting are those used by the Resample tool
+        let (shift_x, shift_y): (Vec<isize>, Vec<isize>) = match method.as_ref() {
+            "nn" => (vec![0], vec![0]),
+            "bilinear" => (vec![0, 1, 0, 1], vec![0, 0, 1, 1]),
+            _ => (vec![-1, 0, 1, 2, -1, 0, 1, 2, -1, 0, 1, 2, -1, 0, 1, 2],
+                  vec![-1, -1, -1, -1, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2]),
+        };
+        let shift_x = Arc::new(shift_x);
+        let shift_y = Arc::new(shift_y);
+        let is_nn = method == "nn";
+
+        let num_procs = num_cpus::get() as isize;
+        let (tx, rx) = mpsc::channel();
+        for tid in 0..num_procs {
+            let input = input.clone();
+            let inverse = inverse.clone();
+            let shift_x = shift_x.clone();
+            let shift_y = shift_y.clone();
+            let tx = tx.clone();
+            thread::spawn(move || {
+                let in_nodata = input.configs.nodata;
+                let num_neighbours = shift_x.len();
+                let mut neighbour = vec![[0f64; 2]; num_neighbours];
+                let (mut col_n, mut row_n): (isize, isize);
+                let (mut dx, mut dy): (f64, f64);
+                let mut sum_dist: f64;
+                let mut z: f64;
+                for row in (0..rows).filter(|r| r % num_procs == tid) {
+                    let mut data = vec![nodata; columns as usize];
+                    let y = north - (row as f64 + 0.5) * cell_size;
+                    for col in 0..columns {
+                        let x = west + (col as f64 + 0.5) * cell_size;
+                        let (x_src, y_src) = inverse.transform(x, y);
+                        if !x_src.is_finite() || !y_src.is_finite() { continue; }
+                        if is_nn {
+                            z = input.get_value(input.get_row_from_y(y_src), input.get_column_from_x(x_src));
+                            if z != in_nodata {
+                                data[col as usize] = z;
+                            }
+                            continue;
+                        }
+                        let row_src = (input.configs.north - y_src) / input.configs.resolution_y - 0.5;
+                        let col_src = (x_src - input.configs.west) / input.configs.resolution_x - 0.5;
+                        if row_src < -0.5 || col_src < -0.5 ||
+                            row_src > input.configs.rows as f64 - 0.5 || col_src > input.configs.columns as f64 - 0.5 {
+                            continue;
+                        }
+                        let origin_row = row_src.floor() as isize;
+                        let origin_col = col_src.floor() as isize;
+                        sum_dist = 0f64;
+                        let mut exact = false;
+                        for n in 0..num_neighbours {
+                            row_n = origin_row + shift_y[n];
+                            col_n = origin_col + shift_x[n];
+                            neighbour[n][0] = input.get_value(row_n, col_n);
+                            dy = row_n as f64 - row_src;
+                            dx = col_n as f64 - col_src;
+                            if neighbour[n][0] == in_nodata {
+                                neighbour[n][1] = 0f64;
+                            } else if dx != 0f64 || dy != 0f64 {
+                                neighbour[n][1] = 1f64 / (dx * dx + dy * dy);
+                                sum_dist += neighbour[n][1];
+                            } else {
+                                data[col as usize] = neighbour[n][0];
+                                exact = true;
+                                break;
+                            }
+                        }
+                        if !exact && sum_dist > 0f64 {
+                            z = 0f64;
+                            for n in 0..num_neighbours {
+                                z += (neighbour[n][0] * neighbour[n][1]) / sum_dist;
+                            }
+                            data[col as usize] = z;
+                        }
+                    }
+                    tx.send((row, data)).unwrap();
+                }
+            });
+        }
+
+        for r in 0..rows {
+            let (row, data) = rx.recv().unwrap();
+            output.set_row_data(row, data);
+            progress = (100.0_f64 * r as f64 / (rows - 1).max(1) as f64) as usize;
+            if progress != old_progress {
+                sink.progress("Progress", progress)?;
+                old_progress = progress;
+            }
+        }
+
+        let end = time::now();
+        let elapsed_time = end - start;
+        output.add_metadata_entry(format!("Created by whitebox_tools\' {} tool", self.get_tool_name()));
+        output.add_metadata_entry(format!("Input file: {}", input_file));
+        output.add_metadata_entry(format!("Output coordinate reference system: {}", destination));
+        output.add_metadata_entry(format!("Resampling method: {}", method));
+        output.add_metadata_entry(format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
+
+        if verbose { sink.message("Saving data...") };
+        let _ = match output.write() {
+            Ok(_) => if verbose { sink.message("Output file written") },
+            Err(e) => return Err(e),
+        };
+        if verbose {
+            sink.message(&format!("{}", &format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", "")));
+        }
+
+        Ok(())
+    }
+}
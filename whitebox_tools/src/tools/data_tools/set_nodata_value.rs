@@ -17,6 +17,7 @@ use std::sync::mpsc;
 use std::thread;
 use raster::*;
 use tools::*;
+use tools::arg_parser::ArgValues;
 use tools::ToolParameter;
 use tools::ParameterType;
 use tools::ParameterFileType;
@@ -121,59 +122,22 @@ impl WhiteboxTool for SetNodataValue {
     fn run<'a>(&self,
                args: Vec<String>,
                working_directory: &'a str,
-               verbose: bool)
+               verbose: bool,
+               sink: &Arc<ProgressSink>)
                -> Result<(), Error> {
-        let mut input_file = String::new();
-        let mut output_file = String::new();
-        let mut back_value = 0f64;
-        
         if args.len() == 0 {
             return Err(Error::new(ErrorKind::InvalidInput,
                                   "Tool run with no paramters."));
         }
-        for i in 0..args.len() {
-            let mut arg = args[i].replace("\"", "");
-            arg = arg.replace("\'", "");
-            let cmd = arg.split("="); // in case an equals sign was used
-            let vec = cmd.collect::<Vec<&str>>();
-            let mut keyval = false;
-            if vec.len() > 1 {
-                keyval = true;
-            }
-            if vec[0].to_lowercase() == "-i" || vec[0].to_lowercase() == "--input" {
-                if keyval {
-                    input_file = vec[1].to_string();
-                } else {
-                    input_file = args[i + 1].to_string();
-                }
-            } else if vec[0].to_lowercase() == "-o" || vec[0].to_lowercase() == "--output" {
-                if keyval {
-                    output_file = vec[1].to_string();
-                } else {
-                    output_file = args[i + 1].to_string();
-                }
-            } else if vec[0].to_lowercase() == "-back_value" || vec[0].to_lowercase() == "--back_value" {
-                if keyval {
-                    back_value = vec[1].to_string().parse().unwrap();
-                } else {
-                    back_value = args[i + 1].parse().unwrap();
-                }
-            }
-        }
+        let args = ArgValues::parse(&self.parameters, &args)?;
+        let input_file = args.required_file("--input", working_directory)?;
+        let output_file = args.required_file("--output", working_directory)?;
+        let back_value = args.get_f64("--back_value")?.unwrap_or(0f64);
 
         if verbose {
-            println!("***************{}", "*".repeat(self.get_tool_name().len()));
-            println!("* Welcome to {} *", self.get_tool_name());
-            println!("***************{}", "*".repeat(self.get_tool_name().len()));
-        }
-
-        let sep: String = path::MAIN_SEPARATOR.to_string();
-
-        if !input_file.contains(&sep) && !input_file.contains("/") {
-            input_file = format!("{}{}", working_directory, input_file);
-        }
-        if !output_file.contains(&sep) && !output_file.contains("/") {
-            output_file = format!("{}{}", working_directory, output_file);
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
+            sink.message(&format!("* Welcome to {} *", self.get_tool_name()));
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
         }
 
         let input = Arc::new(Raster::new(&input_file, "r")?);
@@ -197,8 +161,8 @@ impl WhiteboxTool for SetNodataValue {
                 for row in (0..rows).filter(|r| r % num_procs == tid) {
                     let mut data = vec![nodata; columns as usize];
                     for col in 0..columns {
-                        if input[(row, col)] != back_value {
-                            data[col as usize] = input[(row, col)];
+                        if input.get_value(row, col) != back_value {
+                            data[col as usize] = input.get_value(row, col);
                         } else {
                             data[col as usize] = nodata;
                         }
@@ -211,12 +175,10 @@ impl WhiteboxTool for SetNodataValue {
         for row in 0..rows {
             let data = rx.recv().unwrap();
             output.set_row_data(data.0, data.1);
-            if verbose {
-                progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as i32;
-                if progress != old_progress {
-                    println!("Progress: {}%", progress);
-                    old_progress = progress;
-                }
+            progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as i32;
+            if progress != old_progress {
+                sink.progress("Progress", progress as usize)?;
+                old_progress = progress;
             }
         }
 
@@ -227,19 +189,19 @@ impl WhiteboxTool for SetNodataValue {
         output.add_metadata_entry(format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
 
         if verbose {
-            println!("Saving data...")
+            sink.message("Saving data...")
         };
         let _ = match output.write() {
             Ok(_) => {
                 if verbose {
-                    println!("Output file written")
+                    sink.message("Output file written")
                 }
             }
             Err(e) => return Err(e),
         };
 
         if verbose {
-            println!("{}", &format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
+            sink.message(&format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
         }
 
         Ok(())
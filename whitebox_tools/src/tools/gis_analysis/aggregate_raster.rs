@@ -17,6 +17,7 @@ use std::sync::Arc;
 use std::sync::mpsc;
 use std::thread;
 use tools::*;
+use tools::arg_parser::ArgValues;
 
 pub struct AggregateRaster {
     name: String,
@@ -76,7 +77,7 @@ impl AggregateRaster {
         if e.contains(".exe") {
             short_exe += ".exe";
         }
-        let usage = format!(">>.*{0} -r={1} -v --wd=\"*path*to*data*\" -i=input.dep -o=output.dep --output_text", short_exe, name).replace("*", &sep);
+        let usage = format!(">>.*{0} -r={1} -v --wd=\"*path*to*data*\" -i=input.dep -o=output.dep --agg_factor=2 --type=mean", short_exe, name).replace("*", &sep);
     
         AggregateRaster { 
             name: name, 
@@ -116,75 +117,30 @@ impl WhiteboxTool for AggregateRaster {
         self.toolbox.clone()
     }
 
-    fn run<'a>(&self, args: Vec<String>, working_directory: &'a str, verbose: bool) -> Result<(), Error> {
-        let mut input_file = String::new();
-        let mut output_file = String::new();
-        let mut agg_factor = 2isize;
-        let mut agg_type = String::from("mean");
-        
+    fn run<'a>(&self, args: Vec<String>, working_directory: &'a str, verbose: bool, sink: &Arc<ProgressSink>) -> Result<(), Error> {
         if args.len() == 0 {
             return Err(Error::new(ErrorKind::InvalidInput, "Tool run with no paramters."));
         }
-        for i in 0..args.len() {
-            let mut arg = args[i].replace("\"", "");
-            arg = arg.replace("\'", "");
-            let cmd = arg.split("="); // in case an equals sign was used
-            let vec = cmd.collect::<Vec<&str>>();
-            let mut keyval = false;
-            if vec.len() > 1 {
-                keyval = true;
-            }
-            let flag_val = vec[0].to_lowercase().replace("--", "-");
-            if flag_val == "-i" || flag_val == "-input" {
-                if keyval {
-                    input_file = vec[1].to_string();
-                } else {
-                    input_file = args[i+1].to_string();
-                }
-            } else if flag_val == "-o" || flag_val == "-output" {
-                if keyval {
-                    output_file = vec[1].to_string();
-                } else {
-                    output_file = args[i+1].to_string();
-                }
-            } else if flag_val == "-agg_factor" {
-                if keyval {
-                    agg_factor = vec[1].to_string().parse::<isize>().unwrap();
-                } else {
-                    agg_factor = args[i+1].to_string().parse::<isize>().unwrap();
-                }
-                if agg_factor < 2isize {
-                    println!("WARNING: Aggregation factor cannot be less than 2. It has been modified.");
-                    agg_factor = 2isize;
-                }
-            } else if flag_val == "-type" {
-                if keyval {
-                    agg_type = vec[1].to_string();
-                } else {
-                    agg_type = args[i+1].to_string();
-                }
-            }
+        let args = ArgValues::parse(&self.parameters, &args)?;
+        let input_file = args.required_file("--input", working_directory)?;
+        let output_file = args.required_file("--output", working_directory)?;
+        let mut agg_factor = args.get_i64("--agg_factor")?.map(|v| v as isize).unwrap_or(2isize);
+        if agg_factor < 2isize {
+            sink.warning("WARNING: Aggregation factor cannot be less than 2. It has been modified.");
+            agg_factor = 2isize;
         }
+        let agg_type = args.get_string("--type")?.unwrap_or(String::from("mean"));
 
         if verbose {
-            println!("***************{}", "*".repeat(self.get_tool_name().len()));
-            println!("* Welcome to {} *", self.get_tool_name());
-            println!("***************{}", "*".repeat(self.get_tool_name().len()));
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
+            sink.message(&format!("* Welcome to {} *", self.get_tool_name()));
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
         }
 
-        let sep: String = path::MAIN_SEPARATOR.to_string();
-
         let mut progress: usize;
         let mut old_progress: usize = 1;
 
-        if !input_file.contains(&sep) && !input_file.contains("/") {
-            input_file = format!("{}{}", working_directory, input_file);
-        }
-        if !output_file.contains(&sep) && !output_file.contains("/") {
-            output_file = format!("{}{}", working_directory, output_file);
-        }
-
-        if verbose { println!("Reading input data...") };
+        if verbose { sink.message("Reading input data...") };
         let input = Arc::new(Raster::new(&input_file, "r")?);
 
         let start = time::now();
@@ -259,12 +215,10 @@ impl WhiteboxTool for AggregateRaster {
                 for r in 0..rows_out {
                     let (row, data) = rx.recv().unwrap();
                     output.set_row_data(row, data);
-                    if verbose {
-                        progress = (100.0_f64 * r as f64 / (rows_out - 1) as f64) as usize;
-                        if progress != old_progress {
-                            println!("Progress: {}%", progress);
-                            old_progress = progress;
-                        }
+                    progress = (100.0_f64 * r as f64 / (rows_out - 1) as f64) as usize;
+                    if progress != old_progress {
+                        sink.progress("Progress", progress)?;
+                        old_progress = progress;
                     }
                 }
             },
@@ -306,12 +260,10 @@ impl WhiteboxTool for AggregateRaster {
                 for r in 0..rows_out {
                     let (row, data) = rx.recv().unwrap();
                     output.set_row_data(row, data);
-                    if verbose {
-                        progress = (100.0_f64 * r as f64 / (rows_out - 1) as f64) as usize;
-                        if progress != old_progress {
-                            println!("Progress: {}%", progress);
-                            old_progress = progress;
-                        }
+                    progress = (100.0_f64 * r as f64 / (rows_out - 1) as f64) as usize;
+                    if progress != old_progress {
+                        sink.progress("Progress", progress)?;
+                        old_progress = progress;
                     }
                 }
             },
@@ -353,12 +305,10 @@ impl WhiteboxTool for AggregateRaster {
                 for r in 0..rows_out {
                     let (row, data) = rx.recv().unwrap();
                     output.set_row_data(row, data);
-                    if verbose {
-                        progress = (100.0_f64 * r as f64 / (rows_out - 1) as f64) as usize;
-                        if progress != old_progress {
-                            println!("Progress: {}%", progress);
-                            old_progress = progress;
-                        }
+                    progress = (100.0_f64 * r as f64 / (rows_out - 1) as f64) as usize;
+                    if progress != old_progress {
+                        sink.progress("Progress", progress)?;
+                        old_progress = progress;
                     }
                 }
             },
@@ -400,12 +350,10 @@ impl WhiteboxTool for AggregateRaster {
                 for r in 0..rows_out {
                     let (row, data) = rx.recv().unwrap();
                     output.set_row_data(row, data);
-                    if verbose {
-                        progress = (100.0_f64 * r as f64 / (rows_out - 1) as f64) as usize;
-                        if progress != old_progress {
-                            println!("Progress: {}%", progress);
-                            old_progress = progress;
-                        }
+                    progress = (100.0_f64 * r as f64 / (rows_out - 1) as f64) as usize;
+                    if progress != old_progress {
+                        sink.progress("Progress", progress)?;
+                        old_progress = progress;
                     }
                 }
             },
@@ -450,12 +398,10 @@ impl WhiteboxTool for AggregateRaster {
                 for r in 0..rows_out {
                     let (row, data) = rx.recv().unwrap();
                     output.set_row_data(row, data);
-                    if verbose {
-                        progress = (100.0_f64 * r as f64 / (rows_out - 1) as f64) as usize;
-                        if progress != old_progress {
-                            println!("Progress: {}%", progress);
-                            old_progress = progress;
-                        }
+                    progress = (100.0_f64 * r as f64 / (rows_out - 1) as f64) as usize;
+                    if progress != old_progress {
+                        sink.progress("Progress", progress)?;
+                        old_progress = progress;
                     }
                 }
             },
@@ -470,14 +416,14 @@ impl WhiteboxTool for AggregateRaster {
         output.add_metadata_entry(format!("Aggregation type: {}", agg_type));
         output.add_metadata_entry(format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
 
-        if verbose { println!("Saving data...") };
+        if verbose { sink.message("Saving data...") };
         let _ = match output.write() {
-            Ok(_) => if verbose { println!("Output file written") },
+            Ok(_) => if verbose { sink.message("Output file written") },
             Err(e) => return Err(e),
         };
 
         if verbose {
-            println!("{}", &format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
+            sink.message(&format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
         }
 
         Ok(())
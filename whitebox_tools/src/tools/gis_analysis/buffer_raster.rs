@@ -12,7 +12,9 @@ use std::path;
 use std::f64;
 use raster::*;
 use std::io::{Error, ErrorKind};
+use std::sync::Arc;
 use tools::*;
+use tools::arg_parser::ArgValues;
 use structures::Array2D;
 
 pub struct BufferRaster {
@@ -118,67 +120,27 @@ impl WhiteboxTool for BufferRaster {
         self.toolbox.clone()
     }
 
-    fn run<'a>(&self, args: Vec<String>, working_directory: &'a str, verbose: bool) -> Result<(), Error> {
-        let mut input_file = String::new();
-        let mut output_file = String::new();
-        let mut buffer_size: f64 = 10.0;
-        let mut grid_cell_units = false;
-        
+    fn run<'a>(&self, args: Vec<String>, working_directory: &'a str, verbose: bool, sink: &Arc<ProgressSink>) -> Result<(), Error> {
         if args.len() == 0 {
             return Err(Error::new(ErrorKind::InvalidInput,
                                 "Tool run with no paramters."));
         }
-        for i in 0..args.len() {
-            let mut arg = args[i].replace("\"", "");
-            arg = arg.replace("\'", "");
-            let cmd = arg.split("="); // in case an equals sign was used
-            let vec = cmd.collect::<Vec<&str>>();
-            let mut keyval = false;
-            if vec.len() > 1 {
-                keyval = true;
-            }
-            if vec[0].to_lowercase() == "-i" || vec[0].to_lowercase() == "--input" {
-                if keyval {
-                    input_file = vec[1].to_string();
-                } else {
-                    input_file = args[i+1].to_string();
-                }
-            } else if vec[0].to_lowercase() == "-o" || vec[0].to_lowercase() == "--output" {
-                if keyval {
-                    output_file = vec[1].to_string();
-                } else {
-                    output_file = args[i+1].to_string();
-                }
-            } else if vec[0].to_lowercase() == "-size" || vec[0].to_lowercase() == "--size" {
-                if keyval {
-                    buffer_size = vec[1].to_string().parse::<f64>().unwrap();
-                } else {
-                    buffer_size = args[i+1].to_string().parse::<f64>().unwrap();
-                }
-            } else if vec[0].to_lowercase() == "-gridcells" || vec[0].to_lowercase() == "--gridcells" {
-                grid_cell_units = true;
-            }
-        }
+        let args = ArgValues::parse(&self.parameters, &args)?;
+        let input_file = args.required_file("--input", working_directory)?;
+        let output_file = args.required_file("--output", working_directory)?;
+        let buffer_size = args.get_f64("--size")?.unwrap_or(10.0);
+        let grid_cell_units = args.get_bool("--gridcells")?;
 
         if verbose {
-            println!("***************{}", "*".repeat(self.get_tool_name().len()));
-            println!("* Welcome to {} *", self.get_tool_name());
-            println!("***************{}", "*".repeat(self.get_tool_name().len()));
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
+            sink.message(&format!("* Welcome to {} *", self.get_tool_name()));
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
         }
 
-        let sep: String = path::MAIN_SEPARATOR.to_string();
-
         let mut progress: usize;
         let mut old_progress: usize = 1;
 
-        if !input_file.contains(&sep) && !input_file.contains("/") {
-            input_file = format!("{}{}", working_directory, input_file);
-        }
-        if !output_file.contains(&sep) && !output_file.contains("/") {
-            output_file = format!("{}{}", working_directory, output_file);
-        }
-
-        if verbose { println!("Reading data...") };
+        if verbose { sink.message("Reading data...") };
 
         let input = Raster::new(&input_file, "r")?;
 
@@ -204,32 +166,30 @@ impl WhiteboxTool for BufferRaster {
         
         for row in 0..rows {
             for col in 0..columns {
-                z = input[(row, col)];
+                z = input.get_value(row, col);
                 if z != 0.0 {
-                    output[(row, col)] = 0.0;
+                    output.set_value(row, col, 0.0);
                 } else {
-                    output[(row, col)] = inf_val;
+                    output.set_value(row, col, inf_val);
                 }
             }
-            if verbose {
-                progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as usize;
-                if progress != old_progress {
-                    println!("Initializing Rasters: {}%", progress);
-                    old_progress = progress;
-                }
+            progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as usize;
+            if progress != old_progress {
+                sink.progress("Initializing Rasters", progress)?;
+                old_progress = progress;
             }
         }
 
         for row in 0..rows {
             for col in 0..columns {
-                z = output[(row, col)];
+                z = output.get_value(row, col);
                 if z != 0.0 {
                     z_min = inf_val;
                     which_cell = 0;
                     for i in 0..4 {
                         x = col + d_x[i];
                         y = row + d_y[i];
-                        z2 = output[(y, x)];
+                        z2 = output.get_value(y, x);
                         if z2 != nodata {
                             h = match i {
                                 0 => 2.0 * r_x[(y, x)] + 1.0,
@@ -245,7 +205,7 @@ impl WhiteboxTool for BufferRaster {
                         }
                     }
                     if z_min < z {
-                        output[(row, col)] = z_min;
+                        output.set_value(row, col, z_min);
                         x = col + d_x[which_cell];
                         y = row + d_y[which_cell];
                         r_x[(row, col)] = r_x[(y, x)] + g_x[which_cell];
@@ -253,25 +213,23 @@ impl WhiteboxTool for BufferRaster {
                     }
                 }
             }
-            if verbose {
-                progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as usize;
-                if progress != old_progress {
-                    println!("Progress (1 of 3): {}%", progress);
-                    old_progress = progress;
-                }
+            progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as usize;
+            if progress != old_progress {
+                sink.progress("Progress (1 of 3)", progress)?;
+                old_progress = progress;
             }
         }
         
         for row in (0..rows).rev() {
             for col in (0..columns).rev() {
-                z = output[(row, col)];
+                z = output.get_value(row, col);
                 if z != 0.0 {
                     z_min = inf_val;
                     which_cell = 0;
                     for i in 4..8 {
                         x = col + d_x[i];
                         y = row + d_y[i];
-                        z2 = output[(y, x)];
+                        z2 = output.get_value(y, x);
                         if z2 != nodata {
                             h = match i {
                                 5 => 2.0 * (r_x[(y, x)] + r_y[(y, x)] + 1.0),
@@ -287,7 +245,7 @@ impl WhiteboxTool for BufferRaster {
                         }
                     }
                     if z_min < z {
-                        output[(row, col)] = z_min;
+                        output.set_value(row, col, z_min);
                         x = col + d_x[which_cell];
                         y = row + d_y[which_cell];
                         r_x[(row, col)] = r_x[(y, x)] + g_x[which_cell];
@@ -295,12 +253,10 @@ impl WhiteboxTool for BufferRaster {
                     }
                 }
             }
-            if verbose {
-                progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as usize;
-                if progress != old_progress {
-                    println!("Progress (2 of 3): {}%", progress);
-                    old_progress = progress;
-                }
+            progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as usize;
+            if progress != old_progress {
+                sink.progress("Progress (2 of 3)", progress)?;
+                old_progress = progress;
             }
         }
 
@@ -312,24 +268,22 @@ impl WhiteboxTool for BufferRaster {
         let mut dist: f64;
         for row in 0..rows {
             for col in 0..columns {
-                z = input[(row, col)];
+                z = input.get_value(row, col);
                 if z != nodata {
-                    dist = output[(row, col)].sqrt() * cell_size;
+                    dist = output.get_value(row, col).sqrt() * cell_size;
                     if dist <= buffer_size {
-                        output[(row, col)] = 1.0;
+                        output.set_value(row, col, 1.0);
                     } else {
-                        output[(row, col)] = 0.0;
+                        output.set_value(row, col, 0.0);
                     }
                 } else {
-                    output[(row, col)] = nodata;
+                    output.set_value(row, col, nodata);
                 }
             }
-            if verbose {
-                progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as usize;
-                if progress != old_progress {
-                    println!("Progress (3 of 3): {}%", progress);
-                    old_progress = progress;
-                }
+            progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as usize;
+            if progress != old_progress {
+                sink.progress("Progress (3 of 3)", progress)?;
+                old_progress = progress;
             }
         }
 
@@ -343,14 +297,14 @@ impl WhiteboxTool for BufferRaster {
         output.add_metadata_entry(format!("Grid cells as units: {}", grid_cell_units));
         output.add_metadata_entry(format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
 
-        if verbose { println!("Saving data...") };
+        if verbose { sink.message("Saving data...") };
         let _ = match output.write() {
-            Ok(_) => if verbose { println!("Output file written") },
+            Ok(_) => if verbose { sink.message("Output file written") },
             Err(e) => return Err(e),
         };
 
         if verbose {
-            println!("{}", &format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
+            sink.message(&format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
         }
 
         Ok(())
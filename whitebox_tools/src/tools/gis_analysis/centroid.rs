@@ -14,7 +14,9 @@ use std::path;
 use std::f64;
 use raster::*;
 use std::io::{Error, ErrorKind};
+use std::sync::Arc;
 use tools::*;
+use tools::arg_parser::ArgValues;
 
 pub struct Centroid {
     name: String,
@@ -106,60 +108,26 @@ impl WhiteboxTool for Centroid {
         self.toolbox.clone()
     }
 
-    fn run<'a>(&self, args: Vec<String>, working_directory: &'a str, verbose: bool) -> Result<(), Error> {
-        let mut input_file = String::new();
-        let mut output_file = String::new();
-        let mut text_output = false;
-        
+    fn run<'a>(&self, args: Vec<String>, working_directory: &'a str, verbose: bool, sink: &Arc<ProgressSink>) -> Result<(), Error> {
         if args.len() == 0 {
             return Err(Error::new(ErrorKind::InvalidInput,
                                 "Tool run with no paramters."));
         }
-        for i in 0..args.len() {
-            let mut arg = args[i].replace("\"", "");
-            arg = arg.replace("\'", "");
-            let cmd = arg.split("="); // in case an equals sign was used
-            let vec = cmd.collect::<Vec<&str>>();
-            let mut keyval = false;
-            if vec.len() > 1 {
-                keyval = true;
-            }
-            if vec[0].to_lowercase() == "-i" || vec[0].to_lowercase() == "--input" {
-                if keyval {
-                    input_file = vec[1].to_string();
-                } else {
-                    input_file = args[i+1].to_string();
-                }
-            } else if vec[0].to_lowercase() == "-o" || vec[0].to_lowercase() == "--output" {
-                if keyval {
-                    output_file = vec[1].to_string();
-                } else {
-                    output_file = args[i+1].to_string();
-                }
-            } else if vec[0].to_lowercase() == "-text_output" || vec[0].to_lowercase() == "--text_output" {
-                text_output = true;
-            }
-        }
+        let args = ArgValues::parse(&self.parameters, &args)?;
+        let input_file = args.required_file("--input", working_directory)?;
+        let output_file = args.required_file("--output", working_directory)?;
+        let text_output = args.get_bool("--text_output")?;
 
         if verbose {
-            println!("***************{}", "*".repeat(self.get_tool_name().len()));
-            println!("* Welcome to {} *", self.get_tool_name());
-            println!("***************{}", "*".repeat(self.get_tool_name().len()));
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
+            sink.message(&format!("* Welcome to {} *", self.get_tool_name()));
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
         }
 
-        let sep: String = path::MAIN_SEPARATOR.to_string();
-
         let mut progress: usize;
         let mut old_progress: usize = 1;
 
-        if !input_file.contains(&sep) && !input_file.contains("/") {
-            input_file = format!("{}{}", working_directory, input_file);
-        }
-        if !output_file.contains(&sep) && !output_file.contains("/") {
-            output_file = format!("{}{}", working_directory, output_file);
-        }
-
-        if verbose { println!("Reading data...") };
+        if verbose { sink.message("Reading data...") };
 
         let input = Raster::new(&input_file, "r")?;
         let start = time::now();
@@ -181,7 +149,7 @@ impl WhiteboxTool for Centroid {
         let mut a: usize;
         for row in 0..rows {
             for col in 0..columns {
-                z = input[(row, col)];
+                z = input.get_value(row, col);
                 if z > 0f64 && z != nodata {
                     a = (z - min_val as f64) as usize;
                     total_columns[a] += col as usize;
@@ -189,12 +157,10 @@ impl WhiteboxTool for Centroid {
                     total_n[a] += 1usize;
                 }
             }
-            if verbose {
-                progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as usize;
-                if progress != old_progress {
-                    println!("Progress: {}%", progress);
-                    old_progress = progress;
-                }
+            progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as usize;
+            if progress != old_progress {
+                sink.progress("Progress", progress)?;
+                old_progress = progress;
             }
         }
 
@@ -211,12 +177,12 @@ impl WhiteboxTool for Centroid {
         if text_output {
             let mut col: f64;
             let mut row: f64;
-            println!("Patch Centroid\nPatch ID\tColumn\tRow");
+            sink.message("Patch Centroid\nPatch ID\tColumn\tRow");
             for a in 0..range+1 {
                 if total_n[a] > 0 {
                     col = total_columns[a] as f64 / total_n[a] as f64;
                     row = total_rows[a] as f64 / total_n[a] as f64;
-                    println!("{}\t{}\t{}", (a + min_val), col, row);
+                    sink.message(&format!("{}\t{}\t{}", (a + min_val), col, row));
                 }
             }
         }
@@ -227,14 +193,14 @@ impl WhiteboxTool for Centroid {
         output.add_metadata_entry(format!("Input file: {}", input_file));
         output.add_metadata_entry(format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
 
-        if verbose { println!("Saving data...") };
+        if verbose { sink.message("Saving data...") };
         let _ = match output.write() {
-            Ok(_) => if verbose { println!("Output file written") },
+            Ok(_) => if verbose { sink.message("Output file written") },
             Err(e) => return Err(e),
         };
 
         if verbose {
-            println!("{}", &format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
+            sink.message(&format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
         }
 
         Ok(())
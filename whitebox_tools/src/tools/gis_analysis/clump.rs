@@ -12,7 +12,9 @@ use std::path;
 use std::f64;
 use raster::*;
 use std::io::{Error, ErrorKind};
+use std::sync::Arc;
 use tools::*;
+use tools::arg_parser::ArgValues;
 
 pub struct Clump {
     name: String,
@@ -112,63 +114,27 @@ impl WhiteboxTool for Clump {
         self.toolbox.clone()
     }
 
-    fn run<'a>(&self, args: Vec<String>, working_directory: &'a str, verbose: bool) -> Result<(), Error> {
-        let mut input_file = String::new();
-        let mut output_file = String::new();
-        let mut diag = false;
-        let mut zero_back = false;
-
+    fn run<'a>(&self, args: Vec<String>, working_directory: &'a str, verbose: bool, sink: &Arc<ProgressSink>) -> Result<(), Error> {
         if args.len() == 0 {
             return Err(Error::new(ErrorKind::InvalidInput,
                                 "Tool run with no paramters."));
         }
-        for i in 0..args.len() {
-            let mut arg = args[i].replace("\"", "");
-            arg = arg.replace("\'", "");
-            let cmd = arg.split("="); // in case an equals sign was used
-            let vec = cmd.collect::<Vec<&str>>();
-            let mut keyval = false;
-            if vec.len() > 1 {
-                keyval = true;
-            }
-            if vec[0].to_lowercase() == "-i" || vec[0].to_lowercase() == "--input" {
-                if keyval {
-                    input_file = vec[1].to_string();
-                } else {
-                    input_file = args[i+1].to_string();
-                }
-            } else if vec[0].to_lowercase() == "-o" || vec[0].to_lowercase() == "--output" {
-                if keyval {
-                    output_file = vec[1].to_string();
-                } else {
-                    output_file = args[i+1].to_string();
-                }
-            } else if vec[0].to_lowercase() == "-diag" || vec[0].to_lowercase() == "--diag" {
-                diag = true;
-            } else if vec[0].to_lowercase() == "-zero_back" || vec[0].to_lowercase() == "--zero_back" {
-                zero_back = true;
-            }
-        }
+        let args = ArgValues::parse(&self.parameters, &args)?;
+        let input_file = args.required_file("--input", working_directory)?;
+        let output_file = args.required_file("--output", working_directory)?;
+        let diag = args.get_bool("--diag")?;
+        let zero_back = args.get_bool("--zero_back")?;
 
         if verbose {
-            println!("***************{}", "*".repeat(self.get_tool_name().len()));
-            println!("* Welcome to {} *", self.get_tool_name());
-            println!("***************{}", "*".repeat(self.get_tool_name().len()));
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
+            sink.message(&format!("* Welcome to {} *", self.get_tool_name()));
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
         }
 
-        let sep: String = path::MAIN_SEPARATOR.to_string();
-
         let mut progress: usize;
         let mut old_progress: usize = 1;
 
-        if !input_file.contains(&sep) && !input_file.contains("/") {
-            input_file = format!("{}{}", working_directory, input_file);
-        }
-        if !output_file.contains(&sep) && !output_file.contains("/") {
-            output_file = format!("{}{}", working_directory, output_file);
-        }
-
-        if verbose { println!("Reading data...") };
+        if verbose { sink.message("Reading data...") };
 
         let input = Raster::new(&input_file, "r")?;
         
@@ -201,11 +167,11 @@ impl WhiteboxTool for Clump {
         let mut count: usize; // this is just used to update the progress after every 1000 cells solved.
         for row in 0..rows {
             for col in 0..columns {
-                zin = input[(row, col)];
-                zout = output[(row, col)];
+                zin = input.get_value(row, col);
+                zout = output.get_value(row, col);
                 if zin != nodata && zin != back_val && zout == nodata {
                     fid += 1f64;
-                    output[(row, col)] = fid;
+                    output.set_value(row, col, fid);
                     num_solved_cells += 1;
                     stack.push((row, col));
                     count = 0;
@@ -216,19 +182,17 @@ impl WhiteboxTool for Clump {
                         count += 1;
                         if count == 1000 {
                             count = 0;
-                            if verbose {
-                                progress = (100.0_f64 * num_solved_cells as f64 / (num_cells - 1) as f64) as usize;
-                                if progress != old_progress {
-                                    println!("Performing analysis: {}%", progress);
-                                    old_progress = progress;
-                                }
+                            progress = (100.0_f64 * num_solved_cells as f64 / (num_cells - 1) as f64) as usize;
+                            if progress != old_progress {
+                                sink.progress("Performing analysis", progress)?;
+                                old_progress = progress;
                             }
                         }
                         for i in 0..num_neighbours {
-                            zn = input[(r + dy[i], c + dx[i])];
-                            zout = output[(r + dy[i], c + dx[i])];
+                            zn = input.get_value(r + dy[i], c + dx[i]);
+                            zout = output.get_value(r + dy[i], c + dx[i]);
                             if zn == zin && zout == nodata {
-                                output[(r + dy[i], c + dx[i])] = fid;
+                                output.set_value(r + dy[i], c + dx[i], fid);
                                 num_solved_cells += 1;
                                 stack.push((r + dy[i], c + dx[i]));
                             }
@@ -238,15 +202,13 @@ impl WhiteboxTool for Clump {
                     num_solved_cells += 1;
                 } else if zin == back_val {
                     num_solved_cells += 1;
-                    output[(row, col)] = back_val;
+                    output.set_value(row, col, back_val);
                 }
             }
-            if verbose {
-                progress = (100.0_f64 * num_solved_cells as f64 / (num_cells - 1) as f64) as usize;
-                if progress != old_progress {
-                    println!("Performing analysis: {}%", progress);
-                    old_progress = progress;
-                }
+            progress = (100.0_f64 * num_solved_cells as f64 / (num_cells - 1) as f64) as usize;
+            if progress != old_progress {
+                sink.progress("Performing analysis", progress)?;
+                old_progress = progress;
             }
         }
 
@@ -259,14 +221,14 @@ impl WhiteboxTool for Clump {
         output.add_metadata_entry(format!("Diagonal connectivity: {}", diag));
         output.add_metadata_entry(format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
 
-        if verbose { println!("Saving data...") };
+        if verbose { sink.message("Saving data...") };
         let _ = match output.write() {
-            Ok(_) => if verbose { println!("Output file written") },
+            Ok(_) => if verbose { sink.message("Output file written") },
             Err(e) => return Err(e),
         };
 
         if verbose {
-            println!("{}", &format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
+            sink.message(&format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
         }
 
         Ok(())
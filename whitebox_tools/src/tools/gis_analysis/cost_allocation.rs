@@ -14,8 +14,10 @@ use std::path;
 use std::f64;
 use raster::*;
 use std::io::{Error, ErrorKind};
+use std::sync::Arc;
 use structures::Array2D;
 use tools::*;
+use tools::arg_parser::ArgValues;
 
 pub struct CostAllocation {
     name: String,
@@ -106,67 +108,26 @@ impl WhiteboxTool for CostAllocation {
         self.toolbox.clone()
     }
 
-    fn run<'a>(&self, args: Vec<String>, working_directory: &'a str, verbose: bool) -> Result<(), Error> {
-        let mut d8_file = String::new();
-        let mut pourpts_file = String::new();
-        let mut output_file = String::new();
-        
+    fn run<'a>(&self, args: Vec<String>, working_directory: &'a str, verbose: bool, sink: &Arc<ProgressSink>) -> Result<(), Error> {
         if args.len() == 0 {
             return Err(Error::new(ErrorKind::InvalidInput,
                                 "Tool run with no paramters."));
         }
-        for i in 0..args.len() {
-            let mut arg = args[i].replace("\"", "");
-            arg = arg.replace("\'", "");
-            let cmd = arg.split("="); // in case an equals sign was used
-            let vec = cmd.collect::<Vec<&str>>();
-            let mut keyval = false;
-            if vec.len() > 1 {
-                keyval = true;
-            }
-            if vec[0].to_lowercase() == "-backlink" || vec[0].to_lowercase() == "--backlink" {
-                if keyval {
-                    d8_file = vec[1].to_string();
-                } else {
-                    d8_file = args[i+1].to_string();
-                }
-            } else if vec[0].to_lowercase() == "-source" || vec[0].to_lowercase() == "--source" {
-                if keyval {
-                    pourpts_file = vec[1].to_string();
-                } else {
-                    pourpts_file = args[i+1].to_string();
-                }
-            } else if vec[0].to_lowercase() == "-o" || vec[0].to_lowercase() == "--output" {
-                if keyval {
-                    output_file = vec[1].to_string();
-                } else {
-                    output_file = args[i+1].to_string();
-                }
-            }
-        }
+        let args = ArgValues::parse(&self.parameters, &args)?;
+        let d8_file = args.required_file("--backlink", working_directory)?;
+        let pourpts_file = args.required_file("--source", working_directory)?;
+        let output_file = args.required_file("--output", working_directory)?;
 
         if verbose {
-            println!("***************{}", "*".repeat(self.get_tool_name().len()));
-            println!("* Welcome to {} *", self.get_tool_name());
-            println!("***************{}", "*".repeat(self.get_tool_name().len()));
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
+            sink.message(&format!("* Welcome to {} *", self.get_tool_name()));
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
         }
 
-        let sep: String = path::MAIN_SEPARATOR.to_string();
-
         let mut progress: usize;
         let mut old_progress: usize = 1;
 
-        if !d8_file.contains(&sep) && !d8_file.contains("/") {
-            d8_file = format!("{}{}", working_directory, d8_file);
-        }
-        if !pourpts_file.contains(&sep) && !pourpts_file.contains("/") {
-            pourpts_file = format!("{}{}", working_directory, pourpts_file);
-        }
-        if !output_file.contains(&sep) && !output_file.contains("/") {
-            output_file = format!("{}{}", working_directory, output_file);
-        }
-
-        if verbose { println!("Reading data...") };
+        if verbose { sink.message("Reading data...") };
 
         let pntr = Raster::new(&d8_file, "r")?;
         let pourpts = Raster::new(&pourpts_file, "r")?;
@@ -214,7 +175,7 @@ impl WhiteboxTool for CostAllocation {
         let mut z: f64;
         for row in 0..rows {
             for col in 0..columns {
-                z = pntr[(row, col)];
+                z = pntr.get_value(row, col);
                 if z != pntr_nodata {
                     if z > 0.0 {
                         flow_dir[(row, col)] = pntr_matches[z as usize];
@@ -222,19 +183,17 @@ impl WhiteboxTool for CostAllocation {
                         flow_dir[(row, col)] = -1i8;
                     }
                 } else {
-                    output[(row, col)] = nodata;
+                    output.set_value(row, col, nodata);
                 }
-                z = pourpts[(row, col)];
+                z = pourpts.get_value(row, col);
                 if z != nodata && z > 0.0 {
-                    output[(row, col)] = z;
+                    output.set_value(row, col, z);
                 }
             }
-            if verbose {
-                progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as usize;
-                if progress != old_progress {
-                    println!("Initializing: {}%", progress);
-                    old_progress = progress;
-                }
+            progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as usize;
+            if progress != old_progress {
+                sink.progress("Initializing", progress)?;
+                old_progress = progress;
             }
         }
 
@@ -244,7 +203,7 @@ impl WhiteboxTool for CostAllocation {
         let mut outlet_id: f64;
         for row in 0..rows {
             for col in 0..columns {
-                if output[(row, col)] == low_value { // && flow_dir[(row, col)] != -2i8 {
+                if output.get_value(row, col) == low_value { // && flow_dir[(row, col)] != -2i8 {
                     flag = false;
                     x = col;
                     y = row;
@@ -258,7 +217,7 @@ impl WhiteboxTool for CostAllocation {
                             y += d_y[dir as usize];
 
                             // if the new cell already has a value in the output, use that as the outletID
-                            z = output[(y, x)];
+                            z = output.get_value(y, x);
                             if z != low_value {
                                 outlet_id = z;
                                 flag = true;
@@ -271,7 +230,7 @@ impl WhiteboxTool for CostAllocation {
                     flag = false;
                     x = col;
                     y = row;
-                    output[(y, x)] = outlet_id;
+                    output.set_value(y, x, outlet_id);
                     while !flag {
                         // find its downslope neighbour
                         dir = flow_dir[(y, x)];
@@ -281,22 +240,20 @@ impl WhiteboxTool for CostAllocation {
                             y += d_y[dir as usize];
 
                             // if the new cell already has a value in the output, use that as the outletID
-                            if output[(y, x)] != low_value {
+                            if output.get_value(y, x) != low_value {
                                 flag = true;
                             }
                         } else {
                             flag = true;
                         }
-                        output[(y, x)] = outlet_id;
+                        output.set_value(y, x, outlet_id);
                     }
                 }
             }
-            if verbose {
-                progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as usize;
-                if progress != old_progress {
-                    println!("Progress: {}%", progress);
-                    old_progress = progress;
-                }
+            progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as usize;
+            if progress != old_progress {
+                sink.progress("Progress", progress)?;
+                old_progress = progress;
             }
         }
         
@@ -307,14 +264,14 @@ impl WhiteboxTool for CostAllocation {
         output.add_metadata_entry(format!("Backlink file: {}", d8_file));
         output.add_metadata_entry(format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
 
-        if verbose { println!("Saving data...") };
+        if verbose { sink.message("Saving data...") };
         let _ = match output.write() {
-            Ok(_) => if verbose { println!("Output file written") },
+            Ok(_) => if verbose { sink.message("Output file written") },
             Err(e) => return Err(e),
         };
 
         if verbose {
-            println!("{}", &format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
+            sink.message(&format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
         }
         
         Ok(())
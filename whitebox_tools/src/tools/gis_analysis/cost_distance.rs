@@ -15,7 +15,9 @@ use std::i32;
 use std::f64;
 use raster::*;
 use std::io::{Error, ErrorKind};
+use std::sync::Arc;
 use tools::*;
+use tools::arg_parser::ArgValues;
 
 pub struct CostDistance {
     name: String,
@@ -115,80 +117,30 @@ impl WhiteboxTool for CostDistance {
         self.toolbox.clone()
     }
 
-    fn run<'a>(&self, args: Vec<String>, working_directory: &'a str, verbose: bool) -> Result<(), Error> {
-        let mut source_file = String::new();
-        let mut cost_file = String::new();
-        let mut accum_file = String::new();
-        let mut backlink_file = String::new();
-        
+    fn run<'a>(&self, args: Vec<String>, working_directory: &'a str, verbose: bool, sink: &Arc<ProgressSink>) -> Result<(), Error> {
         if args.len() == 0 {
             return Err(Error::new(ErrorKind::InvalidInput,
                                 "Tool run with no paramters."));
         }
-        for i in 0..args.len() {
-            let mut arg = args[i].replace("\"", "");
-            arg = arg.replace("\'", "");
-            let cmd = arg.split("="); // in case an equals sign was used
-            let vec = cmd.collect::<Vec<&str>>();
-            let mut keyval = false;
-            if vec.len() > 1 {
-                keyval = true;
-            }
-            if vec[0].to_lowercase() == "-source" || vec[0].to_lowercase() == "--source" {
-                if keyval {
-                    source_file = vec[1].to_string();
-                } else {
-                    source_file = args[i+1].to_string();
-                }
-            } else if vec[0].to_lowercase() == "-cost" || vec[0].to_lowercase() == "--cost" {
-                if keyval {
-                    cost_file = vec[1].to_string();
-                } else {
-                    cost_file = args[i+1].to_string();
-                }
-            } else if vec[0].to_lowercase() == "-out_accum" || vec[0].to_lowercase() == "--out_accum" {
-                if keyval {
-                    accum_file = vec[1].to_string();
-                } else {
-                    accum_file = args[i+1].to_string();
-                }
-            } else if vec[0].to_lowercase() == "-out_backlink" || vec[0].to_lowercase() == "--out_backlink" {
-                if keyval {
-                    backlink_file = vec[1].to_string();
-                } else {
-                    backlink_file = args[i+1].to_string();
-                }
-            }
-        }
+        let args = ArgValues::parse(&self.parameters, &args)?;
+        let source_file = args.required_file("--source", working_directory)?;
+        let cost_file = args.required_file("--cost", working_directory)?;
+        let accum_file = args.required_file("--out_accum", working_directory)?;
+        let backlink_file = args.required_file("--out_backlink", working_directory)?;
 
         if verbose {
-            println!("***************{}", "*".repeat(self.get_tool_name().len()));
-            println!("* Welcome to {} *", self.get_tool_name());
-            println!("***************{}", "*".repeat(self.get_tool_name().len()));
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
+            sink.message(&format!("* Welcome to {} *", self.get_tool_name()));
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
         }
 
-        let sep: String = path::MAIN_SEPARATOR.to_string();
-
         let mut progress: usize;
         let mut old_progress: usize = 1;
 
-        if !source_file.contains(&sep) && !source_file.contains("/") {
-            source_file = format!("{}{}", working_directory, source_file);
-        }
-        if !cost_file.contains(&sep) && !cost_file.contains("/") {
-            cost_file = format!("{}{}", working_directory, cost_file);
-        }
-        if !accum_file.contains(&sep) && !accum_file.contains("/") {
-            accum_file = format!("{}{}", working_directory, accum_file);
-        }
-        if !backlink_file.contains(&sep) && !backlink_file.contains("/") {
-            backlink_file = format!("{}{}", working_directory, backlink_file);
-        }
-
-        if verbose { println!("Reading source data...") };
+        if verbose { sink.message("Reading source data...") };
         let source = Raster::new(&source_file, "r")?;
 
-        if verbose { println!("Reading cost data...") };
+        if verbose { sink.message("Reading cost data...") };
         let cost = Raster::new(&cost_file, "r")?;
 
         // make sure the input files have the same size
@@ -210,19 +162,17 @@ impl WhiteboxTool for CostDistance {
 
         for row in 0..rows {
             for col in 0..columns {
-                if source[(row, col)] > 0.0 && cost[(row, col)] != nodata {
-                    output[(row, col)] = 0.0;
-                    backlink[(row, col)] = -1.0;
-                } else if cost[(row, col)] == nodata {
-                    output[(row, col)] = nodata;
+                if source.get_value(row, col) > 0.0 && cost.get_value(row, col) != nodata {
+                    output.set_value(row, col, 0.0);
+                    backlink.set_value(row, col, -1.0);
+                } else if cost.get_value(row, col) == nodata {
+                    output.set_value(row, col, nodata);
                 }
             }
-            if verbose {
-                progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as usize;
-                if progress != old_progress {
-                    println!("Initializing: {}%", progress);
-                    old_progress = progress;
-                }
+            progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as usize;
+            if progress != old_progress {
+                sink.progress("Initializing", progress)?;
+                old_progress = progress;
             }
         }
 
@@ -247,15 +197,15 @@ impl WhiteboxTool for CostDistance {
             did_something = false;
             for row in 0..rows {
                 for col in 0..columns {
-                    accum_val = output[(row, col)];
+                    accum_val = output.get_value(row, col);
                     if accum_val < background_val && accum_val != nodata {
-                        cost1 = cost[(row, col)];
+                        cost1 = cost.get_value(row, col);
                         for n in 0..8 {
                             col_n = col + dx[n];
                             row_n = row + dy[n];
-                            cost2 = cost[(row_n, col_n)];
+                            cost2 = cost.get_value(row_n, col_n);
                             new_cost = accum_val + (cost1 + cost2) / 2.0 * dist[n];
-                            if new_cost < output[(row_n, col_n)] {
+                            if new_cost < output.get_value(row_n, col_n) {
                                 output.set_value(row_n, col_n, new_cost);
                                 backlink.set_value(row_n, col_n, backlink_dir[n]);
                                 did_something = true;
@@ -263,12 +213,10 @@ impl WhiteboxTool for CostDistance {
                         }
                     }
                 }
-                if verbose {
-                    progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as usize;
-                    if progress != old_progress {
-                        println!("Loop {}: {}%", loop_num, progress);
-                        old_progress = progress;
-                    }
+                progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as usize;
+                if progress != old_progress {
+                    sink.progress(&format!("Loop {}", loop_num), progress)?;
+                    old_progress = progress;
                 }
             }
 
@@ -278,15 +226,15 @@ impl WhiteboxTool for CostDistance {
             did_something = false;
             for row in (0..rows).rev() {
                 for col in (0..columns).rev() {
-                    accum_val = output[(row, col)];
+                    accum_val = output.get_value(row, col);
                     if accum_val < background_val && accum_val != nodata {
-                        cost1 = cost[(row, col)];
+                        cost1 = cost.get_value(row, col);
                         for n in 0..8 {
                             col_n = col + dx[n];
                             row_n = row + dy[n];
-                            cost2 = cost[(row_n, col_n)];
+                            cost2 = cost.get_value(row_n, col_n);
                             new_cost = accum_val + (cost1 + cost2) / 2.0 * dist[n];
-                            if new_cost < output[(row_n, col_n)] {
+                            if new_cost < output.get_value(row_n, col_n) {
                                 output.set_value(row_n, col_n, new_cost);
                                 backlink.set_value(row_n, col_n, backlink_dir[n]);
                                 did_something = true;
@@ -294,12 +242,10 @@ impl WhiteboxTool for CostDistance {
                         }
                     }
                 }
-                if verbose {
-                    progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as usize;
-                    if progress != old_progress {
-                        println!("Loop {}: {}%", loop_num, progress);
-                        old_progress = progress;
-                    }
+                progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as usize;
+                if progress != old_progress {
+                    sink.progress(&format!("Loop {}", loop_num), progress)?;
+                    old_progress = progress;
                 }
             }
 
@@ -309,15 +255,15 @@ impl WhiteboxTool for CostDistance {
             did_something = false;
             for row in 0..rows {
                 for col in (0..columns).rev() {
-                    accum_val = output[(row, col)];
+                    accum_val = output.get_value(row, col);
                     if accum_val < background_val && accum_val != nodata {
-                        cost1 = cost[(row, col)];
+                        cost1 = cost.get_value(row, col);
                         for n in 0..8 {
                             col_n = col + dx[n];
                             row_n = row + dy[n];
-                            cost2 = cost[(row_n, col_n)];
+                            cost2 = cost.get_value(row_n, col_n);
                             new_cost = accum_val + (cost1 + cost2) / 2.0 * dist[n];
-                            if new_cost < output[(row_n, col_n)] {
+                            if new_cost < output.get_value(row_n, col_n) {
                                 output.set_value(row_n, col_n, new_cost);
                                 backlink.set_value(row_n, col_n, backlink_dir[n]);
                                 did_something = true;
@@ -325,12 +271,10 @@ impl WhiteboxTool for CostDistance {
                         }
                     }
                 }
-                if verbose {
-                    progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as usize;
-                    if progress != old_progress {
-                        println!("Loop {}: {}%", loop_num, progress);
-                        old_progress = progress;
-                    }
+                progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as usize;
+                if progress != old_progress {
+                    sink.progress(&format!("Loop {}", loop_num), progress)?;
+                    old_progress = progress;
                 }
             }
 
@@ -340,15 +284,15 @@ impl WhiteboxTool for CostDistance {
             did_something = false;
             for row in (0..rows).rev() {
                 for col in 0..columns {
-                    accum_val = output[(row, col)];
+                    accum_val = output.get_value(row, col);
                     if accum_val < background_val && accum_val != nodata {
-                        cost1 = cost[(row, col)];
+                        cost1 = cost.get_value(row, col);
                         for n in 0..8 {
                             col_n = col + dx[n];
                             row_n = row + dy[n];
-                            cost2 = cost[(row_n, col_n)];
+                            cost2 = cost.get_value(row_n, col_n);
                             new_cost = accum_val + (cost1 + cost2) / 2.0 * dist[n];
-                            if new_cost < output[(row_n, col_n)] {
+                            if new_cost < output.get_value(row_n, col_n) {
                                 output.set_value(row_n, col_n, new_cost);
                                 backlink.set_value(row_n, col_n, backlink_dir[n]);
                                 did_something = true;
@@ -356,12 +300,10 @@ impl WhiteboxTool for CostDistance {
                         }
                     }
                 }
-                if verbose {
-                    progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as usize;
-                    if progress != old_progress {
-                        println!("Loop {}: {}%", loop_num, progress);
-                        old_progress = progress;
-                    }
+                progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as usize;
+                if progress != old_progress {
+                    sink.progress(&format!("Loop {}", loop_num), progress)?;
+                    old_progress = progress;
                 }
             }
 
@@ -374,15 +316,15 @@ impl WhiteboxTool for CostDistance {
             did_something = false;
             for col in 0..columns {
                 for row in 0..rows {
-                    accum_val = output[(row, col)];
+                    accum_val = output.get_value(row, col);
                     if accum_val < background_val && accum_val != nodata {
-                        cost1 = cost[(row, col)];
+                        cost1 = cost.get_value(row, col);
                         for n in 0..8 {
                             col_n = col + dx[n];
                             row_n = row + dy[n];
-                            cost2 = cost[(row_n, col_n)];
+                            cost2 = cost.get_value(row_n, col_n);
                             new_cost = accum_val + (cost1 + cost2) / 2.0 * dist[n];
-                            if new_cost < output[(row_n, col_n)] {
+                            if new_cost < output.get_value(row_n, col_n) {
                                 output.set_value(row_n, col_n, new_cost);
                                 backlink.set_value(row_n, col_n, backlink_dir[n]);
                                 did_something = true;
@@ -390,12 +332,10 @@ impl WhiteboxTool for CostDistance {
                         }
                     }
                 }
-                if verbose {
-                    progress = (100.0_f64 * col as f64 / (columns - 1) as f64) as usize;
-                    if progress != old_progress {
-                        println!("Loop {}: {}%", loop_num, progress);
-                        old_progress = progress;
-                    }
+                progress = (100.0_f64 * col as f64 / (columns - 1) as f64) as usize;
+                if progress != old_progress {
+                    sink.progress(&format!("Loop {}", loop_num), progress)?;
+                    old_progress = progress;
                 }
             }
 
@@ -405,15 +345,15 @@ impl WhiteboxTool for CostDistance {
             did_something = false;
             for col in (0..columns).rev() {
                 for row in (0..rows).rev() {
-                    accum_val = output[(row, col)];
+                    accum_val = output.get_value(row, col);
                     if accum_val < background_val && accum_val != nodata {
-                        cost1 = cost[(row, col)];
+                        cost1 = cost.get_value(row, col);
                         for n in 0..8 {
                             col_n = col + dx[n];
                             row_n = row + dy[n];
-                            cost2 = cost[(row_n, col_n)];
+                            cost2 = cost.get_value(row_n, col_n);
                             new_cost = accum_val + (cost1 + cost2) / 2.0 * dist[n];
-                            if new_cost < output[(row_n, col_n)] {
+                            if new_cost < output.get_value(row_n, col_n) {
                                 output.set_value(row_n, col_n, new_cost);
                                 backlink.set_value(row_n, col_n, backlink_dir[n]);
                                 did_something = true;
@@ -421,12 +361,10 @@ impl WhiteboxTool for CostDistance {
                         }
                     }
                 }
-                if verbose {
-                    progress = (100.0_f64 * col as f64 / (columns - 1) as f64) as usize;
-                    if progress != old_progress {
-                        println!("Loop {}: {}%", loop_num, progress);
-                        old_progress = progress;
-                    }
+                progress = (100.0_f64 * col as f64 / (columns - 1) as f64) as usize;
+                if progress != old_progress {
+                    sink.progress(&format!("Loop {}", loop_num), progress)?;
+                    old_progress = progress;
                 }
             }
 
@@ -436,15 +374,15 @@ impl WhiteboxTool for CostDistance {
             did_something = false;
             for col in (0..columns).rev() {
                 for row in 0..rows {
-                    accum_val = output[(row, col)];
+                    accum_val = output.get_value(row, col);
                     if accum_val < background_val && accum_val != nodata {
-                        cost1 = cost[(row, col)];
+                        cost1 = cost.get_value(row, col);
                         for n in 0..8 {
                             col_n = col + dx[n];
                             row_n = row + dy[n];
-                            cost2 = cost[(row_n, col_n)];
+                            cost2 = cost.get_value(row_n, col_n);
                             new_cost = accum_val + (cost1 + cost2) / 2.0 * dist[n];
-                            if new_cost < output[(row_n, col_n)] {
+                            if new_cost < output.get_value(row_n, col_n) {
                                 output.set_value(row_n, col_n, new_cost);
                                 backlink.set_value(row_n, col_n, backlink_dir[n]);
                                 did_something = true;
@@ -452,12 +390,10 @@ impl WhiteboxTool for CostDistance {
                         }
                     }
                 }
-                if verbose {
-                    progress = (100.0_f64 * col as f64 / (columns - 1) as f64) as usize;
-                    if progress != old_progress {
-                        println!("Loop {}: {}%", loop_num, progress);
-                        old_progress = progress;
-                    }
+                progress = (100.0_f64 * col as f64 / (columns - 1) as f64) as usize;
+                if progress != old_progress {
+                    sink.progress(&format!("Loop {}", loop_num), progress)?;
+                    old_progress = progress;
                 }
             }
 
@@ -467,15 +403,15 @@ impl WhiteboxTool for CostDistance {
             did_something = false;
             for col in 0..columns {
                 for row in (0..rows).rev() {
-                    accum_val = output[(row, col)];
+                    accum_val = output.get_value(row, col);
                     if accum_val < background_val && accum_val != nodata {
-                        cost1 = cost[(row, col)];
+                        cost1 = cost.get_value(row, col);
                         for n in 0..8 {
                             col_n = col + dx[n];
                             row_n = row + dy[n];
-                            cost2 = cost[(row_n, col_n)];
+                            cost2 = cost.get_value(row_n, col_n);
                             new_cost = accum_val + (cost1 + cost2) / 2.0 * dist[n];
-                            if new_cost < output[(row_n, col_n)] {
+                            if new_cost < output.get_value(row_n, col_n) {
                                 output.set_value(row_n, col_n, new_cost);
                                 backlink.set_value(row_n, col_n, backlink_dir[n]);
                                 did_something = true;
@@ -483,12 +419,10 @@ impl WhiteboxTool for CostDistance {
                         }
                     }
                 }
-                if verbose {
-                    progress = (100.0_f64 * col as f64 / (columns - 1) as f64) as usize;
-                    if progress != old_progress {
-                        println!("Loop {}: {}%", loop_num, progress);
-                        old_progress = progress;
-                    }
+                progress = (100.0_f64 * col as f64 / (columns - 1) as f64) as usize;
+                if progress != old_progress {
+                    sink.progress(&format!("Loop {}", loop_num), progress)?;
+                    old_progress = progress;
                 }
             }
         }
@@ -558,9 +492,9 @@ impl WhiteboxTool for CostDistance {
         output.add_metadata_entry(format!("Cost raster: {}", cost_file));
         output.add_metadata_entry(format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
 
-        if verbose { println!("Saving data...") };
+        if verbose { sink.message("Saving data...") };
         let _ = match output.write() {
-            Ok(_) => if verbose { println!("Output file written") },
+            Ok(_) => if verbose { sink.message("Output file written") },
             Err(e) => return Err(e),
         };
 
@@ -571,12 +505,12 @@ impl WhiteboxTool for CostDistance {
         backlink.add_metadata_entry(format!("Cost raster: {}", cost_file));
         backlink.add_metadata_entry(format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
         let _ = match backlink.write() {
-            Ok(_) => if verbose { println!("Output file written") },
+            Ok(_) => if verbose { sink.message("Output file written") },
             Err(e) => return Err(e),
         };
         
         if verbose {
-            println!("{}", &format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
+            sink.message(&format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
         }
 
         Ok(())
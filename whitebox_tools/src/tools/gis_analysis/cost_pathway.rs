@@ -14,7 +14,9 @@ use std::path;
 use std::f64;
 use raster::*;
 use std::io::{Error, ErrorKind};
+use std::sync::Arc;
 use tools::*;
+use tools::arg_parser::ArgValues;
 
 pub struct CostPathway {
     name: String,
@@ -114,73 +116,30 @@ impl WhiteboxTool for CostPathway {
         self.toolbox.clone()
     }
 
-    fn run<'a>(&self, args: Vec<String>, working_directory: &'a str, verbose: bool) -> Result<(), Error> {
-        let mut destination_file = String::new();
-        let mut backlink_file = String::new();
-        let mut output_file = String::new();
-        let mut background_val = f64::NEG_INFINITY;
-        
+    fn run<'a>(&self, args: Vec<String>, working_directory: &'a str, verbose: bool, sink: &Arc<ProgressSink>) -> Result<(), Error> {
         if args.len() == 0 {
             return Err(Error::new(ErrorKind::InvalidInput,
                                 "Tool run with no paramters."));
         }
-        for i in 0..args.len() {
-            let mut arg = args[i].replace("\"", "");
-            arg = arg.replace("\'", "");
-            let cmd = arg.split("="); // in case an equals sign was used
-            let vec = cmd.collect::<Vec<&str>>();
-            let mut keyval = false;
-            if vec.len() > 1 {
-                keyval = true;
-            }
-            if vec[0].to_lowercase() == "-destination" || vec[0].to_lowercase() == "--destination" {
-                if keyval {
-                    destination_file = vec[1].to_string();
-                } else {
-                    destination_file = args[i+1].to_string();
-                }
-            } else if vec[0].to_lowercase() == "-backlink" || vec[0].to_lowercase() == "--backlink" {
-                if keyval {
-                    backlink_file = vec[1].to_string();
-                } else {
-                    backlink_file = args[i+1].to_string();
-                }
-            } else if vec[0].to_lowercase() == "-o" || vec[0].to_lowercase() == "--output" {
-                if keyval {
-                    output_file = vec[1].to_string();
-                } else {
-                    output_file = args[i+1].to_string();
-                }
-            } else if vec[0].to_lowercase() == "-zero_background" || vec[0].to_lowercase() == "--zero_background" || vec[0].to_lowercase() == "--esri_style" {
-                background_val = 0f64;
-            }
-        }
+        let args = ArgValues::parse(&self.parameters, &args)?;
+        let destination_file = args.required_file("--destination", working_directory)?;
+        let backlink_file = args.required_file("--backlink", working_directory)?;
+        let output_file = args.required_file("--output", working_directory)?;
+        let mut background_val = if args.get_bool("--zero_background")? { 0f64 } else { f64::NEG_INFINITY };
 
         if verbose {
-            println!("***************{}", "*".repeat(self.get_tool_name().len()));
-            println!("* Welcome to {} *", self.get_tool_name());
-            println!("***************{}", "*".repeat(self.get_tool_name().len()));
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
+            sink.message(&format!("* Welcome to {} *", self.get_tool_name()));
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
         }
 
-        let sep: String = path::MAIN_SEPARATOR.to_string();
-
         let mut progress: usize;
         let mut old_progress: usize = 1;
 
-        if !destination_file.contains(&sep) && !destination_file.contains("/") {
-            destination_file = format!("{}{}", working_directory, destination_file);
-        }
-        if !backlink_file.contains(&sep) && !backlink_file.contains("/") {
-            backlink_file = format!("{}{}", working_directory, backlink_file);
-        }
-        if !output_file.contains(&sep) && !output_file.contains("/") {
-            output_file = format!("{}{}", working_directory, output_file);
-        }
-        
-        if verbose { println!("Reading destination data...") };
+        if verbose { sink.message("Reading destination data...") };
         let destination = Raster::new(&destination_file, "r")?;
 
-        if verbose { println!("Reading backlink data...") };
+        if verbose { sink.message("Reading backlink data...") };
         let backlink = Raster::new(&backlink_file, "r")?;
 
         // make sure the input files have the same size
@@ -218,18 +177,18 @@ impl WhiteboxTool for CostPathway {
         let mut dir: f64;
         for row in 0..rows {
             for col in 0..columns {
-                if destination[(row, col)] > 0.0 && backlink[(row, col)] != nodata {
+                if destination.get_value(row, col) > 0.0 && backlink.get_value(row, col) != nodata {
                     flag = false;
                     x = col;
                     y = row;
                     while !flag {
-                        if output[(y, x)] == background_val {
-                            output[(y, x)] = 1.0;
+                        if output.get_value(y, x) == background_val {
+                            output.set_value(y, x, 1.0);
                         } else {
                             output.increment(y, x, 1.0);
                         }
                         // find its downslope neighbour
-                        dir = backlink[(y, x)];
+                        dir = backlink.get_value(y, x);
                         if dir != nodata && dir > 0.0 {
                             // move x and y accordingly
                             x += dx[pntr_matches[dir as usize]];
@@ -238,16 +197,14 @@ impl WhiteboxTool for CostPathway {
                             flag = true;
                         }
                     }
-                } else if backlink[(row, col)] == nodata {
-                    output[(row, col)] = nodata;
+                } else if backlink.get_value(row, col) == nodata {
+                    output.set_value(row, col, nodata);
                 }
             }
-            if verbose {
-                progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as usize;
-                if progress != old_progress {
-                    println!("Progress: {}%", progress);
-                    old_progress = progress;
-                }
+            progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as usize;
+            if progress != old_progress {
+                sink.progress("Progress", progress)?;
+                old_progress = progress;
             }
         }
 
@@ -262,14 +219,14 @@ impl WhiteboxTool for CostPathway {
         output.add_metadata_entry(format!("Backlink raster: {}", backlink_file));
         output.add_metadata_entry(format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
 
-        if verbose { println!("Saving data...") };
+        if verbose { sink.message("Saving data...") };
         let _ = match output.write() {
-            Ok(_) => if verbose { println!("Output file written") },
+            Ok(_) => if verbose { sink.message("Output file written") },
             Err(e) => return Err(e),
         };
 
         if verbose {
-            println!("{}", &format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
+            sink.message(&format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
         }
 
         Ok(())
@@ -14,8 +14,10 @@ use std::f64;
 use std::io::{Error, ErrorKind};
 use std::sync::mpsc;
 use std::thread;
+use std::sync::Arc;
 use raster::*;
 use tools::*;
+use tools::arg_parser::ArgValues;
 
 pub struct CreatePlane {
     name: String,
@@ -54,7 +56,7 @@ impl CreatePlane {
 
         parameters.push(ToolParameter{
             name: "Gradient".to_owned(), 
-            flags: vec!["--gradient".to_owned()], 
+            flags: vec!["--slope".to_owned(), "--gradient".to_owned()], 
             description: "Slope gradient in degrees (-85.0 to 85.0).".to_owned(),
             parameter_type: ParameterType::Float,
             default_value: Some("15.0".to_owned()),
@@ -132,74 +134,24 @@ impl WhiteboxTool for CreatePlane {
     fn run<'a>(&self,
                args: Vec<String>,
                working_directory: &'a str,
-               verbose: bool)
+               verbose: bool,
+               sink: &Arc<ProgressSink>)
                -> Result<(), Error> {
-        let mut base_file = String::new();
-        let mut output_file = String::new();
-        let mut slope = 15.0;
-        let mut aspect = 90.0;
-        let mut constant_val = 0.0;
-
         if args.len() == 0 {
             return Err(Error::new(ErrorKind::InvalidInput,
                                   "Tool run with no paramters."));
         }
-        for i in 0..args.len() {
-            let mut arg = args[i].replace("\"", "");
-            arg = arg.replace("\'", "");
-            let cmd = arg.split("="); // in case an equals sign was used
-            let vec = cmd.collect::<Vec<&str>>();
-            let mut keyval = false;
-            if vec.len() > 1 {
-                keyval = true;
-            }
-            if vec[0].to_lowercase() == "-base" || vec[0].to_lowercase() == "--base" {
-                if keyval {
-                    base_file = vec[1].to_string();
-                } else {
-                    base_file = args[i + 1].to_string();
-                }
-            } else if vec[0].to_lowercase() == "-o" || vec[0].to_lowercase() == "--output" {
-                if keyval {
-                    output_file = vec[1].to_string();
-                } else {
-                    output_file = args[i + 1].to_string();
-                }
-            } else if vec[0].to_lowercase() == "-slope" || vec[0].to_lowercase() == "--slope" {
-                if keyval {
-                    slope = vec[1].to_string().parse::<f64>().unwrap();
-                } else {
-                    slope = args[i + 1].to_string().parse::<f64>().unwrap();
-                }
-            } else if vec[0].to_lowercase() == "-aspect" || vec[0].to_lowercase() == "--aspect" {
-                if keyval {
-                    aspect = vec[1].to_string().parse::<f64>().unwrap();
-                } else {
-                    aspect = args[i + 1].to_string().parse::<f64>().unwrap();
-                }
-            } else if vec[0].to_lowercase() == "-constant" ||
-                      vec[0].to_lowercase() == "--constant" {
-                if keyval {
-                    constant_val = vec[1].to_string().parse::<f64>().unwrap();
-                } else {
-                    constant_val = args[i + 1].to_string().parse::<f64>().unwrap();
-                }
-            }
-        }
+        let args = ArgValues::parse(&self.parameters, &args)?;
+        let base_file = args.required_file("--base", working_directory)?;
+        let output_file = args.required_file("--output", working_directory)?;
+        let mut slope = args.get_f64("--gradient")?.unwrap_or(15.0);
+        let mut aspect = args.get_f64("--aspect")?.unwrap_or(90.0);
+        let constant_val = args.get_f64("--constant")?.unwrap_or(0.0);
 
         if verbose {
-            println!("***************{}", "*".repeat(self.get_tool_name().len()));
-            println!("* Welcome to {} *", self.get_tool_name());
-            println!("***************{}", "*".repeat(self.get_tool_name().len()));
-        }
-
-        let sep: String = path::MAIN_SEPARATOR.to_string();
-
-        if !base_file.contains(&sep) && !base_file.contains("/") {
-            base_file = format!("{}{}", working_directory, base_file);
-        }
-        if !output_file.contains(&sep) && !output_file.contains("/") {
-            output_file = format!("{}{}", working_directory, output_file);
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
+            sink.message(&format!("* Welcome to {} *", self.get_tool_name()));
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
         }
 
         let base = Raster::new(&base_file, "r")?;
@@ -267,12 +219,10 @@ impl WhiteboxTool for CreatePlane {
         for row in 0..rows {
             let data = rx.recv().unwrap();
             output.set_row_data(data.0, data.1);
-            if verbose {
-                progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as i32;
-                if progress != old_progress {
-                    println!("Progress: {}%", progress);
-                    old_progress = progress;
-                }
+            progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as i32;
+            if progress != old_progress {
+                sink.progress("Progress", progress as usize)?;
+                old_progress = progress;
             }
         }
 
@@ -288,20 +238,19 @@ impl WhiteboxTool for CreatePlane {
                                       .replace("PT", ""));
 
         if verbose {
-            println!("Saving data...")
+            sink.message("Saving data...")
         };
         let _ = match output.write() {
             Ok(_) => {
                 if verbose {
-                    println!("Output file written")
+                    sink.message("Output file written")
                 }
             }
             Err(e) => return Err(e),
         };
 
         if verbose {
-            println!("{}",
-                 &format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
+            sink.message(&format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
         }
 
         Ok(())
@@ -11,12 +11,14 @@ extern crate num_cpus;
 use std::env;
 use std::path;
 use std::f64;
+use std::cmp::min;
 use raster::*;
 use std::io::{Error, ErrorKind};
 use std::sync::Arc;
 use std::sync::mpsc;
 use std::thread;
 use tools::*;
+use tools::arg_parser::ArgValues;
 
 pub struct EdgeProportion {
     name: String,
@@ -107,60 +109,26 @@ impl WhiteboxTool for EdgeProportion {
         self.toolbox.clone()
     }
 
-    fn run<'a>(&self, args: Vec<String>, working_directory: &'a str, verbose: bool) -> Result<(), Error> {
-        let mut input_file = String::new();
-        let mut output_file = String::new();
-        let mut output_text = false;
-        
+    fn run<'a>(&self, args: Vec<String>, working_directory: &'a str, verbose: bool, sink: &Arc<ProgressSink>) -> Result<(), Error> {
         if args.len() == 0 {
             return Err(Error::new(ErrorKind::InvalidInput,
                                 "Tool run with no paramters."));
         }
-        for i in 0..args.len() {
-            let mut arg = args[i].replace("\"", "");
-            arg = arg.replace("\'", "");
-            let cmd = arg.split("="); // in case an equals sign was used
-            let vec = cmd.collect::<Vec<&str>>();
-            let mut keyval = false;
-            if vec.len() > 1 {
-                keyval = true;
-            }
-            if vec[0].to_lowercase() == "-i" || vec[0].to_lowercase() == "--input" {
-                if keyval {
-                    input_file = vec[1].to_string();
-                } else {
-                    input_file = args[i+1].to_string();
-                }
-            } else if vec[0].to_lowercase() == "-o" || vec[0].to_lowercase() == "--output" {
-                if keyval {
-                    output_file = vec[1].to_string();
-                } else {
-                    output_file = args[i+1].to_string();
-                }
-            } else if vec[0].to_lowercase() == "-output_text" || vec[0].to_lowercase() == "--output_text" {
-                output_text = true;
-            }
-        }
+        let args = ArgValues::parse(&self.parameters, &args)?;
+        let input_file = args.required_file("--input", working_directory)?;
+        let output_file = args.required_file("--output", working_directory)?;
+        let output_text = args.get_bool("--output_text")?;
 
         if verbose {
-            println!("***************{}", "*".repeat(self.get_tool_name().len()));
-            println!("* Welcome to {} *", self.get_tool_name());
-            println!("***************{}", "*".repeat(self.get_tool_name().len()));
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
+            sink.message(&format!("* Welcome to {} *", self.get_tool_name()));
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
         }
 
-        let sep: String = path::MAIN_SEPARATOR.to_string();
-
         let mut progress: usize;
         let mut old_progress: usize = 1;
 
-        if !input_file.contains(&sep) && !input_file.contains("/") {
-            input_file = format!("{}{}", working_directory, input_file);
-        }
-        if !output_file.contains(&sep) && !output_file.contains("/") {
-            output_file = format!("{}{}", working_directory, output_file);
-        }
-
-        if verbose { println!("Reading input data...") };
+        if verbose { sink.message("Reading input data...") };
         let input = Arc::new(Raster::new(&input_file, "r")?);
         let rows = input.configs.rows as isize;
         let columns = input.configs.columns as isize;
@@ -170,58 +138,64 @@ impl WhiteboxTool for EdgeProportion {
         let start = time::now();
         
         let num_procs = num_cpus::get() as isize;
-        let (tx, rx) = mpsc::channel();
-        for tid in 0..num_procs {
-            let input = input.clone();
-            let tx = tx.clone();
-            thread::spawn(move || {
-                let mut num_cells = vec![0usize; max_val + 1];
-                let mut num_edge_cells = vec![0usize; max_val + 1];
-                let dx = [ 1, 1, 1, 0, -1, -1, -1, 0 ];
-                let dy = [ -1, 0, 1, 1, 1, 0, -1, -1 ];
-                let mut z: f64;
-                let mut zn: f64;
-                let mut is_edge: bool;
-                let mut bin: usize;
-                for row in (0..rows).filter(|r| r % num_procs == tid) {
-                    for col in 0..columns {
-                        z = input[(row, col)];
-                        if z > 0f64 && z != nodata {
-                            bin = z.floor() as usize;
-                            num_cells[bin] += 1;
-                            is_edge = false;
-                            for n in 0..8 {
-                                zn = input[(row + dy[n], col + dx[n])];
-                                if zn != z {
-                                    is_edge = true;
-                                    break;
+        let mut num_cells = vec![0usize; max_val + 1];
+        let mut num_edge_cells = vec![0usize; max_val + 1];
+        // the patch raster is read in windows of rows, with a halo of one row on either side
+        let window_rows = input.get_window_rows();
+        let mut start_row = 0;
+        while start_row < rows {
+            let end_row = min(start_row + window_rows, rows);
+            let window = Arc::new(input.get_window(start_row - 1, end_row + 1));
+            let (tx, rx) = mpsc::channel();
+            for tid in 0..num_procs {
+                let window = window.clone();
+                let tx = tx.clone();
+                thread::spawn(move || {
+                    let mut num_cells = vec![0usize; max_val + 1];
+                    let mut num_edge_cells = vec![0usize; max_val + 1];
+                    let dx = [ 1, 1, 1, 0, -1, -1, -1, 0 ];
+                    let dy = [ -1, 0, 1, 1, 1, 0, -1, -1 ];
+                    let mut z: f64;
+                    let mut zn: f64;
+                    let mut is_edge: bool;
+                    let mut bin: usize;
+                    for row in (start_row..end_row).filter(|r| r % num_procs == tid) {
+                        for col in 0..columns {
+                            z = window.get_value(row, col);
+                            if z > 0f64 && z != nodata {
+                                bin = z.floor() as usize;
+                                num_cells[bin] += 1;
+                                is_edge = false;
+                                for n in 0..8 {
+                                    zn = window.get_value(row + dy[n], col + dx[n]);
+                                    if zn != z {
+                                        is_edge = true;
+                                        break;
+                                    }
+                                }
+                                if is_edge {
+                                    num_edge_cells[bin] += 1;
                                 }
-                            }
-                            if is_edge {
-                                num_edge_cells[bin] += 1;
                             }
                         }
                     }
-                }
-                tx.send((num_cells, num_edge_cells)).unwrap();
-            });
-        }
-
-        let mut num_cells = vec![0usize; max_val + 1];
-        let mut num_edge_cells = vec![0usize; max_val + 1];
-        for tid in 0..num_procs {
-            let (vec1, vec2) = rx.recv().unwrap();
-            for bin in 0..max_val+1 {
-                num_cells[bin] += vec1[bin];
-                num_edge_cells[bin] += vec2[bin];
+                    tx.send((num_cells, num_edge_cells)).unwrap();
+                });
             }
-            if verbose {
-                progress = (100.0_f64 * tid as f64 / (num_procs - 1) as f64) as usize;
-                if progress != old_progress {
-                    println!("Progress (Loop 1 of 2): {}%", progress);
-                    old_progress = progress;
+
+            for _ in 0..num_procs {
+                let (vec1, vec2) = rx.recv().unwrap();
+                for bin in 0..max_val+1 {
+                    num_cells[bin] += vec1[bin];
+                    num_edge_cells[bin] += vec2[bin];
                 }
             }
+            progress = (100.0_f64 * end_row as f64 / rows as f64) as usize;
+            if progress != old_progress {
+                sink.progress("Progress (Loop 1 of 2)", progress)?;
+                old_progress = progress;
+            }
+            start_row = end_row;
         }
 
         let mut edge_props = vec![nodata; max_val + 1];
@@ -243,7 +217,7 @@ impl WhiteboxTool for EdgeProportion {
                 for row in (0..rows).filter(|r| r % num_procs == tid) {
                     let mut data = vec![nodata; columns as usize];
                     for col in 0..columns {
-                        z = input[(row, col)];
+                        z = input.get_value(row, col);
                         if z > 0f64 && z != nodata {
                             bin = z.floor() as usize;
                             data[col as usize] = edge_props[bin];
@@ -262,12 +236,10 @@ impl WhiteboxTool for EdgeProportion {
         for r in 0..rows {
             let (row, data) = rx.recv().unwrap();
             output.set_row_data(row, data);
-            if verbose {
-                progress = (100.0_f64 * r as f64 / (rows - 1) as f64) as usize;
-                if progress != old_progress {
-                    println!("Progress (Loop 2 of 2): {}%", progress);
-                    old_progress = progress;
-                }
+            progress = (100.0_f64 * r as f64 / (rows - 1) as f64) as usize;
+            if progress != old_progress {
+                sink.progress("Progress (Loop 2 of 2)", progress)?;
+                old_progress = progress;
             }
         }
 
@@ -277,23 +249,23 @@ impl WhiteboxTool for EdgeProportion {
         output.add_metadata_entry(format!("Input file: {}", input_file));
         output.add_metadata_entry(format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
 
-        if verbose { println!("Saving data...") };
+        if verbose { sink.message("Saving data...") };
         let _ = match output.write() {
-            Ok(_) => if verbose { println!("Output file written") },
+            Ok(_) => if verbose { sink.message("Output file written") },
             Err(e) => return Err(e),
         };
 
         if output_text {
-            println!("Edge Proportion\nPatch ID\tValue");
+            sink.message("Edge Proportion\nPatch ID\tValue");
             for bin in 0..max_val+1 {
                 if edge_props[bin] > 0f64 && edge_props[bin] != nodata {
-                    println!("{}\t{}", bin, edge_props[bin]);
+                    sink.message(&format!("{}\t{}", bin, edge_props[bin]));
                 }
             }
         }
 
         if verbose {
-            println!("{}", &format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
+            sink.message(&format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
         }
 
         Ok(())
@@ -16,7 +16,9 @@ use std::io::{Error, ErrorKind};
 use std::sync::Arc;
 use std::sync::mpsc;
 use std::thread;
+use std::cmp::min;
 use tools::*;
+use tools::arg_parser::ArgValues;
 
 pub struct FindPatchOrClassEdgeCells {
     name: String,
@@ -98,57 +100,25 @@ impl WhiteboxTool for FindPatchOrClassEdgeCells {
         self.toolbox.clone()
     }
 
-    fn run<'a>(&self, args: Vec<String>, working_directory: &'a str, verbose: bool) -> Result<(), Error> {
-        let mut input_file = String::new();
-        let mut output_file = String::new();
-        
+    fn run<'a>(&self, args: Vec<String>, working_directory: &'a str, verbose: bool, sink: &Arc<ProgressSink>) -> Result<(), Error> {
         if args.len() == 0 {
             return Err(Error::new(ErrorKind::InvalidInput,
                                 "Tool run with no paramters."));
         }
-        for i in 0..args.len() {
-            let mut arg = args[i].replace("\"", "");
-            arg = arg.replace("\'", "");
-            let cmd = arg.split("="); // in case an equals sign was used
-            let vec = cmd.collect::<Vec<&str>>();
-            let mut keyval = false;
-            if vec.len() > 1 {
-                keyval = true;
-            }
-            if vec[0].to_lowercase() == "-i" || vec[0].to_lowercase() == "--input" {
-                if keyval {
-                    input_file = vec[1].to_string();
-                } else {
-                    input_file = args[i+1].to_string();
-                }
-            } else if vec[0].to_lowercase() == "-o" || vec[0].to_lowercase() == "--output" {
-                if keyval {
-                    output_file = vec[1].to_string();
-                } else {
-                    output_file = args[i+1].to_string();
-                }
-            }
-        }
+        let args = ArgValues::parse(&self.parameters, &args)?;
+        let input_file = args.required_file("--input", working_directory)?;
+        let output_file = args.required_file("--output", working_directory)?;
 
         if verbose {
-            println!("***************{}", "*".repeat(self.get_tool_name().len()));
-            println!("* Welcome to {} *", self.get_tool_name());
-            println!("***************{}", "*".repeat(self.get_tool_name().len()));
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
+            sink.message(&format!("* Welcome to {} *", self.get_tool_name()));
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
         }
 
-        let sep: String = path::MAIN_SEPARATOR.to_string();
-
         let mut progress: usize;
         let mut old_progress: usize = 1;
 
-        if !input_file.contains(&sep) && !input_file.contains("/") {
-            input_file = format!("{}{}", working_directory, input_file);
-        }
-        if !output_file.contains(&sep) && !output_file.contains("/") {
-            output_file = format!("{}{}", working_directory, output_file);
-        }
-
-        if verbose { println!("Reading input data...") };
+        if verbose { sink.message("Reading input data...") };
         let input = Arc::new(Raster::new(&input_file, "r")?);
         let rows = input.configs.rows as isize;
         let columns = input.configs.columns as isize;
@@ -157,49 +127,55 @@ impl WhiteboxTool for FindPatchOrClassEdgeCells {
         let start = time::now();
         
         let num_procs = num_cpus::get() as isize;
-        let (tx, rx) = mpsc::channel();
-        for tid in 0..num_procs {
-            let input = input.clone();
-            let tx = tx.clone();
-            thread::spawn(move || {
-                let dx = [ 1, 1, 1, 0, -1, -1, -1, 0 ];
-                let dy = [ -1, 0, 1, 1, 1, 0, -1, -1 ];
-                let mut z: f64;
-                let mut zn: f64;
-                let mut zout: f64;
-                for row in (0..rows).filter(|r| r % num_procs == tid) {
-                    let mut data = vec![nodata; columns as usize];
-                    for col in 0..columns {
-                        z = input[(row, col)];
-                        if z > 0f64 && z != nodata {
-                            zout = 0f64;
-                            for n in 0..8 {
-                                zn = input[(row + dy[n], col + dx[n])];
-                                if zn != z {
-                                    zout = z;
-                                    break;
+        let mut output = Raster::initialize_using_file(&output_file, &input);
+        // the input raster is read in windows of rows, with a halo of one row on either side
+        let window_rows = input.get_window_rows();
+        let mut start_row = 0;
+        while start_row < rows {
+            let end_row = min(start_row + window_rows, rows);
+            let window = Arc::new(input.get_window(start_row - 1, end_row + 1));
+            let (tx, rx) = mpsc::channel();
+            for tid in 0..num_procs {
+                let window = window.clone();
+                let tx = tx.clone();
+                thread::spawn(move || {
+                    let dx = [ 1, 1, 1, 0, -1, -1, -1, 0 ];
+                    let dy = [ -1, 0, 1, 1, 1, 0, -1, -1 ];
+                    let mut z: f64;
+                    let mut zn: f64;
+                    let mut zout: f64;
+                    for row in (start_row..end_row).filter(|r| r % num_procs == tid) {
+                        let mut data = vec![nodata; columns as usize];
+                        for col in 0..columns {
+                            z = window.get_value(row, col);
+                            if z > 0f64 && z != nodata {
+                                zout = 0f64;
+                                for n in 0..8 {
+                                    zn = window.get_value(row + dy[n], col + dx[n]);
+                                    if zn != z {
+                                        zout = z;
+                                        break;
+                                    }
                                 }
+                                data[col as usize] = zout;
                             }
-                            data[col as usize] = zout;
                         }
+                        tx.send((row, data)).unwrap();
                     }
-                    tx.send((row, data)).unwrap();
-                }
-            });
-        }
+                });
+            }
 
-        let mut output = Raster::initialize_using_file(&output_file, &input);
         
-        for r in 0..rows {
-            let (row, data) = rx.recv().unwrap();
-            output.set_row_data(row, data);
-            if verbose {
+            for r in start_row..end_row {
+                let (row, data) = rx.recv().unwrap();
+                output.set_row_data(row, data);
                 progress = (100.0_f64 * r as f64 / (rows - 1) as f64) as usize;
                 if progress != old_progress {
-                    println!("Progress: {}%", progress);
+                    sink.progress("Progress", progress)?;
                     old_progress = progress;
                 }
             }
+            start_row = end_row;
         }
 
         let end = time::now();
@@ -208,14 +184,14 @@ impl WhiteboxTool for FindPatchOrClassEdgeCells {
         output.add_metadata_entry(format!("Input file: {}", input_file));
         output.add_metadata_entry(format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
 
-        if verbose { println!("Saving data...") };
+        if verbose { sink.message("Saving data...") };
         let _ = match output.write() {
-            Ok(_) => if verbose { println!("Output file written") },
+            Ok(_) => if verbose { sink.message("Output file written") },
             Err(e) => return Err(e),
         };
 
         if verbose {
-            println!("{}", &format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
+            sink.message(&format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
         }
 
         Ok(())
@@ -12,8 +12,10 @@ use std::path;
 use std::f64;
 use raster::*;
 use std::io::{Error, ErrorKind};
+use std::sync::Arc;
 use structures::Array2D;
 use tools::*;
+use tools::arg_parser::ArgValues;
 
 pub struct HighestPosition {
     name: String,
@@ -95,42 +97,19 @@ impl WhiteboxTool for HighestPosition {
         self.toolbox.clone()
     }
 
-    fn run<'a>(&self, args: Vec<String>, working_directory: &'a str, verbose: bool) -> Result<(), Error> {
-        let mut input_files = String::new();
-        let mut output_file = String::new();
-        
+    fn run<'a>(&self, args: Vec<String>, working_directory: &'a str, verbose: bool, sink: &Arc<ProgressSink>) -> Result<(), Error> {
         if args.len() == 0 {
             return Err(Error::new(ErrorKind::InvalidInput,
                                 "Tool run with no paramters."));
         }
-        for i in 0..args.len() {
-            let mut arg = args[i].replace("\"", "");
-            arg = arg.replace("\'", "");
-            let cmd = arg.split("="); // in case an equals sign was used
-            let vec = cmd.collect::<Vec<&str>>();
-            let mut keyval = false;
-            if vec.len() > 1 {
-                keyval = true;
-            }
-            if vec[0].to_lowercase() == "-i" || vec[0].to_lowercase() == "--inputs" {
-                if keyval {
-                    input_files = vec[1].to_string();
-                } else {
-                    input_files = args[i+1].to_string();
-                }
-            } else if vec[0].to_lowercase() == "-o" || vec[0].to_lowercase() == "--output" {
-                if keyval {
-                    output_file = vec[1].to_string();
-                } else {
-                    output_file = args[i+1].to_string();
-                }
-            }
-        }
+        let args = ArgValues::parse(&self.parameters, &args)?;
+        let input_files = args.required_string("--inputs")?;
+        let output_file = args.required_file("--output", working_directory)?;
 
         if verbose {
-            println!("***************{}", "*".repeat(self.get_tool_name().len()));
-            println!("* Welcome to {} *", self.get_tool_name());
-            println!("***************{}", "*".repeat(self.get_tool_name().len()));
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
+            sink.message(&format!("* Welcome to {} *", self.get_tool_name()));
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
         }
 
         let sep: String = path::MAIN_SEPARATOR.to_string();
@@ -138,10 +117,6 @@ impl WhiteboxTool for HighestPosition {
         let mut progress: usize;
         let mut old_progress: usize = 1;
 
-        if !output_file.contains(&sep) && !output_file.contains("/") {
-            output_file = format!("{}{}", working_directory, output_file);
-        }
-
         let mut cmd = input_files.split(";");
         let mut vec = cmd.collect::<Vec<&str>>();
         if vec.len() == 1 {
@@ -168,7 +143,7 @@ impl WhiteboxTool for HighestPosition {
         let mut i = 1;
         for value in vec {
             if !value.trim().is_empty() {
-                if verbose { println!("Reading data...") };
+                if verbose { sink.message("Reading data...") };
 
                 let mut input_file = value.trim().to_owned();
                 if !input_file.contains(&sep) && !input_file.contains("/") {
@@ -193,20 +168,18 @@ impl WhiteboxTool for HighestPosition {
 
                 for row in 0..rows {
                     for col in 0..columns {
-                        z = input[(row, col)];
+                        z = input.get_value(row, col);
                         if z != in_nodata {
                             if z > high_val[(row, col)] {
                                 high_val[(row, col)] = z;
-                                output[(row, col)] = i as f64;
+                                output.set_value(row, col, i as f64);
                             }
                         }
                     }
-                    if verbose {
-                        progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as usize;
-                        if progress != old_progress {
-                            println!("Progress (loop {} of {}): {}%", i, num_files, progress);
-                            old_progress = progress;
-                        }
+                    progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as usize;
+                    if progress != old_progress {
+                        sink.progress(&format!("Progress (loop {} of {})", i, num_files), progress)?;
+                        old_progress = progress;
                     }
                 }
             }
@@ -218,14 +191,14 @@ impl WhiteboxTool for HighestPosition {
         output.add_metadata_entry(format!("Created by whitebox_tools\' {} tool", self.get_tool_name()));
         output.add_metadata_entry(format!("Elapsed Time (including I/O): {}", elapsed_time).replace("PT", ""));
 
-        if verbose { println!("Saving data...") };
+        if verbose { sink.message("Saving data...") };
         let _ = match output.write() {
-            Ok(_) => if verbose { println!("Output file written") },
+            Ok(_) => if verbose { sink.message("Output file written") },
             Err(e) => return Err(e),
         };
 
         if verbose {
-            println!("{}", &format!("Elapsed Time (including I/O): {}", elapsed_time).replace("PT", ""));
+            sink.message(&format!("Elapsed Time (including I/O): {}", elapsed_time).replace("PT", ""));
         }
 
         Ok(())
@@ -18,15 +18,19 @@ mod max_abs_overlay;
 mod max_overlay;
 mod min_abs_overlay;
 mod min_overlay;
+mod ordinary_kriging;
 mod percent_equal_to;
 mod percent_greater_than;
 mod percent_less_than;
 mod pick_from_list;
 mod radius_of_gyration;
 mod raster_cell_assignment;
+mod raster_to_vector_polygons;
 mod reclass;
 mod reclass_equal_interval;
 mod reclass_from_file;
+mod universal_kriging;
+mod variogram;
 mod weighted_sum;
 
 // exports identifiers from private sub-modules in the current module namespace
@@ -49,13 +53,17 @@ pub use self::max_abs_overlay::MaxAbsoluteOverlay;
 pub use self::max_overlay::MaxOverlay;
 pub use self::min_abs_overlay::MinAbsoluteOverlay;
 pub use self::min_overlay::MinOverlay;
+pub use self::ordinary_kriging::OrdinaryKriging;
 pub use self::percent_equal_to::PercentEqualTo;
 pub use self::percent_greater_than::PercentGreaterThan;
 pub use self::percent_less_than::PercentLessThan;
 pub use self::pick_from_list::PickFromList;
 pub use self::radius_of_gyration::RadiusOfGyration;
 pub use self::raster_cell_assignment::RasterCellAssignment;
+pub use self::raster_to_vector_polygons::RasterToVectorPolygons;
 pub use self::reclass::Reclass;
 pub use self::reclass_equal_interval::ReclassEqualInterval;
 pub use self::reclass_from_file::ReclassFromFile;
+pub use self::universal_kriging::UniversalKriging;
+pub use self::variogram::Variogram;
 pub use self::weighted_sum::WeightedSum;
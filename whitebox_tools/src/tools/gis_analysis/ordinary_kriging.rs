@@ -0,0 +1,835 @@
+/*
+This tool is part of the WhiteboxTools geospatial analysis library.
+Authors: Dr. John Lindsay
+Created: April 3, 2018
+Last Modified: April 3, 2018
+License: MIT
+
+Help: This tool interpolates a raster surface from a set of sample points using ordinary
+kriging, the best linear unbiased estimator of a surface with an unknown, but locally
+constant, mean. The input (--input) may be either a vector points file, in which case the
+sampled values are read from a numeric attribute field (--field) or, for PointZ files, from
+the point z values (--use_z), or a LiDAR file, in which case the point elevations are used.
+Withheld LiDAR points are always excluded and point classes may be excluded using
+--exclude_cls. Points that share the same location are merged and assigned their average
+value.
+
+The tool produces two rasters: the kriging estimate (--output) and the kriging variance
+(--variance), which is the expected squared error of the estimate and provides a measure of
+its uncertainty. The output grid is either defined by a base raster (--base) or covers the
+extent of the points with the specified cell size (--cell_size).
+
+The spatial structure of the data is modelled by a spherical, exponential, or Gaussian
+variogram (--model) that is fitted to the empirical semivariogram of the points, calculated
+using the lag size (--lag_size) and number of lags (--num_lags) in the same way as the
+Variogram tool. Any of the fitted nugget, partial sill, and range can be replaced with
+user-specified values (--nugget, --partial_sill, and --range), e.g. those of a model that
+has been examined using the Variogram tool. Each grid cell is estimated from the nearest
+--num_points sample points, optionally limited to those within a search radius (--radius).
+Grid cells without any sample points within the search radius are assigned NoData.
+
+See Also: Variogram, UniversalKriging, LidarTINGridding
+*/
+extern crate time;
+extern crate num_cpus;
+
+use std::env;
+use std::f64;
+use std::io::{Error, ErrorKind};
+use std::path;
+use std::sync::Arc;
+use std::sync::mpsc;
+use std::thread;
+use raster::*;
+use spatial_ref_system::read_spatial_ref;
+use structures::KdTree;
+use structures::kd_tree::squared_euclidean;
+use vector::Point2D;
+use tools::*;
+use tools::arg_parser::ArgValues;
+use super::variogram::{default_lag_size, parse_class_list, read_sample_points, EmpiricalVariogram, VariogramModel, VariogramParameters};
+
+pub struct OrdinaryKriging {
+    name: String,
+    description: String,
+    toolbox: String,
+    parameters: Vec<ToolParameter>,
+    example_usage: String,
+}
+
+impl OrdinaryKriging {
+    pub fn new() -> OrdinaryKriging { // public constructor
+        let name = "OrdinaryKriging".to_string();
+        let toolbox = "GIS Analysis".to_string();
+        let description = "Interpolates a raster and its kriging variance from sample points using ordinary kriging.".to_string();
+
+        let parameters = kriging_parameters();
+
+        let sep: String = path::MAIN_SEPARATOR.to_string();
+        let p = format!("{}", env::current_dir().unwrap().display());
+        let e = format!("{}", env::current_exe().unwrap().display());
+        let mut short_exe = e.replace(&p, "").replace(".exe", "").replace(".", "").replace(&sep, "");
+        if e.contains(".exe") {
+            short_exe += ".exe";
+        }
+        let usage = format!(">>.*{0} -r={1} -v --wd=\"*path*to*data*\" -i=points.shp --field=ELEV -o=surface.tif --variance=variance.tif --cell_size=10.0 --model=spherical
+>>.*{0} -r={1} -v --wd=\"*path*to*data*\" -i=file.las --exclude_cls='1,3,4,5,6,7,18' -o=dem.tif --variance=variance.tif --base=base.tif --num_points=24 --range=50.0", short_exe, name).replace("*", &sep);
+
+        OrdinaryKriging {
+            name: name,
+            description: description,
+            toolbox: toolbox,
+            parameters: parameters,
+            example_usage: usage
+        }
+    }
+}
+
+impl WhiteboxTool for OrdinaryKriging {
+    fn get_source_file(&self) -> String {
+        String::from(file!())
+    }
+
+    fn get_tool_name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn get_tool_description(&self) -> String {
+        self.description.clone()
+    }
+
+    fn get_tool_parameters(&self) -> String {
+        match serde_json::to_string(&self.parameters) {
+            Ok(json_str) => return format!("{{\"parameters\":{}}}", json_str),
+            Err(err) => return format!("{:?}", err),
+        }
+    }
+
+    fn get_example_usage(&self) -> String {
+        self.example_usage.clone()
+    }
+
+    fn get_toolbox(&self) -> String {
+        self.toolbox.clone()
+    }
+
+    fn run<'a>(&self, args: Vec<String>, working_directory: &'a str, verbose: bool, sink: &Arc<ProgressSink>) -> Result<(), Error> {
+        if args.len() == 0 {
+            return Err(Error::new(ErrorKind::InvalidInput,
+                                "Tool run with no paramters."));
+        }
+        let args = ArgValues::parse(&self.parameters, &args)?;
+        let options = KrigingOptions::parse(&args, working_directory)?;
+
+        if verbose {
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
+            sink.message(&format!("* Welcome to {} *", self.get_tool_name()));
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
+        }
+
+        if verbose { sink.message("Reading points data...") };
+        let (points, values) = read_sample_points(&options.input_file, &options.field_name, options.use_z, &options.include_class_vals)?;
+
+        let start = time::now();
+
+        if verbose { sink.message("Fitting the variogram model...") };
+        let variogram = options.variogram(&points, &values);
+        if verbose {
+            sink.message(&format!("{} variogram: nugget={:.6}, partial sill={:.6}, range={:.4}",
+                variogram.model.to_string(), variogram.nugget, variogram.partial_sill, variogram.range));
+        }
+
+        let interpolator = Arc::new(KrigingInterpolator::new(points, values, variogram, 0, options.num_points, options.radius));
+        let (mut output, mut variance) = options.interpolate(interpolator, sink)?;
+
+        let end = time::now();
+        let elapsed_time = end - start;
+
+        for raster in vec![&mut output, &mut variance] {
+            raster.add_metadata_entry(format!("Created by whitebox_tools\' {} tool", self.get_tool_name()));
+            raster.add_metadata_entry(format!("Input file: {}", options.input_file));
+            raster.add_metadata_entry(format!("Variogram model: {}", variogram.model.to_string()));
+            raster.add_metadata_entry(format!("Nugget: {}", variogram.nugget));
+            raster.add_metadata_entry(format!("Partial sill: {}", variogram.partial_sill));
+            raster.add_metadata_entry(format!("Range: {}", variogram.range));
+            raster.add_metadata_entry(format!("Number of points: {}", options.num_points));
+            raster.add_metadata_entry(format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
+        }
+
+        if verbose { sink.message("Saving data...") };
+        let _ = match output.write() {
+            Ok(_) => if verbose { sink.message("Output file written") },
+            Err(e) => return Err(e),
+        };
+        let _ = match variance.write() {
+            Ok(_) => if verbose { sink.message("Variance file written") },
+            Err(e) => return Err(e),
+        };
+
+        if verbose {
+            sink.message(&format!("{}", &format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", "")));
+        }
+
+        Ok(())
+    }
+}
+
+/// Returns the parameters shared by the ordinary and universal kriging tools.
+pub fn kriging_parameters() -> Vec<ToolParameter> {
+    let mut parameters = vec![];
+    parameters.push(ToolParameter{
+        name: "Input File".to_owned(),
+        flags: vec!["-i".to_owned(), "--input".to_owned()],
+        description: "Input vector points file or LiDAR file.".to_owned(),
+        parameter_type: ParameterType::ExistingFile(ParameterFileType::Any),
+        default_value: None,
+        optional: false
+    });
+
+    parameters.push(ToolParameter{
+        name: "Field Name".to_owned(),
+        flags: vec!["--field".to_owned()],
+        description: "Input field name in attribute table; not used with LiDAR input.".to_owned(),
+        parameter_type: ParameterType::VectorAttributeField(AttributeType::Number, "--input".to_string()),
+        default_value: None,
+        optional: true
+    });
+
+    parameters.push(ToolParameter{
+        name: "Use z-coordinate instead of field?".to_owned(),
+        flags: vec!["--use_z".to_owned()],
+        description: "Use the z-coordinates of PointZ input rather than an attribute field.".to_owned(),
+        parameter_type: ParameterType::Boolean,
+        default_value: Some("false".to_owned()),
+        optional: true
+    });
+
+    parameters.push(ToolParameter{
+        name: "Exclusion Classes (0-18, based on LAS spec; e.g. 3,4,5,6,7)".to_owned(),
+        flags: vec!["--exclude_cls".to_owned()],
+        description: "Optional exclude classes from LiDAR input; Valid class values range from 0 to 18, based on LAS specifications. Example, --exclude_cls='3,4,5,6,7,18'.".to_owned(),
+        parameter_type: ParameterType::String,
+        default_value: None,
+        optional: true
+    });
+
+    parameters.push(ToolParameter{
+        name: "Output File".to_owned(),
+        flags: vec!["-o".to_owned(), "--output".to_owned()],
+        description: "Output raster file of kriging estimates.".to_owned(),
+        parameter_type: ParameterType::NewFile(ParameterFileType::Raster),
+        default_value: None,
+        optional: false
+    });
+
+    parameters.push(ToolParameter{
+        name: "Output Variance File".to_owned(),
+        flags: vec!["--variance".to_owned()],
+        description: "Output raster file of kriging variance.".to_owned(),
+        parameter_type: ParameterType::NewFile(ParameterFileType::Raster),
+        default_value: None,
+        optional: false
+    });
+
+    parameters.push(ToolParameter{
+        name: "Cell Size (optional)".to_owned(),
+        flags: vec!["--cell_size".to_owned()],
+        description: "Optionally specified cell size of output raster. Not used when base raster is specified.".to_owned(),
+        parameter_type: ParameterType::Float,
+        default_value: None,
+        optional: true
+    });
+
+    parameters.push(ToolParameter{
+        name: "Base Raster File (optional)".to_owned(),
+        flags: vec!["--base".to_owned()],
+        description: "Optionally specified input base raster file. Not used when a cell size is specified.".to_owned(),
+        parameter_type: ParameterType::ExistingFile(ParameterFileType::Raster),
+        default_value: None,
+        optional: true
+    });
+
+    parameters.push(ToolParameter{
+        name: "Variogram Model".to_owned(),
+        flags: vec!["--model".to_owned()],
+        description: "Variogram model; options are 'spherical' (default), 'exponential', 'gaussian'.".to_owned(),
+        parameter_type: ParameterType::OptionList(vec!["spherical".to_owned(), "exponential".to_owned(), "gaussian".to_owned()]),
+        default_value: Some("spherical".to_owned()),
+        optional: true
+    });
+
+    parameters.push(ToolParameter{
+        name: "Lag Size (optional)".to_owned(),
+        flags: vec!["--lag_size".to_owned()],
+        description: "Optional lag size used to fit the variogram; by default the lags span half of the diagonal of the points' bounding box.".to_owned(),
+        parameter_type: ParameterType::Float,
+        default_value: None,
+        optional: true
+    });
+
+    parameters.push(ToolParameter{
+        name: "Number of Lags".to_owned(),
+        flags: vec!["--num_lags".to_owned()],
+        description: "Number of lags used to fit the variogram.".to_owned(),
+        parameter_type: ParameterType::Integer,
+        default_value: Some("15".to_owned()),
+        optional: true
+    });
+
+    parameters.push(ToolParameter{
+        name: "Nugget (optional)".to_owned(),
+        flags: vec!["--nugget".to_owned()],
+        description: "Optional variogram nugget, replacing the fitted value.".to_owned(),
+        parameter_type: ParameterType::Float,
+        default_value: None,
+        optional: true
+    });
+
+    parameters.push(ToolParameter{
+        name: "Partial Sill (optional)".to_owned(),
+        flags: vec!["--partial_sill".to_owned()],
+        description: "Optional variogram partial sill (sill minus nugget), replacing the fitted value.".to_owned(),
+        parameter_type: ParameterType::Float,
+        default_value: None,
+        optional: true
+    });
+
+    parameters.push(ToolParameter{
+        name: "Range (optional)".to_owned(),
+        flags: vec!["--range".to_owned()],
+        description: "Optional variogram range, replacing the fitted value.".to_owned(),
+        parameter_type: ParameterType::Float,
+        default_value: None,
+        optional: true
+    });
+
+    parameters.push(ToolParameter{
+        name: "Number of Points".to_owned(),
+        flags: vec!["--num_points".to_owned()],
+        description: "Number of nearest sample points used to estimate each grid cell.".to_owned(),
+        parameter_type: ParameterType::Integer,
+        default_value: Some("16".to_owned()),
+        optional: true
+    });
+
+    parameters.push(ToolParameter{
+        name: "Search Radius (optional)".to_owned(),
+        flags: vec!["--radius".to_owned()],
+        description: "Optional search radius; by default the nearest points are used regardless of distance.".to_owned(),
+        parameter_type: ParameterType::Float,
+        default_value: None,
+        optional: true
+    });
+
+    parameters
+}
+
+/// The options shared by the ordinary and universal kriging tools.
+pub struct KrigingOptions {
+    pub input_file: String,
+    pub field_name: String,
+    pub use_z: bool,
+    pub include_class_vals: Vec<bool>,
+    pub output_file: String,
+    pub variance_file: String,
+    pub cell_size: f64,
+    pub base_file: String,
+    pub model: VariogramModel,
+    pub lag_size: f64,
+    pub num_lags: usize,
+    pub nugget: Option<f64>,
+    pub partial_sill: Option<f64>,
+    pub range: Option<f64>,
+    pub num_points: usize,
+    pub radius: f64,
+}
+
+impl KrigingOptions {
+    /// Reads the shared kriging options from the tool arguments.
+    pub fn parse(args: &ArgValues, working_directory: &str) -> Result<KrigingOptions, Error> {
+        let model = args.get_string("--model")?.unwrap_or("spherical".to_string());
+        let options = KrigingOptions {
+            input_file: args.required_file("--input", working_directory)?,
+            field_name: args.get_string("--field")?.unwrap_or(String::new()),
+            use_z: args.get_bool("--use_z")?,
+            include_class_vals: parse_class_list(&args.get_string("--exclude_cls")?.unwrap_or(String::new()))?,
+            output_file: args.get_file("--output", working_directory)?.unwrap_or(String::new()),
+            variance_file: args.get_file("--variance", working_directory)?.unwrap_or(String::new()),
+            cell_size: args.get_f64("--cell_size")?.unwrap_or(0f64),
+            base_file: args.get_file("--base", working_directory)?.unwrap_or(String::new()),
+            model: match VariogramModel::from_str(&model) {
+                Some(m) => m,
+                None => return Err(Error::new(ErrorKind::InvalidInput,
+                    format!("Unrecognized variogram model: {}", model))),
+            },
+            lag_size: args.get_f64("--lag_size")?.unwrap_or(0f64),
+            num_lags: args.get_usize("--num_lags")?.unwrap_or(15),
+            nugget: args.get_f64("--nugget")?,
+            partial_sill: args.get_f64("--partial_sill")?,
+            range: args.get_f64("--range")?,
+            num_points: args.get_usize("--num_points")?.unwrap_or(16),
+            radius: args.get_f64("--radius")?.unwrap_or(f64::INFINITY),
+        };
+
+        if options.output_file.is_empty() || options.variance_file.is_empty() {
+            return Err(Error::new(ErrorKind::InvalidInput,
+                "Both an output file (--output) and a variance file (--variance) must be specified."));
+        }
+        if options.base_file.is_empty() && !(options.cell_size > 0f64) {
+            return Err(Error::new(ErrorKind::InvalidInput,
+                "Either a base raster (--base) or a cell size (--cell_size) greater than zero must be specified."));
+        }
+        if options.num_lags < 2 {
+            return Err(Error::new(ErrorKind::InvalidInput,
+                "The number of lags must be at least two."));
+        }
+        if options.num_points < 1 {
+            return Err(Error::new(ErrorKind::InvalidInput,
+                "The number of points must be at least one."));
+        }
+        if options.range.map_or(false, |r| !(r > 0f64)) {
+            return Err(Error::new(ErrorKind::InvalidInput,
+                "The variogram range must be greater than zero."));
+        }
+        Ok(options)
+    }
+
+    /// Fits the variogram model to the empirical semivariogram of the points, replacing
+    /// any of its parameters that have been specified by the user.
+    pub fn variogram(&self, points: &[Point2D], values: &[f64]) -> VariogramParameters {
+        let lag_size = if self.lag_size > 0f64 { self.lag_size } else { default_lag_size(points, self.num_lags) };
+        let empirical = EmpiricalVariogram::new(points, values, lag_size, self.num_lags);
+        let mut variogram = if empirical.distances.len() > 0 {
+            empirical.fit(self.model).0
+        } else {
+            VariogramParameters { model: self.model, nugget: 0f64, partial_sill: 1f64, range: lag_size * self.num_lags as f64 }
+        };
+        if let Some(nugget) = self.nugget {
+            variogram.nugget = nugget;
+        }
+        if let Some(partial_sill) = self.partial_sill {
+            variogram.partial_sill = partial_sill;
+        }
+        if let Some(range) = self.range {
+            variogram.range = range;
+        }
+        variogram
+    }
+
+    /// Creates the output and variance rasters and fills them with the kriging estimates
+    /// and variances of each grid cell.
+    pub fn interpolate(&self, interpolator: Arc<KrigingInterpolator>, sink: &Arc<ProgressSink>) -> Result<(Raster, Raster), Error> {
+        let mut configs = if !self.base_file.is_empty() {
+            let base = Raster::new(&self.base_file, "r")?;
+            base.configs.clone()
+        } else {
+            let (mut min_x, mut max_x) = (f64::INFINITY, f64::NEG_INFINITY);
+            let (mut min_y, mut max_y) = (f64::INFINITY, f64::NEG_INFINITY);
+            for p in &interpolator.points {
+                min_x = min_x.min(p.x);
+                max_x = max_x.max(p.x);
+                min_y = min_y.min(p.y);
+                max_y = max_y.max(p.y);
+            }
+            let rows = (((max_y - min_y) / self.cell_size).ceil() as usize).max(1);
+            let columns = (((max_x - min_x) / self.cell_size).ceil() as usize).max(1);
+            let mut configs = RasterConfigs { ..Default::default() };
+            configs.rows = rows;
+            configs.columns = columns;
+            configs.north = max_y;
+            configs.south = max_y - rows as f64 * self.cell_size;
+            configs.east = min_x + columns as f64 * self.cell_size;
+            configs.west = min_x;
+            configs.resolution_x = self.cell_size;
+            configs.resolution_y = self.cell_size;
+            if let Some(srs) = read_spatial_ref(&self.input_file)? {
+                configs.epsg_code = srs.epsg_code;
+                configs.coordinate_ref_system_wkt = srs.to_wkt();
+            }
+            configs
+        };
+        let nodata = nodata_value(&interpolator.values, configs.nodata);
+        configs.nodata = nodata;
+        configs.data_type = DataType::F64;
+        configs.photometric_interp = PhotometricInterpretation::Continuous;
+        configs.palette = "spectrum.plt".to_string();
+
+        let mut output = Raster::initialize_using_config(&self.output_file, &configs);
+        let mut variance = Raster::initialize_using_config(&self.variance_file, &configs);
+
+        let rows = configs.rows as isize;
+        let columns = configs.columns as isize;
+        let north = configs.north;
+        let west = configs.west;
+        let res_x = configs.resolution_x;
+        let res_y = configs.resolution_y;
+        let num_procs = num_cpus::get() as isize;
+        let (tx, rx) = mpsc::channel();
+        for tid in 0..num_procs {
+            let interpolator = interpolator.clone();
+            let tx = tx.clone();
+            thread::spawn(move || {
+                for row in (0..rows).filter(|r| r % num_procs == tid) {
+                    let y = north - (row as f64 + 0.5) * res_y;
+                    let mut estimates = vec![nodata; columns as usize];
+                    let mut variances = vec![nodata; columns as usize];
+                    for col in 0..columns {
+                        let x = west + (col as f64 + 0.5) * res_x;
+                        if let Some((z, v)) = interpolator.estimate(x, y) {
+                            estimates[col as usize] = z;
+                            variances[col as usize] = v;
+                        }
+                    }
+                    tx.send((row, estimates, variances)).unwrap();
+                }
+            });
+        }
+        // without this, a thread that panics would leave the loop below waiting forever
+        drop(tx);
+
+        let mut progress: usize;
+        let mut old_progress: usize = 1;
+        for r in 0..rows {
+            let (row, estimates, variances) = match rx.recv() {
+                Ok(data) => data,
+                Err(_) => return Err(Error::new(ErrorKind::Other,
+                    "An interpolation thread stopped before estimating all of the grid rows.")),
+            };
+            output.set_row_data(row, estimates);
+            variance.set_row_data(row, variances);
+            progress = (100.0_f64 * r as f64 / (rows - 1).max(1) as f64) as usize;
+            if progress != old_progress {
+                sink.progress("Interpolating", progress)?;
+                old_progress = progress;
+            }
+        }
+
+        Ok((output, variance))
+    }
+}
+
+/// Returns a NoData value that cannot be mistaken for a kriging estimate or variance. The
+/// preferred value (that of the base raster, or -32768) is used when it lies well below the
+/// range of the sample values and zero; otherwise a value below that range is chosen. Estimates
+/// may extrapolate somewhat beyond the sample values, hence the margin of one data range.
+fn nodata_value(values: &[f64], preferred: f64) -> f64 {
+    let min = values.iter().cloned().fold(f64::INFINITY, f64::min);
+    let max = values.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+    let margin = (max - min).max(1f64);
+    // variances are never negative, so a negative value only needs to avoid the estimates
+    let lower = min.min(0f64) - margin;
+    if preferred < lower {
+        preferred
+    } else if -32768f64 < lower {
+        -32768f64
+    } else {
+        (lower - margin).floor()
+    }
+}
+
+/// Estimates values by local kriging, using the nearest sample points to each location and
+/// polynomial drift terms of the given order (zero for ordinary kriging).
+pub struct KrigingInterpolator {
+    pub points: Vec<Point2D>,
+    pub values: Vec<f64>,
+    pub variogram: VariogramParameters,
+    pub drift_order: usize,
+    pub num_points: usize,
+    pub radius: f64,
+    tree: KdTree<usize, [f64; 2]>,
+}
+
+impl KrigingInterpolator {
+    pub fn new(points: Vec<Point2D>, values: Vec<f64>, variogram: VariogramParameters, drift_order: usize, num_points: usize, radius: f64) -> KrigingInterpolator {
+        let mut tree = KdTree::new_with_capacity(2, 64);
+        for i in 0..points.len() {
+            tree.add([points[i].x, points[i].y], i).unwrap();
+        }
+        KrigingInterpolator {
+            points: points,
+            values: values,
+            variogram: variogram,
+            drift_order: drift_order,
+            num_points: num_points,
+            radius: radius,
+            tree: tree,
+        }
+    }
+
+    /// Returns the kriging estimate and variance at (x, y), or None if there are too few
+    /// sample points within the search radius or the kriging system is singular.
+    pub fn estimate(&self, x: f64, y: f64) -> Option<(f64, f64)> {
+        let neighbours: Vec<usize> = match self.tree.nearest(&[x, y], self.num_points, &squared_euclidean) {
+            Ok(ret) => ret.into_iter().filter(|n| n.0 <= self.radius * self.radius).map(|n| *n.1).collect(),
+            Err(_) => return None,
+        };
+        let n = neighbours.len();
+        let m = num_drift_terms(self.drift_order);
+        if n == 0 || n < m {
+            return None;
+        }
+
+        // The drift terms are evaluated on coordinates relative to the estimated location
+        // and scaled by the neighbourhood size, keeping the system well conditioned.
+        let mut scale = 0f64;
+        for &i in &neighbours {
+            scale = scale.max(((self.points[i].x - x).powi(2) + (self.points[i].y - y).powi(2)).sqrt());
+        }
+        if scale == 0f64 {
+            scale = 1f64;
+        }
+
+        let size = n + m;
+        let mut a = vec![vec![0f64; size]; size];
+        let mut b = vec![0f64; size];
+        for r in 0..n {
+            let pr = &self.points[neighbours[r]];
+            for c in (r + 1)..n {
+                let pc = &self.points[neighbours[c]];
+                let h = ((pr.x - pc.x).powi(2) + (pr.y - pc.y).powi(2)).sqrt();
+                a[r][c] = self.variogram.semivariance(h);
+                a[c][r] = a[r][c];
+            }
+            let f = drift_terms((pr.x - x) / scale, (pr.y - y) / scale, self.drift_order);
+            for k in 0..m {
+                a[r][n + k] = f[k];
+                a[n + k][r] = f[k];
+            }
+            b[r] = self.variogram.semivariance(((pr.x - x).powi(2) + (pr.y - y).powi(2)).sqrt());
+        }
+        let f0 = drift_terms(0f64, 0f64, self.drift_order);
+        for k in 0..m {
+            b[n + k] = f0[k];
+        }
+
+        let weights = match solve_linear_system(a, b.clone()) {
+            Some(w) => w,
+            None => return None,
+        };
+        let mut z = 0f64;
+        for r in 0..n {
+            z += weights[r] * self.values[neighbours[r]];
+        }
+        let mut variance = 0f64;
+        for r in 0..size {
+            variance += weights[r] * b[r];
+        }
+        Some((z, variance.max(0f64)))
+    }
+}
+
+/// Returns the number of polynomial drift terms of the given order.
+pub fn num_drift_terms(order: usize) -> usize {
+    (order + 1) * (order + 2) / 2
+}
+
+/// Evaluates the polynomial drift terms of the given order at (x, y), i.e. 1, x, y, x^2,
+/// xy, y^2, and so on.
+pub fn drift_terms(x: f64, y: f64, order: usize) -> Vec<f64> {
+    let mut terms = Vec::with_capacity(num_drift_terms(order));
+    for d in 0..order + 1 {
+        for k in 0..d + 1 {
+            terms.push(x.powi((d - k) as i32) * y.powi(k as i32));
+        }
+    }
+    terms
+}
+
+/// Solves the linear system a x = b by Gaussian elimination with partial pivoting,
+/// returning None if the system is singular.
+pub fn solve_linear_system(mut a: Vec<Vec<f64>>, mut b: Vec<f64>) -> Option<Vec<f64>> {
+    let n = b.len();
+    let mut max_abs = 0f64;
+    for r in 0..n {
+        for c in 0..n {
+            max_abs = max_abs.max(a[r][c].abs());
+        }
+    }
+    let tolerance = max_abs * 1e-12;
+    for c in 0..n {
+        let mut pivot = c;
+        for r in (c + 1)..n {
+            if a[r][c].abs() > a[pivot][c].abs() {
+                pivot = r;
+            }
+        }
+        if !(a[pivot][c].abs() > tolerance) {
+            return None;
+        }
+        a.swap(c, pivot);
+        b.swap(c, pivot);
+        for r in (c + 1)..n {
+            let factor = a[r][c] / a[c][c];
+            if factor != 0f64 {
+                for k in c..n {
+                    a[r][k] -= factor * a[c][k];
+                }
+                b[r] -= factor * b[c];
+            }
+        }
+    }
+    let mut x = vec![0f64; n];
+    for r in (0..n).rev() {
+        let mut sum = b[r];
+        for k in (r + 1)..n {
+            sum -= a[r][k] * x[k];
+        }
+        x[r] = sum / a[r][r];
+    }
+    Some(x)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::path::Path;
+    use vector::*;
+
+    fn surface(x: f64, y: f64) -> f64 {
+        (x * 0.4).sin() * 10.0 + (y * 0.3).cos() * 5.0 + 0.2 * x
+    }
+
+    // samples of the surface at the centres of every other cell of a 20 x 20 grid of unit cells
+    fn samples() -> (Vec<Point2D>, Vec<f64>) {
+        let mut points = vec![];
+        let mut values = vec![];
+        for row in (0..20).filter(|r| r % 2 == 0) {
+            for col in (0..20).filter(|c| c % 2 == 0) {
+                let (x, y) = (col as f64 + 0.5, 19.5 - row as f64);
+                points.push(Point2D { x: x, y: y });
+                values.push(surface(x, y));
+            }
+        }
+        (points, values)
+    }
+
+    fn variogram(model: VariogramModel, nugget: f64) -> VariogramParameters {
+        VariogramParameters { model: model, nugget: nugget, partial_sill: 30.0, range: 12.0 }
+    }
+
+    #[test]
+    fn kriging_reproduces_the_sample_values() {
+        let (points, values) = samples();
+        for &model in [VariogramModel::Spherical, VariogramModel::Exponential, VariogramModel::Gaussian].iter() {
+            for drift_order in 0..3 {
+                let interpolator = KrigingInterpolator::new(points.clone(), values.clone(), variogram(model, 0.0), drift_order, 16, f64::INFINITY);
+                for i in 0..points.len() {
+                    let (z, v) = interpolator.estimate(points[i].x, points[i].y).unwrap();
+                    assert!((z - values[i]).abs() < 1e-6, "{:?}, drift {}: {} != {}", model, drift_order, z, values[i]);
+                    assert!(v.abs() < 1e-6);
+                }
+                // between the samples, the estimate is uncertain
+                let (_, v) = interpolator.estimate(1.5, 18.5).unwrap();
+                assert!(v > 1e-3);
+            }
+        }
+    }
+
+    #[test]
+    fn a_nugget_smooths_the_estimates_at_the_samples() {
+        let (points, values) = samples();
+        let interpolator = KrigingInterpolator::new(points.clone(), values.clone(), variogram(VariogramModel::Spherical, 5.0), 0, 16, f64::INFINITY);
+        // the semivariance is zero at zero distance, and so the samples are still honoured
+        let (z, v) = interpolator.estimate(points[50].x, points[50].y).unwrap();
+        assert!((z - values[50]).abs() < 1e-6 && v.abs() < 1e-6);
+        // but the variance jumps by at least the nugget a short distance away
+        let (_, v) = interpolator.estimate(points[50].x + 1e-6, points[50].y).unwrap();
+        assert!(v > 5.0);
+    }
+
+    #[test]
+    fn constants_and_trends_are_reproduced() {
+        let (points, _) = samples();
+        let constant = vec![42.5; points.len()];
+        let plane: Vec<f64> = points.iter().map(|p| 3.0 * p.x - 2.0 * p.y + 7.0).collect();
+        let quadratic: Vec<f64> = points.iter().map(|p| 0.1 * p.x * p.x - 0.2 * p.x * p.y + 3.0 * p.y).collect();
+        let ok = KrigingInterpolator::new(points.clone(), constant, variogram(VariogramModel::Spherical, 1.0), 0, 16, f64::INFINITY);
+        let uk1 = KrigingInterpolator::new(points.clone(), plane, variogram(VariogramModel::Exponential, 1.0), 1, 16, f64::INFINITY);
+        let uk2 = KrigingInterpolator::new(points.clone(), quadratic, variogram(VariogramModel::Gaussian, 1.0), 2, 16, f64::INFINITY);
+        for &(x, y) in [(3.3, 4.1), (10.0, 10.0), (0.0, 19.9), (17.2, 2.6)].iter() {
+            assert!((ok.estimate(x, y).unwrap().0 - 42.5).abs() < 1e-9);
+            assert!((uk1.estimate(x, y).unwrap().0 - (3.0 * x - 2.0 * y + 7.0)).abs() < 1e-8);
+            assert!((uk2.estimate(x, y).unwrap().0 - (0.1 * x * x - 0.2 * x * y + 3.0 * y)).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn locations_without_enough_points_are_not_estimated() {
+        let (points, values) = samples();
+        let interpolator = KrigingInterpolator::new(points.clone(), values.clone(), variogram(VariogramModel::Spherical, 0.0), 0, 8, 1.0);
+        assert!(interpolator.estimate(0.5, 19.5).is_some());
+        assert!(interpolator.estimate(1.5, 18.5).is_none());
+        // a quadratic drift needs six points, and only five samples lie within two units
+        let interpolator_near = KrigingInterpolator::new(points.clone(), values.clone(), variogram(VariogramModel::Spherical, 0.0), 2, 8, 2.1);
+        assert!(interpolator_near.estimate(10.5, 9.5).is_none());
+        let interpolator = KrigingInterpolator::new(points, values, variogram(VariogramModel::Spherical, 0.0), 2, 8, 3.0);
+        assert!(interpolator.estimate(0.5, 19.5).is_none());
+        assert!(interpolator.estimate(10.5, 9.5).is_some());
+    }
+
+    #[test]
+    fn nodata_values_lie_outside_of_the_data() {
+        assert_eq!(nodata_value(&[0.0, 100.0], -32768.0), -32768.0);
+        assert_eq!(nodata_value(&[0.0, 100.0], -1e10), -1e10);
+        // a base raster's NoData value within the data is replaced
+        assert_eq!(nodata_value(&[5.0, 10.0], 0.0), -32768.0);
+        assert_eq!(nodata_value(&[-40000.0, -30000.0], -32768.0), -60000.0);
+        assert_eq!(nodata_value(&[-32768.0, -32760.0], -32768.0), -32784.0);
+    }
+
+    #[test]
+    fn linear_systems_are_solved() {
+        let a = vec![vec![0.0, 2.0, 1.0], vec![1.0, -2.0, -3.0], vec![-1.0, 1.0, 2.0]];
+        let x = solve_linear_system(a, vec![-8.0, 0.0, 3.0]).unwrap();
+        let expected = [-4.0, -5.0, 2.0];
+        assert!(x.iter().zip(expected.iter()).all(|(a, b)| (a - b).abs() < 1e-12));
+        assert!(solve_linear_system(vec![vec![1.0, 2.0], vec![2.0, 4.0]], vec![1.0, 2.0]).is_none());
+        assert_eq!(drift_terms(2.0, 3.0, 2), vec![1.0, 2.0, 3.0, 4.0, 6.0, 9.0]);
+        assert_eq!((num_drift_terms(0), num_drift_terms(1), num_drift_terms(2)), (1, 3, 6));
+    }
+
+    #[test]
+    fn the_tool_honours_the_sample_points() {
+        let (points, values) = samples();
+        let points_file = env::temp_dir().join("wbt_ok_samples.shp").to_str().unwrap().to_string();
+        let mut sf = Shapefile::new(&points_file, "w").unwrap();
+        sf.header.shape_type = ShapeType::Point;
+        sf.attributes.add_field(AttributeField::new("FID", 'N', 6u8, 0u8));
+        sf.attributes.add_field(AttributeField::new("VALUE", 'F', 18u8, 12u8));
+        for i in 0..points.len() {
+            let mut sfg = ShapefileGeometry::new(ShapeType::Point);
+            sfg.add_point(points[i].clone());
+            sf.add_record(sfg);
+            // values near the default NoData value
+            sf.attributes.add_record(vec![FieldData::Int(i as i32 + 1), FieldData::Real(values[i] - 32768.0)], false).unwrap();
+        }
+        sf.write().unwrap();
+
+        let (output, variance) = (format!("{}wbt_ok_output.tif", IN_MEMORY_RASTER_PREFIX), format!("{}wbt_ok_variance.tif", IN_MEMORY_RASTER_PREFIX));
+        let args = vec![format!("-i={}", points_file), "--field=VALUE".to_string(), format!("-o={}", output),
+            format!("--variance={}", variance), "--cell_size=1.0".to_string(), "--model=gaussian".to_string()];
+        let mut sink = StdoutProgress::new();
+        sink.set_quiet(true);
+        let sink: Arc<ProgressSink> = Arc::new(sink);
+        OrdinaryKriging::new().run(args, "", false, &sink).unwrap();
+
+        let (output, variance) = (Raster::new(&output, "r").unwrap(), Raster::new(&variance, "r").unwrap());
+        // the grid covers the extent of the points, whose locations are at cell corners
+        assert_eq!((output.configs.rows, output.configs.columns), (18, 18));
+        assert!(output.configs.nodata < -32768.0 - 20.0);
+        assert_eq!(variance.configs.nodata, output.configs.nodata);
+        for row in 0..18 {
+            for col in 0..18 {
+                let (z, v) = (output.get_value(row, col), variance.get_value(row, col));
+                assert!(z != output.configs.nodata && v >= 0f64);
+                let (x, y) = (output.get_x_from_column(col), output.get_y_from_row(row));
+                // the estimates lie near the surface between the samples
+                assert!((z + 32768.0 - surface(x, y)).abs() < 2.0, "({}, {}): {}", x, y, z + 32768.0);
+            }
+        }
+        for ext in ["shp", "shx", "dbf", "cpg"].iter() {
+            let _ = fs::remove_file(Path::new(&points_file).with_extension(ext));
+        }
+    }
+}
@@ -30,6 +30,7 @@ use std::thread;
 use raster::*;
 use std::io::{Error, ErrorKind};
 use tools::*;
+use tools::arg_parser::ArgValues;
 
 pub struct RadiusOfGyration {
     name: String,
@@ -120,60 +121,25 @@ impl WhiteboxTool for RadiusOfGyration {
         self.toolbox.clone()
     }
 
-    fn run<'a>(&self, args: Vec<String>, working_directory: &'a str, verbose: bool) -> Result<(), Error> {
-        let mut input_file = String::new();
-        let mut output_file = String::new();
-        let mut text_output = false;
-        
+    fn run<'a>(&self, args: Vec<String>, working_directory: &'a str, verbose: bool, sink: &Arc<ProgressSink>) -> Result<(), Error> {
         if args.len() == 0 {
             return Err(Error::new(ErrorKind::InvalidInput, "Tool run with no paramters."));
         }
-        for i in 0..args.len() {
-            let mut arg = args[i].replace("\"", "");
-            arg = arg.replace("\'", "");
-            let cmd = arg.split("="); // in case an equals sign was used
-            let vec = cmd.collect::<Vec<&str>>();
-            let mut keyval = false;
-            if vec.len() > 1 {
-                keyval = true;
-            }
-            let flag_val = vec[0].to_lowercase().replace("--", "-");
-            if flag_val == "-i" || flag_val == "-input" {
-                if keyval {
-                    input_file = vec[1].to_string();
-                } else {
-                    input_file = args[i+1].to_string();
-                }
-            } else if flag_val == "-o" || flag_val == "-output" {
-                if keyval {
-                    output_file = vec[1].to_string();
-                } else {
-                    output_file = args[i+1].to_string();
-                }
-            } else if flag_val == "-text_output" {
-                text_output = true;
-            }
-        }
+        let args = ArgValues::parse(&self.parameters, &args)?;
+        let input_file = args.required_file("--input", working_directory)?;
+        let output_file = args.required_file("--output", working_directory)?;
+        let text_output = args.get_bool("--text_output")?;
 
         if verbose {
-            println!("***************{}", "*".repeat(self.get_tool_name().len()));
-            println!("* Welcome to {} *", self.get_tool_name());
-            println!("***************{}", "*".repeat(self.get_tool_name().len()));
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
+            sink.message(&format!("* Welcome to {} *", self.get_tool_name()));
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
         }
 
-        let sep: String = path::MAIN_SEPARATOR.to_string();
-
         let mut progress: usize;
         let mut old_progress: usize = 1;
 
-        if !input_file.contains(&sep) && !input_file.contains("/") {
-            input_file = format!("{}{}", working_directory, input_file);
-        }
-        if !output_file.contains(&sep) && !output_file.contains("/") {
-            output_file = format!("{}{}", working_directory, output_file);
-        }
-
-        if verbose { println!("Reading data...") };
+        if verbose { sink.message("Reading data...") };
 
         let input = Arc::new(Raster::new(&input_file, "r")?);
         let start = time::now();
@@ -227,12 +193,10 @@ impl WhiteboxTool for RadiusOfGyration {
                 total_n[a] += n[a];
             }
             
-            if verbose {
-                progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as usize;
-                if progress != old_progress {
-                    println!("Progress (Loop 1 of 3): {}%", progress);
-                    old_progress = progress;
-                }
+            progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as usize;
+            if progress != old_progress {
+                sink.progress("Progress (Loop 1 of 3)", progress)?;
+                old_progress = progress;
             }
         }
 
@@ -278,12 +242,10 @@ impl WhiteboxTool for RadiusOfGyration {
                 gyradius[a] += g[a];
             }
             
-            if verbose {
-                progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as usize;
-                if progress != old_progress {
-                    println!("Progress (Loop 2 of 3): {}%", progress);
-                    old_progress = progress;
-                }
+            progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as usize;
+            if progress != old_progress {
+                sink.progress("Progress (Loop 2 of 3)", progress)?;
+                old_progress = progress;
             }
         }
 
@@ -298,7 +260,7 @@ impl WhiteboxTool for RadiusOfGyration {
         let mut a: usize;
         for row in 0..rows {
             for col in 0..columns {
-                z = input[(row, col)];
+                z = input.get_value(row, col);
                 if z > 0f64 && z != nodata {
                     a = (z - min_val as f64) as usize;
                     output.set_value(row, col, gyradius[a]);
@@ -306,20 +268,18 @@ impl WhiteboxTool for RadiusOfGyration {
                     output.set_value(row, col, z);
                 }
             }
-            if verbose {
-                progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as usize;
-                if progress != old_progress {
-                    println!("Progress (Loop 3 of 3): {}%", progress);
-                    old_progress = progress;
-                }
+            progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as usize;
+            if progress != old_progress {
+                sink.progress("Progress (Loop 3 of 3)", progress)?;
+                old_progress = progress;
             }
         }
 
         if text_output {
-            println!("Patch Radius of Gyration\nPatch ID\tValue");
+            sink.message("Patch Radius of Gyration\nPatch ID\tValue");
             for a in 0..range+1 {
                 if total_n[a] > 0 {
-                    println!("{:.0}\t{:.4}", (a + min_val), gyradius[a]);
+                    sink.message(&format!("{:.0}\t{:.4}", (a + min_val), gyradius[a]));
                 }
             }
         }
@@ -332,14 +292,14 @@ impl WhiteboxTool for RadiusOfGyration {
         output.add_metadata_entry(format!("Input file: {}", input_file));
         output.add_metadata_entry(format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
 
-        if verbose { println!("Saving data...") };
+        if verbose { sink.message("Saving data...") };
         let _ = match output.write() {
-            Ok(_) => if verbose { println!("Output file written") },
+            Ok(_) => if verbose { sink.message("Output file written") },
             Err(e) => return Err(e),
         };
 
         if verbose {
-            println!("{}", &format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
+            sink.message(&format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
         }
 
         Ok(())
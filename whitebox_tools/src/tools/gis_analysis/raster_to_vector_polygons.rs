@@ -0,0 +1,558 @@
+/*
+This tool is part of the WhiteboxTools geospatial analysis library.
+Authors: Dr. John Lindsay
+Created: March 19, 2018
+Last Modified: March 19, 2018
+License: MIT
+
+Help: This tool converts a raster, e.g. a classified image or the output of the Clump,
+Basins, or Subbasins tools, into a vector polygon Shapefile. Each polygon corresponds to a
+group of contiguous grid cells with the same value, where cells are considered contiguous
+if they share an edge; cells that only touch diagonally are placed in separate polygons.
+NoData cells are not included in any polygon. Region boundaries are traced along the edges
+of grid cells, with outer rings in clockwise order and holes, including those occupied by
+islands of other values, in counter-clockwise order. The output attribute table contains
+an FID field and a VALUE field holding the cell value of each polygon.
+
+The optional --smooth parameter specifies the size of a moving-average filter (an odd
+integer, e.g. 3 or 5) used to smooth the stair-stepped polygon boundaries. Smoothing is
+applied to each boundary segment shared by two neighbouring polygons, with the nodes where
+three or more polygons meet held fixed, so that the smoothed polygons do not contain gaps
+or overlaps.
+*/
+extern crate time;
+
+use std::env;
+use std::path;
+use std::f64;
+use raster::*;
+use vector::*;
+use structures::Array2D;
+use std::io::{Error, ErrorKind};
+use std::sync::Arc;
+use tools::*;
+use tools::arg_parser::ArgValues;
+
+pub struct RasterToVectorPolygons {
+    name: String,
+    description: String,
+    toolbox: String,
+    parameters: Vec<ToolParameter>,
+    example_usage: String,
+}
+
+impl RasterToVectorPolygons {
+    pub fn new() -> RasterToVectorPolygons { // public constructor
+        let name = "RasterToVectorPolygons".to_string();
+        let toolbox = "GIS Analysis".to_string();
+        let description = "Converts groups of contiguous, equal-valued raster grid cells into vector polygons.".to_string();
+
+        let mut parameters = vec![];
+        parameters.push(ToolParameter{
+            name: "Input File".to_owned(),
+            flags: vec!["-i".to_owned(), "--input".to_owned()],
+            description: "Input raster file.".to_owned(),
+            parameter_type: ParameterType::ExistingFile(ParameterFileType::Raster),
+            default_value: None,
+            optional: false
+        });
+
+        parameters.push(ToolParameter{
+            name: "Output File".to_owned(),
+            flags: vec!["-o".to_owned(), "--output".to_owned()],
+            description: "Output vector polygon file.".to_owned(),
+            parameter_type: ParameterType::NewFile(ParameterFileType::Vector(VectorGeometryType::Polygon)),
+            default_value: None,
+            optional: false
+        });
+
+        parameters.push(ToolParameter{
+            name: "Smoothing Filter Size".to_owned(),
+            flags: vec!["--smooth".to_owned()],
+            description: "Optional size of the boundary smoothing filter (an odd integer, e.g. 3 or 5); 0 for no smoothing.".to_owned(),
+            parameter_type: ParameterType::Integer,
+            default_value: Some("0".to_owned()),
+            optional: true
+        });
+
+        let sep: String = path::MAIN_SEPARATOR.to_string();
+        let p = format!("{}", env::current_dir().unwrap().display());
+        let e = format!("{}", env::current_exe().unwrap().display());
+        let mut short_exe = e.replace(&p, "").replace(".exe", "").replace(".", "").replace(&sep, "");
+        if e.contains(".exe") {
+            short_exe += ".exe";
+        }
+        let usage = format!(">>.*{0} -r={1} -v --wd=\"*path*to*data*\" -i=classes.tif -o=classes.shp
+>>.*{0} -r={1} -v --wd=\"*path*to*data*\" -i=basins.dep -o=basins.shp --smooth=5", short_exe, name).replace("*", &sep);
+
+        RasterToVectorPolygons {
+            name: name,
+            description: description,
+            toolbox: toolbox,
+            parameters: parameters,
+            example_usage: usage
+        }
+    }
+}
+
+impl WhiteboxTool for RasterToVectorPolygons {
+    fn get_source_file(&self) -> String {
+        String::from(file!())
+    }
+
+    fn get_tool_name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn get_tool_description(&self) -> String {
+        self.description.clone()
+    }
+
+    fn get_tool_parameters(&self) -> String {
+        match serde_json::to_string(&self.parameters) {
+            Ok(json_str) => return format!("{{\"parameters\":{}}}", json_str),
+            Err(err) => return format!("{:?}", err),
+        }
+    }
+
+    fn get_example_usage(&self) -> String {
+        self.example_usage.clone()
+    }
+
+    fn get_toolbox(&self) -> String {
+        self.toolbox.clone()
+    }
+
+    fn run<'a>(&self, args: Vec<String>, working_directory: &'a str, verbose: bool, sink: &Arc<ProgressSink>) -> Result<(), Error> {
+        if args.len() == 0 {
+            return Err(Error::new(ErrorKind::InvalidInput,
+                                "Tool run with no paramters."));
+        }
+        let args = ArgValues::parse(&self.parameters, &args)?;
+        let input_file = args.required_file("--input", working_directory)?;
+        let output_file = args.required_file("--output", working_directory)?;
+        let mut filter_size = args.get_usize("--smooth")?.unwrap_or(0);
+
+        if verbose {
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
+            sink.message(&format!("* Welcome to {} *", self.get_tool_name()));
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
+        }
+
+        let mut progress: usize;
+        let mut old_progress: usize = 1;
+
+
+        if filter_size > 0 && filter_size < 3 {
+            filter_size = 3;
+        }
+        if filter_size > 0 && filter_size % 2 == 0 {
+            filter_size += 1;
+        }
+        let half_filter = (filter_size / 2) as isize;
+
+        if verbose { sink.message("Reading data...") };
+
+        let input = Raster::new(&input_file, "r")?;
+
+        let start = time::now();
+
+        let nodata = input.configs.nodata;
+        let rows = input.configs.rows as isize;
+        let columns = input.configs.columns as isize;
+
+        // Label the regions of contiguous (edge-connected) cells of equal value. NoData
+        // cells, and the area beyond the grid edges, are given the background label 0.
+        let mut labels: Array2D<i32> = Array2D::new(rows, columns, -1i32, 0i32)?;
+        let mut region_values = vec![nodata];
+        let dx = [ 0, 1, 0, -1 ];
+        let dy = [ -1, 0, 1, 0 ];
+        let mut stack = vec![];
+        let mut z: f64;
+        for row in 0..rows {
+            for col in 0..columns {
+                if labels[(row, col)] != -1 {
+                    continue;
+                }
+                z = input.get_value(row, col);
+                if z == nodata {
+                    labels[(row, col)] = 0;
+                    continue;
+                }
+                let label = region_values.len() as i32;
+                region_values.push(z);
+                labels[(row, col)] = label;
+                stack.push((row, col));
+                while let Some((r, c)) = stack.pop() {
+                    for i in 0..4 {
+                        if labels[(r + dy[i], c + dx[i])] == -1 && input.get_value(r + dy[i], c + dx[i]) == z {
+                            labels[(r + dy[i], c + dx[i])] = label;
+                            stack.push((r + dy[i], c + dx[i]));
+                        }
+                    }
+                }
+            }
+            progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as usize;
+            if progress != old_progress {
+                sink.progress("Labelling regions", progress)?;
+                old_progress = progress;
+            }
+        }
+        let num_regions = region_values.len();
+
+        // Find the boundary edges. Edges run between cell corners, i.e. the vertices
+        // (0..rows+1, 0..columns+1), and are directed so that the region that owns them
+        // lies to their right; each vertex stores its outgoing edges as bit flags.
+        let mut edges: Array2D<u8> = Array2D::new(rows + 1, columns + 1, 0u8, 0u8)?;
+        for row in 0..rows + 1 {
+            for col in 0..columns + 1 {
+                let mut flags = 0u8;
+                for dir in 0..4 {
+                    let (owner, other) = edge_labels(&labels, row, col, dir);
+                    if owner > 0 && owner != other {
+                        flags |= 1u8 << dir;
+                    }
+                }
+                edges[(row, col)] = flags;
+            }
+            progress = (100.0_f64 * row as f64 / rows as f64) as usize;
+            if progress != old_progress {
+                sink.progress("Finding boundaries", progress)?;
+                old_progress = progress;
+            }
+        }
+
+        // Trace the rings. Where a region touches itself diagonally, the boundary turns
+        // right, keeping the diagonal cells apart.
+        let mut rings: Vec<Vec<Vec<(isize, isize)>>> = vec![vec![]; num_regions];
+        for row in 0..rows + 1 {
+            for col in 0..columns + 1 {
+                while edges[(row, col)] != 0 {
+                    let start_dir = (0..4).find(|&d| edges[(row, col)] & (1u8 << d) != 0).unwrap();
+                    let (label, _) = edge_labels(&labels, row, col, start_dir);
+                    let mut ring = vec![(row, col)];
+                    let (mut r, mut c, mut dir) = (row, col, start_dir);
+                    loop {
+                        edges[(r, c)] &= !(1u8 << dir);
+                        r += DR[dir];
+                        c += DC[dir];
+                        ring.push((r, c));
+                        let mut next_dir = None;
+                        for &turn in [1usize, 0, 3].iter() { // right, straight, left
+                            let d = (dir + turn) % 4;
+                            if r == row && c == col && d == start_dir {
+                                break;
+                            }
+                            if edges[(r, c)] & (1u8 << d) != 0 && edge_labels(&labels, r, c, d).0 == label {
+                                next_dir = Some(d);
+                                break;
+                            }
+                        }
+                        match next_dir {
+                            Some(d) => dir = d,
+                            None => break,
+                        }
+                    }
+                    rings[label as usize].push(ring);
+                }
+            }
+            progress = (100.0_f64 * row as f64 / rows as f64) as usize;
+            if progress != old_progress {
+                sink.progress("Tracing boundaries", progress)?;
+                old_progress = progress;
+            }
+        }
+
+        // create the output file
+        let mut output = Shapefile::new(&output_file, "w")?;
+        output.header.shape_type = ShapeType::Polygon;
+        if input.configs.coordinate_ref_system_wkt != "not specified" {
+            output.projection = input.configs.coordinate_ref_system_wkt.clone();
+        }
+
+        let is_integral = region_values[1..].iter().all(|&v| v.fract() == 0f64 && v.abs() < i32::max_value() as f64);
+        output.attributes.add_field(AttributeField::new("FID", 'N', 10u8, 0u8));
+        if is_integral {
+            output.attributes.add_field(AttributeField::new("VALUE", 'N', 11u8, 0u8));
+        } else {
+            output.attributes.add_field(AttributeField::new("VALUE", 'F', 19u8, 8u8));
+        }
+
+        let west = input.configs.west;
+        let north = input.configs.north;
+        let res_x = input.configs.resolution_x;
+        let res_y = input.configs.resolution_y;
+        for label in 1..num_regions {
+            let mut sfg = ShapefileGeometry::new(ShapeType::Polygon);
+            for ring in &rings[label] {
+                let vertices = if half_filter > 0 {
+                    smooth_ring(&labels, ring, half_filter)
+                } else {
+                    simplify_ring(&labels, ring).iter().map(|&(r, c)| (r as f64, c as f64)).collect()
+                };
+                let mut points: Vec<Point2D> = vertices.iter().map(|&(r, c)| Point2D { x: west + c * res_x, y: north - r * res_y }).collect();
+                let first = points[0].clone();
+                points.push(first);
+                sfg.add_part(&points);
+            }
+            output.add_record(sfg);
+            let value = if is_integral {
+                FieldData::Int(region_values[label] as i32)
+            } else {
+                FieldData::Real(region_values[label])
+            };
+            output.attributes.add_record(vec![FieldData::Int(label as i32), value], false)?;
+
+            progress = (100.0_f64 * label as f64 / (num_regions - 1) as f64) as usize;
+            if progress != old_progress {
+                sink.progress("Creating polygons", progress)?;
+                old_progress = progress;
+            }
+        }
+
+        let end = time::now();
+        let elapsed_time = end - start;
+
+        if verbose { sink.message("Saving data...") };
+        let _ = match output.write() {
+            Ok(_) => if verbose { sink.message("Output file written") },
+            Err(e) => return Err(e),
+        };
+
+        if verbose {
+            sink.message(&format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
+        }
+
+        Ok(())
+    }
+}
+
+// Edge directions from a vertex: east, south, west, north.
+const DR: [isize; 4] = [ 0, 1, 0, -1 ];
+const DC: [isize; 4] = [ 1, 0, -1, 0 ];
+
+// Returns the labels of the cells to the right (the owner) and left of an edge leaving
+// vertex (row, col) in direction dir.
+fn edge_labels(labels: &Array2D<i32>, row: isize, col: isize, dir: usize) -> (i32, i32) {
+    match dir {
+        0 => (labels[(row, col)], labels[(row - 1, col)]),
+        1 => (labels[(row, col - 1)], labels[(row, col)]),
+        2 => (labels[(row - 1, col - 1)], labels[(row, col - 1)]),
+        _ => (labels[(row - 1, col)], labels[(row - 1, col - 1)]),
+    }
+}
+
+// A vertex is a node if three or more regions meet there, or if two regions meet
+// diagonally. Nodes are never removed or moved, so that neighbouring polygons share them.
+fn is_node(labels: &Array2D<i32>, row: isize, col: isize) -> bool {
+    let nw = labels[(row - 1, col - 1)];
+    let ne = labels[(row - 1, col)];
+    let sw = labels[(row, col - 1)];
+    let se = labels[(row, col)];
+    let mut distinct = vec![nw];
+    for &l in [ne, sw, se].iter() {
+        if !distinct.contains(&l) {
+            distinct.push(l);
+        }
+    }
+    distinct.len() > 2 || (nw == se && ne == sw && nw != ne)
+}
+
+// Returns the vertices of a closed ring (first == last) without the closing vertex and,
+// if the ring contains a node, rotated to start with one, along with flags indicating
+// which vertices are neither nodes nor corners, i.e. lie within straight runs of edges.
+fn ring_vertices(labels: &Array2D<i32>, ring: &Vec<(isize, isize)>) -> (Vec<(isize, isize)>, Vec<bool>) {
+    let n = ring.len() - 1;
+    let mut vertices = Vec::with_capacity(n);
+    let mut straight = Vec::with_capacity(n);
+    let mut first_node = None;
+    for i in 0..n {
+        let prev = ring[(i + n - 1) % n];
+        let (r, c) = ring[i];
+        let next = ring[i + 1];
+        let node = is_node(labels, r, c);
+        if node && first_node.is_none() {
+            first_node = Some(i);
+        }
+        vertices.push((r, c));
+        straight.push(!node && (r - prev.0, c - prev.1) == (next.0 - r, next.1 - c));
+    }
+    if let Some(i) = first_node {
+        let mut rotated = vertices.split_off(i);
+        rotated.extend(vertices);
+        let mut rotated_straight = straight.split_off(i);
+        rotated_straight.extend(straight);
+        return (rotated, rotated_straight);
+    }
+    (vertices, straight)
+}
+
+// Removes the vertices of a closed ring that lie within straight runs of cell edges.
+fn simplify_ring(labels: &Array2D<i32>, ring: &Vec<(isize, isize)>) -> Vec<(isize, isize)> {
+    let (vertices, straight) = ring_vertices(labels, ring);
+    (0..vertices.len()).filter(|&i| !straight[i]).map(|i| vertices[i]).collect()
+}
+
+// Applies a moving-average filter to the vertices of a closed ring. Rings containing
+// nodes are smoothed as a series of node-to-node segments, holding the nodes fixed and
+// narrowing the filter towards them; other rings are smoothed cyclically. In either case
+// the result does not depend on the direction in which the ring was traced, and the sums
+// are calculated on integer grid coordinates, so that the two polygons sharing a boundary
+// segment produce exactly the same smoothed vertices. Vertices that the filter leaves in
+// place in the middle of straight runs are then removed.
+fn smooth_ring(labels: &Array2D<i32>, ring: &Vec<(isize, isize)>, half_filter: isize) -> Vec<(f64, f64)> {
+    let (vertices, straight) = ring_vertices(labels, ring);
+    let n = vertices.len() as isize;
+    let nodes: Vec<isize> = (0..n).filter(|&i| is_node(labels, vertices[i as usize].0, vertices[i as usize].1)).collect();
+    let mut smoothed = Vec::with_capacity(n as usize);
+    let average = |i: isize, k: isize| {
+        let (mut sum_r, mut sum_c) = (0isize, 0isize);
+        for j in i - k..i + k + 1 {
+            let v = vertices[((j % n + n) % n) as usize];
+            sum_r += v.0;
+            sum_c += v.1;
+        }
+        (sum_r as f64 / (2 * k + 1) as f64, sum_c as f64 / (2 * k + 1) as f64)
+    };
+    if nodes.is_empty() {
+        let k = half_filter.min((n - 1) / 2);
+        for i in 0..n {
+            smoothed.push(average(i, k));
+        }
+    } else {
+        for a in 0..nodes.len() {
+            // the segment from this node to the next, which wraps around to vertex n (i.e. 0)
+            let start = nodes[a];
+            let end = if a + 1 < nodes.len() { nodes[a + 1] } else { n };
+            smoothed.push(average(start, 0));
+            for i in start + 1..end {
+                smoothed.push(average(i, half_filter.min(i - start).min(end - i)));
+            }
+        }
+    }
+    let unmoved = |i: isize| (i - half_filter - 1..i + half_filter + 2).all(|j| straight[((j % n + n) % n) as usize]);
+    (0..n).filter(|&i| !unmoved(i)).map(|i| smoothed[i as usize]).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::path::Path;
+
+    // Writes an in-memory raster with unit cells from rows of values, in which -1 is NoData.
+    fn class_raster(name: &str, values: &[&[i32]]) -> String {
+        let mut configs = RasterConfigs { ..Default::default() };
+        configs.rows = values.len();
+        configs.columns = values[0].len();
+        configs.nodata = -1f64;
+        configs.north = 100f64;
+        configs.south = 100f64 - values.len() as f64;
+        configs.west = 50f64;
+        configs.east = 50f64 + values[0].len() as f64;
+        configs.resolution_x = 1f64;
+        configs.resolution_y = 1f64;
+        configs.data_type = DataType::I32;
+        let file_name = format!("{}{}", IN_MEMORY_RASTER_PREFIX, name);
+        let mut r = Raster::initialize_using_config(&file_name, &configs);
+        for row in 0..values.len() {
+            r.set_row_data(row as isize, values[row].iter().map(|&v| v as f64).collect());
+        }
+        r.write().unwrap();
+        file_name
+    }
+
+    // Runs the tool and returns the rings of each output polygon and its VALUE.
+    fn polygonize(input: &str, output: &str, smooth: usize) -> Vec<(i32, Vec<Vec<Point2D>>)> {
+        let file_name = env::temp_dir().join(output).to_str().unwrap().to_string();
+        let args = vec![format!("-i={}", input), format!("-o={}", file_name), format!("--smooth={}", smooth)];
+        let mut sink = StdoutProgress::new();
+        sink.set_quiet(true);
+        let sink: Arc<ProgressSink> = Arc::new(sink);
+        RasterToVectorPolygons::new().run(args, "", false, &sink).unwrap();
+
+        let sf = Shapefile::new(&file_name, "r").unwrap();
+        assert_eq!(sf.header.shape_type, ShapeType::Polygon);
+        let mut polygons = vec![];
+        for i in 0..sf.num_records {
+            let rec = sf.get_record(i);
+            let rings = (0..rec.parts.len()).map(|p| {
+                let end = if p + 1 < rec.parts.len() { rec.parts[p + 1] as usize } else { rec.points.len() };
+                rec.points[rec.parts[p] as usize..end].to_vec()
+            }).collect();
+            let value = match sf.attributes.get_value(i, "VALUE") {
+                FieldData::Int(v) => v,
+                v => panic!("unexpected VALUE {:?}", v),
+            };
+            polygons.push((value, rings));
+        }
+        for ext in ["shp", "shx", "dbf", "cpg"].iter() {
+            let _ = fs::remove_file(Path::new(&file_name).with_extension(ext));
+        }
+        polygons
+    }
+
+    // twice the signed area, negative for clockwise rings
+    fn ring_area(ring: &[Point2D]) -> f64 {
+        (0..ring.len()).map(|i| {
+            let j = (i + 1) % ring.len();
+            ring[i].x * ring[j].y - ring[j].x * ring[i].y
+        }).sum::<f64>() / 2f64
+    }
+
+    #[test]
+    fn polygons_cover_their_cells() {
+        let input = class_raster("rtvp_regions.tif", &[
+            &[1, 1, 1, 1, 1, -1],
+            &[1, 2, 2, 1, 1, -1],
+            &[1, 2, 2, 1, 3, 3],
+            &[1, 1, 1, 1, 3, 4],
+            &[5, 1, 5, -1, 4, 3],
+        ]);
+        let polygons = polygonize(&input, "wbt_rtvp_regions.shp", 0);
+
+        // the two 5 cells and the 3 cells in the bottom right corner do not share an edge
+        let mut values: Vec<i32> = polygons.iter().map(|p| p.0).collect();
+        values.sort();
+        assert_eq!(values, vec![1, 2, 3, 3, 4, 4, 5, 5]);
+
+        let cell_counts = [(1, 15f64), (2, 4f64), (3, 4f64), (4, 2f64), (5, 2f64)];
+        for &(value, count) in cell_counts.iter() {
+            // outer rings are clockwise and holes counter-clockwise, so the net area is negative
+            let area: f64 = polygons.iter().filter(|p| p.0 == value)
+                .flat_map(|p| p.1.iter().map(|r| ring_area(r))).sum();
+            assert_eq!(-area, count, "value {}", value);
+        }
+        let with_hole = polygons.iter().find(|p| p.0 == 1).unwrap();
+        assert_eq!(with_hole.1.len(), 2);
+        assert_eq!(ring_area(&with_hole.1[1]), 4f64);
+        for p in &polygons {
+            for ring in &p.1 {
+                assert_eq!(ring[0].x, ring[ring.len() - 1].x);
+                assert_eq!(ring[0].y, ring[ring.len() - 1].y);
+            }
+        }
+    }
+
+    #[test]
+    fn smoothed_neighbours_share_boundaries() {
+        let input = class_raster("rtvp_smooth.tif", &[
+            &[7, 7, 7, 7, 7, 7, 7, 7],
+            &[7, 7, 8, 8, 8, 7, 7, 7],
+            &[7, 8, 8, 8, 8, 8, 7, 7],
+            &[7, 8, 8, 8, 8, 7, 7, 7],
+            &[7, 7, 8, 8, 7, 7, 7, 7],
+            &[7, 7, 7, 7, 7, 7, 7, 7],
+        ]);
+        let polygons = polygonize(&input, "wbt_rtvp_smooth.shp", 3);
+        assert_eq!(polygons.len(), 2);
+        let outer = polygons.iter().find(|p| p.0 == 7).unwrap();
+        let inner = polygons.iter().find(|p| p.0 == 8).unwrap();
+        assert_eq!(outer.1.len(), 2);
+        // the smoothed hole and the island fill the same area, without gaps or overlaps
+        let hole_area = ring_area(&outer.1[1]);
+        let island_area = -ring_area(&inner.1[0]);
+        assert!(hole_area > 0f64 && (hole_area - island_area).abs() < 1e-9);
+        assert!((island_area - 15f64).abs() > 1e-6);
+    }
+}
@@ -18,6 +18,7 @@ use std::thread;
 use raster::*;
 use std::io::{Error, ErrorKind};
 use tools::*;
+use tools::arg_parser::ArgValues;
 
 pub struct Reclass {
     name: String,
@@ -119,52 +120,21 @@ impl WhiteboxTool for Reclass {
         self.toolbox.clone()
     }
 
-    fn run<'a>(&self, args: Vec<String>, working_directory: &'a str, verbose: bool) -> Result<(), Error> {
-        let mut input_file = String::new();
-        let mut output_file = String::new();
-        let mut reclass_str = String::new();
-        let mut assign_mode = false;
-         
+    fn run<'a>(&self, args: Vec<String>, working_directory: &'a str, verbose: bool, sink: &Arc<ProgressSink>) -> Result<(), Error> {
         if args.len() == 0 {
             return Err(Error::new(ErrorKind::InvalidInput,
                                 "Tool run with no paramters."));
         }
-        for i in 0..args.len() {
-            let mut arg = args[i].replace("\"", "");
-            arg = arg.replace("\'", "");
-            let cmd = arg.split("="); // in case an equals sign was used
-            let vec = cmd.collect::<Vec<&str>>();
-            let mut keyval = false;
-            if vec.len() > 1 {
-                keyval = true;
-            }
-            if vec[0].to_lowercase() == "-i" || vec[0].to_lowercase() == "--input" {
-                if keyval {
-                    input_file = vec[1].to_string();
-                } else {
-                    input_file = args[i+1].to_string();
-                }
-            } else if vec[0].to_lowercase() == "-o" || vec[0].to_lowercase() == "--output" {
-                if keyval {
-                    output_file = vec[1].to_string();
-                } else {
-                    output_file = args[i+1].to_string();
-                }
-            } else if vec[0].to_lowercase() == "-reclass_vals" || vec[0].to_lowercase() == "--reclass_vals" {
-                if keyval {
-                    reclass_str = vec[1].to_string();
-                } else {
-                    reclass_str = args[i+1].to_string();
-                }
-            } else if vec[0].to_lowercase() == "-assign_mode" || vec[0].to_lowercase() == "--assign_mode" {
-                assign_mode = true;
-            }
-        }
+        let args = ArgValues::parse(&self.parameters, &args)?;
+        let input_file = args.required_file("--input", working_directory)?;
+        let output_file = args.required_file("--output", working_directory)?;
+        let reclass_str = args.required_string("--reclass_vals")?;
+        let assign_mode = args.get_bool("--assign_mode")?;
 
         if verbose {
-            println!("***************{}", "*".repeat(self.get_tool_name().len()));
-            println!("* Welcome to {} *", self.get_tool_name());
-            println!("***************{}", "*".repeat(self.get_tool_name().len()));
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
+            sink.message(&format!("* Welcome to {} *", self.get_tool_name()));
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
         }
 
         let mut v: Vec<&str> = reclass_str.split(";").collect();
@@ -191,19 +161,10 @@ impl WhiteboxTool for Reclass {
         };
         let reclass_vals = Arc::new(reclass_vals);
 
-        let sep: String = path::MAIN_SEPARATOR.to_string();
-
         let mut progress: usize;
         let mut old_progress: usize = 1;
 
-        if !input_file.contains(&sep) && !input_file.contains("/") {
-            input_file = format!("{}{}", working_directory, input_file);
-        }
-        if !output_file.contains(&sep) && !output_file.contains("/") {
-            output_file = format!("{}{}", working_directory, output_file);
-        }
-
-        if verbose { println!("Reading data...") };
+        if verbose { sink.message("Reading data...") };
         let input = Arc::new(Raster::new(&input_file, "r")?);
 
         let start = time::now();
@@ -227,7 +188,7 @@ impl WhiteboxTool for Reclass {
                         let mut data: Vec<f64> = vec![nodata; columns as usize];
                         let mut prev_idx: usize = num_ranges;
                         for col in 0..columns {
-                            z = input[(row, col)];
+                            z = input.get_value(row, col);
                             if z != nodata {
                                 if prev_idx < num_ranges {
                                     // This is a shortcut intended to take advantage of the inherent
@@ -260,12 +221,10 @@ impl WhiteboxTool for Reclass {
                 let (row, data) = rx.recv().unwrap();
                 output.set_row_data(row, data);
                 
-                if verbose {
-                    progress = (100.0_f64 * r as f64 / (rows - 1) as f64) as usize;
-                    if progress != old_progress {
-                        println!("Progress: {}%", progress);
-                        old_progress = progress;
-                    }
+                progress = (100.0_f64 * r as f64 / (rows - 1) as f64) as usize;
+                if progress != old_progress {
+                    sink.progress("Progress", progress)?;
+                    old_progress = progress;
                 }
             }
         } else { // assign_mode
@@ -291,7 +250,7 @@ impl WhiteboxTool for Reclass {
                     for row in (0..rows).filter(|r| r % num_procs == tid) {
                         let mut data: Vec<f64> = vec![nodata; columns as usize];
                         for col in 0..columns {
-                            z = input[(row, col)];
+                            z = input.get_value(row, col);
                             if z != nodata {
                                 // is z in the hashmap?
                             if assign_map.contains_key(&((z * multiplier).round() as i64)) {
@@ -309,12 +268,10 @@ impl WhiteboxTool for Reclass {
                 let (row, data) = rx.recv().unwrap();
                 output.set_row_data(row, data);
                 
-                if verbose {
-                    progress = (100.0_f64 * r as f64 / (rows - 1) as f64) as usize;
-                    if progress != old_progress {
-                        println!("Progress: {}%", progress);
-                        old_progress = progress;
-                    }
+                progress = (100.0_f64 * r as f64 / (rows - 1) as f64) as usize;
+                if progress != old_progress {
+                    sink.progress("Progress", progress)?;
+                    old_progress = progress;
                 }
             }
         }
@@ -326,14 +283,14 @@ impl WhiteboxTool for Reclass {
         output.add_metadata_entry(format!("Reclass values: {:?}", reclass_vals));
         output.add_metadata_entry(format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
 
-        if verbose { println!("Saving data...") };
+        if verbose { sink.message("Saving data...") };
         let _ = match output.write() {
-            Ok(_) => if verbose { println!("Output file written") },
+            Ok(_) => if verbose { sink.message("Output file written") },
             Err(e) => return Err(e),
         };
 
         if verbose {
-            println!("{}", &format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
+            sink.message(&format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
         }
         
         Ok(())
@@ -17,6 +17,7 @@ use std::thread;
 use raster::*;
 use std::io::{Error, ErrorKind};
 use tools::*;
+use tools::arg_parser::ArgValues;
 
 pub struct ReclassEqualInterval {
     name: String,
@@ -127,78 +128,28 @@ impl WhiteboxTool for ReclassEqualInterval {
         self.toolbox.clone()
     }
 
-    fn run<'a>(&self, args: Vec<String>, working_directory: &'a str, verbose: bool) -> Result<(), Error> {
-        let mut input_file = String::new();
-        let mut output_file = String::new();
-        let mut interval_size = 10.0;
-        let mut start_val = f64::NEG_INFINITY;
-        let mut end_val = f64::INFINITY;
-         
+    fn run<'a>(&self, args: Vec<String>, working_directory: &'a str, verbose: bool, sink: &Arc<ProgressSink>) -> Result<(), Error> {
         if args.len() == 0 {
             return Err(Error::new(ErrorKind::InvalidInput,
                                 "Tool run with no paramters."));
         }
-        for i in 0..args.len() {
-            let mut arg = args[i].replace("\"", "");
-            arg = arg.replace("\'", "");
-            let cmd = arg.split("="); // in case an equals sign was used
-            let vec = cmd.collect::<Vec<&str>>();
-            let mut keyval = false;
-            if vec.len() > 1 {
-                keyval = true;
-            }
-            if vec[0].to_lowercase() == "-i" || vec[0].to_lowercase() == "--input" {
-                if keyval {
-                    input_file = vec[1].to_string();
-                } else {
-                    input_file = args[i+1].to_string();
-                }
-            } else if vec[0].to_lowercase() == "-o" || vec[0].to_lowercase() == "--output" {
-                if keyval {
-                    output_file = vec[1].to_string();
-                } else {
-                    output_file = args[i+1].to_string();
-                }
-            } else if vec[0].to_lowercase() == "-interval" || vec[0].to_lowercase() == "--interval" {
-                if keyval {
-                    interval_size = vec[1].to_string().parse::<f64>().unwrap();
-                } else {
-                    interval_size = args[i+1].to_string().parse::<f64>().unwrap();
-                }
-            } else if vec[0].to_lowercase() == "-start_val" || vec[0].to_lowercase() == "--start_val" {
-                if keyval {
-                    start_val = vec[1].to_string().parse::<f64>().unwrap();
-                } else {
-                    start_val = args[i+1].to_string().parse::<f64>().unwrap();
-                }
-            } else if vec[0].to_lowercase() == "-end_val" || vec[0].to_lowercase() == "--end_val" {
-                if keyval {
-                    end_val = vec[1].to_string().parse::<f64>().unwrap();
-                } else {
-                    end_val = args[i+1].to_string().parse::<f64>().unwrap();
-                }
-            }
-        }
+        let args = ArgValues::parse(&self.parameters, &args)?;
+        let input_file = args.required_file("--input", working_directory)?;
+        let output_file = args.required_file("--output", working_directory)?;
+        let interval_size = args.get_f64("--interval")?.unwrap_or(10.0);
+        let mut start_val = args.get_f64("--start_val")?.unwrap_or(f64::NEG_INFINITY);
+        let mut end_val = args.get_f64("--end_val")?.unwrap_or(f64::INFINITY);
 
         if verbose {
-            println!("***************{}", "*".repeat(self.get_tool_name().len()));
-            println!("* Welcome to {} *", self.get_tool_name());
-            println!("***************{}", "*".repeat(self.get_tool_name().len()));
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
+            sink.message(&format!("* Welcome to {} *", self.get_tool_name()));
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
         }
 
-        let sep: String = path::MAIN_SEPARATOR.to_string();
-
         let mut progress: usize;
         let mut old_progress: usize = 1;
 
-        if !input_file.contains(&sep) && !input_file.contains("/") {
-            input_file = format!("{}{}", working_directory, input_file);
-        }
-        if !output_file.contains(&sep) && !output_file.contains("/") {
-            output_file = format!("{}{}", working_directory, output_file);
-        }
-
-        if verbose { println!("Reading data...") };
+        if verbose { sink.message("Reading data...") };
         let input = Arc::new(Raster::new(&input_file, "r")?);
 
         let start = time::now();
@@ -222,7 +173,7 @@ impl WhiteboxTool for ReclassEqualInterval {
                 for row in (0..rows).filter(|r| r % num_procs == tid) {
                     let mut data: Vec<f64> = vec![nodata; columns as usize];
                     for col in 0..columns {
-                        z = input[(row, col)];
+                        z = input.get_value(row, col);
                         if z != nodata {
                             if z >= start_val && z <= end_val {
                                 z = (z / interval_size).floor() * interval_size;
@@ -240,12 +191,10 @@ impl WhiteboxTool for ReclassEqualInterval {
             let (row, data) = rx.recv().unwrap();
             output.set_row_data(row, data);
             
-            if verbose {
-                progress = (100.0_f64 * r as f64 / (rows - 1) as f64) as usize;
-                if progress != old_progress {
-                    println!("Progress: {}%", progress);
-                    old_progress = progress;
-                }
+            progress = (100.0_f64 * r as f64 / (rows - 1) as f64) as usize;
+            if progress != old_progress {
+                sink.progress("Progress", progress)?;
+                old_progress = progress;
             }
         }
 
@@ -255,14 +204,14 @@ impl WhiteboxTool for ReclassEqualInterval {
         output.add_metadata_entry(format!("Input file: {}", input_file));
         output.add_metadata_entry(format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
 
-        if verbose { println!("Saving data...") };
+        if verbose { sink.message("Saving data...") };
         let _ = match output.write() {
-            Ok(_) => if verbose { println!("Output file written") },
+            Ok(_) => if verbose { sink.message("Output file written") },
             Err(e) => return Err(e),
         };
 
         if verbose {
-            println!("{}", &format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
+            sink.message(&format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
         }
         
         Ok(())
@@ -0,0 +1,261 @@
+/*
+This tool is part of the WhiteboxTools geospatial analysis library.
+Authors: Dr. John Lindsay
+Created: April 3, 2018
+Last Modified: April 3, 2018
+License: MIT
+
+Help: This tool interpolates a raster surface from a set of sample points using universal
+kriging, which, unlike ordinary kriging, models the mean of the surface as a polynomial
+trend (drift) of the point coordinates. It is suited to surfaces with a systematic regional
+gradient, such as the elevations of a sloping landscape. The drift may be linear
+(--drift_order=1, the default) or quadratic (--drift_order=2), and is fitted locally within
+the neighbourhood of each grid cell as part of the kriging system.
+
+The input (--input) may be either a vector points file, in which case the sampled values
+are read from a numeric attribute field (--field) or, for PointZ files, from the point z
+values (--use_z), or a LiDAR file, in which case the point elevations are used. Withheld
+LiDAR points are always excluded and point classes may be excluded using --exclude_cls.
+Points that share the same location are merged and assigned their average value.
+
+The tool produces two rasters: the kriging estimate (--output) and the kriging variance
+(--variance). The output grid is either defined by a base raster (--base) or covers the
+extent of the points with the specified cell size (--cell_size).
+
+Because the empirical semivariogram of values containing a trend is inflated by the trend,
+the variogram model (--model) is fitted to the residuals of the values from a global,
+least-squares polynomial trend surface of the drift order, using the lag size (--lag_size)
+and number of lags (--num_lags). Any of the fitted nugget, partial sill, and range can be
+replaced with user-specified values (--nugget, --partial_sill, and --range). Each grid cell
+is estimated from the nearest --num_points sample points, optionally limited to those
+within a search radius (--radius). Grid cells with fewer sample points within the search
+radius than there are drift terms (three for a linear and six for a quadratic drift), or
+whose points do not determine the drift, are assigned NoData.
+
+See Also: Variogram, OrdinaryKriging
+*/
+extern crate time;
+
+use std::env;
+use std::io::{Error, ErrorKind};
+use std::path;
+use std::sync::Arc;
+use vector::Point2D;
+use tools::*;
+use tools::arg_parser::ArgValues;
+use super::ordinary_kriging::{drift_terms, kriging_parameters, num_drift_terms, solve_linear_system, KrigingInterpolator, KrigingOptions};
+use super::variogram::read_sample_points;
+
+pub struct UniversalKriging {
+    name: String,
+    description: String,
+    toolbox: String,
+    parameters: Vec<ToolParameter>,
+    example_usage: String,
+}
+
+impl UniversalKriging {
+    pub fn new() -> UniversalKriging { // public constructor
+        let name = "UniversalKriging".to_string();
+        let toolbox = "GIS Analysis".to_string();
+        let description = "Interpolates a raster and its kriging variance from sample points using universal kriging with a polynomial drift.".to_string();
+
+        let mut parameters = kriging_parameters();
+        parameters.push(ToolParameter{
+            name: "Drift Order".to_owned(),
+            flags: vec!["--drift_order".to_owned()],
+            description: "Polynomial order of the drift; 1 (linear, default) or 2 (quadratic).".to_owned(),
+            parameter_type: ParameterType::Integer,
+            default_value: Some("1".to_owned()),
+            optional: true
+        });
+
+        let sep: String = path::MAIN_SEPARATOR.to_string();
+        let p = format!("{}", env::current_dir().unwrap().display());
+        let e = format!("{}", env::current_exe().unwrap().display());
+        let mut short_exe = e.replace(&p, "").replace(".exe", "").replace(".", "").replace(&sep, "");
+        if e.contains(".exe") {
+            short_exe += ".exe";
+        }
+        let usage = format!(">>.*{0} -r={1} -v --wd=\"*path*to*data*\" -i=points.shp --field=ELEV -o=surface.tif --variance=variance.tif --cell_size=10.0 --drift_order=1
+>>.*{0} -r={1} -v --wd=\"*path*to*data*\" -i=file.las --exclude_cls='1,3,4,5,6,7,18' -o=dem.tif --variance=variance.tif --base=base.tif --model=exponential --drift_order=2 --num_points=24", short_exe, name).replace("*", &sep);
+
+        UniversalKriging {
+            name: name,
+            description: description,
+            toolbox: toolbox,
+            parameters: parameters,
+            example_usage: usage
+        }
+    }
+}
+
+impl WhiteboxTool for UniversalKriging {
+    fn get_source_file(&self) -> String {
+        String::from(file!())
+    }
+
+    fn get_tool_name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn get_tool_description(&self) -> String {
+        self.description.clone()
+    }
+
+    fn get_tool_parameters(&self) -> String {
+        match serde_json::to_string(&self.parameters) {
+            Ok(json_str) => return format!("{{\"parameters\":{}}}", json_str),
+            Err(err) => return format!("{:?}", err),
+        }
+    }
+
+    fn get_example_usage(&self) -> String {
+        self.example_usage.clone()
+    }
+
+    fn get_toolbox(&self) -> String {
+        self.toolbox.clone()
+    }
+
+    fn run<'a>(&self, args: Vec<String>, working_directory: &'a str, verbose: bool, sink: &Arc<ProgressSink>) -> Result<(), Error> {
+        if args.len() == 0 {
+            return Err(Error::new(ErrorKind::InvalidInput,
+                                "Tool run with no paramters."));
+        }
+        let args = ArgValues::parse(&self.parameters, &args)?;
+        let options = KrigingOptions::parse(&args, working_directory)?;
+        let drift_order = args.get_usize("--drift_order")?.unwrap_or(1);
+
+        if verbose {
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
+            sink.message(&format!("* Welcome to {} *", self.get_tool_name()));
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
+        }
+
+        if drift_order < 1 || drift_order > 2 {
+            return Err(Error::new(ErrorKind::InvalidInput,
+                "The drift order must be either 1 (linear) or 2 (quadratic)."));
+        }
+        if options.num_points < num_drift_terms(drift_order) {
+            return Err(Error::new(ErrorKind::InvalidInput,
+                format!("At least {} points are required to estimate a drift of order {}.", num_drift_terms(drift_order), drift_order)));
+        }
+
+        if verbose { sink.message("Reading points data...") };
+        let (points, values) = read_sample_points(&options.input_file, &options.field_name, options.use_z, &options.include_class_vals)?;
+
+        let start = time::now();
+
+        if verbose { sink.message("Fitting the variogram model to the trend residuals...") };
+        let residuals = match trend_residuals(&points, &values, drift_order) {
+            Some(r) => r,
+            None => return Err(Error::new(ErrorKind::InvalidInput,
+                "The sample points do not determine a trend surface of the drift order.")),
+        };
+        let variogram = options.variogram(&points, &residuals);
+        if verbose {
+            sink.message(&format!("{} variogram: nugget={:.6}, partial sill={:.6}, range={:.4}",
+                variogram.model.to_string(), variogram.nugget, variogram.partial_sill, variogram.range));
+        }
+
+        let interpolator = Arc::new(KrigingInterpolator::new(points, values, variogram, drift_order, options.num_points, options.radius));
+        let (mut output, mut variance) = options.interpolate(interpolator, sink)?;
+
+        let end = time::now();
+        let elapsed_time = end - start;
+
+        for raster in vec![&mut output, &mut variance] {
+            raster.add_metadata_entry(format!("Created by whitebox_tools\' {} tool", self.get_tool_name()));
+            raster.add_metadata_entry(format!("Input file: {}", options.input_file));
+            raster.add_metadata_entry(format!("Drift order: {}", drift_order));
+            raster.add_metadata_entry(format!("Variogram model: {}", variogram.model.to_string()));
+            raster.add_metadata_entry(format!("Nugget: {}", variogram.nugget));
+            raster.add_metadata_entry(format!("Partial sill: {}", variogram.partial_sill));
+            raster.add_metadata_entry(format!("Range: {}", variogram.range));
+            raster.add_metadata_entry(format!("Number of points: {}", options.num_points));
+            raster.add_metadata_entry(format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
+        }
+
+        if verbose { sink.message("Saving data...") };
+        let _ = match output.write() {
+            Ok(_) => if verbose { sink.message("Output file written") },
+            Err(e) => return Err(e),
+        };
+        let _ = match variance.write() {
+            Ok(_) => if verbose { sink.message("Variance file written") },
+            Err(e) => return Err(e),
+        };
+
+        if verbose {
+            sink.message(&format!("{}", &format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", "")));
+        }
+
+        Ok(())
+    }
+}
+
+/// Returns the residuals of the values from a global polynomial trend surface of the given
+/// order, fitted by least-squares, or None if the points do not determine the surface.
+fn trend_residuals(points: &[Point2D], values: &[f64], order: usize) -> Option<Vec<f64>> {
+    // centre and scale the coordinates to keep the normal equations well conditioned
+    let n = points.len() as f64;
+    let mean_x = points.iter().map(|p| p.x).sum::<f64>() / n;
+    let mean_y = points.iter().map(|p| p.y).sum::<f64>() / n;
+    let mut scale = 0f64;
+    for p in points {
+        scale = scale.max((p.x - mean_x).abs()).max((p.y - mean_y).abs());
+    }
+    if scale == 0f64 {
+        return None;
+    }
+
+    let m = num_drift_terms(order);
+    let mut xtx = vec![vec![0f64; m]; m];
+    let mut xty = vec![0f64; m];
+    let terms: Vec<Vec<f64>> = points.iter().map(|p| drift_terms((p.x - mean_x) / scale, (p.y - mean_y) / scale, order)).collect();
+    for i in 0..points.len() {
+        for r in 0..m {
+            for c in 0..m {
+                xtx[r][c] += terms[i][r] * terms[i][c];
+            }
+            xty[r] += terms[i][r] * values[i];
+        }
+    }
+    let coefficients = match solve_linear_system(xtx, xty) {
+        Some(b) => b,
+        None => return None,
+    };
+    Some((0..points.len()).map(|i| {
+        values[i] - (0..m).map(|k| coefficients[k] * terms[i][k]).sum::<f64>()
+    }).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn trends_are_removed_from_the_residuals() {
+        let mut points = vec![];
+        let mut values = vec![];
+        let mut seed = 17u64;
+        for _ in 0..200 {
+            seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            let x = 500000.0 + (seed >> 11) as f64 / (1u64 << 53) as f64 * 1000.0;
+            seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            let y = 4800000.0 + (seed >> 11) as f64 / (1u64 << 53) as f64 * 1000.0;
+            points.push(Point2D { x: x, y: y });
+            let (u, v) = ((x - 500000.0) / 100.0, (y - 4800000.0) / 100.0);
+            values.push(0.5 * u * u - u * v + 2.0 * v + 30.0);
+        }
+        // a quadratic trend is removed entirely, but a plane leaves some residuals
+        let residuals = trend_residuals(&points, &values, 2).unwrap();
+        assert!(residuals.iter().all(|r| r.abs() < 1e-6));
+        let residuals = trend_residuals(&points, &values, 1).unwrap();
+        assert!(residuals.iter().any(|r| r.abs() > 1.0));
+        assert!(residuals.iter().sum::<f64>().abs() < 1e-6);
+        // coincident points have no trend
+        assert!(trend_residuals(&vec![Point2D { x: 1.0, y: 1.0 }; 3], &[1.0, 2.0, 3.0], 1).is_none());
+    }
+}
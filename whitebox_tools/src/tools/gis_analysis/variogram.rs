@@ -0,0 +1,750 @@
+/*
+This tool is part of the WhiteboxTools geospatial analysis library.
+Authors: Dr. John Lindsay
+Created: April 3, 2018
+Last Modified: April 3, 2018
+License: MIT
+
+Help: This tool calculates the empirical (experimental) semivariogram of a set of sample
+points and fits the spherical, exponential, and Gaussian variogram models to it. The input
+(--input) may be either a vector points file, in which case the sampled values are read
+from a numeric attribute field (--field) or, for PointZ files, from the point z values
+(--use_z), or a LiDAR file, in which case the point elevations are used. Withheld LiDAR
+points are always excluded and point classes may be excluded using --exclude_cls, e.g.
+--exclude_cls='1,3,4,5,6,7,18' to retain only the ground returns. Points that share the
+same location are merged and assigned their average value.
+
+The empirical semivariance of each lag is half of the mean squared difference between the
+values of all pairs of points whose separation distance falls within the lag. The lags have
+a width of --lag_size and there are --num_lags of them. By default, the lags span half of
+the diagonal of the bounding box of the points. Large data sets, such as LiDAR point clouds,
+are systematically sub-sampled to no more than 5000 points for the calculation of the
+empirical semivariogram.
+
+Each of the models is defined by its nugget, partial sill (the sill less the nugget), and
+range. The exponential and Gaussian models approach their sill asymptotically, and their
+ranges are reported as the practical range, i.e. the distance at which they reach 95% of
+the sill. The models are fitted by weighted least-squares, in which the weight of each lag
+is the number of pairs in the lag divided by the square of its mean distance, giving greater
+influence to the well-populated lags at short distances that matter most for interpolation.
+The output (--output) is an HTML report containing the empirical semivariogram, the
+fitted model parameters, and a graph of the semivariogram and models. The model with the
+smallest weighted sum of squared errors is identified as the best fit.
+
+See Also: OrdinaryKriging, UniversalKriging
+*/
+extern crate time;
+
+use std::env;
+use std::f64;
+use std::fs::File;
+use std::io::BufWriter;
+use std::io::prelude::*;
+use std::io::{Error, ErrorKind};
+use std::path;
+use std::process::Command;
+use std::sync::Arc;
+use lidar::*;
+use rendering::LineGraph;
+use rendering::html::*;
+use vector::*;
+use tools::*;
+use tools::arg_parser::ArgValues;
+
+/// The maximum number of points used to calculate an empirical semivariogram.
+const MAX_VARIOGRAM_POINTS: usize = 5000;
+
+pub struct Variogram {
+    name: String,
+    description: String,
+    toolbox: String,
+    parameters: Vec<ToolParameter>,
+    example_usage: String,
+}
+
+impl Variogram {
+    pub fn new() -> Variogram { // public constructor
+        let name = "Variogram".to_string();
+        let toolbox = "GIS Analysis".to_string();
+        let description = "Calculates the empirical semivariogram of sample points and fits spherical, exponential, and Gaussian models.".to_string();
+
+        let mut parameters = vec![];
+        parameters.push(ToolParameter{
+            name: "Input File".to_owned(),
+            flags: vec!["-i".to_owned(), "--input".to_owned()],
+            description: "Input vector points file or LiDAR file.".to_owned(),
+            parameter_type: ParameterType::ExistingFile(ParameterFileType::Any),
+            default_value: None,
+            optional: false
+        });
+
+        parameters.push(ToolParameter{
+            name: "Field Name".to_owned(),
+            flags: vec!["--field".to_owned()],
+            description: "Input field name in attribute table; not used with LiDAR input.".to_owned(),
+            parameter_type: ParameterType::VectorAttributeField(AttributeType::Number, "--input".to_string()),
+            default_value: None,
+            optional: true
+        });
+
+        parameters.push(ToolParameter{
+            name: "Use z-coordinate instead of field?".to_owned(),
+            flags: vec!["--use_z".to_owned()],
+            description: "Use the z-coordinates of PointZ input rather than an attribute field.".to_owned(),
+            parameter_type: ParameterType::Boolean,
+            default_value: Some("false".to_owned()),
+            optional: true
+        });
+
+        parameters.push(ToolParameter{
+            name: "Exclusion Classes (0-18, based on LAS spec; e.g. 3,4,5,6,7)".to_owned(),
+            flags: vec!["--exclude_cls".to_owned()],
+            description: "Optional exclude classes from LiDAR input; Valid class values range from 0 to 18, based on LAS specifications. Example, --exclude_cls='3,4,5,6,7,18'.".to_owned(),
+            parameter_type: ParameterType::String,
+            default_value: None,
+            optional: true
+        });
+
+        parameters.push(ToolParameter{
+            name: "Output HTML File".to_owned(),
+            flags: vec!["-o".to_owned(), "--output".to_owned()],
+            description: "Output HTML file.".to_owned(),
+            parameter_type: ParameterType::NewFile(ParameterFileType::Html),
+            default_value: None,
+            optional: false
+        });
+
+        parameters.push(ToolParameter{
+            name: "Lag Size (optional)".to_owned(),
+            flags: vec!["--lag_size".to_owned()],
+            description: "Optional lag size; by default the lags span half of the diagonal of the points' bounding box.".to_owned(),
+            parameter_type: ParameterType::Float,
+            default_value: None,
+            optional: true
+        });
+
+        parameters.push(ToolParameter{
+            name: "Number of Lags".to_owned(),
+            flags: vec!["--num_lags".to_owned()],
+            description: "Number of lags.".to_owned(),
+            parameter_type: ParameterType::Integer,
+            default_value: Some("15".to_owned()),
+            optional: true
+        });
+
+        let sep: String = path::MAIN_SEPARATOR.to_string();
+        let p = format!("{}", env::current_dir().unwrap().display());
+        let e = format!("{}", env::current_exe().unwrap().display());
+        let mut short_exe = e.replace(&p, "").replace(".exe", "").replace(".", "").replace(&sep, "");
+        if e.contains(".exe") {
+            short_exe += ".exe";
+        }
+        let usage = format!(">>.*{0} -r={1} -v --wd=\"*path*to*data*\" -i=points.shp --field=ELEV -o=variogram.html --num_lags=20
+>>.*{0} -r={1} -v --wd=\"*path*to*data*\" -i=file.las --exclude_cls='1,3,4,5,6,7,18' -o=variogram.html --lag_size=5.0", short_exe, name).replace("*", &sep);
+
+        Variogram {
+            name: name,
+            description: description,
+            toolbox: toolbox,
+            parameters: parameters,
+            example_usage: usage
+        }
+    }
+}
+
+impl WhiteboxTool for Variogram {
+    fn get_source_file(&self) -> String {
+        String::from(file!())
+    }
+
+    fn get_tool_name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn get_tool_description(&self) -> String {
+        self.description.clone()
+    }
+
+    fn get_tool_parameters(&self) -> String {
+        match serde_json::to_string(&self.parameters) {
+            Ok(json_str) => return format!("{{\"parameters\":{}}}", json_str),
+            Err(err) => return format!("{:?}", err),
+        }
+    }
+
+    fn get_example_usage(&self) -> String {
+        self.example_usage.clone()
+    }
+
+    fn get_toolbox(&self) -> String {
+        self.toolbox.clone()
+    }
+
+    fn run<'a>(&self, args: Vec<String>, working_directory: &'a str, verbose: bool, sink: &Arc<ProgressSink>) -> Result<(), Error> {
+        if args.len() == 0 {
+            return Err(Error::new(ErrorKind::InvalidInput,
+                                "Tool run with no paramters."));
+        }
+        let args = ArgValues::parse(&self.parameters, &args)?;
+        let input_file = args.required_file("--input", working_directory)?;
+        let field_name = args.get_string("--field")?.unwrap_or(String::new());
+        let use_z = args.get_bool("--use_z")?;
+        let include_class_vals = parse_class_list(&args.get_string("--exclude_cls")?.unwrap_or(String::new()))?;
+        let output_file = args.required_file("--output", working_directory)?;
+        let mut lag_size = args.get_f64("--lag_size")?.unwrap_or(0f64);
+        let num_lags = args.get_usize("--num_lags")?.unwrap_or(15);
+
+        if verbose {
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
+            sink.message(&format!("* Welcome to {} *", self.get_tool_name()));
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
+        }
+
+        if num_lags < 2 {
+            return Err(Error::new(ErrorKind::InvalidInput,
+                                "The number of lags must be at least two."));
+        }
+
+        if verbose { sink.message("Reading points data...") };
+        let (points, values) = read_sample_points(&input_file, &field_name, use_z, &include_class_vals)?;
+
+        let start = time::now();
+
+        if lag_size <= 0f64 {
+            lag_size = default_lag_size(&points, num_lags);
+        }
+
+        if verbose { sink.message("Calculating the empirical semivariogram...") };
+        let empirical = EmpiricalVariogram::new(&points, &values, lag_size, num_lags);
+        if empirical.distances.len() < 2 {
+            return Err(Error::new(ErrorKind::InvalidInput,
+                "Fewer than two lags contain pairs of points; try increasing the lag size."));
+        }
+
+        let models = vec![VariogramModel::Spherical, VariogramModel::Exponential, VariogramModel::Gaussian];
+        let mut fits = vec![];
+        for model in &models {
+            fits.push(empirical.fit(*model));
+        }
+        let mut best = 0;
+        for m in 1..fits.len() {
+            if fits[m].1 < fits[best].1 {
+                best = m;
+            }
+        }
+
+        let end = time::now();
+        let elapsed_time = end - start;
+
+        let f = File::create(output_file.clone())?;
+        let mut writer = BufWriter::new(f);
+
+        writer.write_all(&r#"<!DOCTYPE html PUBLIC \"-//W3C//DTD XHTML 1.0 Transitional//EN\" \"http://www.w3.org/TR/xhtml1/DTD/xhtml1-transitional.dtd\">
+        <head>
+            <meta content=\"text/html; charset=iso-8859-1\" http-equiv=\"content-type\">
+            <title>Variogram</title>"#.as_bytes())?;
+
+        // get the style sheet
+        writer.write_all(&get_css().as_bytes())?;
+
+        writer.write_all(&r#"</head>
+        <body>
+            <h1>Variogram</h1>"#.as_bytes())?;
+
+        let short_name = path::Path::new(&input_file).file_name().unwrap().to_str().unwrap().to_string();
+        writer.write_all((format!("<p><strong>Input</strong>: {}<br>", short_name)).as_bytes())?;
+        if !field_name.is_empty() && !use_z && !is_lidar_file(&input_file) {
+            writer.write_all((format!("<strong>Field</strong>: {}<br>", field_name)).as_bytes())?;
+        }
+        writer.write_all((format!("<strong>Number of points</strong>: {}<br>", points.len())).as_bytes())?;
+        if empirical.num_points < points.len() {
+            writer.write_all((format!("<strong>Points used in semivariogram</strong>: {}<br>", empirical.num_points)).as_bytes())?;
+        }
+        writer.write_all((format!("<strong>Lag size</strong>: {:.4}<br>", lag_size)).as_bytes())?;
+        writer.write_all((format!("<strong>Number of lags</strong>: {}", num_lags)).as_bytes())?;
+        writer.write_all(("</p>").as_bytes())?;
+
+        let mut xdata = vec![empirical.distances.clone()];
+        let mut ydata = vec![empirical.semivariances.clone()];
+        let mut series_names = vec!["Empirical".to_string()];
+        let max_dist = lag_size * num_lags as f64;
+        let num_steps = 100;
+        for m in 0..fits.len() {
+            let mut x = vec![];
+            let mut y = vec![];
+            for k in 0..num_steps + 1 {
+                let h = max_dist * k as f64 / num_steps as f64;
+                x.push(h);
+                y.push(fits[m].0.semivariance(h));
+            }
+            xdata.push(x);
+            ydata.push(y);
+            series_names.push(fits[m].0.model.to_string());
+        }
+
+        let graph = LineGraph {
+            parent_id: "graph".to_string(),
+            width: 700f64,
+            height: 500f64,
+            data_x: xdata,
+            data_y: ydata,
+            series_labels: series_names,
+            x_axis_label: "Distance".to_string(),
+            y_axis_label: "Semivariance".to_string(),
+            draw_points: false,
+            draw_gridlines: true,
+            draw_legend: true,
+            draw_grey_background: false,
+        };
+
+        writer.write_all(&format!("<div id='graph' align=\"center\">{}</div>", graph.get_svg()).as_bytes())?;
+
+        writer.write_all("<br><table align=\"center\">".as_bytes())?;
+        writer.write_all("<caption>Fitted Variogram Models</caption>".as_bytes())?;
+        writer.write_all("<tr><th class=\"header\">Model</th><th class=\"header\">Nugget</th><th class=\"header\">Partial Sill</th><th class=\"header\">Range</th><th class=\"header\">WSSE</th></tr>".as_bytes())?;
+        for m in 0..fits.len() {
+            let v = &fits[m].0;
+            writer.write_all(&format!("<tr><td>{}{}</td><td class=\"numberCell\">{:.6}</td><td class=\"numberCell\">{:.6}</td><td class=\"numberCell\">{:.4}</td><td class=\"numberCell\">{:.6e}</td></tr>",
+                v.model.to_string(), if m == best { " (best fit)" } else { "" }, v.nugget, v.partial_sill, v.range, fits[m].1).as_bytes())?;
+        }
+        writer.write_all("</table>".as_bytes())?;
+        writer.write_all("<p>The range of the exponential and Gaussian models is the practical range, at which 95% of the sill is reached. WSSE is the weighted sum of squared errors of the fit.</p>".as_bytes())?;
+
+        writer.write_all("<br><table align=\"center\">".as_bytes())?;
+        writer.write_all("<caption>Empirical Semivariogram</caption>".as_bytes())?;
+        writer.write_all("<tr><th class=\"header\">Lag</th><th class=\"header\">Mean Distance</th><th class=\"header\">Number of Pairs</th><th class=\"header\">Semivariance</th></tr>".as_bytes())?;
+        for j in 0..empirical.distances.len() {
+            writer.write_all(&format!("<tr><td class=\"numberCell\">{}</td><td class=\"numberCell\">{:.4}</td><td class=\"numberCell\">{}</td><td class=\"numberCell\">{:.6}</td></tr>",
+                empirical.lags[j] + 1, empirical.distances[j], empirical.num_pairs[j], empirical.semivariances[j]).as_bytes())?;
+        }
+        writer.write_all("</table>".as_bytes())?;
+
+        writer.write_all("</body>".as_bytes())?;
+
+        let _ = writer.flush();
+
+        if verbose {
+            sink.message(&format!("Best-fitting model: {} (nugget={:.6}, partial sill={:.6}, range={:.4})",
+                fits[best].0.model.to_string(), fits[best].0.nugget, fits[best].0.partial_sill, fits[best].0.range));
+            sink.message(&format!("\n{}", &format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", "")));
+        }
+
+        if verbose {
+            if cfg!(target_os = "macos") || cfg!(target_os = "ios") {
+                let output = Command::new("open")
+                    .arg(output_file.clone())
+                    .output()
+                    .expect("failed to execute process");
+
+                let _ = output.stdout;
+            } else if cfg!(target_os = "windows") {
+                let output = Command::new("explorer.exe")
+                    .arg(output_file.clone())
+                    .output()
+                    .expect("failed to execute process");
+
+                let _ = output.stdout;
+            } else if cfg!(target_os = "linux") {
+                let output = Command::new("xdg-open")
+                    .arg(output_file.clone())
+                    .output()
+                    .expect("failed to execute process");
+
+                let _ = output.stdout;
+            }
+
+            sink.message(&format!("Complete! Please see {} for output.", output_file));
+        }
+
+        Ok(())
+    }
+}
+
+/// Parses a comma- or semicolon-separated list of LAS classes to exclude, returning
+/// the inclusion flag of each of the 256 class values.
+pub fn parse_class_list(exclude_cls_str: &str) -> Result<Vec<bool>, Error> {
+    let mut include_class_vals = vec![true; 256];
+    for value in exclude_cls_str.split(|c| c == ',' || c == ';') {
+        if !value.trim().is_empty() {
+            match value.trim().parse::<u8>() {
+                Ok(c) => include_class_vals[c as usize] = false,
+                Err(_) => return Err(Error::new(ErrorKind::InvalidInput,
+                    format!("Unrecognized class value in --exclude_cls: {}", value.trim()))),
+            }
+        }
+    }
+    Ok(include_class_vals)
+}
+
+fn is_lidar_file(file_name: &str) -> bool {
+    let lower = file_name.to_lowercase();
+    lower.ends_with(".las") || lower.ends_with(".laz") || lower.ends_with(".zip")
+}
+
+/// Reads the locations and values of sample points from either a vector points file or a
+/// LiDAR file. For vector input the values are read from the named numeric attribute field,
+/// or from the z-coordinates of PointZ files when `use_z` is true; null values are skipped.
+/// For LiDAR input the point elevations of the non-withheld points of the included classes
+/// are used. Points that share a location are merged and given their average value.
+pub fn read_sample_points(input_file: &str, field_name: &str, use_z: bool, include_class_vals: &[bool]) -> Result<(Vec<Point2D>, Vec<f64>), Error> {
+    let mut samples: Vec<(f64, f64, f64)> = vec![];
+    if is_lidar_file(input_file) {
+        let mut input = LasReader::new(input_file)?;
+        while let Some(chunk) = input.next() {
+            for point in chunk? {
+                let p = point.point_data();
+                if !p.withheld() && include_class_vals[p.classification() as usize] {
+                    samples.push((p.x, p.y, p.z));
+                }
+            }
+        }
+    } else {
+        let input = Shapefile::new(input_file, "r")?;
+        if input.header.shape_type.base_shape_type() != ShapeType::Point {
+            return Err(Error::new(ErrorKind::InvalidInput,
+                "The input vector data must be of point base shape type."));
+        }
+        if use_z {
+            if input.header.shape_type.dimension() != ShapeTypeDimension::Z {
+                return Err(Error::new(ErrorKind::InvalidInput,
+                    "The input vector data must be of PointZ shape type when --use_z is specified."));
+            }
+        } else if field_name.is_empty() {
+            return Err(Error::new(ErrorKind::InvalidInput,
+                "A numeric attribute field (--field) must be specified unless --use_z is used."));
+        } else if input.attributes.get_field_num(field_name).is_none() {
+            return Err(Error::new(ErrorKind::InvalidInput,
+                format!("The field ({}) does not exist in the input file's attribute table.", field_name)));
+        }
+        for record_num in 0..input.num_records {
+            let record = input.get_record(record_num);
+            if record.points.is_empty() {
+                continue;
+            }
+            let value = if use_z {
+                if record.z_array.is_empty() { continue; }
+                record.z_array[0]
+            } else {
+                match input.attributes.get_value(record_num, field_name) {
+                    FieldData::Null => continue,
+                    v => match v.as_f64() {
+                        Some(z) => z,
+                        None => return Err(Error::new(ErrorKind::InvalidInput,
+                            format!("The field ({}) must be numeric.", field_name))),
+                    },
+                }
+            };
+            samples.push((record.points[0].x, record.points[0].y, value));
+        }
+    }
+
+    samples.retain(|s| s.0.is_finite() && s.1.is_finite() && s.2.is_finite());
+    samples.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap().then(a.1.partial_cmp(&b.1).unwrap()));
+    let mut points = vec![];
+    let mut values = vec![];
+    let mut i = 0;
+    while i < samples.len() {
+        let mut j = i + 1;
+        let mut sum = samples[i].2;
+        while j < samples.len() && samples[j].0 == samples[i].0 && samples[j].1 == samples[i].1 {
+            sum += samples[j].2;
+            j += 1;
+        }
+        points.push(Point2D { x: samples[i].0, y: samples[i].1 });
+        values.push(sum / (j - i) as f64);
+        i = j;
+    }
+    if points.len() < 3 {
+        return Err(Error::new(ErrorKind::InvalidInput,
+            "The input must contain at least three distinct sample points."));
+    }
+    Ok((points, values))
+}
+
+/// Returns the lag size at which `num_lags` lags span half of the diagonal of the
+/// bounding box of the points.
+pub fn default_lag_size(points: &[Point2D], num_lags: usize) -> f64 {
+    let (mut min_x, mut max_x) = (f64::INFINITY, f64::NEG_INFINITY);
+    let (mut min_y, mut max_y) = (f64::INFINITY, f64::NEG_INFINITY);
+    for p in points {
+        min_x = min_x.min(p.x);
+        max_x = max_x.max(p.x);
+        min_y = min_y.min(p.y);
+        max_y = max_y.max(p.y);
+    }
+    let diagonal = ((max_x - min_x) * (max_x - min_x) + (max_y - min_y) * (max_y - min_y)).sqrt();
+    diagonal / 2f64 / num_lags as f64
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum VariogramModel {
+    Spherical,
+    Exponential,
+    Gaussian,
+}
+
+impl VariogramModel {
+    pub fn from_str(s: &str) -> Option<VariogramModel> {
+        let s = s.to_lowercase();
+        if s.contains("sph") {
+            Some(VariogramModel::Spherical)
+        } else if s.contains("exp") {
+            Some(VariogramModel::Exponential)
+        } else if s.contains("gau") {
+            Some(VariogramModel::Gaussian)
+        } else {
+            None
+        }
+    }
+
+    pub fn to_string(&self) -> String {
+        match *self {
+            VariogramModel::Spherical => "Spherical".to_string(),
+            VariogramModel::Exponential => "Exponential".to_string(),
+            VariogramModel::Gaussian => "Gaussian".to_string(),
+        }
+    }
+
+    /// The model's semivariance, scaled to a unit sill and without a nugget, at the
+    /// separation distance `h` for the given (practical) range.
+    fn unit_semivariance(&self, h: f64, range: f64) -> f64 {
+        let r = h / range;
+        match *self {
+            VariogramModel::Spherical => if r >= 1f64 { 1f64 } else { 1.5 * r - 0.5 * r * r * r },
+            VariogramModel::Exponential => 1f64 - (-3f64 * r).exp(),
+            VariogramModel::Gaussian => 1f64 - (-3f64 * r * r).exp(),
+        }
+    }
+}
+
+/// A variogram model with fitted parameters.
+#[derive(Clone, Copy, Debug)]
+pub struct VariogramParameters {
+    pub model: VariogramModel,
+    pub nugget: f64,
+    pub partial_sill: f64,
+    pub range: f64,
+}
+
+impl VariogramParameters {
+    /// Returns the modelled semivariance at the separation distance `h`, which is zero at
+    /// zero distance and includes the nugget at all other distances.
+    pub fn semivariance(&self, h: f64) -> f64 {
+        if h <= 0f64 {
+            return 0f64;
+        }
+        self.nugget + self.partial_sill * self.model.unit_semivariance(h, self.range)
+    }
+}
+
+/// The empirical semivariogram of a set of sample points. Only lags that contain at least
+/// one pair of points are included.
+pub struct EmpiricalVariogram {
+    pub lags: Vec<usize>,
+    pub distances: Vec<f64>,
+    pub semivariances: Vec<f64>,
+    pub num_pairs: Vec<usize>,
+    pub num_points: usize,
+}
+
+impl EmpiricalVariogram {
+    pub fn new(points: &[Point2D], values: &[f64], lag_size: f64, num_lags: usize) -> EmpiricalVariogram {
+        // systematically sub-sample large data sets
+        let stride = (points.len() + MAX_VARIOGRAM_POINTS - 1) / MAX_VARIOGRAM_POINTS;
+        let sample: Vec<usize> = (0..points.len()).filter(|i| i % stride == 0).collect();
+        let max_dist = lag_size * num_lags as f64;
+        let mut sum_dist = vec![0f64; num_lags];
+        let mut sum_sqr_diff = vec![0f64; num_lags];
+        let mut count = vec![0usize; num_lags];
+        for a in 0..sample.len() {
+            let (i, pi) = (sample[a], &points[sample[a]]);
+            for b in (a + 1)..sample.len() {
+                let j = sample[b];
+                let dx = pi.x - points[j].x;
+                let dy = pi.y - points[j].y;
+                let h = (dx * dx + dy * dy).sqrt();
+                if h < max_dist {
+                    let lag = ((h / lag_size) as usize).min(num_lags - 1);
+                    sum_dist[lag] += h;
+                    sum_sqr_diff[lag] += (values[i] - values[j]) * (values[i] - values[j]);
+                    count[lag] += 1;
+                }
+            }
+        }
+        let mut empirical = EmpiricalVariogram {
+            lags: vec![],
+            distances: vec![],
+            semivariances: vec![],
+            num_pairs: vec![],
+            num_points: sample.len(),
+        };
+        for lag in 0..num_lags {
+            if count[lag] > 0 {
+                empirical.lags.push(lag);
+                empirical.distances.push(sum_dist[lag] / count[lag] as f64);
+                empirical.semivariances.push(sum_sqr_diff[lag] / (2 * count[lag]) as f64);
+                empirical.num_pairs.push(count[lag]);
+            }
+        }
+        empirical
+    }
+
+    /// Fits a variogram model by weighted least-squares, weighting each lag by its number
+    /// of pairs divided by its squared mean distance. The nugget and partial sill are solved
+    /// for, subject to being non-negative, at each of a series of trial ranges. Returns the
+    /// fitted parameters and the weighted sum of squared errors.
+    pub fn fit(&self, model: VariogramModel) -> (VariogramParameters, f64) {
+        let weights: Vec<f64> = (0..self.distances.len()).map(|j| {
+            if self.distances[j] > 0f64 {
+                self.num_pairs[j] as f64 / (self.distances[j] * self.distances[j])
+            } else {
+                0f64
+            }
+        }).collect();
+        let max_dist = self.distances.iter().cloned().fold(0f64, f64::max);
+        let mut best = VariogramParameters { model: model, nugget: 0f64, partial_sill: 0f64, range: max_dist };
+        let mut best_wsse = f64::INFINITY;
+        let num_trials = 500;
+        for k in 1..num_trials + 1 {
+            let range = 2f64 * max_dist * k as f64 / num_trials as f64;
+            let (mut sw, mut sf, mut sff, mut sy, mut sfy) = (0f64, 0f64, 0f64, 0f64, 0f64);
+            for j in 0..self.distances.len() {
+                let w = weights[j];
+                let f = model.unit_semivariance(self.distances[j], range);
+                let y = self.semivariances[j];
+                sw += w;
+                sf += w * f;
+                sff += w * f * f;
+                sy += w * y;
+                sfy += w * f * y;
+            }
+            let det = sw * sff - sf * sf;
+            let (mut nugget, mut partial_sill) = if det.abs() > 1e-12 * sw * sff {
+                ((sff * sy - sf * sfy) / det, (sw * sfy - sf * sy) / det)
+            } else {
+                (-1f64, 0f64)
+            };
+            if nugget < 0f64 {
+                nugget = 0f64;
+                partial_sill = if sff > 0f64 { sfy / sff } else { 0f64 };
+            }
+            if partial_sill < 0f64 {
+                partial_sill = 0f64;
+                nugget = if sw > 0f64 { sy / sw } else { 0f64 };
+            }
+            let mut wsse = 0f64;
+            for j in 0..self.distances.len() {
+                let err = self.semivariances[j] - nugget - partial_sill * model.unit_semivariance(self.distances[j], range);
+                wsse += weights[j] * err * err;
+            }
+            if wsse < best_wsse {
+                best_wsse = wsse;
+                best = VariogramParameters { model: model, nugget: nugget, partial_sill: partial_sill, range: range };
+            }
+        }
+        (best, best_wsse)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn models_reach_the_sill_at_the_practical_range() {
+        for &model in [VariogramModel::Spherical, VariogramModel::Exponential, VariogramModel::Gaussian].iter() {
+            let params = VariogramParameters { model: model, nugget: 2.0, partial_sill: 10.0, range: 50.0 };
+            assert_eq!(params.semivariance(0.0), 0.0);
+            assert!(params.semivariance(1e-9) >= 2.0);
+            // semivariances increase with distance
+            let mut prev = 0f64;
+            for i in 1..100 {
+                let g = params.semivariance(i as f64);
+                assert!(g >= prev && g <= 12.0);
+                prev = g;
+            }
+            let at_range = (params.semivariance(50.0) - 2.0) / 10.0;
+            match model {
+                VariogramModel::Spherical => assert_eq!(at_range, 1.0),
+                _ => assert!((at_range - 0.95).abs() < 0.001),
+            }
+            assert_eq!(VariogramModel::from_str(&model.to_string()), Some(model));
+        }
+        assert_eq!(VariogramModel::from_str("exp"), Some(VariogramModel::Exponential));
+        assert_eq!(VariogramModel::from_str("linear"), None);
+    }
+
+    #[test]
+    fn the_empirical_variogram_of_a_linear_trend() {
+        // for z = x the semivariance at a separation of h is h^2 / 2
+        let points: Vec<Point2D> = (0..50).map(|i| Point2D { x: i as f64, y: 0.0 }).collect();
+        let values: Vec<f64> = points.iter().map(|p| p.x).collect();
+        let empirical = EmpiricalVariogram::new(&points, &values, 1.0, 10);
+        assert_eq!(empirical.num_points, 50);
+        assert_eq!(empirical.lags, vec![1, 2, 3, 4, 5, 6, 7, 8, 9]);
+        for j in 0..empirical.lags.len() {
+            let h = empirical.lags[j] as f64;
+            assert_eq!(empirical.distances[j], h);
+            assert_eq!(empirical.num_pairs[j], 50 - empirical.lags[j]);
+            assert!((empirical.semivariances[j] - h * h / 2.0).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn fitting_recovers_the_model_parameters() {
+        for &model in [VariogramModel::Spherical, VariogramModel::Exponential, VariogramModel::Gaussian].iter() {
+            let params = VariogramParameters { model: model, nugget: 1.5, partial_sill: 8.0, range: 6.0 };
+            let distances: Vec<f64> = (1..11).map(|i| i as f64).collect();
+            let empirical = EmpiricalVariogram {
+                lags: (1..11).collect(),
+                semivariances: distances.iter().map(|&h| params.semivariance(h)).collect(),
+                distances: distances,
+                num_pairs: vec![100; 10],
+                num_points: 100,
+            };
+            let (fitted, wsse) = empirical.fit(model);
+            assert_eq!(fitted.model, model);
+            assert!((fitted.nugget - 1.5).abs() < 1e-6, "{:?}", fitted);
+            assert!((fitted.partial_sill - 8.0).abs() < 1e-6, "{:?}", fitted);
+            assert!((fitted.range - 6.0).abs() < 1e-6, "{:?}", fitted);
+            assert!(wsse < 1e-9);
+        }
+    }
+
+    #[test]
+    fn sample_points_are_merged_and_validated() {
+        let file_name = env::temp_dir().join("wbt_variogram_samples.shp").to_str().unwrap().to_string();
+        let mut sf = Shapefile::new(&file_name, "w").unwrap();
+        sf.header.shape_type = ShapeType::Point;
+        sf.attributes.add_field(AttributeField::new("FID", 'N', 6u8, 0u8));
+        sf.attributes.add_field(AttributeField::new("VALUE", 'F', 12u8, 4u8));
+        sf.attributes.add_field(AttributeField::new("NAME", 'C', 10u8, 0u8));
+        let records = [(0.0, 0.0, Some(1.0)), (1.0, 0.0, Some(2.0)), (1.0, 0.0, Some(4.0)), (0.0, 1.0, None), (1.0, 1.0, Some(5.0))];
+        for (i, &(x, y, v)) in records.iter().enumerate() {
+            let mut sfg = ShapefileGeometry::new(ShapeType::Point);
+            sfg.add_point(Point2D { x: x, y: y });
+            sf.add_record(sfg);
+            let value = match v { Some(v) => FieldData::Real(v), None => FieldData::Null };
+            sf.attributes.add_record(vec![FieldData::Int(i as i32), value, FieldData::Text("a".to_string())], false).unwrap();
+        }
+        sf.write().unwrap();
+
+        let include = parse_class_list("").unwrap();
+        let (points, values) = read_sample_points(&file_name, "VALUE", false, &include).unwrap();
+        let merged: Vec<(f64, f64, f64)> = points.iter().zip(values.iter()).map(|(p, &v)| (p.x, p.y, v)).collect();
+        assert_eq!(merged, vec![(0.0, 0.0, 1.0), (1.0, 0.0, 3.0), (1.0, 1.0, 5.0)]);
+
+        assert_eq!(read_sample_points(&file_name, "MISSING", false, &include).err().unwrap().kind(), ErrorKind::InvalidInput);
+        assert_eq!(read_sample_points(&file_name, "NAME", false, &include).err().unwrap().kind(), ErrorKind::InvalidInput);
+        assert_eq!(read_sample_points(&file_name, "", false, &include).err().unwrap().kind(), ErrorKind::InvalidInput);
+        assert_eq!(read_sample_points(&file_name, "", true, &include).err().unwrap().kind(), ErrorKind::InvalidInput);
+
+        let include = parse_class_list("2, 7;9").unwrap();
+        assert!(!include[2] && !include[7] && !include[9] && include[1]);
+        assert_eq!(parse_class_list("ground").err().unwrap().kind(), ErrorKind::InvalidInput);
+        for ext in ["shp", "shx", "dbf", "cpg", "prj"].iter() {
+            let _ = fs::remove_file(path::Path::new(&file_name).with_extension(ext));
+        }
+    }
+}
@@ -17,8 +17,10 @@ use std::sync::mpsc;
 use std::thread;
 use raster::*;
 use std::io::{Error, ErrorKind};
+use std::cmp::min;
 use structures::Array2D;
 use tools::*;
+use tools::arg_parser::ArgValues;
 
 pub struct AverageFlowpathSlope {
     name: String,
@@ -37,7 +39,7 @@ impl AverageFlowpathSlope {
         let mut parameters = vec![];
         parameters.push(ToolParameter{
             name: "Input File".to_owned(), 
-            flags: vec!["-i".to_owned(), "--dem".to_owned()], 
+            flags: vec!["-i".to_owned(), "--input".to_owned(), "--dem".to_owned()], 
             description: "Input raster DEM file.".to_owned(),
             parameter_type: ParameterType::ExistingFile(ParameterFileType::Raster),
             default_value: None,
@@ -100,57 +102,25 @@ impl WhiteboxTool for AverageFlowpathSlope {
         self.toolbox.clone()
     }
 
-    fn run<'a>(&self, args: Vec<String>, working_directory: &'a str, verbose: bool) -> Result<(), Error> {
-        let mut input_file = String::new();
-        let mut output_file = String::new();
-        
+    fn run<'a>(&self, args: Vec<String>, working_directory: &'a str, verbose: bool, sink: &Arc<ProgressSink>) -> Result<(), Error> {
         if args.len() == 0 {
             return Err(Error::new(ErrorKind::InvalidInput,
                                 "Tool run with no paramters."));
         }
-        for i in 0..args.len() {
-            let mut arg = args[i].replace("\"", "");
-            arg = arg.replace("\'", "");
-            let cmd = arg.split("="); // in case an equals sign was used
-            let vec = cmd.collect::<Vec<&str>>();
-            let mut keyval = false;
-            if vec.len() > 1 {
-                keyval = true;
-            }
-            if vec[0].to_lowercase() == "-i" || vec[0].to_lowercase() == "--input" || vec[0].to_lowercase() == "--dem" {
-                if keyval {
-                    input_file = vec[1].to_string();
-                } else {
-                    input_file = args[i+1].to_string();
-                }
-            } else if vec[0].to_lowercase() == "-o" || vec[0].to_lowercase() == "--output" {
-                if keyval {
-                    output_file = vec[1].to_string();
-                } else {
-                    output_file = args[i+1].to_string();
-                }
-            }
-        }
+        let args = ArgValues::parse(&self.parameters, &args)?;
+        let input_file = args.required_file("--dem", working_directory)?;
+        let output_file = args.required_file("--output", working_directory)?;
 
         if verbose {
-            println!("***************{}", "*".repeat(self.get_tool_name().len()));
-            println!("* Welcome to {} *", self.get_tool_name());
-            println!("***************{}", "*".repeat(self.get_tool_name().len()));
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
+            sink.message(&format!("* Welcome to {} *", self.get_tool_name()));
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
         }
 
-        let sep: String = path::MAIN_SEPARATOR.to_string();
-
         let mut progress: usize;
         let mut old_progress: usize = 1;
 
-        if !input_file.contains(&sep) && !input_file.contains("/") {
-            input_file = format!("{}{}", working_directory, input_file);
-        }
-        if !output_file.contains(&sep) && !output_file.contains("/") {
-            output_file = format!("{}{}", working_directory, output_file);
-        }
-
-        if verbose { println!("Reading data...") };
+        if verbose { sink.message("Reading data...") };
 
         let input = Arc::new(Raster::new(&input_file, "r")?);
 
@@ -167,69 +137,74 @@ impl WhiteboxTool for AverageFlowpathSlope {
         let mut flow_dir: Array2D<i8> = Array2D::new(rows, columns, -1, -1)?;
         
         let num_procs = num_cpus::get() as isize;
-        let (tx, rx) = mpsc::channel();
-        for tid in 0..num_procs {
-            let input = input.clone();
-            let tx = tx.clone();
-            thread::spawn(move || {
-                let nodata = input.configs.nodata;
-                let d_x = [ 1, 1, 1, 0, -1, -1, -1, 0 ];
-                let d_y = [ -1, 0, 1, 1, 1, 0, -1, -1 ];
-                let grid_lengths = [diag_cell_size, cell_size_x, diag_cell_size, cell_size_y, diag_cell_size, cell_size_x, diag_cell_size, cell_size_y];
-                let (mut z, mut z_n): (f64, f64);
-                let (mut max_slope, mut slope): (f64, f64);
-                let mut dir: i8;
-                let mut neighbouring_nodata: bool;
-                let mut interior_pit_found = false;
-                for row in (0..rows).filter(|r| r % num_procs == tid) {
-                    let mut data: Vec<i8> = vec![-1i8; columns as usize];
-                    for col in 0..columns {
-                        z = input[(row, col)];
-                        if z != nodata {
-                            dir = 0i8;
-							max_slope = f64::MIN;
-                            neighbouring_nodata = false;
-							for i in 0..8 {
-                                z_n = input[(row + d_y[i], col + d_x[i])];
-                                if z_n != nodata {
-                                    slope = (z - z_n) / grid_lengths[i];
-                                    if slope > max_slope && slope > 0f64 {
-                                        max_slope = slope;
-                                        dir = i as i8;
+        let mut interior_pit_found = false;
+        // the DEM is read in windows of rows, with a halo of one row on either side
+        let window_rows = input.get_window_rows();
+        let mut start_row = 0;
+        while start_row < rows {
+            let end_row = min(start_row + window_rows, rows);
+            let window = Arc::new(input.get_window(start_row - 1, end_row + 1));
+            let (tx, rx) = mpsc::channel();
+            for tid in 0..num_procs {
+                let window = window.clone();
+                let tx = tx.clone();
+                thread::spawn(move || {
+                    let d_x = [ 1, 1, 1, 0, -1, -1, -1, 0 ];
+                    let d_y = [ -1, 0, 1, 1, 1, 0, -1, -1 ];
+                    let grid_lengths = [diag_cell_size, cell_size_x, diag_cell_size, cell_size_y, diag_cell_size, cell_size_x, diag_cell_size, cell_size_y];
+                    let (mut z, mut z_n): (f64, f64);
+                    let (mut max_slope, mut slope): (f64, f64);
+                    let mut dir: i8;
+                    let mut neighbouring_nodata: bool;
+                    let mut interior_pit_found = false;
+                    for row in (start_row..end_row).filter(|r| r % num_procs == tid) {
+                        let mut data: Vec<i8> = vec![-1i8; columns as usize];
+                        for col in 0..columns {
+                            z = window.get_value(row, col);
+                            if z != nodata {
+                                dir = 0i8;
+    							max_slope = f64::MIN;
+                                neighbouring_nodata = false;
+    							for i in 0..8 {
+                                    z_n = window.get_value(row + d_y[i], col + d_x[i]);
+                                    if z_n != nodata {
+                                        slope = (z - z_n) / grid_lengths[i];
+                                        if slope > max_slope && slope > 0f64 {
+                                            max_slope = slope;
+                                            dir = i as i8;
+                                        }
+                                    } else {
+                                        neighbouring_nodata = true;
                                     }
+                                }
+                                if max_slope >= 0f64 {
+                                    data[col as usize] = dir;
                                 } else {
-                                    neighbouring_nodata = true;
+                                    data[col as usize] = -1i8;
+                                    if !neighbouring_nodata {
+                                        interior_pit_found = true;
+                                    }
                                 }
-                            }
-                            if max_slope >= 0f64 {
-                                data[col as usize] = dir;
                             } else {
                                 data[col as usize] = -1i8;
-                                if !neighbouring_nodata {
-                                    interior_pit_found = true;
-                                }
                             }
-                        } else {
-                            data[col as usize] = -1i8;
                         }
+                        tx.send((row, data, interior_pit_found)).unwrap();
                     }
-                    tx.send((row, data, interior_pit_found)).unwrap();
-                }
-            });
-        }
+                });
+            }
 
-        let mut interior_pit_found = false;
-        for r in 0..rows {
-            let (row, data, pit) = rx.recv().unwrap();
-            flow_dir.set_row_data(row, data); //(data.0, data.1);
-            if pit { interior_pit_found = true; }
-            if verbose {
+            for r in start_row..end_row {
+                let (row, data, pit) = rx.recv().unwrap();
+                flow_dir.set_row_data(row, data); //(data.0, data.1);
+                if pit { interior_pit_found = true; }
                 progress = (100.0_f64 * r as f64 / (rows - 1) as f64) as usize;
                 if progress != old_progress {
-                    println!("Flow directions: {}%", progress);
+                    sink.progress("Flow directions", progress)?;
                     old_progress = progress;
                 }
             }
+            start_row = end_row;
         }
 
         // calculate the number of inflowing cells
@@ -250,7 +225,7 @@ impl WhiteboxTool for AverageFlowpathSlope {
                 for row in (0..rows).filter(|r| r % num_procs == tid) {
                     let mut data: Vec<i8> = vec![-1i8; columns as usize];
                     for col in 0..columns {
-                        z = input[(row, col)];
+                        z = input.get_value(row, col);
                         if z != nodata {
                             count = 0i8;
 							for i in 0..8 {
@@ -282,18 +257,16 @@ impl WhiteboxTool for AverageFlowpathSlope {
                     stack.push((row, col));
                     total_flowpath_length[(row, col)] = 0.0;
                     num_flowpaths[(row, col)] = 1;
-                    total_upslope_divide_elev[(row, col)] = input[(row, col)];
+                    total_upslope_divide_elev[(row, col)] = input.get_value(row, col);
                 } else if num_inflowing[(row, col)] == -1i8 {
                     num_solved_cells += 1;
                 }
             }
             
-            if verbose {
-                progress = (100.0_f64 * r as f64 / (rows - 1) as f64) as usize;
-                if progress != old_progress {
-                    println!("Num. inflowing neighbours: {}%", progress);
-                    old_progress = progress;
-                }
+            progress = (100.0_f64 * r as f64 / (rows - 1) as f64) as usize;
+            if progress != old_progress {
+                sink.progress("Num. inflowing neighbours", progress)?;
+                old_progress = progress;
             }
         }
 
@@ -335,16 +308,14 @@ impl WhiteboxTool for AverageFlowpathSlope {
             }
 
             z_mean = total_upslope_divide_elev[(row, col)] / num_flowpaths[(row, col)] as f64;
-            z_diff = z_mean - input[(row, col)];
-            output[(row, col)] = (z_diff / (total_flowpath_length[(row, col)] / num_flowpaths[(row, col)] as f64)).atan().to_degrees();
+            z_diff = z_mean - input.get_value(row, col);
+            output.set_value(row, col, (z_diff / (total_flowpath_length[(row, col)] / num_flowpaths[(row, col)] as f64)).atan().to_degrees());
 
-            if verbose {
-                num_solved_cells += 1;
-                progress = (100.0_f64 * num_solved_cells as f64 / (num_cells - 1) as f64) as usize;
-                if progress != old_progress {
-                    println!("Flowpath tracing: {}%", progress);
-                    old_progress = progress;
-                }
+            num_solved_cells += 1;
+            progress = (100.0_f64 * num_solved_cells as f64 / (num_cells - 1) as f64) as usize;
+            if progress != old_progress {
+                sink.progress("Flowpath tracing", progress)?;
+                old_progress = progress;
             }
         }
 
@@ -356,20 +327,20 @@ impl WhiteboxTool for AverageFlowpathSlope {
         output.add_metadata_entry(format!("Input file: {}", input_file));
         output.add_metadata_entry(format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
 
-        if verbose { println!("Saving data...") };
+        if verbose { sink.message("Saving data...") };
         let _ = match output.write() {
-            Ok(_) => if verbose { println!("Output file written") },
+            Ok(_) => if verbose { sink.message("Output file written") },
             Err(e) => return Err(e),
         };
         if verbose {
-            println!("{}", &format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
+            sink.message(&format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
         }
         if interior_pit_found {
-            println!("**********************************************************************************");
-            println!("WARNING: Interior pit cells were found within the input DEM. It is likely that the 
+            sink.message("**********************************************************************************");
+            sink.warning("WARNING: Interior pit cells were found within the input DEM. It is likely that the 
             DEM needs to be processed to remove topographic depressions and flats prior to
             running this tool.");
-            println!("**********************************************************************************");
+            sink.message("**********************************************************************************");
         }
 
         Ok(())
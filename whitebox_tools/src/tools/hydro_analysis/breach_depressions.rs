@@ -20,8 +20,10 @@ use std::i32;
 use std::f64;
 use raster::*;
 use std::io::{Error, ErrorKind};
+use std::sync::Arc;
 use structures::Array2D;
 use tools::*;
+use tools::arg_parser::ArgValues;
 
 pub struct BreachDepressions {
     name: String,
@@ -40,7 +42,7 @@ impl BreachDepressions {
         let mut parameters = vec![];
         parameters.push(ToolParameter{
             name: "Input DEM File".to_owned(), 
-            flags: vec!["-i".to_owned(), "--dem".to_owned()], 
+            flags: vec!["-i".to_owned(), "--input".to_owned(), "--dem".to_owned()], 
             description: "Input raster DEM file.".to_owned(),
             parameter_type: ParameterType::ExistingFile(ParameterFileType::Raster),
             default_value: None,
@@ -121,77 +123,33 @@ impl WhiteboxTool for BreachDepressions {
         self.toolbox.clone()
     }
 
-    fn run<'a>(&self, args: Vec<String>, working_directory: &'a str, verbose: bool) -> Result<(), Error> {
-        let mut input_file = String::new();
-        let mut output_file = String::new();
-        let mut max_depth = f64::INFINITY;
-        let mut max_length = f64::INFINITY;
-        let mut constrained_mode = false;
-        
+    fn run<'a>(&self, args: Vec<String>, working_directory: &'a str, verbose: bool, sink: &Arc<ProgressSink>) -> Result<(), Error> {
         if args.len() == 0 {
             return Err(Error::new(ErrorKind::InvalidInput,
                                 "Tool run with no paramters."));
         }
-        for i in 0..args.len() {
-            let mut arg = args[i].replace("\"", "");
-            arg = arg.replace("\'", "");
-            let cmd = arg.split("="); // in case an equals sign was used
-            let vec = cmd.collect::<Vec<&str>>();
-            let mut keyval = false;
-            if vec.len() > 1 {
-                keyval = true;
-            }
-            if vec[0].to_lowercase() == "-i" || vec[0].to_lowercase() == "--input" || vec[0].to_lowercase() == "--dem" {
-                if keyval {
-                    input_file = vec[1].to_string();
-                } else {
-                    input_file = args[i+1].to_string();
-                }
-            } else if vec[0].to_lowercase() == "-o" || vec[0].to_lowercase() == "--output" {
-                if keyval {
-                    output_file = vec[1].to_string();
-                } else {
-                    output_file = args[i+1].to_string();
-                }
-            } else if vec[0].to_lowercase() == "-max_depth" || vec[0].to_lowercase() == "--max_depth" {
-                if keyval {
-                    max_depth = vec[1].to_string().parse::<f64>().unwrap();
-                } else {
-                    max_depth = args[i+1].to_string().parse::<f64>().unwrap();
-                }
-                constrained_mode = true;
-            } else if vec[0].to_lowercase() == "-max_length" || vec[0].to_lowercase() == "--max_length" {
-                if keyval {
-                    max_length = vec[1].to_string().parse::<f64>().unwrap();
-                } else {
-                    max_length = args[i+1].to_string().parse::<f64>().unwrap();
-                }
-                constrained_mode = true;
-            }
-        }
+        let args = ArgValues::parse(&self.parameters, &args)?;
+        let input_file = args.required_file("--dem", working_directory)?;
+        let output_file = args.required_file("--output", working_directory)?;
+        let max_depth = args.get_f64("--max_depth")?;
+        let max_length = args.get_f64("--max_length")?;
+        let constrained_mode = max_depth.is_some() || max_length.is_some();
+        let max_depth = max_depth.unwrap_or(f64::INFINITY);
+        let max_length = max_length.unwrap_or(f64::INFINITY);
 
         if verbose {
-            println!("***************{}", "*".repeat(self.get_tool_name().len()));
-            println!("* Welcome to {} *", self.get_tool_name());
-            println!("***************{}", "*".repeat(self.get_tool_name().len()));
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
+            sink.message(&format!("* Welcome to {} *", self.get_tool_name()));
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
         }
 
-        let sep: String = path::MAIN_SEPARATOR.to_string();
-
         let mut progress: usize;
         let mut old_progress: usize = 1;
 
-        if !input_file.contains(&sep) && !input_file.contains("/") {
-            input_file = format!("{}{}", working_directory, input_file);
-        }
-        if !output_file.contains(&sep) && !output_file.contains("/") {
-            output_file = format!("{}{}", working_directory, output_file);
-        }
-
-        if verbose { println!("Reading data...") };
+        if verbose { sink.message("Reading data...") };
 
         if verbose && constrained_mode {
-            println!("Breaching in constrained mode...");
+            sink.message("Breaching in constrained mode...");
         }
 
         let input = Raster::new(&input_file, "r")?;
@@ -260,14 +218,14 @@ impl WhiteboxTool for BreachDepressions {
             for n in 0..8 {
                 row_n = row + dy[n];
                 col_n = col + dx[n];
-                zin_n = input[(row_n, col_n)];
-                zout_n = output[(row_n, col_n)];
+                zin_n = input.get_value(row_n, col_n);
+                zout_n = output.get_value(row_n, col_n);
                 if zout_n == background_val {
                     if zin_n == nodata {
-                        output[(row_n, col_n)] = nodata;
+                        output.set_value(row_n, col_n, nodata);
                         queue.push_back((row_n, col_n));
                     } else {
-                        output[(row_n, col_n)] = zin_n;
+                        output.set_value(row_n, col_n, zin_n);
                         // Push it onto the priority queue for the priority flood operation
                         minheap.push(GridCell{ row: row_n, column: col_n, priority: zin_n });
                     }
@@ -275,12 +233,10 @@ impl WhiteboxTool for BreachDepressions {
                 }
             }
 
-            if verbose {
-                progress = (100.0_f64 * num_solved_cells as f64 / (num_cells - 1) as f64) as usize;
-                if progress != old_progress {
-                    println!("progress: {}%", progress);
-                    old_progress = progress;
-                }
+            progress = (100.0_f64 * num_solved_cells as f64 / (num_cells - 1) as f64) as usize;
+            if progress != old_progress {
+                sink.progress("progress", progress)?;
+                old_progress = progress;
             }
         }
 
@@ -299,16 +255,16 @@ impl WhiteboxTool for BreachDepressions {
                 let cell = minheap.pop().unwrap();
                 row = cell.row;
                 col = cell.column;
-                zout = output[(row, col)];
+                zout = output.get_value(row, col);
                 for n in 0..8 {
                     row_n = row + dy[n];
                     col_n = col + dx[n];
-                    zout_n = output[(row_n, col_n)];
+                    zout_n = output.get_value(row_n, col_n);
                     if zout_n == background_val {
-                        zin_n = input[(row_n, col_n)];
+                        zin_n = input.get_value(row_n, col_n);
                         if zin_n != nodata {
                             flow_dir[(row_n, col_n)] = back_link[n];
-                            output[(row_n, col_n)] = zin_n;
+                            output.set_value(row_n, col_n, zin_n);
                             minheap.push(GridCell{ row: row_n, column: col_n, priority: zin_n });
                             if zin_n < (zout + small_num) {
                                 // Is it a pit cell?
@@ -326,7 +282,7 @@ impl WhiteboxTool for BreachDepressions {
                                     // Trace the flowpath back to a lower cell, if it exists.
                                     x = col_n;
                                     y = row_n;
-                                    z_target = output[(row_n, col_n)];
+                                    z_target = output.get_value(row_n, col_n);
                                     flag = true;
                                     while flag {
                                         dir = flow_dir[(y, x)];
@@ -334,8 +290,8 @@ impl WhiteboxTool for BreachDepressions {
                                             y += dy[dir as usize];
                                             x += dx[dir as usize];
                                             z_target -= small_num;
-                                            if output[(y, x)] > z_target {
-                                                output[(y, x)] = z_target;
+                                            if output.get_value(y, x) > z_target {
+                                                output.set_value(y, x, z_target);
                                             } else {
                                                 flag = false;
                                             }
@@ -347,19 +303,17 @@ impl WhiteboxTool for BreachDepressions {
                             }
                         } else {
                             // Interior nodata cells are still treated as nodata and are not filled.
-                            output[(row_n, col_n)] = nodata;
+                            output.set_value(row_n, col_n, nodata);
                             num_solved_cells += 1;
                         }
                     }
                 }
 
-                if verbose {
-                    num_solved_cells += 1;
-                    progress = (100.0_f64 * num_solved_cells as f64 / (num_cells - 1) as f64) as usize;
-                    if progress != old_progress {
-                        println!("Progress: {}%", progress);
-                        old_progress = progress;
-                    }
+                num_solved_cells += 1;
+                progress = (100.0_f64 * num_solved_cells as f64 / (num_cells - 1) as f64) as usize;
+                if progress != old_progress {
+                    sink.progress("Progress", progress)?;
+                    old_progress = progress;
                 }
             }
         } else { // constrained mode
@@ -371,22 +325,22 @@ impl WhiteboxTool for BreachDepressions {
                 let cell = minheap.pop().unwrap();
                 row = cell.row;
                 col = cell.column;
-                zout = output[(row, col)];
+                zout = output.get_value(row, col);
                 for n in 0..8 {
                     row_n = row + dy[n];
                     col_n = col + dx[n];
-                    zout_n = output[(row_n, col_n)];
+                    zout_n = output.get_value(row_n, col_n);
                     if zout_n == background_val {
-                        zin_n = input[(row_n, col_n)];
+                        zin_n = input.get_value(row_n, col_n);
                         if zin_n != nodata {
                             flow_dir[(row_n, col_n)] = back_link[n];
-                            output[(row_n, col_n)] = zin_n;
+                            output.set_value(row_n, col_n, zin_n);
                             minheap.push(GridCell{ row: row_n, column: col_n, priority: zin_n });
                             if zin_n < (zout + small_num) {
                                 // Trace the flowpath back to a lower cell, if it exists.
                                 x = col_n;
                                 y = row_n;
-                                z_target = output[(row_n, col_n)];
+                                z_target = output.get_value(row_n, col_n);
                                 channel_depth = 0.0;
                                 channel_length = 0.0;
                                 flag = true;
@@ -397,8 +351,8 @@ impl WhiteboxTool for BreachDepressions {
                                         x += dx[dir as usize];
                                         z_target -= small_num;
                                         channel_length += 1.0;
-                                        if output[(y, x)] > z_target {
-                                            carved_depth = input[(y, x)] - z_target;
+                                        if output.get_value(y, x) > z_target {
+                                            carved_depth = input.get_value(y, x) - z_target;
                                             if carved_depth > channel_depth { channel_depth = carved_depth; }
                                         } else {
                                             flag = false;
@@ -411,7 +365,7 @@ impl WhiteboxTool for BreachDepressions {
                                     // It's okay to breach it.
                                     x = col_n;
                                     y = row_n;
-                                    z_target = output[(row_n, col_n)];
+                                    z_target = output.get_value(row_n, col_n);
                                     flag = true;
                                     while flag {
                                         dir = flow_dir[(y, x)];
@@ -419,8 +373,8 @@ impl WhiteboxTool for BreachDepressions {
                                             y += dy[dir as usize];
                                             x += dx[dir as usize];
                                             z_target -= small_num;
-                                            if output[(y, x)] > z_target {
-                                                output[(y, x)] = z_target;
+                                            if output.get_value(y, x) > z_target {
+                                                output.set_value(y, x, z_target);
                                             } else {
                                                 flag = false;
                                             }
@@ -434,24 +388,22 @@ impl WhiteboxTool for BreachDepressions {
                             }
                         } else {
                             // Interior nodata cells are still treated as nodata and are not filled.
-                            output[(row_n, col_n)] = nodata;
+                            output.set_value(row_n, col_n, nodata);
                             num_solved_cells += 1;
                         }
                     }
                 }
 
-                if verbose {
-                    num_solved_cells += 1;
-                    progress = (100.0_f64 * num_solved_cells as f64 / (num_cells - 1) as f64) as usize;
-                    if progress != old_progress {
-                        println!("Progress: {}%", progress);
-                        old_progress = progress;
-                    }
+                num_solved_cells += 1;
+                progress = (100.0_f64 * num_solved_cells as f64 / (num_cells - 1) as f64) as usize;
+                if progress != old_progress {
+                    sink.progress("Progress", progress)?;
+                    old_progress = progress;
                 }
             }
 
             if unresolved_pits && verbose {
-                println!("There were unbreached depressions. The result should be filled to remove additional depressions.");
+                sink.message("There were unbreached depressions. The result should be filled to remove additional depressions.");
             }
         }
         
@@ -463,13 +415,13 @@ impl WhiteboxTool for BreachDepressions {
         output.add_metadata_entry(format!("Input file: {}", input_file));
         output.add_metadata_entry(format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
 
-        if verbose { println!("Saving data...") };
+        if verbose { sink.message("Saving data...") };
         let _ = match output.write() {
-            Ok(_) => if verbose { println!("Output file written") },
+            Ok(_) => if verbose { sink.message("Output file written") },
             Err(e) => return Err(e),
         };
         if verbose {
-            println!("{}", &format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
+            sink.message(&format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
         }
 
         Ok(())
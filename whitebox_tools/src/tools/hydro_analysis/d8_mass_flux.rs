@@ -19,8 +19,10 @@ use std::sync::mpsc;
 use std::thread;
 use raster::*;
 use std::io::{Error, ErrorKind};
+use std::cmp::min;
 use structures::Array2D;
 use tools::*;
+use tools::arg_parser::ArgValues;
 
 pub struct D8MassFlux {
     name: String,
@@ -39,7 +41,7 @@ impl D8MassFlux {
         let mut parameters = vec![];
         parameters.push(ToolParameter{
             name: "Input DEM File".to_owned(), 
-            flags: vec!["--dem".to_owned()], 
+            flags: vec!["-i".to_owned(), "--dem".to_owned()], 
             description: "Input raster DEM file.".to_owned(),
             parameter_type: ParameterType::ExistingFile(ParameterFileType::Raster),
             default_value: None,
@@ -129,88 +131,28 @@ impl WhiteboxTool for D8MassFlux {
         self.toolbox.clone()
     }
 
-    fn run<'a>(&self, args: Vec<String>, working_directory: &'a str, verbose: bool) -> Result<(), Error> {
-        let mut input_file = String::new();
-        let mut loading_file = String::new();
-        let mut efficiency_file = String::new();
-        let mut absorption_file = String::new();
-        let mut output_file = String::new();
-        
+    fn run<'a>(&self, args: Vec<String>, working_directory: &'a str, verbose: bool, sink: &Arc<ProgressSink>) -> Result<(), Error> {
         if args.len() == 0 {
             return Err(Error::new(ErrorKind::InvalidInput,
                                 "Tool run with no paramters."));
         }
-        for i in 0..args.len() {
-            let mut arg = args[i].replace("\"", "");
-            arg = arg.replace("\'", "");
-            let cmd = arg.split("="); // in case an equals sign was used
-            let vec = cmd.collect::<Vec<&str>>();
-            let mut keyval = false;
-            if vec.len() > 1 {
-                keyval = true;
-            }
-            let flag_val = vec[0].to_lowercase().replace("--", "-");
-            if flag_val == "-i" || flag_val == "-dem" {
-                if keyval {
-                    input_file = vec[1].to_string();
-                } else {
-                    input_file = args[i+1].to_string();
-                }
-            } else if flag_val == "-loading" {
-                if keyval {
-                    loading_file = vec[1].to_string();
-                } else {
-                    loading_file = args[i+1].to_string();
-                }
-            } else if flag_val == "-efficiency" {
-                if keyval {
-                    efficiency_file = vec[1].to_string();
-                } else {
-                    efficiency_file = args[i+1].to_string();
-                }
-            } else if flag_val == "-absorption" {
-                if keyval {
-                    absorption_file = vec[1].to_string();
-                } else {
-                    absorption_file = args[i+1].to_string();
-                }
-            } else if flag_val == "-o" || flag_val == "-output" {
-                if keyval {
-                    output_file = vec[1].to_string();
-                } else {
-                    output_file = args[i+1].to_string();
-                }
-            }
-        }
+        let args = ArgValues::parse(&self.parameters, &args)?;
+        let input_file = args.required_file("--dem", working_directory)?;
+        let loading_file = args.required_file("--loading", working_directory)?;
+        let efficiency_file = args.required_file("--efficiency", working_directory)?;
+        let absorption_file = args.required_file("--absorption", working_directory)?;
+        let output_file = args.required_file("--output", working_directory)?;
 
         if verbose {
-            println!("***************{}", "*".repeat(self.get_tool_name().len()));
-            println!("* Welcome to {} *", self.get_tool_name());
-            println!("***************{}", "*".repeat(self.get_tool_name().len()));
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
+            sink.message(&format!("* Welcome to {} *", self.get_tool_name()));
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
         }
 
-        let sep: String = path::MAIN_SEPARATOR.to_string();
-
         let mut progress: usize;
         let mut old_progress: usize = 1;
 
-        if !input_file.contains(&sep) && !input_file.contains("/") {
-            input_file = format!("{}{}", working_directory, input_file);
-        }
-        if !loading_file.contains(&sep) && !loading_file.contains("/") {
-            loading_file = format!("{}{}", working_directory, loading_file);
-        }
-        if !efficiency_file.contains(&sep) && !efficiency_file.contains("/") {
-            efficiency_file = format!("{}{}", working_directory, efficiency_file);
-        }
-        if !absorption_file.contains(&sep) && !absorption_file.contains("/") {
-            absorption_file = format!("{}{}", working_directory, absorption_file);
-        }
-        if !output_file.contains(&sep) && !output_file.contains("/") {
-            output_file = format!("{}{}", working_directory, output_file);
-        }
-
-        if verbose { println!("Reading data...") };
+        if verbose { sink.message("Reading data...") };
 
         let input = Arc::new(Raster::new(&input_file, "r")?);
 
@@ -245,69 +187,74 @@ impl WhiteboxTool for D8MassFlux {
         
         let mut flow_dir: Array2D<i8> = Array2D::new(rows, columns, -1, -1)?;
         let num_procs = num_cpus::get() as isize;
-        let (tx, rx) = mpsc::channel();
-        for tid in 0..num_procs {
-            let input = input.clone();
-            let tx = tx.clone();
-            thread::spawn(move || {
-                let nodata = input.configs.nodata;
-                let dx = [ 1, 1, 1, 0, -1, -1, -1, 0 ];
-                let dy = [ -1, 0, 1, 1, 1, 0, -1, -1 ];
-                let grid_lengths = [diag_cell_size, cell_size_x, diag_cell_size, cell_size_y, diag_cell_size, cell_size_x, diag_cell_size, cell_size_y];
-                let (mut z, mut z_n): (f64, f64);
-                let (mut max_slope, mut slope): (f64, f64);
-                let mut dir: i8;
-                let mut neighbouring_nodata: bool;
-                let mut interior_pit_found = false;
-                for row in (0..rows).filter(|r| r % num_procs == tid) {
-                    let mut data: Vec<i8> = vec![-1i8; columns as usize];
-                    for col in 0..columns {
-                        z = input[(row, col)];
-                        if z != nodata {
-                            dir = 0i8;
-							max_slope = f64::MIN;
-                            neighbouring_nodata = false;
-							for i in 0..8 {
-                                z_n = input[(row + dy[i], col + dx[i])];
-                                if z_n != nodata {
-                                    slope = (z - z_n) / grid_lengths[i];
-                                    if slope > max_slope && slope > 0f64 {
-                                        max_slope = slope;
-                                        dir = i as i8;
+        let mut interior_pit_found = false;
+        // the DEM is read in windows of rows, with a halo of one row on either side
+        let window_rows = input.get_window_rows();
+        let mut start_row = 0;
+        while start_row < rows {
+            let end_row = min(start_row + window_rows, rows);
+            let window = Arc::new(input.get_window(start_row - 1, end_row + 1));
+            let (tx, rx) = mpsc::channel();
+            for tid in 0..num_procs {
+                let window = window.clone();
+                let tx = tx.clone();
+                thread::spawn(move || {
+                    let dx = [ 1, 1, 1, 0, -1, -1, -1, 0 ];
+                    let dy = [ -1, 0, 1, 1, 1, 0, -1, -1 ];
+                    let grid_lengths = [diag_cell_size, cell_size_x, diag_cell_size, cell_size_y, diag_cell_size, cell_size_x, diag_cell_size, cell_size_y];
+                    let (mut z, mut z_n): (f64, f64);
+                    let (mut max_slope, mut slope): (f64, f64);
+                    let mut dir: i8;
+                    let mut neighbouring_nodata: bool;
+                    let mut interior_pit_found = false;
+                    for row in (start_row..end_row).filter(|r| r % num_procs == tid) {
+                        let mut data: Vec<i8> = vec![-1i8; columns as usize];
+                        for col in 0..columns {
+                            z = window.get_value(row, col);
+                            if z != nodata {
+                                dir = 0i8;
+    							max_slope = f64::MIN;
+                                neighbouring_nodata = false;
+    							for i in 0..8 {
+                                    z_n = window.get_value(row + dy[i], col + dx[i]);
+                                    if z_n != nodata {
+                                        slope = (z - z_n) / grid_lengths[i];
+                                        if slope > max_slope && slope > 0f64 {
+                                            max_slope = slope;
+                                            dir = i as i8;
+                                        }
+                                    } else {
+                                        neighbouring_nodata = true;
                                     }
+                                }
+                                if max_slope >= 0f64 {
+                                    data[col as usize] = dir;
                                 } else {
-                                    neighbouring_nodata = true;
+                                    data[col as usize] = -1i8;
+                                    if !neighbouring_nodata {
+                                        interior_pit_found = true;
+                                    }
                                 }
-                            }
-                            if max_slope >= 0f64 {
-                                data[col as usize] = dir;
                             } else {
                                 data[col as usize] = -1i8;
-                                if !neighbouring_nodata {
-                                    interior_pit_found = true;
-                                }
                             }
-                        } else {
-                            data[col as usize] = -1i8;
                         }
+                        tx.send((row, data, interior_pit_found)).unwrap();
                     }
-                    tx.send((row, data, interior_pit_found)).unwrap();
-                }
-            });
-        }
+                });
+            }
 
-        let mut interior_pit_found = false;
-        for r in 0..rows {
-            let (row, data, pit) = rx.recv().unwrap();
-            flow_dir.set_row_data(row, data); //(data.0, data.1);
-            if pit { interior_pit_found = true; }
-            if verbose {
+            for r in start_row..end_row {
+                let (row, data, pit) = rx.recv().unwrap();
+                flow_dir.set_row_data(row, data); //(data.0, data.1);
+                if pit { interior_pit_found = true; }
                 progress = (100.0_f64 * r as f64 / (rows - 1) as f64) as usize;
                 if progress != old_progress {
-                    println!("Flow directions: {}%", progress);
+                    sink.progress("Flow directions", progress)?;
                     old_progress = progress;
                 }
             }
+            start_row = end_row;
         }
 
         // calculate the number of inflowing cells
@@ -328,7 +275,7 @@ impl WhiteboxTool for D8MassFlux {
                 for row in (0..rows).filter(|r| r % num_procs == tid) {
                     let mut data: Vec<i8> = vec![-1i8; columns as usize];
                     for col in 0..columns {
-                        z = input[(row, col)];
+                        z = input.get_value(row, col);
                         if z != nodata {
                             count = 0i8;
 							for i in 0..8 {
@@ -359,12 +306,10 @@ impl WhiteboxTool for D8MassFlux {
                 }
             }
             
-            if verbose {
-                progress = (100.0_f64 * r as f64 / (rows - 1) as f64) as usize;
-                if progress != old_progress {
-                    println!("Num. inflowing neighbours: {}%", progress);
-                    old_progress = progress;
-                }
+            progress = (100.0_f64 * r as f64 / (rows - 1) as f64) as usize;
+            if progress != old_progress {
+                sink.progress("Num. inflowing neighbours", progress)?;
+                old_progress = progress;
             }
         }
 
@@ -393,12 +338,10 @@ impl WhiteboxTool for D8MassFlux {
                         output.set_value(row, col, nodata);
                     }
                 }
-                if verbose {
-                    progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as usize;
-                    if progress != old_progress {
-                        println!("Initializing output raster: {}%", progress);
-                        old_progress = progress;
-                    }
+                progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as usize;
+                if progress != old_progress {
+                    sink.progress("Initializing output raster", progress)?;
+                    old_progress = progress;
                 }
             }
         }
@@ -430,13 +373,11 @@ impl WhiteboxTool for D8MassFlux {
                 }
             }
 
-            if verbose {
-                num_solved_cells += 1;
-                progress = (100.0_f64 * num_solved_cells as f64 / (num_cells - 1) as f64) as usize;
-                if progress != old_progress {
-                    println!("Flow accumulation: {}%", progress);
-                    old_progress = progress;
-                }
+            num_solved_cells += 1;
+            progress = (100.0_f64 * num_solved_cells as f64 / (num_cells - 1) as f64) as usize;
+            if progress != old_progress {
+                sink.progress("Flow accumulation", progress)?;
+                old_progress = progress;
             }
         }
 
@@ -447,12 +388,10 @@ impl WhiteboxTool for D8MassFlux {
                 }
             }
             
-            if verbose {
-                progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as usize;
-                if progress != old_progress {
-                    println!("Correcting values: {}%", progress);
-                    old_progress = progress;
-                }
+            progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as usize;
+            if progress != old_progress {
+                sink.progress("Correcting values", progress)?;
+                old_progress = progress;
             }
         }
 
@@ -463,20 +402,20 @@ impl WhiteboxTool for D8MassFlux {
         output.add_metadata_entry(format!("Input file: {}", input_file));
         output.add_metadata_entry(format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
 
-        if verbose { println!("Saving data...") };
+        if verbose { sink.message("Saving data...") };
         let _ = match output.write() {
-            Ok(_) => if verbose { println!("Output file written") },
+            Ok(_) => if verbose { sink.message("Output file written") },
             Err(e) => return Err(e),
         };
         if verbose {
-            println!("{}", &format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
+            sink.message(&format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
         }
         if interior_pit_found {
-            println!("**********************************************************************************");
-            println!("WARNING: Interior pit cells were found within the input DEM. It is likely that the 
+            sink.message("**********************************************************************************");
+            sink.warning("WARNING: Interior pit cells were found within the input DEM. It is likely that the 
             DEM needs to be processed to remove topographic depressions and flats prior to
             running this tool.");
-            println!("**********************************************************************************");
+            sink.message("**********************************************************************************");
         }
 
         Ok(())
@@ -17,8 +17,10 @@ use std::sync::mpsc;
 use std::thread;
 use raster::*;
 use std::io::{Error, ErrorKind};
+use std::cmp::min;
 use structures::Array2D;
 use tools::*;
+use tools::arg_parser::ArgValues;
 
 pub struct DInfFlowAccumulation {
     name: String,
@@ -37,7 +39,7 @@ impl DInfFlowAccumulation {
         let mut parameters = vec![];
         parameters.push(ToolParameter{
             name: "Input DEM File".to_owned(), 
-            flags: vec!["-i".to_owned(), "--dem".to_owned()], 
+            flags: vec!["-i".to_owned(), "--input".to_owned(), "--dem".to_owned()], 
             description: "Input raster DEM file.".to_owned(),
             parameter_type: ParameterType::ExistingFile(ParameterFileType::Raster),
             default_value: None,
@@ -137,84 +139,36 @@ impl WhiteboxTool for DInfFlowAccumulation {
         self.toolbox.clone()
     }
 
-    fn run<'a>(&self, args: Vec<String>, working_directory: &'a str, verbose: bool) -> Result<(), Error> {
-        let mut input_file = String::new();
-        let mut output_file = String::new();
-        let mut out_type = String::from("sca");
-        let mut convergence_threshold = f64::INFINITY;
-        let mut log_transform = false;
-        let mut clip_max = false;
-        
+    fn run<'a>(&self, args: Vec<String>, working_directory: &'a str, verbose: bool, sink: &Arc<ProgressSink>) -> Result<(), Error> {
         if args.len() == 0 {
             return Err(Error::new(ErrorKind::InvalidInput,
                                 "Tool run with no paramters."));
         }
-        for i in 0..args.len() {
-            let mut arg = args[i].replace("\"", "");
-            arg = arg.replace("\'", "");
-            let cmd = arg.split("="); // in case an equals sign was used
-            let vec = cmd.collect::<Vec<&str>>();
-            let mut keyval = false;
-            if vec.len() > 1 {
-                keyval = true;
-            }
-            if vec[0].to_lowercase() == "-i" || vec[0].to_lowercase() == "--input" || vec[0].to_lowercase() == "--dem" {
-                if keyval {
-                    input_file = vec[1].to_string();
-                } else {
-                    input_file = args[i+1].to_string();
-                }
-            } else if vec[0].to_lowercase() == "-o" || vec[0].to_lowercase() == "--output" {
-                if keyval {
-                    output_file = vec[1].to_string();
-                } else {
-                    output_file = args[i+1].to_string();
-                }
-            } else if vec[0].to_lowercase() == "-out_type" || vec[0].to_lowercase() == "--out_type" {
-                if keyval {
-                    out_type = vec[1].to_lowercase();
-                } else {
-                    out_type = args[i+1].to_lowercase();
-                }
-                if out_type.contains("specific") || out_type.contains("sca") {
-                    out_type = String::from("sca");
-                } else if out_type.contains("cells") {
-                    out_type = String::from("cells");
-                } else {
-                    out_type = String::from("ca");
-                }
-            } else if vec[0].to_lowercase() == "-threshold" || vec[0].to_lowercase() == "--threshold" {
-                if keyval {
-                    convergence_threshold = vec[1].to_string().parse::<f64>().unwrap();
-                } else {
-                    convergence_threshold = args[i+1].to_string().parse::<f64>().unwrap();
-                }
-            } else if vec[0].to_lowercase() == "-log" || vec[0].to_lowercase() == "--log" {
-                log_transform = true;
-            } else if vec[0].to_lowercase() == "-clip" || vec[0].to_lowercase() == "--clip" {
-                clip_max = true;
-            }
+        let args = ArgValues::parse(&self.parameters, &args)?;
+        let input_file = args.required_file("--dem", working_directory)?;
+        let output_file = args.required_file("--output", working_directory)?;
+        let mut out_type = args.get_string("--out_type")?.unwrap_or(String::from("sca")).to_lowercase();
+        if out_type.contains("specific") || out_type.contains("sca") {
+            out_type = String::from("sca");
+        } else if out_type.contains("cells") {
+            out_type = String::from("cells");
+        } else {
+            out_type = String::from("ca");
         }
+        let convergence_threshold = args.get_f64("--threshold")?.unwrap_or(f64::INFINITY);
+        let log_transform = args.get_bool("--log")?;
+        let clip_max = args.get_bool("--clip")?;
 
         if verbose {
-            println!("***************{}", "*".repeat(self.get_tool_name().len()));
-            println!("* Welcome to {} *", self.get_tool_name());
-            println!("***************{}", "*".repeat(self.get_tool_name().len()));
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
+            sink.message(&format!("* Welcome to {} *", self.get_tool_name()));
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
         }
 
-        let sep: String = path::MAIN_SEPARATOR.to_string();
-
         let mut progress: usize;
         let mut old_progress: usize = 1;
 
-        if !input_file.contains(&sep) && !input_file.contains("/") {
-            input_file = format!("{}{}", working_directory, input_file);
-        }
-        if !output_file.contains(&sep) && !output_file.contains("/") {
-            output_file = format!("{}{}", working_directory, output_file);
-        }
-
-        if verbose { println!("Reading data...") };
+        if verbose { sink.message("Reading data...") };
 
         let input = Arc::new(Raster::new(&input_file, "r")?);
 
@@ -231,119 +185,124 @@ impl WhiteboxTool for DInfFlowAccumulation {
         let mut flow_dir: Array2D<f64> = Array2D::new(rows, columns, nodata, nodata)?;
 
         let num_procs = num_cpus::get() as isize;
-        let (tx, rx) = mpsc::channel();
-        for tid in 0..num_procs {
-            let input = input.clone();
-            let tx = tx.clone();
-            thread::spawn(move || {
-                let nodata = input.configs.nodata;
-                let grid_res = (cell_size_x + cell_size_y) / 2.0;
-                let mut dir: f64;
-                let mut max_slope: f64;
-                let mut e0: f64;
-                let mut af: f64;
-                let mut ac: f64;
-                let (mut e1, mut r, mut s1, mut s2, mut s, mut e2): (f64, f64, f64, f64, f64, f64);
+        let mut interior_pit_found = false;
+        // the DEM is read in windows of rows, with a halo of one row on either side
+        let window_rows = input.get_window_rows();
+        let mut start_row = 0;
+        while start_row < rows {
+            let end_row = min(start_row + window_rows, rows);
+            let window = Arc::new(input.get_window(start_row - 1, end_row + 1));
+            let (tx, rx) = mpsc::channel();
+            for tid in 0..num_procs {
+                let window = window.clone();
+                let tx = tx.clone();
+                thread::spawn(move || {
+                    let grid_res = (cell_size_x + cell_size_y) / 2.0;
+                    let mut dir: f64;
+                    let mut max_slope: f64;
+                    let mut e0: f64;
+                    let mut af: f64;
+                    let mut ac: f64;
+                    let (mut e1, mut r, mut s1, mut s2, mut s, mut e2): (f64, f64, f64, f64, f64, f64);
                 
-                let ac_vals = [ 0f64, 1f64, 1f64, 2f64, 2f64, 3f64, 3f64, 4f64 ];
-                let af_vals = [ 1f64, -1f64, 1f64, -1f64, 1f64, -1f64, 1f64, -1f64 ];
-
-                let e1_col = [ 1, 0, 0, -1, -1, 0, 0, 1 ];
-                let e1_row = [ 0, -1, -1, 0, 0, 1, 1, 0 ];
-
-                let e2_col = [ 1, 1, -1, -1, -1, -1, 1, 1 ];
-                let e2_row = [ -1, -1, -1, -1, 1, 1, 1, 1 ];
-
-                let atanof1 = 1.0f64.atan();
-
-                let mut neighbouring_nodata: bool;
-                let mut interior_pit_found = false;
-                for row in (0..rows).filter(|r| r % num_procs == tid) {
-                    let mut data: Vec<f64> = vec![nodata; columns as usize];
-                    for col in 0..columns {
-                        e0 = input[(row, col)];
-                        if e0 != nodata {
-                            dir = 360.0;
-							max_slope = f64::MIN;
-                            neighbouring_nodata = false;
-							for i in 0..8 {
-                                ac = ac_vals[i];
-                                af = af_vals[i];
-                                e1 = input[(row + e1_row[i], col + e1_col[i])];
-                                e2 = input[(row + e2_row[i], col + e2_col[i])];
-                                if e1 != nodata && e2 != nodata {
-                                    if e0 > e1 && e0 > e2 {
-                                        s1 = (e0 - e1) / grid_res;
-                                        if s1 == 0.0 { s1 = 0.00001; }
-                                        s2 = (e1 - e2) / grid_res;
-                                        r = (s2 / s1).atan();
-                                        s = (s1 * s1 + s2 * s2).sqrt();
-                                        if s1 < 0.0 && s2 < 0.0 { s = -1.0 * s; }
-                                        if s1 < 0.0 && s2 == 0.0 { s = -1.0 * s; }
-                                        if s1 == 0.0 && s2 < 0.0 { s = -1.0 * s; }
-                                        if s1 == 0.001 && s2 < 0.0 { s = -1.0 * s; }
-                                        if r < 0.0 || r > atanof1 {
-                                            if r < 0.0 {
+                    let ac_vals = [ 0f64, 1f64, 1f64, 2f64, 2f64, 3f64, 3f64, 4f64 ];
+                    let af_vals = [ 1f64, -1f64, 1f64, -1f64, 1f64, -1f64, 1f64, -1f64 ];
+
+                    let e1_col = [ 1, 0, 0, -1, -1, 0, 0, 1 ];
+                    let e1_row = [ 0, -1, -1, 0, 0, 1, 1, 0 ];
+
+                    let e2_col = [ 1, 1, -1, -1, -1, -1, 1, 1 ];
+                    let e2_row = [ -1, -1, -1, -1, 1, 1, 1, 1 ];
+
+                    let atanof1 = 1.0f64.atan();
+
+                    let mut neighbouring_nodata: bool;
+                    let mut interior_pit_found = false;
+                    for row in (start_row..end_row).filter(|r| r % num_procs == tid) {
+                        let mut data: Vec<f64> = vec![nodata; columns as usize];
+                        for col in 0..columns {
+                            e0 = window.get_value(row, col);
+                            if e0 != nodata {
+                                dir = 360.0;
+    							max_slope = f64::MIN;
+                                neighbouring_nodata = false;
+    							for i in 0..8 {
+                                    ac = ac_vals[i];
+                                    af = af_vals[i];
+                                    e1 = window.get_value(row + e1_row[i], col + e1_col[i]);
+                                    e2 = window.get_value(row + e2_row[i], col + e2_col[i]);
+                                    if e1 != nodata && e2 != nodata {
+                                        if e0 > e1 && e0 > e2 {
+                                            s1 = (e0 - e1) / grid_res;
+                                            if s1 == 0.0 { s1 = 0.00001; }
+                                            s2 = (e1 - e2) / grid_res;
+                                            r = (s2 / s1).atan();
+                                            s = (s1 * s1 + s2 * s2).sqrt();
+                                            if s1 < 0.0 && s2 < 0.0 { s = -1.0 * s; }
+                                            if s1 < 0.0 && s2 == 0.0 { s = -1.0 * s; }
+                                            if s1 == 0.0 && s2 < 0.0 { s = -1.0 * s; }
+                                            if s1 == 0.001 && s2 < 0.0 { s = -1.0 * s; }
+                                            if r < 0.0 || r > atanof1 {
+                                                if r < 0.0 {
+                                                    r = 0.0;
+                                                    s = s1;
+                                                } else {
+                                                    r = atanof1;
+                                                    s = (e0 - e2) / diag_cell_size;
+                                                }
+                                            }
+                                            if s >= max_slope && s != 0.00001 {
+                                                max_slope = s;
+                                                dir = af * r + ac * (PI / 2.0);
+                                            }
+                                        } else if e0 > e1 || e0 > e2 {
+                                            if e0 > e1 {
                                                 r = 0.0;
-                                                s = s1;
+                                                s = (e0 - e1) / grid_res;
                                             } else {
                                                 r = atanof1;
                                                 s = (e0 - e2) / diag_cell_size;
                                             }
+                                            if s >= max_slope && s != 0.00001 {
+                                                max_slope = s;
+                                                dir = af * r + ac * (PI / 2.0);
+                                            }
                                         }
-                                        if s >= max_slope && s != 0.00001 {
-                                            max_slope = s;
-                                            dir = af * r + ac * (PI / 2.0);
-                                        }
-                                    } else if e0 > e1 || e0 > e2 {
-                                        if e0 > e1 {
-                                            r = 0.0;
-                                            s = (e0 - e1) / grid_res;
-                                        } else {
-                                            r = atanof1;
-                                            s = (e0 - e2) / diag_cell_size;
-                                        }
-                                        if s >= max_slope && s != 0.00001 {
-                                            max_slope = s;
-                                            dir = af * r + ac * (PI / 2.0);
-                                        }
+                                    } else {
+                                        neighbouring_nodata = true;
                                     }
-                                } else {
-                                    neighbouring_nodata = true;
                                 }
-                            }
 
-                            if max_slope > 0f64 {
-                                dir = 360.0 - dir.to_degrees() + 90.0;
-                                if dir > 360.0 { dir = dir - 360.0; }
-                                data[col as usize] = dir;
+                                if max_slope > 0f64 {
+                                    dir = 360.0 - dir.to_degrees() + 90.0;
+                                    if dir > 360.0 { dir = dir - 360.0; }
+                                    data[col as usize] = dir;
+                                } else {
+                                    data[col as usize] = -1f64;
+                                    if !neighbouring_nodata {
+                                        interior_pit_found = true;
+                                    }
+                                }
                             } else {
                                 data[col as usize] = -1f64;
-                                if !neighbouring_nodata {
-                                    interior_pit_found = true;
-                                }
                             }
-                        } else {
-                            data[col as usize] = -1f64;
                         }
+                        tx.send((row, data, interior_pit_found)).unwrap();
                     }
-                    tx.send((row, data, interior_pit_found)).unwrap();
-                }
-            });
-        }
+                });
+            }
 
-        let mut interior_pit_found = false;
-        for r in 0..rows {
-            let (row, data, pit) = rx.recv().unwrap();
-            flow_dir.set_row_data(row, data);
-            if pit { interior_pit_found = true; }
-            if verbose {
+            for r in start_row..end_row {
+                let (row, data, pit) = rx.recv().unwrap();
+                flow_dir.set_row_data(row, data);
+                if pit { interior_pit_found = true; }
                 progress = (100.0_f64 * r as f64 / (rows - 1) as f64) as usize;
                 if progress != old_progress {
-                    println!("Flow directions: {}%", progress);
+                    sink.progress("Flow directions", progress)?;
                     old_progress = progress;
                 }
             }
+            start_row = end_row;
         }
         
         // calculate the number of inflowing cells
@@ -403,12 +362,10 @@ impl WhiteboxTool for DInfFlowAccumulation {
                 }
             }
             
-            if verbose {
-                progress = (100.0_f64 * r as f64 / (rows - 1) as f64) as usize;
-                if progress != old_progress {
-                    println!("Num. inflowing neighbours: {}%", progress);
-                    old_progress = progress;
-                }
+            progress = (100.0_f64 * r as f64 / (rows - 1) as f64) as usize;
+            if progress != old_progress {
+                sink.progress("Num. inflowing neighbours", progress)?;
+                old_progress = progress;
             }
         }
 
@@ -422,7 +379,7 @@ impl WhiteboxTool for DInfFlowAccumulation {
             let cell = stack.pop().unwrap();
             row = cell.0;
             col = cell.1;
-            fa = output[(row, col)];
+            fa = output.get_value(row, col);
             num_inflowing[(row, col)] = -1i8;
 
             dir = flow_dir[(row, col)];
@@ -520,13 +477,11 @@ impl WhiteboxTool for DInfFlowAccumulation {
                 }
             }
 
-            if verbose {
-                num_solved_cells += 1;
-                progress = (100.0_f64 * num_solved_cells as f64 / (num_cells - 1) as f64) as usize;
-                if progress != old_progress {
-                    println!("Flow accumulation: {}%", progress);
-                    old_progress = progress;
-                }
+            num_solved_cells += 1;
+            progress = (100.0_f64 * num_solved_cells as f64 / (num_cells - 1) as f64) as usize;
+            if progress != old_progress {
+                sink.progress("Flow accumulation", progress)?;
+                old_progress = progress;
             }
         }
 
@@ -542,37 +497,33 @@ impl WhiteboxTool for DInfFlowAccumulation {
         if log_transform {
             for row in 0..rows {
                 for col in 0..columns {
-                    if input[(row, col)] == nodata {
-                        output[(row, col)] = nodata;
+                    if input.get_value(row, col) == nodata {
+                        output.set_value(row, col, nodata);
                     } else {
-                        output[(row, col)] = (output[(row, col)] * cell_area / avg_cell_size).ln();
+                        output.set_value(row, col, (output.get_value(row, col) * cell_area / avg_cell_size).ln());
                     }
                 }
                 
-                if verbose {
-                    progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as usize;
-                    if progress != old_progress {
-                        println!("Correcting values: {}%", progress);
-                        old_progress = progress;
-                    }
+                progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as usize;
+                if progress != old_progress {
+                    sink.progress("Correcting values", progress)?;
+                    old_progress = progress;
                 }
             }
         } else {
             for row in 0..rows {
                 for col in 0..columns {
-                    if input[(row, col)] == nodata {
-                        output[(row, col)] = nodata;
+                    if input.get_value(row, col) == nodata {
+                        output.set_value(row, col, nodata);
                     } else {
-                        output[(row, col)] = output[(row, col)] * cell_area / avg_cell_size;
+                        output.set_value(row, col, output.get_value(row, col) * cell_area / avg_cell_size);
                     }
                 }
                 
-                if verbose {
-                    progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as usize;
-                    if progress != old_progress {
-                        println!("Correcting values: {}%", progress);
-                        old_progress = progress;
-                    }
+                progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as usize;
+                if progress != old_progress {
+                    sink.progress("Correcting values", progress)?;
+                    old_progress = progress;
                 }
             }
         }
@@ -587,20 +538,20 @@ impl WhiteboxTool for DInfFlowAccumulation {
         output.add_metadata_entry(format!("Input file: {}", input_file));
         output.add_metadata_entry(format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
 
-        if verbose { println!("Saving data...") };
+        if verbose { sink.message("Saving data...") };
         let _ = match output.write() {
-            Ok(_) => if verbose { println!("Output file written") },
+            Ok(_) => if verbose { sink.message("Output file written") },
             Err(e) => return Err(e),
         };
         if verbose {
-            println!("{}", &format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
+            sink.message(&format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
         }
         if interior_pit_found {
-            println!("**********************************************************************************");
-            println!("WARNING: Interior pit cells were found within the input DEM. It is likely that the 
+            sink.message("**********************************************************************************");
+            sink.warning("WARNING: Interior pit cells were found within the input DEM. It is likely that the 
             DEM needs to be processed to remove topographic depressions and flats prior to
             running this tool.");
-            println!("**********************************************************************************");
+            sink.message("**********************************************************************************");
         }
 
         Ok(())
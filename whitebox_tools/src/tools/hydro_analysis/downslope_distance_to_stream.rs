@@ -18,6 +18,7 @@ use raster::*;
 use std::io::{Error, ErrorKind};
 use structures::Array2D;
 use tools::*;
+use tools::arg_parser::ArgValues;
 
 pub struct DownslopeDistanceToStream {
     name: String,
@@ -108,69 +109,28 @@ impl WhiteboxTool for DownslopeDistanceToStream {
         self.toolbox.clone()
     }
 
-    fn run<'a>(&self, args: Vec<String>, working_directory: &'a str, verbose: bool) -> Result<(), Error> {
-        let mut dem_file = String::new();
-        let mut streams_file = String::new();
-        let mut output_file = String::new();
-        
+    fn run<'a>(&self, args: Vec<String>, working_directory: &'a str, verbose: bool, sink: &Arc<ProgressSink>) -> Result<(), Error> {
         if args.len() == 0 {
             return Err(Error::new(ErrorKind::InvalidInput,
                                 "Tool run with no paramters."));
         }
-        for i in 0..args.len() {
-            let mut arg = args[i].replace("\"", "");
-            arg = arg.replace("\'", "");
-            let cmd = arg.split("="); // in case an equals sign was used
-            let vec = cmd.collect::<Vec<&str>>();
-            let mut keyval = false;
-            if vec.len() > 1 {
-                keyval = true;
-            }
-            if vec[0].to_lowercase() == "-dem" || vec[0].to_lowercase() == "--dem" {
-                if keyval {
-                    dem_file = vec[1].to_string();
-                } else {
-                    dem_file = args[i+1].to_string();
-                }
-            } else if vec[0].to_lowercase() == "-streams" || vec[0].to_lowercase() == "--streams" {
-                if keyval {
-                    streams_file = vec[1].to_string();
-                } else {
-                    streams_file = args[i+1].to_string();
-                }
-            } else if vec[0].to_lowercase() == "-o" || vec[0].to_lowercase() == "--output" {
-                if keyval {
-                    output_file = vec[1].to_string();
-                } else {
-                    output_file = args[i+1].to_string();
-                }
-            }
-        }
+        let args = ArgValues::parse(&self.parameters, &args)?;
+        let dem_file = args.required_file("--dem", working_directory)?;
+        let streams_file = args.required_file("--streams", working_directory)?;
+        let output_file = args.required_file("--output", working_directory)?;
 
         if verbose {
-            println!("***************{}", "*".repeat(self.get_tool_name().len()));
-            println!("* Welcome to {} *", self.get_tool_name());
-            println!("***************{}", "*".repeat(self.get_tool_name().len()));
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
+            sink.message(&format!("* Welcome to {} *", self.get_tool_name()));
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
         }
 
-        let sep: String = path::MAIN_SEPARATOR.to_string();
-
         let mut progress: usize;
         let mut old_progress: usize = 1;
 
-        if !dem_file.contains(&sep) && !dem_file.contains("/") {
-            dem_file = format!("{}{}", working_directory, dem_file);
-        }
-        if !streams_file.contains(&sep) && !streams_file.contains("/") {
-            streams_file = format!("{}{}", working_directory, streams_file);
-        }
-        if !output_file.contains(&sep) && !output_file.contains("/") {
-            output_file = format!("{}{}", working_directory, output_file);
-        }
-
-        if verbose { println!("Reading DEM data...") };
+        if verbose { sink.message("Reading DEM data...") };
         let dem = Arc::new(Raster::new(&dem_file, "r")?);
-        if verbose { println!("Reading streams data...") };
+        if verbose { sink.message("Reading streams data...") };
         let streams = Raster::new(&streams_file, "r")?;
 
         let start = time::now();
@@ -211,13 +171,13 @@ impl WhiteboxTool for DownslopeDistanceToStream {
                 for row in (0..rows).filter(|r| r % num_procs == tid) {
                     let mut data: Vec<i8> = vec![flow_nodata; columns as usize];
                     for col in 0..columns {
-                        z = dem[(row, col)];
+                        z = dem.get_value(row, col);
                         if z != nodata {
                             dir = 0i8;
 							max_slope = f64::MIN;
                             neighbouring_nodata = false;
 							for i in 0..8 {
-                                z_n = dem[(row + dy[i], col + dx[i])];
+                                z_n = dem.get_value(row + dy[i], col + dx[i]);
                                 if z_n != nodata {
                                     slope = (z - z_n) / grid_lengths[i];
                                     if slope > max_slope && slope > 0f64 {
@@ -255,28 +215,26 @@ impl WhiteboxTool for DownslopeDistanceToStream {
             flow_dir.set_row_data(row, data);
             if pit { interior_pit_found = true; }
             for col in 0..columns {
-                if streams[(row, col)] > 0f64 && streams[(row, col)] != streams_nodata {
-                    output[(row, col)] = 0f64;
-                    stack.push((row, col, dem[(row, col)]));
+                if streams.get_value(row, col) > 0f64 && streams.get_value(row, col) != streams_nodata {
+                    output.set_value(row, col, 0f64);
+                    stack.push((row, col, dem.get_value(row, col)));
                 }
-                if dem[(row, col)] == nodata {
-                    output[(row, col)] = nodata;
+                if dem.get_value(row, col) == nodata {
+                    output.set_value(row, col, nodata);
                     num_solved_cells += 1;
                 }
                 if flow_dir[(row, col)] == -1 {
-                    if output[(row, col)] != 0f64 {
+                    if output.get_value(row, col) != 0f64 {
                         stack.push((row, col, nodata));
-                        output[(row, col)] = nodata;
+                        output.set_value(row, col, nodata);
                         num_solved_cells += 1;
                     }
                 }
             }
-            if verbose {
-                progress = (100.0_f64 * r as f64 / (rows - 1) as f64) as usize;
-                if progress != old_progress {
-                    println!("Flow directions: {}%", progress);
-                    old_progress = progress;
-                }
+            progress = (100.0_f64 * r as f64 / (rows - 1) as f64) as usize;
+            if progress != old_progress {
+                sink.progress("Flow directions", progress)?;
+                old_progress = progress;
             }
         }
 
@@ -294,24 +252,22 @@ impl WhiteboxTool for DownslopeDistanceToStream {
             for n in 0..8 {
                 row_n = row + dy[n];
                 col_n = col + dx[n];
-                if flow_dir[(row_n, col_n)] == inflowing_vals[n] && output[(row_n, col_n)] == background_value {
+                if flow_dir[(row_n, col_n)] == inflowing_vals[n] && output.get_value(row_n, col_n) == background_value {
                     if stream_dist != nodata {
                         dist = stream_dist + grid_lengths[n];
-                        output[(row_n, col_n)] = dist;
+                        output.set_value(row_n, col_n, dist);
                         stack.push((row_n, col_n, dist));
                     } else {
-                        output[(row_n, col_n)] = nodata;
+                        output.set_value(row_n, col_n, nodata);
                         stack.push((row_n, col_n, nodata));
                     }
                 }
             }
-            if verbose {
-                num_solved_cells += 1;
-                progress = (100.0_f64 * num_solved_cells as f64 / (num_cells - 1) as f64) as usize;
-                if progress != old_progress {
-                    println!("Progress: {}%", progress);
-                    old_progress = progress;
-                }
+            num_solved_cells += 1;
+            progress = (100.0_f64 * num_solved_cells as f64 / (num_cells - 1) as f64) as usize;
+            if progress != old_progress {
+                sink.progress("Progress", progress)?;
+                old_progress = progress;
             }
         }
         
@@ -322,21 +278,21 @@ impl WhiteboxTool for DownslopeDistanceToStream {
         output.add_metadata_entry(format!("Streams file: {}", streams_file));
         output.add_metadata_entry(format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
 
-        if verbose { println!("Saving data...") };
+        if verbose { sink.message("Saving data...") };
         let _ = match output.write() {
-            Ok(_) => if verbose { println!("Output file written") },
+            Ok(_) => if verbose { sink.message("Output file written") },
             Err(e) => return Err(e),
         };
         if verbose {
-            println!("{}", &format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
+            sink.message(&format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
         }
 
         if interior_pit_found {
-            println!("**********************************************************************************");
-            println!("WARNING: Interior pit cells were found within the input DEM. It is likely that the 
+            sink.message("**********************************************************************************");
+            sink.warning("WARNING: Interior pit cells were found within the input DEM. It is likely that the 
             DEM needs to be processed to remove topographic depressions and flats prior to
             running this tool.");
-            println!("**********************************************************************************");
+            sink.message("**********************************************************************************");
         }
         
         Ok(())
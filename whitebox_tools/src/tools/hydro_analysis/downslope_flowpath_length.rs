@@ -12,8 +12,10 @@ use std::path;
 use std::f64;
 use raster::*;
 use std::io::{Error, ErrorKind};
+use std::sync::Arc;
 use structures::Array2D;
 use tools::*;
+use tools::arg_parser::ArgValues;
 
 pub struct DownslopeFlowpathLength {
     name: String,
@@ -68,7 +70,7 @@ impl DownslopeFlowpathLength {
 
         parameters.push(ToolParameter{
             name: "Does the pointer file use the ESRI pointer scheme?".to_owned(), 
-            flags: vec!["--esri_pntr".to_owned()], 
+            flags: vec!["--esri_style".to_owned(), "--esri_pntr".to_owned()], 
             description: "D8 pointer uses the ESRI style scheme.".to_owned(),
             parameter_type: ParameterType::Boolean,
             default_value: Some("false".to_owned()),
@@ -123,59 +125,22 @@ impl WhiteboxTool for DownslopeFlowpathLength {
         self.toolbox.clone()
     }
 
-    fn run<'a>(&self, args: Vec<String>, working_directory: &'a str, verbose: bool) -> Result<(), Error> {
-        let mut d8_file = String::new();
-        let mut watersheds_file = String::new();
-        let mut weights_file = String::new();
-        let mut output_file = String::new();
-        let mut esri_style = false;
-        
+    fn run<'a>(&self, args: Vec<String>, working_directory: &'a str, verbose: bool, sink: &Arc<ProgressSink>) -> Result<(), Error> {
         if args.len() == 0 {
             return Err(Error::new(ErrorKind::InvalidInput,
                                 "Tool run with no paramters."));
         }
-        for i in 0..args.len() {
-            let mut arg = args[i].replace("\"", "");
-            arg = arg.replace("\'", "");
-            let cmd = arg.split("="); // in case an equals sign was used
-            let vec = cmd.collect::<Vec<&str>>();
-            let mut keyval = false;
-            if vec.len() > 1 {
-                keyval = true;
-            }
-            if vec[0].to_lowercase() == "-d8_pntr" || vec[0].to_lowercase() == "--d8_pntr" {
-                if keyval {
-                    d8_file = vec[1].to_string();
-                } else {
-                    d8_file = args[i+1].to_string();
-                }
-            } else if vec[0].to_lowercase() == "-watersheds" || vec[0].to_lowercase() == "--watersheds" {
-                if keyval {
-                    watersheds_file = vec[1].to_string();
-                } else {
-                    watersheds_file = args[i+1].to_string();
-                }
-            } else if vec[0].to_lowercase() == "-weights" || vec[0].to_lowercase() == "--weights" {
-                if keyval {
-                    weights_file = vec[1].to_string();
-                } else {
-                    weights_file = args[i+1].to_string();
-                }
-            } else if vec[0].to_lowercase() == "-o" || vec[0].to_lowercase() == "--output" {
-                if keyval {
-                    output_file = vec[1].to_string();
-                } else {
-                    output_file = args[i+1].to_string();
-                }
-            } else if vec[0].to_lowercase() == "-esri_pntr" || vec[0].to_lowercase() == "--esri_pntr" || vec[0].to_lowercase() == "--esri_style" {
-                esri_style = true;
-            }
-        }
+        let args = ArgValues::parse(&self.parameters, &args)?;
+        let d8_file = args.required_file("--d8_pntr", working_directory)?;
+        let mut watersheds_file = args.get_string("--watersheds")?.unwrap_or(String::new());
+        let mut weights_file = args.get_string("--weights")?.unwrap_or(String::new());
+        let output_file = args.required_file("--output", working_directory)?;
+        let esri_style = args.get_bool("--esri_pntr")?;
 
         if verbose {
-            println!("***************{}", "*".repeat(self.get_tool_name().len()));
-            println!("* Welcome to {} *", self.get_tool_name());
-            println!("***************{}", "*".repeat(self.get_tool_name().len()));
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
+            sink.message(&format!("* Welcome to {} *", self.get_tool_name()));
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
         }
 
         let sep: String = path::MAIN_SEPARATOR.to_string();
@@ -183,12 +148,6 @@ impl WhiteboxTool for DownslopeFlowpathLength {
         let mut progress: usize;
         let mut old_progress: usize = 1;
 
-        if !d8_file.contains(&sep) && !d8_file.contains("/") {
-            d8_file = format!("{}{}", working_directory, d8_file);
-        }
-        if !output_file.contains(&sep) && !output_file.contains("/") {
-            output_file = format!("{}{}", working_directory, output_file);
-        }
         let use_watersheds: bool;
         if !watersheds_file.is_empty() {
             use_watersheds = true;
@@ -208,7 +167,7 @@ impl WhiteboxTool for DownslopeFlowpathLength {
             use_weights = false
         }
         
-        if verbose { println!("Reading pointer data...") };
+        if verbose { sink.message("Reading pointer data...") };
         let pntr = Raster::new(&d8_file, "r")?;
         let rows = pntr.configs.rows as isize;
         let columns = pntr.configs.columns as isize;
@@ -217,7 +176,7 @@ impl WhiteboxTool for DownslopeFlowpathLength {
         let cell_size_y = pntr.configs.resolution_y;
         let diag_cell_size = (cell_size_x * cell_size_x + cell_size_y * cell_size_y).sqrt();
         
-        if verbose { println!("Initializing watershed data...") };
+        if verbose { sink.message("Initializing watershed data...") };
         let watersheds: Array2D<f64> = match use_watersheds {
             false => Array2D::new(1, 1, 1f64, 1f64)?,
             true => {
@@ -232,7 +191,7 @@ impl WhiteboxTool for DownslopeFlowpathLength {
         };
         // let watershed_nodata = watersheds.nodata;
 
-        if verbose { println!("Initializing weights data...") };
+        if verbose { sink.message("Initializing weights data...") };
         let weights: Array2D<f64> = match use_weights {
             false => Array2D::new(1, 1, 1f64, 1f64)?,
             true => {
@@ -290,7 +249,7 @@ impl WhiteboxTool for DownslopeFlowpathLength {
         let (mut x, mut y): (isize, isize);
         for row in 0..rows {
             for col in 0..columns {
-                if pntr[(row, col)] >= 0.0 && pntr[(row, col)] != nodata {
+                if pntr.get_value(row, col) >= 0.0 && pntr.get_value(row, col) != nodata {
                     current_id = watersheds[(row, col)];
                     dist = 0f64;
                     flag = false;
@@ -298,7 +257,7 @@ impl WhiteboxTool for DownslopeFlowpathLength {
                     y = row;
                     while !flag {
                         // find its downslope neighbour
-                        dir = pntr[(y, x)];
+                        dir = pntr.get_value(y, x);
                         if dir > 0f64 && dir != nodata {
                             if dir > 128f64 || pntr_matches[dir as usize] == 999 {
                                 return Err(Error::new(ErrorKind::InvalidInput,
@@ -311,8 +270,8 @@ impl WhiteboxTool for DownslopeFlowpathLength {
 
                             dist += grid_lengths[c] * weights[(y, x)];
 
-                            if output[(y, x)] != -999f64 {
-                                dist += output[(y, x)] * weights[(y, x)];
+                            if output.get_value(y, x) != -999f64 {
+                                dist += output.get_value(y, x) * weights[(y, x)];
                                 flag = true;
                             } else if watersheds[(y, x)] != current_id {
                                 flag = true;
@@ -326,10 +285,10 @@ impl WhiteboxTool for DownslopeFlowpathLength {
                     x = col;
                     y = row;
                     while !flag {
-                        output[(y, x)] = dist;
+                        output.set_value(y, x, dist);
 
                         // find its downslope neighbour
-                        dir = pntr[(y, x)];
+                        dir = pntr.get_value(y, x);
                         if dir > 0f64 && dir != nodata {
                             // move x and y accordingly
                             c = pntr_matches[dir as usize];
@@ -338,24 +297,22 @@ impl WhiteboxTool for DownslopeFlowpathLength {
 
                             dist -= grid_lengths[c] * weights[(y, x)];
 
-                            if output[(y, x)] != -999f64 || watersheds[(y, x)] != current_id {
+                            if output.get_value(y, x) != -999f64 || watersheds[(y, x)] != current_id {
                                 flag = true;
                             }
                         } else {
-                            output[(y, x)] = 0f64;
+                            output.set_value(y, x, 0f64);
                             flag = true;
                         }
                     }
                 } else {
-                    output[(row, col)] = out_nodata;
+                    output.set_value(row, col, out_nodata);
                 }
             }
-            if verbose {
-                progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as usize;
-                if progress != old_progress {
-                    println!("Progress: {}%", progress);
-                    old_progress = progress;
-                }
+            progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as usize;
+            if progress != old_progress {
+                sink.progress("Progress", progress)?;
+                old_progress = progress;
             }
         }
 
@@ -374,13 +331,13 @@ impl WhiteboxTool for DownslopeFlowpathLength {
         }
         output.add_metadata_entry(format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
 
-        if verbose { println!("Saving data...") };
+        if verbose { sink.message("Saving data...") };
         let _ = match output.write() {
-            Ok(_) => if verbose { println!("Output file written") },
+            Ok(_) => if verbose { sink.message("Output file written") },
             Err(e) => return Err(e),
         };
         if verbose {
-            println!("{}", &format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
+            sink.message(&format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
         }
 
         Ok(())
@@ -16,8 +16,10 @@ use std::sync::mpsc;
 use std::thread;
 use raster::*;
 use std::io::{Error, ErrorKind};
+use std::cmp::min;
 use structures::Array2D;
 use tools::*;
+use tools::arg_parser::ArgValues;
 
 pub struct FD8FlowAccumulation {
     name: String,
@@ -36,7 +38,7 @@ impl FD8FlowAccumulation {
         let mut parameters = vec![];
         parameters.push(ToolParameter{
             name: "Input DEM File".to_owned(), 
-            flags: vec!["-i".to_owned(), "--dem".to_owned()], 
+            flags: vec!["-i".to_owned(), "--input".to_owned(), "--dem".to_owned()], 
             description: "Input raster DEM file.".to_owned(),
             parameter_type: ParameterType::ExistingFile(ParameterFileType::Raster),
             default_value: None,
@@ -145,91 +147,37 @@ impl WhiteboxTool for FD8FlowAccumulation {
         self.toolbox.clone()
     }
 
-    fn run<'a>(&self, args: Vec<String>, working_directory: &'a str, verbose: bool) -> Result<(), Error> {
-        let mut input_file = String::new();
-        let mut output_file = String::new();
-        let mut out_type = String::from("sca");
-        let mut exponent = 1.1;
-        let mut convergence_threshold = f64::INFINITY;
-        let mut log_transform = false;
-        let mut clip_max = false;
-        
+    fn run<'a>(&self, args: Vec<String>, working_directory: &'a str, verbose: bool, sink: &Arc<ProgressSink>) -> Result<(), Error> {
         if args.len() == 0 {
             return Err(Error::new(ErrorKind::InvalidInput,
                                 "Tool run with no paramters."));
         }
-        for i in 0..args.len() {
-            let mut arg = args[i].replace("\"", "");
-            arg = arg.replace("\'", "");
-            let cmd = arg.split("="); // in case an equals sign was used
-            let vec = cmd.collect::<Vec<&str>>();
-            let mut keyval = false;
-            if vec.len() > 1 {
-                keyval = true;
-            }
-            if vec[0].to_lowercase() == "-i" || vec[0].to_lowercase() == "--input" || vec[0].to_lowercase() == "--dem" {
-                if keyval {
-                    input_file = vec[1].to_string();
-                } else {
-                    input_file = args[i+1].to_string();
-                }
-            } else if vec[0].to_lowercase() == "-o" || vec[0].to_lowercase() == "--output" {
-                if keyval {
-                    output_file = vec[1].to_string();
-                } else {
-                    output_file = args[i+1].to_string();
-                }
-            } else if vec[0].to_lowercase() == "-out_type" || vec[0].to_lowercase() == "--out_type" {
-                if keyval {
-                    out_type = vec[1].to_lowercase();
-                } else {
-                    out_type = args[i+1].to_lowercase();
-                }
-                if out_type.contains("specific") || out_type.contains("sca") {
-                    out_type = String::from("sca");
-                } else if out_type.contains("cells") {
-                    out_type = String::from("cells");
-                } else {
-                    out_type = String::from("ca");
-                }
-            } else if vec[0].to_lowercase() == "-exponent" || vec[0].to_lowercase() == "--exponent" {
-                if keyval {
-                    exponent = vec[1].to_string().parse::<f64>().unwrap();
-                } else {
-                    exponent = args[i+1].to_string().parse::<f64>().unwrap();
-                }
-            } else if vec[0].to_lowercase() == "-threshold" || vec[0].to_lowercase() == "--threshold" {
-                if keyval {
-                    convergence_threshold = vec[1].to_string().parse::<f64>().unwrap();
-                } else {
-                    convergence_threshold = args[i+1].to_string().parse::<f64>().unwrap();
-                }
-            } else if vec[0].to_lowercase() == "-log" || vec[0].to_lowercase() == "--log" {
-                log_transform = true;
-            } else if vec[0].to_lowercase() == "-clip" || vec[0].to_lowercase() == "--clip" {
-                clip_max = true;
-            }
+        let args = ArgValues::parse(&self.parameters, &args)?;
+        let input_file = args.required_file("--dem", working_directory)?;
+        let output_file = args.required_file("--output", working_directory)?;
+        let mut out_type = args.get_string("--out_type")?.unwrap_or(String::from("sca")).to_lowercase();
+        if out_type.contains("specific") || out_type.contains("sca") {
+            out_type = String::from("sca");
+        } else if out_type.contains("cells") {
+            out_type = String::from("cells");
+        } else {
+            out_type = String::from("ca");
         }
+        let exponent = args.get_f64("--exponent")?.unwrap_or(1.1);
+        let convergence_threshold = args.get_f64("--threshold")?.unwrap_or(f64::INFINITY);
+        let log_transform = args.get_bool("--log")?;
+        let clip_max = args.get_bool("--clip")?;
 
         if verbose {
-            println!("***************{}", "*".repeat(self.get_tool_name().len()));
-            println!("* Welcome to {} *", self.get_tool_name());
-            println!("***************{}", "*".repeat(self.get_tool_name().len()));
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
+            sink.message(&format!("* Welcome to {} *", self.get_tool_name()));
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
         }
 
-        let sep: String = path::MAIN_SEPARATOR.to_string();
-
         let mut progress: usize;
         let mut old_progress: usize = 1;
 
-        if !input_file.contains(&sep) && !input_file.contains("/") {
-            input_file = format!("{}{}", working_directory, input_file);
-        }
-        if !output_file.contains(&sep) && !output_file.contains("/") {
-            output_file = format!("{}{}", working_directory, output_file);
-        }
-
-        if verbose { println!("Reading data...") };
+        if verbose { sink.message("Reading data...") };
 
         let input = Arc::new(Raster::new(&input_file, "r")?);
 
@@ -245,62 +193,68 @@ impl WhiteboxTool for FD8FlowAccumulation {
         // calculate the number of inflowing cells
         let mut num_inflowing: Array2D<i8> = Array2D::new(rows, columns, -1, -1)?;
         let num_procs = num_cpus::get() as isize;
-        let (tx, rx) = mpsc::channel();
-        for tid in 0..num_procs {
-            let input = input.clone();
-            let tx = tx.clone();
-            thread::spawn(move || {
-                let d_x = [ 1, 1, 1, 0, -1, -1, -1, 0 ];
-                let d_y = [ -1, 0, 1, 1, 1, 0, -1, -1 ];
-                let mut z: f64;
-                let mut count: i8;
-                let mut interior_pit_found = false;
-                for row in (0..rows).filter(|r| r % num_procs == tid) {
-                    let mut data: Vec<i8> = vec![-1i8; columns as usize];
-                    for col in 0..columns {
-                        z = input[(row, col)];
-                        if z != nodata {
-                            count = 0i8;
-							for i in 0..8 {
-                                if input[(row + d_y[i], col + d_x[i])] > z {
-                                    count += 1;
-                                }
-                            }
-                            data[col as usize] = count;
-                            if count == 8 {
-                                interior_pit_found = true;
-                            }
-                        }
-                    }
-                    tx.send((row, data, interior_pit_found)).unwrap();
-                }
-            });
-        }
-
         let mut output = Raster::initialize_using_file(&output_file, &input);
         output.reinitialize_values(1.0);
         let mut stack = Vec::with_capacity((rows * columns) as usize);
         let mut num_solved_cells = 0;
         let mut interior_pit_found = false;
-        for r in 0..rows {
-            let (row, data, pit) = rx.recv().unwrap();
-            num_inflowing.set_row_data(row, data);
-            if pit { interior_pit_found = true; }
-            for col in 0..columns {
-                if num_inflowing[(row, col)] == 0i8 {
-                    stack.push((row, col));
-                } else if num_inflowing[(row, col)] == -1i8 {
-                    num_solved_cells += 1;
-                }
+        // the DEM is read in windows of rows, with a halo of one row on either side
+        let window_rows = input.get_window_rows();
+        let mut start_row = 0;
+        while start_row < rows {
+            let end_row = min(start_row + window_rows, rows);
+            let window = Arc::new(input.get_window(start_row - 1, end_row + 1));
+            let (tx, rx) = mpsc::channel();
+            for tid in 0..num_procs {
+                let window = window.clone();
+                let tx = tx.clone();
+                thread::spawn(move || {
+                    let d_x = [ 1, 1, 1, 0, -1, -1, -1, 0 ];
+                    let d_y = [ -1, 0, 1, 1, 1, 0, -1, -1 ];
+                    let mut z: f64;
+                    let mut count: i8;
+                    let mut interior_pit_found = false;
+                    for row in (start_row..end_row).filter(|r| r % num_procs == tid) {
+                        let mut data: Vec<i8> = vec![-1i8; columns as usize];
+                        for col in 0..columns {
+                            z = window.get_value(row, col);
+                            if z != nodata {
+                                count = 0i8;
+    							for i in 0..8 {
+                                    if window.get_value(row + d_y[i], col + d_x[i]) > z {
+                                        count += 1;
+                                    }
+                                }
+                                data[col as usize] = count;
+                                if count == 8 {
+                                    interior_pit_found = true;
+                                }
+                            }
+                        }
+                        tx.send((row, data, interior_pit_found)).unwrap();
+                    }
+                });
             }
+
+            for r in start_row..end_row {
+                let (row, data, pit) = rx.recv().unwrap();
+                num_inflowing.set_row_data(row, data);
+                if pit { interior_pit_found = true; }
+                for col in 0..columns {
+                    if num_inflowing[(row, col)] == 0i8 {
+                        stack.push((row, col));
+                    } else if num_inflowing[(row, col)] == -1i8 {
+                        num_solved_cells += 1;
+                    }
+                }
             
-            if verbose {
                 progress = (100.0_f64 * r as f64 / (rows - 1) as f64) as usize;
                 if progress != old_progress {
-                    println!("Num. inflowing neighbours: {}%", progress);
+                    sink.progress("Num. inflowing neighbours", progress)?;
                     old_progress = progress;
                 }
             }
+            start_row = end_row;
         }
 
         let d_x = [ 1, 1, 1, 0, -1, -1, -1, 0 ];
@@ -317,8 +271,8 @@ impl WhiteboxTool for FD8FlowAccumulation {
             let cell = stack.pop().unwrap();
             row = cell.0;
             col = cell.1;
-            z = input[(row, col)];
-            fa = output[(row, col)];
+            z = input.get_value(row, col);
+            fa = output.get_value(row, col);
             num_inflowing[(row, col)] = -1i8;
 
             let mut total_weights = 0.0;
@@ -328,7 +282,7 @@ impl WhiteboxTool for FD8FlowAccumulation {
                 for i in 0..8 {
                     row_n = row + d_y[i];
                     col_n = col + d_x[i];
-                    z_n = input[(row_n, col_n)];
+                    z_n = input.get_value(row_n, col_n);
                     if z_n < z && z_n != nodata {
                         weights[i] = (z - z_n).powf(exponent);
                         total_weights += weights[i];
@@ -340,7 +294,7 @@ impl WhiteboxTool for FD8FlowAccumulation {
                 dir = 0i8;
                 max_slope = f64::MIN;
                 for i in 0..8 {
-                    z_n = input[(row + d_y[i], col + d_x[i])];
+                    z_n = input.get_value(row + d_y[i], col + d_x[i]);
                     if z_n != nodata {
                         slope = (z - z_n) / grid_lengths[i];
                         if slope > 0f64 {
@@ -372,13 +326,11 @@ impl WhiteboxTool for FD8FlowAccumulation {
                 }
             }
 
-            if verbose {
-                num_solved_cells += 1;
-                progress = (100.0_f64 * num_solved_cells as f64 / (num_cells - 1) as f64) as usize;
-                if progress != old_progress {
-                    println!("Flow accumulation: {}%", progress);
-                    old_progress = progress;
-                }
+            num_solved_cells += 1;
+            progress = (100.0_f64 * num_solved_cells as f64 / (num_cells - 1) as f64) as usize;
+            if progress != old_progress {
+                sink.progress("Flow accumulation", progress)?;
+                old_progress = progress;
             }
         }
 
@@ -394,37 +346,33 @@ impl WhiteboxTool for FD8FlowAccumulation {
         if log_transform {
             for row in 0..rows {
                 for col in 0..columns {
-                    if input[(row, col)] == nodata {
-                        output[(row, col)] = nodata;
+                    if input.get_value(row, col) == nodata {
+                        output.set_value(row, col, nodata);
                     } else {
-                        output[(row, col)] = (output[(row, col)] * cell_area / avg_cell_size).ln();
+                        output.set_value(row, col, (output.get_value(row, col) * cell_area / avg_cell_size).ln());
                     }
                 }
                 
-                if verbose {
-                    progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as usize;
-                    if progress != old_progress {
-                        println!("Correcting values: {}%", progress);
-                        old_progress = progress;
-                    }
+                progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as usize;
+                if progress != old_progress {
+                    sink.progress("Correcting values", progress)?;
+                    old_progress = progress;
                 }
             }
         } else {
             for row in 0..rows {
                 for col in 0..columns {
-                    if input[(row, col)] == nodata {
-                        output[(row, col)] = nodata;
+                    if input.get_value(row, col) == nodata {
+                        output.set_value(row, col, nodata);
                     } else {
-                        output[(row, col)] = output[(row, col)] * cell_area / avg_cell_size;
+                        output.set_value(row, col, output.get_value(row, col) * cell_area / avg_cell_size);
                     }
                 }
                 
-                if verbose {
-                    progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as usize;
-                    if progress != old_progress {
-                        println!("Correcting values: {}%", progress);
-                        old_progress = progress;
-                    }
+                progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as usize;
+                if progress != old_progress {
+                    sink.progress("Correcting values", progress)?;
+                    old_progress = progress;
                 }
             }
         }
@@ -439,20 +387,20 @@ impl WhiteboxTool for FD8FlowAccumulation {
         output.add_metadata_entry(format!("Input file: {}", input_file));
         output.add_metadata_entry(format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
 
-        if verbose { println!("Saving data...") };
+        if verbose { sink.message("Saving data...") };
         let _ = match output.write() {
-            Ok(_) => if verbose { println!("Output file written") },
+            Ok(_) => if verbose { sink.message("Output file written") },
             Err(e) => return Err(e),
         };
         if verbose {
-            println!("{}", &format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
+            sink.message(&format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
         }
         if interior_pit_found {
-            println!("**********************************************************************************");
-            println!("WARNING: Interior pit cells were found within the input DEM. It is likely that the 
+            sink.message("**********************************************************************************");
+            sink.warning("WARNING: Interior pit cells were found within the input DEM. It is likely that the 
             DEM needs to be processed to remove topographic depressions and flats prior to
             running this tool.");
-            println!("**********************************************************************************");
+            sink.message("**********************************************************************************");
         }
 
         Ok(())
@@ -15,8 +15,10 @@ use std::io::{Error, ErrorKind};
 use std::sync::Arc;
 use std::sync::mpsc;
 use std::thread;
+use std::cmp::min;
 use raster::*;
 use tools::*;
+use tools::arg_parser::ArgValues;
 
 pub struct FillSingleCellPits {
     name: String,
@@ -37,7 +39,7 @@ impl FillSingleCellPits {
         let mut parameters = vec![];
         parameters.push(ToolParameter{
             name: "Input DEM File".to_owned(), 
-            flags: vec!["-i".to_owned(), "--dem".to_owned()], 
+            flags: vec!["-i".to_owned(), "--input".to_owned(), "--dem".to_owned()], 
             description: "Input raster DEM file.".to_owned(),
             parameter_type: ParameterType::ExistingFile(ParameterFileType::Raster),
             default_value: None,
@@ -106,52 +108,21 @@ impl WhiteboxTool for FillSingleCellPits {
     fn run<'a>(&self,
                args: Vec<String>,
                working_directory: &'a str,
-               verbose: bool)
+               verbose: bool,
+               sink: &Arc<ProgressSink>)
                -> Result<(), Error> {
-        let mut input_file = String::new();
-        let mut output_file = String::new();
-        
         if args.len() == 0 {
             return Err(Error::new(ErrorKind::InvalidInput,
                                   "Tool run with no paramters."));
         }
-        for i in 0..args.len() {
-            let mut arg = args[i].replace("\"", "");
-            arg = arg.replace("\'", "");
-            let cmd = arg.split("="); // in case an equals sign was used
-            let vec = cmd.collect::<Vec<&str>>();
-            let mut keyval = false;
-            if vec.len() > 1 {
-                keyval = true;
-            }
-            if vec[0].to_lowercase() == "-i" || vec[0].to_lowercase() == "--input" || vec[0].to_lowercase() == "--dem" {
-                if keyval {
-                    input_file = vec[1].to_string();
-                } else {
-                    input_file = args[i + 1].to_string();
-                }
-            } else if vec[0].to_lowercase() == "-o" || vec[0].to_lowercase() == "--output" {
-                if keyval {
-                    output_file = vec[1].to_string();
-                } else {
-                    output_file = args[i + 1].to_string();
-                }
-            }
-        }
+        let args = ArgValues::parse(&self.parameters, &args)?;
+        let input_file = args.required_file("--dem", working_directory)?;
+        let output_file = args.required_file("--output", working_directory)?;
 
         if verbose {
-            println!("***************{}", "*".repeat(self.get_tool_name().len()));
-            println!("* Welcome to {} *", self.get_tool_name());
-            println!("***************{}", "*".repeat(self.get_tool_name().len()));
-        }
-
-        let sep: String = path::MAIN_SEPARATOR.to_string();
-
-        if !input_file.contains(&sep) && !input_file.contains("/") {
-            input_file = format!("{}{}", working_directory, input_file);
-        }
-        if !output_file.contains(&sep) && !output_file.contains("/") {
-            output_file = format!("{}{}", working_directory, output_file);
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
+            sink.message(&format!("* Welcome to {} *", self.get_tool_name()));
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
         }
 
         let input = Arc::new(Raster::new(&input_file, "r")?);
@@ -167,55 +138,61 @@ impl WhiteboxTool for FillSingleCellPits {
         let mut output = Raster::initialize_using_file(&output_file, &input);
         
         let num_procs = num_cpus::get() as isize;
-        let (tx, rx) = mpsc::channel();
-        for tid in 0..num_procs {
-            let input = input.clone();
-            let tx = tx.clone();
-            thread::spawn(move || {
-                let mut z: f64;
-                let mut zn: f64;
-                let mut min_zn: f64;
-                let mut flag: bool;
-                let small_val = 0.0001;
-                let dx = [ 1, 1, 1, 0, -1, -1, -1, 0 ];
-                let dy = [ -1, 0, 1, 1, 1, 0, -1, -1 ];
-                for row in (0..rows).filter(|r| r % num_procs == tid) {
-                    let mut data = vec![nodata; columns as usize];
-                    for col in 0..columns {
-                        z = input[(row, col)];
-                        if z != nodata {
-                            flag = true;
-                            min_zn = f64::INFINITY;
-                            for n in 0..8 {
-                                zn = input[(row + dy[n], col + dx[n])];
-                                if zn < min_zn { min_zn = zn; }
-                                if zn != nodata && zn < z {
-                                    flag = false;
-                                    break;
+        // the DEM is read in windows of rows, with a halo of one row on either side
+        let window_rows = input.get_window_rows();
+        let mut start_row = 0;
+        while start_row < rows {
+            let end_row = min(start_row + window_rows, rows);
+            let window = Arc::new(input.get_window(start_row - 1, end_row + 1));
+            let (tx, rx) = mpsc::channel();
+            for tid in 0..num_procs {
+                let window = window.clone();
+                let tx = tx.clone();
+                thread::spawn(move || {
+                    let mut z: f64;
+                    let mut zn: f64;
+                    let mut min_zn: f64;
+                    let mut flag: bool;
+                    let small_val = 0.0001;
+                    let dx = [ 1, 1, 1, 0, -1, -1, -1, 0 ];
+                    let dy = [ -1, 0, 1, 1, 1, 0, -1, -1 ];
+                    for row in (start_row..end_row).filter(|r| r % num_procs == tid) {
+                        let mut data = vec![nodata; columns as usize];
+                        for col in 0..columns {
+                            z = window.get_value(row, col);
+                            if z != nodata {
+                                flag = true;
+                                min_zn = f64::INFINITY;
+                                for n in 0..8 {
+                                    zn = window.get_value(row + dy[n], col + dx[n]);
+                                    if zn < min_zn { min_zn = zn; }
+                                    if zn != nodata && zn < z {
+                                        flag = false;
+                                        break;
+                                    }
+                                }
+                                if !flag { 
+                                    data[col as usize] = z;
+                                } else {
+                                    data[col as usize] = min_zn + small_val;
                                 }
-                            }
-                            if !flag { 
-                                data[col as usize] = z;
-                            } else {
-                                data[col as usize] = min_zn + small_val;
                             }
                         }
+                        tx.send((row, data)).unwrap();
                     }
-                    tx.send((row, data)).unwrap();
-                }
-            });
-        }
+                });
+            }
 
-        for row in 0..rows {
-            let data = rx.recv().unwrap();
-            output.set_row_data(data.0, data.1);
-            if verbose {
+            for row in start_row..end_row {
+                let data = rx.recv().unwrap();
+                output.set_row_data(data.0, data.1);
                 progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as i32;
                 if progress != old_progress {
-                    println!("Progress: {}%", progress);
+                    sink.progress("Progress", progress as usize)?;
                     old_progress = progress;
                 }
             }
+            start_row = end_row;
         }
 
         let end = time::now();
@@ -227,19 +204,18 @@ impl WhiteboxTool for FillSingleCellPits {
                                       .replace("PT", ""));
 
         if verbose {
-            println!("Saving data...")
+            sink.message("Saving data...")
         };
         let _ = match output.write() {
             Ok(_) => {
                 if verbose {
-                    println!("Output file written")
+                    sink.message("Output file written")
                 }
             }
             Err(e) => return Err(e),
         };
         if verbose {
-            println!("{}",
-                 &format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
+            sink.message(&format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
         }
 
         Ok(())
@@ -18,6 +18,7 @@ use std::thread;
 use raster::*;
 use structures::Array2D;
 use tools::*;
+use tools::arg_parser::ArgValues;
 
 pub struct FindParallelFlow {
     name: String,
@@ -117,63 +118,23 @@ impl WhiteboxTool for FindParallelFlow {
     fn run<'a>(&self,
                args: Vec<String>,
                working_directory: &'a str,
-               verbose: bool)
+               verbose: bool,
+               sink: &Arc<ProgressSink>)
                -> Result<(), Error> {
-        let mut d8_file = String::new();
-        let mut use_streams = false;
-        let mut streams_file = String::new();
-        let mut output_file = String::new();
-        
         if args.len() == 0 {
             return Err(Error::new(ErrorKind::InvalidInput,
                                   "Tool run with no paramters."));
         }
-        for i in 0..args.len() {
-            let mut arg = args[i].replace("\"", "");
-            arg = arg.replace("\'", "");
-            let cmd = arg.split("="); // in case an equals sign was used
-            let vec = cmd.collect::<Vec<&str>>();
-            let mut keyval = false;
-            if vec.len() > 1 {
-                keyval = true;
-            }
-            if vec[0].to_lowercase() == "-d8_pntr" || vec[0].to_lowercase() == "--d8_pntr" {
-                if keyval {
-                    d8_file = vec[1].to_string();
-                } else {
-                    d8_file = args[i+1].to_string();
-                }
-            } else if vec[0].to_lowercase() == "-streams" || vec[0].to_lowercase() == "--streams" {
-                if keyval {
-                    streams_file = vec[1].to_string();
-                } else {
-                    streams_file = args[i+1].to_string();
-                }
-                if !streams_file.is_empty() {
-                    use_streams = true;
-                }
-            } else if vec[0].to_lowercase() == "-o" || vec[0].to_lowercase() == "--output" {
-                if keyval {
-                    output_file = vec[1].to_string();
-                } else {
-                    output_file = args[i + 1].to_string();
-                }
-            }
-        }
+        let args = ArgValues::parse(&self.parameters, &args)?;
+        let d8_file = args.required_file("--d8_pntr", working_directory)?;
+        let streams_file = args.get_file("--streams", working_directory)?.unwrap_or(String::new());
+        let use_streams = !streams_file.is_empty();
+        let output_file = args.required_file("--output", working_directory)?;
 
         if verbose {
-            println!("***************{}", "*".repeat(self.get_tool_name().len()));
-            println!("* Welcome to {} *", self.get_tool_name());
-            println!("***************{}", "*".repeat(self.get_tool_name().len()));
-        }
-
-        let sep: String = path::MAIN_SEPARATOR.to_string();
-
-        if !d8_file.contains(&sep) && !d8_file.contains("/") {
-            d8_file = format!("{}{}", working_directory, d8_file);
-        }
-        if !output_file.contains(&sep) && !output_file.contains("/") {
-            output_file = format!("{}{}", working_directory, output_file);
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
+            sink.message(&format!("* Welcome to {} *", self.get_tool_name()));
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
         }
 
         let pntr = Arc::new(Raster::new(&d8_file, "r")?);
@@ -193,7 +154,7 @@ impl WhiteboxTool for FindParallelFlow {
                 Array2D::new(1, 1, 1f64, 1f64)?
             },
             true => {
-                if verbose { println!("Reading streams data...") };
+                if verbose { sink.message("Reading streams data...") };
                 let r = Raster::new(&streams_file, "r")?;
                 if r.configs.rows != rows as usize || r.configs.columns != columns as usize {
                     return Err(Error::new(ErrorKind::InvalidInput,
@@ -226,13 +187,13 @@ impl WhiteboxTool for FindParallelFlow {
                 for row in (0..rows).filter(|r| r % num_procs == tid) {
                     let mut data = vec![nodata; columns as usize];
                     for col in 0..columns {
-                        z = pntr[(row, col)];
+                        z = pntr.get_value(row, col);
                         stream_val = streams[(row, col)];
                         if z != nodata && stream_val != streams_nodata && stream_val > 0f64 {
                             is_parallel = false;
                             for n in 0..8 {
                                 if z != outflowing_vals[n] {
-                                    zn = pntr[(row + dy[n], col + dx[n])];
+                                    zn = pntr.get_value(row + dy[n], col + dx[n]);
                                     stream_valn = streams[(row + dy[n], col + dx[n])];
                                     if zn == z && 
                                         zn != inflowing_vals[n] && 
@@ -259,12 +220,10 @@ impl WhiteboxTool for FindParallelFlow {
         for row in 0..rows {
             let data = rx.recv().unwrap();
             output.set_row_data(data.0, data.1);
-            if verbose {
-                progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as i32;
-                if progress != old_progress {
-                    println!("Progress: {}%", progress);
-                    old_progress = progress;
-                }
+            progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as i32;
+            if progress != old_progress {
+                sink.progress("Progress", progress as usize)?;
+                old_progress = progress;
             }
         }
 
@@ -280,20 +239,19 @@ impl WhiteboxTool for FindParallelFlow {
                                       .replace("PT", ""));
 
         if verbose {
-            println!("Saving data...")
+            sink.message("Saving data...")
         };
         let _ = match output.write() {
             Ok(_) => {
                 if verbose {
-                    println!("Output file written")
+                    sink.message("Output file written")
                 }
             }
             Err(e) => return Err(e),
         };
 
         if verbose {
-            println!("{}",
-                 &format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
+            sink.message(&format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
         }
 
         Ok(())
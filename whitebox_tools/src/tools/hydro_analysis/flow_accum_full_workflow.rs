@@ -23,8 +23,10 @@ use std::i32;
 use std::f64;
 use raster::*;
 use std::io::{Error, ErrorKind};
+use std::cmp::min;
 use structures::Array2D;
 use tools::*;
+use tools::arg_parser::ArgValues;
 
 pub struct FlowAccumulationFullWorkflow {
     name: String,
@@ -43,7 +45,7 @@ impl FlowAccumulationFullWorkflow {
         let mut parameters = vec![];
         parameters.push(ToolParameter{
             name: "Input DEM File".to_owned(), 
-            flags: vec!["-i".to_owned(), "--dem".to_owned()], 
+            flags: vec!["-i".to_owned(), "--input".to_owned(), "--dem".to_owned()], 
             description: "Input raster DEM file.".to_owned(),
             parameter_type: ParameterType::ExistingFile(ParameterFileType::Raster),
             default_value: None,
@@ -106,7 +108,7 @@ impl FlowAccumulationFullWorkflow {
 
         parameters.push(ToolParameter{
             name: "Does the pointer file use the ESRI pointer scheme?".to_owned(), 
-            flags: vec!["--esri_pntr".to_owned()], 
+            flags: vec!["--esri_style".to_owned(), "--esri_pntr".to_owned()], 
             description: "D8 pointer uses the ESRI style scheme.".to_owned(),
             parameter_type: ParameterType::Boolean,
             default_value: Some("false".to_owned()),
@@ -160,100 +162,38 @@ impl WhiteboxTool for FlowAccumulationFullWorkflow {
         self.toolbox.clone()
     }
 
-    fn run<'a>(&self, args: Vec<String>, working_directory: &'a str, verbose: bool) -> Result<(), Error> {
-        let mut input_file = String::new();
-        let mut outdem_file = String::new();
-        let mut pntr_file = String::new();
-        let mut accum_file = String::new();
-        let mut out_type = String::from("sca");
-        let mut log_transform = false;
-        let mut clip_max = false;
-        let mut esri_style = false;
-
+    fn run<'a>(&self, args: Vec<String>, working_directory: &'a str, verbose: bool, sink: &Arc<ProgressSink>) -> Result<(), Error> {
         if args.len() == 0 {
             return Err(Error::new(ErrorKind::InvalidInput,
                                 "Tool run with no paramters."));
         }
-        for i in 0..args.len() {
-            let mut arg = args[i].replace("\"", "");
-            arg = arg.replace("\'", "");
-            let cmd = arg.split("="); // in case an equals sign was used
-            let vec = cmd.collect::<Vec<&str>>();
-            let mut keyval = false;
-            if vec.len() > 1 {
-                keyval = true;
-            }
-            if vec[0].to_lowercase() == "-i" || vec[0].to_lowercase() == "--input" || vec[0].to_lowercase() == "--dem" {
-                if keyval {
-                    input_file = vec[1].to_string();
-                } else {
-                    input_file = args[i+1].to_string();
-                }
-            } else if vec[0].to_lowercase() == "-out_dem" || vec[0].to_lowercase() == "--out_dem" {
-                if keyval {
-                    outdem_file = vec[1].to_string();
-                } else {
-                    outdem_file = args[i+1].to_string();
-                }
-            } else if vec[0].to_lowercase() == "-out_pntr" || vec[0].to_lowercase() == "--out_pntr" {
-                if keyval {
-                    pntr_file = vec[1].to_string();
-                } else {
-                    pntr_file = args[i+1].to_string();
-                }
-            } else if vec[0].to_lowercase() == "-out_accum" || vec[0].to_lowercase() == "--out_accum" {
-                if keyval {
-                    accum_file = vec[1].to_string();
-                } else {
-                    accum_file = args[i+1].to_string();
-                }
-            } else if vec[0].to_lowercase() == "-out_type" || vec[0].to_lowercase() == "--out_type" {
-                if keyval {
-                    out_type = vec[1].to_lowercase();
-                } else {
-                    out_type = args[i+1].to_lowercase();
-                }
-                if out_type.contains("specific") || out_type.contains("sca") {
-                    out_type = String::from("sca");
-                } else if out_type.contains("cells") {
-                    out_type = String::from("cells");
-                } else {
-                    out_type = String::from("ca");
-                }
-            } else if vec[0].to_lowercase() == "-log" || vec[0].to_lowercase() == "--log" {
-                log_transform = true;
-            } else if vec[0].to_lowercase() == "-clip" || vec[0].to_lowercase() == "--clip" {
-                clip_max = true;
-            } else if vec[0].to_lowercase() == "-esri_style" || vec[0].to_lowercase() == "--esri_style" || vec[0].to_lowercase() == "--esri_pntr" {
-                esri_style = true;
-            }
+        let args = ArgValues::parse(&self.parameters, &args)?;
+        let input_file = args.required_file("--dem", working_directory)?;
+        let outdem_file = args.required_file("--out_dem", working_directory)?;
+        let pntr_file = args.required_file("--out_pntr", working_directory)?;
+        let accum_file = args.required_file("--out_accum", working_directory)?;
+        let mut out_type = args.get_string("--out_type")?.unwrap_or(String::from("sca")).to_lowercase();
+        if out_type.contains("specific") || out_type.contains("sca") {
+            out_type = String::from("sca");
+        } else if out_type.contains("cells") {
+            out_type = String::from("cells");
+        } else {
+            out_type = String::from("ca");
         }
+        let log_transform = args.get_bool("--log")?;
+        let clip_max = args.get_bool("--clip")?;
+        let esri_style = args.get_bool("--esri_pntr")?;
 
         if verbose {
-            println!("***************{}", "*".repeat(self.get_tool_name().len()));
-            println!("* Welcome to {} *", self.get_tool_name());
-            println!("***************{}", "*".repeat(self.get_tool_name().len()));
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
+            sink.message(&format!("* Welcome to {} *", self.get_tool_name()));
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
         }
 
-        let sep: String = path::MAIN_SEPARATOR.to_string();
-
         let mut progress: usize;
         let mut old_progress: usize = 1;
 
-        if !input_file.contains(&sep) && !input_file.contains("/") {
-            input_file = format!("{}{}", working_directory, input_file);
-        }
-        if !outdem_file.contains(&sep) && !outdem_file.contains("/") {
-            outdem_file = format!("{}{}", working_directory, outdem_file);
-        }
-        if !pntr_file.contains(&sep) && !pntr_file.contains("/") {
-            pntr_file = format!("{}{}", working_directory, pntr_file);
-        }
-        if !accum_file.contains(&sep) && !accum_file.contains("/") {
-            accum_file = format!("{}{}", working_directory, accum_file);
-        }
-
-        if verbose { println!("Reading data...") };
+        if verbose { sink.message("Reading data...") };
 
         let input = Arc::new(Raster::new(&input_file, "r")?);
 
@@ -280,57 +220,63 @@ impl WhiteboxTool for FlowAccumulationFullWorkflow {
         let eight_grid_res = input.configs.resolution_x * 8.0;
 
         let num_procs = num_cpus::get() as isize;
-        let (tx, rx) = mpsc::channel();
-        for tid in 0..num_procs {
-            let input = input.clone();
-            let tx = tx.clone();
-            thread::spawn(move || {
-                let dx = [ 1, 1, 1, 0, -1, -1, -1, 0 ];
-                let dy = [ -1, 0, 1, 1, 1, 0, -1, -1 ];
-                let mut n: [f64; 8] = [0.0; 8];
-                let mut z: f64;
-                let (mut fx, mut fy): (f64, f64);
-                for row in (0..rows).filter(|r| r % num_procs == tid) {
-                    let mut data = vec![nodata; columns as usize];
-                    for col in 0..columns {
-                        z = input[(row, col)];
-                        if z != nodata {
-                            for c in 0..8 {
-                                n[c] = input[(row + dy[c], col + dx[c])];
-                                if n[c] != nodata {
-                                    n[c] = n[c] * z_factor;
-                                } else {
-                                    n[c] = z * z_factor;
+        let mut aspect: Array2D<f64> = Array2D::new(rows, columns, nodata, nodata)?;
+        // the DEM is read in windows of rows, with a halo of one row on either side
+        let window_rows = input.get_window_rows();
+        let mut start_row = 0;
+        while start_row < rows {
+            let end_row = min(start_row + window_rows, rows);
+            let window = Arc::new(input.get_window(start_row - 1, end_row + 1));
+            let (tx, rx) = mpsc::channel();
+            for tid in 0..num_procs {
+                let window = window.clone();
+                let tx = tx.clone();
+                thread::spawn(move || {
+                    let dx = [ 1, 1, 1, 0, -1, -1, -1, 0 ];
+                    let dy = [ -1, 0, 1, 1, 1, 0, -1, -1 ];
+                    let mut n: [f64; 8] = [0.0; 8];
+                    let mut z: f64;
+                    let (mut fx, mut fy): (f64, f64);
+                    for row in (start_row..end_row).filter(|r| r % num_procs == tid) {
+                        let mut data = vec![nodata; columns as usize];
+                        for col in 0..columns {
+                            z = window.get_value(row, col);
+                            if z != nodata {
+                                for c in 0..8 {
+                                    n[c] = window.get_value(row + dy[c], col + dx[c]);
+                                    if n[c] != nodata {
+                                        n[c] = n[c] * z_factor;
+                                    } else {
+                                        n[c] = z * z_factor;
+                                    }
                                 }
-                            }
-                            // calculate slope
-                            fy = (n[6] - n[4] + 2.0 * (n[7] - n[3]) + n[0] - n[2]) / eight_grid_res;
-                            fx = (n[2] - n[4] + 2.0 * (n[1] - n[5]) + n[0] - n[6]) / eight_grid_res;
-                            if fx != 0f64 {
-                                data[col as usize] = 180f64 - ((fy / fx).atan()).to_degrees() + 90f64 * (fx / (fx).abs());
+                                // calculate slope
+                                fy = (n[6] - n[4] + 2.0 * (n[7] - n[3]) + n[0] - n[2]) / eight_grid_res;
+                                fx = (n[2] - n[4] + 2.0 * (n[1] - n[5]) + n[0] - n[6]) / eight_grid_res;
+                                if fx != 0f64 {
+                                    data[col as usize] = 180f64 - ((fy / fx).atan()).to_degrees() + 90f64 * (fx / (fx).abs());
                                 
-                            } else {
-                                data[col as usize] = nodata;
+                                } else {
+                                    data[col as usize] = nodata;
+                                }
                             }
                         }
+                        tx.send((row, data)).unwrap();
                     }
-                    tx.send((row, data)).unwrap();
-                }
-            });
-        }
+                });
+            }
 
-        let mut aspect: Array2D<f64> = Array2D::new(rows, columns, nodata, nodata)?;
-        for row in 0..rows {
-            let data = rx.recv().unwrap();
-            aspect.set_row_data(data.0, data.1);
+            for row in start_row..end_row {
+                let data = rx.recv().unwrap();
+                aspect.set_row_data(data.0, data.1);
             
-            if verbose {
                 progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as usize;
                 if progress != old_progress {
-                    println!("Calculating aspect: {}%", progress);
+                    sink.progress("Calculating aspect", progress)?;
                     old_progress = progress;
                 }
             }
+            start_row = end_row;
         }
 
 
@@ -394,11 +340,11 @@ impl WhiteboxTool for FlowAccumulationFullWorkflow {
             for n in 0..8 {
                 row_n = row + dy[n];
                 col_n = col + dx[n];
-                zin_n = input[(row_n, col_n)];
-                zout_n = output[(row_n, col_n)];
+                zin_n = input.get_value(row_n, col_n);
+                zout_n = output.get_value(row_n, col_n);
                 if zout_n == background_val {
                     if zin_n == nodata {
-                        output[(row_n, col_n)] = nodata;
+                        output.set_value(row_n, col_n, nodata);
                         queue.push_back((row_n, col_n));
                     } else {
                         // see if it's the lowest of its neighbours
@@ -406,13 +352,13 @@ impl WhiteboxTool for FlowAccumulationFullWorkflow {
                         for p in 0..8 {
                             y = row_n + dy[p];
                             x = col_n + dx[p];
-                            if input[(y, x)] < zin_n && input[(y, x)] != nodata {
+                            if input.get_value(y, x) < zin_n && input.get_value(y, x) != nodata {
                                 is_lowest = false;
                                 break;
                             }
                         }
                         if is_lowest {
-                            output[(row_n, col_n)] = zin_n;
+                            output.set_value(row_n, col_n, zin_n);
                             // Push it onto the priority queue for the priority flood operation
                             minheap.push(GridCell{ row: row_n, column: col_n, priority: zin_n });
                             // flow_dir[(row_n, col_n)] = 0;
@@ -422,12 +368,10 @@ impl WhiteboxTool for FlowAccumulationFullWorkflow {
                 }
             }
 
-            if verbose {
-                progress = (100.0_f64 * num_solved_cells as f64 / (num_cells - 1) as f64) as usize;
-                if progress != old_progress {
-                    println!("progress: {}%", progress);
-                    old_progress = progress;
-                }
+            progress = (100.0_f64 * num_solved_cells as f64 / (num_cells - 1) as f64) as usize;
+            if progress != old_progress {
+                sink.progress("progress", progress)?;
+                old_progress = progress;
             }
         }
 
@@ -443,13 +387,13 @@ impl WhiteboxTool for FlowAccumulationFullWorkflow {
             let cell = minheap.pop().unwrap();
             row = cell.row;
             col = cell.column;
-            zout = output[(row, col)];
+            zout = output.get_value(row, col);
             for n in 0..8 {
                 row_n = row + dy[n];
                 col_n = col + dx[n];
-                zout_n = output[(row_n, col_n)];
+                zout_n = output.get_value(row_n, col_n);
                 if zout_n == background_val {
-                    zin_n = input[(row_n, col_n)];
+                    zin_n = input.get_value(row_n, col_n);
                     if zin_n != nodata {
                         flow_dir[(row_n, col_n)] = back_link[n];
 
@@ -457,13 +401,13 @@ impl WhiteboxTool for FlowAccumulationFullWorkflow {
                         // output[(row_n, col_n)] = zin_n;
                         // minheap.push(GridCell{ row: row_n, column: col_n, priority: zin_n });
 
-                        output[(row_n, col_n)] = zin_n;
+                        output.set_value(row_n, col_n, zin_n);
                         minheap.push(GridCell{ row: row_n, column: col_n, priority: zin_n });
                         if zin_n < (zout + small_num) {
                             // Trace the flowpath back to a lower cell, if it exists.
                             x = col_n;
                             y = row_n;
-                            z_target = output[(row_n, col_n)];
+                            z_target = output.get_value(row_n, col_n);
                             flag = true;
                             while flag {
                                 dir = flow_dir[(y, x)];
@@ -471,8 +415,8 @@ impl WhiteboxTool for FlowAccumulationFullWorkflow {
                                     y += dy[dir as usize];
                                     x += dx[dir as usize];
                                     z_target -= small_num;
-                                    if output[(y, x)] > z_target {
-                                        output[(y, x)] = z_target;
+                                    if output.get_value(y, x) > z_target {
+                                        output.set_value(y, x, z_target);
                                     } else {
                                         flag = false;
                                     }
@@ -483,7 +427,7 @@ impl WhiteboxTool for FlowAccumulationFullWorkflow {
                         }
                     } else {
                         // Interior nodata cells are still treated as nodata and are not filled.
-                        output[(row_n, col_n)] = nodata;
+                        output.set_value(row_n, col_n, nodata);
                         num_solved_cells += 1;
                     }
                 } else if zout_n > zout && zout_n != nodata && aspect[(row_n, col_n)] != nodata {
@@ -513,13 +457,11 @@ impl WhiteboxTool for FlowAccumulationFullWorkflow {
                 }
             }
 
-            if verbose {
-                num_solved_cells += 1;
-                progress = (100.0_f64 * num_solved_cells as f64 / (num_cells - 1) as f64) as usize;
-                if progress != old_progress {
-                    println!("Progress: {}%", progress);
-                    old_progress = progress;
-                }
+            num_solved_cells += 1;
+            progress = (100.0_f64 * num_solved_cells as f64 / (num_cells - 1) as f64) as usize;
+            if progress != old_progress {
+                sink.progress("Progress", progress)?;
+                old_progress = progress;
             }
         }
 
@@ -529,9 +471,9 @@ impl WhiteboxTool for FlowAccumulationFullWorkflow {
         output.add_metadata_entry(format!("Input file: {}", input_file));
         output.add_metadata_entry(format!("Elapsed Time (including I/O): {}", time::now() - start).replace("PT", ""));
 
-        if verbose { println!("Saving DEM data...") };
+        if verbose { sink.message("Saving DEM data...") };
         let _ = match output.write() {
-            Ok(_) => if verbose { println!("Output file written") },
+            Ok(_) => if verbose { sink.message("Output file written") },
             Err(e) => return Err(e),
         };
 
@@ -554,7 +496,7 @@ impl WhiteboxTool for FlowAccumulationFullWorkflow {
                 for row in (0..rows).filter(|r| r % num_procs == tid) {
                     let mut data: Vec<i8> = vec![-1i8; columns as usize];
                     for col in 0..columns {
-                        z = input[(row, col)];
+                        z = input.get_value(row, col);
                         if z != nodata {
                             count = 0i8;
 							for i in 0..8 {
@@ -587,12 +529,10 @@ impl WhiteboxTool for FlowAccumulationFullWorkflow {
                 }
             }
             
-            if verbose {
-                progress = (100.0_f64 * r as f64 / (rows - 1) as f64) as usize;
-                if progress != old_progress {
-                    println!("Num. inflowing neighbours: {}%", progress);
-                    old_progress = progress;
-                }
+            progress = (100.0_f64 * r as f64 / (rows - 1) as f64) as usize;
+            if progress != old_progress {
+                sink.progress("Num. inflowing neighbours", progress)?;
+                old_progress = progress;
             }
         }
 
@@ -602,7 +542,7 @@ impl WhiteboxTool for FlowAccumulationFullWorkflow {
             let cell = stack.pop().unwrap();
             row = cell.0;
             col = cell.1;
-            fa = output[(row, col)];
+            fa = output.get_value(row, col);
             num_inflowing.decrement(row, col, 1i8);
             dir = flow_dir[(row, col)];
             if dir >= 0 {
@@ -615,13 +555,11 @@ impl WhiteboxTool for FlowAccumulationFullWorkflow {
                 }
             }
 
-            if verbose {
-                num_solved_cells += 1;
-                progress = (100.0_f64 * num_solved_cells as f64 / (num_cells - 1) as f64) as usize;
-                if progress != old_progress {
-                    println!("Flow accumulation: {}%", progress);
-                    old_progress = progress;
-                }
+            num_solved_cells += 1;
+            progress = (100.0_f64 * num_solved_cells as f64 / (num_cells - 1) as f64) as usize;
+            if progress != old_progress {
+                sink.progress("Flow accumulation", progress)?;
+                old_progress = progress;
             }
         }
 
@@ -643,51 +581,47 @@ impl WhiteboxTool for FlowAccumulationFullWorkflow {
         if log_transform {
             for row in 0..rows {
                 for col in 0..columns {
-                    if input[(row, col)] == nodata {
-                        output[(row, col)] = nodata;
+                    if input.get_value(row, col) == nodata {
+                        output.set_value(row, col, nodata);
                     } else {
                         let dir = flow_dir[(row, col)];
                         if dir >= 0 {
-                            output[(row, col)] = (output[(row, col)] * cell_area / flow_widths[dir as usize]).ln();
-                            pntr[(row, col)] = pntr_vals[flow_dir[(row, col)] as usize];
+                            output.set_value(row, col, (output.get_value(row, col) * cell_area / flow_widths[dir as usize]).ln());
+                            pntr.set_value(row, col, pntr_vals[flow_dir[(row, col)] as usize]);
                         } else {
-                            output[(row, col)] = (output[(row, col)] * cell_area / flow_widths[3]).ln();
-                            pntr[(row, col)] = 0f64;
+                            output.set_value(row, col, (output.get_value(row, col) * cell_area / flow_widths[3]).ln());
+                            pntr.set_value(row, col, 0f64);
                         }
                     }
                 }
                 
-                if verbose {
-                    progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as usize;
-                    if progress != old_progress {
-                        println!("Correcting values: {}%", progress);
-                        old_progress = progress;
-                    }
+                progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as usize;
+                if progress != old_progress {
+                    sink.progress("Correcting values", progress)?;
+                    old_progress = progress;
                 }
             }
         } else {
             for row in 0..rows {
                 for col in 0..columns {
-                    if input[(row, col)] == nodata {
-                        output[(row, col)] = nodata;
+                    if input.get_value(row, col) == nodata {
+                        output.set_value(row, col, nodata);
                     } else {
                         let dir = flow_dir[(row, col)];
                         if dir >= 0 {
-                            output[(row, col)] = output[(row, col)] * cell_area / flow_widths[dir as usize];
-                            pntr[(row, col)] = pntr_vals[flow_dir[(row, col)] as usize];
+                            output.set_value(row, col, output.get_value(row, col) * cell_area / flow_widths[dir as usize]);
+                            pntr.set_value(row, col, pntr_vals[flow_dir[(row, col)] as usize]);
                         } else {
-                            output[(row, col)] = output[(row, col)] * cell_area / flow_widths[3];
-                            pntr[(row, col)] = 0f64;
+                            output.set_value(row, col, output.get_value(row, col) * cell_area / flow_widths[3]);
+                            pntr.set_value(row, col, 0f64);
                         }
                     }
                 }
                 
-                if verbose {
-                    progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as usize;
-                    if progress != old_progress {
-                        println!("Correcting values: {}%", progress);
-                        old_progress = progress;
-                    }
+                progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as usize;
+                if progress != old_progress {
+                    sink.progress("Correcting values", progress)?;
+                    old_progress = progress;
                 }
             }
         }
@@ -701,9 +635,9 @@ impl WhiteboxTool for FlowAccumulationFullWorkflow {
         pntr.add_metadata_entry(format!("Input file: {}", input_file));
         pntr.add_metadata_entry(format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
 
-        if verbose { println!("Saving flow pointer data...") };
+        if verbose { sink.message("Saving flow pointer data...") };
         let _ = match pntr.write() {
-            Ok(_) => if verbose { println!("Output file written") },
+            Ok(_) => if verbose { sink.message("Output file written") },
             Err(e) => return Err(e),
         };
 
@@ -715,13 +649,13 @@ impl WhiteboxTool for FlowAccumulationFullWorkflow {
         output.add_metadata_entry(format!("Input file: {}", input_file));
         output.add_metadata_entry(format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
 
-        if verbose { println!("Saving accumulation data...") };
+        if verbose { sink.message("Saving accumulation data...") };
         let _ = match output.write() {
-            Ok(_) => if verbose { println!("Output file written") },
+            Ok(_) => if verbose { sink.message("Output file written") },
             Err(e) => return Err(e),
         };
         if verbose {
-            println!("{}", &format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
+            sink.message(&format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
         }
 
         Ok(())
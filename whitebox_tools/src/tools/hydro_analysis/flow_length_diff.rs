@@ -12,8 +12,10 @@ use std::path;
 use std::f64;
 use raster::*;
 use std::io::{Error, ErrorKind};
+use std::sync::Arc;
 use structures::Array2D;
 use tools::*;
+use tools::arg_parser::ArgValues;
 
 pub struct FlowLengthDiff {
     name: String,
@@ -50,7 +52,7 @@ impl FlowLengthDiff {
 
         parameters.push(ToolParameter{
             name: "Does the pointer file use the ESRI pointer scheme?".to_owned(), 
-            flags: vec!["--esri_pntr".to_owned()], 
+            flags: vec!["--esri_style".to_owned(), "--esri_pntr".to_owned()], 
             description: "D8 pointer uses the ESRI style scheme.".to_owned(),
             parameter_type: ParameterType::Boolean,
             default_value: Some("false".to_owned()),
@@ -104,60 +106,26 @@ impl WhiteboxTool for FlowLengthDiff {
         self.toolbox.clone()
     }
 
-    fn run<'a>(&self, args: Vec<String>, working_directory: &'a str, verbose: bool) -> Result<(), Error> {
-        let mut d8_file = String::new();
-        let mut output_file = String::new();
-        let mut esri_style = false;
-        
+    fn run<'a>(&self, args: Vec<String>, working_directory: &'a str, verbose: bool, sink: &Arc<ProgressSink>) -> Result<(), Error> {
         if args.len() == 0 {
             return Err(Error::new(ErrorKind::InvalidInput,
                                 "Tool run with no paramters."));
         }
-        for i in 0..args.len() {
-            let mut arg = args[i].replace("\"", "");
-            arg = arg.replace("\'", "");
-            let cmd = arg.split("="); // in case an equals sign was used
-            let vec = cmd.collect::<Vec<&str>>();
-            let mut keyval = false;
-            if vec.len() > 1 {
-                keyval = true;
-            }
-            if vec[0].to_lowercase() == "-d8_pntr" || vec[0].to_lowercase() == "--d8_pntr" {
-                if keyval {
-                    d8_file = vec[1].to_string();
-                } else {
-                    d8_file = args[i+1].to_string();
-                }
-            } else if vec[0].to_lowercase() == "-o" || vec[0].to_lowercase() == "--output" {
-                if keyval {
-                    output_file = vec[1].to_string();
-                } else {
-                    output_file = args[i+1].to_string();
-                }
-            } else if vec[0].to_lowercase() == "-esri_pntr" || vec[0].to_lowercase() == "--esri_pntr" || vec[0].to_lowercase() == "--esri_style" {
-                esri_style = true;
-            }
-        }
+        let args = ArgValues::parse(&self.parameters, &args)?;
+        let d8_file = args.required_file("--d8_pntr", working_directory)?;
+        let output_file = args.required_file("--output", working_directory)?;
+        let esri_style = args.get_bool("--esri_pntr")?;
 
         if verbose {
-            println!("***************{}", "*".repeat(self.get_tool_name().len()));
-            println!("* Welcome to {} *", self.get_tool_name());
-            println!("***************{}", "*".repeat(self.get_tool_name().len()));
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
+            sink.message(&format!("* Welcome to {} *", self.get_tool_name()));
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
         }
 
-        let sep: String = path::MAIN_SEPARATOR.to_string();
-
         let mut progress: usize;
         let mut old_progress: usize = 1;
 
-        if !d8_file.contains(&sep) && !d8_file.contains("/") {
-            d8_file = format!("{}{}", working_directory, d8_file);
-        }
-        if !output_file.contains(&sep) && !output_file.contains("/") {
-            output_file = format!("{}{}", working_directory, output_file);
-        }
-        
-        if verbose { println!("Reading pointer data...") };
+        if verbose { sink.message("Reading pointer data...") };
         let pntr = Raster::new(&d8_file, "r")?;
         let rows = pntr.configs.rows as isize;
         let columns = pntr.configs.columns as isize;
@@ -206,14 +174,14 @@ impl WhiteboxTool for FlowLengthDiff {
         let (mut x, mut y): (isize, isize);
         for row in 0..rows {
             for col in 0..columns {
-                if pntr[(row, col)] >= 0.0 && pntr[(row, col)] != nodata {
+                if pntr.get_value(row, col) >= 0.0 && pntr.get_value(row, col) != nodata {
                     dist = 0f64;
                     flag = false;
                     x = col;
                     y = row;
                     while !flag {
                         // find its downslope neighbour
-                        dir = pntr[(y, x)];
+                        dir = pntr.get_value(y, x);
                         if dir > 0f64 && dir != nodata {
                             if dir > 128f64 || pntr_matches[dir as usize] == 999 {
                                 return Err(Error::new(ErrorKind::InvalidInput,
@@ -242,7 +210,7 @@ impl WhiteboxTool for FlowLengthDiff {
                         dfl[(y, x)] = dist;
 
                         // find its downslope neighbour
-                        dir = pntr[(y, x)];
+                        dir = pntr.get_value(y, x);
                         if dir > 0f64 && dir != nodata {
                             // move x and y accordingly
                             c = pntr_matches[dir as usize];
@@ -263,12 +231,10 @@ impl WhiteboxTool for FlowLengthDiff {
                     dfl[(row, col)] = out_nodata;
                 }
             }
-            if verbose {
-                progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as usize;
-                if progress != old_progress {
-                    println!("Progress: {}%", progress);
-                    old_progress = progress;
-                }
+            progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as usize;
+            if progress != old_progress {
+                sink.progress("Progress", progress)?;
+                old_progress = progress;
             }
         }
 
@@ -294,20 +260,18 @@ impl WhiteboxTool for FlowLengthDiff {
                         }
                     }
                     if max_abs_diff != f64::NEG_INFINITY {
-                        output[(row, col)] = max_abs_diff;
+                        output.set_value(row, col, max_abs_diff);
                     } else {
-                        output[(row, col)] = out_nodata;
+                        output.set_value(row, col, out_nodata);
                     }
                 } else {
-                    output[(row, col)] = out_nodata;
+                    output.set_value(row, col, out_nodata);
                 }
             }
-            if verbose {
-                progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as usize;
-                if progress != old_progress {
-                    println!("Progress: {}%", progress);
-                    old_progress = progress;
-                }
+            progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as usize;
+            if progress != old_progress {
+                sink.progress("Progress", progress)?;
+                old_progress = progress;
             }
         }
 
@@ -321,13 +285,13 @@ impl WhiteboxTool for FlowLengthDiff {
         output.add_metadata_entry(format!("Input D8 pointer file: {}", d8_file));
         output.add_metadata_entry(format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
 
-        if verbose { println!("Saving data...") };
+        if verbose { sink.message("Saving data...") };
         let _ = match output.write() {
-            Ok(_) => if verbose { println!("Output file written") },
+            Ok(_) => if verbose { sink.message("Output file written") },
             Err(e) => return Err(e),
         };
         if verbose {
-            println!("{}", &format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
+            sink.message(&format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
         }
 
         Ok(())
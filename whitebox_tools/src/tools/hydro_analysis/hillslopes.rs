@@ -12,8 +12,10 @@ use std::path;
 use std::f64;
 use raster::*;
 use std::io::{Error, ErrorKind};
+use std::sync::Arc;
 use structures::Array2D;
 use tools::*;
+use tools::arg_parser::ArgValues;
 
 pub struct Hillslopes {
     name: String,
@@ -59,7 +61,7 @@ impl Hillslopes {
 
         parameters.push(ToolParameter{
             name: "Does the pointer file use the ESRI pointer scheme?".to_owned(), 
-            flags: vec!["--esri_pntr".to_owned()], 
+            flags: vec!["--esri_style".to_owned(), "--esri_pntr".to_owned()], 
             description: "D8 pointer uses the ESRI style scheme.".to_owned(),
             parameter_type: ParameterType::Boolean,
             default_value: Some("false".to_owned()),
@@ -113,70 +115,27 @@ impl WhiteboxTool for Hillslopes {
         self.toolbox.clone()
     }
 
-    fn run<'a>(&self, args: Vec<String>, working_directory: &'a str, verbose: bool) -> Result<(), Error> {
-        let mut d8_file = String::new();
-        let mut streams_file = String::new();
-        let mut output_file = String::new();
-        let mut esri_style = false;
-        
+    fn run<'a>(&self, args: Vec<String>, working_directory: &'a str, verbose: bool, sink: &Arc<ProgressSink>) -> Result<(), Error> {
         if args.len() == 0 {
             return Err(Error::new(ErrorKind::InvalidInput,
                                 "Tool run with no paramters."));
         }
-        for i in 0..args.len() {
-            let mut arg = args[i].replace("\"", "");
-            arg = arg.replace("\'", "");
-            let cmd = arg.split("="); // in case an equals sign was used
-            let vec = cmd.collect::<Vec<&str>>();
-            let mut keyval = false;
-            if vec.len() > 1 {
-                keyval = true;
-            }
-            if vec[0].to_lowercase() == "-d8_pntr" || vec[0].to_lowercase() == "--d8_pntr" {
-                if keyval {
-                    d8_file = vec[1].to_string();
-                } else {
-                    d8_file = args[i+1].to_string();
-                }
-            } else if vec[0].to_lowercase() == "-streams" || vec[0].to_lowercase() == "--streams" {
-                if keyval {
-                    streams_file = vec[1].to_string();
-                } else {
-                    streams_file = args[i+1].to_string();
-                }
-            } else if vec[0].to_lowercase() == "-o" || vec[0].to_lowercase() == "--output" {
-                if keyval {
-                    output_file = vec[1].to_string();
-                } else {
-                    output_file = args[i+1].to_string();
-                }
-            } else if vec[0].to_lowercase() == "-esri_pntr" || vec[0].to_lowercase() == "--esri_pntr" || vec[0].to_lowercase() == "--esri_style" {
-                esri_style = true;
-            }
-        }
+        let args = ArgValues::parse(&self.parameters, &args)?;
+        let d8_file = args.required_file("--d8_pntr", working_directory)?;
+        let streams_file = args.required_file("--streams", working_directory)?;
+        let output_file = args.required_file("--output", working_directory)?;
+        let esri_style = args.get_bool("--esri_pntr")?;
 
         if verbose {
-            println!("***************{}", "*".repeat(self.get_tool_name().len()));
-            println!("* Welcome to {} *", self.get_tool_name());
-            println!("***************{}", "*".repeat(self.get_tool_name().len()));
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
+            sink.message(&format!("* Welcome to {} *", self.get_tool_name()));
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
         }
 
-        let sep: String = path::MAIN_SEPARATOR.to_string();
-
         let mut progress: usize;
         let mut old_progress: usize = 1;
 
-        if !d8_file.contains(&sep) && !d8_file.contains("/") {
-            d8_file = format!("{}{}", working_directory, d8_file);
-        }
-        if !streams_file.contains(&sep) && !streams_file.contains("/") {
-            streams_file = format!("{}{}", working_directory, streams_file);
-        }
-        if !output_file.contains(&sep) && !output_file.contains("/") {
-            output_file = format!("{}{}", working_directory, output_file);
-        }
-
-        if verbose { println!("Reading data...") };
+        if verbose { sink.message("Reading data...") };
 
         let pntr = Raster::new(&d8_file, "r")?;
         let streams = Raster::new(&streams_file, "r")?;
@@ -213,11 +172,11 @@ impl WhiteboxTool for Hillslopes {
         let mut current_id = 1f64;
         for row in 0..rows {
             for col in 0..columns {
-                if streams[(row, col)] > 0.0 && streams[(row, col)] != nodata {
+                if streams.get_value(row, col) > 0.0 && streams.get_value(row, col) != nodata {
                     count = 0i8;
                     for i in 0..8 {
-                        if streams[(row + dy[i], col + dx[i])] > 0.0 &&
-                            pntr[(row + dy[i], col + dx[i])] == inflowing_vals[i] {
+                        if streams.get_value(row + dy[i], col + dx[i]) > 0.0 &&
+                            pntr.get_value(row + dy[i], col + dx[i]) == inflowing_vals[i] {
                             count += 1;
                         }
                     }
@@ -230,7 +189,7 @@ impl WhiteboxTool for Hillslopes {
                         current_id += 1f64;
                     }
                 } else {
-                    if pntr[(row, col)] != pntr_nodata {
+                    if pntr.get_value(row, col) != pntr_nodata {
                         pourpts[(row, col)] = 0.0;
                     } else {
                         pourpts[(row, col)] = nodata;
@@ -238,12 +197,10 @@ impl WhiteboxTool for Hillslopes {
                     num_solved_cells += 1;
                 }
             }
-            if verbose {
-                progress = (100.0_f64 * num_solved_cells as f64 / (num_cells - 1) as f64) as usize;
-                if progress != old_progress {
-                    println!("Processing streams: {}%", progress);
-                    old_progress = progress;
-                }
+            progress = (100.0_f64 * num_solved_cells as f64 / (num_cells - 1) as f64) as usize;
+            if progress != old_progress {
+                sink.progress("Processing streams", progress)?;
+                old_progress = progress;
             }
         }
 
@@ -289,7 +246,7 @@ impl WhiteboxTool for Hillslopes {
             val = pourpts[(row, col)];
 
             // find the downstream cell
-            dir = pntr[(row, col)] as usize;
+            dir = pntr.get_value(row, col) as usize;
             if dir > 0 {
                 if dir > 128 || pntr_matches[dir] == 999 {
                     return Err(Error::new(ErrorKind::InvalidInput,
@@ -311,12 +268,10 @@ impl WhiteboxTool for Hillslopes {
                 }
             }
 
-            if verbose {
-                progress = (100.0_f64 * num_solved_cells as f64 / (num_cells - 1) as f64) as usize;
-                if progress != old_progress {
-                    println!("Processing streams: {}%", progress);
-                    old_progress = progress;
-                }
+            progress = (100.0_f64 * num_solved_cells as f64 / (num_cells - 1) as f64) as usize;
+            if progress != old_progress {
+                sink.progress("Processing streams", progress)?;
+                old_progress = progress;
             }
         }
 
@@ -340,20 +295,18 @@ impl WhiteboxTool for Hillslopes {
         let mut z: f64;
         for row in 0..rows {
             for col in 0..columns {
-                if pntr[(row, col)] == pntr_nodata {
-                    output[(row, col)] = nodata;
+                if pntr.get_value(row, col) == pntr_nodata {
+                    output.set_value(row, col, nodata);
                 }
                 z = pourpts[(row, col)];
                 if z != nodata && z > 0.0 {
-                    output[(row, col)] = z;
+                    output.set_value(row, col, z);
                 }
             }
-            if verbose {
-                progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as usize;
-                if progress != old_progress {
-                    println!("Watershedding (Loop 1 of 2): {}%", progress);
-                    old_progress = progress;
-                }
+            progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as usize;
+            if progress != old_progress {
+                sink.progress("Watershedding (Loop 1 of 2)", progress)?;
+                old_progress = progress;
             }
         }
 
@@ -362,20 +315,20 @@ impl WhiteboxTool for Hillslopes {
         let mut outlet_id: f64;
         for row in 0..rows {
             for col in 0..columns {
-                if output[(row, col)] == low_value {
+                if output.get_value(row, col) == low_value {
                     flag = false;
                     x = col;
                     y = row;
                     outlet_id = nodata;
                     while !flag {
-                        dir = pntr[(y, x)] as usize;
+                        dir = pntr.get_value(y, x) as usize;
                         if dir > 0 {
                             c = pntr_matches[dir];
                             y += dy[c];
                             x += dx[c];
 
                             // if the new cell already has a value in the output, use that as the outletID
-                            z = output[(y, x)];
+                            z = output.get_value(y, x);
                             if z != low_value {
                                 outlet_id = z;
                                 flag = true;
@@ -388,48 +341,44 @@ impl WhiteboxTool for Hillslopes {
                     flag = false;
                     x = col;
                     y = row;
-                    output[(y, x)] = outlet_id;
+                    output.set_value(y, x, outlet_id);
                     while !flag {
                         // find its downslope neighbour
-                        dir = pntr[(y, x)] as usize;
+                        dir = pntr.get_value(y, x) as usize;
                         if dir > 0 {
                             c = pntr_matches[dir];
                             y += dy[c];
                             x += dx[c];
 
                             // if the new cell already has a value in the output, use that as the outletID
-                            if output[(y, x)] != low_value {
+                            if output.get_value(y, x) != low_value {
                                 flag = true;
                             }
                         } else {
                             flag = true;
                         }
-                        output[(y, x)] = outlet_id;
+                        output.set_value(y, x, outlet_id);
                     }
                 }
             }
-            if verbose {
-                progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as usize;
-                if progress != old_progress {
-                    println!("Watershedding (Loop 2 of 2): {}%", progress);
-                    old_progress = progress;
-                }
+            progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as usize;
+            if progress != old_progress {
+                sink.progress("Watershedding (Loop 2 of 2)", progress)?;
+                old_progress = progress;
             }
         }
 
         // Replace all stream cells with 0's
         for row in 0..rows {
             for col in 0..columns {
-                if streams[(row, col)] > 0f64 && streams[(row, col)] != nodata {
-                    output[(row, col)] = 0f64;
+                if streams.get_value(row, col) > 0f64 && streams.get_value(row, col) != nodata {
+                    output.set_value(row, col, 0f64);
                 }
             }
-            if verbose {
-                progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as usize;
-                if progress != old_progress {
-                    println!("Watershedding (Loop 1 of 2): {}%", progress);
-                    old_progress = progress;
-                }
+            progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as usize;
+            if progress != old_progress {
+                sink.progress("Watershedding (Loop 1 of 2)", progress)?;
+                old_progress = progress;
             }
         }
 
@@ -450,28 +399,28 @@ impl WhiteboxTool for Hillslopes {
         let mut old_id: f64;
         for row in 0..rows {
             for col in 0..columns {
-                if visited[(row, col)] > 0 && pntr[(row, col)] != pntr_nodata && output[(row, col)] > 0f64 {
+                if visited[(row, col)] > 0 && pntr.get_value(row, col) != pntr_nodata && output.get_value(row, col) > 0f64 {
                     current_id += 1f64;
-                    old_id = output[(row, col)];
+                    old_id = output.get_value(row, col);
                     stack.push((row, col));
                     while !stack.is_empty() {
                         let cell = stack.pop().unwrap();
                         row2 = cell.0;
                         col2 = cell.1;
-                        output[(row2, col2)] = current_id;
+                        output.set_value(row2, col2, current_id);
                         visited[(row2, col2)] = 0;
                         
                         for n in 0..8 {
                             y = row2 + dy[n];
                             x = col2 + dx[n];
-                            if output[(y, x)] == old_id && visited[(y, x)] > 0 {
+                            if output.get_value(y, x) == old_id && visited[(y, x)] > 0 {
                                 let diag = card1[n];
                                 if diag == 8 { // its a cardinal direction
                                     stack.push((y, x));
                                 } else {
                                     // clumping can't cross a stream via a diagonal
-                                    if streams[(row2 + dy[card2[diag]], col2 + dx[card2[diag]])] == 0f64 ||
-                                        streams[(row2 + dy[card3[diag]], col2 + dx[card3[diag]])] == 0f64 {
+                                    if streams.get_value(row2 + dy[card2[diag]], col2 + dx[card2[diag]]) == 0f64 ||
+                                        streams.get_value(row2 + dy[card3[diag]], col2 + dx[card3[diag]]) == 0f64 {
                                         stack.push((y, x));
                                     }
                                 }
@@ -480,12 +429,10 @@ impl WhiteboxTool for Hillslopes {
                     }
                 }
             }
-            if verbose {
-                progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as usize;
-                if progress != old_progress {
-                    println!("Grouping hillslopes: {}%", progress);
-                    old_progress = progress;
-                }
+            progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as usize;
+            if progress != old_progress {
+                sink.progress("Grouping hillslopes", progress)?;
+                old_progress = progress;
             }
         }
         
@@ -496,13 +443,13 @@ impl WhiteboxTool for Hillslopes {
         output.add_metadata_entry(format!("Streams file: {}", streams_file));
         output.add_metadata_entry(format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
 
-        if verbose { println!("Saving data...") };
+        if verbose { sink.message("Saving data...") };
         let _ = match output.write() {
-            Ok(_) => if verbose { println!("Output file written") },
+            Ok(_) => if verbose { sink.message("Output file written") },
             Err(e) => return Err(e),
         };
         if verbose {
-            println!("{}", &format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
+            sink.message(&format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
         }
         
         Ok(())
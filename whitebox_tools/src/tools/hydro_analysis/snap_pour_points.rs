@@ -14,7 +14,9 @@ use std::path;
 use std::f64;
 use raster::*;
 use std::io::{Error, ErrorKind};
+use std::sync::Arc;
 use tools::*;
+use tools::arg_parser::ArgValues;
 
 pub struct SnapPourPoints {
     name: String,
@@ -114,74 +116,27 @@ impl WhiteboxTool for SnapPourPoints {
         self.toolbox.clone()
     }
 
-    fn run<'a>(&self, args: Vec<String>, working_directory: &'a str, verbose: bool) -> Result<(), Error> {
-        let mut pourpts_file = String::new();
-        let mut flow_accum_file = String::new();
-        let mut output_file = String::new();
-        let mut snap_dist = 0.0;
-        
+    fn run<'a>(&self, args: Vec<String>, working_directory: &'a str, verbose: bool, sink: &Arc<ProgressSink>) -> Result<(), Error> {
         if args.len() == 0 {
             return Err(Error::new(ErrorKind::InvalidInput,
                                 "Tool run with no paramters."));
         }
-        for i in 0..args.len() {
-            let mut arg = args[i].replace("\"", "");
-            arg = arg.replace("\'", "");
-            let cmd = arg.split("="); // in case an equals sign was used
-            let vec = cmd.collect::<Vec<&str>>();
-            let mut keyval = false;
-            if vec.len() > 1 {
-                keyval = true;
-            }
-            if vec[0].to_lowercase() == "-pour_pts" || vec[0].to_lowercase() == "--pour_pts" {
-                if keyval {
-                    pourpts_file = vec[1].to_string();
-                } else {
-                    pourpts_file = args[i+1].to_string();
-                }
-            } else if vec[0].to_lowercase() == "-flow_accum" || vec[0].to_lowercase() == "--flow_accum" {
-                if keyval {
-                    flow_accum_file = vec[1].to_string();
-                } else {
-                    flow_accum_file = args[i+1].to_string();
-                }
-            } else if vec[0].to_lowercase() == "-o" || vec[0].to_lowercase() == "--output" {
-                if keyval {
-                    output_file = vec[1].to_string();
-                } else {
-                    output_file = args[i+1].to_string();
-                }
-            } else if vec[0].to_lowercase() == "-snap_dist" || vec[0].to_lowercase() == "--snap_dist" {
-                if keyval {
-                    snap_dist = vec[1].to_string().parse::<f64>().unwrap();
-                } else {
-                    snap_dist = args[i+1].to_string().parse::<f64>().unwrap();
-                }
-            }
-        }
+        let args = ArgValues::parse(&self.parameters, &args)?;
+        let pourpts_file = args.required_file("--pour_pts", working_directory)?;
+        let flow_accum_file = args.required_file("--flow_accum", working_directory)?;
+        let output_file = args.required_file("--output", working_directory)?;
+        let snap_dist = args.get_f64("--snap_dist")?.unwrap_or(0.0);
 
         if verbose {
-            println!("***************{}", "*".repeat(self.get_tool_name().len()));
-            println!("* Welcome to {} *", self.get_tool_name());
-            println!("***************{}", "*".repeat(self.get_tool_name().len()));
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
+            sink.message(&format!("* Welcome to {} *", self.get_tool_name()));
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
         }
 
-        let sep: String = path::MAIN_SEPARATOR.to_string();
-
         let mut progress: usize;
         let mut old_progress: usize = 1;
 
-        if !pourpts_file.contains(&sep) && !pourpts_file.contains("/") {
-            pourpts_file = format!("{}{}", working_directory, pourpts_file);
-        }
-        if !flow_accum_file.contains(&sep) && !flow_accum_file.contains("/") {
-            flow_accum_file = format!("{}{}", working_directory, flow_accum_file);
-        }
-        if !output_file.contains(&sep) && !output_file.contains("/") {
-            output_file = format!("{}{}", working_directory, output_file);
-        }
-
-        if verbose { println!("Reading data...") };
+        if verbose { sink.message("Reading data...") };
 
         let pourpts = Raster::new(&pourpts_file, "r")?;
         let flow_accum = Raster::new(&flow_accum_file, "r")?;
@@ -210,14 +165,14 @@ impl WhiteboxTool for SnapPourPoints {
         let mut yn: isize;
         for row in 0..rows {
             for col in 0..columns {
-                outlet_id = pourpts[(row, col)];
+                outlet_id = pourpts.get_value(row, col);
                 if outlet_id > 0.0 && outlet_id != nodata {
                     max_accum = 0.0;
                     xn = col;
                     yn = row;
                     for x in (col - snap_dist_int)..(col + snap_dist_int + 1) {
                         for y in (row - snap_dist_int)..(row + snap_dist_int + 1) {
-                            zn = flow_accum[(y, x)];
+                            zn = flow_accum.get_value(y, x);
                             if zn > max_accum && zn != fa_nodata {
                                 max_accum = zn;
                                 xn = x;
@@ -225,15 +180,13 @@ impl WhiteboxTool for SnapPourPoints {
                             }
                         }
                     }
-                    output[(yn, xn)] = outlet_id;
+                    output.set_value(yn, xn, outlet_id);
                 }
             }
-            if verbose {
-                progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as usize;
-                if progress != old_progress {
-                    println!("Initializing: {}%", progress);
-                    old_progress = progress;
-                }
+            progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as usize;
+            if progress != old_progress {
+                sink.progress("Initializing", progress)?;
+                old_progress = progress;
             }
         }
         
@@ -245,13 +198,13 @@ impl WhiteboxTool for SnapPourPoints {
         output.add_metadata_entry(format!("Snap distance: {}", snap_dist));
         output.add_metadata_entry(format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
 
-        if verbose { println!("Saving data...") };
+        if verbose { sink.message("Saving data...") };
         let _ = match output.write() {
-            Ok(_) => if verbose { println!("Output file written") },
+            Ok(_) => if verbose { sink.message("Output file written") },
             Err(e) => return Err(e),
         };
         if verbose {
-            println!("{}", &format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
+            sink.message(&format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
         }
         
         Ok(())
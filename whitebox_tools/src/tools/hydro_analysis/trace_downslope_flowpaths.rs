@@ -14,7 +14,9 @@ use std::path;
 use std::f64;
 use raster::*;
 use std::io::{Error, ErrorKind};
+use std::sync::Arc;
 use tools::*;
+use tools::arg_parser::ArgValues;
 
 pub struct TraceDownslopeFlowpaths {
     name: String,
@@ -42,7 +44,7 @@ impl TraceDownslopeFlowpaths {
 
         parameters.push(ToolParameter{
             name: "Input D8 Pointer File".to_owned(), 
-            flags: vec!["--d8_pntr".to_owned()], 
+            flags: vec!["--flow_dir".to_owned(), "--d8_pntr".to_owned()], 
             description: "Input D8 pointer raster file.".to_owned(),
             parameter_type: ParameterType::ExistingFile(ParameterFileType::Raster),
             default_value: None,
@@ -60,7 +62,7 @@ impl TraceDownslopeFlowpaths {
 
         parameters.push(ToolParameter{
             name: "Does the pointer file use the ESRI pointer scheme?".to_owned(), 
-            flags: vec!["--esri_pntr".to_owned()], 
+            flags: vec!["--esri_style".to_owned(), "--esri_pntr".to_owned()], 
             description: "D8 pointer uses the ESRI style scheme.".to_owned(),
             parameter_type: ParameterType::Boolean,
             default_value: Some("false".to_owned()),
@@ -123,76 +125,31 @@ impl WhiteboxTool for TraceDownslopeFlowpaths {
         self.toolbox.clone()
     }
 
-    fn run<'a>(&self, args: Vec<String>, working_directory: &'a str, verbose: bool) -> Result<(), Error> {
-        let mut seed_file = String::new();
-        let mut flowdir_file = String::new();
-        let mut output_file = String::new();
-        let mut esri_style = false;
-        let mut background_val = f64::NEG_INFINITY;
-        
+    fn run<'a>(&self, args: Vec<String>, working_directory: &'a str, verbose: bool, sink: &Arc<ProgressSink>) -> Result<(), Error> {
         if args.len() == 0 {
             return Err(Error::new(ErrorKind::InvalidInput,
                                 "Tool run with no paramters."));
         }
-        for i in 0..args.len() {
-            let mut arg = args[i].replace("\"", "");
-            arg = arg.replace("\'", "");
-            let cmd = arg.split("="); // in case an equals sign was used
-            let vec = cmd.collect::<Vec<&str>>();
-            let mut keyval = false;
-            if vec.len() > 1 {
-                keyval = true;
-            }
-            if vec[0].to_lowercase() == "-seed_pts" || vec[0].to_lowercase() == "--seed_pts" {
-                if keyval {
-                    seed_file = vec[1].to_string();
-                } else {
-                    seed_file = args[i+1].to_string();
-                }
-            } else if vec[0].to_lowercase() == "--d8_pntr" || vec[0].to_lowercase() == "-flow_dir" || vec[0].to_lowercase() == "--flow_dir" {
-                if keyval {
-                    flowdir_file = vec[1].to_string();
-                } else {
-                    flowdir_file = args[i+1].to_string();
-                }
-            } else if vec[0].to_lowercase() == "-o" || vec[0].to_lowercase() == "--output" {
-                if keyval {
-                    output_file = vec[1].to_string();
-                } else {
-                    output_file = args[i+1].to_string();
-                }
-            } else if vec[0].to_lowercase() == "-esri_pntr" || vec[0].to_lowercase() == "--esri_pntr" || vec[0].to_lowercase() == "--esri_style" {
-                esri_style = true;
-            } else if vec[0].to_lowercase() == "-zero_background" || vec[0].to_lowercase() == "--zero_background" {
-                background_val = 0f64;
-            }
-        }
+        let args = ArgValues::parse(&self.parameters, &args)?;
+        let seed_file = args.required_file("--seed_pts", working_directory)?;
+        let flowdir_file = args.required_file("--d8_pntr", working_directory)?;
+        let output_file = args.required_file("--output", working_directory)?;
+        let esri_style = args.get_bool("--esri_pntr")?;
+        let mut background_val = if args.get_bool("--zero_background")? { 0f64 } else { f64::NEG_INFINITY };
 
         if verbose {
-            println!("***************{}", "*".repeat(self.get_tool_name().len()));
-            println!("* Welcome to {} *", self.get_tool_name());
-            println!("***************{}", "*".repeat(self.get_tool_name().len()));
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
+            sink.message(&format!("* Welcome to {} *", self.get_tool_name()));
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
         }
 
-        let sep: String = path::MAIN_SEPARATOR.to_string();
-
         let mut progress: usize;
         let mut old_progress: usize = 1;
 
-        if !seed_file.contains(&sep) && !seed_file.contains("/") {
-            seed_file = format!("{}{}", working_directory, seed_file);
-        }
-        if !flowdir_file.contains(&sep) && !flowdir_file.contains("/") {
-            flowdir_file = format!("{}{}", working_directory, flowdir_file);
-        }
-        if !output_file.contains(&sep) && !output_file.contains("/") {
-            output_file = format!("{}{}", working_directory, output_file);
-        }
-        
-        if verbose { println!("Reading destination data...") };
+        if verbose { sink.message("Reading destination data...") };
         let seeds = Raster::new(&seed_file, "r")?;
 
-        if verbose { println!("Reading backlink data...") };
+        if verbose { sink.message("Reading backlink data...") };
         let flowdir = Raster::new(&flowdir_file, "r")?;
 
         // make sure the input files have the same size
@@ -243,18 +200,18 @@ impl WhiteboxTool for TraceDownslopeFlowpaths {
         let mut dir: f64;
         for row in 0..rows {
             for col in 0..columns {
-                if seeds[(row, col)] > 0.0 && flowdir[(row, col)] != nodata {
+                if seeds.get_value(row, col) > 0.0 && flowdir.get_value(row, col) != nodata {
                     flag = false;
                     x = col;
                     y = row;
                     while !flag {
-                        if output[(y, x)] == background_val {
-                            output[(y, x)] = 1.0;
+                        if output.get_value(y, x) == background_val {
+                            output.set_value(y, x, 1.0);
                         } else {
                             output.increment(y, x, 1.0);
                         }
                         // find its downslope neighbour
-                        dir = flowdir[(y, x)];
+                        dir = flowdir.get_value(y, x);
                         if dir != nodata && dir > 0.0 {
                             // move x and y accordingly
                             x += dx[pntr_matches[dir as usize]];
@@ -263,16 +220,14 @@ impl WhiteboxTool for TraceDownslopeFlowpaths {
                             flag = true;
                         }
                     }
-                } else if flowdir[(row, col)] == nodata {
-                    output[(row, col)] = nodata;
+                } else if flowdir.get_value(row, col) == nodata {
+                    output.set_value(row, col, nodata);
                 }
             }
-            if verbose {
-                progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as usize;
-                if progress != old_progress {
-                    println!("Progress: {}%", progress);
-                    old_progress = progress;
-                }
+            progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as usize;
+            if progress != old_progress {
+                sink.progress("Progress", progress)?;
+                old_progress = progress;
             }
         }
 
@@ -287,13 +242,13 @@ impl WhiteboxTool for TraceDownslopeFlowpaths {
         output.add_metadata_entry(format!("D8 flow direction (pointer) raster: {}", flowdir_file));
         output.add_metadata_entry(format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
 
-        if verbose { println!("Saving data...") };
+        if verbose { sink.message("Saving data...") };
         let _ = match output.write() {
-            Ok(_) => if verbose { println!("Output file written") },
+            Ok(_) => if verbose { sink.message("Output file written") },
             Err(e) => return Err(e),
         };
         if verbose {
-            println!("{}", &format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
+            sink.message(&format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
         }
 
         Ok(())
@@ -19,6 +19,7 @@ use raster::*;
 use structures::Array2D;
 use std::io::{Error, ErrorKind};
 use tools::*;
+use tools::arg_parser::ArgValues;
 
 pub struct AdaptiveFilter {
     name: String,
@@ -55,7 +56,7 @@ impl AdaptiveFilter {
 
         parameters.push(ToolParameter{
             name: "Filter X-Dimension".to_owned(), 
-            flags: vec!["--filterx".to_owned()], 
+            flags: vec!["--filter".to_owned(), "--filterx".to_owned()], 
             description: "Size of the filter kernel in the x-direction.".to_owned(),
             parameter_type: ParameterType::Integer,
             default_value: Some("11".to_owned()),
@@ -64,7 +65,7 @@ impl AdaptiveFilter {
 
         parameters.push(ToolParameter{
             name: "Filter Y-Dimension".to_owned(), 
-            flags: vec!["--filtery".to_owned()], 
+            flags: vec!["--filter".to_owned(), "--filtery".to_owned()], 
             description: "Size of the filter kernel in the y-direction.".to_owned(),
             parameter_type: ParameterType::Integer,
             default_value: Some("11".to_owned()),
@@ -87,7 +88,7 @@ impl AdaptiveFilter {
         if e.contains(".exe") {
             short_exe += ".exe";
         }
-        let usage = format!(">>.*{} -r={} -v --wd=\"*path*to*data*\" -i=DEM.dep -o=output.dep --filter=25 --threshold = 2.0", short_exe, name).replace("*", &sep);
+        let usage = format!(">>.*{} -r={} -v --wd=\"*path*to*data*\" -i=DEM.dep -o=output.dep --filter=25 --threshold=2.0", short_exe, name).replace("*", &sep);
     
         AdaptiveFilter { 
             name: name, 
@@ -127,73 +128,24 @@ impl WhiteboxTool for AdaptiveFilter {
         self.toolbox.clone()
     }
 
-    fn run<'a>(&self, args: Vec<String>, working_directory: &'a str, verbose: bool) -> Result<(), Error> {
-        let mut input_file = String::new();
-        let mut output_file = String::new();
-        let mut filter_size_x = 11usize;
-        let mut filter_size_y = 11usize;
-        let mut threshold = 2.0f64;
+    fn run<'a>(&self, args: Vec<String>, working_directory: &'a str, verbose: bool, sink: &Arc<ProgressSink>) -> Result<(), Error> {
         if args.len() == 0 {
             return Err(Error::new(ErrorKind::InvalidInput,
                                 "Tool run with no paramters."));
         }
-        for i in 0..args.len() {
-            let mut arg = args[i].replace("\"", "");
-            arg = arg.replace("\'", "");
-            let cmd = arg.split("="); // in case an equals sign was used
-            let vec = cmd.collect::<Vec<&str>>();
-            let mut keyval = false;
-            if vec.len() > 1 {
-                keyval = true;
-            }
-            if vec[0].to_lowercase() == "-i" || vec[0].to_lowercase() == "--input" {
-                if keyval {
-                    input_file = vec[1].to_string();
-                } else {
-                    input_file = args[i + 1].to_string();
-                }
-            } else if vec[0].to_lowercase() == "-o" || vec[0].to_lowercase() == "--output" {
-                if keyval {
-                    output_file = vec[1].to_string();
-                } else {
-                    output_file = args[i + 1].to_string();
-                }
-            } else if vec[0].to_lowercase() == "-filter" || vec[0].to_lowercase() == "--filter" {
-                if keyval {
-                    filter_size_x = vec[1].to_string().parse::<usize>().unwrap();
-                } else {
-                    filter_size_x = args[i + 1].to_string().parse::<usize>().unwrap();
-                }
-                filter_size_y = filter_size_x;
-            } else if vec[0].to_lowercase() == "-filterx" || vec[0].to_lowercase() == "--filterx" {
-                if keyval {
-                    filter_size_x = vec[1].to_string().parse::<usize>().unwrap();
-                } else {
-                    filter_size_x = args[i + 1].to_string().parse::<usize>().unwrap();
-                }
-            } else if vec[0].to_lowercase() == "-filtery" || vec[0].to_lowercase() == "--filtery" {
-                if keyval {
-                    filter_size_y = vec[1].to_string().parse::<usize>().unwrap();
-                } else {
-                    filter_size_y = args[i + 1].to_string().parse::<usize>().unwrap();
-                }
-            } else if vec[0].to_lowercase() == "-threshold" || vec[0].to_lowercase() == "--threshold" {
-                if keyval {
-                    threshold = vec[1].to_string().parse::<f64>().unwrap();
-                } else {
-                    threshold = args[i + 1].to_string().parse::<f64>().unwrap();
-                }
-            }
-        }
+        let args = ArgValues::parse(&self.parameters, &args)?;
+        let input_file = args.required_file("--input", working_directory)?;
+        let output_file = args.required_file("--output", working_directory)?;
+        let mut filter_size_x = args.get_usize("--filterx")?.unwrap_or(11usize);
+        let mut filter_size_y = args.get_usize("--filtery")?.unwrap_or(11usize);
+        let threshold = args.get_f64("--threshold")?.unwrap_or(2.0f64);
 
         if verbose {
-            println!("***************{}", "*".repeat(self.get_tool_name().len()));
-            println!("* Welcome to {} *", self.get_tool_name());
-            println!("***************{}", "*".repeat(self.get_tool_name().len()));
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
+            sink.message(&format!("* Welcome to {} *", self.get_tool_name()));
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
         }
 
-        let sep: String = path::MAIN_SEPARATOR.to_string();
-
         if filter_size_x < 3 {
             filter_size_x = 3;
         }
@@ -214,15 +166,8 @@ impl WhiteboxTool for AdaptiveFilter {
         let mut progress: usize;
         let mut old_progress: usize = 1;
 
-        if !input_file.contains(&sep) && !input_file.contains("/") {
-            input_file = format!("{}{}", working_directory, input_file);
-        }
-        if !output_file.contains(&sep) && !output_file.contains("/") {
-            output_file = format!("{}{}", working_directory, output_file);
-        }
-
         if verbose {
-            println!("Reading data...")
+            sink.message("Reading data...")
         };
 
         let input = Arc::new(Raster::new(&input_file, "r")?);
@@ -250,7 +195,7 @@ impl WhiteboxTool for AdaptiveFilter {
             sum_sqr = 0f64;
             sum_n = 0;
             for col in 0..columns {
-                val = input[(row, col)];
+                val = input.get_value(row, col);
                 if val == nodata {
                     val = 0f64;
                 } else {
@@ -272,12 +217,10 @@ impl WhiteboxTool for AdaptiveFilter {
                     integral_n[(row, col)] = sum_n;
                 }
             }
-            if verbose {
-                progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as usize;
-                if progress != old_progress {
-                    println!("Calculating integral images: {}%", progress);
-                    old_progress = progress;
-                }
+            progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as usize;
+            if progress != old_progress {
+                sink.progress("Calculating integral images", progress)?;
+                old_progress = progress;
             }
         }
 
@@ -317,7 +260,7 @@ impl WhiteboxTool for AdaptiveFilter {
                     }
                     let mut data = vec![nodata; columns as usize];
                     for col in 0..columns {
-                        z = input[(row, col)];
+                        z = input.get_value(row, col);
                         if z != nodata {
                             x1 = col - midpoint_x - 1;
                             if x1 < 0 {
@@ -364,12 +307,10 @@ impl WhiteboxTool for AdaptiveFilter {
         for row in 0..rows {
             let data = rx.recv().unwrap();
             output.set_row_data(data.0, data.1);
-            if verbose {
-                progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as usize;
-                if progress != old_progress {
-                    println!("Progress: {}%", progress);
-                    old_progress = progress;
-                }
+            progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as usize;
+            if progress != old_progress {
+                sink.progress("Progress", progress)?;
+                old_progress = progress;
             }
         }
 
@@ -386,20 +327,19 @@ impl WhiteboxTool for AdaptiveFilter {
                                                                                                 ""));
 
         if verbose {
-            println!("Saving data...")
+            sink.message("Saving data...")
         };
         let _ = match output.write() {
             Ok(_) => {
                 if verbose {
-                    println!("Output file written")
+                    sink.message("Output file written")
                 }
             }
             Err(e) => return Err(e),
         };
 
         if verbose {
-            println!("{}",
-                    &format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
+            sink.message(&format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
         }
 
         Ok(())
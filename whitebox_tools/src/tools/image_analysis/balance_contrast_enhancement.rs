@@ -17,6 +17,7 @@ use std::thread;
 use raster::*;
 use std::io::{Error, ErrorKind};
 use tools::*;
+use tools::arg_parser::ArgValues;
 
 /// Tool struct containing the essential descriptors required to interact with the tool.
 pub struct BalanceContrastEnhancement {
@@ -116,67 +117,29 @@ impl WhiteboxTool for BalanceContrastEnhancement {
     fn run<'a>(&self,
                args: Vec<String>,
                working_directory: &'a str,
-               verbose: bool)
+               verbose: bool,
+               sink: &Arc<ProgressSink>)
                -> Result<(), Error> {
-        let mut input_file = String::new();
-        let mut output_file = String::new();
-        let mut e = 100f64;
         if args.len() == 0 {
             return Err(Error::new(ErrorKind::InvalidInput,
                                   "Tool run with no paramters."));
         }
-        for i in 0..args.len() {
-            let mut arg = args[i].replace("\"", "");
-            arg = arg.replace("\'", "");
-            let cmd = arg.split("="); // in case an equals sign was used
-            let vec = cmd.collect::<Vec<&str>>();
-            let mut keyval = false;
-            if vec.len() > 1 {
-                keyval = true;
-            }
-            if vec[0].to_lowercase() == "-i" || vec[0].to_lowercase() == "-input" ||
-               vec[0].to_lowercase() == "--input" {
-                if keyval {
-                    input_file = vec[1].to_string();
-                } else {
-                    input_file = args[i + 1].to_string();
-                }
-            } else if vec[0].to_lowercase() == "-o" || vec[0].to_lowercase() == "--output" {
-                if keyval {
-                    output_file = vec[1].to_string();
-                } else {
-                    output_file = args[i + 1].to_string();
-                }
-            } else if vec[0].to_lowercase() == "-band_mean" ||
-                      vec[0].to_lowercase() == "--band_mean" {
-                if keyval {
-                    e = vec[1].to_string().parse::<f64>().unwrap();
-                } else {
-                    e = args[i + 1].to_string().parse::<f64>().unwrap();
-                }
-            }
-        }
+        let args = ArgValues::parse(&self.parameters, &args)?;
+        let input_file = args.required_file("--input", working_directory)?;
+        let output_file = args.required_file("--output", working_directory)?;
+        let e = args.get_f64("--band_mean")?.unwrap_or(100f64);
 
         if verbose {
-            println!("***************{}", "*".repeat(self.get_tool_name().len()));
-            println!("* Welcome to {} *", self.get_tool_name());
-            println!("***************{}", "*".repeat(self.get_tool_name().len()));
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
+            sink.message(&format!("* Welcome to {} *", self.get_tool_name()));
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
         }
 
-        let sep: String = path::MAIN_SEPARATOR.to_string();
-
         let mut progress: usize;
         let mut old_progress: usize = 1;
 
-        if !input_file.contains(&sep) && !input_file.contains("/") {
-            input_file = format!("{}{}", working_directory, input_file);
-        }
-        if !output_file.contains(&sep) && !output_file.contains("/") {
-            output_file = format!("{}{}", working_directory, output_file);
-        }
-
         if verbose {
-            println!("Reading image data...")
+            sink.message("Reading image data...")
         };
         let input = Arc::new(Raster::new(&input_file, "r")?);
 
@@ -212,7 +175,7 @@ impl WhiteboxTool for BalanceContrastEnhancement {
                 let mut b_sqr_total = 0f64;
                 for row in (0..rows).filter(|rt| rt % num_procs == tid) {
                     for col in 0..columns {
-                        z = input[(row, col)];
+                        z = input.get_value(row, col);
                         if z != nodata {
                             num_pixels += 1f64;
                             r = z as u32 & 0xFF;
@@ -323,12 +286,10 @@ impl WhiteboxTool for BalanceContrastEnhancement {
 
             num_pixels += tnum_pixels;
 
-            if verbose {
-                progress = (100.0_f64 * tid as f64 / (num_procs - 1) as f64) as usize;
-                if progress != old_progress {
-                    println!("Progress (Loop 1 of 2): {}%", progress);
-                    old_progress = progress;
-                }
+            progress = (100.0_f64 * tid as f64 / (num_procs - 1) as f64) as usize;
+            if progress != old_progress {
+                sink.progress("Progress (Loop 1 of 2)", progress)?;
+                old_progress = progress;
             }
         }
 
@@ -367,7 +328,7 @@ impl WhiteboxTool for BalanceContrastEnhancement {
                 for row in (0..rows).filter(|rt| rt % num_procs == tid) {
                     let mut data = vec![nodata; columns as usize];
                     for col in 0..columns {
-                        z = input[(row, col)];
+                        z = input.get_value(row, col);
                         if z != nodata {
                             r = z as u32 & 0xFF;
                             g = (z as u32 >> 8) & 0xFF;
@@ -417,12 +378,10 @@ impl WhiteboxTool for BalanceContrastEnhancement {
         for row in 0..rows {
             let data = rx.recv().unwrap();
             output.set_row_data(data.0, data.1);
-            if verbose {
-                progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as usize;
-                if progress != old_progress {
-                    println!("Progress (Loop 2 of 2): {}%", progress);
-                    old_progress = progress;
-                }
+            progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as usize;
+            if progress != old_progress {
+                sink.progress("Progress (Loop 2 of 2)", progress)?;
+                old_progress = progress;
             }
         }
 
@@ -436,19 +395,18 @@ impl WhiteboxTool for BalanceContrastEnhancement {
                                       .replace("PT", ""));
 
         if verbose {
-            println!("Saving data...")
+            sink.message("Saving data...")
         };
         let _ = match output.write() {
             Ok(_) => {
                 if verbose {
-                    println!("Output file written")
+                    sink.message("Output file written")
                 }
             }
             Err(e) => return Err(e),
         };
         if verbose {
-            println!("{}",
-                 &format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
+            sink.message(&format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
         }
 
         Ok(())
@@ -18,6 +18,7 @@ use std::thread;
 use raster::*;
 use std::io::{Error, ErrorKind};
 use tools::*;
+use tools::arg_parser::ArgValues;
 
 pub struct ConservativeSmoothingFilter {
     name: String,
@@ -54,7 +55,7 @@ impl ConservativeSmoothingFilter {
 
         parameters.push(ToolParameter{
             name: "Filter X-Dimension".to_owned(), 
-            flags: vec!["--filterx".to_owned()], 
+            flags: vec!["--filter".to_owned(), "--filterx".to_owned()], 
             description: "Size of the filter kernel in the x-direction.".to_owned(),
             parameter_type: ParameterType::Integer,
             default_value: Some("11".to_owned()),
@@ -63,7 +64,7 @@ impl ConservativeSmoothingFilter {
 
         parameters.push(ToolParameter{
             name: "Filter Y-Dimension".to_owned(), 
-            flags: vec!["--filtery".to_owned()], 
+            flags: vec!["--filter".to_owned(), "--filtery".to_owned()], 
             description: "Size of the filter kernel in the y-direction.".to_owned(),
             parameter_type: ParameterType::Integer,
             default_value: Some("11".to_owned()),
@@ -117,66 +118,23 @@ impl WhiteboxTool for ConservativeSmoothingFilter {
         self.toolbox.clone()
     }
 
-    fn run<'a>(&self, args: Vec<String>, working_directory: &'a str, verbose: bool) -> Result<(), Error> {
-        let mut input_file = String::new();
-        let mut output_file = String::new();
-        let mut filter_size_x = 11usize;
-        let mut filter_size_y = 11usize;
+    fn run<'a>(&self, args: Vec<String>, working_directory: &'a str, verbose: bool, sink: &Arc<ProgressSink>) -> Result<(), Error> {
         if args.len() == 0 {
             return Err(Error::new(ErrorKind::InvalidInput,
                                 "Tool run with no paramters."));
         }
-        for i in 0..args.len() {
-            let mut arg = args[i].replace("\"", "");
-            arg = arg.replace("\'", "");
-            let cmd = arg.split("="); // in case an equals sign was used
-            let vec = cmd.collect::<Vec<&str>>();
-            let mut keyval = false;
-            if vec.len() > 1 {
-                keyval = true;
-            }
-            if vec[0].to_lowercase() == "-i" || vec[0].to_lowercase() == "--input" {
-                if keyval {
-                    input_file = vec[1].to_string();
-                } else {
-                    input_file = args[i+1].to_string();
-                }
-            } else if vec[0].to_lowercase() == "-o" || vec[0].to_lowercase() == "--output" {
-                if keyval {
-                    output_file = vec[1].to_string();
-                } else {
-                    output_file = args[i+1].to_string();
-                }
-            } else if vec[0].to_lowercase() == "-filter" || vec[0].to_lowercase() == "--filter" {
-                if keyval {
-                    filter_size_x = vec[1].to_string().parse::<usize>().unwrap();
-                } else {
-                    filter_size_x = args[i+1].to_string().parse::<usize>().unwrap();
-                }
-                filter_size_y = filter_size_x;
-            } else if vec[0].to_lowercase() == "-filterx" || vec[0].to_lowercase() == "--filterx" {
-                if keyval {
-                    filter_size_x = vec[1].to_string().parse::<usize>().unwrap();
-                } else {
-                    filter_size_x = args[i+1].to_string().parse::<usize>().unwrap();
-                }
-            } else if vec[0].to_lowercase() == "-filtery" || vec[0].to_lowercase() == "--filtery" {
-                if keyval {
-                    filter_size_y = vec[1].to_string().parse::<usize>().unwrap();
-                } else {
-                    filter_size_y = args[i+1].to_string().parse::<usize>().unwrap();
-                }
-            }
-        }
+        let args = ArgValues::parse(&self.parameters, &args)?;
+        let input_file = args.required_file("--input", working_directory)?;
+        let output_file = args.required_file("--output", working_directory)?;
+        let mut filter_size_x = args.get_usize("--filterx")?.unwrap_or(11usize);
+        let mut filter_size_y = args.get_usize("--filtery")?.unwrap_or(11usize);
 
         if verbose {
-            println!("***************{}", "*".repeat(self.get_tool_name().len()));
-            println!("* Welcome to {} *", self.get_tool_name());
-            println!("***************{}", "*".repeat(self.get_tool_name().len()));
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
+            sink.message(&format!("* Welcome to {} *", self.get_tool_name()));
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
         }
 
-        let sep: String = path::MAIN_SEPARATOR.to_string();
-
         if filter_size_x < 3 { filter_size_x = 3; }
         if filter_size_y < 3 { filter_size_y = 3; }
 
@@ -194,14 +152,7 @@ impl WhiteboxTool for ConservativeSmoothingFilter {
         let mut progress: usize;
         let mut old_progress: usize = 1;
 
-        if !input_file.contains(&sep) && !input_file.contains("/") {
-            input_file = format!("{}{}", working_directory, input_file);
-        }
-        if !output_file.contains(&sep) && !output_file.contains("/") {
-            output_file = format!("{}{}", working_directory, output_file);
-        }
-
-        if verbose { println!("Reading data...") };
+        if verbose { sink.message("Reading data...") };
 
         let input = Arc::new(Raster::new(&input_file, "r")?);
 
@@ -251,7 +202,7 @@ impl WhiteboxTool for ConservativeSmoothingFilter {
                                 min_val = f64::INFINITY;
                                 max_val = f64::NEG_INFINITY;
                                 for row2 in start_row..end_row+1 {
-                                    z_n = input[(row2, col2)];
+                                    z_n = input.get_value(row2, col2);
                                     if z_n != nodata {
                                         if z_n < min_val { min_val = z_n; }
                                         if z_n > max_val { max_val = z_n; }
@@ -261,7 +212,7 @@ impl WhiteboxTool for ConservativeSmoothingFilter {
                                 filter_max_vals.push_back(max_val);
                             }
                         }
-                        z = input[(row, col)];
+                        z = input.get_value(row, col);
                         if z != nodata {
                             min_val = f64::INFINITY;
                             max_val = f64::NEG_INFINITY;
@@ -304,12 +255,10 @@ impl WhiteboxTool for ConservativeSmoothingFilter {
         for row in 0..rows {
             let data = rx.recv().unwrap();
             output.set_row_data(data.0, data.1);
-            if verbose {
-                progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as usize;
-                if progress != old_progress {
-                    println!("Progress: {}%", progress);
-                    old_progress = progress;
-                }
+            progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as usize;
+            if progress != old_progress {
+                sink.progress("Progress", progress)?;
+                old_progress = progress;
             }
         }
 
@@ -321,13 +270,13 @@ impl WhiteboxTool for ConservativeSmoothingFilter {
         output.add_metadata_entry(format!("Filter size y: {}", filter_size_y));
         output.add_metadata_entry(format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
 
-        if verbose { println!("Saving data...") };
+        if verbose { sink.message("Saving data...") };
         let _ = match output.write() {
-            Ok(_) => if verbose { println!("Output file written") },
+            Ok(_) => if verbose { sink.message("Output file written") },
             Err(e) => return Err(e),
         };
         if verbose {
-            println!("{}", &format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
+            sink.message(&format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
         }
 
         Ok(())
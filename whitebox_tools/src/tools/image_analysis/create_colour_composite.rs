@@ -2,7 +2,7 @@
 This tool is part of the WhiteboxTools geospatial analysis library.
 Authors: Dr. John Lindsay
 Created: July 19, 2017
-Last Modified: Dec. 14, 2017
+Last Modified: March 14, 2018
 License: MIT
 */
 extern crate time;
@@ -18,6 +18,7 @@ use raster::*;
 use std::io::{Error, ErrorKind};
 use structures::Array2D;
 use tools::*;
+use tools::arg_parser::ArgValues;
 
 /// Tool struct containing the essential descriptors required to interact with the tool.
 pub struct CreateColourComposite {
@@ -33,14 +34,14 @@ impl CreateColourComposite {
     pub fn new() -> CreateColourComposite {
         let name = "CreateColourComposite".to_string();
         let toolbox = "Image Processing Tools".to_string();
-        let description = "Creates a colour-composite image from three bands of multispectral imagery."
+        let description = "Creates a colour-composite image from three bands of multispectral imagery, or from a single multi-band image."
             .to_string();
 
         let mut parameters = vec![];
         parameters.push(ToolParameter{
             name: "Input Red Band Image File".to_owned(), 
             flags: vec!["--red".to_owned()], 
-            description: "Input red band image file.".to_owned(),
+            description: "Input red band image file, or a multi-band image whose first three bands are used as red, green, and blue.".to_owned(),
             parameter_type: ParameterType::ExistingFile(ParameterFileType::Raster),
             default_value: None,
             optional: false
@@ -49,19 +50,19 @@ impl CreateColourComposite {
         parameters.push(ToolParameter{
             name: "Input Green Band Image File".to_owned(), 
             flags: vec!["--green".to_owned()], 
-            description: "Input green band image file.".to_owned(),
+            description: "Input green band image file (optional if --red is a multi-band image).".to_owned(),
             parameter_type: ParameterType::ExistingFile(ParameterFileType::Raster),
             default_value: None,
-            optional: false
+            optional: true
         });
 
         parameters.push(ToolParameter{
             name: "Input Blue Band Image File".to_owned(), 
             flags: vec!["--blue".to_owned()], 
-            description: "Input blue band image file.".to_owned(),
+            description: "Input blue band image file (optional if --red is a multi-band image).".to_owned(),
             parameter_type: ParameterType::ExistingFile(ParameterFileType::Raster),
             default_value: None,
-            optional: false
+            optional: true
         });
 
         parameters.push(ToolParameter{
@@ -84,7 +85,7 @@ impl CreateColourComposite {
 
         parameters.push(ToolParameter{
             name: "Perform balance contrast enhancement?".to_owned(), 
-            flags: vec!["--enhance".to_owned()], 
+            flags: vec!["--enchance".to_owned(), "--enhance".to_owned()], 
             description: "Optional flag indicating whether a balance contrast enhancement is performed.".to_owned(),
             parameter_type: ParameterType::Boolean,
             default_value: Some("true".to_owned()),
@@ -102,7 +103,8 @@ impl CreateColourComposite {
             short_exe += ".exe";
         }
         let usage = format!(">>.*{0} -r={1} -v --wd=\"*path*to*data*\" --red=band3.dep --green=band2.dep --blue=band1.dep -o=output.dep
->>.*{0} -r={1} -v --wd=\"*path*to*data*\" --red=band3.dep --green=band2.dep --blue=band1.dep --opacity=a.dep -o=output.dep", short_exe, name).replace("*", &sep);
+>>.*{0} -r={1} -v --wd=\"*path*to*data*\" --red=band3.dep --green=band2.dep --blue=band1.dep --opacity=a.dep -o=output.dep
+>>.*{0} -r={1} -v --wd=\"*path*to*data*\" --red=multispectral.tif -o=output.tif", short_exe, name).replace("*", &sep);
 
         CreateColourComposite {
             name: name,
@@ -145,105 +147,57 @@ impl WhiteboxTool for CreateColourComposite {
     fn run<'a>(&self,
                args: Vec<String>,
                working_directory: &'a str,
-               verbose: bool)
+               verbose: bool,
+               sink: &Arc<ProgressSink>)
                -> Result<(), Error> {
-        let mut input1_file = String::new();
-        let mut input2_file = String::new();
-        let mut input3_file = String::new();
-        let mut input4_file = String::new();
-        let mut input4_used = false;
-        let mut output_file = String::new();
-        let mut enhance = false;
         if args.len() == 0 {
             return Err(Error::new(ErrorKind::InvalidInput,
                                   "Tool run with no paramters."));
         }
-        for i in 0..args.len() {
-            let mut arg = args[i].replace("\"", "");
-            arg = arg.replace("\'", "");
-            let cmd = arg.split("="); // in case an equals sign was used
-            let vec = cmd.collect::<Vec<&str>>();
-            let mut keyval = false;
-            if vec.len() > 1 {
-                keyval = true;
-            }
-            if vec[0].to_lowercase() == "-red" || vec[0].to_lowercase() == "--red" {
-                if keyval {
-                    input1_file = vec[1].to_string();
-                } else {
-                    input1_file = args[i + 1].to_string();
-                }
-            } else if vec[0].to_lowercase() == "-green" || vec[0].to_lowercase() == "--green" {
-                if keyval {
-                    input2_file = vec[1].to_string();
-                } else {
-                    input2_file = args[i + 1].to_string();
-                }
-            } else if vec[0].to_lowercase() == "-blue" || vec[0].to_lowercase() == "--blue" {
-                if keyval {
-                    input3_file = vec[1].to_string();
-                } else {
-                    input3_file = args[i + 1].to_string();
-                }
-            } else if vec[0].to_lowercase() == "-opacity" || vec[0].to_lowercase() == "--opacity" {
-                if keyval {
-                    input4_file = vec[1].to_string();
-                } else {
-                    input4_file = args[i + 1].to_string();
-                }
-                input4_used = true;
-            } else if vec[0].to_lowercase() == "-o" || vec[0].to_lowercase() == "--output" {
-                if keyval {
-                    output_file = vec[1].to_string();
-                } else {
-                    output_file = args[i + 1].to_string();
-                }
-            } else if vec[0].to_lowercase() == "-enchance" || vec[0].to_lowercase() == "--enhance" {
-                enhance = true;
-            }
-        }
+        let args = ArgValues::parse(&self.parameters, &args)?;
+        let input1_file = args.required_file("--red", working_directory)?;
+        let input2_file = args.get_file("--green", working_directory)?.unwrap_or(String::new());
+        let input3_file = args.get_file("--blue", working_directory)?.unwrap_or(String::new());
+        let input4_file = args.get_file("--opacity", working_directory)?.unwrap_or(String::new());
+        let input4_used = !input4_file.is_empty();
+        let output_file = args.required_file("--output", working_directory)?;
+        let enhance = args.get_bool("--enhance")?;
 
         if verbose {
-            println!("***************{}", "*".repeat(self.get_tool_name().len()));
-            println!("* Welcome to {} *", self.get_tool_name());
-            println!("***************{}", "*".repeat(self.get_tool_name().len()));
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
+            sink.message(&format!("* Welcome to {} *", self.get_tool_name()));
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
         }
 
-        let sep: String = path::MAIN_SEPARATOR.to_string();
-
         let mut progress: usize;
         let mut old_progress: usize = 1;
 
-        if !input1_file.contains(&sep) && !input1_file.contains("/") {
-            input1_file = format!("{}{}", working_directory, input1_file);
-        }
-        if !input2_file.contains(&sep) && !input2_file.contains("/") {
-            input2_file = format!("{}{}", working_directory, input2_file);
-        }
-        if !input3_file.contains(&sep) && !input3_file.contains("/") {
-            input3_file = format!("{}{}", working_directory, input3_file);
-        }
-        if input4_used {
-            if !input4_file.contains(&sep) && !input4_file.contains("/") {
-                input4_file = format!("{}{}", working_directory, input4_file);
-            }
-        }
-        if !output_file.contains(&sep) && !output_file.contains("/") {
-            output_file = format!("{}{}", working_directory, output_file);
-        }
-
         if verbose {
-            println!("Reading red band data...")
+            sink.message("Reading red band data...")
         };
-        let input_r = Arc::new(Raster::new(&input1_file, "r")?);
-        if verbose {
-            println!("Reading green band data...")
-        };
-        let input_g = Arc::new(Raster::new(&input2_file, "r")?);
-        if verbose {
-            println!("Reading blue band data...")
+        let input = Raster::new(&input1_file, "r")?;
+        let (input_r, input_g, input_b) = if input2_file.is_empty() && input3_file.is_empty() {
+            // a single stacked image supplies the red, green, and blue bands
+            if input.num_bands() < 3 {
+                return Err(Error::new(ErrorKind::InvalidInput,
+                                      "The green and blue bands may only be omitted when the red input is an image with at least three bands."));
+            }
+            (Arc::new(input.get_band(0)?), Arc::new(input.get_band(1)?), Arc::new(input.get_band(2)?))
+        } else {
+            if input2_file.is_empty() || input3_file.is_empty() {
+                return Err(Error::new(ErrorKind::InvalidInput,
+                                      "Both the green and blue band images must be specified."));
+            }
+            if verbose {
+                sink.message("Reading green band data...")
+            };
+            let input_g = Raster::new(&input2_file, "r")?;
+            if verbose {
+                sink.message("Reading blue band data...")
+            };
+            let input_b = Raster::new(&input3_file, "r")?;
+            (Arc::new(input), Arc::new(input_g), Arc::new(input_b))
         };
-        let input_b = Arc::new(Raster::new(&input3_file, "r")?);
 
         let rows = input_r.configs.rows as isize;
         let columns = input_r.configs.columns as isize;
@@ -261,7 +215,7 @@ impl WhiteboxTool for CreateColourComposite {
         let input_a = match input4_used {
             true => {
                 if verbose {
-                    println!("Reading opacity data...")
+                    sink.message("Reading opacity data...")
                 };
                 let opacity = Raster::new(&input4_file, "r")?;
                 a_min = opacity.configs.display_min;
@@ -314,9 +268,9 @@ impl WhiteboxTool for CreateColourComposite {
                 for row in (0..rows).filter(|r| r % num_procs == tid) {
                     let mut data = vec![nodata_r; columns as usize];
                     for col in 0..columns {
-                        red_val = input_r[(row, col)];
-                        green_val = input_g[(row, col)];
-                        blue_val = input_b[(row, col)];
+                        red_val = input_r.get_value(row, col);
+                        green_val = input_g.get_value(row, col);
+                        blue_val = input_b.get_value(row, col);
                         if red_val != nodata_r && green_val != nodata_g && blue_val != nodata_b {
                             red_val = (red_val - red_min) / red_range * 255f64;
                             if red_val < 0f64 {
@@ -368,12 +322,10 @@ impl WhiteboxTool for CreateColourComposite {
         for row in 0..rows {
             let data = rx.recv().unwrap();
             output.set_row_data(data.0, data.1);
-            if verbose {
-                progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as usize;
-                if progress != old_progress {
-                    println!("Progress: {}%", progress);
-                    old_progress = progress;
-                }
+            progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as usize;
+            if progress != old_progress {
+                sink.progress("Progress", progress)?;
+                old_progress = progress;
             }
         }
 
@@ -401,7 +353,7 @@ impl WhiteboxTool for CreateColourComposite {
 
             for row in 0..rows {
                 for col in 0..columns {
-                    z = output[(row, col)];
+                    z = output.get_value(row, col);
                     if z != nodata_r {
                         num_pixels += 1f64;
                         r = z as u32 & 0xFF;
@@ -436,12 +388,10 @@ impl WhiteboxTool for CreateColourComposite {
                         b_sqr_total += (b * b) as f64;
                     }
                 }
-                if verbose {
-                    progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as usize;
-                    if progress != old_progress {
-                        println!("Performing Enhancement (1 of 2): {}%", progress);
-                        old_progress = progress;
-                    }
+                progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as usize;
+                if progress != old_progress {
+                    sink.progress("Performing Enhancement (1 of 2)", progress)?;
+                    old_progress = progress;
                 }
             }
 
@@ -470,7 +420,7 @@ impl WhiteboxTool for CreateColourComposite {
 
             for row in 0..rows {
                 for col in 0..columns {
-                    z = output[(row, col)];
+                    z = output.get_value(row, col);
                     if z != nodata_r {
                         r = z as u32 & 0xFF;
                         g = (z as u32 >> 8) & 0xFF;
@@ -505,16 +455,13 @@ impl WhiteboxTool for CreateColourComposite {
                         g_out = g_outf as u32;
                         b_out = b_outf as u32;
 
-                        output[(row, col)] = ((a << 24) | (b_out << 16) | (g_out << 8) | r_out) as
-                                             f64
-                    }
+                        output.set_value(row, col, ((a << 24) | (b_out << 16) | (g_out << 8) | r_out) as
+                                             f64)}
                 }
-                if verbose {
-                    progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as usize;
-                    if progress != old_progress {
-                        println!("Performing Enhancement (2 of 2): {}%", progress);
-                        old_progress = progress;
-                    }
+                progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as usize;
+                if progress != old_progress {
+                    sink.progress("Performing Enhancement (2 of 2)", progress)?;
+                    old_progress = progress;
                 }
             }
         }
@@ -534,19 +481,18 @@ impl WhiteboxTool for CreateColourComposite {
                                       .replace("PT", ""));
 
         if verbose {
-            println!("Saving data...")
+            sink.message("Saving data...")
         };
         let _ = match output.write() {
             Ok(_) => {
                 if verbose {
-                    println!("Output file written")
+                    sink.message("Output file written")
                 }
             }
             Err(e) => return Err(e),
         };
         if verbose {
-            println!("{}",
-                 &format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
+            sink.message(&format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
         }
 
         Ok(())
@@ -18,6 +18,7 @@ use raster::*;
 use structures::Array2D;
 use std::io::{Error, ErrorKind};
 use tools::*;
+use tools::arg_parser::ArgValues;
 
 /// Tool struct containing the essential descriptors required to interact with the tool.
 pub struct DirectDecorrelationStretch {
@@ -65,7 +66,7 @@ impl DirectDecorrelationStretch {
 
         parameters.push(ToolParameter{
             name: "Percent to clip the upper tail".to_owned(), 
-            flags: vec!["--clip".to_owned()], 
+            flags: vec!["--clip_percent".to_owned(), "--clip".to_owned()], 
             description: "Optional percent to clip the upper tail by during the stretch.".to_owned(),
             parameter_type: ParameterType::Float,
             default_value: Some("1.0".to_owned()),
@@ -128,70 +129,32 @@ impl WhiteboxTool for DirectDecorrelationStretch {
     fn run<'a>(&self,
                args: Vec<String>,
                working_directory: &'a str,
-               verbose: bool)
+               verbose: bool,
+               sink: &Arc<ProgressSink>)
                -> Result<(), Error> {
-        let mut input_file = String::new();
-        let mut output_file = String::new();
-        let mut achromatic_factor = 0.5f64;
-        let mut clip_percent = 0.01f64;
         if args.len() == 0 {
             return Err(Error::new(ErrorKind::InvalidInput,
                                   "Tool run with no paramters."));
         }
-        for i in 0..args.len() {
-            let mut arg = args[i].replace("\"", "");
-            arg = arg.replace("\'", "");
-            let cmd = arg.split("="); // in case an equals sign was used
-            let vec = cmd.collect::<Vec<&str>>();
-            let mut keyval = false;
-            if vec.len() > 1 {
-                keyval = true;
-            }
-            if vec[0].to_lowercase() == "-i" || vec[0].to_lowercase() == "-input" ||
-               vec[0].to_lowercase() == "--input" {
-                if keyval {
-                    input_file = vec[1].to_string();
-                } else {
-                    input_file = args[i + 1].to_string();
-                }
-            } else if vec[0].to_lowercase() == "-o" || vec[0].to_lowercase() == "--output" {
-                if keyval {
-                    output_file = vec[1].to_string();
-                } else {
-                    output_file = args[i + 1].to_string();
-                }
-            } else if vec[0].to_lowercase() == "-k" || vec[0].to_lowercase() == "--k" {
-                if keyval {
-                    achromatic_factor = vec[1].to_string().parse::<f64>().unwrap();
-                } else {
-                    achromatic_factor = args[i + 1].to_string().parse::<f64>().unwrap();
-                }
-            } else if vec[0].to_lowercase() == "-clip_percent" ||
-                      vec[0].to_lowercase() == "--clip_percent" ||
-                      vec[0].to_lowercase() == "--clip" {
-                if keyval {
-                    clip_percent = vec[1].to_string().parse::<f64>().unwrap();
-                } else {
-                    clip_percent = args[i + 1].to_string().parse::<f64>().unwrap();
-                }
-                if clip_percent < 0f64 {
-                    clip_percent = 0f64;
-                }
-                if clip_percent > 50f64 {
-                    clip_percent = 50f64;
-                }
-                clip_percent = clip_percent / 100f64;
-            }
+        let args = ArgValues::parse(&self.parameters, &args)?;
+        let input_file = args.required_file("--input", working_directory)?;
+        let output_file = args.required_file("--output", working_directory)?;
+        let mut achromatic_factor = args.get_f64("-k")?.unwrap_or(0.5f64);
+        let mut clip_percent = args.get_f64("--clip")?.unwrap_or(0.01f64);
+        if clip_percent < 0f64 {
+            clip_percent = 0f64;
+        }
+        if clip_percent > 50f64 {
+            clip_percent = 50f64;
         }
+        clip_percent = clip_percent / 100f64;
 
         if verbose {
-            println!("***************{}", "*".repeat(self.get_tool_name().len()));
-            println!("* Welcome to {} *", self.get_tool_name());
-            println!("***************{}", "*".repeat(self.get_tool_name().len()));
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
+            sink.message(&format!("* Welcome to {} *", self.get_tool_name()));
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
         }
 
-        let sep: String = path::MAIN_SEPARATOR.to_string();
-
         let mut progress: usize;
         let mut old_progress: usize = 1;
 
@@ -202,15 +165,8 @@ impl WhiteboxTool for DirectDecorrelationStretch {
             achromatic_factor = 1f64;
         }
 
-        if !input_file.contains(&sep) && !input_file.contains("/") {
-            input_file = format!("{}{}", working_directory, input_file);
-        }
-        if !output_file.contains(&sep) && !output_file.contains("/") {
-            output_file = format!("{}{}", working_directory, output_file);
-        }
-
         if verbose {
-            println!("Reading image data...")
+            sink.message("Reading image data...")
         };
         let input = Arc::new(Raster::new(&input_file, "r")?);
         // let input = Raster::new(&input_file, "r")?;
@@ -241,7 +197,7 @@ impl WhiteboxTool for DirectDecorrelationStretch {
                     let mut histo_blue = [0usize; 256];
                     let mut num_cells = 0;
                     for col in 0..columns {
-                        z = input[(row, col)];
+                        z = input.get_value(row, col);
                         if z != nodata {
                             red = z as u32 & 0xFF;
                             green = (z as u32 >> 8) & 0xFF;
@@ -327,12 +283,10 @@ impl WhiteboxTool for DirectDecorrelationStretch {
             }
 
             num_cells += data.7;
-            if verbose {
-                progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as usize;
-                if progress != old_progress {
-                    println!("Progress (Loop 1 of 2): {}%", progress);
-                    old_progress = progress;
-                }
+            progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as usize;
+            if progress != old_progress {
+                sink.progress("Progress (Loop 1 of 2)", progress)?;
+                old_progress = progress;
             }
         }
 
@@ -424,7 +378,7 @@ impl WhiteboxTool for DirectDecorrelationStretch {
                 for row in (0..rows).filter(|row_val| row_val % num_procs == tid) {
                     let mut data = vec![rgb_nodata; columns as usize];
                     for col in 0..columns {
-                        z = input[(row, col)];
+                        z = input.get_value(row, col);
                         if z != nodata {
                             red = red_band[(row, col)] as u32;
                             if red < stretch_min as u32 {
@@ -472,12 +426,10 @@ impl WhiteboxTool for DirectDecorrelationStretch {
         for row in 0..rows {
             let data = rx.recv().unwrap();
             output.set_row_data(data.0, data.1);
-            if verbose {
-                progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as usize;
-                if progress != old_progress {
-                    println!("Progress (Loop 2 of 2): {}%", progress);
-                    old_progress = progress;
-                }
+            progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as usize;
+            if progress != old_progress {
+                sink.progress("Progress (Loop 2 of 2)", progress)?;
+                old_progress = progress;
             }
         }
 
@@ -492,12 +444,12 @@ impl WhiteboxTool for DirectDecorrelationStretch {
                                       .replace("PT", ""));
 
         if verbose {
-            println!("Saving data...")
+            sink.message("Saving data...")
         };
         let _ = match output.write() {
             Ok(_) => {
                 if verbose {
-                    println!("Output file written")
+                    sink.message("Output file written")
                 }
             }
             Err(e) => return Err(e),
@@ -623,8 +575,7 @@ impl WhiteboxTool for DirectDecorrelationStretch {
         // }
 
         if verbose {
-            println!("{}",
-                 &format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
+            sink.message(&format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
         }
 
         Ok(())
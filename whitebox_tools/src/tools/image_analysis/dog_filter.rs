@@ -18,6 +18,7 @@ use std::thread;
 use raster::*;
 use std::io::{Error, ErrorKind};
 use tools::*;
+use tools::arg_parser::ArgValues;
 
 pub struct DiffOfGaussianFilter {
     name: String,
@@ -117,66 +118,23 @@ impl WhiteboxTool for DiffOfGaussianFilter {
         self.toolbox.clone()
     }
 
-    fn run<'a>(&self, args: Vec<String>, working_directory: &'a str, verbose: bool) -> Result<(), Error> {
-        let mut input_file = String::new();
-        let mut output_file = String::new();
+    fn run<'a>(&self, args: Vec<String>, working_directory: &'a str, verbose: bool, sink: &Arc<ProgressSink>) -> Result<(), Error> {
         let mut filter_size1 = 0usize;
         let mut filter_size2 = 0usize;
-        let mut sigma1 = 2.0;
-        let mut sigma2 = 4.0;
         if args.len() == 0 {
             return Err(Error::new(ErrorKind::InvalidInput,
                                 "Tool run with no paramters."));
         }
-        for i in 0..args.len() {
-            let mut arg = args[i].replace("\"", "");
-            arg = arg.replace("\'", "");
-            let cmd = arg.split("="); // in case an equals sign was used
-            let vec = cmd.collect::<Vec<&str>>();
-            let mut keyval = false;
-            if vec.len() > 1 {
-                keyval = true;
-            }
-            if vec[0].to_lowercase() == "-i" || vec[0].to_lowercase() == "--input" {
-                if keyval {
-                    input_file = vec[1].to_string();
-                } else {
-                    input_file = args[i + 1].to_string();
-                }
-            } else if vec[0].to_lowercase() == "-o" || vec[0].to_lowercase() == "--output" {
-                if keyval {
-                    output_file = vec[1].to_string();
-                } else {
-                    output_file = args[i + 1].to_string();
-                }
-            } else if vec[0].to_lowercase() == "-sigma1" || vec[0].to_lowercase() == "--sigma1" {
-                if keyval {
-                    sigma1 = vec[1].to_string().parse::<f64>().unwrap();
-                } else {
-                    sigma1 = args[i + 1].to_string().parse::<f64>().unwrap();
-                }
-            } else if vec[0].to_lowercase() == "-sigma2" || vec[0].to_lowercase() == "--sigma2" {
-                if keyval {
-                    sigma2 = vec[1].to_string().parse::<f64>().unwrap();
-                } else {
-                    sigma2 = args[i + 1].to_string().parse::<f64>().unwrap();
-                }
-            }
-        }
+        let args = ArgValues::parse(&self.parameters, &args)?;
+        let input_file = args.required_file("--input", working_directory)?;
+        let output_file = args.required_file("--output", working_directory)?;
+        let mut sigma1 = args.get_f64("--sigma1")?.unwrap_or(2.0);
+        let mut sigma2 = args.get_f64("--sigma2")?.unwrap_or(4.0);
 
         if verbose {
-            println!("***************{}", "*".repeat(self.get_tool_name().len()));
-            println!("* Welcome to {} *", self.get_tool_name());
-            println!("***************{}", "*".repeat(self.get_tool_name().len()));
-        }
-
-        let sep: String = path::MAIN_SEPARATOR.to_string();
-
-        if !input_file.contains(&sep) && !input_file.contains("/") {
-            input_file = format!("{}{}", working_directory, input_file);
-        }
-        if !output_file.contains(&sep) && !output_file.contains("/") {
-            output_file = format!("{}{}", working_directory, output_file);
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
+            sink.message(&format!("* Welcome to {} *", self.get_tool_name()));
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
         }
 
         if sigma1 < 0.5 {
@@ -281,7 +239,7 @@ impl WhiteboxTool for DiffOfGaussianFilter {
         let mut old_progress: usize = 1;
 
         if verbose {
-            println!("Reading data...")
+            sink.message("Reading data...")
         };
 
         let input = Arc::new(Raster::new(&input_file, "r")?);
@@ -319,14 +277,14 @@ impl WhiteboxTool for DiffOfGaussianFilter {
                 for row in (0..rows).filter(|r| r % num_procs == tid) {
                     let mut data = vec![nodata; columns as usize];
                     for col in 0..columns {
-                        z = input_data[(row, col)];
+                        z = input_data.get_value(row, col);
                         if z != nodata {
                             sum = 0.0;
                             z_final1 = 0.0;
                             for a in 0..num_pixels_in_filter1 {
                                 x = col + d_x1[a];
                                 y = row + d_y1[a];
-                                zn = input_data[(y, x)];
+                                zn = input_data.get_value(y, x);
                                 if zn != nodata {
                                     sum += weights1[a];
                                     z_final1 += weights1[a] * zn;
@@ -339,7 +297,7 @@ impl WhiteboxTool for DiffOfGaussianFilter {
                             for a in 0..num_pixels_in_filter2 {
                                 x = col + d_x2[a];
                                 y = row + d_y2[a];
-                                zn = input_data[(y, x)];
+                                zn = input_data.get_value(y, x);
                                 if zn != nodata {
                                     sum += weights2[a];
                                     z_final2 += weights2[a] * zn;
@@ -359,12 +317,10 @@ impl WhiteboxTool for DiffOfGaussianFilter {
         for row in 0..rows {
             let data = rx.recv().unwrap();
             output.set_row_data(data.0, data.1);
-            if verbose {
-                progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as usize;
-                if progress != old_progress {
-                    println!("Progress: {}%", progress);
-                    old_progress = progress;
-                }
+            progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as usize;
+            if progress != old_progress {
+                sink.progress("Progress", progress)?;
+                old_progress = progress;
             }
         }
 
@@ -378,20 +334,19 @@ impl WhiteboxTool for DiffOfGaussianFilter {
         output.add_metadata_entry(format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
 
         if verbose {
-            println!("Saving data...")
+            sink.message("Saving data...")
         };
         let _ = match output.write() {
             Ok(_) => {
                 if verbose {
-                    println!("Output file written")
+                    sink.message("Output file written")
                 }
             }
             Err(e) => return Err(e),
         };
 
         if verbose {
-            println!("{}",
-                    &format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
+            sink.message(&format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
         }
 
         Ok(())
@@ -17,6 +17,7 @@ use std::sync::Arc;
 use std::sync::mpsc;
 use std::thread;
 use tools::*;
+use tools::arg_parser::ArgValues;
 
 pub struct HistogramMatchingTwoImages {
     name: String,
@@ -115,68 +116,28 @@ impl WhiteboxTool for HistogramMatchingTwoImages {
     fn run<'a>(&self,
                args: Vec<String>,
                working_directory: &'a str,
-               verbose: bool)
+               verbose: bool,
+               sink: &Arc<ProgressSink>)
                -> Result<(), Error> {
-        let mut input_file1 = String::new();
-        let mut input_file2 = String::new();
-        let mut output_file = String::new();
-        
         if args.len() == 0 {
             return Err(Error::new(ErrorKind::InvalidInput,
                                   "Tool run with no paramters."));
         }
-        for i in 0..args.len() {
-            let mut arg = args[i].replace("\"", "");
-            arg = arg.replace("\'", "");
-            let cmd = arg.split("="); // in case an equals sign was used
-            let vec = cmd.collect::<Vec<&str>>();
-            let mut keyval = false;
-            if vec.len() > 1 {
-                keyval = true;
-            }
-            if vec[0].to_lowercase() == "-i1" || vec[0].to_lowercase() == "--i1" || vec[0].to_lowercase() == "--input1" {
-                if keyval {
-                    input_file1 = vec[1].to_string();
-                } else {
-                    input_file1 = args[i + 1].to_string();
-                }
-            } else if vec[0].to_lowercase() == "-i2" || vec[0].to_lowercase() == "--i2" || vec[0].to_lowercase() == "--input2" {
-                if keyval {
-                    input_file2 = vec[1].to_string();
-                } else {
-                    input_file2 = args[i + 1].to_string();
-                }
-            } else if vec[0].to_lowercase() == "-o" || vec[0].to_lowercase() == "--output" {
-                if keyval {
-                    output_file = vec[1].to_string();
-                } else {
-                    output_file = args[i + 1].to_string();
-                }
-            }
-        }
+        let args = ArgValues::parse(&self.parameters, &args)?;
+        let input_file1 = args.required_file("--input1", working_directory)?;
+        let input_file2 = args.required_file("--input2", working_directory)?;
+        let output_file = args.required_file("--output", working_directory)?;
 
         if verbose {
-            println!("***************{}", "*".repeat(self.get_tool_name().len()));
-            println!("* Welcome to {} *", self.get_tool_name());
-            println!("***************{}", "*".repeat(self.get_tool_name().len()));
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
+            sink.message(&format!("* Welcome to {} *", self.get_tool_name()));
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
         }
 
-        let sep: String = path::MAIN_SEPARATOR.to_string();
-
         let mut progress: usize;
         let mut old_progress: usize = 1;
 
-        if !input_file1.contains(&sep) && !input_file1.contains("/") {
-            input_file1 = format!("{}{}", working_directory, input_file1);
-        }
-        if !input_file2.contains(&sep) && !input_file2.contains("/") {
-            input_file2 = format!("{}{}", working_directory, input_file2);
-        }
-        if !output_file.contains(&sep) && !output_file.contains("/") {
-            output_file = format!("{}{}", working_directory, output_file);
-        }
-
-        if verbose { println!("Reading input data...") };
+        if verbose { sink.message("Reading input data...") };
         let input1 = Arc::new(Raster::new(&input_file1, "r")?);
         // let input2 = Arc::new(Raster::new(&input_file2, "r")?);
         // let input1 = Raster::new(&input_file1, "r")?;
@@ -214,7 +175,7 @@ impl WhiteboxTool for HistogramMatchingTwoImages {
         let mut bin_num;
         for row in 0..rows1 {
             for col in 0..columns1 {
-                z = input1[(row, col)];
+                z = input1.get_value(row, col);
                 if z != nodata1 {
                     numcells1 += 1f64;
                     bin_num = ((z - min_value1) / bin_size) as usize;
@@ -222,12 +183,10 @@ impl WhiteboxTool for HistogramMatchingTwoImages {
                     histogram[bin_num] += 1f64;
                 }
             }
-            if verbose {
-                progress = (100.0_f64 * row as f64 / (rows1 - 1) as f64) as usize;
-                if progress != old_progress {
-                    println!("Loop 1 of 3: {}%", progress);
-                    old_progress = progress;
-                }
+            progress = (100.0_f64 * row as f64 / (rows1 - 1) as f64) as usize;
+            if progress != old_progress {
+                sink.progress("Loop 1 of 3", progress)?;
+                old_progress = progress;
             }
         }
 
@@ -253,7 +212,7 @@ impl WhiteboxTool for HistogramMatchingTwoImages {
         
         for row in 0..rows2 {
             for col in 0..columns2 {
-                z = input2[(row, col)];
+                z = input2.get_value(row, col);
                 if z != nodata2 {
                     numcells2 += 1f64;
                     bin_num = ((z - min_value2) / bin_size) as usize;
@@ -261,12 +220,10 @@ impl WhiteboxTool for HistogramMatchingTwoImages {
                     histogram2[bin_num] += 1f64;
                 }
             }
-            if verbose {
-                progress = (100.0_f64 * row as f64 / (rows2 - 1) as f64) as usize;
-                if progress != old_progress {
-                    println!("Loop 2 of 3: {}%", progress);
-                    old_progress = progress;
-                }
+            progress = (100.0_f64 * row as f64 / (rows2 - 1) as f64) as usize;
+            if progress != old_progress {
+                sink.progress("Loop 2 of 3", progress)?;
+                old_progress = progress;
             }
         }
         
@@ -388,7 +345,7 @@ impl WhiteboxTool for HistogramMatchingTwoImages {
                 for row in (0..rows1).filter(|r| r % num_procs == tid) {
                     let mut data: Vec<f64> = vec![nodata1; columns1 as usize];
                     for col in 0..columns1 {
-                        z = input1[(row, col)];
+                        z = input1.get_value(row, col);
                         if z != nodata1 {
                             bin_num = ((z - min_value1) / bin_size) as usize;
                             if bin_num > num_bins_less_one1 { bin_num = num_bins_less_one1; }
@@ -424,12 +381,10 @@ impl WhiteboxTool for HistogramMatchingTwoImages {
         for r in 0..rows1 {
             let (row, data) = rx.recv().unwrap();
             output.set_row_data(row, data);
-            if verbose {
-                progress = (100.0_f64 * r as f64 / (rows1 - 1) as f64) as usize;
-                if progress != old_progress {
-                    println!("Loop 3 of 3: {}%", progress);
-                    old_progress = progress;
-                }
+            progress = (100.0_f64 * r as f64 / (rows1 - 1) as f64) as usize;
+            if progress != old_progress {
+                sink.progress("Loop 3 of 3", progress)?;
+                old_progress = progress;
             }
         }
 
@@ -444,19 +399,18 @@ impl WhiteboxTool for HistogramMatchingTwoImages {
                                       .replace("PT", ""));
 
         if verbose {
-            println!("Saving data...")
+            sink.message("Saving data...")
         };
         let _ = match output.write() {
             Ok(_) => {
                 if verbose {
-                    println!("Output file written")
+                    sink.message("Output file written")
                 }
             }
             Err(e) => return Err(e),
         };
         if verbose {
-            println!("{}",
-                 &format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
+            sink.message(&format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
         }
 
         Ok(())
@@ -17,6 +17,7 @@ use std::thread;
 use raster::*;
 use std::io::{Error, ErrorKind};
 use tools::*;
+use tools::arg_parser::ArgValues;
 
 /// Tool struct containing the essential descriptors required to interact with the tool.
 pub struct IhsToRgb {
@@ -38,7 +39,7 @@ impl IhsToRgb {
         let mut parameters = vec![];
         parameters.push(ToolParameter{
             name: "Input Intensity File".to_owned(), 
-            flags: vec!["--intensity".to_owned()], 
+            flags: vec!["-i".to_owned(), "--intensity".to_owned()], 
             description: "Input intensity file.".to_owned(),
             parameter_type: ParameterType::ExistingFile(ParameterFileType::Raster),
             default_value: None,
@@ -47,7 +48,7 @@ impl IhsToRgb {
 
         parameters.push(ToolParameter{
             name: "Input Hue File".to_owned(), 
-            flags: vec!["--hue".to_owned()], 
+            flags: vec!["-h".to_owned(), "--hue".to_owned()], 
             description: "Input hue file.".to_owned(),
             parameter_type: ParameterType::ExistingFile(ParameterFileType::Raster),
             default_value: None,
@@ -56,7 +57,7 @@ impl IhsToRgb {
 
         parameters.push(ToolParameter{
             name: "Input Saturation File".to_owned(), 
-            flags: vec!["--saturation".to_owned()], 
+            flags: vec!["-s".to_owned(), "--saturation".to_owned()], 
             description: "Input saturation file.".to_owned(),
             parameter_type: ParameterType::ExistingFile(ParameterFileType::Raster),
             default_value: None,
@@ -74,7 +75,7 @@ impl IhsToRgb {
 
         parameters.push(ToolParameter{
             name: "Output Green Band File (optional; only if colour-composite not specified)".to_owned(), 
-            flags: vec!["--green".to_owned()], 
+            flags: vec!["-g".to_owned(), "--green".to_owned()], 
             description: "Output green band file. Optionally specified if colour-composite not specified.".to_owned(),
             parameter_type: ParameterType::NewFile(ParameterFileType::Raster),
             default_value: None,
@@ -83,7 +84,7 @@ impl IhsToRgb {
 
         parameters.push(ToolParameter{
             name: "Output Blue Band File (optional; only if colour-composite not specified)".to_owned(), 
-            flags: vec!["--blue".to_owned()], 
+            flags: vec!["-b".to_owned(), "--blue".to_owned()], 
             description: "Output blue band file. Optionally specified if colour-composite not specified.".to_owned(),
             parameter_type: ParameterType::NewFile(ParameterFileType::Raster),
             default_value: None,
@@ -92,7 +93,7 @@ impl IhsToRgb {
 
         parameters.push(ToolParameter{
             name: "Output Colour-Composite File (optional; only if individual bands not specified)".to_owned(), 
-            flags: vec!["-o".to_owned(), "--output".to_owned()], 
+            flags: vec!["-o".to_owned(), "--composite".to_owned(), "--output".to_owned()], 
             description: "Output colour-composite file. Only used if individual bands are not specified.".to_owned(),
             parameter_type: ParameterType::NewFile(ParameterFileType::Raster),
             default_value: None,
@@ -147,109 +148,35 @@ impl WhiteboxTool for IhsToRgb {
         self.toolbox.clone()
     }
 
-    fn run<'a>(&self, args: Vec<String>, working_directory: &'a str, verbose: bool) -> Result<(), Error> {
-        let mut red_file = String::new();
-        let mut green_file = String::new();
-        let mut blue_file = String::new();
-        let mut intensity_file = String::new();
-        let mut hue_file = String::new();
-        let mut saturation_file = String::new();
-        let mut composite_file = String::new();
-        let mut use_composite = false;
+    fn run<'a>(&self, args: Vec<String>, working_directory: &'a str, verbose: bool, sink: &Arc<ProgressSink>) -> Result<(), Error> {
         if args.len() == 0 {
             return Err(Error::new(ErrorKind::InvalidInput,
                                 "Tool run with no paramters."));
         }
-        for i in 0..args.len() {
-            let mut arg = args[i].replace("\"", "");
-            arg = arg.replace("\'", "");
-            let cmd = arg.split("="); // in case an equals sign was used
-            let vec = cmd.collect::<Vec<&str>>();
-            let mut keyval = false;
-            if vec.len() > 1 {
-                keyval = true;
-            }
-            if vec[0].to_lowercase() == "-red" || vec[0].to_lowercase() == "--red" {
-                if keyval {
-                    red_file = vec[1].to_string();
-                } else {
-                    red_file = args[i+1].to_string();
-                }
-            } else if vec[0].to_lowercase() == "-g" || vec[0].to_lowercase() == "-green" || vec[0].to_lowercase() == "--green" {
-                if keyval {
-                    green_file = vec[1].to_string();
-                } else {
-                    green_file = args[i+1].to_string();
-                }
-            } else if vec[0].to_lowercase() == "-b" || vec[0].to_lowercase() == "-blue" || vec[0].to_lowercase() == "--blue" {
-                if keyval {
-                    blue_file = vec[1].to_string();
-                } else {
-                    blue_file = args[i+1].to_string();
-                }
-            } else if vec[0].to_lowercase() == "-i" || vec[0].to_lowercase() == "-intensity" || vec[0].to_lowercase() == "--intensity" {
-                if keyval {
-                    intensity_file = vec[1].to_string();
-                } else {
-                    intensity_file = args[i+1].to_string();
-                }
-            } else if vec[0].to_lowercase() == "-h" || vec[0].to_lowercase() == "-hue" || vec[0].to_lowercase() == "--hue" {
-                if keyval {
-                    hue_file = vec[1].to_string();
-                } else {
-                    hue_file = args[i+1].to_string();
-                }
-            } else if vec[0].to_lowercase() == "-s" || vec[0].to_lowercase() == "-saturation" || vec[0].to_lowercase() == "--saturation" {
-                if keyval {
-                    saturation_file = vec[1].to_string();
-                } else {
-                    saturation_file = args[i+1].to_string();
-                }
-            } else if vec[0].to_lowercase() == "-o" || vec[0].to_lowercase() == "-composite" || vec[0].to_lowercase() == "--composite" {
-                if keyval {
-                    composite_file = vec[1].to_string();
-                } else {
-                    composite_file = args[i+1].to_string();
-                }
-                use_composite = true;
-            }
-        }
+        let args = ArgValues::parse(&self.parameters, &args)?;
+        let red_file = args.get_file("--red", working_directory)?.unwrap_or(String::new());
+        let green_file = args.get_file("--green", working_directory)?.unwrap_or(String::new());
+        let blue_file = args.get_file("--blue", working_directory)?.unwrap_or(String::new());
+        let intensity_file = args.required_file("--intensity", working_directory)?;
+        let hue_file = args.required_file("--hue", working_directory)?;
+        let saturation_file = args.required_file("--saturation", working_directory)?;
+        let composite_file = args.get_file("--output", working_directory)?.unwrap_or(String::new());
+        let use_composite = !composite_file.is_empty();
 
         if verbose {
-            println!("***************{}", "*".repeat(self.get_tool_name().len()));
-            println!("* Welcome to {} *", self.get_tool_name());
-            println!("***************{}", "*".repeat(self.get_tool_name().len()));
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
+            sink.message(&format!("* Welcome to {} *", self.get_tool_name()));
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
         }
 
-        let sep: String = path::MAIN_SEPARATOR.to_string();
-
         let mut progress: usize;
         let mut old_progress: usize = 1;
 
-        if !red_file.contains(&sep) && !red_file.contains("/") {
-            red_file = format!("{}{}", working_directory, red_file);
-        }
-        if !green_file.contains(&sep) && !green_file.contains("/") {
-            green_file = format!("{}{}", working_directory, green_file);
-        }
-        if !blue_file.contains(&sep) && !blue_file.contains("/") {
-            blue_file = format!("{}{}", working_directory, blue_file);
-        }
-        if !intensity_file.contains(&sep) && !intensity_file.contains("/") {
-            intensity_file = format!("{}{}", working_directory, intensity_file);
-        }
-        if !hue_file.contains(&sep) && !hue_file.contains("/") {
-            hue_file = format!("{}{}", working_directory, hue_file);
-        }
-        if !saturation_file.contains(&sep) && !saturation_file.contains("/") {
-            saturation_file = format!("{}{}", working_directory, saturation_file);
-        }
-
-        if verbose { println!("Reading intensity band data...") };
+        if verbose { sink.message("Reading intensity band data...") };
         let input_i = Arc::new(Raster::new(&intensity_file, "r")?);
-        if verbose { println!("Reading hue band data...") };
+        if verbose { sink.message("Reading hue band data...") };
         let input_h = Arc::new(Raster::new(&hue_file, "r")?);
-        if verbose { println!("Reading saturation band data...") };
+        if verbose { sink.message("Reading saturation band data...") };
         let input_s = Arc::new(Raster::new(&saturation_file, "r")?);
 
         let rows = input_i.configs.rows as isize;
@@ -285,9 +212,9 @@ impl WhiteboxTool for IhsToRgb {
                     let mut green_data = vec![nodata_i; columns as usize];
                     let mut blue_data = vec![nodata_i; columns as usize];
                     for col in 0..columns {
-                        i = input_i[(row, col)];
-                        h = input_h[(row, col)];
-                        s = input_s[(row, col)];
+                        i = input_i.get_value(row, col);
+                        h = input_h.get_value(row, col);
+                        s = input_s.get_value(row, col);
                         if i != nodata_i && h != nodata_h && s != nodata_s {
                             if h <= 1f64 {
                                 r = i * (1f64 + 2f64 * s - 3f64 * s * h) / 3f64;
@@ -331,12 +258,10 @@ impl WhiteboxTool for IhsToRgb {
                 output_r.set_row_data(data.0, data.1);
                 output_g.set_row_data(data.0, data.2);
                 output_b.set_row_data(data.0, data.3);
-                if verbose {
-                    progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as usize;
-                    if progress != old_progress {
-                        println!("Progress: {}%", progress);
-                        old_progress = progress;
-                    }
+                progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as usize;
+                if progress != old_progress {
+                    sink.progress("Progress", progress)?;
+                    old_progress = progress;
                 }
             }
 
@@ -349,9 +274,9 @@ impl WhiteboxTool for IhsToRgb {
             output_r.add_metadata_entry(format!("Input saturation image file: {}", saturation_file));
             output_r.add_metadata_entry(format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
 
-            if verbose { println!("Saving red data...") };
+            if verbose { sink.message("Saving red data...") };
             let _ = match output_r.write() {
-                Ok(_) => if verbose { println!("Output file written") },
+                Ok(_) => if verbose { sink.message("Output file written") },
                 Err(e) => return Err(e),
             };
 
@@ -361,9 +286,9 @@ impl WhiteboxTool for IhsToRgb {
             output_g.add_metadata_entry(format!("Input saturation image file: {}", saturation_file));
             output_g.add_metadata_entry(format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
 
-            if verbose { println!("Saving green data...") };
+            if verbose { sink.message("Saving green data...") };
             let _ = match output_g.write() {
-                Ok(_) => if verbose { println!("Output file written") },
+                Ok(_) => if verbose { sink.message("Output file written") },
                 Err(e) => return Err(e),
             };
 
@@ -373,13 +298,13 @@ impl WhiteboxTool for IhsToRgb {
             output_b.add_metadata_entry(format!("Input saturation image file: {}", saturation_file));
             output_b.add_metadata_entry(format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
 
-            if verbose { println!("Saving blue data...") };
+            if verbose { sink.message("Saving blue data...") };
             let _ = match output_b.write() {
-                Ok(_) => if verbose { println!("Output file written") },
+                Ok(_) => if verbose { sink.message("Output file written") },
                 Err(e) => return Err(e),
             };
 
-            println!("{}", &format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
+            sink.message(&format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
 
         } else {
             let mut output = Raster::initialize_using_file(&composite_file, &input_i);
@@ -398,12 +323,10 @@ impl WhiteboxTool for IhsToRgb {
                     out_data[col as usize] = (alpha_mask | (b << 16) | (g << 8) | r) as f64;
                 }
                 output.set_row_data(data.0, out_data);
-                if verbose {
-                    progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as usize;
-                    if progress != old_progress {
-                        println!("Progress: {}%", progress);
-                        old_progress = progress;
-                    }
+                progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as usize;
+                if progress != old_progress {
+                    sink.progress("Progress", progress)?;
+                    old_progress = progress;
                 }
             }
 
@@ -416,13 +339,13 @@ impl WhiteboxTool for IhsToRgb {
             output.add_metadata_entry(format!("Input saturation image file: {}", saturation_file));
             output.add_metadata_entry(format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
 
-            if verbose { println!("Saving red data...") };
+            if verbose { sink.message("Saving red data...") };
             let _ = match output.write() {
-                Ok(_) => if verbose { println!("Output file written") },
+                Ok(_) => if verbose { sink.message("Output file written") },
                 Err(e) => return Err(e),
             };
             if verbose {
-                println!("{}", &format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
+                sink.message(&format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
             }
         }
 
@@ -2,7 +2,7 @@
 This tool is part of the WhiteboxTools geospatial analysis library.
 Authors: Dr. John Lindsay
 Created: Dec. 27, 2017
-Last Modified: Dec. 27, 2017
+Last Modified: April 6, 2018
 License: MIT
 */
 extern crate time;
@@ -22,6 +22,7 @@ use std::thread;
 use raster::*;
 use std::io::{Error, ErrorKind};
 use tools::*;
+use tools::arg_parser::ArgValues;
 use self::rand::distributions::{IndependentSample, Range};
 use rendering::html::*;
 
@@ -43,7 +44,7 @@ impl KMeansClustering {
         parameters.push(ToolParameter{
             name: "Input Files".to_owned(), 
             flags: vec!["-i".to_owned(), "--inputs".to_owned()], 
-            description: "Input raster files.".to_owned(),
+            description: "Input raster files; multi-band images contribute each of their bands.".to_owned(),
             parameter_type: ParameterType::FileList(ParameterFileType::Raster),
             default_value: None,
             optional: false
@@ -159,89 +160,28 @@ impl WhiteboxTool for KMeansClustering {
         self.toolbox.clone()
     }
 
-    fn run<'a>(&self, args: Vec<String>, working_directory: &'a str, verbose: bool) -> Result<(), Error> {
-        let mut input_files_str = String::new();
-        let mut output_file = String::new();
-        let mut output_html_file = String::new();
-        let mut num_classes = 0usize;
-        let mut max_iterations = 10usize;
-        let mut percent_changed_threshold = 5f64;
-        let mut initialization_mode = 1;
-        let mut min_class_size = 10;
-        
+    fn run<'a>(&self, args: Vec<String>, working_directory: &'a str, verbose: bool, sink: &Arc<ProgressSink>) -> Result<(), Error> {
         if args.len() == 0 {
             return Err(Error::new(ErrorKind::InvalidInput,
                                 "Tool run with no paramters."));
         }
-        for i in 0..args.len() {
-            let mut arg = args[i].replace("\"", "");
-            arg = arg.replace("\'", "");
-            let cmd = arg.split("="); // in case an equals sign was used
-            let vec = cmd.collect::<Vec<&str>>();
-            let mut keyval = false;
-            if vec.len() > 1 {
-                keyval = true;
-            }
-            let flag_val = vec[0].to_lowercase().replace("--", "-");
-            if flag_val == "-i" || flag_val == "-inputs" {
-                if keyval {
-                    input_files_str = vec[1].to_string();
-                } else {
-                    input_files_str = args[i+1].to_string();
-                }
-            } else if flag_val == "-o" || flag_val == "-output" {
-                if keyval {
-                    output_file = vec[1].to_string();
-                } else {
-                    output_file = args[i+1].to_string();
-                }
-            } else if flag_val == "-out_html" {
-                if keyval {
-                    output_html_file = vec[1].to_string();
-                } else {
-                    output_html_file = args[i+1].to_string();
-                }
-            } else if flag_val == "-classes" {
-                if keyval {
-                    num_classes = vec[1].to_string().parse::<usize>().unwrap();
-                } else {
-                    num_classes = args[i + 1].to_string().parse::<usize>().unwrap();
-                }
-            } else if flag_val == "-max_iterations" {
-                if keyval {
-                    max_iterations = vec[1].to_string().parse::<usize>().unwrap();
-                } else {
-                    max_iterations = args[i + 1].to_string().parse::<usize>().unwrap();
-                }
-            } else if flag_val == "-class_change" {
-                if keyval {
-                    percent_changed_threshold = vec[1].to_string().parse::<f64>().unwrap();
-                } else {
-                    percent_changed_threshold = args[i + 1].to_string().parse::<f64>().unwrap();
-                }
-            } else if flag_val == "-initialize" {
-                if keyval {
-                    if vec[1].to_string().to_lowercase().contains("rand") {
-                        initialization_mode = 0;
-                    }
-                } else {
-                    if args[i + 1].to_string().to_lowercase().contains("diag") {
-                        initialization_mode = 1;
-                    }
-                }
-            } else if flag_val == "-min_class_size" {
-                if keyval {
-                    min_class_size = vec[1].to_string().parse::<usize>().unwrap();
-                } else {
-                    min_class_size = args[i + 1].to_string().parse::<usize>().unwrap();
-                }
-            }
-        }
+        let args = ArgValues::parse(&self.parameters, &args)?;
+        let input_files_str = args.required_string("--inputs")?;
+        let output_file = args.required_file("--output", working_directory)?;
+        let mut output_html_file = args.get_file("--out_html", working_directory)?.unwrap_or(String::new());
+        let num_classes = args.get_usize("--classes")?.unwrap_or(0usize);
+        let max_iterations = args.get_usize("--max_iterations")?.unwrap_or(10usize);
+        let percent_changed_threshold = args.get_f64("--class_change")?.unwrap_or(5f64);
+        let initialization_mode = match args.get_string("--initialize")? {
+            Some(ref mode) if mode.to_lowercase().contains("rand") => 0,
+            _ => 1,
+        };
+        let min_class_size = args.get_usize("--min_class_size")?.unwrap_or(10);
 
         if verbose {
-            println!("***************{}", "*".repeat(self.get_tool_name().len()));
-            println!("* Welcome to {} *", self.get_tool_name());
-            println!("***************{}", "*".repeat(self.get_tool_name().len()));
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
+            sink.message(&format!("* Welcome to {} *", self.get_tool_name()));
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
         }
 
         let sep: String = path::MAIN_SEPARATOR.to_string();
@@ -249,15 +189,7 @@ impl WhiteboxTool for KMeansClustering {
         let mut progress: usize;
         let mut old_progress: usize = 1;
 
-        if !output_file.contains(&sep) && !output_file.contains("/") {
-            output_file = format!("{}{}", working_directory, output_file);
-        }
-
-        if !output_html_file.contains(&sep) {
-            output_html_file = format!("{}{}", working_directory, output_html_file);
-        }
-
-        if !output_html_file.ends_with(".html") {
+        if !output_html_file.is_empty() && !output_html_file.ends_with(".html") {
             output_html_file.push_str(".html");
         }
 
@@ -267,12 +199,6 @@ impl WhiteboxTool for KMeansClustering {
             cmd = input_files_str.split(",");
             input_files = cmd.collect::<Vec<&str>>();
         }
-        let num_files = input_files.len();
-        if num_files < 2 {
-            return Err(Error::new(ErrorKind::InvalidInput,
-                                "There is something incorrect about the input files. At least two inputs are required to operate this tool."));
-        }
-
         if max_iterations < 2 || max_iterations > 250 {
             return Err(Error::new(ErrorKind::InvalidInput,
                 "Maximum iterations should be between 2 and 250."));
@@ -288,41 +214,60 @@ impl WhiteboxTool for KMeansClustering {
         let mut rows = -1isize;
         let mut columns = -1isize;
         
-        let mut nodata: Vec<f64> = Vec::with_capacity(num_files);
-        let mut minimum: Vec<f64> = Vec::with_capacity(num_files);
-        let mut maximum: Vec<f64> = Vec::with_capacity(num_files);
-        let mut input_raster: Vec<Raster> = Vec::with_capacity(num_files);
-
-        for i in 0..num_files {
-            println!("Reading file {} of {}", i+1, num_files);
+        // Stacked multi-band images contribute each of their bands as a separate input.
+        let mut input_raster: Vec<Raster> = vec![];
+        let mut image_names: Vec<String> = vec![];
+        for i in 0..input_files.len() {
+            sink.message(&format!("Reading file {} of {}", i+1, input_files.len()));
             if !input_files[i].trim().is_empty() {
-                
                 let mut input_file = input_files[i].trim().to_owned();
                 if !input_file.contains(&sep) && !input_file.contains("/") {
                     input_file = format!("{}{}", working_directory, input_file);
                 }
-                input_raster.push(Raster::new(&input_file, "r")?);
-                nodata.push(input_raster[i].configs.nodata);
-                minimum.push(input_raster[i].configs.minimum);
-                maximum.push(input_raster[i].configs.maximum);
-
-                if rows == -1 || columns == -1 {
-                    rows = input_raster[i].configs.rows as isize;
-                    columns = input_raster[i].configs.columns as isize;
-                    if num_classes < 2 || num_classes as isize > (rows * columns) {
-                        return Err(Error::new(ErrorKind::InvalidInput,
-                            "Number of classes should be between 2 and rows x columns."));
-                    }
-                    if min_class_size > ((rows * columns) as usize / num_classes) {
-                        return Err(Error::new(ErrorKind::InvalidInput,
-                            "Min class size should be less than rows x columns / num_classes."));
+                let input = Raster::new(&input_file, "r")?;
+                if input.num_bands() > 1 {
+                    for band in 0..input.num_bands() {
+                        input_raster.push(input.get_band(band)?);
+                        image_names.push(format!("{}:{}", input_files[i].trim(), band + 1));
                     }
                 } else {
-                    if input_raster[i].configs.rows as isize != rows ||
-                        input_raster[i].configs.columns as isize != columns {
-                        return Err(Error::new(ErrorKind::InvalidInput,
-                            "All input images must share the same dimensions (rows and columns) and spatial extent."));
-                    }
+                    input_raster.push(input);
+                    image_names.push(input_files[i].trim().to_owned());
+                }
+            }
+        }
+
+        let num_files = input_raster.len();
+        if num_files < 2 {
+            return Err(Error::new(ErrorKind::InvalidInput,
+                                "There is something incorrect about the input files. At least two input bands, either as separate files or as a multi-band image, are required to operate this tool."));
+        }
+
+        let mut nodata: Vec<f64> = Vec::with_capacity(num_files);
+        let mut minimum: Vec<f64> = Vec::with_capacity(num_files);
+        let mut maximum: Vec<f64> = Vec::with_capacity(num_files);
+
+        for i in 0..num_files {
+            nodata.push(input_raster[i].configs.nodata);
+            minimum.push(input_raster[i].configs.minimum);
+            maximum.push(input_raster[i].configs.maximum);
+
+            if rows == -1 || columns == -1 {
+                rows = input_raster[i].configs.rows as isize;
+                columns = input_raster[i].configs.columns as isize;
+                if num_classes < 2 || num_classes as isize > (rows * columns) {
+                    return Err(Error::new(ErrorKind::InvalidInput,
+                        "Number of classes should be between 2 and rows x columns."));
+                }
+                if min_class_size > ((rows * columns) as usize / num_classes) {
+                    return Err(Error::new(ErrorKind::InvalidInput,
+                        "Min class size should be less than rows x columns / num_classes."));
+                }
+            } else {
+                if input_raster[i].configs.rows as isize != rows ||
+                    input_raster[i].configs.columns as isize != columns {
+                    return Err(Error::new(ErrorKind::InvalidInput,
+                        "All input images must share the same dimensions (rows and columns) and spatial extent."));
                 }
             }
         }
@@ -442,8 +387,9 @@ impl WhiteboxTool for KMeansClustering {
                         if !n_counted { n += 1f64; }
                         which_class = data[col as usize] as usize;
                         z = output.get_value(row, col);
-                        class = z as usize - 1usize;
-                        if z == out_nodata || which_class != class {
+                        // cells that have not yet been classified hold the NoData value
+                        class = if z == out_nodata { num_classes } else { z as usize - 1usize };
+                        if which_class != class {
                             cells_changed += 1f64;
                             output.set_value(row, col, which_class as f64 + 1f64);
                         }
@@ -460,12 +406,10 @@ impl WhiteboxTool for KMeansClustering {
                     }
                 }
                 
-                if verbose {
-                    progress = (100.0_f64 * r as f64 / (rows - 1) as f64) as usize;
-                    if progress != old_progress {
-                        println!("Progress (loop {} of {}): {}%", loop_num+1, max_iterations, progress);
-                        old_progress = progress;
-                    }
+                progress = (100.0_f64 * r as f64 / (rows - 1) as f64) as usize;
+                if progress != old_progress {
+                    sink.progress(&format!("Progress (loop {} of {})", loop_num+1, max_iterations), progress)?;
+                    old_progress = progress;
                 }
             }
 
@@ -553,10 +497,10 @@ impl WhiteboxTool for KMeansClustering {
                 }
             }
 
-            println!("Cluster sizes: {:?}", class_n);
+            sink.message(&format!("Cluster sizes: {:?}", class_n));
 
             percent_changed = 100f64 *  cells_changed / n;
-            println!("Cells changed {} ({:.4} percent)", cells_changed, percent_changed);
+            sink.message(&format!("Cells changed {} ({:.4} percent)", cells_changed, percent_changed));
             if percent_changed < percent_changed_threshold { break; }
         }
         
@@ -577,13 +521,13 @@ impl WhiteboxTool for KMeansClustering {
         }
         output.add_metadata_entry(format!("Elapsed Time (including I/O): {}", elapsed_time).replace("PT", ""));
 
-        if verbose { println!("Saving data...") };
+        if verbose { sink.message("Saving data...") };
         let _ = match output.write() {
-            Ok(_) => if verbose { println!("Output file written") },
+            Ok(_) => if verbose { sink.message("Output file written") },
             Err(e) => return Err(e),
         };
         if verbose {
-            println!("{}", &format!("Elapsed Time (including I/O): {}", elapsed_time).replace("PT", ""));
+            sink.message(&format!("Elapsed Time (including I/O): {}", elapsed_time).replace("PT", ""));
         }
         
         if !output_html_file.trim().is_empty() {
@@ -607,7 +551,7 @@ impl WhiteboxTool for KMeansClustering {
 
             writer.write_all(&format!("<strong>Num. bands</strong>: {}<br>", num_files).as_bytes())?;
             for i in 0..num_files {
-                writer.write_all(&format!("<strong>Image {}</strong>: {}<br>", i+1, input_files[i].clone()).as_bytes())?;
+                writer.write_all(&format!("<strong>Image {}</strong>: {}<br>", i+1, image_names[i]).as_bytes())?;
             }
             writer.write_all(&format!("<strong>Num. clusters</strong>: {}<br>", num_classes).as_bytes())?;
             writer.write_all(&format!("<strong>Max. iterations</strong>: {}<br>", max_iterations).as_bytes())?;
@@ -715,10 +659,105 @@ impl WhiteboxTool for KMeansClustering {
                     let _ = output.stdout;
                 }
 
-                println!("Complete! Please see {} for output.", output_html_file);
+                sink.message(&format!("Complete! Please see {} for output.", output_html_file));
             }
         }
 
         Ok(())
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    // the value of a band of an image of four clusters of values, with some noise
+    fn band_value(band: usize, row: usize, col: usize, seed: &mut u64) -> f64 {
+        let centres = [[10.0, 200.0, 50.0], [60.0, 20.0, 50.0], [110.0, 120.0, 150.0], [160.0, 60.0, 250.0]];
+        *seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+        let noise = (*seed >> 11) as f64 / (1u64 << 53) as f64 * 8.0 - 4.0;
+        centres[(row / 10) * 2 + col / 15][band] + noise
+    }
+
+    fn run(wd: &str, inputs: &str, output: &str) -> Raster {
+        let args = vec![format!("-i={}", inputs), format!("-o={}", output), "--out_html=report.html".to_string(),
+            "--classes=4".to_string(), "--max_iterations=20".to_string(), "--class_change=0".to_string(),
+            "--initialize=diagonal".to_string(), "--min_class_size=10".to_string()];
+        let mut sink = StdoutProgress::new();
+        sink.set_quiet(true);
+        let sink: Arc<ProgressSink> = Arc::new(sink);
+        KMeansClustering::new().run(args, wd, false, &sink).unwrap();
+        Raster::new(&format!("{}{}", wd, output), "r").unwrap()
+    }
+
+    #[test]
+    fn the_bands_of_a_multiband_image_are_clustered_as_separate_images() {
+        let dir = env::temp_dir().join("wbt_k_means_bands");
+        let _ = fs::create_dir_all(&dir);
+        let wd = format!("{}{}", dir.to_str().unwrap(), path::MAIN_SEPARATOR);
+        let mut configs = RasterConfigs { ..Default::default() };
+        configs.rows = 20;
+        configs.columns = 30;
+        configs.nodata = -32768f64;
+        configs.north = 20f64;
+        configs.south = 0f64;
+        configs.west = 0f64;
+        configs.east = 30f64;
+        configs.resolution_x = 1f64;
+        configs.resolution_y = 1f64;
+        configs.data_type = DataType::F32;
+        let mut bands = vec![];
+        let mut seed = 5u64;
+        for band in 0..3 {
+            let mut r = Raster::initialize_using_config(&format!("{}band{}.tif", wd, band + 1), &configs);
+            for row in 0..20 {
+                for col in 0..30 {
+                    let value = if row == 3 && col == band { -32768f64 } else { band_value(band, row, col, &mut seed) };
+                    r.set_value(row as isize, col as isize, value);
+                }
+            }
+            r.write().unwrap();
+            bands.push(r);
+        }
+        configs.bands = 3;
+        let mut stack = Raster::initialize_using_config(&format!("{}stack.tif", wd), &configs);
+        for band in 0..3 {
+            for row in 0..20 {
+                for col in 0..30 {
+                    stack.set_band_value(band, row, col, bands[band].get_value(row, col));
+                }
+            }
+        }
+        stack.write().unwrap();
+
+        let separate = run(&wd, "band1.tif;band2.tif;band3.tif", "separate.tif");
+        let stacked = run(&wd, "stack.tif", "stacked.tif");
+        let mut classes = vec![];
+        for row in 0..20 {
+            for col in 0..30 {
+                let z = separate.get_value(row, col);
+                assert_eq!(stacked.get_value(row, col), z, "({}, {})", row, col);
+                if row == 3 && col < 3 {
+                    assert_eq!(z, separate.configs.nodata);
+                } else {
+                    let cluster = (row as usize / 10) * 2 + col as usize / 15;
+                    // each cluster is given a class of its own
+                    if classes.len() <= cluster {
+                        classes.push(z);
+                    } else {
+                        assert_eq!(classes[cluster], z);
+                    }
+                }
+            }
+        }
+        classes.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        classes.dedup();
+        assert_eq!(classes.len(), 4);
+
+        // the report names the bands of the image
+        let mut report = String::new();
+        File::open(dir.join("report.html")).unwrap().read_to_string(&mut report).unwrap();
+        assert!(report.contains("stack.tif:3"), "{}", report);
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -2,7 +2,7 @@
 This tool is part of the WhiteboxTools geospatial analysis library.
 Authors: Dr. John Lindsay
 Created: Dec. 30, 2017
-Last Modified: Dec. 30, 2017
+Last Modified: March 14, 2018
 License: MIT
 
 Notes: This modified k-means algorithm is similar to that described by Mather (2004). 
@@ -30,6 +30,7 @@ use std::thread;
 use raster::*;
 use std::io::{Error, ErrorKind};
 use tools::*;
+use tools::arg_parser::ArgValues;
 use self::rand::distributions::{IndependentSample, Range};
 use rendering::html::*;
 
@@ -50,8 +51,8 @@ impl ModifiedKMeansClustering {
         let mut parameters = vec![];
         parameters.push(ToolParameter{
             name: "Input Files".to_owned(), 
-            flags: vec!["-i".to_owned(), "--inputs".to_owned()], 
-            description: "Input raster files.".to_owned(),
+            flags: vec!["-i".to_owned(), "--input".to_owned(), "--inputs".to_owned()], 
+            description: "Input raster files; multi-band images contribute each of their bands.".to_owned(),
             parameter_type: ParameterType::FileList(ParameterFileType::Raster),
             default_value: None,
             optional: false
@@ -176,97 +177,25 @@ impl WhiteboxTool for ModifiedKMeansClustering {
         self.toolbox.clone()
     }
 
-    fn run<'a>(&self, args: Vec<String>, working_directory: &'a str, verbose: bool) -> Result<(), Error> {
-        let mut input_files_str = String::new();
-        let mut output_file = String::new();
-        let mut output_html_file = String::new();
-        let mut num_classes = 1000usize;
-        let mut max_iterations = 10usize;
-        let mut percent_changed_threshold = 5f64;
-        // let mut initialization_mode = 1;
-        // let mut min_class_size = 10;
-        let mut merger_dist = 1f64;
-        
+    fn run<'a>(&self, args: Vec<String>, working_directory: &'a str, verbose: bool, sink: &Arc<ProgressSink>) -> Result<(), Error> {
         if args.len() == 0 {
             return Err(Error::new(ErrorKind::InvalidInput,
                                 "Tool run with no paramters."));
         }
-        for i in 0..args.len() {
-            let mut arg = args[i].replace("\"", "");
-            arg = arg.replace("\'", "");
-            let cmd = arg.split("="); // in case an equals sign was used
-            let vec = cmd.collect::<Vec<&str>>();
-            let mut keyval = false;
-            if vec.len() > 1 {
-                keyval = true;
-            }
-            let flag_val = vec[0].to_lowercase().replace("--", "-");
-            if flag_val == "-i" || flag_val == "-inputs" || flag_val == "-input" {
-                if keyval {
-                    input_files_str = vec[1].to_string();
-                } else {
-                    input_files_str = args[i+1].to_string();
-                }
-            } else if flag_val == "-o" || flag_val == "-output" {
-                if keyval {
-                    output_file = vec[1].to_string();
-                } else {
-                    output_file = args[i+1].to_string();
-                }
-            } else if flag_val == "-out_html" {
-                if keyval {
-                    output_html_file = vec[1].to_string();
-                } else {
-                    output_html_file = args[i+1].to_string();
-                }
-            } else if flag_val == "-start_clusters" {
-                if keyval {
-                    num_classes = vec[1].to_string().parse::<usize>().unwrap();
-                } else {
-                    num_classes = args[i + 1].to_string().parse::<usize>().unwrap();
-                }
-            } else if flag_val == "-merger_dist" {
-                if keyval {
-                    merger_dist = vec[1].to_string().parse::<f64>().unwrap();
-                } else {
-                    merger_dist = args[i + 1].to_string().parse::<f64>().unwrap();
-                }
-                merger_dist *= merger_dist;
-            } else if flag_val == "-max_iterations" {
-                if keyval {
-                    max_iterations = vec[1].to_string().parse::<usize>().unwrap();
-                } else {
-                    max_iterations = args[i + 1].to_string().parse::<usize>().unwrap();
-                }
-            } else if flag_val == "-class_change" {
-                if keyval {
-                    percent_changed_threshold = vec[1].to_string().parse::<f64>().unwrap();
-                } else {
-                    percent_changed_threshold = args[i + 1].to_string().parse::<f64>().unwrap();
-                }
-            // } else if flag_val == "-initialize" {
-            //     if keyval {
-            //         if vec[1].to_string().to_lowercase().contains("rand") {
-            //             initialization_mode = 0;
-            //         }
-            //     } else {
-            //         if args[i + 1].to_string().to_lowercase().contains("diag") {
-            //             initialization_mode = 1;
-            //         }
-            //     }
-            // } else if flag_val == "-min_class_size" {
-            //     if keyval {
-            //         min_class_size = vec[1].to_string().parse::<usize>().unwrap();
-            //     } else {
-            //         min_class_size = args[i + 1].to_string().parse::<usize>().unwrap();
-            //     }
-            }
-        }
+        let args = ArgValues::parse(&self.parameters, &args)?;
+        let input_files_str = args.required_string("--inputs")?;
+        let output_file = args.required_file("--output", working_directory)?;
+        let mut output_html_file = args.get_file("--out_html", working_directory)?.unwrap_or(String::new());
+        let mut num_classes = args.get_usize("--start_clusters")?.unwrap_or(1000usize);
+        let mut merger_dist = args.get_f64("--merger_dist")?.unwrap_or(1f64);
+        merger_dist *= merger_dist;
+        let max_iterations = args.get_usize("--max_iterations")?.unwrap_or(10usize);
+        let percent_changed_threshold = args.get_f64("--class_change")?.unwrap_or(5f64);
 
         if verbose {
-            println!("***************{}", "*".repeat(self.get_tool_name().len()));
-            println!("* Welcome to {} *", self.get_tool_name());
-            println!("***************{}", "*".repeat(self.get_tool_name().len()));
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
+            sink.message(&format!("* Welcome to {} *", self.get_tool_name()));
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
         }
 
         let sep: String = path::MAIN_SEPARATOR.to_string();
@@ -274,15 +203,7 @@ impl WhiteboxTool for ModifiedKMeansClustering {
         let mut progress: usize;
         let mut old_progress: usize = 1;
 
-        if !output_file.contains(&sep) && !output_file.contains("/") {
-            output_file = format!("{}{}", working_directory, output_file);
-        }
-
-        if !output_html_file.contains(&sep) {
-            output_html_file = format!("{}{}", working_directory, output_html_file);
-        }
-
-        if !output_html_file.ends_with(".html") {
+        if !output_html_file.is_empty() && !output_html_file.ends_with(".html") {
             output_html_file.push_str(".html");
         }
 
@@ -292,12 +213,6 @@ impl WhiteboxTool for ModifiedKMeansClustering {
             cmd = input_files_str.split(",");
             input_files = cmd.collect::<Vec<&str>>();
         }
-        let num_files = input_files.len();
-        if num_files < 2 {
-            return Err(Error::new(ErrorKind::InvalidInput,
-                                "There is something incorrect about the input files. At least two inputs are required to operate this tool."));
-        }
-
         if max_iterations < 2 || max_iterations > 250 {
             return Err(Error::new(ErrorKind::InvalidInput,
                 "Maximum iterations should be between 2 and 250."));
@@ -318,41 +233,60 @@ impl WhiteboxTool for ModifiedKMeansClustering {
         let mut rows = -1isize;
         let mut columns = -1isize;
         
-        let mut nodata: Vec<f64> = Vec::with_capacity(num_files);
-        let mut minimum: Vec<f64> = Vec::with_capacity(num_files);
-        let mut maximum: Vec<f64> = Vec::with_capacity(num_files);
-        let mut input_raster: Vec<Raster> = Vec::with_capacity(num_files);
-
-        for i in 0..num_files {
-            println!("Reading file {} of {}", i+1, num_files);
+        // Stacked multi-band images contribute each of their bands as a separate input.
+        let mut input_raster: Vec<Raster> = vec![];
+        let mut image_names: Vec<String> = vec![];
+        for i in 0..input_files.len() {
+            sink.message(&format!("Reading file {} of {}", i+1, input_files.len()));
             if !input_files[i].trim().is_empty() {
-                
                 let mut input_file = input_files[i].trim().to_owned();
                 if !input_file.contains(&sep) && !input_file.contains("/") {
                     input_file = format!("{}{}", working_directory, input_file);
                 }
-                input_raster.push(Raster::new(&input_file, "r")?);
-                nodata.push(input_raster[i].configs.nodata);
-                minimum.push(input_raster[i].configs.minimum);
-                maximum.push(input_raster[i].configs.maximum);
-
-                if rows == -1 || columns == -1 {
-                    rows = input_raster[i].configs.rows as isize;
-                    columns = input_raster[i].configs.columns as isize;
-                    if num_classes < 2 || num_classes as isize > (rows * columns) {
-                        return Err(Error::new(ErrorKind::InvalidInput,
-                            "Number of classes should be between 2 and rows x columns."));
+                let input = Raster::new(&input_file, "r")?;
+                if input.num_bands() > 1 {
+                    for band in 0..input.num_bands() {
+                        input_raster.push(input.get_band(band)?);
+                        image_names.push(format!("{}:{}", input_files[i].trim(), band + 1));
                     }
-                    // if min_class_size > ((rows * columns) as usize / num_classes) {
-                    //     return Err(Error::new(ErrorKind::InvalidInput,
-                    //         "Min class size should be less than rows x columns / num_classes."));
-                    // }
                 } else {
-                    if input_raster[i].configs.rows as isize != rows ||
-                        input_raster[i].configs.columns as isize != columns {
-                        return Err(Error::new(ErrorKind::InvalidInput,
-                            "All input images must share the same dimensions (rows and columns) and spatial extent."));
-                    }
+                    input_raster.push(input);
+                    image_names.push(input_files[i].trim().to_owned());
+                }
+            }
+        }
+
+        let num_files = input_raster.len();
+        if num_files < 2 {
+            return Err(Error::new(ErrorKind::InvalidInput,
+                                "There is something incorrect about the input files. At least two input bands, either as separate files or as a multi-band image, are required to operate this tool."));
+        }
+
+        let mut nodata: Vec<f64> = Vec::with_capacity(num_files);
+        let mut minimum: Vec<f64> = Vec::with_capacity(num_files);
+        let mut maximum: Vec<f64> = Vec::with_capacity(num_files);
+
+        for i in 0..num_files {
+            nodata.push(input_raster[i].configs.nodata);
+            minimum.push(input_raster[i].configs.minimum);
+            maximum.push(input_raster[i].configs.maximum);
+
+            if rows == -1 || columns == -1 {
+                rows = input_raster[i].configs.rows as isize;
+                columns = input_raster[i].configs.columns as isize;
+                if num_classes < 2 || num_classes as isize > (rows * columns) {
+                    return Err(Error::new(ErrorKind::InvalidInput,
+                        "Number of classes should be between 2 and rows x columns."));
+                }
+                // if min_class_size > ((rows * columns) as usize / num_classes) {
+                //     return Err(Error::new(ErrorKind::InvalidInput,
+                //         "Min class size should be less than rows x columns / num_classes."));
+                // }
+            } else {
+                if input_raster[i].configs.rows as isize != rows ||
+                    input_raster[i].configs.columns as isize != columns {
+                    return Err(Error::new(ErrorKind::InvalidInput,
+                        "All input images must share the same dimensions (rows and columns) and spatial extent."));
                 }
             }
         }
@@ -479,12 +413,10 @@ impl WhiteboxTool for ModifiedKMeansClustering {
                     }
                 }
                 
-                if verbose {
-                    progress = (100.0_f64 * r as f64 / (rows - 1) as f64) as usize;
-                    if progress != old_progress {
-                        println!("Progress (loop {} of {}): {}%", loop_num + 1, max_iterations, progress);
-                        old_progress = progress;
-                    }
+                progress = (100.0_f64 * r as f64 / (rows - 1) as f64) as usize;
+                if progress != old_progress {
+                    sink.progress(&format!("Progress (loop {} of {})", loop_num + 1, max_iterations), progress)?;
+                    old_progress = progress;
                 }
             }
 
@@ -539,10 +471,10 @@ impl WhiteboxTool for ModifiedKMeansClustering {
 
             num_classes = class_n.len();
 
-            println!("Number of clusters: {}", num_classes);
+            sink.message(&format!("Number of clusters: {}", num_classes));
 
             percent_changed = 100f64 *  cells_changed / n;
-            println!("Cells changed {} ({:.4} percent)", cells_changed, percent_changed);
+            sink.message(&format!("Cells changed {} ({:.4} percent)", cells_changed, percent_changed));
             if percent_changed < percent_changed_threshold { break; }
         }
         
@@ -564,13 +496,13 @@ impl WhiteboxTool for ModifiedKMeansClustering {
         // }
         output.add_metadata_entry(format!("Elapsed Time (including I/O): {}", elapsed_time).replace("PT", ""));
 
-        if verbose { println!("Saving data...") };
+        if verbose { sink.message("Saving data...") };
         let _ = match output.write() {
-            Ok(_) => if verbose { println!("Output file written") },
+            Ok(_) => if verbose { sink.message("Output file written") },
             Err(e) => return Err(e),
         };
         if verbose {
-            println!("{}", &format!("Elapsed Time (including I/O): {}", elapsed_time).replace("PT", ""));
+            sink.message(&format!("Elapsed Time (including I/O): {}", elapsed_time).replace("PT", ""));
         }
         
         if !output_html_file.trim().is_empty() {
@@ -594,7 +526,7 @@ impl WhiteboxTool for ModifiedKMeansClustering {
 
             writer.write_all(&format!("<strong>Num. bands</strong>: {}<br>", num_files).as_bytes())?;
             for i in 0..num_files {
-                writer.write_all(&format!("<strong>Image {}</strong>: {}<br>", i+1, input_files[i].clone()).as_bytes())?;
+                writer.write_all(&format!("<strong>Image {}</strong>: {}<br>", i+1, image_names[i]).as_bytes())?;
             }
             writer.write_all(&format!("<strong>Num. clusters</strong>: {}<br>", num_classes).as_bytes())?;
             writer.write_all(&format!("<strong>Max. iterations</strong>: {}<br>", max_iterations).as_bytes())?;
@@ -702,7 +634,7 @@ impl WhiteboxTool for ModifiedKMeansClustering {
                     let _ = output.stdout;
                 }
 
-                println!("Complete! Please see {} for output.", output_html_file);
+                sink.message(&format!("Complete! Please see {} for output.", output_html_file));
             }
         }
 
@@ -35,6 +35,7 @@ use std::thread;
 use raster::*;
 use std::io::{Error, ErrorKind};
 use tools::*;
+use tools::arg_parser::ArgValues;
 
 pub struct Mosaic {
     name: String,
@@ -125,57 +126,27 @@ impl WhiteboxTool for Mosaic {
         self.toolbox.clone()
     }
 
-    fn run<'a>(&self, args: Vec<String>, working_directory: &'a str, verbose: bool) -> Result<(), Error> {
-        let mut input_files = String::new();
-        let mut output_file = String::new();
-        let mut method = String::from("cc");
-        
+    fn run<'a>(&self, args: Vec<String>, working_directory: &'a str, verbose: bool, sink: &Arc<ProgressSink>) -> Result<(), Error> {
         if args.len() == 0 {
             return Err(Error::new(ErrorKind::InvalidInput,
                                 "Tool run with no paramters."));
         }
-        for i in 0..args.len() {
-            let mut arg = args[i].replace("\"", "");
-            arg = arg.replace("\'", "");
-            let cmd = arg.split("="); // in case an equals sign was used
-            let vec = cmd.collect::<Vec<&str>>();
-            let mut keyval = false;
-            if vec.len() > 1 {
-                keyval = true;
-            }
-            let flag_val = vec[0].to_lowercase().replace("--", "-");
-            if flag_val == "-i" || flag_val == "-inputs" {
-                input_files = if keyval {
-                    vec[1].to_string()
-                } else {
-                    args[i+1].to_string()
-                };
-            } else if flag_val == "-o" || flag_val == "-output" {
-                output_file = if keyval {
-                    vec[1].to_string()
-                } else {
-                    args[i+1].to_string()
-                };
-            } else if flag_val == "-method" {
-                method = if keyval {
-                    vec[1].to_string()
-                } else {
-                    args[i+1].to_string()
-                };
-                if method.to_lowercase().contains("nn") || method.to_lowercase().contains("nearest") {
-                    method = "nn".to_string();
-                } else if method.to_lowercase().contains("bilinear") || method.to_lowercase().contains("bi") {
-                    method = "bilinear".to_string();
-                } else if method.to_lowercase().contains("cc") || method.to_lowercase().contains("cubic") {
-                    method = "cc".to_string();
-                }
-            }
+        let args = ArgValues::parse(&self.parameters, &args)?;
+        let input_files = args.required_string("--inputs")?;
+        let output_file = args.required_file("--output", working_directory)?;
+        let mut method = args.get_string("--method")?.unwrap_or(String::from("cc"));
+        if method.to_lowercase().contains("nn") || method.to_lowercase().contains("nearest") {
+            method = "nn".to_string();
+        } else if method.to_lowercase().contains("bilinear") || method.to_lowercase().contains("bi") {
+            method = "bilinear".to_string();
+        } else if method.to_lowercase().contains("cc") || method.to_lowercase().contains("cubic") {
+            method = "cc".to_string();
         }
 
         if verbose {
-            println!("***************{}", "*".repeat(self.get_tool_name().len()));
-            println!("* Welcome to {} *", self.get_tool_name());
-            println!("***************{}", "*".repeat(self.get_tool_name().len()));
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
+            sink.message(&format!("* Welcome to {} *", self.get_tool_name()));
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
         }
 
         let sep: String = path::MAIN_SEPARATOR.to_string();
@@ -183,10 +154,6 @@ impl WhiteboxTool for Mosaic {
         let mut progress: usize;
         let mut old_progress: usize = 1;
 
-        if !output_file.contains(&sep) && !output_file.contains("/") {
-            output_file = format!("{}{}", working_directory, output_file);
-        }
-
         let mut cmd = input_files.split(";");
         let mut input_vec = cmd.collect::<Vec<&str>>();
         if input_vec.len() == 1 {
@@ -202,7 +169,7 @@ impl WhiteboxTool for Mosaic {
         let start = time::now();
 
         // read the input files
-        if verbose { println!("Reading data...") };
+        if verbose { sink.message("Reading data...") };
         let mut inputs: Vec<Raster> = Vec::with_capacity(num_files);
         let mut nodata_vals: Vec<f64> = Vec::with_capacity(num_files);
         let mut north = f64::NEG_INFINITY;
@@ -340,12 +307,10 @@ impl WhiteboxTool for Mosaic {
                         output.set_value(row, col, data[col as usize]);
                     }
                 }
-                if verbose {
-                    progress = (100.0_f64 * r as f64 / (rows - 1) as f64) as usize;
-                    if progress != old_progress {
-                        println!("Progress: {}%", progress);
-                        old_progress = progress;
-                    }
+                progress = (100.0_f64 * r as f64 / (rows - 1) as f64) as usize;
+                if progress != old_progress {
+                    sink.progress("Progress", progress)?;
+                    old_progress = progress;
                 }
             }
         } else if method == "cc" {
@@ -423,12 +388,10 @@ impl WhiteboxTool for Mosaic {
                         output.set_value(row, col as isize, data[col]);
                     }
                 }
-                if verbose {
-                    progress = (100.0_f64 * r as f64 / (rows - 1) as f64) as usize;
-                    if progress != old_progress {
-                        println!("Progress: {}%", progress);
-                        old_progress = progress;
-                    }
+                progress = (100.0_f64 * r as f64 / (rows - 1) as f64) as usize;
+                if progress != old_progress {
+                    sink.progress("Progress", progress)?;
+                    old_progress = progress;
                 }
             }
 
@@ -504,12 +467,10 @@ impl WhiteboxTool for Mosaic {
                         output.set_value(row, col as isize, data[col]);
                     }
                 }
-                if verbose {
-                    progress = (100.0_f64 * r as f64 / (rows - 1) as f64) as usize;
-                    if progress != old_progress {
-                        println!("Progress: {}%", progress);
-                        old_progress = progress;
-                    }
+                progress = (100.0_f64 * r as f64 / (rows - 1) as f64) as usize;
+                if progress != old_progress {
+                    sink.progress("Progress", progress)?;
+                    old_progress = progress;
                 }
             }
         }
@@ -519,13 +480,13 @@ impl WhiteboxTool for Mosaic {
         output.add_metadata_entry(format!("Modified by whitebox_tools\' {} tool", self.get_tool_name()));
         output.add_metadata_entry(format!("Resampling method: {}", method));
         
-        if verbose { println!("Saving data...") };
+        if verbose { sink.message("Saving data...") };
         let _ = match output.write() {
-            Ok(_) => if verbose { println!("Output file written") },
+            Ok(_) => if verbose { sink.message("Output file written") },
             Err(e) => return Err(e),
         };
         if verbose {
-            println!("{}", &format!("Elapsed Time (including I/O): {}", elapsed_time).replace("PT", ""));
+            sink.message(&format!("Elapsed Time (including I/O): {}", elapsed_time).replace("PT", ""));
         }
 
         Ok(())
@@ -17,6 +17,7 @@ use std::thread;
 use raster::*;
 use std::io::{Error, ErrorKind};
 use tools::*;
+use tools::arg_parser::ArgValues;
 
 pub struct NormalizedDifferenceVegetationIndex {
     name: String,
@@ -133,79 +134,30 @@ impl WhiteboxTool for NormalizedDifferenceVegetationIndex {
         self.toolbox.clone()
     }
 
-    fn run<'a>(&self, args: Vec<String>, working_directory: &'a str, verbose: bool) -> Result<(), Error> {
-        let mut nir_file = String::new();
-        let mut red_file = String::new();
-        let mut output_file = String::new();
-        let mut clip_amount = 0.0;
-        let mut osavi_mode = false;
-        let mut correction_factor = 0.0;
+    fn run<'a>(&self, args: Vec<String>, working_directory: &'a str, verbose: bool, sink: &Arc<ProgressSink>) -> Result<(), Error> {
         if args.len() == 0 {
             return Err(Error::new(ErrorKind::InvalidInput,
                                 "Tool run with no paramters."));
         }
-        for i in 0..args.len() {
-            let mut arg = args[i].replace("\"", "");
-            arg = arg.replace("\'", "");
-            let cmd = arg.split("="); // in case an equals sign was used
-            let vec = cmd.collect::<Vec<&str>>();
-            let mut keyval = false;
-            if vec.len() > 1 {
-                keyval = true;
-            }
-            if vec[0].to_lowercase() == "-nir" || vec[0].to_lowercase() == "--nir" {
-                if keyval {
-                    nir_file = vec[1].to_string();
-                } else {
-                    nir_file = args[i+1].to_string();
-                }
-            } else if vec[0].to_lowercase() == "-red" || vec[0].to_lowercase() == "--red" {
-                if keyval {
-                    red_file = vec[1].to_string();
-                } else {
-                    red_file = args[i+1].to_string();
-                }
-            } else if vec[0].to_lowercase() == "-o" || vec[0].to_lowercase() == "--output" {
-                if keyval {
-                    output_file = vec[1].to_string();
-                } else {
-                    output_file = args[i+1].to_string();
-                }
-            } else if vec[0].to_lowercase() == "-clip" || vec[0].to_lowercase() == "--clip" {
-                if keyval {
-                    clip_amount = vec[1].to_string().parse::<f64>().unwrap();
-                } else {
-                    clip_amount = args[i + 1].to_string().parse::<f64>().unwrap();
-                }
-                if clip_amount < 0.0 { clip_amount == 0.0; }
-            } else if vec[0].to_lowercase() == "-osavi" || vec[0].to_lowercase() == "--osavi" {
-                osavi_mode = true;
-                correction_factor = 0.16;
-            }
-        }
+        let args = ArgValues::parse(&self.parameters, &args)?;
+        let nir_file = args.required_file("--nir", working_directory)?;
+        let red_file = args.required_file("--red", working_directory)?;
+        let output_file = args.required_file("--output", working_directory)?;
+        let mut clip_amount = args.get_f64("--clip")?.unwrap_or(0.0);
+        if clip_amount < 0.0 { clip_amount = 0.0; }
+        let osavi_mode = args.get_bool("--osavi")?;
+        let correction_factor = if osavi_mode { 0.16 } else { 0.0 };
 
         if verbose {
-            println!("***************{}", "*".repeat(self.get_tool_name().len()));
-            println!("* Welcome to {} *", self.get_tool_name());
-            println!("***************{}", "*".repeat(self.get_tool_name().len()));
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
+            sink.message(&format!("* Welcome to {} *", self.get_tool_name()));
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
         }
 
-        let sep: String = path::MAIN_SEPARATOR.to_string();
-
         let mut progress: usize;
         let mut old_progress: usize = 1;
 
-        if !nir_file.contains(&sep) && !nir_file.contains("/") {
-            nir_file = format!("{}{}", working_directory, nir_file);
-        }
-        if !red_file.contains(&sep) && !red_file.contains("/") {
-            red_file = format!("{}{}", working_directory, red_file);
-        }
-        if !output_file.contains(&sep) && !output_file.contains("/") {
-            output_file = format!("{}{}", working_directory, output_file);
-        }
-
-        if verbose { println!("Reading data...") };
+        if verbose { sink.message("Reading data...") };
 
         let nir = Arc::new(Raster::new(&nir_file, "r")?);
         let rows = nir.configs.rows as isize;
@@ -236,8 +188,8 @@ impl WhiteboxTool for NormalizedDifferenceVegetationIndex {
                 for row in (0..rows).filter(|r| r % num_procs == tid) {
                     let mut data = vec![nir_nodata; columns as usize];
                     for col in 0..columns {
-                        z_nir = nir[(row, col)];
-                        z_red = red[(row, col)];
+                        z_nir = nir.get_value(row, col);
+                        z_red = red.get_value(row, col);
                         if z_nir != nir_nodata && z_red != red_nodata {
                             if z_nir + z_red != 0.0 {
                                 data[col as usize] = (z_nir - z_red) / (z_nir + z_red + correction_factor); 
@@ -254,17 +206,15 @@ impl WhiteboxTool for NormalizedDifferenceVegetationIndex {
         for row in 0..rows {
             let data = rx.recv().unwrap();
             output.set_row_data(data.0, data.1);
-            if verbose {
-                progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as usize;
-                if progress != old_progress {
-                    println!("Progress: {}%", progress);
-                    old_progress = progress;
-                }
+            progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as usize;
+            if progress != old_progress {
+                sink.progress("Progress", progress)?;
+                old_progress = progress;
             }
         }
 
         if clip_amount > 0.0 {
-            println!("Clipping output...");
+            sink.message("Clipping output...");
             output.clip_min_and_max_by_percent(clip_amount);
         }
 
@@ -276,13 +226,13 @@ impl WhiteboxTool for NormalizedDifferenceVegetationIndex {
         output.add_metadata_entry(format!("Optimised Soil-Adjusted Vegetation Index (OSAVI) mode: {}", osavi_mode));
         output.add_metadata_entry(format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
 
-        if verbose { println!("Saving data...") };
+        if verbose { sink.message("Saving data...") };
         let _ = match output.write() {
-            Ok(_) => if verbose { println!("Output file written") },
+            Ok(_) => if verbose { sink.message("Output file written") },
             Err(e) => return Err(e),
         };
         if verbose {
-            println!("{}", &format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
+            sink.message(&format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
         }
 
         Ok(())
@@ -2,7 +2,7 @@
 This tool is part of the WhiteboxTools geospatial analysis library.
 Authors: Dr. John Lindsay
 Created: July 27, 2017
-Last Modified: Dec. 15, 2017
+Last Modified: March 14, 2018
 License: MIT
 */
 extern crate time;
@@ -18,6 +18,7 @@ use raster::*;
 use std::io::{Error, ErrorKind};
 use structures::Array2D;
 use tools::*;
+use tools::arg_parser::ArgValues;
 
 /// Tool struct containing the essential descriptors required to interact with the tool.
 pub struct PanchromaticSharpening {
@@ -47,7 +48,7 @@ impl PanchromaticSharpening {
 
         parameters.push(ToolParameter{
             name: "Input Green Band File (optional; only if colour-composite not specified)".to_owned(), 
-            flags: vec!["--green".to_owned()], 
+            flags: vec!["-g".to_owned(), "--green".to_owned()], 
             description: "Input green band image file. Optionally specified if colour-composite not specified.".to_owned(),
             parameter_type: ParameterType::ExistingFile(ParameterFileType::Raster),
             default_value: None,
@@ -56,7 +57,7 @@ impl PanchromaticSharpening {
 
         parameters.push(ToolParameter{
             name: "Input Blue Band File (optional; only if colour-composite not specified)".to_owned(), 
-            flags: vec!["--blue".to_owned()], 
+            flags: vec!["-b".to_owned(), "--blue".to_owned()], 
             description: "Input blue band image file. Optionally specified if colour-composite not specified.".to_owned(),
             parameter_type: ParameterType::ExistingFile(ParameterFileType::Raster),
             default_value: None,
@@ -65,8 +66,8 @@ impl PanchromaticSharpening {
 
         parameters.push(ToolParameter{
             name: "Input Colour-Composite Image File (optional; only if individual bands not specified)".to_owned(), 
-            flags: vec!["--composite".to_owned()], 
-            description: "Input colour-composite image file. Only used if individual bands are not specified.".to_owned(),
+            flags: vec!["-c".to_owned(), "--composite".to_owned()], 
+            description: "Input colour-composite image file, or a multi-band image whose first three bands are used as red, green, and blue. Only used if individual bands are not specified.".to_owned(),
             parameter_type: ParameterType::ExistingFile(ParameterFileType::Raster),
             default_value: None,
             optional: true
@@ -74,7 +75,7 @@ impl PanchromaticSharpening {
 
         parameters.push(ToolParameter{
             name: "Input Panchromatic Band File".to_owned(), 
-            flags: vec!["--pan".to_owned()], 
+            flags: vec!["-p".to_owned(), "--pan".to_owned()], 
             description: "Input panchromatic band file.".to_owned(),
             parameter_type: ParameterType::ExistingFile(ParameterFileType::Raster),
             default_value: None,
@@ -160,116 +161,37 @@ impl WhiteboxTool for PanchromaticSharpening {
     fn run<'a>(&self,
                args: Vec<String>,
                working_directory: &'a str,
-               verbose: bool)
+               verbose: bool,
+               sink: &Arc<ProgressSink>)
                -> Result<(), Error> {
-        let mut red_file = String::new();
-        let mut green_file = String::new();
-        let mut blue_file = String::new();
-        let mut composite_file = String::new();
-        let mut use_composite = false;
-        let mut pan_file = String::new();
-        let mut output_file = String::new();
-        let mut fusion_method = String::from("brovey");
-
         if args.len() == 0 {
             return Err(Error::new(ErrorKind::InvalidInput,
                                   "Tool run with no paramters."));
         }
-        for i in 0..args.len() {
-            let mut arg = args[i].replace("\"", "");
-            arg = arg.replace("\'", "");
-            let cmd = arg.split("="); // in case an equals sign was used
-            let vec = cmd.collect::<Vec<&str>>();
-            let mut keyval = false;
-            if vec.len() > 1 {
-                keyval = true;
-            }
-            if vec[0].to_lowercase() == "-red" || vec[0].to_lowercase() == "--red" {
-                if keyval {
-                    red_file = vec[1].to_string();
-                } else {
-                    red_file = args[i + 1].to_string();
-                }
-            } else if vec[0].to_lowercase() == "-g" || vec[0].to_lowercase() == "-green" ||
-                      vec[0].to_lowercase() == "--green" {
-                if keyval {
-                    green_file = vec[1].to_string();
-                } else {
-                    green_file = args[i + 1].to_string();
-                }
-            } else if vec[0].to_lowercase() == "-b" || vec[0].to_lowercase() == "-blue" ||
-                      vec[0].to_lowercase() == "--blue" {
-                if keyval {
-                    blue_file = vec[1].to_string();
-                } else {
-                    blue_file = args[i + 1].to_string();
-                }
-            } else if vec[0].to_lowercase() == "-p" || vec[0].to_lowercase() == "-pan" ||
-                      vec[0].to_lowercase() == "--pan" {
-                if keyval {
-                    pan_file = vec[1].to_string();
-                } else {
-                    pan_file = args[i + 1].to_string();
-                }
-            } else if vec[0].to_lowercase() == "-c" || vec[0].to_lowercase() == "-composite" ||
-                      vec[0].to_lowercase() == "--composite" {
-                if keyval {
-                    composite_file = vec[1].to_string();
-                } else {
-                    composite_file = args[i + 1].to_string();
-                }
-                use_composite = true;
-            } else if vec[0].to_lowercase() == "-o" || vec[0].to_lowercase() == "-output" ||
-                      vec[0].to_lowercase() == "--output" {
-                if keyval {
-                    output_file = vec[1].to_string();
-                } else {
-                    output_file = args[i + 1].to_string();
-                }
-            } else if vec[0].to_lowercase() == "-method" || vec[0].to_lowercase() == "--method" {
-                if keyval {
-                    fusion_method = vec[1].to_string();
-                } else {
-                    fusion_method = args[i + 1].to_string();
-                }
-                if fusion_method.to_lowercase().contains("bro") {
-                    fusion_method = String::from("brovey");
-                } else {
-                    fusion_method = String::from("ihs");
-                }
-            }
+        let args = ArgValues::parse(&self.parameters, &args)?;
+        let red_file = args.get_file("--red", working_directory)?.unwrap_or(String::new());
+        let green_file = args.get_file("--green", working_directory)?.unwrap_or(String::new());
+        let blue_file = args.get_file("--blue", working_directory)?.unwrap_or(String::new());
+        let pan_file = args.required_file("--pan", working_directory)?;
+        let composite_file = args.get_file("--composite", working_directory)?.unwrap_or(String::new());
+        let use_composite = !composite_file.is_empty();
+        let output_file = args.required_file("--output", working_directory)?;
+        let mut fusion_method = args.get_string("--method")?.unwrap_or(String::from("brovey"));
+        if fusion_method.to_lowercase().contains("bro") {
+            fusion_method = String::from("brovey");
+        } else {
+            fusion_method = String::from("ihs");
         }
 
         if verbose {
-            println!("***************{}", "*".repeat(self.get_tool_name().len()));
-            println!("* Welcome to {} *", self.get_tool_name());
-            println!("***************{}", "*".repeat(self.get_tool_name().len()));
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
+            sink.message(&format!("* Welcome to {} *", self.get_tool_name()));
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
         }
 
-        let sep: String = path::MAIN_SEPARATOR.to_string();
-
         let mut progress: usize;
         let mut old_progress: usize = 1;
 
-        if !red_file.contains(&sep) && !red_file.contains("/") {
-            red_file = format!("{}{}", working_directory, red_file);
-        }
-        if !green_file.contains(&sep) && !green_file.contains("/") {
-            green_file = format!("{}{}", working_directory, green_file);
-        }
-        if !blue_file.contains(&sep) && !blue_file.contains("/") {
-            blue_file = format!("{}{}", working_directory, blue_file);
-        }
-        if !composite_file.contains(&sep) && !composite_file.contains("/") {
-            composite_file = format!("{}{}", working_directory, composite_file);
-        }
-        if !pan_file.contains(&sep) && !pan_file.contains("/") {
-            pan_file = format!("{}{}", working_directory, pan_file);
-        }
-        if !output_file.contains(&sep) && !output_file.contains("/") {
-            output_file = format!("{}{}", working_directory, output_file);
-        }
-
         let num_procs = num_cpus::get() as isize;
 
         // let get_row_from_y; //: &Fn(f64) -> isize;
@@ -283,11 +205,21 @@ impl WhiteboxTool for PanchromaticSharpening {
         let resolution_x: f64;
         let resolution_y: f64;
 
+        let mut input_c = None;
         if use_composite {
             if verbose {
-                println!("Reading multispec image data...")
+                sink.message("Reading multispec image data...")
             };
-            let input_c = Raster::new(&composite_file, "r")?;
+            input_c = Some(Raster::new(&composite_file, "r")?);
+        }
+        // a multi-band composite is handled like three individually specified bands
+        let is_stacked = match input_c {
+            Some(ref c) => c.num_bands() >= 3,
+            None => false,
+        };
+
+        if use_composite && !is_stacked {
+            let input_c = input_c.unwrap();
 
             rows_ms = input_c.configs.rows as isize;
             columns_ms = input_c.configs.columns as isize;
@@ -301,18 +233,24 @@ impl WhiteboxTool for PanchromaticSharpening {
             input = input_c.get_data_as_array2d();
 
         } else {
-            if verbose {
-                println!("Reading red band data...")
-            };
-            let input_r = Raster::new(&red_file, "r")?;
-            if verbose {
-                println!("Reading green band data...")
-            };
-            let input_g = Raster::new(&green_file, "r")?;
-            if verbose {
-                println!("Reading blue band data...")
+            let (input_r, input_g, input_b) = if is_stacked {
+                let input_c = input_c.unwrap();
+                (input_c.get_band(0)?, input_c.get_band(1)?, input_c.get_band(2)?)
+            } else {
+                if verbose {
+                    sink.message("Reading red band data...")
+                };
+                let input_r = Raster::new(&red_file, "r")?;
+                if verbose {
+                    sink.message("Reading green band data...")
+                };
+                let input_g = Raster::new(&green_file, "r")?;
+                if verbose {
+                    sink.message("Reading blue band data...")
+                };
+                let input_b = Raster::new(&blue_file, "r")?;
+                (input_r, input_g, input_b)
             };
-            let input_b = Raster::new(&blue_file, "r")?;
 
             // make sure the input files have the same size
             if input_r.configs.rows != input_g.configs.rows ||
@@ -350,9 +288,9 @@ impl WhiteboxTool for PanchromaticSharpening {
             let b_range = input_b.configs.display_max - input_b.configs.display_min;
             for row in 0..rows_ms {
                 for col in 0..columns_ms {
-                    r = input_r[(row, col)];
-                    g = input_g[(row, col)];
-                    b = input_b[(row, col)];
+                    r = input_r.get_value(row, col);
+                    g = input_g.get_value(row, col);
+                    b = input_b.get_value(row, col);
                     if r != nodata_r && g != nodata_g && b != nodata_b {
                         r = (r - r_min) / r_range * 255f64;
                         if r < 0f64 {
@@ -391,7 +329,7 @@ impl WhiteboxTool for PanchromaticSharpening {
         let input = Arc::new(input);
 
         if verbose {
-            println!("Reading pan image data...")
+            sink.message("Reading pan image data...")
         };
         let pan = Arc::new(Raster::new(&pan_file, "r")?);
         let rows_pan = pan.configs.rows as isize;
@@ -433,7 +371,7 @@ impl WhiteboxTool for PanchromaticSharpening {
                         for col in 0..columns_pan {
                             x = pan.get_x_from_column(col);
                             source_col = get_column_from_x(x);
-                            z_pan = pan[(row, col)];
+                            z_pan = pan.get_value(row, col);
                             z_ms = input[(source_row, source_col)];
                             if z_ms != nodata_ms && z_pan != nodata_pan {
                                 p = (z_pan - pan_min) / pan_range;
@@ -476,12 +414,10 @@ impl WhiteboxTool for PanchromaticSharpening {
             for row in 0..rows_pan {
                 let data = rx.recv().unwrap();
                 output.set_row_data(data.0, data.1);
-                if verbose {
-                    progress = (100.0_f64 * row as f64 / (rows_pan - 1) as f64) as usize;
-                    if progress != old_progress {
-                        println!("Progress: {}%", progress);
-                        old_progress = progress;
-                    }
+                progress = (100.0_f64 * row as f64 / (rows_pan - 1) as f64) as usize;
+                if progress != old_progress {
+                    sink.progress("Progress", progress)?;
+                    old_progress = progress;
                 }
             }
 
@@ -527,12 +463,10 @@ impl WhiteboxTool for PanchromaticSharpening {
                 if data > overall_max {
                     overall_max = data;
                 }
-                if verbose {
-                    progress = (100.0_f64 * tid as f64 / (num_procs - 1) as f64) as usize;
-                    if progress != old_progress {
-                        println!("Progress: {}%", progress);
-                        old_progress = progress;
-                    }
+                progress = (100.0_f64 * tid as f64 / (num_procs - 1) as f64) as usize;
+                if progress != old_progress {
+                    sink.progress("Progress", progress)?;
+                    old_progress = progress;
                 }
             }
 
@@ -563,7 +497,7 @@ impl WhiteboxTool for PanchromaticSharpening {
                         for col in 0..columns_pan {
                             x = pan.get_x_from_column(col);
                             source_col = get_column_from_x(x);
-                            z_pan = pan[(row, col)];
+                            z_pan = pan.get_value(row, col);
                             z_ms = input[(source_row, source_col)];
                             if z_ms != nodata_ms && z_pan != nodata_pan {
                                 p = (z_pan - pan_min) / pan_range;
@@ -653,12 +587,10 @@ impl WhiteboxTool for PanchromaticSharpening {
             for row in 0..rows_pan {
                 let data = rx.recv().unwrap();
                 output.set_row_data(data.0, data.1);
-                if verbose {
-                    progress = (100.0_f64 * row as f64 / (rows_pan - 1) as f64) as usize;
-                    if progress != old_progress {
-                        println!("Progress: {}%", progress);
-                        old_progress = progress;
-                    }
+                progress = (100.0_f64 * row as f64 / (rows_pan - 1) as f64) as usize;
+                if progress != old_progress {
+                    sink.progress("Progress", progress)?;
+                    old_progress = progress;
                 }
             }
         }
@@ -681,19 +613,18 @@ impl WhiteboxTool for PanchromaticSharpening {
                                       .replace("PT", ""));
 
         if verbose {
-            println!("Saving data...")
+            sink.message("Saving data...")
         };
         let _ = match output.write() {
             Ok(_) => {
                 if verbose {
-                    println!("Output file written")
+                    sink.message("Output file written")
                 }
             }
             Err(e) => return Err(e),
         };
         if verbose {
-            println!("{}",
-                 &format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
+            sink.message(&format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
         }
 
         Ok(())
@@ -22,6 +22,7 @@ use std::sync::Arc;
 use std::sync::mpsc;
 use std::thread;
 use tools::*;
+use tools::arg_parser::ArgValues;
 
 pub struct PercentageContrastStretch {
     name: String,
@@ -69,7 +70,7 @@ impl PercentageContrastStretch {
 
         parameters.push(ToolParameter{
             name: "Tail".to_owned(), 
-            flags: vec!["--tail".to_owned()], 
+            flags: vec!["--tails".to_owned(), "--tail".to_owned()], 
             description: "Specified which tails to clip; options include 'upper', 'lower', and 'both' (default is 'both').".to_owned(),
             parameter_type: ParameterType::OptionList(vec!["upper".to_owned(), "lower".to_owned(), "both".to_owned()]),
             default_value: Some("both".to_owned()),
@@ -145,89 +146,38 @@ impl WhiteboxTool for PercentageContrastStretch {
     fn run<'a>(&self,
                args: Vec<String>,
                working_directory: &'a str,
-               verbose: bool)
+               verbose: bool,
+               sink: &Arc<ProgressSink>)
                -> Result<(), Error> {
-        let mut input_file = String::new();
-        let mut output_file = String::new();
-        let mut tail = String::from("both");
-        let mut clip = f64::NEG_INFINITY;
-        let mut num_tones = 256f64;
-
         if args.len() == 0 {
             return Err(Error::new(ErrorKind::InvalidInput,
                                   "Tool run with no paramters."));
         }
-        for i in 0..args.len() {
-            let mut arg = args[i].replace("\"", "");
-            arg = arg.replace("\'", "");
-            let cmd = arg.split("="); // in case an equals sign was used
-            let vec = cmd.collect::<Vec<&str>>();
-            let mut keyval = false;
-            if vec.len() > 1 {
-                keyval = true;
-            }
-            if vec[0].to_lowercase() == "-i" || vec[0].to_lowercase() == "--input" {
-                if keyval {
-                    input_file = vec[1].to_string();
-                } else {
-                    input_file = args[i + 1].to_string();
-                }
-            } else if vec[0].to_lowercase() == "-o" || vec[0].to_lowercase() == "--output" {
-                if keyval {
-                    output_file = vec[1].to_string();
-                } else {
-                    output_file = args[i + 1].to_string();
-                }
-            } else if vec[0].to_lowercase() == "-clip" || vec[0].to_lowercase() == "--clip" {
-                if keyval {
-                    clip = vec[1].to_string().parse::<f64>().unwrap();
-                } else {
-                    clip = args[i + 1].to_string().parse::<f64>().unwrap();
-                }
-            } else if vec[0].to_lowercase() == "-tail" || vec[0].to_lowercase() == "--tail"
-                      || vec[0].to_lowercase() == "--tails" {
-                if keyval {
-                    tail = vec[1].to_string();
-                } else {
-                    tail = args[i + 1].to_string();
-                }
-                if tail.to_lowercase().contains("u") {
-                    tail = String::from("upper");
-                } else if tail.to_lowercase().contains("l") {
-                    tail = String::from("lower");
-                } else {
-                    tail = String::from("both");
-                }
-            } else if vec[0].to_lowercase() == "-num_tones" ||
-                      vec[0].to_lowercase() == "--num_tones" {
-                if keyval {
-                    num_tones = vec[1].to_string().parse::<f64>().unwrap();
-                } else {
-                    num_tones = args[i + 1].to_string().parse::<f64>().unwrap();
-                }
-            }
+        let args = ArgValues::parse(&self.parameters, &args)?;
+        let input_file = args.required_file("--input", working_directory)?;
+        let output_file = args.required_file("--output", working_directory)?;
+        let clip = args.get_f64("--clip")?.unwrap_or(f64::NEG_INFINITY);
+        let mut tail = args.get_string("--tail")?.unwrap_or(String::from("both"));
+        if tail.to_lowercase().contains("u") {
+            tail = String::from("upper");
+        } else if tail.to_lowercase().contains("l") {
+            tail = String::from("lower");
+        } else {
+            tail = String::from("both");
         }
+        let mut num_tones = args.get_f64("--num_tones")?.unwrap_or(256f64);
 
         if verbose {
-            println!("***************{}", "*".repeat(self.get_tool_name().len()));
-            println!("* Welcome to {} *", self.get_tool_name());
-            println!("***************{}", "*".repeat(self.get_tool_name().len()));
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
+            sink.message(&format!("* Welcome to {} *", self.get_tool_name()));
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
         }
 
-        let sep: String = path::MAIN_SEPARATOR.to_string();
-
         let mut progress: usize;
         let mut old_progress: usize = 1;
 
-        if !input_file.contains(&sep) && !input_file.contains("/") {
-            input_file = format!("{}{}", working_directory, input_file);
-        }
-        if !output_file.contains(&sep) && !output_file.contains("/") {
-            output_file = format!("{}{}", working_directory, output_file);
-        }
-
         if num_tones < 16f64 {
-            println!("Warning: The output number of greytones must be at least 16. The value has been modified.");
+            sink.warning("Warning: The output number of greytones must be at least 16. The value has been modified.");
             num_tones = 16f64;
         }
 
@@ -238,7 +188,7 @@ impl WhiteboxTool for PercentageContrastStretch {
         }
 
         if verbose {
-            println!("Reading input data...")
+            sink.message("Reading input data...")
         };
         let input = Arc::new(Raster::new(&input_file, "r")?);
         let rows = input.configs.rows as isize;
@@ -263,7 +213,7 @@ impl WhiteboxTool for PercentageContrastStretch {
         let start = time::now();
 
         if verbose {
-            println!("Calculating clip values...")
+            sink.message("Calculating clip values...")
         };
 
         let (min_val, max_val) = if !is_rgb_image {
@@ -306,12 +256,10 @@ impl WhiteboxTool for PercentageContrastStretch {
                         histo[bin] += 1usize;
                     }
                 }
-                if verbose {
-                    progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as usize;
-                    if progress != old_progress {
-                        println!("Calculating clip values: {}%", progress);
-                        old_progress = progress;
-                    }
+                progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as usize;
+                if progress != old_progress {
+                    sink.progress("Calculating clip values", progress)?;
+                    old_progress = progress;
                 }
             }
             let num_cells_in_tail = (n * clip / 100f64).round() as usize;
@@ -417,12 +365,10 @@ impl WhiteboxTool for PercentageContrastStretch {
         for r in 0..rows {
             let (row, data) = rx.recv().unwrap();
             output.set_row_data(row, data);
-            if verbose {
-                progress = (100.0_f64 * r as f64 / (rows - 1) as f64) as usize;
-                if progress != old_progress {
-                    println!("Progress: {}%", progress);
-                    old_progress = progress;
-                }
+            progress = (100.0_f64 * r as f64 / (rows - 1) as f64) as usize;
+            if progress != old_progress {
+                sink.progress("Progress", progress)?;
+                old_progress = progress;
             }
         }
 
@@ -438,20 +384,19 @@ impl WhiteboxTool for PercentageContrastStretch {
                                       .replace("PT", ""));
 
         if verbose {
-            println!("Saving data...")
+            sink.message("Saving data...")
         };
         let _ = match output.write() {
             Ok(_) => {
                 if verbose {
-                    println!("Output file written")
+                    sink.message("Output file written")
                 }
             }
             Err(e) => return Err(e),
         }; 
 
         if verbose {
-            println!("{}",
-                 &format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
+            sink.message(&format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
         }
 
         Ok(())
@@ -20,6 +20,7 @@ use std::sync::mpsc;
 use std::thread;
 use raster::*;
 use tools::*;
+use tools::arg_parser::ArgValues;
 
 pub struct RemoveSpurs {
     name: String,
@@ -38,7 +39,7 @@ impl RemoveSpurs {
         let mut parameters = vec![];
         parameters.push(ToolParameter{
             name: "Input File".to_owned(), 
-            flags: vec!["-i".to_owned(), "--input".to_owned()], 
+            flags: vec!["-i".to_owned(), "--dem".to_owned(), "--input".to_owned()], 
             description: "Input raster file.".to_owned(),
             parameter_type: ParameterType::ExistingFile(ParameterFileType::Raster),
             default_value: None,
@@ -117,64 +118,26 @@ impl WhiteboxTool for RemoveSpurs {
         self.toolbox.clone()
     }
 
-    fn run<'a>(&self, args: Vec<String>, working_directory: &'a str, verbose: bool) -> Result<(), Error> {
-        let mut input_file = String::new();
-        let mut output_file = String::new();
-        let mut max_iterations = 10;
-        
+    fn run<'a>(&self, args: Vec<String>, working_directory: &'a str, verbose: bool, sink: &Arc<ProgressSink>) -> Result<(), Error> {
         if args.len() == 0 {
             return Err(Error::new(ErrorKind::InvalidInput,
                                 "Tool run with no paramters."));
         }
-        for i in 0..args.len() {
-            let mut arg = args[i].replace("\"", "");
-            arg = arg.replace("\'", "");
-            let cmd = arg.split("="); // in case an equals sign was used
-            let vec = cmd.collect::<Vec<&str>>();
-            let mut keyval = false;
-            if vec.len() > 1 {
-                keyval = true;
-            }
-            if vec[0].to_lowercase() == "-i" || vec[0].to_lowercase() == "--input" || vec[0].to_lowercase() == "--dem" {
-                if keyval {
-                    input_file = vec[1].to_string();
-                } else {
-                    input_file = args[i+1].to_string();
-                }
-            } else if vec[0].to_lowercase() == "-o" || vec[0].to_lowercase() == "--output" {
-                if keyval {
-                    output_file = vec[1].to_string();
-                } else {
-                    output_file = args[i+1].to_string();
-                }
-            } else if vec[0].to_lowercase() == "-iterations" || vec[0].to_lowercase() == "--iterations" {
-                if keyval {
-                    max_iterations = vec[1].to_string().parse::<usize>().unwrap();
-                } else {
-                    max_iterations = args[i + 1].to_string().parse::<usize>().unwrap();
-                }
-            }
-        }
+        let args = ArgValues::parse(&self.parameters, &args)?;
+        let input_file = args.required_file("--input", working_directory)?;
+        let output_file = args.required_file("--output", working_directory)?;
+        let max_iterations = args.get_usize("--iterations")?.unwrap_or(10);
 
         if verbose {
-            println!("***************{}", "*".repeat(self.get_tool_name().len()));
-            println!("* Welcome to {} *", self.get_tool_name());
-            println!("***************{}", "*".repeat(self.get_tool_name().len()));
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
+            sink.message(&format!("* Welcome to {} *", self.get_tool_name()));
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
         }
 
-        let sep: String = path::MAIN_SEPARATOR.to_string();
-
         let mut progress: usize;
         let mut old_progress: usize = 1;
 
-        if !input_file.contains(&sep) && !input_file.contains("/") {
-            input_file = format!("{}{}", working_directory, input_file);
-        }
-        if !output_file.contains(&sep) && !output_file.contains("/") {
-            output_file = format!("{}{}", working_directory, output_file);
-        }
-
-        if verbose { println!("Reading data...") };
+        if verbose { sink.message("Reading data...") };
 
         let input = Arc::new(Raster::new(&input_file, "r")?);
         let rows = input.configs.rows as isize;
@@ -192,9 +155,9 @@ impl WhiteboxTool for RemoveSpurs {
                 for row in (0..rows).filter(|r| r % num_procs == tid) {
                     let mut data: Vec<f64> = vec![nodata; columns as usize];
                     for col in 0..columns {
-                        if input[(row, col)] > 0.0 && input[(row, col)] != nodata {
+                        if input.get_value(row, col) > 0.0 && input.get_value(row, col) != nodata {
                             data[col as usize] = 1.0;
-                        } else if input[(row, col)] == 0.0 {
+                        } else if input.get_value(row, col) == 0.0 {
                             data[col as usize] = 0.0;
                         }
                     }
@@ -208,12 +171,10 @@ impl WhiteboxTool for RemoveSpurs {
             let (row, data) = rx.recv().unwrap();
             output.set_row_data(row, data);
             
-            if verbose {
-                progress = (100.0_f64 * r as f64 / (rows - 1) as f64) as usize;
-                if progress != old_progress {
-                    println!("Initializing output: {}%", progress);
-                    old_progress = progress;
-                }
+            progress = (100.0_f64 * r as f64 / (rows - 1) as f64) as usize;
+            if progress != old_progress {
+                sink.progress("Initializing output", progress)?;
+                old_progress = progress;
             }
         }
 
@@ -235,11 +196,11 @@ impl WhiteboxTool for RemoveSpurs {
                 for a in 0..8 {
                     for row in 0..rows {
                         for col in 0..columns {
-                            z = output[(row, col)];
+                            z = output.get_value(row, col);
                             if z > 0.0 && z != nodata {
                                 // fill the neighbours array
                                 for i in 0..8 {
-                                    neighbours[i] = output[(row + dy[i], col + dx[i])];
+                                    neighbours[i] = output.get_value(row + dy[i], col + dx[i]);
                                 }
                                 
                                 // scan through element
@@ -250,29 +211,27 @@ impl WhiteboxTool for RemoveSpurs {
                                     }
                                 }
                                 if pattern_match {
-                                    output[(row, col)] = 0.0;
+                                    output.set_value(row, col, 0.0);
                                     did_something = true;
                                 }
                             }
                         }
                     }
-                    if verbose {
-                        progress = (100.0_f64 * a as f64 / 7.0) as usize;
-                        if progress != old_progress {
-                            println!("Loop Number {}: {}%", loop_num + 1, progress);
-                            old_progress = progress;
-                        }
+                    progress = (100.0_f64 * a as f64 / 7.0) as usize;
+                    if progress != old_progress {
+                        sink.progress(&format!("Loop Number {}", loop_num + 1), progress)?;
+                        old_progress = progress;
                     }
                 }
             } else {
                 for a in 0..8 {
                     for row in (0..rows).rev() {
                         for col in (0..columns).rev() {
-                            z = output[(row, col)];
+                            z = output.get_value(row, col);
                             if z > 0.0 && z != nodata {
                                 // fill the neighbours array
                                 for i in 0..8 {
-                                    neighbours[i] = output[(row + dy[i], col + dx[i])];
+                                    neighbours[i] = output.get_value(row + dy[i], col + dx[i]);
                                 }
                                 
                                 // scan through element
@@ -283,18 +242,16 @@ impl WhiteboxTool for RemoveSpurs {
                                     }
                                 }
                                 if pattern_match {
-                                    output[(row, col)] = 0.0;
+                                    output.set_value(row, col, 0.0);
                                     did_something = true;
                                 }
                             }
                         }
                     }
-                    if verbose {
-                        progress = (100.0_f64 * a as f64 / 7.0) as usize;
-                        if progress != old_progress {
-                            println!("Loop Number {}: {}%", loop_num + 1, progress);
-                            old_progress = progress;
-                        }
+                    progress = (100.0_f64 * a as f64 / 7.0) as usize;
+                    if progress != old_progress {
+                        sink.progress(&format!("Loop Number {}", loop_num + 1), progress)?;
+                        old_progress = progress;
                     }
                 }
             }
@@ -310,13 +267,13 @@ impl WhiteboxTool for RemoveSpurs {
         output.add_metadata_entry(format!("Max iterations: {}", max_iterations));
         output.add_metadata_entry(format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
 
-        if verbose { println!("Saving data...") };
+        if verbose { sink.message("Saving data...") };
         let _ = match output.write() {
-            Ok(_) => if verbose { println!("Output file written") },
+            Ok(_) => if verbose { sink.message("Output file written") },
             Err(e) => return Err(e),
         };
         if verbose {
-            println!("{}", &format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
+            sink.message(&format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
         }
 
         Ok(())
@@ -29,6 +29,7 @@ use std::thread;
 use raster::*;
 use std::io::{Error, ErrorKind};
 use tools::*;
+use tools::arg_parser::ArgValues;
 
 pub struct Resample {
     name: String,
@@ -119,56 +120,26 @@ impl WhiteboxTool for Resample {
         self.toolbox.clone()
     }
 
-    fn run<'a>(&self, args: Vec<String>, working_directory: &'a str, verbose: bool) -> Result<(), Error> {
-        let mut input_files = String::new();
-        let mut destination_file = String::new();
-        let mut method = String::from("cc");
-        
+    fn run<'a>(&self, args: Vec<String>, working_directory: &'a str, verbose: bool, sink: &Arc<ProgressSink>) -> Result<(), Error> {
         if args.len() == 0 {
             return Err(Error::new(ErrorKind::InvalidInput, "Tool run with no paramters."));
         }
-        for i in 0..args.len() {
-            let mut arg = args[i].replace("\"", "");
-            arg = arg.replace("\'", "");
-            let cmd = arg.split("="); // in case an equals sign was used
-            let vec = cmd.collect::<Vec<&str>>();
-            let mut keyval = false;
-            if vec.len() > 1 {
-                keyval = true;
-            }
-            let flag_val = vec[0].to_lowercase().replace("--", "-");
-            if flag_val == "-i" || flag_val == "-inputs" {
-                input_files = if keyval {
-                    vec[1].to_string()
-                } else {
-                    args[i+1].to_string()
-                };
-            } else if flag_val == "-destination" {
-                destination_file = if keyval {
-                    vec[1].to_string()
-                } else {
-                    args[i+1].to_string()
-                };
-            } else if flag_val == "-method" {
-                method = if keyval {
-                    vec[1].to_string()
-                } else {
-                    args[i+1].to_string()
-                };
-                if method.to_lowercase().contains("nn") || method.to_lowercase().contains("nearest") {
-                    method = "nn".to_string();
-                } else if method.to_lowercase().contains("bilinear") || method.to_lowercase().contains("bi") {
-                    method = "bilinear".to_string();
-                } else if method.to_lowercase().contains("cc") || method.to_lowercase().contains("cubic") {
-                    method = "cc".to_string();
-                }
-            }
+        let args = ArgValues::parse(&self.parameters, &args)?;
+        let input_files = args.required_string("--inputs")?;
+        let destination_file = args.required_file("--destination", working_directory)?;
+        let mut method = args.get_string("--method")?.unwrap_or(String::from("cc"));
+        if method.to_lowercase().contains("nn") || method.to_lowercase().contains("nearest") {
+            method = "nn".to_string();
+        } else if method.to_lowercase().contains("bilinear") || method.to_lowercase().contains("bi") {
+            method = "bilinear".to_string();
+        } else if method.to_lowercase().contains("cc") || method.to_lowercase().contains("cubic") {
+            method = "cc".to_string();
         }
 
         if verbose {
-            println!("***************{}", "*".repeat(self.get_tool_name().len()));
-            println!("* Welcome to {} *", self.get_tool_name());
-            println!("***************{}", "*".repeat(self.get_tool_name().len()));
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
+            sink.message(&format!("* Welcome to {} *", self.get_tool_name()));
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
         }
 
         let sep: String = path::MAIN_SEPARATOR.to_string();
@@ -176,10 +147,6 @@ impl WhiteboxTool for Resample {
         let mut progress: usize;
         let mut old_progress: usize = 1;
 
-        if !destination_file.contains(&sep) && !destination_file.contains("/") {
-            destination_file = format!("{}{}", working_directory, destination_file);
-        }
-
         // see if the destination file exists.
         if !path::Path::new(&destination_file).exists() {
             return Err(Error::new(ErrorKind::InvalidInput,
@@ -207,7 +174,7 @@ impl WhiteboxTool for Resample {
         let nodata = destination.configs.nodata;
 
         // read the input files
-        if verbose { println!("Reading data...") };
+        if verbose { sink.message("Reading data...") };
         let mut inputs: Vec<Raster> = Vec::with_capacity(num_files);
         let mut nodata_vals: Vec<f64> = Vec::with_capacity(num_files);
         for i in 0..num_files {
@@ -278,12 +245,10 @@ impl WhiteboxTool for Resample {
                         destination.set_value(row, col, data[col as usize]);
                     }
                 }
-                if verbose {
-                    progress = (100.0_f64 * r as f64 / (rows - 1) as f64) as usize;
-                    if progress != old_progress {
-                        println!("Progress: {}%", progress);
-                        old_progress = progress;
-                    }
+                progress = (100.0_f64 * r as f64 / (rows - 1) as f64) as usize;
+                if progress != old_progress {
+                    sink.progress("Progress", progress)?;
+                    old_progress = progress;
                 }
             }
         } else if method == "cc" {
@@ -359,12 +324,10 @@ impl WhiteboxTool for Resample {
                         destination.set_value(row, col as isize, data[col]);
                     }
                 }
-                if verbose {
-                    progress = (100.0_f64 * r as f64 / (rows - 1) as f64) as usize;
-                    if progress != old_progress {
-                        println!("Progress: {}%", progress);
-                        old_progress = progress;
-                    }
+                progress = (100.0_f64 * r as f64 / (rows - 1) as f64) as usize;
+                if progress != old_progress {
+                    sink.progress("Progress", progress)?;
+                    old_progress = progress;
                 }
             }
 
@@ -438,12 +401,10 @@ impl WhiteboxTool for Resample {
                         destination.set_value(row, col as isize, data[col]);
                     }
                 }
-                if verbose {
-                    progress = (100.0_f64 * r as f64 / (rows - 1) as f64) as usize;
-                    if progress != old_progress {
-                        println!("Progress: {}%", progress);
-                        old_progress = progress;
-                    }
+                progress = (100.0_f64 * r as f64 / (rows - 1) as f64) as usize;
+                if progress != old_progress {
+                    sink.progress("Progress", progress)?;
+                    old_progress = progress;
                 }
             }
         }
@@ -452,13 +413,13 @@ impl WhiteboxTool for Resample {
         let elapsed_time = end - start;
         destination.add_metadata_entry(format!("Modified by whitebox_tools\' {} tool", self.get_tool_name()));
         
-        if verbose { println!("Saving data...") };
+        if verbose { sink.message("Saving data...") };
         let _ = match destination.write() {
-            Ok(_) => if verbose { println!("Destination file written") },
+            Ok(_) => if verbose { sink.message("Destination file written") },
             Err(e) => return Err(e),
         };
         if verbose {
-            println!("{}", &format!("Elapsed Time (including I/O): {}", elapsed_time).replace("PT", ""));
+            sink.message(&format!("Elapsed Time (including I/O): {}", elapsed_time).replace("PT", ""));
         }
 
         Ok(())
@@ -17,6 +17,7 @@ use std::thread;
 use raster::*;
 use std::io::{Error, ErrorKind};
 use tools::*;
+use tools::arg_parser::ArgValues;
 
 /// Tool struct containing the essential descriptors required to interact with the tool.
 pub struct RgbToIhs {
@@ -47,7 +48,7 @@ impl RgbToIhs {
 
         parameters.push(ToolParameter{
             name: "Input Green Band File (optional; only if colour-composite not specified)".to_owned(), 
-            flags: vec!["--green".to_owned()], 
+            flags: vec!["-g".to_owned(), "--green".to_owned()], 
             description: "Input green band image file. Optionally specified if colour-composite not specified.".to_owned(),
             parameter_type: ParameterType::ExistingFile(ParameterFileType::Raster),
             default_value: None,
@@ -56,7 +57,7 @@ impl RgbToIhs {
 
         parameters.push(ToolParameter{
             name: "Input Blue Band File (optional; only if colour-composite not specified)".to_owned(), 
-            flags: vec!["--blue".to_owned()], 
+            flags: vec!["-b".to_owned(), "--blue".to_owned()], 
             description: "Input blue band image file. Optionally specified if colour-composite not specified.".to_owned(),
             parameter_type: ParameterType::ExistingFile(ParameterFileType::Raster),
             default_value: None,
@@ -74,7 +75,7 @@ impl RgbToIhs {
 
         parameters.push(ToolParameter{
             name: "Output Intensity File".to_owned(), 
-            flags: vec!["--intensity".to_owned()], 
+            flags: vec!["-i".to_owned(), "--intensity".to_owned()], 
             description: "Output intensity raster file.".to_owned(),
             parameter_type: ParameterType::NewFile(ParameterFileType::Raster),
             default_value: None,
@@ -83,7 +84,7 @@ impl RgbToIhs {
 
         parameters.push(ToolParameter{
             name: "Output Hue File".to_owned(), 
-            flags: vec!["--hue".to_owned()], 
+            flags: vec!["-h".to_owned(), "--hue".to_owned()], 
             description: "Output hue raster file.".to_owned(),
             parameter_type: ParameterType::NewFile(ParameterFileType::Raster),
             default_value: None,
@@ -92,7 +93,7 @@ impl RgbToIhs {
 
         parameters.push(ToolParameter{
             name: "Output Saturation File".to_owned(), 
-            flags: vec!["--saturation".to_owned()], 
+            flags: vec!["-s".to_owned(), "--saturation".to_owned()], 
             description: "Output saturation raster file.".to_owned(),
             parameter_type: ParameterType::NewFile(ParameterFileType::Raster),
             default_value: None,
@@ -154,115 +155,38 @@ impl WhiteboxTool for RgbToIhs {
         self.toolbox.clone()
     }
     
-    fn run<'a>(&self, args: Vec<String>, working_directory: &'a str, verbose: bool) -> Result<(), Error> {
-        let mut red_file = String::new();
-        let mut green_file = String::new();
-        let mut blue_file = String::new();
-        let mut intensity_file = String::new();
-        let mut hue_file = String::new();
-        let mut saturation_file = String::new();
-        let mut composite_file = String::new();
-        let mut use_composite = false;
+    fn run<'a>(&self, args: Vec<String>, working_directory: &'a str, verbose: bool, sink: &Arc<ProgressSink>) -> Result<(), Error> {
         if args.len() == 0 {
             return Err(Error::new(ErrorKind::InvalidInput,
                                 "Tool run with no paramters."));
         }
-        for i in 0..args.len() {
-            let mut arg = args[i].replace("\"", "");
-            arg = arg.replace("\'", "");
-            let cmd = arg.split("="); // in case an equals sign was used
-            let vec = cmd.collect::<Vec<&str>>();
-            let mut keyval = false;
-            if vec.len() > 1 {
-                keyval = true;
-            }
-            if vec[0].to_lowercase() == "-red" || vec[0].to_lowercase() == "--red" {
-                if keyval {
-                    red_file = vec[1].to_string();
-                } else {
-                    red_file = args[i+1].to_string();
-                }
-            } else if vec[0].to_lowercase() == "-g" || vec[0].to_lowercase() == "-green" || vec[0].to_lowercase() == "--green" {
-                if keyval {
-                    green_file = vec[1].to_string();
-                } else {
-                    green_file = args[i+1].to_string();
-                }
-            } else if vec[0].to_lowercase() == "-b" || vec[0].to_lowercase() == "-blue" || vec[0].to_lowercase() == "--blue" {
-                if keyval {
-                    blue_file = vec[1].to_string();
-                } else {
-                    blue_file = args[i+1].to_string();
-                }
-            } else if vec[0].to_lowercase() == "-composite" || vec[0].to_lowercase() == "--composite" {
-                if keyval {
-                    composite_file = vec[1].to_string();
-                } else {
-                    composite_file = args[i+1].to_string();
-                }
-                use_composite = true;
-            } else if vec[0].to_lowercase() == "-i" || vec[0].to_lowercase() == "-intensity" || vec[0].to_lowercase() == "--intensity" {
-                if keyval {
-                    intensity_file = vec[1].to_string();
-                } else {
-                    intensity_file = args[i+1].to_string();
-                }
-            } else if vec[0].to_lowercase() == "-h" || vec[0].to_lowercase() == "-hue" || vec[0].to_lowercase() == "--hue" {
-                if keyval {
-                    hue_file = vec[1].to_string();
-                } else {
-                    hue_file = args[i+1].to_string();
-                }
-            } else if vec[0].to_lowercase() == "-s" || vec[0].to_lowercase() == "-saturation" || vec[0].to_lowercase() == "--saturation" {
-                if keyval {
-                    saturation_file = vec[1].to_string();
-                } else {
-                    saturation_file = args[i+1].to_string();
-                }
-            }
-        }
+        let args = ArgValues::parse(&self.parameters, &args)?;
+        let red_file = args.get_file("--red", working_directory)?.unwrap_or(String::new());
+        let green_file = args.get_file("--green", working_directory)?.unwrap_or(String::new());
+        let blue_file = args.get_file("--blue", working_directory)?.unwrap_or(String::new());
+        let composite_file = args.get_file("--composite", working_directory)?.unwrap_or(String::new());
+        let use_composite = !composite_file.is_empty();
+        let intensity_file = args.required_file("--intensity", working_directory)?;
+        let hue_file = args.required_file("--hue", working_directory)?;
+        let saturation_file = args.required_file("--saturation", working_directory)?;
 
         if verbose {
-            println!("***************{}", "*".repeat(self.get_tool_name().len()));
-            println!("* Welcome to {} *", self.get_tool_name());
-            println!("***************{}", "*".repeat(self.get_tool_name().len()));
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
+            sink.message(&format!("* Welcome to {} *", self.get_tool_name()));
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
         }
 
-        let sep: String = path::MAIN_SEPARATOR.to_string();
-
         let mut progress: usize;
         let mut old_progress: usize = 1;
 
-        if !red_file.contains(&sep) && !red_file.contains("/") {
-            red_file = format!("{}{}", working_directory, red_file);
-        }
-        if !green_file.contains(&sep) && !green_file.contains("/") {
-            green_file = format!("{}{}", working_directory, green_file);
-        }
-        if !blue_file.contains(&sep) && !blue_file.contains("/") {
-            blue_file = format!("{}{}", working_directory, blue_file);
-        }
-        if !composite_file.contains(&sep) && !composite_file.contains("/") {
-            composite_file = format!("{}{}", working_directory, composite_file);
-        }
-        if !intensity_file.contains(&sep) && !intensity_file.contains("/") {
-            intensity_file = format!("{}{}", working_directory, intensity_file);
-        }
-        if !hue_file.contains(&sep) && !hue_file.contains("/") {
-            hue_file = format!("{}{}", working_directory, hue_file);
-        }
-        if !saturation_file.contains(&sep) && !saturation_file.contains("/") {
-            saturation_file = format!("{}{}", working_directory, saturation_file);
-        }
-
         let num_procs = num_cpus::get() as isize;
 
         if !use_composite {
-            if verbose { println!("Reading red band data...") };
+            if verbose { sink.message("Reading red band data...") };
             let input_r = Arc::new(Raster::new(&red_file, "r")?);
-            if verbose { println!("Reading green band data...") };
+            if verbose { sink.message("Reading green band data...") };
             let input_g = Arc::new(Raster::new(&green_file, "r")?);
-            if verbose { println!("Reading blue band data...") };
+            if verbose { sink.message("Reading blue band data...") };
             let input_b = Arc::new(Raster::new(&blue_file, "r")?);
 
             let rows = input_r.configs.rows as isize;
@@ -306,9 +230,9 @@ impl WhiteboxTool for RgbToIhs {
                         let mut hue_data = vec![nodata_r; columns as usize];
                         let mut saturation_data = vec![nodata_r; columns as usize];
                         for col in 0..columns {
-                            r = input_r[(row, col)];
-                            g = input_g[(row, col)];
-                            b = input_b[(row, col)];
+                            r = input_r.get_value(row, col);
+                            g = input_g.get_value(row, col);
+                            b = input_b.get_value(row, col);
                             if r != nodata_r && g != nodata_g && b != nodata_b {
                                 r = (r - overall_min) / range;
                                 if r < 0f64 { r = 0f64; }
@@ -371,12 +295,10 @@ impl WhiteboxTool for RgbToIhs {
                 output_i.set_row_data(data.0, data.1);
                 output_h.set_row_data(data.0, data.2);
                 output_s.set_row_data(data.0, data.3);
-                if verbose {
-                    progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as usize;
-                    if progress != old_progress {
-                        println!("Progress: {}%", progress);
-                        old_progress = progress;
-                    }
+                progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as usize;
+                if progress != old_progress {
+                    sink.progress("Progress", progress)?;
+                    old_progress = progress;
                 }
             }
 
@@ -387,9 +309,9 @@ impl WhiteboxTool for RgbToIhs {
             // output_i.add_metadata_entry(format!("Input colour composite file: {}", composite_file));
             output_i.add_metadata_entry(format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
 
-            if verbose { println!("Saving intensity data...") };
+            if verbose { sink.message("Saving intensity data...") };
             let _ = match output_i.write() {
-                Ok(_) => if verbose { println!("Output file written") },
+                Ok(_) => if verbose { sink.message("Output file written") },
                 Err(e) => return Err(e),
             };
 
@@ -397,9 +319,9 @@ impl WhiteboxTool for RgbToIhs {
             // output_h.add_metadata_entry(format!("Input colour composite file: {}", composite_file));
             output_h.add_metadata_entry(format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
 
-            if verbose { println!("Saving hue data...") };
+            if verbose { sink.message("Saving hue data...") };
             let _ = match output_h.write() {
-                Ok(_) => if verbose { println!("Output file written") },
+                Ok(_) => if verbose { sink.message("Output file written") },
                 Err(e) => return Err(e),
             };
 
@@ -407,15 +329,15 @@ impl WhiteboxTool for RgbToIhs {
             // output_s.add_metadata_entry(format!("Input colour composite file: {}", composite_file));
             output_s.add_metadata_entry(format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
 
-            if verbose { println!("Saving saturation data...") };
+            if verbose { sink.message("Saving saturation data...") };
             let _ = match output_s.write() {
-                Ok(_) => if verbose { println!("Output file written") },
+                Ok(_) => if verbose { sink.message("Output file written") },
                 Err(e) => return Err(e),
             };
 
-            println!("{}", &format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
+            sink.message(&format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
         } else {
-            if verbose { println!("Reading image data...") };
+            if verbose { sink.message("Reading image data...") };
             let input = Arc::new(Raster::new(&composite_file, "r")?);
             let rows = input.configs.rows as isize;
             let columns = input.configs.columns as isize;
@@ -435,7 +357,7 @@ impl WhiteboxTool for RgbToIhs {
                     let mut z: f64;
                     for row in (0..rows).filter(|r| r % num_procs == tid) {
                         for col in 0..columns {
-                            z = input[(row, col)];
+                            z = input.get_value(row, col);
                             if z != nodata {
                                 r = (z as u32 & 0xFF) as f64;
                                 g = ((z as u32 >> 8) & 0xFF) as f64;
@@ -462,12 +384,10 @@ impl WhiteboxTool for RgbToIhs {
                 let data = rx.recv().unwrap();
                 if data.0 < overall_min { overall_min = data.0; }
                 if data.1 > overall_max { overall_max = data.1; }
-                if verbose {
-                    progress = (100.0_f64 * tid as f64 / (num_procs - 1) as f64) as usize;
-                    if progress != old_progress {
-                        println!("Progress: {}%", progress);
-                        old_progress = progress;
-                    }
+                progress = (100.0_f64 * tid as f64 / (num_procs - 1) as f64) as usize;
+                if progress != old_progress {
+                    sink.progress("Progress", progress)?;
+                    old_progress = progress;
                 }
             }
 
@@ -486,7 +406,7 @@ impl WhiteboxTool for RgbToIhs {
                         let mut hue_data = vec![nodata; columns as usize];
                         let mut saturation_data = vec![nodata; columns as usize];
                         for col in 0..columns {
-                            z = input[(row, col)];
+                            z = input.get_value(row, col);
                             if z != nodata {
                                 r = (z as u32 & 0xFF) as f64;
                                 g = ((z as u32 >> 8) & 0xFF) as f64;
@@ -553,12 +473,10 @@ impl WhiteboxTool for RgbToIhs {
                 output_i.set_row_data(data.0, data.1);
                 output_h.set_row_data(data.0, data.2);
                 output_s.set_row_data(data.0, data.3);
-                if verbose {
-                    progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as usize;
-                    if progress != old_progress {
-                        println!("Progress: {}%", progress);
-                        old_progress = progress;
-                    }
+                progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as usize;
+                if progress != old_progress {
+                    sink.progress("Progress", progress)?;
+                    old_progress = progress;
                 }
             }
 
@@ -569,9 +487,9 @@ impl WhiteboxTool for RgbToIhs {
             output_i.add_metadata_entry(format!("Input colour composite file: {}", composite_file));
             output_i.add_metadata_entry(format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
 
-            if verbose { println!("Saving intensity data...") };
+            if verbose { sink.message("Saving intensity data...") };
             let _ = match output_i.write() {
-                Ok(_) => if verbose { println!("Output file written") },
+                Ok(_) => if verbose { sink.message("Output file written") },
                 Err(e) => return Err(e),
             };
 
@@ -579,9 +497,9 @@ impl WhiteboxTool for RgbToIhs {
             output_h.add_metadata_entry(format!("Input colour composite file: {}", composite_file));
             output_h.add_metadata_entry(format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
 
-            if verbose { println!("Saving hue data...") };
+            if verbose { sink.message("Saving hue data...") };
             let _ = match output_h.write() {
-                Ok(_) => if verbose { println!("Output file written") },
+                Ok(_) => if verbose { sink.message("Output file written") },
                 Err(e) => return Err(e),
             };
 
@@ -589,13 +507,13 @@ impl WhiteboxTool for RgbToIhs {
             output_s.add_metadata_entry(format!("Input colour composite file: {}", composite_file));
             output_s.add_metadata_entry(format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
 
-            if verbose { println!("Saving saturation data...") };
+            if verbose { sink.message("Saving saturation data...") };
             let _ = match output_s.write() {
-                Ok(_) => if verbose { println!("Output file written") },
+                Ok(_) => if verbose { sink.message("Output file written") },
                 Err(e) => return Err(e),
             };
             if verbose {
-                println!("{}", &format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
+                sink.message(&format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
             }
         }
 
@@ -2,12 +2,13 @@
 This tool is part of the WhiteboxTools geospatial analysis library.
 Authors: Dr. John Lindsay
 Created: June 27, 2017
-Last Modified: January 21, 2018
+Last Modified: March 16, 2018
 License: MIT
 */
 extern crate time;
 extern crate num_cpus;
 
+use std::cmp::min;
 use std::env;
 use std::path;
 use std::f64;
@@ -17,6 +18,7 @@ use std::thread;
 use raster::*;
 use std::io::{Error, ErrorKind};
 use tools::*;
+use tools::arg_parser::ArgValues;
 
 pub struct SobelFilter {
     name: String,
@@ -123,81 +125,38 @@ impl WhiteboxTool for SobelFilter {
         self.toolbox.clone()
     }
 
-    fn run<'a>(&self, args: Vec<String>, working_directory: &'a str, verbose: bool) -> Result<(), Error> {
+    fn run<'a>(&self, args: Vec<String>, working_directory: &'a str, verbose: bool, sink: &Arc<ProgressSink>) -> Result<(), Error> {
         if args.len() == 0 {
             return Err(Error::new(ErrorKind::InvalidInput,
                                 "Tool run with no paramters."));
         }
         
-        let mut input_file = String::new();
-        let mut output_file = String::new();
-        let mut variant = "3x3".to_string();
-        let mut clip_amount = 0.0;
-        for i in 0..args.len() {
-            let mut arg = args[i].replace("\"", "");
-            arg = arg.replace("\'", "");
-            let cmd = arg.split("="); // in case an equals sign was used
-            let vec = cmd.collect::<Vec<&str>>();
-            let mut keyval = false;
-            if vec.len() > 1 {
-                keyval = true;
-            }
-            if vec[0].to_lowercase() == "-i" || vec[0].to_lowercase() == "--input" {
-                if keyval {
-                    input_file = vec[1].to_string();
-                } else {
-                    input_file = args[i + 1].to_string();
-                }
-            } else if vec[0].to_lowercase() == "-o" || vec[0].to_lowercase() == "--output" {
-                if keyval {
-                    output_file = vec[1].to_string();
-                } else {
-                    output_file = args[i + 1].to_string();
-                }
-            } else if vec[0].to_lowercase() == "-variant" || vec[0].to_lowercase() == "--variant" {
-                if keyval {
-                    variant = vec[1].to_string();
-                } else {
-                    variant = args[i + 1].to_string();
-                }
-                if variant.contains("5") {
-                    variant = "5x5".to_string();
-                } else {
-                    variant = "3x3".to_string();
-                }
-            } else if vec[0].to_lowercase() == "-clip" || vec[0].to_lowercase() == "--clip" {
-                if keyval {
-                    clip_amount = vec[1].to_string().parse::<f64>().unwrap();
-                } else {
-                    clip_amount = args[i + 1].to_string().parse::<f64>().unwrap();
-                }
-                if clip_amount < 0.0 { clip_amount == 0.0; }
-            }
+        let args = ArgValues::parse(&self.parameters, &args)?;
+        let input_file = args.required_file("--input", working_directory)?;
+        let output_file = args.required_file("--output", working_directory)?;
+        let mut variant = args.get_string("--variant")?.unwrap_or("3x3".to_string());
+        if variant.contains("5") {
+            variant = "5x5".to_string();
+        } else {
+            variant = "3x3".to_string();
         }
+        let mut clip_amount = args.get_f64("--clip")?.unwrap_or(0.0);
+        if clip_amount < 0.0 { clip_amount = 0.0; }
 
         if verbose {
-            println!("***************{}", "*".repeat(self.get_tool_name().len()));
-            println!("* Welcome to {} *", self.get_tool_name());
-            println!("***************{}", "*".repeat(self.get_tool_name().len()));
-        }
-
-        let sep: String = path::MAIN_SEPARATOR.to_string();
-
-        if !input_file.contains(&sep) && !input_file.contains("/") {
-            input_file = format!("{}{}", working_directory, input_file);
-        }
-        if !output_file.contains(&sep) && !output_file.contains("/") {
-            output_file = format!("{}{}", working_directory, output_file);
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
+            sink.message(&format!("* Welcome to {} *", self.get_tool_name()));
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
         }
 
         let mut progress: usize;
         let mut old_progress: usize = 1;
 
         if verbose {
-            println!("Reading data...")
+            sink.message("Reading data...")
         };
 
-        let input = Arc::new(Raster::new(&input_file, "r")?);
+        let input = Raster::new(&input_file, "r")?;
         
         let start = time::now();
 
@@ -208,88 +167,95 @@ impl WhiteboxTool for SobelFilter {
         let mut output = Raster::initialize_using_file(&output_file, &input);
 
         let num_procs = num_cpus::get() as isize;
-        let (tx, rx) = mpsc::channel();
-        for tid in 0..num_procs {
-            let input = input.clone();
-            let variant = variant.clone();
-            let tx1 = tx.clone();
-            thread::spawn(move || {
-                let (mut slope_x, mut slope_y): (f64, f64);
-                let mut z: f64;
-                let mut zn: f64;
+        // The input image is read in windows of rows, each with a halo of two rows on either side, so
+        // that rasters that are too large to hold in memory are streamed through the tool.
+        let window_rows = input.get_window_rows();
+        let mut start_row = 0;
+        while start_row < rows {
+            let end_row = min(start_row + window_rows, rows);
+            let window = Arc::new(input.get_window(start_row - 2, end_row + 2));
+            let (tx, rx) = mpsc::channel();
+            for tid in 0..num_procs {
+                let window = window.clone();
+                let variant = variant.clone();
+                let tx1 = tx.clone();
+                thread::spawn(move || {
+                    let (mut slope_x, mut slope_y): (f64, f64);
+                    let mut z: f64;
+                    let mut zn: f64;
 
-                if variant.contains("3x3") {
-                    let dx = [ 1, 1, 1, 0, -1, -1, -1, 0 ];
-                    let dy = [ -1, 0, 1, 1, 1, 0, -1, -1 ];
-                    let mask_x = [ 1.0, 2.0, 1.0, 0.0, -1.0, -2.0, -1.0, 0.0 ];
-                    let mask_y = [ 1.0, 0.0, -1.0, -2.0, -1.0, 0.0, 1.0, 2.0 ];
-                    let num_pixels_in_filter = dx.len();
+                    if variant.contains("3x3") {
+                        let dx = [ 1, 1, 1, 0, -1, -1, -1, 0 ];
+                        let dy = [ -1, 0, 1, 1, 1, 0, -1, -1 ];
+                        let mask_x = [ 1.0, 2.0, 1.0, 0.0, -1.0, -2.0, -1.0, 0.0 ];
+                        let mask_y = [ 1.0, 0.0, -1.0, -2.0, -1.0, 0.0, 1.0, 2.0 ];
+                        let num_pixels_in_filter = dx.len();
 
-                    for row in (0..rows).filter(|r| r % num_procs == tid) {
-                        let mut data = vec![nodata; columns as usize];
-                        for col in 0..columns {
-                            z = input[(row, col)];
-                            if z != nodata {
-                                slope_x = 0.0;
-                                slope_y = 0.0;
-                                for i in 0..num_pixels_in_filter {
-                                    zn = input[(row + dy[i], col + dx[i])];
-                                    if zn == nodata {
-                                        zn = z; // replace it with z
+                        for row in (start_row..end_row).filter(|r| r % num_procs == tid) {
+                            let mut data = vec![nodata; columns as usize];
+                            for col in 0..columns {
+                                z = window.get_value(row, col);
+                                if z != nodata {
+                                    slope_x = 0.0;
+                                    slope_y = 0.0;
+                                    for i in 0..num_pixels_in_filter {
+                                        zn = window.get_value(row + dy[i], col + dx[i]);
+                                        if zn == nodata {
+                                            zn = z; // replace it with z
+                                        }
+                                        slope_x += zn * mask_x[i];
+                                        slope_y += zn * mask_y[i];
                                     }
-                                    slope_x += zn * mask_x[i];
-                                    slope_y += zn * mask_y[i];
+                                    data[col as usize] = (slope_x * slope_x + slope_y * slope_y).sqrt();
                                 }
-                                data[col as usize] = (slope_x * slope_x + slope_y * slope_y).sqrt();
                             }
+                            tx1.send((row, data)).unwrap();
                         }
-                        tx1.send((row, data)).unwrap();
-                    }
-                } else { // 5x5
-                    let dx = [ -2, -1, 0, 1, 2, -2, -1, 0, 1, 2, -2, -1, 0, 1, 2, -2, -1, 0, 1, 2, -2, -1, 0, 1, 2 ];
-                    let dy = [ -2, -2, -2, -2, -2, -1, -1, -1, -1, -1, 0, 0, 0, 0, 0, 1, 1, 1, 1, 1, 2, 2, 2, 2, 2 ];
-                    let mask_x = [ 2.0, 1.0, 0.0, -1.0, -2.0, 3.0, 2.0, 0.0, -2.0, -3.0, 4.0, 3.0, 0.0, -3.0, -4.0, 3.0, 2.0, 0.0, -2.0, -3.0, 2.0, 1.0, 0.0, -1.0, -2.0 ];
-                    let mask_y = [ 2.0, 3.0, 4.0, 3.0, 2.0, 1.0, 2.0, 3.0, 2.0, 1.0, 0.0, 0.0, 0.0, 0.0, 0.0, -1.0, -2.0, -3.0, -2.0, -1.0, -2.0, -3.0, -4.0, -3.0, -2.0 ];
-                    let num_pixels_in_filter = dx.len();
+                    } else { // 5x5
+                        let dx = [ -2, -1, 0, 1, 2, -2, -1, 0, 1, 2, -2, -1, 0, 1, 2, -2, -1, 0, 1, 2, -2, -1, 0, 1, 2 ];
+                        let dy = [ -2, -2, -2, -2, -2, -1, -1, -1, -1, -1, 0, 0, 0, 0, 0, 1, 1, 1, 1, 1, 2, 2, 2, 2, 2 ];
+                        let mask_x = [ 2.0, 1.0, 0.0, -1.0, -2.0, 3.0, 2.0, 0.0, -2.0, -3.0, 4.0, 3.0, 0.0, -3.0, -4.0, 3.0, 2.0, 0.0, -2.0, -3.0, 2.0, 1.0, 0.0, -1.0, -2.0 ];
+                        let mask_y = [ 2.0, 3.0, 4.0, 3.0, 2.0, 1.0, 2.0, 3.0, 2.0, 1.0, 0.0, 0.0, 0.0, 0.0, 0.0, -1.0, -2.0, -3.0, -2.0, -1.0, -2.0, -3.0, -4.0, -3.0, -2.0 ];
+                        let num_pixels_in_filter = dx.len();
 
-                    for row in (0..rows).filter(|r| r % num_procs == tid) {
-                        let mut data = vec![nodata; columns as usize];
-                        for col in 0..columns {
-                            z = input[(row, col)];
-                            if z != nodata {
-                                slope_x = 0.0;
-                                slope_y = 0.0;
-                                for i in 0..num_pixels_in_filter {
-                                    zn = input[(row + dy[i], col + dx[i])];
-                                    if zn == nodata {
-                                        zn = z; // replace it with z
+                        for row in (start_row..end_row).filter(|r| r % num_procs == tid) {
+                            let mut data = vec![nodata; columns as usize];
+                            for col in 0..columns {
+                                z = window.get_value(row, col);
+                                if z != nodata {
+                                    slope_x = 0.0;
+                                    slope_y = 0.0;
+                                    for i in 0..num_pixels_in_filter {
+                                        zn = window.get_value(row + dy[i], col + dx[i]);
+                                        if zn == nodata {
+                                            zn = z; // replace it with z
+                                        }
+                                        slope_x += zn * mask_x[i];
+                                        slope_y += zn * mask_y[i];
                                     }
-                                    slope_x += zn * mask_x[i];
-                                    slope_y += zn * mask_y[i];
+                                    data[col as usize] = (slope_x * slope_x + slope_y * slope_y).sqrt();
                                 }
-                                data[col as usize] = (slope_x * slope_x + slope_y * slope_y).sqrt();
                             }
+                            tx1.send((row, data)).unwrap();
                         }
-                        tx1.send((row, data)).unwrap();
                     }
-                }
-            });
-        }
+                });
+            }
 
-        for row in 0..rows {
-            let data = rx.recv().unwrap();
-            output.set_row_data(data.0, data.1);
-            if verbose {
+            for row in start_row..end_row {
+                let data = rx.recv().unwrap();
+                output.set_row_data(data.0, data.1);
                 progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as usize;
                 if progress != old_progress {
-                    println!("Progress: {}%", progress);
+                    sink.progress("Progress", progress)?;
                     old_progress = progress;
                 }
             }
+            start_row = end_row;
         }
 
         if clip_amount > 0.0 {
-            println!("Clipping output...");
+            sink.message("Clipping output...");
             // let (mean, stdev) = output.calculate_mean_and_stdev();
             // let mut z: f64;
             // for row in 0..rows {
@@ -322,20 +288,19 @@ impl WhiteboxTool for SobelFilter {
         output.add_metadata_entry(format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
 
         if verbose {
-            println!("Saving data...")
+            sink.message("Saving data...")
         };
         let _ = match output.write() {
             Ok(_) => {
                 if verbose {
-                    println!("Output file written")
+                    sink.message("Output file written")
                 }
             }
             Err(e) => return Err(e),
         };
 
         if verbose {
-            println!("{}",
-                    &format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
+            sink.message(&format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
         }
 
         Ok(())
@@ -19,6 +19,7 @@ use raster::*;
 use std::io::{Error, ErrorKind};
 use structures::Array2D;
 use tools::*;
+use tools::arg_parser::ArgValues;
 
 pub struct TophatTransform {
     name: String,
@@ -55,7 +56,7 @@ impl TophatTransform {
 
         parameters.push(ToolParameter{
             name: "Filter X-Dimension".to_owned(), 
-            flags: vec!["--filterx".to_owned()], 
+            flags: vec!["--filter".to_owned(), "--filterx".to_owned()], 
             description: "Size of the filter kernel in the x-direction.".to_owned(),
             parameter_type: ParameterType::Integer,
             default_value: Some("11".to_owned()),
@@ -64,7 +65,7 @@ impl TophatTransform {
 
         parameters.push(ToolParameter{
             name: "Filter Y-Dimension".to_owned(), 
-            flags: vec!["--filtery".to_owned()], 
+            flags: vec!["--filter".to_owned(), "--filtery".to_owned()], 
             description: "Size of the filter kernel in the y-direction.".to_owned(),
             parameter_type: ParameterType::Integer,
             default_value: Some("11".to_owned()),
@@ -134,78 +135,29 @@ impl WhiteboxTool for TophatTransform {
         self.toolbox.clone()
     }
 
-    fn run<'a>(&self, args: Vec<String>, working_directory: &'a str, verbose: bool) -> Result<(), Error> {
-        let mut input_file = String::new();
-        let mut output_file = String::new();
-        let mut filter_size_x = 11usize;
-        let mut filter_size_y = 11usize;
-        let mut variant = "white".to_string();
+    fn run<'a>(&self, args: Vec<String>, working_directory: &'a str, verbose: bool, sink: &Arc<ProgressSink>) -> Result<(), Error> {
         if args.len() == 0 {
             return Err(Error::new(ErrorKind::InvalidInput,
                                 "Tool run with no paramters."));
         }
-        for i in 0..args.len() {
-            let mut arg = args[i].replace("\"", "");
-            arg = arg.replace("\'", "");
-            let cmd = arg.split("="); // in case an equals sign was used
-            let vec = cmd.collect::<Vec<&str>>();
-            let mut keyval = false;
-            if vec.len() > 1 {
-                keyval = true;
-            }
-            if vec[0].to_lowercase() == "-i" || vec[0].to_lowercase() == "--input" {
-                if keyval {
-                    input_file = vec[1].to_string();
-                } else {
-                    input_file = args[i+1].to_string();
-                }
-            } else if vec[0].to_lowercase() == "-o" || vec[0].to_lowercase() == "--output" {
-                if keyval {
-                    output_file = vec[1].to_string();
-                } else {
-                    output_file = args[i+1].to_string();
-                }
-            } else if vec[0].to_lowercase() == "-filter" || vec[0].to_lowercase() == "--filter" {
-                if keyval {
-                    filter_size_x = vec[1].to_string().parse::<usize>().unwrap();
-                } else {
-                    filter_size_x = args[i+1].to_string().parse::<usize>().unwrap();
-                }
-                filter_size_y = filter_size_x;
-            } else if vec[0].to_lowercase() == "-filterx" || vec[0].to_lowercase() == "--filterx" {
-                if keyval {
-                    filter_size_x = vec[1].to_string().parse::<usize>().unwrap();
-                } else {
-                    filter_size_x = args[i+1].to_string().parse::<usize>().unwrap();
-                }
-            } else if vec[0].to_lowercase() == "-filtery" || vec[0].to_lowercase() == "--filtery" {
-                if keyval {
-                    filter_size_y = vec[1].to_string().parse::<usize>().unwrap();
-                } else {
-                    filter_size_y = args[i+1].to_string().parse::<usize>().unwrap();
-                }
-            } else if vec[0].to_lowercase() == "-variant" || vec[0].to_lowercase() == "--variant" {
-                if keyval {
-                    variant = vec[1].to_string();
-                } else {
-                    variant = args[i + 1].to_string();
-                }
-                if variant.to_lowercase().contains("b") {
-                    variant = "black".to_string();
-                } else {
-                    variant = "white".to_string();
-                }
-            }
+        let args = ArgValues::parse(&self.parameters, &args)?;
+        let input_file = args.required_file("--input", working_directory)?;
+        let output_file = args.required_file("--output", working_directory)?;
+        let mut filter_size_x = args.get_usize("--filterx")?.unwrap_or(11usize);
+        let mut filter_size_y = args.get_usize("--filtery")?.unwrap_or(11usize);
+        let mut variant = args.get_string("--variant")?.unwrap_or("white".to_string());
+        if variant.to_lowercase().contains("b") {
+            variant = "black".to_string();
+        } else {
+            variant = "white".to_string();
         }
 
         if verbose {
-            println!("***************{}", "*".repeat(self.get_tool_name().len()));
-            println!("* Welcome to {} *", self.get_tool_name());
-            println!("***************{}", "*".repeat(self.get_tool_name().len()));
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
+            sink.message(&format!("* Welcome to {} *", self.get_tool_name()));
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
         }
 
-        let sep: String = path::MAIN_SEPARATOR.to_string();
-
         if filter_size_x < 3 { filter_size_x = 3; }
         if filter_size_y < 3 { filter_size_y = 3; }
 
@@ -222,14 +174,7 @@ impl WhiteboxTool for TophatTransform {
         let mut progress: usize;
         let mut old_progress: usize = 1;
 
-        if !input_file.contains(&sep) && !input_file.contains("/") {
-            input_file = format!("{}{}", working_directory, input_file);
-        }
-        if !output_file.contains(&sep) && !output_file.contains("/") {
-            output_file = format!("{}{}", working_directory, output_file);
-        }
-
-        if verbose { println!("Reading data...") };
+        if verbose { sink.message("Reading data...") };
 
         let input = Arc::new(Raster::new(&input_file, "r")?);
 
@@ -302,12 +247,10 @@ impl WhiteboxTool for TophatTransform {
             for row in 0..rows {
                 let data = rx.recv().unwrap();
                 erosion.set_row_data(data.0, data.1);
-                if verbose {
-                    progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as usize;
-                    if progress != old_progress {
-                        println!("Progress (Loop 1 of 2): {}%", progress);
-                        old_progress = progress;
-                    }
+                progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as usize;
+                if progress != old_progress {
+                    sink.progress("Progress (Loop 1 of 2)", progress)?;
+                    old_progress = progress;
                 }
             }
 
@@ -360,7 +303,7 @@ impl WhiteboxTool for TophatTransform {
                                     if filter_max_vals[i] > max_val { max_val = filter_max_vals[i]; }
                                 }
                                 if max_val > f64::NEG_INFINITY {
-                                    data[col as usize] = input_data[(row, col)] - max_val;
+                                    data[col as usize] = input_data.get_value(row, col) - max_val;
                                 }
                             }
                         }
@@ -373,12 +316,10 @@ impl WhiteboxTool for TophatTransform {
             for row in 0..rows {
                 let data = rx.recv().unwrap();
                 output.set_row_data(data.0, data.1);
-                if verbose {
-                    progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as usize;
-                    if progress != old_progress {
-                        println!("Progress (Loop 2 of 2): {}%", progress);
-                        old_progress = progress;
-                    }
+                progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as usize;
+                if progress != old_progress {
+                    sink.progress("Progress (Loop 2 of 2)", progress)?;
+                    old_progress = progress;
                 }
             }
 
@@ -391,13 +332,13 @@ impl WhiteboxTool for TophatTransform {
             output.add_metadata_entry(format!("Variant: {}", variant));
             output.add_metadata_entry(format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
 
-            if verbose { println!("Saving data...") };
+            if verbose { sink.message("Saving data...") };
             let _ = match output.write() {
-                Ok(_) => if verbose { println!("Output file written") },
+                Ok(_) => if verbose { sink.message("Output file written") },
                 Err(e) => return Err(e),
             };
 
-            println!("{}", &format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
+            sink.message(&format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
 
         } else { // black top-hat transform 
             // first perform the dilation
@@ -460,12 +401,10 @@ impl WhiteboxTool for TophatTransform {
             for row in 0..rows {
                 let data = rx.recv().unwrap();
                 dilation.set_row_data(data.0, data.1);
-                if verbose {
-                    progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as usize;
-                    if progress != old_progress {
-                        println!("Progress (Loop 1 of 2): {}%", progress);
-                        old_progress = progress;
-                    }
+                progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as usize;
+                if progress != old_progress {
+                    sink.progress("Progress (Loop 1 of 2)", progress)?;
+                    old_progress = progress;
                 }
             }
 
@@ -518,7 +457,7 @@ impl WhiteboxTool for TophatTransform {
                                     if filter_min_vals[i] < min_val { min_val = filter_min_vals[i]; }
                                 }
                                 if min_val < f64::INFINITY {
-                                    data[col as usize] = min_val - input_data[(row, col)];
+                                    data[col as usize] = min_val - input_data.get_value(row, col);
                                 }
                             }
                         }
@@ -531,12 +470,10 @@ impl WhiteboxTool for TophatTransform {
             for row in 0..rows {
                 let data = rx.recv().unwrap();
                 output.set_row_data(data.0, data.1);
-                if verbose {
-                    progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as usize;
-                    if progress != old_progress {
-                        println!("Progress (Loop 2 of 2): {}%", progress);
-                        old_progress = progress;
-                    }
+                progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as usize;
+                if progress != old_progress {
+                    sink.progress("Progress (Loop 2 of 2)", progress)?;
+                    old_progress = progress;
                 }
             }
 
@@ -549,13 +486,13 @@ impl WhiteboxTool for TophatTransform {
             output.add_metadata_entry(format!("Variant: {}", variant));
             output.add_metadata_entry(format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
 
-            if verbose { println!("Saving data...") };
+            if verbose { sink.message("Saving data...") };
             let _ = match output.write() {
-                Ok(_) => if verbose { println!("Output file written") },
+                Ok(_) => if verbose { sink.message("Output file written") },
                 Err(e) => return Err(e),
             };
             if verbose {
-                println!("{}", &format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
+                sink.message(&format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
             }
         }
 
@@ -19,6 +19,7 @@ use std::path;
 use lidar::*;
 use raster::*;
 use tools::*;
+use tools::arg_parser::ArgValues;
 
 pub struct BlockMinimum {
     name: String,
@@ -133,54 +134,19 @@ impl WhiteboxTool for BlockMinimum {
     fn run<'a>(&self,
                args: Vec<String>,
                working_directory: &'a str,
-               verbose: bool)
+               verbose: bool,
+               sink: &Arc<ProgressSink>)
                -> Result<(), Error> {
-        let mut input_file: String = "".to_string();
-        let mut output_file: String = "".to_string();
-        let mut grid_res: f64 = 1.0;
-        let mut palette = "default".to_string();
-
         // read the arguments
         if args.len() == 0 {
             return Err(Error::new(ErrorKind::InvalidInput,
                                   "Tool run with no paramters."));
         }
-        for i in 0..args.len() {
-            let mut arg = args[i].replace("\"", "");
-            arg = arg.replace("\'", "");
-            let cmd = arg.split("="); // in case an equals sign was used
-            let vec = cmd.collect::<Vec<&str>>();
-            let mut keyval = false;
-            if vec.len() > 1 {
-                keyval = true;
-            }
-            let flag_val = vec[0].to_lowercase().replace("--", "-");
-            if flag_val == "-i" || flag_val == "-input" {
-                input_file = if keyval {
-                    vec[1].to_string()
-                } else {
-                    args[i + 1].to_string()
-                };
-            } else if flag_val == "-o" || flag_val == "-output" {
-                output_file = if keyval {
-                    vec[1].to_string()
-                } else {
-                    args[i + 1].to_string()
-                };
-            } else if flag_val == "-resolution" {
-                grid_res = if keyval {
-                    vec[1].to_string().parse::<f64>().unwrap()
-                } else {
-                    args[i + 1].to_string().parse::<f64>().unwrap()
-                };
-            } else if flag_val == "-palette" {
-                palette = if keyval {
-                    vec[1].to_string()
-                } else {
-                    args[i + 1].to_string()
-                };
-            }
-        }
+        let args = ArgValues::parse(&self.parameters, &args)?;
+        let mut input_file = args.get_string("--input")?.unwrap_or("".to_string());
+        let mut output_file = args.get_string("--output")?.unwrap_or("".to_string());
+        let grid_res = args.get_f64("--resolution")?.unwrap_or(1.0);
+        let palette = args.get_string("--palette")?.unwrap_or("default".to_string());
 
         let start = time::now();
 
@@ -192,27 +158,30 @@ impl WhiteboxTool for BlockMinimum {
                     "This tool must be run by specifying either an individual input file or a working directory."));
             }
             match fs::read_dir(working_directory) {
-                Err(why) => println!("! {:?}", why.kind()),
+                Err(why) => sink.message(&format!("! {:?}", why.kind())),
                 Ok(paths) => for path in paths {
                     let s = format!("{:?}", path.unwrap().path());
                     if s.replace("\"", "").to_lowercase().ends_with(".las") {
                         inputs.push(format!("{:?}", s.replace("\"", "")));
                         outputs.push(inputs[inputs.len()-1].replace(".las", ".tif").replace(".LAS", ".tif"))
+                    } else if s.replace("\"", "").to_lowercase().ends_with(".laz") {
+                        inputs.push(format!("{:?}", s.replace("\"", "")));
+                        outputs.push(inputs[inputs.len()-1].replace(".laz", ".tif").replace(".LAZ", ".tif"))
                     }
                 },
             }
         } else {
             inputs.push(input_file.clone());
             if output_file.is_empty() {
-                output_file = input_file.clone().replace(".las", ".tif").replace(".LAS", ".tif");
+                output_file = input_file.clone().replace(".las", ".tif").replace(".LAS", ".tif").replace(".laz", ".tif").replace(".LAZ", ".tif");
             }
             outputs.push(output_file);
         }
 
         if verbose {
-            println!("***************{}", "*".repeat(self.get_tool_name().len()));
-            println!("* Welcome to {} *", self.get_tool_name());
-            println!("***************{}", "*".repeat(self.get_tool_name().len()));
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
+            sink.message(&format!("* Welcome to {} *", self.get_tool_name()));
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
         }
 
         for k in 0..inputs.len() {
@@ -220,7 +189,7 @@ impl WhiteboxTool for BlockMinimum {
             output_file = outputs[k].replace("\"", "").clone();
 
             if verbose && inputs.len() > 1 {
-                println!("Gridding {} of {} ({})", k+1, inputs.len(), input_file.clone());
+                sink.message(&format!("Gridding {} of {} ({})", k+1, inputs.len(), input_file.clone()));
             }
 
             if !input_file.contains(path::MAIN_SEPARATOR) {
@@ -231,7 +200,7 @@ impl WhiteboxTool for BlockMinimum {
             }
 
             if verbose && inputs.len() == 1 {
-                println!("Reading input LAS file...");
+                sink.message("Reading input LAS file...");
             }
             let input = match LasFile::new(&input_file, "r") {
                 Ok(lf) => lf,
@@ -241,7 +210,7 @@ impl WhiteboxTool for BlockMinimum {
             let start_run = time::now();
 
             if verbose && inputs.len() == 1 {
-                println!("Performing analysis...");
+                sink.message("Performing analysis...");
             }
 
             let n_points = input.header.number_of_points as usize;
@@ -304,15 +273,13 @@ impl WhiteboxTool for BlockMinimum {
                 row = data.0;
                 col = data.1;
                 z = data.2;
-                if output[(row, col)] == nodata || z < output[(row, col)] {
+                if output.get_value(row, col) == nodata || z < output.get_value(row, col) {
                     output.set_value(row, col, z);
                 }
-                if verbose {
-                    progress = (100.0_f64 * i as f64 / num_points) as i32;
-                    if progress != old_progress {
-                        println!("Progress: {}%", progress);
-                        old_progress = progress;
-                    }
+                progress = (100.0_f64 * i as f64 / num_points) as i32;
+                if progress != old_progress {
+                    sink.progress("Progress", progress as usize)?;
+                    old_progress = progress;
                 }
             }
 
@@ -325,12 +292,12 @@ impl WhiteboxTool for BlockMinimum {
                                         .replace("PT", ""));
 
             if verbose {
-                println!("Saving data...")
+                sink.message("Saving data...")
             };
             let _ = match output.write() {
                 Ok(_) => {
                     if verbose {
-                        println!("Output file written")
+                        sink.message("Output file written")
                     }
                 }
                 Err(e) => return Err(e),
@@ -340,8 +307,7 @@ impl WhiteboxTool for BlockMinimum {
         let end = time::now();
         let elapsed_time = end - start;
         if verbose {
-            println!("{}",
-                 &format!("Elapsed Time (including I/O): {}", elapsed_time).replace("PT", ""));
+            sink.message(&format!("Elapsed Time (including I/O): {}", elapsed_time).replace("PT", ""));
         }
 
 
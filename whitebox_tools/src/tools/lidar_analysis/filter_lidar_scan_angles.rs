@@ -2,7 +2,7 @@
 This tool is part of the WhiteboxTools geospatial analysis library.
 Authors: Dr. John Lindsay
 Created: September 17, 2017
-Last Modified: February 14, 2018
+Last Modified: March 28, 2018
 License: MIT
 
 NOTES: 1. This tool outputs a LAS file, compared with the original Whitebox GAT tool, which output a Shapefile.
@@ -14,8 +14,10 @@ use std::env;
 use std::f64;
 use std::path;
 use std::io::{Error, ErrorKind};
+use std::sync::Arc;
 use lidar::*;
 use tools::*;
+use tools::arg_parser::ArgValues;
 
 pub struct FilterLidarScanAngles {
     name: String,
@@ -113,47 +115,20 @@ impl WhiteboxTool for FilterLidarScanAngles {
         self.toolbox.clone()
     }
 
-    fn run<'a>(&self, args: Vec<String>, working_directory: &'a str, verbose: bool) -> Result<(), Error> {
-        let mut input_file: String = "".to_string();
-        let mut output_file: String = "".to_string();
-        let mut threshold = 0i16;
-        
+    fn run<'a>(&self, args: Vec<String>, working_directory: &'a str, verbose: bool, sink: &Arc<ProgressSink>) -> Result<(), Error> {
         // read the arguments
         if args.len() == 0 {
             return Err(Error::new(ErrorKind::InvalidInput, "Tool run with no paramters."));
         }
-        for i in 0..args.len() {
-            let mut arg = args[i].replace("\"", "");
-            arg = arg.replace("\'", "");
-            let cmd = arg.split("="); // in case an equals sign was used
-            let vec = cmd.collect::<Vec<&str>>();
-            let mut keyval = false;
-            if vec.len() > 1 { keyval = true; }
-            if vec[0].to_lowercase() == "-i" || vec[0].to_lowercase() == "--input" {
-                if keyval {
-                    input_file = vec[1].to_string();
-                } else {
-                    input_file = args[i+1].to_string();
-                }
-            } else if vec[0].to_lowercase() == "-o" || vec[0].to_lowercase() == "--output" {
-                if keyval {
-                    output_file = vec[1].to_string();
-                } else {
-                    output_file = args[i+1].to_string();
-                }
-            } else if vec[0].to_lowercase() == "-threshold" || vec[0].to_lowercase() == "--threshold" {
-                if keyval {
-                    threshold = vec[1].to_string().parse::<i16>().unwrap().abs();
-                } else {
-                    threshold = args[i+1].to_string().parse::<i16>().unwrap().abs();
-                }
-            }
-        }
+        let args = ArgValues::parse(&self.parameters, &args)?;
+        let mut input_file = args.required_string("--input")?;
+        let mut output_file = args.required_string("--output")?;
+        let threshold = args.get_i64("--threshold")?.map(|v| (v as i16).abs()).unwrap_or(0i16);
 
         if verbose {
-            println!("***************{}", "*".repeat(self.get_tool_name().len()));
-            println!("* Welcome to {} *", self.get_tool_name());
-            println!("***************{}", "*".repeat(self.get_tool_name().len()));
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
+            sink.message(&format!("* Welcome to {} *", self.get_tool_name()));
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
         }
 
         let sep = path::MAIN_SEPARATOR;
@@ -164,49 +139,51 @@ impl WhiteboxTool for FilterLidarScanAngles {
             output_file = format!("{}{}", working_directory, output_file);
         }
 
-        if verbose { println!("Reading input LAS file..."); }
-        let input = match LasFile::new(&input_file, "r") {
+        let mut input = match LasReader::new(&input_file) {
             Ok(lf) => lf,
             Err(err) => panic!("Error reading file {}: {}", input_file, err),
         };
 
         let start = time::now();
 
-        if verbose { println!("Performing analysis..."); }
+        if verbose { sink.message("Performing analysis..."); }
 
-        let n_points = input.header.number_of_points as usize;
         let num_points: f64 = (input.header.number_of_points - 1) as f64; // used for progress calculation only
 
         let mut progress: i32;
         let mut old_progress: i32 = -1;
         
-        // now output the data
-        let mut output = LasFile::initialize_using_file(&output_file, &input);
-        output.header.system_id = "EXTRACTION".to_string();
+        // the points are read and written in chunks, so that files of any size can be filtered
+        let mut header = input.header.clone();
+        header.system_id = "EXTRACTION".to_string();
+        let mut output = LasWriter::new(&output_file, &header, &input.vlr_data)?;
+        for evlr in &input.evlr_data {
+            output.add_evlr(evlr.clone());
+        }
 
-        for i in 0..n_points {
-            if input[i].scan_angle.abs() <= threshold {
-                output.add_point_record(input.get_record(i));
-            }
-            if verbose {
-                progress = (100.0_f64 * i as f64 / num_points) as i32;
-                if progress != old_progress {
-                    println!("Progress: {}%", progress);
-                    old_progress = progress;
+        while let Some(chunk) = input.next() {
+            for point in chunk? {
+                if point.point_data().scan_angle.abs() <= threshold {
+                    output.add_point_record(point)?;
                 }
             }
+            progress = (100.0_f64 * (input.points_read() - 1) as f64 / num_points) as i32;
+            if progress != old_progress {
+                sink.progress("Progress", progress as usize)?;
+                old_progress = progress;
+            }
         }
 
+        let _ = match output.finish() {
+            Ok(_) => sink.message("Complete!"),
+            Err(e) => return Err(e),
+        };
+
         let end = time::now();
         let elapsed_time = end - start;
 
-        if verbose { println!("Writing output LAS file..."); }
-        let _ = match output.write() {
-            Ok(_) => println!("Complete!"),
-            Err(e) => println!("error while writing: {:?}", e),
-        };
         if verbose {
-            println!("{}", &format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
+            sink.message(&format!("Elapsed Time: {}", elapsed_time).replace("PT", ""));
         }
 
         Ok(())
@@ -11,8 +11,10 @@ use std::env;
 use std::f64;
 use std::path;
 use std::io::{Error, ErrorKind};
+use std::sync::Arc;
 use lidar::*;
 use tools::*;
+use tools::arg_parser::ArgValues;
 
 pub struct FindFlightlineEdgePoints {
     name: String,
@@ -101,40 +103,19 @@ impl WhiteboxTool for FindFlightlineEdgePoints {
         self.toolbox.clone()
     }
     
-    fn run<'a>(&self, args: Vec<String>, working_directory: &'a str, verbose: bool) -> Result<(), Error> {
-        let mut input_file: String = "".to_string();
-        let mut output_file: String = "".to_string();
-        
+    fn run<'a>(&self, args: Vec<String>, working_directory: &'a str, verbose: bool, sink: &Arc<ProgressSink>) -> Result<(), Error> {
         // read the arguments
         if args.len() == 0 {
             return Err(Error::new(ErrorKind::InvalidInput, "Tool run with no paramters."));
         }
-        for i in 0..args.len() {
-            let mut arg = args[i].replace("\"", "");
-            arg = arg.replace("\'", "");
-            let cmd = arg.split("="); // in case an equals sign was used
-            let vec = cmd.collect::<Vec<&str>>();
-            let mut keyval = false;
-            if vec.len() > 1 { keyval = true; }
-            if vec[0].to_lowercase() == "-i" || vec[0].to_lowercase() == "--input" {
-                if keyval {
-                    input_file = vec[1].to_string();
-                } else {
-                    input_file = args[i+1].to_string();
-                }
-            } else if vec[0].to_lowercase() == "-o" || vec[0].to_lowercase() == "--output" {
-                if keyval {
-                    output_file = vec[1].to_string();
-                } else {
-                    output_file = args[i+1].to_string();
-                }
-            }
-        }
+        let args = ArgValues::parse(&self.parameters, &args)?;
+        let mut input_file = args.required_string("--input")?;
+        let mut output_file = args.required_string("--output")?;
 
         if verbose {
-            println!("***************{}", "*".repeat(self.get_tool_name().len()));
-            println!("* Welcome to {} *", self.get_tool_name());
-            println!("***************{}", "*".repeat(self.get_tool_name().len()));
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
+            sink.message(&format!("* Welcome to {} *", self.get_tool_name()));
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
         }
 
         let sep = path::MAIN_SEPARATOR;
@@ -145,7 +126,7 @@ impl WhiteboxTool for FindFlightlineEdgePoints {
             output_file = format!("{}{}", working_directory, output_file);
         }
 
-        if verbose { println!("Reading input LAS file..."); }
+        if verbose { sink.message("Reading input LAS file..."); }
         let input = match LasFile::new(&input_file, "r") {
             Ok(lf) => lf,
             Err(err) => panic!("Error reading file {}: {}", input_file, err),
@@ -167,30 +148,28 @@ impl WhiteboxTool for FindFlightlineEdgePoints {
                 output.add_point_record(input.get_record(i));
                 num_output_points += 1;
             }
-            if verbose {
-                progress = (100.0_f64 * i as f64 / num_points) as i32;
-                if progress != old_progress {
-                    println!("Progress: {}%", progress);
-                    old_progress = progress;
-                }
+            progress = (100.0_f64 * i as f64 / num_points) as i32;
+            if progress != old_progress {
+                sink.progress("Progress", progress as usize)?;
+                old_progress = progress;
             }
         }
 
         let end = time::now();
         let elapsed_time = end - start;
 
-        println!("");
+        sink.message("");
         if num_output_points > 0 {
-        if verbose { println!("Writing output LAS file..."); }
+        if verbose { sink.message("Writing output LAS file..."); }
             let _ = match output.write() {
-                Ok(_) => println!("Complete!"),
-                Err(e) => println!("error while writing: {:?}", e),
+                Ok(_) => sink.message("Complete!"),
+                Err(e) => return Err(e),
             };
         } else {
-            println!("No points were flagged as flightline edge features in the LAS files.");
+            sink.message("No points were flagged as flightline edge features in the LAS files.");
         }
         if verbose {
-            println!("{}", &format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
+            sink.message(&format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
         }
 
         Ok(())
@@ -2,7 +2,7 @@
 This tool is part of the WhiteboxTools geospatial analysis library.
 Authors: Dr. John Lindsay
 Created: July 16, 2017
-Last Modified: Dec. 15, 2017
+Last Modified: March 27, 2018
 License: MIT
 */
 extern crate time;
@@ -14,8 +14,10 @@ use std;
 use std::env;
 use std::io::{Error, ErrorKind};
 use std::path;
+use std::sync::Arc;
 use lidar::*;
 use tools::*;
+use tools::arg_parser::ArgValues;
 
 pub struct LasToAscii {
     name: String,
@@ -95,33 +97,18 @@ impl WhiteboxTool for LasToAscii {
         self.toolbox.clone()
     }
 
-    fn run<'a>(&self, args: Vec<String>, working_directory: &'a str, verbose: bool) -> Result<(), Error> {
-        let mut input_files: String = String::new();
-    
+    fn run<'a>(&self, args: Vec<String>, working_directory: &'a str, verbose: bool, sink: &Arc<ProgressSink>) -> Result<(), Error> {
         // read the arguments
         if args.len() == 0 {
             return Err(Error::new(ErrorKind::InvalidInput, "Tool run with no paramters."));
         }
-        for i in 0..args.len() {
-            let mut arg = args[i].replace("\"", "");
-            arg = arg.replace("\'", "");
-            let cmd = arg.split("="); // in case an equals sign was used
-            let vec = cmd.collect::<Vec<&str>>();
-            let mut keyval = false;
-            if vec.len() > 1 { keyval = true; }
-            if vec[0].to_lowercase() == "-i" || vec[0].to_lowercase() == "--inputs" {
-                if keyval {
-                    input_files = vec[1].to_string();
-                } else {
-                    input_files = args[i+1].to_string();
-                }
-            }
-        }
+        let args = ArgValues::parse(&self.parameters, &args)?;
+        let input_files = args.required_string("--inputs")?;
 
         if verbose {
-            println!("***************{}", "*".repeat(self.get_tool_name().len()));
-            println!("* Welcome to {} *", self.get_tool_name());
-            println!("***************{}", "*".repeat(self.get_tool_name().len()));
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
+            sink.message(&format!("* Welcome to {} *", self.get_tool_name()));
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
         }
 
         let sep = std::path::MAIN_SEPARATOR;
@@ -146,7 +133,7 @@ impl WhiteboxTool for LasToAscii {
                     input_file = format!("{}{}", working_directory, input_file);
                 }
 
-                let input: LasFile = match LasFile::new(&input_file, "r") {
+                let mut input = match LasReader::new(&input_file) {
                     Ok(lf) => lf,
                     Err(_) => return Err(Error::new(ErrorKind::NotFound, format!("No such file or directory ({})", input_file))),
                 };
@@ -155,6 +142,8 @@ impl WhiteboxTool for LasToAscii {
                     input_file.replace(".las", ".txt")
                 } else if input_file.to_lowercase().ends_with(".zip") {
                     input_file.replace(".zip", ".txt")
+                } else if input_file.to_lowercase().ends_with(".laz") {
+                    input_file.replace(".laz", ".txt")
                 } else {
                     return Err(Error::new(ErrorKind::NotFound, format!("No such file or directory ({})", input_file)));
                 };
@@ -166,21 +155,22 @@ impl WhiteboxTool for LasToAscii {
                 let n_points = input.header.number_of_points as usize;
 
                 writer.write_all("X Y Z Intensity Class Return Num_returns\n".as_bytes())?;
-                for k in 0..n_points {
-                    let pd = input[k];
-                    let s = format!("{} {} {} {} {} {} {}\n", pd.x, pd.y, pd.z, pd.intensity, pd.classification(), pd.return_number(), pd.number_of_returns());
-                    writer.write_all(s.as_bytes())?;
-
-                    if verbose {
-                        progress = (100.0_f64 * k as f64 / (n_points - 1) as f64) as usize;
-                        if progress != old_progress {
-                            if num_files > 1 {
-                                println!("Creating file: {} of {}: {}%", i, num_files, progress);
-                            } else {
-                                println!("Progress: {}%", progress);
-                            }
-                            old_progress = progress;
+                // the points are read in chunks, so that files of any size can be converted
+                while let Some(chunk) = input.next() {
+                    for point in chunk? {
+                        let pd = point.point_data();
+                        let s = format!("{} {} {} {} {} {} {}\n", pd.x, pd.y, pd.z, pd.intensity, pd.classification(), pd.return_number(), pd.number_of_returns());
+                        writer.write_all(s.as_bytes())?;
+                    }
+
+                    progress = (100.0_f64 * (input.points_read() - 1) as f64 / (n_points - 1) as f64) as usize;
+                    if progress != old_progress {
+                        if num_files > 1 {
+                            sink.progress(&format!("Creating file: {} of {}", i, num_files), progress)?;
+                        } else {
+                            sink.progress("Progress", progress)?;
                         }
+                        old_progress = progress;
                     }
                 }
                 let _ = writer.flush();
@@ -191,7 +181,7 @@ impl WhiteboxTool for LasToAscii {
         if verbose {
             let end = time::now();
             let elapsed_time = end - start;
-            println!("{}", &format!("Elapsed Time: {}", elapsed_time).replace("PT", ""));
+            sink.message(&format!("Elapsed Time: {}", elapsed_time).replace("PT", ""));
         }
 
         Ok(())
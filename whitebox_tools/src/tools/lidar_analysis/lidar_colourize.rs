@@ -2,7 +2,7 @@
 This tool is part of the WhiteboxTools geospatial analysis library.
 Authors: Dr. John Lindsay
 Created: February 18, 2018
-Last Modified: February 18, 2018
+Last Modified: March 28, 2018
 License: MIT
 */
 extern crate time;
@@ -18,6 +18,7 @@ use std::thread;
 use lidar::*;
 use raster::*;
 use tools::*;
+use tools::arg_parser::ArgValues;
 
 pub struct LidarColourize {
     name: String,
@@ -115,48 +116,20 @@ impl WhiteboxTool for LidarColourize {
         self.toolbox.clone()
     }
 
-    fn run<'a>(&self, args: Vec<String>, working_directory: &'a str, verbose: bool) -> Result<(), Error> {
-        let mut input_lidar_file: String = "".to_string();
-        let mut input_image_file: String = "".to_string();
-        let mut output_file: String = "".to_string();
-        
+    fn run<'a>(&self, args: Vec<String>, working_directory: &'a str, verbose: bool, sink: &Arc<ProgressSink>) -> Result<(), Error> {
         // read the arguments
         if args.len() == 0 {
             return Err(Error::new(ErrorKind::InvalidInput, "Tool run with no paramters."));
         }
-        for i in 0..args.len() {
-            let mut arg = args[i].replace("\"", "");
-            arg = arg.replace("\'", "");
-            let cmd = arg.split("="); // in case an equals sign was used
-            let vec = cmd.collect::<Vec<&str>>();
-            let mut keyval = false;
-            if vec.len() > 1 { keyval = true; }
-            let flag_val = vec[0].to_lowercase().replace("--", "-");
-            if flag_val == "-in_lidar" {
-                input_lidar_file = if keyval {
-                    vec[1].to_string()
-                } else {
-                    args[i+1].to_string()
-                };
-            } else if flag_val == "-in_image" {
-                input_image_file = if keyval {
-                    vec[1].to_string()
-                } else {
-                    args[i+1].to_string()
-                };
-            } else if flag_val == "-o" || flag_val == "-output" {
-                output_file = if keyval {
-                    vec[1].to_string()
-                } else {
-                    args[i+1].to_string()
-                };
-            }
-        }
+        let args = ArgValues::parse(&self.parameters, &args)?;
+        let mut input_lidar_file = args.required_string("--in_lidar")?;
+        let mut input_image_file = args.required_string("--in_image")?;
+        let mut output_file = args.required_string("--output")?;
 
         if verbose {
-            println!("***************{}", "*".repeat(self.get_tool_name().len()));
-            println!("* Welcome to {} *", self.get_tool_name());
-            println!("***************{}", "*".repeat(self.get_tool_name().len()));
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
+            sink.message(&format!("* Welcome to {} *", self.get_tool_name()));
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
         }
 
         let sep = path::MAIN_SEPARATOR;
@@ -170,13 +143,13 @@ impl WhiteboxTool for LidarColourize {
             output_file = format!("{}{}", working_directory, output_file);
         }
 
-        if verbose { println!("Reading input files..."); }
+        if verbose { sink.message("Reading input files..."); }
         let in_lidar = Arc::new(LasFile::new(&input_lidar_file, "r")?);
         let in_image = Arc::new(Raster::new(&input_image_file, "r")?);
 
         let start = time::now();
 
-        if verbose { println!("Performing analysis..."); }
+        if verbose { sink.message("Performing analysis..."); }
 
         let n_points = in_lidar.header.number_of_points as usize;
         let num_points: f64 = (in_lidar.header.number_of_points - 1) as f64; // used for progress calculation only
@@ -211,25 +184,20 @@ impl WhiteboxTool for LidarColourize {
         for i in 0..n_points {
             let data = rx.recv().unwrap();
             colour_values[data.0] = data.1;
-            if verbose {
-                progress = (100.0_f64 * i as f64 / num_points) as i32;
-                if progress != old_progress {
-                    println!("Progress: {}%", progress);
-                    old_progress = progress;
-                }
+            progress = (100.0_f64 * i as f64 / num_points) as i32;
+            if progress != old_progress {
+                sink.progress("Progress", progress as usize)?;
+                old_progress = progress;
             }
         }
 
-        ////////////////////////////////////////////////////////////////
-        // NOTICE THIS NEEDS UPDATING ONCE LAS 1.4 OUTPUT IS SUPPORTED /
-        ////////////////////////////////////////////////////////////////
-
         // now output the data
         let mut output = LasFile::initialize_using_file(&output_file, &in_lidar);
         let out_pt_format = match in_lidar.header.point_format {
             0 | 2 => 2, // No GPS data supplied
             1 | 3 | 4 | 5 => 3, // GPS data is supplied
-            6 | 7 | 8 | 9 | 10  => 3, // This is a 64-bit format and will require LAS 1.4 output support. For now, output point format 3.
+            6 | 7 | 9 => 7, // 64-bit formats
+            8 | 10 => 8, // 64-bit formats with a NIR band, which is preserved
             _ => return Err(Error::new(ErrorKind::InvalidInput, "Unsupport input point record format.")),
         };
         output.header.point_format = out_pt_format;
@@ -252,35 +220,48 @@ impl WhiteboxTool for LidarColourize {
                     point_data: p, 
                     colour_data: rgb 
                 });
-            } else {
+            } else if out_pt_format == 3 {
                 gps = in_lidar.get_gps_time(i)?;
                 output.add_point_record(LidarPointRecord::PointRecord3 { 
                     point_data: p, 
                     gps_data: gps, 
                     colour_data: rgb 
                 });
+            } else if out_pt_format == 7 {
+                gps = in_lidar.get_gps_time(i)?;
+                output.add_point_record(LidarPointRecord::PointRecord7 { 
+                    point_data: p, 
+                    gps_data: gps, 
+                    colour_data: rgb 
+                });
+            } else {
+                gps = in_lidar.get_gps_time(i)?;
+                let nir = in_lidar.get_rgb(i)?.nir;
+                output.add_point_record(LidarPointRecord::PointRecord8 { 
+                    point_data: p, 
+                    gps_data: gps, 
+                    colour_data: ColourData{ red: r, green: g, blue: b, nir: nir }
+                });
             }
 
-            if verbose {
-                progress = (100.0_f64 * i as f64 / num_points) as i32;
-                if progress != old_progress {
-                    println!("Saving data: {}%", progress);
-                    old_progress = progress;
-                }
+            progress = (100.0_f64 * i as f64 / num_points) as i32;
+            if progress != old_progress {
+                sink.progress("Saving data", progress as usize)?;
+                old_progress = progress;
             }
         }
 
         let end = time::now();
         let elapsed_time = end - start;
 
-        println!("");
-        if verbose { println!("Writing output LAS file..."); }
+        sink.message("");
+        if verbose { sink.message("Writing output LAS file..."); }
         let _ = match output.write() {
-            Ok(_) => println!("Complete!"),
-            Err(e) => println!("error while writing: {:?}", e),
+            Ok(_) => sink.message("Complete!"),
+            Err(e) => return Err(e),
         };
         if verbose {
-            println!("{}", &format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
+            sink.message(&format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
         }
 
         Ok(())
@@ -2,16 +2,19 @@
 This tool is part of the WhiteboxTools geospatial analysis library.
 Authors: Dr. John Lindsay
 Created: June 2, 2017
-Last Modified: February 14, 2018
+Last Modified: March 28, 2018
 License: MIT
 */
 
 use std::env;
 use std::f64;
+use std::fs;
 use std::path;
 use std::io::{Error, ErrorKind};
+use std::sync::Arc;
 use lidar::*;
 use tools::*;
+use tools::arg_parser::ArgValues;
 
 pub struct LidarElevationSlice {
     name: String,
@@ -147,209 +150,142 @@ impl WhiteboxTool for LidarElevationSlice {
         self.toolbox.clone()
     }
 
-    fn run<'a>(&self, args: Vec<String>, working_directory: &'a str, verbose: bool) -> Result<(), Error> {
-        let mut input_file: String = "".to_string();
-        let mut output_file: String = "".to_string();
-        let mut minz = -f64::INFINITY;
-        let mut maxz = f64::INFINITY;
-        let mut filter = true;
-        let mut in_class_value = 2u8;
-        let mut out_class_value = 1u8;
-
+    fn run<'a>(&self, args: Vec<String>, working_directory: &'a str, verbose: bool, sink: &Arc<ProgressSink>) -> Result<(), Error> {
         // read the arguments
         if args.len() == 0 {
             return Err(Error::new(ErrorKind::InvalidInput, "Tool run with no paramters."));
         }
-        for i in 0..args.len() {
-            let mut arg = args[i].replace("\"", "");
-            arg = arg.replace("\'", "");
-            let cmd = arg.split("="); // in case an equals sign was used
-            let vec = cmd.collect::<Vec<&str>>();
-            let mut keyval = false;
-            if vec.len() > 1 { keyval = true; }
-            if vec[0].to_lowercase() == "-i" || vec[0].to_lowercase() == "--input" {
-                if keyval {
-                    input_file = vec[1].to_string();
-                } else {
-                    input_file = args[i+1].to_string();
-                }
-            } else if vec[0].to_lowercase() == "-o" || vec[0].to_lowercase() == "--output" {
-                if keyval {
-                    output_file = vec[1].to_string();
-                } else {
-                    output_file = args[i+1].to_string();
-                }
-            } else if vec[0].to_lowercase() == "-maxz" || vec[0].to_lowercase() == "--maxz" {
-                if keyval {
-                    maxz = vec[1].to_string().parse::<f64>().unwrap();
-                } else {
-                    maxz = args[i+1].to_string().parse::<f64>().unwrap();
-                }
-            } else if vec[0].to_lowercase() == "-minz" || vec[0].to_lowercase() == "--minz" {
-                if keyval {
-                    minz = vec[1].to_string().parse::<f64>().unwrap();
-                } else {
-                    minz = args[i+1].to_string().parse::<f64>().unwrap();
-                }
-            } else if vec[0].to_lowercase() == "-class" || vec[0].to_lowercase() == "--class" {
-                filter = false;
-            } else if vec[0].to_lowercase() == "-inclassval" || vec[0].to_lowercase() == "--inclassval" {
-                filter = false;
-                if keyval {
-                    in_class_value = vec[1].to_string().parse::<u8>().unwrap();
-                } else {
-                    in_class_value = args[i+1].to_string().parse::<u8>().unwrap();
-                }
-            } else if vec[0].to_lowercase() == "-outclassval" || vec[0].to_lowercase() == "--outclassval" {
-                filter = false;
-                if keyval {
-                    out_class_value = vec[1].to_string().parse::<u8>().unwrap();
-                } else {
-                    out_class_value = args[i+1].to_string().parse::<u8>().unwrap();
-                }
-            }
-        }
-
-        if !input_file.contains(path::MAIN_SEPARATOR) && !input_file.contains("/") {
-            input_file = format!("{}{}", working_directory, input_file);
-        }
-        if !output_file.contains(path::MAIN_SEPARATOR) && !output_file.contains("/") {
-            output_file = format!("{}{}", working_directory, output_file);
-        }
+        let args = ArgValues::parse(&self.parameters, &args)?;
+        let input_file = args.required_file("--input", working_directory)?;
+        let output_file = args.required_file("--output", working_directory)?;
+        let maxz = args.get_f64("--maxz")?.unwrap_or(f64::INFINITY);
+        let minz = args.get_f64("--minz")?.unwrap_or(-f64::INFINITY);
+        // setting either class value also switches from filtering to classifying points
+        let filter = !(args.get_bool("--class")? || args.is_given("--inclassval")? || args.is_given("--outclassval")?);
+        let in_class_value = args.get_usize("--inclassval")?.unwrap_or(2);
+        let out_class_value = args.get_usize("--outclassval")?.unwrap_or(1);
 
         if verbose {
-            println!("***********************************");
-            println!("* Welcome to lidar_elevation_slice *");
-            println!("************************************");
+            sink.message("***********************************");
+            sink.message("* Welcome to lidar_elevation_slice *");
+            sink.message("************************************");
         }
 
         if in_class_value > 31 || out_class_value > 31 {
             return Err(Error::new(ErrorKind::InvalidInput, "Error: Either the in-slice or out-of-slice class values are larger than 31."));
         }
 
-        let sep = path::MAIN_SEPARATOR;
-        if !input_file.contains(sep) {
-            input_file = format!("{}{}", working_directory, input_file);
-        }
-        if !output_file.contains(sep) {
-            output_file = format!("{}{}", working_directory, output_file);
-        }
-
-        if verbose { println!("Reading input LAS file..."); }
-        let input: LasFile = match LasFile::new(&input_file, "r") {
+        let mut input = match LasReader::new(&input_file) {
             Ok(lf) => lf,
             Err(_) => return Err(Error::new(ErrorKind::NotFound, format!("No such file or directory ({})", input_file))),
         };
-        let mut output = LasFile::initialize_using_file(&output_file, &input);
-        output.header.system_id = "EXTRACTION".to_string();
+        let mut header = input.header.clone();
+        header.system_id = "EXTRACTION".to_string();
+        let mut output = LasWriter::new(&output_file, &header, &input.vlr_data)?;
+        for evlr in &input.evlr_data {
+            output.add_evlr(evlr.clone());
+        }
 
-        if verbose { println!("Performing analysis..."); }
+        if verbose { sink.message("Performing analysis..."); }
         let mut z: f64;
         let mut progress: i32;
         let mut old_progress: i32 = -1;
         let mut num_points_filtered: i64 = 0;
         let num_points: f64 = (input.header.number_of_points - 1) as f64;
 
-        if filter {
-            for i in 0..input.header.number_of_points as usize {
-                z = input.get_point_info(i).z;
-                if z >= minz && z <= maxz {
-                    output.add_point_record(input.get_record(i));
-                    num_points_filtered += 1;
-                }
-                if verbose {
-                    progress = (100.0_f64 * i as f64 / num_points) as i32;
-                    if progress != old_progress {
-                        println!("Progress: {}%", progress);
-                        old_progress = progress;
+        // the points are read and written in chunks, so that files of any size can be processed
+        while let Some(chunk) = input.next() {
+            for pr in chunk? {
+                z = pr.point_data().z;
+                if filter {
+                    if z >= minz && z <= maxz {
+                        output.add_point_record(pr)?;
+                        num_points_filtered += 1;
                     }
-                }
-            }
-        } else {
-            for i in 0..input.header.number_of_points as usize {
-                let mut class_val = out_class_value; // outside elevation slice
-                z = input.get_point_info(i).z;
-                if z >= minz && z <= maxz {
-                    class_val = in_class_value; // inside elevation slice
-                }
-                let pr = input.get_record(i);
-                let pr2: LidarPointRecord;
-                match pr {
-                    LidarPointRecord::PointRecord0 { mut point_data }  => {
-                        point_data.set_classification(class_val);
-                        pr2 = LidarPointRecord::PointRecord0 { point_data: point_data };
+                } else {
+                    let mut class_val = out_class_value as u8; // outside elevation slice
+                    if z >= minz && z <= maxz {
+                        class_val = in_class_value as u8; // inside elevation slice
+                    }
+                    let pr2: LidarPointRecord;
+                    match pr {
+                        LidarPointRecord::PointRecord0 { mut point_data }  => {
+                            point_data.set_classification(class_val);
+                            pr2 = LidarPointRecord::PointRecord0 { point_data: point_data };
 
-                    },
-                    LidarPointRecord::PointRecord1 { mut point_data, gps_data } => {
-                        point_data.set_classification(class_val);
-                        pr2 = LidarPointRecord::PointRecord1 { point_data: point_data, gps_data: gps_data };
-                    },
-                    LidarPointRecord::PointRecord2 { mut point_data, colour_data } => {
-                        point_data.set_classification(class_val);
-                        pr2 = LidarPointRecord::PointRecord2 { point_data: point_data, colour_data: colour_data };
-                    },
-                    LidarPointRecord::PointRecord3 { mut point_data, gps_data, colour_data } => {
-                        point_data.set_classification(class_val);
-                        pr2 = LidarPointRecord::PointRecord3 { point_data: point_data,
-                            gps_data: gps_data, colour_data: colour_data};
-                    },
-                    LidarPointRecord::PointRecord4 { mut point_data, gps_data, wave_packet } => {
-                        point_data.set_classification(class_val);
-                        pr2 = LidarPointRecord::PointRecord4 { point_data: point_data,
-                            gps_data: gps_data, wave_packet: wave_packet};
-                    },
-                    LidarPointRecord::PointRecord5 { mut point_data, gps_data, colour_data, wave_packet } => {
-                        point_data.set_classification(class_val);
-                        pr2 = LidarPointRecord::PointRecord5 { point_data: point_data,
-                            gps_data: gps_data, colour_data: colour_data, wave_packet: wave_packet};
-                    },
-                    LidarPointRecord::PointRecord6 { mut point_data, gps_data } => {
-                        point_data.set_classification(class_val);
-                        pr2 = LidarPointRecord::PointRecord6 { point_data: point_data,
-                            gps_data: gps_data};
-                    },
-                    LidarPointRecord::PointRecord7 { mut point_data, gps_data, colour_data } => {
-                        point_data.set_classification(class_val);
-                        pr2 = LidarPointRecord::PointRecord7 { point_data: point_data,
-                            gps_data: gps_data, colour_data: colour_data};
-                    },
-                    LidarPointRecord::PointRecord8 { mut point_data, gps_data, colour_data } => {
-                        point_data.set_classification(class_val);
-                        pr2 = LidarPointRecord::PointRecord8 { point_data: point_data,
-                            gps_data: gps_data, colour_data: colour_data};
-                    },
-                    LidarPointRecord::PointRecord9 { mut point_data, gps_data, wave_packet } => {
-                        point_data.set_classification(class_val);
-                        pr2 = LidarPointRecord::PointRecord9 { point_data: point_data,
-                            gps_data: gps_data, wave_packet: wave_packet};
-                    },
-                    LidarPointRecord::PointRecord10 { mut point_data, gps_data, colour_data, wave_packet } => {
-                        point_data.set_classification(class_val);
-                        pr2 = LidarPointRecord::PointRecord10 { point_data: point_data,
-                            gps_data: gps_data, colour_data: colour_data, wave_packet: wave_packet};
-                    },
-                }
-                output.add_point_record(pr2);
-                if verbose {
-                    progress = (100.0_f64 * i as f64 / num_points) as i32;
-                    if progress != old_progress {
-                        println!("Saving data: {}%", progress);
-                        old_progress = progress;
+                        },
+                        LidarPointRecord::PointRecord1 { mut point_data, gps_data } => {
+                            point_data.set_classification(class_val);
+                            pr2 = LidarPointRecord::PointRecord1 { point_data: point_data, gps_data: gps_data };
+                        },
+                        LidarPointRecord::PointRecord2 { mut point_data, colour_data } => {
+                            point_data.set_classification(class_val);
+                            pr2 = LidarPointRecord::PointRecord2 { point_data: point_data, colour_data: colour_data };
+                        },
+                        LidarPointRecord::PointRecord3 { mut point_data, gps_data, colour_data } => {
+                            point_data.set_classification(class_val);
+                            pr2 = LidarPointRecord::PointRecord3 { point_data: point_data,
+                                gps_data: gps_data, colour_data: colour_data};
+                        },
+                        LidarPointRecord::PointRecord4 { mut point_data, gps_data, wave_packet } => {
+                            point_data.set_classification(class_val);
+                            pr2 = LidarPointRecord::PointRecord4 { point_data: point_data,
+                                gps_data: gps_data, wave_packet: wave_packet};
+                        },
+                        LidarPointRecord::PointRecord5 { mut point_data, gps_data, colour_data, wave_packet } => {
+                            point_data.set_classification(class_val);
+                            pr2 = LidarPointRecord::PointRecord5 { point_data: point_data,
+                                gps_data: gps_data, colour_data: colour_data, wave_packet: wave_packet};
+                        },
+                        LidarPointRecord::PointRecord6 { mut point_data, gps_data } => {
+                            point_data.set_classification(class_val);
+                            pr2 = LidarPointRecord::PointRecord6 { point_data: point_data,
+                                gps_data: gps_data};
+                        },
+                        LidarPointRecord::PointRecord7 { mut point_data, gps_data, colour_data } => {
+                            point_data.set_classification(class_val);
+                            pr2 = LidarPointRecord::PointRecord7 { point_data: point_data,
+                                gps_data: gps_data, colour_data: colour_data};
+                        },
+                        LidarPointRecord::PointRecord8 { mut point_data, gps_data, colour_data } => {
+                            point_data.set_classification(class_val);
+                            pr2 = LidarPointRecord::PointRecord8 { point_data: point_data,
+                                gps_data: gps_data, colour_data: colour_data};
+                        },
+                        LidarPointRecord::PointRecord9 { mut point_data, gps_data, wave_packet } => {
+                            point_data.set_classification(class_val);
+                            pr2 = LidarPointRecord::PointRecord9 { point_data: point_data,
+                                gps_data: gps_data, wave_packet: wave_packet};
+                        },
+                        LidarPointRecord::PointRecord10 { mut point_data, gps_data, colour_data, wave_packet } => {
+                            point_data.set_classification(class_val);
+                            pr2 = LidarPointRecord::PointRecord10 { point_data: point_data,
+                                gps_data: gps_data, colour_data: colour_data, wave_packet: wave_packet};
+                        },
                     }
+                    output.add_point_record(pr2)?;
+                    num_points_filtered += 1;
                 }
             }
-            num_points_filtered = 1;
+            progress = (100.0_f64 * (input.points_read() - 1) as f64 / num_points) as i32;
+            if progress != old_progress {
+                sink.progress("Progress", progress as usize)?;
+                old_progress = progress;
+            }
         }
 
-        if num_points_filtered > 0 {
-            if verbose { println!("Writing output LAS file..."); }
-            let _ = match output.write() {
-                Ok(_) => println!("Complete!"),
-                Err(e) => println!("error while writing: {:?}", e),
-            };
-        } else {
-            println!("No points were contained in the elevation slice.");
-        }
+        let _ = match output.finish() {
+            Ok(_) => {
+                if num_points_filtered > 0 {
+                    sink.message("Complete!");
+                } else {
+                    // an empty output file is not kept
+                    let _ = fs::remove_file(&output_file);
+                    sink.message("No points were contained in the elevation slice.");
+                }
+            },
+            Err(e) => return Err(e),
+        };
 
         Ok(())
     }
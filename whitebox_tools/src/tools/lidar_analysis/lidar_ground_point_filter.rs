@@ -18,6 +18,7 @@ use std::thread;
 use lidar::*;
 // use lidar::point_data::*;
 use tools::*;
+use tools::arg_parser::ArgValues;
 use structures::FixedRadiusSearch2D;
 
 pub struct LidarGroundPointFilter {
@@ -134,61 +135,22 @@ impl WhiteboxTool for LidarGroundPointFilter {
         self.toolbox.clone()
     }
 
-    fn run<'a>(&self, args: Vec<String>, working_directory: &'a str, verbose: bool) -> Result<(), Error> {
-        let mut input_file: String = "".to_string();
-        let mut output_file: String = "".to_string();
-        let mut search_radius: f64 = -1.0;
-        let mut height_threshold: f64 = 1.0;
-        let mut slope_threshold: f64 = 15.0;
-        
+    fn run<'a>(&self, args: Vec<String>, working_directory: &'a str, verbose: bool, sink: &Arc<ProgressSink>) -> Result<(), Error> {
         // read the arguments
         if args.len() == 0 {
             return Err(Error::new(ErrorKind::InvalidInput, "Tool run with no paramters."));
         }
-        for i in 0..args.len() {
-            let mut arg = args[i].replace("\"", "");
-            arg = arg.replace("\'", "");
-            let cmd = arg.split("="); // in case an equals sign was used
-            let vec = cmd.collect::<Vec<&str>>();
-            let mut keyval = false;
-            if vec.len() > 1 { keyval = true; }
-            if vec[0].to_lowercase() == "-i" || vec[0].to_lowercase() == "--input" {
-                if keyval {
-                    input_file = vec[1].to_string();
-                } else {
-                    input_file = args[i+1].to_string();
-                }
-            } else if vec[0].to_lowercase() == "-o" || vec[0].to_lowercase() == "--output" {
-                if keyval {
-                    output_file = vec[1].to_string();
-                } else {
-                    output_file = args[i+1].to_string();
-                }
-            } else if vec[0].to_lowercase() == "-radius" || vec[0].to_lowercase() == "--radius" {
-                if keyval {
-                    search_radius = vec[1].to_string().parse::<f64>().unwrap();
-                } else {
-                    search_radius = args[i+1].to_string().parse::<f64>().unwrap();
-                }
-            } else if vec[0].to_lowercase() == "-height_threshold" || vec[0].to_lowercase() == "--height_threshold" {
-                if keyval {
-                    height_threshold = vec[1].to_string().parse::<f64>().unwrap();
-                } else {
-                    height_threshold = args[i+1].to_string().parse::<f64>().unwrap();
-                }
-            } else if vec[0].to_lowercase() == "-slope_threshold" || vec[0].to_lowercase() == "--slope_threshold" {
-                if keyval {
-                    slope_threshold = vec[1].to_string().parse::<f64>().unwrap();
-                } else {
-                    slope_threshold = args[i+1].to_string().parse::<f64>().unwrap();
-                }
-            }
-        }
+        let args = ArgValues::parse(&self.parameters, &args)?;
+        let mut input_file = args.required_string("--input")?;
+        let mut output_file = args.required_string("--output")?;
+        let search_radius = args.get_f64("--radius")?.unwrap_or(-1.0);
+        let height_threshold = args.get_f64("--height_threshold")?.unwrap_or(1.0);
+        let mut slope_threshold = args.get_f64("--slope_threshold")?.unwrap_or(15.0);
 
         if verbose {
-            println!("***************{}", "*".repeat(self.get_tool_name().len()));
-            println!("* Welcome to {} *", self.get_tool_name());
-            println!("***************{}", "*".repeat(self.get_tool_name().len()));
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
+            sink.message(&format!("* Welcome to {} *", self.get_tool_name()));
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
         }
 
         let sep = path::MAIN_SEPARATOR;
@@ -199,7 +161,7 @@ impl WhiteboxTool for LidarGroundPointFilter {
             output_file = format!("{}{}", working_directory, output_file);
         }
 
-        if verbose { println!("Reading input LAS file..."); }
+        if verbose { sink.message("Reading input LAS file..."); }
         let input = match LasFile::new(&input_file, "r") {
             Ok(lf) => lf,
             Err(err) => panic!("Error reading file {}: {}", input_file, err),
@@ -207,7 +169,7 @@ impl WhiteboxTool for LidarGroundPointFilter {
 
         let start = time::now();
 
-        if verbose { println!("Performing analysis..."); }
+        if verbose { sink.message("Performing analysis..."); }
 
         slope_threshold = slope_threshold.to_radians().tan();
 
@@ -222,12 +184,10 @@ impl WhiteboxTool for LidarGroundPointFilter {
             if p.is_late_return() && !p.is_classified_noise() {
                 frs.insert(p.x, p.y, i);
             }
-            if verbose {
-                progress = (100.0_f64 * i as f64 / num_points) as i32;
-                if progress != old_progress {
-                    println!("Binning points: {}%", progress);
-                    old_progress = progress;
-                }
+            progress = (100.0_f64 * i as f64 / num_points) as i32;
+            if progress != old_progress {
+                sink.progress("Binning points", progress as usize)?;
+                old_progress = progress;
             }
         }
 
@@ -273,12 +233,10 @@ impl WhiteboxTool for LidarGroundPointFilter {
         for i in 0..n_points {
             let data = rx.recv().unwrap();
             neighbourhood_min[data.0] = data.1;
-            if verbose {
-                progress = (100.0_f64 * i as f64 / num_points) as i32;
-                if progress != old_progress {
-                    println!("Erosion: {}%", progress);
-                    old_progress = progress;
-                }
+            progress = (100.0_f64 * i as f64 / num_points) as i32;
+            if progress != old_progress {
+                sink.progress("Erosion", progress as usize)?;
+                old_progress = progress;
             }
         }
 
@@ -321,12 +279,10 @@ impl WhiteboxTool for LidarGroundPointFilter {
                 let z = input.get_point_info(data.0).z;
                 residuals[data.0] = z - data.1;
             }
-            if verbose {
-                progress = (100.0_f64 * i as f64 / num_points) as i32;
-                if progress != old_progress {
-                    println!("Dilation: {}%", progress);
-                    old_progress = progress;
-                }
+            progress = (100.0_f64 * i as f64 / num_points) as i32;
+            if progress != old_progress {
+                sink.progress("Dilation", progress as usize)?;
+                old_progress = progress;
             }
         }
 
@@ -376,12 +332,10 @@ impl WhiteboxTool for LidarGroundPointFilter {
         for i in 0..n_points {
             let data = rx.recv().unwrap();
             is_off_terrain[data.0] = data.1;
-            if verbose {
-                progress = (100.0_f64 * i as f64 / num_points) as i32;
-                if progress != old_progress {
-                    println!("Slope-based Filter: {}%", progress);
-                    old_progress = progress;
-                }
+            progress = (100.0_f64 * i as f64 / num_points) as i32;
+            if progress != old_progress {
+                sink.progress("Slope-based Filter", progress as usize)?;
+                old_progress = progress;
             }
         }
 
@@ -394,25 +348,23 @@ impl WhiteboxTool for LidarGroundPointFilter {
             if !is_off_terrain[i] {
                 output.add_point_record(input.get_record(i));
             }
-            if verbose {
-                progress = (100.0_f64 * i as f64 / num_points) as i32;
-                if progress != old_progress {
-                    println!("Saving data: {}%", progress);
-                    old_progress = progress;
-                }
+            progress = (100.0_f64 * i as f64 / num_points) as i32;
+            if progress != old_progress {
+                sink.progress("Saving data", progress as usize)?;
+                old_progress = progress;
             }
         }
 
         let end = time::now();
         let elapsed_time = end - start;
 
-        if verbose { println!("Writing output LAS file..."); }
+        if verbose { sink.message("Writing output LAS file..."); }
         let _ = match output.write() {
-            Ok(_) => println!("Complete!"),
-            Err(e) => println!("error while writing: {:?}", e),
+            Ok(_) => sink.message("Complete!"),
+            Err(e) => return Err(e),
         };
         if verbose {
-            println!("{}", &format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
+            sink.message(&format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
         }
 
         Ok(())
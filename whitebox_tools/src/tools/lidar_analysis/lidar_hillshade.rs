@@ -18,6 +18,7 @@ use std::sync::mpsc;
 use std::thread;
 use lidar::*;
 use tools::*;
+use tools::arg_parser::ArgValues;
 use self::na::Vector3;
 use structures::FixedRadiusSearch3D;
 
@@ -136,61 +137,22 @@ impl WhiteboxTool for LidarHillshade {
         self.toolbox.clone()
     }
 
-    fn run<'a>(&self, args: Vec<String>, working_directory: &'a str, verbose: bool) -> Result<(), Error> {
-        let mut input_file: String = "".to_string();
-        let mut output_file: String = "".to_string();
-        let mut search_radius: f64 = -1.0;
-        let mut azimuth = 315.0f64;
-        let mut altitude = 30.0f64;
-
+    fn run<'a>(&self, args: Vec<String>, working_directory: &'a str, verbose: bool, sink: &Arc<ProgressSink>) -> Result<(), Error> {
         // read the arguments
         if args.len() == 0 {
             return Err(Error::new(ErrorKind::InvalidInput, "Tool run with no paramters."));
         }
-        for i in 0..args.len() {
-            let mut arg = args[i].replace("\"", "");
-            arg = arg.replace("\'", "");
-            let cmd = arg.split("="); // in case an equals sign was used
-            let vec = cmd.collect::<Vec<&str>>();
-            let mut keyval = false;
-            if vec.len() > 1 { keyval = true; }
-            if vec[0].to_lowercase() == "-i" || vec[0].to_lowercase() == "--input" {
-                if keyval {
-                    input_file = vec[1].to_string();
-                } else {
-                    input_file = args[i+1].to_string();
-                }
-            } else if vec[0].to_lowercase() == "-o" || vec[0].to_lowercase() == "--output" {
-                if keyval {
-                    output_file = vec[1].to_string();
-                } else {
-                    output_file = args[i+1].to_string();
-                }
-            } else if vec[0].to_lowercase() == "-azimuth" || vec[0].to_lowercase() == "--azimuth" {
-                if keyval {
-                    azimuth = vec[1].to_string().parse::<f64>().unwrap();
-                } else {
-                    azimuth = args[i+1].to_string().parse::<f64>().unwrap();
-                }
-            } else if vec[0].to_lowercase() == "-altitude" || vec[0].to_lowercase() == "--altitude" {
-                if keyval {
-                    altitude = vec[1].to_string().parse::<f64>().unwrap();
-                } else {
-                    altitude = args[i+1].to_string().parse::<f64>().unwrap();
-                }
-            } else if vec[0].to_lowercase() == "-radius" || vec[0].to_lowercase() == "--radius" {
-                if keyval {
-                    search_radius = vec[1].to_string().parse::<f64>().unwrap();
-                } else {
-                    search_radius = args[i+1].to_string().parse::<f64>().unwrap();
-                }
-            }
-        }
+        let args = ArgValues::parse(&self.parameters, &args)?;
+        let mut input_file = args.required_string("--input")?;
+        let mut output_file = args.required_string("--output")?;
+        let mut azimuth = args.get_f64("--azimuth")?.unwrap_or(315.0f64);
+        let mut altitude = args.get_f64("--altitude")?.unwrap_or(30.0f64);
+        let search_radius = args.get_f64("--radius")?.unwrap_or(-1.0);
 
         if verbose {
-            println!("***************{}", "*".repeat(self.get_tool_name().len()));
-            println!("* Welcome to {} *", self.get_tool_name());
-            println!("***************{}", "*".repeat(self.get_tool_name().len()));
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
+            sink.message(&format!("* Welcome to {} *", self.get_tool_name()));
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
         }
 
         azimuth = (azimuth - 90f64).to_radians();
@@ -206,7 +168,7 @@ impl WhiteboxTool for LidarHillshade {
             output_file = format!("{}{}", working_directory, output_file);
         }
 
-        if verbose { println!("Reading input LAS file..."); }
+        if verbose { sink.message("Reading input LAS file..."); }
         let input = match LasFile::new(&input_file, "r") {
             Ok(lf) => lf,
             Err(err) => panic!("Error reading file {}: {}", input_file, err),
@@ -214,7 +176,7 @@ impl WhiteboxTool for LidarHillshade {
 
         let start = time::now();
 
-        if verbose { println!("Performing analysis..."); }
+        if verbose { sink.message("Performing analysis..."); }
 
         let n_points = input.header.number_of_points as usize;
         let num_points: f64 = (input.header.number_of_points - 1) as f64; // used for progress calculation only
@@ -225,12 +187,10 @@ impl WhiteboxTool for LidarHillshade {
         for i in 0..n_points {
             let p: PointData = input.get_point_info(i);
             frs.insert(p.x, p.y, p.z, i);
-            if verbose {
-                progress = (100.0_f64 * i as f64 / num_points) as i32;
-                if progress != old_progress {
-                    println!("Binning points: {}%", progress);
-                    old_progress = progress;
-                }
+            progress = (100.0_f64 * i as f64 / num_points) as i32;
+            if progress != old_progress {
+                sink.progress("Binning points", progress as usize)?;
+                old_progress = progress;
             }
         }
 
@@ -263,12 +223,10 @@ impl WhiteboxTool for LidarHillshade {
         for i in 0..n_points {
             let data = rx.recv().unwrap();
             normal_values[data.0] = data.1;
-            if verbose {
-                progress = (100.0_f64 * i as f64 / num_points) as i32;
-                if progress != old_progress {
-                    println!("Calculating point normals: {}%", progress);
-                    old_progress = progress;
-                }
+            progress = (100.0_f64 * i as f64 / num_points) as i32;
+            if progress != old_progress {
+                sink.progress("Calculating point normals", progress as usize)?;
+                old_progress = progress;
             }
         }
 
@@ -307,26 +265,24 @@ impl WhiteboxTool for LidarHillshade {
             let rgb: ColourData = ColourData{ red: v, green: v, blue: v, nir: 0u16 };
             let lpr: LidarPointRecord = LidarPointRecord::PointRecord2 { point_data: p, colour_data: rgb };
             output.add_point_record(lpr);
-            if verbose {
-                progress = (100.0_f64 * i as f64 / num_points) as i32;
-                if progress != old_progress {
-                    println!("Saving data: {}%", progress);
-                    old_progress = progress;
-                }
+            progress = (100.0_f64 * i as f64 / num_points) as i32;
+            if progress != old_progress {
+                sink.progress("Saving data", progress as usize)?;
+                old_progress = progress;
             }
         }
 
         let end = time::now();
         let elapsed_time = end - start;
 
-        println!("");
-        if verbose { println!("Writing output LAS file..."); }
+        sink.message("");
+        if verbose { sink.message("Writing output LAS file..."); }
         let _ = match output.write() {
-            Ok(_) => println!("Complete!"),
-            Err(e) => println!("error while writing: {:?}", e),
+            Ok(_) => sink.message("Complete!"),
+            Err(e) => return Err(e),
         };
         if verbose {
-            println!("{}", &format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
+            sink.message(&format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
         }
 
         Ok(())
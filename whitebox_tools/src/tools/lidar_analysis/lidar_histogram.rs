@@ -16,7 +16,9 @@ use std::f64;
 use std::process::Command;
 use lidar::*;
 use std::io::{Error, ErrorKind};
+use std::sync::Arc;
 use tools::*;
+use tools::arg_parser::ArgValues;
 use rendering::Histogram;
 use rendering::html::*;
 
@@ -82,7 +84,7 @@ impl LidarHistogram {
         if e.contains(".exe") {
             short_exe += ".exe";
         }
-        let usage = format!(">>.*{0} -r={1} -v --wd=\"*path*to*data*\" -i=\"file1.tif, file2.tif, file3.tif\" -o=outfile.htm --contiguity=Bishopsl",
+        let usage = format!(">>.*{0} -r={1} -v --wd=\"*path*to*data*\" -i=file.las -o=outfile.html --parameter=elevation --clip=1.0",
                             short_exe, name).replace("*", &sep);
 
         LidarHistogram {
@@ -133,74 +135,30 @@ impl WhiteboxTool for LidarHistogram {
     fn run<'a>(&self,
                args: Vec<String>,
                working_directory: &'a str,
-               verbose: bool)
+               verbose: bool,
+               sink: &Arc<ProgressSink>)
                -> Result<(), Error> {
-        let mut input_file = String::new();
-        let mut output_file = String::new();
-        let mut parameter = "elevation".to_string();
-        let mut clip_percent = 1.0f64;
-
         if args.len() == 0 {
             return Err(Error::new(ErrorKind::InvalidInput, "Tool run with no paramters."));
         }
-        for i in 0..args.len() {
-            let mut arg = args[i].replace("\"", "");
-            arg = arg.replace("\'", "");
-            let cmd = arg.split("="); // in case an equals sign was used
-            let vec = cmd.collect::<Vec<&str>>();
-            let mut keyval = false;
-            if vec.len() > 1 {
-                keyval = true;
-            }
-            let flag_val = vec[0].to_lowercase().replace("--", "-");
-            if flag_val == "-i" || flag_val == "-input" {
-                if keyval {
-                    input_file = vec[1].to_string();
-                } else {
-                    input_file = args[i+1].to_string();
-                }
-            } else if flag_val == "-o" || flag_val == "-output" {
-                if keyval {
-                    output_file = vec[1].to_string();
-                } else {
-                    output_file = args[i + 1].to_string();
-                }
-            } else if flag_val == "-parameter" {
-                if keyval {
-                    parameter = vec[1].to_string().to_lowercase();
-                } else {
-                    parameter = args[i + 1].to_string().to_lowercase();
-                }
-            } else if flag_val == "-clip" {
-                if keyval {
-                    clip_percent = vec[1].to_string().parse::<f64>().unwrap();
-                } else {
-                    clip_percent = args[i + 1].to_string().parse::<f64>().unwrap();
-                }
-            }
-        }
+        let args = ArgValues::parse(&self.parameters, &args)?;
+        let input_file = args.required_file("--input", working_directory)?;
+        let output_file = args.required_file("--output", working_directory)?;
+        let parameter = args.get_string("--parameter")?.unwrap_or("elevation".to_string()).to_lowercase();
+        let mut clip_percent = args.get_f64("--clip")?.unwrap_or(1.0f64);
 
         if verbose {
-            println!("***************{}", "*".repeat(self.get_tool_name().len()));
-            println!("* Welcome to {} *", self.get_tool_name());
-            println!("***************{}", "*".repeat(self.get_tool_name().len()));
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
+            sink.message(&format!("* Welcome to {} *", self.get_tool_name()));
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
         }
 
-        let sep: String = path::MAIN_SEPARATOR.to_string();
-
         let mut progress: i32;
         let mut old_progress: i32 = -1;
 
         let start = time::now();
         
-        if !input_file.contains(&sep) && !input_file.contains("/") {
-            input_file = format!("{}{}", working_directory, input_file);
-        }
-        if !output_file.contains(&sep) && !output_file.contains("/") {
-            output_file = format!("{}{}", working_directory, output_file);
-        }
-
-        if verbose { println!("Reading input LAS file..."); }
+        if verbose { sink.message("Reading input LAS file..."); }
         let input = match LasFile::new(&input_file, "r") {
             Ok(lf) => lf,
             Err(err) => panic!("Error reading file {}: {}", input_file, err),
@@ -216,7 +174,7 @@ impl WhiteboxTool for LidarHistogram {
             "scan angle" => 2,
             "class" => 3,
             _ => { 
-                println!("Warning: unrecognized parameter; elevation will be used");
+                sink.warning("Warning: unrecognized parameter; elevation will be used");
                 0 // elevation
             },
         };
@@ -235,12 +193,10 @@ impl WhiteboxTool for LidarHistogram {
             };
             if z < min { min = z; }
             if z > max { max = z; }
-            if verbose {
-                progress = (100.0_f64 * i as f64 / num_points) as i32;
-                if progress != old_progress {
-                    println!("Calculating min and max: {}%", progress);
-                    old_progress = progress;
-                }
+            progress = (100.0_f64 * i as f64 / num_points) as i32;
+            if progress != old_progress {
+                sink.progress("Calculating min and max", progress as usize)?;
+                old_progress = progress;
             }
         }
         
@@ -261,12 +217,10 @@ impl WhiteboxTool for LidarHistogram {
                 };
                 bin = ((z - min) / bin_width).floor() as isize;
                 freq_data[bin as usize] += 1;
-                if verbose {
-                    progress = (100.0_f64 * i as f64 / num_points) as i32;
-                    if progress != old_progress {
-                        println!("Clipping the tails: {}%", progress);
-                        old_progress = progress;
-                    }
+                progress = (100.0_f64 * i as f64 / num_points) as i32;
+                if progress != old_progress {
+                    sink.progress("Clipping the tails", progress as usize)?;
+                    old_progress = progress;
                 }
             }
 
@@ -324,12 +278,10 @@ impl WhiteboxTool for LidarHistogram {
                 if bin >= 0 && bin < num_bins as isize {
                     freq_data[bin as usize] += 1;
                 }
-                if verbose {
-                    progress = (100.0_f64 * i as f64 / num_points) as i32;
-                    if progress != old_progress {
-                        println!("Binning the data: {}%", progress);
-                        old_progress = progress;
-                    }
+                progress = (100.0_f64 * i as f64 / num_points) as i32;
+                if progress != old_progress {
+                    sink.progress("Binning the data", progress as usize)?;
+                    old_progress = progress;
                 }
             }
         } else {
@@ -345,12 +297,10 @@ impl WhiteboxTool for LidarHistogram {
                 if bin >= 0 && bin < num_bins as isize {
                     freq_data[bin as usize] += 1;
                 }
-                if verbose {
-                    progress = (100.0_f64 * i as f64 / num_points) as i32;
-                    if progress != old_progress {
-                        println!("Binning the data: {}%", progress);
-                        old_progress = progress;
-                    }
+                progress = (100.0_f64 * i as f64 / num_points) as i32;
+                if progress != old_progress {
+                    sink.progress("Binning the data", progress as usize)?;
+                    old_progress = progress;
                 }
             }
         }
@@ -406,8 +356,7 @@ impl WhiteboxTool for LidarHistogram {
         let elapsed_time = end - start;
 
         
-        if verbose { println!("\n{}",
-                 &format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", "")); }
+        if verbose { sink.message(&format!("\n{}", &format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""))); }
 
 
         if verbose {
@@ -435,7 +384,7 @@ impl WhiteboxTool for LidarHistogram {
                 let _ = output.stdout;
             }
             if verbose {
-                println!("Complete! Please see {} for output.", output_file);
+                sink.message(&format!("Complete! Please see {} for output.", output_file));
             }
         }
 
@@ -26,6 +26,7 @@ use raster::*;
 use structures::BoundingBox;
 use structures::FixedRadiusSearch2D;
 use tools::*;
+use tools::arg_parser::ArgValues;
 
 pub struct LidarIdwInterpolation {
     name: String,
@@ -204,115 +205,39 @@ impl WhiteboxTool for LidarIdwInterpolation {
     fn run<'a>(&self,
                args: Vec<String>,
                working_directory: &'a str,
-               verbose: bool)
+               verbose: bool,
+               sink: &Arc<ProgressSink>)
                -> Result<(), Error> {
-        let mut input_file: String = "".to_string();
-        let mut output_file: String = "".to_string();
-        let mut interp_parameter = "elevation".to_string();
-        let mut return_type = "all".to_string();
-        let mut grid_res: f64 = 1.0;
-        let mut weight = 1.0;
-        let mut search_radius = 2.5;
         let mut include_class_vals = vec![true; 256];
-        let mut palette = "default".to_string();
-        let mut exclude_cls_str = String::new();
-        let mut max_z = f64::INFINITY;
-        let mut min_z = f64::NEG_INFINITY;
 
         // read the arguments
         if args.len() == 0 {
             return Err(Error::new(ErrorKind::InvalidInput, "Tool run with no paramters."));
         }
-        for i in 0..args.len() {
-            let mut arg = args[i].replace("\"", "");
-            arg = arg.replace("\'", "");
-            let cmd = arg.split("="); // in case an equals sign was used
-            let vec = cmd.collect::<Vec<&str>>();
-            let mut keyval = false;
-            if vec.len() > 1 {
-                keyval = true;
-            }
-            let flag_val = vec[0].to_lowercase().replace("--", "-");
-            if flag_val == "-i" || flag_val == "-input" {
-                input_file = if keyval {
-                    vec[1].to_string()
-                } else {
-                    args[i + 1].to_string()
-                };
-            } else if flag_val == "-o" || flag_val == "-output" {
-                output_file = if keyval {
-                    vec[1].to_string()
-                } else {
-                    args[i + 1].to_string()
-                };
-            } else if flag_val == "-parameter" {
-                interp_parameter = if keyval {
-                    vec[1].to_string()
-                } else {
-                    args[i + 1].to_string()
-                };
-            } else if flag_val == "-returns" {
-                return_type = if keyval {
-                    vec[1].to_string()
-                } else {
-                    args[i + 1].to_string()
-                };
-            } else if flag_val == "-resolution" {
-                grid_res = if keyval {
-                    vec[1].to_string().parse::<f64>().unwrap()
-                } else {
-                    args[i + 1].to_string().parse::<f64>().unwrap()
-                };
-            } else if flag_val == "-weight" {
-                weight = if keyval {
-                    vec[1].to_string().parse::<f64>().unwrap()
-                } else {
-                    args[i + 1].to_string().parse::<f64>().unwrap()
-                };
-            } else if flag_val == "-radius" {
-                search_radius = if keyval {
-                    vec[1].to_string().parse::<f64>().unwrap()
-                } else {
-                    args[i + 1].to_string().parse::<f64>().unwrap()
-                };
-            } else if flag_val == "-palette" {
-                palette = if keyval {
-                    vec[1].to_string()
-                } else {
-                    args[i + 1].to_string()
-                };
-            } else if flag_val == "-exclude_cls" {
-                exclude_cls_str = if keyval {
-                    vec[1].to_string()
-                } else {
-                    args[i + 1].to_string()
-                };
-                let mut cmd = exclude_cls_str.split(",");
-                let mut vec = cmd.collect::<Vec<&str>>();
-                if vec.len() == 1 {
-                    cmd = exclude_cls_str.split(";");
-                    vec = cmd.collect::<Vec<&str>>();
-                }
-                for value in vec {
-                    if !value.trim().is_empty() {
-                        let c = value.trim().parse::<usize>().unwrap();
-                        include_class_vals[c] = false;
-                    }
-                }
-            } else if flag_val == "-minz" {
-                min_z = if keyval {
-                    vec[1].to_string().parse::<f64>().unwrap()
-                } else {
-                    args[i + 1].to_string().parse::<f64>().unwrap()
-                };
-            } else if flag_val == "-maxz" {
-                max_z = if keyval {
-                    vec[1].to_string().parse::<f64>().unwrap()
-                } else {
-                    args[i + 1].to_string().parse::<f64>().unwrap()
-                };
+        let args = ArgValues::parse(&self.parameters, &args)?;
+        let mut input_file = args.get_string("--input")?.unwrap_or("".to_string());
+        let mut output_file = args.get_string("--output")?.unwrap_or("".to_string());
+        let interp_parameter = args.get_string("--parameter")?.unwrap_or("elevation".to_string());
+        let return_type = args.get_string("--returns")?.unwrap_or("all".to_string());
+        let grid_res = args.get_f64("--resolution")?.unwrap_or(1.0);
+        let weight = args.get_f64("--weight")?.unwrap_or(1.0);
+        let search_radius = args.get_f64("--radius")?.unwrap_or(2.5);
+        let palette = args.get_string("--palette")?.unwrap_or("default".to_string());
+        let exclude_cls_str = args.get_string("--exclude_cls")?.unwrap_or(String::new());
+        let mut cmd = exclude_cls_str.split(",");
+        let mut vec = cmd.collect::<Vec<&str>>();
+        if vec.len() == 1 {
+            cmd = exclude_cls_str.split(";");
+            vec = cmd.collect::<Vec<&str>>();
+        }
+        for value in vec {
+            if !value.trim().is_empty() {
+                let c = value.trim().parse::<usize>().unwrap();
+                include_class_vals[c] = false;
             }
         }
+        let min_z = args.get_f64("--minz")?.unwrap_or(f64::NEG_INFINITY);
+        let max_z = args.get_f64("--maxz")?.unwrap_or(f64::INFINITY);
 
         let (all_returns, late_returns, early_returns): (bool, bool, bool);
         if return_type.contains("last") {
@@ -331,9 +256,9 @@ impl WhiteboxTool for LidarIdwInterpolation {
         }
 
         if verbose {
-            println!("***************{}", "*".repeat(self.get_tool_name().len()));
-            println!("* Welcome to {} *", self.get_tool_name());
-            println!("***************{}", "*".repeat(self.get_tool_name().len()));
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
+            sink.message(&format!("* Welcome to {} *", self.get_tool_name()));
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
         }
 
         let start = time::now();
@@ -346,7 +271,7 @@ impl WhiteboxTool for LidarIdwInterpolation {
                     "This tool must be run by specifying either an individual input file or a working directory."));
             }
             match fs::read_dir(working_directory) {
-                Err(why) => println!("! {:?}", why.kind()),
+                Err(why) => sink.message(&format!("! {:?}", why.kind())),
                 Ok(paths) => for path in paths {
                     let s = format!("{:?}", path.unwrap().path());
                     if s.replace("\"", "").to_lowercase().ends_with(".las") {
@@ -355,6 +280,9 @@ impl WhiteboxTool for LidarIdwInterpolation {
                     } else if s.replace("\"", "").to_lowercase().ends_with(".zip") {
                         inputs.push(format!("{:?}", s.replace("\"", "")));
                         outputs.push(inputs[inputs.len()-1].replace(".zip", ".tif").replace(".ZIP", ".tif"))
+                    } else if s.replace("\"", "").to_lowercase().ends_with(".laz") {
+                        inputs.push(format!("{:?}", s.replace("\"", "")));
+                        outputs.push(inputs[inputs.len()-1].replace(".laz", ".tif").replace(".LAZ", ".tif"))
                     }
                 },
             }
@@ -364,7 +292,7 @@ impl WhiteboxTool for LidarIdwInterpolation {
             }
             inputs.push(input_file.clone());
             if output_file.is_empty() {
-                output_file = input_file.clone().replace(".las", ".tif").replace(".LAS", ".tif");
+                output_file = input_file.clone().replace(".las", ".tif").replace(".LAS", ".tif").replace(".laz", ".tif").replace(".LAZ", ".tif");
             }
             outputs.push(output_file);
         }
@@ -386,7 +314,7 @@ impl WhiteboxTool for LidarIdwInterpolation {
         }
 
         if verbose {
-            println!("Performing interpolation...");
+            sink.message("Performing interpolation...");
         }
 
         let num_tiles = inputs.len();
@@ -409,6 +337,7 @@ impl WhiteboxTool for LidarIdwInterpolation {
             let exclude_cls_str = exclude_cls_str.clone();
             let include_class_vals = include_class_vals.clone();
             let tx2 = tx2.clone();
+            let sink = sink.clone();
             thread::spawn(move || {
                 let mut tile = 0;
                 while tile < num_tiles {
@@ -433,7 +362,7 @@ impl WhiteboxTool for LidarIdwInterpolation {
                     let mut frs: FixedRadiusSearch2D<f64> = FixedRadiusSearch2D::new(search_radius);
                     
                     if verbose && inputs.len() == 1 {
-                        println!("Reading input LAS file...");
+                        sink.message("Reading input LAS file...");
                     }
 
                     let mut progress: i32;
@@ -463,10 +392,10 @@ impl WhiteboxTool for LidarIdwInterpolation {
                                                 }
                                             }
                                         }
-                                        if verbose && inputs.len() == 1 {
+                                        if inputs.len() == 1 {
                                             progress = (100.0_f64 * i as f64 / num_points) as i32;
                                             if progress != old_progress {
-                                                println!("Binning points: {}%", progress);
+                                                sink.report_progress("Binning points", progress as usize);
                                                 old_progress = progress;
                                             }
                                         }
@@ -485,10 +414,10 @@ impl WhiteboxTool for LidarIdwInterpolation {
                                                 }
                                             }
                                         }
-                                        if verbose && inputs.len() == 1 {
+                                        if inputs.len() == 1 {
                                             progress = (100.0_f64 * i as f64 / num_points) as i32;
                                             if progress != old_progress {
-                                                println!("Binning points: {}%", progress);
+                                                sink.report_progress("Binning points", progress as usize);
                                                 old_progress = progress;
                                             }
                                         }
@@ -507,10 +436,10 @@ impl WhiteboxTool for LidarIdwInterpolation {
                                                 }
                                             }
                                         }
-                                        if verbose && inputs.len() == 1 {
+                                        if inputs.len() == 1 {
                                             progress = (100.0_f64 * i as f64 / num_points) as i32;
                                             if progress != old_progress {
-                                                println!("Binning points: {}%", progress);
+                                                sink.report_progress("Binning points", progress as usize);
                                                 old_progress = progress;
                                             }
                                         }
@@ -529,10 +458,10 @@ impl WhiteboxTool for LidarIdwInterpolation {
                                                 }
                                             }
                                         }
-                                        if verbose && inputs.len() == 1 {
+                                        if inputs.len() == 1 {
                                             progress = (100.0_f64 * i as f64 / num_points) as i32;
                                             if progress != old_progress {
-                                                println!("Binning points: {}%", progress);
+                                                sink.report_progress("Binning points", progress as usize);
                                                 old_progress = progress;
                                             }
                                         }
@@ -552,10 +481,10 @@ impl WhiteboxTool for LidarIdwInterpolation {
                                                 }
                                             }
                                         }
-                                        if verbose && inputs.len() == 1 {
+                                        if inputs.len() == 1 {
                                             progress = (100.0_f64 * i as f64 / num_points) as i32;
                                             if progress != old_progress {
-                                                println!("Binning points: {}%", progress);
+                                                sink.report_progress("Binning points", progress as usize);
                                                 old_progress = progress;
                                             }
                                         }
@@ -621,10 +550,10 @@ impl WhiteboxTool for LidarIdwInterpolation {
                                     }
                                 }
                             }
-                            if verbose && inputs.len() == 1 {
+                            if inputs.len() == 1 {
                                 progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as i32;
                                 if progress != old_progress {
-                                    println!("Progress: {}%", progress);
+                                    sink.report_progress("Progress", progress as usize);
                                     old_progress = progress;
                                 }
                             }
@@ -676,12 +605,10 @@ impl WhiteboxTool for LidarIdwInterpolation {
                         for row in 0..rows {
                             let data = rx.recv().unwrap();
                             output.set_row_data(data.0, data.1);
-                            if verbose {
-                                progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as i32;
-                                if progress != old_progress {
-                                    println!("Progress: {}%", progress);
-                                    old_progress = progress;
-                                }
+                            progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as i32;
+                            if progress != old_progress {
+                                sink.report_progress("Progress", progress as usize);
+                                old_progress = progress;
                             }
                         }
                     }
@@ -700,7 +627,7 @@ impl WhiteboxTool for LidarIdwInterpolation {
                     output.add_metadata_entry(format!("Elapsed Time (including I/O): {}", elapsed_time_run).replace("PT", ""));
 
                     if verbose && inputs.len() == 1 {
-                        println!("Saving data...")
+                        sink.message("Saving data...")
                     };
 
                     let _ = output.write().unwrap();
@@ -715,14 +642,12 @@ impl WhiteboxTool for LidarIdwInterpolation {
         for tile in 0..inputs.len() {
             let tile_completed = rx2.recv().unwrap();
             if verbose {
-                    println!("Finished interpolating {} ({} of {})", inputs[tile_completed].replace("\"", "").replace(working_directory, "").replace(".las", ""), tile+1, inputs.len());
+                    sink.message(&format!("Finished interpolating {} ({} of {})", inputs[tile_completed].replace("\"", "").replace(working_directory, "").replace(".las", "").replace(".laz", ""), tile+1, inputs.len()));
             }
-            if verbose {
-                progress = (100.0_f64 * tile as f64 / (inputs.len() - 1) as f64) as i32;
-                if progress != old_progress {
-                    println!("Progress: {}%", progress);
-                    old_progress = progress;
-                }
+            progress = (100.0_f64 * tile as f64 / (inputs.len() - 1) as f64) as i32;
+            if progress != old_progress {
+                sink.progress("Progress", progress as usize)?;
+                old_progress = progress;
             }
         }
 
@@ -730,7 +655,7 @@ impl WhiteboxTool for LidarIdwInterpolation {
         let elapsed_time = end - start;    
 
         if verbose {
-            println!("{}", &format!("Elapsed Time (including I/O): {}", elapsed_time).replace("PT", ""));
+            sink.message(&format!("Elapsed Time (including I/O): {}", elapsed_time).replace("PT", ""));
         }
 
         Ok(())
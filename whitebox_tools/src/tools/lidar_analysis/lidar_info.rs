@@ -2,7 +2,7 @@
 This tool is part of the WhiteboxTools geospatial analysis library.
 Authors: Dr. John Lindsay
 Created: June 1, 2017
-Last Modified: December 26, 2017
+Last Modified: March 28, 2018
 License: MIT
 */
 
@@ -15,8 +15,10 @@ use std::io::{Error, ErrorKind};
 use std::path;
 use std::u16;
 use std::process::Command;
+use std::sync::Arc;
 use lidar::*;
 use tools::*;
+use tools::arg_parser::ArgValues;
 
 /// This tool can be used to print basic information about the data contained within a LAS file, used to store LiDAR
 /// data. The reported information will include including data on the header, point return frequency, and classification 
@@ -31,7 +33,7 @@ use tools::*;
 /// | --geokeys | Flag indicates whether to print the geokeys.                    |
 ///
 /// # Example
-/// ```
+/// ```text
 /// >>./whitebox_tools -r=LidarInfo --wd=/path/to/data/ -i=file.las --vlr --geokeys
 /// ```
 
@@ -141,46 +143,20 @@ impl WhiteboxTool for LidarInfo {
         self.toolbox.clone()
     }
 
-    fn run<'a>(&self, args: Vec<String>, working_directory: &'a str, verbose: bool) -> Result<(), Error> {
-        let mut input_file: String = "".to_string();
-        let mut output_file = String::new();
-        let mut show_vlrs = false;
-        let mut show_geokeys = false;
-        let mut keyval: bool;
+    fn run<'a>(&self, args: Vec<String>, working_directory: &'a str, verbose: bool, sink: &Arc<ProgressSink>) -> Result<(), Error> {
         if args.len() == 0 {
             return Err(Error::new(ErrorKind::InvalidInput, "Tool run with no paramters."));
         }
-        for i in 0..args.len() {
-            let mut arg = args[i].replace("\"", "");
-            arg = arg.replace("\'", "");
-            let cmd = arg.split("="); // in case an equals sign was used
-            let vec = cmd.collect::<Vec<&str>>();
-            keyval = false;
-            if vec.len() > 1 { keyval = true; }
-            let flag_val = vec[0].to_lowercase().replace("--", "-");
-            if flag_val == "-i" || flag_val == "-input" {
-                if keyval {
-                    input_file = vec[1].to_string();
-                } else {
-                    input_file = args[i+1].to_string();
-                }
-            } else if flag_val == "-o" || flag_val == "-output" {
-                if keyval {
-                    output_file = vec[1].to_string();
-                } else {
-                    output_file = args[i + 1].to_string();
-                }
-            } else if flag_val == "-vlr" {
-                show_vlrs = true;
-            } else if flag_val == "-geokeys" {
-                show_geokeys = true;
-            }
-        }
+        let args = ArgValues::parse(&self.parameters, &args)?;
+        let mut input_file = args.required_string("--input")?;
+        let mut output_file = args.get_string("--output")?.unwrap_or(String::new());
+        let show_vlrs = args.get_bool("--vlr")?;
+        let show_geokeys = args.get_bool("--geokeys")?;
 
         if verbose {
-            println!("***************{}", "*".repeat(self.get_tool_name().len()));
-            println!("* Welcome to {} *", self.get_tool_name());
-            println!("***************{}", "*".repeat(self.get_tool_name().len()));
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
+            sink.message(&format!("* Welcome to {} *", self.get_tool_name()));
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
         }
 
         let sep = std::path::MAIN_SEPARATOR;
@@ -192,7 +168,7 @@ impl WhiteboxTool for LidarInfo {
             input_file = format!("{}{}", working_directory, input_file);
         }
 
-        if output_file.len() == 0 { output_file = input_file.replace(".las", "_summary.html"); }
+        if output_file.len() == 0 { output_file = input_file.replace(".las", "_summary.html").replace(".laz", "_summary.html"); }
 
 
         let f = File::create(output_file.clone())?;
@@ -434,6 +410,10 @@ impl WhiteboxTool for LidarInfo {
                 s = "<p>VLRs have not been set.</p>";
                 writer.write_all(s.as_bytes())?;
             }
+            for (i, evlr) in input.evlr_data.iter().enumerate() {
+                let s1 = &format!("<p>EVLR {}:<br>{}</p>", i, evlr);
+                writer.write_all(s1.as_bytes())?;
+            }
         }
 
         if show_geokeys {
@@ -473,7 +453,7 @@ impl WhiteboxTool for LidarInfo {
                 let _ = output.stdout;
             }
             if verbose {
-                println!("Complete! Please see {} for output.", output_file);
+                sink.message(&format!("Complete! Please see {} for output.", output_file));
             }
         }
 
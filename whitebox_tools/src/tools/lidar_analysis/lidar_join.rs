@@ -2,7 +2,7 @@
 This tool is part of the WhiteboxTools geospatial analysis library.
 Authors: Dr. John Lindsay
 Created: June 21, 2017
-Last Modified: December 15, 2017
+Last Modified: March 27, 2018
 License: MIT
 */
 
@@ -10,8 +10,10 @@ use std;
 use std::env;
 use std::io::{Error, ErrorKind};
 use std::path;
+use std::sync::Arc;
 use lidar::*;
 use tools::*;
+use tools::arg_parser::ArgValues;
 
 pub struct LidarJoin {
     name: String,
@@ -100,40 +102,19 @@ impl WhiteboxTool for LidarJoin {
         self.toolbox.clone()
     }
 
-    fn run<'a>(&self, args: Vec<String>, working_directory: &'a str, verbose: bool) -> Result<(), Error> {
-        let mut input_files: String = String::new();
-        let mut output_file = String::new();
-
+    fn run<'a>(&self, args: Vec<String>, working_directory: &'a str, verbose: bool, sink: &Arc<ProgressSink>) -> Result<(), Error> {
         // read the arguments
         if args.len() == 0 {
             return Err(Error::new(ErrorKind::InvalidInput, "Tool run with no paramters."));
         }
-        for i in 0..args.len() {
-            let mut arg = args[i].replace("\"", "");
-            arg = arg.replace("\'", "");
-            let cmd = arg.split("="); // in case an equals sign was used
-            let vec = cmd.collect::<Vec<&str>>();
-            let mut keyval = false;
-            if vec.len() > 1 { keyval = true; }
-            if vec[0].to_lowercase() == "-i" || vec[0].to_lowercase() == "--inputs" {
-                if keyval {
-                    input_files = vec[1].to_string();
-                } else {
-                    input_files = args[i+1].to_string();
-                }
-            } else if vec[0].to_lowercase() == "-o" || vec[0].to_lowercase() == "--output" {
-                if keyval {
-                    output_file = vec[1].to_string();
-                } else {
-                    output_file = args[i+1].to_string();
-                }
-            }
-        }
+        let args = ArgValues::parse(&self.parameters, &args)?;
+        let input_files = args.required_string("--inputs")?;
+        let mut output_file = args.required_string("--output")?;
 
         if verbose {
-            println!("***************{}", "*".repeat(self.get_tool_name().len()));
-            println!("* Welcome to {} *", self.get_tool_name());
-            println!("***************{}", "*".repeat(self.get_tool_name().len()));
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
+            sink.message(&format!("* Welcome to {} *", self.get_tool_name()));
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
         }
 
         let sep = std::path::MAIN_SEPARATOR;
@@ -142,7 +123,7 @@ impl WhiteboxTool for LidarJoin {
             output_file = format!("{}{}", working_directory, output_file);
         }
 
-        let mut output: LasFile = LasFile::new(&output_file, "w")?;
+        let mut output: Option<LasWriter> = None;
 
         let mut cmd = input_files.split(";");
         let mut vec = cmd.collect::<Vec<&str>>();
@@ -160,7 +141,7 @@ impl WhiteboxTool for LidarJoin {
                     input_file = format!("{}{}", working_directory, input_file);
                 }
 
-                let input = match LasFile::new(&input_file, "r") {
+                let mut input = match LasReader::new(&input_file) {
                     Ok(lf) => lf,
                     Err(_) => return Err(Error::new(ErrorKind::NotFound, format!("No such file or directory ({})", input_file))),
                 };
@@ -173,24 +154,28 @@ impl WhiteboxTool for LidarJoin {
                     }
                 }
 
-                if i == 0 {
-                    output = LasFile::initialize_using_file(&output_file, &input);
+                if output.is_none() {
+                    // the output uses the header, including the scale factors and offsets, of the first file
+                    output = Some(LasWriter::new(&output_file, &input.header, &input.vlr_data)?);
                 }
 
-                let n_points = input.header.number_of_points as usize;
-
-                let mut pr: LidarPointRecord;
-                for i in 0..n_points {
-                    pr = input.get_record(i);
-                    output.add_point_record(pr);
+                // the points are read and written in chunks, so that files of any size can be joined
+                if let Some(ref mut output) = output {
+                    while let Some(chunk) = input.next() {
+                        for pr in chunk? {
+                            output.add_point_record(pr)?;
+                        }
+                    }
                 }
             }
             i += 1;
-            if verbose { println!("Adding file: {} of {}", i, num_files); }
+            if verbose { sink.message(&format!("Adding file: {} of {}", i, num_files)); }
         }
 
-        if verbose { println!("Writing output LAS file..."); }
-        output.write()?;
+        match output {
+            Some(output) => output.finish()?,
+            None => return Err(Error::new(ErrorKind::InvalidInput, "No input files were specified.")),
+        }
 
         Ok(())
     }
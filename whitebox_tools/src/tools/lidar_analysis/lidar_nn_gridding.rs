@@ -26,6 +26,7 @@ use raster::*;
 use structures::BoundingBox;
 use structures::FixedRadiusSearch2D;
 use tools::*;
+use tools::arg_parser::ArgValues;
 
 pub struct LidarNearestNeighbourGridding {
     name: String,
@@ -194,117 +195,45 @@ impl WhiteboxTool for LidarNearestNeighbourGridding {
     fn run<'a>(&self,
                args: Vec<String>,
                working_directory: &'a str,
-               verbose: bool)
+               verbose: bool,
+               sink: &Arc<ProgressSink>)
                -> Result<(), Error> {
-        let mut input_file: String = "".to_string();
-        let mut output_file: String = "".to_string();
         // let mut lakes_file: String = "".to_string();
-        let mut interp_parameter = "elevation".to_string();
-        let mut return_type = "all".to_string();
-        let mut grid_res: f64 = 1.0;
-        let mut search_radius = 2.5;
         let mut include_class_vals = vec![true; 256];
-        let mut palette = "default".to_string();
-        let mut exclude_cls_str = String::new();
-        let mut max_z = f64::INFINITY;
-        let mut min_z = f64::NEG_INFINITY;
         
         // read the arguments
         if args.len() == 0 {
             return Err(Error::new(ErrorKind::InvalidInput,
                                   "Tool run with no paramters."));
         }
-        for i in 0..args.len() {
-            let mut arg = args[i].replace("\"", "");
-            arg = arg.replace("\'", "");
-            let cmd = arg.split("="); // in case an equals sign was used
-            let vec = cmd.collect::<Vec<&str>>();
-            let mut keyval = false;
-            if vec.len() > 1 {
-                keyval = true;
-            }
-            if vec[0].to_lowercase() == "-i" || vec[0].to_lowercase() == "--input" {
-                if keyval {
-                    input_file = vec[1].to_string();
-                } else {
-                    input_file = args[i + 1].to_string();
-                }
-            } else if vec[0].to_lowercase() == "-o" || vec[0].to_lowercase() == "--output" {
-                if keyval {
-                    output_file = vec[1].to_string();
-                } else {
-                    output_file = args[i + 1].to_string();
-                }
-            } else if vec[0].to_lowercase() == "-parameter" ||
-                      vec[0].to_lowercase() == "--parameter" {
-                if keyval {
-                    interp_parameter = vec[1].to_string();
-                } else {
-                    interp_parameter = args[i + 1].to_string();
-                }
-            } else if vec[0].to_lowercase() == "-returns" || vec[0].to_lowercase() == "--returns" {
-                if keyval {
-                    return_type = vec[1].to_string();
-                } else {
-                    return_type = args[i + 1].to_string();
-                }
-            } else if vec[0].to_lowercase() == "-resolution" ||
-                      vec[0].to_lowercase() == "--resolution" {
-                if keyval {
-                    grid_res = vec[1].to_string().parse::<f64>().unwrap();
-                } else {
-                    grid_res = args[i + 1].to_string().parse::<f64>().unwrap();
-                }
-            } else if vec[0].to_lowercase() == "-radius" || vec[0].to_lowercase() == "--radius" {
-                if keyval {
-                    search_radius = vec[1].to_string().parse::<f64>().unwrap();
-                } else {
-                    search_radius = args[i + 1].to_string().parse::<f64>().unwrap();
-                }
-            } else if vec[0].to_lowercase() == "-palette" || vec[0].to_lowercase() == "--palette" {
-                if keyval {
-                    palette = vec[1].to_string();
-                } else {
-                    palette = args[i + 1].to_string();
-                }
-            } else if vec[0].to_lowercase() == "-exclude_cls" ||
-                      vec[0].to_lowercase() == "--exclude_cls" {
-                if keyval {
-                    exclude_cls_str = vec[1].to_string();
-                } else {
-                    exclude_cls_str = args[i + 1].to_string();
-                }
-                let mut cmd = exclude_cls_str.split(",");
-                let mut vec = cmd.collect::<Vec<&str>>();
-                if vec.len() == 1 {
-                    cmd = exclude_cls_str.split(";");
-                    vec = cmd.collect::<Vec<&str>>();
-                }
-                for value in vec {
-                    if !value.trim().is_empty() {
-                        let c = value.trim().parse::<usize>().unwrap();
-                        include_class_vals[c] = false;
-                    }
-                }
-            } else if vec[0].to_lowercase() == "-minz" || vec[0].to_lowercase() == "--minz" {
-                if keyval {
-                    min_z = vec[1].to_string().parse::<f64>().unwrap();
-                } else {
-                    min_z = args[i + 1].to_string().parse::<f64>().unwrap();
-                }
-            } else if vec[0].to_lowercase() == "-maxz" || vec[0].to_lowercase() == "--maxz" {
-                if keyval {
-                    max_z = vec[1].to_string().parse::<f64>().unwrap();
-                } else {
-                    max_z = args[i + 1].to_string().parse::<f64>().unwrap();
-                }
+        let args = ArgValues::parse(&self.parameters, &args)?;
+        let mut input_file = args.get_string("--input")?.unwrap_or("".to_string());
+        let mut output_file = args.get_string("--output")?.unwrap_or("".to_string());
+        let interp_parameter = args.get_string("--parameter")?.unwrap_or("elevation".to_string());
+        let return_type = args.get_string("--returns")?.unwrap_or("all".to_string());
+        let grid_res = args.get_f64("--resolution")?.unwrap_or(1.0);
+        let search_radius = args.get_f64("--radius")?.unwrap_or(2.5);
+        let palette = args.get_string("--palette")?.unwrap_or("default".to_string());
+        let exclude_cls_str = args.get_string("--exclude_cls")?.unwrap_or(String::new());
+        let mut cmd = exclude_cls_str.split(",");
+        let mut vec = cmd.collect::<Vec<&str>>();
+        if vec.len() == 1 {
+            cmd = exclude_cls_str.split(";");
+            vec = cmd.collect::<Vec<&str>>();
+        }
+        for value in vec {
+            if !value.trim().is_empty() {
+                let c = value.trim().parse::<usize>().unwrap();
+                include_class_vals[c] = false;
             }
         }
+        let min_z = args.get_f64("--minz")?.unwrap_or(f64::NEG_INFINITY);
+        let max_z = args.get_f64("--maxz")?.unwrap_or(f64::INFINITY);
 
         if verbose {
-            println!("***************{}", "*".repeat(self.get_tool_name().len()));
-            println!("* Welcome to {} *", self.get_tool_name());
-            println!("***************{}", "*".repeat(self.get_tool_name().len()));
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
+            sink.message(&format!("* Welcome to {} *", self.get_tool_name()));
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
         }
 
         let start = time::now();
@@ -333,7 +262,7 @@ impl WhiteboxTool for LidarNearestNeighbourGridding {
                     "This tool must be run by specifying either an individual input file or a working directory."));
             }
             match fs::read_dir(working_directory) {
-                Err(why) => println!("! {:?}", why.kind()),
+                Err(why) => sink.message(&format!("! {:?}", why.kind())),
                 Ok(paths) => for path in paths {
                     let s = format!("{:?}", path.unwrap().path());
                     if s.replace("\"", "").to_lowercase().ends_with(".las") {
@@ -342,6 +271,9 @@ impl WhiteboxTool for LidarNearestNeighbourGridding {
                     } else if s.replace("\"", "").to_lowercase().ends_with(".zip") {
                         inputs.push(format!("{:?}", s.replace("\"", "")));
                         outputs.push(inputs[inputs.len()-1].replace(".zip", ".tif").replace(".ZIP", ".tif"))
+                    } else if s.replace("\"", "").to_lowercase().ends_with(".laz") {
+                        inputs.push(format!("{:?}", s.replace("\"", "")));
+                        outputs.push(inputs[inputs.len()-1].replace(".laz", ".tif").replace(".LAZ", ".tif"))
                     }
                 },
             }
@@ -351,7 +283,7 @@ impl WhiteboxTool for LidarNearestNeighbourGridding {
             }
             inputs.push(input_file.clone());
             if output_file.is_empty() {
-                output_file = input_file.clone().replace(".las", ".tif").replace(".LAS", ".tif");
+                output_file = input_file.clone().replace(".las", ".tif").replace(".LAS", ".tif").replace(".laz", ".tif").replace(".LAZ", ".tif");
             }
             outputs.push(output_file);
         }
@@ -398,7 +330,7 @@ impl WhiteboxTool for LidarNearestNeighbourGridding {
         // }
 
         if verbose {
-            println!("Performing interpolation...");
+            sink.message("Performing interpolation...");
         }
 
         let num_tiles = inputs.len();
@@ -421,6 +353,7 @@ impl WhiteboxTool for LidarNearestNeighbourGridding {
             let exclude_cls_str = exclude_cls_str.clone();
             let include_class_vals = include_class_vals.clone();
             let tx2 = tx2.clone();
+            let sink = sink.clone();
             thread::spawn(move || {
                 let mut tile = 0;
                 while tile < num_tiles {
@@ -444,7 +377,7 @@ impl WhiteboxTool for LidarNearestNeighbourGridding {
                     let mut frs: FixedRadiusSearch2D<f64> = FixedRadiusSearch2D::new(search_radius);
                     
                     if verbose && inputs.len() == 1 {
-                        println!("Reading input LAS file...");
+                        sink.message("Reading input LAS file...");
                     }
 
                     let mut progress: i32;
@@ -474,10 +407,10 @@ impl WhiteboxTool for LidarNearestNeighbourGridding {
                                                 }
                                             }
                                         }
-                                        if verbose && inputs.len() == 1 {
+                                        if inputs.len() == 1 {
                                             progress = (100.0_f64 * i as f64 / num_points) as i32;
                                             if progress != old_progress {
-                                                println!("Binning points: {}%", progress);
+                                                sink.report_progress("Binning points", progress as usize);
                                                 old_progress = progress;
                                             }
                                         }
@@ -496,10 +429,10 @@ impl WhiteboxTool for LidarNearestNeighbourGridding {
                                                 }
                                             }
                                         }
-                                        if verbose && inputs.len() == 1 {
+                                        if inputs.len() == 1 {
                                             progress = (100.0_f64 * i as f64 / num_points) as i32;
                                             if progress != old_progress {
-                                                println!("Binning points: {}%", progress);
+                                                sink.report_progress("Binning points", progress as usize);
                                                 old_progress = progress;
                                             }
                                         }
@@ -518,10 +451,10 @@ impl WhiteboxTool for LidarNearestNeighbourGridding {
                                                 }
                                             }
                                         }
-                                        if verbose && inputs.len() == 1 {
+                                        if inputs.len() == 1 {
                                             progress = (100.0_f64 * i as f64 / num_points) as i32;
                                             if progress != old_progress {
-                                                println!("Binning points: {}%", progress);
+                                                sink.report_progress("Binning points", progress as usize);
                                                 old_progress = progress;
                                             }
                                         }
@@ -540,10 +473,10 @@ impl WhiteboxTool for LidarNearestNeighbourGridding {
                                                 }
                                             }
                                         }
-                                        if verbose && inputs.len() == 1 {
+                                        if inputs.len() == 1 {
                                             progress = (100.0_f64 * i as f64 / num_points) as i32;
                                             if progress != old_progress {
-                                                println!("Binning points: {}%", progress);
+                                                sink.report_progress("Binning points", progress as usize);
                                                 old_progress = progress;
                                             }
                                         }
@@ -563,10 +496,10 @@ impl WhiteboxTool for LidarNearestNeighbourGridding {
                                                 }
                                             }
                                         }
-                                        if verbose && inputs.len() == 1 {
+                                        if inputs.len() == 1 {
                                             progress = (100.0_f64 * i as f64 / num_points) as i32;
                                             if progress != old_progress {
-                                                println!("Binning points: {}%", progress);
+                                                sink.report_progress("Binning points", progress as usize);
                                                 old_progress = progress;
                                             }
                                         }
@@ -626,10 +559,10 @@ impl WhiteboxTool for LidarNearestNeighbourGridding {
                                     output.set_value(row, col, val);
                                 }
                             }
-                            if verbose && inputs.len() == 1 {
+                            if inputs.len() == 1 {
                                 progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as i32;
                                 if progress != old_progress {
-                                    println!("Progress: {}%", progress);
+                                    sink.report_progress("Progress", progress as usize);
                                     old_progress = progress;
                                 }
                             }
@@ -675,12 +608,10 @@ impl WhiteboxTool for LidarNearestNeighbourGridding {
                         for row in 0..rows {
                             let data = rx.recv().unwrap();
                             output.set_row_data(data.0, data.1);
-                            if verbose {
-                                progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as i32;
-                                if progress != old_progress {
-                                    println!("Progress: {}%", progress);
-                                    old_progress = progress;
-                                }
+                            progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as i32;
+                            if progress != old_progress {
+                                sink.report_progress("Progress", progress as usize);
+                                old_progress = progress;
                             }
                         }
                     }
@@ -698,7 +629,7 @@ impl WhiteboxTool for LidarNearestNeighbourGridding {
                     output.add_metadata_entry(format!("Elapsed Time (including I/O): {}", elapsed_time_run).replace("PT", ""));
 
                     if verbose && inputs.len() == 1 {
-                        println!("Saving data...")
+                        sink.message("Saving data...")
                     };
 
                     let _ = output.write().unwrap();
@@ -713,14 +644,12 @@ impl WhiteboxTool for LidarNearestNeighbourGridding {
         for tile in 0..inputs.len() {
             let tile_completed = rx2.recv().unwrap();
             if verbose {
-                    println!("Finished interpolating {} ({} of {})", inputs[tile_completed].replace("\"", "").replace(working_directory, "").replace(".las", ""), tile+1, inputs.len());
+                    sink.message(&format!("Finished interpolating {} ({} of {})", inputs[tile_completed].replace("\"", "").replace(working_directory, "").replace(".las", "").replace(".laz", ""), tile+1, inputs.len()));
             }
-            if verbose {
-                progress = (100.0_f64 * tile as f64 / (inputs.len() - 1) as f64) as i32;
-                if progress != old_progress {
-                    println!("Progress: {}%", progress);
-                    old_progress = progress;
-                }
+            progress = (100.0_f64 * tile as f64 / (inputs.len() - 1) as f64) as i32;
+            if progress != old_progress {
+                sink.progress("Progress", progress as usize)?;
+                old_progress = progress;
             }
         }
 
@@ -728,7 +657,7 @@ impl WhiteboxTool for LidarNearestNeighbourGridding {
         let elapsed_time = end - start;    
 
         if verbose {
-            println!("{}", &format!("Elapsed Time (including I/O): {}", elapsed_time).replace("PT", ""));
+            sink.message(&format!("Elapsed Time (including I/O): {}", elapsed_time).replace("PT", ""));
         }
 
         Ok(())
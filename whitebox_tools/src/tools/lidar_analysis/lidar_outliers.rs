@@ -22,6 +22,7 @@ use std::thread;
 use lidar::*;
 // use lidar::point_data::*;
 use tools::*;
+use tools::arg_parser::ArgValues;
 use structures::FixedRadiusSearch2D;
 
 pub struct LidarRemoveOutliers {
@@ -129,55 +130,21 @@ impl WhiteboxTool for LidarRemoveOutliers {
         self.toolbox.clone()
     }
 
-    fn run<'a>(&self, args: Vec<String>, working_directory: &'a str, verbose: bool) -> Result<(), Error> {
-        let mut input_file: String = "".to_string();
-        let mut output_file: String = "".to_string();
-        let mut search_radius: f64 = 2.0;
-        let mut elev_diff: f64 = 50.0;
-        
+    fn run<'a>(&self, args: Vec<String>, working_directory: &'a str, verbose: bool, sink: &Arc<ProgressSink>) -> Result<(), Error> {
         // read the arguments
         if args.len() == 0 {
             return Err(Error::new(ErrorKind::InvalidInput, "Tool run with no paramters."));
         }
-        for i in 0..args.len() {
-            let mut arg = args[i].replace("\"", "");
-            arg = arg.replace("\'", "");
-            let cmd = arg.split("="); // in case an equals sign was used
-            let vec = cmd.collect::<Vec<&str>>();
-            let mut keyval = false;
-            if vec.len() > 1 { keyval = true; }
-            let flag_val = vec[0].to_lowercase().replace("--", "-");
-            if flag_val == "-i" || flag_val == "-input" {
-                input_file = if keyval {
-                    vec[1].to_string()
-                } else {
-                    args[i+1].to_string()
-                };
-            } else if flag_val == "-o" || flag_val == "-output" {
-                output_file = if keyval {
-                    vec[1].to_string()
-                } else {
-                    args[i+1].to_string()
-                };
-            } else if flag_val == "-radius" {
-                search_radius = if keyval {
-                    vec[1].to_string().parse::<f64>().unwrap()
-                } else {
-                    args[i+1].to_string().parse::<f64>().unwrap()
-                };
-            } else if flag_val == "-elev_diff" {
-                elev_diff = if keyval {
-                    vec[1].to_string().parse::<f64>().unwrap()
-                } else {
-                    args[i+1].to_string().parse::<f64>().unwrap()
-                };
-            }
-        }
+        let args = ArgValues::parse(&self.parameters, &args)?;
+        let mut input_file = args.required_string("--input")?;
+        let mut output_file = args.required_string("--output")?;
+        let search_radius = args.get_f64("--radius")?.unwrap_or(2.0);
+        let elev_diff = args.get_f64("--elev_diff")?.unwrap_or(50.0);
 
         if verbose {
-            println!("***************{}", "*".repeat(self.get_tool_name().len()));
-            println!("* Welcome to {} *", self.get_tool_name());
-            println!("***************{}", "*".repeat(self.get_tool_name().len()));
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
+            sink.message(&format!("* Welcome to {} *", self.get_tool_name()));
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
         }
 
         let sep = path::MAIN_SEPARATOR;
@@ -188,7 +155,7 @@ impl WhiteboxTool for LidarRemoveOutliers {
             output_file = format!("{}{}", working_directory, output_file);
         }
 
-        if verbose { println!("Reading input LAS file..."); }
+        if verbose { sink.message("Reading input LAS file..."); }
         let input = match LasFile::new(&input_file, "r") {
             Ok(lf) => lf,
             Err(err) => panic!("Error reading file {}: {}", input_file, err),
@@ -196,7 +163,7 @@ impl WhiteboxTool for LidarRemoveOutliers {
 
         let start = time::now();
 
-        if verbose { println!("Performing analysis..."); }
+        if verbose { sink.message("Performing analysis..."); }
 
         let n_points = input.header.number_of_points as usize;
         let num_points: f64 = (input.header.number_of_points - 1) as f64; // used for progress calculation only
@@ -207,12 +174,10 @@ impl WhiteboxTool for LidarRemoveOutliers {
         for i in 0..n_points {
             let p: PointData = input.get_point_info(i);
             frs.insert(p.x, p.y, i);
-            if verbose {
-                progress = (100.0_f64 * i as f64 / num_points) as i32;
-                if progress != old_progress {
-                    println!("Binning points: {}%", progress);
-                    old_progress = progress;
-                }
+            progress = (100.0_f64 * i as f64 / num_points) as i32;
+            if progress != old_progress {
+                sink.progress("Binning points", progress as usize)?;
+                old_progress = progress;
             }
         }
 
@@ -253,12 +218,10 @@ impl WhiteboxTool for LidarRemoveOutliers {
         for i in 0..n_points {
             let data = rx.recv().unwrap();
             residuals[data.0] = data.1;
-            if verbose {
-                progress = (100.0_f64 * i as f64 / num_points) as i32;
-                if progress != old_progress {
-                    println!("Progress: {}%", progress);
-                    old_progress = progress;
-                }
+            progress = (100.0_f64 * i as f64 / num_points) as i32;
+            if progress != old_progress {
+                sink.progress("Progress", progress as usize)?;
+                old_progress = progress;
             }
         }
 
@@ -271,25 +234,23 @@ impl WhiteboxTool for LidarRemoveOutliers {
             if residuals[i].abs() < elev_diff {
                 output.add_point_record(input.get_record(i));
             }
-            if verbose {
-                progress = (100.0_f64 * i as f64 / num_points) as i32;
-                if progress != old_progress {
-                    println!("Saving data: {}%", progress);
-                    old_progress = progress;
-                }
+            progress = (100.0_f64 * i as f64 / num_points) as i32;
+            if progress != old_progress {
+                sink.progress("Saving data", progress as usize)?;
+                old_progress = progress;
             }
         }
 
         let end = time::now();
         let elapsed_time = end - start;
 
-        if verbose { println!("Writing output LAS file..."); }
+        if verbose { sink.message("Writing output LAS file..."); }
         let _ = match output.write() {
-            Ok(_) => println!("Complete!"),
-            Err(e) => println!("error while writing: {:?}", e),
+            Ok(_) => sink.message("Complete!"),
+            Err(e) => return Err(e),
         };
         if verbose {
-            println!("{}", &format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
+            sink.message(&format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
         }
 
         Ok(())
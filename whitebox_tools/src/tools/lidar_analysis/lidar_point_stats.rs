@@ -28,6 +28,7 @@ use std::path;
 use lidar::*;
 use raster::*;
 use tools::*;
+use tools::arg_parser::ArgValues;
 use structures::Array2D;
 
 pub struct LidarPointStats {
@@ -102,7 +103,7 @@ impl LidarPointStats {
 
         parameters.push(ToolParameter{
             name: "Output predominant class?".to_owned(), 
-            flags: vec!["--predom_class".to_owned()], 
+            flags: vec!["--predominant_class".to_owned(), "--predom_class".to_owned()], 
             description: "Flag indicating whether or not to output the predominant classification raster.".to_owned(),
             parameter_type: ParameterType::Boolean,
             default_value: None,
@@ -169,55 +170,22 @@ impl WhiteboxTool for LidarPointStats {
     fn run<'a>(&self,
                args: Vec<String>,
                working_directory: &'a str,
-               verbose: bool)
+               verbose: bool,
+               sink: &Arc<ProgressSink>)
                -> Result<(), Error> {
-        let mut input_file: String = "".to_string();
-        let mut grid_res: f64 = 1.0;
-        let mut num_points = false;
-        let mut num_pulses = false;
-        let mut z_range = false;
-        let mut intensity_range = false;
-        let mut predominant_class = false;
-        
         // read the arguments
         if args.len() == 0 {
             return Err(Error::new(ErrorKind::InvalidInput,
                                   "Tool run with no paramters."));
         }
-        for i in 0..args.len() {
-            let mut arg = args[i].replace("\"", "");
-            arg = arg.replace("\'", "");
-            let cmd = arg.split("="); // in case an equals sign was used
-            let vec = cmd.collect::<Vec<&str>>();
-            let mut keyval = false;
-            if vec.len() > 1 {
-                keyval = true;
-            }
-            let flag_val = vec[0].to_lowercase().replace("--", "-");
-            if flag_val == "-i" || flag_val == "-input" {
-                input_file = if keyval {
-                    vec[1].to_string()
-                } else {
-                    args[i + 1].to_string()
-                };
-            } else if flag_val == "-resolution" {
-                grid_res = if keyval {
-                    vec[1].to_string().parse::<f64>().unwrap()
-                } else {
-                    args[i + 1].to_string().parse::<f64>().unwrap()
-                };
-            } else if flag_val == "-num_points" {
-                num_points = true;
-            } else if flag_val == "-num_pulses" {
-                num_pulses = true;
-            } else if flag_val == "-z_range" || flag_val == "elev_range" {
-                z_range = true;
-            } else if flag_val == "-intensity_range" || flag_val == "i_range" {
-                intensity_range = true;
-            } else if flag_val == "-predom_class" || flag_val == "-predominant_class" {
-                predominant_class = true;
-            }
-        }
+        let args = ArgValues::parse(&self.parameters, &args)?;
+        let input_file = args.get_string("--input")?.unwrap_or("".to_string());
+        let grid_res = args.get_f64("--resolution")?.unwrap_or(1.0);
+        let mut num_points = args.get_bool("--num_points")?;
+        let mut num_pulses = args.get_bool("--num_pulses")?;
+        let mut z_range = args.get_bool("--z_range")?;
+        let mut intensity_range = args.get_bool("--intensity_range")?;
+        let mut predominant_class = args.get_bool("--predom_class")?;
 
         let start = time::now();
 
@@ -237,10 +205,11 @@ impl WhiteboxTool for LidarPointStats {
                     "This tool must be run by specifying either an individual input file or a working directory."));
             }
             match fs::read_dir(working_directory) {
-                Err(why) => println!("! {:?}", why.kind()),
+                Err(why) => sink.message(&format!("! {:?}", why.kind())),
                 Ok(paths) => for path in paths {
                     let s = format!("{:?}", path.unwrap().path());
-                    if s.replace("\"", "").to_lowercase().ends_with(".las") {
+                    let lower = s.replace("\"", "").to_lowercase();
+                    if lower.ends_with(".las") || lower.ends_with(".laz") {
                         inputs.push(format!("{:?}", s.replace("\"", "")));
                     }
                 },
@@ -250,9 +219,9 @@ impl WhiteboxTool for LidarPointStats {
         }
 
         if verbose {
-            println!("***************{}", "*".repeat(self.get_tool_name().len()));
-            println!("* Welcome to {} *", self.get_tool_name());
-            println!("***************{}", "*".repeat(self.get_tool_name().len()));
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
+            sink.message(&format!("* Welcome to {} *", self.get_tool_name()));
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
         }
 
         let num_tiles = inputs.len();
@@ -266,6 +235,7 @@ impl WhiteboxTool for LidarPointStats {
             // copy over the string parameters
             let tool_name = self.get_tool_name();
             let tx2 = tx2.clone();
+            let sink = sink.clone();
             thread::spawn(move || {
                 let mut tile = 0;
                 while tile < num_tiles {
@@ -277,7 +247,7 @@ impl WhiteboxTool for LidarPointStats {
                     
                     let input_file = inputs[tile].replace("\"", "").clone();
                     if verbose && inputs.len() == 1 {
-                        println!("Reading input LAS file...");
+                        sink.message("Reading input LAS file...");
                     }
                     let input = match LasFile::new(&input_file, "r") {
                         Ok(lf) => lf,
@@ -315,11 +285,11 @@ impl WhiteboxTool for LidarPointStats {
                     let num_points_float: f64 = (input.header.number_of_points - 1) as f64; // used for progress calculation only
                         
                     if num_points || num_pulses {
-                        let out_file_num_pnts = input_file.replace(".las", "_num_pnts.tif").clone();
+                        let out_file_num_pnts = input_file.replace(".las", "_num_pnts.tif").replace(".laz", "_num_pnts.tif").clone();
                         let mut out_num_pnts = Raster::initialize_using_config(&out_file_num_pnts, &configs);
                         out_num_pnts.reinitialize_values(0f64);
 
-                        let out_file_num_pulses = input_file.replace(".las", "_num_pulses.tif").clone();
+                        let out_file_num_pulses = input_file.replace(".las", "_num_pulses.tif").replace(".laz", "_num_pulses.tif").clone();
                         let mut out_num_pulses = Raster::initialize_using_config(&out_file_num_pulses, &configs);
                         out_num_pulses.reinitialize_values(0f64);
 
@@ -339,10 +309,10 @@ impl WhiteboxTool for LidarPointStats {
                                 out_num_pulses.increment(row, col, 1f64);
                             }
 
-                            if verbose && inputs.len() == 1 {
+                            if inputs.len() == 1 {
                                 progress = (100.0_f64 * i as f64 / num_points_float) as i32;
                                 if progress != old_progress {
-                                    println!("Progress: {}%", progress);
+                                    sink.report_progress("Progress", progress as usize);
                                     old_progress = progress;
                                 }
                             }
@@ -352,7 +322,7 @@ impl WhiteboxTool for LidarPointStats {
                         let elapsed_time_run = end_run - start_run;  
 
                         if verbose && inputs.len() == 1 {
-                            println!("Saving data...")
+                            sink.message("Saving data...")
                         };
 
                         if num_points {
@@ -375,12 +345,12 @@ impl WhiteboxTool for LidarPointStats {
                     if z_range || intensity_range {
                         let mut min_z: Array2D<f64> = Array2D::new(rows as isize, columns as isize, f64::INFINITY, nodata).unwrap();
                         let mut max_z: Array2D<f64> = Array2D::new(rows as isize, columns as isize, f64::NEG_INFINITY, nodata).unwrap();
-                        let out_file_elev_range = input_file.replace(".las", "_elev_range.tif").clone();
+                        let out_file_elev_range = input_file.replace(".las", "_elev_range.tif").replace(".laz", "_elev_range.tif").clone();
                         let mut out_elev_range = Raster::initialize_using_config(&out_file_elev_range, &configs);
 
                         let mut min_i: Array2D<u16> = Array2D::new(rows as isize, columns as isize, u16::max_value(), 0u16).unwrap();
                         let mut max_i: Array2D<u16> = Array2D::new(rows as isize, columns as isize, u16::min_value(), 0u16).unwrap();
-                        let out_file_intensity_range = input_file.replace(".las", "_intensity_range.tif").clone();
+                        let out_file_intensity_range = input_file.replace(".las", "_intensity_range.tif").replace(".laz", "_intensity_range.tif").clone();
                         let mut out_intensity_range = Raster::initialize_using_config(&out_file_intensity_range, &configs);
 
                         let start_run = time::now();
@@ -426,10 +396,10 @@ impl WhiteboxTool for LidarPointStats {
                                 out_intensity_range.set_value(row, col, (max_i.get_value(row, col) - min_i.get_value(row, col)) as f64);
                             }
 
-                            if verbose && inputs.len() == 1 {
+                            if inputs.len() == 1 {
                                 progress = (100.0_f64 * i as f64 / num_points_float) as i32;
                                 if progress != old_progress {
-                                    println!("Progress: {}%", progress);
+                                    sink.report_progress("Progress", progress as usize);
                                     old_progress = progress;
                                 }
                             }
@@ -439,7 +409,7 @@ impl WhiteboxTool for LidarPointStats {
                         let elapsed_time_run = end_run - start_run;  
 
                         if verbose && inputs.len() == 1 {
-                            println!("Saving data...")
+                            sink.message("Saving data...")
                         };
 
                         if z_range {
@@ -465,7 +435,7 @@ impl WhiteboxTool for LidarPointStats {
                         for _ in 0..19 {
                             class_histo.push(Array2D::new(rows as isize, columns as isize, 0u16, 0u16).unwrap());
                         }
-                        let out_file_predominant_class = input_file.replace(".las", "_predominant_class.tif").clone();
+                        let out_file_predominant_class = input_file.replace(".las", "_predominant_class.tif").replace(".laz", "_predominant_class.tif").clone();
                         let mut out_predominant_class = Raster::initialize_using_config(&out_file_predominant_class, &configs);
 
                         let start_run = time::now();
@@ -488,10 +458,10 @@ impl WhiteboxTool for LidarPointStats {
                                 out_predominant_class.set_value(row, col, class as f64);
                             }
 
-                            if verbose && inputs.len() == 1 {
+                            if inputs.len() == 1 {
                                 progress = (100.0_f64 * i as f64 / num_points_float) as i32;
                                 if progress != old_progress {
-                                    println!("Progress: {}%", progress);
+                                    sink.report_progress("Progress", progress as usize);
                                     old_progress = progress;
                                 }
                             }
@@ -501,7 +471,7 @@ impl WhiteboxTool for LidarPointStats {
                         let elapsed_time_run = end_run - start_run;  
 
                         if verbose && inputs.len() == 1 {
-                            println!("Saving data...")
+                            sink.message("Saving data...")
                         };
 
                         out_predominant_class.add_metadata_entry(format!("Created by whitebox_tools\' {} tool", tool_name));
@@ -522,14 +492,12 @@ impl WhiteboxTool for LidarPointStats {
         for tile in 0..inputs.len() {
             let tile_completed = rx2.recv().unwrap();
             if verbose {
-                    println!("Finished {} ({} of {})", inputs[tile_completed].replace("\"", "").replace(working_directory, "").replace(".las", ""), tile+1, inputs.len());
+                    sink.message(&format!("Finished {} ({} of {})", inputs[tile_completed].replace("\"", "").replace(working_directory, "").replace(".las", "").replace(".laz", ""), tile+1, inputs.len()));
             }
-            if verbose {
-                progress = (100.0_f64 * tile as f64 / (inputs.len() - 1) as f64) as i32;
-                if progress != old_progress {
-                    println!("Progress: {}%", progress);
-                    old_progress = progress;
-                }
+            progress = (100.0_f64 * tile as f64 / (inputs.len() - 1) as f64) as i32;
+            if progress != old_progress {
+                sink.progress("Progress", progress as usize)?;
+                old_progress = progress;
             }
         }
 
@@ -537,7 +505,7 @@ impl WhiteboxTool for LidarPointStats {
         let elapsed_time = end - start;    
 
         if verbose {
-            println!("{}", &format!("Elapsed Time (including I/O): {}", elapsed_time).replace("PT", ""));
+            sink.message(&format!("Elapsed Time (including I/O): {}", elapsed_time).replace("PT", ""));
         }
 
         Ok(())
@@ -613,7 +581,7 @@ impl WhiteboxTool for LidarPointStats {
         //             configs.data_type = DataType::F64;
         //             configs.photometric_interp = PhotometricInterpretation::Continuous;
 
-        //             output_file = input_file.replace(".las", "_num_pnts.tif").clone();
+        //             output_file = input_file.replace(".las", "_num_pnts.tif").replace(".laz", "_num_pnts.tif").clone();
 
         //             let mut output = Raster::initialize_using_config(&output_file, &configs);
         //             output.reinitialize_values(0f64);
@@ -701,7 +669,7 @@ impl WhiteboxTool for LidarPointStats {
         // for tile in 0..inputs.len() {
         //     let tile_completed = rx2.recv().unwrap();
         //     if verbose {
-        //         println!("Finished interpolating {} ({} of {})", inputs[tile_completed].replace("\"", "").replace(working_directory, "").replace(".las", ""), tile+1, inputs.len());
+        //         println!("Finished interpolating {} ({} of {})", inputs[tile_completed].replace("\"", "").replace(working_directory, "").replace(".las", "").replace(".laz", ""), tile+1, inputs.len());
         //     }
         //     if verbose {
         //         progress = (100.0_f64 * tile as f64 / (inputs.len() - 1) as f64) as i32;
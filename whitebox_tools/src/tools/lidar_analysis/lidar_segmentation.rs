@@ -26,6 +26,7 @@ use std::sync::mpsc;
 use std::thread;
 use lidar::*;
 use tools::*;
+use tools::arg_parser::ArgValues;
 use self::na::Vector3;
 use structures::FixedRadiusSearch3D;
 use self::rand::Rng;
@@ -144,62 +145,22 @@ impl WhiteboxTool for LidarSegmentation {
         self.toolbox.clone()
     }
 
-    fn run<'a>(&self, args: Vec<String>, working_directory: &'a str, verbose: bool) -> Result<(), Error> {
-        let mut input_file: String = "".to_string();
-        let mut output_file: String = "".to_string();
-        let mut search_radius = 5f64;
-        let mut max_norm_diff = 2f64;
-        let mut max_z_diff = 1f64;
-        
+    fn run<'a>(&self, args: Vec<String>, working_directory: &'a str, verbose: bool, sink: &Arc<ProgressSink>) -> Result<(), Error> {
         // read the arguments
         if args.len() == 0 {
             return Err(Error::new(ErrorKind::InvalidInput, "Tool run with no paramters."));
         }
-        for i in 0..args.len() {
-            let mut arg = args[i].replace("\"", "");
-            arg = arg.replace("\'", "");
-            let cmd = arg.split("="); // in case an equals sign was used
-            let vec = cmd.collect::<Vec<&str>>();
-            let mut keyval = false;
-            if vec.len() > 1 { keyval = true; }
-            let flag_val = vec[0].to_lowercase().replace("--", "-");
-            if flag_val == "-i" || flag_val == "-input" {
-                if keyval {
-                    input_file = vec[1].to_string();
-                } else {
-                    input_file = args[i+1].to_string();
-                }
-            } else if flag_val == "-o" || flag_val == "-output" {
-                if keyval {
-                    output_file = vec[1].to_string();
-                } else {
-                    output_file = args[i+1].to_string();
-                }
-            } else if flag_val == "-dist" || flag_val == "-radius" {
-                if keyval {
-                    search_radius = vec[1].to_string().parse::<f64>().unwrap();
-                } else {
-                    search_radius = args[i+1].to_string().parse::<f64>().unwrap();
-                }
-            } else if flag_val == "-norm_diff" {
-                if keyval {
-                    max_norm_diff = vec[1].to_string().parse::<f64>().unwrap();
-                } else {
-                    max_norm_diff = args[i+1].to_string().parse::<f64>().unwrap();
-                }
-            } else if flag_val == "-maxzdiff" {
-                if keyval {
-                    max_z_diff = vec[1].to_string().parse::<f64>().unwrap();
-                } else {
-                    max_z_diff = args[i+1].to_string().parse::<f64>().unwrap();
-                }
-            }
-        }
+        let args = ArgValues::parse(&self.parameters, &args)?;
+        let mut input_file = args.required_string("--input")?;
+        let mut output_file = args.required_string("--output")?;
+        let search_radius = args.get_f64("--radius")?.unwrap_or(5f64);
+        let mut max_norm_diff = args.get_f64("--norm_diff")?.unwrap_or(2f64);
+        let max_z_diff = args.get_f64("--maxzdiff")?.unwrap_or(1f64);
 
         if verbose {
-            println!("***************{}", "*".repeat(self.get_tool_name().len()));
-            println!("* Welcome to {} *", self.get_tool_name());
-            println!("***************{}", "*".repeat(self.get_tool_name().len()));
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
+            sink.message(&format!("* Welcome to {} *", self.get_tool_name()));
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
         }
 
         let sep = path::MAIN_SEPARATOR;
@@ -210,7 +171,7 @@ impl WhiteboxTool for LidarSegmentation {
             output_file = format!("{}{}", working_directory, output_file);
         }
 
-        if verbose { println!("Reading input LAS file..."); }
+        if verbose { sink.message("Reading input LAS file..."); }
         let input = match LasFile::new(&input_file, "r") {
             Ok(lf) => lf,
             Err(err) => panic!("Error reading file {}: {}", input_file, err),
@@ -232,17 +193,15 @@ impl WhiteboxTool for LidarSegmentation {
         /////////////////////////////////////////////////////////
         // Calculate the normals for each point in the dataset //
         /////////////////////////////////////////////////////////
-        if verbose { println!("Calculating point normals..."); }
+        if verbose { sink.message("Calculating point normals..."); }
         let mut frs3d: FixedRadiusSearch3D<usize> = FixedRadiusSearch3D::new(search_radius);
         for point_num in 0..n_points {
             let p: PointData = input.get_point_info(point_num);
             frs3d.insert(p.x, p.y, p.z, point_num); 
-            if verbose {
-                progress = (100.0_f64 * point_num as f64 / num_points) as i32;
-                if progress != old_progress {
-                    println!("Binning points in 3D: {}%", progress);
-                    old_progress = progress;
-                }
+            progress = (100.0_f64 * point_num as f64 / num_points) as i32;
+            if progress != old_progress {
+                sink.progress("Binning points in 3D", progress as usize)?;
+                old_progress = progress;
             }
         }
 
@@ -276,19 +235,17 @@ impl WhiteboxTool for LidarSegmentation {
         for point_num in 0..n_points {
             let data = rx.recv().unwrap();
             normal_vectors[data.0] = data.1;
-            if verbose {
-                progress = (100.0_f64 * point_num as f64 / num_points) as i32;
-                if progress != old_progress {
-                    println!("Calculating point normals: {}%", progress);
-                    old_progress = progress;
-                }
+            progress = (100.0_f64 * point_num as f64 / num_points) as i32;
+            if progress != old_progress {
+                sink.progress("Calculating point normals", progress as usize)?;
+                old_progress = progress;
             }
         }
 
         ////////////////////////////////////////
         // Perform the segmentation operation //
         ////////////////////////////////////////
-        if verbose { println!("Segmenting the point cloud..."); }
+        if verbose { sink.message("Segmenting the point cloud..."); }
         let mut segment_id = vec![0usize; n_points];
         let mut current_segment = 0usize;
         let mut point_id: usize;
@@ -313,12 +270,10 @@ impl WhiteboxTool for LidarSegmentation {
 
             while !stack.is_empty() {
                 solved_points += 1;
-                if verbose {
-                    progress = (100f64 * solved_points as f64 / num_points) as i32;
-                    if progress != old_progress {
-                        println!("Segmenting the point cloud: {}%", progress);
-                        old_progress = progress;
-                    }
+                progress = (100f64 * solved_points as f64 / num_points) as i32;
+                if progress != old_progress {
+                    sink.progress("Segmenting the point cloud", progress as usize)?;
+                    old_progress = progress;
                 }
                 point_id = stack.pop().unwrap();
                 /* Check the neighbours to see if there are any
@@ -377,12 +332,10 @@ impl WhiteboxTool for LidarSegmentation {
             let rgb: ColourData = ColourData{ red: clrs[seg_val].0, green: clrs[seg_val].1, blue: clrs[seg_val].2, nir: 0u16 };
             let lpr: LidarPointRecord = LidarPointRecord::PointRecord2 { point_data: p, colour_data: rgb };
             output.add_point_record(lpr);
-            if verbose {
-                progress = (100.0_f64 * point_num as f64 / num_points) as i32;
-                if progress != old_progress {
-                    println!("Saving data: {}%", progress);
-                    old_progress = progress;
-                }
+            progress = (100.0_f64 * point_num as f64 / num_points) as i32;
+            if progress != old_progress {
+                sink.progress("Saving data", progress as usize)?;
+                old_progress = progress;
             }
         }
         // let (mut r, mut g, mut b): (u16, u16, u16);
@@ -407,14 +360,14 @@ impl WhiteboxTool for LidarSegmentation {
         let end = time::now();
         let elapsed_time = end - start;
 
-        println!("");
-        if verbose { println!("Writing output LAS file..."); }
+        sink.message("");
+        if verbose { sink.message("Writing output LAS file..."); }
         let _ = match output.write() {
-            Ok(_) => println!("Complete!"),
-            Err(e) => println!("error while writing: {:?}", e),
+            Ok(_) => sink.message("Complete!"),
+            Err(e) => return Err(e),
         };
         if verbose {
-            println!("{}", &format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
+            sink.message(&format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
         }
 
         Ok(())
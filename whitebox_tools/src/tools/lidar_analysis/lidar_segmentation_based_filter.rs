@@ -19,6 +19,7 @@ use std::sync::mpsc;
 use std::thread;
 use lidar::*;
 use tools::*;
+use tools::arg_parser::ArgValues;
 use self::na::Vector3;
 use structures::{ FixedRadiusSearch2D, FixedRadiusSearch3D };
 
@@ -145,81 +146,26 @@ impl WhiteboxTool for LidarSegmentationBasedFilter {
         self.toolbox.clone()
     }
 
-    fn run<'a>(&self, args: Vec<String>, working_directory: &'a str, verbose: bool) -> Result<(), Error> {
-        let mut input_file: String = "".to_string();
-        let mut output_file: String = "".to_string();
-        let mut search_radius = 5f64;
-        let mut max_norm_diff = 2f64;
-        let mut max_z_diff = 1f64;
+    fn run<'a>(&self, args: Vec<String>, working_directory: &'a str, verbose: bool, sink: &Arc<ProgressSink>) -> Result<(), Error> {
         let ground_class_value = 2u8;
         let otp_class_value = 1u8;
-        let mut filter = true;
 
         // read the arguments
         if args.len() == 0 {
             return Err(Error::new(ErrorKind::InvalidInput, "Tool run with no paramters."));
         }
-        for i in 0..args.len() {
-            let mut arg = args[i].replace("\"", "");
-            arg = arg.replace("\'", "");
-            let cmd = arg.split("="); // in case an equals sign was used
-            let vec = cmd.collect::<Vec<&str>>();
-            let mut keyval = false;
-            if vec.len() > 1 { keyval = true; }
-            let flag_val = vec[0].to_lowercase().replace("--", "-");
-            if flag_val == "-i" || flag_val == "-input" {
-                if keyval {
-                    input_file = vec[1].to_string();
-                } else {
-                    input_file = args[i+1].to_string();
-                }
-            } else if flag_val == "-o" || flag_val == "-output" {
-                if keyval {
-                    output_file = vec[1].to_string();
-                } else {
-                    output_file = args[i+1].to_string();
-                }
-            } else if flag_val == "-dist" || flag_val == "-radius" {
-                if keyval {
-                    search_radius = vec[1].to_string().parse::<f64>().unwrap();
-                } else {
-                    search_radius = args[i+1].to_string().parse::<f64>().unwrap();
-                }
-            } else if flag_val == "-norm_diff" {
-                if keyval {
-                    max_norm_diff = vec[1].to_string().parse::<f64>().unwrap();
-                } else {
-                    max_norm_diff = args[i+1].to_string().parse::<f64>().unwrap();
-                }
-            } else if flag_val == "-maxzdiff" {
-                if keyval {
-                    max_z_diff = vec[1].to_string().parse::<f64>().unwrap();
-                } else {
-                    max_z_diff = args[i+1].to_string().parse::<f64>().unwrap();
-                }
-            } else if flag_val == "-classify" {
-                filter = false;
-            // } else if flag_val == "-groundclass" {
-            //     filter = false;
-            //     if keyval {
-            //         ground_class_value = vec[1].to_string().parse::<u8>().unwrap();
-            //     } else {
-            //         ground_class_value = args[i+1].to_string().parse::<u8>().unwrap();
-            //     }
-            // } else if flag_val == "-otp_class_value" {
-            //     filter = false;
-            //     if keyval {
-            //         otp_class_value = vec[1].to_string().parse::<u8>().unwrap();
-            //     } else {
-            //         otp_class_value = args[i+1].to_string().parse::<u8>().unwrap();
-            //     }
-            }
-        }
+        let args = ArgValues::parse(&self.parameters, &args)?;
+        let mut input_file = args.required_string("--input")?;
+        let mut output_file = args.required_string("--output")?;
+        let search_radius = args.get_f64("--radius")?.unwrap_or(5f64);
+        let mut max_norm_diff = args.get_f64("--norm_diff")?.unwrap_or(2f64);
+        let max_z_diff = args.get_f64("--maxzdiff")?.unwrap_or(1f64);
+        let filter = !args.get_bool("--classify")?;
 
         if verbose {
-            println!("***************{}", "*".repeat(self.get_tool_name().len()));
-            println!("* Welcome to {} *", self.get_tool_name());
-            println!("***************{}", "*".repeat(self.get_tool_name().len()));
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
+            sink.message(&format!("* Welcome to {} *", self.get_tool_name()));
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
         }
 
         let sep = path::MAIN_SEPARATOR;
@@ -230,7 +176,7 @@ impl WhiteboxTool for LidarSegmentationBasedFilter {
             output_file = format!("{}{}", working_directory, output_file);
         }
 
-        if verbose { println!("Reading input LAS file..."); }
+        if verbose { sink.message("Reading input LAS file..."); }
         let input = match LasFile::new(&input_file, "r") {
             Ok(lf) => lf,
             Err(err) => panic!("Error reading file {}: {}", input_file, err),
@@ -240,7 +186,7 @@ impl WhiteboxTool for LidarSegmentationBasedFilter {
 
         let start = time::now();
 
-        if verbose { println!("Performing tophat transform..."); }
+        if verbose { sink.message("Performing tophat transform..."); }
 
         if max_norm_diff < 0f64 { max_norm_diff = 0f64; }
         if max_norm_diff > 90f64 { max_norm_diff = 90f64; }
@@ -256,12 +202,10 @@ impl WhiteboxTool for LidarSegmentationBasedFilter {
         for i in 0..n_points {
             let p: PointData = input.get_point_info(i);
             frs2d.insert(p.x, p.y, i);
-            if verbose {
-                progress = (100.0_f64 * i as f64 / num_points) as i32;
-                if progress != old_progress {
-                    println!("Binning points in 2D: {}%", progress);
-                    old_progress = progress;
-                }
+            progress = (100.0_f64 * i as f64 / num_points) as i32;
+            if progress != old_progress {
+                sink.progress("Binning points in 2D", progress as usize)?;
+                old_progress = progress;
             }
         }
 
@@ -304,12 +248,10 @@ impl WhiteboxTool for LidarSegmentationBasedFilter {
         for point_num in 0..n_points {
             let data = rx.recv().unwrap();
             neighbourhood_min[data.0] = data.1;
-            if verbose {
-                progress = (100.0_f64 * point_num as f64 / num_points) as i32;
-                if progress != old_progress {
-                    println!("Erosion: {}%", progress);
-                    old_progress = progress;
-                }
+            progress = (100.0_f64 * point_num as f64 / num_points) as i32;
+            if progress != old_progress {
+                sink.progress("Erosion", progress as usize)?;
+                old_progress = progress;
             }
         }
 
@@ -344,30 +286,26 @@ impl WhiteboxTool for LidarSegmentationBasedFilter {
             let data = rx.recv().unwrap();
             let z = input.get_point_info(data.0).z;
             residuals[data.0] = z - data.1;
-            if verbose {
-                progress = (100.0_f64 * point_num as f64 / num_points) as i32;
-                if progress != old_progress {
-                    println!("Dilation: {}%", progress);
-                    old_progress = progress;
-                }
+            progress = (100.0_f64 * point_num as f64 / num_points) as i32;
+            if progress != old_progress {
+                sink.progress("Dilation", progress as usize)?;
+                old_progress = progress;
             }
         }
 
         /////////////////////////////////////////////////////////
         // Calculate the normals for each point in the dataset //
         /////////////////////////////////////////////////////////
-        if verbose { println!("Calculating point normals..."); }
+        if verbose { sink.message("Calculating point normals..."); }
         let mut frs3d: FixedRadiusSearch3D<usize> = FixedRadiusSearch3D::new(search_radius);
         for point_num in 0..n_points {
             let p: PointData = input.get_point_info(point_num);
             frs3d.insert(p.x, p.y, residuals[point_num], point_num); 
             // using the top-hat transformed data to calculate normals
-            if verbose {
-                progress = (100.0_f64 * point_num as f64 / num_points) as i32;
-                if progress != old_progress {
-                    println!("Binning points in 3D: {}%", progress);
-                    old_progress = progress;
-                }
+            progress = (100.0_f64 * point_num as f64 / num_points) as i32;
+            if progress != old_progress {
+                sink.progress("Binning points in 3D", progress as usize)?;
+                old_progress = progress;
             }
         }
 
@@ -405,19 +343,17 @@ impl WhiteboxTool for LidarSegmentationBasedFilter {
                 is_ground_point[data.0] = true;
                 stack.push(data.0);
             }
-            if verbose {
-                progress = (100.0_f64 * point_num as f64 / num_points) as i32;
-                if progress != old_progress {
-                    println!("Calculating point normals: {}%", progress);
-                    old_progress = progress;
-                }
+            progress = (100.0_f64 * point_num as f64 / num_points) as i32;
+            if progress != old_progress {
+                sink.progress("Calculating point normals", progress as usize)?;
+                old_progress = progress;
             }
         }
 
         ////////////////////////////////////////
         // Perform the segmentation operation //
         ////////////////////////////////////////
-        if verbose { println!("Segmenting the point cloud..."); }
+        if verbose { sink.message("Segmenting the point cloud..."); }
         let mut point_id: usize;
         let mut norm_diff: f64;
         let mut height_diff: f64;
@@ -449,12 +385,10 @@ impl WhiteboxTool for LidarSegmentationBasedFilter {
                 }
             }
 
-            if verbose {
-                progress = (100f64 * (1f64 - stack.len() as f64 / num_points)) as i32;
-                if progress != old_progress {
-                    println!("Segmenting the point cloud: {}%", progress);
-                    old_progress = progress;
-                }
+            progress = (100f64 * (1f64 - stack.len() as f64 / num_points)) as i32;
+            if progress != old_progress {
+                sink.progress("Segmenting the point cloud", progress as usize)?;
+                old_progress = progress;
             }
         }
 
@@ -472,12 +406,10 @@ impl WhiteboxTool for LidarSegmentationBasedFilter {
                 } else {
                     num_points_filtered += 1;
                 }
-                if verbose {
-                    progress = (100.0_f64 * point_num as f64 / num_points) as i32;
-                    if progress != old_progress {
-                        println!("Saving data: {}%", progress);
-                        old_progress = progress;
-                    }
+                progress = (100.0_f64 * point_num as f64 / num_points) as i32;
+                if progress != old_progress {
+                    sink.progress("Saving data", progress as usize)?;
+                    old_progress = progress;
                 }
             }
         } else { // classify
@@ -544,32 +476,30 @@ impl WhiteboxTool for LidarSegmentationBasedFilter {
                     },
                 }
                 output.add_point_record(pr2);
-                if verbose {
-                    progress = (100.0_f64 * point_num as f64 / num_points) as i32;
-                    if progress != old_progress {
-                        println!("Saving data: {}%", progress);
-                        old_progress = progress;
-                    }
+                progress = (100.0_f64 * point_num as f64 / num_points) as i32;
+                if progress != old_progress {
+                    sink.progress("Saving data", progress as usize)?;
+                    old_progress = progress;
                 }
             }
             num_points_filtered = 1; // so it passes the saving
         }
 
         if num_points_filtered == 0 {
-            println!("Warning: No points were filtered from the point cloud.");
+            sink.warning("Warning: No points were filtered from the point cloud.");
         }
 
         let end = time::now();
         let elapsed_time = end - start;
 
-        println!("");
-        if verbose { println!("Writing output LAS file..."); }
+        sink.message("");
+        if verbose { sink.message("Writing output LAS file..."); }
         let _ = match output.write() {
-            Ok(_) => println!("Complete!"),
-            Err(e) => println!("error while writing: {:?}", e),
+            Ok(_) => sink.message("Complete!"),
+            Err(e) => return Err(e),
         };
         if verbose {
-            println!("{}", &format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
+            sink.message(&format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
         }
 
         Ok(())
@@ -11,8 +11,10 @@ use std::io::{Error, ErrorKind};
 use std::fs::DirBuilder;
 use std::path;
 use std::path::Path;
+use std::sync::Arc;
 use lidar::*;
 use tools::*;
+use tools::arg_parser::ArgValues;
 
 pub struct LidarTile {
     name: String,
@@ -90,7 +92,7 @@ impl LidarTile {
         if e.contains(".exe") {
             short_exe += ".exe";
         }
-        let usage = format!(">>.*{0} -r={1} -v -i=*path*to*data*input.las --width_x=1000.0 --width_y=2500.0 -=min_points=100", short_exe, name).replace("*", &sep);
+        let usage = format!(">>.*{0} -r={1} -v -i=*path*to*data*input.las --width_x=1000.0 --width_y=2500.0 --min_points=100", short_exe, name).replace("*", &sep);
     
         LidarTile { 
             name: name, 
@@ -137,68 +139,23 @@ impl WhiteboxTool for LidarTile {
         self.toolbox.clone()
     }
 
-    fn run<'a>(&self, args: Vec<String>, working_directory: &'a str, verbose: bool) -> Result<(), Error> {
-        let mut input_file: String = String::new();
-        let mut width_x = 1000.0;
-        let mut width_y = 1000.0;
-        let mut origin_x = 0.0;
-        let mut origin_y = 0.0;
-        let mut min_points = 0;
-
+    fn run<'a>(&self, args: Vec<String>, working_directory: &'a str, verbose: bool, sink: &Arc<ProgressSink>) -> Result<(), Error> {
         // read the arguments
         if args.len() == 0 {
             return Err(Error::new(ErrorKind::InvalidInput, "Tool run with no paramters."));
         }
-        for i in 0..args.len() {
-            let mut arg = args[i].replace("\"", "");
-            arg = arg.replace("\'", "");
-            let cmd = arg.split("="); // in case an equals sign was used
-            let vec = cmd.collect::<Vec<&str>>();
-            let mut keyval = false;
-            if vec.len() > 1 { keyval = true; }
-            if vec[0].to_lowercase() == "-i" || vec[0].to_lowercase() == "--input" {
-                if keyval {
-                    input_file = vec[1].to_string();
-                } else {
-                    input_file = args[i+1].to_string();
-                }
-            } else if vec[0].to_lowercase() == "-width_x" || vec[0].to_lowercase() == "--width_x" {
-                if keyval {
-                    width_x = vec[1].to_string().parse::<f64>().unwrap();
-                } else {
-                    width_x = args[i+1].to_string().parse::<f64>().unwrap();
-                }
-            } else if vec[0].to_lowercase() == "-width_y" || vec[0].to_lowercase() == "--width_y" {
-                if keyval {
-                    width_y = vec[1].to_string().parse::<f64>().unwrap();
-                } else {
-                    width_y = args[i+1].to_string().parse::<f64>().unwrap();
-                }
-            } else if vec[0].to_lowercase() == "-origin_x" || vec[0].to_lowercase() == "--origin_x" {
-                if keyval {
-                    origin_x = vec[1].to_string().parse::<f64>().unwrap();
-                } else {
-                    origin_x = args[i+1].to_string().parse::<f64>().unwrap();
-                }
-            } else if vec[0].to_lowercase() == "-origin_y" || vec[0].to_lowercase() == "--origin_y" {
-                if keyval {
-                    origin_y = vec[1].to_string().parse::<f64>().unwrap();
-                } else {
-                    origin_y = args[i+1].to_string().parse::<f64>().unwrap();
-                }
-            } else if vec[0].to_lowercase() == "-min_points" || vec[0].to_lowercase() == "--min_points" {
-                if keyval {
-                    min_points = vec[1].to_string().parse::<usize>().unwrap();
-                } else {
-                    min_points = args[i+1].to_string().parse::<usize>().unwrap();
-                }
-            }
-        }
+        let args = ArgValues::parse(&self.parameters, &args)?;
+        let mut input_file = args.required_string("--input")?;
+        let width_x = args.get_f64("--width_x")?.unwrap_or(1000.0);
+        let width_y = args.get_f64("--width_y")?.unwrap_or(1000.0);
+        let origin_x = args.get_f64("--origin_x")?.unwrap_or(0.0);
+        let origin_y = args.get_f64("--origin_y")?.unwrap_or(0.0);
+        let min_points = args.get_usize("--min_points")?.unwrap_or(0);
 
         if verbose {
-            println!("***************{}", "*".repeat(self.get_tool_name().len()));
-            println!("* Welcome to {} *", self.get_tool_name());
-            println!("***************{}", "*".repeat(self.get_tool_name().len()));
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
+            sink.message(&format!("* Welcome to {} *", self.get_tool_name()));
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
         }
 
         let sep = std::path::MAIN_SEPARATOR;
@@ -207,7 +164,7 @@ impl WhiteboxTool for LidarTile {
             input_file = format!("{}{}", working_directory, input_file);
         }
 
-        if verbose { println!("Performing analysis..."); }
+        if verbose { sink.message("Performing analysis..."); }
 
         let input = match LasFile::new(&input_file, "r") {
             Ok(lf) => lf,
@@ -245,12 +202,10 @@ impl WhiteboxTool for LidarTile {
             row = (((p.y - origin_y) / width_y) - start_y_grid).floor() as usize; // relative to the grid edge
             tile_data[i] = row * cols + col;
 
-            if verbose {
-                progress = (100.0_f64 * i as f64 / num_points) as i32;
-                if progress != old_progress {
-                    println!("Progress (Loop 1 of 3): {}%", progress);
-                    old_progress = progress;
-                }
+            progress = (100.0_f64 * i as f64 / num_points) as i32;
+            if progress != old_progress {
+                sink.progress("Progress (Loop 1 of 3)", progress as usize)?;
+                old_progress = progress;
             }
         }
 
@@ -266,12 +221,10 @@ impl WhiteboxTool for LidarTile {
             if first_point_num[tile_data[i]] == n_points_plus_one {
                 first_point_num[tile_data[i]] = i;
             }
-            if verbose {
-                progress = (100.0_f64 * i as f64 / num_points) as i32;
-                if progress != old_progress {
-                    println!("Progress (Loop 2 of 3): {}%", progress);
-                    old_progress = progress;
-                }
+            progress = (100.0_f64 * i as f64 / num_points) as i32;
+            if progress != old_progress {
+                sink.progress("Progress (Loop 2 of 3)", progress as usize)?;
+                old_progress = progress;
             }
         }
 
@@ -303,13 +256,15 @@ impl WhiteboxTool for LidarTile {
             None => "".to_string(),
         };
         let output_dir: String = format!("{}{}{}{}", dir.to_string(), sep, name, sep);
+        // the tiles are compressed if the input file is
+        let extension = if input_file.to_lowercase().ends_with(".laz") { "laz" } else { "las" };
         DirBuilder::new().recursive(true).create(output_dir.clone()).unwrap();
         let mut num_tiles_created = 0;
         for tile_num in 0..num_tiles {
             if output_tile[tile_num] {
                 row = (tile_num as f64 / cols as f64).floor() as usize;
                 col = tile_num % cols;
-                let output_file = format!("{}{}_row{}_col{}.las", output_dir, name, row - min_row + 1, col - min_col + 1);
+                let output_file = format!("{}{}_row{}_col{}.{}", output_dir, name, row - min_row + 1, col - min_col + 1, extension);
                 let mut output = LasFile::initialize_using_file(&output_file, &input);
                 output.header.system_id = "EXTRACTION".to_string();
 
@@ -325,18 +280,16 @@ impl WhiteboxTool for LidarTile {
                 num_tiles_created += 1;
             }
 
-            if verbose {
-                progress = (100.0_f64 * tile_num as f64 / (num_tiles - 1) as f64) as i32;
-                if progress != old_progress {
-                    println!("Progress (Loop 3 of 3): {}%", progress);
-                    old_progress = progress;
-                }
+            progress = (100.0_f64 * tile_num as f64 / (num_tiles - 1) as f64) as i32;
+            if progress != old_progress {
+                sink.progress("Progress (Loop 3 of 3)", progress as usize)?;
+                old_progress = progress;
             }
         }
 
         if num_tiles_created > 0 {
             if verbose {
-                println!("Successfully created {} tiles.", num_tiles_created);
+                sink.message(&format!("Successfully created {} tiles.", num_tiles_created));
             }
         } else if num_tiles_created == 0 {
             return Err(Error::new(ErrorKind::Other, "Error: No tiles were created."));
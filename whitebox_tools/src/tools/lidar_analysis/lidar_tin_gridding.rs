@@ -0,0 +1,580 @@
+/*
+This tool is part of the WhiteboxTools geospatial analysis library.
+Authors: Dr. John Lindsay
+Created: April 2, 2018
+Last Modified: April 2, 2018
+License: MIT
+
+Help: This tool creates a raster grid from a LiDAR point cloud by linear interpolation within
+the triangles of a Delaunay triangulated irregular network (TIN) of the points. The value of
+each grid cell is found from the plane that passes through the three points of the triangle
+that contains the cell centre. Unlike nearest-neighbour and inverse-distance weighted
+gridding, TIN interpolation honours the points exactly and does not require a search radius.
+The interpolated parameter (--parameter) may be the point elevation (the default),
+intensity, class, scan angle, or user data.
+
+The points that are included in the TIN can be restricted to the first or last returns
+(--returns), to a range of elevations (--minz and --maxz), and by excluding classes
+(--exclude_cls), e.g. --exclude_cls='3,4,5,6,7,18' to leave out vegetation, buildings and
+noise. Withheld points are always excluded.
+
+Triangles connect all of the points on the convex hull of the point cloud, and so they
+bridge areas without points, such as water bodies and building footprints that have been
+excluded, as well as the concave edges of the data. Triangles with any edge longer than
+the maximum triangle edge length (--max_triangle_edge_length) are not interpolated, and
+the grid cells that they contain are assigned NoData.
+
+This tool can be run on a single input file (--input), or on all of the LAS files within
+the working directory if --input is not specified, in which case each output raster takes
+the name of its input file. When a directory of LiDAR tiles is gridded, the points of
+neighbouring tiles that lie within a buffer of each tile are included in its TIN, so that
+there are no gaps or edge effects between the output rasters. The buffer is the maximum
+triangle edge length, or ten grid cells if no maximum is specified.
+
+See Also: LidarNearestNeighbourGridding, LidarIdwInterpolation, ContoursFromPoints
+*/
+extern crate time;
+extern crate num_cpus;
+
+use std::env;
+use std::f64;
+use std::fs;
+use std::io::{Error, ErrorKind};
+use std::path;
+use std::sync::{Arc, Mutex};
+use std::sync::mpsc;
+use std::thread;
+use lidar::*;
+use raster::*;
+use spatial_ref_system::read_spatial_ref;
+use structures::{BoundingBox, Triangulation};
+use vector::Point2D;
+use tools::*;
+use tools::arg_parser::ArgValues;
+
+pub struct LidarTINGridding {
+    name: String,
+    description: String,
+    toolbox: String,
+    parameters: Vec<ToolParameter>,
+    example_usage: String,
+}
+
+impl LidarTINGridding {
+    pub fn new() -> LidarTINGridding { // public constructor
+        let name = "LidarTINGridding".to_string();
+        let toolbox = "LiDAR Tools".to_string();
+        let description = "Creates a raster grid based on a Delaunay triangular irregular network (TIN) fitted to LiDAR points.".to_string();
+
+        let mut parameters = vec![];
+        parameters.push(ToolParameter{
+            name: "Input File".to_owned(),
+            flags: vec!["-i".to_owned(), "--input".to_owned()],
+            description: "Input LiDAR file (including extension).".to_owned(),
+            parameter_type: ParameterType::ExistingFile(ParameterFileType::Lidar),
+            default_value: None,
+            optional: true
+        });
+
+        parameters.push(ToolParameter{
+            name: "Output File".to_owned(),
+            flags: vec!["-o".to_owned(), "--output".to_owned()],
+            description: "Output raster file (including extension).".to_owned(),
+            parameter_type: ParameterType::NewFile(ParameterFileType::Raster),
+            default_value: None,
+            optional: true
+        });
+
+        parameters.push(ToolParameter{
+            name: "Interpolation Parameter".to_owned(),
+            flags: vec!["--parameter".to_owned()],
+            description: "Interpolation parameter; options are 'elevation' (default), 'intensity', 'class', 'scan angle', 'user data'.".to_owned(),
+            parameter_type: ParameterType::OptionList(vec!["elevation".to_owned(), "intensity".to_owned(), "class".to_owned(), "scan angle".to_owned(), "user data".to_owned()]),
+            default_value: Some("elevation".to_owned()),
+            optional: true
+        });
+
+        parameters.push(ToolParameter{
+            name: "Point Returns Included".to_owned(),
+            flags: vec!["--returns".to_owned()],
+            description: "Point return types to include; options are 'all' (default), 'last', 'first'.".to_owned(),
+            parameter_type: ParameterType::OptionList(vec!["all".to_owned(), "last".to_owned(), "first".to_owned()]),
+            default_value: Some("all".to_owned()),
+            optional: true
+        });
+
+        parameters.push(ToolParameter{
+            name: "Grid Resolution".to_owned(),
+            flags: vec!["--resolution".to_owned()],
+            description: "Output raster's grid resolution.".to_owned(),
+            parameter_type: ParameterType::Float,
+            default_value: Some("1.0".to_owned()),
+            optional: true
+        });
+
+        parameters.push(ToolParameter{
+            name: "Exclusion Classes (0-18, based on LAS spec; e.g. 3,4,5,6,7)".to_owned(),
+            flags: vec!["--exclude_cls".to_owned()],
+            description: "Optional exclude classes from interpolation; Valid class values range from 0 to 18, based on LAS specifications. Example, --exclude_cls='3,4,5,6,7,18'.".to_owned(),
+            parameter_type: ParameterType::String,
+            default_value: None,
+            optional: true
+        });
+
+        parameters.push(ToolParameter{
+            name: "Minimum Elevation Value (optional)".to_owned(),
+            flags: vec!["--minz".to_owned()],
+            description: "Optional minimum elevation for inclusion in interpolation.".to_owned(),
+            parameter_type: ParameterType::Float,
+            default_value: None,
+            optional: true
+        });
+
+        parameters.push(ToolParameter{
+            name: "Maximum Elevation Value (optional)".to_owned(),
+            flags: vec!["--maxz".to_owned()],
+            description: "Optional maximum elevation for inclusion in interpolation.".to_owned(),
+            parameter_type: ParameterType::Float,
+            default_value: None,
+            optional: true
+        });
+
+        parameters.push(ToolParameter{
+            name: "Maximum Triangle Edge Length (optional)".to_owned(),
+            flags: vec!["--max_triangle_edge_length".to_owned()],
+            description: "Optional maximum triangle edge length; triangles larger than this size will not be gridded.".to_owned(),
+            parameter_type: ParameterType::Float,
+            default_value: None,
+            optional: true
+        });
+
+        let sep: String = path::MAIN_SEPARATOR.to_string();
+        let p = format!("{}", env::current_dir().unwrap().display());
+        let e = format!("{}", env::current_exe().unwrap().display());
+        let mut short_exe = e.replace(&p, "").replace(".exe", "").replace(".", "").replace(&sep, "");
+        if e.contains(".exe") {
+            short_exe += ".exe";
+        }
+        let usage = format!(">>.*{0} -r={1} -v --wd=\"*path*to*data*\" -i=file.las -o=outfile.tif --returns=last --resolution=2.0 --exclude_cls='3,4,5,6,7,18' --max_triangle_edge_length=5.0
+>>.*{0} -r={1} -v --wd=\"*path*to*data*\" --parameter=intensity --resolution=1.0 --max_triangle_edge_length=10.0", short_exe, name).replace("*", &sep);
+
+        LidarTINGridding {
+            name: name,
+            description: description,
+            toolbox: toolbox,
+            parameters: parameters,
+            example_usage: usage
+        }
+    }
+}
+
+impl WhiteboxTool for LidarTINGridding {
+    fn get_source_file(&self) -> String {
+        String::from(file!())
+    }
+
+    fn get_tool_name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn get_tool_description(&self) -> String {
+        self.description.clone()
+    }
+
+    fn get_tool_parameters(&self) -> String {
+        match serde_json::to_string(&self.parameters) {
+            Ok(json_str) => return format!("{{\"parameters\":{}}}", json_str),
+            Err(err) => return format!("{:?}", err),
+        }
+    }
+
+    fn get_example_usage(&self) -> String {
+        self.example_usage.clone()
+    }
+
+    fn get_toolbox(&self) -> String {
+        self.toolbox.clone()
+    }
+
+    fn run<'a>(&self, args: Vec<String>, working_directory: &'a str, verbose: bool, sink: &Arc<ProgressSink>) -> Result<(), Error> {
+        if args.len() == 0 {
+            return Err(Error::new(ErrorKind::InvalidInput,
+                                "Tool run with no paramters."));
+        }
+        let args = ArgValues::parse(&self.parameters, &args)?;
+        let input_file = args.get_file("--input", working_directory)?;
+        let output_file = args.get_file("--output", working_directory)?;
+        let interp_parameter = args.get_string("--parameter")?.unwrap_or("elevation".to_string());
+        let return_type = args.get_string("--returns")?.unwrap_or("all".to_string());
+        let grid_res = args.get_f64("--resolution")?.unwrap_or(1f64);
+        let exclude_cls_str = args.get_string("--exclude_cls")?.unwrap_or(String::new());
+        let mut include_class_vals = vec![true; 256];
+        for value in exclude_cls_str.split(|c| c == ',' || c == ';') {
+            if !value.trim().is_empty() {
+                match value.trim().parse::<u8>() {
+                    Ok(c) => include_class_vals[c as usize] = false,
+                    Err(_) => return Err(Error::new(ErrorKind::InvalidInput,
+                        format!("Unrecognized class value in --exclude_cls: {}", value.trim()))),
+                }
+            }
+        }
+        let min_z = args.get_f64("--minz")?.unwrap_or(f64::NEG_INFINITY);
+        let max_z = args.get_f64("--maxz")?.unwrap_or(f64::INFINITY);
+        let max_edge_length = args.get_f64("--max_triangle_edge_length")?.unwrap_or(f64::INFINITY);
+
+        if verbose {
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
+            sink.message(&format!("* Welcome to {} *", self.get_tool_name()));
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
+        }
+
+        if !(grid_res > 0f64) {
+            return Err(Error::new(ErrorKind::InvalidInput,
+                                "The grid resolution must be greater than zero."));
+        }
+        if !(max_edge_length > 0f64) {
+            return Err(Error::new(ErrorKind::InvalidInput,
+                                "The maximum triangle edge length must be greater than zero."));
+        }
+
+        let start = time::now();
+
+        let (all_returns, late_returns, early_returns) = if return_type.contains("last") {
+            (false, true, false)
+        } else if return_type.contains("first") {
+            (false, false, true)
+        } else {
+            (true, false, false)
+        };
+
+        let mut inputs = vec![];
+        let mut outputs = vec![];
+        if input_file.is_none() {
+            if working_directory.is_empty() {
+                return Err(Error::new(ErrorKind::InvalidInput,
+                    "This tool must be run by specifying either an individual input file or a working directory."));
+            }
+            for path in fs::read_dir(working_directory)? {
+                let s = format!("{}", path?.path().display());
+                let lower = s.to_lowercase();
+                if lower.ends_with(".las") || lower.ends_with(".laz") || lower.ends_with(".zip") {
+                    outputs.push(format!("{}.tif", &s[..s.len() - 4]));
+                    inputs.push(s);
+                }
+            }
+        } else if let Some(input_file) = input_file {
+            let output_file = match output_file {
+                Some(f) => f,
+                None => format!("{}.tif", &input_file[..input_file.len() - 4]),
+            };
+            inputs.push(input_file);
+            outputs.push(output_file);
+        }
+        if inputs.len() == 0 {
+            return Err(Error::new(ErrorKind::InvalidInput,
+                "There are no LiDAR files in the working directory."));
+        }
+
+        // The points of neighbouring tiles that lie within a buffer of each tile are included
+        // in its TIN, so that there are no edge effects.
+        let mut bounding_boxes = vec![];
+        for in_file in &inputs {
+            let header = LasHeader::read_las_header(in_file)?;
+            bounding_boxes.push(BoundingBox::new(header.min_x, header.max_x, header.min_y, header.max_y));
+        }
+        let buffer = if max_edge_length.is_finite() { max_edge_length } else { 10f64 * grid_res };
+
+        if verbose {
+            sink.message("Performing interpolation...");
+        }
+
+        let num_tiles = inputs.len();
+        let tile_list = Arc::new(Mutex::new(0..num_tiles));
+        let inputs = Arc::new(inputs);
+        let outputs = Arc::new(outputs);
+        let bounding_boxes = Arc::new(bounding_boxes);
+        let num_procs = (num_cpus::get() as usize).min(num_tiles);
+        let (tx, rx) = mpsc::channel();
+        for _ in 0..num_procs {
+            let inputs = inputs.clone();
+            let outputs = outputs.clone();
+            let bounding_boxes = bounding_boxes.clone();
+            let tile_list = tile_list.clone();
+            let interp_parameter = interp_parameter.clone();
+            let return_type = return_type.clone();
+            let exclude_cls_str = exclude_cls_str.clone();
+            let include_class_vals = include_class_vals.clone();
+            let tool_name = self.get_tool_name();
+            let tx = tx.clone();
+            let sink = sink.clone();
+            thread::spawn(move || {
+                loop {
+                    // get the next tile up for interpolation
+                    let tile = match tile_list.lock().unwrap().next() {
+                        Some(val) => val,
+                        None => break,
+                    };
+                    let result = (|| -> Result<(), Error> {
+                        let start_run = time::now();
+                        let bb = bounding_boxes[tile];
+                        let buffered_bb = BoundingBox::new(bb.min_x - buffer, bb.max_x + buffer, bb.min_y - buffer, bb.max_y + buffer);
+
+                        if num_tiles == 1 {
+                            sink.message("Reading input LAS file...");
+                        }
+                        let mut points: Vec<Point2D> = vec![];
+                        let mut values: Vec<f64> = vec![];
+                        for m in 0..inputs.len() {
+                            if !bounding_boxes[m].overlaps(buffered_bb) {
+                                continue;
+                            }
+                            let mut input = LasReader::new(&inputs[m])?;
+                            let n_points = input.header.number_of_points as usize;
+                            let mut progress: usize;
+                            let mut old_progress: usize = 1;
+                            while let Some(chunk) = input.next() {
+                                for point in chunk? {
+                                    let p = point.point_data();
+                                    if p.withheld() || !include_class_vals[p.classification() as usize] {
+                                        continue;
+                                    }
+                                    if !(all_returns || (p.is_late_return() && late_returns) || (p.is_early_return() && early_returns)) {
+                                        continue;
+                                    }
+                                    if p.z < min_z || p.z > max_z || p.x < buffered_bb.min_x || p.x > buffered_bb.max_x || p.y < buffered_bb.min_y || p.y > buffered_bb.max_y {
+                                        continue;
+                                    }
+                                    points.push(Point2D { x: p.x, y: p.y });
+                                    values.push(match &interp_parameter as &str {
+                                        "intensity" => p.intensity as f64,
+                                        "class" => p.classification() as f64,
+                                        "scan angle" => p.scan_angle as f64,
+                                        "user data" => p.user_data as f64,
+                                        _ => p.z,
+                                    });
+                                }
+                                if num_tiles == 1 {
+                                    progress = (100.0_f64 * input.points_read() as f64 / n_points.max(1) as f64) as usize;
+                                    if progress != old_progress {
+                                        sink.progress("Reading points", progress)?;
+                                        old_progress = progress;
+                                    }
+                                }
+                            }
+                        }
+
+                        if num_tiles == 1 {
+                            sink.message("Creating the TIN...");
+                        }
+                        let tin = Triangulation::new(&points);
+
+                        let west = bb.min_x;
+                        let north = bb.max_y;
+                        let rows = (((north - bb.min_y) / grid_res).ceil() as isize).max(1);
+                        let columns = (((bb.max_x - west) / grid_res).ceil() as isize).max(1);
+                        let nodata = -32768.0f64;
+
+                        let mut configs = RasterConfigs { ..Default::default() };
+                        configs.rows = rows as usize;
+                        configs.columns = columns as usize;
+                        configs.north = north;
+                        configs.south = north - rows as f64 * grid_res;
+                        configs.east = west + columns as f64 * grid_res;
+                        configs.west = west;
+                        configs.resolution_x = grid_res;
+                        configs.resolution_y = grid_res;
+                        configs.nodata = nodata;
+                        configs.data_type = DataType::F64;
+                        configs.photometric_interp = PhotometricInterpretation::Continuous;
+                        if let Some(srs) = read_spatial_ref(&inputs[tile])? {
+                            configs.epsg_code = srs.epsg_code;
+                            configs.coordinate_ref_system_wkt = srs.to_wkt();
+                        }
+
+                        let mut output = Raster::initialize_using_config(&outputs[tile], &configs);
+
+                        // Each triangle is interpolated over the grid cells whose centres it contains.
+                        // Cells on an edge shared by two triangles receive the same value from both.
+                        let num_triangles = tin.num_triangles();
+                        let mut progress: usize;
+                        let mut old_progress: usize = 1;
+                        for t in 0..num_triangles {
+                            let v = tin.triangle_points(t);
+                            let (p1, p2, p3) = (&points[v[0]], &points[v[1]], &points[v[2]]);
+                            if max_edge_length.is_finite() {
+                                let length = |a: &Point2D, b: &Point2D| ((a.x - b.x) * (a.x - b.x) + (a.y - b.y) * (a.y - b.y)).sqrt();
+                                if length(p1, p2) > max_edge_length || length(p2, p3) > max_edge_length || length(p3, p1) > max_edge_length {
+                                    continue;
+                                }
+                            }
+                            let det = (p2.y - p3.y) * (p1.x - p3.x) + (p3.x - p2.x) * (p1.y - p3.y);
+                            if det == 0f64 {
+                                continue;
+                            }
+                            let min_x = p1.x.min(p2.x).min(p3.x);
+                            let max_x = p1.x.max(p2.x).max(p3.x);
+                            let min_y = p1.y.min(p2.y).min(p3.y);
+                            let max_y = p1.y.max(p2.y).max(p3.y);
+                            let start_col = (((min_x - west) / grid_res - 0.5).ceil() as isize).max(0);
+                            let end_col = (((max_x - west) / grid_res - 0.5).floor() as isize).min(columns - 1);
+                            let start_row = (((north - max_y) / grid_res - 0.5).ceil() as isize).max(0);
+                            let end_row = (((north - min_y) / grid_res - 0.5).floor() as isize).min(rows - 1);
+                            for row in start_row..end_row + 1 {
+                                let y = north - (row as f64 + 0.5) * grid_res;
+                                for col in start_col..end_col + 1 {
+                                    let x = west + (col as f64 + 0.5) * grid_res;
+                                    // barycentric coordinates, with a small tolerance so that cells
+                                    // on the shared edges of triangles are not missed
+                                    let w1 = ((p2.y - p3.y) * (x - p3.x) + (p3.x - p2.x) * (y - p3.y)) / det;
+                                    let w2 = ((p3.y - p1.y) * (x - p3.x) + (p1.x - p3.x) * (y - p3.y)) / det;
+                                    let w3 = 1f64 - w1 - w2;
+                                    if w1 >= -1e-9 && w2 >= -1e-9 && w3 >= -1e-9 {
+                                        output.set_value(row, col, w1 * values[v[0]] + w2 * values[v[1]] + w3 * values[v[2]]);
+                                    }
+                                }
+                            }
+                            if num_tiles == 1 {
+                                progress = (100.0_f64 * t as f64 / (num_triangles - 1).max(1) as f64) as usize;
+                                if progress != old_progress {
+                                    sink.progress("Interpolating", progress)?;
+                                    old_progress = progress;
+                                }
+                            }
+                        }
+
+                        let end_run = time::now();
+                        let elapsed_time_run = end_run - start_run;
+
+                        output.add_metadata_entry(format!("Created by whitebox_tools\' {} tool", tool_name));
+                        output.add_metadata_entry(format!("Input file: {}", inputs[tile]));
+                        output.add_metadata_entry(format!("Grid resolution: {}", grid_res));
+                        output.add_metadata_entry(format!("Interpolation parameter: {}", interp_parameter));
+                        output.add_metadata_entry(format!("Returns: {}", return_type));
+                        output.add_metadata_entry(format!("Excluded classes: {}", exclude_cls_str));
+                        if max_edge_length.is_finite() {
+                            output.add_metadata_entry(format!("Maximum triangle edge length: {}", max_edge_length));
+                        }
+                        output.add_metadata_entry(format!("Elapsed Time (including I/O): {}", elapsed_time_run).replace("PT", ""));
+
+                        if num_tiles == 1 {
+                            sink.message("Saving data...");
+                        }
+                        output.write()?;
+                        Ok(())
+                    })();
+                    tx.send((tile, result)).unwrap();
+                }
+            });
+        }
+
+        let mut progress: usize;
+        let mut old_progress: usize = 1;
+        for i in 0..num_tiles {
+            let (tile, result) = rx.recv().unwrap();
+            if let Err(e) = result {
+                return Err(Error::new(e.kind(), format!("Error interpolating {}: {}", inputs[tile], e)));
+            }
+            if num_tiles > 1 {
+                if verbose {
+                    sink.message(&format!("Finished interpolating {} ({} of {})", inputs[tile].replace(working_directory, ""), i + 1, num_tiles));
+                }
+                progress = (100.0_f64 * (i + 1) as f64 / num_tiles as f64) as usize;
+                if progress != old_progress {
+                    sink.progress("Progress", progress)?;
+                    old_progress = progress;
+                }
+            }
+        }
+
+        let end = time::now();
+        let elapsed_time = end - start;
+
+        if verbose {
+            sink.message(&format!("Elapsed Time (including I/O): {}", elapsed_time).replace("PT", ""));
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_file(name: &str) -> String {
+        env::temp_dir().join(name).to_str().unwrap().to_string()
+    }
+
+    fn plane(x: f64, y: f64) -> f64 {
+        0.5 * x + 0.25 * y + 100.0
+    }
+
+    // Writes a LAS file of jittered ground points on a plane, with a block of building
+    // points, which lie well above it, in the middle.
+    fn plane_points(file_name: &str) {
+        let mut output = LasFile::new(file_name, "w").unwrap();
+        output.add_header(LasHeader { point_format: 0, x_scale_factor: 0.0001, y_scale_factor: 0.0001,
+            z_scale_factor: 0.0001, ..Default::default() });
+        let mut seed = 99u64;
+        let mut jitter = || {
+            seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            ((seed >> 11) as f64 / (1u64 << 53) as f64 - 0.5) * 0.8
+        };
+        for row in 0..51 {
+            for col in 0..51 {
+                let (x, y) = if row == 0 || col == 0 || row == 50 || col == 50 {
+                    (col as f64, row as f64)
+                } else {
+                    (col as f64 + jitter(), row as f64 + jitter())
+                };
+                let is_building = x > 20.0 && x < 30.0 && y > 20.0 && y < 30.0;
+                let mut p = PointData { x: x, y: y, z: plane(x, y) + if is_building { 1000.0 } else { 0.0 }, ..Default::default() };
+                p.set_classification(if is_building { 6 } else { 2 });
+                output.add_point_record(LidarPointRecord::PointRecord0 { point_data: p });
+            }
+        }
+        output.write().unwrap();
+    }
+
+    fn grid(input: &str, output: &str, extra_args: &[&str]) -> Raster {
+        let mut args = vec![format!("-i={}", input), format!("-o={}", output), "--resolution=0.5".to_string(),
+            "--exclude_cls=6".to_string()];
+        args.extend(extra_args.iter().map(|a| a.to_string()));
+        let mut sink = StdoutProgress::new();
+        sink.set_quiet(true);
+        let sink: Arc<ProgressSink> = Arc::new(sink);
+        LidarTINGridding::new().run(args, "", false, &sink).unwrap();
+        let r = Raster::new(output, "r").unwrap();
+        let _ = fs::remove_file(output);
+        r
+    }
+
+    #[test]
+    fn a_plane_is_reproduced() {
+        let (input, output) = (temp_file("wbt_ltg_plane.las"), temp_file("wbt_ltg_plane.tif"));
+        plane_points(&input);
+
+        // the excluded building points are bridged by triangles
+        let r = grid(&input, &output, &[]);
+        assert_eq!((r.configs.rows, r.configs.columns), (100, 100));
+        for row in 0..100 {
+            for col in 0..100 {
+                let (x, y) = (r.get_x_from_column(col), r.get_y_from_row(row));
+                let z = r.get_value(row, col);
+                assert!((z - plane(x, y)).abs() < 1e-3, "({}, {}): {}", x, y, z);
+            }
+        }
+
+        // ...unless their edges are longer than the maximum
+        let r = grid(&input, &output, &["--max_triangle_edge_length=3.0"]);
+        let nodata = r.configs.nodata;
+        assert_eq!(r.get_value(r.get_row_from_y(25.0), r.get_column_from_x(25.0)), nodata);
+        let (row, col) = (r.get_row_from_y(10.0), r.get_column_from_x(10.0));
+        assert!((r.get_value(row, col) - plane(r.get_x_from_column(col), r.get_y_from_row(row))).abs() < 1e-3);
+        let num_nodata = (0..100).flat_map(|row| (0..100).map(move |col| (row, col)))
+            .filter(|&(row, col)| r.get_value(row, col) == nodata).count();
+        // the gap is roughly 10 m square, and so covers about 400 cells
+        assert!(num_nodata > 300 && num_nodata < 500, "{} NoData cells", num_nodata);
+        let _ = fs::remove_file(&input);
+    }
+}
@@ -21,9 +21,11 @@ mod lidar_point_stats;
 mod lidar_segmentation;
 mod lidar_segmentation_based_filter;
 mod lidar_tile;
+mod lidar_tin_gridding;
 mod lidar_tophat_transform;
 mod normal_vectors;
 mod remove_duplicates;
+mod reproject_lidar;
 
 // exports identifiers from private sub-modules in the current module namespace
 pub use self::block_maximum::BlockMaximum;
@@ -48,6 +50,8 @@ pub use self::lidar_point_stats::LidarPointStats;
 pub use self::lidar_segmentation::LidarSegmentation;
 pub use self::lidar_segmentation_based_filter::LidarSegmentationBasedFilter;
 pub use self::lidar_tile::LidarTile;
+pub use self::lidar_tin_gridding::LidarTINGridding;
 pub use self::lidar_tophat_transform::LidarTophatTransform;
 pub use self::normal_vectors::NormalVectors;
-pub use self::remove_duplicates::LidarRemoveDuplicates;
\ No newline at end of file
+pub use self::remove_duplicates::LidarRemoveDuplicates;
+pub use self::reproject_lidar::ReprojectLidar;
\ No newline at end of file
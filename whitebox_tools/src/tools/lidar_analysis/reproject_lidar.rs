@@ -0,0 +1,218 @@
+/*
+This tool is part of the WhiteboxTools geospatial analysis library.
+Authors: Dr. John Lindsay
+Created: March 29, 2018
+Last Modified: March 29, 2018
+License: MIT
+
+Notes: The horizontal coordinates of each point are transformed into the output coordinate
+reference system, including a datum shift where the two systems are based on different
+datums. Elevations are not modified. The output coordinate reference system is written
+as a WKT record in LAS 1.4 files and as GeoKeys in earlier versions.
+*/
+
+use std::env;
+use std::f64;
+use std::path;
+use std::io::{Error, ErrorKind};
+use std::sync::Arc;
+use lidar::*;
+use spatial_ref_system::{read_spatial_ref, SpatialRef, Transformation};
+use tools::*;
+use tools::arg_parser::ArgValues;
+
+pub struct ReprojectLidar {
+    name: String,
+    description: String,
+    toolbox: String,
+    parameters: Vec<ToolParameter>,
+    example_usage: String,
+}
+
+impl ReprojectLidar {
+    pub fn new() -> ReprojectLidar { // public constructor
+        let name = "ReprojectLidar".to_string();
+        let toolbox = "LiDAR Tools".to_string();
+        let description = "Transforms the points of a LiDAR (LAS) file into another coordinate reference system.".to_string();
+
+        let mut parameters = vec![];
+        parameters.push(ToolParameter{
+            name: "Input File".to_owned(),
+            flags: vec!["-i".to_owned(), "--input".to_owned()],
+            description: "Input LiDAR file.".to_owned(),
+            parameter_type: ParameterType::ExistingFile(ParameterFileType::Lidar),
+            default_value: None,
+            optional: false
+        });
+
+        parameters.push(ToolParameter{
+            name: "Output File".to_owned(),
+            flags: vec!["-o".to_owned(), "--output".to_owned()],
+            description: "Output LiDAR file.".to_owned(),
+            parameter_type: ParameterType::NewFile(ParameterFileType::Lidar),
+            default_value: None,
+            optional: false
+        });
+
+        parameters.push(ToolParameter{
+            name: "Output Coordinate Reference System".to_owned(),
+            flags: vec!["--crs".to_owned()],
+            description: "Output coordinate reference system, as an EPSG code, WKT, .prj file, or a file with the desired system.".to_owned(),
+            parameter_type: ParameterType::String,
+            default_value: None,
+            optional: false
+        });
+
+        let sep: String = path::MAIN_SEPARATOR.to_string();
+        let p = format!("{}", env::current_dir().unwrap().display());
+        let e = format!("{}", env::current_exe().unwrap().display());
+        let mut short_exe = e.replace(&p, "").replace(".exe", "").replace(".", "").replace(&sep, "");
+        if e.contains(".exe") {
+            short_exe += ".exe";
+        }
+        let usage = format!(">>.*{0} -r={1} -v --wd=\"*path*to*data*\" -i=\"input.las\" -o=\"output.las\" --crs=EPSG:26917
+>>.*{0} -r={1} -v --wd=\"*path*to*data*\" -i=\"input.las\" -o=\"output.las\" --crs=\"other.las\"", short_exe, name).replace("*", &sep);
+
+        ReprojectLidar {
+            name: name,
+            description: description,
+            toolbox: toolbox,
+            parameters: parameters,
+            example_usage: usage
+        }
+    }
+}
+
+impl WhiteboxTool for ReprojectLidar {
+    fn get_source_file(&self) -> String {
+        String::from(file!())
+    }
+
+    fn get_tool_name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn get_tool_description(&self) -> String {
+        self.description.clone()
+    }
+
+    fn get_tool_parameters(&self) -> String {
+        match serde_json::to_string(&self.parameters) {
+            Ok(json_str) => return format!("{{\"parameters\":{}}}", json_str),
+            Err(err) => return format!("{:?}", err),
+        }
+    }
+
+    fn get_example_usage(&self) -> String {
+        self.example_usage.clone()
+    }
+
+    fn get_toolbox(&self) -> String {
+        self.toolbox.clone()
+    }
+
+    fn run<'a>(&self, args: Vec<String>, working_directory: &'a str, verbose: bool, sink: &Arc<ProgressSink>) -> Result<(), Error> {
+        // read the arguments
+        if args.len() == 0 {
+            return Err(Error::new(ErrorKind::InvalidInput, "Tool run with no paramters."));
+        }
+        let args = ArgValues::parse(&self.parameters, &args)?;
+        let input_file = args.required_file("--input", working_directory)?;
+        let output_file = args.required_file("--output", working_directory)?;
+        let crs = args.required_string("--crs")?;
+
+        if verbose {
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
+            sink.message(&format!("* Welcome to {} *", self.get_tool_name()));
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
+        }
+
+        let destination = SpatialRef::from_user_input(&crs, working_directory)?;
+        let source = match read_spatial_ref(&input_file)? {
+            Some(srs) => srs,
+            None => return Err(Error::new(ErrorKind::InvalidInput,
+                "The input LiDAR file does not specify a coordinate reference system.")),
+        };
+        if verbose {
+            sink.message(&format!("Input coordinate reference system: {}", source));
+            sink.message(&format!("Output coordinate reference system: {}", destination));
+        }
+        let transformation = Transformation::new(&source, &destination);
+
+        let mut input = LasReader::new(&input_file)?;
+
+        // The offsets are based on the transformed extent of the points, which is found by
+        // transforming points along the edges of the input bounding box.
+        let mut header = input.header.clone();
+        let (mut min_x, mut min_y) = (f64::INFINITY, f64::INFINITY);
+        let num_steps = 20;
+        for i in 0..(num_steps + 1) {
+            let t = i as f64 / num_steps as f64;
+            let x = header.min_x + t * (header.max_x - header.min_x);
+            let y = header.min_y + t * (header.max_y - header.min_y);
+            for &(px, py) in &[(x, header.min_y), (x, header.max_y), (header.min_x, y), (header.max_x, y)] {
+                let (tx, ty) = transformation.transform(px, py);
+                if tx < min_x { min_x = tx; }
+                if ty < min_y { min_y = ty; }
+            }
+        }
+        if !min_x.is_finite() || !min_y.is_finite() {
+            return Err(Error::new(ErrorKind::InvalidInput,
+                "The extent of the input LiDAR file could not be transformed into the output coordinate reference system."));
+        }
+        let scale = if destination.is_geographic() { 0.000_000_1 } else { 0.001 };
+        header.x_scale_factor = scale;
+        header.y_scale_factor = scale;
+        header.x_offset = min_x.floor();
+        header.y_offset = min_y.floor();
+
+        let mut vlr_data: Vec<Vlr> = input.vlr_data.iter().filter(|vlr| !vlr.is_crs()).cloned().collect();
+        let is_las14 = header.version_major > 1 || header.version_minor >= 4;
+        if is_las14 {
+            vlr_data.push(Vlr::new_wkt(&destination.to_wkt()));
+        } else {
+            vlr_data.extend(Vlr::new_geokeys(&destination.to_geokeys()));
+        }
+        let mut output = LasWriter::new(&output_file, &header, &vlr_data)?;
+        for evlr in input.evlr_data.iter().filter(|vlr| !vlr.is_crs()) {
+            output.add_evlr(evlr.clone());
+        }
+
+        if verbose { sink.message("Performing analysis..."); }
+        let mut progress: i32;
+        let mut old_progress: i32 = -1;
+        let num_points = (input.header.number_of_points as f64 - 1f64).max(1f64);
+        let mut num_failed = 0usize;
+        while let Some(chunk) = input.next() {
+            for mut pr in chunk? {
+                let mut p = pr.point_data();
+                let (x, y) = transformation.transform(p.x, p.y);
+                if !x.is_finite() || !y.is_finite() {
+                    num_failed += 1;
+                    continue;
+                }
+                p.x = x;
+                p.y = y;
+                pr.set_point_data(p);
+                output.add_point_record(pr)?;
+            }
+            progress = (100.0_f64 * (input.points_read() - 1) as f64 / num_points) as i32;
+            if progress != old_progress {
+                sink.progress("Progress", progress as usize)?;
+                old_progress = progress;
+            }
+        }
+
+        if num_failed > 0 {
+            sink.warning(&format!("{} points could not be transformed and were not written to the output file.", num_failed));
+        }
+
+        if verbose { sink.message("Writing output LAS file..."); }
+        let _ = match output.finish() {
+            Ok(_) => if verbose { sink.message("Complete!") },
+            Err(e) => return Err(e),
+        };
+
+        Ok(())
+    }
+}
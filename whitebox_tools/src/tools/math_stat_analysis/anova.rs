@@ -23,6 +23,7 @@ use std::thread;
 use raster::*;
 use std::io::{Error, ErrorKind};
 use tools::*;
+use tools::arg_parser::ArgValues;
 
 pub struct Anova {
     name: String,
@@ -120,70 +121,30 @@ impl WhiteboxTool for Anova {
         self.toolbox.clone()
     }
 
-    fn run<'a>(&self, args: Vec<String>, working_directory: &'a str, verbose: bool) -> Result<(), Error> {
-        let mut input_file = String::new();
-        let mut feature_file = String::new();
-        let mut output_file = String::new();
-         
+    fn run<'a>(&self, args: Vec<String>, working_directory: &'a str, verbose: bool, sink: &Arc<ProgressSink>) -> Result<(), Error> {
         if args.len() == 0 {
             return Err(Error::new(ErrorKind::InvalidInput,
                                 "Tool run with no paramters."));
         }
-        for i in 0..args.len() {
-            let mut arg = args[i].replace("\"", "");
-            arg = arg.replace("\'", "");
-            let cmd = arg.split("="); // in case an equals sign was used
-            let vec = cmd.collect::<Vec<&str>>();
-            let mut keyval = false;
-            if vec.len() > 1 {
-                keyval = true;
-            }
-            if vec[0].to_lowercase() == "-i" || vec[0].to_lowercase() == "--input" {
-                if keyval {
-                    input_file = vec[1].to_string();
-                } else {
-                    input_file = args[i+1].to_string();
-                }
-            } else if vec[0].to_lowercase() == "-features" || vec[0].to_lowercase() == "--features" {
-                if keyval {
-                    feature_file = vec[1].to_string();
-                } else {
-                    feature_file = args[i+1].to_string();
-                }
-            } else if vec[0].to_lowercase() == "-o" || vec[0].to_lowercase() == "--output" {
-                if keyval {
-                    output_file = vec[1].to_string();
-                } else {
-                    output_file = args[i+1].to_string();
-                }
-            }
-        }
+        let args = ArgValues::parse(&self.parameters, &args)?;
+        let input_file = args.required_file("--input", working_directory)?;
+        let feature_file = args.required_file("--features", working_directory)?;
+        let mut output_file = args.required_file("--output", working_directory)?;
 
         if verbose {
-            println!("***************{}", "*".repeat(self.get_tool_name().len()));
-            println!("* Welcome to {} *", self.get_tool_name());
-            println!("***************{}", "*".repeat(self.get_tool_name().len()));
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
+            sink.message(&format!("* Welcome to {} *", self.get_tool_name()));
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
         }
 
-        let sep: String = path::MAIN_SEPARATOR.to_string();
-
         let mut progress: usize;
         let mut old_progress: usize = 1;
 
-        if !input_file.contains(&sep) && !input_file.contains("/") {
-            input_file = format!("{}{}", working_directory, input_file);
-        }
-        if !feature_file.contains(&sep) && !feature_file.contains("/") {
-            feature_file = format!("{}{}", working_directory, feature_file);
-        }
-        if !output_file.contains(&sep) && !output_file.contains("/") {
-            output_file = format!("{}{}", working_directory, output_file);
-        }
         if !output_file.ends_with(".html") {
             output_file = output_file + ".html";
         }
 
-        if verbose { println!("Reading data...") };
+        if verbose { sink.message("Reading data...") };
 
         let input = Arc::new(Raster::new(&input_file, "r")?);
 
@@ -325,12 +286,10 @@ impl WhiteboxTool for Anova {
             if d < min_id { min_id = d; }
             if e > max_id { max_id = e; }
             
-            if verbose {
-                progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as usize;
-                if progress != old_progress {
-                    println!("Progress (loop 1 of 2): {}%", progress);
-                    old_progress = progress;
-                }
+            progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as usize;
+            if progress != old_progress {
+                sink.progress("Progress (loop 1 of 2)", progress)?;
+                old_progress = progress;
             }
         }
 
@@ -360,12 +319,10 @@ impl WhiteboxTool for Anova {
                     sum_sqr[vec_id] += z * z;
                 }
             }
-            if verbose {
-                progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as usize;
-                if progress != old_progress {
-                    println!("Progress (loop 2 of 2): {}%", progress);
-                    old_progress = progress;
-                }
+            progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as usize;
+            if progress != old_progress {
+                sink.progress("Progress (loop 2 of 2)", progress)?;
+                old_progress = progress;
             }
         }
 
@@ -400,7 +357,7 @@ impl WhiteboxTool for Anova {
         let ms_w = ss_w / df_w as f64;
         let f = ms_b / ms_w;
 
-        println!("Calclating the p-value...");
+        sink.message("Calclating the p-value...");
         // Formula Used:
         // P value = [ 1 / Β(ndf/2,ddf/2) ] × [ (ndf × x) / (ndf × x + ddf) ]^ndf/2 × [1 - (ndf × x) / (ndf × x + ddf) ]^ddf/2 × x-1
         // Where,
@@ -411,7 +368,7 @@ impl WhiteboxTool for Anova {
         // from: https://www.easycalculation.com/statistics/f-test-p-value.php
         let p = f_call(f_spin(f, df_b, df_w));
 
-        println!("Saving output...");
+        sink.message("Saving output...");
 
 
 
@@ -604,13 +561,13 @@ impl WhiteboxTool for Anova {
                 let _ = output.stdout;
             }
 
-            println!("Complete! Please see {} for output.", output_file);
+            sink.message(&format!("Complete! Please see {} for output.", output_file));
         }
 
         let end = time::now();
         let elapsed_time = end - start;
         if verbose {
-            println!("\n{}", &format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
+            sink.message(&format!("\n{}", &format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", "")));
         }
 
         Ok(())
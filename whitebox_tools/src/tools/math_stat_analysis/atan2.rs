@@ -17,6 +17,7 @@ use std::thread;
 use raster::*;
 use std::io::{Error, ErrorKind};
 use tools::*;
+use tools::arg_parser::ArgValues;
 
 pub struct Atan2 {
     name: String,
@@ -114,49 +115,20 @@ impl WhiteboxTool for Atan2 {
         self.toolbox.clone()
     }
 
-    fn run<'a>(&self, args: Vec<String>, working_directory: &'a str, verbose: bool) -> Result<(), Error> {
-        let mut input1 = String::new();
-        let mut input2 = String::new();
-        let mut output_file = String::new();
-         
+    fn run<'a>(&self, args: Vec<String>, working_directory: &'a str, verbose: bool, sink: &Arc<ProgressSink>) -> Result<(), Error> {
         if args.len() == 0 {
             return Err(Error::new(ErrorKind::InvalidInput,
                                 "Tool run with no paramters."));
         }
-        for i in 0..args.len() {
-            let mut arg = args[i].replace("\"", "");
-            arg = arg.replace("\'", "");
-            let cmd = arg.split("="); // in case an equals sign was used
-            let vec = cmd.collect::<Vec<&str>>();
-            let mut keyval = false;
-            if vec.len() > 1 {
-                keyval = true;
-            }
-            if vec[0].to_lowercase() == "-input_y" || vec[0].to_lowercase() == "--input_y" {
-                if keyval {
-                    input1 = vec[1].to_string();
-                } else {
-                    input1 = args[i+1].to_string();
-                }
-            } else if vec[0].to_lowercase() == "-input_x" || vec[0].to_lowercase() == "--input_x" {
-                if keyval {
-                    input2 = vec[1].to_string();
-                } else {
-                    input2 = args[i+1].to_string();
-                }
-            } else if vec[0].to_lowercase() == "-o" || vec[0].to_lowercase() == "--output" {
-                if keyval {
-                    output_file = vec[1].to_string();
-                } else {
-                    output_file = args[i+1].to_string();
-                }
-            }
-        }
+        let args = ArgValues::parse(&self.parameters, &args)?;
+        let mut input1 = args.required_string("--input_y")?;
+        let mut input2 = args.required_string("--input_x")?;
+        let output_file = args.required_file("--output", working_directory)?;
 
         if verbose {
-            println!("***************{}", "*".repeat(self.get_tool_name().len()));
-            println!("* Welcome to {} *", self.get_tool_name());
-            println!("***************{}", "*".repeat(self.get_tool_name().len()));
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
+            sink.message(&format!("* Welcome to {} *", self.get_tool_name()));
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
         }
 
         let sep: String = path::MAIN_SEPARATOR.to_string();
@@ -164,10 +136,6 @@ impl WhiteboxTool for Atan2 {
         let mut progress: usize;
         let mut old_progress: usize = 1;
 
-        if !output_file.contains(&sep) && !output_file.contains("/") {
-            output_file = format!("{}{}", working_directory, output_file);
-        }
-
         // Are either of the inputs constants?
         let mut input1_constant = f64::NEG_INFINITY;
         let input1_is_constant = match input1.parse::<f64>() {
@@ -200,10 +168,10 @@ impl WhiteboxTool for Atan2 {
         if input1_is_constant && input2_is_constant {
             // return Err(Error::new(ErrorKind::InvalidInput,
             //                     "At least one of the inputs must be a raster."));
-            println!("{}", input1_constant.atan2(input2_constant));
+            sink.message(&format!("{}", input1_constant.atan2(input2_constant)));
             return Ok(());
         } else if input1_is_constant && !input2_is_constant {
-            if verbose { println!("Reading data...") };
+            if verbose { sink.message("Reading data...") };
             let in2 = Arc::new(Raster::new(&input2, "r")?);
 
             let start = time::now();
@@ -221,7 +189,7 @@ impl WhiteboxTool for Atan2 {
                     for row in (0..rows).filter(|r| r % num_procs == tid) {
                         let mut data: Vec<f64> = vec![nodata2; columns as usize];
                         for col in 0..columns {
-                            z2 = in2[(row, col)];
+                            z2 = in2.get_value(row, col);
                             if z2 != nodata2 {
                                 data[col as usize] = input1_constant.atan2(z2);
                             } else {
@@ -238,12 +206,10 @@ impl WhiteboxTool for Atan2 {
                 let (row, data) = rx.recv().unwrap();
                 output.set_row_data(row, data);
                 
-                if verbose {
-                    progress = (100.0_f64 * r as f64 / (rows - 1) as f64) as usize;
-                    if progress != old_progress {
-                        println!("Progress: {}%", progress);
-                        old_progress = progress;
-                    }
+                progress = (100.0_f64 * r as f64 / (rows - 1) as f64) as usize;
+                if progress != old_progress {
+                    sink.progress("Progress", progress)?;
+                    old_progress = progress;
                 }
             }
 
@@ -252,16 +218,16 @@ impl WhiteboxTool for Atan2 {
             output.add_metadata_entry(format!("Created by whitebox_tools\' {} tool", self.get_tool_name()));
             output.add_metadata_entry(format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
 
-            if verbose { println!("Saving data...") };
+            if verbose { sink.message("Saving data...") };
             let _ = match output.write() {
-                Ok(_) => if verbose { println!("Output file written") },
+                Ok(_) => if verbose { sink.message("Output file written") },
                 Err(e) => return Err(e),
             };  
             if verbose {
-                println!("{}", &format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
+                sink.message(&format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
             }
         } else if !input1_is_constant && input2_is_constant {
-            if verbose { println!("Reading data...") };
+            if verbose { sink.message("Reading data...") };
             let in1 = Arc::new(Raster::new(&input1, "r")?);
             
             let start = time::now();
@@ -279,7 +245,7 @@ impl WhiteboxTool for Atan2 {
                     for row in (0..rows).filter(|r| r % num_procs == tid) {
                         let mut data: Vec<f64> = vec![nodata1; columns as usize];
                         for col in 0..columns {
-                            z1 = in1[(row, col)];
+                            z1 = in1.get_value(row, col);
                             if z1 != nodata1 {
                                 data[col as usize] = z1.atan2(input2_constant);
                             } else {
@@ -296,12 +262,10 @@ impl WhiteboxTool for Atan2 {
                 let (row, data) = rx.recv().unwrap();
                 output.set_row_data(row, data);
                 
-                if verbose {
-                    progress = (100.0_f64 * r as f64 / (rows - 1) as f64) as usize;
-                    if progress != old_progress {
-                        println!("Progress: {}%", progress);
-                        old_progress = progress;
-                    }
+                progress = (100.0_f64 * r as f64 / (rows - 1) as f64) as usize;
+                if progress != old_progress {
+                    sink.progress("Progress", progress)?;
+                    old_progress = progress;
                 }
             }
 
@@ -310,16 +274,16 @@ impl WhiteboxTool for Atan2 {
             output.add_metadata_entry(format!("Created by whitebox_tools\' {} tool", self.get_tool_name()));
             output.add_metadata_entry(format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
 
-            if verbose { println!("Saving data...") };
+            if verbose { sink.message("Saving data...") };
             let _ = match output.write() {
-                Ok(_) => if verbose { println!("Output file written") },
+                Ok(_) => if verbose { sink.message("Output file written") },
                 Err(e) => return Err(e),
             };
             if verbose {
-                println!("{}", &format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
+                sink.message(&format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
             }
         } else { // !input1_is_constant && !input2_is_constant
-            if verbose { println!("Reading data...") };
+            if verbose { sink.message("Reading data...") };
             let in1 = Arc::new(Raster::new(&input1, "r")?);
             let in2 = Arc::new(Raster::new(&input2, "r")?);
 
@@ -347,8 +311,8 @@ impl WhiteboxTool for Atan2 {
                     for row in (0..rows).filter(|r| r % num_procs == tid) {
                         let mut data: Vec<f64> = vec![nodata1; columns as usize];
                         for col in 0..columns {
-                            z1 = in1[(row, col)];
-                            z2 = in2[(row, col)];
+                            z1 = in1.get_value(row, col);
+                            z2 = in2.get_value(row, col);
                             if z1 != nodata1 && z2 != nodata2 {
                                 data[col as usize] = z1.atan2(z2);
                             } else {
@@ -365,12 +329,10 @@ impl WhiteboxTool for Atan2 {
                 let (row, data) = rx.recv().unwrap();
                 output.set_row_data(row, data);
                 
-                if verbose {
-                    progress = (100.0_f64 * r as f64 / (rows - 1) as f64) as usize;
-                    if progress != old_progress {
-                        println!("Progress: {}%", progress);
-                        old_progress = progress;
-                    }
+                progress = (100.0_f64 * r as f64 / (rows - 1) as f64) as usize;
+                if progress != old_progress {
+                    sink.progress("Progress", progress)?;
+                    old_progress = progress;
                 }
             }
 
@@ -379,13 +341,13 @@ impl WhiteboxTool for Atan2 {
             output.add_metadata_entry(format!("Created by whitebox_tools\' {} tool", self.get_tool_name()));
             output.add_metadata_entry(format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
 
-            if verbose { println!("Saving data...") };
+            if verbose { sink.message("Saving data...") };
             let _ = match output.write() {
-                Ok(_) => if verbose { println!("Output file written") },
+                Ok(_) => if verbose { sink.message("Output file written") },
                 Err(e) => return Err(e),
             };
             if verbose {
-                println!("{}", &format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
+                sink.message(&format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
             }
 
         }
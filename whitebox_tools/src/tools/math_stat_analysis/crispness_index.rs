@@ -35,6 +35,7 @@ use std::process::Command;
 use raster::*;
 use std::io::{Error, ErrorKind};
 use tools::*;
+use tools::arg_parser::ArgValues;
 
 pub struct CrispnessIndex {
     name: String,
@@ -134,53 +135,26 @@ impl WhiteboxTool for CrispnessIndex {
     fn run<'a>(&self,
                args: Vec<String>,
                working_directory: &'a str,
-               verbose: bool)
+               verbose: bool,
+               sink: &Arc<ProgressSink>)
                -> Result<(), Error> {
-        let mut input_file = String::new();
-        let mut output_file = String::new();
-
         if args.len() == 0 {
             return Err(Error::new(ErrorKind::InvalidInput,
                                   "Tool run with no paramters."));
         }
-        for i in 0..args.len() {
-            let mut arg = args[i].replace("\"", "");
-            arg = arg.replace("\'", "");
-            let cmd = arg.split("="); // in case an equals sign was used
-            let vec = cmd.collect::<Vec<&str>>();
-            let mut keyval = false;
-            if vec.len() > 1 {
-                keyval = true;
-            }
-            if vec[0].to_lowercase() == "-i" || vec[0].to_lowercase() == "--input" {
-                if keyval {
-                    input_file = vec[1].to_string();
-                } else {
-                    input_file = args[i + 1].to_string();
-                }
-            } else if vec[0].to_lowercase() == "-o" || vec[0].to_lowercase() == "--output" {
-                if keyval {
-                    output_file = vec[1].to_string();
-                } else {
-                    output_file = args[i + 1].to_string();
-                }
-            }
-        }
+        let args = ArgValues::parse(&self.parameters, &args)?;
+        let input_file = args.required_file("--input", working_directory)?;
+        let mut output_file = args.get_file("--output", working_directory)?.unwrap_or(String::new());
 
         if verbose {
-            println!("***************{}", "*".repeat(self.get_tool_name().len()));
-            println!("* Welcome to {} *", self.get_tool_name());
-            println!("***************{}", "*".repeat(self.get_tool_name().len()));
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
+            sink.message(&format!("* Welcome to {} *", self.get_tool_name()));
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
         }
 
-        let sep: String = path::MAIN_SEPARATOR.to_string();
-
         let mut progress: usize;
         let mut old_progress: usize = 1;
 
-        if !input_file.contains(&sep) && !input_file.contains("/") {
-            input_file = format!("{}{}", working_directory, input_file);
-        }
         if output_file.len() == 0 {
             // output_file not specified and should be based on input file
             let p = path::Path::new(&input_file);
@@ -189,12 +163,9 @@ impl WhiteboxTool for CrispnessIndex {
             extension.push_str(ext);
             output_file = input_file.replace(&extension, ".html");
         }
-        if !output_file.contains(&sep) && !output_file.contains("/") {
-            output_file = format!("{}{}", working_directory, output_file);
-        }
 
         if verbose {
-            println!("Reading data...")
+            sink.message("Reading data...")
         };
 
         let input = Arc::new(Raster::new(&input_file, "r")?);
@@ -220,7 +191,7 @@ impl WhiteboxTool for CrispnessIndex {
                     let mut s = 0.0;
                     let mut warning = false;
                     for col in 0..columns {
-                        z = input[(row, col)];
+                        z = input.get_value(row, col);
                         if z != nodata {
                             if z < 0f64 || z > 1f64 {
                                 warning = true;
@@ -245,12 +216,10 @@ impl WhiteboxTool for CrispnessIndex {
                 warning = true;
             }
 
-            if verbose {
-                progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as usize;
-                if progress != old_progress {
-                    println!("Progress (Loop 1 of 2): {}%", progress);
-                    old_progress = progress;
-                }
+            progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as usize;
+            if progress != old_progress {
+                sink.progress("Progress (Loop 1 of 2)", progress)?;
+                old_progress = progress;
             }
         }
 
@@ -265,7 +234,7 @@ impl WhiteboxTool for CrispnessIndex {
                 for row in (0..rows).filter(|r| r % num_procs == tid) {
                     let mut total_dev = 0f64;
                     for col in 0..columns {
-                        z = input[(row, col)];
+                        z = input.get_value(row, col);
                         if z != nodata {
                             total_dev += (z - mean) * (z - mean);
                         }
@@ -279,12 +248,10 @@ impl WhiteboxTool for CrispnessIndex {
         for row in 0..rows {
             total_dev += rx.recv().unwrap();
 
-            if verbose {
-                progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as usize;
-                if progress != old_progress {
-                    println!("Progress (Loop 2 of 2): {}%", progress);
-                    old_progress = progress;
-                }
+            progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as usize;
+            if progress != old_progress {
+                sink.progress("Progress (Loop 2 of 2)", progress)?;
+                old_progress = progress;
             }
         }
 
@@ -296,7 +263,7 @@ impl WhiteboxTool for CrispnessIndex {
         let elapsed_time = end - start;
 
         if warning {
-            println!("WARNING: This tool is intended to be applied to membership probability (MP) rasters, with probability values 
+            sink.warning("WARNING: This tool is intended to be applied to membership probability (MP) rasters, with probability values 
 ranging from 0-1. The input image contains values outside this range.");
         }
 
@@ -306,8 +273,7 @@ ranging from 0-1. The input image contains values outside this range.");
         // println!("SSb: {}", denominator);
         // println!("Crispness index: {}", crispness);
         if verbose {
-            println!("\n{}",
-                 &format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
+            sink.message(&format!("\n{}", &format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", "")));
         }
 
 
@@ -442,7 +408,7 @@ ranging from 0-1. The input image contains values outside this range. <em>Theref
                 let _ = output.stdout;
             }
             if verbose {
-                println!("Complete! Please see {} for output.", output_file);
+                sink.message(&format!("Complete! Please see {} for output.", output_file));
             }
         }
 
@@ -17,6 +17,7 @@ use std::thread;
 use raster::*;
 use std::io::{Error, ErrorKind};
 use tools::*;
+use tools::arg_parser::ArgValues;
 
 pub struct Divide {
     name: String,
@@ -36,7 +37,7 @@ impl Divide {
         let mut parameters = vec![];
         parameters.push(ToolParameter{
             name: "Input File Or Constant Value".to_owned(), 
-            flags: vec!["--input1".to_owned()], 
+            flags: vec!["--i1".to_owned(), "--input1".to_owned()], 
             description: "Input raster file or constant value.".to_owned(),
             parameter_type: ParameterType::ExistingFileOrFloat(ParameterFileType::Raster),
             default_value: None,
@@ -45,7 +46,7 @@ impl Divide {
 
         parameters.push(ToolParameter{
             name: "Input File Or Constant Value".to_owned(), 
-            flags: vec!["--input2".to_owned()], 
+            flags: vec!["--i2".to_owned(), "--input2".to_owned()], 
             description: "Input raster file or constant value.".to_owned(),
             parameter_type: ParameterType::ExistingFileOrFloat(ParameterFileType::Raster),
             default_value: None,
@@ -115,49 +116,20 @@ impl WhiteboxTool for Divide {
         self.toolbox.clone()
     }
 
-    fn run<'a>(&self, args: Vec<String>, working_directory: &'a str, verbose: bool) -> Result<(), Error> {
-        let mut input1 = String::new();
-        let mut input2 = String::new();
-        let mut output_file = String::new();
-         
+    fn run<'a>(&self, args: Vec<String>, working_directory: &'a str, verbose: bool, sink: &Arc<ProgressSink>) -> Result<(), Error> {
         if args.len() == 0 {
             return Err(Error::new(ErrorKind::InvalidInput,
                                 "Tool run with no paramters."));
         }
-        for i in 0..args.len() {
-            let mut arg = args[i].replace("\"", "");
-            arg = arg.replace("\'", "");
-            let cmd = arg.split("="); // in case an equals sign was used
-            let vec = cmd.collect::<Vec<&str>>();
-            let mut keyval = false;
-            if vec.len() > 1 {
-                keyval = true;
-            }
-            if vec[0].to_lowercase() == "-i1" || vec[0].to_lowercase() == "--input1" {
-                if keyval {
-                    input1 = vec[1].to_string();
-                } else {
-                    input1 = args[i+1].to_string();
-                }
-            } else if vec[0].to_lowercase() == "-i2" || vec[0].to_lowercase() == "--input2" {
-                if keyval {
-                    input2 = vec[1].to_string();
-                } else {
-                    input2 = args[i+1].to_string();
-                }
-            } else if vec[0].to_lowercase() == "-o" || vec[0].to_lowercase() == "--output" {
-                if keyval {
-                    output_file = vec[1].to_string();
-                } else {
-                    output_file = args[i+1].to_string();
-                }
-            }
-        }
+        let args = ArgValues::parse(&self.parameters, &args)?;
+        let mut input1 = args.required_string("--input1")?;
+        let mut input2 = args.required_string("--input2")?;
+        let output_file = args.required_file("--output", working_directory)?;
 
         if verbose {
-            println!("***************{}", "*".repeat(self.get_tool_name().len()));
-            println!("* Welcome to {} *", self.get_tool_name());
-            println!("***************{}", "*".repeat(self.get_tool_name().len()));
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
+            sink.message(&format!("* Welcome to {} *", self.get_tool_name()));
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
         }
 
         let sep: String = path::MAIN_SEPARATOR.to_string();
@@ -165,10 +137,6 @@ impl WhiteboxTool for Divide {
         let mut progress: usize;
         let mut old_progress: usize = 1;
 
-        if !output_file.contains(&sep) && !output_file.contains("/") {
-            output_file = format!("{}{}", working_directory, output_file);
-        }
-
         // Are either of the inputs constants?
         let mut input1_constant = f64::NEG_INFINITY;
         let input1_is_constant = match input1.parse::<f64>() {
@@ -202,13 +170,13 @@ impl WhiteboxTool for Divide {
             // return Err(Error::new(ErrorKind::InvalidInput,
             //                     "At least one of the inputs must be a raster."));
             if input2_constant != 0.0 {
-                println!("{}", input1_constant / input2_constant);
+                sink.message(&format!("{}", input1_constant / input2_constant));
             } else {
-                println!("Inf");
+                sink.message("Inf");
             }
             return Ok(());
         } else if input1_is_constant && !input2_is_constant {
-            if verbose { println!("Reading data...") };
+            if verbose { sink.message("Reading data...") };
             let in2 = Arc::new(Raster::new(&input2, "r")?);
 
             let start = time::now();
@@ -226,7 +194,7 @@ impl WhiteboxTool for Divide {
                     for row in (0..rows).filter(|r| r % num_procs == tid) {
                         let mut data: Vec<f64> = vec![nodata2; columns as usize];
                         for col in 0..columns {
-                            z2 = in2[(row, col)];
+                            z2 = in2.get_value(row, col);
                             if z2 != nodata2 {
                                 if z2 != 0.0 {
                                     data[col as usize] = input1_constant / z2;
@@ -247,12 +215,10 @@ impl WhiteboxTool for Divide {
                 let (row, data) = rx.recv().unwrap();
                 output.set_row_data(row, data);
                 
-                if verbose {
-                    progress = (100.0_f64 * r as f64 / (rows - 1) as f64) as usize;
-                    if progress != old_progress {
-                        println!("Progress: {}%", progress);
-                        old_progress = progress;
-                    }
+                progress = (100.0_f64 * r as f64 / (rows - 1) as f64) as usize;
+                if progress != old_progress {
+                    sink.progress("Progress", progress)?;
+                    old_progress = progress;
                 }
             }
 
@@ -261,16 +227,16 @@ impl WhiteboxTool for Divide {
             output.add_metadata_entry(format!("Created by whitebox_tools\' {} tool", self.get_tool_name()));
             output.add_metadata_entry(format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
 
-            if verbose { println!("Saving data...") };
+            if verbose { sink.message("Saving data...") };
             let _ = match output.write() {
-                Ok(_) => if verbose { println!("Output file written") },
+                Ok(_) => if verbose { sink.message("Output file written") },
                 Err(e) => return Err(e),
             };  
             if verbose {
-                println!("{}", &format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
+                sink.message(&format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
             }
         } else if !input1_is_constant && input2_is_constant {
-            if verbose { println!("Reading data...") };
+            if verbose { sink.message("Reading data...") };
             let in1 = Arc::new(Raster::new(&input1, "r")?);
             
             let start = time::now();
@@ -288,7 +254,7 @@ impl WhiteboxTool for Divide {
                     for row in (0..rows).filter(|r| r % num_procs == tid) {
                         let mut data: Vec<f64> = vec![nodata1; columns as usize];
                         for col in 0..columns {
-                            z1 = in1[(row, col)];
+                            z1 = in1.get_value(row, col);
                             if z1 != nodata1 {
                                 if input2_constant != 0.0 {
                                     data[col as usize] = z1 / input2_constant;
@@ -309,12 +275,10 @@ impl WhiteboxTool for Divide {
                 let (row, data) = rx.recv().unwrap();
                 output.set_row_data(row, data);
                 
-                if verbose {
-                    progress = (100.0_f64 * r as f64 / (rows - 1) as f64) as usize;
-                    if progress != old_progress {
-                        println!("Progress: {}%", progress);
-                        old_progress = progress;
-                    }
+                progress = (100.0_f64 * r as f64 / (rows - 1) as f64) as usize;
+                if progress != old_progress {
+                    sink.progress("Progress", progress)?;
+                    old_progress = progress;
                 }
             }
 
@@ -323,16 +287,16 @@ impl WhiteboxTool for Divide {
             output.add_metadata_entry(format!("Created by whitebox_tools\' {} tool", self.get_tool_name()));
             output.add_metadata_entry(format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
 
-            if verbose { println!("Saving data...") };
+            if verbose { sink.message("Saving data...") };
             let _ = match output.write() {
-                Ok(_) => if verbose { println!("Output file written") },
+                Ok(_) => if verbose { sink.message("Output file written") },
                 Err(e) => return Err(e),
             };
             if verbose {
-                println!("{}", &format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
+                sink.message(&format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
             }
         } else { // !input1_is_constant && !input2_is_constant
-            if verbose { println!("Reading data...") };
+            if verbose { sink.message("Reading data...") };
             let in1 = Arc::new(Raster::new(&input1, "r")?);
             let in2 = Arc::new(Raster::new(&input2, "r")?);
 
@@ -360,8 +324,8 @@ impl WhiteboxTool for Divide {
                     for row in (0..rows).filter(|r| r % num_procs == tid) {
                         let mut data: Vec<f64> = vec![nodata1; columns as usize];
                         for col in 0..columns {
-                            z1 = in1[(row, col)];
-                            z2 = in2[(row, col)];
+                            z1 = in1.get_value(row, col);
+                            z2 = in2.get_value(row, col);
                             if z1 != nodata1 && z2 != nodata2 {
                                 if z2 != 0.0 {
                                     data[col as usize] = z1 / z2;
@@ -382,12 +346,10 @@ impl WhiteboxTool for Divide {
                 let (row, data) = rx.recv().unwrap();
                 output.set_row_data(row, data);
                 
-                if verbose {
-                    progress = (100.0_f64 * r as f64 / (rows - 1) as f64) as usize;
-                    if progress != old_progress {
-                        println!("Progress: {}%", progress);
-                        old_progress = progress;
-                    }
+                progress = (100.0_f64 * r as f64 / (rows - 1) as f64) as usize;
+                if progress != old_progress {
+                    sink.progress("Progress", progress)?;
+                    old_progress = progress;
                 }
             }
 
@@ -396,13 +358,13 @@ impl WhiteboxTool for Divide {
             output.add_metadata_entry(format!("Created by whitebox_tools\' {} tool", self.get_tool_name()));
             output.add_metadata_entry(format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
 
-            if verbose { println!("Saving data...") };
+            if verbose { sink.message("Saving data...") };
             let _ = match output.write() {
-                Ok(_) => if verbose { println!("Output file written") },
+                Ok(_) => if verbose { sink.message("Output file written") },
                 Err(e) => return Err(e),
             };
             if verbose {
-                println!("{}", &format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
+                sink.message(&format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
             }
 
         }
@@ -26,6 +26,7 @@ use std::thread;
 use raster::*;
 use std::io::{Error, ErrorKind};
 use tools::*;
+use tools::arg_parser::ArgValues;
 
 pub struct ExtractRasterStatistics {
     name: String,
@@ -150,95 +151,35 @@ impl WhiteboxTool for ExtractRasterStatistics {
         self.toolbox.clone()
     }
 
-    fn run<'a>(&self, args: Vec<String>, working_directory: &'a str, verbose: bool) -> Result<(), Error> {
-        let mut input_file = String::new();
-        let mut features_file = String::new();
-        let mut output_file = String::new();
+    fn run<'a>(&self, args: Vec<String>, working_directory: &'a str, verbose: bool, sink: &Arc<ProgressSink>) -> Result<(), Error> {
         // let mut out_table = false;
-        let mut output_html_file = String::new();
-        let mut stat_type = String::from("average");
          
         if args.len() == 0 {
             return Err(Error::new(ErrorKind::InvalidInput,
                                 "Tool run with no paramters."));
         }
-        for i in 0..args.len() {
-            let mut arg = args[i].replace("\"", "");
-            arg = arg.replace("\'", "");
-            let cmd = arg.split("="); // in case an equals sign was used
-            let vec = cmd.collect::<Vec<&str>>();
-            let mut keyval = false;
-            if vec.len() > 1 {
-                keyval = true;
-            }
-            let flag_val = vec[0].to_lowercase().replace("--", "-");
-            if flag_val == "-i" || flag_val == "-input" {
-                if keyval {
-                    input_file = vec[1].to_string();
-                } else {
-                    input_file = args[i+1].to_string();
-                }
-            } else if flag_val == "-features" {
-                if keyval {
-                    features_file = vec[1].to_string();
-                } else {
-                    features_file = args[i+1].to_string();
-                }
-            } else if flag_val == "-o" || flag_val == "-output" {
-                if keyval {
-                    output_file = vec[1].to_string();
-                } else {
-                    output_file = args[i+1].to_string();
-                }
-            } else if flag_val == "-out_table" {
-                // out_table = true;
-                if keyval {
-                    output_html_file = vec[1].to_string();
-                } else {
-                    output_html_file = args[i+1].to_string();
-                }
-            } else if flag_val == "-stat" {
-                if keyval {
-                    stat_type = vec[1].to_string().to_lowercase();
-                } else {
-                    stat_type = args[i+1].to_string().to_lowercase();
-                }
-            }
-        }
+        let args = ArgValues::parse(&self.parameters, &args)?;
+        let input_file = args.required_file("--input", working_directory)?;
+        let features_file = args.required_file("--features", working_directory)?;
+        let output_file = args.get_file("--output", working_directory)?.unwrap_or(String::new());
+        let output_html_file = args.get_file("--out_table", working_directory)?.unwrap_or(String::new());
+        let stat_type = args.get_string("--stat")?.unwrap_or(String::from("average")).to_lowercase();
 
         if verbose {
-            println!("***************{}", "*".repeat(self.get_tool_name().len()));
-            println!("* Welcome to {} *", self.get_tool_name());
-            println!("***************{}", "*".repeat(self.get_tool_name().len()));
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
+            sink.message(&format!("* Welcome to {} *", self.get_tool_name()));
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
         }
 
-        let sep: String = path::MAIN_SEPARATOR.to_string();
-
         let mut progress: usize;
         let mut old_progress: usize = 1;
 
-        if !input_file.contains(&sep) && !input_file.contains("/") {
-            input_file = format!("{}{}", working_directory, input_file);
-        }
-        if !features_file.contains(&sep) && !features_file.contains("/") {
-            features_file = format!("{}{}", working_directory, features_file);
-        }
-        if !output_file.is_empty() {
-            if !output_file.contains(&sep) && !output_file.contains("/") {
-                output_file = format!("{}{}", working_directory, output_file);
-            }
-        }
-        if !output_html_file.is_empty() {
-            if !output_html_file.contains(&sep) {
-                output_html_file = format!("{}{}", working_directory, output_html_file);
-            }
-        }
         if output_file.is_empty() && output_html_file.is_empty() {
             return Err(Error::new(ErrorKind::InvalidInput,
                 "At least one of --output or --out_table must be specified."));
         }
 
-        if verbose { println!("Reading data...") };
+        if verbose { sink.message("Reading data...") };
         let input = Arc::new(Raster::new(&input_file, "r")?);
         let features = Arc::new(Raster::new(&features_file, "r")?);
 
@@ -285,12 +226,10 @@ impl WhiteboxTool for ExtractRasterStatistics {
             if min < min_id { min_id = min; }
             if max > max_id { max_id = max; }
             
-            if verbose {
-                progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as usize;
-                if progress != old_progress {
-                    println!("Progress (Loop 1 of 3): {}%", progress);
-                    old_progress = progress;
-                }
+            progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as usize;
+            if progress != old_progress {
+                sink.progress("Progress (Loop 1 of 3)", progress)?;
+                old_progress = progress;
             }
         }
 
@@ -328,12 +267,10 @@ impl WhiteboxTool for ExtractRasterStatistics {
                     }
                 }
             }
-            if verbose {
-                progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as usize;
-                if progress != old_progress {
-                    println!("Progress (Loop 2 of 3): {}%", progress);
-                    old_progress = progress;
-                }
+            progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as usize;
+            if progress != old_progress {
+                sink.progress("Progress (Loop 2 of 3)", progress)?;
+                old_progress = progress;
             }
         }
         
@@ -354,12 +291,10 @@ impl WhiteboxTool for ExtractRasterStatistics {
                             (val - features_average[id]);
                 }
             }
-            if verbose {
-                progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as usize;
-                if progress != old_progress {
-                    println!("Progress (Loop 3 of 3): {}%", progress);
-                    old_progress = progress;
-                }
+            progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as usize;
+            if progress != old_progress {
+                sink.progress("Progress (Loop 3 of 3)", progress)?;
+                old_progress = progress;
             }
         }
         
@@ -395,12 +330,10 @@ impl WhiteboxTool for ExtractRasterStatistics {
                         output.set_value(row, col, out_stat[id]);
                     }
                 }
-                if verbose {
-                    progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as usize;
-                    if progress != old_progress {
-                        println!("Output: {}%", progress);
-                        old_progress = progress;
-                    }
+                progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as usize;
+                if progress != old_progress {
+                    sink.progress("Output", progress)?;
+                    old_progress = progress;
                 }
             }
 
@@ -412,9 +345,9 @@ impl WhiteboxTool for ExtractRasterStatistics {
             output.add_metadata_entry(format!("Statistic: {}", stat_type));
             output.add_metadata_entry(format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
 
-            if verbose { println!("Saving data...") };
+            if verbose { sink.message("Saving data...") };
             let _ = match output.write() {
-                Ok(_) => if verbose { println!("Output file written") },
+                Ok(_) => if verbose { sink.message("Output file written") },
                 Err(e) => return Err(e),
             };
         }
@@ -555,14 +488,14 @@ impl WhiteboxTool for ExtractRasterStatistics {
                     let _ = output.stdout;
                 }
 
-                println!("Complete! Please see {} for output.", output_html_file);
+                sink.message(&format!("Complete! Please see {} for output.", output_html_file));
             }
 
         }
         if verbose {
             let end = time::now();
             let elapsed_time = end - start;
-            println!("{}", &format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
+            sink.message(&format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
         }
         
         Ok(())
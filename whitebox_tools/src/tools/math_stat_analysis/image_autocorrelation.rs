@@ -22,6 +22,7 @@ use std::process::Command;
 use raster::*;
 use std::io::{Error, ErrorKind};
 use tools::*;
+use tools::arg_parser::ArgValues;
 use self::statrs::distribution::{Normal, Univariate};
 
 pub struct ImageAutocorrelation {
@@ -134,51 +135,22 @@ impl WhiteboxTool for ImageAutocorrelation {
     fn run<'a>(&self,
                args: Vec<String>,
                working_directory: &'a str,
-               verbose: bool)
+               verbose: bool,
+               sink: &Arc<ProgressSink>)
                -> Result<(), Error> {
-        let mut input_files: String = String::new();
-        let mut output_file = String::new();
-        let mut contiguity = String::new();
-
         if args.len() == 0 {
             return Err(Error::new(ErrorKind::InvalidInput,
                                   "Tool run with no paramters."));
         }
-        for i in 0..args.len() {
-            let mut arg = args[i].replace("\"", "");
-            arg = arg.replace("\'", "");
-            let cmd = arg.split("="); // in case an equals sign was used
-            let vec = cmd.collect::<Vec<&str>>();
-            let mut keyval = false;
-            if vec.len() > 1 {
-                keyval = true;
-            }
-            let flag_val = vec[0].to_lowercase().replace("--", "-");
-            if flag_val == "-i" || flag_val == "-inputs" {
-                if keyval {
-                    input_files = vec[1].to_string();
-                } else {
-                    input_files = args[i+1].to_string();
-                }
-            } else if flag_val == "-o" || flag_val == "-output" {
-                if keyval {
-                    output_file = vec[1].to_string();
-                } else {
-                    output_file = args[i + 1].to_string();
-                }
-            } else if flag_val == "-contiguity" {
-                if keyval {
-                    contiguity = vec[1].to_string().to_lowercase();
-                } else {
-                    contiguity = args[i+1].to_string().to_lowercase();
-                }
-            }
-        }
+        let args = ArgValues::parse(&self.parameters, &args)?;
+        let input_files = args.required_string("--inputs")?;
+        let mut output_file = args.required_file("--output", working_directory)?;
+        let contiguity = args.get_string("--contiguity")?.unwrap_or(String::new()).to_lowercase();
 
         if verbose {
-            println!("***************{}", "*".repeat(self.get_tool_name().len()));
-            println!("* Welcome to {} *", self.get_tool_name());
-            println!("***************{}", "*".repeat(self.get_tool_name().len()));
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
+            sink.message(&format!("* Welcome to {} *", self.get_tool_name()));
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
         }
 
         let sep: String = path::MAIN_SEPARATOR.to_string();
@@ -215,9 +187,6 @@ impl WhiteboxTool for ImageAutocorrelation {
             extension.push_str(ext);
             output_file = files_vec[0].replace(&extension, ".html");
         }
-        if !output_file.contains(&sep) && !output_file.contains("/") {
-            output_file = format!("{}{}", working_directory, output_file);
-        }
 
         let mut file_names = vec![];
         for a in 0..files_vec.len() {
@@ -252,7 +221,7 @@ impl WhiteboxTool for ImageAutocorrelation {
         let mut p_value_r = vec![0f64; num_files];
         let mut rows: isize = 0;
         let mut columns: isize = 0;
-        if verbose { println!("Calculating image averages..."); }
+        if verbose { sink.message("Calculating image averages..."); }
         for a in 0..num_files {
             let value = &file_names[a]; //files_vec[a];
             let input_file = value.trim(); //.to_owned();
@@ -293,10 +262,10 @@ impl WhiteboxTool for ImageAutocorrelation {
                 image_totals[a] += total;
                 n[a] += image_n;
 
-                if verbose && num_procs > 1 {
+                if num_procs > 1 {
                     progress = (100.0_f64 * np as f64 / (num_procs - 1) as f64) as usize;
                     if progress != old_progress {
-                        println!("Progress (Loop 1 of 2): {}%", progress);
+                        sink.progress("Progress (Loop 1 of 2)", progress)?;
                         old_progress = progress;
                     }
                 }
@@ -336,12 +305,10 @@ impl WhiteboxTool for ImageAutocorrelation {
                         s2 += wij * wij;
                     }
                 }
-                if verbose {
-                    progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as usize;
-                    if progress != old_progress {
-                        println!("Progress (Loop 2 of 2): {}%", progress);
-                        old_progress = progress;
-                    }
+                progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as usize;
+                if progress != old_progress {
+                    sink.progress("Progress (Loop 2 of 2)", progress)?;
+                    old_progress = progress;
                 }
             }
             
@@ -367,12 +334,10 @@ impl WhiteboxTool for ImageAutocorrelation {
             z_r[a] = (i[a] - e_i[a]) / var_randomization[a].sqrt(); 
             p_value_r[a] = 2f64 * (1f64 - distribution.cdf(z_r[a].abs()));
                 
-            if verbose {
-                progress = (100.0_f64 * a as f64 / (num_files - 1) as f64) as usize;
-                if progress != old_progress {
-                    println!("Loop {} of {}: {}%", (a + 1), files_vec.len(), progress);
-                    old_progress = progress;
-                }
+            progress = (100.0_f64 * a as f64 / (num_files - 1) as f64) as usize;
+            if progress != old_progress {
+                sink.progress(&format!("Loop {} of {}", (a + 1), files_vec.len()), progress)?;
+                old_progress = progress;
             }
         }
 
@@ -381,8 +346,7 @@ impl WhiteboxTool for ImageAutocorrelation {
         let elapsed_time = end - start;
 
         
-        if verbose { println!("\n{}",
-                 &format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", "")); }
+        if verbose { sink.message(&format!("\n{}", &format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""))); }
 
         let f = File::create(output_file.clone())?;
         let mut writer = BufWriter::new(f);
@@ -489,7 +453,7 @@ impl WhiteboxTool for ImageAutocorrelation {
                 let _ = output.stdout;
             }
             if verbose {
-                println!("Complete! Please see {} for output.", output_file);
+                sink.message(&format!("Complete! Please see {} for output.", output_file));
             }
         }
 
@@ -21,6 +21,7 @@ use std::process::Command;
 use raster::*;
 use std::io::{Error, ErrorKind};
 use tools::*;
+use tools::arg_parser::ArgValues;
 
 pub struct ImageCorrelation {
     name: String,
@@ -119,43 +120,21 @@ impl WhiteboxTool for ImageCorrelation {
     fn run<'a>(&self,
                args: Vec<String>,
                working_directory: &'a str,
-               verbose: bool)
+               verbose: bool,
+               sink: &Arc<ProgressSink>)
                -> Result<(), Error> {
-        let mut input_files: String = String::new();
-        let mut output_file = String::new();
-
         if args.len() == 0 {
             return Err(Error::new(ErrorKind::InvalidInput,
                                   "Tool run with no paramters."));
         }
-        for i in 0..args.len() {
-            let mut arg = args[i].replace("\"", "");
-            arg = arg.replace("\'", "");
-            let cmd = arg.split("="); // in case an equals sign was used
-            let vec = cmd.collect::<Vec<&str>>();
-            let mut keyval = false;
-            if vec.len() > 1 {
-                keyval = true;
-            }
-            if vec[0].to_lowercase() == "-i" || vec[0].to_lowercase() == "--inputs" {
-                if keyval {
-                    input_files = vec[1].to_string();
-                } else {
-                    input_files = args[i+1].to_string();
-                }
-            } else if vec[0].to_lowercase() == "-o" || vec[0].to_lowercase() == "--output" {
-                if keyval {
-                    output_file = vec[1].to_string();
-                } else {
-                    output_file = args[i + 1].to_string();
-                }
-            }
-        }
+        let args = ArgValues::parse(&self.parameters, &args)?;
+        let input_files = args.required_string("--inputs")?;
+        let mut output_file = args.get_file("--output", working_directory)?.unwrap_or(String::new());
 
         if verbose {
-            println!("***************{}", "*".repeat(self.get_tool_name().len()));
-            println!("* Welcome to {} *", self.get_tool_name());
-            println!("***************{}", "*".repeat(self.get_tool_name().len()));
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
+            sink.message(&format!("* Welcome to {} *", self.get_tool_name()));
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
         }
 
         let sep: String = path::MAIN_SEPARATOR.to_string();
@@ -180,9 +159,6 @@ impl WhiteboxTool for ImageCorrelation {
             extension.push_str(ext);
             output_file = files_vec[0].replace(&extension, ".html");
         }
-        if !output_file.contains(&sep) && !output_file.contains("/") {
-            output_file = format!("{}{}", working_directory, output_file);
-        }
 
         let mut file_names = vec![];
         for a in 0..files_vec.len() {
@@ -207,7 +183,7 @@ impl WhiteboxTool for ImageCorrelation {
         let mut correlation_matrix = vec![vec![-99f64; num_files]; num_files];
         let mut rows: isize = 0;
         let mut columns: isize = 0;
-        if verbose { println!("Calculating image averages..."); }
+        if verbose { sink.message("Calculating image averages..."); }
         for a in 0..num_files {
             let value = &file_names[a]; //files_vec[a];
             let input_file = value.trim(); //.to_owned();
@@ -233,7 +209,7 @@ impl WhiteboxTool for ImageCorrelation {
                     let mut z: f64;
                     for row in (0..rows).filter(|r| r % num_procs == tid) {
                         for col in 0..columns {
-                            z = input[(row, col)];
+                            z = input.get_value(row, col);
                             if z != nodata {
                                 // image_totals[a] += z;
                                 // image_n[a] += 1f64;
@@ -252,17 +228,15 @@ impl WhiteboxTool for ImageCorrelation {
             }
             image_averages[a] = image_totals[a] / image_n[a];
             
-            if verbose {
-                progress = (100.0_f64 * a as f64 / (num_files - 1) as f64) as usize;
-                if progress != old_progress {
-                    println!("Calculating image averages ({} of {}): {}%", (a + 1), files_vec.len(), progress);
-                    old_progress = progress;
-                }
+            progress = (100.0_f64 * a as f64 / (num_files - 1) as f64) as usize;
+            if progress != old_progress {
+                sink.progress(&format!("Calculating image averages ({} of {})", (a + 1), files_vec.len()), progress)?;
+                old_progress = progress;
             }
         }
         let image_averages = Arc::new(image_averages);
 
-        if verbose { println!("Calculating the correlation matrix:"); }
+        if verbose { sink.message("Calculating the correlation matrix:"); }
         let mut i = 0;
         for a in 0..num_files {
             let value = &file_names[a];
@@ -289,8 +263,8 @@ impl WhiteboxTool for ImageCorrelation {
                             let mut total_product_deviations = 0f64;
                             for row in (0..rows).filter(|r| r % num_procs == tid) {
                                 for col in 0..columns {
-                                    z1 = image1[(row, col)];
-                                    z2 = image2[(row, col)];
+                                    z1 = image1.get_value(row, col);
+                                    z2 = image2.get_value(row, col);
                                     if z1 != nodata1 && z2 != nodata2 {
                                         image1_total_deviation += (z1 - image_averages[a]) * (z1 - image_averages[a]);
                                         image2_total_deviation += (z2 - image_averages[b]) * (z2 - image_averages[b]);
@@ -315,12 +289,10 @@ impl WhiteboxTool for ImageCorrelation {
             }
             i += 1;
 
-            if verbose {
-                progress = (100.0_f64 * a as f64 / (num_files - 1) as f64) as usize;
-                if progress != old_progress {
-                    println!("Calculating the correlation matrix ({} of {}): {}%", (a + 1), files_vec.len(), progress);
-                    old_progress = progress;
-                }
+            progress = (100.0_f64 * a as f64 / (num_files - 1) as f64) as usize;
+            if progress != old_progress {
+                sink.progress(&format!("Calculating the correlation matrix ({} of {})", (a + 1), files_vec.len()), progress)?;
+                old_progress = progress;
             }
         }
 
@@ -333,8 +305,7 @@ impl WhiteboxTool for ImageCorrelation {
         let elapsed_time = end - start;
 
         
-        if verbose { println!("\n{}",
-                 &format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", "")); }
+        if verbose { sink.message(&format!("\n{}", &format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""))); }
 
 
         let f = File::create(output_file.clone())?;
@@ -452,7 +423,7 @@ impl WhiteboxTool for ImageCorrelation {
                 let _ = output.stdout;
             }
             if verbose {
-                println!("Complete! Please see {} for output.", output_file);
+                sink.message(&format!("Complete! Please see {} for output.", output_file));
             }
         }
 
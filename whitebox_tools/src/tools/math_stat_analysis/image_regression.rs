@@ -23,6 +23,7 @@ use std::process::Command;
 use raster::*;
 use std::io::{Error, ErrorKind};
 use tools::*;
+use tools::arg_parser::ArgValues;
 use self::statrs::distribution::{FisherSnedecor, StudentsT, Univariate};
 
 pub struct ImageRegression {
@@ -146,77 +147,30 @@ impl WhiteboxTool for ImageRegression {
     fn run<'a>(&self,
                args: Vec<String>,
                working_directory: &'a str,
-               verbose: bool)
+               verbose: bool,
+               sink: &Arc<ProgressSink>)
                -> Result<(), Error> {
-        let mut input_file1 = String::new();
-        let mut input_file2 = String::new();
-        let mut output_file = String::new();
-        let mut residuals_file = String::new();
-        let mut standardize_residuals = false;
-        let mut output_residuals = false;
-
         if args.len() == 0 {
             return Err(Error::new(ErrorKind::InvalidInput,
                                   "Tool run with no paramters."));
         }
-        for i in 0..args.len() {
-            let mut arg = args[i].replace("\"", "");
-            arg = arg.replace("\'", "");
-            let cmd = arg.split("="); // in case an equals sign was used
-            let vec = cmd.collect::<Vec<&str>>();
-            let mut keyval = false;
-            if vec.len() > 1 {
-                keyval = true;
-            }
-            if vec[0].to_lowercase() == "-i1" || vec[0].to_lowercase() == "--i1" || vec[0].to_lowercase() == "--input1" {
-                if keyval {
-                    input_file1 = vec[1].to_string();
-                } else {
-                    input_file1 = args[i+1].to_string();
-                }
-            } else if vec[0].to_lowercase() == "-i2" || vec[0].to_lowercase() == "--i2" || vec[0].to_lowercase() == "--input2" {
-                if keyval {
-                    input_file2 = vec[1].to_string();
-                } else {
-                    input_file2 = args[i+1].to_string();
-                }
-            } else if vec[0].to_lowercase() == "-o" || vec[0].to_lowercase() == "--output" {
-                if keyval {
-                    output_file = vec[1].to_string();
-                } else {
-                    output_file = args[i + 1].to_string();
-                }
-            } else if vec[0].to_lowercase() == "-out_residuals" || vec[0].to_lowercase() == "--out_residuals" {
-                if keyval {
-                    residuals_file = vec[1].to_string();
-                } else {
-                    residuals_file = args[i + 1].to_string();
-                }
-                output_residuals = true;
-            } else if vec[0].to_lowercase() == "-standardize" || vec[0].to_lowercase() == "--standardize" {
-                standardize_residuals = true;
-            }
-        }
+        let args = ArgValues::parse(&self.parameters, &args)?;
+        let input_file1 = args.required_file("--input1", working_directory)?;
+        let input_file2 = args.required_file("--input2", working_directory)?;
+        let mut output_file = args.required_file("--output", working_directory)?;
+        let residuals_file = args.get_file("--out_residuals", working_directory)?.unwrap_or(String::new());
+        let output_residuals = !residuals_file.is_empty();
+        let standardize_residuals = args.get_bool("--standardize")?;
 
         if verbose {
-            println!("***************{}", "*".repeat(self.get_tool_name().len()));
-            println!("* Welcome to {} *", self.get_tool_name());
-            println!("***************{}", "*".repeat(self.get_tool_name().len()));
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
+            sink.message(&format!("* Welcome to {} *", self.get_tool_name()));
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
         }
 
-        let sep: String = path::MAIN_SEPARATOR.to_string();
-
         let mut progress: usize;
         let mut old_progress: usize = 1;
 
-        if !input_file1.contains(&sep) && !input_file1.contains("/") {
-            input_file1 = format!("{}{}", working_directory, input_file1);
-        }
-
-        if !input_file2.contains(&sep) && !input_file2.contains("/") {
-            input_file2 = format!("{}{}", working_directory, input_file2);
-        }
-
         if output_file.len() == 0 {
             // output_file not specified and should be based on input file
             let p = path::Path::new(&input_file1);
@@ -228,9 +182,6 @@ impl WhiteboxTool for ImageRegression {
         if !output_file.ends_with(".html") {
             output_file = output_file + ".html";
         }
-        if !output_file.contains(&sep) && !output_file.contains("/") {
-            output_file = format!("{}{}", working_directory, output_file);
-        }
 
         let input1 = Arc::new(Raster::new(&input_file1, "r")?);
         let rows = input1.configs.rows as isize;
@@ -245,7 +196,7 @@ impl WhiteboxTool for ImageRegression {
 
         let start = time::now();
         
-        if verbose { println!("Loop 1 of 2..."); }
+        if verbose { sink.message("Loop 1 of 2..."); }
 
         let num_procs = num_cpus::get() as isize;
         let (tx, rx) = mpsc::channel();
@@ -264,8 +215,8 @@ impl WhiteboxTool for ImageRegression {
                 let mut y: f64;
                 for row in (0..rows).filter(|r| r % num_procs == tid) {
                     for col in 0..columns {
-                        x = input1[(row, col)];
-                        y = input2[(row, col)];
+                        x = input1.get_value(row, col);
+                        y = input2.get_value(row, col);
                         if x != nodata1 && y != nodata2 {
                             sum_x += x;
                             sum_y += y;
@@ -303,7 +254,7 @@ impl WhiteboxTool for ImageRegression {
         let y_mean = sum_y / n;
         let x_mean = sum_x / n;
         
-        if verbose { println!("Loop 2 of 2..."); }
+        if verbose { sink.message("Loop 2 of 2..."); }
         let (tx, rx) = mpsc::channel();
         for tid in 0..num_procs {
             let input1 = input1.clone();
@@ -317,8 +268,8 @@ impl WhiteboxTool for ImageRegression {
                 let mut ss_error = 0f64;
                 for row in (0..rows).filter(|r| r % num_procs == tid) {
                     for col in 0..columns {
-                        x = input1[(row, col)];
-                        y = input2[(row, col)];
+                        x = input1.get_value(row, col);
+                        y = input2.get_value(row, col);
                         if x != nodata1 && y != nodata2 {
                             y_estimate = slope * x + intercept;
                             ss_error += (y - y_estimate) * (y - y_estimate);
@@ -362,9 +313,6 @@ impl WhiteboxTool for ImageRegression {
         let slope_pvalue =  2f64 * (1f64 - t.cdf(slope.abs() / slope_se));
         
         if output_residuals {
-            if !residuals_file.contains(&sep) {
-                residuals_file = format!("{}{}", working_directory, residuals_file);
-            }
             let (tx, rx) = mpsc::channel();
             for tid in 0..num_procs {
                 let input1 = input1.clone();
@@ -378,8 +326,8 @@ impl WhiteboxTool for ImageRegression {
                     for row in (0..rows).filter(|r| r % num_procs == tid) {
                         let mut data: Vec<f64> = vec![nodata1; columns as usize];
                         for col in 0..columns {
-                            x = input1[(row, col)];
-                            y = input2[(row, col)];
+                            x = input1.get_value(row, col);
+                            y = input2.get_value(row, col);
                             if x != nodata1 && y != nodata2 {
                                 y_estimate = slope * x + intercept;
                                 residual = if standardize_residuals { 
@@ -400,18 +348,16 @@ impl WhiteboxTool for ImageRegression {
                 let (row, data) = rx.recv().unwrap();
                 output.set_row_data(row, data);
                 
-                if verbose {
-                    progress = (100.0_f64 * r as f64 / (rows - 1) as f64) as usize;
-                    if progress != old_progress {
-                        println!("Creating residuals image: {}%", progress);
-                        old_progress = progress;
-                    }
+                progress = (100.0_f64 * r as f64 / (rows - 1) as f64) as usize;
+                if progress != old_progress {
+                    sink.progress("Creating residuals image", progress)?;
+                    old_progress = progress;
                 }
             }
             
-            if verbose { println!("Saving data...") };
+            if verbose { sink.message("Saving data...") };
             let _ = match output.write() {
-                Ok(_) => if verbose { println!("Output file written") },
+                Ok(_) => if verbose { sink.message("Output file written") },
                 Err(e) => return Err(e),
             };
         }
@@ -419,7 +365,7 @@ impl WhiteboxTool for ImageRegression {
         let end = time::now();
         let elapsed_time = end - start;
 
-        if verbose { println!("\n{}",  &format!("Elapsed Time: {}", elapsed_time).replace("PT", "")); }
+        if verbose { sink.message(&format!("\n{}", &format!("Elapsed Time: {}", elapsed_time).replace("PT", ""))); }
 
 
         let f = File::create(output_file.clone())?;
@@ -670,7 +616,7 @@ impl WhiteboxTool for ImageRegression {
                 let _ = output.stdout;
             }
             if verbose {
-                println!("Complete! Please see {} for output.", output_file);
+                sink.message(&format!("Complete! Please see {} for output.", output_file));
             }
         }
 
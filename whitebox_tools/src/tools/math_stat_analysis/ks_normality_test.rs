@@ -25,8 +25,10 @@ use std::fs::File;
 use std::io::prelude::*;
 use std::io::{Error, ErrorKind};
 use std::process::Command;
+use std::sync::Arc;
 use raster::*;
 use tools::*;
+use tools::arg_parser::ArgValues;
 use rendering::html::*;
 use rendering::Histogram;
 use self::rand::distributions::{IndependentSample, Range};
@@ -49,7 +51,7 @@ impl KSTestForNormality {
         let mut parameters = vec![];
         parameters.push(ToolParameter{
             name: "Input File".to_owned(), 
-            flags: vec!["-i".to_owned(), "--input".to_owned()], 
+            flags: vec!["-i".to_owned(), "--base".to_owned(), "--input".to_owned()], 
             description: "Input raster file.".to_owned(),
             parameter_type: ParameterType::ExistingFile(ParameterFileType::Raster),
             default_value: None,
@@ -135,61 +137,24 @@ impl WhiteboxTool for KSTestForNormality {
     fn run<'a>(&self,
                args: Vec<String>,
                working_directory: &'a str,
-               verbose: bool)
+               verbose: bool,
+               sink: &Arc<ProgressSink>)
                -> Result<(), Error> {
-        let mut input_file = String::new();
-        let mut output_file = String::new();
-        let mut num_samples = 0usize;
-        
         if args.len() == 0 {
             return Err(Error::new(ErrorKind::InvalidInput,
                                   "Tool run with no paramters."));
         }
-        for i in 0..args.len() {
-            let mut arg = args[i].replace("\"", "");
-            arg = arg.replace("\'", "");
-            let cmd = arg.split("="); // in case an equals sign was used
-            let vec = cmd.collect::<Vec<&str>>();
-            let mut keyval = false;
-            if vec.len() > 1 {
-                keyval = true;
-            }
-            let flag_val = vec[0].to_lowercase().replace("--", "-");
-            if flag_val == "-i" || flag_val == "-input" || flag_val == "-base" {
-                input_file = if keyval {
-                    vec[1].to_string()
-                } else {
-                    args[i + 1].to_string()
-                };
-            } else if flag_val == "-o" || flag_val == "-output" {
-                output_file = if keyval {
-                    vec[1].to_string()
-                } else {
-                    args[i + 1].to_string()
-                };
-            } else if flag_val == "-num_samples" {
-                num_samples = if keyval {
-                    vec[1].to_string().parse::<usize>().unwrap()
-                } else {
-                    args[i + 1].to_string().parse::<usize>().unwrap()
-                };
-            }
-        }
+        let args = ArgValues::parse(&self.parameters, &args)?;
+        let input_file = args.required_file("--input", working_directory)?;
+        let mut output_file = args.required_file("--output", working_directory)?;
+        let mut num_samples = args.get_usize("--num_samples")?.unwrap_or(0usize);
 
         if verbose {
-            println!("***************{}", "*".repeat(self.get_tool_name().len()));
-            println!("* Welcome to {} *", self.get_tool_name());
-            println!("***************{}", "*".repeat(self.get_tool_name().len()));
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
+            sink.message(&format!("* Welcome to {} *", self.get_tool_name()));
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
         }
 
-        let sep: String = path::MAIN_SEPARATOR.to_string();
-
-        if !input_file.contains(&sep) && !input_file.contains("/") {
-            input_file = format!("{}{}", working_directory, input_file);
-        }
-        if !output_file.contains(&sep) && !output_file.contains("/") {
-            output_file = format!("{}{}", working_directory, output_file);
-        }
         if !output_file.ends_with(".html") {
             output_file = output_file + ".html";
         }
@@ -243,12 +208,10 @@ impl WhiteboxTool for KSTestForNormality {
                         freq_data[bin_num] += 1;
                     }
                 }
-                if verbose {
-                    progress = (100.0_f64 * row as f64 / rows as f64) as i32;
-                    if progress != old_progress {
-                        println!("Progress: {}%", progress);
-                        old_progress = progress;
-                    }
+                progress = (100.0_f64 * row as f64 / rows as f64) as i32;
+                if progress != old_progress {
+                    sink.progress("Progress", progress as usize)?;
+                    old_progress = progress;
                 }
             }
 
@@ -261,12 +224,10 @@ impl WhiteboxTool for KSTestForNormality {
                         total_deviation += (z - mean) * (z - mean);
                     }
                 }
-                if verbose {
-                    progress = (100.0_f64 * row as f64 / rows as f64) as i32;
-                    if progress != old_progress {
-                        println!("Progress: {}%", progress);
-                        old_progress = progress;
-                    }
+                progress = (100.0_f64 * row as f64 / rows as f64) as i32;
+                if progress != old_progress {
+                    sink.progress("Progress", progress as usize)?;
+                    old_progress = progress;
                 }
             }
         } else {
@@ -298,12 +259,10 @@ impl WhiteboxTool for KSTestForNormality {
                     cell_index = row * columns + col;
                     sample_cells.push(cell_index);
 
-                    if verbose {
-                        progress = (100.0_f64 * sample_num as f64 / num_samples as f64) as i32;
-                        if progress != old_progress {
-                            println!("Progress: {}%", progress);
-                            old_progress = progress;
-                        }
+                    progress = (100.0_f64 * sample_num as f64 / num_samples as f64) as i32;
+                    if progress != old_progress {
+                        sink.progress("Progress", progress as usize)?;
+                        old_progress = progress;
                     }
                 }
             }
@@ -317,12 +276,10 @@ impl WhiteboxTool for KSTestForNormality {
                 z = input.get_value(row, col);
                 total_deviation += (z - mean) * (z - mean);
 
-                if verbose {
-                    progress = (100.0_f64 * sample_num as f64 / num_samples as f64) as i32;
-                    if progress != old_progress {
-                        println!("Progress: {}%", progress);
-                        old_progress = progress;
-                    }
+                progress = (100.0_f64 * sample_num as f64 / num_samples as f64) as i32;
+                if progress != old_progress {
+                    sink.progress("Progress", progress as usize)?;
+                    old_progress = progress;
                 }
             }
         }
@@ -449,14 +406,14 @@ impl WhiteboxTool for KSTestForNormality {
                 let _ = output.stdout;
             }
 
-            println!("Complete! Please see {} for output.", output_file);
+            sink.message(&format!("Complete! Please see {} for output.", output_file));
         }
         
         
         let end = time::now();
         let elapsed_time = end - start;
         if verbose {
-            println!("{}", &format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
+            sink.message(&format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
         }
         
         Ok(())
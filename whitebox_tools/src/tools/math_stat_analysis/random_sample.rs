@@ -21,8 +21,10 @@ use std::env;
 use std::path;
 use std::f64;
 use std::io::{Error, ErrorKind};
+use std::sync::Arc;
 use raster::*;
 use tools::*;
+use tools::arg_parser::ArgValues;
 use self::rand::distributions::{IndependentSample, Range};
 
 pub struct RandomSample {
@@ -44,7 +46,7 @@ impl RandomSample {
         let mut parameters = vec![];
         parameters.push(ToolParameter{
             name: "Input Base File".to_owned(), 
-            flags: vec!["-i".to_owned(), "--base".to_owned()], 
+            flags: vec!["-i".to_owned(), "--input".to_owned(), "--base".to_owned()], 
             description: "Input raster file.".to_owned(),
             parameter_type: ParameterType::ExistingFile(ParameterFileType::Raster),
             default_value: None,
@@ -129,60 +131,22 @@ impl WhiteboxTool for RandomSample {
     fn run<'a>(&self,
                args: Vec<String>,
                working_directory: &'a str,
-               verbose: bool)
+               verbose: bool,
+               sink: &Arc<ProgressSink>)
                -> Result<(), Error> {
-        let mut input_file = String::new();
-        let mut output_file = String::new();
-        let mut num_samples = 1000usize;
-        
         if args.len() == 0 {
             return Err(Error::new(ErrorKind::InvalidInput,
                                   "Tool run with no paramters."));
         }
-        for i in 0..args.len() {
-            let mut arg = args[i].replace("\"", "");
-            arg = arg.replace("\'", "");
-            let cmd = arg.split("="); // in case an equals sign was used
-            let vec = cmd.collect::<Vec<&str>>();
-            let mut keyval = false;
-            if vec.len() > 1 {
-                keyval = true;
-            }
-            let flag_val = vec[0].to_lowercase().replace("--", "-");
-            if flag_val == "-i" || flag_val == "-input" || flag_val == "-base" {
-                input_file = if keyval {
-                    vec[1].to_string()
-                } else {
-                    args[i + 1].to_string()
-                };
-            } else if flag_val == "-o" || flag_val == "-output" {
-                output_file = if keyval {
-                    vec[1].to_string()
-                } else {
-                    args[i + 1].to_string()
-                };
-            } else if flag_val == "-num_samples" {
-                num_samples = if keyval {
-                    vec[1].to_string().parse::<usize>().unwrap()
-                } else {
-                    args[i + 1].to_string().parse::<usize>().unwrap()
-                };
-            }
-        }
+        let args = ArgValues::parse(&self.parameters, &args)?;
+        let input_file = args.required_file("--base", working_directory)?;
+        let output_file = args.required_file("--output", working_directory)?;
+        let num_samples = args.get_usize("--num_samples")?.unwrap_or(1000usize);
 
         if verbose {
-            println!("***************{}", "*".repeat(self.get_tool_name().len()));
-            println!("* Welcome to {} *", self.get_tool_name());
-            println!("***************{}", "*".repeat(self.get_tool_name().len()));
-        }
-
-        let sep: String = path::MAIN_SEPARATOR.to_string();
-
-        if !input_file.contains(&sep) && !input_file.contains("/") {
-            input_file = format!("{}{}", working_directory, input_file);
-        }
-        if !output_file.contains(&sep) && !output_file.contains("/") {
-            output_file = format!("{}{}", working_directory, output_file);
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
+            sink.message(&format!("* Welcome to {} *", self.get_tool_name()));
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
         }
 
         let input = Raster::new(&input_file, "r")?;
@@ -216,17 +180,15 @@ impl WhiteboxTool for RandomSample {
                 sample_num += 1;
                 output.set_value(row, col, sample_num as f64);
 
-                if verbose {
-                    progress = (100.0_f64 * sample_num as f64 / num_samples as f64) as i32;
-                    if progress != old_progress {
-                        println!("Progress: {}%", progress);
-                        old_progress = progress;
-                    }
+                progress = (100.0_f64 * sample_num as f64 / num_samples as f64) as i32;
+                if progress != old_progress {
+                    sink.progress("Progress", progress as usize)?;
+                    old_progress = progress;
                 }
             }
             num_tries += 1;
             if num_tries >= 20 * num_samples {
-                println!("Warning: num_samples may be too large for the size of grid.");
+                sink.warning("Warning: num_samples may be too large for the size of grid.");
                 break;
             }
         }
@@ -244,18 +206,18 @@ impl WhiteboxTool for RandomSample {
                                       .replace("PT", ""));
 
         if verbose {
-            println!("Saving data...")
+            sink.message("Saving data...")
         };
         let _ = match output.write() {
             Ok(_) => {
                 if verbose {
-                    println!("Output file written")
+                    sink.message("Output file written")
                 }
             },
             Err(e) => return Err(e),
         };
         if verbose {
-            println!("{}", &format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
+            sink.message(&format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
         }
 
         Ok(())
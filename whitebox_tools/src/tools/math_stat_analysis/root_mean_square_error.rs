@@ -17,6 +17,7 @@ use std::thread;
 use raster::*;
 use std::io::{Error, ErrorKind};
 use tools::*;
+use tools::arg_parser::ArgValues;
 
 pub struct RootMeanSquareError {
     name: String,
@@ -105,57 +106,25 @@ impl WhiteboxTool for RootMeanSquareError {
         self.toolbox.clone()
     }
 
-    fn run<'a>(&self, args: Vec<String>, working_directory: &'a str, verbose: bool) -> Result<(), Error> {
-        let mut input_file = String::new();
-        let mut base_file = String::new();
-         
+    fn run<'a>(&self, args: Vec<String>, working_directory: &'a str, verbose: bool, sink: &Arc<ProgressSink>) -> Result<(), Error> {
         if args.len() == 0 {
             return Err(Error::new(ErrorKind::InvalidInput,
                                 "Tool run with no paramters."));
         }
-        for i in 0..args.len() {
-            let mut arg = args[i].replace("\"", "");
-            arg = arg.replace("\'", "");
-            let cmd = arg.split("="); // in case an equals sign was used
-            let vec = cmd.collect::<Vec<&str>>();
-            let mut keyval = false;
-            if vec.len() > 1 {
-                keyval = true;
-            }
-            if vec[0].to_lowercase() == "-i" || vec[0].to_lowercase() == "--input" {
-                if keyval {
-                    input_file = vec[1].to_string();
-                } else {
-                    input_file = args[i+1].to_string();
-                }
-            } else if vec[0].to_lowercase() == "-base" || vec[0].to_lowercase() == "--base" {
-                if keyval {
-                    base_file = vec[1].to_string();
-                } else {
-                    base_file = args[i+1].to_string();
-                }
-            }
-        }
+        let args = ArgValues::parse(&self.parameters, &args)?;
+        let input_file = args.required_file("--input", working_directory)?;
+        let base_file = args.required_file("--base", working_directory)?;
 
         if verbose {
-            println!("***************{}", "*".repeat(self.get_tool_name().len()));
-            println!("* Welcome to {} *", self.get_tool_name());
-            println!("***************{}", "*".repeat(self.get_tool_name().len()));
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
+            sink.message(&format!("* Welcome to {} *", self.get_tool_name()));
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
         }
 
-        let sep: String = path::MAIN_SEPARATOR.to_string();
-
         let mut progress: usize;
         let mut old_progress: usize = 1;
 
-        if !input_file.contains(&sep) && !input_file.contains("/") {
-            input_file = format!("{}{}", working_directory, input_file);
-        }
-        if !base_file.contains(&sep) && !base_file.contains("/") {
-            base_file = format!("{}{}", working_directory, base_file);
-        }
-
-        if verbose { println!("Reading data...") };
+        if verbose { sink.message("Reading data...") };
 
         let input = Arc::new(Raster::new(&input_file, "r")?);
         let base_raster = Arc::new(Raster::new(&base_file, "r")?);
@@ -182,8 +151,8 @@ impl WhiteboxTool for RootMeanSquareError {
                         let mut s = 0.0f64;
                         let mut sq = 0.0f64;
                         for col in 0..columns {
-                            z1 = input[(row, col)];
-                            z2 = base_raster[(row, col)];
+                            z1 = input.get_value(row, col);
+                            z2 = base_raster.get_value(row, col);
                             if z1 != nodata && z2 != nodata_base {
                                 n += 1;
                                 s += z1 - z2;
@@ -204,24 +173,22 @@ impl WhiteboxTool for RootMeanSquareError {
                 sum += b;
                 sq_sum += c;
 
-                if verbose {
-                    progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as usize;
-                    if progress != old_progress {
-                        println!("Progress: {}%", progress);
-                        old_progress = progress;
-                    }
+                progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as usize;
+                if progress != old_progress {
+                    sink.progress("Progress", progress)?;
+                    old_progress = progress;
                 }
             }
 
             let rmse = (sq_sum / num_cells as f64).sqrt();
 			let mean_vertical_error = sum / num_cells as f64;
 
-            println!("\nVertical Accuracy Analysis:\n");
-			println!("Comparison File: {}", input_file);
-			println!("Base File: {}", base_file);
-			println!("Mean vertical error: {:.4}", mean_vertical_error); 
-			println!("RMSE: {:.4}", rmse);
-			println!("Accuracy at 95% confidence limit (m): {:.4}", rmse * 1.96f64);
+            sink.message("\nVertical Accuracy Analysis:\n");
+			sink.message(&format!("Comparison File: {}", input_file));
+			sink.message(&format!("Base File: {}", base_file));
+			sink.message(&format!("Mean vertical error: {:.4}", mean_vertical_error)); 
+			sink.message(&format!("RMSE: {:.4}", rmse));
+			sink.message(&format!("Accuracy at 95% confidence limit (m): {:.4}", rmse * 1.96f64));
 
         } else {
             /* The two grids are not of the same resolution. Bilinear resampling will have to be 
@@ -258,7 +225,7 @@ impl WhiteboxTool for RootMeanSquareError {
                         let mut s = 0.0f64;
                         let mut sq = 0.0f64;
                         for col in 0..columns {
-                            z1 = input[(row, col)];
+                            z1 = input.get_value(row, col);
                             if z1 != nodata {
                                 x = input.get_x_from_column(col);
                                 src_row = (src_north - y) / src_resolution_y;
@@ -268,10 +235,10 @@ impl WhiteboxTool for RootMeanSquareError {
                                 dx = src_col - src_col.floor();
                                 dy = src_row - src_row.floor();
 
-                                n0 = base_raster[(origin_row, origin_col)];
-                                n1 = base_raster[(origin_row, origin_col + 1)];
-                                n2 = base_raster[(origin_row + 1, origin_col)];
-                                n3 = base_raster[(origin_row + 1, origin_col+ 1)];
+                                n0 = base_raster.get_value(origin_row, origin_col);
+                                n1 = base_raster.get_value(origin_row, origin_col + 1);
+                                n2 = base_raster.get_value(origin_row + 1, origin_col);
+                                n3 = base_raster.get_value(origin_row + 1, origin_col+ 1);
 
                                 if n0 != nodata_base && n1 != nodata_base && n2 != nodata_base && n3 != nodata_base { 
                                     // This is the bilinear interpolation equation.
@@ -311,31 +278,29 @@ impl WhiteboxTool for RootMeanSquareError {
                 sum += b;
                 sq_sum += c;
 
-                if verbose {
-                    progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as usize;
-                    if progress != old_progress {
-                        println!("Progress: {}%", progress);
-                        old_progress = progress;
-                    }
+                progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as usize;
+                if progress != old_progress {
+                    sink.progress("Progress", progress)?;
+                    old_progress = progress;
                 }
             }
 
             let rmse = (sq_sum / num_cells as f64).sqrt();
 			let mean_vertical_error = sum / num_cells as f64;
 
-            println!("\nVertical Accuracy Analysis:\n");
-			println!("Comparison File: {}", input_file);
-			println!("Base File: {}", base_file);
-			println!("Mean vertical error: {:.4}", mean_vertical_error); 
-			println!("RMSE: {:.4}", rmse);
-			println!("Accuracy at 95% confidence limit (m): {:.4}", rmse * 1.96f64);
+            sink.message("\nVertical Accuracy Analysis:\n");
+			sink.message(&format!("Comparison File: {}", input_file));
+			sink.message(&format!("Base File: {}", base_file));
+			sink.message(&format!("Mean vertical error: {:.4}", mean_vertical_error)); 
+			sink.message(&format!("RMSE: {:.4}", rmse));
+			sink.message(&format!("Accuracy at 95% confidence limit (m): {:.4}", rmse * 1.96f64));
 
         }
 
         let end = time::now();
         let elapsed_time = end - start;
         if verbose {
-            println!("\n{}", &format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
+            sink.message(&format!("\n{}", &format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", "")));
         }
 
         Ok(())
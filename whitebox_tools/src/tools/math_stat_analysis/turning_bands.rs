@@ -18,6 +18,7 @@ use std::sync::mpsc;
 use std::thread;
 use raster::*;
 use tools::*;
+use tools::arg_parser::ArgValues;
 use self::rand::distributions::{Normal, IndependentSample, Range};
 
 pub struct TurningBandsSimulation {
@@ -39,7 +40,7 @@ impl TurningBandsSimulation {
         let mut parameters = vec![];
         parameters.push(ToolParameter{
             name: "Input Base File".to_owned(), 
-            flags: vec!["-i".to_owned(), "--base".to_owned()], 
+            flags: vec!["-i".to_owned(), "--input".to_owned(), "--base".to_owned()], 
             description: "Input base raster file.".to_owned(),
             parameter_type: ParameterType::ExistingFile(ParameterFileType::Raster),
             default_value: None,
@@ -133,66 +134,23 @@ impl WhiteboxTool for TurningBandsSimulation {
     fn run<'a>(&self,
                args: Vec<String>,
                working_directory: &'a str,
-               verbose: bool)
+               verbose: bool,
+               sink: &Arc<ProgressSink>)
                -> Result<(), Error> {
-        let mut input_file = String::new();
-        let mut output_file = String::new();
-        let mut range = 1f64;
-        let mut iterations = 1000;
-        
         if args.len() == 0 {
             return Err(Error::new(ErrorKind::InvalidInput,
                                   "Tool run with no paramters."));
         }
-        for i in 0..args.len() {
-            let mut arg = args[i].replace("\"", "");
-            arg = arg.replace("\'", "");
-            let cmd = arg.split("="); // in case an equals sign was used
-            let vec = cmd.collect::<Vec<&str>>();
-            let mut keyval = false;
-            if vec.len() > 1 {
-                keyval = true;
-            }
-            if vec[0].to_lowercase() == "-i" || vec[0].to_lowercase() == "--input" || vec[0].to_lowercase() == "--base" {
-                if keyval {
-                    input_file = vec[1].to_string();
-                } else {
-                    input_file = args[i + 1].to_string();
-                }
-            } else if vec[0].to_lowercase() == "-o" || vec[0].to_lowercase() == "--output" {
-                if keyval {
-                    output_file = vec[1].to_string();
-                } else {
-                    output_file = args[i + 1].to_string();
-                }
-            } else if vec[0].to_lowercase() == "-range" || vec[0].to_lowercase() == "--range" {
-                if keyval {
-                    range = vec[1].to_string().parse::<f64>().unwrap();
-                } else {
-                    range = args[i+1].to_string().parse::<f64>().unwrap();
-                }
-            } else if vec[0].to_lowercase() == "-iterations" || vec[0].to_lowercase() == "--iterations" {
-                if keyval {
-                    iterations = vec[1].to_string().parse::<usize>().unwrap();
-                } else {
-                    iterations = args[i+1].to_string().parse::<usize>().unwrap();
-                }
-            }
-        }
+        let args = ArgValues::parse(&self.parameters, &args)?;
+        let input_file = args.required_file("--base", working_directory)?;
+        let output_file = args.required_file("--output", working_directory)?;
+        let range = args.get_f64("--range")?.unwrap_or(1f64);
+        let iterations = args.get_usize("--iterations")?.unwrap_or(1000);
 
         if verbose {
-            println!("***************{}", "*".repeat(self.get_tool_name().len()));
-            println!("* Welcome to {} *", self.get_tool_name());
-            println!("***************{}", "*".repeat(self.get_tool_name().len()));
-        }
-
-        let sep: String = path::MAIN_SEPARATOR.to_string();
-
-        if !input_file.contains(&sep) && !input_file.contains("/") {
-            input_file = format!("{}{}", working_directory, input_file);
-        }
-        if !output_file.contains(&sep) && !output_file.contains("/") {
-            output_file = format!("{}{}", working_directory, output_file);
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
+            sink.message(&format!("* Welcome to {} *", self.get_tool_name()));
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
         }
 
         let input = Raster::new(&input_file, "r")?;
@@ -420,12 +378,10 @@ impl WhiteboxTool for TurningBandsSimulation {
                 output.increment_row_data(row, data);
             }
 
-            if verbose {
-                progress = (100.0_f64 * i as f64 / (iterations - 1) as f64) as i32;
-                if progress != old_progress {
-                    println!("Progress (Loop 1 of 2): {}%", progress);
-                    old_progress = progress;
-                }
+            progress = (100.0_f64 * i as f64 / (iterations - 1) as f64) as i32;
+            if progress != old_progress {
+                sink.progress("Progress (Loop 1 of 2)", progress as usize)?;
+                old_progress = progress;
             }
 
         }
@@ -434,15 +390,13 @@ impl WhiteboxTool for TurningBandsSimulation {
         for row in 0..rows {
             for col in 0..columns {
                 // output[(row, col)] = (output[(row, col)] - mean) / stdev; // / iterations as f64;
-                output[(row, col)] = output[(row, col)] / iterations_rooted;
+                output.set_value(row, col, output.get_value(row, col) / iterations_rooted);
             }
 
-            if verbose {
-                progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as i32;
-                if progress != old_progress {
-                    println!("Progress (Loop 2 of 2): {}%", progress);
-                    old_progress = progress;
-                }
+            progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as i32;
+            if progress != old_progress {
+                sink.progress("Progress (Loop 2 of 2)", progress as usize)?;
+                old_progress = progress;
             }
         }
 
@@ -460,19 +414,18 @@ impl WhiteboxTool for TurningBandsSimulation {
                                       .replace("PT", ""));
 
         if verbose {
-            println!("Saving data...")
+            sink.message("Saving data...")
         };
         let _ = match output.write() {
             Ok(_) => {
                 if verbose {
-                    println!("Output file written")
+                    sink.message("Output file written")
                 }
             }
             Err(e) => return Err(e),
         };
         if verbose {
-            println!("{}",
-                 &format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
+            sink.message(&format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
         }
 
         Ok(())
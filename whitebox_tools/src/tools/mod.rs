@@ -6,17 +6,25 @@ pub mod lidar_analysis;
 pub mod math_stat_analysis;
 pub mod stream_network_analysis;
 pub mod terrain_analysis;
+mod arg_parser;
+mod catalog;
+mod progress;
+mod workflow;
+
+pub use self::progress::{CancellationToken, JsonProgress, ProgressSink, StdoutProgress};
+pub use self::arg_parser::{ParameterValue, ToolArgs, ToolError, ToolOutput};
 
 use serde_json;
 // use serde_json::Value;
 use tools;
 use std::io::{Error, ErrorKind};
+use std::sync::Arc;
 
-#[derive(Default)]
 pub struct ToolManager {
     pub working_dir: String,
     pub verbose: bool,
     tool_names: Vec<String>,
+    sink: Arc<ProgressSink>,
 }
 
 impl ToolManager {
@@ -29,6 +37,7 @@ impl ToolManager {
         tool_names.push("ConvertRasterFormat".to_string());
         tool_names.push("NewRasterFromBase".to_string());
         tool_names.push("PrintGeoTiffTags".to_string());
+        tool_names.push("ReprojectRaster".to_string());
         tool_names.push("SetNodataValue".to_string());
 
         // gis_analysis
@@ -51,15 +60,19 @@ impl ToolManager {
         tool_names.push("MaxOverlay".to_string());
         tool_names.push("MinAbsoluteOverlay".to_string());
         tool_names.push("MinOverlay".to_string());
+        tool_names.push("OrdinaryKriging".to_string());
         tool_names.push("PercentEqualTo".to_string());
         tool_names.push("PercentGreaterThan".to_string());
         tool_names.push("PercentLessThan".to_string());
         tool_names.push("PickFromList".to_string());
         tool_names.push("RadiusOfGyration".to_string());
         tool_names.push("RasterCellAssignment".to_string());
+        tool_names.push("RasterToVectorPolygons".to_string());
         tool_names.push("Reclass".to_string());
         tool_names.push("ReclassEqualInterval".to_string());
         tool_names.push("ReclassFromFile".to_string());
+        tool_names.push("UniversalKriging".to_string());
+        tool_names.push("Variogram".to_string());
         tool_names.push("WeightedSum".to_string());
 
         // hydro_analysis
@@ -181,9 +194,11 @@ impl ToolManager {
         tool_names.push("LidarRemoveOutliers".to_string());
         tool_names.push("LidarSegmentation".to_string());
         tool_names.push("LidarSegmentationBasedFilter".to_string());
+        tool_names.push("LidarTINGridding".to_string());
         tool_names.push("LidarTile".to_string());
         tool_names.push("LidarTophatTransform".to_string());
         tool_names.push("NormalVectors".to_string());
+        tool_names.push("ReprojectLidar".to_string());
         
         // mathematical and statistical_analysis
         tool_names.push("AbsoluteValue".to_string());
@@ -263,6 +278,7 @@ impl ToolManager {
         tool_names.push("LengthOfUpstreamChannels".to_string());
         tool_names.push("LongProfile".to_string());
         tool_names.push("LongProfileFromPoints".to_string());
+        tool_names.push("RasterStreamsToVector".to_string());
         tool_names.push("RasterizeStreams".to_string());
         tool_names.push("RemoveShortStreams".to_string());
         tool_names.push("ShreveStreamMagnitude".to_string());
@@ -277,6 +293,8 @@ impl ToolManager {
 
         // terrain_analysis
         tool_names.push("Aspect".to_string());
+        tool_names.push("ContoursFromPoints".to_string());
+        tool_names.push("ContoursFromRaster".to_string());
         tool_names.push("FeaturePreservingDenoise".to_string());
         tool_names.push("DevFromMeanElev".to_string());
         tool_names.push("DiffFromMeanElev".to_string());
@@ -323,14 +341,28 @@ impl ToolManager {
 
         tool_names.sort();
 
+        let mut sink = StdoutProgress::new();
+        sink.set_quiet(!*verbose_mode);
         let tm = ToolManager {
             working_dir: working_directory.to_string(),
             verbose: *verbose_mode,
             tool_names: tool_names,
+            sink: Arc::new(sink),
         };
         Ok(tm)
     }
 
+    /// Sets the sink that receives the progress, messages, and warnings of the tools that
+    /// are run, e.g. a `JsonProgress` sink. Tools report progress, and check for cancellation,
+    /// in both verbose and non-verbose mode; messages are only reported in verbose mode.
+    pub fn set_progress_sink(&mut self, sink: Arc<ProgressSink>) {
+        self.sink = sink;
+    }
+
+    pub fn get_progress_sink(&self) -> Arc<ProgressSink> {
+        self.sink.clone()
+    }
+
     fn get_tool(&self, tool_name: &str) -> Option<Box<WhiteboxTool + 'static>> {
         match tool_name.to_lowercase().replace("_", "").as_ref() {
             // data_tools
@@ -338,6 +370,7 @@ impl ToolManager {
             "convertrasterformat" => Some(Box::new(tools::data_tools::ConvertRasterFormat::new())),
             "newrasterfrombase" => Some(Box::new(tools::data_tools::NewRasterFromBase::new())),
             "printgeotifftags" => Some(Box::new(tools::data_tools::PrintGeoTiffTags::new())),
+            "reprojectraster" => Some(Box::new(tools::data_tools::ReprojectRaster::new())),
             "setnodatavalue" => Some(Box::new(tools::data_tools::SetNodataValue::new())),
 
             // gis_analysis
@@ -362,17 +395,21 @@ impl ToolManager {
             "maxoverlay" => Some(Box::new(tools::gis_analysis::MaxOverlay::new())),
             "minabsoluteoverlay" => Some(Box::new(tools::gis_analysis::MinAbsoluteOverlay::new())),
             "minoverlay" => Some(Box::new(tools::gis_analysis::MinOverlay::new())),
+            "ordinarykriging" => Some(Box::new(tools::gis_analysis::OrdinaryKriging::new())),
             "percentequalto" => Some(Box::new(tools::gis_analysis::PercentEqualTo::new())),
             "percentgreaterthan" => Some(Box::new(tools::gis_analysis::PercentGreaterThan::new())),
             "percentlessthan" => Some(Box::new(tools::gis_analysis::PercentLessThan::new())),
             "pickfromlist" => Some(Box::new(tools::gis_analysis::PickFromList::new())),
             "radiusofgyration" => Some(Box::new(tools::gis_analysis::RadiusOfGyration::new())),
             "rastercellassignment" => Some(Box::new(tools::gis_analysis::RasterCellAssignment::new())),
+            "rastertovectorpolygons" => Some(Box::new(tools::gis_analysis::RasterToVectorPolygons::new())),
             "reclass" => Some(Box::new(tools::gis_analysis::Reclass::new())),
             "reclassequalinterval" => {
                 Some(Box::new(tools::gis_analysis::ReclassEqualInterval::new()))
             }
             "reclassfromfile" => Some(Box::new(tools::gis_analysis::ReclassFromFile::new())),
+            "universalkriging" => Some(Box::new(tools::gis_analysis::UniversalKriging::new())),
+            "variogram" => Some(Box::new(tools::gis_analysis::Variogram::new())),
             "weightedsum" => Some(Box::new(tools::gis_analysis::WeightedSum::new())),
 
 
@@ -540,11 +577,13 @@ impl ToolManager {
             "lidarremoveoutliers" => Some(Box::new(tools::lidar_analysis::LidarRemoveOutliers::new())),
             "lidarsegmentation" => Some(Box::new(tools::lidar_analysis::LidarSegmentation::new())),
             "lidarsegmentationbasedfilter" => Some(Box::new(tools::lidar_analysis::LidarSegmentationBasedFilter::new())),
+            "lidartingridding" => Some(Box::new(tools::lidar_analysis::LidarTINGridding::new())),
             "lidartile" => Some(Box::new(tools::lidar_analysis::LidarTile::new())),
             "lidartophattransform" => {
                 Some(Box::new(tools::lidar_analysis::LidarTophatTransform::new()))
             }
             "normalvectors" => Some(Box::new(tools::lidar_analysis::NormalVectors::new())),
+            "reprojectlidar" => Some(Box::new(tools::lidar_analysis::ReprojectLidar::new())),
             
             // mathematical and statistical_analysis
             "absolutevalue" => Some(Box::new(tools::math_stat_analysis::AbsoluteValue::new())),
@@ -636,6 +675,7 @@ impl ToolManager {
             }
             "longprofile" => Some(Box::new(tools::stream_network_analysis::LongProfile::new())),
             "longprofilefrompoints" => Some(Box::new(tools::stream_network_analysis::LongProfileFromPoints::new())),
+            "rasterstreamstovector" => Some(Box::new(tools::stream_network_analysis::RasterStreamsToVector::new())),
             "rasterizestreams" => Some(Box::new(tools::stream_network_analysis::RasterizeStreams::new())),
             "removeshortstreams" => {
                 Some(Box::new(tools::stream_network_analysis::RemoveShortStreams::new()))
@@ -670,6 +710,8 @@ impl ToolManager {
 
             // terrain_analysis
             "aspect" => Some(Box::new(tools::terrain_analysis::Aspect::new())),
+            "contoursfrompoints" => Some(Box::new(tools::terrain_analysis::ContoursFromPoints::new())),
+            "contoursfromraster" => Some(Box::new(tools::terrain_analysis::ContoursFromRaster::new())),
             "featurepreservingdenoise" => Some(Box::new(tools::terrain_analysis::FeaturePreservingDenoise::new())),
             "devfrommeanelev" => Some(Box::new(tools::terrain_analysis::DevFromMeanElev::new())),
             "difffrommeanelev" => Some(Box::new(tools::terrain_analysis::DiffFromMeanElev::new())),
@@ -734,13 +776,24 @@ impl ToolManager {
         }
     }
 
+    /// Runs a tool with command-line arguments. The arguments are parsed and validated
+    /// against the tool's parameters before the tool is run.
     pub fn run_tool(&self, tool_name: String, args: Vec<String>) -> Result<(), Error> {
         // if !working_dir.is_empty() {
         //     tool_args_vec.insert(0, format!("--wd={}", working_dir));
         // }
 
         match self.get_tool(tool_name.as_ref()) {
-            Some(tool) => return tool.run(args, &self.working_dir, self.verbose),
+            Some(tool) => {
+                let tool_name = tool.get_tool_name();
+                self.sink.started(&tool_name);
+                let result = match arg_parser::parse_args(&tool, &args, &self.working_dir) {
+                    Ok(args) => tool.run(args, &self.working_dir, self.verbose, &self.sink),
+                    Err(e) => Err(e.into()),
+                };
+                self.sink.finished(&tool_name, &result);
+                return result;
+            },
             None => {
                 return Err(Error::new(ErrorKind::NotFound,
                                       format!("Unrecognized tool name {}.", tool_name)))
@@ -748,6 +801,22 @@ impl ToolManager {
         }
     }
 
+    /// Runs a tool using typed arguments rather than command-line strings. The arguments
+    /// are validated against the tool's parameters before it is run, input and output rasters
+    /// may be held in memory rather than on disk, and the tool's outputs are returned.
+    pub fn run_tool_with_args(&self, tool_name: &str, args: ToolArgs) -> Result<ToolOutput, ToolError> {
+        match self.get_tool(tool_name) {
+            Some(tool) => arg_parser::run_with_args(tool, args, &self.working_dir, self.verbose, &self.sink),
+            None => Err(ToolError::UnrecognizedTool(tool_name.to_string())),
+        }
+    }
+
+    /// Runs a workflow, i.e. a set of tool calls described in a JSON file, in which the
+    /// outputs of some steps are the inputs of others. See `workflow::run_workflow`.
+    pub fn run_workflow(&self, file_name: &str) -> Result<(), Error> {
+        workflow::run_workflow(self, file_name)
+    }
+
     pub fn tool_help(&self, tool_name: String) -> Result<(), Error> {
         if !tool_name.is_empty() {
             match self.get_tool(tool_name.as_ref()) {
@@ -802,6 +871,18 @@ impl ToolManager {
         Ok(())
     }
 
+    /// Prints the catalog of all of the tools, including each tool's parameters, as a
+    /// JSON document. See `catalog::get_catalog`.
+    pub fn catalog(&self) -> Result<(), Error> {
+        println!("{}", self.get_catalog()?);
+        Ok(())
+    }
+
+    /// Returns the catalog of all of the tools as a JSON document.
+    pub fn get_catalog(&self) -> Result<String, Error> {
+        catalog::get_catalog(self)
+    }
+
     pub fn list_tools(&self) {
         let mut tool_details: Vec<(String, String)> = Vec::new();
 
@@ -866,7 +947,8 @@ pub trait WhiteboxTool {
     fn run<'a>(&self,
                args: Vec<String>,
                working_directory: &'a str,
-               verbose: bool)
+               verbose: bool,
+               sink: &Arc<ProgressSink>)
                -> Result<(), Error>;
 }
 
@@ -887,6 +969,11 @@ fn get_help<'a>(wt: Box<WhiteboxTool + 'a>) -> String {
         }
         p.push_str(&format!("{:width$} {}\n", s.trim().trim_matches(','), d["description"].as_str().unwrap(), width = 18));
     }
+    if a.iter().any(|d| d["parameter_type"].to_string().contains("Vector")) {
+        p.push_str("\nVector files may be Shapefiles, GeoJSON (.geojson or .json) files, or GeoPackages. A GeoJSON
+file without a crs member is assumed to be in WGS 84 (EPSG:4326) when its coordinates are
+within the range of longitudes and latitudes, as the GeoJSON specification (RFC 7946) requires.\n");
+    }
     let example = wt.get_example_usage();
     let s: String;
     if example.len() <= 1 {
@@ -924,7 +1011,7 @@ fn get_name_and_description<'a>(wt: Box<WhiteboxTool + 'a>) -> (String, String)
     (wt.get_tool_name(), wt.get_tool_description())
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 struct ToolParameter {
     name: String,
     flags: Vec<String>,
@@ -944,7 +1031,7 @@ impl ToolParameter {
     }
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 enum ParameterType {
     Boolean,
     String,
@@ -958,9 +1045,10 @@ enum ParameterType {
     FileList(ParameterFileType),
     Directory,
     OptionList(Vec<String>),
+    VectorAttributeField(AttributeType, String),
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 enum ParameterFileType {
     Any,
     Lidar,
@@ -971,10 +1059,24 @@ enum ParameterFileType {
     Html,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 enum VectorGeometryType {
     Any,
     Point,
     Line,
     Polygon,
 }
+
+/// The type of a vector attribute field parameter. The String held by 
+/// ParameterType::VectorAttributeField is the flag of the vector file parameter
+/// whose attribute table contains the field.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+enum AttributeType {
+    Any,
+    Integer,
+    Float,
+    Number,
+    Text,
+    Boolean,
+    Date,
+}
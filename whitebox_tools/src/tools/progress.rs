@@ -0,0 +1,285 @@
+/*
+This code is part of the WhiteboxTools geospatial analysis library.
+Authors: Dr. John Lindsay
+Created: March 24, 2018
+Last Modified: April 6, 2018
+License: MIT
+*/
+
+use std::io::{Error, ErrorKind};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use serde_json;
+
+/// Receives the progress, messages, and warnings of a running tool. A sink is passed to
+/// `WhiteboxTool::run` and tools report through it rather than printing directly. Tools
+/// report progress, and check for cancellation using `progress`, whether or not they are run
+/// in verbose mode, and stop with an `ErrorKind::Interrupted` error when the run has been
+/// cancelled. It is up to the sink whether progress is displayed.
+pub trait ProgressSink: Send + Sync {
+    /// Reports the percent complete of a named stage of a tool, e.g. "Progress" or
+    /// "Calculating flow directions".
+    fn report_progress(&self, stage: &str, percent: usize);
+
+    /// Reports an informational message, e.g. the elapsed time or an output file name.
+    fn message(&self, text: &str);
+
+    /// Reports a warning, i.e. a problem that does not stop the tool.
+    fn warning(&self, text: &str);
+
+    /// Returns true if the run has been cancelled.
+    fn is_cancelled(&self) -> bool {
+        false
+    }
+
+    /// Called by the `ToolManager` before a tool is run.
+    fn started(&self, _tool_name: &str) {}
+
+    /// Called by the `ToolManager` after a tool has run.
+    fn finished(&self, _tool_name: &str, _result: &Result<(), Error>) {}
+
+    /// Reports progress and returns an error if the run has been cancelled.
+    fn progress(&self, stage: &str, percent: usize) -> Result<(), Error> {
+        self.report_progress(stage, percent);
+        if self.is_cancelled() {
+            return Err(Error::new(ErrorKind::Interrupted, "The tool was cancelled."));
+        }
+        Ok(())
+    }
+}
+
+/// A cooperative cancellation flag. Clones share the same flag, so a token can be handed
+/// to a sink and cancelled from another thread.
+#[derive(Clone, Debug, Default)]
+pub struct CancellationToken {
+    cancelled: Arc<AtomicBool>,
+}
+
+impl CancellationToken {
+    pub fn new() -> CancellationToken {
+        CancellationToken { cancelled: Arc::new(AtomicBool::new(false)) }
+    }
+
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst)
+    }
+}
+
+/// Prints progress and messages to the standard output as plain text. This is the default sink.
+/// A quiet sink does not print progress, which is how the `ToolManager` runs tools when it is
+/// not in verbose mode; cancellation is still checked.
+#[derive(Clone, Debug, Default)]
+pub struct StdoutProgress {
+    token: CancellationToken,
+    quiet: bool,
+}
+
+impl StdoutProgress {
+    pub fn new() -> StdoutProgress {
+        StdoutProgress { token: CancellationToken::new(), quiet: false }
+    }
+
+    pub fn with_token(token: CancellationToken) -> StdoutProgress {
+        StdoutProgress { token: token, quiet: false }
+    }
+
+    pub fn token(&self) -> CancellationToken {
+        self.token.clone()
+    }
+
+    pub fn set_quiet(&mut self, quiet: bool) {
+        self.quiet = quiet;
+    }
+
+    // the line printed for a progress report, or None in quiet mode
+    fn progress_line(&self, stage: &str, percent: usize) -> Option<String> {
+        if self.quiet {
+            return None;
+        }
+        Some(format!("{}: {}%", stage, percent))
+    }
+}
+
+impl ProgressSink for StdoutProgress {
+    fn report_progress(&self, stage: &str, percent: usize) {
+        if let Some(line) = self.progress_line(stage, percent) {
+            println!("{}", line);
+        }
+    }
+
+    fn message(&self, text: &str) {
+        println!("{}", text);
+    }
+
+    fn warning(&self, text: &str) {
+        println!("{}", text);
+    }
+
+    fn is_cancelled(&self) -> bool {
+        self.token.is_cancelled()
+    }
+}
+
+/// Prints one JSON object per line to the standard output, e.g.
+///
+/// `{"event":"progress","stage":"Progress","percent":42}`
+///
+/// The event is one of "started", "progress", "message", "warning", or "finished". The
+/// "finished" event has a "success" value and, for a failed run, an "error" message.
+#[derive(Clone, Debug, Default)]
+pub struct JsonProgress {
+    token: CancellationToken,
+}
+
+impl JsonProgress {
+    pub fn new() -> JsonProgress {
+        JsonProgress { token: CancellationToken::new() }
+    }
+
+    pub fn with_token(token: CancellationToken) -> JsonProgress {
+        JsonProgress { token: token }
+    }
+
+    pub fn token(&self) -> CancellationToken {
+        self.token.clone()
+    }
+}
+
+fn json_string(s: &str) -> String {
+    serde_json::to_string(s).unwrap_or_else(|_| "\"\"".to_string())
+}
+
+fn progress_event(stage: &str, percent: usize) -> String {
+    format!("{{\"event\":\"progress\",\"stage\":{},\"percent\":{}}}", json_string(stage), percent)
+}
+
+// a "message" or "warning" event
+fn text_event(event: &str, text: &str) -> String {
+    format!("{{\"event\":\"{}\",\"text\":{}}}", event, json_string(text))
+}
+
+fn started_event(tool_name: &str) -> String {
+    format!("{{\"event\":\"started\",\"tool\":{}}}", json_string(tool_name))
+}
+
+fn finished_event(tool_name: &str, result: &Result<(), Error>) -> String {
+    match *result {
+        Ok(()) => format!("{{\"event\":\"finished\",\"tool\":{},\"success\":true}}", json_string(tool_name)),
+        Err(ref e) => {
+            format!("{{\"event\":\"finished\",\"tool\":{},\"success\":false,\"error\":{}}}",
+                    json_string(tool_name),
+                    json_string(&e.to_string()))
+        }
+    }
+}
+
+impl ProgressSink for JsonProgress {
+    fn report_progress(&self, stage: &str, percent: usize) {
+        println!("{}", progress_event(stage, percent));
+    }
+
+    fn message(&self, text: &str) {
+        println!("{}", text_event("message", text));
+    }
+
+    fn warning(&self, text: &str) {
+        println!("{}", text_event("warning", text));
+    }
+
+    fn is_cancelled(&self) -> bool {
+        self.token.is_cancelled()
+    }
+
+    fn started(&self, tool_name: &str) {
+        println!("{}", started_event(tool_name));
+    }
+
+    fn finished(&self, tool_name: &str, result: &Result<(), Error>) {
+        println!("{}", finished_event(tool_name, result));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+    use serde_json::Value;
+
+    fn parse(line: &str) -> Value {
+        assert!(!line.contains('\n'), "{}", line);
+        serde_json::from_str(line).unwrap()
+    }
+
+    #[test]
+    fn json_events_are_single_lines() {
+        let v = parse(&progress_event("Calculating flow directions", 42));
+        assert_eq!((v["event"].as_str(), v["stage"].as_str(), v["percent"].as_u64()),
+                   (Some("progress"), Some("Calculating flow directions"), Some(42)));
+        assert_eq!(v.as_object().unwrap().len(), 3);
+        let v = parse(&started_event("D8Pointer"));
+        assert_eq!((v["event"].as_str(), v["tool"].as_str()), (Some("started"), Some("D8Pointer")));
+        let v = parse(&finished_event("D8Pointer", &Ok(())));
+        assert_eq!((v["event"].as_str(), v["tool"].as_str(), v["success"].as_bool()),
+                   (Some("finished"), Some("D8Pointer"), Some(true)));
+        assert!(v.get("error").is_none());
+        let e = Err(Error::new(ErrorKind::NotFound, "No such file: \"dem.tif\""));
+        let v = parse(&finished_event("D8Pointer", &e));
+        assert_eq!((v["success"].as_bool(), v["error"].as_str()), (Some(false), Some("No such file: \"dem.tif\"")));
+        for &event in ["message", "warning"].iter() {
+            let text = "Output file:\n\tC:\\data\\\"out\".tif \u{1} \u{e9}";
+            let v = parse(&text_event(event, text));
+            assert_eq!((v["event"].as_str(), v["text"].as_str()), (Some(event), Some(text)));
+        }
+    }
+
+    #[test]
+    fn strings_are_escaped() {
+        assert_eq!(json_string("dem.tif"), "\"dem.tif\"");
+        assert_eq!(json_string("a \"b\" \\ c"), "\"a \\\"b\\\" \\\\ c\"");
+        assert_eq!(json_string("line 1\nline 2\r\t"), "\"line 1\\nline 2\\r\\t\"");
+        assert_eq!(json_string("\u{0}\u{1f}"), "\"\\u0000\\u001f\"");
+        assert_eq!(json_string(""), "\"\"");
+        assert_eq!(json_string("\u{e9}t\u{e9}"), "\"\u{e9}t\u{e9}\"");
+    }
+
+    #[test]
+    fn quiet_sinks_do_not_report_progress() {
+        let mut sink = StdoutProgress::new();
+        assert_eq!(sink.progress_line("Progress", 42), Some("Progress: 42%".to_string()));
+        sink.set_quiet(true);
+        assert_eq!(sink.progress_line("Progress", 42), None);
+        // cancellation is still checked
+        sink.token().cancel();
+        assert_eq!(sink.progress("Progress", 43).err().unwrap().kind(), ErrorKind::Interrupted);
+    }
+
+    #[test]
+    fn cancelled_tokens_stop_progress() {
+        let token = CancellationToken::new();
+        let sinks: Vec<Box<ProgressSink>> = vec![
+            Box::new(StdoutProgress::with_token(token.clone())),
+            Box::new(JsonProgress::with_token(token.clone())),
+        ];
+        for sink in &sinks {
+            assert!(!sink.is_cancelled());
+            assert!(sink.progress("Progress", 10).is_ok());
+        }
+        // clones share the flag, so the run can be cancelled from another thread
+        let other = token.clone();
+        thread::spawn(move || other.cancel()).join().unwrap();
+        assert!(token.is_cancelled());
+        for sink in &sinks {
+            assert!(sink.is_cancelled());
+            let e = sink.progress("Progress", 20).err().unwrap();
+            assert_eq!(e.kind(), ErrorKind::Interrupted);
+            assert_eq!(e.to_string(), "The tool was cancelled.");
+        }
+        // a new token is independent of the cancelled one
+        assert!(StdoutProgress::new().progress("Progress", 30).is_ok());
+        assert!(!JsonProgress::new().token().is_cancelled());
+    }
+}
@@ -16,8 +16,10 @@ use std::io::{Error, ErrorKind};
 use std::sync::Arc;
 use std::sync::mpsc;
 use std::thread;
+use std::cmp::min;
 use raster::*;
 use tools::*;
+use tools::arg_parser::ArgValues;
 
 pub struct ExtractValleys {
     name: String,
@@ -142,82 +144,35 @@ impl WhiteboxTool for ExtractValleys {
     fn run<'a>(&self,
                args: Vec<String>,
                working_directory: &'a str,
-               verbose: bool)
+               verbose: bool,
+               sink: &Arc<ProgressSink>)
                -> Result<(), Error> {
-        let mut input_file = String::new();
-        let mut output_file = String::new();
-        let mut variant = String::from("lq");
-        let mut line_thin = false;
-        let mut filter_size = 5;
-        
         if args.len() == 0 {
             return Err(Error::new(ErrorKind::InvalidInput,
                                   "Tool run with no paramters."));
         }
-        for i in 0..args.len() {
-            let mut arg = args[i].replace("\"", "");
-            arg = arg.replace("\'", "");
-            let cmd = arg.split("="); // in case an equals sign was used
-            let vec = cmd.collect::<Vec<&str>>();
-            let mut keyval = false;
-            if vec.len() > 1 {
-                keyval = true;
-            }
-            if vec[0].to_lowercase() == "-dem" || vec[0].to_lowercase() == "--dem" {
-                if keyval {
-                    input_file = vec[1].to_string();
-                } else {
-                    input_file = args[i+1].to_string();
-                }
-            } else if vec[0].to_lowercase() == "-o" || vec[0].to_lowercase() == "--output" {
-                if keyval {
-                    output_file = vec[1].to_string();
-                } else {
-                    output_file = args[i + 1].to_string();
-                }
-            } else if vec[0].to_lowercase() == "-variant" || vec[0].to_lowercase() == "--variant" {
-                if keyval {
-                    variant = vec[1].to_string();
-                } else {
-                    variant = args[i+1].to_string();
-                }
-                if variant.to_lowercase().contains("q") {
-                    variant = String::from("lq");
-                } else if variant.to_lowercase().contains("j") {
-                    variant = String::from("JandR");
-                } else { //if variant.to_lowercase().contains("p") {
-                    variant = String::from("PandD");
-                }
-            } else if vec[0].to_lowercase() == "-line_thin" || vec[0].to_lowercase() == "--line_thin" {
-                line_thin = true;
-            } else if vec[0].to_lowercase() == "-filter" || vec[0].to_lowercase() == "--filter" {
-                if keyval {
-                    filter_size = vec[1].to_string().parse::<usize>().unwrap();
-                } else {
-                    filter_size = args[i + 1].to_string().parse::<usize>().unwrap();
-                }
-
-                //the filter dimensions must be odd numbers such that there is a middle pixel
-                if (filter_size as f64 / 2f64).floor() == filter_size as f64 / 2f64 {
-                    println!("WARNING: Filter dimensions must be odd numbers. The specified filter dimension has been modified.");
-                    filter_size += 1;
-                }
-            }
+        let args = ArgValues::parse(&self.parameters, &args)?;
+        let input_file = args.required_file("--dem", working_directory)?;
+        let output_file = args.required_file("--output", working_directory)?;
+        let mut variant = args.get_string("--variant")?.unwrap_or(String::from("lq"));
+        if variant.to_lowercase().contains("q") {
+            variant = String::from("lq");
+        } else if variant.to_lowercase().contains("j") {
+            variant = String::from("JandR");
+        } else { //if variant.to_lowercase().contains("p") {
+            variant = String::from("PandD");
         }
-
-        if verbose {
-            println!("***************{}", "*".repeat(self.get_tool_name().len()));
-            println!("* Welcome to {} *", self.get_tool_name());
-            println!("***************{}", "*".repeat(self.get_tool_name().len()));
+        let line_thin = args.get_bool("--line_thin")?;
+        let mut filter_size = args.get_usize("--filter")?.unwrap_or(5);
+        if (filter_size as f64 / 2f64).floor() == filter_size as f64 / 2f64 {
+            sink.warning("WARNING: Filter dimensions must be odd numbers. The specified filter dimension has been modified.");
+            filter_size += 1;
         }
 
-        let sep: String = path::MAIN_SEPARATOR.to_string();
-
-        if !input_file.contains(&sep) && !input_file.contains("/") {
-            input_file = format!("{}{}", working_directory, input_file);
-        }
-        if !output_file.contains(&sep) && !output_file.contains("/") {
-            output_file = format!("{}{}", working_directory, output_file);
+        if verbose {
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
+            sink.message(&format!("* Welcome to {} *", self.get_tool_name()));
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
         }
 
         let input = Arc::new(Raster::new(&input_file, "r")?);
@@ -238,81 +193,87 @@ impl WhiteboxTool for ExtractValleys {
                 
                 // This one can be performed conccurently.
                 let num_procs = num_cpus::get() as isize;
-                let (tx, rx) = mpsc::channel();
-                for tid in 0..num_procs {
-                    let input = input.clone();
-                    let tx = tx.clone();
-                    thread::spawn(move || {
-                        let num_cells_in_filter = filter_size * filter_size;
-                        let mut dx = vec![0isize; num_cells_in_filter];
-                        let mut dy = vec![0isize; num_cells_in_filter];
-                        let midpoint = (filter_size as f64 / 2f64).floor() as isize;
-                        let mut z: f64;
-                        let mut zn: f64;
-                        let large_value = f64::INFINITY;
-                        let mut n: f64;
-                        let mut lower_quartile: usize;
+                // the DEM is read in windows of rows, with a halo of one row on either side
+                let window_rows = input.get_window_rows();
+                let mut start_row = 0;
+                while start_row < rows {
+                    let end_row = min(start_row + window_rows, rows);
+                    let window = Arc::new(input.get_window(start_row - 1, end_row + 1));
+                    let (tx, rx) = mpsc::channel();
+                    for tid in 0..num_procs {
+                        let window = window.clone();
+                        let tx = tx.clone();
+                        thread::spawn(move || {
+                            let num_cells_in_filter = filter_size * filter_size;
+                            let mut dx = vec![0isize; num_cells_in_filter];
+                            let mut dy = vec![0isize; num_cells_in_filter];
+                            let midpoint = (filter_size as f64 / 2f64).floor() as isize;
+                            let mut z: f64;
+                            let mut zn: f64;
+                            let large_value = f64::INFINITY;
+                            let mut n: f64;
+                            let mut lower_quartile: usize;
                         
-                        // let mut filter_shape = vec![1f64; num_cells_in_filter];
-                        // //see which pixels in the filter lie within the largest ellipse 
-                        // //that fits in the filter box 
-                        // let mut asqr = midpoint * midpoint;
-                        let mut i = 0;
-                        for row in 0..filter_size as isize {
-                            for col in 0..filter_size as isize {
-                                dx[i] = col - midpoint;
-                                dy[i] = row - midpoint;
-                                // z = (dx[i] * dx[i]) / asqr + (dy[i] * dy[i]) / asqr;
-                                // if z > 1f64 {
-                                //     filter_shape[i] = 0f64;
-                                // }
-                                i += 1;
+                            // let mut filter_shape = vec![1f64; num_cells_in_filter];
+                            // //see which pixels in the filter lie within the largest ellipse 
+                            // //that fits in the filter box 
+                            // let mut asqr = midpoint * midpoint;
+                            let mut i = 0;
+                            for row in 0..filter_size as isize {
+                                for col in 0..filter_size as isize {
+                                    dx[i] = col - midpoint;
+                                    dy[i] = row - midpoint;
+                                    // z = (dx[i] * dx[i]) / asqr + (dy[i] * dy[i]) / asqr;
+                                    // if z > 1f64 {
+                                    //     filter_shape[i] = 0f64;
+                                    // }
+                                    i += 1;
+                                }
                             }
-                        }
                         
-                        for row in (0..rows).filter(|r| r % num_procs == tid) {
-                            let mut data = vec![nodata; columns as usize];
-                            for col in 0..columns {
-                                z = input[(row, col)];
-                                if z != nodata  {
-                                    let mut cell_data = vec![1f64; num_cells_in_filter];
-                                    n = 0f64;
-                                    for i in 0..num_cells_in_filter {
-                                        zn = input[(row + dy[i], col + dx[i])];
-                                        if zn != nodata {
-                                            cell_data[i] = zn;
-                                            n += 1f64;
-                                        } else {
-                                            cell_data[i] = large_value;
+                            for row in (start_row..end_row).filter(|r| r % num_procs == tid) {
+                                let mut data = vec![nodata; columns as usize];
+                                for col in 0..columns {
+                                    z = window.get_value(row, col);
+                                    if z != nodata  {
+                                        let mut cell_data = vec![1f64; num_cells_in_filter];
+                                        n = 0f64;
+                                        for i in 0..num_cells_in_filter {
+                                            zn = window.get_value(row + dy[i], col + dx[i]);
+                                            if zn != nodata {
+                                                cell_data[i] = zn;
+                                                n += 1f64;
+                                            } else {
+                                                cell_data[i] = large_value;
+                                            }
                                         }
-                                    }
-                                    if n > 0f64 {
-                                        // sort the array
-                                        cell_data.sort_by(|a, b| a.partial_cmp(b).unwrap_or(Equal));
-                                        lower_quartile = (n / 4f64).floor() as usize;
-                                        if z <= cell_data[lower_quartile] {
-                                            data[col as usize] = 1f64;
+                                        if n > 0f64 {
+                                            // sort the array
+                                            cell_data.sort_by(|a, b| a.partial_cmp(b).unwrap_or(Equal));
+                                            lower_quartile = (n / 4f64).floor() as usize;
+                                            if z <= cell_data[lower_quartile] {
+                                                data[col as usize] = 1f64;
+                                            }
                                         }
+                                    } else {
+                                        data[col as usize] = nodata;
                                     }
-                                } else {
-                                    data[col as usize] = nodata;
                                 }
+                                tx.send((row, data)).unwrap();
                             }
-                            tx.send((row, data)).unwrap();
-                        }
-                    });
-                }
+                        });
+                    }
 
-                for row in 0..rows {
-                    let data = rx.recv().unwrap();
-                    output.set_row_data(data.0, data.1);
-                    if verbose {
+                    for row in start_row..end_row {
+                        let data = rx.recv().unwrap();
+                        output.set_row_data(data.0, data.1);
                         progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as i32;
                         if progress != old_progress {
-                            println!("Progress: {}%", progress);
+                            sink.progress("Progress", progress as usize)?;
                             old_progress = progress;
                         }
                     }
+                    start_row = end_row;
                 }
 
             },
@@ -320,49 +281,55 @@ impl WhiteboxTool for ExtractValleys {
                 // This one can be performed conccurently.
                 // output.reinitialize_values(0f64);
                 let num_procs = num_cpus::get() as isize;
-                let (tx, rx) = mpsc::channel();
-                for tid in 0..num_procs {
-                    let input = input.clone();
-                    let tx = tx.clone();
-                    thread::spawn(move || {
-                        let (mut z, mut zn1, mut zn2): (f64, f64, f64);
-                        let dx = [ 0, 0, -1, 1 ];
-                        let dy = [ -1, 1, 0, 0 ];
-                        for row in (0..rows).filter(|r| r % num_procs == tid) {
-                            let mut data = vec![nodata; columns as usize];
-                            for col in 0..columns {
-                                z = input[(row, col)];
-                                if z != nodata  {
-                                    zn1 = input[(row + dy[0], col + dx[0])];
-                                    zn2 = input[(row + dy[1], col + dx[1])];
-                                    if zn1 != nodata && zn2 != nodata && zn1 > z && zn2 > z {
-                                        data[col as usize] = 1f64;
-                                    } else {
-                                        zn1 = input[(row + dy[2], col + dx[2])];
-                                        zn2 = input[(row + dy[3], col + dx[3])];
+                // the DEM is read in windows of rows, with a halo of one row on either side
+                let window_rows = input.get_window_rows();
+                let mut start_row = 0;
+                while start_row < rows {
+                    let end_row = min(start_row + window_rows, rows);
+                    let window = Arc::new(input.get_window(start_row - 1, end_row + 1));
+                    let (tx, rx) = mpsc::channel();
+                    for tid in 0..num_procs {
+                        let window = window.clone();
+                        let tx = tx.clone();
+                        thread::spawn(move || {
+                            let (mut z, mut zn1, mut zn2): (f64, f64, f64);
+                            let dx = [ 0, 0, -1, 1 ];
+                            let dy = [ -1, 1, 0, 0 ];
+                            for row in (start_row..end_row).filter(|r| r % num_procs == tid) {
+                                let mut data = vec![nodata; columns as usize];
+                                for col in 0..columns {
+                                    z = window.get_value(row, col);
+                                    if z != nodata  {
+                                        zn1 = window.get_value(row + dy[0], col + dx[0]);
+                                        zn2 = window.get_value(row + dy[1], col + dx[1]);
                                         if zn1 != nodata && zn2 != nodata && zn1 > z && zn2 > z {
                                             data[col as usize] = 1f64;
                                         } else {
-                                            data[col as usize] = 0f64;
+                                            zn1 = window.get_value(row + dy[2], col + dx[2]);
+                                            zn2 = window.get_value(row + dy[3], col + dx[3]);
+                                            if zn1 != nodata && zn2 != nodata && zn1 > z && zn2 > z {
+                                                data[col as usize] = 1f64;
+                                            } else {
+                                                data[col as usize] = 0f64;
+                                            }
                                         }
                                     }
                                 }
+                                tx.send((row, data)).unwrap();
                             }
-                            tx.send((row, data)).unwrap();
-                        }
-                    });
-                }
+                        });
+                    }
 
-                for row in 0..rows {
-                    let data = rx.recv().unwrap();
-                    output.set_row_data(data.0, data.1);
-                    if verbose {
+                    for row in start_row..end_row {
+                        let data = rx.recv().unwrap();
+                        output.set_row_data(data.0, data.1);
                         progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as i32;
                         if progress != old_progress {
-                            println!("Progress: {}%", progress);
+                            sink.progress("Progress", progress as usize)?;
                             old_progress = progress;
                         }
                     }
+                    start_row = end_row;
                 }
             },
             _ => { // "PandD"
@@ -377,12 +344,12 @@ impl WhiteboxTool for ExtractValleys {
                 let num_scan_cells = dx.len();
                 for row in 0..rows {
                     for col in 0..columns {
-                        z = input[(row, col)];
+                        z = input.get_value(row, col);
                         if z != nodata  {
                             maxz = z;
                             which_cell = 3;
                             for n in 0..num_scan_cells {
-                                z = input[(row + dy[n], col + dx[n])];
+                                z = input.get_value(row + dy[n], col + dx[n]);
                                 if z != nodata {
                                     if z > maxz {
                                         maxz = z;
@@ -392,22 +359,20 @@ impl WhiteboxTool for ExtractValleys {
                             }
                             output.set_value(row + dy[which_cell], col + dx[which_cell], 0f64);
                         } else {
-                            output[(row, col)] = nodata;
+                            output.set_value(row, col, nodata);
                         }
                     }
-                    if verbose {
-                        progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as i32;
-                        if progress != old_progress {
-                            println!("Progress: {}%", progress);
-                            old_progress = progress;
-                        }
+                    progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as i32;
+                    if progress != old_progress {
+                        sink.progress("Progress", progress as usize)?;
+                        old_progress = progress;
                     }
                 }
             }
         }
         
         if line_thin {
-            println!("Line thinning operation...");
+            sink.message("Line thinning operation...");
             let mut did_something = true;
             let mut loop_num = 0;
             let dx = [ 1, 1, 1, 0, -1, -1, -1, 0 ];
@@ -431,11 +396,11 @@ impl WhiteboxTool for ExtractValleys {
                 for a in 0..8 {
                     for row in 0..rows {
                         for col in 0..columns {
-                            z = output[(row, col)];
+                            z = output.get_value(row, col);
                             if z > 0.0 && z != nodata {
                                 // fill the neighbours array
                                 for i in 0..8 {
-                                    neighbours[i] = output[(row + dy[i], col + dx[i])];
+                                    neighbours[i] = output.get_value(row + dy[i], col + dx[i]);
                                 }
                                 
                                 // scan through element
@@ -446,18 +411,16 @@ impl WhiteboxTool for ExtractValleys {
                                     }
                                 }
                                 if pattern_match {
-                                    output[(row, col)] = 0.0;
+                                    output.set_value(row, col, 0.0);
                                     did_something = true;
                                 }
                             }
                         }
                     }
-                    if verbose {
-                        progress = (100.0_f64 * a as f64 / 7.0) as i32;
-                        if progress != old_progress {
-                            println!("Loop Number {}: {}%", loop_num, progress);
-                            old_progress = progress;
-                        }
+                    progress = (100.0_f64 * a as f64 / 7.0) as i32;
+                    if progress != old_progress {
+                        sink.progress(&format!("Loop Number {}", loop_num), progress as usize)?;
+                        old_progress = progress;
                     }
                 }
             }
@@ -477,19 +440,18 @@ impl WhiteboxTool for ExtractValleys {
                                       .replace("PT", ""));
 
         if verbose {
-            println!("Saving data...")
+            sink.message("Saving data...")
         };
         let _ = match output.write() {
             Ok(_) => {
                 if verbose {
-                    println!("Output file written")
+                    sink.message("Output file written")
                 }
             }
             Err(e) => return Err(e),
         };
         if verbose {
-            println!("{}",
-                 &format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
+            sink.message(&format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
         }
 
         Ok(())
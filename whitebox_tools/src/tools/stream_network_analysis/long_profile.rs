@@ -21,6 +21,7 @@ use std::thread;
 use raster::*;
 use std::io::{Error, ErrorKind};
 use tools::*;
+use tools::arg_parser::ArgValues;
 use rendering::LineGraph;
 use rendering::html::*;
 use structures::Array2D;
@@ -78,7 +79,7 @@ impl LongProfile {
 
         parameters.push(ToolParameter{
             name: "Does the pointer file use the ESRI pointer scheme?".to_owned(), 
-            flags: vec!["--esri_pntr".to_owned()], 
+            flags: vec!["--esri_style".to_owned(), "--esri_pntr".to_owned()], 
             description: "D8 pointer uses the ESRI style scheme.".to_owned(),
             parameter_type: ParameterType::Boolean,
             default_value: Some("false".to_owned()),
@@ -139,85 +140,32 @@ impl WhiteboxTool for LongProfile {
         self.toolbox.clone()
     }
 
-    fn run<'a>(&self, args: Vec<String>, working_directory: &'a str, verbose: bool) -> Result<(), Error> {
-        let mut d8_file = String::new();
-        let mut streams_file = String::new();
-        let mut dem_file = String::new();
-        let mut output_file = String::new();
-        let mut esri_style = false;
-        
+    fn run<'a>(&self, args: Vec<String>, working_directory: &'a str, verbose: bool, sink: &Arc<ProgressSink>) -> Result<(), Error> {
         if args.len() == 0 {
             return Err(Error::new(ErrorKind::InvalidInput,
                                 "Tool run with no paramters."));
         }
-        for i in 0..args.len() {
-            let mut arg = args[i].replace("\"", "");
-            arg = arg.replace("\'", "");
-            let cmd = arg.split("="); // in case an equals sign was used
-            let vec = cmd.collect::<Vec<&str>>();
-            let mut keyval = false;
-            if vec.len() > 1 {
-                keyval = true;
-            }
-            let flag_val = vec[0].to_lowercase().replace("--", "-");
-            if flag_val == "-d8_pntr" {
-                d8_file = if keyval {
-                    vec[1].to_string()
-                } else {
-                    args[i+1].to_string()
-                };
-            } else if flag_val == "-streams" {
-                streams_file = if keyval {
-                    vec[1].to_string()
-                } else {
-                    args[i+1].to_string()
-                };
-            } else if flag_val == "-dem" {
-                dem_file = if keyval {
-                    vec[1].to_string()
-                } else {
-                    args[i+1].to_string()
-                };
-            } else if flag_val == "-o" || flag_val == "-output" {
-                output_file = if keyval {
-                    vec[1].to_string()
-                } else {
-                    args[i+1].to_string()
-                };
-            } else if flag_val == "-esri_pntr" || flag_val == "-esri_style" {
-                esri_style = true;
-            }
-        }
+        let args = ArgValues::parse(&self.parameters, &args)?;
+        let d8_file = args.required_file("--d8_pntr", working_directory)?;
+        let streams_file = args.required_file("--streams", working_directory)?;
+        let dem_file = args.required_file("--dem", working_directory)?;
+        let output_file = args.required_file("--output", working_directory)?;
+        let esri_style = args.get_bool("--esri_pntr")?;
 
         if verbose {
-            println!("***************{}", "*".repeat(self.get_tool_name().len()));
-            println!("* Welcome to {} *", self.get_tool_name());
-            println!("***************{}", "*".repeat(self.get_tool_name().len()));
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
+            sink.message(&format!("* Welcome to {} *", self.get_tool_name()));
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
         }
 
-        let sep: String = path::MAIN_SEPARATOR.to_string();
-
         let mut progress: usize;
         let mut old_progress: usize = 1;
 
-        if !d8_file.contains(&sep) && !d8_file.contains("/") {
-            d8_file = format!("{}{}", working_directory, d8_file);
-        }
-        if !streams_file.contains(&sep) && !streams_file.contains("/") {
-            streams_file = format!("{}{}", working_directory, streams_file);
-        }
-        if !dem_file.contains(&sep) && !dem_file.contains("/") {
-            dem_file = format!("{}{}", working_directory, dem_file);
-        }
-        if !output_file.contains(&sep) && !output_file.contains("/") {
-            output_file = format!("{}{}", working_directory, output_file);
-        }
-
-        if verbose { println!("Reading pointer data...") };
+        if verbose { sink.message("Reading pointer data...") };
         let pntr = Raster::new(&d8_file, "r")?;
-        if verbose { println!("Reading streams data...") };
+        if verbose { sink.message("Reading streams data...") };
         let streams = Raster::new(&streams_file, "r")?;
-        if verbose { println!("Reading DEM data...") };
+        if verbose { sink.message("Reading DEM data...") };
         let dem = Raster::new(&dem_file, "r")?;
         
         let start = time::now();
@@ -242,7 +190,7 @@ impl WhiteboxTool for LongProfile {
         let diag_cell_size = (cell_size_x * cell_size_x + cell_size_y * cell_size_y).sqrt();
         
         // find all the stream head cells and add them to a list
-        if verbose { println!("Finding channel heads..."); }
+        if verbose { sink.message("Finding channel heads..."); }
         let streams = Arc::new(streams);
         let pntr = Arc::new(pntr);
         let num_procs = num_cpus::get() as isize;
@@ -292,16 +240,14 @@ impl WhiteboxTool for LongProfile {
                 heads.push((r, col));
             }
             
-            if verbose {
-                progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as usize;
-                if progress != old_progress {
-                    println!("Finding channel heads: {}%", progress);
-                    old_progress = progress;
-                }
+            progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as usize;
+            if progress != old_progress {
+                sink.progress("Finding channel heads", progress)?;
+                old_progress = progress;
             }
         }
 
-        if verbose { println!("Traversing streams..."); }
+        if verbose { sink.message("Traversing streams..."); }
         // Now traverse each flowpath starting from each stream head and
         // retrieve the elevation and distance from outlet data.
         let mut xdata = vec![];
@@ -383,12 +329,10 @@ impl WhiteboxTool for LongProfile {
             traverse_num += 1;
             stream_lengths[h] = dist;
             // update progress here
-            if verbose {
-                progress = (100.0_f64 * h as f64 / (num_heads-1) as f64) as usize;
-                if progress != old_progress {
-                    println!("Loop 1 of 2: {}%", progress);
-                    old_progress = progress;
-                }
+            progress = (100.0_f64 * h as f64 / (num_heads-1) as f64) as usize;
+            if progress != old_progress {
+                sink.progress("Loop 1 of 2", progress)?;
+                old_progress = progress;
             }
         }
 
@@ -440,12 +384,10 @@ impl WhiteboxTool for LongProfile {
                 traverse_num += 1;
             }
             // update progress here
-            if verbose {
-                progress = (100.0_f64 * h as f64 / (num_heads-1) as f64) as usize;
-                if progress != old_progress {
-                    println!("Loop 2 of 2: {}%", progress);
-                    old_progress = progress;
-                }
+            progress = (100.0_f64 * h as f64 / (num_heads-1) as f64) as usize;
+            if progress != old_progress {
+                sink.progress("Loop 2 of 2", progress)?;
+                old_progress = progress;
             }
         }
 
@@ -494,7 +436,7 @@ impl WhiteboxTool for LongProfile {
 
         let _ = writer.flush();
 
-        if verbose { println!("\n{}",  &format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", "")); }
+        if verbose { sink.message(&format!("\n{}", &format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""))); }
 
         if verbose {
             if cfg!(target_os = "macos") || cfg!(target_os = "ios") {
@@ -521,7 +463,7 @@ impl WhiteboxTool for LongProfile {
                 let _ = output.stdout;
             }
 
-            println!("Complete! Please see {} for output.", output_file);
+            sink.message(&format!("Complete! Please see {} for output.", output_file));
         }
 
         Ok(())
@@ -17,7 +17,9 @@ use std::f64;
 use raster::*;
 use vector::{Shapefile, ShapeType};
 use std::io::{Error, ErrorKind};
+use std::sync::Arc;
 use tools::*;
+use tools::arg_parser::ArgValues;
 use rendering::LineGraph;
 use rendering::html::*;
 use structures::Array2D;
@@ -75,7 +77,7 @@ impl LongProfileFromPoints {
 
         parameters.push(ToolParameter{
             name: "Does the pointer file use the ESRI pointer scheme?".to_owned(), 
-            flags: vec!["--esri_pntr".to_owned()], 
+            flags: vec!["--esri_style".to_owned(), "--esri_pntr".to_owned()], 
             description: "D8 pointer uses the ESRI style scheme.".to_owned(),
             parameter_type: ParameterType::Boolean,
             default_value: Some("false".to_owned()),
@@ -136,87 +138,34 @@ impl WhiteboxTool for LongProfileFromPoints {
         self.toolbox.clone()
     }
 
-    fn run<'a>(&self, args: Vec<String>, working_directory: &'a str, verbose: bool) -> Result<(), Error> {
-        let mut d8_file = String::new();
-        let mut points_file = String::new();
-        let mut dem_file = String::new();
-        let mut output_file = String::new();
-        let mut esri_style = false;
-        
+    fn run<'a>(&self, args: Vec<String>, working_directory: &'a str, verbose: bool, sink: &Arc<ProgressSink>) -> Result<(), Error> {
         if args.len() == 0 {
             return Err(Error::new(ErrorKind::InvalidInput,
                                 "Tool run with no paramters."));
         }
-        for i in 0..args.len() {
-            let mut arg = args[i].replace("\"", "");
-            arg = arg.replace("\'", "");
-            let cmd = arg.split("="); // in case an equals sign was used
-            let vec = cmd.collect::<Vec<&str>>();
-            let mut keyval = false;
-            if vec.len() > 1 {
-                keyval = true;
-            }
-            let flag_val = vec[0].to_lowercase().replace("--", "-");
-            if flag_val == "-d8_pntr" {
-                d8_file = if keyval {
-                    vec[1].to_string()
-                } else {
-                    args[i+1].to_string()
-                };
-            } else if flag_val == "-points" {
-                points_file = if keyval {
-                    vec[1].to_string()
-                } else {
-                    args[i+1].to_string()
-                };
-            } else if flag_val == "-dem" {
-                dem_file = if keyval {
-                    vec[1].to_string()
-                } else {
-                    args[i+1].to_string()
-                };
-            } else if flag_val == "-o" || flag_val == "-output" {
-                output_file = if keyval {
-                    vec[1].to_string()
-                } else {
-                    args[i+1].to_string()
-                };
-            } else if flag_val == "-esri_pntr" || flag_val == "-esri_style" {
-                esri_style = true;
-            }
-        }
+        let args = ArgValues::parse(&self.parameters, &args)?;
+        let d8_file = args.required_file("--d8_pntr", working_directory)?;
+        let points_file = args.required_file("--points", working_directory)?;
+        let dem_file = args.required_file("--dem", working_directory)?;
+        let output_file = args.required_file("--output", working_directory)?;
+        let esri_style = args.get_bool("--esri_pntr")?;
 
         if verbose {
-            println!("***************{}", "*".repeat(self.get_tool_name().len()));
-            println!("* Welcome to {} *", self.get_tool_name());
-            println!("***************{}", "*".repeat(self.get_tool_name().len()));
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
+            sink.message(&format!("* Welcome to {} *", self.get_tool_name()));
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
         }
 
-        let sep: String = path::MAIN_SEPARATOR.to_string();
-
         let mut progress: usize;
         let mut old_progress: usize = 1;
 
-        if !d8_file.contains(&sep) && !d8_file.contains("/") {
-            d8_file = format!("{}{}", working_directory, d8_file);
-        }
-        if !points_file.contains(&sep) && !points_file.contains("/") {
-            points_file = format!("{}{}", working_directory, points_file);
-        }
-        if !dem_file.contains(&sep) && !dem_file.contains("/") {
-            dem_file = format!("{}{}", working_directory, dem_file);
-        }
-        if !output_file.contains(&sep) && !output_file.contains("/") {
-            output_file = format!("{}{}", working_directory, output_file);
-        }
-
-        if verbose { println!("Reading pointer data...") };
+        if verbose { sink.message("Reading pointer data...") };
         let pntr = Raster::new(&d8_file, "r")?;
 
-        if verbose { println!("Reading points data...") };
+        if verbose { sink.message("Reading points data...") };
         let points = Shapefile::new(&points_file, "r")?;
         
-        if verbose { println!("Reading DEM data...") };
+        if verbose { sink.message("Reading DEM data...") };
         let dem = Raster::new(&dem_file, "r")?;
         
         let start = time::now();
@@ -247,16 +196,14 @@ impl WhiteboxTool for LongProfileFromPoints {
             let col = dem.get_column_from_x(record.points[0].x);
             heads.push((row, col));
             
-            if verbose {
-                progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as usize;
-                if progress != old_progress {
-                    println!("Finding channel heads: {}%", progress);
-                    old_progress = progress;
-                }
+            progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as usize;
+            if progress != old_progress {
+                sink.progress("Finding channel heads", progress)?;
+                old_progress = progress;
             }
         }
 
-        if verbose { println!("Traversing flowpaths..."); }
+        if verbose { sink.message("Traversing flowpaths..."); }
         // Now traverse each flowpath starting from each stream head and
         // retrieve the elevation and distance from outlet data.
         let mut xdata = vec![];
@@ -334,12 +281,10 @@ impl WhiteboxTool for LongProfileFromPoints {
             traverse_num += 1;
             stream_lengths[h] = dist;
             // update progress here
-            if verbose {
-                progress = (100.0_f64 * h as f64 / (num_heads-1) as f64) as usize;
-                if progress != old_progress {
-                    println!("Loop 1 of 2: {}%", progress);
-                    old_progress = progress;
-                }
+            progress = (100.0_f64 * h as f64 / (num_heads-1) as f64) as usize;
+            if progress != old_progress {
+                sink.progress("Loop 1 of 2", progress)?;
+                old_progress = progress;
             }
         }
 
@@ -387,12 +332,10 @@ impl WhiteboxTool for LongProfileFromPoints {
                 traverse_num += 1;
             }
             // update progress here
-            if verbose {
-                progress = (100.0_f64 * h as f64 / (num_heads-1) as f64) as usize;
-                if progress != old_progress {
-                    println!("Loop 2 of 2: {}%", progress);
-                    old_progress = progress;
-                }
+            progress = (100.0_f64 * h as f64 / (num_heads-1) as f64) as usize;
+            if progress != old_progress {
+                sink.progress("Loop 2 of 2", progress)?;
+                old_progress = progress;
             }
         }
 
@@ -440,7 +383,7 @@ impl WhiteboxTool for LongProfileFromPoints {
 
         let _ = writer.flush();
 
-        if verbose { println!("\n{}",  &format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", "")); }
+        if verbose { sink.message(&format!("\n{}", &format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""))); }
 
         if verbose {
             if cfg!(target_os = "macos") || cfg!(target_os = "ios") {
@@ -467,7 +410,7 @@ impl WhiteboxTool for LongProfileFromPoints {
                 let _ = output.stdout;
             }
 
-            println!("Complete! Please see {} for output.", output_file);
+            sink.message(&format!("Complete! Please see {} for output.", output_file));
         }
 
         Ok(())
@@ -8,6 +8,7 @@ mod hack_order;
 mod horton_order;
 mod long_profile;
 mod long_profile_from_points;
+mod raster_streams_to_vector;
 mod rasterize_streams;
 mod remove_short_streams;
 mod shreve_magnitude;
@@ -32,6 +33,7 @@ pub use self::hack_order::HackStreamOrder;
 pub use self::horton_order::HortonStreamOrder;
 pub use self::long_profile::LongProfile;
 pub use self::long_profile_from_points::LongProfileFromPoints;
+pub use self::raster_streams_to_vector::RasterStreamsToVector;
 pub use self::rasterize_streams::RasterizeStreams;
 pub use self::remove_short_streams::RemoveShortStreams;
 pub use self::shreve_magnitude::ShreveStreamMagnitude;
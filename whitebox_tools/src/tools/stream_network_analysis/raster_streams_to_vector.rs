@@ -0,0 +1,654 @@
+/*
+This tool is part of the WhiteboxTools geospatial analysis library.
+Authors: Dr. John Lindsay
+Created: March 20, 2018
+Last Modified: March 20, 2018
+License: MIT
+
+Help: This tool converts a raster stream network into a vector polyline Shapefile. Each
+stream link, i.e. the section of channel between a channel head or confluence and the next
+confluence or outlet downstream, is traced along the D8 flow pointer and written as a single
+polyline feature. Each line runs from the centre of the first cell in the link to the centre
+of the cell that the link drains to, such that the lines of tributaries meet the line of the
+downstream link at the confluence cell and the vector network is topologically connected.
+
+The output attribute table contains the following fields:
+
+LINK_ID:  The link identifier, equal to the value assigned by the StreamLinkIdentifier tool.
+UP_NODE:  The identifier of the node (channel head or confluence) at the upstream end.
+DN_NODE:  The identifier of the node (confluence or outlet) at the downstream end.
+STRAHLER: The Strahler stream order of the link.
+SHREVE:   The Shreve stream magnitude of the link.
+LENGTH:   The length of the link, measured as in the StreamLinkLength tool.
+SLOPE:    The average slope of the link in percent, measured as in the StreamLinkSlope
+          tool. This field is only included if a DEM is specified using --dem, and is
+          left blank for links of zero length.
+
+Grid cells in the streams raster with a value greater than zero are considered to be stream
+cells. The D8 pointer can be created using the D8Pointer tool; use the --esri_pntr flag if
+the pointer uses the ESRI style pointer scheme.
+*/
+extern crate time;
+
+use std::collections::HashMap;
+use std::env;
+use std::path;
+use std::f64;
+use raster::*;
+use vector::*;
+use structures::Array2D;
+use std::io::{Error, ErrorKind};
+use std::sync::Arc;
+use tools::*;
+use tools::arg_parser::ArgValues;
+
+pub struct RasterStreamsToVector {
+    name: String,
+    description: String,
+    toolbox: String,
+    parameters: Vec<ToolParameter>,
+    example_usage: String,
+}
+
+impl RasterStreamsToVector {
+    pub fn new() -> RasterStreamsToVector { // public constructor
+        let name = "RasterStreamsToVector".to_string();
+        let toolbox = "Stream Network Analysis".to_string();
+        let description = "Converts a raster stream network into a topologically connected vector polyline network.".to_string();
+
+        let mut parameters = vec![];
+        parameters.push(ToolParameter{
+            name: "Input Streams File".to_owned(),
+            flags: vec!["--streams".to_owned()],
+            description: "Input raster streams file.".to_owned(),
+            parameter_type: ParameterType::ExistingFile(ParameterFileType::Raster),
+            default_value: None,
+            optional: false
+        });
+
+        parameters.push(ToolParameter{
+            name: "Input D8 Pointer File".to_owned(),
+            flags: vec!["--d8_pntr".to_owned()],
+            description: "Input raster D8 pointer file.".to_owned(),
+            parameter_type: ParameterType::ExistingFile(ParameterFileType::Raster),
+            default_value: None,
+            optional: false
+        });
+
+        parameters.push(ToolParameter{
+            name: "Input DEM File (optional)".to_owned(),
+            flags: vec!["--dem".to_owned()],
+            description: "Optional input raster DEM file, used to calculate link slopes.".to_owned(),
+            parameter_type: ParameterType::ExistingFile(ParameterFileType::Raster),
+            default_value: None,
+            optional: true
+        });
+
+        parameters.push(ToolParameter{
+            name: "Output File".to_owned(),
+            flags: vec!["-o".to_owned(), "--output".to_owned()],
+            description: "Output vector polyline file.".to_owned(),
+            parameter_type: ParameterType::NewFile(ParameterFileType::Vector(VectorGeometryType::Line)),
+            default_value: None,
+            optional: false
+        });
+
+        parameters.push(ToolParameter{
+            name: "Does the pointer file use the ESRI pointer scheme?".to_owned(),
+            flags: vec!["--esri_style".to_owned(), "--esri_pntr".to_owned()],
+            description: "D8 pointer uses the ESRI style scheme.".to_owned(),
+            parameter_type: ParameterType::Boolean,
+            default_value: Some("false".to_owned()),
+            optional: true
+        });
+
+        let sep: String = path::MAIN_SEPARATOR.to_string();
+        let p = format!("{}", env::current_dir().unwrap().display());
+        let e = format!("{}", env::current_exe().unwrap().display());
+        let mut short_exe = e.replace(&p, "").replace(".exe", "").replace(".", "").replace(&sep, "");
+        if e.contains(".exe") {
+            short_exe += ".exe";
+        }
+        let usage = format!(">>.*{0} -r={1} -v --wd=\"*path*to*data*\" --streams=streams.tif --d8_pntr=D8.tif -o=streams.shp
+>>.*{0} -r={1} -v --wd=\"*path*to*data*\" --streams=streams.tif --d8_pntr=D8.tif --dem=DEM.tif -o=streams.shp --esri_pntr", short_exe, name).replace("*", &sep);
+
+        RasterStreamsToVector {
+            name: name,
+            description: description,
+            toolbox: toolbox,
+            parameters: parameters,
+            example_usage: usage
+        }
+    }
+}
+
+impl WhiteboxTool for RasterStreamsToVector {
+    fn get_source_file(&self) -> String {
+        String::from(file!())
+    }
+
+    fn get_tool_name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn get_tool_description(&self) -> String {
+        self.description.clone()
+    }
+
+    fn get_tool_parameters(&self) -> String {
+        match serde_json::to_string(&self.parameters) {
+            Ok(json_str) => return format!("{{\"parameters\":{}}}", json_str),
+            Err(err) => return format!("{:?}", err),
+        }
+    }
+
+    fn get_example_usage(&self) -> String {
+        self.example_usage.clone()
+    }
+
+    fn get_toolbox(&self) -> String {
+        self.toolbox.clone()
+    }
+
+    fn run<'a>(&self, args: Vec<String>, working_directory: &'a str, verbose: bool, sink: &Arc<ProgressSink>) -> Result<(), Error> {
+        if args.len() == 0 {
+            return Err(Error::new(ErrorKind::InvalidInput,
+                                "Tool run with no paramters."));
+        }
+        let args = ArgValues::parse(&self.parameters, &args)?;
+        let streams_file = args.required_file("--streams", working_directory)?;
+        let d8_file = args.required_file("--d8_pntr", working_directory)?;
+        let dem_file = args.get_file("--dem", working_directory)?;
+        let output_file = args.required_file("--output", working_directory)?;
+        let esri_style = args.get_bool("--esri_pntr")?;
+
+        if verbose {
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
+            sink.message(&format!("* Welcome to {} *", self.get_tool_name()));
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
+        }
+
+        let mut progress: usize;
+        let mut old_progress: usize = 1;
+
+
+        if verbose { sink.message("Reading pointer data...") };
+        let pntr = Raster::new(&d8_file, "r")?;
+        let pntr_nodata = pntr.configs.nodata;
+        if verbose { sink.message("Reading streams data...") };
+        let streams = Raster::new(&streams_file, "r")?;
+        let dem = match dem_file {
+            Some(ref dem_file) => {
+                if verbose { sink.message("Reading DEM data...") };
+                Some(Raster::new(dem_file, "r")?)
+            },
+            None => None,
+        };
+        let use_dem = dem.is_some();
+
+        let start = time::now();
+
+        let rows = pntr.configs.rows as isize;
+        let columns = pntr.configs.columns as isize;
+        let nodata = streams.configs.nodata;
+        let cell_size_x = streams.configs.resolution_x;
+        let cell_size_y = streams.configs.resolution_y;
+        let diag_cell_size = (cell_size_x * cell_size_x + cell_size_y * cell_size_y).sqrt();
+
+        // make sure the input files have the same size
+        if streams.configs.rows != pntr.configs.rows || streams.configs.columns != pntr.configs.columns {
+            return Err(Error::new(ErrorKind::InvalidInput,
+                                "The input files must have the same number of rows and columns and spatial extent."));
+        }
+        if let Some(ref dem) = dem {
+            if dem.configs.rows != pntr.configs.rows || dem.configs.columns != pntr.configs.columns {
+                return Err(Error::new(ErrorKind::InvalidInput,
+                                    "The input files must have the same number of rows and columns and spatial extent."));
+            }
+        }
+
+        let dx = [ 1, 1, 1, 0, -1, -1, -1, 0 ];
+        let dy = [ -1, 0, 1, 1, 1, 0, -1, -1 ];
+        let mut inflowing_vals = [ 16f64, 32f64, 64f64, 128f64, 1f64, 2f64, 4f64, 8f64 ];
+        if esri_style {
+            inflowing_vals = [ 8f64, 16f64, 32f64, 64f64, 128f64, 1f64, 2f64, 4f64 ];
+        }
+
+        // Create a mapping from the pointer values to cells offsets.
+        let mut pntr_matches: [usize; 129] = [999usize; 129];
+        if !esri_style {
+            // This maps Whitebox-style D8 pointer values
+            // onto the cell offsets in dx and dy.
+            pntr_matches[1] = 0usize;
+            pntr_matches[2] = 1usize;
+            pntr_matches[4] = 2usize;
+            pntr_matches[8] = 3usize;
+            pntr_matches[16] = 4usize;
+            pntr_matches[32] = 5usize;
+            pntr_matches[64] = 6usize;
+            pntr_matches[128] = 7usize;
+        } else {
+            // This maps Esri-style D8 pointer values
+            // onto the cell offsets in dx and dy.
+            pntr_matches[1] = 1usize;
+            pntr_matches[2] = 2usize;
+            pntr_matches[4] = 3usize;
+            pntr_matches[8] = 4usize;
+            pntr_matches[16] = 5usize;
+            pntr_matches[32] = 6usize;
+            pntr_matches[64] = 7usize;
+            pntr_matches[128] = 0usize;
+        }
+        let grid_lengths = [diag_cell_size, cell_size_x, diag_cell_size, cell_size_y, diag_cell_size, cell_size_x, diag_cell_size, cell_size_y];
+
+        // Returns the index into dx and dy of the flow direction of a stream cell,
+        // or None if the cell does not have a downslope neighbour.
+        let flow_dir = |row: isize, col: isize| -> Result<Option<usize>, Error> {
+            let z = pntr.get_value(row, col);
+            if z <= 0f64 || z == pntr_nodata {
+                return Ok(None);
+            }
+            let dir = z as usize;
+            if dir > 128 || pntr_matches[dir] == 999 {
+                return Err(Error::new(ErrorKind::InvalidInput,
+                    "An unexpected value has been identified in the pointer image. This tool requires a pointer grid that has been created using either the D8 or Rho8 tools."));
+            }
+            Ok(Some(pntr_matches[dir]))
+        };
+
+        // calculate the number of inflowing cells
+        let mut num_inflowing: Array2D<i8> = Array2D::new(rows, columns, -1, -1)?;
+        let mut link_id: Array2D<i32> = Array2D::new(rows, columns, 0, 0)?;
+        let mut stack = vec![];
+        let mut count: i8;
+        let mut current_id = 1i32;
+        let mut num_stream_cells = 0usize;
+        for row in 0..rows {
+            for col in 0..columns {
+                if streams.get_value(row, col) > 0.0 && streams.get_value(row, col) != nodata {
+                    count = 0i8;
+                    for i in 0..8 {
+                        if streams.get_value(row + dy[i], col + dx[i]) > 0.0 && streams.get_value(row + dy[i], col + dx[i]) != nodata &&
+                            pntr.get_value(row + dy[i], col + dx[i]) == inflowing_vals[i] {
+                            count += 1;
+                        }
+                    }
+                    num_inflowing[(row, col)] = count;
+                    num_stream_cells += 1;
+                    if count == 0 {
+                        // It's a headwater; add it to the stack
+                        stack.push((row, col));
+                        link_id[(row, col)] = current_id;
+                        current_id += 1;
+                    }
+                }
+            }
+            progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as usize;
+            if progress != old_progress {
+                sink.progress("Finding headwaters", progress)?;
+                old_progress = progress;
+            }
+        }
+
+        // Assign link identifiers in the same way as the StreamLinkIdentifier tool, with
+        // a new link beginning at each confluence.
+        let mut link_starts = vec![];
+        for &cell in &stack {
+            link_starts.push(cell);
+        }
+        let (mut row_n, mut col_n): (isize, isize);
+        let mut num_solved = 0usize;
+        while let Some((row, col)) = stack.pop() {
+            if let Some(c) = flow_dir(row, col)? {
+                row_n = row + dy[c];
+                col_n = col + dx[c];
+                if num_inflowing[(row_n, col_n)] > 1 {
+                    current_id += 1;
+                    link_id[(row_n, col_n)] = current_id;
+                    link_starts.push((row_n, col_n));
+                } else if num_inflowing[(row_n, col_n)] == 1 && link_id[(row_n, col_n)] == 0 {
+                    link_id[(row_n, col_n)] = link_id[(row, col)];
+                }
+
+                if num_inflowing[(row_n, col_n)] > 0 {
+                    num_inflowing.decrement(row_n, col_n, 1);
+                    if num_inflowing[(row_n, col_n)] == 0 {
+                        stack.push((row_n, col_n));
+                    }
+                }
+            }
+            num_solved += 1;
+            progress = (100.0_f64 * num_solved as f64 / num_stream_cells as f64) as usize;
+            if progress != old_progress {
+                sink.progress("Identifying links", progress)?;
+                old_progress = progress;
+            }
+        }
+
+        // Trace each link downstream from its first cell. A confluence reached by more
+        // than two tributaries is assigned several IDs in turn, of which the last is kept.
+        let num_ids = current_id as usize + 1;
+        let mut links: Vec<Option<StreamLink>> = vec![None; num_ids];
+        for &(row, col) in &link_starts {
+            let id = link_id[(row, col)];
+            if id <= 0 || links[id as usize].is_some() {
+                continue;
+            }
+            let mut link = StreamLink::new(id);
+            let (mut r, mut c) = (row, col);
+            loop {
+                link.cells.push((r, c));
+                if let Some(ref dem) = dem {
+                    let z = dem.get_value(r, c);
+                    if z != dem.configs.nodata {
+                        if z < link.min_elev { link.min_elev = z; }
+                        if z > link.max_elev { link.max_elev = z; }
+                    }
+                }
+                match flow_dir(r, c)? {
+                    Some(dir) => {
+                        link.length += grid_lengths[dir];
+                        r += dy[dir];
+                        c += dx[dir];
+                        if link_id[(r, c)] != id {
+                            // the cell that this link drains to, either the confluence at the
+                            // top of the downstream link or a cell off of the stream network
+                            link.outlet = (r, c);
+                            if link_id[(r, c)] > 0 {
+                                link.downstream_link = link_id[(r, c)];
+                            }
+                            break;
+                        }
+                    },
+                    None => {
+                        link.outlet = (r, c);
+                        break;
+                    }
+                }
+                if link.cells.len() > (rows * columns) as usize {
+                    return Err(Error::new(ErrorKind::InvalidInput,
+                        "A loop has been identified in the flow pointer. This tool requires a pointer grid that has been created using either the D8 or Rho8 tools."));
+                }
+            }
+            links[id as usize] = Some(link);
+        }
+
+        // Calculate the Strahler order and Shreve magnitude of each link, working downstream
+        // from the channel heads.
+        let mut num_upstream = vec![0usize; num_ids];
+        for link in links.iter().filter_map(|l| l.as_ref()) {
+            if link.downstream_link > 0 {
+                num_upstream[link.downstream_link as usize] += 1;
+            }
+        }
+        let mut max_upstream_order = vec![0i32; num_ids];
+        let mut num_max_order = vec![0i32; num_ids];
+        let mut upstream_magnitude = vec![0i32; num_ids];
+        let mut queue: Vec<usize> = (1..num_ids).filter(|&i| links[i].is_some() && num_upstream[i] == 0).collect();
+        while let Some(i) = queue.pop() {
+            let (strahler, shreve, downstream_link) = {
+                let link = links[i].as_mut().unwrap();
+                if max_upstream_order[i] == 0 {
+                    link.strahler = 1;
+                    link.shreve = 1;
+                } else {
+                    link.strahler = if num_max_order[i] > 1 { max_upstream_order[i] + 1 } else { max_upstream_order[i] };
+                    link.shreve = upstream_magnitude[i];
+                }
+                (link.strahler, link.shreve, link.downstream_link as usize)
+            };
+            if downstream_link > 0 {
+                if strahler > max_upstream_order[downstream_link] {
+                    max_upstream_order[downstream_link] = strahler;
+                    num_max_order[downstream_link] = 1;
+                } else if strahler == max_upstream_order[downstream_link] {
+                    num_max_order[downstream_link] += 1;
+                }
+                upstream_magnitude[downstream_link] += shreve;
+                num_upstream[downstream_link] -= 1;
+                if num_upstream[downstream_link] == 0 {
+                    queue.push(downstream_link);
+                }
+            }
+        }
+
+        // create the output file
+        let mut output = Shapefile::new(&output_file, "w")?;
+        output.header.shape_type = ShapeType::PolyLine;
+        if streams.configs.coordinate_ref_system_wkt != "not specified" {
+            output.projection = streams.configs.coordinate_ref_system_wkt.clone();
+        }
+
+        output.attributes.add_field(AttributeField::new("FID", 'N', 10u8, 0u8));
+        output.attributes.add_field(AttributeField::new("LINK_ID", 'N', 10u8, 0u8));
+        output.attributes.add_field(AttributeField::new("UP_NODE", 'N', 10u8, 0u8));
+        output.attributes.add_field(AttributeField::new("DN_NODE", 'N', 10u8, 0u8));
+        output.attributes.add_field(AttributeField::new("STRAHLER", 'N', 5u8, 0u8));
+        output.attributes.add_field(AttributeField::new("SHREVE", 'N', 10u8, 0u8));
+        output.attributes.add_field(AttributeField::new("LENGTH", 'F', 19u8, 8u8));
+        if use_dem {
+            output.attributes.add_field(AttributeField::new("SLOPE", 'F', 19u8, 8u8));
+        }
+
+        // Nodes are the first cell of each link and the cell that it drains to. The latter
+        // may lie off of the grid, so a map is used rather than an array. Outlet nodes are
+        // keyed separately, so that a single-cell link without a downslope neighbour does
+        // not begin and end at the same node.
+        let mut node_ids: HashMap<(isize, isize, bool), i32> = HashMap::new();
+        let mut fid = 0i32;
+        for i in 1..num_ids {
+            if let Some(ref link) = links[i] {
+                fid += 1;
+                let first = link.cells[0];
+                let num_nodes = node_ids.len() as i32;
+                let up_node = *node_ids.entry((first.0, first.1, false)).or_insert(num_nodes + 1);
+                let num_nodes = node_ids.len() as i32;
+                let is_outlet = link.downstream_link == 0;
+                let dn_node = *node_ids.entry((link.outlet.0, link.outlet.1, is_outlet)).or_insert(num_nodes + 1);
+
+                let mut points: Vec<Point2D> = link.cells.iter().map(|&(r, c)| Point2D {
+                    x: streams.get_x_from_column(c),
+                    y: streams.get_y_from_row(r)
+                }).collect();
+                if link.outlet != link.cells[link.cells.len() - 1] || points.len() == 1 {
+                    points.push(Point2D {
+                        x: streams.get_x_from_column(link.outlet.1),
+                        y: streams.get_y_from_row(link.outlet.0)
+                    });
+                }
+                let mut sfg = ShapefileGeometry::new(ShapeType::PolyLine);
+                sfg.add_part(&points);
+                output.add_record(sfg);
+
+                let mut rec = vec![
+                    FieldData::Int(fid),
+                    FieldData::Int(link.id),
+                    FieldData::Int(up_node),
+                    FieldData::Int(dn_node),
+                    FieldData::Int(link.strahler),
+                    FieldData::Int(link.shreve),
+                    FieldData::Real(link.length)
+                ];
+                if use_dem {
+                    if link.length > 0f64 && link.max_elev >= link.min_elev {
+                        rec.push(FieldData::Real((link.max_elev - link.min_elev) / link.length * 100f64));
+                    } else {
+                        rec.push(FieldData::Null);
+                    }
+                }
+                output.attributes.add_record(rec, false)?;
+            }
+
+            progress = (100.0_f64 * i as f64 / (num_ids - 1) as f64) as usize;
+            if progress != old_progress {
+                sink.progress("Creating polylines", progress)?;
+                old_progress = progress;
+            }
+        }
+
+        let end = time::now();
+        let elapsed_time = end - start;
+
+        if verbose { sink.message("Saving data...") };
+        let _ = match output.write() {
+            Ok(_) => if verbose { sink.message("Output file written") },
+            Err(e) => return Err(e),
+        };
+
+        if verbose {
+            sink.message(&format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Clone)]
+struct StreamLink {
+    id: i32,
+    cells: Vec<(isize, isize)>,
+    outlet: (isize, isize),
+    downstream_link: i32,
+    length: f64,
+    min_elev: f64,
+    max_elev: f64,
+    strahler: i32,
+    shreve: i32,
+}
+
+impl StreamLink {
+    fn new(id: i32) -> StreamLink {
+        StreamLink {
+            id: id,
+            cells: vec![],
+            outlet: (0, 0),
+            downstream_link: 0,
+            length: 0f64,
+            min_elev: f64::INFINITY,
+            max_elev: f64::NEG_INFINITY,
+            strahler: 0,
+            shreve: 0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::path::Path;
+
+    // Writes an in-memory raster with unit cells from rows of values.
+    fn int_raster(name: &str, values: &[&[i32]]) -> String {
+        let mut configs = RasterConfigs { ..Default::default() };
+        configs.rows = values.len();
+        configs.columns = values[0].len();
+        configs.nodata = -32768f64;
+        configs.north = 100f64;
+        configs.south = 100f64 - values.len() as f64;
+        configs.west = 50f64;
+        configs.east = 50f64 + values[0].len() as f64;
+        configs.resolution_x = 1f64;
+        configs.resolution_y = 1f64;
+        configs.data_type = DataType::I16;
+        let file_name = format!("{}{}", IN_MEMORY_RASTER_PREFIX, name);
+        let mut r = Raster::initialize_using_config(&file_name, &configs);
+        for row in 0..values.len() {
+            r.set_row_data(row as isize, values[row].iter().map(|&v| v as f64).collect());
+        }
+        r.write().unwrap();
+        file_name
+    }
+
+    fn int_field(sf: &Shapefile, record: usize, name: &str) -> i32 {
+        match sf.attributes.get_value(record, name) {
+            FieldData::Int(v) => v,
+            v => panic!("unexpected {} {:?}", name, v),
+        }
+    }
+
+    #[test]
+    fn links_join_at_confluences() {
+        // Two tributaries meet at row 2, column 2 and a third joins the main stem at
+        // row 4, column 2. The outlet is at the bottom of column 2.
+        let streams = int_raster("rstv_streams.tif", &[
+            &[1, 0, 0, 0, 1, 0, 0],
+            &[0, 1, 0, 1, 0, 0, 0],
+            &[0, 0, 1, 0, 0, 0, 0],
+            &[0, 0, 1, 0, 0, 0, 0],
+            &[0, 0, 1, 1, 1, 1, 0],
+            &[0, 0, 1, 0, 0, 0, 0],
+            &[0, 0, 1, 0, 0, 0, 0],
+        ]);
+        let pntr = int_raster("rstv_pntr.tif", &[
+            &[4, 0, 0, 0, 16, 0, 0],
+            &[0, 4, 0, 16, 0, 0, 0],
+            &[0, 0, 8, 0, 0, 0, 0],
+            &[0, 0, 8, 0, 0, 0, 0],
+            &[0, 0, 8, 32, 32, 32, 0],
+            &[0, 0, 8, 0, 0, 0, 0],
+            &[0, 0, 0, 0, 0, 0, 0],
+        ]);
+        let file_name = env::temp_dir().join("wbt_rstv_links.shp").to_str().unwrap().to_string();
+        let args = vec![format!("--streams={}", streams), format!("--d8_pntr={}", pntr), format!("-o={}", file_name)];
+        let mut sink = StdoutProgress::new();
+        sink.set_quiet(true);
+        let sink: Arc<ProgressSink> = Arc::new(sink);
+        RasterStreamsToVector::new().run(args, "", false, &sink).unwrap();
+
+        let sf = Shapefile::new(&file_name, "r").unwrap();
+        for ext in ["shp", "shx", "dbf", "cpg", "prj"].iter() {
+            let _ = fs::remove_file(Path::new(&file_name).with_extension(ext));
+        }
+        assert_eq!(sf.header.shape_type, ShapeType::PolyLine);
+        assert_eq!(sf.num_records, 5);
+
+        // (first cell, last vertex, number of vertices, Strahler order, Shreve magnitude)
+        let expected = [
+            ((0, 0), (2, 2), 3, 1, 1),
+            ((0, 4), (2, 2), 3, 1, 1),
+            ((2, 2), (4, 2), 3, 2, 2),
+            ((4, 5), (4, 2), 4, 1, 1),
+            ((4, 2), (6, 2), 3, 2, 3),
+        ];
+        let centre = |(row, col): (isize, isize)| (50.5 + col as f64, 99.5 - row as f64);
+        let mut record_of = vec![0usize; expected.len()];
+        for (i, &(first, last, num_points, strahler, shreve)) in expected.iter().enumerate() {
+            let rec = (0..sf.num_records).find(|&r| {
+                let p = &sf.get_record(r).points[0];
+                (p.x, p.y) == centre(first)
+            }).expect("no link starts at the expected cell");
+            let geom = sf.get_record(rec);
+            assert_eq!(geom.num_parts, 1);
+            assert_eq!(geom.parts, vec![0]);
+            assert_eq!(geom.points.len(), num_points);
+            let end = &geom.points[num_points - 1];
+            assert_eq!((end.x, end.y), centre(last));
+            assert_eq!(int_field(&sf, rec, "STRAHLER"), strahler);
+            assert_eq!(int_field(&sf, rec, "SHREVE"), shreve);
+            record_of[i] = rec;
+        }
+
+        // tributaries end at the node where the downstream link begins
+        let up = |i: usize| int_field(&sf, record_of[i], "UP_NODE");
+        let dn = |i: usize| int_field(&sf, record_of[i], "DN_NODE");
+        assert_eq!(dn(0), up(2));
+        assert_eq!(dn(1), up(2));
+        assert_eq!(dn(2), up(4));
+        assert_eq!(dn(3), up(4));
+        let mut nodes: Vec<i32> = (0..5).map(|i| up(i)).collect();
+        nodes.push(dn(4));
+        nodes.sort();
+        nodes.dedup();
+        assert_eq!(nodes.len(), 6);
+
+        match sf.attributes.get_value(record_of[3], "LENGTH") {
+            FieldData::Real(v) => assert!((v - 3f64).abs() < 1e-9),
+            v => panic!("unexpected LENGTH {:?}", v),
+        }
+        match sf.attributes.get_value(record_of[0], "LENGTH") {
+            FieldData::Real(v) => assert!((v - 2f64 * 2f64.sqrt()).abs() < 1e-6),
+            v => panic!("unexpected LENGTH {:?}", v),
+        }
+    }
+}
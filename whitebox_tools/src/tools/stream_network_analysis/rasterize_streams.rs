@@ -18,8 +18,10 @@ use std::f64;
 use raster::*;
 use vector::{Shapefile, ShapeType};
 use std::io::{Error, ErrorKind};
+use std::sync::Arc;
 use structures::{Array2D, BoundingBox};
 use tools::*;
+use tools::arg_parser::ArgValues;
 
 pub struct RasterizeStreams {
     name: String,
@@ -88,7 +90,7 @@ impl RasterizeStreams {
         if e.contains(".exe") {
             short_exe += ".exe";
         }
-        let usage = format!(">>.*{0} -r={1} -v --wd=\"*path*to*data*\" --d8_pntr=D8.dep --streams=streams.dep -o=output.dep", short_exe, name).replace("*", &sep);
+        let usage = format!(">>.*{0} -r={1} -v --wd=\"*path*to*data*\" --streams=streams.shp --base=raster.dep -o=output.dep", short_exe, name).replace("*", &sep);
     
         RasterizeStreams { 
             name: name, 
@@ -135,78 +137,33 @@ impl WhiteboxTool for RasterizeStreams {
         self.toolbox.clone()
     }
 
-    fn run<'a>(&self, args: Vec<String>, working_directory: &'a str, verbose: bool) -> Result<(), Error> {
-        let mut streams_file = String::new();
-        let mut base_file = String::new();
-        let mut output_file = String::new();
-        let mut out_nodata = false;
+    fn run<'a>(&self, args: Vec<String>, working_directory: &'a str, verbose: bool, sink: &Arc<ProgressSink>) -> Result<(), Error> {
         let mut background_val = 0f64;
-        let mut feature_id = false;
         
         if args.len() == 0 {
             return Err(Error::new(ErrorKind::InvalidInput,
                                 "Tool run with no paramters."));
         }
-        for i in 0..args.len() {
-            let mut arg = args[i].replace("\"", "");
-            arg = arg.replace("\'", "");
-            let cmd = arg.split("="); // in case an equals sign was used
-            let vec = cmd.collect::<Vec<&str>>();
-            let mut keyval = false;
-            if vec.len() > 1 {
-                keyval = true;
-            }
-            let flag_val = vec[0].to_lowercase().replace("--", "-");
-            if flag_val == "-streams" {
-                streams_file = if keyval {
-                    vec[1].to_string()
-                } else {
-                    args[i+1].to_string()
-                };
-            } else if flag_val == "-base" {
-                base_file = if keyval {
-                    vec[1].to_string()
-                } else {
-                    args[i+1].to_string()
-                };
-            } else if flag_val == "-o" || flag_val == "-output" {
-                output_file = if keyval {
-                    vec[1].to_string()
-                } else {
-                    args[i+1].to_string()
-                };
-            } else if flag_val == "-nodata" {
-                out_nodata = true;
-            } else if flag_val == "-feature_id" {
-                feature_id = true;
-            }
-        }
+        let args = ArgValues::parse(&self.parameters, &args)?;
+        let streams_file = args.required_file("--streams", working_directory)?;
+        let base_file = args.required_file("--base", working_directory)?;
+        let output_file = args.required_file("--output", working_directory)?;
+        let out_nodata = args.get_bool("--nodata")?;
+        let feature_id = args.get_bool("--feature_id")?;
 
         if verbose {
-            println!("***************{}", "*".repeat(self.get_tool_name().len()));
-            println!("* Welcome to {} *", self.get_tool_name());
-            println!("***************{}", "*".repeat(self.get_tool_name().len()));
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
+            sink.message(&format!("* Welcome to {} *", self.get_tool_name()));
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
         }
 
-        let sep: String = path::MAIN_SEPARATOR.to_string();
-
         let mut progress: usize;
         let mut old_progress: usize = 1;
 
-        if !streams_file.contains(&sep) && !streams_file.contains("/") {
-            streams_file = format!("{}{}", working_directory, streams_file);
-        }
-        if !base_file.contains(&sep) && !base_file.contains("/") {
-            base_file = format!("{}{}", working_directory, base_file);
-        }
-        if !output_file.contains(&sep) && !output_file.contains("/") {
-            output_file = format!("{}{}", working_directory, output_file);
-        }
-
-        if verbose { println!("Reading streams data...") };
+        if verbose { sink.message("Reading streams data...") };
         let streams = Shapefile::new(&streams_file, "r")?;
         
-        if verbose { println!("Reading base raster data...") };
+        if verbose { sink.message("Reading base raster data...") };
         let base = Raster::new(&base_file, "r")?;
         let rows = base.configs.rows as isize;
         let columns = base.configs.columns as isize;
@@ -345,12 +302,10 @@ impl WhiteboxTool for RasterizeStreams {
             }
 
             count += 1f64;
-            if verbose {
-                progress = (100.0_f64 * count / (streams.num_records - 1) as f64) as usize;
-                if progress != old_progress {
-                    println!("Rasterizing Streams: {}%", progress);
-                    old_progress = progress;
-                }
+            progress = (100.0_f64 * count / (streams.num_records - 1) as f64) as usize;
+            if progress != old_progress {
+                sink.progress("Rasterizing Streams", progress)?;
+                old_progress = progress;
             }
         }
 
@@ -361,7 +316,7 @@ impl WhiteboxTool for RasterizeStreams {
         let dx4 = [ 1isize, 0isize, -1isize, 0isize ];
 		let dy4 = [ 0isize, 1isize, 0isize, -1isize ];
         let (mut row_n, mut col_n): (isize, isize);
-        println!("Counting stream cell adjacencies...");
+        sink.message("Counting stream cell adjacencies...");
         for row in 0..rows {
             for col in 0..columns {
                 id = output.get_value(row, col);
@@ -381,17 +336,15 @@ impl WhiteboxTool for RasterizeStreams {
                     }
                 }
             }
-            if verbose {
-                progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as usize;
-                if progress != old_progress {
-                    println!("Progress: {}%", progress);
-                    old_progress = progress;
-                }
+            progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as usize;
+            if progress != old_progress {
+                sink.progress("Progress", progress)?;
+                old_progress = progress;
             }
         }
 
         if !feature_id {
-            println!("Updating output...");
+            sink.message("Updating output...");
             for row in 0..rows {
                 for col in 0..columns {
                     z = output.get_value(row, col);
@@ -399,20 +352,18 @@ impl WhiteboxTool for RasterizeStreams {
                         output.set_value(row, col, 1f64);
                     }
                 }
-                if verbose {
-                    progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as usize;
-                    if progress != old_progress {
-                        println!("Progress: {}%", progress);
-                        old_progress = progress;
-                    }
+                progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as usize;
+                if progress != old_progress {
+                    sink.progress("Progress", progress)?;
+                    old_progress = progress;
                 }
             }
         }
 
         if verbose {
-            println!("Number of stream cells: {}", num_stream_cells);
-            println!("Number of stream collisions: {}", num_link_collisions);
-            println!("Number of stream adjacencies: {}", num_adjacencies);
+            sink.message(&format!("Number of stream cells: {}", num_stream_cells));
+            sink.message(&format!("Number of stream collisions: {}", num_link_collisions));
+            sink.message(&format!("Number of stream adjacencies: {}", num_adjacencies));
         }
 
         let end = time::now();
@@ -424,13 +375,13 @@ impl WhiteboxTool for RasterizeStreams {
         output.add_metadata_entry(format!("Input base file: {}", base_file));
         output.add_metadata_entry(format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
 
-        if verbose { println!("Saving data...") };
+        if verbose { sink.message("Saving data...") };
         let _ = match output.write() {
-            Ok(_) => if verbose { println!("Output file written") },
+            Ok(_) => if verbose { sink.message("Output file written") },
             Err(e) => return Err(e),
         };
         if verbose {
-            println!("{}", &format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
+            sink.message(&format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
         }
 
         Ok(())
@@ -17,6 +17,7 @@ use std::sync::mpsc;
 use std::thread;
 use raster::*;
 use tools::*;
+use tools::arg_parser::ArgValues;
 
 pub struct StreamSlopeContinuous {
     name: String,
@@ -71,7 +72,7 @@ impl StreamSlopeContinuous {
 
         parameters.push(ToolParameter{
             name: "Does the pointer file use the ESRI pointer scheme?".to_owned(), 
-            flags: vec!["--esri_pntr".to_owned()], 
+            flags: vec!["--esri_style".to_owned(), "--esri_pntr".to_owned()], 
             description: "D8 pointer uses the ESRI style scheme.".to_owned(),
             parameter_type: ParameterType::Boolean,
             default_value: Some("false".to_owned()),
@@ -94,7 +95,7 @@ impl StreamSlopeContinuous {
         if e.contains(".exe") {
             short_exe += ".exe";
         }
-        let usage = format!(">>.*{0} -r={1} -v --wd=\"*path*to*data*\" --d8_pntr=D8.dep --linkid=streamsID.dep --dem=dem.dep -o=output.dep
+        let usage = format!(">>.*{0} -r={1} -v --wd=\"*path*to*data*\" --d8_pntr=D8.dep --streams=streamsID.dep --dem=dem.dep -o=output.dep
 >>.*{0} -r={1} -v --wd=\"*path*to*data*\" --d8_pntr=D8.flt --streams=streamsID.flt --dem=dem.flt -o=output.flt --esri_pntr --zero_background", short_exe, name).replace("*", &sep);
     
         StreamSlopeContinuous { 
@@ -142,88 +143,34 @@ impl WhiteboxTool for StreamSlopeContinuous {
         self.toolbox.clone()
     }
 
-    fn run<'a>(&self, args: Vec<String>, working_directory: &'a str, verbose: bool) -> Result<(), Error> {
-        let mut d8_file = String::new();
-        let mut streams_file = String::new();
-        let mut dem_file = String::new();
-        let mut output_file = String::new();
-        let mut esri_style = false;
-        let mut background_val = f64::NEG_INFINITY;
-        
+    fn run<'a>(&self, args: Vec<String>, working_directory: &'a str, verbose: bool, sink: &Arc<ProgressSink>) -> Result<(), Error> {
         if args.len() == 0 {
             return Err(Error::new(ErrorKind::InvalidInput,
                                 "Tool run with no paramters."));
         }
-        for i in 0..args.len() {
-            let mut arg = args[i].replace("\"", "");
-            arg = arg.replace("\'", "");
-            let cmd = arg.split("="); // in case an equals sign was used
-            let vec = cmd.collect::<Vec<&str>>();
-            let mut keyval = false;
-            if vec.len() > 1 {
-                keyval = true;
-            }
-            if vec[0].to_lowercase() == "-d8_pntr" || vec[0].to_lowercase() == "--d8_pntr" {
-                if keyval {
-                    d8_file = vec[1].to_string();
-                } else {
-                    d8_file = args[i+1].to_string();
-                }
-            } else if vec[0].to_lowercase() == "-streams" || vec[0].to_lowercase() == "--streams" {
-                if keyval {
-                    streams_file = vec[1].to_string();
-                } else {
-                    streams_file = args[i+1].to_string();
-                }
-            } else if vec[0].to_lowercase() == "-dem" || vec[0].to_lowercase() == "--dem" {
-                if keyval {
-                    dem_file = vec[1].to_string();
-                } else {
-                    dem_file = args[i+1].to_string();
-                }
-            } else if vec[0].to_lowercase() == "-o" || vec[0].to_lowercase() == "--output" {
-                if keyval {
-                    output_file = vec[1].to_string();
-                } else {
-                    output_file = args[i+1].to_string();
-                }
-            } else if vec[0].to_lowercase() == "-esri_pntr" || vec[0].to_lowercase() == "--esri_pntr" || vec[0].to_lowercase() == "--esri_style" {
-                esri_style = true;
-            } else if vec[0].to_lowercase() == "-zero_background" || vec[0].to_lowercase() == "--zero_background" {
-                background_val = 0f64;
-            }
-        }
+        let args = ArgValues::parse(&self.parameters, &args)?;
+        let d8_file = args.required_file("--d8_pntr", working_directory)?;
+        let streams_file = args.required_file("--streams", working_directory)?;
+        let dem_file = args.required_file("--dem", working_directory)?;
+        let output_file = args.required_file("--output", working_directory)?;
+        let esri_style = args.get_bool("--esri_pntr")?;
+        let mut background_val = if args.get_bool("--zero_background")? { 0f64 } else { f64::NEG_INFINITY };
 
         if verbose {
-            println!("***************{}", "*".repeat(self.get_tool_name().len()));
-            println!("* Welcome to {} *", self.get_tool_name());
-            println!("***************{}", "*".repeat(self.get_tool_name().len()));
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
+            sink.message(&format!("* Welcome to {} *", self.get_tool_name()));
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
         }
 
-        let sep: String = path::MAIN_SEPARATOR.to_string();
-
         let mut progress: usize;
         let mut old_progress: usize = 1;
 
-        if !d8_file.contains(&sep) && !d8_file.contains("/") {
-            d8_file = format!("{}{}", working_directory, d8_file);
-        }
-        if !streams_file.contains(&sep) && !streams_file.contains("/") {
-            streams_file = format!("{}{}", working_directory, streams_file);
-        }
-        if !dem_file.contains(&sep) && !dem_file.contains("/") {
-            dem_file = format!("{}{}", working_directory, dem_file);
-        }
-        if !output_file.contains(&sep) && !output_file.contains("/") {
-            output_file = format!("{}{}", working_directory, output_file);
-        }
-
-        if verbose { println!("Reading pointer data...") };
+        if verbose { sink.message("Reading pointer data...") };
         let pntr = Arc::new(Raster::new(&d8_file, "r")?);
         let pntr_nodata = pntr.configs.nodata;
-        if verbose { println!("Reading link ID data...") };
+        if verbose { sink.message("Reading link ID data...") };
         let streams = Arc::new(Raster::new(&streams_file, "r")?);
-        if verbose { println!("Reading DEM data...") };
+        if verbose { sink.message("Reading DEM data...") };
         let dem = Arc::new(Raster::new(&dem_file, "r")?);
         
         let start = time::now();
@@ -307,17 +254,17 @@ impl WhiteboxTool for StreamSlopeContinuous {
                 for row in (0..rows).filter(|r| r % num_procs == tid) {
                     let mut data = vec![nodata; columns as usize];
                     for col in 0..columns {
-                        if streams[(row, col)] > 0f64 && streams[(row, col)] != nodata && 
-                           dem[(row, col)] != dem_nodata && pntr[(row, col)] != pntr_nodata {
+                        if streams.get_value(row, col) > 0f64 && streams.get_value(row, col) != nodata && 
+                           dem.get_value(row, col) != dem_nodata && pntr.get_value(row, col) != pntr_nodata {
                             // first get the average elevation and distance of the inflowing stream cells
                             z_inflowing = 0f64;
                             dist = 0f64;
                             n_inflowing = 0f64;
                             for n in 0..8 {
-                                if streams[(row + dy[n], col + dx[n])] > 0.0 &&
-                                    streams[(row + dy[n], col + dx[n])] != nodata &&
-                                    pntr[(row + dy[n], col + dx[n])] == inflowing_vals[n] {
-                                    z_inflowing += dem[(row + dy[n], col + dx[n])];
+                                if streams.get_value(row + dy[n], col + dx[n]) > 0.0 &&
+                                    streams.get_value(row + dy[n], col + dx[n]) != nodata &&
+                                    pntr.get_value(row + dy[n], col + dx[n]) == inflowing_vals[n] {
+                                    z_inflowing += dem.get_value(row + dy[n], col + dx[n]);
                                     dist += grid_lengths[n];
                                     n_inflowing += 1f64;
                                 }
@@ -326,21 +273,21 @@ impl WhiteboxTool for StreamSlopeContinuous {
                                 z_inflowing = z_inflowing / n_inflowing;
                                 dist = dist / n_inflowing;
                             } else {
-                                z_inflowing = dem[(row, col)];
+                                z_inflowing = dem.get_value(row, col);
                             }
 
                             // now find the elevation of the downslope stream cell
-                            dir = pntr[(row, col)] as usize;
+                            dir = pntr.get_value(row, col) as usize;
                             if dir > 0 {
                                 c = pntr_matches[dir];
-                                z_dn = dem[(row + dy[c], col + dx[c])];
+                                z_dn = dem.get_value(row + dy[c], col + dx[c]);
                                 if z_dn != dem_nodata {
                                     dist += grid_lengths[c];
                                 } else {
-                                    z_dn = dem[(row, col)];
+                                    z_dn = dem.get_value(row, col);
                                 }
                             } else {
-                                z_dn = dem[(row, col)];
+                                z_dn = dem.get_value(row, col);
                             }
                             if dist > 0f64 {
                                 data[col as usize] = ((z_inflowing - z_dn) / dist).atan().to_degrees();
@@ -360,12 +307,10 @@ impl WhiteboxTool for StreamSlopeContinuous {
             let data = rx.recv().unwrap();
             output.set_row_data(data.0, data.1);
             
-            if verbose {
-                progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as usize;
-                if progress != old_progress {
-                    println!("Performing analysis: {}%", progress);
-                    old_progress = progress;
-                }
+            progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as usize;
+            if progress != old_progress {
+                sink.progress("Performing analysis", progress)?;
+                old_progress = progress;
             }
         }
 
@@ -384,13 +329,13 @@ impl WhiteboxTool for StreamSlopeContinuous {
         output.add_metadata_entry(format!("Input DEM file: {}", dem_file));
         output.add_metadata_entry(format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
 
-        if verbose { println!("Saving data...") };
+        if verbose { sink.message("Saving data...") };
         let _ = match output.write() {
-            Ok(_) => if verbose { println!("Output file written") },
+            Ok(_) => if verbose { sink.message("Output file written") },
             Err(e) => return Err(e),
         };
         if verbose {
-            println!("{}", &format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
+            sink.message(&format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
         }
 
         Ok(())
@@ -0,0 +1,385 @@
+/*
+This tool is part of the WhiteboxTools geospatial analysis library.
+Authors: Dr. John Lindsay
+Created: April 1, 2018
+Last Modified: April 1, 2018
+License: MIT
+
+Help: This tool creates a vector contour coverage from the ground returns of a LiDAR point
+cloud (--input). The points that are classified as ground (class 2), and that have not been
+withheld, are connected by a Delaunay triangulated irregular network (TIN), and contours
+are traced across the triangles by linear interpolation of the point elevations along the
+triangle edges. The contour levels are the values of the base contour (--base) plus
+multiples of the contour interval (--interval). A point elevation that is exactly equal to
+a contour level is treated as lying above it, which ensures that contours never cross or
+branch. Points with duplicate x and y coordinates are only included once.
+
+The TIN covers the convex hull of the ground points, and so contours end at the edge of the
+hull; lines that do not end there are closed. The optional --smooth parameter specifies the
+size of a moving average filter, in vertices, that is applied to the contour lines, e.g.
+3, 5, 7, 9.
+
+The output vector polyline file contains one feature for each contour line, with an ELEV
+field that holds the contour level. The ground points of a LAS file can be classified
+using the LidarGroundPointFilter tool.
+
+See Also: ContoursFromRaster, LidarGroundPointFilter
+*/
+extern crate time;
+
+use std::env;
+use std::f64;
+use std::path;
+use lidar::*;
+use vector::*;
+use spatial_ref_system::read_spatial_ref;
+use structures::Triangulation;
+use std::io::{Error, ErrorKind};
+use std::sync::Arc;
+use tools::*;
+use tools::arg_parser::ArgValues;
+use super::contours_from_raster::{level_range, smooth_line, ContourLines};
+
+pub struct ContoursFromPoints {
+    name: String,
+    description: String,
+    toolbox: String,
+    parameters: Vec<ToolParameter>,
+    example_usage: String,
+}
+
+impl ContoursFromPoints {
+    pub fn new() -> ContoursFromPoints { // public constructor
+        let name = "ContoursFromPoints".to_string();
+        let toolbox = "Geomorphometric Analysis".to_string();
+        let description = "Derives a vector contour coverage from the ground returns of a LiDAR point cloud, using a TIN.".to_string();
+
+        let mut parameters = vec![];
+        parameters.push(ToolParameter{
+            name: "Input LiDAR File".to_owned(),
+            flags: vec!["-i".to_owned(), "--input".to_owned()],
+            description: "Input LiDAR file.".to_owned(),
+            parameter_type: ParameterType::ExistingFile(ParameterFileType::Lidar),
+            default_value: None,
+            optional: false
+        });
+
+        parameters.push(ToolParameter{
+            name: "Output Contour File".to_owned(),
+            flags: vec!["-o".to_owned(), "--output".to_owned()],
+            description: "Output vector contour file.".to_owned(),
+            parameter_type: ParameterType::NewFile(ParameterFileType::Vector(VectorGeometryType::Line)),
+            default_value: None,
+            optional: false
+        });
+
+        parameters.push(ToolParameter{
+            name: "Contour Interval".to_owned(),
+            flags: vec!["--interval".to_owned()],
+            description: "Contour interval.".to_owned(),
+            parameter_type: ParameterType::Float,
+            default_value: Some("10.0".to_owned()),
+            optional: true
+        });
+
+        parameters.push(ToolParameter{
+            name: "Base Contour".to_owned(),
+            flags: vec!["--base".to_owned()],
+            description: "Base contour height.".to_owned(),
+            parameter_type: ParameterType::Float,
+            default_value: Some("0.0".to_owned()),
+            optional: true
+        });
+
+        parameters.push(ToolParameter{
+            name: "Smoothing Filter Size".to_owned(),
+            flags: vec!["--smooth".to_owned()],
+            description: "Optional smoothing filter size (in vertices), e.g. 3, 5, 7, 9; 0 for no smoothing.".to_owned(),
+            parameter_type: ParameterType::Integer,
+            default_value: Some("0".to_owned()),
+            optional: true
+        });
+
+        let sep: String = path::MAIN_SEPARATOR.to_string();
+        let p = format!("{}", env::current_dir().unwrap().display());
+        let e = format!("{}", env::current_exe().unwrap().display());
+        let mut short_exe = e.replace(&p, "").replace(".exe", "").replace(".", "").replace(&sep, "");
+        if e.contains(".exe") {
+            short_exe += ".exe";
+        }
+        let usage = format!(">>.*{0} -r={1} -v --wd=\"*path*to*data*\" -i=points.las -o=contours.shp --interval=1.0
+>>.*{0} -r={1} -v --wd=\"*path*to*data*\" -i=points.las -o=contours.shp --interval=0.5 --base=0.25 --smooth=5", short_exe, name).replace("*", &sep);
+
+        ContoursFromPoints {
+            name: name,
+            description: description,
+            toolbox: toolbox,
+            parameters: parameters,
+            example_usage: usage
+        }
+    }
+}
+
+impl WhiteboxTool for ContoursFromPoints {
+    fn get_source_file(&self) -> String {
+        String::from(file!())
+    }
+
+    fn get_tool_name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn get_tool_description(&self) -> String {
+        self.description.clone()
+    }
+
+    fn get_tool_parameters(&self) -> String {
+        match serde_json::to_string(&self.parameters) {
+            Ok(json_str) => return format!("{{\"parameters\":{}}}", json_str),
+            Err(err) => return format!("{:?}", err),
+        }
+    }
+
+    fn get_example_usage(&self) -> String {
+        self.example_usage.clone()
+    }
+
+    fn get_toolbox(&self) -> String {
+        self.toolbox.clone()
+    }
+
+    fn run<'a>(&self, args: Vec<String>, working_directory: &'a str, verbose: bool, sink: &Arc<ProgressSink>) -> Result<(), Error> {
+        if args.len() == 0 {
+            return Err(Error::new(ErrorKind::InvalidInput,
+                                "Tool run with no paramters."));
+        }
+        let args = ArgValues::parse(&self.parameters, &args)?;
+        let input_file = args.required_file("--input", working_directory)?;
+        let output_file = args.required_file("--output", working_directory)?;
+        let interval = args.get_f64("--interval")?.unwrap_or(10f64);
+        let base = args.get_f64("--base")?.unwrap_or(0f64);
+        let filter_size = args.get_usize("--smooth")?.unwrap_or(0);
+
+        if verbose {
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
+            sink.message(&format!("* Welcome to {} *", self.get_tool_name()));
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
+        }
+
+        if !(interval > 0f64) {
+            return Err(Error::new(ErrorKind::InvalidInput,
+                                "The contour interval must be greater than zero."));
+        }
+
+        let mut progress: usize;
+        let mut old_progress: usize = 1;
+
+
+        if verbose { sink.message("Reading input LAS file...") };
+        let mut input = match LasReader::new(&input_file) {
+            Ok(lf) => lf,
+            Err(_) => return Err(Error::new(ErrorKind::NotFound, format!("No such file or directory ({})", input_file))),
+        };
+        let n_points = input.header.number_of_points as usize;
+
+        let start = time::now();
+
+        // the points are read in chunks, and only the ground returns are kept
+        let mut points: Vec<Point2D> = vec![];
+        let mut z_values: Vec<f64> = vec![];
+        while let Some(chunk) = input.next() {
+            for point in chunk? {
+                let p = point.point_data();
+                if !p.withheld() && p.classification() == 2 {
+                    points.push(Point2D { x: p.x, y: p.y });
+                    z_values.push(p.z);
+                }
+            }
+            progress = (100.0_f64 * input.points_read() as f64 / n_points.max(1) as f64) as usize;
+            if progress != old_progress {
+                sink.progress("Reading points", progress)?;
+                old_progress = progress;
+            }
+        }
+        if points.len() == 0 {
+            return Err(Error::new(ErrorKind::InvalidInput,
+                "The input file does not contain any points classified as ground (class 2). The LidarGroundPointFilter tool can be used to classify them."));
+        }
+
+        if verbose { sink.message("Creating the TIN...") };
+        let tin = Triangulation::new(&points);
+        if tin.num_triangles() == 0 {
+            return Err(Error::new(ErrorKind::InvalidInput,
+                "The ground points of the input file could not be triangulated, because there are fewer than three of them or they are collinear."));
+        }
+
+        // A crossing point is identified by the lower of the two halfedges of the triangle
+        // edge that it lies on; hull edges only have one.
+        let mut contours = ContourLines::new();
+        let num_triangles = tin.num_triangles();
+        let mut crossings = Vec::with_capacity(2);
+        for t in 0..num_triangles {
+            let vertices = tin.triangle_points(t);
+            let z = [z_values[vertices[0]], z_values[vertices[1]], z_values[vertices[2]]];
+            let min_z = z.iter().fold(f64::INFINITY, |a, &b| a.min(b));
+            let max_z = z.iter().fold(f64::NEG_INFINITY, |a, &b| a.max(b));
+            let (k_low, k_high) = level_range(min_z, max_z, base, interval);
+            for k in k_low..k_high + 1 {
+                let level = base + k as f64 * interval;
+                crossings.clear();
+                for a in 0..3 {
+                    let b = (a + 1) % 3;
+                    if (z[a] >= level) != (z[b] >= level) {
+                        let e = 3 * t + a;
+                        let edge = e.min(tin.halfedges[e]);
+                        let s = (level - z[a]) / (z[b] - z[a]);
+                        let (p1, p2) = (&points[vertices[a]], &points[vertices[b]]);
+                        crossings.push((edge, Point2D { x: p1.x + s * (p2.x - p1.x), y: p1.y + s * (p2.y - p1.y) }));
+                    }
+                }
+                if crossings.len() == 2 {
+                    contours.add_segment(k, crossings[0].0, &crossings[0].1, crossings[1].0, &crossings[1].1);
+                }
+            }
+            progress = (100.0_f64 * t as f64 / (num_triangles - 1).max(1) as f64) as usize;
+            if progress != old_progress {
+                sink.progress("Tracing contours", progress)?;
+                old_progress = progress;
+            }
+        }
+
+        // create the output file
+        let mut output = Shapefile::new(&output_file, "w")?;
+        output.header.shape_type = ShapeType::PolyLine;
+        if let Some(srs) = read_spatial_ref(&input_file)? {
+            output.projection = srs.to_wkt();
+        }
+        output.attributes.add_field(AttributeField::new("FID", 'N', 10u8, 0u8));
+        output.attributes.add_field(AttributeField::new("ELEV", 'F', 19u8, 8u8));
+
+        let lines = contours.trace();
+        let num_lines = lines.len();
+        for (i, (k, mut line)) in lines.into_iter().enumerate() {
+            smooth_line(&mut line, filter_size);
+            let mut sfg = ShapefileGeometry::new(ShapeType::PolyLine);
+            sfg.add_part(&line);
+            output.add_record(sfg);
+            output.attributes.add_record(vec![
+                FieldData::Int(i as i32 + 1),
+                FieldData::Real(base + k as f64 * interval)
+            ], false)?;
+
+            progress = (100.0_f64 * i as f64 / (num_lines - 1).max(1) as f64) as usize;
+            if progress != old_progress {
+                sink.progress("Creating polylines", progress)?;
+                old_progress = progress;
+            }
+        }
+
+        let end = time::now();
+        let elapsed_time = end - start;
+
+        if verbose { sink.message("Saving data...") };
+        let _ = match output.write() {
+            Ok(_) => if verbose { sink.message("Output file written") },
+            Err(e) => return Err(e),
+        };
+
+        if verbose {
+            sink.message(&format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::path::Path;
+
+    fn temp_file(name: &str) -> String {
+        env::temp_dir().join(name).to_str().unwrap().to_string()
+    }
+
+    // Writes a LAS file of points on a jittered grid, sampling a cone centred on (20, 20),
+    // with spikes that are either unclassified or withheld.
+    fn cone_points(file_name: &str, ground_class: u8) {
+        let mut output = LasFile::new(file_name, "w").unwrap();
+        output.add_header(LasHeader { point_format: 0, x_scale_factor: 0.0001, y_scale_factor: 0.0001,
+            z_scale_factor: 0.0001, ..Default::default() });
+        let mut seed = 42u64;
+        let mut jitter = || {
+            seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            ((seed >> 11) as f64 / (1u64 << 53) as f64 - 0.5) * 0.5
+        };
+        for row in 0..41 {
+            for col in 0..41 {
+                let (x, y) = (col as f64 + jitter(), row as f64 + jitter());
+                let mut p = PointData { x: x, y: y, z: ((x - 20.0) * (x - 20.0) + (y - 20.0) * (y - 20.0)).sqrt(), ..Default::default() };
+                p.set_classification(ground_class);
+                if (row + col) % 7 == 0 {
+                    p.z += 1000.0;
+                    if (row + col) % 2 == 0 { p.set_classification(1); } else { p.set_withheld(true); }
+                }
+                output.add_point_record(LidarPointRecord::PointRecord0 { point_data: p });
+            }
+        }
+        output.write().unwrap();
+    }
+
+    fn run_tool(input: &str, output: &str) -> Result<(), Error> {
+        let args = vec![format!("-i={}", input), format!("-o={}", output), "--interval=5".to_string()];
+        let mut sink = StdoutProgress::new();
+        sink.set_quiet(true);
+        let sink: Arc<ProgressSink> = Arc::new(sink);
+        ContoursFromPoints::new().run(args, "", false, &sink)
+    }
+
+    #[test]
+    fn cone_contours_are_closed_circles() {
+        let (input, output) = (temp_file("wbt_cfp_cone.las"), temp_file("wbt_cfp_cone.shp"));
+        cone_points(&input, 2);
+        run_tool(&input, &output).unwrap();
+
+        let sf = Shapefile::new(&output, "r").unwrap();
+        let mut levels = vec![];
+        for i in 0..sf.num_records {
+            let elev = match sf.attributes.get_value(i, "ELEV") {
+                FieldData::Real(v) => v,
+                v => panic!("unexpected ELEV {:?}", v),
+            };
+            // the spikes are excluded, and so no contour is above the cone
+            assert!(elev <= 25.0);
+            levels.push(elev);
+            if elev > 15.0 {
+                continue;
+            }
+            let points = &sf.get_record(i).points;
+            let (first, last) = (&points[0], &points[points.len() - 1]);
+            assert!(first.x == last.x && first.y == last.y);
+            for p in points {
+                let d = ((p.x - 20.0) * (p.x - 20.0) + (p.y - 20.0) * (p.y - 20.0)).sqrt();
+                assert!((d - elev).abs() < 0.25, "level {}, distance {}", elev, d);
+            }
+        }
+        // one closed line for each level that lies within the hull of the points
+        for &level in [5.0, 10.0, 15.0].iter() {
+            assert_eq!(levels.iter().filter(|&&l| l == level).count(), 1, "level {}", level);
+        }
+        let _ = fs::remove_file(&input);
+        for ext in ["shp", "shx", "dbf", "cpg"].iter() {
+            let _ = fs::remove_file(Path::new(&output).with_extension(ext));
+        }
+    }
+
+    #[test]
+    fn ground_points_are_required() {
+        let (input, output) = (temp_file("wbt_cfp_unclassified.las"), temp_file("wbt_cfp_unclassified.shp"));
+        cone_points(&input, 1);
+        assert_eq!(run_tool(&input, &output).unwrap_err().kind(), ErrorKind::InvalidInput);
+        assert_eq!(run_tool(&temp_file("wbt_cfp_missing.las"), &output).unwrap_err().kind(), ErrorKind::NotFound);
+        let _ = fs::remove_file(&input);
+    }
+}
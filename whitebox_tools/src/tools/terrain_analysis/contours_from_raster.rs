@@ -0,0 +1,632 @@
+/*
+This tool is part of the WhiteboxTools geospatial analysis library.
+Authors: Dr. John Lindsay
+Created: April 1, 2018
+Last Modified: April 1, 2018
+License: MIT
+
+Help: This tool creates a vector contour coverage from an input raster surface model (--input),
+such as a digital elevation model (DEM). Contours are traced using the marching squares
+algorithm, which finds where each contour level crosses the lines connecting the centres of
+neighbouring grid cells, using linear interpolation between cell values. The contour levels
+are the values of the base contour (--base) plus multiples of the contour interval
+(--interval). A cell value that is exactly equal to a contour level is treated as lying
+above it, and ambiguous saddle points are resolved using the average of the four
+surrounding cells, which ensures that contours never cross or branch.
+
+Contours are not traced across NoData cells, and so lines end at the edges of areas of
+NoData, as well as at the edges of the grid. Lines that do not end at an edge are closed.
+
+Marching squares contours have a vertex at each grid cell edge that they cross, which can
+give them a jagged appearance. The optional --smooth parameter specifies the size of a
+moving average filter, in vertices, that is applied to the contour lines, e.g. 3, 5, 7, 9.
+The end points of open lines are not moved by smoothing.
+
+The output vector polyline file contains one feature for each contour line, with an ELEV
+field that holds the contour level.
+
+See Also: ContoursFromPoints
+*/
+extern crate time;
+
+use std::collections::HashMap;
+use std::env;
+use std::f64;
+use std::path;
+use std::usize;
+use raster::*;
+use vector::*;
+use std::io::{Error, ErrorKind};
+use std::sync::Arc;
+use tools::*;
+use tools::arg_parser::ArgValues;
+
+pub struct ContoursFromRaster {
+    name: String,
+    description: String,
+    toolbox: String,
+    parameters: Vec<ToolParameter>,
+    example_usage: String,
+}
+
+impl ContoursFromRaster {
+    pub fn new() -> ContoursFromRaster { // public constructor
+        let name = "ContoursFromRaster".to_string();
+        let toolbox = "Geomorphometric Analysis".to_string();
+        let description = "Derives a vector contour coverage from a raster surface.".to_string();
+
+        let mut parameters = vec![];
+        parameters.push(ToolParameter{
+            name: "Input Raster Surface File".to_owned(),
+            flags: vec!["-i".to_owned(), "--input".to_owned()],
+            description: "Input surface raster file.".to_owned(),
+            parameter_type: ParameterType::ExistingFile(ParameterFileType::Raster),
+            default_value: None,
+            optional: false
+        });
+
+        parameters.push(ToolParameter{
+            name: "Output Contour File".to_owned(),
+            flags: vec!["-o".to_owned(), "--output".to_owned()],
+            description: "Output vector contour file.".to_owned(),
+            parameter_type: ParameterType::NewFile(ParameterFileType::Vector(VectorGeometryType::Line)),
+            default_value: None,
+            optional: false
+        });
+
+        parameters.push(ToolParameter{
+            name: "Contour Interval".to_owned(),
+            flags: vec!["--interval".to_owned()],
+            description: "Contour interval.".to_owned(),
+            parameter_type: ParameterType::Float,
+            default_value: Some("10.0".to_owned()),
+            optional: true
+        });
+
+        parameters.push(ToolParameter{
+            name: "Base Contour".to_owned(),
+            flags: vec!["--base".to_owned()],
+            description: "Base contour height.".to_owned(),
+            parameter_type: ParameterType::Float,
+            default_value: Some("0.0".to_owned()),
+            optional: true
+        });
+
+        parameters.push(ToolParameter{
+            name: "Smoothing Filter Size".to_owned(),
+            flags: vec!["--smooth".to_owned()],
+            description: "Optional smoothing filter size (in vertices), e.g. 3, 5, 7, 9; 0 for no smoothing.".to_owned(),
+            parameter_type: ParameterType::Integer,
+            default_value: Some("0".to_owned()),
+            optional: true
+        });
+
+        let sep: String = path::MAIN_SEPARATOR.to_string();
+        let p = format!("{}", env::current_dir().unwrap().display());
+        let e = format!("{}", env::current_exe().unwrap().display());
+        let mut short_exe = e.replace(&p, "").replace(".exe", "").replace(".", "").replace(&sep, "");
+        if e.contains(".exe") {
+            short_exe += ".exe";
+        }
+        let usage = format!(">>.*{0} -r={1} -v --wd=\"*path*to*data*\" -i=DEM.tif -o=contours.shp --interval=10.0
+>>.*{0} -r={1} -v --wd=\"*path*to*data*\" -i=DEM.tif -o=contours.shp --interval=2.0 --base=0.5 --smooth=5", short_exe, name).replace("*", &sep);
+
+        ContoursFromRaster {
+            name: name,
+            description: description,
+            toolbox: toolbox,
+            parameters: parameters,
+            example_usage: usage
+        }
+    }
+}
+
+impl WhiteboxTool for ContoursFromRaster {
+    fn get_source_file(&self) -> String {
+        String::from(file!())
+    }
+
+    fn get_tool_name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn get_tool_description(&self) -> String {
+        self.description.clone()
+    }
+
+    fn get_tool_parameters(&self) -> String {
+        match serde_json::to_string(&self.parameters) {
+            Ok(json_str) => return format!("{{\"parameters\":{}}}", json_str),
+            Err(err) => return format!("{:?}", err),
+        }
+    }
+
+    fn get_example_usage(&self) -> String {
+        self.example_usage.clone()
+    }
+
+    fn get_toolbox(&self) -> String {
+        self.toolbox.clone()
+    }
+
+    fn run<'a>(&self, args: Vec<String>, working_directory: &'a str, verbose: bool, sink: &Arc<ProgressSink>) -> Result<(), Error> {
+        if args.len() == 0 {
+            return Err(Error::new(ErrorKind::InvalidInput,
+                                "Tool run with no paramters."));
+        }
+        let args = ArgValues::parse(&self.parameters, &args)?;
+        let input_file = args.required_file("--input", working_directory)?;
+        let output_file = args.required_file("--output", working_directory)?;
+        let interval = args.get_f64("--interval")?.unwrap_or(10f64);
+        let base = args.get_f64("--base")?.unwrap_or(0f64);
+        let filter_size = args.get_usize("--smooth")?.unwrap_or(0);
+
+        if verbose {
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
+            sink.message(&format!("* Welcome to {} *", self.get_tool_name()));
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
+        }
+
+        if !(interval > 0f64) {
+            return Err(Error::new(ErrorKind::InvalidInput,
+                                "The contour interval must be greater than zero."));
+        }
+
+        let mut progress: usize;
+        let mut old_progress: usize = 1;
+
+
+        if verbose { sink.message("Reading data...") };
+        let input = Raster::new(&input_file, "r")?;
+
+        let start = time::now();
+
+        let rows = input.configs.rows as isize;
+        let columns = input.configs.columns as isize;
+        let nodata = input.configs.nodata;
+
+        // Each square of four neighbouring cell centres is crossed by the contours with
+        // levels between its lowest and highest values. The crossing points are identified
+        // by the cell edge that they lie on; horizontal edges are even and vertical are odd.
+        let edge_id = |row: isize, col: isize, vertical: bool| -> usize {
+            2 * (row * columns + col) as usize + if vertical { 1 } else { 0 }
+        };
+        let mut contours = ContourLines::new();
+        let mut z = [0f64; 4];
+        let mut crossings = Vec::with_capacity(4);
+        for row in 0..rows - 1 {
+            for col in 0..columns - 1 {
+                // the corners, clockwise from the top-left, and the edges that follow them
+                let corners = [(row, col), (row, col + 1), (row + 1, col + 1), (row + 1, col)];
+                let edges = [edge_id(row, col, false), edge_id(row, col + 1, true),
+                    edge_id(row + 1, col, false), edge_id(row, col, true)];
+                let mut is_nodata = false;
+                for k in 0..4 {
+                    z[k] = input.get_value(corners[k].0, corners[k].1);
+                    if z[k] == nodata {
+                        is_nodata = true;
+                    }
+                }
+                if is_nodata {
+                    continue;
+                }
+                let min_z = z.iter().fold(f64::INFINITY, |a, &b| a.min(b));
+                let max_z = z.iter().fold(f64::NEG_INFINITY, |a, &b| a.max(b));
+                let (k_low, k_high) = level_range(min_z, max_z, base, interval);
+                for k in k_low..k_high + 1 {
+                    let level = base + k as f64 * interval;
+                    crossings.clear();
+                    for a in 0..4 {
+                        let b = (a + 1) % 4;
+                        if (z[a] >= level) != (z[b] >= level) {
+                            let t = (level - z[a]) / (z[b] - z[a]);
+                            let (x1, y1) = (input.get_x_from_column(corners[a].1), input.get_y_from_row(corners[a].0));
+                            let (x2, y2) = (input.get_x_from_column(corners[b].1), input.get_y_from_row(corners[b].0));
+                            crossings.push((edges[a], Point2D { x: x1 + t * (x2 - x1), y: y1 + t * (y2 - y1) }));
+                        }
+                    }
+                    if crossings.len() == 2 {
+                        contours.add_segment(k, crossings[0].0, &crossings[0].1, crossings[1].0, &crossings[1].1);
+                    } else if crossings.len() == 4 {
+                        // A saddle; if the top-left corner is on the same side of the contour as
+                        // the centre, the other two corners are cut off, otherwise it is.
+                        let centre_above = (z[0] + z[1] + z[2] + z[3]) / 4f64 >= level;
+                        let (c, d) = if (z[0] >= level) == centre_above { (0, 2) } else { (3, 1) };
+                        contours.add_segment(k, crossings[c].0, &crossings[c].1, crossings[(c + 1) % 4].0, &crossings[(c + 1) % 4].1);
+                        contours.add_segment(k, crossings[d].0, &crossings[d].1, crossings[(d + 1) % 4].0, &crossings[(d + 1) % 4].1);
+                    }
+                }
+            }
+            progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as usize;
+            if progress != old_progress {
+                sink.progress("Tracing contours", progress)?;
+                old_progress = progress;
+            }
+        }
+
+        // create the output file
+        let mut output = Shapefile::new(&output_file, "w")?;
+        output.header.shape_type = ShapeType::PolyLine;
+        if input.configs.coordinate_ref_system_wkt != "not specified" {
+            output.projection = input.configs.coordinate_ref_system_wkt.clone();
+        }
+        output.attributes.add_field(AttributeField::new("FID", 'N', 10u8, 0u8));
+        output.attributes.add_field(AttributeField::new("ELEV", 'F', 19u8, 8u8));
+
+        let lines = contours.trace();
+        let num_lines = lines.len();
+        for (i, (k, mut points)) in lines.into_iter().enumerate() {
+            smooth_line(&mut points, filter_size);
+            let mut sfg = ShapefileGeometry::new(ShapeType::PolyLine);
+            sfg.add_part(&points);
+            output.add_record(sfg);
+            output.attributes.add_record(vec![
+                FieldData::Int(i as i32 + 1),
+                FieldData::Real(base + k as f64 * interval)
+            ], false)?;
+
+            progress = (100.0_f64 * i as f64 / (num_lines - 1).max(1) as f64) as usize;
+            if progress != old_progress {
+                sink.progress("Creating polylines", progress)?;
+                old_progress = progress;
+            }
+        }
+
+        let end = time::now();
+        let elapsed_time = end - start;
+
+        if verbose { sink.message("Saving data...") };
+        let _ = match output.write() {
+            Ok(_) => if verbose { sink.message("Output file written") },
+            Err(e) => return Err(e),
+        };
+
+        if verbose {
+            sink.message(&format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
+        }
+
+        Ok(())
+    }
+}
+
+/// Returns the range of the indices of the contour levels, base + k * interval, that lie
+/// above a minimum value and at or below a maximum value.
+pub fn level_range(min_z: f64, max_z: f64, base: f64, interval: f64) -> (isize, isize) {
+    let mut k_low = ((min_z - base) / interval).floor() as isize;
+    while base + k_low as f64 * interval <= min_z {
+        k_low += 1;
+    }
+    while base + (k_low - 1) as f64 * interval > min_z {
+        k_low -= 1;
+    }
+    let mut k_high = ((max_z - base) / interval).floor() as isize;
+    while base + (k_high + 1) as f64 * interval <= max_z {
+        k_high += 1;
+    }
+    while base + k_high as f64 * interval > max_z {
+        k_high -= 1;
+    }
+    (k_low, k_high)
+}
+
+/// The segments of a set of contours, which are joined into lines where they meet. Each
+/// crossing point is identified by its level and the edge, of a grid or triangulation,
+/// that it lies on, and is shared by at most two segments.
+pub struct ContourLines {
+    node_ids: HashMap<(isize, usize), usize>,
+    points: Vec<Point2D>,
+    levels: Vec<isize>,
+    links: Vec<[usize; 2]>,
+}
+
+impl ContourLines {
+    pub fn new() -> ContourLines {
+        ContourLines {
+            node_ids: HashMap::new(),
+            points: vec![],
+            levels: vec![],
+            links: vec![],
+        }
+    }
+
+    /// Adds the segment of the contour with level index k between the points where it
+    /// crosses two edges.
+    pub fn add_segment(&mut self, k: isize, edge1: usize, p1: &Point2D, edge2: usize, p2: &Point2D) {
+        let a = self.node(k, edge1, p1);
+        let b = self.node(k, edge2, p2);
+        for &(n, other) in &[(a, b), (b, a)] {
+            if self.links[n][0] == usize::MAX {
+                self.links[n][0] = other;
+            } else {
+                self.links[n][1] = other;
+            }
+        }
+    }
+
+    fn node(&mut self, k: isize, edge: usize, p: &Point2D) -> usize {
+        let n = self.points.len();
+        let id = *self.node_ids.entry((k, edge)).or_insert(n);
+        if id == n {
+            self.points.push(p.clone());
+            self.levels.push(k);
+            self.links.push([usize::MAX; 2]);
+        }
+        id
+    }
+
+    /// Joins the segments into lines, which are returned with their level indices. Closed
+    /// lines begin and end with the same point.
+    pub fn trace(&self) -> Vec<(isize, Vec<Point2D>)> {
+        let num_nodes = self.points.len();
+        let mut visited = vec![false; num_nodes];
+        let mut lines = vec![];
+        // open lines begin at a node with a single segment, and are traced first
+        for closed in 0..2 {
+            for n in 0..num_nodes {
+                if visited[n] || (closed == 0 && self.links[n][1] != usize::MAX) {
+                    continue;
+                }
+                let mut points = vec![];
+                let (mut prev, mut current) = (usize::MAX, n);
+                loop {
+                    visited[current] = true;
+                    points.push(self.points[current].clone());
+                    let next = if self.links[current][0] != prev { self.links[current][0] } else { self.links[current][1] };
+                    if next == usize::MAX || visited[next] {
+                        break;
+                    }
+                    prev = current;
+                    current = next;
+                }
+                if closed == 1 {
+                    points.push(self.points[n].clone());
+                }
+                if points.len() > 1 {
+                    lines.push((self.levels[n], points));
+                }
+            }
+        }
+        lines
+    }
+}
+
+/// Smooths a line using a moving average filter of a number of vertices. The end points of
+/// open lines are fixed, while closed lines are smoothed around their start.
+pub fn smooth_line(points: &mut Vec<Point2D>, filter_size: usize) {
+    let half = filter_size / 2;
+    let n = points.len();
+    if half == 0 || n < 3 {
+        return;
+    }
+    let closed = points[0].x == points[n - 1].x && points[0].y == points[n - 1].y;
+    let source = points.clone();
+    if closed {
+        let m = n - 1; // the unique points
+        for i in 0..m {
+            let (mut x, mut y) = (0f64, 0f64);
+            for j in 0..2 * half + 1 {
+                let p = &source[(i + m * half + j - half) % m];
+                x += p.x;
+                y += p.y;
+            }
+            points[i] = Point2D { x: x / (2 * half + 1) as f64, y: y / (2 * half + 1) as f64 };
+        }
+        points[m] = points[0].clone();
+    } else {
+        for i in 1..n - 1 {
+            // the window shrinks towards the ends of the line
+            let h = half.min(i).min(n - 1 - i);
+            let (mut x, mut y) = (0f64, 0f64);
+            for p in &source[i - h..i + h + 1] {
+                x += p.x;
+                y += p.y;
+            }
+            points[i] = Point2D { x: x / (2 * h + 1) as f64, y: y / (2 * h + 1) as f64 };
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::path::Path;
+
+    // Writes an in-memory raster with unit cells, whose cell centres are at integer coordinates.
+    fn surface_raster(name: &str, rows: usize, columns: usize, nodata: f64, z: &Fn(isize, isize) -> f64) -> String {
+        let mut configs = RasterConfigs { ..Default::default() };
+        configs.rows = rows;
+        configs.columns = columns;
+        configs.nodata = nodata;
+        configs.north = rows as f64 - 0.5;
+        configs.south = -0.5;
+        configs.west = -0.5;
+        configs.east = columns as f64 - 0.5;
+        configs.resolution_x = 1f64;
+        configs.resolution_y = 1f64;
+        configs.data_type = DataType::F64;
+        let file_name = format!("{}{}", IN_MEMORY_RASTER_PREFIX, name);
+        let mut r = Raster::initialize_using_config(&file_name, &configs);
+        for row in 0..rows as isize {
+            for col in 0..columns as isize {
+                r.set_value(row, col, z(row, col));
+            }
+        }
+        r.write().unwrap();
+        file_name
+    }
+
+    // Runs the tool and returns each output line and its ELEV.
+    fn contour(input: &str, output: &str, interval: f64, base: f64, smooth: usize) -> Vec<(f64, Vec<Point2D>)> {
+        let file_name = env::temp_dir().join(output).to_str().unwrap().to_string();
+        let args = vec![format!("-i={}", input), format!("-o={}", file_name), format!("--interval={}", interval),
+            format!("--base={}", base), format!("--smooth={}", smooth)];
+        let mut sink = StdoutProgress::new();
+        sink.set_quiet(true);
+        let sink: Arc<ProgressSink> = Arc::new(sink);
+        ContoursFromRaster::new().run(args, "", false, &sink).unwrap();
+
+        let sf = Shapefile::new(&file_name, "r").unwrap();
+        assert_eq!(sf.header.shape_type, ShapeType::PolyLine);
+        let mut lines = vec![];
+        for i in 0..sf.num_records {
+            let rec = sf.get_record(i);
+            assert_eq!(rec.parts, vec![0]);
+            let elev = match sf.attributes.get_value(i, "ELEV") {
+                FieldData::Real(v) => v,
+                v => panic!("unexpected ELEV {:?}", v),
+            };
+            lines.push((elev, rec.points.clone()));
+        }
+        for ext in ["shp", "shx", "dbf", "cpg"].iter() {
+            let _ = fs::remove_file(Path::new(&file_name).with_extension(ext));
+        }
+        lines
+    }
+
+    fn is_closed(line: &[Point2D]) -> bool {
+        line[0].x == line[line.len() - 1].x && line[0].y == line[line.len() - 1].y
+    }
+
+    fn ring_area(ring: &[Point2D]) -> f64 {
+        (0..ring.len() - 1).map(|i| ring[i].x * ring[i + 1].y - ring[i + 1].x * ring[i].y).sum::<f64>().abs() / 2f64
+    }
+
+    #[test]
+    fn levels_lie_above_the_minimum_and_at_or_below_the_maximum() {
+        assert_eq!(level_range(0.0, 10.0, 0.0, 5.0), (1, 2));
+        assert_eq!(level_range(0.1, 9.9, 0.0, 5.0), (1, 1));
+        assert_eq!(level_range(-12.0, -3.0, 2.0, 5.0), (-2, -1));
+        assert_eq!(level_range(1.0, 4.0, 0.0, 5.0), (1, 0));
+        // levels that are not exactly representable are compared as they are computed
+        for &(min_z, max_z, base, interval) in [(0.3, 0.9, 0.0, 0.1), (0.7, 2.1, 0.1, 0.2), (-0.3, 0.3, 0.0, 0.1)].iter() {
+            let (k_low, k_high) = level_range(min_z, max_z, base, interval);
+            assert!(base + (k_low - 1) as f64 * interval <= min_z && base + k_low as f64 * interval > min_z);
+            assert!(base + k_high as f64 * interval <= max_z && base + (k_high + 1) as f64 * interval > max_z);
+        }
+    }
+
+    #[test]
+    fn segments_are_joined_into_lines() {
+        let p = |x: f64, y: f64| Point2D { x: x, y: y };
+        let mut contours = ContourLines::new();
+        // a closed square at level 1, added out of order
+        contours.add_segment(1, 0, &p(0.0, 0.0), 1, &p(1.0, 0.0));
+        contours.add_segment(1, 2, &p(1.0, 1.0), 3, &p(0.0, 1.0));
+        contours.add_segment(1, 1, &p(1.0, 0.0), 2, &p(1.0, 1.0));
+        contours.add_segment(1, 3, &p(0.0, 1.0), 0, &p(0.0, 0.0));
+        // an open line at level 2 that shares edge ids, but not the level, with the square
+        contours.add_segment(2, 1, &p(5.0, 0.0), 2, &p(6.0, 0.0));
+        contours.add_segment(2, 2, &p(6.0, 0.0), 9, &p(7.0, 1.0));
+
+        let lines = contours.trace();
+        assert_eq!(lines.len(), 2);
+        // open lines are traced first, from one of their ends
+        assert_eq!(lines[0].0, 2);
+        let open: Vec<(f64, f64)> = lines[0].1.iter().map(|p| (p.x, p.y)).collect();
+        assert_eq!(open, vec![(5.0, 0.0), (6.0, 0.0), (7.0, 1.0)]);
+        assert_eq!(lines[1].0, 1);
+        assert_eq!(lines[1].1.len(), 5);
+        assert!(is_closed(&lines[1].1));
+        assert_eq!(ring_area(&lines[1].1), 1.0);
+    }
+
+    #[test]
+    fn smoothing_fixes_the_ends_of_open_lines() {
+        let mut line: Vec<Point2D> = (0..7).map(|i| Point2D { x: i as f64, y: if i % 2 == 0 { 0.0 } else { 1.0 } }).collect();
+        let original = line.clone();
+        smooth_line(&mut line, 3);
+        assert_eq!((line[0].x, line[0].y), (0.0, 0.0));
+        assert_eq!((line[6].x, line[6].y), (6.0, 0.0));
+        assert!((line[1].y - 1.0 / 3.0).abs() < 1e-12 && (line[2].y - 2.0 / 3.0).abs() < 1e-12);
+
+        // closed lines remain closed
+        let mut ring = vec![Point2D { x: 0.0, y: 0.0 }, Point2D { x: 2.0, y: 0.0 }, Point2D { x: 2.0, y: 2.0 },
+            Point2D { x: 0.0, y: 2.0 }, Point2D { x: 0.0, y: 0.0 }];
+        smooth_line(&mut ring, 3);
+        assert!(is_closed(&ring));
+        assert!((ring[0].x - 2.0 / 3.0).abs() < 1e-12 && (ring[0].y - 2.0 / 3.0).abs() < 1e-12);
+
+        // a filter size of one does nothing
+        let mut unchanged = original.clone();
+        smooth_line(&mut unchanged, 1);
+        assert!(unchanged.iter().zip(original.iter()).all(|(a, b)| a.x == b.x && a.y == b.y));
+    }
+
+    #[test]
+    fn cone_contours_are_closed_circles() {
+        let input = surface_raster("cfr_cone.tif", 41, 41, -9999f64, &|row, col| {
+            (((row - 20) * (row - 20) + (col - 20) * (col - 20)) as f64).sqrt()
+        });
+        let lines = contour(&input, "wbt_cfr_cone.shp", 5.0, 0.0, 0);
+        // a cell value that equals a level lies above it, and so the 20 contour is closed,
+        // although it touches the middle of each edge of the grid
+        for &radius in [5.0, 10.0, 15.0, 20.0].iter() {
+            let rings: Vec<&Vec<Point2D>> = lines.iter().filter(|l| l.0 == radius).map(|l| &l.1).collect();
+            assert_eq!(rings.len(), 1, "level {}", radius);
+            let ring = rings[0];
+            assert!(is_closed(ring));
+            for p in ring {
+                let d = ((p.x - 20.0) * (p.x - 20.0) + (p.y - 20.0) * (p.y - 20.0)).sqrt();
+                assert!((d - radius).abs() < 0.1, "level {}, distance {}", radius, d);
+            }
+            let area = ring_area(ring);
+            assert!((area / (f64::consts::PI * radius * radius) - 1.0).abs() < 0.02);
+        }
+        // the 25 contour is cut by the edges of the grid into four open lines
+        let corners: Vec<&Vec<Point2D>> = lines.iter().filter(|l| l.0 == 25.0).map(|l| &l.1).collect();
+        assert_eq!(corners.len(), 4);
+        assert!(corners.iter().all(|l| !is_closed(l)));
+        assert_eq!(lines.len(), 4 + 4);
+    }
+
+    #[test]
+    fn contours_end_at_nodata() {
+        // a plane that rises to the east, with a row of NoData across it
+        let input = surface_raster("cfr_plane.tif", 11, 10, -9999f64, &|row, col| {
+            if row == 5 { -9999f64 } else { col as f64 * 10.0 + 0.5 }
+        });
+        let lines = contour(&input, "wbt_cfr_plane.shp", 10.0, 0.0, 0);
+        // nine levels, each of which is cut in two
+        assert_eq!(lines.len(), 18);
+        for &(elev, ref line) in &lines {
+            assert!(!is_closed(line));
+            assert_eq!(line.len(), 5);
+            // the contour crosses each row at the same interpolated position
+            let x = (elev - 0.5) / 10.0;
+            assert!(line.iter().all(|p| (p.x - x).abs() < 1e-9), "level {}", elev);
+        }
+    }
+
+    #[test]
+    fn contours_of_a_rough_surface_do_not_cross_or_branch() {
+        let mut seed = 123456789u64;
+        let values: Vec<f64> = (0..30 * 30).map(|_| {
+            seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            // no value equals a level, so that no contour passes through a cell centre
+            ((seed >> 11) as f64 / (1u64 << 53) as f64 * 100.0).floor() + 0.5
+        }).collect();
+        let input = surface_raster("cfr_rough.tif", 30, 30, -9999f64, &|row, col| values[(row * 30 + col) as usize]);
+        let lines = contour(&input, "wbt_cfr_rough.shp", 10.0, 0.0, 0);
+        assert!(lines.len() > 10);
+
+        // every vertex lies on a single line, except for the repeated start of a closed line
+        let mut vertices = HashMap::new();
+        for (i, &(elev, ref line)) in lines.iter().enumerate() {
+            assert_eq!(elev % 10.0, 0.0);
+            let end = if is_closed(line) { line.len() - 1 } else { line.len() };
+            for p in &line[0..end] {
+                let key = (elev as i64, (p.x * 1e6).round() as i64, (p.y * 1e6).round() as i64);
+                assert!(vertices.insert(key, i).is_none(), "a vertex of line {} is shared", i);
+                // vertices lie on the lines between cell centres
+                assert!(p.x == p.x.round() || p.y == p.y.round());
+            }
+            // open lines end at the edges of the grid
+            if !is_closed(line) {
+                for p in &[&line[0], &line[line.len() - 1]] {
+                    assert!(p.x == 0.0 || p.x == 29.0 || p.y == 0.0 || p.y == 29.0);
+                }
+            }
+        }
+    }
+}
@@ -24,6 +24,7 @@ use std::ops::SubAssign;
 use raster::*;
 use std::io::{Error, ErrorKind};
 use tools::*;
+use tools::arg_parser::ArgValues;
 use structures::Array2D;
 
 pub struct FeaturePreservingDenoise {
@@ -43,7 +44,7 @@ impl FeaturePreservingDenoise {
         let mut parameters = vec![];
         parameters.push(ToolParameter{
             name: "Input DEM File".to_owned(), 
-            flags: vec!["-i".to_owned(), "--dem".to_owned()], 
+            flags: vec!["-i".to_owned(), "--input".to_owned(), "--dem".to_owned()], 
             description: "Input raster DEM file.".to_owned(),
             parameter_type: ParameterType::ExistingFile(ParameterFileType::Raster),
             default_value: None,
@@ -149,70 +150,23 @@ impl WhiteboxTool for FeaturePreservingDenoise {
         self.toolbox.clone()
     }
 
-    fn run<'a>(&self, args: Vec<String>, working_directory: &'a str, verbose: bool) -> Result<(), Error> {
-        let mut input_file = String::new();
-        let mut output_file = String::new();
-        let mut filter_size = 11usize;
-        let mut max_norm_diff = 15f64;
-        let mut num_iter = 5;
-        let mut z_factor = 1f64;
-
+    fn run<'a>(&self, args: Vec<String>, working_directory: &'a str, verbose: bool, sink: &Arc<ProgressSink>) -> Result<(), Error> {
         if args.len() == 0 {
             return Err(Error::new(ErrorKind::InvalidInput,
                                 "Tool run with no paramters."));
         }
-        for i in 0..args.len() {
-            let mut arg = args[i].replace("\"", "");
-            arg = arg.replace("\'", "");
-            let cmd = arg.split("="); // in case an equals sign was used
-            let vec = cmd.collect::<Vec<&str>>();
-            let mut keyval = false;
-            if vec.len() > 1 {
-                keyval = true;
-            }
-            if vec[0].to_lowercase() == "-i" || vec[0].to_lowercase() == "--input" || vec[0].to_lowercase() == "--dem" {
-                if keyval {
-                    input_file = vec[1].to_string();
-                } else {
-                    input_file = args[i+1].to_string();
-                }
-            } else if vec[0].to_lowercase() == "-o" || vec[0].to_lowercase() == "--output" {
-                if keyval {
-                    output_file = vec[1].to_string();
-                } else {
-                    output_file = args[i+1].to_string();
-                }
-            } else if vec[0].to_lowercase() == "-filter" || vec[0].to_lowercase() == "--filter" {
-                if keyval {
-                    filter_size = vec[1].to_string().parse::<usize>().unwrap();
-                } else {
-                    filter_size = args[i+1].to_string().parse::<usize>().unwrap();
-                }
-            } else if vec[0].to_lowercase() == "-norm_diff" || vec[0].to_lowercase() == "--norm_diff" {
-                if keyval {
-                    max_norm_diff = vec[1].to_string().parse::<f64>().unwrap();
-                } else {
-                    max_norm_diff = args[i+1].to_string().parse::<f64>().unwrap();
-                }
-            } else if vec[0].to_lowercase() == "-num_iter" || vec[0].to_lowercase() == "--num_iter" {
-                if keyval {
-                    num_iter = vec[1].to_string().parse::<usize>().unwrap();
-                } else {
-                    num_iter = args[i+1].to_string().parse::<usize>().unwrap();
-                }
-            } else if vec[0].to_lowercase() == "-zfactor" || vec[0].to_lowercase() == "--zfactor" {
-                if keyval {
-                    z_factor = vec[1].to_string().parse::<f64>().unwrap();
-                } else {
-                    z_factor = args[i+1].to_string().parse::<f64>().unwrap();
-                }
-            }
-        }
+        let args = ArgValues::parse(&self.parameters, &args)?;
+        let input_file = args.required_file("--dem", working_directory)?;
+        let output_file = args.required_file("--output", working_directory)?;
+        let mut filter_size = args.get_usize("--filter")?.unwrap_or(11usize);
+        let mut max_norm_diff = args.get_f64("--norm_diff")?.unwrap_or(15f64);
+        let mut num_iter = args.get_usize("--num_iter")?.unwrap_or(5);
+        let mut z_factor = args.get_f64("--zfactor")?.unwrap_or(1f64);
 
         if verbose {
-            println!("***************{}", "*".repeat(self.get_tool_name().len()));
-            println!("* Welcome to {} *", self.get_tool_name());
-            println!("***************{}", "*".repeat(self.get_tool_name().len()));
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
+            sink.message(&format!("* Welcome to {} *", self.get_tool_name()));
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
         }
 
         if filter_size < 3 { filter_size = 3; }
@@ -220,19 +174,10 @@ impl WhiteboxTool for FeaturePreservingDenoise {
         if max_norm_diff > 90f64 { max_norm_diff = 90f64; }
         let threshold = max_norm_diff.to_radians().cos();
 
-        let sep: String = path::MAIN_SEPARATOR.to_string();
-
         let mut progress: usize;
         let mut old_progress: usize = 1;
 
-        if !input_file.contains(&sep) && !input_file.contains("/") {
-            input_file = format!("{}{}", working_directory, input_file);
-        }
-        if !output_file.contains(&sep) && !output_file.contains("/") {
-            output_file = format!("{}{}", working_directory, output_file);
-        }
-
-        if verbose { println!("Reading data...") };
+        if verbose { sink.message("Reading data...") };
 
         let input = Arc::new(Raster::new(&input_file, "r")?);
 
@@ -246,7 +191,7 @@ impl WhiteboxTool for FeaturePreservingDenoise {
             if mid_lat <= 90.0 && mid_lat >= -90.0 {
                 mid_lat = mid_lat.to_radians();
                 z_factor = 1.0 / (113200.0 * mid_lat.cos());
-                println!("It appears that the DEM is in geographic coordinates. The z-factor has been updated: {}.", z_factor);
+                sink.message(&format!("It appears that the DEM is in geographic coordinates. The z-factor has been updated: {}.", z_factor));
             }
         }
 
@@ -300,17 +245,15 @@ impl WhiteboxTool for FeaturePreservingDenoise {
             let data = rx.recv().unwrap();
             nv.set_row_data(data.0, data.1);
             
-            if verbose {
-                progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as usize;
-                if progress != old_progress {
-                    println!("Calculating normal vectors: {}%", progress);
-                    old_progress = progress;
-                }
+            progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as usize;
+            if progress != old_progress {
+                sink.progress("Calculating normal vectors", progress)?;
+                old_progress = progress;
             }
         }
 
         let t1 = time::now();
-        println!("{}", format!("Calculating normal vectors: {}", t1 - start).replace("PT", ""));
+        sink.message(&format!("{}", format!("Calculating normal vectors: {}", t1 - start).replace("PT", "")));
 
         //////////////////////////////////////////////////////////
         // Smooth the normal vector field of the fitted planes. //
@@ -382,17 +325,15 @@ impl WhiteboxTool for FeaturePreservingDenoise {
             let data = rx.recv().unwrap();
             nv_smooth.set_row_data(data.0, data.1);
             
-            if verbose {
-                progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as usize;
-                if progress != old_progress {
-                    println!("Smoothing normal vectors: {}%", progress);
-                    old_progress = progress;
-                }
+            progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as usize;
+            if progress != old_progress {
+                sink.progress("Smoothing normal vectors", progress)?;
+                old_progress = progress;
             }
         }
 
         let t2 = time::now();
-        println!("{}", format!("Smoothing normal vectors: {}", t2 - t1).replace("PT", ""));
+        sink.message(&format!("{}", format!("Smoothing normal vectors: {}", t2 - t1).replace("PT", "")));
 
         ///////////////////////////////////////////////////////////////////////////
         // Update the elevations of the DEM based on the smoothed normal vectors //
@@ -411,9 +352,9 @@ impl WhiteboxTool for FeaturePreservingDenoise {
         let mut zn: f64;
         let mut output = Raster::initialize_using_file(&output_file, &input);
         output.set_data_from_raster(&input)?;
-        println!("Updating elevations...");
+        sink.message("Updating elevations...");
         for loop_num in 0..num_iter {
-            println!("Iteration {} of {}...", loop_num+1, num_iter);
+            sink.message(&format!("Iteration {} of {}...", loop_num+1, num_iter));
 
             for row in 0..rows {
                 for col in 0..columns {
@@ -441,12 +382,10 @@ impl WhiteboxTool for FeaturePreservingDenoise {
                         }
                     }
                 }
-                if verbose {
-                    progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as usize;
-                    if progress != old_progress {
-                        println!("Updating DEM elevations (Loop {} of {}): {}%", loop_num+1, num_iter, progress);
-                        old_progress = progress;
-                    }
+                progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as usize;
+                if progress != old_progress {
+                    sink.progress(&format!("Updating DEM elevations (Loop {} of {})", loop_num+1, num_iter), progress)?;
+                    old_progress = progress;
                 }
             }
         }
@@ -1069,13 +1008,13 @@ impl WhiteboxTool for FeaturePreservingDenoise {
         output.add_metadata_entry(format!("Z-factor: {}", z_factor));
         output.add_metadata_entry(format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
 
-        if verbose { println!("Saving data...") };
+        if verbose { sink.message("Saving data...") };
         let _ = match output.write() {
-            Ok(_) => if verbose { println!("Output file written") },
+            Ok(_) => if verbose { sink.message("Output file written") },
             Err(e) => return Err(e),
         };
         if verbose {
-            println!("{}", &format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
+            sink.message(&format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
         }
 
         Ok(())
@@ -17,6 +17,7 @@ use std::thread;
 use raster::*;
 use std::io::{Error, ErrorKind};
 use tools::*;
+use tools::arg_parser::ArgValues;
 use structures::FixedRadiusSearch2D;
 
 pub struct FillMissingData {
@@ -115,52 +116,22 @@ impl WhiteboxTool for FillMissingData {
         self.toolbox.clone()
     }
 
-    fn run<'a>(&self, args: Vec<String>, working_directory: &'a str, verbose: bool) -> Result<(), Error> {
-        let mut input_file = String::new();
-        let mut output_file = String::new();
-        let mut filter_size = 11usize;
+    fn run<'a>(&self, args: Vec<String>, working_directory: &'a str, verbose: bool, sink: &Arc<ProgressSink>) -> Result<(), Error> {
         if args.len() == 0 {
             return Err(Error::new(ErrorKind::InvalidInput,
                                 "Tool run with no paramters."));
         }
-        for i in 0..args.len() {
-            let mut arg = args[i].replace("\"", "");
-            arg = arg.replace("\'", "");
-            let cmd = arg.split("="); // in case an equals sign was used
-            let vec = cmd.collect::<Vec<&str>>();
-            let mut keyval = false;
-            if vec.len() > 1 {
-                keyval = true;
-            }
-            if vec[0].to_lowercase() == "-i" || vec[0].to_lowercase() == "--input" {
-                if keyval {
-                    input_file = vec[1].to_string();
-                } else {
-                    input_file = args[i+1].to_string();
-                }
-            } else if vec[0].to_lowercase() == "-o" || vec[0].to_lowercase() == "--output" {
-                if keyval {
-                    output_file = vec[1].to_string();
-                } else {
-                    output_file = args[i+1].to_string();
-                }
-            } else if vec[0].to_lowercase() == "-filter" || vec[0].to_lowercase() == "--filter" {
-                if keyval {
-                    filter_size = vec[1].to_string().parse::<usize>().unwrap();
-                } else {
-                    filter_size = args[i+1].to_string().parse::<usize>().unwrap();
-                }
-            }
-        }
+        let args = ArgValues::parse(&self.parameters, &args)?;
+        let input_file = args.required_file("--input", working_directory)?;
+        let output_file = args.required_file("--output", working_directory)?;
+        let mut filter_size = args.get_usize("--filter")?.unwrap_or(11usize);
 
         if verbose {
-            println!("***************{}", "*".repeat(self.get_tool_name().len()));
-            println!("* Welcome to {} *", self.get_tool_name());
-            println!("***************{}", "*".repeat(self.get_tool_name().len()));
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
+            sink.message(&format!("* Welcome to {} *", self.get_tool_name()));
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
         }
 
-        let sep: String = path::MAIN_SEPARATOR.to_string();
-
         // The filter dimensions must be odd numbers such that there is a middle pixel
         if (filter_size as f64 / 2f64).floor() == (filter_size as f64 / 2f64) {
             filter_size += 1;
@@ -171,14 +142,7 @@ impl WhiteboxTool for FillMissingData {
         let mut progress: usize;
         let mut old_progress: usize = 1;
 
-        if !input_file.contains(&sep) && !input_file.contains("/") {
-            input_file = format!("{}{}", working_directory, input_file);
-        }
-        if !output_file.contains(&sep) && !output_file.contains("/") {
-            output_file = format!("{}{}", working_directory, output_file);
-        }
-
-        if verbose { println!("Reading data...") };
+        if verbose { sink.message("Reading data...") };
 
         let input = Raster::new(&input_file, "r")?;
         let mut output = Raster::initialize_using_file(&output_file, &input);
@@ -192,27 +156,25 @@ impl WhiteboxTool for FillMissingData {
         let d_y = [ -1, 0, 1, 1, 1, 0, -1, -1 ];
 
         // Interpolate the data holes. Start by locating all the edge cells.
-        if verbose { println!("Interpolating data holes...") };
+        if verbose { sink.message("Interpolating data holes...") };
         let mut frs: FixedRadiusSearch2D<f64> = FixedRadiusSearch2D::new(filter_size as f64);
         for row in 0..rows {
             for col in 0..columns {
-                if input[(row, col)] != nodata {
+                if input.get_value(row, col) != nodata {
                     for i in 0..8 {
                         row_n = row + d_y[i];
                         col_n = col + d_x[i];
-                        if input[(row_n, col_n)] == nodata {
-                            frs.insert(col as f64, row as f64, input[(row, col)]);
+                        if input.get_value(row_n, col_n) == nodata {
+                            frs.insert(col as f64, row as f64, input.get_value(row, col));
                             break;
                         }
                     }
                 }
             }
-            if verbose {
-                progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as usize;
-                if progress != old_progress {
-                    println!("Finding edge cells: {}%", progress);
-                    old_progress = progress;
-                }
+            progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as usize;
+            if progress != old_progress {
+                sink.progress("Finding edge cells", progress)?;
+                old_progress = progress;
             }
         }
 
@@ -268,7 +230,7 @@ impl WhiteboxTool for FillMissingData {
                 for row in (0..rows).filter(|r| r % num_procs == tid) {
                     let mut data = vec![nodata; columns as usize];
                     for col in 0..columns {
-                        if input[(row, col)] == nodata {
+                        if input.get_value(row, col) == nodata {
                             sum_weights = 0f64;
                             let ret = frs.search(col as f64, row as f64);
                             for j in 0..ret.len() {
@@ -290,7 +252,7 @@ impl WhiteboxTool for FillMissingData {
                                 data[col as usize] = nodata;
                             }
                         } else {
-                            data[col as usize] = input[(row, col)];
+                            data[col as usize] = input.get_value(row, col);
                         }
                     }
                     tx1.send((row, data)).unwrap();
@@ -301,12 +263,10 @@ impl WhiteboxTool for FillMissingData {
         for row in 0..rows {
             let data = rx.recv().unwrap();
             output.set_row_data(data.0, data.1);
-            if verbose {
-                progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as usize;
-                if progress != old_progress {
-                    println!("Performing analysis: {}%", progress);
-                    old_progress = progress;
-                }
+            progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as usize;
+            if progress != old_progress {
+                sink.progress("Performing analysis", progress)?;
+                old_progress = progress;
             }
         }
 
@@ -318,13 +278,13 @@ impl WhiteboxTool for FillMissingData {
         output.add_metadata_entry(format!("Filter size x: {}", filter_size));
         output.add_metadata_entry(format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
         
-        if verbose { println!("Saving data...") };
+        if verbose { sink.message("Saving data...") };
         let _ = match output.write() {
-            Ok(_) => if verbose { println!("Output file written") },
+            Ok(_) => if verbose { sink.message("Output file written") },
             Err(e) => return Err(e),
         };
         if verbose {
-            println!("{}", &format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
+            sink.message(&format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
         }
         
         Ok(())
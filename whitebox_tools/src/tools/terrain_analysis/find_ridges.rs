@@ -15,8 +15,10 @@ use std::io::{Error, ErrorKind};
 use std::sync::Arc;
 use std::sync::mpsc;
 use std::thread;
+use std::cmp::min;
 use raster::*;
 use tools::*;
+use tools::arg_parser::ArgValues;
 
 pub struct FindRidges {
     name: String,
@@ -122,54 +124,21 @@ impl WhiteboxTool for FindRidges {
     fn run<'a>(&self,
                args: Vec<String>,
                working_directory: &'a str,
-               verbose: bool)
+               verbose: bool,
+               sink: &Arc<ProgressSink>)
                -> Result<(), Error> {
-        let mut input_file = String::new();
-        let mut output_file = String::new();
-        let mut line_thin = false;
-        
         if args.len() == 0 {
             return Err(Error::new(ErrorKind::InvalidInput, "Tool run with no paramters."));
         }
-        for i in 0..args.len() {
-            let mut arg = args[i].replace("\"", "");
-            arg = arg.replace("\'", "");
-            let cmd = arg.split("="); // in case an equals sign was used
-            let vec = cmd.collect::<Vec<&str>>();
-            let mut keyval = false;
-            if vec.len() > 1 {
-                keyval = true;
-            }
-            if vec[0].to_lowercase() == "-i" || vec[0].to_lowercase() == "-dem" || vec[0].to_lowercase() == "--dem" {
-                if keyval {
-                    input_file = vec[1].to_string();
-                } else {
-                    input_file = args[i+1].to_string();
-                }
-            } else if vec[0].to_lowercase() == "-o" || vec[0].to_lowercase() == "--output" {
-                if keyval {
-                    output_file = vec[1].to_string();
-                } else {
-                    output_file = args[i + 1].to_string();
-                }
-            } else if vec[0].to_lowercase() == "-line_thin" || vec[0].to_lowercase() == "--line_thin" {
-                line_thin = true;
-            }
-        }
+        let args = ArgValues::parse(&self.parameters, &args)?;
+        let input_file = args.required_file("--dem", working_directory)?;
+        let output_file = args.required_file("--output", working_directory)?;
+        let line_thin = args.get_bool("--line_thin")?;
 
         if verbose {
-            println!("***************{}", "*".repeat(self.get_tool_name().len()));
-            println!("* Welcome to {} *", self.get_tool_name());
-            println!("***************{}", "*".repeat(self.get_tool_name().len()));
-        }
-
-        let sep: String = path::MAIN_SEPARATOR.to_string();
-
-        if !input_file.contains(&sep) && !input_file.contains("/") {
-            input_file = format!("{}{}", working_directory, input_file);
-        }
-        if !output_file.contains(&sep) && !output_file.contains("/") {
-            output_file = format!("{}{}", working_directory, output_file);
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
+            sink.message(&format!("* Welcome to {} *", self.get_tool_name()));
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
         }
 
         let input = Arc::new(Raster::new(&input_file, "r")?);
@@ -188,53 +157,59 @@ impl WhiteboxTool for FindRidges {
                 
         // This one can be performed conccurently.
         let num_procs = num_cpus::get() as isize;
-        let (tx, rx) = mpsc::channel();
-        for tid in 0..num_procs {
-            let input = input.clone();
-            let tx = tx.clone();
-            thread::spawn(move || {
-                let dx = vec![0, 0, -1, 1];
-                let dy = vec![-1, 1, 0, 0];
-                let mut z: f64;
-                let (mut zn1, mut zn2): (f64, f64);
+        // the DEM is read in windows of rows, with a halo of one row on either side
+        let window_rows = input.get_window_rows();
+        let mut start_row = 0;
+        while start_row < rows {
+            let end_row = min(start_row + window_rows, rows);
+            let window = Arc::new(input.get_window(start_row - 1, end_row + 1));
+            let (tx, rx) = mpsc::channel();
+            for tid in 0..num_procs {
+                let window = window.clone();
+                let tx = tx.clone();
+                thread::spawn(move || {
+                    let dx = vec![0, 0, -1, 1];
+                    let dy = vec![-1, 1, 0, 0];
+                    let mut z: f64;
+                    let (mut zn1, mut zn2): (f64, f64);
                 
-                for row in (0..rows).filter(|r| r % num_procs == tid) {
-                    let mut data = vec![nodata; columns as usize];
-                    for col in 0..columns {
-                        z = input[(row, col)];
-                        if z != nodata  {
-                            zn1 = input.get_value(row + dy[0], col + dx[0]);
-                            zn2 = input.get_value(row + dy[1], col + dx[1]);
-                            if zn1 != nodata && zn2 != nodata && zn1 < z && zn2 < z {
-                                data[col as usize] = 1f64;
-                            } else {
-                                zn1 = input.get_value(row + dy[2], col + dx[2]);
-                                zn2 = input.get_value(row + dy[3], col + dx[3]);
+                    for row in (start_row..end_row).filter(|r| r % num_procs == tid) {
+                        let mut data = vec![nodata; columns as usize];
+                        for col in 0..columns {
+                            z = window.get_value(row, col);
+                            if z != nodata  {
+                                zn1 = window.get_value(row + dy[0], col + dx[0]);
+                                zn2 = window.get_value(row + dy[1], col + dx[1]);
                                 if zn1 != nodata && zn2 != nodata && zn1 < z && zn2 < z {
                                     data[col as usize] = 1f64;
+                                } else {
+                                    zn1 = window.get_value(row + dy[2], col + dx[2]);
+                                    zn2 = window.get_value(row + dy[3], col + dx[3]);
+                                    if zn1 != nodata && zn2 != nodata && zn1 < z && zn2 < z {
+                                        data[col as usize] = 1f64;
+                                    }
                                 }
                             }
                         }
+                        tx.send((row, data)).unwrap();
                     }
-                    tx.send((row, data)).unwrap();
-                }
-            });
-        }
+                });
+            }
 
-        for row in 0..rows {
-            let data = rx.recv().unwrap();
-            output.set_row_data(data.0, data.1);
-            if verbose {
+            for row in start_row..end_row {
+                let data = rx.recv().unwrap();
+                output.set_row_data(data.0, data.1);
                 progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as i32;
                 if progress != old_progress {
-                    println!("Progress: {}%", progress);
+                    sink.progress("Progress", progress as usize)?;
                     old_progress = progress;
                 }
             }
+            start_row = end_row;
         }
         
         if line_thin {
-            println!("Line thinning operation...");
+            sink.message("Line thinning operation...");
             let mut did_something = true;
             let mut loop_num = 0;
             let dx = [ 1, 1, 1, 0, -1, -1, -1, 0 ];
@@ -258,11 +233,11 @@ impl WhiteboxTool for FindRidges {
                 for a in 0..8 {
                     for row in 0..rows {
                         for col in 0..columns {
-                            z = output[(row, col)];
+                            z = output.get_value(row, col);
                             if z > 0.0 && z != nodata {
                                 // fill the neighbours array
                                 for i in 0..8 {
-                                    neighbours[i] = output[(row + dy[i], col + dx[i])];
+                                    neighbours[i] = output.get_value(row + dy[i], col + dx[i]);
                                 }
                                 
                                 // scan through element
@@ -273,18 +248,16 @@ impl WhiteboxTool for FindRidges {
                                     }
                                 }
                                 if pattern_match {
-                                    output[(row, col)] = 0.0;
+                                    output.set_value(row, col, 0.0);
                                     did_something = true;
                                 }
                             }
                         }
                     }
-                    if verbose {
-                        progress = (100.0_f64 * a as f64 / 7.0) as i32;
-                        if progress != old_progress {
-                            println!("Loop Number {}: {}%", loop_num, progress);
-                            old_progress = progress;
-                        }
+                    progress = (100.0_f64 * a as f64 / 7.0) as i32;
+                    if progress != old_progress {
+                        sink.progress(&format!("Loop Number {}", loop_num), progress as usize)?;
+                        old_progress = progress;
                     }
                 }
             }
@@ -300,19 +273,18 @@ impl WhiteboxTool for FindRidges {
                                       .replace("PT", ""));
 
         if verbose {
-            println!("Saving data...")
+            sink.message("Saving data...")
         };
         let _ = match output.write() {
             Ok(_) => {
                 if verbose {
-                    println!("Output file written")
+                    sink.message("Output file written")
                 }
             }
             Err(e) => return Err(e),
         };
         if verbose {
-            println!("{}",
-                 &format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
+            sink.message(&format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
         }
 
         Ok(())
@@ -2,12 +2,13 @@
 This tool is part of the WhiteboxTools geospatial analysis library.
 Authors: Dr. John Lindsay
 Created: June 22, 2017
-Last Modified: January 21, 2018
+Last Modified: March 16, 2018
 License: MIT
 */
 extern crate time;
 extern crate num_cpus;
 
+use std::cmp::min;
 use std::env;
 use std::path;
 use std::f64;
@@ -17,6 +18,7 @@ use std::thread;
 use raster::*;
 use std::io::{Error, ErrorKind};
 use tools::*;
+use tools::arg_parser::ArgValues;
 
 pub struct Hillshade {
     name: String,
@@ -35,7 +37,7 @@ impl Hillshade {
         let mut parameters = vec![];
         parameters.push(ToolParameter{
             name: "Input DEM File".to_owned(), 
-            flags: vec!["-i".to_owned(), "--dem".to_owned()], 
+            flags: vec!["-i".to_owned(), "--input".to_owned(), "--dem".to_owned()], 
             description: "Input raster DEM file.".to_owned(),
             parameter_type: ParameterType::ExistingFile(ParameterFileType::Raster),
             default_value: None,
@@ -132,80 +134,30 @@ impl WhiteboxTool for Hillshade {
         self.toolbox.clone()
     }
 
-    fn run<'a>(&self, args: Vec<String>, working_directory: &'a str, verbose: bool) -> Result<(), Error> {
-        let mut input_file = String::new();
-        let mut output_file = String::new();
-        let mut azimuth = 315.0f64;
-        let mut altitude = 30.0f64;
-        let mut z_factor = 1f64;
-
+    fn run<'a>(&self, args: Vec<String>, working_directory: &'a str, verbose: bool, sink: &Arc<ProgressSink>) -> Result<(), Error> {
         if args.len() == 0 {
             return Err(Error::new(ErrorKind::InvalidInput,
                                 "Tool run with no paramters."));
         }
-        for i in 0..args.len() {
-            let mut arg = args[i].replace("\"", "");
-            arg = arg.replace("\'", "");
-            let cmd = arg.split("="); // in case an equals sign was used
-            let vec = cmd.collect::<Vec<&str>>();
-            let mut keyval = false;
-            if vec.len() > 1 {
-                keyval = true;
-            }
-            if vec[0].to_lowercase() == "-i" || vec[0].to_lowercase() == "--input" || vec[0].to_lowercase() == "--dem" {
-                if keyval {
-                    input_file = vec[1].to_string();
-                } else {
-                    input_file = args[i+1].to_string();
-                }
-            } else if vec[0].to_lowercase() == "-o" || vec[0].to_lowercase() == "--output" {
-                if keyval {
-                    output_file = vec[1].to_string();
-                } else {
-                    output_file = args[i+1].to_string();
-                }
-            } else if vec[0].to_lowercase() == "-azimuth" || vec[0].to_lowercase() == "--azimuth" {
-                if keyval {
-                    azimuth = vec[1].to_string().parse::<f64>().unwrap();
-                } else {
-                    azimuth = args[i+1].to_string().parse::<f64>().unwrap();
-                }
-            } else if vec[0].to_lowercase() == "-altitude" || vec[0].to_lowercase() == "--altitude" {
-                if keyval {
-                    altitude = vec[1].to_string().parse::<f64>().unwrap();
-                } else {
-                    altitude = args[i+1].to_string().parse::<f64>().unwrap();
-                }
-            } else if vec[0].to_lowercase() == "-zfactor" || vec[0].to_lowercase() == "--zfactor" {
-                if keyval {
-                    z_factor = vec[1].to_string().parse::<f64>().unwrap();
-                } else {
-                    z_factor = args[i+1].to_string().parse::<f64>().unwrap();
-                }
-            }
-        }
+        let args = ArgValues::parse(&self.parameters, &args)?;
+        let input_file = args.required_file("--dem", working_directory)?;
+        let output_file = args.required_file("--output", working_directory)?;
+        let mut azimuth = args.get_f64("--azimuth")?.unwrap_or(315.0f64);
+        let mut altitude = args.get_f64("--altitude")?.unwrap_or(30.0f64);
+        let mut z_factor = args.get_f64("--zfactor")?.unwrap_or(1f64);
 
         if verbose {
-            println!("***************{}", "*".repeat(self.get_tool_name().len()));
-            println!("* Welcome to {} *", self.get_tool_name());
-            println!("***************{}", "*".repeat(self.get_tool_name().len()));
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
+            sink.message(&format!("* Welcome to {} *", self.get_tool_name()));
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
         }
 
-        let sep: String = path::MAIN_SEPARATOR.to_string();
-
         let mut progress: usize;
         let mut old_progress: usize = 1;
 
-        if !input_file.contains(&sep) && !input_file.contains("/") {
-            input_file = format!("{}{}", working_directory, input_file);
-        }
-        if !output_file.contains(&sep) && !output_file.contains("/") {
-            output_file = format!("{}{}", working_directory, output_file);
-        }
-
-        if verbose { println!("Reading data...") };
+        if verbose { sink.message("Reading data...") };
 
-        let input = Arc::new(Raster::new(&input_file, "r")?);
+        let input = Raster::new(&input_file, "r")?;
 
         let start = time::now();
 
@@ -226,84 +178,91 @@ impl WhiteboxTool for Hillshade {
         
         let mut output = Raster::initialize_using_file(&output_file, &input);
         let rows = input.configs.rows as isize;
+        let columns = input.configs.columns as isize;
+        let nodata = input.configs.nodata;
+
+        let mut histo: [f64; 256] = [0.0; 256];
+        let mut num_cells = 0.0;
 
         let num_procs = num_cpus::get() as isize;
-        let (tx, rx) = mpsc::channel();
-        for tid in 0..num_procs {
-            let input = input.clone();
-            let tx1 = tx.clone();
-            thread::spawn(move || {
-                let nodata = input.configs.nodata;
-                let columns = input.configs.columns as isize;
-                let d_x = [ 1, 1, 1, 0, -1, -1, -1, 0 ];
-                let d_y = [ -1, 0, 1, 1, 1, 0, -1, -1 ];
-                let mut n: [f64; 8] = [0.0; 8];
-                let mut z: f64;
-                let (mut term1, mut term2, mut term3): (f64, f64, f64);
-                let (mut fx, mut fy): (f64, f64);
-                let mut tan_slope: f64;
-                let mut aspect: f64;
-                for row in (0..rows).filter(|r| r % num_procs == tid) {
-                    let mut data = vec![nodata; columns as usize];
-                    for col in 0..columns {
-                        z = input[(row, col)];
-                        if z != nodata {
-                            z = z * z_factor;
-                            for c in 0..8 {
-                                n[c] = input[(row + d_y[c], col + d_x[c])];
-                                if n[c] != nodata {
-                                    n[c] = n[c] * z_factor;
+        // The DEM is read in windows of rows, each with a halo of one row on either side, so
+        // that rasters that are too large to hold in memory are streamed through the tool.
+        let window_rows = input.get_window_rows();
+        let mut start_row = 0;
+        while start_row < rows {
+            let end_row = min(start_row + window_rows, rows);
+            let window = Arc::new(input.get_window(start_row - 1, end_row + 1));
+            let (tx, rx) = mpsc::channel();
+            for tid in 0..num_procs {
+                let window = window.clone();
+                let tx1 = tx.clone();
+                thread::spawn(move || {
+                    let d_x = [ 1, 1, 1, 0, -1, -1, -1, 0 ];
+                    let d_y = [ -1, 0, 1, 1, 1, 0, -1, -1 ];
+                    let mut n: [f64; 8] = [0.0; 8];
+                    let mut z: f64;
+                    let (mut term1, mut term2, mut term3): (f64, f64, f64);
+                    let (mut fx, mut fy): (f64, f64);
+                    let mut tan_slope: f64;
+                    let mut aspect: f64;
+                    for row in (start_row..end_row).filter(|r| r % num_procs == tid) {
+                        let mut data = vec![nodata; columns as usize];
+                        for col in 0..columns {
+                            z = window.get_value(row, col);
+                            if z != nodata {
+                                z = z * z_factor;
+                                for c in 0..8 {
+                                    n[c] = window.get_value(row + d_y[c], col + d_x[c]);
+                                    if n[c] != nodata {
+                                        n[c] = n[c] * z_factor;
+                                    } else {
+                                        n[c] = z;
+                                    }
+                                }
+                                // calculate slope and aspect
+                                fy = (n[6] - n[4] + 2.0 * (n[7] - n[3]) + n[0] - n[2]) / eight_grid_res;
+                                fx = (n[2] - n[4] + 2.0 * (n[1] - n[5]) + n[0] - n[6]) / eight_grid_res;
+                                if fx != 0f64 {
+                                    tan_slope = (fx * fx + fy * fy).sqrt();
+                                    aspect = (180f64 - ((fy / fx).atan()).to_degrees() + 90f64 * (fx / (fx).abs())).to_radians();
+                                    term1 = tan_slope / (1f64 + tan_slope * tan_slope).sqrt();
+                                    term2 = sin_theta / tan_slope;
+                                    term3 = cos_theta * (azimuth - aspect).sin();
+                                    z = term1 * (term2 - term3);
                                 } else {
-                                    n[c] = z;
+                                    z = 0.5;
                                 }
+                                z = z * 255.0;
+                                if z < 0.0 {
+                                    z = 0.0;
+                                }
+                                data[col as usize] = z;
                             }
-                            // calculate slope and aspect
-                            fy = (n[6] - n[4] + 2.0 * (n[7] - n[3]) + n[0] - n[2]) / eight_grid_res;
-                            fx = (n[2] - n[4] + 2.0 * (n[1] - n[5]) + n[0] - n[6]) / eight_grid_res;
-                            if fx != 0f64 {
-                                tan_slope = (fx * fx + fy * fy).sqrt();
-                                aspect = (180f64 - ((fy / fx).atan()).to_degrees() + 90f64 * (fx / (fx).abs())).to_radians();
-                                term1 = tan_slope / (1f64 + tan_slope * tan_slope).sqrt();
-                                term2 = sin_theta / tan_slope;
-                                term3 = cos_theta * (azimuth - aspect).sin();
-                                z = term1 * (term2 - term3);
-                            } else {
-                                z = 0.5;
-                            }
-                            z = z * 255.0;
-                            if z < 0.0 {
-                                z = 0.0;
-                            }
-                            data[col as usize] = z;
                         }
+                        tx1.send((row, data)).unwrap();
                     }
-                    tx1.send((row, data)).unwrap();
-                }
-            });
-        }
+                });
+            }
 
-        let mut histo: [f64; 256] = [0.0; 256];
-        let nodata = input.configs.nodata;
-        let mut num_cells = 0.0;
-        for row in 0..rows {
-            let data = rx.recv().unwrap();
-            let mut bin: usize;
-            for col in 0..data.1.len() {
-                if data.1[col] != nodata {
-                    bin = data.1[col].round() as usize;
-                    histo[bin] += 1.0;
-                    num_cells += 1.0;
+            for row in start_row..end_row {
+                let data = rx.recv().unwrap();
+                let mut bin: usize;
+                for col in 0..data.1.len() {
+                    if data.1[col] != nodata {
+                        bin = data.1[col].round() as usize;
+                        histo[bin] += 1.0;
+                        num_cells += 1.0;
+                    }
                 }
-            }
-            output.set_row_data(data.0, data.1);
+                output.set_row_data(data.0, data.1);
             
-            if verbose {
                 progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as usize;
                 if progress != old_progress {
-                    println!("Performing analysis: {}%", progress);
+                    sink.progress("Performing analysis", progress)?;
                     old_progress = progress;
                 }
             }
+            start_row = end_row;
         }
 
         let mut new_min = 0;
@@ -343,13 +302,13 @@ impl WhiteboxTool for Hillshade {
         output.add_metadata_entry(format!("Z-factor: {}", z_factor));
         output.add_metadata_entry(format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
 
-        if verbose { println!("Saving data...") };
+        if verbose { sink.message("Saving data...") };
         let _ = match output.write() {
-            Ok(_) => if verbose { println!("Output file written") },
+            Ok(_) => if verbose { sink.message("Output file written") },
             Err(e) => return Err(e),
         };
         if verbose {
-            println!("{}", &format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
+            sink.message(&format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
         }
 
         Ok(())
@@ -18,6 +18,7 @@ use raster::*;
 use structures::Array2D;
 use std::io::{Error, ErrorKind};
 use tools::*;
+use tools::arg_parser::ArgValues;
 
 pub struct MaxAnisotropyDev {
     name: String,
@@ -36,7 +37,7 @@ impl MaxAnisotropyDev {
         let mut parameters = vec![];
         parameters.push(ToolParameter{
             name: "Input DEM File".to_owned(), 
-            flags: vec!["-i".to_owned(), "--dem".to_owned()], 
+            flags: vec!["-i".to_owned(), "--input".to_owned(), "--dem".to_owned()], 
             description: "Input raster DEM file.".to_owned(),
             parameter_type: ParameterType::ExistingFile(ParameterFileType::Raster),
             default_value: None,
@@ -142,90 +143,32 @@ impl WhiteboxTool for MaxAnisotropyDev {
         self.toolbox.clone()
     }
 
-    fn run<'a>(&self, args: Vec<String>, working_directory: &'a str, verbose: bool) -> Result<(), Error> {
-        let mut input_file = String::new();
-        let mut output_mag_file = String::new();
-        let mut output_scale_file = String::new();
-        let mut min_scale = 3isize;
-        let mut max_scale = 100isize;
-        let mut step = 2isize;
+    fn run<'a>(&self, args: Vec<String>, working_directory: &'a str, verbose: bool, sink: &Arc<ProgressSink>) -> Result<(), Error> {
         if args.len() == 0 {
             return Err(Error::new(ErrorKind::InvalidInput,
                                 "Tool run with no paramters."));
         }
-        for i in 0..args.len() {
-            let mut arg = args[i].replace("\"", "");
-            arg = arg.replace("\'", "");
-            let cmd = arg.split("="); // in case an equals sign was used
-            let vec = cmd.collect::<Vec<&str>>();
-            let mut keyval = false;
-            if vec.len() > 1 {
-                keyval = true;
-            }
-            if vec[0].to_lowercase() == "-i" || vec[0].to_lowercase() == "--input" || vec[0].to_lowercase() == "--dem" {
-                if keyval {
-                    input_file = vec[1].to_string();
-                } else {
-                    input_file = args[i + 1].to_string();
-                }
-            } else if vec[0].to_lowercase() == "-out_mag" || vec[0].to_lowercase() == "--out_mag" {
-                if keyval {
-                    output_mag_file = vec[1].to_string();
-                } else {
-                    output_mag_file = args[i + 1].to_string();
-                }
-            } else if vec[0].to_lowercase() == "-out_scale" || vec[0].to_lowercase() == "--out_scale" {
-                if keyval {
-                    output_scale_file = vec[1].to_string();
-                } else {
-                    output_scale_file = args[i + 1].to_string();
-                }
-            } else if vec[0].to_lowercase() == "-min_scale" || vec[0].to_lowercase() == "--min_scale" {
-                if keyval {
-                    min_scale = vec[1].to_string().parse::<isize>().unwrap();
-                } else {
-                    min_scale = args[i + 1].to_string().parse::<isize>().unwrap();
-                }
-                if min_scale < 3 { min_scale = 3; }
-            } else if vec[0].to_lowercase() == "-max_scale" || vec[0].to_lowercase() == "--max_scale" {
-                if keyval {
-                    max_scale = vec[1].to_string().parse::<isize>().unwrap();
-                } else {
-                    max_scale = args[i + 1].to_string().parse::<isize>().unwrap();
-                }
-                if max_scale < 5 { max_scale = 5; }
-            } else if vec[0].to_lowercase() == "-step" || vec[0].to_lowercase() == "--step" {
-                if keyval {
-                    step = vec[1].to_string().parse::<isize>().unwrap();
-                } else {
-                    step = args[i + 1].to_string().parse::<isize>().unwrap();
-                }
-                if step < 1 { step = 1; }
-            }
-        }
+        let args = ArgValues::parse(&self.parameters, &args)?;
+        let input_file = args.required_file("--dem", working_directory)?;
+        let output_mag_file = args.required_file("--out_mag", working_directory)?;
+        let output_scale_file = args.required_file("--out_scale", working_directory)?;
+        let mut min_scale = args.get_i64("--min_scale")?.map(|v| v as isize).unwrap_or(3isize);
+        if min_scale < 3 { min_scale = 3; }
+        let mut max_scale = args.get_i64("--max_scale")?.map(|v| v as isize).unwrap_or(100isize);
+        if max_scale < 5 { max_scale = 5; }
+        let mut step = args.get_i64("--step")?.map(|v| v as isize).unwrap_or(2isize);
+        if step < 1 { step = 1; }
 
         if verbose {
-            println!("***************{}", "*".repeat(self.get_tool_name().len()));
-            println!("* Welcome to {} *", self.get_tool_name());
-            println!("***************{}", "*".repeat(self.get_tool_name().len()));
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
+            sink.message(&format!("* Welcome to {} *", self.get_tool_name()));
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
         }
 
-        let sep: String = path::MAIN_SEPARATOR.to_string();
-
         let mut progress: usize;
         let mut old_progress: usize = 1;
 
-        if !input_file.contains(&sep) && !input_file.contains("/") {
-            input_file = format!("{}{}", working_directory, input_file);
-        }
-        if !output_mag_file.contains(&sep) && !output_mag_file.contains("/") {
-            output_mag_file = format!("{}{}", working_directory, output_mag_file);
-        }
-        if !output_scale_file.contains(&sep) && !output_scale_file.contains("/") {
-            output_scale_file = format!("{}{}", working_directory, output_scale_file);
-        }
-
-        if verbose { println!("Reading data...") };
+        if verbose { sink.message("Reading data...") };
         let input = Arc::new(Raster::new(&input_file, "r")?);
 
         let start = time::now();
@@ -250,7 +193,7 @@ impl WhiteboxTool for MaxAnisotropyDev {
             sum_sqr = 0f64;
             sum_n = 0;
             for col in 0..columns {
-                val = input[(row, col)];
+                val = input.get_value(row, col);
                 if val == nodata {
                     val = 0f64;
                 } else {
@@ -271,12 +214,10 @@ impl WhiteboxTool for MaxAnisotropyDev {
                     integral_n[(row, col)] = sum_n;
                 }
             }
-            if verbose {
-                progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as usize;
-                if progress != old_progress {
-                    println!("Creating integral images: {}%", progress);
-                    old_progress = progress;
-                }
+            progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as usize;
+            if progress != old_progress {
+                sink.progress("Creating integral images", progress)?;
+                old_progress = progress;
             }
         }
 
@@ -328,7 +269,7 @@ impl WhiteboxTool for MaxAnisotropyDev {
                         if y1 >= 0 && y4 < rows  { // restricts edge effects
 
                             for col in 0..columns {
-                                z = input_data[(row, col)];
+                                z = input_data.get_value(row, col);
                                 if z != nodata {
                                     // left to right:
                                     // -midpoint  -middle_pane_radius  +middle_pane_radius  +midpoint  
@@ -495,24 +436,22 @@ impl WhiteboxTool for MaxAnisotropyDev {
                 for col in 0..columns {
                     z2 = data[col as usize];
                     if z2 != nodata {
-                        z1 = output_mag[(row, col)];
+                        z1 = output_mag.get_value(row, col);
                         if z1 != nodata {
                             if z2 * z2 > z1 * z1 {
-                                output_mag[(row, col)] = z2;
-                                output_scale[(row, col)] = midpoint as f64;
+                                output_mag.set_value(row, col, z2);
+                                output_scale.set_value(row, col, midpoint as f64);
                             }
                         } else {
-                            output_mag[(row, col)] = z2;
-                            output_scale[(row, col)] = midpoint as f64;
+                            output_mag.set_value(row, col, z2);
+                            output_scale.set_value(row, col, midpoint as f64);
                         }
                     }
                 }
-                if verbose {
-                    progress = (100.0_f64 * r as f64 / (rows - 1) as f64) as usize;
-                    if progress != old_progress {
-                        println!("Progress (Loop {} of {}): {}%", loop_num, num_loops, progress);
-                        old_progress = progress;
-                    }
+                progress = (100.0_f64 * r as f64 / (rows - 1) as f64) as usize;
+                if progress != old_progress {
+                    sink.progress(&format!("Progress (Loop {} of {})", loop_num, num_loops), progress)?;
+                    old_progress = progress;
                 }
             }
         }
@@ -527,11 +466,11 @@ impl WhiteboxTool for MaxAnisotropyDev {
         output_mag.add_metadata_entry(format!("Step size y: {}", step));
         output_mag.add_metadata_entry(format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
 
-        if verbose { println!("Saving magnitude data...") };
+        if verbose { sink.message("Saving magnitude data...") };
         let _ = match output_mag.write() {
             Ok(_) => {
                 if verbose {
-                    println!("Output file written")
+                    sink.message("Output file written")
                 }
             }
             Err(e) => return Err(e),
@@ -545,19 +484,18 @@ impl WhiteboxTool for MaxAnisotropyDev {
         output_scale.add_metadata_entry(format!("Step size: {}", step));
         output_scale.add_metadata_entry(format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
 
-        if verbose { println!("Saving scale data...") };
+        if verbose { sink.message("Saving scale data...") };
         let _ = match output_scale.write() {
             Ok(_) => {
                 if verbose {
-                    println!("Output file written")
+                    sink.message("Output file written")
                 }
             }
             Err(e) => return Err(e),
         };
 
         if verbose {
-            println!("{}",
-                    &format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
+            sink.message(&format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
         }
 
         Ok(())
@@ -19,7 +19,9 @@ use raster::*;
 use vector::{Shapefile, ShapeType};
 use structures::Array2D;
 use std::io::{Error, ErrorKind};
+use std::sync::Arc;
 use tools::*;
+use tools::arg_parser::ArgValues;
 use rendering::LineGraph;
 use rendering::html::*;
 
@@ -40,7 +42,7 @@ impl MaxElevDevSignature {
         let mut parameters = vec![];
         parameters.push(ToolParameter{
             name: "Input DEM File".to_owned(), 
-            flags: vec!["-i".to_owned(), "--dem".to_owned()], 
+            flags: vec!["-i".to_owned(), "--input".to_owned(), "--dem".to_owned()], 
             description: "Input raster DEM file.".to_owned(),
             parameter_type: ParameterType::ExistingFile(ParameterFileType::Raster),
             default_value: None,
@@ -146,66 +148,19 @@ impl WhiteboxTool for MaxElevDevSignature {
         self.toolbox.clone()
     }
 
-    fn run<'a>(&self, args: Vec<String>, working_directory: &'a str, verbose: bool) -> Result<(), Error> {
-        let mut input_file = String::new();
-        let mut points_file = String::new();
-        let mut output_file = String::new();
-        let mut min_scale = 1isize;
-        let mut max_scale = 100isize;
-        let mut step = 10isize;
+    fn run<'a>(&self, args: Vec<String>, working_directory: &'a str, verbose: bool, sink: &Arc<ProgressSink>) -> Result<(), Error> {
         if args.len() == 0 {
             return Err(Error::new(ErrorKind::InvalidInput,
                                 "Tool run with no paramters."));
         }
-        for i in 0..args.len() {
-            let mut arg = args[i].replace("\"", "");
-            arg = arg.replace("\'", "");
-            let cmd = arg.split("="); // in case an equals sign was used
-            let vec = cmd.collect::<Vec<&str>>();
-            let mut keyval = false;
-            if vec.len() > 1 {
-                keyval = true;
-            }
-            let flag_val = vec[0].to_lowercase().replace("--", "-");
-            if flag_val == "-i" || flag_val == "-input" || flag_val == "-dem" {
-                input_file = if keyval {
-                    vec[1].to_string()
-                } else {
-                    args[i + 1].to_string()
-                };
-            } else if flag_val == "-points" {
-                points_file = if keyval {
-                    vec[1].to_string()
-                } else {
-                    args[i + 1].to_string()
-                };
-            } else if flag_val == "-o" || flag_val == "-output" {
-                output_file = if keyval {
-                    vec[1].to_string()
-                } else {
-                    args[i + 1].to_string()
-                };
-            } else if flag_val == "-min_scale" {
-                min_scale = if keyval {
-                    vec[1].to_string().parse::<isize>().unwrap()
-                } else {
-                    args[i + 1].to_string().parse::<isize>().unwrap()
-                };
-                if min_scale < 1 { min_scale = 1; }
-            } else if flag_val == "-max_scale" {
-                max_scale = if keyval {
-                    vec[1].to_string().parse::<isize>().unwrap()
-                } else {
-                    args[i + 1].to_string().parse::<isize>().unwrap()
-                };
-            } else if flag_val == "-step" {
-                step = if keyval {
-                    vec[1].to_string().parse::<isize>().unwrap()
-                } else {
-                    args[i + 1].to_string().parse::<isize>().unwrap()
-                };
-            }
-        }
+        let args = ArgValues::parse(&self.parameters, &args)?;
+        let input_file = args.required_file("--dem", working_directory)?;
+        let points_file = args.required_file("--points", working_directory)?;
+        let output_file = args.required_file("--output", working_directory)?;
+        let mut min_scale = args.get_i64("--min_scale")?.map(|v| v as isize).unwrap_or(1isize);
+        if min_scale < 1 { min_scale = 1; }
+        let mut max_scale = args.get_i64("--max_scale")?.map(|v| v as isize).unwrap_or(100isize);
+        let mut step = args.get_i64("--step")?.map(|v| v as isize).unwrap_or(10isize);
 
         if max_scale < min_scale { 
             let ms = min_scale;
@@ -220,27 +175,15 @@ impl WhiteboxTool for MaxElevDevSignature {
         if step < 1 { step = 1; }
 
         if verbose {
-            println!("***************{}", "*".repeat(self.get_tool_name().len()));
-            println!("* Welcome to {} *", self.get_tool_name());
-            println!("***************{}", "*".repeat(self.get_tool_name().len()));
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
+            sink.message(&format!("* Welcome to {} *", self.get_tool_name()));
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
         }
 
-        let sep: String = path::MAIN_SEPARATOR.to_string();
-
         let mut progress: usize;
         let mut old_progress: usize = 1;
 
-        if !input_file.contains(&sep) && !input_file.contains("/") {
-            input_file = format!("{}{}", working_directory, input_file);
-        }
-        if !points_file.contains(&sep) && !points_file.contains("/") {
-            points_file = format!("{}{}", working_directory, points_file);
-        }
-        if !output_file.contains(&sep) && !output_file.contains("/") {
-            output_file = format!("{}{}", working_directory, output_file);
-        }
-
-        if verbose { println!("Reading data...") };
+        if verbose { sink.message("Reading data...") };
         let input = Raster::new(&input_file, "r")?;
         let start = time::now();
 
@@ -248,7 +191,7 @@ impl WhiteboxTool for MaxElevDevSignature {
         let columns = input.configs.columns as isize;
         let nodata = input.configs.nodata;
 
-        if verbose { println!("Reading points data...") };
+        if verbose { sink.message("Reading points data...") };
         let points = Shapefile::new(&points_file, "r")?;
 
         // make sure the input vector file is of points type
@@ -273,12 +216,10 @@ impl WhiteboxTool for MaxElevDevSignature {
                 series_names.push(format!("Site {}", record_num + 1));
             }
             
-            if verbose {
-                progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as usize;
-                if progress != old_progress {
-                    println!("Finding site row/column values: {}%", progress);
-                    old_progress = progress;
-                }
+            progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as usize;
+            if progress != old_progress {
+                sink.progress("Finding site row/column values", progress)?;
+                old_progress = progress;
             }
         }
     
@@ -298,7 +239,7 @@ impl WhiteboxTool for MaxElevDevSignature {
             sum_sqr = 0f64;
             sum_n = 0;
             for col in 0..columns {
-                val = input[(row, col)];
+                val = input.get_value(row, col);
                 if val == nodata {
                     val = 0f64;
                 } else {
@@ -319,12 +260,10 @@ impl WhiteboxTool for MaxElevDevSignature {
                     i_n[(row, col)] = sum_n;
                 }
             }
-            if verbose {
-                progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as usize;
-                if progress != old_progress {
-                    println!("Creating integral images: {}%", progress);
-                    old_progress = progress;
-                }
+            progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as usize;
+            if progress != old_progress {
+                sink.progress("Creating integral images", progress)?;
+                old_progress = progress;
             }
         }
 
@@ -381,7 +320,7 @@ impl WhiteboxTool for MaxElevDevSignature {
         
         let end = time::now();
         let elapsed_time = end - start;
-        if verbose { println!("\n{}",  &format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", "")); }
+        if verbose { sink.message(&format!("\n{}", &format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""))); }
 
         let f = File::create(output_file.clone())?;
         let mut writer = BufWriter::new(f);
@@ -427,7 +366,7 @@ impl WhiteboxTool for MaxElevDevSignature {
 
         let _ = writer.flush();
 
-        if verbose { println!("\n{}",  &format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", "")); }
+        if verbose { sink.message(&format!("\n{}", &format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""))); }
 
         if verbose {
             if cfg!(target_os = "macos") || cfg!(target_os = "ios") {
@@ -453,7 +392,7 @@ impl WhiteboxTool for MaxElevDevSignature {
                 let _ = output.stdout;
             }
             if verbose {
-                println!("Complete! Please see {} for output.", output_file);
+                sink.message(&format!("Complete! Please see {} for output.", output_file));
             }
         }
 
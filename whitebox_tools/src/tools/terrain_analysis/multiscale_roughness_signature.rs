@@ -26,6 +26,7 @@ use vector::{Shapefile, ShapeType};
 use structures::Array2D;
 use std::io::{Error, ErrorKind};
 use tools::*;
+use tools::arg_parser::ArgValues;
 use rendering::LineGraph;
 use rendering::html::*;
 
@@ -47,7 +48,7 @@ impl MultiscaleRoughnessSignature {
         let mut parameters = vec![];
         parameters.push(ToolParameter{
             name: "Input DEM File".to_owned(), 
-            flags: vec!["-i".to_owned(), "--dem".to_owned()], 
+            flags: vec!["-i".to_owned(), "--input".to_owned(), "--dem".to_owned()], 
             description: "Input raster DEM file.".to_owned(),
             parameter_type: ParameterType::ExistingFile(ParameterFileType::Raster),
             default_value: None,
@@ -153,66 +154,19 @@ impl WhiteboxTool for MultiscaleRoughnessSignature {
         self.toolbox.clone()
     }
 
-    fn run<'a>(&self, args: Vec<String>, working_directory: &'a str, verbose: bool) -> Result<(), Error> {
-        let mut input_file = String::new();
-        let mut points_file = String::new();
-        let mut output_file = String::new();
-        let mut min_scale = 1isize;
-        let mut max_scale = 100isize;
-        let mut step = 1isize;
+    fn run<'a>(&self, args: Vec<String>, working_directory: &'a str, verbose: bool, sink: &Arc<ProgressSink>) -> Result<(), Error> {
         if args.len() == 0 {
             return Err(Error::new(ErrorKind::InvalidInput,
                                 "Tool run with no paramters."));
         }
-        for i in 0..args.len() {
-            let mut arg = args[i].replace("\"", "");
-            arg = arg.replace("\'", "");
-            let cmd = arg.split("="); // in case an equals sign was used
-            let vec = cmd.collect::<Vec<&str>>();
-            let mut keyval = false;
-            if vec.len() > 1 {
-                keyval = true;
-            }
-            let flag_val = vec[0].to_lowercase().replace("--", "-");
-            if flag_val == "-i" || flag_val == "-input" || flag_val == "-dem" {
-                input_file = if keyval {
-                    vec[1].to_string()
-                } else {
-                    args[i + 1].to_string()
-                };
-            } else if flag_val == "-points" {
-                points_file = if keyval {
-                    vec[1].to_string()
-                } else {
-                    args[i + 1].to_string()
-                };
-            } else if flag_val == "-o" || flag_val == "-output" {
-                output_file = if keyval {
-                    vec[1].to_string()
-                } else {
-                    args[i + 1].to_string()
-                };
-            } else if flag_val == "-min_scale" {
-                min_scale = if keyval {
-                    vec[1].to_string().parse::<isize>().unwrap()
-                } else {
-                    args[i + 1].to_string().parse::<isize>().unwrap()
-                };
-                if min_scale < 1 { min_scale = 1; }
-            } else if flag_val == "-max_scale" {
-                max_scale = if keyval {
-                    vec[1].to_string().parse::<isize>().unwrap()
-                } else {
-                    args[i + 1].to_string().parse::<isize>().unwrap()
-                };
-            } else if flag_val == "-step" {
-                step = if keyval {
-                    vec[1].to_string().parse::<isize>().unwrap()
-                } else {
-                    args[i + 1].to_string().parse::<isize>().unwrap()
-                };
-            }
-        }
+        let args = ArgValues::parse(&self.parameters, &args)?;
+        let input_file = args.required_file("--dem", working_directory)?;
+        let points_file = args.required_file("--points", working_directory)?;
+        let output_file = args.required_file("--output", working_directory)?;
+        let mut min_scale = args.get_i64("--min_scale")?.map(|v| v as isize).unwrap_or(1isize);
+        if min_scale < 1 { min_scale = 1; }
+        let mut max_scale = args.get_i64("--max_scale")?.map(|v| v as isize).unwrap_or(100isize);
+        let mut step = args.get_i64("--step")?.map(|v| v as isize).unwrap_or(1isize);
 
         if max_scale < min_scale { 
             let ms = min_scale;
@@ -227,34 +181,22 @@ impl WhiteboxTool for MultiscaleRoughnessSignature {
         if step < 1 { step = 1; }
 
         if verbose {
-            println!("***************{}", "*".repeat(self.get_tool_name().len()));
-            println!("* Welcome to {} *", self.get_tool_name());
-            println!("***************{}", "*".repeat(self.get_tool_name().len()));
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
+            sink.message(&format!("* Welcome to {} *", self.get_tool_name()));
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
         }
 
-        let sep: String = path::MAIN_SEPARATOR.to_string();
-
         let mut progress: usize;
         let mut old_progress: usize = 1;
 
-        if !input_file.contains(&sep) && !input_file.contains("/") {
-            input_file = format!("{}{}", working_directory, input_file);
-        }
-        if !points_file.contains(&sep) && !points_file.contains("/") {
-            points_file = format!("{}{}", working_directory, points_file);
-        }
-        if !output_file.contains(&sep) && !output_file.contains("/") {
-            output_file = format!("{}{}", working_directory, output_file);
-        }
-        
-        if verbose { println!("Reading DEM data...") };
+        if verbose { sink.message("Reading DEM data...") };
         let input = Arc::new(Raster::new(&input_file, "r")?);
         let start = time::now();
         let rows = input.configs.rows as isize;
         let columns = input.configs.columns as isize;
         let nodata = input.configs.nodata;
 
-        if verbose { println!("Reading points data...") };
+        if verbose { sink.message("Reading points data...") };
         let points = Shapefile::new(&points_file, "r")?;
 
         // make sure the input vector file is of points type
@@ -279,12 +221,10 @@ impl WhiteboxTool for MultiscaleRoughnessSignature {
                 series_names.push(format!("Site {}", record_num + 1));
             }
             
-            if verbose {
-                progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as usize;
-                if progress != old_progress {
-                    println!("Finding site row/column values: {}%", progress);
-                    old_progress = progress;
-                }
+            progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as usize;
+            if progress != old_progress {
+                sink.progress("Finding site row/column values", progress)?;
+                old_progress = progress;
             }
         }
 
@@ -333,12 +273,10 @@ impl WhiteboxTool for MultiscaleRoughnessSignature {
             let data = rx.recv().unwrap();
             nv.set_row_data(data.0, data.1);
             
-            if verbose {
-                progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as usize;
-                if progress != old_progress {
-                    println!("Calculating normal vectors: {}%", progress);
-                    old_progress = progress;
-                }
+            progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as usize;
+            if progress != old_progress {
+                sink.progress("Calculating normal vectors", progress)?;
+                old_progress = progress;
             }
         }
 
@@ -357,7 +295,7 @@ impl WhiteboxTool for MultiscaleRoughnessSignature {
             sum = 0f64;
             sum_n = 0;
             for col in 0..columns {
-                val = input[(row, col)];
+                val = input.get_value(row, col);
                 if val == nodata {
                     val = 0f64;
                 } else {
@@ -374,12 +312,10 @@ impl WhiteboxTool for MultiscaleRoughnessSignature {
                     integral_n[(row, col)] = sum_n;
                 }
             }
-            if verbose {
-                progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as usize;
-                if progress != old_progress {
-                    println!("Creating integral images: {}%", progress);
-                    old_progress = progress;
-                }
+            progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as usize;
+            if progress != old_progress {
+                sink.progress("Creating integral images", progress)?;
+                old_progress = progress;
             }
         }
 
@@ -393,7 +329,7 @@ impl WhiteboxTool for MultiscaleRoughnessSignature {
         for midpoint in (min_scale..max_scale).filter(|s| (s - min_scale) % step == 0) { // .step_by(step) { once step_by is stabilized
             // loop_num += 1;
 
-            println!("Filter Size {} / {}", midpoint, max_scale);
+            sink.message(&format!("Filter Size {} / {}", midpoint, max_scale));
 
             ////////////////////////////////////////////////////////////////////////////
             // Use the integral image to smooth the DEM at a scale of the filter size //
@@ -421,7 +357,7 @@ impl WhiteboxTool for MultiscaleRoughnessSignature {
                         }
                         let mut data = vec![nodata; columns as usize];
                         for col in 0..columns {
-                            z = input_data[(row, col)];
+                            z = input_data.get_value(row, col);
                             if z != nodata {
                                 x1 = col - midpoint - 1;
                                 if x1 < 0 {
@@ -508,7 +444,7 @@ impl WhiteboxTool for MultiscaleRoughnessSignature {
             let mut z: f64;
             for s in 0..signature_sites.len() {
                 let (row, col) = signature_sites[s];
-                z = input[(row, col)];
+                z = input.get_value(row, col);
                 if z != nodata {
                     y1 = row - midpoint - 1;
                     if y1 < 0 {
@@ -542,7 +478,7 @@ impl WhiteboxTool for MultiscaleRoughnessSignature {
 
         let end = time::now();
         let elapsed_time = end - start;
-        if verbose { println!("\n{}",  &format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", "")); }
+        if verbose { sink.message(&format!("\n{}", &format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""))); }
 
         let f = File::create(output_file.clone())?;
         let mut writer = BufWriter::new(f);
@@ -610,7 +546,7 @@ impl WhiteboxTool for MultiscaleRoughnessSignature {
                 let _ = output.stdout;
             }
 
-            println!("Complete! Please see {} for output.", output_file);
+            sink.message(&format!("Complete! Please see {} for output.", output_file));
         }
 
         Ok(())
@@ -17,6 +17,7 @@ use std::thread;
 use raster::*;
 use std::io::{Error, ErrorKind};
 use tools::*;
+use tools::arg_parser::ArgValues;
 
 /// Tool struct containing the essential descriptors required to interact with the tool.
 pub struct MultiscaleTopographicPositionImage {
@@ -135,87 +136,32 @@ impl WhiteboxTool for MultiscaleTopographicPositionImage {
         self.toolbox.clone()
     }
 
-    fn run<'a>(&self, args: Vec<String>, working_directory: &'a str, verbose: bool) -> Result<(), Error> {
-        let mut input1_file = String::new();
-        let mut input2_file = String::new();
-        let mut input3_file = String::new();
-        let mut output_file = String::new();
-        let mut cutoff = 1.2f64;
+    fn run<'a>(&self, args: Vec<String>, working_directory: &'a str, verbose: bool, sink: &Arc<ProgressSink>) -> Result<(), Error> {
         if args.len() == 0 {
             return Err(Error::new(ErrorKind::InvalidInput,
                                 "Tool run with no paramters."));
         }
-        for i in 0..args.len() {
-            let mut arg = args[i].replace("\"", "");
-            arg = arg.replace("\'", "");
-            let cmd = arg.split("="); // in case an equals sign was used
-            let vec = cmd.collect::<Vec<&str>>();
-            let mut keyval = false;
-            if vec.len() > 1 {
-                keyval = true;
-            }
-            if vec[0].to_lowercase() == "-broad" || vec[0].to_lowercase() == "--broad" {
-                if keyval {
-                    input1_file = vec[1].to_string();
-                } else {
-                    input1_file = args[i+1].to_string();
-                }
-            } else if vec[0].to_lowercase() == "-meso" || vec[0].to_lowercase() == "--meso" {
-                if keyval {
-                    input2_file = vec[1].to_string();
-                } else {
-                    input2_file = args[i+1].to_string();
-                }
-            } else if vec[0].to_lowercase() == "-local" || vec[0].to_lowercase() == "--local" {
-                if keyval {
-                    input3_file = vec[1].to_string();
-                } else {
-                    input3_file = args[i+1].to_string();
-                }
-            } else if vec[0].to_lowercase() == "-o" || vec[0].to_lowercase() == "--output" {
-                if keyval {
-                    output_file = vec[1].to_string();
-                } else {
-                    output_file = args[i+1].to_string();
-                }
-            } else if vec[0].to_lowercase() == "-lightness" || vec[0].to_lowercase() == "--lightness" {
-                if keyval {
-                    cutoff = vec[1].to_string().parse::<f64>().unwrap();
-                } else {
-                    cutoff = args[i+1].to_string().parse::<f64>().unwrap();
-                }
-            }
-        }
+        let args = ArgValues::parse(&self.parameters, &args)?;
+        let input1_file = args.required_file("--broad", working_directory)?;
+        let input2_file = args.required_file("--meso", working_directory)?;
+        let input3_file = args.required_file("--local", working_directory)?;
+        let output_file = args.required_file("--output", working_directory)?;
+        let cutoff = args.get_f64("--lightness")?.unwrap_or(1.2f64);
 
         if verbose {
-            println!("***************{}", "*".repeat(self.get_tool_name().len()));
-            println!("* Welcome to {} *", self.get_tool_name());
-            println!("***************{}", "*".repeat(self.get_tool_name().len()));
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
+            sink.message(&format!("* Welcome to {} *", self.get_tool_name()));
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
         }
 
-        let sep: String = path::MAIN_SEPARATOR.to_string();
-
         let mut progress: usize;
         let mut old_progress: usize = 1;
 
-        if !input1_file.contains(&sep) && input1_file.contains("/") {
-            input1_file = format!("{}{}", working_directory, input1_file);
-        }
-        if !input2_file.contains(&sep) && input2_file.contains("/") {
-            input2_file = format!("{}{}", working_directory, input2_file);
-        }
-        if !input3_file.contains(&sep) && input3_file.contains("/") {
-            input3_file = format!("{}{}", working_directory, input3_file);
-        }
-        if !output_file.contains(&sep) && !output_file.contains("/") {
-            output_file = format!("{}{}", working_directory, output_file);
-        }
-
-        if verbose { println!("Reading broad-scale DEV data...") };
+        if verbose { sink.message("Reading broad-scale DEV data...") };
         let input_r = Arc::new(Raster::new(&input1_file, "r")?);
-        if verbose { println!("Reading meso-scale DEV data...") };
+        if verbose { sink.message("Reading meso-scale DEV data...") };
         let input_g = Arc::new(Raster::new(&input2_file, "r")?);
-        if verbose { println!("Reading local-scale DEV data...") };
+        if verbose { sink.message("Reading local-scale DEV data...") };
         let input_b = Arc::new(Raster::new(&input3_file, "r")?);
 
         let rows = input_r.configs.rows as isize;
@@ -257,9 +203,9 @@ impl WhiteboxTool for MultiscaleTopographicPositionImage {
                 for row in (0..rows).filter(|r| r % num_procs == tid) {
                     let mut data = vec![nodata_r; columns as usize];
                     for col in 0..columns {
-                        red_val = input_r[(row, col)];
-                        green_val = input_g[(row, col)];
-                        blue_val = input_b[(row, col)];
+                        red_val = input_r.get_value(row, col);
+                        green_val = input_g.get_value(row, col);
+                        blue_val = input_b.get_value(row, col);
                         if red_val != nodata_r && green_val != nodata_g && blue_val != nodata_b {
 
                             /* Replaced the linear interpolation with this logistic function.*/
@@ -305,12 +251,10 @@ impl WhiteboxTool for MultiscaleTopographicPositionImage {
         for row in 0..rows {
             let data = rx.recv().unwrap();
             output.set_row_data(data.0, data.1);
-            if verbose {
-                progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as usize;
-                if progress != old_progress {
-                    println!("Progress: {}%", progress);
-                    old_progress = progress;
-                }
+            progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as usize;
+            if progress != old_progress {
+                sink.progress("Progress", progress)?;
+                old_progress = progress;
             }
         }
 
@@ -322,13 +266,13 @@ impl WhiteboxTool for MultiscaleTopographicPositionImage {
         output.add_metadata_entry(format!("Input local-scale image file: {}", input3_file));
         output.add_metadata_entry(format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
 
-        if verbose { println!("Saving data...") };
+        if verbose { sink.message("Saving data...") };
         let _ = match output.write() {
-            Ok(_) => if verbose { println!("Output file written") },
+            Ok(_) => if verbose { sink.message("Output file written") },
             Err(e) => return Err(e),
         };
         if verbose {
-            println!("{}", &format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
+            sink.message(&format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
         }
 
         Ok(())
@@ -2,7 +2,7 @@
 This tool is part of the WhiteboxTools geospatial analysis library.
 Authors: Dr. John Lindsay
 Created: July 12, 2017
-Last Modified: Dec. 15, 2017
+Last Modified: March 15, 2018
 License: MIT
 
 Notes: Some degree of DEM smoothing is likely required to get reasonable results.
@@ -18,7 +18,9 @@ use std::sync::mpsc;
 use std::thread;
 use raster::*;
 use std::io::{Error, ErrorKind};
+use std::cmp::min;
 use tools::*;
+use tools::arg_parser::ArgValues;
 
 pub struct PennockLandformClass {
     name: String,
@@ -37,7 +39,7 @@ impl PennockLandformClass {
         let mut parameters = vec![];
         parameters.push(ToolParameter{
             name: "Input DEM File".to_owned(), 
-            flags: vec!["-i".to_owned(), "--dem".to_owned()], 
+            flags: vec!["-i".to_owned(), "--input".to_owned(), "--dem".to_owned()], 
             description: "Input raster DEM file.".to_owned(),
             parameter_type: ParameterType::ExistingFile(ParameterFileType::Raster),
             default_value: None,
@@ -143,85 +145,29 @@ impl WhiteboxTool for PennockLandformClass {
         self.toolbox.clone()
     }
 
-    fn run<'a>(&self, args: Vec<String>, working_directory: &'a str, verbose: bool) -> Result<(), Error> {
-        let mut input_file = String::new();
-        let mut output_file = String::new();
-        let mut z_factor = 1f64;
-        let mut slope_threshold = 3f64;
-        let mut prof_threshold = 0.1_f64;
-        let mut plan_threshold = 0f64;
-
+    fn run<'a>(&self, args: Vec<String>, working_directory: &'a str, verbose: bool, sink: &Arc<ProgressSink>) -> Result<(), Error> {
         if args.len() == 0 {
             return Err(Error::new(ErrorKind::InvalidInput,
                                 "Tool run with no paramters."));
         }
-        for i in 0..args.len() {
-            let mut arg = args[i].replace("\"", "");
-            arg = arg.replace("\'", "");
-            let cmd = arg.split("="); // in case an equals sign was used
-            let vec = cmd.collect::<Vec<&str>>();
-            let mut keyval = false;
-            if vec.len() > 1 {
-                keyval = true;
-            }
-            if vec[0].to_lowercase() == "-i" || vec[0].to_lowercase() == "--input" || vec[0].to_lowercase() == "--dem" {
-                if keyval {
-                    input_file = vec[1].to_string();
-                } else {
-                    input_file = args[i+1].to_string();
-                }
-            } else if vec[0].to_lowercase() == "-o" || vec[0].to_lowercase() == "--output" {
-                if keyval {
-                    output_file = vec[1].to_string();
-                } else {
-                    output_file = args[i+1].to_string();
-                }
-            } else if vec[0].to_lowercase() == "-zfactor" || vec[0].to_lowercase() == "--zfactor" {
-                if keyval {
-                    z_factor = vec[1].to_string().parse::<f64>().unwrap();
-                } else {
-                    z_factor = args[i+1].to_string().parse::<f64>().unwrap();
-                }
-            } else if vec[0].to_lowercase() == "-slope" || vec[0].to_lowercase() == "--slope" {
-                if keyval {
-                    slope_threshold = vec[1].to_string().parse::<f64>().unwrap();
-                } else {
-                    slope_threshold = args[i+1].to_string().parse::<f64>().unwrap();
-                }
-            } else if vec[0].to_lowercase() == "-prof" || vec[0].to_lowercase() == "--prof" {
-                if keyval {
-                    prof_threshold = vec[1].to_string().parse::<f64>().unwrap();
-                } else {
-                    prof_threshold = args[i+1].to_string().parse::<f64>().unwrap();
-                }
-            } else if vec[0].to_lowercase() == "-plan" || vec[0].to_lowercase() == "--plan" {
-                if keyval {
-                    plan_threshold = vec[1].to_string().parse::<f64>().unwrap();
-                } else {
-                    plan_threshold = args[i+1].to_string().parse::<f64>().unwrap();
-                }
-            }
-        }
+        let args = ArgValues::parse(&self.parameters, &args)?;
+        let input_file = args.required_file("--dem", working_directory)?;
+        let output_file = args.required_file("--output", working_directory)?;
+        let mut z_factor = args.get_f64("--zfactor")?.unwrap_or(1f64);
+        let slope_threshold = args.get_f64("--slope")?.unwrap_or(3f64);
+        let prof_threshold = args.get_f64("--prof")?.unwrap_or(0.1_f64);
+        let plan_threshold = args.get_f64("--plan")?.unwrap_or(0f64);
 
         if verbose {
-            println!("***************{}", "*".repeat(self.get_tool_name().len()));
-            println!("* Welcome to {} *", self.get_tool_name());
-            println!("***************{}", "*".repeat(self.get_tool_name().len()));
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
+            sink.message(&format!("* Welcome to {} *", self.get_tool_name()));
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
         }
 
-        let sep: String = path::MAIN_SEPARATOR.to_string();
-
         let mut progress: usize;
         let mut old_progress: usize = 1;
 
-        if !input_file.contains(&sep) && !input_file.contains("/") {
-            input_file = format!("{}{}", working_directory, input_file);
-        }
-        if !output_file.contains(&sep) && !output_file.contains("/") {
-            output_file = format!("{}{}", working_directory, output_file);
-        }
-
-        if verbose { println!("Reading data...") };
+        if verbose { sink.message("Reading data...") };
 
         let input = Arc::new(Raster::new(&input_file, "r")?);
 
@@ -248,97 +194,104 @@ impl WhiteboxTool for PennockLandformClass {
         let nodata = input.configs.nodata;
         output.configs.nodata = -128f64;
         output.configs.data_type = DataType::I8;
+        output.reinitialize_values(-128f64);
         output.configs.photometric_interp = PhotometricInterpretation::Continuous; //Categorical;
         // output.configs.palette = "qual.plt".to_string();
                
         let num_procs = num_cpus::get() as isize;
-        let (tx, rx) = mpsc::channel();
-        for tid in 0..num_procs {
-            let input = input.clone();
-            let tx = tx.clone();
-            thread::spawn(move || {
-                let dx = [ 1, 1, 1, 0, -1, -1, -1, 0 ];
-                let dy = [ -1, 0, 1, 1, 1, 0, -1, -1 ];
-                let mut n: [f64; 8] = [0.0; 8];
-                let mut z: f64;
-                let (mut zx, mut zy, mut zxx, mut zyy, mut zxy, mut zx2, mut zy2): (f64, f64, f64, f64, f64, f64, f64);
-                let mut p: f64;
-                let mut q: f64;
-                let (mut fx, mut fy): (f64, f64);
-                let mut slope: f64;
-                let mut plan: f64;
-                let mut prof: f64;
-                for row in (0..rows).filter(|r| r % num_procs == tid) {
-                    let mut data = vec![-128f64; columns as usize];
-                    for col in 0..columns {
-                        z = input[(row, col)];
-                        if z != nodata {
-                            z = z * z_factor;
-                            for c in 0..8 {
-                                n[c] = input[(row + dy[c], col + dx[c])];
-                                if n[c] != nodata {
-                                    n[c] = n[c] * z_factor;
-                                } else {
-                                    n[c] = z;
+        // the DEM is read in windows of rows, with a halo of one row on either side
+        let window_rows = input.get_window_rows();
+        let mut start_row = 0;
+        while start_row < rows {
+            let end_row = min(start_row + window_rows, rows);
+            let window = Arc::new(input.get_window(start_row - 1, end_row + 1));
+            let (tx, rx) = mpsc::channel();
+            for tid in 0..num_procs {
+                let window = window.clone();
+                let tx = tx.clone();
+                thread::spawn(move || {
+                    let dx = [ 1, 1, 1, 0, -1, -1, -1, 0 ];
+                    let dy = [ -1, 0, 1, 1, 1, 0, -1, -1 ];
+                    let mut n: [f64; 8] = [0.0; 8];
+                    let mut z: f64;
+                    let (mut zx, mut zy, mut zxx, mut zyy, mut zxy, mut zx2, mut zy2): (f64, f64, f64, f64, f64, f64, f64);
+                    let mut p: f64;
+                    let mut q: f64;
+                    let (mut fx, mut fy): (f64, f64);
+                    let mut slope: f64;
+                    let mut plan: f64;
+                    let mut prof: f64;
+                    for row in (start_row..end_row).filter(|r| r % num_procs == tid) {
+                        let mut data = vec![-128f64; columns as usize];
+                        for col in 0..columns {
+                            z = window.get_value(row, col);
+                            if z != nodata {
+                                z = z * z_factor;
+                                for c in 0..8 {
+                                    n[c] = window.get_value(row + dy[c], col + dx[c]);
+                                    if n[c] != nodata {
+                                        n[c] = n[c] * z_factor;
+                                    } else {
+                                        n[c] = z;
+                                    }
                                 }
-                            }
-                            // calculate curvature
-                            zx = (n[1] - n[5]) / cell_size_times2;
-                            zy = (n[7] - n[3]) / cell_size_times2;
-                            zxx = (n[1] - 2.0f64 * z + n[5]) / cell_size_sqrd;
-                            zyy = (n[7] - 2.0f64 * z + n[3]) / cell_size_sqrd;
-                            zxy = (-n[6] + n[0] + n[4] - n[2]) / four_times_cell_size_sqrd;
-                            zx2 = zx * zx;
-                            zy2 = zy * zy;
-                            p = zx2 + zy2;
-                            q = p + 1f64;
-                            if p > 0.0f64 {
-                                fy = (n[6] - n[4] + 2.0 * (n[7] - n[3]) + n[0] - n[2]) / eight_grid_res;
-                                fx = (n[2] - n[4] + 2.0 * (n[1] - n[5]) + n[0] - n[6]) / eight_grid_res;
-                                slope = (fx * fx + fy * fy).sqrt().atan().to_degrees();
-                                plan = -1f64 * ((zxx * zy2 - 2f64 * zxy * zx * zy + zyy * zx2) / p.powf(1.5f64)).to_degrees();
-                                prof = -1f64 * ((zxx * zx2 - 2f64 * zxy * zx * zy + zyy * zy2) / (p * q.powf(1.5f64))).to_degrees();
+                                // calculate curvature
+                                zx = (n[1] - n[5]) / cell_size_times2;
+                                zy = (n[7] - n[3]) / cell_size_times2;
+                                zxx = (n[1] - 2.0f64 * z + n[5]) / cell_size_sqrd;
+                                zyy = (n[7] - 2.0f64 * z + n[3]) / cell_size_sqrd;
+                                zxy = (-n[6] + n[0] + n[4] - n[2]) / four_times_cell_size_sqrd;
+                                zx2 = zx * zx;
+                                zy2 = zy * zy;
+                                p = zx2 + zy2;
+                                q = p + 1f64;
+                                if p > 0.0f64 {
+                                    fy = (n[6] - n[4] + 2.0 * (n[7] - n[3]) + n[0] - n[2]) / eight_grid_res;
+                                    fx = (n[2] - n[4] + 2.0 * (n[1] - n[5]) + n[0] - n[6]) / eight_grid_res;
+                                    slope = (fx * fx + fy * fy).sqrt().atan().to_degrees();
+                                    plan = -1f64 * ((zxx * zy2 - 2f64 * zxy * zx * zy + zyy * zx2) / p.powf(1.5f64)).to_degrees();
+                                    prof = -1f64 * ((zxx * zx2 - 2f64 * zxy * zx * zy + zyy * zy2) / (p * q.powf(1.5f64))).to_degrees();
                                 
-                                if prof < -prof_threshold && plan <= -plan_threshold && slope > slope_threshold {
-                                    //Convergent Footslope
-                                    data[col as usize] = 1f64;
-                                } else if prof < -prof_threshold && plan > plan_threshold && slope > slope_threshold {
-                                    //Divergent Footslope
-                                    data[col as usize] = 2f64;
-                                } else if prof > prof_threshold && plan <= plan_threshold && slope > slope_threshold {
-                                    //Convergent Shoulder
-                                    data[col as usize] = 3f64;
-                                } else if prof > prof_threshold && plan > plan_threshold && slope > slope_threshold {
-                                    //Divergent Shoulder
-                                    data[col as usize] = 4f64;
-                                } else if prof >= -prof_threshold && prof < prof_threshold && slope > slope_threshold && plan <= -plan_threshold {
-                                    //Convergent Backslope
-                                    data[col as usize] = 5f64;
-                                } else if prof >= -prof_threshold && prof < prof_threshold && slope > slope_threshold && plan > plan_threshold {
-                                    //Divergent Backslope
-                                    data[col as usize] = 6f64;
-                                } else if slope <= slope_threshold {
-                                    //Level
-                                    data[col as usize] = 7f64;
+                                    if prof < -prof_threshold && plan <= -plan_threshold && slope > slope_threshold {
+                                        //Convergent Footslope
+                                        data[col as usize] = 1f64;
+                                    } else if prof < -prof_threshold && plan > plan_threshold && slope > slope_threshold {
+                                        //Divergent Footslope
+                                        data[col as usize] = 2f64;
+                                    } else if prof > prof_threshold && plan <= plan_threshold && slope > slope_threshold {
+                                        //Convergent Shoulder
+                                        data[col as usize] = 3f64;
+                                    } else if prof > prof_threshold && plan > plan_threshold && slope > slope_threshold {
+                                        //Divergent Shoulder
+                                        data[col as usize] = 4f64;
+                                    } else if prof >= -prof_threshold && prof < prof_threshold && slope > slope_threshold && plan <= -plan_threshold {
+                                        //Convergent Backslope
+                                        data[col as usize] = 5f64;
+                                    } else if prof >= -prof_threshold && prof < prof_threshold && slope > slope_threshold && plan > plan_threshold {
+                                        //Divergent Backslope
+                                        data[col as usize] = 6f64;
+                                    } else if slope <= slope_threshold {
+                                        //Level
+                                        data[col as usize] = 7f64;
+                                    }
                                 }
                             }
                         }
+                        tx.send((row, data)).unwrap();
                     }
-                    tx.send((row, data)).unwrap();
-                }
-            });
-        }
+                });
+            }
 
-        for r in 0..rows {
-            let (row, data) = rx.recv().unwrap();
-            output.set_row_data(row, data);
-            if verbose {
+            for r in start_row..end_row {
+                let (row, data) = rx.recv().unwrap();
+                output.set_row_data(row, data);
                 progress = (100.0_f64 * r as f64 / (rows - 1) as f64) as usize;
                 if progress != old_progress {
-                    println!("Progress: {}%", progress);
+                    sink.progress("Progress", progress)?;
                     old_progress = progress;
                 }
             }
+            start_row = end_row;
         }
 
         let end = time::now();
@@ -359,25 +312,25 @@ impl WhiteboxTool for PennockLandformClass {
         output.add_metadata_entry(format!("5      Convergent Backslope"));
         output.add_metadata_entry(format!("6      Divergent Backslope"));
         output.add_metadata_entry(format!("7      Level"));
-        if verbose { println!("Saving data...") };
+        if verbose { sink.message("Saving data...") };
         let _ = match output.write() {
-            Ok(_) => if verbose { println!("Output file written") },
+            Ok(_) => if verbose { sink.message("Output file written") },
             Err(e) => return Err(e),
         };
 
         if verbose {
-            println!("CLASSIFICATION KEY");
-            println!("Value  Class");
-            println!("1      Convergent Footslope");
-            println!("2      Divergent Footslope");
-            println!("3      Convergent Shoulder");
-            println!("4      Divergent Shoulder");
-            println!("5      Convergent Backslope");
-            println!("6      Divergent Backslope");
-            println!("7      Level");
+            sink.message("CLASSIFICATION KEY");
+            sink.message("Value  Class");
+            sink.message("1      Convergent Footslope");
+            sink.message("2      Divergent Footslope");
+            sink.message("3      Convergent Shoulder");
+            sink.message("4      Divergent Shoulder");
+            sink.message("5      Convergent Backslope");
+            sink.message("6      Divergent Backslope");
+            sink.message("7      Level");
             
         
-            println!("{}", &format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
+            sink.message(&format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
         }
 
         Ok(())
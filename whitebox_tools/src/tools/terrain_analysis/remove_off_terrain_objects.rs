@@ -14,10 +14,12 @@ use std::io::{Error, ErrorKind};
 use std::path;
 use std::f64;
 use std::collections::VecDeque;
+use std::sync::Arc;
 use raster::*;
 use structures::FixedRadiusSearch2D;
 use structures::Array2D;
 use tools::*;
+use tools::arg_parser::ArgValues;
 
 pub struct RemoveOffTerrainObjects {
     name: String,
@@ -124,56 +126,21 @@ impl WhiteboxTool for RemoveOffTerrainObjects {
         self.toolbox.clone()
     }
 
-    fn run<'a>(&self, args: Vec<String>, working_directory: &'a str, verbose: bool) -> Result<(), Error> {
-        let mut input_file = String::new();
-        let mut output_file = String::new();
-        let mut filter_size = 11usize;
-        let mut slope_threshold = 15f64;
-        let mut keyval: bool;
+    fn run<'a>(&self, args: Vec<String>, working_directory: &'a str, verbose: bool, sink: &Arc<ProgressSink>) -> Result<(), Error> {
         if args.len() == 0 {
             return Err(Error::new(ErrorKind::InvalidInput, "Tool run with no paramters."));
         }
-        for i in 0..args.len() {
-            let mut arg = args[i].replace("\"", "");
-            arg = arg.replace("\'", "");
-            let cmd = arg.split("="); // in case an equals sign was used
-            let vec = cmd.collect::<Vec<&str>>();
-            keyval = false;
-            if vec.len() > 1 { keyval = true; }
-            if vec[0].to_lowercase() == "-i" || vec[0].to_lowercase() == "--input" || vec[0].to_lowercase() == "--dem" {
-                if keyval {
-                    input_file = vec[1].to_string();
-                } else {
-                    input_file = args[i+1].to_string();
-                }
-            } else if vec[0].to_lowercase() == "-o" || vec[0].to_lowercase() == "--output" {
-                if keyval {
-                    output_file = vec[1].to_string();
-                } else {
-                    output_file = args[i+1].to_string();
-                }
-            } else if vec[0].to_lowercase() == "-filter" || vec[0].to_lowercase() == "--filter" {
-                if keyval {
-                    filter_size = vec[1].to_string().parse::<usize>().unwrap();
-                } else {
-                    filter_size = args[i+1].to_string().parse::<usize>().unwrap();
-                }
-            } else if vec[0].to_lowercase() == "-slope" || vec[0].to_lowercase() == "--slope" {
-                if keyval {
-                    slope_threshold = vec[1].to_string().parse::<f64>().unwrap();
-                } else {
-                    slope_threshold = args[i+1].to_string().parse::<f64>().unwrap();
-                }
-            }
-        }
+        let args = ArgValues::parse(&self.parameters, &args)?;
+        let input_file = args.required_file("--dem", working_directory)?;
+        let output_file = args.required_file("--output", working_directory)?;
+        let mut filter_size = args.get_usize("--filter")?.unwrap_or(11usize);
+        let slope_threshold = args.get_f64("--slope")?.unwrap_or(15f64);
         if verbose {
-            println!("***************{}", "*".repeat(self.get_tool_name().len()));
-            println!("* Welcome to {} *", self.get_tool_name());
-            println!("***************{}", "*".repeat(self.get_tool_name().len()));
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
+            sink.message(&format!("* Welcome to {} *", self.get_tool_name()));
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
         }
 
-        let sep: String = path::MAIN_SEPARATOR.to_string();
-
         // The filter dimensions must be odd numbers such that there is a middle pixel
         if (filter_size as f64 / 2f64).floor() == (filter_size as f64 / 2f64) {
             filter_size += 1;
@@ -186,14 +153,7 @@ impl WhiteboxTool for RemoveOffTerrainObjects {
         let mut progress: usize;
         let mut old_progress: usize = 1;
 
-        if !input_file.contains(&sep) && !input_file.contains("/") {
-            input_file = format!("{}{}", working_directory, input_file);
-        }
-        if !output_file.contains(&sep) && !output_file.contains("/") {
-            output_file = format!("{}{}", working_directory, output_file);
-        }
-
-        if verbose { println!("Reading data...") };
+        if verbose { sink.message("Reading data...") };
         let input = Raster::new(&input_file, "r")?;
 
         let start = time::now();
@@ -220,7 +180,7 @@ impl WhiteboxTool for RemoveOffTerrainObjects {
 
         // Perform the white tophat transform
         { // This additional scope is simply to ensure that erosion is cleaned up at the end of the white tophat transform.
-            if verbose { println!("Performing tophat transform...") };
+            if verbose { sink.message("Performing tophat transform...") };
             let mut erosion: Array2D<f64> = Array2D::new(rows, columns, 0f64, nodata)?;
             for row in 0..rows {
                 let mut filter_vals: VecDeque<f64> = VecDeque::with_capacity(filter_size);
@@ -265,12 +225,10 @@ impl WhiteboxTool for RemoveOffTerrainObjects {
                         tophat[(row, col)] = nodata;
                     }
                 }
-                if verbose {
-                    progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as usize;
-                    if progress != old_progress {
-                        println!("Performing erosion: {}%", progress);
-                        old_progress = progress;
-                    }
+                progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as usize;
+                if progress != old_progress {
+                    sink.progress("Performing erosion", progress)?;
+                    old_progress = progress;
                 }
             }
 
@@ -312,18 +270,16 @@ impl WhiteboxTool for RemoveOffTerrainObjects {
                         }
                     }
                 }
-                if verbose {
-                    progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as usize;
-                    if progress != old_progress {
-                        println!("Performing dilation: {}%", progress);
-                        old_progress = progress;
-                    }
+                progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as usize;
+                if progress != old_progress {
+                    sink.progress("Performing dilation", progress)?;
+                    old_progress = progress;
                 }
             }
         }
 
         // Back-fill the shallow hills using region growing
-        if verbose { println!("Backfilling hills...") };
+        if verbose { sink.message("Backfilling hills...") };
         let initial_value = f64::NEG_INFINITY;
         let mut out: Array2D<f64> = Array2D::new(rows, columns, initial_value, nodata)?;
         let mut stack: Vec<GridCell> = vec![];
@@ -341,12 +297,10 @@ impl WhiteboxTool for RemoveOffTerrainObjects {
                     out[(row, col)] = nodata;
                 }
             }
-            if verbose {
-                progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as usize;
-                if progress != old_progress {
-                    println!("Finding seed cells: {}%", progress);
-                    old_progress = progress;
-                }
+            progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as usize;
+            if progress != old_progress {
+                sink.progress("Finding seed cells", progress)?;
+                old_progress = progress;
             }
         }
 
@@ -369,7 +323,7 @@ impl WhiteboxTool for RemoveOffTerrainObjects {
         }
 
         // Interpolate the data holes. Start by locating all the edge cells.
-        if verbose { println!("Interpolating data holes...") };
+        if verbose { sink.message("Interpolating data holes...") };
         let mut frs: FixedRadiusSearch2D<f64> = FixedRadiusSearch2D::new(filter_size as f64 / 1.5f64);
         for row in 0..rows {
             for col in 0..columns {
@@ -384,12 +338,10 @@ impl WhiteboxTool for RemoveOffTerrainObjects {
                     }
                 }
             }
-            if verbose {
-                progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as usize;
-                if progress != old_progress {
-                    println!("Finding OTO edge cells: {}%", progress);
-                    old_progress = progress;
-                }
+            progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as usize;
+            if progress != old_progress {
+                sink.progress("Finding OTO edge cells", progress)?;
+                old_progress = progress;
             }
         }
 
@@ -422,12 +374,10 @@ impl WhiteboxTool for RemoveOffTerrainObjects {
                     out[(row, col)] = opening[(row, col)] + tophat[(row, col)];
                 }
             }
-            if verbose {
-                progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as usize;
-                if progress != old_progress {
-                    println!("Interpolating data holes: {}%", progress);
-                    old_progress = progress;
-                }
+            progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as usize;
+            if progress != old_progress {
+                sink.progress("Interpolating data holes", progress)?;
+                old_progress = progress;
             }
         }
 
@@ -444,12 +394,10 @@ impl WhiteboxTool for RemoveOffTerrainObjects {
                     output.set_value(row, col, nodata);
                 }
             }
-            if verbose {
-                progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as usize;
-                if progress != old_progress {
-                    println!("Outputing data: {}%", progress);
-                    old_progress = progress;
-                }
+            progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as usize;
+            if progress != old_progress {
+                sink.progress("Outputing data", progress)?;
+                old_progress = progress;
             }
         }
 
@@ -459,13 +407,13 @@ impl WhiteboxTool for RemoveOffTerrainObjects {
         output.add_metadata_entry(format!("Slope threshold: {}", slope_threshold));
         output.add_metadata_entry(format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
 
-        if verbose { println!("Saving data...") };
+        if verbose { sink.message("Saving data...") };
         let _ = match output.write() {
-            Ok(_) => if verbose { println!("Output file written") },
+            Ok(_) => if verbose { sink.message("Output file written") },
             Err(e) => return Err(e),
         };
         if verbose {
-            println!("{}", &format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
+            sink.message(&format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
         }
 
 
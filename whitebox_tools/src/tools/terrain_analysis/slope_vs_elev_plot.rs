@@ -20,7 +20,9 @@ use std::thread;
 use std::process::Command;
 use raster::*;
 use std::io::{Error, ErrorKind};
+use std::cmp::min;
 use tools::*;
+use tools::arg_parser::ArgValues;
 use rendering::LineGraph;
 use rendering::html::*;
 use structures::Array2D;
@@ -129,50 +131,21 @@ impl WhiteboxTool for SlopeVsElevationPlot {
     fn run<'a>(&self,
                args: Vec<String>,
                working_directory: &'a str,
-               verbose: bool)
+               verbose: bool,
+               sink: &Arc<ProgressSink>)
                -> Result<(), Error> {
-        let mut input_files_str = String::new();
-        let mut watershed_files_str = "".to_string();
-        let mut output_file = String::new();
-
         if args.len() == 0 {
             return Err(Error::new(ErrorKind::InvalidInput, "Tool run with no paramters."));
         }
-        for i in 0..args.len() {
-            let mut arg = args[i].replace("\"", "");
-            arg = arg.replace("\'", "");
-            let cmd = arg.split("="); // in case an equals sign was used
-            let vec = cmd.collect::<Vec<&str>>();
-            let mut keyval = false;
-            if vec.len() > 1 {
-                keyval = true;
-            }
-            let flag_val = vec[0].to_lowercase().replace("--", "-");
-            if flag_val == "-i" || flag_val == "-inputs" {
-                input_files_str = if keyval {
-                    vec[1].to_string()
-                } else {
-                    args[i+1].to_string()
-                };
-            } else if flag_val == "-watershed" {
-                watershed_files_str = if keyval {
-                    vec[1].to_string()
-                } else {
-                    args[i+1].to_string()
-                };
-            } else if flag_val == "-o" || flag_val == "-output" {
-                output_file = if keyval {
-                    vec[1].to_string()
-                } else {
-                    args[i + 1].to_string()
-                };
-            }
-        }
+        let args = ArgValues::parse(&self.parameters, &args)?;
+        let input_files_str = args.required_string("--inputs")?;
+        let watershed_files_str = args.get_string("--watershed")?.unwrap_or("".to_string());
+        let output_file = args.required_file("--output", working_directory)?;
 
         if verbose {
-            println!("***************{}", "*".repeat(self.get_tool_name().len()));
-            println!("* Welcome to {} *", self.get_tool_name());
-            println!("***************{}", "*".repeat(self.get_tool_name().len()));
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
+            sink.message(&format!("* Welcome to {} *", self.get_tool_name()));
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
         }
 
         let sep: String = path::MAIN_SEPARATOR.to_string();
@@ -194,10 +167,6 @@ impl WhiteboxTool for SlopeVsElevationPlot {
                                 "There is something incorrect about the input DEM files. At least one input DEM is required to operate this tool."));
         }
 
-        if !output_file.contains(&sep) && !output_file.contains("/") {
-            output_file = format!("{}{}", working_directory, output_file);
-        }
-
         let f = File::create(output_file.clone())?;
         let mut writer = BufWriter::new(f);
 
@@ -249,54 +218,58 @@ impl WhiteboxTool for SlopeVsElevationPlot {
                 
                 // calculate slope
                 let num_procs = num_cpus::get() as isize;
-                let (tx, rx) = mpsc::channel();
-                for tid in 0..num_procs {
-                    let input = input.clone();
-                    let tx1 = tx.clone();
-                    thread::spawn(move || {
-                        let nodata = input.configs.nodata;
-                        let columns = input.configs.columns as isize;
-                        let d_x = [ 1, 1, 1, 0, -1, -1, -1, 0 ];
-                        let d_y = [ -1, 0, 1, 1, 1, 0, -1, -1 ];
-                        let mut n: [f64; 8] = [0.0; 8];
-                        let mut z: f64;
-                        let (mut fx, mut fy): (f64, f64);
-                        for row in (0..rows).filter(|r| r % num_procs == tid) {
-                            let mut data = vec![nodata; columns as usize];
-                            for col in 0..columns {
-                                z = input[(row, col)];
-                                if z != nodata {
-                                    for c in 0..8 {
-                                        n[c] = input[(row + d_y[c], col + d_x[c])];
-                                        if n[c] != nodata {
-                                            n[c] = n[c] * z_factor;
-                                        } else {
-                                            n[c] = z * z_factor;
+                let mut slope: Array2D<f64> = Array2D::new(rows, columns, nodata, nodata)?;
+                // the DEM is read in windows of rows, with a halo of one row on either side
+                let window_rows = input.get_window_rows();
+                let mut start_row = 0;
+                while start_row < rows {
+                    let end_row = min(start_row + window_rows, rows);
+                    let window = Arc::new(input.get_window(start_row - 1, end_row + 1));
+                    let (tx, rx) = mpsc::channel();
+                    for tid in 0..num_procs {
+                        let window = window.clone();
+                        let tx1 = tx.clone();
+                        thread::spawn(move || {
+                            let d_x = [ 1, 1, 1, 0, -1, -1, -1, 0 ];
+                            let d_y = [ -1, 0, 1, 1, 1, 0, -1, -1 ];
+                            let mut n: [f64; 8] = [0.0; 8];
+                            let mut z: f64;
+                            let (mut fx, mut fy): (f64, f64);
+                            for row in (start_row..end_row).filter(|r| r % num_procs == tid) {
+                                let mut data = vec![nodata; columns as usize];
+                                for col in 0..columns {
+                                    z = window.get_value(row, col);
+                                    if z != nodata {
+                                        for c in 0..8 {
+                                            n[c] = window.get_value(row + d_y[c], col + d_x[c]);
+                                            if n[c] != nodata {
+                                                n[c] = n[c] * z_factor;
+                                            } else {
+                                                n[c] = z * z_factor;
+                                            }
                                         }
+                                        // calculate slope
+                                        fy = (n[6] - n[4] + 2.0 * (n[7] - n[3]) + n[0] - n[2]) / eight_grid_res;
+                                        fx = (n[2] - n[4] + 2.0 * (n[1] - n[5]) + n[0] - n[6]) / eight_grid_res;
+                                        data[col as usize] = (fx * fx + fy * fy).sqrt().atan().to_degrees();
                                     }
-                                    // calculate slope
-                                    fy = (n[6] - n[4] + 2.0 * (n[7] - n[3]) + n[0] - n[2]) / eight_grid_res;
-                                    fx = (n[2] - n[4] + 2.0 * (n[1] - n[5]) + n[0] - n[6]) / eight_grid_res;
-                                    data[col as usize] = (fx * fx + fy * fy).sqrt().atan().to_degrees();
                                 }
+                                tx1.send((row, data)).unwrap();
                             }
-                            tx1.send((row, data)).unwrap();
-                        }
-                    });
-                }
+                        });
+                    }
 
-                let mut slope: Array2D<f64> = Array2D::new(rows, columns, nodata, nodata)?;
-                for row in 0..rows {
-                    let data = rx.recv().unwrap();
-                    slope.set_row_data(data.0, data.1);
+                    for row in start_row..end_row {
+                        let data = rx.recv().unwrap();
+                        slope.set_row_data(data.0, data.1);
                     
-                    if verbose {
                         progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as usize;
                         if progress != old_progress {
-                            println!("Slope analysis: {}%", progress);
+                            sink.progress("Slope analysis", progress)?;
                             old_progress = progress;
                         }
                     }
+                    start_row = end_row;
                 }
                 
                 let min = input.configs.minimum;
@@ -321,12 +294,10 @@ impl WhiteboxTool for SlopeVsElevationPlot {
                             slope_data[bin] += slope.get_value(row, col);
                         }
                     }
-                    if verbose {
-                        progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as usize;
-                        if progress != old_progress {
-                            println!("Binning the data: {}%", progress);
-                            old_progress = progress;
-                        }
+                    progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as usize;
+                    if progress != old_progress {
+                        sink.progress("Binning the data", progress)?;
+                        old_progress = progress;
                     }
                 }
 
@@ -384,54 +355,58 @@ impl WhiteboxTool for SlopeVsElevationPlot {
                 
                 // calculate slope
                 let num_procs = num_cpus::get() as isize;
-                let (tx, rx) = mpsc::channel();
-                for tid in 0..num_procs {
-                    let input = input.clone();
-                    let tx1 = tx.clone();
-                    thread::spawn(move || {
-                        let nodata = input.configs.nodata;
-                        let columns = input.configs.columns as isize;
-                        let d_x = [ 1, 1, 1, 0, -1, -1, -1, 0 ];
-                        let d_y = [ -1, 0, 1, 1, 1, 0, -1, -1 ];
-                        let mut n: [f64; 8] = [0.0; 8];
-                        let mut z: f64;
-                        let (mut fx, mut fy): (f64, f64);
-                        for row in (0..rows).filter(|r| r % num_procs == tid) {
-                            let mut data = vec![nodata; columns as usize];
-                            for col in 0..columns {
-                                z = input[(row, col)];
-                                if z != nodata {
-                                    for c in 0..8 {
-                                        n[c] = input[(row + d_y[c], col + d_x[c])];
-                                        if n[c] != nodata {
-                                            n[c] = n[c] * z_factor;
-                                        } else {
-                                            n[c] = z * z_factor;
+                let mut slope: Array2D<f64> = Array2D::new(rows, columns, nodata, nodata)?;
+                // the DEM is read in windows of rows, with a halo of one row on either side
+                let window_rows = input.get_window_rows();
+                let mut start_row = 0;
+                while start_row < rows {
+                    let end_row = min(start_row + window_rows, rows);
+                    let window = Arc::new(input.get_window(start_row - 1, end_row + 1));
+                    let (tx, rx) = mpsc::channel();
+                    for tid in 0..num_procs {
+                        let window = window.clone();
+                        let tx1 = tx.clone();
+                        thread::spawn(move || {
+                            let d_x = [ 1, 1, 1, 0, -1, -1, -1, 0 ];
+                            let d_y = [ -1, 0, 1, 1, 1, 0, -1, -1 ];
+                            let mut n: [f64; 8] = [0.0; 8];
+                            let mut z: f64;
+                            let (mut fx, mut fy): (f64, f64);
+                            for row in (start_row..end_row).filter(|r| r % num_procs == tid) {
+                                let mut data = vec![nodata; columns as usize];
+                                for col in 0..columns {
+                                    z = window.get_value(row, col);
+                                    if z != nodata {
+                                        for c in 0..8 {
+                                            n[c] = window.get_value(row + d_y[c], col + d_x[c]);
+                                            if n[c] != nodata {
+                                                n[c] = n[c] * z_factor;
+                                            } else {
+                                                n[c] = z * z_factor;
+                                            }
                                         }
+                                        // calculate slope
+                                        fy = (n[6] - n[4] + 2.0 * (n[7] - n[3]) + n[0] - n[2]) / eight_grid_res;
+                                        fx = (n[2] - n[4] + 2.0 * (n[1] - n[5]) + n[0] - n[6]) / eight_grid_res;
+                                        data[col as usize] = (fx * fx + fy * fy).sqrt().atan().to_degrees();
                                     }
-                                    // calculate slope
-                                    fy = (n[6] - n[4] + 2.0 * (n[7] - n[3]) + n[0] - n[2]) / eight_grid_res;
-                                    fx = (n[2] - n[4] + 2.0 * (n[1] - n[5]) + n[0] - n[6]) / eight_grid_res;
-                                    data[col as usize] = (fx * fx + fy * fy).sqrt().atan().to_degrees();
                                 }
+                                tx1.send((row, data)).unwrap();
                             }
-                            tx1.send((row, data)).unwrap();
-                        }
-                    });
-                }
+                        });
+                    }
 
-                let mut slope: Array2D<f64> = Array2D::new(rows, columns, nodata, nodata)?;
-                for row in 0..rows {
-                    let data = rx.recv().unwrap();
-                    slope.set_row_data(data.0, data.1);
+                    for row in start_row..end_row {
+                        let data = rx.recv().unwrap();
+                        slope.set_row_data(data.0, data.1);
                     
-                    if verbose {
                         progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as usize;
                         if progress != old_progress {
-                            println!("Slope analysis: {}%", progress);
+                            sink.progress("Slope analysis", progress)?;
                             old_progress = progress;
                         }
                     }
+                    start_row = end_row;
                 }
                 
                 let mut watershed_file = watershed_files[i].to_string();
@@ -448,7 +423,7 @@ impl WhiteboxTool for SlopeVsElevationPlot {
                 let watershed_min = watershed.configs.minimum;
                 let watershed_max = watershed.configs.maximum;
                 let num_watersheds = (watershed_max - watershed_min) as usize + 1;
-                println!("Num. Watersheds {}", num_watersheds);
+                sink.message(&format!("Num. Watersheds {}", num_watersheds));
 
                 // get the number of watersheds, and the min and max elev for each watershed
                 let mut min_elevs = vec![f64::INFINITY; num_watersheds];
@@ -468,12 +443,10 @@ impl WhiteboxTool for SlopeVsElevationPlot {
                             }
                         }
                     }
-                    if verbose {
-                        progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as usize;
-                        if progress != old_progress {
-                            println!("Processing watershed data: {}%", progress);
-                            old_progress = progress;
-                        }
+                    progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as usize;
+                    if progress != old_progress {
+                        sink.progress("Processing watershed data", progress)?;
+                        old_progress = progress;
                     }
                 }
 
@@ -520,12 +493,10 @@ impl WhiteboxTool for SlopeVsElevationPlot {
                             }
                         }
                     }
-                    if verbose {
-                        progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as usize;
-                        if progress != old_progress {
-                            println!("Binning the data: {}%", progress);
-                            old_progress = progress;
-                        }
+                    progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as usize;
+                    if progress != old_progress {
+                        sink.progress("Binning the data", progress)?;
+                        old_progress = progress;
                     }
                 }
 
@@ -577,8 +548,7 @@ impl WhiteboxTool for SlopeVsElevationPlot {
 
         let _ = writer.flush();
 
-        if verbose { println!("\n{}",
-                 &format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", "")); }
+        if verbose { sink.message(&format!("\n{}", &format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""))); }
 
         if verbose {
             if cfg!(target_os = "macos") || cfg!(target_os = "ios") {
@@ -605,7 +575,7 @@ impl WhiteboxTool for SlopeVsElevationPlot {
                 let _ = output.stdout;
             }
 
-            println!("Complete! Please see {} for output.", output_file);
+            sink.message(&format!("Complete! Please see {} for output.", output_file));
         }
 
         Ok(())
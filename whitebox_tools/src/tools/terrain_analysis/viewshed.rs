@@ -2,7 +2,7 @@
 This tool is part of the WhiteboxTools geospatial analysis library.
 Authors: Dr. John Lindsay
 Created: January 10, 2018
-Last Modified: January 10, 2018
+Last Modified: March 18, 2018
 License: MIT
 
 Help: This tool can be used to calculate the viewshed (i.e. the visible area) from a 
@@ -13,7 +13,10 @@ Viewing station locations will be inferred from all non-zero, non-NoData grid ce
 the viewing station raster. The output image will be a Boolean raster, containing 1's 
 and 0's, where 1's imply that the grid cell is visible from at least one viewing station. 
 The viewing height is in the same units as the elevations of the DEM and represent a 
-height above the ground elevation from which the viewshed is calculated. Viewshed 
+height above the ground elevation from which the viewshed is calculated. Alternatively, 
+the name of a numeric field in the viewing station file's attribute table can be specified 
+(--height_field), in which case each station's height is read from that field; stations 
+with a null value in the field use the --height value. Viewshed 
 analysis is a very computationally intensive task. Depending on the size of the input 
 DEM grid and the number of viewing stations, this operation may take considerable time 
 to complete.
@@ -32,6 +35,7 @@ use vector::*;
 use structures::Array2D;
 use std::io::{Error, ErrorKind};
 use tools::*;
+use tools::arg_parser::ArgValues;
 
 pub struct Viewshed {
     name: String,
@@ -51,7 +55,7 @@ impl Viewshed {
         let mut parameters = vec![];
         parameters.push(ToolParameter{
             name: "Input DEM File".to_owned(), 
-            flags: vec!["--dem".to_owned()], 
+            flags: vec!["-i".to_owned(), "--input".to_owned(), "--dem".to_owned()], 
             description: "Input raster DEM file.".to_owned(),
             parameter_type: ParameterType::ExistingFile(ParameterFileType::Raster),
             default_value: None,
@@ -60,7 +64,7 @@ impl Viewshed {
 
         parameters.push(ToolParameter{
             name: "Viewing Station Vector File".to_owned(), 
-            flags: vec!["--stations".to_owned()], 
+            flags: vec!["--station".to_owned(), "--stations".to_owned()], 
             description: "Input viewing station vector file.".to_owned(),
             parameter_type: ParameterType::ExistingFile(ParameterFileType::Vector(VectorGeometryType::Point)), //ExistingFile(ParameterFileType::Raster),
             default_value: None,
@@ -85,6 +89,15 @@ impl Viewshed {
             optional: false
         });
 
+        parameters.push(ToolParameter{
+            name: "Station Height Field".to_owned(), 
+            flags: vec!["--height_field".to_owned()], 
+            description: "Optional attribute field containing the height of each viewing station, in z units.".to_owned(),
+            parameter_type: ParameterType::VectorAttributeField(AttributeType::Number, "--stations".to_string()),
+            default_value: None,
+            optional: true
+        });
+
         let sep: String = path::MAIN_SEPARATOR.to_string();
         let p = format!("{}", env::current_dir().unwrap().display());
         let e = format!("{}", env::current_exe().unwrap().display());
@@ -92,7 +105,8 @@ impl Viewshed {
         if e.contains(".exe") {
             short_exe += ".exe";
         }
-        let usage = format!(">>.*{0} -r={1} -v --wd=\"*path*to*data*\" --dem='dem.dep' --stations='stations.shp' -o=output.dep --height=10.0", short_exe, name).replace("*", &sep);
+        let usage = format!(">>.*{0} -r={1} -v --wd=\"*path*to*data*\" --dem='dem.dep' --stations='stations.shp' -o=output.dep --height=10.0
+>>.*{0} -r={1} -v --wd=\"*path*to*data*\" --dem='dem.dep' --stations='stations.shp' -o=output.dep --height_field=HEIGHT", short_exe, name).replace("*", &sep);
     
         Viewshed { 
             name: name, 
@@ -139,82 +153,35 @@ impl WhiteboxTool for Viewshed {
         self.toolbox.clone()
     }
 
-    fn run<'a>(&self, args: Vec<String>, working_directory: &'a str, verbose: bool) -> Result<(), Error> {
-        let mut input_file = String::new();
-        let mut stations_file = String::new();
-        let mut output_file = String::new();
-        let mut height = 2.0;
-         
+    fn run<'a>(&self, args: Vec<String>, working_directory: &'a str, verbose: bool, sink: &Arc<ProgressSink>) -> Result<(), Error> {
         if args.len() == 0 {
             return Err(Error::new(ErrorKind::InvalidInput, "Tool run with no paramters."));
         }
-        for i in 0..args.len() {
-            let mut arg = args[i].replace("\"", "");
-            arg = arg.replace("\'", "");
-            let cmd = arg.split("="); // in case an equals sign was used
-            let vec = cmd.collect::<Vec<&str>>();
-            let mut keyval = false;
-            if vec.len() > 1 {
-                keyval = true;
-            }
-            let flag_val = vec[0].to_lowercase().replace("--", "-");
-            if flag_val == "-i" || flag_val == "-input" || flag_val == "-dem" {
-                input_file = if keyval {
-                    vec[1].to_string()
-                } else {
-                    args[i+1].to_string()
-                };
-            } else if flag_val == "-stations" || flag_val == "-station" {
-                stations_file = if keyval {
-                    vec[1].to_string()
-                } else {
-                    args[i+1].to_string()
-                };
-            } else if flag_val == "-o" || flag_val == "-output" {
-                if keyval {
-                    output_file = vec[1].to_string();
-                } else {
-                    output_file = args[i+1].to_string();
-                }
-            } else if flag_val == "-height" {
-                height = if keyval {
-                    vec[1].to_string().parse::<f64>().unwrap()
-                } else {
-                    args[i+1].to_string().parse::<f64>().unwrap()
-                };
-            }
-        }
+        let args = ArgValues::parse(&self.parameters, &args)?;
+        let input_file = args.required_file("--dem", working_directory)?;
+        let stations_file = args.required_file("--stations", working_directory)?;
+        let output_file = args.required_file("--output", working_directory)?;
+        let height_field = args.get_string("--height_field")?.unwrap_or(String::new());
+        let mut height = args.get_f64("--height")?.unwrap_or(2.0);
 
         if verbose {
-            println!("***************{}", "*".repeat(self.get_tool_name().len()));
-            println!("* Welcome to {} *", self.get_tool_name());
-            println!("***************{}", "*".repeat(self.get_tool_name().len()));
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
+            sink.message(&format!("* Welcome to {} *", self.get_tool_name()));
+            sink.message(&format!("***************{}", "*".repeat(self.get_tool_name().len())));
         }
 
         let num_procs = num_cpus::get() as isize;
 
-        let sep: String = path::MAIN_SEPARATOR.to_string();
-
         let mut progress: usize;
         let mut old_progress: usize = 1;
 
-        if !input_file.contains(&sep) && !input_file.contains("/") {
-            input_file = format!("{}{}", working_directory, input_file);
-        }
-        if !stations_file.contains(&sep) && !stations_file.contains("/") {
-            stations_file = format!("{}{}", working_directory, stations_file);
-        }
-        if !output_file.contains(&sep) && !output_file.contains("/") {
-            output_file = format!("{}{}", working_directory, output_file);
-        }
-
-        if verbose { println!("Reading data...") };
+        if verbose { sink.message("Reading data...") };
         let dem = Arc::new(Raster::new(&input_file, "r")?);
         
         let start = time::now();
 
         if height < 0f64 {
-            println!("Warning: Input station height cannot be less than zero.");
+            sink.warning("Warning: Input station height cannot be less than zero.");
             height = 0f64;
         }
 
@@ -238,6 +205,7 @@ impl WhiteboxTool for Viewshed {
         // let mut z: f64;
         let mut station_x = vec![];
         let mut station_y = vec![];
+        let mut station_height = vec![];
         // for row in 0..rows {
         //     for col in 0..columns {
         //         z = stations.get_value(row, col);
@@ -256,18 +224,39 @@ impl WhiteboxTool for Viewshed {
         //     }
         // }
 
+        if !height_field.is_empty() && stations.attributes.get_field_num(&height_field).is_none() {
+            return Err(Error::new(ErrorKind::InvalidInput,
+                format!("The station height field ({}) does not exist in the viewing station file's attribute table.", height_field)));
+        }
+
         for record_num in 0..stations.num_records {
             let record = stations.get_record(record_num);
             station_y.push(record.points[0].y);
             station_x.push(record.points[0].x);
-
-            if verbose {
-                progress = (100.0_f64 * record_num as f64 / (stations.num_records - 1) as f64) as usize;
-                if progress != old_progress {
-                    println!("Locating view stations: {}%", progress);
-                    old_progress = progress;
+            let mut stn_height = height;
+            if !height_field.is_empty() {
+                match stations.attributes.get_value(record_num, &height_field) {
+                    FieldData::Null => {},
+                    v => {
+                        stn_height = match v.as_f64() {
+                            Some(h) => h,
+                            None => return Err(Error::new(ErrorKind::InvalidInput,
+                                format!("The station height field ({}) must be numeric.", height_field))),
+                        };
+                        if stn_height < 0f64 {
+                            sink.warning("Warning: Station height cannot be less than zero.");
+                            stn_height = 0f64;
+                        }
+                    },
                 }
             }
+            station_height.push(stn_height);
+
+            progress = (100.0_f64 * record_num as f64 / (stations.num_records - 1) as f64) as usize;
+            if progress != old_progress {
+                sink.progress("Locating view stations", progress)?;
+                old_progress = progress;
+            }
         }
 
         let (mut stn_x, mut stn_y): (f64, f64);
@@ -278,13 +267,13 @@ impl WhiteboxTool for Viewshed {
         let num_stn = station_x.len();
         while !station_x.is_empty() {
             stn_num += 1;
-            println!("Station {} of {}", stn_num, num_stn);
+            sink.message(&format!("Station {} of {}", stn_num, num_stn));
 
             stn_x = station_x.pop().unwrap();
             stn_col = dem.get_column_from_x(stn_x);
             stn_y = station_y.pop().unwrap();
             stn_row = dem.get_row_from_y(stn_y);
-            stn_z = dem.get_value(stn_row, stn_col) + height;
+            stn_z = dem.get_value(stn_row, stn_col) + station_height.pop().unwrap();
 
             // now calculate the view angle
             let (tx, rx) = mpsc::channel();
@@ -321,12 +310,10 @@ impl WhiteboxTool for Viewshed {
                 let (row, data) = rx.recv().unwrap();
                 view_angle.set_row_data(row, data);
                 
-                if verbose {
-                    progress = (100.0_f64 * r as f64 / (rows - 1) as f64) as usize;
-                    if progress != old_progress {
-                        println!("Calculating view angle (Station {} of {}): {}%", stn_num, num_stn, progress);
-                        old_progress = progress;
-                    }
+                progress = (100.0_f64 * r as f64 / (rows - 1) as f64) as usize;
+                if progress != old_progress {
+                    sink.progress(&format!("Calculating view angle (Station {} of {})", stn_num, num_stn), progress)?;
+                    old_progress = progress;
                 }
             }
 
@@ -617,12 +604,10 @@ impl WhiteboxTool for Viewshed {
                     }
                 }
 
-                if verbose {
-                    progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as usize;
-                    if progress != old_progress {
-                        println!("Creating output: (Station {} of {}): {}%", stn_num, num_stn, progress);
-                        old_progress = progress;
-                    }
+                progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as usize;
+                if progress != old_progress {
+                    sink.progress(&format!("Creating output: (Station {} of {})", stn_num, num_stn), progress)?;
+                    old_progress = progress;
                 }
             }
 
@@ -637,13 +622,13 @@ impl WhiteboxTool for Viewshed {
         output.add_metadata_entry(format!("DEM file: {}", input_file));
         output.add_metadata_entry(format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
 
-        if verbose { println!("Saving data...") };
+        if verbose { sink.message("Saving data...") };
         let _ = match output.write() {
-            Ok(_) => if verbose { println!("Output file written") },
+            Ok(_) => if verbose { sink.message("Output file written") },
             Err(e) => return Err(e),
         };
         if verbose {
-            println!("{}", &format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
+            sink.message(&format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
         }
         
         Ok(())
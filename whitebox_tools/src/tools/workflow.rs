@@ -0,0 +1,670 @@
+/*
+This code is part of the WhiteboxTools geospatial analysis library.
+Authors: Dr. John Lindsay
+Created: March 23, 2018
+Last Modified: April 6, 2018
+License: MIT
+*/
+
+extern crate num_cpus;
+extern crate time;
+
+use std::collections::{BTreeMap, HashMap};
+use std::fs::File;
+use std::io::prelude::*;
+use std::io::{Error, ErrorKind};
+use std::sync::{mpsc, Arc};
+use std::thread;
+use serde_json;
+use serde_json::Value;
+use raster::Raster;
+use tools::arg_parser;
+use tools::arg_parser::{full_path, long_flag, normalize_flag};
+use tools::{ParameterFileType, ParameterType, ParameterValue, ProgressSink, ToolArgs, ToolError, ToolManager, ToolOutput};
+
+// The workflow file, e.g.
+//
+// {
+//   "steps": [
+//     { "id": "breach", "tool": "BreachDepressions", "args": { "dem": "DEM.tif", "output": "@dem" } },
+//     { "id": "pointer", "tool": "D8Pointer", "args": { "dem": "@dem", "output": "@pntr" } },
+//     ...
+//   ],
+//   "persist": { "pntr": "pointer.tif" }
+// }
+#[derive(Deserialize)]
+struct WorkflowFile {
+    steps: Vec<StepDefinition>,
+    #[serde(default)]
+    persist: BTreeMap<String, String>,
+}
+
+#[derive(Deserialize)]
+struct StepDefinition {
+    #[serde(default)]
+    id: Option<String>,
+    tool: String,
+    #[serde(default)]
+    args: serde_json::Map<String, Value>,
+}
+
+struct Step {
+    id: String,
+    tool_name: String,
+    args: Vec<(String, Value)>,
+    // the named in-memory rasters read by the step, keyed by argument flag
+    inputs: Vec<(String, String)>,
+    // the named in-memory rasters produced by the step, keyed by the ToolOutput key
+    outputs: Vec<(String, String)>,
+    dependencies: Vec<usize>,
+}
+
+enum StepState {
+    Waiting,
+    Running,
+    Completed,
+    Failed,
+    /// Stopped by, or not started because of, the cancellation of the run.
+    Cancelled,
+}
+
+/// Reads a workflow file and runs its steps. Each step is a tool call; an argument value
+/// of the form `@name` refers to an in-memory raster, which is produced by the step that uses
+/// the name for one of its output rasters and can be read by any later step. Steps run as
+/// soon as the steps that produce their inputs (named rasters or files) have completed, so
+/// independent steps run in parallel. Named rasters listed in the `persist` map are also
+/// written to disk. The run stops at the first failed step. When the run is cancelled, no
+/// further steps are started, and the running steps stop when they next report progress.
+pub fn run_workflow(tm: &ToolManager, file_name: &str) -> Result<(), Error> {
+    let file_name = full_path(file_name, &tm.working_dir);
+    let mut f = File::open(&file_name)?;
+    let mut contents = String::new();
+    f.read_to_string(&mut contents)?;
+    let workflow: WorkflowFile = match serde_json::from_str(&contents) {
+        Ok(w) => w,
+        Err(e) => return Err(Error::new(ErrorKind::InvalidData,
+                                        format!("Error reading the workflow file {}: {}", file_name, e))),
+    };
+    let steps = read_steps(tm, workflow.steps)?;
+
+    // check the named rasters
+    let mut producers: HashMap<String, usize> = HashMap::new();
+    for (i, step) in steps.iter().enumerate() {
+        for &(_, ref name) in &step.outputs {
+            if let Some(j) = producers.insert(name.clone(), i) {
+                return Err(invalid_workflow(&format!("The raster @{} is produced by both step '{}' and step '{}'.",
+                                                     name, steps[j].id, step.id)));
+            }
+        }
+    }
+    let mut num_consumers: HashMap<String, usize> = HashMap::new();
+    for step in &steps {
+        for &(_, ref name) in &step.inputs {
+            if !producers.contains_key(name) {
+                return Err(invalid_workflow(&format!("The raster @{} used by step '{}' is not produced by any step.",
+                                                     name, step.id)));
+            }
+            *num_consumers.entry(name.clone()).or_insert(0) += 1;
+        }
+    }
+    for name in workflow.persist.keys() {
+        if !producers.contains_key(name.trim_matches('@')) {
+            return Err(invalid_workflow(&format!("The persisted raster @{} is not produced by any step.", name)));
+        }
+    }
+    let persist: HashMap<String, String> = workflow.persist.iter()
+        .map(|(k, v)| (k.trim_matches('@').to_string(), full_path(v, &tm.working_dir)))
+        .collect();
+    let steps = add_dependencies(tm, steps, &producers)?;
+    check_for_cycles(&steps)?;
+
+    // run the steps
+    let start = time::now();
+    let num_procs = num_cpus::get();
+    let mut states: Vec<StepState> = steps.iter().map(|_| StepState::Waiting).collect();
+    // each named raster is shared by the steps that read it, each of which reads its own copy
+    let mut rasters: HashMap<String, Arc<Raster>> = HashMap::new();
+    let mut num_running = 0;
+    let mut failure: Option<(usize, ToolError)> = None;
+    let sink = tm.get_progress_sink();
+    let (tx, rx) = mpsc::channel();
+    loop {
+        if failure.is_none() && !sink.is_cancelled() {
+            for i in 0..steps.len() {
+                if num_running >= num_procs {
+                    break;
+                }
+                let ready = match states[i] {
+                    StepState::Waiting => steps[i].dependencies.iter().all(|&j| match states[j] {
+                        StepState::Completed => true,
+                        _ => false,
+                    }),
+                    _ => false,
+                };
+                if !ready {
+                    continue;
+                }
+                let step = &steps[i];
+                let mut inputs = vec![];
+                for &(ref flag, ref name) in &step.inputs {
+                    inputs.push((flag.clone(), rasters[name].clone()));
+                    let n = num_consumers.get_mut(name).unwrap();
+                    *n -= 1;
+                    if *n == 0 {
+                        rasters.remove(name);
+                    }
+                }
+                let persisted: Vec<(String, String)> = step.outputs.iter()
+                    .filter(|&&(_, ref name)| persist.contains_key(name))
+                    .map(|&(ref key, ref name)| (key.clone(), persist[name].clone()))
+                    .collect();
+                if tm.verbose {
+                    sink.message(&format!("Running step '{}' ({})...", step.id, step.tool_name));
+                }
+                let outputs: Vec<String> = step.outputs.iter().map(|&(ref key, _)| key.clone()).collect();
+                let tool_name = step.tool_name.clone();
+                let args = step.args.clone();
+                let working_dir = tm.working_dir.clone();
+                let verbose = tm.verbose;
+                let step_sink = sink.clone();
+                let tx = tx.clone();
+                thread::spawn(move || {
+                    let result = run_step(&tool_name, args, inputs, outputs, persisted, &working_dir, verbose, step_sink);
+                    tx.send((i, result)).unwrap();
+                });
+                states[i] = StepState::Running;
+                num_running += 1;
+            }
+        }
+        if num_running == 0 {
+            break;
+        }
+        let (i, result) = rx.recv().unwrap();
+        num_running -= 1;
+        match result {
+            Ok(mut output) => {
+                let mut missing = None;
+                for &(ref key, ref name) in &steps[i].outputs {
+                    match output.rasters.remove(key) {
+                        Some(r) => {
+                            if num_consumers.get(name).map_or(false, |&n| n > 0) {
+                                rasters.insert(name.clone(), Arc::new(r));
+                            }
+                        },
+                        None => missing = Some(name.clone()),
+                    }
+                }
+                if let Some(name) = missing {
+                    states[i] = StepState::Failed;
+                    if failure.is_none() {
+                        let e = Error::new(ErrorKind::Other, format!("the tool did not produce the raster @{}", name));
+                        failure = Some((i, ToolError::Failed(e)));
+                    }
+                    continue;
+                }
+                states[i] = StepState::Completed;
+                if tm.verbose {
+                    sink.message(&format!("Step '{}' ({}) completed.", steps[i].id, steps[i].tool_name));
+                }
+            },
+            Err(ToolError::Failed(ref e)) if e.kind() == ErrorKind::Interrupted => {
+                states[i] = StepState::Cancelled;
+            },
+            Err(e) => {
+                states[i] = StepState::Failed;
+                if failure.is_none() {
+                    failure = Some((i, e));
+                }
+            },
+        }
+    }
+    if sink.is_cancelled() {
+        for state in states.iter_mut() {
+            if let StepState::Waiting = *state {
+                *state = StepState::Cancelled;
+            }
+        }
+    }
+    let list = |wanted: fn(&StepState) -> bool| -> String {
+        let ids: Vec<&str> = steps.iter().zip(states.iter())
+            .filter(|&(_, s)| wanted(s))
+            .map(|(step, _)| step.id.as_ref())
+            .collect();
+        if ids.is_empty() { "none".to_string() } else { ids.join(", ") }
+    };
+    let is_completed = |s: &StepState| match *s { StepState::Completed => true, _ => false };
+    let is_waiting = |s: &StepState| match *s { StepState::Waiting => true, _ => false };
+    let is_cancelled = |s: &StepState| match *s { StepState::Cancelled => true, _ => false };
+
+    if let Some((i, e)) = failure {
+        let mut message = format!("Workflow step '{}' ({}) failed: {}", steps[i].id, steps[i].tool_name, e);
+        message.push_str(&format!("\nCompleted steps: {}", list(is_completed)));
+        if states.iter().any(is_cancelled) {
+            message.push_str(&format!("\nCancelled steps: {}", list(is_cancelled)));
+        }
+        message.push_str(&format!("\nSteps not run: {}", list(is_waiting)));
+        return Err(Error::new(ErrorKind::Other, message));
+    }
+    if states.iter().any(is_cancelled) {
+        return Err(Error::new(ErrorKind::Interrupted, format!("The workflow was cancelled.\nCompleted steps: {}\nCancelled steps: {}",
+                                                              list(is_completed), list(is_cancelled))));
+    }
+
+    if tm.verbose {
+        let end = time::now();
+        let elapsed_time = end - start;
+        sink.message(&format!("Workflow completed ({} steps). Elapsed Time: {}", steps.len(), elapsed_time).replace("PT", ""));
+    }
+    Ok(())
+}
+
+fn invalid_workflow(message: &str) -> Error {
+    Error::new(ErrorKind::InvalidInput, message)
+}
+
+// Checks the tool and arguments of each step and finds the named rasters that it reads
+// and produces.
+fn read_steps(tm: &ToolManager, definitions: Vec<StepDefinition>) -> Result<Vec<Step>, Error> {
+    if definitions.is_empty() {
+        return Err(invalid_workflow("The workflow does not contain any steps."));
+    }
+    let mut steps: Vec<Step> = vec![];
+    for (i, def) in definitions.into_iter().enumerate() {
+        let id = def.id.unwrap_or(format!("{}", i + 1));
+        if steps.iter().any(|s| s.id == id) {
+            return Err(invalid_workflow(&format!("There is more than one step with the id '{}'.", id)));
+        }
+        let tool = match tm.get_tool(&def.tool) {
+            Some(t) => t,
+            None => return Err(invalid_workflow(&format!("Step '{}': unrecognized tool name {}.", id, def.tool))),
+        };
+        let parameters = arg_parser::get_parameters(&tool)?;
+        let mut inputs = vec![];
+        let mut outputs = vec![];
+        let mut args = vec![];
+        for (flag, value) in def.args {
+            let indices = match arg_parser::find_parameters(&parameters, &flag) {
+                Ok(v) => v,
+                Err(e) => return Err(invalid_workflow(&format!("Step '{}': {}", id, e))),
+            };
+            if let Value::String(ref s) = value {
+                if s.starts_with("@") {
+                    let name = s[1..].trim().to_string();
+                    let p = &parameters[indices[0]];
+                    match p.parameter_type {
+                        ParameterType::NewFile(ParameterFileType::Raster) => {
+                            outputs.push((normalize_flag(&long_flag(p)), name));
+                        },
+                        ParameterType::ExistingFile(ParameterFileType::Raster) |
+                        ParameterType::ExistingFileOrFloat(ParameterFileType::Raster) => {
+                            inputs.push((flag.clone(), name));
+                        },
+                        _ => return Err(invalid_workflow(&format!("Step '{}': the parameter '{}' is not a raster file, so it can not be the in-memory raster @{}.",
+                                                                  id, p.name, name))),
+                    }
+                    continue;
+                }
+            }
+            args.push((flag, value));
+        }
+        steps.push(Step {
+            id: id,
+            tool_name: tool.get_tool_name(),
+            args: args,
+            inputs: inputs,
+            outputs: outputs,
+            dependencies: vec![],
+        });
+    }
+    Ok(steps)
+}
+
+// A step depends on the steps producing the named rasters that it reads, and on any step
+// that writes a file that it reads.
+fn add_dependencies(tm: &ToolManager, mut steps: Vec<Step>, producers: &HashMap<String, usize>) -> Result<Vec<Step>, Error> {
+    let mut output_files: HashMap<String, usize> = HashMap::new();
+    let mut input_files: Vec<Vec<String>> = vec![];
+    for (i, step) in steps.iter().enumerate() {
+        let tool = tm.get_tool(&step.tool_name).unwrap();
+        let parameters = arg_parser::get_parameters(&tool)?;
+        let mut files = vec![];
+        for &(ref flag, ref value) in &step.args {
+            let p = &parameters[arg_parser::find_parameters(&parameters, flag).unwrap()[0]];
+            let values: Vec<String> = match *value {
+                Value::String(ref s) => {
+                    match p.parameter_type {
+                        ParameterType::FileList(_) => s.split(if s.contains(";") { ";" } else { "," }).map(|v| v.trim().to_string()).collect(),
+                        _ => vec![s.clone()],
+                    }
+                },
+                Value::Array(ref a) => a.iter().filter_map(|v| v.as_str().map(|v| v.to_string())).collect(),
+                _ => vec![],
+            };
+            for v in values {
+                let v = full_path(&v, &tm.working_dir);
+                match p.parameter_type {
+                    ParameterType::NewFile(_) => {
+                        if let Some(j) = output_files.insert(v.clone(), i) {
+                            return Err(invalid_workflow(&format!("The file {} is written by both step '{}' and step '{}'.",
+                                                                 v, steps[j].id, step.id)));
+                        }
+                    },
+                    ParameterType::ExistingFile(_) |
+                    ParameterType::ExistingFileOrFloat(_) |
+                    ParameterType::FileList(_) => files.push(v),
+                    _ => {},
+                }
+            }
+        }
+        input_files.push(files);
+    }
+    for i in 0..steps.len() {
+        let mut dependencies: Vec<usize> = steps[i].inputs.iter().map(|&(_, ref name)| producers[name]).collect();
+        for f in &input_files[i] {
+            if let Some(&j) = output_files.get(f) {
+                dependencies.push(j);
+            }
+        }
+        dependencies.sort();
+        dependencies.dedup();
+        steps[i].dependencies = dependencies;
+    }
+    Ok(steps)
+}
+
+fn check_for_cycles(steps: &[Step]) -> Result<(), Error> {
+    let mut done = vec![false; steps.len()];
+    let mut num_done = 0;
+    loop {
+        let mut progress = false;
+        for i in 0..steps.len() {
+            if !done[i] && steps[i].dependencies.iter().all(|&j| done[j]) {
+                done[i] = true;
+                num_done += 1;
+                progress = true;
+            }
+        }
+        if num_done == steps.len() {
+            return Ok(());
+        }
+        if !progress {
+            let ids: Vec<&str> = steps.iter().zip(done.iter())
+                .filter(|&(_, d)| !*d)
+                .map(|(s, _)| s.id.as_ref())
+                .collect();
+            return Err(invalid_workflow(&format!("The steps {} depend on one another in a cycle.", ids.join(", "))));
+        }
+    }
+}
+
+fn run_step(tool_name: &str,
+            args: Vec<(String, Value)>,
+            inputs: Vec<(String, Arc<Raster>)>,
+            outputs: Vec<String>,
+            persisted: Vec<(String, String)>,
+            working_dir: &str,
+            verbose: bool,
+            sink: Arc<ProgressSink>)
+            -> Result<ToolOutput, ToolError> {
+    let mut tool_args = ToolArgs::new();
+    for (flag, value) in args {
+        let value = match value {
+            Value::Null => continue,
+            Value::Bool(b) => ParameterValue::Boolean(b),
+            Value::Number(ref n) if n.is_i64() => ParameterValue::Integer(n.as_i64().unwrap()),
+            Value::Number(ref n) => ParameterValue::Float(n.as_f64().unwrap_or(0f64)),
+            Value::String(s) => ParameterValue::String(s),
+            Value::Array(a) => {
+                ParameterValue::StringList(a.iter().map(|v| match *v {
+                    Value::String(ref s) => s.clone(),
+                    ref v => v.to_string(),
+                }).collect())
+            },
+            Value::Object(_) => {
+                return Err(ToolError::InvalidParameter { parameter: flag, message: "the value can not be an object".to_string() });
+            },
+        };
+        tool_args.set(&flag, value);
+    }
+    for (flag, r) in inputs {
+        tool_args.set(&flag, r);
+    }
+    for key in outputs {
+        tool_args.set(&key, ParameterValue::InMemoryRaster);
+    }
+    let mut tm = ToolManager::new(working_dir, &verbose).map_err(ToolError::Failed)?;
+    tm.set_progress_sink(sink);
+    let mut output = tm.run_tool_with_args(tool_name, tool_args)?;
+    for (key, file_name) in persisted {
+        if let Some(r) = output.rasters.get_mut(&key) {
+            // Rather than copying the raster, it is written under the persisted file name and its
+            // name and configurations, which some formats adjust when writing, are then restored.
+            let (name, configs) = (r.file_name.clone(), r.configs.clone());
+            r.set_file_name(&file_name);
+            let result = r.write();
+            r.set_file_name(&name);
+            r.configs = configs;
+            result.map_err(ToolError::Failed)?;
+            output.files.insert(key, file_name);
+        }
+    }
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use std::fs;
+    use std::path::{Path, PathBuf, MAIN_SEPARATOR};
+    use raster::{DataType, RasterConfigs};
+    use tools::{CancellationToken, StdoutProgress};
+
+    // A quiet sink that cancels the run when the nth tool starts or finishes.
+    struct CancellingSink {
+        token: CancellationToken,
+        on_start: bool,
+        after: usize,
+        count: ::std::sync::Mutex<usize>,
+    }
+
+    impl ProgressSink for CancellingSink {
+        fn report_progress(&self, _stage: &str, _percent: usize) {}
+        fn message(&self, _text: &str) {}
+        fn warning(&self, _text: &str) {}
+        fn is_cancelled(&self) -> bool {
+            self.token.is_cancelled()
+        }
+        fn started(&self, _tool_name: &str) {
+            if self.on_start {
+                self.count_call();
+            }
+        }
+        fn finished(&self, _tool_name: &str, _result: &Result<(), Error>) {
+            if !self.on_start {
+                self.count_call();
+            }
+        }
+    }
+
+    impl CancellingSink {
+        fn count_call(&self) {
+            let mut count = self.count.lock().unwrap();
+            *count += 1;
+            if *count == self.after {
+                self.token.cancel();
+            }
+        }
+    }
+
+    fn test_dir(name: &str) -> (PathBuf, String) {
+        let dir = env::temp_dir().join(name);
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let wd = format!("{}{}", dir.to_str().unwrap(), MAIN_SEPARATOR);
+        (dir, wd)
+    }
+
+    fn manager(wd: &str, sink: Arc<ProgressSink>) -> ToolManager {
+        let mut tm = ToolManager::new(wd, &false).unwrap();
+        tm.set_progress_sink(sink);
+        tm
+    }
+
+    fn quiet_manager(wd: &str) -> ToolManager {
+        let mut sink = StdoutProgress::new();
+        sink.set_quiet(true);
+        manager(wd, Arc::new(sink))
+    }
+
+    // writes a 3 x 4 raster in which each cell's value is its index
+    fn write_input(dir: &Path, name: &str) {
+        let mut configs = RasterConfigs { ..Default::default() };
+        configs.rows = 3;
+        configs.columns = 4;
+        configs.nodata = -9999f64;
+        configs.north = 30f64;
+        configs.south = 0f64;
+        configs.west = 0f64;
+        configs.east = 40f64;
+        configs.resolution_x = 10f64;
+        configs.resolution_y = 10f64;
+        configs.data_type = DataType::F32;
+        let mut r = Raster::initialize_using_config(dir.join(name).to_str().unwrap(), &configs);
+        for row in 0..3 {
+            for col in 0..4 {
+                r.set_value(row, col, (row * 4 + col) as f64);
+            }
+        }
+        r.write().unwrap();
+    }
+
+    fn write_workflow(dir: &Path, contents: &str) -> String {
+        let file_name = dir.join("workflow.json").to_str().unwrap().to_string();
+        let mut f = File::create(&file_name).unwrap();
+        f.write_all(contents.as_bytes()).unwrap();
+        file_name
+    }
+
+    #[test]
+    fn named_rasters_are_passed_between_steps_and_persisted() {
+        let (dir, wd) = test_dir("wbt_workflow_named");
+        write_input(&dir, "dem.tif");
+        let workflow = write_workflow(&dir, r#"{
+            "steps": [
+                { "id": "offset", "tool": "Add", "args": { "input1": "@double", "input2": 1, "output": "@result" } },
+                { "id": "double", "tool": "Add", "args": { "input1": "dem.tif", "input2": "dem.tif", "output": "@double" } },
+                { "id": "copy", "tool": "Add", "args": { "input1": "@result", "input2": 0.0, "output": "copy.tif" } }
+            ],
+            "persist": { "@result": "result.tif" }
+        }"#);
+        run_workflow(&quiet_manager(&wd), &workflow).unwrap();
+
+        for name in ["result.tif", "copy.tif"].iter() {
+            let r = Raster::new(dir.join(name).to_str().unwrap(), "r").unwrap();
+            assert_eq!((r.configs.rows, r.configs.columns), (3, 4));
+            for row in 0..3 {
+                for col in 0..4 {
+                    assert_eq!(r.get_value(row, col), 2.0 * (row * 4 + col) as f64 + 1.0);
+                }
+            }
+        }
+        // only the persisted named raster is written
+        assert!(!dir.join("double.tif").exists());
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn steps_that_depend_on_one_another_are_rejected() {
+        let (dir, wd) = test_dir("wbt_workflow_cycle");
+        let tm = quiet_manager(&wd);
+        let workflow = write_workflow(&dir, r#"{ "steps": [
+            { "id": "first", "tool": "Add", "args": { "input1": "@b", "input2": 1, "output": "@a" } },
+            { "id": "second", "tool": "Add", "args": { "input1": "@a", "input2": 1, "output": "@b" } }
+        ] }"#);
+        let e = run_workflow(&tm, &workflow).err().unwrap();
+        assert_eq!(e.kind(), ErrorKind::InvalidInput);
+        assert!(e.to_string().contains("first, second depend on one another in a cycle"), "{}", e);
+
+        // a cycle through files, with a step that is not part of it
+        let workflow = write_workflow(&dir, r#"{ "steps": [
+            { "id": "free", "tool": "Add", "args": { "input1": 1, "input2": 1, "output": "free.tif" } },
+            { "id": "first", "tool": "Add", "args": { "input1": "b.tif", "input2": 1, "output": "a.tif" } },
+            { "id": "second", "tool": "Add", "args": { "input1": "a.tif", "input2": 1, "output": "b.tif" } }
+        ] }"#);
+        let e = run_workflow(&tm, &workflow).err().unwrap();
+        assert_eq!(e.kind(), ErrorKind::InvalidInput);
+        assert!(e.to_string().contains("The steps first, second depend"), "{}", e);
+        // nothing is run
+        assert!(!dir.join("free.tif").exists());
+
+        let invalid = [
+            r#"{ "steps": [] }"#,
+            r#"{ "steps": [ { "tool": "Add", "args": { "input1": "@missing", "input2": 1, "output": "x.tif" } } ] }"#,
+            r#"{ "steps": [ { "tool": "Add", "args": { "input1": 1, "input2": 1, "output": "@a" } },
+                            { "tool": "Add", "args": { "input1": 2, "input2": 1, "output": "@a" } } ] }"#,
+            r#"{ "steps": [ { "tool": "Add", "args": { "input1": 1, "input2": 1, "output": "@a" } } ], "persist": { "b": "b.tif" } }"#,
+            r#"{ "steps": [ { "tool": "NoSuchTool" } ] }"#,
+            r#"{ "steps": [ { "tool": "Add", "args": { "inptu1": 1 } } ] }"#,
+        ];
+        for contents in invalid.iter() {
+            let workflow = write_workflow(&dir, contents);
+            assert_eq!(run_workflow(&tm, &workflow).err().unwrap().kind(), ErrorKind::InvalidInput, "{}", contents);
+        }
+        let workflow = write_workflow(&dir, "{ \"steps\": ");
+        assert_eq!(run_workflow(&tm, &workflow).err().unwrap().kind(), ErrorKind::InvalidData);
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn a_failed_step_stops_the_steps_that_depend_on_it() {
+        let (dir, wd) = test_dir("wbt_workflow_failure");
+        write_input(&dir, "dem.tif");
+        let workflow = write_workflow(&dir, r#"{ "steps": [
+            { "id": "good", "tool": "Add", "args": { "input1": "dem.tif", "input2": 1, "output": "good.tif" } },
+            { "id": "bad", "tool": "Add", "args": { "input1": "missing.tif", "input2": 1, "output": "@x" } },
+            { "id": "after", "tool": "Add", "args": { "input1": "@x", "input2": 1, "output": "after.tif" } },
+            { "id": "last", "tool": "Add", "args": { "input1": "after.tif", "input2": 1, "output": "last.tif" } }
+        ] }"#);
+        let e = run_workflow(&quiet_manager(&wd), &workflow).err().unwrap();
+        assert_eq!(e.kind(), ErrorKind::Other);
+        let message = e.to_string();
+        assert!(message.starts_with("Workflow step 'bad' (Add) failed:"), "{}", message);
+        assert!(message.contains("\nSteps not run: after, last"), "{}", message);
+        assert!(!dir.join("after.tif").exists() && !dir.join("last.tif").exists());
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn cancelled_runs_start_no_further_steps() {
+        let (dir, wd) = test_dir("wbt_workflow_cancel");
+        write_input(&dir, "dem.tif");
+        let workflow = write_workflow(&dir, r#"{ "steps": [
+            { "id": "first", "tool": "Add", "args": { "input1": "dem.tif", "input2": 1, "output": "@a" } },
+            { "id": "second", "tool": "Add", "args": { "input1": "@a", "input2": 1, "output": "@b" } },
+            { "id": "third", "tool": "Add", "args": { "input1": "@b", "input2": 1, "output": "third.tif" } }
+        ] }"#);
+
+        // cancelled after the first step has finished
+        let sink = CancellingSink { token: CancellationToken::new(), on_start: false, after: 1, count: Default::default() };
+        let e = run_workflow(&manager(&wd, Arc::new(sink)), &workflow).err().unwrap();
+        assert_eq!(e.kind(), ErrorKind::Interrupted);
+        assert_eq!(e.to_string(), "The workflow was cancelled.\nCompleted steps: first\nCancelled steps: second, third");
+
+        // cancelled while the second step is running, which stops when it reports progress
+        let sink = CancellingSink { token: CancellationToken::new(), on_start: true, after: 2, count: Default::default() };
+        let e = run_workflow(&manager(&wd, Arc::new(sink)), &workflow).err().unwrap();
+        assert_eq!(e.kind(), ErrorKind::Interrupted);
+        assert_eq!(e.to_string(), "The workflow was cancelled.\nCompleted steps: first\nCancelled steps: second, third");
+        assert!(!dir.join("third.tif").exists());
+
+        // cancelled before the run
+        let token = CancellationToken::new();
+        token.cancel();
+        let mut sink = StdoutProgress::with_token(token);
+        sink.set_quiet(true);
+        let e = run_workflow(&manager(&wd, Arc::new(sink)), &workflow).err().unwrap();
+        assert_eq!(e.to_string(), "The workflow was cancelled.\nCompleted steps: none\nCancelled steps: first, second, third");
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,756 @@
+/*
+This code is part of the WhiteboxTools geospatial analysis library.
+Authors: Dr. John Lindsay
+Created: March 30, 2018
+Last Modified: April 6, 2018
+License: MIT
+
+Notes: GeoJSON (RFC 7946) files are read into, and written from, the same data model as
+Shapefiles, so that any tool that works with vectors also works with GeoJSON. A file
+holds a single geometry type, and so Point and MultiPoint features are read as a
+MultiPoint file when both occur, LineStrings and MultiLineStrings become PolyLines, and
+Polygons and MultiPolygons become Polygons. Positions with a third ordinate are read
+as Z shape types. Polygon rings are reoriented between the two conventions, i.e. RFC
+7946 exterior rings are counter-clockwise while Shapefile outer rings are clockwise.
+Attribute field types are inferred from the property values and the properties of each
+feature are written in field order. The coordinate reference system is read from the legacy
+crs member, if there is one; otherwise a file whose coordinates are within the range of
+longitudes and latitudes is assumed to be in WGS 84 (EPSG:4326), as RFC 7946 requires, and
+the tool help of every tool with a vector parameter says so.
+*/
+
+use std::fmt;
+use std::fs::File;
+use std::io::prelude::*;
+use std::io::{BufReader, BufWriter, Error, ErrorKind};
+use std::path::Path;
+use serde::de::{Deserialize, Deserializer, MapAccess, Visitor};
+use serde_json;
+use serde_json::Value;
+use spatial_ref_system::SpatialRef;
+use super::geometry::*;
+use super::shapefile::*;
+
+/// Returns true if the file name has a GeoJSON (.geojson or .json) extension.
+pub fn is_geojson_file(file_name: &str) -> bool {
+    match Path::new(file_name).extension() {
+        Some(ext) => {
+            let ext = ext.to_string_lossy().to_lowercase();
+            ext == "geojson" || ext == "json"
+        },
+        None => false,
+    }
+}
+
+// A FeatureCollection, a single Feature, or a bare geometry.
+#[derive(Deserialize)]
+struct GeoJsonObject {
+    #[serde(rename = "type")]
+    object_type: String,
+    #[serde(default)]
+    features: Vec<Feature>,
+    geometry: Option<Geometry>,
+    properties: Option<Properties>,
+    #[serde(default)]
+    coordinates: Value,
+    #[serde(default)]
+    geometries: Vec<Geometry>,
+    crs: Option<Value>,
+}
+
+#[derive(Deserialize)]
+struct Feature {
+    geometry: Option<Geometry>,
+    properties: Option<Properties>,
+}
+
+#[derive(Deserialize)]
+struct Geometry {
+    #[serde(rename = "type")]
+    geometry_type: String,
+    #[serde(default)]
+    coordinates: Value,
+    #[serde(default)]
+    geometries: Vec<Geometry>,
+}
+
+// The properties of a feature, in the order in which they appear in the file.
+struct Properties(Vec<(String, Value)>);
+
+struct PropertiesVisitor;
+
+impl<'de> Visitor<'de> for PropertiesVisitor {
+    type Value = Properties;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a JSON object")
+    }
+
+    fn visit_unit<E>(self) -> Result<Properties, E> {
+        Ok(Properties(vec![]))
+    }
+
+    fn visit_map<M: MapAccess<'de>>(self, mut access: M) -> Result<Properties, M::Error> {
+        let mut values = vec![];
+        while let Some((key, value)) = access.next_entry::<String, Value>()? {
+            values.push((key, value));
+        }
+        Ok(Properties(values))
+    }
+}
+
+impl<'de> Deserialize<'de> for Properties {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Properties, D::Error> {
+        deserializer.deserialize_any(PropertiesVisitor)
+    }
+}
+
+fn invalid(message: String) -> Error {
+    Error::new(ErrorKind::InvalidData, message)
+}
+
+fn parse_position(value: &Value) -> Result<Position, Error> {
+    if let Some(a) = value.as_array() {
+        if a.len() >= 2 {
+            if let (Some(x), Some(y)) = (a[0].as_f64(), a[1].as_f64()) {
+                return Ok(Position::new(x, y, if a.len() > 2 { a[2].as_f64() } else { None }, None));
+            }
+        }
+    }
+    Err(invalid(format!("Invalid GeoJSON position: {}", value)))
+}
+
+fn parse_positions(value: &Value) -> Result<Vec<Position>, Error> {
+    match value.as_array() {
+        Some(a) => a.iter().map(parse_position).collect(),
+        None => Err(invalid(format!("Invalid GeoJSON coordinates: {}", value))),
+    }
+}
+
+fn parse_lines(value: &Value) -> Result<Vec<Vec<Position>>, Error> {
+    match value.as_array() {
+        Some(a) => a.iter().map(parse_positions).collect(),
+        None => Err(invalid(format!("Invalid GeoJSON coordinates: {}", value))),
+    }
+}
+
+fn parse_geometry(geometry: &Geometry) -> Result<Parts, Error> {
+    let c = &geometry.coordinates;
+    match geometry.geometry_type.as_ref() {
+        "Point" => Ok(Parts::Points(vec![parse_position(c)?], false)),
+        "MultiPoint" => Ok(Parts::Points(parse_positions(c)?, true)),
+        "LineString" => Ok(Parts::Lines(vec![parse_positions(c)?])),
+        "MultiLineString" => Ok(Parts::Lines(parse_lines(c)?)),
+        "Polygon" => Ok(Parts::Polygons(vec![parse_lines(c)?])),
+        "MultiPolygon" => {
+            match c.as_array() {
+                Some(a) => Ok(Parts::Polygons(a.iter().map(parse_lines).collect::<Result<Vec<_>, Error>>()?)),
+                None => Err(invalid(format!("Invalid GeoJSON coordinates: {}", c))),
+            }
+        },
+        "GeometryCollection" => {
+            // collections are merged if their members are of the same base type
+            let mut merged: Option<Parts> = None;
+            for g in &geometry.geometries {
+                let parts = parse_geometry(g)?;
+                merged = Some(match (merged, parts) {
+                    (None, p) => p,
+                    (Some(Parts::Points(mut a, _)), Parts::Points(b, _)) => { a.extend(b); Parts::Points(a, true) },
+                    (Some(Parts::Lines(mut a)), Parts::Lines(b)) => { a.extend(b); Parts::Lines(a) },
+                    (Some(Parts::Polygons(mut a)), Parts::Polygons(b)) => { a.extend(b); Parts::Polygons(a) },
+                    _ => return Err(invalid("GeometryCollections containing more than one type of geometry are not supported.".to_string())),
+                });
+            }
+            match merged {
+                Some(p) => Ok(p),
+                None => Err(invalid("Empty GeometryCollections are not supported.".to_string())),
+            }
+        },
+        t => Err(invalid(format!("Unrecognized GeoJSON geometry type: {}", t))),
+    }
+}
+
+// reads the EPSG code of a legacy named crs member, e.g. "urn:ogc:def:crs:EPSG::26917"
+fn parse_crs_name(crs: &Value) -> Option<u16> {
+    let name = match crs.pointer("/properties/name").and_then(|v| v.as_str()) {
+        Some(n) => n.to_uppercase(),
+        None => return None,
+    };
+    if name.ends_with("CRS84") {
+        return Some(4326);
+    }
+    if !name.contains("EPSG") {
+        return None;
+    }
+    let digits: String = name.chars().rev().take_while(|c| c.is_digit(10)).collect::<Vec<char>>().into_iter().rev().collect();
+    digits.parse::<u16>().ok()
+}
+
+// reads the WKT of a legacy linked crs member, i.e. a .prj file relative to the GeoJSON file
+fn read_crs_link(crs: &Value, file_name: &str) -> Option<String> {
+    let href = match crs.pointer("/properties/href").and_then(|v| v.as_str()) {
+        Some(h) => h,
+        None => return None,
+    };
+    let prj = match Path::new(file_name).parent() {
+        Some(dir) => dir.join(href),
+        None => Path::new(href).to_path_buf(),
+    };
+    let mut wkt = String::new();
+    match File::open(prj) {
+        Ok(mut f) => if f.read_to_string(&mut wkt).is_err() { return None; },
+        Err(_) => return None,
+    }
+    Some(wkt)
+}
+
+// reads the WKT of the coordinate reference system of a legacy crs member, if it is recognized
+fn crs_wkt(crs: &Value, file_name: &str) -> Option<String> {
+    if let Some(wkt) = read_crs_link(crs, file_name) {
+        return Some(wkt);
+    }
+    parse_crs_name(crs)
+        .and_then(|code| SpatialRef::from_epsg(code).ok())
+        .map(|srs| srs.to_wkt())
+}
+
+// The crs member of a GeoJSON object; the other members are skipped without being decoded.
+#[derive(Deserialize)]
+struct CrsMember {
+    crs: Option<Value>,
+}
+
+/// Reads the coordinate reference system of a GeoJSON file from its crs member alone,
+/// without reading the features, returning its WKT, or None if there is no crs member.
+pub fn read_geojson_crs(file_name: &str) -> Result<Option<String>, Error> {
+    let f = File::open(file_name)?;
+    let object: CrsMember = match serde_json::from_reader(BufReader::new(f)) {
+        Ok(o) => o,
+        Err(e) => return Err(invalid(format!("Error reading GeoJSON file {}: {}", file_name, e))),
+    };
+    match object.crs {
+        None | Some(Value::Null) => Ok(None),
+        Some(crs) => match crs_wkt(&crs, file_name) {
+            Some(wkt) => Ok(Some(wkt)),
+            None => Err(invalid(format!("The crs member of the GeoJSON file {} is not recognized.", file_name))),
+        },
+    }
+}
+
+/// Reads a GeoJSON file into a Shapefile's records, attributes, and projection.
+pub fn read(sf: &mut Shapefile) -> Result<(), Error> {
+    let f = File::open(&sf.file_name)?;
+    let object: GeoJsonObject = match serde_json::from_reader(BufReader::new(f)) {
+        Ok(o) => o,
+        Err(e) => return Err(invalid(format!("Error reading GeoJSON file {}: {}", sf.file_name, e))),
+    };
+
+    let features = match object.object_type.as_ref() {
+        "FeatureCollection" => object.features,
+        "Feature" => vec![Feature { geometry: object.geometry, properties: object.properties }],
+        t => vec![Feature {
+            geometry: Some(Geometry { geometry_type: t.to_string(), coordinates: object.coordinates, geometries: object.geometries }),
+            properties: None,
+        }],
+    };
+
+    // geometries
+    let mut parts = Vec::with_capacity(features.len());
+    for feature in &features {
+        parts.push(match feature.geometry {
+            Some(ref g) => Some(parse_geometry(g)?),
+            None => None,
+        });
+    }
+    set_records(sf, parts, "GeoJSON")?;
+
+    // attributes
+    let properties: Vec<Vec<(String, Value)>> = features.into_iter()
+        .map(|f| f.properties.map(|p| p.0).unwrap_or(vec![]))
+        .collect();
+    let mut field_names: Vec<String> = vec![];
+    for p in &properties {
+        for &(ref key, _) in p {
+            if !field_names.contains(key) {
+                field_names.push(key.clone());
+            }
+        }
+    }
+    let mut fields = vec![];
+    for name in &field_names {
+        let values = properties.iter().filter_map(|p| p.iter().find(|&&(ref k, _)| k == name).map(|&(_, ref v)| v));
+        fields.push(infer_field(name, values));
+    }
+    for field in &fields {
+        sf.attributes.add_field(field.clone());
+    }
+    if fields.len() > 0 {
+        for p in &properties {
+            let record = fields.iter().map(|field| {
+                match p.iter().find(|&&(ref k, _)| *k == field.name) {
+                    Some(&(_, ref v)) => to_field_data(field, v),
+                    None => FieldData::Null,
+                }
+            }).collect();
+            sf.attributes.add_record(record, false)?;
+        }
+    }
+
+    // The crs member was removed from the GeoJSON specification, which requires WGS 84
+    // coordinates, but it is still written by many applications.
+    let crs = object.crs.unwrap_or(Value::Null);
+    if let Some(wkt) = crs_wkt(&crs, &sf.file_name) {
+        sf.projection = wkt;
+    } else if sf.header.x_min >= -180f64 && sf.header.x_max <= 180f64 &&
+        sf.header.y_min >= -90f64 && sf.header.y_max <= 90f64 {
+        if let Ok(srs) = SpatialRef::from_epsg(4326) {
+            sf.projection = srs.to_wkt();
+        }
+    }
+
+    Ok(())
+}
+
+// infers the type of an attribute field from the non-null values of a property
+fn infer_field<'a, I: Iterator<Item = &'a Value>>(name: &str, values: I) -> AttributeField {
+    let (mut all_int, mut all_number, mut all_bool, mut any) = (true, true, true, false);
+    let (mut int_length, mut decimals, mut text_length) = (1usize, 0usize, 1usize);
+    for v in values {
+        if v.is_null() { continue; }
+        any = true;
+        let is_int = match v.as_i64() {
+            Some(i) => i >= i32::min_value() as i64 && i <= i32::max_value() as i64,
+            None => false,
+        };
+        all_int = all_int && is_int;
+        all_number = all_number && v.is_number();
+        all_bool = all_bool && v.is_boolean();
+        let s = match v {
+            &Value::String(ref s) => s.clone(),
+            _ => v.to_string(),
+        };
+        text_length = text_length.max(s.len()); // in bytes, as the text is written as UTF-8
+        if v.is_number() {
+            let mut split = s.split('.');
+            let int_part = split.next().unwrap_or("");
+            int_length = int_length.max(int_part.len());
+            decimals = decimals.max(split.next().map(|d| d.len()).unwrap_or(0));
+        }
+    }
+    if !any {
+        AttributeField::new(name, 'C', 1u8, 0u8)
+    } else if all_int {
+        AttributeField::new(name, 'N', (int_length as u8).max(1u8), 0u8)
+    } else if all_number {
+        let decimals = decimals.max(1).min(15);
+        AttributeField::new(name, 'F', (int_length + 1 + decimals).min(254) as u8, decimals as u8)
+    } else if all_bool {
+        AttributeField::new(name, 'L', 1u8, 0u8)
+    } else {
+        AttributeField::new(name, 'C', text_length.min(254) as u8, 0u8)
+    }
+}
+
+fn to_field_data(field: &AttributeField, value: &Value) -> FieldData {
+    if value.is_null() {
+        return FieldData::Null;
+    }
+    match field.field_type {
+        'N' if field.decimal_count == 0 => value.as_i64().map(|v| FieldData::Int(v as i32)).unwrap_or(FieldData::Null),
+        'N' | 'F' => value.as_f64().map(FieldData::Real).unwrap_or(FieldData::Null),
+        'L' => value.as_bool().map(FieldData::Bool).unwrap_or(FieldData::Null),
+        _ => match value {
+            &Value::String(ref s) => FieldData::Text(s.clone()),
+            _ => FieldData::Text(value.to_string()),
+        },
+    }
+}
+
+/// Writes a Shapefile's records and attributes as a GeoJSON FeatureCollection. The
+/// records must already have been validated.
+pub fn write(sf: &Shapefile) -> Result<(), Error> {
+    let f = File::create(&sf.file_name)?;
+    let mut writer = BufWriter::new(f);
+
+    writer.write_all(b"{\n\"type\": \"FeatureCollection\",\n")?;
+    if !sf.projection.is_empty() {
+        // Coordinates are assumed to be WGS 84 unless another system is named. Systems
+        // without an EPSG code are written to a linked .prj file.
+        let epsg_code = SpatialRef::from_wkt(&sf.projection).map(|srs| srs.epsg_code).unwrap_or(0);
+        if epsg_code != 0 && epsg_code != 4326 {
+            writer.write_all(format!("\"crs\": {{ \"type\": \"name\", \"properties\": {{ \"name\": \"urn:ogc:def:crs:EPSG::{}\" }} }},\n", epsg_code).as_bytes())?;
+        } else if epsg_code == 0 {
+            let prj = Path::new(&sf.file_name).with_extension("prj");
+            let mut f = BufWriter::new(File::create(&prj)?);
+            f.write_all(sf.projection.as_bytes())?;
+            f.flush()?;
+            let href = prj.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or(String::new());
+            writer.write_all(format!("\"crs\": {{ \"type\": \"link\", \"properties\": {{ \"href\": {}, \"type\": \"esriwkt\" }} }},\n", json_string(&href)).as_bytes())?;
+        }
+    }
+    writer.write_all(b"\"features\": [\n")?;
+    for i in 0..sf.records.len() {
+        let geometry = geometry_to_json(&sf.records[i]);
+        let mut properties = vec![];
+        if sf.attributes.get_num_records() > i {
+            let record = sf.attributes.get_record(i);
+            for (field, value) in sf.attributes.fields.iter().zip(record.iter()) {
+                properties.push(format!("{}: {}", json_string(&field.name), field_data_to_json(value)));
+            }
+        }
+        writer.write_all(format!("{{ \"type\": \"Feature\", \"geometry\": {}, \"properties\": {{ {} }} }}{}\n",
+            geometry, properties.join(", "), if i < sf.records.len() - 1 { "," } else { "" }).as_bytes())?;
+    }
+    writer.write_all(b"]\n}\n")?;
+    writer.flush()?;
+    Ok(())
+}
+
+fn json_string(s: &str) -> String {
+    serde_json::to_string(s).unwrap_or("\"\"".to_string())
+}
+
+fn json_number(v: f64) -> String {
+    if v.is_finite() { format!("{}", v) } else { "null".to_string() }
+}
+
+fn field_data_to_json(value: &FieldData) -> String {
+    match value {
+        &FieldData::Int(v) => format!("{}", v),
+        &FieldData::Real(v) => json_number(v),
+        &FieldData::Text(ref s) => json_string(s),
+        &FieldData::Date(ref d) => format!("\"{:04}-{:02}-{:02}\"", d.year, d.month, d.day),
+        &FieldData::Bool(v) => format!("{}", v),
+        &FieldData::Null => "null".to_string(),
+    }
+}
+
+fn positions_to_json(positions: &[Position]) -> String {
+    let positions: Vec<String> = positions.iter().map(|p| {
+        match p.z {
+            Some(z) => format!("[{}, {}, {}]", json_number(p.x), json_number(p.y), json_number(z)),
+            None => format!("[{}, {}]", json_number(p.x), json_number(p.y)),
+        }
+    }).collect();
+    format!("[{}]", positions.join(", "))
+}
+
+fn geometry_to_json(sfg: &ShapefileGeometry) -> String {
+    match record_parts(sfg) {
+        None => "null".to_string(),
+        Some(Parts::Points(v, false)) => {
+            let s = positions_to_json(&v[0..1]);
+            format!("{{ \"type\": \"Point\", \"coordinates\": {} }}", &s[1..s.len() - 1])
+        },
+        Some(Parts::Points(v, true)) => {
+            format!("{{ \"type\": \"MultiPoint\", \"coordinates\": {} }}", positions_to_json(&v))
+        },
+        Some(Parts::Lines(v)) => {
+            let lines: Vec<String> = v.iter().map(|line| positions_to_json(line)).collect();
+            if lines.len() == 1 {
+                format!("{{ \"type\": \"LineString\", \"coordinates\": {} }}", lines[0])
+            } else {
+                format!("{{ \"type\": \"MultiLineString\", \"coordinates\": [{}] }}", lines.join(", "))
+            }
+        },
+        Some(Parts::Polygons(v)) => {
+            let polygons: Vec<String> = v.iter().map(|rings| {
+                let rings: Vec<String> = rings.iter().map(|ring| positions_to_json(ring)).collect();
+                format!("[{}]", rings.join(", "))
+            }).collect();
+            if polygons.len() == 1 {
+                format!("{{ \"type\": \"Polygon\", \"coordinates\": {} }}", polygons[0])
+            } else {
+                format!("{{ \"type\": \"MultiPolygon\", \"coordinates\": [{}] }}", polygons.join(", "))
+            }
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::fs;
+    use vector::Point2D;
+    use super::*;
+
+    fn temp_file(name: &str) -> String {
+        env::temp_dir().join(name).to_str().unwrap().to_string()
+    }
+
+    fn write_text(file_name: &str, text: &str) {
+        let mut f = File::create(file_name).unwrap();
+        f.write_all(text.as_bytes()).unwrap();
+    }
+
+    fn points(coordinates: &[(f64, f64)]) -> Vec<Point2D> {
+        coordinates.iter().map(|&(x, y)| Point2D { x: x, y: y }).collect()
+    }
+
+    // a clockwise square with a counter-clockwise hole, as in a Shapefile
+    fn polygon_with_hole(x: f64) -> ShapefileGeometry {
+        let mut sfg = ShapefileGeometry::new(ShapeType::Polygon);
+        sfg.add_part(&points(&[(x, 0.0), (x, 10.0), (x + 10.0, 10.0), (x + 10.0, 0.0), (x, 0.0)]));
+        sfg.add_part(&points(&[(x + 2.0, 2.0), (x + 4.0, 2.0), (x + 4.0, 4.0), (x + 2.0, 4.0), (x + 2.0, 2.0)]));
+        sfg
+    }
+
+    fn assert_same_records(a: &ShapefileGeometry, b: &ShapefileGeometry) {
+        assert_eq!(a.shape_type, b.shape_type);
+        assert_eq!(a.parts, b.parts);
+        assert_eq!(a.points.len(), b.points.len());
+        for i in 0..a.points.len() {
+            assert_eq!((a.points[i].x, a.points[i].y), (b.points[i].x, b.points[i].y));
+        }
+        assert_eq!(a.z_array, b.z_array);
+    }
+
+    fn read_json(file_name: &str) -> Value {
+        serde_json::from_reader(BufReader::new(File::open(file_name).unwrap())).unwrap()
+    }
+
+    #[test]
+    fn geojson_round_trip_for_every_geometry_type() {
+        let mut point = ShapefileGeometry::new(ShapeType::Point);
+        point.add_point(Point2D { x: -81.25, y: 43.5 });
+        let mut multipoint = ShapefileGeometry::new(ShapeType::MultiPoint);
+        multipoint.add_point(Point2D { x: 1.0, y: 2.0 });
+        multipoint.add_point(Point2D { x: 3.0, y: 4.0 });
+        let mut line = ShapefileGeometry::new(ShapeType::PolyLine);
+        line.add_part(&points(&[(0.0, 0.0), (1.0, 1.0), (2.0, 0.5)]));
+        let mut multiline = ShapefileGeometry::new(ShapeType::PolyLine);
+        multiline.add_part(&points(&[(0.0, 0.0), (1.0, 1.0)]));
+        multiline.add_part(&points(&[(5.0, 5.0), (6.0, 5.0)]));
+        let mut multipolygon = polygon_with_hole(0.0);
+        multipolygon.add_part(&points(&[(20.0, 0.0), (20.0, 1.0), (21.0, 1.0), (21.0, 0.0), (20.0, 0.0)]));
+        let mut pointz = ShapefileGeometry::new(ShapeType::PointZ);
+        pointz.add_pointz(Point2D { x: 1.5, y: 2.5 }, 100.25, 0.0);
+        let mut linez = ShapefileGeometry::new(ShapeType::PolyLineZ);
+        linez.add_partz(&points(&[(0.0, 0.0), (1.0, 1.0)]), &[10.0, 20.0], &[0.0, 0.0]);
+
+        let files: Vec<(&str, Vec<ShapefileGeometry>)> = vec![
+            ("point", vec![point]),
+            ("multipoint", vec![multipoint]),
+            ("lines", vec![line, multiline]),
+            ("polygons", vec![polygon_with_hole(0.0), multipolygon]),
+            ("pointz", vec![pointz]),
+            ("linez", vec![linez]),
+        ];
+        for (name, records) in files {
+            let file_name = temp_file(&format!("wbt_geojson_{}.geojson", name));
+            let mut sf = Shapefile::new(&file_name, "w").unwrap();
+            sf.header.shape_type = records[0].shape_type.clone();
+            for record in &records {
+                sf.add_record(record.clone());
+            }
+            sf.add_record(ShapefileGeometry::new(ShapeType::Null));
+            sf.write().unwrap();
+
+            let input = Shapefile::new(&file_name, "r").unwrap();
+            assert_eq!(input.header.shape_type, sf.header.shape_type, "{}", name);
+            assert_eq!(input.num_records, records.len() + 1);
+            for i in 0..records.len() {
+                assert_same_records(input.get_record(i), &records[i]);
+            }
+            assert_eq!(input.get_record(records.len()).shape_type, ShapeType::Null);
+            assert_eq!(input.attributes.get_value(1, "FID"), FieldData::Int(2));
+            let _ = fs::remove_file(&file_name);
+        }
+    }
+
+    #[test]
+    fn exterior_rings_are_counter_clockwise() {
+        let file_name = temp_file("wbt_geojson_rings.geojson");
+        let mut sf = Shapefile::new(&file_name, "w").unwrap();
+        sf.header.shape_type = ShapeType::Polygon;
+        sf.add_record(polygon_with_hole(0.0));
+        sf.write().unwrap();
+
+        let json = read_json(&file_name);
+        let geometry = &json["features"][0]["geometry"];
+        assert_eq!(geometry["type"], "Polygon");
+        let rings: Vec<Vec<Point2D>> = geometry["coordinates"].as_array().unwrap().iter().map(|ring| {
+            ring.as_array().unwrap().iter().map(|p| Point2D { x: p[0].as_f64().unwrap(), y: p[1].as_f64().unwrap() }).collect()
+        }).collect();
+        assert!(signed_area(&rings[0]) > 0f64);
+        assert!(signed_area(&rings[1]) < 0f64);
+        let _ = fs::remove_file(&file_name);
+    }
+
+    #[test]
+    fn attributes_are_written_as_properties() {
+        let file_name = temp_file("wbt_geojson_attributes.geojson");
+        let mut sf = Shapefile::new(&file_name, "w").unwrap();
+        sf.header.shape_type = ShapeType::Point;
+        sf.attributes.add_field(AttributeField::new("ID", 'N', 6u8, 0u8));
+        sf.attributes.add_field(AttributeField::new("VALUE", 'F', 12u8, 3u8));
+        sf.attributes.add_field(AttributeField::new("NAME", 'C', 20u8, 0u8));
+        sf.attributes.add_field(AttributeField::new("FLAG", 'L', 1u8, 0u8));
+        sf.attributes.add_field(AttributeField::new("DATE", 'D', 8u8, 0u8));
+        let records = vec![
+            vec![FieldData::Int(1), FieldData::Real(2.5), FieldData::Text("Lac Ste-Thérèse \"east\"".to_string()),
+                 FieldData::Bool(true), FieldData::Date(DateData { year: 2018, month: 4, day: 6 })],
+            vec![FieldData::Int(-20), FieldData::Real(-0.125), FieldData::Null, FieldData::Bool(false), FieldData::Null],
+        ];
+        for (i, record) in records.into_iter().enumerate() {
+            let mut sfg = ShapefileGeometry::new(ShapeType::Point);
+            sfg.add_point(Point2D { x: i as f64, y: 0.0 });
+            sf.add_record(sfg);
+            sf.attributes.add_record(record, false).unwrap();
+        }
+        sf.write().unwrap();
+
+        let input = Shapefile::new(&file_name, "r").unwrap();
+        let names: Vec<&str> = input.attributes.fields.iter().map(|f| f.name.as_ref()).collect();
+        assert_eq!(names, vec!["ID", "VALUE", "NAME", "FLAG", "DATE"]);
+        let types: Vec<char> = input.attributes.fields.iter().map(|f| f.field_type).collect();
+        // dates are written as ISO 8601 text
+        assert_eq!(types, vec!['N', 'F', 'C', 'L', 'C']);
+        assert_eq!(input.attributes.get_value(0, "NAME"), FieldData::Text("Lac Ste-Thérèse \"east\"".to_string()));
+        assert_eq!(input.attributes.get_value(0, "DATE"), FieldData::Text("2018-04-06".to_string()));
+        assert_eq!(input.attributes.get_value(1, "ID"), FieldData::Int(-20));
+        assert_eq!(input.attributes.get_value(1, "VALUE"), FieldData::Real(-0.125));
+        assert_eq!(input.attributes.get_value(1, "NAME"), FieldData::Null);
+        assert_eq!(input.attributes.get_value(1, "FLAG"), FieldData::Bool(false));
+        let _ = fs::remove_file(&file_name);
+    }
+
+    #[test]
+    fn foreign_geojson_is_read() {
+        let file_name = temp_file("wbt_geojson_foreign.json");
+        write_text(&file_name, r#"{ "type": "FeatureCollection", "features": [
+            { "type": "Feature", "geometry": { "type": "Point", "coordinates": [1.0, 2.0] },
+              "properties": { "count": 3, "height": 1, "name": "één", "ok": true, "note": null } },
+            { "type": "Feature", "geometry": { "type": "MultiPoint", "coordinates": [[3.0, 4.0], [5.0, 6.0, 7.0]] },
+              "properties": { "height": 2.75, "extra": "only here", "count": 4 } },
+            { "type": "Feature", "geometry": null, "properties": null },
+            { "type": "Feature", "geometry": { "type": "GeometryCollection", "geometries": [
+                { "type": "Point", "coordinates": [8.0, 9.0] }, { "type": "MultiPoint", "coordinates": [[10.0, 11.0]] } ] } }
+        ] }"#);
+        let sf = Shapefile::new(&file_name, "r").unwrap();
+        // points and multipoints are read as multipoints, with a z value if any position has one
+        assert_eq!(sf.header.shape_type, ShapeType::MultiPointZ);
+        assert_eq!(sf.num_records, 4);
+        assert_eq!(sf.get_record(0).points.len(), 1);
+        assert_eq!(sf.get_record(1).z_array, vec![0.0, 7.0]);
+        assert_eq!(sf.get_record(2).shape_type, ShapeType::Null);
+        assert_eq!(sf.get_record(3).points.len(), 2);
+
+        // the fields are in the order in which the properties first appear
+        let fields: Vec<(&str, char)> = sf.attributes.fields.iter().map(|f| (f.name.as_ref(), f.field_type)).collect();
+        assert_eq!(fields, vec![("count", 'N'), ("height", 'F'), ("name", 'C'), ("ok", 'L'), ("note", 'C'), ("extra", 'C')]);
+        // text lengths are in bytes
+        assert_eq!(sf.attributes.fields[2].field_length, 5);
+        assert_eq!(sf.attributes.get_value(0, "height"), FieldData::Real(1.0));
+        assert_eq!(sf.attributes.get_value(1, "count"), FieldData::Int(4));
+        assert_eq!(sf.attributes.get_value(1, "name"), FieldData::Null);
+        assert_eq!(sf.attributes.get_value(2, "count"), FieldData::Null);
+        assert_eq!(sf.attributes.get_value(3, "extra"), FieldData::Null);
+        let _ = fs::remove_file(&file_name);
+    }
+
+    #[test]
+    fn single_features_and_bare_geometries_are_read() {
+        let file_name = temp_file("wbt_geojson_single.geojson");
+        write_text(&file_name, r#"{ "type": "Feature", "properties": { "id": 7 },
+            "geometry": { "type": "Polygon", "coordinates": [[[0, 0], [4, 0], [4, 4], [0, 4]]] } }"#);
+        let sf = Shapefile::new(&file_name, "r").unwrap();
+        assert_eq!(sf.header.shape_type, ShapeType::Polygon);
+        // the ring is closed and made clockwise
+        let ring = &sf.get_record(0).points;
+        assert_eq!(ring.len(), 5);
+        assert!(signed_area(ring) < 0f64);
+        assert_eq!(sf.attributes.get_value(0, "id"), FieldData::Int(7));
+
+        write_text(&file_name, r#"{ "type": "MultiLineString", "coordinates": [[[0, 0], [1, 1]], [[2, 2], [3, 3], [4, 2]]] }"#);
+        let sf = Shapefile::new(&file_name, "r").unwrap();
+        assert_eq!(sf.header.shape_type, ShapeType::PolyLine);
+        assert_eq!(sf.get_record(0).parts, vec![0, 2]);
+        assert_eq!((sf.header.x_max, sf.header.y_max), (4.0, 3.0));
+        let _ = fs::remove_file(&file_name);
+    }
+
+    #[test]
+    fn invalid_geojson_is_rejected() {
+        let file_name = temp_file("wbt_geojson_invalid.geojson");
+        let texts = [
+            r#"{ "type": "FeatureCollection", "features": [ "#,
+            r#"{ "type": "FeatureCollection", "features": [
+                { "type": "Feature", "geometry": { "type": "Point", "coordinates": [0, 0] } },
+                { "type": "Feature", "geometry": { "type": "LineString", "coordinates": [[0, 0], [1, 1]] } } ] }"#,
+            r#"{ "type": "Point", "coordinates": [0] }"#,
+            r#"{ "type": "LineString", "coordinates": [[0, 0], ["a", 1]] }"#,
+            r#"{ "type": "Circle", "coordinates": [0, 0] }"#,
+            r#"{ "type": "GeometryCollection", "geometries": [] }"#,
+        ];
+        for text in texts.iter() {
+            write_text(&file_name, text);
+            let err = Shapefile::new(&file_name, "r").err().expect(text);
+            assert_eq!(err.kind(), ErrorKind::InvalidData);
+        }
+        let _ = fs::remove_file(&file_name);
+    }
+
+    #[test]
+    fn coordinate_reference_systems_are_read_and_written() {
+        let file_name = temp_file("wbt_geojson_crs.geojson");
+        let utm = SpatialRef::from_epsg(26917).unwrap();
+        let point_file = |crs: &str, x: f64, y: f64| {
+            write_text(&file_name, &format!(r#"{{ "type": "FeatureCollection", {} "features": [
+                {{ "type": "Feature", "geometry": {{ "type": "Point", "coordinates": [{}, {}] }}, "properties": {{}} }} ] }}"#, crs, x, y));
+        };
+
+        point_file(r#""crs": { "type": "name", "properties": { "name": "urn:ogc:def:crs:EPSG::26917" } },"#, 500_000.0, 4_800_000.0);
+        assert_eq!(read_geojson_crs(&file_name).unwrap(), Some(utm.to_wkt()));
+        assert_eq!(Shapefile::new(&file_name, "r").unwrap().projection, utm.to_wkt());
+
+        point_file(r#""crs": { "type": "name", "properties": { "name": "urn:ogc:def:crs:OGC:1.3:CRS84" } },"#, -81.0, 43.0);
+        assert_eq!(read_geojson_crs(&file_name).unwrap(), Some(SpatialRef::from_epsg(4326).unwrap().to_wkt()));
+
+        point_file(r#""crs": { "type": "name", "properties": { "name": "unknown" } },"#, -81.0, 43.0);
+        assert_eq!(read_geojson_crs(&file_name).unwrap_err().kind(), ErrorKind::InvalidData);
+
+        // without a crs member, geographic coordinates are taken to be WGS 84 when the file is read
+        point_file("", -81.0, 43.0);
+        assert_eq!(read_geojson_crs(&file_name).unwrap(), None);
+        assert_eq!(Shapefile::new(&file_name, "r").unwrap().projection, SpatialRef::from_epsg(4326).unwrap().to_wkt());
+        point_file("", 500_000.0, 4_800_000.0);
+        assert!(Shapefile::new(&file_name, "r").unwrap().projection.is_empty());
+
+        // systems with an EPSG code are named, while others are linked to a .prj file
+        let mut sf = Shapefile::new(&file_name, "w").unwrap();
+        sf.header.shape_type = ShapeType::Point;
+        let mut sfg = ShapefileGeometry::new(ShapeType::Point);
+        sfg.add_point(Point2D { x: 500_000.0, y: 4_800_000.0 });
+        sf.add_record(sfg);
+        sf.projection = utm.to_wkt();
+        sf.write().unwrap();
+        assert_eq!(read_json(&file_name)["crs"]["properties"]["name"], "urn:ogc:def:crs:EPSG::26917");
+
+        let mut custom = utm.clone();
+        custom.epsg_code = 0;
+        custom.name = "Custom TM".to_string();
+        sf.projection = custom.to_wkt();
+        sf.write().unwrap();
+        let prj = Path::new(&file_name).with_extension("prj");
+        assert_eq!(read_json(&file_name)["crs"]["properties"]["href"], "wbt_geojson_crs.prj");
+        assert!(prj.is_file());
+        assert_eq!(read_geojson_crs(&file_name).unwrap(), Some(custom.to_wkt()));
+        assert_eq!(Shapefile::new(&file_name, "r").unwrap().projection, custom.to_wkt());
+
+        // WGS 84 is the default, and so it is not named
+        sf.projection = SpatialRef::from_epsg(4326).unwrap().to_wkt();
+        sf.write().unwrap();
+        assert!(read_json(&file_name).get("crs").is_none());
+        let _ = fs::remove_file(&prj);
+        let _ = fs::remove_file(&file_name);
+    }
+
+    #[test]
+    fn geojson_files_are_recognized_by_extension() {
+        assert!(is_geojson_file("roads.geojson"));
+        assert!(is_geojson_file("/data/Roads.JSON"));
+        assert!(!is_geojson_file("roads.shp"));
+        assert!(!is_geojson_file("geojson"));
+    }
+}
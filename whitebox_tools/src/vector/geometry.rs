@@ -0,0 +1,341 @@
+/*
+This code is part of the WhiteboxTools geospatial analysis library.
+Authors: Dr. John Lindsay
+Created: March 31, 2018
+Last Modified: April 6, 2018
+License: MIT
+
+Notes: Conversions between the Shapefile geometry model and the simple features (points,
+lines, and polygons with holes) of the GeoJSON and GeoPackage formats. A Shapefile holds a
+single geometry type, and so Point and MultiPoint features are stored as MultiPoints when
+both occur, lines become PolyLines, and polygons become Polygons. Shapefile outer rings are
+clockwise and holes are counter-clockwise, while simple feature exterior rings are
+counter-clockwise and holes are clockwise.
+*/
+
+use std::cmp::Ordering;
+use std::f64;
+use std::io::{Error, ErrorKind};
+use vector::{point_in_poly, Point2D};
+use super::shapefile::*;
+
+/// A coordinate of a simple feature, with optional z and measure values.
+#[derive(Clone, Copy, Debug)]
+pub struct Position {
+    pub x: f64,
+    pub y: f64,
+    pub z: Option<f64>,
+    pub m: Option<f64>,
+}
+
+impl Position {
+    pub fn new(x: f64, y: f64, z: Option<f64>, m: Option<f64>) -> Position {
+        Position { x: x, y: y, z: z, m: m }
+    }
+}
+
+/// The parts of a simple feature geometry. The flag of a Points geometry is true for a
+/// MultiPoint, and each polygon is a list of rings, the first of which is the exterior.
+pub enum Parts {
+    Points(Vec<Position>, bool),
+    Lines(Vec<Vec<Position>>),
+    Polygons(Vec<Vec<Vec<Position>>>),
+}
+
+impl Parts {
+    fn positions<'a>(&'a self) -> Box<Iterator<Item = &'a Position> + 'a> {
+        match self {
+            &Parts::Points(ref v, _) => Box::new(v.iter()),
+            &Parts::Lines(ref v) => Box::new(v.iter().flat_map(|l| l.iter())),
+            &Parts::Polygons(ref v) => Box::new(v.iter().flat_map(|r| r.iter().flat_map(|l| l.iter()))),
+        }
+    }
+}
+
+/// Returns twice the signed area of a ring, which is negative for clockwise rings.
+pub fn signed_area(ring: &[Point2D]) -> f64 {
+    let mut area = 0f64;
+    for i in 0..ring.len() {
+        let j = (i + 1) % ring.len();
+        area += ring[i].x * ring[j].y - ring[j].x * ring[i].y;
+    }
+    area
+}
+
+/// Sets the shape type, records, and extent of a Shapefile from the geometries of a set of
+/// features, which are None for features without a geometry.
+pub fn set_records(sf: &mut Shapefile, features: Vec<Option<Parts>>, format_name: &str) -> Result<(), Error> {
+    let mut base_type = ShapeType::Null;
+    let (mut has_z, mut has_m) = (false, false);
+    for p in features.iter().filter_map(|p| p.as_ref()) {
+        let t = match p {
+            &Parts::Points(_, false) => ShapeType::Point,
+            &Parts::Points(_, true) => ShapeType::MultiPoint,
+            &Parts::Lines(_) => ShapeType::PolyLine,
+            &Parts::Polygons(_) => ShapeType::Polygon,
+        };
+        for pos in p.positions() {
+            has_z = has_z || pos.z.is_some();
+            has_m = has_m || pos.m.is_some();
+        }
+        base_type = match (base_type, t) {
+            (ShapeType::Null, t) => t,
+            (ShapeType::Point, ShapeType::MultiPoint) | (ShapeType::MultiPoint, ShapeType::Point) => ShapeType::MultiPoint,
+            (a, b) => {
+                if a != b {
+                    return Err(Error::new(ErrorKind::InvalidData,
+                        format!("The {} file {} contains more than one type of geometry ({} and {}).", format_name, sf.file_name, a, b)));
+                }
+                a
+            },
+        };
+    }
+    let shape_type = match (base_type, has_z, has_m) {
+        (ShapeType::Point, true, _) => ShapeType::PointZ,
+        (ShapeType::MultiPoint, true, _) => ShapeType::MultiPointZ,
+        (ShapeType::PolyLine, true, _) => ShapeType::PolyLineZ,
+        (ShapeType::Polygon, true, _) => ShapeType::PolygonZ,
+        (ShapeType::Point, false, true) => ShapeType::PointM,
+        (ShapeType::MultiPoint, false, true) => ShapeType::MultiPointM,
+        (ShapeType::PolyLine, false, true) => ShapeType::PolyLineM,
+        (ShapeType::Polygon, false, true) => ShapeType::PolygonM,
+        (t, _, _) => t,
+    };
+    sf.header.shape_type = shape_type.clone();
+
+    let add_values = |sfg: &mut ShapefileGeometry, positions: &[Position]| {
+        if has_z { sfg.z_array.extend(positions.iter().map(|p| p.z.unwrap_or(0f64))); }
+        if has_m { sfg.m_array.extend(positions.iter().map(|p| p.m.unwrap_or(M_NO_DATA))); }
+    };
+    for p in features {
+        let mut sfg = match p {
+            None => ShapefileGeometry::new(ShapeType::Null),
+            Some(Parts::Points(v, _)) => {
+                let mut sfg = ShapefileGeometry::new(shape_type.clone());
+                for p in &v {
+                    sfg.add_point(Point2D { x: p.x, y: p.y });
+                }
+                add_values(&mut sfg, &v);
+                sfg
+            },
+            Some(Parts::Lines(v)) => {
+                let mut sfg = ShapefileGeometry::new(shape_type.clone());
+                for line in v.iter().filter(|l| l.len() > 0) {
+                    let points: Vec<Point2D> = line.iter().map(|p| Point2D { x: p.x, y: p.y }).collect();
+                    sfg.add_part(&points);
+                    add_values(&mut sfg, line);
+                }
+                sfg
+            },
+            Some(Parts::Polygons(v)) => {
+                let mut sfg = ShapefileGeometry::new(shape_type.clone());
+                for polygon in v {
+                    for (i, mut ring) in polygon.into_iter().enumerate() {
+                        if ring.len() == 0 { continue; }
+                        let (first, last) = (ring[0], ring[ring.len() - 1]);
+                        if first.x != last.x || first.y != last.y {
+                            ring.push(first);
+                        }
+                        let mut points: Vec<Point2D> = ring.iter().map(|p| Point2D { x: p.x, y: p.y }).collect();
+                        // the first ring is the exterior, which is clockwise in a Shapefile
+                        let is_clockwise = signed_area(&points) < 0f64;
+                        if (i == 0) != is_clockwise {
+                            points.reverse();
+                            ring.reverse();
+                        }
+                        sfg.add_part(&points);
+                        add_values(&mut sfg, &ring);
+                    }
+                }
+                sfg
+            },
+        };
+        if sfg.shape_type != ShapeType::Null && sfg.points.len() == 0 {
+            sfg = ShapefileGeometry::new(ShapeType::Null);
+        }
+        sfg.calculate_extent();
+        sf.records.push(sfg);
+    }
+    sf.num_records = sf.records.len();
+
+    // the file extent
+    sf.header.x_min = f64::INFINITY;
+    sf.header.y_min = f64::INFINITY;
+    sf.header.x_max = f64::NEG_INFINITY;
+    sf.header.y_max = f64::NEG_INFINITY;
+    sf.header.z_min = f64::INFINITY;
+    sf.header.z_max = f64::NEG_INFINITY;
+    sf.header.m_min = f64::INFINITY;
+    sf.header.m_max = f64::NEG_INFINITY;
+    for rec in sf.records.iter().filter(|r| r.shape_type != ShapeType::Null) {
+        if rec.x_min < sf.header.x_min { sf.header.x_min = rec.x_min; }
+        if rec.y_min < sf.header.y_min { sf.header.y_min = rec.y_min; }
+        if rec.x_max > sf.header.x_max { sf.header.x_max = rec.x_max; }
+        if rec.y_max > sf.header.y_max { sf.header.y_max = rec.y_max; }
+        if has_z {
+            if rec.z_min < sf.header.z_min { sf.header.z_min = rec.z_min; }
+            if rec.z_max > sf.header.z_max { sf.header.z_max = rec.z_max; }
+        }
+        if has_m && rec.m_min > M_NO_DATA_THRESHOLD {
+            if rec.m_min < sf.header.m_min { sf.header.m_min = rec.m_min; }
+            if rec.m_max > sf.header.m_max { sf.header.m_max = rec.m_max; }
+        }
+    }
+    if sf.header.x_min > sf.header.x_max {
+        sf.header.x_min = 0f64;
+        sf.header.y_min = 0f64;
+        sf.header.x_max = 0f64;
+        sf.header.y_max = 0f64;
+    }
+    if sf.header.z_min > sf.header.z_max {
+        sf.header.z_min = 0f64;
+        sf.header.z_max = 0f64;
+    }
+    if sf.header.m_min > sf.header.m_max {
+        sf.header.m_min = 0f64;
+        sf.header.m_max = 0f64;
+    }
+    Ok(())
+}
+
+/// Returns the simple feature parts of a record, or None for a Null record. Each hole of a
+/// polygon is assigned to the smallest outer ring that contains it.
+pub fn record_parts(sfg: &ShapefileGeometry) -> Option<Parts> {
+    let n = sfg.points.len();
+    let dimension = sfg.shape_type.dimension();
+    let position = |i: usize| {
+        let z = if dimension == ShapeTypeDimension::Z && i < sfg.z_array.len() { Some(sfg.z_array[i]) } else { None };
+        let m = if i < sfg.m_array.len() && sfg.m_array[i] >= M_NO_DATA_THRESHOLD { Some(sfg.m_array[i]) } else { None };
+        Position::new(sfg.points[i].x, sfg.points[i].y, z, m)
+    };
+    let part_range = |part: usize| -> (usize, usize) {
+        let start = sfg.parts[part] as usize;
+        let end = if part < sfg.parts.len() - 1 { sfg.parts[part + 1] as usize } else { n };
+        (start, end)
+    };
+    if n == 0 {
+        return None;
+    }
+    match sfg.shape_type.base_shape_type() {
+        ShapeType::Null => None,
+        ShapeType::Point => Some(Parts::Points(vec![position(0)], false)),
+        ShapeType::MultiPoint => Some(Parts::Points((0..n).map(|i| position(i)).collect(), true)),
+        ShapeType::PolyLine => {
+            Some(Parts::Lines((0..sfg.parts.len()).map(|part| {
+                let (start, end) = part_range(part);
+                (start..end).map(|i| position(i)).collect()
+            }).collect()))
+        },
+        _ => {
+            let mut polygons: Vec<Vec<usize>> = vec![];
+            let mut outer_rings: Vec<Vec<Point2D>> = vec![];
+            let mut holes = vec![];
+            for part in 0..sfg.parts.len() {
+                let (start, end) = part_range(part);
+                let mut ring = sfg.points[start..end].to_vec();
+                if signed_area(&ring) < 0f64 || (part == 0 && sfg.parts.len() == 1) {
+                    // point_in_poly expects counter-clockwise rings
+                    ring.reverse();
+                    polygons.push(vec![part]);
+                    outer_rings.push(ring);
+                } else {
+                    holes.push((part, ring));
+                }
+            }
+            // a hole belongs to the smallest outer ring that contains it, since the larger ones
+            // may contain it only because they contain its ring's outer ring, e.g. an island in a lake
+            let areas: Vec<f64> = outer_rings.iter().map(|outer| signed_area(outer).abs()).collect();
+            for (part, ring) in holes {
+                let container = (0..outer_rings.len())
+                    .filter(|&j| outer_rings[j].len() > 1 && ring.iter().any(|p| point_in_poly(p, &outer_rings[j])))
+                    .min_by(|&a, &b| areas[a].partial_cmp(&areas[b]).unwrap_or(Ordering::Equal));
+                match container {
+                    Some(j) => polygons[j].push(part),
+                    None => {
+                        // an unenclosed counter-clockwise ring is treated as an outer ring
+                        polygons.push(vec![part]);
+                        outer_rings.push(vec![]);
+                    },
+                }
+            }
+            Some(Parts::Polygons(polygons.iter().map(|rings| {
+                rings.iter().map(|&part| {
+                    let (start, end) = part_range(part);
+                    let is_clockwise = signed_area(&sfg.points[start..end]) < 0f64;
+                    // exterior rings are counter-clockwise and holes are clockwise
+                    let reverse = if part == rings[0] { is_clockwise } else { !is_clockwise };
+                    let mut ring: Vec<Position> = (start..end).map(|i| position(i)).collect();
+                    if reverse { ring.reverse(); }
+                    ring
+                }).collect()
+            }).collect()))
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ring(x: f64, size: f64, clockwise: bool) -> Vec<Point2D> {
+        let mut r = vec![Point2D { x: x, y: x }, Point2D { x: x, y: x + size }, Point2D { x: x + size, y: x + size },
+                         Point2D { x: x + size, y: x }, Point2D { x: x, y: x }];
+        if !clockwise { r.reverse(); }
+        r
+    }
+
+    fn polygon_record(rings: &[Vec<Point2D>]) -> ShapefileGeometry {
+        let mut sfg = ShapefileGeometry::new(ShapeType::Polygon);
+        for r in rings {
+            sfg.add_part(r);
+        }
+        sfg
+    }
+
+    #[test]
+    fn signed_area_of_clockwise_ring_is_negative() {
+        assert_eq!(signed_area(&ring(0.0, 2.0, true)), -8.0);
+        assert_eq!(signed_area(&ring(0.0, 2.0, false)), 8.0);
+    }
+
+    #[test]
+    fn holes_belong_to_the_smallest_containing_ring() {
+        // a lake within a polygon, containing an island that has a pond of its own
+        let sfg = polygon_record(&[ring(0.0, 100.0, true), ring(10.0, 80.0, false),
+                                   ring(20.0, 60.0, true), ring(30.0, 40.0, false)]);
+        let polygons = match record_parts(&sfg) {
+            Some(Parts::Polygons(p)) => p,
+            _ => panic!("expected polygons"),
+        };
+        assert_eq!(polygons.len(), 2);
+        let starts: Vec<Vec<f64>> = polygons.iter().map(|p| p.iter().map(|r| r[0].x).collect()).collect();
+        assert_eq!(starts, vec![vec![0.0, 10.0], vec![20.0, 30.0]]);
+        for polygon in &polygons {
+            // exterior rings are counter-clockwise and holes are clockwise
+            let points: Vec<Vec<Point2D>> = polygon.iter().map(|r| r.iter().map(|p| Point2D { x: p.x, y: p.y }).collect()).collect();
+            assert!(signed_area(&points[0]) > 0f64);
+            assert!(signed_area(&points[1]) < 0f64);
+        }
+
+        // the order of the rings does not matter
+        let sfg = polygon_record(&[ring(30.0, 40.0, false), ring(20.0, 60.0, true),
+                                   ring(10.0, 80.0, false), ring(0.0, 100.0, true)]);
+        match record_parts(&sfg) {
+            Some(Parts::Polygons(p)) => {
+                let starts: Vec<Vec<f64>> = p.iter().map(|p| p.iter().map(|r| r[0].x).collect()).collect();
+                assert_eq!(starts, vec![vec![20.0, 30.0], vec![0.0, 10.0]]);
+            },
+            _ => panic!("expected polygons"),
+        }
+    }
+
+    #[test]
+    fn lone_counter_clockwise_ring_is_an_exterior() {
+        let sfg = polygon_record(&[ring(0.0, 5.0, false), ring(10.0, 5.0, false)]);
+        match record_parts(&sfg) {
+            Some(Parts::Polygons(p)) => assert_eq!(p.iter().map(|p| p.len()).collect::<Vec<usize>>(), vec![1, 1]),
+            _ => panic!("expected polygons"),
+        }
+        assert!(record_parts(&ShapefileGeometry::new(ShapeType::Null)).is_none());
+    }
+}
@@ -0,0 +1,737 @@
+/*
+This code is part of the WhiteboxTools geospatial analysis library.
+Authors: Dr. John Lindsay
+Created: March 31, 2018
+Last Modified: April 6, 2018
+License: MIT
+
+Notes: GeoPackage feature tables are read into, and written from, the same data model as
+Shapefiles. The first feature table listed in a GeoPackage's contents is read. Geometries
+are stored as GeoPackage binary blobs, i.e. a short header followed by the ISO well-known
+binary (WKB) encoding of the geometry; extended GeoPackage geometry types are not supported.
+INTEGER, REAL, TEXT, BOOLEAN, and DATE columns are mapped onto the N, F, C, L, and D
+attribute field types, respectively, and BLOB columns are ignored. A file is written with a
+single feature table, named after the file, with an integer 'fid' primary key.
+*/
+
+use std::f64;
+use std::io::{Error, ErrorKind};
+use std::path::Path;
+use byteorder::{ByteOrder, BigEndian, LittleEndian};
+use io_utils::{create_geopackage, get_srs_id, open_geopackage, read_srs, GeoPackageContents, SqlValue};
+use spatial_ref_system::SpatialRef;
+use super::geometry::*;
+use super::shapefile::*;
+
+fn invalid(message: String) -> Error {
+    Error::new(ErrorKind::InvalidData, message)
+}
+
+/// Reads the first feature table of a GeoPackage into a Shapefile's records, attributes,
+/// and projection.
+pub fn read(sf: &mut Shapefile) -> Result<(), Error> {
+    let (db, contents) = open_geopackage(&sf.file_name, "features")?;
+    let table = db.read_table(&contents.table_name)?;
+
+    // the geometry column
+    let geometry_columns = db.read_table("gpkg_geometry_columns")?;
+    let mut geometry_column = None;
+    for row in 0..geometry_columns.rows.len() {
+        let name = geometry_columns.get_value(row, "table_name");
+        if name.as_str().map(|n| n.to_lowercase()) == Some(contents.table_name.to_lowercase()) {
+            geometry_column = geometry_columns.get_value(row, "column_name").as_str()
+                .and_then(|c| table.get_column_num(c));
+        }
+    }
+    let geometry_column = match geometry_column {
+        Some(c) => c,
+        None => return Err(invalid(format!("The geometry column of the GeoPackage table {} could not be found.", contents.table_name))),
+    };
+
+    let mut features = Vec::with_capacity(table.rows.len());
+    for row in &table.rows {
+        features.push(match row[geometry_column] {
+            SqlValue::Blob(ref blob) => read_geometry_blob(blob)?,
+            _ => None,
+        });
+    }
+    set_records(sf, features, "GeoPackage")?;
+
+    // attributes
+    let mut columns = vec![];
+    for (c, column) in table.columns.iter().enumerate() {
+        if c == geometry_column || column.is_rowid || column.declared_type.contains("BLOB") {
+            continue;
+        }
+        let field = infer_field(&column.name, &column.declared_type, table.rows.iter().map(|r| &r[c]));
+        sf.attributes.add_field(field);
+        columns.push(c);
+    }
+    if columns.len() > 0 {
+        for row in &table.rows {
+            let record = columns.iter().zip(sf.attributes.fields.iter())
+                .map(|(&c, field)| to_field_data(field, &row[c]))
+                .collect();
+            sf.attributes.add_record(record, false)?;
+        }
+    }
+
+    let (epsg_code, wkt) = read_srs(&db, contents.srs_id)?;
+    if !wkt.is_empty() {
+        sf.projection = wkt;
+    } else if epsg_code != 0 {
+        if let Ok(srs) = SpatialRef::from_epsg(epsg_code) {
+            sf.projection = srs.to_wkt();
+        }
+    }
+    Ok(())
+}
+
+// chooses the attribute field type of a column from its declared type, and its width from the values
+fn infer_field<'a, I: Iterator<Item = &'a SqlValue>>(name: &str, declared_type: &str, values: I) -> AttributeField {
+    let (mut int_length, mut text_length, mut all_i32) = (1usize, 1usize, true);
+    for v in values {
+        match v {
+            &SqlValue::Integer(i) => {
+                int_length = int_length.max(format!("{}", i).len());
+                all_i32 = all_i32 && i >= i32::min_value() as i64 && i <= i32::max_value() as i64;
+            },
+            &SqlValue::Real(r) => int_length = int_length.max(format!("{}", r.trunc()).len()),
+            &SqlValue::Text(ref s) => text_length = text_length.max(s.len()),
+            _ => {},
+        }
+    }
+    if declared_type.starts_with("BOOL") {
+        AttributeField::new(name, 'L', 1u8, 0u8)
+    } else if declared_type.contains("INT") && all_i32 {
+        AttributeField::new(name, 'N', int_length.min(18) as u8, 0u8)
+    } else if declared_type.contains("INT") || declared_type.contains("REAL") || declared_type.contains("FLOA")
+        || declared_type.contains("DOUB") || declared_type.contains("NUM") {
+        AttributeField::new(name, 'F', (int_length + 9).min(254) as u8, 8u8)
+    } else if declared_type == "DATE" {
+        AttributeField::new(name, 'D', 8u8, 0u8)
+    } else {
+        AttributeField::new(name, 'C', text_length.min(254) as u8, 0u8)
+    }
+}
+
+fn to_field_data(field: &AttributeField, value: &SqlValue) -> FieldData {
+    if value.is_null() {
+        return FieldData::Null;
+    }
+    match field.field_type {
+        'N' => value.as_i64().map(|v| FieldData::Int(v as i32)).unwrap_or(FieldData::Null),
+        'F' => value.as_f64().map(FieldData::Real).unwrap_or(FieldData::Null),
+        'L' => value.as_i64().map(|v| FieldData::Bool(v != 0)).unwrap_or(FieldData::Null),
+        'D' => {
+            let s = value.as_str().unwrap_or("");
+            let parts: Vec<&str> = s.splitn(3, '-').collect();
+            if parts.len() == 3 {
+                if let (Ok(y), Ok(m), Ok(d)) = (parts[0].parse::<u16>(), parts[1].parse::<u8>(), parts[2][..parts[2].len().min(2)].parse::<u8>()) {
+                    return FieldData::Date(DateData { year: y, month: m, day: d });
+                }
+            }
+            FieldData::Null
+        },
+        _ => match value {
+            &SqlValue::Text(ref s) => FieldData::Text(s.clone()),
+            &SqlValue::Integer(v) => FieldData::Text(format!("{}", v)),
+            &SqlValue::Real(v) => FieldData::Text(format!("{}", v)),
+            _ => FieldData::Null,
+        },
+    }
+}
+
+// A cursor over WKB data, whose byte order may change between geometries.
+struct WkbReader<'a> {
+    buffer: &'a [u8],
+    pos: usize,
+    little_endian: bool,
+}
+
+impl<'a> WkbReader<'a> {
+    fn check(&self, length: usize) -> Result<(), Error> {
+        if self.pos + length > self.buffer.len() {
+            return Err(invalid("A GeoPackage geometry is truncated.".to_string()));
+        }
+        Ok(())
+    }
+
+    fn read_u8(&mut self) -> Result<u8, Error> {
+        self.check(1)?;
+        self.pos += 1;
+        Ok(self.buffer[self.pos - 1])
+    }
+
+    fn read_u32(&mut self) -> Result<u32, Error> {
+        self.check(4)?;
+        let b = &self.buffer[self.pos..self.pos + 4];
+        self.pos += 4;
+        Ok(if self.little_endian { LittleEndian::read_u32(b) } else { BigEndian::read_u32(b) })
+    }
+
+    fn read_f64(&mut self) -> Result<f64, Error> {
+        self.check(8)?;
+        let b = &self.buffer[self.pos..self.pos + 8];
+        self.pos += 8;
+        Ok(if self.little_endian { LittleEndian::read_f64(b) } else { BigEndian::read_f64(b) })
+    }
+}
+
+// reads a GeoPackage binary geometry, returning None for empty geometries
+fn read_geometry_blob(blob: &[u8]) -> Result<Option<Parts>, Error> {
+    if blob.len() < 8 || &blob[0..2] != b"GP" {
+        return Err(invalid("A GeoPackage geometry does not have a valid header.".to_string()));
+    }
+    let flags = blob[3];
+    if flags & 0b0010_0000 != 0 {
+        return Err(invalid("Extended GeoPackage geometry types are not supported.".to_string()));
+    }
+    if flags & 0b0001_0000 != 0 {
+        return Ok(None);
+    }
+    let envelope_size = match (flags >> 1) & 0b111 {
+        0 => 0,
+        1 => 32,
+        2 | 3 => 48,
+        4 => 64,
+        _ => return Err(invalid("A GeoPackage geometry has an invalid envelope.".to_string())),
+    };
+    let mut reader = WkbReader { buffer: blob, pos: 8 + envelope_size, little_endian: true };
+    let parts = read_wkb(&mut reader)?;
+    let is_empty = match parts {
+        Parts::Points(ref v, _) => v.len() == 0,
+        Parts::Lines(ref v) => v.iter().all(|l| l.len() == 0),
+        Parts::Polygons(ref v) => v.iter().all(|p| p.iter().all(|r| r.len() == 0)),
+    };
+    Ok(if is_empty { None } else { Some(parts) })
+}
+
+fn read_wkb(reader: &mut WkbReader) -> Result<Parts, Error> {
+    reader.little_endian = reader.read_u8()? == 1;
+    let code = reader.read_u32()?;
+    // ISO WKB adds 1000, 2000, or 3000 to the type for Z, M, and ZM geometries, while
+    // extended WKB sets the high bits
+    let (mut has_z, mut has_m) = (code & 0x8000_0000 != 0, code & 0x4000_0000 != 0);
+    if code & 0x2000_0000 != 0 {
+        reader.read_u32()?; // an embedded SRID
+    }
+    let code = code & 0x0FFF_FFFF;
+    match code / 1000 {
+        1 => has_z = true,
+        2 => has_m = true,
+        3 => { has_z = true; has_m = true; },
+        _ => {},
+    }
+    let read_position = |reader: &mut WkbReader| -> Result<Position, Error> {
+        let x = reader.read_f64()?;
+        let y = reader.read_f64()?;
+        let z = if has_z { Some(reader.read_f64()?) } else { None };
+        let m = if has_m { Some(reader.read_f64()?) } else { None };
+        Ok(Position::new(x, y, z, m.and_then(|m| if m.is_nan() { None } else { Some(m) })))
+    };
+    let read_positions = |reader: &mut WkbReader| -> Result<Vec<Position>, Error> {
+        let n = reader.read_u32()? as usize;
+        reader.check(n * 16)?;
+        let mut positions = Vec::with_capacity(n);
+        for _ in 0..n {
+            positions.push(read_position(reader)?);
+        }
+        Ok(positions)
+    };
+    let read_rings = |reader: &mut WkbReader| -> Result<Vec<Vec<Position>>, Error> {
+        let n = reader.read_u32()? as usize;
+        let mut rings = vec![];
+        for _ in 0..n {
+            rings.push(read_positions(reader)?);
+        }
+        Ok(rings)
+    };
+    match code % 1000 {
+        1 => {
+            let p = read_position(reader)?;
+            // an empty point has NaN coordinates
+            Ok(Parts::Points(if p.x.is_nan() || p.y.is_nan() { vec![] } else { vec![p] }, false))
+        },
+        2 => Ok(Parts::Lines(vec![read_positions(reader)?])),
+        3 => Ok(Parts::Polygons(vec![read_rings(reader)?])),
+        4...7 => {
+            let n = reader.read_u32()? as usize;
+            let mut merged: Option<Parts> = None;
+            for _ in 0..n {
+                let member = read_wkb(reader)?;
+                merged = Some(match (merged, member) {
+                    (None, Parts::Points(v, _)) => Parts::Points(v, true),
+                    (None, p) => p,
+                    (Some(Parts::Points(mut a, _)), Parts::Points(b, _)) => { a.extend(b); Parts::Points(a, true) },
+                    (Some(Parts::Lines(mut a)), Parts::Lines(b)) => { a.extend(b); Parts::Lines(a) },
+                    (Some(Parts::Polygons(mut a)), Parts::Polygons(b)) => { a.extend(b); Parts::Polygons(a) },
+                    _ => return Err(invalid("GeometryCollections containing more than one type of geometry are not supported.".to_string())),
+                });
+            }
+            Ok(merged.unwrap_or(match code % 1000 {
+                5 => Parts::Lines(vec![]),
+                6 => Parts::Polygons(vec![]),
+                _ => Parts::Points(vec![], true),
+            }))
+        },
+        t => Err(invalid(format!("Unsupported WKB geometry type: {}", t))),
+    }
+}
+
+/// Writes a Shapefile's records and attributes as a GeoPackage feature table. The records
+/// must already have been validated.
+pub fn write(sf: &Shapefile) -> Result<(), Error> {
+    let table_name = Path::new(&sf.file_name).file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or("features".to_string());
+    let dimension = sf.header.shape_type.dimension();
+    // The Z shape types have optional measures, which are written if any record has them.
+    let has_z = dimension == ShapeTypeDimension::Z;
+    let has_m = dimension == ShapeTypeDimension::Measure || (has_z && sf.records.iter().any(|r| r.m_array.len() > 0));
+    let parts: Vec<Option<Parts>> = sf.records.iter().map(|r| record_parts(r)).collect();
+    let is_single = parts.iter().all(|p| match p {
+        &Some(Parts::Lines(ref v)) => v.len() == 1,
+        &Some(Parts::Polygons(ref v)) => v.len() == 1,
+        _ => true,
+    });
+    let geometry_type = match (sf.header.shape_type.base_shape_type(), is_single) {
+        (ShapeType::Point, _) => "POINT",
+        (ShapeType::MultiPoint, _) => "MULTIPOINT",
+        (ShapeType::PolyLine, true) => "LINESTRING",
+        (ShapeType::PolyLine, false) => "MULTILINESTRING",
+        (ShapeType::Polygon, true) => "POLYGON",
+        (ShapeType::Polygon, false) => "MULTIPOLYGON",
+        _ => "GEOMETRY",
+    };
+
+    let epsg_code = SpatialRef::from_wkt(&sf.projection).map(|srs| srs.epsg_code).unwrap_or(0);
+    let srs_id = get_srs_id(epsg_code, &sf.projection);
+    let (fid_column, geometry_column) = column_names(&sf.attributes.fields);
+    let contents = GeoPackageContents {
+        table_name: table_name.clone(),
+        data_type: "features".to_string(),
+        min_x: if sf.records.len() > 0 { Some(sf.header.x_min) } else { None },
+        min_y: if sf.records.len() > 0 { Some(sf.header.y_min) } else { None },
+        max_x: if sf.records.len() > 0 { Some(sf.header.x_max) } else { None },
+        max_y: if sf.records.len() > 0 { Some(sf.header.y_max) } else { None },
+        srs_id: srs_id,
+    };
+    let mut writer = create_geopackage(&sf.file_name, &contents, epsg_code, &sf.projection, vec![])?;
+
+    writer.add_table("gpkg_geometry_columns", GEOMETRY_COLUMNS_SQL, vec![vec![
+        SqlValue::Text(table_name.clone()), SqlValue::Text(geometry_column.clone()),
+        SqlValue::Text(geometry_type.to_string()), SqlValue::Integer(srs_id),
+        SqlValue::Integer(if has_z { 1 } else { 0 }), SqlValue::Integer(if has_m { 1 } else { 0 })]])?;
+
+    let mut definitions = vec![
+        format!("{} INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL", quote(&fid_column)),
+        format!("{} {}", quote(&geometry_column), geometry_type),
+    ];
+    for field in &sf.attributes.fields {
+        let sql_type = match field.field_type {
+            'N' if field.decimal_count == 0 => "INTEGER",
+            'N' | 'F' => "DOUBLE",
+            'L' => "BOOLEAN",
+            'D' => "DATE",
+            _ => "TEXT",
+        };
+        definitions.push(format!("{} {}", quote(&field.name), sql_type));
+    }
+    let sql = format!("CREATE TABLE {} ({})", quote(&table_name), definitions.join(", "));
+
+    writer.begin_table(&table_name, &sql)?;
+    for (i, p) in parts.iter().enumerate() {
+        let mut row = vec![SqlValue::Integer(i as i64 + 1)];
+        row.push(match p {
+            &Some(ref p) => SqlValue::Blob(geometry_blob(p, &sf.records[i], srs_id, is_single, has_z, has_m)),
+            &None => SqlValue::Null,
+        });
+        if sf.attributes.get_num_records() > i {
+            for value in sf.attributes.get_record(i) {
+                row.push(match value {
+                    &FieldData::Int(v) => SqlValue::Integer(v as i64),
+                    &FieldData::Real(v) => if v.is_finite() { SqlValue::Real(v) } else { SqlValue::Null },
+                    &FieldData::Text(ref s) => SqlValue::Text(s.clone()),
+                    &FieldData::Date(ref d) => SqlValue::Text(format!("{:04}-{:02}-{:02}", d.year, d.month, d.day)),
+                    &FieldData::Bool(v) => SqlValue::Integer(if v { 1 } else { 0 }),
+                    &FieldData::Null => SqlValue::Null,
+                });
+            }
+        }
+        writer.add_row(row)?;
+    }
+    writer.end_table()?;
+    writer.finish()
+}
+
+const GEOMETRY_COLUMNS_SQL: &'static str = "CREATE TABLE gpkg_geometry_columns (
+  table_name TEXT NOT NULL,
+  column_name TEXT NOT NULL,
+  geometry_type_name TEXT NOT NULL,
+  srs_id INTEGER NOT NULL,
+  z TINYINT NOT NULL,
+  m TINYINT NOT NULL,
+  CONSTRAINT pk_geom_cols PRIMARY KEY (table_name, column_name),
+  CONSTRAINT uk_gc_table_name UNIQUE (table_name),
+  CONSTRAINT fk_gc_tn FOREIGN KEY (table_name) REFERENCES gpkg_contents(table_name),
+  CONSTRAINT fk_gc_srs FOREIGN KEY (srs_id) REFERENCES gpkg_spatial_ref_sys (srs_id))";
+
+fn quote(identifier: &str) -> String {
+    format!("\"{}\"", identifier.replace("\"", "\"\""))
+}
+
+// the names of the primary key and geometry columns, which must differ from the field names
+fn column_names(fields: &[AttributeField]) -> (String, String) {
+    let is_free = |name: &str| !fields.iter().any(|f| f.name.to_lowercase() == name);
+    let fid = ["fid", "gpkg_fid", "ogc_fid"].iter().find(|n| is_free(n)).unwrap_or(&"gpkg_fid_");
+    let geom = ["geom", "geometry", "gpkg_geom"].iter().find(|n| is_free(n)).unwrap_or(&"gpkg_geom_");
+    (fid.to_string(), geom.to_string())
+}
+
+// encodes a geometry as a little-endian GeoPackage binary geometry with an xy envelope
+fn geometry_blob(parts: &Parts, sfg: &ShapefileGeometry, srs_id: i64, is_single: bool, has_z: bool, has_m: bool) -> Vec<u8> {
+    let mut blob = vec![b'G', b'P', 0u8, 0b0000_0011];
+    let mut buf = [0u8; 8];
+    LittleEndian::write_i32(&mut buf, srs_id as i32);
+    blob.extend_from_slice(&buf[0..4]);
+    for v in &[sfg.x_min, sfg.x_max, sfg.y_min, sfg.y_max] {
+        LittleEndian::write_f64(&mut buf, *v);
+        blob.extend_from_slice(&buf);
+    }
+
+    let offset = match (has_z, has_m) {
+        (true, true) => 3000,
+        (true, false) => 1000,
+        (false, true) => 2000,
+        _ => 0,
+    };
+    let write_u32 = |blob: &mut Vec<u8>, v: u32| {
+        let mut buf = [0u8; 4];
+        LittleEndian::write_u32(&mut buf, v);
+        blob.extend_from_slice(&buf);
+    };
+    let write_header = |blob: &mut Vec<u8>, code: u32| {
+        blob.push(1u8); // little-endian
+        write_u32(blob, code + offset);
+    };
+    let write_positions = |blob: &mut Vec<u8>, positions: &[Position], with_count: bool| {
+        if with_count {
+            write_u32(blob, positions.len() as u32);
+        }
+        for p in positions {
+            let mut values = vec![p.x, p.y];
+            if has_z { values.push(p.z.unwrap_or(0f64)); }
+            if has_m { values.push(p.m.unwrap_or(f64::NAN)); }
+            for v in values {
+                let mut buf = [0u8; 8];
+                LittleEndian::write_f64(&mut buf, v);
+                blob.extend_from_slice(&buf);
+            }
+        }
+    };
+    let write_polygon = |blob: &mut Vec<u8>, rings: &[Vec<Position>]| {
+        write_header(blob, 3);
+        write_u32(blob, rings.len() as u32);
+        for ring in rings {
+            write_positions(blob, ring, true);
+        }
+    };
+    match parts {
+        &Parts::Points(ref v, false) => {
+            write_header(&mut blob, 1);
+            write_positions(&mut blob, &v[0..1], false);
+        },
+        &Parts::Points(ref v, true) => {
+            write_header(&mut blob, 4);
+            write_u32(&mut blob, v.len() as u32);
+            for p in v {
+                write_header(&mut blob, 1);
+                write_positions(&mut blob, &[*p], false);
+            }
+        },
+        &Parts::Lines(ref v) => {
+            if !is_single {
+                write_header(&mut blob, 5);
+                write_u32(&mut blob, v.len() as u32);
+            }
+            for line in v {
+                write_header(&mut blob, 2);
+                write_positions(&mut blob, line, true);
+            }
+        },
+        &Parts::Polygons(ref v) => {
+            if !is_single {
+                write_header(&mut blob, 6);
+                write_u32(&mut blob, v.len() as u32);
+            }
+            for polygon in v {
+                write_polygon(&mut blob, polygon);
+            }
+        },
+    }
+    blob
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::fs;
+    use io_utils::SqliteReader;
+    use vector::Point2D;
+    use super::*;
+
+    fn temp_file(name: &str) -> String {
+        env::temp_dir().join(name).to_str().unwrap().to_string()
+    }
+
+    fn points(coordinates: &[(f64, f64)]) -> Vec<Point2D> {
+        coordinates.iter().map(|&(x, y)| Point2D { x: x, y: y }).collect()
+    }
+
+    fn test_geometry(shape_type: ShapeType, offset: f64, num_parts: usize) -> ShapefileGeometry {
+        let mut sfg = ShapefileGeometry::new(shape_type.clone());
+        let parts: Vec<Vec<Point2D>> = match shape_type.base_shape_type() {
+            ShapeType::Point => vec![points(&[(offset, -offset)])],
+            ShapeType::MultiPoint => vec![points(&[(offset, 1.5), (2.5, offset), (-offset, 0.25)])],
+            ShapeType::PolyLine => vec![points(&[(offset, 0.0), (1.0, offset), (7.0, 3.0)]), points(&[(-offset, 2.0), (4.0, 4.0)])],
+            _ => vec![
+                // a clockwise exterior ring with a counter-clockwise hole, and a second polygon
+                points(&[(offset, 0.0), (offset, 4.0), (offset + 4.0, 4.0), (offset + 4.0, 0.0), (offset, 0.0)]),
+                points(&[(offset + 1.0, 1.0), (offset + 2.0, 1.0), (offset + 2.0, 2.0), (offset + 1.0, 2.0), (offset + 1.0, 1.0)]),
+                points(&[(offset + 10.0, 0.0), (offset + 10.0, 1.0), (offset + 11.0, 1.0), (offset + 11.0, 0.0), (offset + 10.0, 0.0)]),
+            ],
+        };
+        let num_parts = if shape_type.base_shape_type() == ShapeType::Polygon && num_parts == 1 { 2 } else { num_parts };
+        for part in parts.into_iter().take(num_parts) {
+            let z: Vec<f64> = (0..part.len()).map(|i| offset * 10.0 + i as f64).collect();
+            let m: Vec<f64> = (0..part.len()).map(|i| if i == 1 { M_NO_DATA } else { offset - i as f64 }).collect();
+            match (shape_type.base_shape_type(), shape_type.dimension()) {
+                (ShapeType::Point, ShapeTypeDimension::XY) | (ShapeType::MultiPoint, ShapeTypeDimension::XY) => {
+                    for p in part { sfg.add_point(p); }
+                },
+                (ShapeType::Point, ShapeTypeDimension::Z) | (ShapeType::MultiPoint, ShapeTypeDimension::Z) => {
+                    for i in 0..part.len() { sfg.add_pointz(part[i].clone(), z[i], m[i]); }
+                },
+                (ShapeType::Point, _) | (ShapeType::MultiPoint, _) => {
+                    for i in 0..part.len() { sfg.add_pointm(part[i].clone(), m[i]); }
+                },
+                (_, ShapeTypeDimension::XY) => sfg.add_part(&part),
+                (_, ShapeTypeDimension::Z) => sfg.add_partz(&part, &z, &m),
+                (_, ShapeTypeDimension::Measure) => sfg.add_partm(&part, &m),
+            }
+        }
+        sfg
+    }
+
+    fn geometry_type_name(file_name: &str) -> (String, i64, i64) {
+        let db = SqliteReader::new(file_name).unwrap();
+        let table = db.read_table("gpkg_geometry_columns").unwrap();
+        (table.get_value(0, "geometry_type_name").as_str().unwrap().to_string(),
+            table.get_value(0, "z").as_i64().unwrap(), table.get_value(0, "m").as_i64().unwrap())
+    }
+
+    #[test]
+    fn geopackage_round_trip_for_every_shape_type() {
+        let shape_types = [ShapeType::Point, ShapeType::PolyLine, ShapeType::Polygon, ShapeType::MultiPoint,
+            ShapeType::PointZ, ShapeType::PolyLineZ, ShapeType::PolygonZ, ShapeType::MultiPointZ,
+            ShapeType::PointM, ShapeType::PolyLineM, ShapeType::PolygonM, ShapeType::MultiPointM];
+        for shape_type in shape_types.iter() {
+            for &num_parts in [1usize, 3].iter() {
+                let file_name = temp_file(&format!("wbt_geopackage_{}_{}.gpkg", shape_type.to_int(), num_parts));
+                let mut sf = Shapefile::new(&file_name, "w").unwrap();
+                sf.header.shape_type = shape_type.clone();
+                sf.add_record(test_geometry(shape_type.clone(), 3.0, num_parts));
+                sf.add_record(ShapefileGeometry::new(ShapeType::Null));
+                sf.add_record(test_geometry(shape_type.clone(), -2.0, num_parts));
+                sf.write().unwrap();
+
+                let expected_type = match (shape_type.base_shape_type(), num_parts) {
+                    (ShapeType::Point, _) => "POINT",
+                    (ShapeType::MultiPoint, _) => "MULTIPOINT",
+                    (ShapeType::PolyLine, 1) => "LINESTRING",
+                    (ShapeType::PolyLine, _) => "MULTILINESTRING",
+                    (_, 1) => "POLYGON",
+                    _ => "MULTIPOLYGON",
+                };
+                let dimension = shape_type.dimension();
+                assert_eq!(geometry_type_name(&file_name), (expected_type.to_string(),
+                    if dimension == ShapeTypeDimension::Z { 1 } else { 0 },
+                    if dimension == ShapeTypeDimension::XY { 0 } else { 1 }));
+
+                let input = Shapefile::new(&file_name, "r").unwrap();
+                assert_eq!(input.header.shape_type, *shape_type);
+                assert_eq!(input.num_records, 3);
+                assert_eq!(input.get_record(1).shape_type, ShapeType::Null);
+                for &i in [0usize, 2].iter() {
+                    let (a, b) = (sf.get_record(i), input.get_record(i));
+                    assert_eq!(b.parts, a.parts);
+                    assert_eq!(b.points.len(), a.points.len());
+                    for j in 0..a.points.len() {
+                        assert_eq!((b.points[j].x, b.points[j].y), (a.points[j].x, a.points[j].y));
+                    }
+                    assert_eq!(b.z_array, a.z_array);
+                    assert_eq!(b.m_array.len(), a.m_array.len());
+                    for j in 0..a.m_array.len() {
+                        // 'no data' measures are written as NaN
+                        assert_eq!(b.m_array[j] < M_NO_DATA_THRESHOLD, a.m_array[j] < M_NO_DATA_THRESHOLD);
+                        if a.m_array[j] >= M_NO_DATA_THRESHOLD {
+                            assert_eq!(b.m_array[j], a.m_array[j]);
+                        }
+                    }
+                }
+                assert_eq!((input.header.x_min, input.header.x_max, input.header.y_min, input.header.y_max),
+                    (sf.header.x_min, sf.header.x_max, sf.header.y_min, sf.header.y_max));
+                let _ = fs::remove_file(&file_name);
+            }
+        }
+    }
+
+    #[test]
+    fn attributes_are_written_as_columns() {
+        let file_name = temp_file("wbt_geopackage_attributes.gpkg");
+        let mut sf = Shapefile::new(&file_name, "w").unwrap();
+        sf.header.shape_type = ShapeType::Point;
+        // fields that share the names of the usual primary key and geometry columns
+        sf.attributes.add_field(AttributeField::new("FID", 'N', 6u8, 0u8));
+        sf.attributes.add_field(AttributeField::new("geom", 'F', 12u8, 3u8));
+        sf.attributes.add_field(AttributeField::new("NAME", 'C', 20u8, 0u8));
+        sf.attributes.add_field(AttributeField::new("FLAG", 'L', 1u8, 0u8));
+        sf.attributes.add_field(AttributeField::new("DATE", 'D', 8u8, 0u8));
+        let records = vec![
+            vec![FieldData::Int(1), FieldData::Real(2.5), FieldData::Text("Lac Ste-Thérèse".to_string()),
+                 FieldData::Bool(true), FieldData::Date(DateData { year: 2018, month: 4, day: 6 })],
+            vec![FieldData::Int(-20), FieldData::Real(f64::NAN), FieldData::Null, FieldData::Bool(false), FieldData::Null],
+        ];
+        for (i, record) in records.into_iter().enumerate() {
+            sf.add_record(test_geometry(ShapeType::Point, i as f64, 1));
+            sf.attributes.add_record(record, false).unwrap();
+        }
+        sf.write().unwrap();
+
+        let db = SqliteReader::new(&file_name).unwrap();
+        let table = db.read_table("wbt_geopackage_attributes").unwrap();
+        let columns: Vec<(&str, &str)> = table.columns.iter().map(|c| (c.name.as_ref(), c.declared_type.as_ref())).collect();
+        assert_eq!(columns, vec![("gpkg_fid", "INTEGER"), ("geometry", "POINT"), ("FID", "INTEGER"), ("geom", "DOUBLE"),
+            ("NAME", "TEXT"), ("FLAG", "BOOLEAN"), ("DATE", "DATE")]);
+        assert_eq!(table.get_value(1, "gpkg_fid"), SqlValue::Integer(2));
+        assert_eq!(table.get_value(0, "DATE"), SqlValue::Text("2018-04-06".to_string()));
+
+        let input = Shapefile::new(&file_name, "r").unwrap();
+        let fields: Vec<(&str, char)> = input.attributes.fields.iter().map(|f| (f.name.as_ref(), f.field_type)).collect();
+        assert_eq!(fields, vec![("FID", 'N'), ("geom", 'F'), ("NAME", 'C'), ("FLAG", 'L'), ("DATE", 'D')]);
+        assert_eq!(input.attributes.get_record(0), sf.attributes.get_record(0));
+        assert_eq!(input.attributes.get_value(1, "FID"), FieldData::Int(-20));
+        assert_eq!(input.attributes.get_value(1, "geom"), FieldData::Null);
+        assert_eq!(input.attributes.get_value(1, "NAME"), FieldData::Null);
+        assert_eq!(input.attributes.get_value(1, "FLAG"), FieldData::Bool(false));
+        let _ = fs::remove_file(&file_name);
+    }
+
+    #[test]
+    fn spatial_reference_systems_are_written() {
+        let file_name = temp_file("wbt_geopackage_srs.gpkg");
+        let utm = SpatialRef::from_epsg(26917).unwrap();
+        let mut custom = utm.clone();
+        custom.epsg_code = 0;
+        custom.name = "Custom TM".to_string();
+        for &(ref wkt, srs_id) in [(utm.to_wkt(), 26917i64), (custom.to_wkt(), 100000), (String::new(), -1)].iter() {
+            let mut sf = Shapefile::new(&file_name, "w").unwrap();
+            sf.header.shape_type = ShapeType::Point;
+            sf.add_record(test_geometry(ShapeType::Point, 1.0, 1));
+            sf.projection = wkt.clone();
+            sf.write().unwrap();
+
+            let (_, contents) = open_geopackage(&file_name, "features").unwrap();
+            assert_eq!(contents.srs_id, srs_id);
+            assert_eq!(contents.table_name, "wbt_geopackage_srs");
+            assert_eq!(Shapefile::new(&file_name, "r").unwrap().projection, *wkt);
+        }
+        let _ = fs::remove_file(&file_name);
+    }
+
+    fn wkb_header(blob: &mut Vec<u8>, little_endian: bool, code: u32) {
+        blob.push(if little_endian { 1 } else { 0 });
+        let mut buf = [0u8; 4];
+        if little_endian { LittleEndian::write_u32(&mut buf, code) } else { BigEndian::write_u32(&mut buf, code) }
+        blob.extend_from_slice(&buf);
+    }
+
+    fn wkb_values(blob: &mut Vec<u8>, little_endian: bool, values: &[f64]) {
+        for &v in values {
+            let mut buf = [0u8; 8];
+            if little_endian { LittleEndian::write_f64(&mut buf, v) } else { BigEndian::write_f64(&mut buf, v) }
+            blob.extend_from_slice(&buf);
+        }
+    }
+
+    #[test]
+    fn foreign_geometry_encodings_are_read() {
+        // a big-endian extended-WKB LineStringZ within a blob that has an xyz envelope
+        let mut blob = vec![b'G', b'P', 0u8, 0b0000_0100, 0, 0, 0, 0];
+        wkb_values(&mut blob, true, &[0.0, 1.0, 0.0, 1.0, 5.0, 6.0]);
+        wkb_header(&mut blob, false, 0x8000_0002);
+        blob.extend_from_slice(&[0, 0, 0, 2]);
+        wkb_values(&mut blob, false, &[0.0, 0.0, 5.0, 1.0, 1.0, 6.0]);
+        match read_geometry_blob(&blob).unwrap() {
+            Some(Parts::Lines(lines)) => {
+                assert_eq!(lines.len(), 1);
+                let p = lines[0][1];
+                assert_eq!((p.x, p.y, p.z, p.m), (1.0, 1.0, Some(6.0), None));
+            },
+            _ => panic!("A LineStringZ was expected."),
+        }
+
+        // an ISO WKB GeometryCollection of a Point and a MultiPoint with measures, one of them NaN
+        let mut blob = vec![b'G', b'P', 0u8, 0b0000_0001, 0, 0, 0, 0];
+        wkb_header(&mut blob, true, 2007);
+        blob.extend_from_slice(&[2, 0, 0, 0]);
+        wkb_header(&mut blob, true, 2001);
+        wkb_values(&mut blob, true, &[1.0, 2.0, 3.0]);
+        wkb_header(&mut blob, false, 2004);
+        blob.extend_from_slice(&[0, 0, 0, 1]);
+        wkb_header(&mut blob, true, 2001);
+        wkb_values(&mut blob, true, &[4.0, 5.0, f64::NAN]);
+        match read_geometry_blob(&blob).unwrap() {
+            Some(Parts::Points(v, true)) => {
+                let positions: Vec<(f64, f64, Option<f64>, Option<f64>)> = v.iter().map(|p| (p.x, p.y, p.z, p.m)).collect();
+                assert_eq!(positions, vec![(1.0, 2.0, None, Some(3.0)), (4.0, 5.0, None, None)]);
+            },
+            _ => panic!("A MultiPointM was expected."),
+        }
+
+        // empty geometries, flagged or with NaN coordinates
+        assert!(read_geometry_blob(&[b'G', b'P', 0u8, 0b0001_0001, 0, 0, 0, 0]).unwrap().is_none());
+        let mut blob = vec![b'G', b'P', 0u8, 0b0000_0001, 0, 0, 0, 0];
+        wkb_header(&mut blob, true, 1);
+        wkb_values(&mut blob, true, &[f64::NAN, f64::NAN]);
+        assert!(read_geometry_blob(&blob).unwrap().is_none());
+    }
+
+    #[test]
+    fn invalid_geometries_are_rejected() {
+        let mut truncated = vec![b'G', b'P', 0u8, 0b0000_0001, 0, 0, 0, 0];
+        wkb_header(&mut truncated, true, 2);
+        truncated.extend_from_slice(&[100, 0, 0, 0]);
+        wkb_values(&mut truncated, true, &[0.0, 0.0]);
+        let mut mixed = vec![b'G', b'P', 0u8, 0b0000_0001, 0, 0, 0, 0];
+        wkb_header(&mut mixed, true, 7);
+        mixed.extend_from_slice(&[2, 0, 0, 0]);
+        wkb_header(&mut mixed, true, 1);
+        wkb_values(&mut mixed, true, &[0.0, 0.0]);
+        wkb_header(&mut mixed, true, 2);
+        mixed.extend_from_slice(&[1, 0, 0, 0]);
+        wkb_values(&mut mixed, true, &[0.0, 0.0]);
+        let mut curve = vec![b'G', b'P', 0u8, 0b0000_0001, 0, 0, 0, 0];
+        wkb_header(&mut curve, true, 8);
+        let blobs = vec![
+            b"XX\0\x01\0\0\0\0".to_vec(),
+            vec![b'G', b'P', 0u8, 0b0010_0001, 0, 0, 0, 0],
+            vec![b'G', b'P', 0u8, 0b0000_1011, 0, 0, 0, 0],
+            truncated, mixed, curve,
+        ];
+        for blob in blobs {
+            assert_eq!(read_geometry_blob(&blob).err().unwrap().kind(), ErrorKind::InvalidData);
+        }
+    }
+}
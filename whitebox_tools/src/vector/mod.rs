@@ -2,23 +2,32 @@
 This code is part of the WhiteboxTools geospatial analysis library.
 Authors: Dr. John Lindsay
 Created: June 21, 2017
-Last Modified: July 17, 2017
+Last Modified: March 31, 2018
 License: MIT
 */
 
 /* 
-Eventually this will be used to support multiple vector formats but
-for now it's just Shapefiles.
+Vector data are held in the Shapefile data model. ESRI Shapefiles, GeoJSON files,
+and GeoPackage feature tables are supported, and the format is selected by the file
+extension.
 */
 
 use std::fmt;
 
 // private sub-module defined in other files
+mod geojson;
+mod geometry;
+mod geopackage;
 mod shapefile;
 
 // exports identifiers from private sub-modules in the current module namespace
+pub use self::geojson::{is_geojson_file, read_geojson_crs};
 pub use self::shapefile::Shapefile;
 pub use self::shapefile::ShapeType;
+pub use self::shapefile::ShapeTypeDimension;
+pub use self::shapefile::ShapefileGeometry;
+pub use self::shapefile::{M_NO_DATA, M_NO_DATA_THRESHOLD};
+pub use self::shapefile::{AttributeTable, AttributeHeader, AttributeField, AttributeEncoding, FieldData, DateData};
 
 #[derive(Default, Clone, Debug)]
 pub struct Point2D {
@@ -0,0 +1,625 @@
+/*
+This code is part of the WhiteboxTools geospatial analysis library.
+Authors: Dr. John Lindsay
+Created: March 18, 2018
+Last Modified: April 6, 2018
+License: MIT
+*/
+extern crate time;
+
+use std::io::prelude::*;
+use std::io::{Error, ErrorKind};
+use std::io::BufWriter;
+use std::fs;
+use std::fs::File;
+use std::fmt;
+use std::path::Path;
+use byteorder::{LittleEndian, WriteBytesExt};
+use io_utils::{ByteOrderReader, Endianness};
+
+/// The dBase (.dbf) table holding the attributes of a Shapefile's records.
+#[derive(Default, Clone)]
+pub struct AttributeTable {
+    pub header: AttributeHeader,
+    pub fields: Vec<AttributeField>,
+    pub encoding: AttributeEncoding,
+    records: Vec<Vec<FieldData>>,
+    deleted: Vec<bool>,
+}
+
+impl AttributeTable {
+    /// Reads a .dbf file. The text encoding is taken from the .cpg sidecar file, if
+    /// there is one, and otherwise from the language driver ID in the header.
+    pub fn read<'a>(file_name: &'a str) -> Result<AttributeTable, Error> {
+        let mut f = File::open(file_name)?;
+        let metadata = fs::metadata(file_name)?;
+        let file_size: usize = metadata.len() as usize;
+        let mut buffer = vec![0; file_size];
+        f.read_exact(&mut buffer)?;
+        if file_size < 32 {
+            return Err(Error::new(ErrorKind::InvalidData, "The .dbf file is too short to contain a header."));
+        }
+
+        let mut table = AttributeTable::default();
+        let mut bor = ByteOrderReader::new(buffer, Endianness::LittleEndian);
+        table.header.version = bor.read_u8();
+        table.header.year = 1900u16 + bor.read_u8() as u16;
+        table.header.month = bor.read_u8();
+        table.header.day = bor.read_u8();
+        table.header.num_records = bor.read_u32();
+        table.header.header_length = bor.read_u16();
+        table.header.record_length = bor.read_u16();
+        bor.seek(29);
+        table.header.language_driver = bor.read_u8();
+
+        table.encoding = AttributeEncoding::from_language_driver(table.header.language_driver);
+        let cpg_file = Path::new(file_name).with_extension("cpg");
+        if cpg_file.exists() {
+            let mut f = File::open(cpg_file)?;
+            let mut s = String::new();
+            f.read_to_string(&mut s)?;
+            if let Some(encoding) = AttributeEncoding::from_code_page(&s) {
+                table.encoding = encoding;
+            }
+        }
+
+        // field descriptors, terminated by 0x0D
+        bor.seek(32);
+        while bor.pos + 32 <= file_size && bor.buffer[bor.pos] != 0x0D {
+            let name_bytes = bor.buffer[bor.pos..bor.pos + 11].to_vec();
+            let name_end = name_bytes.iter().position(|&b| b == 0).unwrap_or(11);
+            let name = table.encoding.decode(&name_bytes[0..name_end]).trim().to_string();
+            bor.pos += 11;
+            let field_type = bor.read_u8() as char;
+            bor.pos += 4;
+            let field_length = bor.read_u8();
+            let decimal_count = bor.read_u8();
+            bor.pos += 14;
+            table.fields.push(AttributeField::new(&name, field_type, field_length, decimal_count));
+        }
+
+        let record_length = table.header.record_length as usize;
+        let mut pos = table.header.header_length as usize;
+        for _ in 0..table.header.num_records {
+            if pos + record_length > file_size {
+                return Err(Error::new(ErrorKind::InvalidData, "The .dbf file contains a truncated record."));
+            }
+            table.deleted.push(bor.buffer[pos] == 0x2A);
+            let mut p = pos + 1;
+            let mut rec = Vec::with_capacity(table.fields.len());
+            for field in &table.fields {
+                let len = field.field_length as usize;
+                let s = table.encoding.decode(&bor.buffer[p..p + len]);
+                rec.push(field.parse_value(&s));
+                p += len;
+            }
+            table.records.push(rec);
+            pos += record_length;
+        }
+
+        Ok(table)
+    }
+
+    /// Writes the table to a .dbf file, along with a .cpg file naming its encoding.
+    pub fn write<'a>(&mut self, file_name: &'a str) -> Result<(), Error> {
+        let now = time::now();
+        self.header.version = 0x03;
+        self.header.year = (now.tm_year + 1900) as u16;
+        self.header.month = (now.tm_mon + 1) as u8;
+        self.header.day = now.tm_mday as u8;
+        self.header.num_records = self.records.len() as u32;
+        // both lengths are stored as 16-bit values, which limits the number and size of the fields
+        let header_length = 32usize + 32usize * self.fields.len() + 1usize;
+        let record_length = 1usize + self.fields.iter().map(|f| f.field_length as usize).sum::<usize>();
+        if header_length > u16::MAX as usize {
+            return Err(Error::new(ErrorKind::InvalidInput,
+                format!("The attribute table has {} fields, more than the {} that a .dbf file can hold.", self.fields.len(), (u16::MAX as usize - 33) / 32)));
+        }
+        if record_length > u16::MAX as usize {
+            return Err(Error::new(ErrorKind::InvalidInput,
+                format!("The attribute table's records are {} bytes long, more than the {} bytes that a .dbf file can hold.", record_length, u16::MAX)));
+        }
+        self.header.header_length = header_length as u16;
+        self.header.record_length = record_length as u16;
+        self.header.language_driver = self.encoding.language_driver();
+
+        let f = File::create(file_name)?;
+        let mut writer = BufWriter::new(f);
+        writer.write_u8(self.header.version)?;
+        writer.write_u8((self.header.year - 1900) as u8)?;
+        writer.write_u8(self.header.month)?;
+        writer.write_u8(self.header.day)?;
+        writer.write_u32::<LittleEndian>(self.header.num_records)?;
+        writer.write_u16::<LittleEndian>(self.header.header_length)?;
+        writer.write_u16::<LittleEndian>(self.header.record_length)?;
+        writer.write_all(&[0u8; 17])?;
+        writer.write_u8(self.header.language_driver)?;
+        writer.write_all(&[0u8; 2])?;
+
+        for field in &self.fields {
+            let mut name = self.encoding.encode(&field.name, 10);
+            name.resize(11, 0u8);
+            writer.write_all(&name)?;
+            writer.write_u8(field.field_type as u8)?;
+            writer.write_all(&[0u8; 4])?;
+            writer.write_u8(field.field_length)?;
+            writer.write_u8(field.decimal_count)?;
+            writer.write_all(&[0u8; 14])?;
+        }
+        writer.write_u8(0x0D)?;
+
+        for i in 0..self.records.len() {
+            writer.write_u8(if self.deleted[i] { 0x2A } else { 0x20 })?;
+            for j in 0..self.fields.len() {
+                let bytes = self.fields[j].format_value(&self.records[i][j], &self.encoding);
+                writer.write_all(&bytes)?;
+            }
+        }
+        writer.write_u8(0x1A)?;
+        writer.flush()?;
+
+        let f = File::create(Path::new(file_name).with_extension("cpg"))?;
+        let mut writer = BufWriter::new(f);
+        writer.write_all(self.encoding.code_page().as_bytes())?;
+        writer.flush()?;
+
+        Ok(())
+    }
+
+    pub fn get_num_fields(&self) -> usize {
+        self.fields.len()
+    }
+
+    pub fn get_num_records(&self) -> usize {
+        self.records.len()
+    }
+
+    /// Returns the index of the named field. Field names are not case sensitive.
+    pub fn get_field_num<'a>(&self, name: &'a str) -> Option<usize> {
+        let name = name.to_lowercase();
+        self.fields.iter().position(|f| f.name.to_lowercase() == name)
+    }
+
+    /// Adds a field to the table. Any existing records are given a Null value for the field.
+    pub fn add_field(&mut self, field: AttributeField) {
+        self.fields.push(field);
+        for rec in &mut self.records {
+            rec.push(FieldData::Null);
+        }
+    }
+
+    /// Adds a record to the table. The record must contain one value for each field.
+    pub fn add_record(&mut self, record: Vec<FieldData>, deleted: bool) -> Result<(), Error> {
+        if record.len() != self.fields.len() {
+            return Err(Error::new(ErrorKind::InvalidInput,
+                format!("The record contains {} values but the table has {} fields.", record.len(), self.fields.len())));
+        }
+        self.records.push(record);
+        self.deleted.push(deleted);
+        Ok(())
+    }
+
+    pub fn get_record<'a>(&'a self, index: usize) -> &'a Vec<FieldData> {
+        if index >= self.records.len() { panic!("Record index out of bounds"); }
+        &self.records[index]
+    }
+
+    pub fn is_deleted(&self, index: usize) -> bool {
+        self.deleted[index]
+    }
+
+    /// Returns the value of the named field for a record, or FieldData::Null if
+    /// the table has no such field.
+    pub fn get_value<'a>(&self, record_index: usize, field_name: &'a str) -> FieldData {
+        match self.get_field_num(field_name) {
+            Some(j) => self.get_record(record_index)[j].clone(),
+            None => FieldData::Null,
+        }
+    }
+
+    pub fn set_value<'a>(&mut self, record_index: usize, field_name: &'a str, value: FieldData) -> Result<(), Error> {
+        match self.get_field_num(field_name) {
+            Some(j) => {
+                if record_index >= self.records.len() { panic!("Record index out of bounds"); }
+                self.records[record_index][j] = value;
+                Ok(())
+            },
+            None => Err(Error::new(ErrorKind::InvalidInput, format!("There is no field named {} in the attribute table.", field_name))),
+        }
+    }
+}
+
+#[derive(Default, Clone, Debug)]
+pub struct AttributeHeader {
+    pub version: u8,
+    pub year: u16,
+    pub month: u8,
+    pub day: u8,
+    pub num_records: u32,
+    pub header_length: u16,
+    pub record_length: u16,
+    pub language_driver: u8,
+}
+
+/// A field (column) in an attribute table. The field type is one of the dBase
+/// types 'C' (character), 'N' (numeric), 'F' (float), 'L' (logical) or 'D' (date).
+#[derive(Default, Clone, Debug)]
+pub struct AttributeField {
+    pub name: String,
+    pub field_type: char,
+    pub field_length: u8,
+    pub decimal_count: u8,
+}
+
+impl AttributeField {
+    pub fn new<'a>(name: &'a str, field_type: char, field_length: u8, decimal_count: u8) -> AttributeField {
+        let field_length = match field_type {
+            'L' => 1u8,
+            'D' => 8u8,
+            _ => field_length,
+        };
+        AttributeField {
+            name: name.to_string(),
+            field_type: field_type,
+            field_length: field_length,
+            decimal_count: decimal_count,
+        }
+    }
+
+    fn parse_value<'a>(&self, s: &'a str) -> FieldData {
+        match self.field_type {
+            'N' | 'F' => {
+                let s = s.trim();
+                if s.is_empty() || s.starts_with("*") {
+                    FieldData::Null
+                } else if self.decimal_count == 0 && !s.contains(".") && !s.contains("e") && !s.contains("E") {
+                    match s.parse::<i32>() {
+                        Ok(v) => FieldData::Int(v),
+                        Err(_) => match s.parse::<f64>() {
+                            Ok(v) => FieldData::Real(v),
+                            Err(_) => FieldData::Null,
+                        },
+                    }
+                } else {
+                    match s.parse::<f64>() {
+                        Ok(v) => FieldData::Real(v),
+                        Err(_) => FieldData::Null,
+                    }
+                }
+            },
+            'L' => {
+                match s.trim() {
+                    "T" | "t" | "Y" | "y" => FieldData::Bool(true),
+                    "F" | "f" | "N" | "n" => FieldData::Bool(false),
+                    _ => FieldData::Null,
+                }
+            },
+            'D' => {
+                let s = s.trim();
+                if s.len() == 8 && s.chars().all(|c| c.is_digit(10)) {
+                    FieldData::Date(DateData {
+                        year: s[0..4].parse::<u16>().unwrap(),
+                        month: s[4..6].parse::<u8>().unwrap(),
+                        day: s[6..8].parse::<u8>().unwrap(),
+                    })
+                } else {
+                    FieldData::Null
+                }
+            },
+            _ => {
+                let s = s.trim_right_matches(|c| c == ' ' || c == '\0');
+                if s.is_empty() {
+                    FieldData::Null
+                } else {
+                    FieldData::Text(s.to_string())
+                }
+            },
+        }
+    }
+
+    fn format_value(&self, value: &FieldData, encoding: &AttributeEncoding) -> Vec<u8> {
+        let len = self.field_length as usize;
+        let s = match self.field_type {
+            'N' | 'F' => {
+                let s = match value {
+                    &FieldData::Int(v) => format!("{}", v),
+                    &FieldData::Real(v) => {
+                        let mut s = format!("{:.*}", self.decimal_count as usize, v);
+                        // drop decimal places, rather than the value, if it is too wide
+                        let mut d = self.decimal_count as usize;
+                        while s.len() > len && d > 0 {
+                            d -= 1;
+                            s = format!("{:.*}", d, v);
+                        }
+                        s
+                    },
+                    &FieldData::Bool(v) => if v { "1".to_string() } else { "0".to_string() },
+                    &FieldData::Text(ref v) => v.trim().to_string(),
+                    _ => String::new(),
+                };
+                if s.len() > len {
+                    "*".repeat(len)
+                } else {
+                    format!("{:>1$}", s, len)
+                }
+            },
+            'L' => {
+                match value {
+                    &FieldData::Bool(true) => "T".to_string(),
+                    &FieldData::Bool(false) => "F".to_string(),
+                    _ => "?".to_string(),
+                }
+            },
+            'D' => {
+                match value {
+                    &FieldData::Date(ref d) => format!("{:04}{:02}{:02}", d.year, d.month, d.day),
+                    _ => " ".repeat(8),
+                }
+            },
+            _ => {
+                match value {
+                    &FieldData::Null => String::new(),
+                    v => format!("{}", v),
+                }
+            },
+        };
+        let mut bytes = encoding.encode(&s, len);
+        bytes.resize(len, b' ');
+        bytes
+    }
+}
+
+/// The value of a field in an attribute table record.
+#[derive(Clone, Debug, PartialEq)]
+pub enum FieldData {
+    Int(i32),
+    Real(f64),
+    Text(String),
+    Date(DateData),
+    Bool(bool),
+    Null,
+}
+
+impl FieldData {
+    /// Returns numeric values as f64, and None for Null and non-numeric values.
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            &FieldData::Int(v) => Some(v as f64),
+            &FieldData::Real(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    pub fn is_null(&self) -> bool {
+        *self == FieldData::Null
+    }
+}
+
+impl fmt::Display for FieldData {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            &FieldData::Int(v) => write!(f, "{}", v),
+            &FieldData::Real(v) => write!(f, "{}", v),
+            &FieldData::Text(ref v) => write!(f, "{}", v),
+            &FieldData::Date(ref v) => write!(f, "{:04}-{:02}-{:02}", v.year, v.month, v.day),
+            &FieldData::Bool(v) => write!(f, "{}", v),
+            &FieldData::Null => write!(f, "null"),
+        }
+    }
+}
+
+#[derive(Default, Clone, Debug, PartialEq)]
+pub struct DateData {
+    pub year: u16,
+    pub month: u8,
+    pub day: u8,
+}
+
+/// The character encoding of the text in an attribute table.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum AttributeEncoding {
+    Utf8,
+    Windows1252,
+}
+
+// The characters of Windows-1252 in the range 0x80-0x9F. The five undefined
+// code points map onto the corresponding control characters.
+const WINDOWS_1252_HIGH: [char; 32] = [
+    '\u{20AC}', '\u{81}', '\u{201A}', '\u{192}', '\u{201E}', '\u{2026}', '\u{2020}', '\u{2021}',
+    '\u{2C6}', '\u{2030}', '\u{160}', '\u{2039}', '\u{152}', '\u{8D}', '\u{17D}', '\u{8F}',
+    '\u{90}', '\u{2018}', '\u{2019}', '\u{201C}', '\u{201D}', '\u{2022}', '\u{2013}', '\u{2014}',
+    '\u{2DC}', '\u{2122}', '\u{161}', '\u{203A}', '\u{153}', '\u{9D}', '\u{17E}', '\u{178}',
+];
+
+impl AttributeEncoding {
+    /// Interprets the contents of a .cpg file.
+    pub fn from_code_page<'a>(code_page: &'a str) -> Option<AttributeEncoding> {
+        let cp = code_page.trim().to_uppercase().replace("-", "").replace("_", "").replace(" ", "");
+        if cp == "UTF8" || cp == "65001" {
+            Some(AttributeEncoding::Utf8)
+        } else if cp.contains("1252") || cp == "ISO88591" || cp == "LATIN1" || cp == "88591" {
+            Some(AttributeEncoding::Windows1252)
+        } else {
+            None
+        }
+    }
+
+    /// Interprets the language driver ID stored in byte 29 of a .dbf header.
+    pub fn from_language_driver(ldid: u8) -> AttributeEncoding {
+        match ldid {
+            0x03 | 0x57 | 0x58 | 0x59 => AttributeEncoding::Windows1252,
+            _ => AttributeEncoding::Utf8,
+        }
+    }
+
+    fn code_page(&self) -> String {
+        match self {
+            &AttributeEncoding::Utf8 => "UTF-8".to_string(),
+            &AttributeEncoding::Windows1252 => "1252".to_string(),
+        }
+    }
+
+    fn language_driver(&self) -> u8 {
+        match self {
+            &AttributeEncoding::Utf8 => 0x00,
+            &AttributeEncoding::Windows1252 => 0x57,
+        }
+    }
+
+    fn decode(&self, bytes: &[u8]) -> String {
+        match self {
+            &AttributeEncoding::Utf8 => {
+                match String::from_utf8(bytes.to_vec()) {
+                    Ok(s) => s,
+                    // many older files without a .cpg are in a Windows code page
+                    Err(_) => AttributeEncoding::Windows1252.decode(bytes),
+                }
+            },
+            &AttributeEncoding::Windows1252 => {
+                bytes.iter().map(|&b| {
+                    if b >= 0x80 && b < 0xA0 {
+                        WINDOWS_1252_HIGH[(b - 0x80) as usize]
+                    } else {
+                        b as char
+                    }
+                }).collect()
+            },
+        }
+    }
+
+    // encodes a string, truncating it to at most max_bytes without splitting a character
+    fn encode(&self, s: &str, max_bytes: usize) -> Vec<u8> {
+        let mut bytes = vec![];
+        for c in s.chars() {
+            let mut buf = [0u8; 4];
+            let encoded: &[u8] = match self {
+                &AttributeEncoding::Utf8 => c.encode_utf8(&mut buf).as_bytes(),
+                &AttributeEncoding::Windows1252 => {
+                    let code = c as u32;
+                    buf[0] = if code < 0x80 || (code >= 0xA0 && code <= 0xFF) {
+                        code as u8
+                    } else {
+                        match WINDOWS_1252_HIGH.iter().position(|&h| h == c) {
+                            Some(i) => 0x80 + i as u8,
+                            None => b'?',
+                        }
+                    };
+                    &buf[0..1]
+                },
+            };
+            if bytes.len() + encoded.len() > max_bytes {
+                break;
+            }
+            bytes.extend_from_slice(encoded);
+        }
+        bytes
+    }
+}
+
+impl Default for AttributeEncoding {
+    fn default() -> AttributeEncoding { AttributeEncoding::Utf8 }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+
+    fn temp_file(name: &str) -> String {
+        env::temp_dir().join(name).to_str().unwrap().to_string()
+    }
+
+    fn test_table(encoding: AttributeEncoding) -> AttributeTable {
+        let mut table = AttributeTable::default();
+        table.encoding = encoding;
+        table.add_field(AttributeField::new("ID", 'N', 11u8, 0u8));
+        table.add_field(AttributeField::new("ELEV", 'N', 12u8, 3u8));
+        table.add_field(AttributeField::new("AREA", 'F', 19u8, 6u8));
+        table.add_field(AttributeField::new("NAME", 'C', 24u8, 0u8));
+        table.add_field(AttributeField::new("VISITED", 'L', 0u8, 0u8));
+        table.add_field(AttributeField::new("SURVEYED", 'D', 0u8, 0u8));
+        table.add_record(vec![FieldData::Int(1), FieldData::Real(101.125), FieldData::Real(-2.5e-3),
+            FieldData::Text("Lac Témiscamingue".to_string()), FieldData::Bool(true),
+            FieldData::Date(DateData { year: 2017, month: 6, day: 21 })], false).unwrap();
+        table.add_record(vec![FieldData::Int(-2147483648), FieldData::Null, FieldData::Real(1234567.5),
+            FieldData::Text("“quoted” – €".to_string()), FieldData::Bool(false), FieldData::Null], true).unwrap();
+        table.add_record(vec![FieldData::Null, FieldData::Real(-0.5), FieldData::Null, FieldData::Null,
+            FieldData::Null, FieldData::Date(DateData { year: 1999, month: 12, day: 31 })], false).unwrap();
+        table
+    }
+
+    #[test]
+    fn dbf_round_trip_for_every_field_type_and_encoding() {
+        for &encoding in [AttributeEncoding::Utf8, AttributeEncoding::Windows1252].iter() {
+            let file_name = temp_file(&format!("wbt_attributes_{:?}.dbf", encoding));
+            let mut table = test_table(encoding);
+            table.write(&file_name).unwrap();
+
+            let input = AttributeTable::read(&file_name).unwrap();
+            assert_eq!(input.encoding, encoding);
+            assert_eq!(input.get_num_fields(), 6);
+            assert_eq!(input.get_num_records(), 3);
+            for j in 0..6 {
+                assert_eq!(input.fields[j].name, table.fields[j].name);
+                assert_eq!(input.fields[j].field_type, table.fields[j].field_type);
+                assert_eq!(input.fields[j].field_length, table.fields[j].field_length);
+                assert_eq!(input.fields[j].decimal_count, table.fields[j].decimal_count);
+            }
+            for i in 0..3 {
+                assert_eq!(input.get_record(i), table.get_record(i), "record {}", i);
+                assert_eq!(input.is_deleted(i), i == 1);
+            }
+            assert_eq!(input.get_value(0, "elev"), FieldData::Real(101.125));
+            assert_eq!(input.get_value(0, "missing"), FieldData::Null);
+            let _ = fs::remove_file(&file_name);
+            let _ = fs::remove_file(Path::new(&file_name).with_extension("cpg"));
+        }
+    }
+
+    #[test]
+    fn values_are_fitted_to_their_fields() {
+        let encoding = AttributeEncoding::Utf8;
+        let field = AttributeField::new("V", 'N', 6u8, 3u8);
+        // decimal places are dropped before the value is replaced by asterisks
+        assert_eq!(field.format_value(&FieldData::Real(123.4567), &encoding), b"123.46".to_vec());
+        assert_eq!(field.format_value(&FieldData::Real(12345.6), &encoding), b" 12346".to_vec());
+        assert_eq!(field.format_value(&FieldData::Int(1234567), &encoding), b"******".to_vec());
+        assert_eq!(field.parse_value("******"), FieldData::Null);
+
+        // text is truncated without splitting a multi-byte character
+        let field = AttributeField::new("T", 'C', 4u8, 0u8);
+        assert_eq!(field.format_value(&FieldData::Text("abé€".to_string()), &encoding), "abé".as_bytes().to_vec());
+        assert_eq!(field.format_value(&FieldData::Text("ab".to_string()), &encoding), b"ab  ".to_vec());
+    }
+
+    #[test]
+    fn mismatched_records_are_rejected() {
+        let mut table = test_table(AttributeEncoding::Utf8);
+        assert_eq!(table.add_record(vec![FieldData::Int(1)], false).unwrap_err().kind(), ErrorKind::InvalidInput);
+        assert_eq!(table.get_num_records(), 3);
+        assert!(table.set_value(0, "NO_SUCH_FIELD", FieldData::Int(1)).is_err());
+        table.set_value(2, "id", FieldData::Int(3)).unwrap();
+        assert_eq!(table.get_value(2, "ID"), FieldData::Int(3));
+
+        // fields added later are Null in the existing records
+        table.add_field(AttributeField::new("EXTRA", 'N', 5u8, 0u8));
+        assert_eq!(table.get_value(0, "EXTRA"), FieldData::Null);
+    }
+
+    #[test]
+    fn oversized_tables_are_not_written() {
+        let file_name = temp_file("wbt_attributes_oversized.dbf");
+        let mut table = AttributeTable::default();
+        for i in 0..2100 {
+            table.add_field(AttributeField::new(&format!("F{}", i), 'N', 1u8, 0u8));
+        }
+        assert_eq!(table.write(&file_name).unwrap_err().kind(), ErrorKind::InvalidInput);
+
+        let mut table = AttributeTable::default();
+        for i in 0..300 {
+            table.add_field(AttributeField::new(&format!("F{}", i), 'C', 254u8, 0u8));
+        }
+        assert_eq!(table.write(&file_name).unwrap_err().kind(), ErrorKind::InvalidInput);
+        assert!(!Path::new(&file_name).exists());
+    }
+}
@@ -2,16 +2,29 @@
 This code is part of the WhiteboxTools geospatial analysis library.
 Authors: Dr. John Lindsay
 Created: June 21, 2017
-Last Modified: July 17, 2017
+Last Modified: March 31, 2018
 License: MIT
 */
 use std::io::prelude::*;
 use std::io::{Error, ErrorKind};
+use std::io::BufWriter;
+use std::f64;
 use std::fs;
 use std::fs::File;
 use std::fmt;
-use io_utils::{ByteOrderReader, Endianness};
+use std::path::Path;
+use byteorder::{BigEndian, LittleEndian, WriteBytesExt};
+use io_utils::{is_geopackage_file, ByteOrderReader, Endianness};
 use vector::Point2D;
+use vector::{geojson, geopackage};
+
+mod attributes;
+pub use self::attributes::{AttributeTable, AttributeHeader, AttributeField, AttributeEncoding, FieldData, DateData};
+
+/// Measures less than this value are treated as 'no data' by the Shapefile specification.
+pub const M_NO_DATA_THRESHOLD: f64 = -1.0e38;
+/// The value used to write 'no data' measures.
+pub const M_NO_DATA: f64 = -1.0e39;
 
 // 100 bytes in size
 #[derive(Default, Clone)]
@@ -72,9 +85,16 @@ pub struct Shapefile {
     pub header: ShapefileHeader,
     pub num_records: usize,
     pub records: Vec<ShapefileGeometry>,
+    pub attributes: AttributeTable,
+    pub projection: String,
 }
 
 impl Shapefile {
+    /// Opens a Shapefile. When `file_mode` is "r" the .shp file and its .dbf and .prj 
+    /// sidecars, if present, are read. Files with a .geojson or .json extension are 
+    /// read, and written, as GeoJSON, and .gpkg files as GeoPackages. In "w" mode an empty Shapefile is returned; set 
+    /// `header.shape_type` (and optionally `projection`), add fields to `attributes`, 
+    /// add records using `add_record` and `attributes.add_record`, and then call `write`.
     pub fn new<'a>(file_name: &'a str, file_mode: &'a str) -> Result<Shapefile, Error> {
         let fm: String = file_mode.to_lowercase();
         let mut sf = Shapefile {
@@ -85,8 +105,8 @@ impl Shapefile {
         if sf.file_mode.contains("r") {
             sf.read()?;
         } else {
-            // write
-            // return Ok(r);
+            sf.header.file_code = 9994i32;
+            sf.header.version = 1000i32;
         }
         Ok(sf)
         
@@ -97,16 +117,37 @@ impl Shapefile {
         &self.records[index]
     }
 
+    /// Adds a record to a Shapefile opened in write mode. The record's shape type 
+    /// must either match that of the file or be ShapeType::Null.
+    pub fn add_record(&mut self, geometry: ShapefileGeometry) {
+        if geometry.shape_type != self.header.shape_type && geometry.shape_type != ShapeType::Null {
+            panic!("The record's ShapeType ({}) does not match that of the Shapefile ({}).", geometry.shape_type, self.header.shape_type);
+        }
+        self.records.push(geometry);
+        self.num_records = self.records.len();
+    }
+
+    fn get_sidecar_file(&self, extension: &str) -> String {
+        Path::new(&self.file_name).with_extension(extension).to_str().unwrap().to_string()
+    }
+
     fn read(&mut self) -> Result<(), Error>  {
+        if geojson::is_geojson_file(&self.file_name) {
+            return geojson::read(self);
+        }
+        if is_geopackage_file(&self.file_name) {
+            return geopackage::read(self);
+        }
+
         // read the header
-        let mut f = File::open(self.file_name.clone()).unwrap(); //?;
-        let metadata = fs::metadata(self.file_name.clone()).unwrap(); //?;
+        let mut f = File::open(self.file_name.clone())?;
+        let metadata = fs::metadata(self.file_name.clone())?;
         let file_size: usize = metadata.len() as usize;
         // let header_size = 100usize;
         let mut buffer = vec![0; file_size];
 
         // read the file's bytes into a buffer
-        f.read(&mut buffer).unwrap(); //?;
+        f.read_exact(&mut buffer)?;
 
         // Note: the shapefile format uses mixed endianness for whatever reason.
         // The ByteOrderReader was set up to have one consistent endianness. As
@@ -132,97 +173,62 @@ impl Shapefile {
         self.header.m_min = bor.read_f64();
         self.header.m_max = bor.read_f64();
 
-        // Read the data
-        bor.byte_order = Endianness::LittleEndian;
         match self.header.shape_type {
-            ShapeType::Point => {
-                while bor.pos < file_size {
-                    bor.pos += 12; // Don't need to read the header and shapeType. It's not going to change.
-                    let sfg = ShapefileGeometry {
-                        shape_type: ShapeType::Point, 
-                        num_points: 1i32, 
-                        points: vec![ Point2D{ x: bor.read_f64(), y: bor.read_f64() } ],
-                        ..Default::default()
-                    };
-                    self.records.push(sfg);
-                }
+            ShapeType::Null => {
+                return Err(Error::new(ErrorKind::InvalidInput, "Unrecognized ShapeType."));
             },
+            _ => {},
+        }
 
-            ShapeType::PolyLine | ShapeType::Polygon => {
-                while bor.pos < file_size {
-                    bor.pos += 8;
-                    let mut sfg = ShapefileGeometry {
-                        shape_type: ShapeType::from_int(bor.read_i32()), 
-                        x_min: bor.read_f64(),
-                        y_min: bor.read_f64(),
-                        x_max: bor.read_f64(),
-                        y_max: bor.read_f64(),
-                        num_parts: bor.read_i32(),
-                        num_points: bor.read_i32(), 
-                        ..Default::default()
-                    };
-
-                    for _ in 0..sfg.num_parts {
-                        sfg.parts.push(bor.read_i32());
+        // Read the data. Each record is preceded by a BigEndian record number and 
+        // content length (in 16-bit words) and may be a Null shape.
+        while bor.pos + 8 <= file_size {
+            bor.byte_order = Endianness::BigEndian;
+            bor.pos += 4; // Don't need the record number.
+            let content_length = 2 * bor.read_i32() as usize;
+            let record_end = bor.pos + content_length;
+            if record_end > file_size || content_length < 4 {
+                return Err(Error::new(ErrorKind::InvalidData, "The Shapefile contains a truncated record."));
+            }
+            bor.byte_order = Endianness::LittleEndian;
+            let shape_type = ShapeType::from_int(bor.read_i32());
+            let mut sfg = ShapefileGeometry::new(shape_type.clone());
+
+            match shape_type {
+                ShapeType::Null => {},
+
+                ShapeType::Point | ShapeType::PointZ | ShapeType::PointM => {
+                    let p = Point2D{ x: bor.read_f64(), y: bor.read_f64() };
+                    sfg.x_min = p.x;
+                    sfg.x_max = p.x;
+                    sfg.y_min = p.y;
+                    sfg.y_max = p.y;
+                    sfg.num_points = 1i32;
+                    sfg.points.push(p);
+                    if shape_type == ShapeType::PointZ {
+                        let z = bor.read_f64();
+                        sfg.z_min = z;
+                        sfg.z_max = z;
+                        sfg.z_array.push(z);
                     }
-
-                    for _ in 0..sfg.num_points {
-                        sfg.points.push(Point2D{ x: bor.read_f64(), y: bor.read_f64() });
-                    }  
-
-                    self.records.push(sfg);
-                }
-            },
-
-            ShapeType::MultiPoint => {
-                while bor.pos < file_size {
-                    bor.pos += 8;
-                    let mut sfg = ShapefileGeometry {
-                        shape_type: ShapeType::from_int(bor.read_i32()), 
-                        x_min: bor.read_f64(),
-                        y_min: bor.read_f64(),
-                        x_max: bor.read_f64(),
-                        y_max: bor.read_f64(),
-                        num_points: bor.read_i32(), 
-                        ..Default::default()
-                    };
-
-                    for _ in 0..sfg.num_points {
-                        sfg.points.push(Point2D{ x: bor.read_f64(), y: bor.read_f64() });
-                    }  
-
-                    self.records.push(sfg);
-                }
-            },
-
-            ShapeType::PointZ => {
-                while bor.pos < file_size {
-                    bor.pos += 12; // Don't need to read the header and shapeType. It's not going to change.
-                    let sfg = ShapefileGeometry {
-                        shape_type: ShapeType::Point, 
-                        num_points: 1i32, 
-                        points: vec![ Point2D{ x: bor.read_f64(), y: bor.read_f64() } ],
-                        z_array: vec![ bor.read_f64() ],
-                        m_array: vec![ bor.read_f64() ],
-                        ..Default::default()
-                    };
-                    self.records.push(sfg);
-                }
-            },
-
-            ShapeType::PolyLineZ | ShapeType::PolygonZ => {
-                while bor.pos < file_size {
-                    bor.pos += 8;
-                    let mut sfg = ShapefileGeometry {
-                        shape_type: ShapeType::from_int(bor.read_i32()), 
-                        x_min: bor.read_f64(),
-                        y_min: bor.read_f64(),
-                        x_max: bor.read_f64(),
-                        y_max: bor.read_f64(),
-                        num_parts: bor.read_i32(),
-                        num_points: bor.read_i32(), 
-                        ..Default::default()
-                    };
+                    if shape_type != ShapeType::Point && bor.pos + 8 <= record_end {
+                        let m = bor.read_f64();
+                        sfg.m_min = m;
+                        sfg.m_max = m;
+                        sfg.m_array.push(m);
+                    }
+                },
+
+                _ => {
+                    sfg.x_min = bor.read_f64();
+                    sfg.y_min = bor.read_f64();
+                    sfg.x_max = bor.read_f64();
+                    sfg.y_max = bor.read_f64();
+                    let base_shape_type = shape_type.base_shape_type();
+                    if base_shape_type != ShapeType::MultiPoint {
+                        sfg.num_parts = bor.read_i32();
+                    }
+                    sfg.num_points = bor.read_i32();
 
                     for _ in 0..sfg.num_parts {
                         sfg.parts.push(bor.read_i32());
@@ -232,136 +238,192 @@ impl Shapefile {
                         sfg.points.push(Point2D{ x: bor.read_f64(), y: bor.read_f64() });
                     }  
 
-                    sfg.z_min = bor.read_f64();
-                    sfg.z_max = bor.read_f64();
-                    for _ in 0..sfg.num_points {
-                        sfg.z_array.push(bor.read_f64());
+                    if shape_type.dimension() == ShapeTypeDimension::Z {
+                        sfg.z_min = bor.read_f64();
+                        sfg.z_max = bor.read_f64();
+                        for _ in 0..sfg.num_points {
+                            sfg.z_array.push(bor.read_f64());
+                        }
                     }
 
-                    sfg.m_min = bor.read_f64();
-                    sfg.m_max = bor.read_f64();
-                    for _ in 0..sfg.num_points {
-                        sfg.m_array.push(bor.read_f64());
-                    }  
-
-                    self.records.push(sfg);
-                }
-            },
-
-            ShapeType::MultiPointZ => {
-                while bor.pos < file_size {
-                    bor.pos += 8;
-                    let mut sfg = ShapefileGeometry {
-                        shape_type: ShapeType::from_int(bor.read_i32()), 
-                        x_min: bor.read_f64(),
-                        y_min: bor.read_f64(),
-                        x_max: bor.read_f64(),
-                        y_max: bor.read_f64(),
-                        num_points: bor.read_i32(), 
-                        ..Default::default()
-                    };
-
-                    for _ in 0..sfg.num_points {
-                        sfg.points.push(Point2D{ x: bor.read_f64(), y: bor.read_f64() });
-                    }  
-
-                    sfg.z_min = bor.read_f64();
-                    sfg.z_max = bor.read_f64();
-                    for _ in 0..sfg.num_points {
-                        sfg.z_array.push(bor.read_f64());
-                    }
-
-                    sfg.m_min = bor.read_f64();
-                    sfg.m_max = bor.read_f64();
-                    for _ in 0..sfg.num_points {
-                        sfg.m_array.push(bor.read_f64());
+                    // The measures are optional for Z shapes.
+                    if shape_type.dimension() != ShapeTypeDimension::XY && bor.pos + 16 + 8 * sfg.num_points as usize <= record_end {
+                        sfg.m_min = bor.read_f64();
+                        sfg.m_max = bor.read_f64();
+                        for _ in 0..sfg.num_points {
+                            sfg.m_array.push(bor.read_f64());
+                        }
                     }
+                },
+            }
 
-                    self.records.push(sfg);
-                }
-            },
-
-            ShapeType::PointM => {
-                while bor.pos < file_size {
-                    bor.pos += 12; // Don't need to read the header and shapeType. It's not going to change.
-                    let sfg = ShapefileGeometry {
-                        shape_type: ShapeType::Point, 
-                        num_points: 1i32, 
-                        points: vec![ Point2D{ x: bor.read_f64(), y: bor.read_f64() } ],
-                        m_array: vec![ bor.read_f64() ],
-                        ..Default::default()
-                    };
-                    self.records.push(sfg);
-                }
-            },
-
-            ShapeType::PolyLineM | ShapeType::PolygonM => {
-                while bor.pos < file_size {
-                    bor.pos += 8;
-                    let mut sfg = ShapefileGeometry {
-                        shape_type: ShapeType::from_int(bor.read_i32()), 
-                        x_min: bor.read_f64(),
-                        y_min: bor.read_f64(),
-                        x_max: bor.read_f64(),
-                        y_max: bor.read_f64(),
-                        num_parts: bor.read_i32(),
-                        num_points: bor.read_i32(), 
-                        ..Default::default()
-                    };
+            bor.pos = record_end;
+            self.records.push(sfg);
+        }
 
-                    for _ in 0..sfg.num_parts {
-                        sfg.parts.push(bor.read_i32());
-                    }
+        self.num_records = self.records.len();
 
-                    for _ in 0..sfg.num_points {
-                        sfg.points.push(Point2D{ x: bor.read_f64(), y: bor.read_f64() });
-                    }  
+        // read the attribute table, if there is one
+        let dbf_file = self.get_sidecar_file("dbf");
+        if Path::new(&dbf_file).exists() {
+            self.attributes = AttributeTable::read(&dbf_file)?;
+        }
 
-                    sfg.m_min = bor.read_f64();
-                    sfg.m_max = bor.read_f64();
-                    for _ in 0..sfg.num_points {
-                        sfg.m_array.push(bor.read_f64());
-                    }  
+        // read the projection sidecar file, if there is one
+        let prj_file = self.get_sidecar_file("prj");
+        if Path::new(&prj_file).exists() {
+            let mut f = File::open(prj_file)?;
+            let mut s = String::new();
+            f.read_to_string(&mut s)?;
+            self.projection = s.trim().to_string();
+        }
 
-                    self.records.push(sfg);
-                }
-            },
+        Ok(())
+    }
 
-            ShapeType::MultiPointM => {
-                while bor.pos < file_size {
-                    bor.pos += 8;
-                    
-                    let mut sfg = ShapefileGeometry {
-                        shape_type: ShapeType::from_int(bor.read_i32()), 
-                        x_min: bor.read_f64(),
-                        y_min: bor.read_f64(),
-                        x_max: bor.read_f64(),
-                        y_max: bor.read_f64(),
-                        num_points: bor.read_i32(), 
-                        ..Default::default()
-                    };
+    /// Writes the Shapefile's main (.shp) file, index (.shx) file, attribute table 
+    /// (.dbf and .cpg) and, if a projection has been set, its .prj sidecar file. If 
+    /// no attribute fields have been added, an FID field is created. GeoJSON files are 
+    /// written as a single FeatureCollection.
+    pub fn write(&mut self) -> Result<(), Error> {
+        if self.file_mode == "r" {
+            return Err(Error::new(ErrorKind::Other, "The file was opened in read-only mode"));
+        }
+        if self.header.shape_type == ShapeType::Null {
+            return Err(Error::new(ErrorKind::Other, "The ShapeType of a Shapefile must be set before it can be written."));
+        }
 
-                    for _ in 0..sfg.num_points {
-                        sfg.points.push(Point2D{ x: bor.read_f64(), y: bor.read_f64() });
-                    }  
+        if self.attributes.get_num_fields() == 0 && self.attributes.get_num_records() == 0 {
+            self.attributes.add_field(AttributeField::new("FID", 'N', 10u8, 0u8));
+            for i in 0..self.records.len() {
+                self.attributes.add_record(vec![FieldData::Int(i as i32 + 1)], false)?;
+            }
+        }
+        if self.attributes.get_num_records() != self.records.len() {
+            return Err(Error::new(ErrorKind::InvalidInput, format!("The attribute table has {} records but the Shapefile has {} records.", self.attributes.get_num_records(), self.records.len())));
+        }
 
-                    sfg.m_min = bor.read_f64();
-                    sfg.m_max = bor.read_f64();
-                    for _ in 0..sfg.num_points {
-                        sfg.m_array.push(bor.read_f64());
-                    }
+        // update the bounding boxes of the records and the file
+        let dimension = self.header.shape_type.dimension();
+        self.header.x_min = f64::INFINITY;
+        self.header.y_min = f64::INFINITY;
+        self.header.x_max = f64::NEG_INFINITY;
+        self.header.y_max = f64::NEG_INFINITY;
+        self.header.z_min = f64::INFINITY;
+        self.header.z_max = f64::NEG_INFINITY;
+        self.header.m_min = f64::INFINITY;
+        self.header.m_max = f64::NEG_INFINITY;
+        for rec in &mut self.records {
+            if rec.shape_type != self.header.shape_type && rec.shape_type != ShapeType::Null {
+                return Err(Error::new(ErrorKind::InvalidInput, "Each record in a Shapefile must have the same ShapeType as the file, or be Null."));
+            }
+            rec.validate()?;
+            if rec.shape_type == ShapeType::Null || rec.points.len() == 0 {
+                continue;
+            }
+            rec.calculate_extent();
+            if rec.x_min < self.header.x_min { self.header.x_min = rec.x_min; }
+            if rec.y_min < self.header.y_min { self.header.y_min = rec.y_min; }
+            if rec.x_max > self.header.x_max { self.header.x_max = rec.x_max; }
+            if rec.y_max > self.header.y_max { self.header.y_max = rec.y_max; }
+            if dimension == ShapeTypeDimension::Z {
+                if rec.z_min < self.header.z_min { self.header.z_min = rec.z_min; }
+                if rec.z_max > self.header.z_max { self.header.z_max = rec.z_max; }
+            }
+            if dimension != ShapeTypeDimension::XY && rec.m_array.len() > 0 {
+                if rec.m_min < self.header.m_min { self.header.m_min = rec.m_min; }
+                if rec.m_max > self.header.m_max { self.header.m_max = rec.m_max; }
+            }
+        }
+        if self.header.x_min > self.header.x_max {
+            // there are no non-null records
+            self.header.x_min = 0f64;
+            self.header.y_min = 0f64;
+            self.header.x_max = 0f64;
+            self.header.y_max = 0f64;
+        }
+        if self.header.z_min > self.header.z_max {
+            self.header.z_min = 0f64;
+            self.header.z_max = 0f64;
+        }
+        if self.header.m_min > self.header.m_max {
+            self.header.m_min = 0f64;
+            self.header.m_max = 0f64;
+        }
 
-                    self.records.push(sfg);
-                }
-            },
+        if geojson::is_geojson_file(&self.file_name) {
+            return geojson::write(self);
+        }
+        if is_geopackage_file(&self.file_name) {
+            return geopackage::write(self);
+        }
 
-            _ => {
-                return Err(Error::new(ErrorKind::InvalidInput, "Unrecognized ShapeType."));
-            },
+        // file lengths are measured in 16-bit words
+        let mut shp_length = 50i32; // the header
+        for rec in &self.records {
+            shp_length += 4 + rec.get_content_length(); // record header + content
+        }
+        let shx_length = 50i32 + 4i32 * self.records.len() as i32;
+        self.header.file_length = shp_length;
+        self.header.file_code = 9994i32;
+        self.header.version = 1000i32;
+
+        // the main file
+        let f = File::create(&self.file_name)?;
+        let mut shp = BufWriter::new(f);
+        self.write_header(&mut shp, shp_length)?;
+
+        // the index file
+        let f = File::create(self.get_sidecar_file("shx"))?;
+        let mut shx = BufWriter::new(f);
+        self.write_header(&mut shx, shx_length)?;
+
+        let mut offset = 50i32;
+        for i in 0..self.records.len() {
+            let rec = &self.records[i];
+            let content_length = rec.get_content_length();
+            shx.write_i32::<BigEndian>(offset)?;
+            shx.write_i32::<BigEndian>(content_length)?;
+            offset += 4 + content_length;
+
+            shp.write_i32::<BigEndian>(i as i32 + 1)?; // record numbers are 1-based
+            shp.write_i32::<BigEndian>(content_length)?;
+            rec.write(&mut shp)?;
+        }
+        shp.flush()?;
+        shx.flush()?;
+
+        // the attribute table
+        let dbf_file = self.get_sidecar_file("dbf");
+        self.attributes.write(&dbf_file)?;
+
+        // the projection file
+        if !self.projection.is_empty() {
+            let f = File::create(self.get_sidecar_file("prj"))?;
+            let mut prj = BufWriter::new(f);
+            prj.write_all(self.projection.as_bytes())?;
+            prj.flush()?;
         }
 
-        self.num_records = self.records.len();
+        Ok(())
+    }
 
+    fn write_header<W: Write>(&self, writer: &mut W, file_length: i32) -> Result<(), Error> {
+        writer.write_i32::<BigEndian>(self.header.file_code)?;
+        for _ in 0..5 {
+            writer.write_i32::<BigEndian>(0i32)?;
+        }
+        writer.write_i32::<BigEndian>(file_length)?;
+        writer.write_i32::<LittleEndian>(self.header.version)?;
+        writer.write_i32::<LittleEndian>(self.header.shape_type.to_int())?;
+        writer.write_f64::<LittleEndian>(self.header.x_min)?;
+        writer.write_f64::<LittleEndian>(self.header.y_min)?;
+        writer.write_f64::<LittleEndian>(self.header.x_max)?;
+        writer.write_f64::<LittleEndian>(self.header.y_max)?;
+        writer.write_f64::<LittleEndian>(self.header.z_min)?;
+        writer.write_f64::<LittleEndian>(self.header.z_max)?;
+        writer.write_f64::<LittleEndian>(self.header.m_min)?;
+        writer.write_f64::<LittleEndian>(self.header.m_max)?;
         Ok(())
     }
 }
@@ -407,6 +469,202 @@ impl Default for ShapefileGeometry {
     }
 }
 
+impl ShapefileGeometry {
+    /// Creates an empty geometry of the specified ShapeType.
+    pub fn new(shape_type: ShapeType) -> ShapefileGeometry {
+        ShapefileGeometry {
+            shape_type: shape_type,
+            ..Default::default()
+        }
+    }
+
+    /// Adds a point to a Point or MultiPoint geometry.
+    pub fn add_point(&mut self, point: Point2D) {
+        self.points.push(point);
+        self.num_points += 1;
+    }
+
+    /// Adds a point with a z value and a measure to a PointZ or MultiPointZ geometry.
+    pub fn add_pointz(&mut self, point: Point2D, z: f64, m: f64) {
+        self.add_point(point);
+        self.z_array.push(z);
+        self.m_array.push(m);
+    }
+
+    /// Adds a point with a measure to a PointM or MultiPointM geometry.
+    pub fn add_pointm(&mut self, point: Point2D, m: f64) {
+        self.add_point(point);
+        self.m_array.push(m);
+    }
+
+    /// Adds a part (a line or a polygon ring) to a PolyLine or Polygon geometry. 
+    /// Polygon rings should be closed, with outer rings in clockwise order and 
+    /// holes in counter-clockwise order.
+    pub fn add_part(&mut self, points: &[Point2D]) {
+        self.parts.push(self.points.len() as i32);
+        self.num_parts += 1;
+        for p in points {
+            self.add_point(p.clone());
+        }
+    }
+
+    /// Adds a part, with z values and measures, to a PolyLineZ or PolygonZ geometry.
+    pub fn add_partz(&mut self, points: &[Point2D], z_values: &[f64], m_values: &[f64]) {
+        self.add_part(points);
+        self.z_array.extend_from_slice(z_values);
+        self.m_array.extend_from_slice(m_values);
+    }
+
+    /// Adds a part, with measures, to a PolyLineM or PolygonM geometry.
+    pub fn add_partm(&mut self, points: &[Point2D], m_values: &[f64]) {
+        self.add_part(points);
+        self.m_array.extend_from_slice(m_values);
+    }
+
+    /// Updates the bounding box, and z and measure ranges, from the geometry's points. 
+    /// Measures less than -10^38 are 'no data' values and are ignored.
+    pub fn calculate_extent(&mut self) {
+        self.x_min = f64::INFINITY;
+        self.y_min = f64::INFINITY;
+        self.x_max = f64::NEG_INFINITY;
+        self.y_max = f64::NEG_INFINITY;
+        for p in &self.points {
+            if p.x < self.x_min { self.x_min = p.x; }
+            if p.y < self.y_min { self.y_min = p.y; }
+            if p.x > self.x_max { self.x_max = p.x; }
+            if p.y > self.y_max { self.y_max = p.y; }
+        }
+        self.z_min = f64::INFINITY;
+        self.z_max = f64::NEG_INFINITY;
+        for &z in &self.z_array {
+            if z < self.z_min { self.z_min = z; }
+            if z > self.z_max { self.z_max = z; }
+        }
+        self.m_min = f64::INFINITY;
+        self.m_max = f64::NEG_INFINITY;
+        for &m in &self.m_array {
+            if m < M_NO_DATA_THRESHOLD { continue; }
+            if m < self.m_min { self.m_min = m; }
+            if m > self.m_max { self.m_max = m; }
+        }
+        if self.points.len() == 0 {
+            self.x_min = 0f64;
+            self.y_min = 0f64;
+            self.x_max = 0f64;
+            self.y_max = 0f64;
+        }
+        if self.z_min > self.z_max {
+            self.z_min = 0f64;
+            self.z_max = 0f64;
+        }
+        if self.m_array.len() == 0 {
+            self.m_min = 0f64;
+            self.m_max = 0f64;
+        } else if self.m_min > self.m_max {
+            // all of the measures are 'no data'
+            self.m_min = M_NO_DATA;
+            self.m_max = M_NO_DATA;
+        }
+    }
+
+    // checks that the point, part, z and measure arrays are consistent with the shape type
+    fn validate(&self) -> Result<(), Error> {
+        let n = self.points.len();
+        let dimension = self.shape_type.dimension();
+        let ok = match self.shape_type.base_shape_type() {
+            ShapeType::Null => true,
+            ShapeType::Point => n == 1,
+            ShapeType::MultiPoint => true,
+            _ => self.parts.len() > 0 && self.parts[0] == 0 && 
+                    self.parts.iter().all(|&p| p >= 0 && (p as usize) < n) && 
+                    self.parts.windows(2).all(|w| w[0] < w[1]),
+        } && match dimension {
+            ShapeTypeDimension::XY => true,
+            ShapeTypeDimension::Measure => self.m_array.len() == n,
+            ShapeTypeDimension::Z => self.z_array.len() == n && (self.m_array.len() == 0 || self.m_array.len() == n),
+        };
+        if !ok {
+            return Err(Error::new(ErrorKind::InvalidInput, format!("A {} record has inconsistent points, parts, z values or measures.", self.shape_type)));
+        }
+        Ok(())
+    }
+
+    // the length of the record contents, in 16-bit words
+    fn get_content_length(&self) -> i32 {
+        let n = self.points.len();
+        let has_measures = match self.shape_type.dimension() {
+            ShapeTypeDimension::XY => false,
+            ShapeTypeDimension::Measure => true,
+            ShapeTypeDimension::Z => self.m_array.len() > 0,
+        };
+        let mut bytes = 4usize; // shape type
+        match self.shape_type.base_shape_type() {
+            ShapeType::Null => {},
+            ShapeType::Point => {
+                bytes += 16;
+                if self.shape_type.dimension() == ShapeTypeDimension::Z { bytes += 8; }
+                if has_measures { bytes += 8; }
+            },
+            base => {
+                bytes += 32 + 4 + 16 * n; // bounding box, num_points, points
+                if base != ShapeType::MultiPoint {
+                    bytes += 4 + 4 * self.parts.len(); // num_parts, parts
+                }
+                if self.shape_type.dimension() == ShapeTypeDimension::Z { bytes += 16 + 8 * n; }
+                if has_measures { bytes += 16 + 8 * n; }
+            },
+        }
+        (bytes / 2) as i32
+    }
+
+    fn write<W: Write>(&self, writer: &mut W) -> Result<(), Error> {
+        writer.write_i32::<LittleEndian>(self.shape_type.to_int())?;
+        let base_shape_type = self.shape_type.base_shape_type();
+        if base_shape_type == ShapeType::Null {
+            return Ok(());
+        }
+        if base_shape_type != ShapeType::Point {
+            writer.write_f64::<LittleEndian>(self.x_min)?;
+            writer.write_f64::<LittleEndian>(self.y_min)?;
+            writer.write_f64::<LittleEndian>(self.x_max)?;
+            writer.write_f64::<LittleEndian>(self.y_max)?;
+            if base_shape_type != ShapeType::MultiPoint {
+                writer.write_i32::<LittleEndian>(self.parts.len() as i32)?;
+            }
+            writer.write_i32::<LittleEndian>(self.points.len() as i32)?;
+            if base_shape_type != ShapeType::MultiPoint {
+                for &part in &self.parts {
+                    writer.write_i32::<LittleEndian>(part)?;
+                }
+            }
+        }
+        for p in &self.points {
+            writer.write_f64::<LittleEndian>(p.x)?;
+            writer.write_f64::<LittleEndian>(p.y)?;
+        }
+        let is_point = base_shape_type == ShapeType::Point;
+        if self.shape_type.dimension() == ShapeTypeDimension::Z {
+            if !is_point {
+                writer.write_f64::<LittleEndian>(self.z_min)?;
+                writer.write_f64::<LittleEndian>(self.z_max)?;
+            }
+            for &z in &self.z_array {
+                writer.write_f64::<LittleEndian>(z)?;
+            }
+        }
+        if self.shape_type.dimension() != ShapeTypeDimension::XY && self.m_array.len() > 0 {
+            if !is_point {
+                writer.write_f64::<LittleEndian>(self.m_min)?;
+                writer.write_f64::<LittleEndian>(self.m_max)?;
+            }
+            for &m in &self.m_array {
+                writer.write_f64::<LittleEndian>(m)?;
+            }
+        }
+        Ok(())
+    }
+}
+
 impl fmt::Display for ShapefileGeometry {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         let s = format!("shape_type: {}
@@ -444,7 +702,7 @@ m_array: {:?}",
 }
 
 #[repr(u16)]
-#[derive(Clone, PartialEq)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum ShapeType { 
     Null = 0,
     Point = 1, 
@@ -481,6 +739,18 @@ impl ShapeType {
         }
     }
 
+    pub fn to_int(&self) -> i32 {
+        self.clone() as i32
+    }
+
+    pub fn dimension(&self) -> ShapeTypeDimension {
+        match self {
+            &ShapeType::PointZ | &ShapeType::PolyLineZ | &ShapeType::PolygonZ | &ShapeType::MultiPointZ => ShapeTypeDimension::Z,
+            &ShapeType::PointM | &ShapeType::PolyLineM | &ShapeType::PolygonM | &ShapeType::MultiPointM => ShapeTypeDimension::Measure,
+            _ => ShapeTypeDimension::XY,
+        }
+    }
+
     pub fn base_shape_type(&self) -> ShapeType {
         match self {
             &ShapeType::Null => ShapeType::Null,
@@ -492,6 +762,14 @@ impl ShapeType {
     }
 }
 
+/// The coordinate dimensions of a ShapeType. Z shapes also carry optional measures.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ShapeTypeDimension {
+    XY,
+    Measure,
+    Z,
+}
+
 impl Default for ShapeType {
     fn default() -> ShapeType { ShapeType::Null }
 }
@@ -515,4 +793,125 @@ impl fmt::Display for ShapeType {
         };
         write!(f, "{}", printable)
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+
+    fn temp_file(name: &str) -> String {
+        env::temp_dir().join(name).to_str().unwrap().to_string()
+    }
+
+    // closed, clockwise, square rings
+    fn square(x: f64, y: f64, size: f64) -> Vec<Point2D> {
+        vec![Point2D { x: x, y: y }, Point2D { x: x, y: y + size }, Point2D { x: x + size, y: y + size },
+             Point2D { x: x + size, y: y }, Point2D { x: x, y: y }]
+    }
+
+    fn test_geometry(shape_type: ShapeType, offset: f64) -> ShapefileGeometry {
+        let mut sfg = ShapefileGeometry::new(shape_type.clone());
+        let dimension = shape_type.dimension();
+        let parts: Vec<Vec<Point2D>> = match shape_type.base_shape_type() {
+            ShapeType::Point => vec![vec![Point2D { x: offset, y: -offset }]],
+            ShapeType::MultiPoint => vec![vec![Point2D { x: offset, y: 1.5 }, Point2D { x: 2.5, y: offset }, Point2D { x: -offset, y: 0.25 }]],
+            ShapeType::PolyLine => vec![vec![Point2D { x: offset, y: 0.0 }, Point2D { x: 1.0, y: offset }, Point2D { x: 7.0, y: 3.0 }],
+                                        vec![Point2D { x: -offset, y: 2.0 }, Point2D { x: 4.0, y: 4.0 }]],
+            _ => {
+                let mut hole = square(offset + 1.0, 1.0, 2.0);
+                hole.reverse();
+                vec![square(offset, 0.0, 4.0), hole, square(offset + 10.0, 0.0, 1.0)]
+            },
+        };
+        for part in parts {
+            let z: Vec<f64> = (0..part.len()).map(|i| offset * 10.0 + i as f64).collect();
+            let m: Vec<f64> = (0..part.len()).map(|i| if i == 1 { M_NO_DATA } else { offset - i as f64 }).collect();
+            match (shape_type.base_shape_type(), dimension) {
+                (ShapeType::Point, ShapeTypeDimension::XY) | (ShapeType::MultiPoint, ShapeTypeDimension::XY) => {
+                    for p in part { sfg.add_point(p); }
+                },
+                (ShapeType::Point, ShapeTypeDimension::Z) | (ShapeType::MultiPoint, ShapeTypeDimension::Z) => {
+                    for i in 0..part.len() { sfg.add_pointz(part[i].clone(), z[i], m[i]); }
+                },
+                (ShapeType::Point, _) | (ShapeType::MultiPoint, _) => {
+                    for i in 0..part.len() { sfg.add_pointm(part[i].clone(), m[i]); }
+                },
+                (_, ShapeTypeDimension::XY) => sfg.add_part(&part),
+                (_, ShapeTypeDimension::Z) => sfg.add_partz(&part, &z, &m),
+                (_, ShapeTypeDimension::Measure) => sfg.add_partm(&part, &m),
+            }
+        }
+        sfg
+    }
+
+    #[test]
+    fn shapefile_round_trip_for_every_shape_type() {
+        let shape_types = [ShapeType::Point, ShapeType::PolyLine, ShapeType::Polygon, ShapeType::MultiPoint,
+            ShapeType::PointZ, ShapeType::PolyLineZ, ShapeType::PolygonZ, ShapeType::MultiPointZ,
+            ShapeType::PointM, ShapeType::PolyLineM, ShapeType::PolygonM, ShapeType::MultiPointM];
+        for shape_type in shape_types.iter() {
+            let file_name = temp_file(&format!("wbt_shapefile_{}.shp", shape_type.to_int()));
+            let mut sf = Shapefile::new(&file_name, "w").unwrap();
+            sf.header.shape_type = shape_type.clone();
+            sf.projection = "GEOGCS[\"WGS 84\"]".to_string();
+            sf.add_record(test_geometry(shape_type.clone(), 3.0));
+            sf.add_record(ShapefileGeometry::new(ShapeType::Null));
+            sf.add_record(test_geometry(shape_type.clone(), -2.0));
+            sf.write().unwrap();
+
+            let input = Shapefile::new(&file_name, "r").unwrap();
+            assert_eq!(input.header.shape_type, *shape_type);
+            assert_eq!(input.num_records, 3);
+            assert_eq!(input.projection, sf.projection);
+            assert_eq!(input.attributes.get_num_records(), 3);
+            assert_eq!(input.attributes.get_value(2, "FID"), FieldData::Int(3));
+            assert_eq!(input.header.x_min, sf.header.x_min);
+            assert_eq!(input.header.y_max, sf.header.y_max);
+            assert_eq!(input.header.z_max, sf.header.z_max);
+            assert_eq!(input.get_record(1).shape_type, ShapeType::Null);
+            for &i in [0usize, 2].iter() {
+                let (a, b) = (sf.get_record(i), input.get_record(i));
+                assert_eq!(b.shape_type, *shape_type);
+                assert_eq!(b.parts, a.parts);
+                assert_eq!(b.points.len(), a.points.len());
+                for j in 0..a.points.len() {
+                    assert_eq!((b.points[j].x, b.points[j].y), (a.points[j].x, a.points[j].y));
+                }
+                assert_eq!(b.z_array, a.z_array);
+                assert_eq!(b.m_array.len(), a.m_array.len());
+                for j in 0..a.m_array.len() {
+                    // 'no data' measures remain 'no data'
+                    assert_eq!(b.m_array[j] < M_NO_DATA_THRESHOLD, a.m_array[j] < M_NO_DATA_THRESHOLD);
+                    if a.m_array[j] >= M_NO_DATA_THRESHOLD {
+                        assert_eq!(b.m_array[j], a.m_array[j]);
+                    }
+                }
+                assert_eq!((b.x_min, b.x_max, b.y_min, b.y_max), (a.x_min, a.x_max, a.y_min, a.y_max));
+            }
+
+            // the index holds an 8-byte entry for each record
+            let shx = Path::new(&file_name).with_extension("shx");
+            assert_eq!(fs::metadata(&shx).unwrap().len(), 100 + 8 * 3);
+            for ext in ["shp", "shx", "dbf", "prj", "cpg"].iter() {
+                let _ = fs::remove_file(Path::new(&file_name).with_extension(ext));
+            }
+        }
+    }
+
+    #[test]
+    fn inconsistent_records_are_not_written() {
+        let file_name = temp_file("wbt_shapefile_invalid.shp");
+        let mut sf = Shapefile::new(&file_name, "w").unwrap();
+        sf.header.shape_type = ShapeType::PolyLineZ;
+        let mut sfg = ShapefileGeometry::new(ShapeType::PolyLineZ);
+        sfg.add_partz(&[Point2D { x: 0.0, y: 0.0 }, Point2D { x: 1.0, y: 1.0 }], &[1.0], &[]);
+        sf.add_record(sfg);
+        assert_eq!(sf.write().unwrap_err().kind(), ErrorKind::InvalidInput);
+
+        let mut sf = Shapefile::new(&file_name, "w").unwrap();
+        sf.header.shape_type = ShapeType::Point;
+        sf.add_record(test_geometry(ShapeType::Point, 1.0));
+        sf.attributes.add_field(AttributeField::new("NAME", 'C', 10u8, 0u8));
+        assert_eq!(sf.write().unwrap_err().kind(), ErrorKind::InvalidInput);
+    }
+}
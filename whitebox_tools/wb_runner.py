@@ -98,11 +98,11 @@ class FileSelector(tk.Frame):
                     ftypes = [('Raster files', ('*.dep', '*.tif',
                                                 '*.tiff', '*.flt',
                                                 '*.sdat', '*.rdc',
-                                                '*.asc'))]
+                                                '*.asc', '*.gpkg'))]
                 elif 'Lidar' in self.file_type:
                     ftypes = [("LiDAR files", ('*.las', '*.zip'))]
                 elif 'Vector' in self.file_type:
-                    ftypes = [("Shapefiles", "*.shp")]
+                    ftypes = [("Vector files", ("*.shp", "*.geojson", "*.json", "*.gpkg"))]
                 elif 'Text' in self.file_type:
                     ftypes = [("Text files", "*.txt"), ("all files", "*.*")]
                 elif 'Html' in self.file_type:
@@ -241,11 +241,11 @@ class FileOrFloat(tk.Frame):
                 ftypes = [('Raster files', ('*.dep', '*.tif',
                                             '*.tiff', '*.flt',
                                             '*.sdat', '*.rdc',
-                                            '*.asc'))]
+                                            '*.asc', '*.gpkg'))]
             elif 'Lidar' in self.file_type:
                 ftypes = [("LiDAR files", ('*.las', '*.zip'))]
             elif 'Vector' in self.file_type:
-                ftypes = [("Shapefiles", "*.shp")]
+                ftypes = [("Vector files", ("*.shp", "*.geojson", "*.json", "*.gpkg"))]
             elif 'Text' in self.file_type:
                 ftypes = [("Text files", "*.txt"), ("all files", "*.*")]
             elif 'Html' in self.file_type:
@@ -385,11 +385,11 @@ class MultifileSelector(tk.Frame):
                 ftypes = [('Raster files', ('*.dep', '*.tif',
                                             '*.tiff', '*.flt',
                                             '*.sdat', '*.rdc',
-                                            '*.asc'))]
+                                            '*.asc', '*.gpkg'))]
             elif 'Lidar' in self.file_type:
                 ftypes = [("LiDAR files", ('*.las', '*.zip'))]
             elif 'Vector' in self.file_type:
-                ftypes = [("Shapefiles", "*.shp")]
+                ftypes = [("Vector files", ("*.shp", "*.geojson", "*.json", "*.gpkg"))]
             elif 'Text' in self.file_type:
                 ftypes = [("Text files", "*.txt"), ("all files", "*.*")]
             elif 'Html' in self.file_type:
@@ -951,7 +951,7 @@ class WbRunner(tk.Frame):
                 param_num = param_num + 1
             elif ('Float' in pt or 'Integer' in pt or
                   'String' in pt or 'StringOrNumber' in pt or
-                  'StringList' in pt):
+                  'StringList' in pt or 'VectorAttributeField' in pt):
                 b = DataInput(json_str, self.tool_args_frame)
                 b.grid(row=param_num, column=0, sticky=tk.NSEW)
                 param_num = param_num + 1
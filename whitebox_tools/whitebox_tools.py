@@ -222,6 +222,30 @@ class WhiteboxTools(object):
         except (OSError, ValueError, CalledProcessError) as err:
             return err
 
+    def catalog(self):
+        ''' Retrieve the name, toolbox, description, example usage, and parameters
+        of every tool, as a single JSON document.
+        '''
+        try:
+            os.chdir(self.exe_path)
+            args = []
+            args.append("." + os.path.sep + self.exe_name)
+            args.append("--catalog")
+
+            proc = Popen(args, shell=False, stdout=PIPE,
+                         stderr=STDOUT, bufsize=1, universal_newlines=True)
+            ret = ""
+            while True:
+                line = proc.stdout.readline()
+                if line != '':
+                    ret += line
+                else:
+                    break
+
+            return ret
+        except (OSError, ValueError, CalledProcessError) as err:
+            return err
+
     def toolbox(self, tool_name=''):
         ''' Retrieve the toolbox for a specific tool.
         '''
@@ -371,6 +395,28 @@ callback -- Custom functon for handling tool text outputs.
         # returns 1 if error
         return self.run_tool('PrintGeoTiffTags', args, callback)
 
+    def reproject_raster(self, input, output, crs, cell_size=None, method="cc", callback=default_callback):
+        """ Transforms a raster into another coordinate reference system.
+
+        Keyword arguments:
+
+        input -- Input raster file. 
+        output -- Output raster file. 
+        crs -- Output coordinate reference system, as an EPSG code, WKT, .prj file, or a file with the desired system. 
+        cell_size -- Optionally specified cell size of output raster, in the units of the output coordinate reference system. 
+        method -- Resampling method. 
+        callback -- Custom functon for handling tool text outputs.
+        """
+        args = []
+        args.append("--input='{}'".format(input))
+        args.append("--output='{}'".format(output))
+        args.append("--crs='{}'".format(crs))
+        if cell_size is not None:
+            args.append("--cell_size='{}'".format(cell_size))
+        args.append("--method={}".format(method))
+        # returns 1 if error
+        return self.run_tool('ReprojectRaster', args, callback)
+
     def set_nodata_value(self, input, output, back_value=0.0, callback=default_callback):
         """ Assign a specified value in an input image to the NoData value.
 
@@ -469,6 +515,59 @@ callback -- Custom functon for handling tool text outputs.
         # returns 1 if error
         return self.run_tool('CreatePlane', args, callback)
 
+    def ordinary_kriging(self, input, output, variance, field=None, use_z=False, exclude_cls=None, cell_size=None, base=None, model="spherical", lag_size=None, num_lags=15, nugget=None, partial_sill=None, range=None, num_points=16, radius=None, callback=default_callback):
+        """ Interpolates a raster and its kriging variance from sample points using ordinary kriging.
+
+        Keyword arguments:
+
+        input -- Input vector points file or LiDAR file. 
+        output -- Output raster file of kriging estimates. 
+        variance -- Output raster file of kriging variance. 
+        field -- Input field name in attribute table; not used with LiDAR input. 
+        use_z -- Use the z-coordinates of PointZ input rather than an attribute field. 
+        exclude_cls -- Optional exclude classes from LiDAR input; Valid class values range from 0 to 18, based on LAS specifications. Example, --exclude_cls='3,4,5,6,7,18'. 
+        cell_size -- Optionally specified cell size of output raster. Not used when base raster is specified. 
+        base -- Optionally specified input base raster file. Not used when a cell size is specified. 
+        model -- Variogram model; options are 'spherical' (default), 'exponential', 'gaussian'. 
+        lag_size -- Optional lag size used to fit the variogram; by default the lags span half of the diagonal of the points' bounding box. 
+        num_lags -- Number of lags used to fit the variogram. 
+        nugget -- Optional variogram nugget, replacing the fitted value. 
+        partial_sill -- Optional variogram partial sill (sill minus nugget), replacing the fitted value. 
+        range -- Optional variogram range, replacing the fitted value. 
+        num_points -- Number of nearest sample points used to estimate each grid cell. 
+        radius -- Optional search radius; by default the nearest points are used regardless of distance. 
+        callback -- Custom functon for handling tool text outputs.
+        """
+        args = []
+        args.append("--input='{}'".format(input))
+        args.append("--output='{}'".format(output))
+        args.append("--variance='{}'".format(variance))
+        if field is not None:
+            args.append("--field='{}'".format(field))
+        if use_z:
+            args.append("--use_z")
+        if exclude_cls is not None:
+            args.append("--exclude_cls='{}'".format(exclude_cls))
+        if cell_size is not None:
+            args.append("--cell_size='{}'".format(cell_size))
+        if base is not None:
+            args.append("--base='{}'".format(base))
+        args.append("--model={}".format(model))
+        if lag_size is not None:
+            args.append("--lag_size='{}'".format(lag_size))
+        args.append("--num_lags={}".format(num_lags))
+        if nugget is not None:
+            args.append("--nugget='{}'".format(nugget))
+        if partial_sill is not None:
+            args.append("--partial_sill='{}'".format(partial_sill))
+        if range is not None:
+            args.append("--range='{}'".format(range))
+        args.append("--num_points={}".format(num_points))
+        if radius is not None:
+            args.append("--radius='{}'".format(radius))
+        # returns 1 if error
+        return self.run_tool('OrdinaryKriging', args, callback)
+
     def radius_of_gyration(self, input, output, text_output=False, callback=default_callback):
         """ Calculates the distance of cells from their polygon's centroid.
 
@@ -504,6 +603,107 @@ callback -- Custom functon for handling tool text outputs.
         # returns 1 if error
         return self.run_tool('RasterCellAssignment', args, callback)
 
+    def raster_to_vector_polygons(self, input, output, smooth=0, callback=default_callback):
+        """ Converts groups of contiguous, equal-valued raster grid cells into vector polygons.
+
+        Keyword arguments:
+
+        input -- Input raster file. 
+        output -- Output vector polygon file. 
+        smooth -- Optional size of the boundary smoothing filter (an odd integer, e.g. 3 or 5); 0 for no smoothing. 
+        callback -- Custom functon for handling tool text outputs.
+        """
+        args = []
+        args.append("--input='{}'".format(input))
+        args.append("--output='{}'".format(output))
+        args.append("--smooth={}".format(smooth))
+        # returns 1 if error
+        return self.run_tool('RasterToVectorPolygons', args, callback)
+
+    def universal_kriging(self, input, output, variance, field=None, use_z=False, exclude_cls=None, cell_size=None, base=None, model="spherical", lag_size=None, num_lags=15, nugget=None, partial_sill=None, range=None, num_points=16, radius=None, drift_order=1, callback=default_callback):
+        """ Interpolates a raster and its kriging variance from sample points using universal kriging with a polynomial drift.
+
+        Keyword arguments:
+
+        input -- Input vector points file or LiDAR file. 
+        output -- Output raster file of kriging estimates. 
+        variance -- Output raster file of kriging variance. 
+        field -- Input field name in attribute table; not used with LiDAR input. 
+        use_z -- Use the z-coordinates of PointZ input rather than an attribute field. 
+        exclude_cls -- Optional exclude classes from LiDAR input; Valid class values range from 0 to 18, based on LAS specifications. Example, --exclude_cls='3,4,5,6,7,18'. 
+        cell_size -- Optionally specified cell size of output raster. Not used when base raster is specified. 
+        base -- Optionally specified input base raster file. Not used when a cell size is specified. 
+        model -- Variogram model; options are 'spherical' (default), 'exponential', 'gaussian'. 
+        lag_size -- Optional lag size used to fit the variogram; by default the lags span half of the diagonal of the points' bounding box. 
+        num_lags -- Number of lags used to fit the variogram. 
+        nugget -- Optional variogram nugget, replacing the fitted value. 
+        partial_sill -- Optional variogram partial sill (sill minus nugget), replacing the fitted value. 
+        range -- Optional variogram range, replacing the fitted value. 
+        num_points -- Number of nearest sample points used to estimate each grid cell. 
+        radius -- Optional search radius; by default the nearest points are used regardless of distance. 
+        drift_order -- Polynomial order of the drift; 1 (linear, default) or 2 (quadratic). 
+        callback -- Custom functon for handling tool text outputs.
+        """
+        args = []
+        args.append("--input='{}'".format(input))
+        args.append("--output='{}'".format(output))
+        args.append("--variance='{}'".format(variance))
+        if field is not None:
+            args.append("--field='{}'".format(field))
+        if use_z:
+            args.append("--use_z")
+        if exclude_cls is not None:
+            args.append("--exclude_cls='{}'".format(exclude_cls))
+        if cell_size is not None:
+            args.append("--cell_size='{}'".format(cell_size))
+        if base is not None:
+            args.append("--base='{}'".format(base))
+        args.append("--model={}".format(model))
+        if lag_size is not None:
+            args.append("--lag_size='{}'".format(lag_size))
+        args.append("--num_lags={}".format(num_lags))
+        if nugget is not None:
+            args.append("--nugget='{}'".format(nugget))
+        if partial_sill is not None:
+            args.append("--partial_sill='{}'".format(partial_sill))
+        if range is not None:
+            args.append("--range='{}'".format(range))
+        args.append("--num_points={}".format(num_points))
+        if radius is not None:
+            args.append("--radius='{}'".format(radius))
+        args.append("--drift_order={}".format(drift_order))
+        # returns 1 if error
+        return self.run_tool('UniversalKriging', args, callback)
+
+    def variogram(self, input, output, field=None, use_z=False, exclude_cls=None, lag_size=None, num_lags=15, callback=default_callback):
+        """ Calculates the empirical semivariogram of sample points and fits spherical, exponential, and Gaussian models.
+
+        Keyword arguments:
+
+        input -- Input vector points file or LiDAR file. 
+        output -- Output HTML file. 
+        field -- Input field name in attribute table; not used with LiDAR input. 
+        use_z -- Use the z-coordinates of PointZ input rather than an attribute field. 
+        exclude_cls -- Optional exclude classes from LiDAR input; Valid class values range from 0 to 18, based on LAS specifications. Example, --exclude_cls='3,4,5,6,7,18'. 
+        lag_size -- Optional lag size; by default the lags span half of the diagonal of the points' bounding box. 
+        num_lags -- Number of lags. 
+        callback -- Custom functon for handling tool text outputs.
+        """
+        args = []
+        args.append("--input='{}'".format(input))
+        args.append("--output='{}'".format(output))
+        if field is not None:
+            args.append("--field='{}'".format(field))
+        if use_z:
+            args.append("--use_z")
+        if exclude_cls is not None:
+            args.append("--exclude_cls='{}'".format(exclude_cls))
+        if lag_size is not None:
+            args.append("--lag_size='{}'".format(lag_size))
+        args.append("--num_lags={}".format(num_lags))
+        # returns 1 if error
+        return self.run_tool('Variogram', args, callback)
+
     ###############################
     # GIS Analysis/Distance Tools #
     ###############################
@@ -928,6 +1128,48 @@ callback -- Custom functon for handling tool text outputs.
         args.append("--zfactor={}".format(zfactor))
         return self.run_tool('Aspect', args, callback)  # returns 1 if error
 
+    def contours_from_points(self, input, output, interval=10.0, base=0.0, smooth=0, callback=default_callback):
+        """ Derives a vector contour coverage from the ground returns of a LiDAR point cloud, using a TIN.
+
+        Keyword arguments:
+
+        input -- Input LiDAR file. 
+        output -- Output vector contour file. 
+        interval -- Contour interval. 
+        base -- Base contour height. 
+        smooth -- Optional smoothing filter size (in vertices), e.g. 3, 5, 7, 9; 0 for no smoothing. 
+        callback -- Custom functon for handling tool text outputs.
+        """
+        args = []
+        args.append("--input='{}'".format(input))
+        args.append("--output='{}'".format(output))
+        args.append("--interval={}".format(interval))
+        args.append("--base={}".format(base))
+        args.append("--smooth={}".format(smooth))
+        # returns 1 if error
+        return self.run_tool('ContoursFromPoints', args, callback)
+
+    def contours_from_raster(self, input, output, interval=10.0, base=0.0, smooth=0, callback=default_callback):
+        """ Derives a vector contour coverage from a raster surface.
+
+        Keyword arguments:
+
+        input -- Input surface raster file. 
+        output -- Output vector contour file. 
+        interval -- Contour interval. 
+        base -- Base contour height. 
+        smooth -- Optional smoothing filter size (in vertices), e.g. 3, 5, 7, 9; 0 for no smoothing. 
+        callback -- Custom functon for handling tool text outputs.
+        """
+        args = []
+        args.append("--input='{}'".format(input))
+        args.append("--output='{}'".format(output))
+        args.append("--interval={}".format(interval))
+        args.append("--base={}".format(base))
+        args.append("--smooth={}".format(smooth))
+        # returns 1 if error
+        return self.run_tool('ContoursFromRaster', args, callback)
+
     def dev_from_mean_elev(self, dem, output, filterx=11, filtery=11, callback=default_callback):
         """ Calculates deviation from mean elevation.
 
@@ -1696,7 +1938,7 @@ callback -- Custom functon for handling tool text outputs.
         # returns 1 if error
         return self.run_tool('TotalCurvature', args, callback)
 
-    def viewshed(self, dem, stations, output, height=2.0, callback=default_callback):
+    def viewshed(self, dem, stations, output, height=2.0, height_field=None, callback=default_callback):
         """ Identifies the viewshed for a point or set of points.
 
         Keyword arguments:
@@ -1705,6 +1947,7 @@ callback -- Custom functon for handling tool text outputs.
         stations -- Input viewing station vector file. 
         output -- Output raster file. 
         height -- Viewing station height, in z units. 
+        height_field -- Optional attribute field containing the height of each viewing station, in z units. 
         callback -- Custom functon for handling tool text outputs.
         """
         args = []
@@ -1712,6 +1955,8 @@ callback -- Custom functon for handling tool text outputs.
         args.append("--stations='{}'".format(stations))
         args.append("--output='{}'".format(output))
         args.append("--height={}".format(height))
+        if height_field is not None:
+            args.append("--height_field='{}'".format(height_field))
         return self.run_tool('Viewshed', args, callback)  # returns 1 if error
 
     def wetness_index(self, sca, slope, output, callback=default_callback):
@@ -3985,6 +4230,41 @@ callback -- Custom functon for handling tool text outputs.
         args.append("--min_points={}".format(min_points))
         return self.run_tool('LidarTile', args, callback)  # returns 1 if error
 
+    def lidar_tin_gridding(self, input=None, output=None, parameter="elevation", returns="all", resolution=1.0, exclude_cls=None, minz=None, maxz=None, max_triangle_edge_length=None, callback=default_callback):
+        """ Creates a raster grid based on a Delaunay triangular irregular network (TIN) fitted to LiDAR points.
+
+        Keyword arguments:
+
+        input -- Input LiDAR file (including extension). 
+        output -- Output raster file (including extension). 
+        parameter -- Interpolation parameter; options are 'elevation' (default), 'intensity', 'class', 'scan angle', 'user data'. 
+        returns -- Point return types to include; options are 'all' (default), 'last', 'first'. 
+        resolution -- Output raster's grid resolution. 
+        exclude_cls -- Optional exclude classes from interpolation; Valid class values range from 0 to 18, based on LAS specifications. Example, --exclude_cls='3,4,5,6,7,18'. 
+        minz -- Optional minimum elevation for inclusion in interpolation. 
+        maxz -- Optional maximum elevation for inclusion in interpolation. 
+        max_triangle_edge_length -- Optional maximum triangle edge length; triangles larger than this size will not be gridded. 
+        callback -- Custom functon for handling tool text outputs.
+        """
+        args = []
+        if input is not None:
+            args.append("--input='{}'".format(input))
+        if output is not None:
+            args.append("--output='{}'".format(output))
+        args.append("--parameter={}".format(parameter))
+        args.append("--returns={}".format(returns))
+        args.append("--resolution={}".format(resolution))
+        if exclude_cls is not None:
+            args.append("--exclude_cls='{}'".format(exclude_cls))
+        if minz is not None:
+            args.append("--minz='{}'".format(minz))
+        if maxz is not None:
+            args.append("--maxz='{}'".format(maxz))
+        if max_triangle_edge_length is not None:
+            args.append("--max_triangle_edge_length='{}'".format(max_triangle_edge_length))
+        # returns 1 if error
+        return self.run_tool('LidarTINGridding', args, callback)
+
     def lidar_tophat_transform(self, input, output, radius=1.0, callback=default_callback):
         """ Performs a white top-hat transform on a Lidar dataset; as an estimate of height above ground, this is useful for modelling the vegetation canopy.
 
@@ -4019,6 +4299,23 @@ callback -- Custom functon for handling tool text outputs.
         # returns 1 if error
         return self.run_tool('NormalVectors', args, callback)
 
+    def reproject_lidar(self, input, output, crs, callback=default_callback):
+        """ Transforms the points of a LiDAR (LAS) file into another coordinate reference system.
+
+        Keyword arguments:
+
+        input -- Input LiDAR file. 
+        output -- Output LiDAR file. 
+        crs -- Output coordinate reference system, as an EPSG code, WKT, .prj file, or a file with the desired system. 
+        callback -- Custom functon for handling tool text outputs.
+        """
+        args = []
+        args.append("--input='{}'".format(input))
+        args.append("--output='{}'".format(output))
+        args.append("--crs='{}'".format(crs))
+        # returns 1 if error
+        return self.run_tool('ReprojectLidar', args, callback)
+
     ########################
     # Math and Stats Tools #
     ########################
@@ -5273,6 +5570,29 @@ callback -- Custom functon for handling tool text outputs.
         # returns 1 if error
         return self.run_tool('LongProfileFromPoints', args, callback)
 
+    def raster_streams_to_vector(self, streams, d8_pntr, output, dem=None, esri_pntr=False, callback=default_callback):
+        """ Converts a raster stream network into a topologically connected vector polyline network.
+
+        Keyword arguments:
+
+        streams -- Input raster streams file. 
+        d8_pntr -- Input raster D8 pointer file. 
+        dem -- Optional input raster DEM file, used to calculate link slopes. 
+        output -- Output vector polyline file. 
+        esri_pntr -- D8 pointer uses the ESRI style scheme. 
+        callback -- Custom functon for handling tool text outputs.
+        """
+        args = []
+        args.append("--streams='{}'".format(streams))
+        args.append("--d8_pntr='{}'".format(d8_pntr))
+        if dem is not None:
+            args.append("--dem='{}'".format(dem))
+        args.append("--output='{}'".format(output))
+        if esri_pntr:
+            args.append("--esri_pntr")
+        # returns 1 if error
+        return self.run_tool('RasterStreamsToVector', args, callback)
+
     def remove_short_streams(self, d8_pntr, streams, output, min_length, esri_pntr=False, callback=default_callback):
         """ Removes short first-order streams from a stream network.
 